@@ -0,0 +1,505 @@
+use console::style;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_table, print_warning, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::agent::replay::{
+    get_agent_run_store, FrameChange, FrameInput, ReplayFrame, ReplayOverrides, Rerun, RunDiff,
+};
+use mcp_common::agent::team::{get_agent_team, AgentProfile, ExecutionGraph, TeamRunner};
+use mcp_common::agent::{
+    get_agent_runs, AgentBudget, AgentRunner, AgentStatus, AgentStep, AgentTranscript, NoTools, StepKind,
+};
+use mcp_common::error::McpResult;
+use mcp_common::events::{get_event_bus, names, Backpressure, Event, Topic};
+use mcp_common::service::ChatService;
+
+/// Longest tool result shown for a step
+const MAX_RESULT_CHARS: usize = 200;
+
+/// Print a step of a run, by a named agent or not
+fn print_step(step: &AgentStep, agent: Option<&str>) {
+    let label = match agent {
+        Some(agent) => format!("[{} {}]", agent, step.number),
+        None => format!("[{}]", step.number),
+    };
+    match &step.kind {
+        StepKind::Think { text } => println!("{} {}", style(label).cyan(), text.trim()),
+        StepKind::ToolCall { call } => println!("    {} {} {}", style("→").yellow(), call.name, call.arguments),
+        StepKind::Observe { result } => {
+            let text = result.result.to_string();
+            let shown: String = text.chars().take(MAX_RESULT_CHARS).collect();
+            let more = if shown.len() < text.len() { "…" } else { "" };
+            println!("    {} {}{}", style("←").green(), shown, more);
+        }
+    }
+}
+
+/// Print how a run ended
+fn print_outcome(transcript: &AgentTranscript) {
+    let summary = format!("{} steps, about {} tokens", transcript.replies(), transcript.tokens);
+    let agent = transcript.agent.as_deref().unwrap_or("Agent");
+    let reason = transcript.stopped_because.as_deref().unwrap_or_default();
+    match transcript.status {
+        AgentStatus::Finished => print_success(&format!("{} finished after {}", agent, summary)),
+        AgentStatus::HandedOff => print_info(&format!("{} {} after {}", agent, reason, summary)),
+        status => print_warning(&format!("{} {} after {}: {}", agent, status, summary, reason)),
+    }
+}
+
+/// Print a step event of a conversation's run
+fn print_step_event(event: &Event, conversation_id: &str) {
+    if event.name != names::AGENT_STEP || event.payload["conversation_id"] != conversation_id {
+        return;
+    }
+    if let Ok(step) = serde_json::from_value::<AgentStep>(event.payload["step"].clone()) {
+        print_step(&step, event.payload["agent"].as_str());
+    }
+}
+
+/// Let an agent, or the agent team, work on a task in a conversation,
+/// showing the steps as they are taken
+pub async fn run(
+    chat_service: Arc<ChatService>,
+    conversation_id: &str,
+    task: &str,
+    budget: AgentBudget,
+    team: bool,
+) -> CliResult<()> {
+    if team {
+        print_info("The agent team is working on the task. Tools only run in the desktop app.");
+    } else {
+        print_info(&format!(
+            "Agent working on the task: at most {} steps, {} seconds and {} tokens. Tools only run in the desktop app.",
+            budget.max_steps, budget.max_seconds, budget.max_tokens
+        ));
+    }
+
+    let run = async {
+        if team {
+            TeamRunner::new(get_agent_team().get())
+                .run(&chat_service, conversation_id, task, &NoTools)
+                .await
+        } else {
+            let transcript = AgentRunner::new(budget)
+                .run(&chat_service, conversation_id, task, &NoTools)
+                .await?;
+            Ok(vec![transcript])
+        }
+    };
+    follow(conversation_id, run).await
+}
+
+/// Show the steps of the runs in a conversation as they are taken until they
+/// are over, then how each ended; Ctrl+C aborts the current run after its
+/// current step
+async fn follow(conversation_id: &str, run: impl Future<Output = McpResult<Vec<AgentTranscript>>>) -> CliResult<()> {
+    let mut events = get_event_bus().subscribe(&[Topic::Conversation], 64, Backpressure::DropNewest);
+    tokio::pin!(run);
+    let result = loop {
+        tokio::select! {
+            result = &mut run => break result,
+            Some(event) = events.recv() => print_step_event(&event, conversation_id),
+            Ok(()) = tokio::signal::ctrl_c() => {
+                if let Some(control) = get_agent_runs().get(conversation_id) {
+                    print_warning("Aborting after the current step");
+                    control.abort();
+                }
+            }
+        }
+    };
+    for event in events.drain() {
+        print_step_event(&event, conversation_id);
+    }
+    get_event_bus().unsubscribe(events.id());
+
+    for transcript in &result? {
+        print_outcome(transcript);
+    }
+    Ok(())
+}
+
+/// Show the transcript of the latest agent run in a conversation
+pub async fn transcript(chat_service: Arc<ChatService>, conversation_id: &str, json: bool) -> CliResult<()> {
+    let conversation = chat_service.get_conversation(conversation_id).await?;
+    let transcript = AgentTranscript::latest(&conversation)
+        .ok_or_else(|| CliError::Unknown("No agent has run in this conversation".to_string()))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&transcript)?);
+        return Ok(());
+    }
+
+    print_info(&format!("Task: {}", transcript.task));
+    if !transcript.plan.is_empty() {
+        print_info("Plan:");
+        for (n, item) in transcript.plan.iter().enumerate() {
+            println!("  {}. {}", n + 1, item);
+        }
+    }
+    for step in &transcript.steps {
+        print_step(step, transcript.agent.as_deref());
+    }
+    print_outcome(&transcript);
+    Ok(())
+}
+
+/// List the agents of the team and the rules routing tasks to them
+pub fn list() -> CliResult<()> {
+    let team = get_agent_team().get();
+    if team.agents.is_empty() {
+        print_info("No agents; add one with `mcp agent add`");
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "Agent".to_string(),
+            width: 16,
+            style: None,
+        },
+        TableColumn {
+            title: "Tools".to_string(),
+            width: 24,
+            style: None,
+        },
+        TableColumn {
+            title: "Budget".to_string(),
+            width: 28,
+            style: None,
+        },
+        TableColumn {
+            title: "System prompt".to_string(),
+            width: 40,
+            style: None,
+        },
+    ];
+    let rows: Vec<Vec<String>> = team
+        .agents
+        .iter()
+        .map(|agent| {
+            let tools = if agent.tools.is_empty() {
+                "all".to_string()
+            } else {
+                agent.tools.join(", ")
+            };
+            let budget = format!(
+                "{} steps, {} s, {} tokens",
+                agent.budget.max_steps, agent.budget.max_seconds, agent.budget.max_tokens
+            );
+            vec![
+                agent.name.clone(),
+                tools,
+                budget,
+                agent.system_prompt.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+
+    if team.rules.is_empty() {
+        print_info(&format!("Tasks start with {}", team.agents[0].name));
+    } else {
+        print_info("Routing rules:");
+        for rule in &team.rules {
+            println!("  /{}/ → {}", rule.pattern, rule.agent);
+        }
+        print_info(&format!("Other tasks start with {}", team.agents[0].name));
+    }
+    Ok(())
+}
+
+/// Add an agent to the team, or replace the one of its name
+pub fn add(name: String, prompt: Option<String>, tools: Vec<String>, budget: AgentBudget) -> CliResult<()> {
+    let replaced = get_agent_team().get().agent(&name).is_some();
+    get_agent_team().set_agent(AgentProfile {
+        name: name.clone(),
+        system_prompt: prompt,
+        tools,
+        budget,
+    })?;
+    if replaced {
+        print_success(&format!("Replaced agent {}", name));
+    } else {
+        print_success(&format!("Added agent {}", name));
+    }
+    Ok(())
+}
+
+/// Remove an agent from the team
+pub fn remove(name: &str) -> CliResult<()> {
+    if get_agent_team().remove_agent(name)? {
+        print_success(&format!("Removed agent {} and the rules routing to it", name));
+    } else {
+        print_info(&format!("There is no agent named {}", name));
+    }
+    Ok(())
+}
+
+/// Start tasks matching a pattern with an agent
+pub fn route(pattern: &str, agent: &str) -> CliResult<()> {
+    get_agent_team().add_rule(pattern, agent)?;
+    print_success(&format!("Tasks matching /{}/ start with {}", pattern, agent));
+    Ok(())
+}
+
+/// Remove a routing rule
+pub fn unroute(pattern: &str) -> CliResult<()> {
+    if get_agent_team().remove_rule(pattern)? {
+        print_success(&format!("Removed the rule for /{}/", pattern));
+    } else {
+        print_info(&format!("There is no rule for /{}/", pattern));
+    }
+    Ok(())
+}
+
+/// Print the agent runs of a conversation and the handoffs between them
+pub async fn graph(chat_service: Arc<ChatService>, conversation_id: &str, dot: bool) -> CliResult<()> {
+    let conversation = chat_service.get_conversation(conversation_id).await?;
+    let graph = ExecutionGraph::of(&conversation);
+    if graph.nodes.is_empty() {
+        return Err(CliError::Unknown("No agent has run in this conversation".to_string()));
+    }
+    if dot {
+        print!("{}", graph.to_dot());
+    } else {
+        println!("{}", serde_json::to_string_pretty(&graph)?);
+    }
+    Ok(())
+}
+
+/// First characters of a run ID, enough to name the run
+fn short(run_id: &str) -> String {
+    run_id.chars().take(8).collect()
+}
+
+/// List the agent runs kept for replays, newest first
+pub fn runs(conversation_id: Option<&str>) -> CliResult<()> {
+    let runs = get_agent_run_store().list(conversation_id);
+    if runs.is_empty() {
+        print_info("No agent runs kept");
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "Run".to_string(),
+            width: 10,
+            style: None,
+        },
+        TableColumn {
+            title: "Started".to_string(),
+            width: 18,
+            style: None,
+        },
+        TableColumn {
+            title: "Status".to_string(),
+            width: 14,
+            style: None,
+        },
+        TableColumn {
+            title: "Steps".to_string(),
+            width: 6,
+            style: None,
+        },
+        TableColumn {
+            title: "Task".to_string(),
+            width: 40,
+            style: None,
+        },
+        TableColumn {
+            title: "Replays".to_string(),
+            width: 16,
+            style: None,
+        },
+    ];
+    let rows: Vec<Vec<String>> = runs
+        .iter()
+        .map(|run| {
+            let task = match &run.agent {
+                Some(agent) => format!("{}: {}", agent, run.task),
+                None => run.task.clone(),
+            };
+            let replays = run
+                .replay_of
+                .as_ref()
+                .map(|origin| format!("{} from {}", short(&origin.run_id), origin.from_step))
+                .unwrap_or_default();
+            vec![
+                short(&run.run_id),
+                run.started_at
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string(),
+                run.status.to_string(),
+                run.replies.to_string(),
+                task,
+                replays,
+            ]
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Print a reply of a kept run: what the model was given, what it replied
+/// and what its tool calls returned
+fn print_frame(frame: &ReplayFrame) {
+    println!(
+        "{} {}",
+        style(format!("Step {}", frame.number)).cyan().bold(),
+        style(format!("about {} tokens", frame.tokens)).dim()
+    );
+    match &frame.input {
+        FrameInput::Prompt { text } => {
+            println!("  {}", style("Given the prompt:").dim());
+            for line in text.lines() {
+                println!("    {}", line);
+            }
+        }
+        FrameInput::ToolResults { results } => {
+            println!("  {}", style(format!("Given {} tool results", results.len())).dim());
+        }
+    }
+    println!("  {}", style("Replied:").dim());
+    for line in frame.response.trim().lines() {
+        println!("    {}", line);
+    }
+    for (n, call) in frame.calls.iter().enumerate() {
+        println!("  {} {} {}", style("→").yellow(), call.name, call.arguments);
+        if let Some(result) = frame.results.get(n) {
+            let text = result.result.to_string();
+            let shown: String = text.chars().take(MAX_RESULT_CHARS).collect();
+            let more = if shown.len() < text.len() { "…" } else { "" };
+            println!("  {} {}{}", style("←").green(), shown, more);
+        }
+    }
+}
+
+/// Step through a kept run
+pub fn replay(run_id: &str, step: Option<u32>, json: bool) -> CliResult<()> {
+    let record = get_agent_run_store().get(run_id)?;
+    let frames: Vec<ReplayFrame> = match step {
+        Some(number) => vec![record.frame(number).ok_or_else(|| {
+            CliError::InvalidArgument(format!(
+                "Run {} has steps 1 to {}",
+                record.transcript.run_id,
+                record.transcript.replies()
+            ))
+        })?],
+        None => record.frames(),
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&frames)?);
+        return Ok(());
+    }
+
+    print_info(&format!(
+        "Run {} in conversation {}: {}",
+        record.transcript.run_id, record.conversation_id, record.transcript.task
+    ));
+    if let Some(origin) = &record.replay_of {
+        print_info(&format!("Replays run {} from step {}", origin.run_id, origin.from_step));
+    }
+    for frame in &frames {
+        print_frame(frame);
+    }
+    if step.is_none() {
+        print_outcome(&record.transcript);
+    }
+    Ok(())
+}
+
+/// Run a kept run again from one of its steps, in a copy of its conversation
+pub async fn rerun(
+    chat_service: Arc<ChatService>,
+    run_id: &str,
+    from: u32,
+    mut overrides: ReplayOverrides,
+    max_steps: Option<u32>,
+    max_seconds: Option<u64>,
+    max_tokens: Option<u64>,
+) -> CliResult<()> {
+    let record = get_agent_run_store().get(run_id)?;
+    if max_steps.is_some() || max_seconds.is_some() || max_tokens.is_some() {
+        let budget = record.transcript.budget;
+        overrides.budget = Some(AgentBudget {
+            max_steps: max_steps.unwrap_or(budget.max_steps),
+            max_seconds: max_seconds.unwrap_or(budget.max_seconds),
+            max_tokens: max_tokens.unwrap_or(budget.max_tokens),
+        });
+    }
+
+    let rerun = Rerun::prepare(&chat_service, &record, from, overrides).await?;
+    print_info(&format!(
+        "Running {} again from step {} in conversation {}. Tools only run in the desktop app.",
+        record.transcript.run_id,
+        from,
+        rerun.conversation_id()
+    ));
+    let run = async {
+        let transcript = rerun.run(&chat_service, &NoTools).await?;
+        Ok(vec![transcript])
+    };
+    follow(rerun.conversation_id(), run).await
+}
+
+/// Compare a kept run with another, by default the run it replays or the one
+/// before it in its conversation
+pub fn diff(run_id: &str, other: Option<&str>, json: bool) -> CliResult<()> {
+    let store = get_agent_run_store();
+    let after = store.get(run_id)?;
+    let before = match other {
+        Some(other) => store.get(other)?,
+        None => store
+            .previous(&after)
+            .ok_or_else(|| CliError::InvalidArgument("There is no earlier run to compare with".to_string()))?,
+    };
+    let diff = RunDiff::between(&before, &after);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+
+    for summary in [&diff.before, &diff.after] {
+        print_info(&format!(
+            "{}: {}, {} steps, about {} tokens",
+            summary.run_id, summary.status, summary.replies, summary.tokens
+        ));
+    }
+    match diff.diverged_at {
+        Some(number) => print_warning(&format!("The runs part ways at step {}", number)),
+        None => print_success("The runs did the same"),
+    }
+    for frame in diff.frames.iter().filter(|f| f.change != FrameChange::Same) {
+        println!();
+        match frame.change {
+            FrameChange::Added => println!(
+                "{}",
+                style(format!("Step {} only in the second run", frame.number)).green()
+            ),
+            FrameChange::Removed => println!(
+                "{}",
+                style(format!("Step {} only in the first run", frame.number)).red()
+            ),
+            _ => println!("{}", style(format!("Step {} differs", frame.number)).yellow()),
+        }
+        if let Some(before) = &frame.before {
+            for line in before.response.trim().lines() {
+                println!("{}", style(format!("- {}", line)).red());
+            }
+            for call in &before.calls {
+                println!("{}", style(format!("- → {} {}", call.name, call.arguments)).red());
+            }
+        }
+        if let Some(after) = &frame.after {
+            for line in after.response.trim().lines() {
+                println!("{}", style(format!("+ {}", line)).green());
+            }
+            for call in &after.calls {
+                println!("{}", style(format!("+ → {} {}", call.name, call.arguments)).green());
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use chrono::Utc;
+use console::style;
+use dialoguer::{Confirm, MultiSelect};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_warning};
+use crate::error::{CliError, CliResult};
+use mcp_common::models::MessageRole;
+use mcp_common::service::apply::{self, ApplicationRecord};
+use mcp_common::service::ChatService;
+
+/// Run the apply command
+pub async fn run(
+    chat_service: Arc<ChatService>,
+    conversation_id: String,
+    message_id: Option<String>,
+    repo: Option<PathBuf>,
+    hunks: Vec<usize>,
+    dry_run: bool,
+    yes: bool,
+) -> CliResult<()> {
+    let conversation = chat_service.get_conversation(&conversation_id).await?;
+
+    // Pick the requested message, or the latest assistant message containing changes
+    let (message, patch) = conversation
+        .messages
+        .iter()
+        .rev()
+        .filter(|m| match &message_id {
+            Some(id) => &m.id == id,
+            None => m.role == MessageRole::Assistant,
+        })
+        .map(|m| (m, apply::parse_patch(&m.text())))
+        .find(|(_, patch)| !patch.is_empty())
+        .ok_or_else(|| CliError::InvalidArgument("No diff or file blocks found in the conversation".to_string()))?;
+
+    let root = match repo {
+        Some(path) => path,
+        None => match apply::bound_repository(&conversation_id) {
+            Some(path) => path,
+            None => std::env::current_dir()?,
+        },
+    };
+    let root = root.canonicalize()?;
+
+    print_info(&format!("Changes from message {} against {}", message.id, root.display()));
+    println!();
+
+    // Show the preview
+    let previews = apply::preview(&patch, &root)?;
+    let mut applicable = Vec::new();
+    for file in &previews {
+        println!("{} {}", style(&file.action).cyan(), style(&file.path).bold());
+        for hunk in &file.hunks {
+            let status = if hunk.applies {
+                applicable.push(hunk.index);
+                style("ok".to_string()).green()
+            } else {
+                style("does not apply".to_string()).red()
+            };
+            println!("  [{}] {}", hunk.index, status);
+            for line in hunk.text.lines() {
+                let line = match line.chars().next() {
+                    Some('+') => style(line).green(),
+                    Some('-') => style(line).red(),
+                    _ => style(line).dim(),
+                };
+                println!("      {}", line);
+            }
+        }
+    }
+    println!();
+
+    // Decide which hunks to apply
+    let selection = if !hunks.is_empty() {
+        hunks
+    } else if yes || dry_run {
+        applicable.clone()
+    } else {
+        let labels: Vec<String> = applicable.iter().map(|i| format!("Hunk {}", i)).collect();
+        let defaults = vec![true; labels.len()];
+        MultiSelect::new()
+            .with_prompt("Select hunks to apply")
+            .items(&labels)
+            .defaults(&defaults)
+            .interact()?
+            .into_iter()
+            .map(|i| applicable[i])
+            .collect()
+    };
+
+    if selection.is_empty() {
+        print_warning("No hunks selected");
+        return Ok(());
+    }
+
+    // Always check the full selection before writing
+    apply::apply(&patch, &root, Some(&selection), true)?;
+    if dry_run {
+        print_success(&format!("{} hunk(s) apply cleanly (dry run)", selection.len()));
+        return Ok(());
+    }
+
+    if !yes
+        && !Confirm::new()
+            .with_prompt(format!("Apply {} hunk(s)?", selection.len()))
+            .default(true)
+            .interact()?
+    {
+        return Err(CliError::Cancelled);
+    }
+
+    let report = apply::apply(&patch, &root, Some(&selection), false)?;
+    let record = ApplicationRecord {
+        message_id: message.id.clone(),
+        repository: root,
+        files: report.files.clone(),
+        hunks: report.applied_hunks.clone(),
+        applied_at: Utc::now(),
+    };
+    apply::record_application(&chat_service, &conversation_id, &record).await?;
+
+    print_success(&format!(
+        "Applied {} hunk(s) to {} file(s)",
+        report.applied_hunks.len(),
+        report.files.len()
+    ));
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success};
+use crate::error::CliResult;
+use mcp_common::service::ChatService;
+use mcp_common::tr;
+
+/// Archive or unarchive conversations
+pub async fn run(chat_service: Arc<ChatService>, conversation_ids: Vec<String>, unarchive: bool) -> CliResult<()> {
+    let changed = chat_service.archive_conversations(&conversation_ids, !unarchive).await?;
+
+    if changed == 0 {
+        print_info(&tr!("conversation-nothing-changed"));
+    } else if unarchive {
+        print_success(&tr!("conversation-unarchived", count = changed));
+    } else {
+        print_success(&tr!("conversation-archived", count = changed));
+    }
+    Ok(())
+}
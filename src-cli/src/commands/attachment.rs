@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+
+use crate::display::{print_info, print_success, print_table, TableColumn};
+use crate::error::CliResult;
+use mcp_common::config::get_attachment_store;
+
+/// Format a byte count for display
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// Add a file to a conversation
+pub fn add(conversation_id: &str, path: PathBuf) -> CliResult<()> {
+    let attachment = get_attachment_store().add_file(conversation_id, &path)?;
+    print_success(&format!(
+        "Attached {} ({}, {}) as {}",
+        attachment.file_name,
+        attachment.media_type,
+        format_size(attachment.size),
+        attachment.id
+    ));
+    Ok(())
+}
+
+/// List attachments, optionally for one conversation
+pub fn list(conversation_id: Option<String>) -> CliResult<()> {
+    let store = get_attachment_store();
+    let attachments = store.list(conversation_id.as_deref());
+    if attachments.is_empty() {
+        print_info("No attachments");
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "ID".to_string(),
+            width: 38,
+            style: None,
+        },
+        TableColumn {
+            title: "File".to_string(),
+            width: 30,
+            style: None,
+        },
+        TableColumn {
+            title: "Type".to_string(),
+            width: 20,
+            style: None,
+        },
+        TableColumn {
+            title: "Size".to_string(),
+            width: 10,
+            style: None,
+        },
+    ];
+
+    let rows: Vec<Vec<String>> = attachments
+        .iter()
+        .map(|a| {
+            vec![
+                a.id.clone(),
+                a.file_name.clone(),
+                a.media_type.clone(),
+                format_size(a.size),
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+
+    match conversation_id {
+        Some(id) => {
+            let total = store.usage().get(&id).copied().unwrap_or(0);
+            print_info(&format!("{} attached to {}", format_size(total), id));
+        }
+        None => {
+            let total: u64 = store.usage().values().sum();
+            print_info(&format!("{} in {} attachment(s)", format_size(total), attachments.len()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Open an attachment with the default application
+pub fn open(id: &str) -> CliResult<()> {
+    get_attachment_store().open(id)?;
+    Ok(())
+}
+
+/// Delete an attachment
+pub fn delete(id: &str) -> CliResult<()> {
+    get_attachment_store().delete(id)?;
+    print_success(&format!("Attachment {} deleted", id));
+    Ok(())
+}
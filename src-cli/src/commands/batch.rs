@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::display::{print_info, print_success, print_warning, show_spinner};
+use crate::error::{CliError, CliResult};
+use mcp_common::config::get_settings;
+use mcp_common::service::batch::{load_items, BatchBackend, BatchOptions, BatchOutput, BatchRunner};
+use mcp_common::service::experiments::get_experiment_store;
+use mcp_common::service::ChatService;
+
+/// Run the batch run command
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    chat_service: Arc<ChatService>,
+    input: PathBuf,
+    output: Option<PathBuf>,
+    provider: bool,
+    model: Option<String>,
+    concurrency: usize,
+    poll_interval: u64,
+    experiment: Option<String>,
+) -> CliResult<()> {
+    let mut items = load_items(&input)?;
+    if items.is_empty() {
+        return Err(CliError::InvalidArgument(format!("{} contains no prompts", input.display())));
+    }
+
+    // Remember which variant each prompt got so results can be attributed
+    let assigned: HashMap<String, usize> = match &experiment {
+        Some(experiment) => {
+            let variants = get_experiment_store().apply_to_batch(experiment, &mut items)?;
+            print_info(&format!("Alternating variants of experiment '{}'", experiment));
+            items.iter().map(|item| item.custom_id.clone()).zip(variants).collect()
+        }
+        None => HashMap::new(),
+    };
+
+    let options = BatchOptions {
+        backend: if provider { BatchBackend::Provider } else { BatchBackend::Local },
+        model: model.unwrap_or_else(|| get_settings().lock().unwrap().api.model.clone()),
+        concurrency,
+        poll_interval: Duration::from_secs(poll_interval),
+        ..BatchOptions::default()
+    };
+
+    // Without an output file, results go into conversations
+    let output = match output {
+        Some(path) => BatchOutput::File(path),
+        None => BatchOutput::Conversations,
+    };
+
+    print_info(&format!("Running {} prompt(s) from {}", items.len(), input.display()));
+
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    let runner = BatchRunner::new(chat_service).with_progress(progress_tx);
+
+    let spinner = show_spinner();
+    let batch = runner.run(items, &options, &output);
+    tokio::pin!(batch);
+
+    // Update the spinner while the batch runs
+    let results = loop {
+        tokio::select! {
+            result = &mut batch => break result,
+            Some(progress) = progress_rx.recv() => {
+                spinner.set_message(&format!(
+                    "{}/{} done, {} failed",
+                    progress.succeeded + progress.failed,
+                    progress.total,
+                    progress.failed
+                ));
+            }
+        }
+    };
+
+    let results = match results {
+        Ok(results) => {
+            spinner.success("Batch complete");
+            results
+        }
+        Err(e) => {
+            spinner.error(&format!("Batch failed: {}", e));
+            return Err(e.into());
+        }
+    };
+
+    if let Some(experiment) = &experiment {
+        let variants: Vec<usize> = results
+            .iter()
+            .map(|r| assigned.get(&r.custom_id).copied().unwrap_or_default())
+            .collect();
+        get_experiment_store().record_batch(experiment, &results, &variants)?;
+    }
+
+    let failed: Vec<_> = results.iter().filter(|r| r.error.is_some()).collect();
+    for result in &failed {
+        print_warning(&format!(
+            "{}: {}",
+            result.custom_id,
+            result.error.as_deref().unwrap_or_default()
+        ));
+    }
+
+    match &output {
+        BatchOutput::File(path) => print_success(&format!(
+            "{} succeeded, {} failed; results written to {}",
+            results.len() - failed.len(),
+            failed.len(),
+            path.display()
+        )),
+        BatchOutput::Conversations => print_success(&format!(
+            "{} succeeded, {} failed; results saved as conversations",
+            results.len() - failed.len(),
+            failed.len()
+        )),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use crate::display::{print_error, print_info, print_success, print_table, print_warning, show_spinner, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::service::bench::{BenchmarkReport, BenchmarkRunner};
+use mcp_common::service::ChatService;
+
+/// Regressions larger than this (in percent) are reported as warnings
+const REGRESSION_THRESHOLD_PERCENT: f64 = 10.0;
+
+/// Run the bench command
+pub async fn run(
+    chat_service: Arc<ChatService>,
+    models: Vec<String>,
+    json: bool,
+    no_save: bool,
+) -> CliResult<()> {
+    // Resolve the models to benchmark
+    let available = chat_service.available_models().await?;
+    let selected: Vec<_> = if models.is_empty() {
+        available
+    } else {
+        let mut selected = Vec::new();
+        for name in &models {
+            match available.iter().find(|m| &m.id == name || &m.name == name) {
+                Some(model) => selected.push(model.clone()),
+                None => return Err(CliError::InvalidArgument(format!("Unknown model: {}", name))),
+            }
+        }
+        selected
+    };
+
+    if selected.is_empty() {
+        print_info("No models available to benchmark");
+        return Ok(());
+    }
+
+    let runner = BenchmarkRunner::new(chat_service);
+
+    let spinner = show_spinner();
+    spinner.set_message(&format!("Benchmarking {} model(s)...", selected.len()));
+
+    let report = match runner.run(&selected).await {
+        Ok(report) => {
+            spinner.success("Benchmark complete");
+            report
+        }
+        Err(e) => {
+            spinner.error(&format!("Benchmark failed: {}", e));
+            return Err(e.into());
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report)?;
+    }
+
+    // Compare against the previous run before saving this one
+    for regression in runner.compare_with_history(&report) {
+        if regression.decode_change_percent <= -REGRESSION_THRESHOLD_PERCENT
+            || regression.ttft_change_percent >= REGRESSION_THRESHOLD_PERCENT
+        {
+            print_warning(&format!(
+                "{}: decode {:+.1}%, time to first token {:+.1}% compared to the previous run",
+                regression.model_id, regression.decode_change_percent, regression.ttft_change_percent
+            ));
+        }
+    }
+
+    if !no_save {
+        match runner.save_to_history(&report) {
+            Ok(_) => print_success("Benchmark saved to history"),
+            Err(e) => print_error(&format!("Failed to save benchmark history: {}", e)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Print a benchmark report as a table
+fn print_report(report: &BenchmarkReport) -> CliResult<()> {
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+
+    let columns = vec![
+        column("Model", 30),
+        column("TTFT (ms)", 10),
+        column("Prefill tok/s", 14),
+        column("Decode tok/s", 13),
+        column("Peak mem (MB)", 14),
+        column("Failures", 8),
+    ];
+
+    let rows: Vec<Vec<String>> = report
+        .models
+        .iter()
+        .map(|model| {
+            let failures = model.measurements.iter().filter(|m| m.error.is_some()).count();
+            vec![
+                model.model_id.clone(),
+                format!("{:.0}", model.avg_time_to_first_token_ms()),
+                format!("{:.1}", model.avg_prefill_tokens_per_sec()),
+                format!("{:.1}", model.avg_decode_tokens_per_sec()),
+                model
+                    .peak_memory_bytes
+                    .map(|b| (b / (1024 * 1024)).to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                failures.to_string(),
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
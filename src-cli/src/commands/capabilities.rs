@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_table, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::service::capabilities::{CapabilityTable, Feature};
+use mcp_common::service::ChatService;
+
+/// Parse "on"/"off" and the like
+fn parse_switch(value: &str) -> CliResult<bool> {
+    match value.to_lowercase().as_str() {
+        "on" | "yes" | "true" => Ok(true),
+        "off" | "no" | "false" => Ok(false),
+        _ => Err(CliError::InvalidArgument(format!(
+            "Expected on or off, got '{}'",
+            value
+        ))),
+    }
+}
+
+/// List what models can do, with the user's corrections applied
+pub async fn list(chat_service: Arc<ChatService>, model: Option<String>, json: bool) -> CliResult<()> {
+    let table = CapabilityTable::new();
+    let models: Vec<_> = chat_service
+        .available_models()
+        .await?
+        .into_iter()
+        .filter(|m| model.as_ref().is_none_or(|prefix| m.id.starts_with(prefix.as_str())))
+        .collect();
+    if json {
+        let described: Vec<serde_json::Value> = models
+            .iter()
+            .map(|m| serde_json::json!({ "model": m.id, "provider": m.provider, "capabilities": table.for_model(m) }))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "models": described,
+                "overrides": table.overrides(),
+            }))?
+        );
+        return Ok(());
+    }
+    if models.is_empty() {
+        print_info("No models match");
+        return Ok(());
+    }
+
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let mut columns = vec![column("Model", 32), column("Provider", 12)];
+    columns.extend(Feature::ALL.iter().map(|f| column(&f.to_string(), 10)));
+    let rows: Vec<Vec<String>> = models
+        .iter()
+        .map(|m| {
+            let capabilities = table.for_model(m);
+            let mut row = vec![m.id.clone(), m.provider.clone()];
+            row.extend(Feature::ALL.iter().map(|f| {
+                let supported = f.supported_by(&capabilities);
+                let mark = if supported { "yes" } else { "no" };
+                if supported == f.supported_by(&m.capabilities) {
+                    mark.to_string()
+                } else {
+                    format!("{}*", mark)
+                }
+            }));
+            row
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+
+    let overrides = table.overrides();
+    if !overrides.is_empty() {
+        print_info("* corrected by you:");
+        for (target, features) in overrides {
+            let list: Vec<String> = features
+                .iter()
+                .map(|(f, on)| format!("{} {}", f, if *on { "on" } else { "off" }))
+                .collect();
+            println!("  {}: {}", target, list.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Say whether a provider, or models by ID prefix, have a feature
+pub fn set(target: &str, feature: &str, state: &str) -> CliResult<()> {
+    let feature: Feature = feature.parse()?;
+    let supported = parse_switch(state)?;
+    CapabilityTable::new().set(target, feature, supported)?;
+    print_success(&format!(
+        "{}: {} {}",
+        target,
+        feature,
+        if supported { "on" } else { "off" }
+    ));
+    Ok(())
+}
+
+/// Drop corrections of a provider or model prefix
+pub fn reset(target: &str, feature: Option<String>) -> CliResult<()> {
+    let feature = feature.map(|f| f.parse::<Feature>()).transpose()?;
+    if CapabilityTable::new().reset(target, feature)? {
+        print_success(&format!("{} uses the capabilities its models report again", target));
+    } else {
+        print_info(&format!("No corrections for {}", target));
+    }
+    Ok(())
+}
+
+/// Ask for replies in JSON in a conversation, or stop asking
+pub async fn json_mode(chat_service: Arc<ChatService>, conversation_id: &str, state: &str) -> CliResult<()> {
+    let enabled = parse_switch(state)?;
+    chat_service.set_json_mode(conversation_id, enabled).await?;
+    if !enabled {
+        print_success("Replies are free text again");
+    } else if chat_service.capabilities(conversation_id).await?.json_mode {
+        print_success("Replies are held to JSON");
+    } else {
+        print_success("The model has no JSON mode; JSON is asked for in the prompt");
+    }
+    Ok(())
+}
@@ -0,0 +1,305 @@
+use crate::display::{print_info, print_success, print_table, print_warning, show_spinner_with_message, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::models::huggingface::{self, HardwareLimits};
+use mcp_common::models::registry::{get_model_registry, CatalogFormat, CatalogSource, CatalogTrust};
+use mcp_common::models::ChatTemplate;
+
+fn trust_name(trust: CatalogTrust) -> &'static str {
+    match trust {
+        CatalogTrust::Trusted => "trusted",
+        CatalogTrust::Verify => "verify",
+        CatalogTrust::Blocked => "blocked",
+    }
+}
+
+fn print_refresh_result(catalog: &CatalogSource) {
+    match &catalog.last_error {
+        Some(error) => print_warning(&format!("Could not fetch {}: {}", catalog.name, error)),
+        None => print_success(&format!("Catalog {} is up to date", catalog.name)),
+    }
+}
+
+/// List model catalogs and the models they offer
+pub fn list() -> CliResult<()> {
+    let registry = get_model_registry();
+    let catalogs = registry.catalogs();
+    if catalogs.is_empty() {
+        print_info("No model catalogs; add one with `mcp model add-catalog <url>`");
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "ID".to_string(),
+            width: 16,
+            style: None,
+        },
+        TableColumn {
+            title: "URL".to_string(),
+            width: 50,
+            style: None,
+        },
+        TableColumn {
+            title: "Trust".to_string(),
+            width: 8,
+            style: None,
+        },
+        TableColumn {
+            title: "Models".to_string(),
+            width: 7,
+            style: None,
+        },
+        TableColumn {
+            title: "Refreshed".to_string(),
+            width: 17,
+            style: None,
+        },
+    ];
+    let entries = registry.entries();
+    let rows: Vec<Vec<String>> = catalogs
+        .iter()
+        .map(|c| {
+            let refreshed = match (&c.last_error, c.refreshed_at) {
+                (Some(_), _) => "failed".to_string(),
+                (None, Some(at)) => at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string(),
+                (None, None) => "never".to_string(),
+            };
+            vec![
+                c.id.clone(),
+                c.url.clone(),
+                trust_name(c.trust).to_string(),
+                entries.iter().filter(|e| e.catalog == c.id).count().to_string(),
+                refreshed,
+            ]
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+
+    for catalog in catalogs.iter().filter(|c| c.last_error.is_some()) {
+        print_warning(&format!("{}: {}", catalog.id, catalog.last_error.as_deref().unwrap_or_default()));
+    }
+    if catalogs.iter().any(|c| c.trust == CatalogTrust::Verify) {
+        print_info("Catalogs set to verify only offer models with a checksum");
+    }
+    Ok(())
+}
+
+/// Add a catalog and fetch it
+pub async fn add(url: &str, name: Option<String>, format: &str, trust: &str, token: Option<String>) -> CliResult<()> {
+    let format: CatalogFormat = format.parse()?;
+    let trust: CatalogTrust = trust.parse()?;
+    // Name the catalog after its host unless told otherwise
+    let name = name.unwrap_or_else(|| {
+        url.split("://")
+            .nth(1)
+            .and_then(|rest| rest.split('/').next())
+            .filter(|host| !host.is_empty())
+            .unwrap_or("catalog")
+            .to_string()
+    });
+
+    let registry = get_model_registry();
+    let catalog = registry.add_catalog(&name, url, format, trust)?;
+    if token.is_some() {
+        registry.set_token(&catalog.id, token.as_deref())?;
+    }
+
+    let spinner = show_spinner_with_message(&format!("Fetching {}...", url));
+    match registry.refresh(&catalog.id).await {
+        Ok(count) => spinner.success(&format!("Added catalog {} with {} models", catalog.id, count)),
+        Err(e) => spinner.warning(&format!("Added catalog {}, but it could not be fetched yet: {}", catalog.id, e)),
+    }
+    Ok(())
+}
+
+/// Remove a catalog
+pub fn remove(id: &str) -> CliResult<()> {
+    get_model_registry().remove_catalog(id)?;
+    print_success(&format!("Removed catalog {}; downloaded models were kept", id));
+    Ok(())
+}
+
+/// Change how far a catalog is trusted
+pub fn set_trust(id: &str, trust: &str) -> CliResult<()> {
+    let catalog = get_model_registry().set_trust(id, trust.parse()?)?;
+    print_success(&format!("Catalog {} is now {}", catalog.id, trust_name(catalog.trust)));
+    Ok(())
+}
+
+/// Change how often a catalog is fetched
+pub fn set_refresh_hours(id: &str, hours: u32) -> CliResult<()> {
+    let catalog = get_model_registry().set_refresh_hours(id, hours)?;
+    print_success(&format!("Catalog {} is fetched every {} hours", catalog.id, catalog.refresh_hours));
+    Ok(())
+}
+
+/// Store or remove the access token of a catalog
+pub fn set_token(id: &str, token: Option<String>) -> CliResult<()> {
+    let catalog = get_model_registry().set_token(id, token.as_deref())?;
+    if catalog.has_token {
+        print_success(&format!("Token for {} saved", catalog.id));
+    } else {
+        print_success(&format!("Token for {} removed", catalog.id));
+    }
+    Ok(())
+}
+
+/// Fetch one catalog or all of them now
+pub async fn refresh(id: Option<String>) -> CliResult<()> {
+    let registry = get_model_registry();
+    let spinner = show_spinner_with_message("Refreshing model catalogs...");
+    match &id {
+        Some(id) => {
+            // Make sure the catalog exists; fetch errors are shown below
+            registry.catalog(id)?;
+            let _ = registry.refresh(id).await;
+        }
+        None => {
+            registry.refresh_all(true).await;
+        }
+    }
+    spinner.abandon();
+
+    for catalog in registry.catalogs().iter().filter(|c| id.as_deref().is_none_or(|id| c.id == id)) {
+        print_refresh_result(catalog);
+    }
+    Ok(())
+}
+
+fn format_size(bytes: Option<u64>) -> String {
+    match bytes {
+        Some(bytes) if bytes >= 1 << 30 => format!("{:.1} GB", bytes as f64 / (1u64 << 30) as f64),
+        Some(bytes) => format!("{} MB", bytes >> 20),
+        None => "?".to_string(),
+    }
+}
+
+/// Search the Hugging Face Hub for GGUF models that fit this machine
+pub async fn search_hf(query: &str, limit: usize, max_size_gb: Option<f64>, all_sizes: bool, json: bool) -> CliResult<()> {
+    let limits = match (all_sizes, max_size_gb) {
+        (true, _) => HardwareLimits::unlimited(),
+        (false, Some(gb)) => HardwareLimits {
+            max_file_bytes: (gb * (1u64 << 30) as f64) as u64,
+        },
+        (false, None) => HardwareLimits::detect(),
+    };
+
+    let spinner = show_spinner_with_message(&format!("Searching Hugging Face for \"{}\"...", query));
+    let models = match huggingface::search(query, limits, limit).await {
+        Ok(models) => {
+            spinner.abandon();
+            models
+        }
+        Err(e) => {
+            spinner.error(&format!("Search failed: {}", e));
+            return Err(e.into());
+        }
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&models)?);
+        return Ok(());
+    }
+    if models.is_empty() {
+        print_info("No GGUF models found that fit this machine; search all sizes with `--all-sizes`");
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "Repository".to_string(),
+            width: 44,
+            style: None,
+        },
+        TableColumn {
+            title: "Suggested file".to_string(),
+            width: 40,
+            style: None,
+        },
+        TableColumn {
+            title: "Size".to_string(),
+            width: 9,
+            style: None,
+        },
+        TableColumn {
+            title: "License".to_string(),
+            width: 14,
+            style: None,
+        },
+        TableColumn {
+            title: "Downloads".to_string(),
+            width: 10,
+            style: None,
+        },
+    ];
+    let rows: Vec<Vec<String>> = models
+        .iter()
+        .map(|m| {
+            let file = m.best_file();
+            let license = match (&m.license, m.gated) {
+                (Some(license), true) => format!("{} (gated)", license),
+                (Some(license), false) => license.clone(),
+                (None, true) => "gated".to_string(),
+                (None, false) => "unknown".to_string(),
+            };
+            vec![
+                m.repo.clone(),
+                file.map(|f| f.path.clone()).unwrap_or_default(),
+                format_size(file.and_then(|f| f.size)),
+                license,
+                m.downloads.to_string(),
+            ]
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+    print_info("Check each model's license before use; add one with `mcp model add-hf <repository> <file>`");
+    Ok(())
+}
+
+/// Register a GGUF file from the Hub as a local model
+pub async fn add_hf(repo: &str, path: Option<String>, chat_template: Option<&str>) -> CliResult<()> {
+    let chat_template = chat_template
+        .map(|name| name.parse::<ChatTemplate>())
+        .transpose()
+        .map_err(CliError::InvalidArgument)?;
+
+    let path = match path {
+        Some(path) => path,
+        None => {
+            // Pick the largest file that fits, as the search suggests
+            let model = huggingface::model_info(repo).await?;
+            model
+                .best_file()
+                .map(|f| f.path.clone())
+                .ok_or_else(|| CliError::InvalidArgument(format!("{} has no GGUF file that fits this machine", repo)))?
+        }
+    };
+
+    let spinner = show_spinner_with_message(&format!("Reading {}...", path));
+    match huggingface::register(repo, &path, chat_template).await {
+        Ok(entry) => {
+            let license = entry.entry.license.as_deref().unwrap_or("unknown license");
+            let template = entry.entry.chat_template.as_deref().unwrap_or("detected when loaded");
+            spinner.success(&format!(
+                "Registered {} ({}, {} tokens context, {} chat template); download it from the app's model list",
+                entry.entry.id, license, entry.entry.context_size, template
+            ));
+            Ok(())
+        }
+        Err(e) => {
+            spinner.error(&format!("Could not register {}: {}", path, e));
+            Err(e.into())
+        }
+    }
+}
+
+/// Store or remove the Hugging Face token
+pub fn set_hf_token(token: Option<String>) -> CliResult<()> {
+    huggingface::set_token(token.as_deref())?;
+    if token.is_some() {
+        print_success("Hugging Face token saved");
+    } else {
+        print_success("Hugging Face token removed");
+    }
+    Ok(())
+}
@@ -1,10 +1,14 @@
-use dialoguer::Input;
+use dialoguer::{Confirm, Input};
 use std::io::{self, Write};
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
 use crate::error::CliResult;
 use crate::display::{format_message, print_error, print_info, MessageFormat, show_spinner};
+use console::style;
+use mcp_common::rag::citations::Citations;
+use mcp_common::service::estimate::format_cost;
+use mcp_common::service::stream::{BlockKind, StreamEvent, Usage};
 use mcp_common::{error::McpResult, models::Message, service::ChatService};
 
 /// Run the chat command
@@ -67,6 +71,11 @@ pub async fn run(
         }
     };
     
+    if !confirm_cost(&chat_service, &conversation_id, &message_content).await? {
+        print_info("Message not sent");
+        return Ok(());
+    }
+    
     // Send message
     let spinner = show_spinner();
     spinner.set_message("Sending message...");
@@ -74,7 +83,7 @@ pub async fn run(
     if stream {
         // Stream response
         let mut stream = chat_service
-            .send_message_streaming(&conversation_id, &message_content)
+            .send_message_events(&conversation_id, &message_content)
             .await?;
         
         spinner.info("Response:");
@@ -83,22 +92,30 @@ pub async fn run(
         println!("{}", format_message(&Message::user(&message_content), MessageFormat::Colored));
         println!();
         
-        // Print assistant response as it streams
-        let mut full_message = String::new();
+        // Print assistant response as it streams, fences and tool calls included
+        let mut code_block = None;
+        let mut usage = None;
         
         while let Some(result) = stream.recv().await {
             match result {
-                Ok(message) => {
-                    // Get the new text
-                    let text = message.text();
-                    
-                    // Only print the new part since the last update
-                    if text.len() > full_message.len() {
-                        let new_text = &text[full_message.len()..];
-                        print!("{}", new_text);
-                        io::stdout().flush()?;
-                        full_message = text;
+                Ok(event) => {
+                    match event {
+                        StreamEvent::TextDelta { text, .. } => print!("{}", text),
+                        StreamEvent::ContentBlockStart { index, block: BlockKind::Code { language } } => {
+                            code_block = Some(index);
+                            println!("```{}", language.unwrap_or_default());
+                        }
+                        StreamEvent::ContentBlockStart { block: BlockKind::ToolCall { name, .. }, .. } => {
+                            println!("{}", style(format!("[calling {}]", name)).dim());
+                        }
+                        StreamEvent::ContentBlockStop { index } if code_block == Some(index) => {
+                            code_block = None;
+                            println!("```");
+                        }
+                        StreamEvent::Usage(final_usage) => usage = Some(final_usage),
+                        _ => {}
                     }
+                    io::stdout().flush()?;
                 }
                 Err(e) => {
                     print_error(&format!("Error receiving message: {}", e));
@@ -108,6 +125,24 @@ pub async fn run(
         }
         
         println!("\n");
+        if let Some(usage) = usage {
+            println!("{}", style(format_usage(&usage)).dim());
+        }
+        
+        // Citations are worked out once the whole reply is in
+        let conversation = chat_service.get_conversation(&conversation_id).await?;
+        let footnotes = conversation
+            .messages
+            .last()
+            .and_then(Citations::of)
+            .map(|c| c.footnotes())
+            .unwrap_or_default();
+        if !footnotes.is_empty() {
+            for footnote in footnotes {
+                println!("{}", style(footnote).dim());
+            }
+            println!();
+        }
     } else {
         // Regular response
         match chat_service.send_message(&conversation_id, &message_content).await {
@@ -131,3 +166,29 @@ pub async fn run(
     
     Ok(())
 }
+
+/// Tokens and cost of a reply, e.g. "1200 in, 85 out, ≈ $0.01"
+fn format_usage(usage: &Usage) -> String {
+    format!(
+        "{} in, {} out, {}{}",
+        usage.input_tokens,
+        usage.output_tokens,
+        format_cost(usage.cost, &usage.currency),
+        if usage.estimated { " (estimated)" } else { "" }
+    )
+}
+
+/// Ask before sending a message projected to cost more than the configured
+/// threshold; returns whether to send
+pub async fn confirm_cost(chat_service: &ChatService, conversation_id: &str, content: &str) -> CliResult<bool> {
+    let estimate = chat_service.estimate_cost(conversation_id, content).await?;
+    if !estimate.needs_confirmation {
+        return Ok(true);
+    }
+    
+    let prompt = format!(
+        "This message costs {} ({} tokens in, ~{} out on {}). Send it?",
+        estimate.label, estimate.input_tokens, estimate.output_tokens, estimate.model
+    );
+    Ok(Confirm::new().with_prompt(prompt).default(false).interact()?)
+}
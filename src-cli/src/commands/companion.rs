@@ -0,0 +1,42 @@
+use crate::display::{print_info, print_success, print_table, TableColumn};
+use crate::error::CliResult;
+use mcp_common::webhooks::companion::get_companion_store;
+
+/// List paired browser extensions
+pub fn list() -> CliResult<()> {
+    let extensions = get_companion_store().list();
+    if extensions.is_empty() {
+        print_info("No paired extensions; start `mcp daemon` and pair from the extension");
+        return Ok(());
+    }
+
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let columns = vec![column("ID", 10), column("Name", 24), column("Origin", 50), column("Last used", 18)];
+    let rows: Vec<Vec<String>> = extensions
+        .iter()
+        .map(|e| {
+            vec![
+                e.id.chars().take(8).collect(),
+                e.name.clone(),
+                e.origin.clone(),
+                e.last_used_at
+                    .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "never".to_string()),
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Unpair an extension; its token stops working
+pub fn revoke(id: &str) -> CliResult<()> {
+    let extension = get_companion_store().revoke(id)?;
+    print_success(&format!("Unpaired '{}'", extension.name));
+    Ok(())
+}
@@ -0,0 +1,329 @@
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Path, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use log::{info, warn};
+use serde::Deserialize;
+use std::future::IntoFuture;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::display::print_info;
+use crate::error::{to_cli_error, CliResult};
+use mcp_common::error::{McpError, McpResult};
+use mcp_common::events::{get_event_bus, names, Backpressure, Topic};
+use mcp_common::feeds::spawn_feed_scheduler;
+use mcp_common::reminders::{spawn_reminder_scheduler, Reminder};
+use mcp_common::service::ChatService;
+use mcp_common::snapshots::spawn_share_expiry;
+use mcp_common::webhooks::companion::{
+    self, get_companion_store, Companion, CompanionStore, PageSelection, RateLimiter,
+};
+use mcp_common::webhooks::inbound::{get_inbound_store, parse_body, InboundGateway, MAX_BODY_BYTES};
+use mcp_common::webhooks::spawn_webhook_dispatcher;
+
+/// Requests a paired extension can make per minute
+const COMPANION_REQUESTS_PER_MINUTE: usize = 20;
+
+/// Pairings that can be started per minute, across all origins
+const PAIRINGS_PER_MINUTE: usize = 5;
+
+/// Print reminders as they come due
+fn spawn_reminder_printer() -> tokio::task::JoinHandle<()> {
+    let mut subscription = get_event_bus().subscribe(&[Topic::System], 64, Backpressure::DropNewest);
+    tokio::spawn(async move {
+        while let Some(event) = subscription.recv().await {
+            if event.name != names::REMINDER_DUE {
+                continue;
+            }
+            match serde_json::from_value::<Reminder>(event.payload) {
+                Ok(reminder) => print_info(&format!("Reminder: {} — {}", reminder.body(), reminder.link())),
+                Err(e) => warn!("Unreadable reminder: {}", e),
+            }
+        }
+    })
+}
+
+/// Run until interrupted: deliver outgoing webhooks, feed digests and
+/// reminders, revoke expired shares, add messages posted to
+/// `/inbound/<route>` to their conversations, and serve the browser extension under `/companion`
+///
+/// The extension is served on its own listener on the loopback address,
+/// whatever address the inbound endpoint listens on.
+pub async fn run(chat_service: Arc<ChatService>, listen: SocketAddr, companion_port: u16) -> CliResult<()> {
+    let dispatcher = spawn_webhook_dispatcher(Duration::from_secs(30));
+    let feeds = spawn_feed_scheduler(chat_service.clone(), Duration::from_secs(60));
+    let shares = spawn_share_expiry(Duration::from_secs(3600));
+    let reminders = spawn_reminder_scheduler(Duration::from_secs(60));
+    let reminder_printer = spawn_reminder_printer();
+    let gateway = Arc::new(InboundGateway::new(get_inbound_store(), chat_service.clone()));
+    let companion = Arc::new(CompanionState {
+        store: get_companion_store(),
+        companion: Companion::new(chat_service),
+        requests: RateLimiter::new(COMPANION_REQUESTS_PER_MINUTE, Duration::from_secs(60)),
+        pairings: RateLimiter::new(PAIRINGS_PER_MINUTE, Duration::from_secs(60)),
+    });
+
+    let inbound_routes = Router::new()
+        .route("/inbound/:route", post(inbound))
+        .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+        .with_state(gateway);
+    let companion_routes = Router::new()
+        .route("/companion/pair", post(pair).options(preflight))
+        .route("/companion/pair/confirm", post(confirm_pairing).options(preflight))
+        .route("/companion/summarize", post(summarize).options(preflight))
+        .route("/companion/ask", post(ask).options(preflight))
+        .route("/companion/conversations", post(open_conversation).options(preflight))
+        .layer(DefaultBodyLimit::max(companion::MAX_BODY_BYTES))
+        .with_state(companion);
+
+    let companion_listen = SocketAddr::from((Ipv4Addr::LOCALHOST, companion_port));
+    let listener = tokio::net::TcpListener::bind(listen).await?;
+    let companion_listener = tokio::net::TcpListener::bind(companion_listen).await?;
+    if !listen.ip().is_loopback() {
+        warn!("The inbound endpoint is reachable from other machines; keep route tokens secret");
+    }
+    print_info(&format!(
+        "Accepting inbound messages on http://{}/inbound/<route> and the browser extension on http://{}/companion; Ctrl+C stops",
+        listen, companion_listen
+    ));
+
+    let shutdown = || async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    tokio::try_join!(
+        axum::serve(listener, inbound_routes).with_graceful_shutdown(shutdown()).into_future(),
+        axum::serve(companion_listener, companion_routes).with_graceful_shutdown(shutdown()).into_future(),
+    )
+    .map_err(to_cli_error)?;
+
+    dispatcher.abort();
+    feeds.abort();
+    shares.abort();
+    reminders.abort();
+    reminder_printer.abort();
+    info!("Daemon stopped");
+    Ok(())
+}
+
+/// `POST /inbound/<route>` with `Authorization: Bearer <token>`
+async fn inbound(
+    State(gateway): State<Arc<InboundGateway>>,
+    Path(route): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let header_value = |name| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or("");
+    let token = header_value(header::AUTHORIZATION).strip_prefix("Bearer ").unwrap_or("");
+
+    let result = match parse_body(header_value(header::CONTENT_TYPE), &body) {
+        Ok(message) => gateway.deliver(&route, token, &message).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(receipt) => Json(receipt).into_response(),
+        Err(e) => {
+            warn!("Inbound message to {} refused: {}", route, e);
+            error_response(&e)
+        }
+    }
+}
+
+/// JSON error with a status
+fn status_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+/// JSON error with the status matching the error
+fn error_response(e: &McpError) -> Response {
+    let status = match e {
+        McpError::Authentication(_) => StatusCode::UNAUTHORIZED,
+        McpError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+        McpError::RateLimit(_) => StatusCode::TOO_MANY_REQUESTS,
+        _ => StatusCode::BAD_GATEWAY,
+    };
+    status_response(status, &e.to_string())
+}
+
+/// Shared by the browser extension endpoints
+struct CompanionState {
+    store: Arc<CompanionStore>,
+    companion: Companion,
+    requests: RateLimiter,
+    pairings: RateLimiter,
+}
+
+/// Body of `/companion/pair`
+#[derive(Deserialize)]
+struct PairRequest {
+    name: String,
+}
+
+/// Body of `/companion/pair/confirm`
+#[derive(Deserialize)]
+struct ConfirmRequest {
+    pairing_id: String,
+    code: String,
+}
+
+/// Body of the page endpoints
+#[derive(Deserialize)]
+struct PageRequest {
+    #[serde(flatten)]
+    page: PageSelection,
+    #[serde(default)]
+    question: Option<String>,
+}
+
+fn origin_of(headers: &HeaderMap) -> &str {
+    headers.get(header::ORIGIN).and_then(|v| v.to_str().ok()).unwrap_or("")
+}
+
+/// Let an extension origin read the response; other origins get no CORS
+/// headers, so browsers keep web pages from reading it
+fn with_cors(origin: &str, mut response: Response) -> Response {
+    if companion::is_extension_origin(origin) {
+        if let Ok(value) = HeaderValue::from_str(origin) {
+            let headers = response.headers_mut();
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, value);
+            headers.insert(header::VARY, HeaderValue::from_static("Origin"));
+        }
+    }
+    response
+}
+
+fn too_many_requests(wait: Duration) -> Response {
+    let mut response = status_response(StatusCode::TOO_MANY_REQUESTS, "Too many requests");
+    let seconds = (wait.as_secs() + u64::from(wait.subsec_nanos() > 0)).max(1).to_string();
+    if let Ok(value) = HeaderValue::from_str(&seconds) {
+        response.headers_mut().insert(header::RETRY_AFTER, value);
+    }
+    response
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, Box<Response>> {
+    serde_json::from_slice(body)
+        .map_err(|e| Box::new(status_response(StatusCode::BAD_REQUEST, &format!("Invalid request: {}", e))))
+}
+
+/// `OPTIONS` preflight: only extension origins, only what the endpoints use
+async fn preflight(headers: HeaderMap) -> Response {
+    let origin = origin_of(&headers);
+    if !companion::is_extension_origin(origin) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    let allowed = response.headers_mut();
+    allowed.insert(header::ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static("POST, OPTIONS"));
+    allowed.insert(
+        header::ACCESS_CONTROL_ALLOW_HEADERS,
+        HeaderValue::from_static("authorization, content-type"),
+    );
+    allowed.insert(header::ACCESS_CONTROL_MAX_AGE, HeaderValue::from_static("600"));
+    with_cors(origin, response)
+}
+
+/// `POST /companion/pair` with `{"name"}`: show a code for the user to type
+/// into the extension
+async fn pair(State(state): State<Arc<CompanionState>>, headers: HeaderMap, body: Bytes) -> Response {
+    let origin = origin_of(&headers).to_string();
+    let response = match state.pairings.check("pair") {
+        Err(wait) => too_many_requests(wait),
+        Ok(()) => match parse_json::<PairRequest>(&body) {
+            Err(response) => *response,
+            Ok(request) => match state.store.start_pairing(&origin, &request.name) {
+                Ok(pending) => {
+                    print_info(&format!(
+                        "Browser extension '{}' ({}) wants to pair; enter code {} in it",
+                        pending.name, pending.origin, pending.code
+                    ));
+                    Json(serde_json::json!({ "pairing_id": pending.id, "expires_at": pending.expires_at }))
+                        .into_response()
+                }
+                Err(e) => {
+                    warn!("Pairing from {} refused: {}", origin, e);
+                    error_response(&e)
+                }
+            },
+        },
+    };
+    with_cors(&origin, response)
+}
+
+/// `POST /companion/pair/confirm` with `{"pairing_id", "code"}`: the token
+async fn confirm_pairing(State(state): State<Arc<CompanionState>>, headers: HeaderMap, body: Bytes) -> Response {
+    let origin = origin_of(&headers).to_string();
+    let response = match parse_json::<ConfirmRequest>(&body) {
+        Err(response) => *response,
+        Ok(request) => match state.store.complete_pairing(&request.pairing_id, &origin, &request.code) {
+            Ok(pairing) => {
+                print_info(&format!("Paired browser extension '{}'", pairing.extension.name));
+                Json(serde_json::json!({ "id": pairing.extension.id, "token": pairing.token })).into_response()
+            }
+            Err(e) => error_response(&e),
+        },
+    };
+    with_cors(&origin, response)
+}
+
+/// Check the token and origin of a page request and count it against the
+/// extension's limit
+fn authorize(state: &CompanionState, headers: &HeaderMap) -> Result<(), Box<Response>> {
+    let origin = origin_of(headers);
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or("");
+    let extension = state
+        .store
+        .authenticate(token, origin)
+        .ok_or_else(|| Box::new(status_response(StatusCode::UNAUTHORIZED, "Not paired; pair the extension first")))?;
+    state.requests.check(&extension.id).map_err(|wait| Box::new(too_many_requests(wait)))
+}
+
+/// Run a page request: authorize, read the body, answer
+async fn page_request<F, Fut>(state: Arc<CompanionState>, headers: HeaderMap, body: Bytes, handle: F) -> Response
+where
+    F: FnOnce(Arc<CompanionState>, PageRequest) -> Fut,
+    Fut: std::future::Future<Output = McpResult<companion::CompanionReply>>,
+{
+    let origin = origin_of(&headers).to_string();
+    let response = match authorize(&state, &headers).and_then(|()| parse_json::<PageRequest>(&body)) {
+        Err(response) => *response,
+        Ok(request) => match handle(state, request).await {
+            Ok(reply) => Json(reply).into_response(),
+            Err(e) => error_response(&e),
+        },
+    };
+    with_cors(&origin, response)
+}
+
+/// `POST /companion/summarize` with `{"url", "title", "text"}`
+async fn summarize(State(state): State<Arc<CompanionState>>, headers: HeaderMap, body: Bytes) -> Response {
+    page_request(state, headers, body, |state, request| async move {
+        state.companion.summarize(&request.page).await
+    })
+    .await
+}
+
+/// `POST /companion/ask` with the page and a `question`
+async fn ask(State(state): State<Arc<CompanionState>>, headers: HeaderMap, body: Bytes) -> Response {
+    page_request(state, headers, body, |state, request| async move {
+        let question = request.question.unwrap_or_default();
+        state.companion.answer(&request.page, &question).await
+    })
+    .await
+}
+
+/// `POST /companion/conversations` with the page and an optional first
+/// `question`: a new conversation holding the page
+async fn open_conversation(State(state): State<Arc<CompanionState>>, headers: HeaderMap, body: Bytes) -> Response {
+    page_request(state, headers, body, |state, request| async move {
+        state.companion.open(&request.page, request.question.as_deref()).await
+    })
+    .await
+}
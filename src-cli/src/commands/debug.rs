@@ -0,0 +1,152 @@
+use crate::display::{print_info, print_success, print_table, TableColumn};
+use crate::error::CliResult;
+use mcp_common::config::get_settings;
+use mcp_common::protocol::inspector::{get_inspector, CapturedMessage, Exchange, Transport};
+
+fn print_message(label: &str, message: &CapturedMessage) {
+    println!("  {}:", label);
+    for (name, value) in &message.headers {
+        println!("    {}: {}", name, value);
+    }
+    if let Some(body) = &message.body {
+        println!();
+        for line in body.text.lines() {
+            println!("    {}", line);
+        }
+        if body.truncated {
+            println!("    ... ({} bytes in total)", body.size);
+        }
+    }
+}
+
+fn print_exchange(exchange: &Exchange) {
+    let time = exchange.at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+    let status = exchange.status.map(|s| s.to_string()).unwrap_or_default();
+    println!("#{} {} {} {} {}", exchange.id, time, exchange.method, exchange.url, status);
+    if let Some(ms) = exchange.duration_ms {
+        println!("  Duration: {} ms", ms);
+    }
+    if let Some(error) = &exchange.error {
+        println!("  Error: {}", error);
+    }
+    if let Some(request) = &exchange.request {
+        print_message("Request", request);
+    }
+    if let Some(response) = &exchange.response {
+        print_message("Response", response);
+    }
+    println!();
+}
+
+/// Show the most recent captured API exchanges
+pub fn last_requests(count: usize, full: bool, json: bool) -> CliResult<()> {
+    let exchanges = get_inspector().recent(count);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&exchanges)?);
+        return Ok(());
+    }
+
+    if exchanges.is_empty() {
+        if get_inspector().is_enabled() {
+            print_info("No requests captured yet");
+        } else {
+            print_info("The request inspector is off; turn it on with `mcp debug inspector on`");
+        }
+        return Ok(());
+    }
+
+    if full {
+        exchanges.iter().for_each(print_exchange);
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "ID".to_string(),
+            width: 6,
+            style: None,
+        },
+        TableColumn {
+            title: "Time".to_string(),
+            width: 10,
+            style: None,
+        },
+        TableColumn {
+            title: "Method".to_string(),
+            width: 8,
+            style: None,
+        },
+        TableColumn {
+            title: "URL".to_string(),
+            width: 50,
+            style: None,
+        },
+        TableColumn {
+            title: "Status".to_string(),
+            width: 8,
+            style: None,
+        },
+        TableColumn {
+            title: "Time (ms)".to_string(),
+            width: 10,
+            style: None,
+        },
+    ];
+    let rows: Vec<Vec<String>> = exchanges
+        .iter()
+        .map(|e| {
+            let status = match (e.status, &e.error, e.transport) {
+                (Some(status), _, _) => status.to_string(),
+                (None, Some(_), _) => "failed".to_string(),
+                (None, None, Transport::WebSocket) => "frame".to_string(),
+                (None, None, Transport::Http) => String::new(),
+            };
+            vec![
+                e.id.to_string(),
+                e.at.with_timezone(&chrono::Local).format("%H:%M:%S").to_string(),
+                e.method.clone(),
+                e.url.clone(),
+                status,
+                e.duration_ms.map(|ms| ms.to_string()).unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+    print_info("Show headers and bodies with `--full`, or one exchange with `mcp debug show <id>`");
+    Ok(())
+}
+
+/// Show one captured exchange with headers and bodies
+pub fn show(id: u64) -> CliResult<()> {
+    match get_inspector().get(id) {
+        Some(exchange) => print_exchange(&exchange),
+        None => print_info(&format!("No captured exchange #{}; it may have been dropped from the buffer", id)),
+    }
+    Ok(())
+}
+
+/// Turn request capturing on or off
+pub fn set_inspector(enabled: bool) -> CliResult<()> {
+    let settings = get_settings();
+    let mut settings = settings.lock().unwrap();
+    settings.debug.inspector = enabled;
+    settings.save()?;
+
+    if enabled {
+        print_success(&format!(
+            "Capturing the last {} API requests; headers are redacted and bodies capped at {} bytes",
+            settings.debug.inspector_capacity, settings.debug.inspector_max_body
+        ));
+    } else {
+        print_success("Request inspector turned off");
+    }
+    Ok(())
+}
+
+/// Forget all captured exchanges
+pub fn clear() -> CliResult<()> {
+    get_inspector().clear();
+    print_success("Cleared captured requests");
+    Ok(())
+}
@@ -4,31 +4,32 @@ use std::sync::Arc;
 use crate::display::{print_error, print_success, show_spinner};
 use crate::error::CliResult;
 use mcp_common::service::ChatService;
+use mcp_common::tr;
 
 /// Run the delete command
 pub async fn run(chat_service: Arc<ChatService>, conversation_id: String) -> CliResult<()> {
     // Confirm deletion
     if !Confirm::new()
-        .with_prompt(format!("Are you sure you want to delete conversation {}?", conversation_id))
+        .with_prompt(tr!("conversation-delete-confirm", id = conversation_id.as_str()))
         .default(false)
         .interact()?
     {
-        print_error("Operation cancelled");
+        print_error(&tr!("error-cancelled"));
         return Ok(());
     }
     
     let spinner = show_spinner();
-    spinner.set_message(&format!("Deleting conversation {}...", conversation_id));
+    spinner.set_message(&tr!("conversation-deleting", id = conversation_id.as_str()));
     
     // Delete the conversation
     match chat_service.delete_conversation(&conversation_id).await {
         Ok(_) => {
-            spinner.success("Conversation deleted");
-            print_success(&format!("Deleted conversation {}", conversation_id));
+            spinner.success(&tr!("conversation-trashed"));
+            print_success(&tr!("conversation-deleted", id = conversation_id.as_str()));
             Ok(())
         }
         Err(e) => {
-            spinner.error(&format!("Failed to delete conversation: {}", e));
+            spinner.error(&tr!("conversation-delete-failed", error = e.to_string()));
             Err(e.into())
         }
     }
@@ -0,0 +1,113 @@
+use dialoguer::Password;
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_table, print_warning, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::environment::{get_environments, EnvValue, Scope, MASK};
+use mcp_common::service::ChatService;
+
+/// Scope named by a conversation or a workspace
+fn scope(conversation_id: Option<String>, workspace: Option<String>) -> CliResult<Scope> {
+    match (conversation_id, workspace) {
+        (Some(id), None) => Ok(Scope::Conversation(id)),
+        (None, Some(workspace)) => Ok(Scope::Workspace(workspace)),
+        _ => Err(CliError::Unknown("Name either a conversation or a workspace".to_string())),
+    }
+}
+
+/// List the variables of a workspace or conversation, or everything a
+/// conversation sees
+pub async fn list(
+    chat_service: Arc<ChatService>,
+    conversation_id: Option<String>,
+    workspace: Option<String>,
+    resolved: bool,
+) -> CliResult<()> {
+    let (title, rows): (String, Vec<Vec<String>>) = match (resolved, conversation_id.clone()) {
+        (true, Some(id)) => {
+            let environment = chat_service.environment(&id).await?;
+            let rows = environment
+                .variables()
+                .iter()
+                .map(|(name, v)| {
+                    let value = if v.secret { MASK.to_string() } else { v.value.clone() };
+                    vec![name.clone(), value, if v.secret { "yes" } else { "" }.to_string()]
+                })
+                .collect();
+            (format!("Environment of conversation {}", id), rows)
+        }
+        _ => {
+            let scope = scope(conversation_id, workspace)?;
+            let rows = get_environments()
+                .list(&scope)
+                .into_iter()
+                .map(|(name, value)| match value {
+                    EnvValue::Plain { value } => vec![name, value, String::new()],
+                    EnvValue::Secret { .. } => vec![name, MASK.to_string(), "yes".to_string()],
+                })
+                .collect();
+            (format!("Variables of {}", scope), rows)
+        }
+    };
+
+    if rows.is_empty() {
+        print_info("No variables; set one with `mcp env set`");
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "Name".to_string(),
+            width: 24,
+            style: None,
+        },
+        TableColumn {
+            title: "Value".to_string(),
+            width: 50,
+            style: None,
+        },
+        TableColumn {
+            title: "Secret".to_string(),
+            width: 8,
+            style: None,
+        },
+    ];
+    print_info(&title);
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Set a variable of a workspace or conversation
+pub fn set(
+    name: &str,
+    value: Option<String>,
+    secret: bool,
+    conversation_id: Option<String>,
+    workspace: Option<String>,
+) -> CliResult<()> {
+    let scope = scope(conversation_id, workspace)?;
+    let value = match value {
+        Some(value) => {
+            if secret {
+                print_warning("The value may be kept in your shell history; leave it out to be asked for it");
+            }
+            value
+        }
+        None => Password::new().with_prompt(format!("Value of {}", name)).interact()?,
+    };
+    get_environments().set(&scope, name, &value, secret)?;
+    let kind = if secret { "secret " } else { "" };
+    print_success(&format!("Set {}{} for {}", kind, name, scope));
+    Ok(())
+}
+
+/// Remove a variable of a workspace or conversation
+pub fn unset(name: &str, conversation_id: Option<String>, workspace: Option<String>) -> CliResult<()> {
+    let scope = scope(conversation_id, workspace)?;
+    if get_environments().unset(&scope, name)? {
+        print_success(&format!("Removed {} from {}", name, scope));
+    } else {
+        print_info(&format!("{} isn't set for {}", name, scope));
+    }
+    Ok(())
+}
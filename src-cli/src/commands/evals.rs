@@ -0,0 +1,168 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::display::{print_error, print_info, print_success, print_table, print_warning, show_spinner, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::service::evals::{load_suite, EvalReport, EvalRunner};
+use mcp_common::service::ChatService;
+
+/// Run a suite; fails when any case fails or regresses so CI can gate on it
+pub async fn run(
+    chat_service: Arc<ChatService>,
+    suite: PathBuf,
+    models: Vec<String>,
+    concurrency: usize,
+    junit: Option<String>,
+    json: bool,
+    no_save: bool,
+) -> CliResult<()> {
+    let suite = load_suite(&suite)?;
+
+    // Resolve the models to evaluate
+    let available = chat_service.available_models().await?;
+    let selected: Vec<_> = if models.is_empty() {
+        available
+    } else {
+        let mut selected = Vec::new();
+        for name in &models {
+            match available.iter().find(|m| &m.id == name || &m.name == name) {
+                Some(model) => selected.push(model.clone()),
+                None => return Err(CliError::InvalidArgument(format!("Unknown model: {}", name))),
+            }
+        }
+        selected
+    };
+
+    if selected.is_empty() {
+        print_info("No models available to evaluate");
+        return Ok(());
+    }
+
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    let runner = EvalRunner::new(chat_service)
+        .with_concurrency(concurrency)
+        .with_progress(progress_tx);
+
+    let spinner = show_spinner();
+    spinner.set_message(&format!(
+        "Running {} case(s) on {} model(s)...",
+        suite.cases.len(),
+        selected.len()
+    ));
+    let evaluation = runner.run(&suite, &selected);
+    tokio::pin!(evaluation);
+
+    // Update the spinner while the cases run
+    let report = loop {
+        tokio::select! {
+            result = &mut evaluation => break result,
+            Some(progress) = progress_rx.recv() => {
+                spinner.set_message(&format!(
+                    "{}/{} done, {} failed",
+                    progress.passed + progress.failed,
+                    progress.total,
+                    progress.failed
+                ));
+            }
+        }
+    };
+
+    let report = match report {
+        Ok(report) => {
+            spinner.success("Evaluation complete");
+            report
+        }
+        Err(e) => {
+            spinner.error(&format!("Evaluation failed: {}", e));
+            return Err(e.into());
+        }
+    };
+
+    let to_stdout = junit.as_deref() == Some("-");
+    match junit.as_deref() {
+        Some("-") => print!("{}", report.to_junit()),
+        Some(path) => {
+            std::fs::write(path, report.to_junit())?;
+            print_success(&format!("JUnit report written to {}", path));
+        }
+        None => {}
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if !to_stdout {
+        print_report(&report)?;
+    }
+
+    // Compare against the previous run before saving this one
+    let comparisons = runner.compare_with_history(&report);
+    let mut regressions = 0;
+    for comparison in &comparisons {
+        if !comparison.regressions.is_empty() {
+            regressions += comparison.regressions.len();
+            print_warning(&format!(
+                "{}: {} regressed ({:.0}% -> {:.0}% passing)",
+                comparison.model_id,
+                comparison.regressions.join(", "),
+                comparison.previous_pass_rate * 100.0,
+                comparison.pass_rate * 100.0
+            ));
+        }
+    }
+
+    if !no_save {
+        if let Err(e) = runner.save_to_history(&report) {
+            print_error(&format!("Failed to save evaluation history: {}", e));
+        }
+    }
+
+    let failed: usize = report.models.iter().map(|m| m.failed()).sum();
+    if failed > 0 || regressions > 0 {
+        return Err(CliError::Unknown(format!(
+            "{} case(s) failed, {} regression(s)",
+            failed, regressions
+        )));
+    }
+    Ok(())
+}
+
+/// Print pass rates per model and the failed cases
+fn print_report(report: &EvalReport) -> CliResult<()> {
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+
+    let columns = vec![
+        column("Model", 30),
+        column("Provider", 12),
+        column("Passed", 8),
+        column("Failed", 8),
+        column("Pass rate", 10),
+    ];
+
+    let rows: Vec<Vec<String>> = report
+        .models
+        .iter()
+        .map(|model| {
+            vec![
+                model.model_id.clone(),
+                model.provider.clone(),
+                model.passed().to_string(),
+                model.failed().to_string(),
+                format!("{:.0}%", model.pass_rate() * 100.0),
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+
+    for model in &report.models {
+        for case in model.cases.iter().filter(|c| !c.passed) {
+            let reason = case.error.clone().unwrap_or_else(|| case.failures.join("; "));
+            print_warning(&format!("{} / {}: {}", model.model_id, case.case_id, reason));
+        }
+    }
+    Ok(())
+}
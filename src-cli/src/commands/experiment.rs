@@ -0,0 +1,170 @@
+use crate::display::{print_info, print_success, print_table, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::service::experiments::{get_experiment_store, Variant};
+
+/// Split a "name=value" argument
+fn split_pair(arg: &str) -> CliResult<(String, String)> {
+    arg.split_once('=')
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .filter(|(name, _)| !name.is_empty())
+        .ok_or_else(|| CliError::InvalidArgument(format!("Expected name=value, got '{}'", arg)))
+}
+
+/// Create and start an experiment
+pub fn create(name: &str, variants: Vec<String>, templates: Vec<String>) -> CliResult<()> {
+    let mut parsed = Vec::with_capacity(variants.len());
+    for arg in &variants {
+        let (variant_name, system_prompt) = split_pair(arg)?;
+        parsed.push(Variant {
+            name: variant_name,
+            system_prompt: if system_prompt.is_empty() { None } else { Some(system_prompt) },
+            prompt_template: None,
+        });
+    }
+
+    for arg in &templates {
+        let (variant_name, template) = split_pair(arg)?;
+        let variant = parsed
+            .iter_mut()
+            .find(|v| v.name == variant_name)
+            .ok_or_else(|| CliError::InvalidArgument(format!("Template for unknown variant '{}'", variant_name)))?;
+        variant.prompt_template = Some(template);
+    }
+
+    let experiment = get_experiment_store().create(name, parsed)?;
+    print_success(&format!(
+        "Experiment '{}' started ({}); new conversations alternate between {} variants",
+        experiment.name,
+        experiment.id,
+        experiment.variants.len()
+    ));
+    Ok(())
+}
+
+/// List experiments
+pub fn list() -> CliResult<()> {
+    let experiments = get_experiment_store().list();
+    if experiments.is_empty() {
+        print_info("No experiments defined");
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "ID".to_string(),
+            width: 38,
+            style: None,
+        },
+        TableColumn {
+            title: "Name".to_string(),
+            width: 20,
+            style: None,
+        },
+        TableColumn {
+            title: "Variants".to_string(),
+            width: 30,
+            style: None,
+        },
+        TableColumn {
+            title: "Status".to_string(),
+            width: 10,
+            style: None,
+        },
+    ];
+
+    let rows: Vec<Vec<String>> = experiments
+        .iter()
+        .map(|experiment| {
+            vec![
+                experiment.id.clone(),
+                experiment.name.clone(),
+                experiment
+                    .variants
+                    .iter()
+                    .map(|v| v.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                if experiment.active { "Running".to_string() } else { "Stopped".to_string() },
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Show the comparison report of an experiment
+pub fn report(experiment: &str, json: bool) -> CliResult<()> {
+    let report = get_experiment_store().report(experiment)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "Variant".to_string(),
+            width: 20,
+            style: None,
+        },
+        TableColumn {
+            title: "Conversations".to_string(),
+            width: 15,
+            style: None,
+        },
+        TableColumn {
+            title: "Up".to_string(),
+            width: 8,
+            style: None,
+        },
+        TableColumn {
+            title: "Down".to_string(),
+            width: 8,
+            style: None,
+        },
+        TableColumn {
+            title: "Approval".to_string(),
+            width: 10,
+            style: None,
+        },
+    ];
+
+    let rows: Vec<Vec<String>> = report
+        .variants
+        .iter()
+        .map(|v| {
+            vec![
+                v.name.clone(),
+                v.conversations.to_string(),
+                v.up.to_string(),
+                v.down.to_string(),
+                if v.up + v.down > 0 {
+                    format!("{:.0}%", v.approval_rate * 100.0)
+                } else {
+                    "-".to_string()
+                },
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+
+    match (&report.leader, report.z_score) {
+        (Some(leader), Some(z)) => {
+            let significance = if z.abs() > 1.96 { "significant" } else { "not yet significant" };
+            print_info(&format!("'{}' leads (z = {:.2}, {} at 95%)", leader, z, significance));
+        }
+        (Some(leader), None) => print_info(&format!("'{}' leads; not enough ratings to compare", leader)),
+        _ => print_info("No ratings recorded yet"),
+    }
+
+    Ok(())
+}
+
+/// Stop an experiment
+pub fn stop(experiment: &str) -> CliResult<()> {
+    get_experiment_store().stop(experiment)?;
+    print_success(&format!("Experiment '{}' stopped", experiment));
+    Ok(())
+}
@@ -3,7 +3,10 @@ use std::io::{self, Write};
 use std::path::Path;
 use std::sync::Arc;
 
-use crate::display::{format_conversation, print_error, print_success, show_spinner, MessageFormat};
+use crate::display::{
+    format_conversation, format_conversation_with_history, print_error, print_success, print_warning, show_spinner,
+    MessageFormat,
+};
 use crate::error::CliResult;
 use mcp_common::service::ChatService;
 
@@ -13,6 +16,7 @@ pub async fn run(
     conversation_id: String,
     format: String,
     output: Option<String>,
+    history: bool,
 ) -> CliResult<()> {
     let spinner = show_spinner();
     spinner.set_message(&format!("Loading conversation {}...", conversation_id));
@@ -40,8 +44,21 @@ pub async fn run(
         }
     };
     
-    // Format the conversation
-    let formatted = format_conversation(&conversation, format_mode);
+    // Format the conversation, with earlier versions of edits if asked
+    let formatted = if history {
+        format_conversation_with_history(&conversation, format_mode)
+    } else {
+        format_conversation(&conversation, format_mode)
+    };
+    
+    // Secret environment values never leave in an export
+    let formatted = match chat_service.environment(&conversation_id).await {
+        Ok(environment) => environment.mask(&formatted),
+        Err(e) => {
+            print_warning(&format!("Secret environment values may not be masked: {}", e));
+            formatted
+        }
+    };
     
     // Output the formatted conversation
     match output {
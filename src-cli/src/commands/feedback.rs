@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success};
+use crate::error::{CliError, CliResult};
+use mcp_common::models::Rating;
+use mcp_common::service::feedback;
+use mcp_common::service::ChatService;
+
+/// Rate a message
+pub async fn rate(
+    chat_service: Arc<ChatService>,
+    message_id: &str,
+    rating: &str,
+    comment: Option<String>,
+) -> CliResult<()> {
+    let rating = match rating.to_lowercase().as_str() {
+        "up" | "+" => Some(Rating::Up),
+        "down" | "-" => Some(Rating::Down),
+        "clear" | "none" => None,
+        other => {
+            return Err(CliError::InvalidArgument(format!(
+                "Unknown rating '{}'; use up, down or clear",
+                other
+            )))
+        }
+    };
+
+    chat_service.rate_message(message_id, rating, comment).await?;
+
+    match rating {
+        Some(Rating::Up) => print_success("Rated up"),
+        Some(Rating::Down) => print_success("Rated down"),
+        None => print_success("Rating cleared"),
+    }
+    Ok(())
+}
+
+/// Export rated messages as JSONL
+pub async fn export(chat_service: Arc<ChatService>, output: PathBuf) -> CliResult<()> {
+    let conversations = chat_service.list_conversations().await?;
+    let count = feedback::export_jsonl(&conversations, &output)?;
+
+    if count == 0 {
+        print_info("No rated messages to export");
+    } else {
+        print_success(&format!("Exported {} rated message(s) to {}", count, output.display()));
+    }
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_table, print_warning, show_spinner_with_message, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::feeds::{get_feed_store, DigestTarget, Feed, FeedDigester, FeedFilter, NewFeed};
+use mcp_common::service::ChatService;
+
+fn describe_target(feed: &Feed) -> String {
+    match &feed.target {
+        DigestTarget::Conversation { conversation_id } => {
+            format!("chat {}", conversation_id.chars().take(8).collect::<String>())
+        }
+        DigestTarget::Notification => "notification".to_string(),
+    }
+}
+
+/// List subscribed feeds
+pub fn list() -> CliResult<()> {
+    let feeds = get_feed_store().list();
+    if feeds.is_empty() {
+        print_info("No feeds; subscribe to one with `mcp feeds add <url>`");
+        return Ok(());
+    }
+
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let columns = vec![
+        column("ID", 10),
+        column("Title", 30),
+        column("Digests to", 14),
+        column("Every", 8),
+        column("Last checked", 18),
+        column("Last error", 30),
+    ];
+    let rows: Vec<Vec<String>> = feeds
+        .iter()
+        .map(|f| {
+            vec![
+                f.id.chars().take(8).collect(),
+                f.title.clone(),
+                describe_target(f),
+                if f.enabled { format!("{}m", f.interval_minutes) } else { "paused".to_string() },
+                f.last_checked_at
+                    .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "never".to_string()),
+                f.last_error.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Subscribe to a feed
+#[allow(clippy::too_many_arguments)]
+pub async fn add(
+    chat_service: Arc<ChatService>,
+    url: String,
+    conversation: Option<String>,
+    notify: bool,
+    title: Option<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    every: u32,
+    model: Option<String>,
+) -> CliResult<()> {
+    let target = match (conversation, notify) {
+        (Some(conversation_id), _) => {
+            // Digests need somewhere to land
+            chat_service.get_conversation(&conversation_id).await?;
+            DigestTarget::Conversation { conversation_id }
+        }
+        (None, true) => DigestTarget::Notification,
+        (None, false) => {
+            return Err(CliError::InvalidArgument(
+                "Choose where digests go with --conversation <id> or --notify".to_string(),
+            ))
+        }
+    };
+    if let Some(model) = &model {
+        let models = chat_service.available_models().await?;
+        if !models.iter().any(|m| &m.id == model || m.name.eq_ignore_ascii_case(model)) {
+            return Err(CliError::InvalidArgument(format!("Model {} is not available", model)));
+        }
+    }
+
+    let feed = get_feed_store().add(NewFeed {
+        url,
+        title,
+        filter: FeedFilter { include, exclude },
+        target,
+        model,
+        interval_minutes: Some(every),
+    })?;
+    print_success(&format!(
+        "Subscribed to {}; checked every {} minutes",
+        feed.url, feed.interval_minutes
+    ));
+    print_info("Feeds are checked while the desktop app or `mcp daemon` runs; `mcp feeds run` checks now");
+    Ok(())
+}
+
+/// Unsubscribe from a feed
+pub fn remove(id: &str) -> CliResult<()> {
+    get_feed_store().remove(id)?;
+    print_success("Unsubscribed");
+    Ok(())
+}
+
+/// Replace a feed's filter
+pub fn set_filter(id: &str, include: Vec<String>, exclude: Vec<String>) -> CliResult<()> {
+    let feed = get_feed_store().set_filter(id, FeedFilter { include, exclude })?;
+    if feed.filter == FeedFilter::default() {
+        print_success(&format!("Every item of {} is digested", feed.title));
+    } else {
+        print_success(&format!("Filter of {} updated", feed.title));
+    }
+    Ok(())
+}
+
+/// Resume or pause a feed
+pub fn set_enabled(id: &str, enabled: bool) -> CliResult<()> {
+    let feed = get_feed_store().set_enabled(id, enabled)?;
+    print_success(&format!("{} {}", feed.title, if enabled { "resumed" } else { "paused" }));
+    Ok(())
+}
+
+/// Check one feed, or every enabled one, and deliver digests
+pub async fn run(chat_service: Arc<ChatService>, id: Option<String>) -> CliResult<()> {
+    let store = get_feed_store();
+    let feeds: Vec<Feed> = match id {
+        Some(id) => vec![store.get(&id)?],
+        None => store.list().into_iter().filter(|f| f.enabled).collect(),
+    };
+    if feeds.is_empty() {
+        print_info("No feeds to check");
+        return Ok(());
+    }
+
+    let digester = FeedDigester::new(chat_service);
+    for feed in feeds {
+        let spinner = show_spinner_with_message(&format!("Checking {}...", feed.title));
+        match digester.run(&feed.id).await {
+            Ok(Some(digest)) => {
+                spinner.success(&format!("{}: {} new item(s)", digest.feed_title, digest.items.len()));
+                if digest.target == DigestTarget::Notification {
+                    println!("{}\n", digest.to_markdown());
+                }
+            }
+            Ok(None) => spinner.success(&format!("{}: nothing new", feed.title)),
+            Err(e) => spinner.error(&format!("{}: {}", feed.title, e)),
+        }
+    }
+    Ok(())
+}
+
+/// Mark what a feed holds now as read
+pub async fn mark_read(chat_service: Arc<ChatService>, id: &str) -> CliResult<()> {
+    let store = get_feed_store();
+    let feed = store.get(id)?;
+    let parsed = FeedDigester::new(chat_service).fetch(&feed.url).await?;
+    let unread = feed.unread(&parsed.items).len();
+    if unread == 0 {
+        print_warning(&format!("Nothing unread in {}", feed.title));
+        return Ok(());
+    }
+
+    store.mark_read(&feed.id, parsed.items.into_iter().map(|item| item.id))?;
+    print_success(&format!("Marked {} item(s) of {} read", unread, feed.title));
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use crate::display::{print_info, print_success, print_table, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::service::filters::{get_filter_settings, ReplacementRule, SecretAction};
+
+/// Describe where a profile applies
+fn scope(workspace: Option<&str>) -> String {
+    match workspace {
+        Some(w) => format!("workspace '{}'", w),
+        None => "the default profile".to_string(),
+    }
+}
+
+/// Show the filters of a workspace or the default profile
+pub fn show(workspace: Option<String>) -> CliResult<()> {
+    let settings = get_filter_settings();
+    let settings = settings.read().unwrap();
+    let profile = settings.profile(workspace.as_deref());
+
+    print_info(&format!("Content filters for {}", scope(workspace.as_deref())));
+    println!("  Profanity: {}", if profile.profanity { "masked" } else { "off" });
+    if !profile.profanity_words.is_empty() {
+        println!("  Extra words: {}", profile.profanity_words.join(", "));
+    }
+    let secrets = match profile.secrets {
+        SecretAction::Off => "off",
+        SecretAction::Flag => "flagged",
+        SecretAction::Redact => "redacted",
+    };
+    println!("  Secrets: {}", secrets);
+
+    if profile.replacements.is_empty() {
+        println!("  No custom replacements");
+    } else {
+        let columns = vec![
+            TableColumn {
+                title: "#".to_string(),
+                width: 4,
+                style: None,
+            },
+            TableColumn {
+                title: "Pattern".to_string(),
+                width: 30,
+                style: None,
+            },
+            TableColumn {
+                title: "Replacement".to_string(),
+                width: 20,
+                style: None,
+            },
+            TableColumn {
+                title: "Description".to_string(),
+                width: 30,
+                style: None,
+            },
+        ];
+        let rows: Vec<Vec<String>> = profile
+            .replacements
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                vec![
+                    (i + 1).to_string(),
+                    rule.pattern.clone(),
+                    rule.replacement.clone(),
+                    rule.description.clone(),
+                ]
+            })
+            .collect();
+        print_table(&columns, &rows)?;
+    }
+
+    if workspace.is_none() && !settings.workspaces.is_empty() {
+        let mut names: Vec<&String> = settings.workspaces.keys().collect();
+        names.sort();
+        print_info(&format!(
+            "Workspaces with their own filters: {}",
+            names.iter().map(|n| n.as_str()).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Change a built-in filter
+pub fn set(filter: &str, value: &str, workspace: Option<String>) -> CliResult<()> {
+    let settings = get_filter_settings();
+    let mut settings = settings.write().unwrap();
+    let profile = settings.profile_mut(workspace.as_deref());
+
+    match (filter.to_lowercase().as_str(), value.to_lowercase().as_str()) {
+        ("profanity", "on") => profile.profanity = true,
+        ("profanity", "off") => profile.profanity = false,
+        ("secrets", "off") => profile.secrets = SecretAction::Off,
+        ("secrets", "flag") => profile.secrets = SecretAction::Flag,
+        ("secrets", "redact") => profile.secrets = SecretAction::Redact,
+        ("profanity", _) => {
+            return Err(CliError::InvalidArgument(format!(
+                "Unknown value '{}'; use on or off",
+                value
+            )))
+        }
+        ("secrets", _) => {
+            return Err(CliError::InvalidArgument(format!(
+                "Unknown value '{}'; use off, flag or redact",
+                value
+            )))
+        }
+        _ => {
+            return Err(CliError::InvalidArgument(format!(
+                "Unknown filter '{}'; use profanity or secrets",
+                filter
+            )))
+        }
+    }
+
+    settings.save()?;
+    print_success(&format!("Set {} to {} for {}", filter, value, scope(workspace.as_deref())));
+    Ok(())
+}
+
+/// Add a custom regex replacement
+pub fn add_rule(
+    pattern: &str,
+    replacement: &str,
+    description: Option<String>,
+    workspace: Option<String>,
+) -> CliResult<()> {
+    let settings = get_filter_settings();
+    let mut settings = settings.write().unwrap();
+    settings.profile_mut(workspace.as_deref()).replacements.push(ReplacementRule {
+        pattern: pattern.to_string(),
+        replacement: replacement.to_string(),
+        description: description.unwrap_or_default(),
+    });
+
+    if let Err(e) = settings.validate() {
+        settings.profile_mut(workspace.as_deref()).replacements.pop();
+        return Err(e.into());
+    }
+    settings.save()?;
+
+    print_success(&format!("Added replacement to {}", scope(workspace.as_deref())));
+    Ok(())
+}
+
+/// Remove a custom replacement by its number in `filter show`
+pub fn remove_rule(number: usize, workspace: Option<String>) -> CliResult<()> {
+    let settings = get_filter_settings();
+    let mut settings = settings.write().unwrap();
+    let rules = &mut settings.profile_mut(workspace.as_deref()).replacements;
+    if number == 0 || number > rules.len() {
+        return Err(CliError::InvalidArgument(format!("No replacement #{}", number)));
+    }
+    let rule = rules.remove(number - 1);
+    settings.save()?;
+
+    print_success(&format!("Removed replacement '{}'", rule.pattern));
+    Ok(())
+}
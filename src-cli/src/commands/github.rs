@@ -0,0 +1,82 @@
+use dialoguer::Password;
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, show_spinner_with_message};
+use crate::error::{CliError, CliResult};
+use mcp_common::integrations::github::{self, get_github_client, GitHubRef};
+use mcp_common::integrations::import_context;
+use mcp_common::service::ChatService;
+
+/// Store the token in the secret store, asking for it when not given
+pub fn set_token(token: Option<String>, remove: bool) -> CliResult<()> {
+    if remove {
+        github::set_token(None)?;
+        print_success("Removed the GitHub token");
+        return Ok(());
+    }
+
+    let token = match token {
+        Some(token) => token,
+        None => Password::new().with_prompt("GitHub token").interact()?,
+    };
+    github::set_token(Some(&token))?;
+    print_success("Stored the GitHub token");
+    Ok(())
+}
+
+/// Print lines of a file
+pub async fn snippet(reference: &str) -> CliResult<()> {
+    let reference: GitHubRef = reference.parse()?;
+    let GitHubRef::File {
+        owner,
+        repo,
+        path,
+        reference,
+        lines,
+    } = reference
+    else {
+        return Err(CliError::InvalidArgument(
+            "Not a file; use `mcp github import` for issues and pull requests".to_string(),
+        ));
+    };
+
+    let snippet = get_github_client()
+        .snippet(&owner, &repo, &path, reference.as_deref(), lines)
+        .await?;
+    println!("{}", snippet.to_markdown());
+    Ok(())
+}
+
+/// Add a file, issue or pull request to a conversation
+pub async fn import(chat_service: Arc<ChatService>, conversation_id: &str, reference: &str) -> CliResult<()> {
+    let reference: GitHubRef = reference.parse()?;
+    let spinner = show_spinner_with_message("Fetching from GitHub...");
+    let item = match get_github_client().fetch(&reference).await {
+        Ok(item) => item,
+        Err(e) => {
+            spinner.error("Failed to fetch from GitHub");
+            return Err(e.into());
+        }
+    };
+    import_context(&chat_service, conversation_id, &item).await?;
+    spinner.success(&format!("Imported {} ({})", item.reference, item.title));
+    Ok(())
+}
+
+/// Show the API rate limit
+pub async fn rate() -> CliResult<()> {
+    let client = get_github_client();
+    match client.check_rate_limit().await? {
+        Some(limit) => print_info(&format!(
+            "{} of {} requests left, resets at {}",
+            limit.remaining,
+            limit.limit,
+            limit.reset_at.with_timezone(&chrono::Local).format("%H:%M")
+        )),
+        None => print_info("GitHub didn't report a rate limit"),
+    }
+    if !client.has_token() {
+        print_info("Without a token the limit is 60 requests an hour; set one with `mcp github token`");
+    }
+    Ok(())
+}
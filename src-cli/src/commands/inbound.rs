@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_table, TableColumn};
+use crate::error::CliResult;
+use mcp_common::service::ChatService;
+use mcp_common::webhooks::inbound::get_inbound_store;
+
+/// List routes
+pub fn list() -> CliResult<()> {
+    let routes = get_inbound_store().list();
+    if routes.is_empty() {
+        print_info("No inbound routes; create one with `mcp inbound add <name> <conversation>`");
+        return Ok(());
+    }
+
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let columns = vec![column("Name", 20), column("Conversation", 38), column("Reply", 6)];
+    let rows: Vec<Vec<String>> = routes
+        .iter()
+        .map(|r| vec![r.name.clone(), r.conversation_id.clone(), if r.reply { "yes" } else { "no" }.to_string()])
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Create a route and print its token
+pub async fn add(chat_service: Arc<ChatService>, name: &str, conversation_id: &str, reply: bool) -> CliResult<()> {
+    // Catch a mistyped conversation now rather than on the first message
+    let conversation = chat_service.get_conversation(conversation_id).await?;
+    let route = get_inbound_store().add(name, &conversation.id, reply)?;
+    print_success(&format!("Messages posted to /inbound/{} go to '{}'", route.name, conversation.title));
+    println!("Token: {}", route.token);
+    print_info("Send it as `Authorization: Bearer <token>`; `mcp daemon` serves the endpoint");
+    Ok(())
+}
+
+/// Remove a route
+pub fn remove(name: &str) -> CliResult<()> {
+    get_inbound_store().remove(name)?;
+    print_success(&format!("Route {} removed", name));
+    Ok(())
+}
+
+/// Replace a route's token and print it
+pub fn rotate_token(name: &str) -> CliResult<()> {
+    let route = get_inbound_store().rotate_token(name)?;
+    print_success(&format!("New token for {}", route.name));
+    println!("{}", route.token);
+    Ok(())
+}
@@ -169,6 +169,10 @@ pub async fn run(
             }
         } else {
             // Not a command, send as a message
+            if !commands::chat::confirm_cost(&chat_service, &current_conversation_id, &input).await? {
+                print_info("Message not sent");
+                continue;
+            }
             println!();
             
             match chat_service
@@ -0,0 +1,152 @@
+use dialoguer::Password;
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_table, show_spinner_with_message, TableColumn};
+use crate::error::CliResult;
+use mcp_common::integrations::import_context;
+use mcp_common::integrations::issues::{self, get_issue_importer, get_issue_settings, IssueField, IssueTracker};
+use mcp_common::service::ChatService;
+
+const TRACKERS: [IssueTracker; 2] = [IssueTracker::Jira, IssueTracker::Linear];
+
+/// Fields given on the command line, or the configured ones
+fn fields_or_default(fields: Option<String>) -> CliResult<Vec<IssueField>> {
+    match fields {
+        Some(fields) => Ok(IssueField::parse_list(&fields)?),
+        None => Ok(get_issue_settings().read().unwrap().fields.clone()),
+    }
+}
+
+/// Show the settings and which trackers are ready
+pub fn show() -> CliResult<()> {
+    let settings = get_issue_settings().read().unwrap().clone();
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let columns = vec![column("Tracker", 8), column("Ready", 6), column("Default", 8)];
+    let rows: Vec<Vec<String>> = TRACKERS
+        .iter()
+        .map(|&tracker| {
+            vec![
+                tracker.as_str().to_string(),
+                if issues::is_configured(tracker) { "yes" } else { "no" }.to_string(),
+                if settings.default_tracker == Some(tracker) { "*" } else { "" }.to_string(),
+            ]
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+
+    if let Some(url) = &settings.jira_url {
+        match &settings.jira_email {
+            Some(email) => print_info(&format!("Jira site: {} as {}", url, email)),
+            None => print_info(&format!("Jira site: {}", url)),
+        }
+    }
+    let fields: Vec<&str> = settings.fields.iter().map(IssueField::as_str).collect();
+    print_info(&format!("Imported fields: {}", fields.join(", ")));
+    Ok(())
+}
+
+/// Change the settings
+pub fn configure(
+    jira_url: Option<String>,
+    jira_email: Option<String>,
+    default: Option<String>,
+    fields: Option<String>,
+) -> CliResult<()> {
+    let settings = get_issue_settings();
+    let mut settings = settings.write().unwrap();
+    if let Some(url) = jira_url {
+        settings.jira_url = Some(url.trim().trim_end_matches('/').to_string()).filter(|u| !u.is_empty());
+    }
+    if let Some(email) = jira_email {
+        settings.jira_email = Some(email.trim().to_string()).filter(|e| !e.is_empty());
+    }
+    if let Some(default) = default {
+        settings.default_tracker = Some(default.parse()?);
+    }
+    if let Some(fields) = fields {
+        settings.fields = IssueField::parse_list(&fields)?;
+    }
+    settings.save()?;
+    get_issue_importer().clear_cache();
+    print_success("Saved the issue tracker settings");
+    Ok(())
+}
+
+/// Store a token in the secret store, asking for it when not given
+pub fn set_token(tracker: &str, token: Option<String>, remove: bool) -> CliResult<()> {
+    let tracker: IssueTracker = tracker.parse()?;
+    if remove {
+        issues::set_token(tracker, None)?;
+        print_success(&format!("Removed the {} token", tracker.name()));
+        return Ok(());
+    }
+
+    let token = match token {
+        Some(token) => token,
+        None => Password::new()
+            .with_prompt(format!("{} token", tracker.name()))
+            .interact()?,
+    };
+    issues::set_token(tracker, Some(&token))?;
+    print_success(&format!("Stored the {} token", tracker.name()));
+    if tracker == IssueTracker::Jira && get_issue_settings().read().unwrap().jira_url.is_none() {
+        print_info("Set the Jira site with `mcp issues config --jira-url`");
+    }
+    Ok(())
+}
+
+/// Print an issue
+pub async fn get(reference: &str, fields: Option<String>, refresh: bool) -> CliResult<()> {
+    let fields = fields_or_default(fields)?;
+    let issue = get_issue_importer().fetch(reference, refresh).await?;
+    println!("{}", issue.to_markdown(&fields));
+    Ok(())
+}
+
+/// Find issues by text
+pub async fn search(query: &str, tracker: Option<String>, limit: usize) -> CliResult<()> {
+    let tracker = tracker.map(|t| t.parse()).transpose()?;
+    let results = get_issue_importer().search(tracker, query, limit).await?;
+    if results.is_empty() {
+        print_info("No issues found");
+        return Ok(());
+    }
+
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let columns = vec![column("Key", 12), column("Status", 14), column("Title", 60)];
+    let rows: Vec<Vec<String>> = results
+        .into_iter()
+        .map(|issue| vec![issue.key, issue.status.unwrap_or_default(), issue.title])
+        .collect();
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Add an issue to a conversation
+pub async fn import(
+    chat_service: Arc<ChatService>,
+    conversation_id: &str,
+    reference: &str,
+    fields: Option<String>,
+) -> CliResult<()> {
+    let fields = fields_or_default(fields)?;
+    let spinner = show_spinner_with_message("Fetching the issue...");
+    let issue = match get_issue_importer().fetch(reference, false).await {
+        Ok(issue) => issue,
+        Err(e) => {
+            spinner.error("Failed to fetch the issue");
+            return Err(e.into());
+        }
+    };
+    import_context(&chat_service, conversation_id, &issue.to_context(&fields)).await?;
+    spinner.success(&format!("Imported {} ({})", issue.key, issue.title));
+    Ok(())
+}
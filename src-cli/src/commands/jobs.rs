@@ -0,0 +1,98 @@
+use crate::display::{print_info, print_success, print_table, TableColumn};
+use crate::error::CliResult;
+use mcp_common::jobs::{get_job_manager, Job, JobState};
+
+fn progress(job: &Job) -> String {
+    match job.progress.fraction() {
+        Some(fraction) => format!("{:.0}%", fraction * 100.0),
+        None if job.progress.done > 0 => job.progress.done.to_string(),
+        None => String::new(),
+    }
+}
+
+/// List background jobs of the app and the CLI
+pub fn list(all: bool, json: bool) -> CliResult<()> {
+    let jobs: Vec<Job> = get_job_manager()
+        .list()
+        .into_iter()
+        .filter(|job| all || !job.state.is_finished())
+        .collect();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&jobs)?);
+        return Ok(());
+    }
+
+    if jobs.is_empty() {
+        if all {
+            print_info("No jobs");
+        } else {
+            print_info("No active jobs; show finished ones with `--all`");
+        }
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "ID".to_string(),
+            width: 10,
+            style: None,
+        },
+        TableColumn {
+            title: "Kind".to_string(),
+            width: 16,
+            style: None,
+        },
+        TableColumn {
+            title: "Title".to_string(),
+            width: 30,
+            style: None,
+        },
+        TableColumn {
+            title: "State".to_string(),
+            width: 12,
+            style: None,
+        },
+        TableColumn {
+            title: "Progress".to_string(),
+            width: 10,
+            style: None,
+        },
+        TableColumn {
+            title: "Updated".to_string(),
+            width: 20,
+            style: None,
+        },
+    ];
+    let rows: Vec<Vec<String>> = jobs
+        .iter()
+        .map(|job| {
+            vec![
+                job.id.chars().take(8).collect(),
+                job.kind.to_string(),
+                job.title.clone(),
+                job.state.to_string(),
+                progress(job),
+                job.updated_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string(),
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+    for job in jobs.iter().filter(|job| job.state == JobState::Failed) {
+        if let Some(error) = &job.error {
+            println!("{}: {}", &job.id[..8.min(job.id.len())], error);
+        }
+    }
+    Ok(())
+}
+
+/// Ask a job to stop
+pub fn cancel(id: &str) -> CliResult<()> {
+    let job = get_job_manager().cancel(id)?;
+    if job.state == JobState::Cancelled {
+        print_success(&format!("Canceled {} of {}", job.kind, job.title));
+    } else {
+        print_success(&format!("Asked the app to stop {} of {}", job.kind, job.title));
+    }
+    Ok(())
+}
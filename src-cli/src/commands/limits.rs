@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use crate::display::{
+    format_message, print_info, print_success, print_table, print_warning, MessageFormat, TableColumn,
+};
+use crate::error::{CliError, CliResult};
+use mcp_common::config::get_attachment_store;
+use mcp_common::service::limits::{format_bytes, Fit, Fix, LimitsTable, RequestLimits};
+use mcp_common::service::ChatService;
+
+/// Providers with documented limits
+const KNOWN_PROVIDERS: [&str; 2] = ["anthropic", "local"];
+
+/// Megabytes to bytes
+fn from_mb(mb: u64) -> u64 {
+    mb * 1024 * 1024
+}
+
+/// Show request size limits by provider
+pub fn show(provider: Option<String>, json: bool) -> CliResult<()> {
+    let table = LimitsTable::new();
+    let overrides = table.overrides();
+    let providers: Vec<String> = match provider {
+        Some(provider) => vec![provider],
+        None => {
+            let mut providers: Vec<String> = KNOWN_PROVIDERS.iter().map(|p| p.to_string()).collect();
+            providers.extend(
+                overrides
+                    .keys()
+                    .filter(|p| !providers.contains(p))
+                    .cloned()
+                    .collect::<Vec<_>>(),
+            );
+            providers
+        }
+    };
+    if json {
+        let limits: serde_json::Map<String, serde_json::Value> = providers
+            .iter()
+            .map(|p| Ok((p.clone(), serde_json::to_value(table.for_provider(p))?)))
+            .collect::<Result<_, serde_json::Error>>()?;
+        println!("{}", serde_json::to_string_pretty(&limits)?);
+        return Ok(());
+    }
+
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let columns = vec![
+        column("Provider", 16),
+        column("Request", 10),
+        column("Image", 10),
+        column("Pixels", 8),
+        column("Images", 8),
+        column("Source", 10),
+    ];
+    let rows: Vec<Vec<String>> = providers
+        .iter()
+        .map(|p| {
+            let limits = table.for_provider(p);
+            vec![
+                p.clone(),
+                format_bytes(limits.max_request_bytes),
+                format_bytes(limits.max_image_bytes),
+                limits.max_image_dimension.to_string(),
+                limits.max_images.to_string(),
+                if overrides.contains_key(p) { "yours" } else { "default" }.to_string(),
+            ]
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Set a provider's limits, keeping the ones not given
+pub fn set(
+    provider: &str,
+    request_mb: Option<u64>,
+    image_mb: Option<u64>,
+    dimension: Option<u32>,
+    images: Option<usize>,
+) -> CliResult<()> {
+    let table = LimitsTable::new();
+    let current = table.for_provider(provider);
+    let limits = RequestLimits {
+        max_request_bytes: request_mb.map(from_mb).unwrap_or(current.max_request_bytes),
+        max_image_bytes: image_mb.map(from_mb).unwrap_or(current.max_image_bytes),
+        max_image_dimension: dimension.unwrap_or(current.max_image_dimension),
+        max_images: images.unwrap_or(current.max_images),
+    };
+    table.set(provider, limits)?;
+    print_success(&format!(
+        "{} requests are limited to {}, images to {} and {} pixels, {} images at most",
+        provider,
+        format_bytes(limits.max_request_bytes),
+        format_bytes(limits.max_image_bytes),
+        limits.max_image_dimension,
+        limits.max_images
+    ));
+    Ok(())
+}
+
+/// Go back to a provider's documented limits
+pub fn reset(provider: &str) -> CliResult<()> {
+    if LimitsTable::new().reset(provider)? {
+        print_success(&format!("{} is back to its documented limits", provider));
+    } else {
+        print_info(&format!("{} has no limits of yours", provider));
+    }
+    Ok(())
+}
+
+/// Store a smaller copy of an image, within the limits of the provider of
+/// its conversation
+pub async fn downscale(chat_service: Arc<ChatService>, id: &str) -> CliResult<()> {
+    let store = get_attachment_store();
+    let attachment = store
+        .get(id)
+        .ok_or_else(|| CliError::InvalidArgument(format!("Attachment {} not found", id)))?;
+    let conversation = chat_service.get_conversation(&attachment.conversation_id).await?;
+    let limits = LimitsTable::new().for_provider(&conversation.model.provider);
+    let smaller = store.downscale(id, limits.max_image_dimension, limits.max_image_bytes)?;
+    print_success(&format!(
+        "Stored {} ({}, was {}) as {}",
+        smaller.file_name,
+        format_bytes(smaller.size),
+        format_bytes(attachment.size),
+        smaller.id
+    ));
+    Ok(())
+}
+
+/// Check a message with attachments against the provider's limits
+pub async fn check(
+    chat_service: Arc<ChatService>,
+    conversation_id: &str,
+    message: &str,
+    attachments: &[String],
+) -> CliResult<()> {
+    let preflight = chat_service.preflight(conversation_id, message, attachments).await?;
+    print_info(&format!(
+        "Request of {} with {} image(s), within {} and {} images",
+        format_bytes(preflight.request_bytes),
+        preflight.images.len(),
+        format_bytes(preflight.limits.max_request_bytes),
+        preflight.limits.max_images
+    ));
+    if preflight.passed() {
+        print_success("The request is within the provider's limits");
+        return Ok(());
+    }
+    for problem in &preflight.problems {
+        print_warning(&problem.to_string());
+    }
+    for fix in preflight.fixes() {
+        match fix {
+            Fix::Downscale => print_info("Send with --downscale to store smaller copies of the images"),
+            Fix::Split => print_info("Send with --split to send the images over several requests"),
+        }
+    }
+    Ok(())
+}
+
+/// Send a message with attachments and print the replies
+pub async fn send(
+    chat_service: Arc<ChatService>,
+    conversation_id: &str,
+    message: &str,
+    attachments: &[String],
+    fit: Fit,
+) -> CliResult<()> {
+    let replies = chat_service
+        .send_message_with_attachments(conversation_id, message, attachments, fit)
+        .await?;
+    for reply in &replies {
+        println!("{}", format_message(reply, MessageFormat::Colored));
+    }
+    Ok(())
+}
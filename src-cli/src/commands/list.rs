@@ -6,11 +6,18 @@ use crate::error::CliResult;
 use mcp_common::service::ChatService;
 
 /// Run the list command
-pub async fn run(chat_service: Arc<ChatService>) -> CliResult<()> {
+pub async fn run(chat_service: Arc<ChatService>, all: bool, trashed: bool) -> CliResult<()> {
+    if trashed {
+        return run_trashed(chat_service).await;
+    }
+    
     let spinner = show_spinner();
     spinner.set_message("Loading conversations...");
     
-    let conversations = chat_service.list_conversations().await?;
+    let mut conversations = chat_service.list_conversations().await?;
+    if !all {
+        conversations.retain(|c| !ChatService::is_archived(c));
+    }
     
     if conversations.is_empty() {
         spinner.info("No conversations found");
@@ -70,3 +77,58 @@ pub async fn run(chat_service: Arc<ChatService>) -> CliResult<()> {
     
     Ok(())
 }
+
+/// List conversations in the trash
+async fn run_trashed(chat_service: Arc<ChatService>) -> CliResult<()> {
+    let spinner = show_spinner();
+    spinner.set_message("Loading trash...");
+    
+    let trashed = chat_service.list_trashed().await?;
+    
+    if trashed.is_empty() {
+        spinner.info("Trash is empty");
+        return Ok(());
+    }
+    
+    spinner.success(&format!("Found {} trashed conversations", trashed.len()));
+    
+    let columns = vec![
+        TableColumn {
+            title: "ID".to_string(),
+            width: 36,
+            style: Some(Style::new().dim()),
+        },
+        TableColumn {
+            title: "Title".to_string(),
+            width: 30,
+            style: Some(Style::new().cyan()),
+        },
+        TableColumn {
+            title: "Deleted".to_string(),
+            width: 20,
+            style: None,
+        },
+        TableColumn {
+            title: "Purged After".to_string(),
+            width: 20,
+            style: Some(Style::new().yellow()),
+        },
+    ];
+    
+    let rows: Vec<Vec<String>> = trashed
+        .into_iter()
+        .map(|entry| {
+            vec![
+                entry.conversation.id,
+                entry.conversation.title,
+                entry.deleted_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string(),
+                entry.expires_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string(),
+            ]
+        })
+        .collect();
+    
+    print_table(&columns, &rows)?;
+    print_info("Restore with `mcp restore ID`, delete for good with `mcp purge ID`");
+    
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use crate::display::{print_info, print_success, show_spinner_with_message};
+use crate::error::{CliError, CliResult};
+use mcp_common::auth;
+use mcp_common::config::{get_settings, OAuthSettings};
+
+/// Gateway settings given on the command line
+pub struct GatewayArgs {
+    pub device_url: Option<String>,
+    pub token_url: Option<String>,
+    pub client_id: Option<String>,
+    pub scopes: Vec<String>,
+    pub audience: Option<String>,
+}
+
+/// Log in to an OAuth gateway with the device-code flow
+pub async fn login(gateway: GatewayArgs) -> CliResult<()> {
+    // Flags update the stored gateway configuration
+    {
+        let settings = get_settings();
+        let mut settings = settings.lock().unwrap();
+        let oauth = &mut settings.auth.oauth;
+        let previous: OAuthSettings = oauth.clone();
+        if gateway.device_url.is_some() {
+            oauth.device_authorization_url = gateway.device_url;
+        }
+        if gateway.token_url.is_some() {
+            oauth.token_url = gateway.token_url;
+        }
+        if gateway.client_id.is_some() {
+            oauth.client_id = gateway.client_id;
+        }
+        if !gateway.scopes.is_empty() {
+            oauth.scopes = gateway.scopes;
+        }
+        if gateway.audience.is_some() {
+            oauth.audience = gateway.audience;
+        }
+        if *oauth != previous {
+            settings.save()?;
+        }
+    }
+
+    let authorization = auth::start_device_login().await?;
+    println!();
+    print_info(&format!("Open {} and enter the code:", authorization.verification_uri));
+    println!();
+    println!("    {}", authorization.user_code);
+    println!();
+    if let Some(url) = &authorization.verification_uri_complete {
+        print_info(&format!("Or open {} to skip entering it", url));
+    }
+
+    let spinner = show_spinner_with_message("Waiting for you to approve the login...");
+    match auth::poll_device_login(&authorization).await {
+        Ok(_) => {
+            spinner.success("Logged in; requests now use your gateway login");
+            Ok(())
+        }
+        Err(e) => {
+            spinner.error(&format!("Login failed: {}", e));
+            Err(e.into())
+        }
+    }
+}
+
+/// Forget the OAuth login and go back to the API key
+pub fn logout() -> CliResult<()> {
+    if !auth::oauth_status().logged_in {
+        return Err(CliError::InvalidArgument("Not logged in".to_string()));
+    }
+    auth::logout()?;
+    print_success("Logged out; requests use the API key again");
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use console::style;
+use dialoguer::{Confirm, MultiSelect, Select};
+use std::path::PathBuf;
+
+use crate::display::{print_info, print_success, print_table, print_warning, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::logs::{logs_dir, BundleItem, LogBundle};
+
+/// Format a byte count for display
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn print_items(items: &[BundleItem]) -> CliResult<()> {
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let columns = vec![column("File", 24), column("Size", 10), column("Contents", 44)];
+    let rows: Vec<Vec<String>> = items
+        .iter()
+        .map(|item| vec![item.name.clone(), format_size(item.size() as u64), item.description.clone()])
+        .collect();
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+fn print_item(item: &BundleItem) {
+    println!("{}", style(format!("── {} ──", item.name)).bold());
+    println!("{}", item.content);
+    println!();
+}
+
+/// Let the user read files until they choose to continue
+fn review(items: &[BundleItem]) -> CliResult<()> {
+    let mut labels: Vec<String> = items.iter().map(|item| format!("Read {}", item.name)).collect();
+    labels.push("Continue".to_string());
+
+    loop {
+        let choice = Select::new()
+            .with_prompt("Review what will be shared")
+            .items(&labels)
+            .default(labels.len() - 1)
+            .interact()?;
+        match items.get(choice) {
+            Some(item) => print_item(item),
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Collect recent logs, redacted settings and diagnostics, let the user
+/// review and pick the files, and zip them
+pub fn bundle(days: u32, output: Option<String>, show: bool, yes: bool) -> CliResult<()> {
+    let mut bundle = LogBundle::collect(days)?;
+    let output = output
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(bundle.file_name()));
+
+    print_info("Secrets are redacted from every file; check nothing else is private before sharing");
+    print_items(&bundle.items)?;
+    println!();
+
+    if show {
+        bundle.items.iter().for_each(print_item);
+    }
+
+    if !yes {
+        review(&bundle.items)?;
+
+        let labels: Vec<&str> = bundle.items.iter().map(|item| item.name.as_str()).collect();
+        let defaults = vec![true; labels.len()];
+        let selection = MultiSelect::new()
+            .with_prompt("Files to include")
+            .items(&labels)
+            .defaults(&defaults)
+            .interact()?;
+        let mut index = 0;
+        bundle.items.retain(|_| {
+            index += 1;
+            selection.contains(&(index - 1))
+        });
+        if bundle.items.is_empty() {
+            print_warning("No files selected");
+            return Ok(());
+        }
+
+        if !Confirm::new()
+            .with_prompt(format!(
+                "Write {} file(s) ({}) to {}?",
+                bundle.items.len(),
+                format_size(bundle.size() as u64),
+                output.display()
+            ))
+            .default(true)
+            .interact()?
+        {
+            return Err(CliError::Cancelled);
+        }
+    }
+
+    let size = bundle.write_zip(&output)?;
+    print_success(&format!("Wrote {} ({})", output.display(), format_size(size)));
+    Ok(())
+}
+
+/// Show where log files are kept
+pub fn path() {
+    println!("{}", logs_dir().display());
+}
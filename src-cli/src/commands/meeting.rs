@@ -0,0 +1,95 @@
+use chrono::NaiveDate;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::display::{print_info, print_success, show_spinner_with_message};
+use crate::error::{CliError, CliResult};
+use mcp_common::service::meetings::{MeetingSummarizer, SummaryFormat, Transcript};
+use mcp_common::service::ChatService;
+use mcp_common::utils::clock;
+
+/// Summarize a transcript file, or text typed or piped in when the file is `-`
+#[allow(clippy::too_many_arguments)]
+pub async fn summarize(
+    chat_service: Arc<ChatService>,
+    file: PathBuf,
+    format: String,
+    title: Option<String>,
+    date: Option<String>,
+    model: Option<String>,
+    markdown: Option<PathBuf>,
+    ics: Option<PathBuf>,
+) -> CliResult<()> {
+    let format: SummaryFormat = format.parse()?;
+    let date = match date {
+        Some(date) => NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+            .map_err(|_| CliError::InvalidArgument(format!("Invalid date '{}'; expected YYYY-MM-DD", date)))?,
+        None => clock::now().date_naive(),
+    };
+
+    let transcript = if file.as_os_str() == "-" {
+        read_live().await?
+    } else {
+        Transcript::parse(&std::fs::read_to_string(&file)?)
+    };
+    if transcript.is_empty() {
+        return Err(CliError::InputError("The transcript is empty".to_string()));
+    }
+
+    let mut summarizer = MeetingSummarizer::new(chat_service.clone()).with_format(format);
+    if let Some(name) = model {
+        let available = chat_service.available_models().await?;
+        match available.into_iter().find(|m| m.id == name || m.name == name) {
+            Some(model) => summarizer = summarizer.with_model(model),
+            None => return Err(CliError::InvalidArgument(format!("Unknown model: {}", name))),
+        }
+    }
+
+    let spinner = show_spinner_with_message(&format!(
+        "Summarizing {} line(s) from {} participant(s)...",
+        transcript.lines.len(),
+        transcript.participants().len()
+    ));
+    let notes = match summarizer.summarize(&transcript, title.as_deref(), date).await {
+        Ok(notes) => {
+            spinner.success(&format!(
+                "{} decision(s), {} action item(s)",
+                notes.decisions.len(),
+                notes.action_items.len()
+            ));
+            notes
+        }
+        Err(e) => {
+            spinner.error("Summarizing failed");
+            return Err(e.into());
+        }
+    };
+
+    if markdown.is_none() && ics.is_none() {
+        println!("{}", notes.to_markdown());
+    }
+    if let Some(path) = markdown {
+        std::fs::write(&path, notes.to_markdown())?;
+        print_success(&format!("Notes written to {}", path.display()));
+    }
+    if let Some(path) = ics {
+        if notes.action_items.is_empty() {
+            print_info("No action items; the task file has no tasks");
+        }
+        std::fs::write(&path, notes.to_ics())?;
+        print_success(&format!("Tasks written to {}", path.display()));
+    }
+    Ok(())
+}
+
+/// Collect a transcript line by line until the input ends
+async fn read_live() -> CliResult<Transcript> {
+    print_info("Reading the transcript; end it with Ctrl-D");
+    let mut transcript = Transcript::default();
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
+        transcript.push_text(&line);
+    }
+    Ok(transcript)
+}
@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_table, TableColumn};
+use crate::error::CliResult;
+use mcp_common::memory::get_memory_store;
+use mcp_common::service::ChatService;
+
+/// Describe where notes apply
+fn scope(workspace: Option<&str>) -> String {
+    match workspace {
+        Some(w) => format!("workspace '{}'", w),
+        None => "conversations outside any workspace".to_string(),
+    }
+}
+
+/// List the notes of a workspace
+pub fn list(workspace: Option<String>) -> CliResult<()> {
+    let notes = get_memory_store().list(workspace.as_deref());
+    if notes.is_empty() {
+        print_info(&format!("Nothing remembered for {}", scope(workspace.as_deref())));
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "ID".to_string(),
+            width: 10,
+            style: None,
+        },
+        TableColumn {
+            title: "Note".to_string(),
+            width: 60,
+            style: None,
+        },
+        TableColumn {
+            title: "Updated".to_string(),
+            width: 18,
+            style: None,
+        },
+    ];
+    let rows: Vec<Vec<String>> = notes
+        .iter()
+        .map(|n| {
+            vec![
+                n.id.chars().take(8).collect(),
+                n.text.clone(),
+                n.updated_at.format("%Y-%m-%d %H:%M").to_string(),
+            ]
+        })
+        .collect();
+
+    print_info(&format!("Remembered for {}", scope(workspace.as_deref())));
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Remember facts, for a conversation's workspace or a given one
+pub async fn remember(
+    chat_service: Arc<ChatService>,
+    text: &str,
+    conversation_id: Option<String>,
+    workspace: Option<String>,
+) -> CliResult<()> {
+    let notes = match conversation_id {
+        Some(id) => chat_service.remember(&id, text).await?,
+        None => get_memory_store().remember(workspace.as_deref(), text, None)?,
+    };
+    for note in notes {
+        print_success(&format!("Remembered: {}", note.text));
+    }
+    Ok(())
+}
+
+/// Resolve a note ID, or the prefix shown by `memory list`
+fn resolve(id: &str) -> String {
+    let matches: Vec<String> = get_memory_store()
+        .all()
+        .into_iter()
+        .map(|n| n.id)
+        .filter(|n| n.starts_with(id))
+        .collect();
+    match matches.as_slice() {
+        [only] => only.clone(),
+        // Let the store report the unknown or ambiguous ID
+        _ => id.to_string(),
+    }
+}
+
+/// Change the text of a note
+pub fn edit(id: &str, text: &str) -> CliResult<()> {
+    let note = get_memory_store().update(&resolve(id), text)?;
+    print_success(&format!("Updated: {}", note.text));
+    Ok(())
+}
+
+/// Forget a note
+pub fn forget(id: &str) -> CliResult<()> {
+    get_memory_store().delete(&resolve(id))?;
+    print_success("Forgotten");
+    Ok(())
+}
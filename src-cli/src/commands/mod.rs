@@ -1,15 +1,58 @@
+pub mod agent;
+pub mod apply;
+pub mod archive;
+pub mod attachment;
+pub mod batch;
+pub mod bench;
+pub mod capabilities;
+pub mod catalog;
 pub mod chat;
+pub mod companion;
+pub mod daemon;
+pub mod debug;
 pub mod delete;
+pub mod env;
+pub mod evals;
+pub mod experiment;
 pub mod export;
+pub mod feedback;
+pub mod feeds;
+pub mod filter;
+pub mod github;
+pub mod inbound;
 pub mod interactive;
+pub mod issues;
+pub mod jobs;
+pub mod limits;
 pub mod list;
+pub mod login;
+pub mod logs;
+pub mod meeting;
+pub mod memory;
 pub mod model;
 pub mod new;
+pub mod pricing;
+pub mod rag;
+pub mod reminders;
+pub mod script;
 pub mod setup;
+pub mod share;
 pub mod show;
+pub mod stats;
+pub mod status;
+pub mod storage;
 pub mod system;
+pub mod team;
+pub mod templates;
+pub mod tools;
+pub mod translation;
+pub mod trash;
+pub mod undo;
+pub mod webhook;
 
 use clap::{Parser, Subcommand};
+use mcp_common::agent::AgentBudget;
+use std::path::PathBuf;
 
 /// MCP Client Command Line Interface
 #[derive(Parser)]
@@ -23,9 +66,25 @@ pub struct Cli {
     #[arg(short, long)]
     pub quiet: bool,
     
+    /// Language for messages, e.g. `de`; detected from the system by default
+    #[arg(long, global = true)]
+    pub lang: Option<String>,
+    
+    /// Keep all data in a directory next to the executable, or in DIR
+    #[arg(long, global = true, value_name = "DIR", num_args = 0..=1, require_equals = true, default_missing_value = "")]
+    pub portable: Option<String>,
+    
+    /// Run a scripted session from a YAML file instead of a subcommand, for smoke tests and demos
+    #[arg(long, value_name = "FILE")]
+    pub script: Option<PathBuf>,
+    
+    /// With --script, write a JUnit XML report to this file, or to stdout when no file is given
+    #[arg(long, requires = "script", num_args = 0..=1, default_missing_value = "-")]
+    pub junit: Option<String>,
+    
     /// Subcommand to execute
     #[command(subcommand)]
-    pub command: Commands,
+    pub command: Option<Commands>,
 }
 
 /// Available commands
@@ -47,7 +106,15 @@ pub enum Commands {
     },
     
     /// List conversations
-    List,
+    List {
+        /// Include archived conversations
+        #[arg(short, long)]
+        all: bool,
+        
+        /// List conversations in the trash instead
+        #[arg(long)]
+        trashed: bool,
+    },
     
     /// Create a new conversation
     New {
@@ -60,12 +127,50 @@ pub enum Commands {
         model: Option<String>,
     },
     
-    /// Delete a conversation
+    /// Move a conversation to the trash
     Delete {
         /// Conversation ID
         conversation_id: String,
     },
     
+    /// Archive conversations
+    Archive {
+        /// Conversation IDs
+        #[arg(required = true)]
+        conversation_ids: Vec<String>,
+        
+        /// Unarchive instead
+        #[arg(long)]
+        unarchive: bool,
+    },
+    
+    /// Undo the latest deletion, archive or message edit
+    Undo {
+        /// List the operations that can be undone instead
+        #[arg(short, long)]
+        list: bool,
+    },
+    
+    /// Redo the most recently undone operation
+    Redo,
+    
+    /// Restore a conversation from the trash
+    Restore {
+        /// Conversation ID
+        conversation_id: String,
+    },
+    
+    /// Permanently delete conversations from the trash
+    Purge {
+        /// Conversation IDs
+        #[arg(required_unless_present = "all")]
+        conversation_ids: Vec<String>,
+        
+        /// Empty the whole trash
+        #[arg(long)]
+        all: bool,
+    },
+    
     /// Show conversation details
     Show {
         /// Conversation ID
@@ -75,6 +180,43 @@ pub enum Commands {
     /// Configure API settings
     Setup,
     
+    /// Log in to an enterprise gateway with an OAuth device code
+    Login {
+        /// Device authorization endpoint of the identity provider
+        #[arg(long)]
+        device_url: Option<String>,
+        
+        /// Token endpoint of the identity provider
+        #[arg(long)]
+        token_url: Option<String>,
+        
+        /// OAuth client ID
+        #[arg(long)]
+        client_id: Option<String>,
+        
+        /// Scope to request; repeat for several
+        #[arg(long = "scope")]
+        scopes: Vec<String>,
+        
+        /// Audience to request tokens for
+        #[arg(long)]
+        audience: Option<String>,
+    },
+    
+    /// Forget the OAuth login and use the API key again
+    Logout,
+    
+    /// Check the API key and show connection health
+    Status {
+        /// Check again instead of using a recent result
+        #[arg(long)]
+        refresh: bool,
+        
+        /// Print the result as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
     /// Export a conversation
     Export {
         /// Conversation ID
@@ -87,6 +229,10 @@ pub enum Commands {
         /// Output file (default: stdout)
         #[arg(short, long)]
         output: Option<String>,
+        
+        /// Include earlier versions of edited messages
+        #[arg(long)]
+        history: bool,
     },
     
     /// Set system message for a conversation
@@ -106,12 +252,328 @@ pub enum Commands {
         conversation_id: Option<String>,
     },
     
+    /// Show usage over recent weeks
+    Stats {
+        /// Number of weeks to show
+        #[arg(short, long, default_value_t = 12)]
+        weeks: usize,
+        
+        /// Print the data as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Benchmark models with standardized prompts
+    Bench {
+        /// Models to benchmark (default: all available)
+        #[arg(short, long)]
+        model: Vec<String>,
+        
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        
+        /// Do not store the result in the benchmark history
+        #[arg(long)]
+        no_save: bool,
+    },
+    
+    /// Apply a diff or file blocks from an assistant message to a repository
+    Apply {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// Message to apply (default: the latest assistant message with changes)
+        #[arg(long)]
+        message_id: Option<String>,
+        
+        /// Repository root (default: the conversation's bound repository, then the current directory)
+        #[arg(short, long)]
+        repo: Option<PathBuf>,
+        
+        /// Hunks to apply by index, e.g. 1,3 (default: all)
+        #[arg(long, value_delimiter = ',')]
+        hunks: Vec<usize>,
+        
+        /// Check that the patch applies without writing files
+        #[arg(long)]
+        dry_run: bool,
+        
+        /// Apply without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    
+    /// Regression-test models against suites of prompts
+    Evals {
+        /// Evals subcommand
+        #[command(subcommand)]
+        command: EvalsCommands,
+    },
+    
+    /// Run prompts from a file as a non-interactive batch
+    Batch {
+        /// Batch subcommand
+        #[command(subcommand)]
+        command: BatchCommands,
+    },
+    
     /// Model management
     Model {
         /// Model subcommand
         #[command(subcommand)]
         command: ModelCommands,
     },
+    
+    /// Manage files attached to conversations
+    Attachment {
+        /// Attachment subcommand
+        #[command(subcommand)]
+        command: AttachmentCommands,
+    },
+    
+    /// Conversation storage maintenance
+    Storage {
+        /// Storage subcommand
+        #[command(subcommand)]
+        command: StorageCommands,
+    },
+    
+    /// Rate messages and export feedback
+    Feedback {
+        /// Feedback subcommand
+        #[command(subcommand)]
+        command: FeedbackCommands,
+    },
+    
+    /// A/B prompt experiments
+    Experiment {
+        /// Experiment subcommand
+        #[command(subcommand)]
+        command: ExperimentCommands,
+    },
+    
+    /// Content filters applied to responses
+    Filter {
+        /// Filter subcommand
+        #[command(subcommand)]
+        command: FilterCommands,
+    },
+    
+    /// Shared team prompts and conversations
+    Team {
+        /// Team subcommand
+        #[command(subcommand)]
+        command: TeamCommands,
+    },
+    
+    /// URLs called when events happen
+    Webhook {
+        /// Webhook subcommand
+        #[command(subcommand)]
+        command: WebhookCommands,
+    },
+    
+    /// Notes remembered for new conversations
+    Memory {
+        /// Memory subcommand
+        #[command(subcommand)]
+        command: MemoryCommands,
+    },
+    
+    /// Environment variables of workspaces and conversations, for tools and prompt templates
+    Env {
+        /// Environment subcommand
+        #[command(subcommand)]
+        command: EnvCommands,
+    },
+    
+    /// Tool calls: dry runs and what they would have done
+    Tools {
+        /// Tools subcommand
+        #[command(subcommand)]
+        command: ToolsCommands,
+    },
+    
+    /// Agents working through a task on their own
+    Agent {
+        /// Agent subcommand
+        #[command(subcommand)]
+        command: AgentCommands,
+    },
+    
+    /// Knowledge bases searched for context
+    Rag {
+        /// Knowledge base subcommand
+        #[command(subcommand)]
+        command: RagCommands,
+    },
+    
+    /// Background jobs such as model downloads
+    Jobs {
+        /// Jobs subcommand
+        #[command(subcommand)]
+        command: JobsCommands,
+    },
+    
+    /// Developer diagnostics
+    Debug {
+        /// Debug subcommand
+        #[command(subcommand)]
+        command: DebugCommands,
+    },
+    
+    /// Application logs
+    Logs {
+        /// Logs subcommand
+        #[command(subcommand)]
+        command: LogsCommands,
+    },
+    
+    /// Run in the background: deliver webhooks and feed digests, accept inbound messages and serve the browser extension
+    Daemon {
+        /// Address the inbound endpoint listens on
+        #[arg(long, default_value = "127.0.0.1:8765")]
+        listen: std::net::SocketAddr,
+        
+        /// Port the browser extension endpoint listens on, always on 127.0.0.1
+        #[arg(long, default_value_t = 8766)]
+        companion_port: u16,
+    },
+    
+    /// Routes that add incoming messages to conversations
+    Inbound {
+        /// Inbound subcommand
+        #[command(subcommand)]
+        command: InboundCommands,
+    },
+    
+    /// Meeting notes from transcripts
+    Meeting {
+        /// Meeting subcommand
+        #[command(subcommand)]
+        command: MeetingCommands,
+    },
+    
+    /// Translate conversations between your language and the model's
+    Translation {
+        /// Translation subcommand
+        #[command(subcommand)]
+        command: TranslationCommands,
+    },
+    
+    /// Code, issues and pull requests from GitHub
+    Github {
+        /// GitHub subcommand
+        #[command(subcommand)]
+        command: GithubCommands,
+    },
+    
+    /// Issues from Jira and Linear
+    Issues {
+        /// Issues subcommand
+        #[command(subcommand)]
+        command: IssuesCommands,
+    },
+    
+    /// Digests of RSS and Atom feeds
+    Feeds {
+        /// Feeds subcommand
+        #[command(subcommand)]
+        command: FeedsCommands,
+    },
+    
+    /// Follow-up reminders on conversations
+    Reminders {
+        /// Reminders subcommand
+        #[command(subcommand)]
+        command: RemindersCommands,
+    },
+    
+    /// Browser extensions paired with the daemon
+    Companion {
+        /// Companion subcommand
+        #[command(subcommand)]
+        command: CompanionCommands,
+    },
+    
+    /// Share conversations as HTML snapshots
+    Share {
+        /// Share subcommand
+        #[command(subcommand)]
+        command: ShareCommands,
+    },
+    
+    /// Prompt and workspace templates, and the community gallery
+    Templates {
+        /// Templates subcommand
+        #[command(subcommand)]
+        command: TemplatesCommands,
+    },
+}
+
+/// Evals subcommands
+#[derive(Subcommand)]
+pub enum EvalsCommands {
+    /// Run a suite of test cases and compare with the previous run
+    Run {
+        /// Suite file (.json)
+        suite: PathBuf,
+        
+        /// Models to evaluate (default: all available)
+        #[arg(short, long)]
+        model: Vec<String>,
+        
+        /// Cases run at the same time
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        
+        /// Write a JUnit XML report to this file, or to stdout when no file is given
+        #[arg(long, num_args = 0..=1, default_missing_value = "-")]
+        junit: Option<String>,
+        
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        
+        /// Do not store the result in the evaluation history
+        #[arg(long)]
+        no_save: bool,
+    },
+}
+
+/// Batch subcommands
+#[derive(Subcommand)]
+pub enum BatchCommands {
+    /// Run a JSONL or CSV file of prompts
+    Run {
+        /// Input file (.jsonl or .csv)
+        input: PathBuf,
+        
+        /// Write results to this JSONL file instead of saving conversations
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        
+        /// Use the provider's batch endpoint instead of the local queue
+        #[arg(long)]
+        provider: bool,
+        
+        /// Default model for prompts that don't set one
+        #[arg(short, long)]
+        model: Option<String>,
+        
+        /// Concurrent requests for the local queue
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        
+        /// Seconds between status checks when using the provider endpoint
+        #[arg(long, default_value_t = 30)]
+        poll_interval: u64,
+        
+        /// Alternate the variants of this experiment across the prompts
+        #[arg(short, long)]
+        experiment: Option<String>,
+    },
 }
 
 /// Model subcommands
@@ -131,7 +593,1757 @@ pub enum ModelCommands {
         /// Conversation ID
         conversation_id: String,
         
-        /// Model name
+        /// Model name or alias
+        model: String,
+    },
+    
+    /// List model aliases and their fallback chains
+    Aliases,
+    
+    /// Define an alias as an ordered fallback chain of models
+    Alias {
+        /// Alias name, e.g. "fast"
+        name: String,
+        
+        /// Models to try in order
+        #[arg(required = true)]
+        models: Vec<String>,
+        
+        /// Description shown in the alias list
+        #[arg(short, long)]
+        description: Option<String>,
+    },
+    
+    /// Remove a model alias
+    RemoveAlias {
+        /// Alias name
+        name: String,
+    },
+    
+    /// List model catalogs
+    Catalogs,
+    
+    /// Add a model catalog: a JSON manifest or a Hugging Face collection
+    AddCatalog {
+        /// Manifest or collection URL (http, https or file)
+        url: String,
+        
+        /// Display name; defaults to the host
+        #[arg(short, long)]
+        name: Option<String>,
+        
+        /// `manifest` or `huggingface`
+        #[arg(short, long, default_value = "manifest")]
+        format: String,
+        
+        /// `trusted`, `verify` (only models with checksums) or `blocked`
+        #[arg(short, long, default_value = "verify")]
+        trust: String,
+        
+        /// Access token sent to the catalog and its downloads
+        #[arg(long)]
+        token: Option<String>,
+    },
+    
+    /// Remove a model catalog
+    RemoveCatalog {
+        /// Catalog ID
+        id: String,
+    },
+    
+    /// Change how far a catalog is trusted
+    TrustCatalog {
+        /// Catalog ID
+        id: String,
+        
+        /// `trusted`, `verify` or `blocked`
+        #[arg(value_parser = ["trusted", "verify", "blocked"])]
+        trust: String,
+    },
+    
+    /// Store the access token of a catalog; omit the token to remove it
+    CatalogToken {
+        /// Catalog ID
+        id: String,
+        
+        /// Access token
+        token: Option<String>,
+    },
+    
+    /// Change how often a catalog is fetched
+    CatalogInterval {
+        /// Catalog ID
+        id: String,
+        
+        /// Hours between fetches
+        hours: u32,
+    },
+    
+    /// Fetch catalogs now
+    RefreshCatalogs {
+        /// Only this catalog
+        id: Option<String>,
+    },
+    
+    /// Search Hugging Face for GGUF models that fit this machine
+    SearchHf {
+        /// Search terms
+        query: String,
+        
+        /// Repositories to look at
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+        
+        /// Largest file size in GB instead of what fits in memory
+        #[arg(long)]
+        max_size: Option<f64>,
+        
+        /// Show files of any size
+        #[arg(long, conflicts_with = "max_size")]
+        all_sizes: bool,
+        
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Register a GGUF file from Hugging Face as a local model
+    AddHf {
+        /// Repository, e.g. TheBloke/Llama-2-7B-Chat-GGUF
+        repo: String,
+        
+        /// File in the repository; defaults to the largest that fits
+        file: Option<String>,
+        
+        /// Chat template to use instead of the one the file names:
+        /// chatml, llama3, mistral, zephyr, gemma or plain
+        #[arg(long)]
+        chat_template: Option<String>,
+    },
+    
+    /// Store the Hugging Face token for gated models; omit it to remove it
+    HfToken {
+        /// Access token
+        token: Option<String>,
+    },
+    
+    /// List model prices
+    Prices {
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Set the price of models whose ID starts with a prefix
+    SetPrice {
+        /// Model ID or prefix, e.g. claude-3-opus
+        model: String,
+        
+        /// US dollars per million prompt tokens
+        input: f64,
+        
+        /// US dollars per million response tokens
+        output: f64,
+    },
+    
+    /// Drop your price of a model and use the listed one
+    ResetPrice {
+        /// Model ID or prefix
         model: String,
     },
+    
+    /// Fetch the price list now
+    RefreshPrices {
+        /// Price list URL to use from now on
+        url: Option<String>,
+    },
+    
+    /// Show or set the currency costs are shown in
+    Currency {
+        /// Currency code, e.g. EUR
+        code: Option<String>,
+        
+        /// Units of the currency per US dollar, overriding the listed rate
+        #[arg(long, requires = "code")]
+        rate: Option<f64>,
+    },
+    
+    /// Show what models can do: vision, tools, streaming and JSON mode
+    Capabilities {
+        /// Only models whose ID starts with this
+        model: Option<String>,
+        
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Correct whether a provider, or models by ID prefix, have a feature
+    SetCapability {
+        /// Provider name or model ID prefix
+        target: String,
+        
+        /// `vision`, `tools`, `streaming` or `json_mode`
+        feature: String,
+        
+        /// `on` or `off`
+        #[arg(value_parser = ["on", "off"])]
+        state: String,
+    },
+    
+    /// Drop corrections of a provider or model prefix
+    ResetCapability {
+        /// Provider name or model ID prefix
+        target: String,
+        
+        /// Only this feature
+        feature: Option<String>,
+    },
+    
+    /// Ask for replies in JSON in a conversation
+    JsonMode {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// `on` or `off`
+        #[arg(value_parser = ["on", "off"])]
+        state: String,
+    },
+    
+    /// Show request size limits by provider
+    Limits {
+        /// Only this provider
+        provider: Option<String>,
+        
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Set a provider's request size limits; unset ones stay as they are
+    SetLimits {
+        /// Provider name
+        provider: String,
+        
+        /// Largest request, in megabytes
+        #[arg(long)]
+        request_mb: Option<u64>,
+        
+        /// Largest image, in megabytes
+        #[arg(long)]
+        image_mb: Option<u64>,
+        
+        /// Longest side of an image, in pixels
+        #[arg(long)]
+        dimension: Option<u32>,
+        
+        /// Most images in one request
+        #[arg(long)]
+        images: Option<usize>,
+    },
+    
+    /// Go back to a provider's documented limits
+    ResetLimits {
+        /// Provider name
+        provider: String,
+    },
+}
+
+/// Experiment subcommands
+#[derive(Subcommand)]
+pub enum ExperimentCommands {
+    /// Start an experiment; new conversations alternate between its variants
+    Create {
+        /// Experiment name
+        name: String,
+        
+        /// Variant as "name=system prompt"; give at least two
+        #[arg(short, long = "variant", required = true)]
+        variants: Vec<String>,
+        
+        /// Prompt template per variant as "name=template", using {prompt} for the user's text
+        #[arg(short, long = "template")]
+        templates: Vec<String>,
+    },
+    
+    /// List experiments
+    List,
+    
+    /// Compare the ratings of an experiment's variants
+    Report {
+        /// Experiment ID or name
+        experiment: String,
+        
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Stop enrolling new conversations
+    Stop {
+        /// Experiment ID or name
+        experiment: String,
+    },
+}
+
+/// Team subcommands
+#[derive(Subcommand)]
+pub enum TeamCommands {
+    /// Join a self-hosted team server
+    Join {
+        /// Team server URL
+        url: String,
+        
+        /// Access token; asked for when omitted
+        #[arg(long)]
+        token: Option<String>,
+    },
+    
+    /// Leave the team and remove its data from this device
+    Leave,
+    
+    /// Show team, role and sync state
+    Status,
+    
+    /// Push local changes and pull the team's
+    Sync,
+    
+    /// List the team prompt library
+    Prompts,
+    
+    /// Print a prompt from the library
+    ShowPrompt {
+        /// Prompt name or ID
+        name: String,
+    },
+    
+    /// Add a prompt to the library, or update the one with that name
+    AddPrompt {
+        /// Prompt name
+        name: String,
+        
+        /// Prompt text
+        content: String,
+        
+        /// What the prompt is for
+        #[arg(short, long)]
+        description: Option<String>,
+        
+        /// Tag for filtering; repeat for several
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+    },
+    
+    /// Remove a prompt from the library
+    RemovePrompt {
+        /// Prompt name or ID
+        name: String,
+    },
+    
+    /// Share a conversation's title and details with the team
+    Share {
+        /// Conversation ID
+        conversation_id: String,
+    },
+    
+    /// Stop sharing a conversation
+    Unshare {
+        /// Conversation ID
+        conversation_id: String,
+    },
+    
+    /// List conversations shared with the team
+    Shared,
+    
+    /// List team members
+    Members,
+    
+    /// Invite someone by email (admins only)
+    Invite {
+        /// Email address
+        email: String,
+        
+        /// Role (viewer, member or admin)
+        #[arg(short, long, default_value = "member")]
+        role: String,
+    },
+    
+    /// Change a member's role (admins only)
+    SetRole {
+        /// Member ID
+        member_id: String,
+        
+        /// Role (viewer, member or admin)
+        role: String,
+    },
+    
+    /// Remove a member from the team (admins only)
+    RemoveMember {
+        /// Member ID
+        member_id: String,
+    },
+}
+
+/// Webhook subcommands
+#[derive(Subcommand)]
+pub enum WebhookCommands {
+    /// List registered webhooks
+    List,
+    
+    /// Register a URL to be called on events
+    Add {
+        /// URL called with a POST; https, or http to localhost
+        url: String,
+        
+        /// Event to call it on (message_completed, conversation_created,
+        /// budget_exceeded); repeat for several, all of them by default
+        #[arg(short, long = "event")]
+        events: Vec<String>,
+        
+        /// What the webhook is for
+        #[arg(short, long)]
+        description: Option<String>,
+    },
+    
+    /// Remove a webhook
+    Remove {
+        /// Webhook ID or the start of it
+        id: String,
+    },
+    
+    /// Resume calling a webhook
+    Enable {
+        /// Webhook ID or the start of it
+        id: String,
+    },
+    
+    /// Stop calling a webhook for now, dropping its waiting deliveries
+    Disable {
+        /// Webhook ID or the start of it
+        id: String,
+    },
+    
+    /// Change the events a webhook is called on
+    Events {
+        /// Webhook ID or the start of it
+        id: String,
+        
+        /// Events to call it on; all of them when none are given
+        events: Vec<String>,
+    },
+    
+    /// Replace a webhook's signing secret and print the new one
+    RotateSecret {
+        /// Webhook ID or the start of it
+        id: String,
+    },
+    
+    /// Send a test ping
+    Test {
+        /// Webhook ID or the start of it
+        id: String,
+    },
+    
+    /// Show deliveries waiting to be retried
+    Queue,
+    
+    /// Retry the deliveries that are due now
+    Retry,
+}
+
+/// Inbound subcommands
+#[derive(Subcommand)]
+pub enum InboundCommands {
+    /// List routes
+    List,
+    
+    /// Create a route to a conversation and print its token
+    Add {
+        /// Route name, used in the URL
+        name: String,
+        
+        /// Conversation the messages are added to
+        conversation_id: String,
+        
+        /// Generate a reply to each message and return it
+        #[arg(long)]
+        reply: bool,
+    },
+    
+    /// Remove a route
+    Remove {
+        /// Route name
+        name: String,
+    },
+    
+    /// Replace a route's token and print the new one
+    RotateToken {
+        /// Route name
+        name: String,
+    },
+}
+
+/// Meeting subcommands
+#[derive(Subcommand)]
+pub enum MeetingCommands {
+    /// Summarize a transcript into notes, decisions and action items
+    Summarize {
+        /// Transcript file (text, WebVTT or SRT), or `-` to type or pipe it in
+        file: PathBuf,
+        
+        /// Summary format (detailed, concise or bullet)
+        #[arg(short, long, default_value = "detailed")]
+        format: String,
+        
+        /// Meeting title (default: suggested by the model)
+        #[arg(short, long)]
+        title: Option<String>,
+        
+        /// Day of the meeting, YYYY-MM-DD, for resolving due dates (default: today)
+        #[arg(short, long)]
+        date: Option<String>,
+        
+        /// Model to summarize with
+        #[arg(short, long)]
+        model: Option<String>,
+        
+        /// Write the notes as Markdown to this file
+        #[arg(long, value_name = "FILE")]
+        markdown: Option<PathBuf>,
+        
+        /// Write the action items as calendar tasks (.ics) to this file
+        #[arg(long, value_name = "FILE")]
+        ics: Option<PathBuf>,
+    },
+}
+
+/// Translation subcommands
+#[derive(Subcommand)]
+pub enum TranslationCommands {
+    /// Show the settings and which providers are ready
+    Show,
+    
+    /// Translate every conversation without a language of its own
+    Enable {
+        /// Your language, or `auto` to detect it from each message
+        #[arg(long, default_value = "auto")]
+        user: String,
+        
+        /// Language the model is addressed in
+        #[arg(long, default_value = "en")]
+        model: String,
+    },
+    
+    /// Translate only conversations with a language of their own
+    Disable,
+    
+    /// Choose the provider (deepl, google or local)
+    Provider {
+        /// Provider name
+        provider: String,
+        
+        /// Registry model for local translation
+        #[arg(long)]
+        model: Option<String>,
+    },
+    
+    /// Store the API key of DeepL or Google; asks for it when not given
+    Key {
+        /// Provider name
+        provider: String,
+        
+        /// API key
+        key: Option<String>,
+        
+        /// Remove the stored key
+        #[arg(long, conflicts_with = "key")]
+        remove: bool,
+    },
+    
+    /// Set the languages of a conversation
+    Language {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// Your language, or `auto` to detect it; stops translating when left out
+        language: Option<String>,
+        
+        /// Language the model is addressed in
+        #[arg(long, default_value = "en")]
+        model: String,
+        
+        /// Provider for this conversation
+        #[arg(long)]
+        provider: Option<String>,
+    },
+    
+    /// Translate a text
+    Text {
+        /// Text to translate
+        text: String,
+        
+        /// Target language
+        #[arg(long)]
+        to: String,
+        
+        /// Source language (default: detected)
+        #[arg(long)]
+        from: Option<String>,
+    },
+}
+
+/// Feedback subcommands
+#[derive(Subcommand)]
+pub enum FeedbackCommands {
+    /// Rate a message
+    Rate {
+        /// Message ID
+        message_id: String,
+        
+        /// Rating (up, down or clear)
+        rating: String,
+        
+        /// Reason for the rating
+        #[arg(short, long)]
+        comment: Option<String>,
+    },
+    
+    /// Export rated messages with their prompts as JSONL
+    Export {
+        /// Output file
+        output: PathBuf,
+    },
+}
+
+/// Storage subcommands
+#[derive(Subcommand)]
+pub enum StorageCommands {
+    /// Deduplicate large message parts and delete unused blobs
+    Compact,
+    
+    /// Show disk usage by category
+    Usage,
+}
+
+/// Attachment subcommands
+#[derive(Subcommand)]
+pub enum AttachmentCommands {
+    /// Attach a file to a conversation
+    Add {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// File to attach
+        path: PathBuf,
+    },
+    
+    /// List attachments with their sizes
+    List {
+        /// Only show attachments of this conversation
+        conversation_id: Option<String>,
+    },
+    
+    /// Open an attachment with the default application
+    Open {
+        /// Attachment ID
+        id: String,
+    },
+    
+    /// Delete an attachment
+    Delete {
+        /// Attachment ID
+        id: String,
+    },
+    
+    /// Store a smaller copy of an image within its provider's limits
+    Downscale {
+        /// Attachment ID
+        id: String,
+    },
+    
+    /// Check a message with attachments against the provider's limits
+    Check {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// Message content
+        message: String,
+        
+        /// Attachment IDs to send with the message
+        #[arg(short, long)]
+        attach: Vec<String>,
+    },
+    
+    /// Send a message with attachments
+    Send {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// Message content
+        message: String,
+        
+        /// Attachment IDs to send with the message
+        #[arg(short, long)]
+        attach: Vec<String>,
+        
+        /// Downscale images over the provider's limits
+        #[arg(long)]
+        downscale: bool,
+        
+        /// Send images over several requests when they don't fit in one
+        #[arg(long)]
+        split: bool,
+    },
+}
+
+/// Content filter subcommands
+#[derive(Subcommand)]
+pub enum FilterCommands {
+    /// Show the filters in effect
+    Show {
+        /// Workspace whose filters to show
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    
+    /// Configure a built-in filter (profanity on|off, secrets off|flag|redact)
+    Set {
+        /// Filter name
+        filter: String,
+        
+        /// New value
+        value: String,
+        
+        /// Workspace to configure instead of the default profile
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    
+    /// Add a custom regex replacement
+    AddRule {
+        /// Regular expression to match
+        pattern: String,
+        
+        /// Replacement text
+        replacement: String,
+        
+        /// What the rule is for
+        #[arg(short, long)]
+        description: Option<String>,
+        
+        /// Workspace to configure instead of the default profile
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    
+    /// Remove a custom replacement by its number
+    RemoveRule {
+        /// Number shown by `filter show`
+        number: usize,
+        
+        /// Workspace to configure instead of the default profile
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+}
+
+/// Memory subcommands
+#[derive(Subcommand)]
+pub enum MemoryCommands {
+    /// List remembered notes
+    List {
+        /// Workspace whose notes to list
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    
+    /// Remember facts for later conversations
+    Remember {
+        /// Facts to remember; each sentence becomes a note
+        text: String,
+        
+        /// Conversation whose workspace the notes belong to
+        #[arg(short, long, conflicts_with = "workspace")]
+        conversation: Option<String>,
+        
+        /// Workspace the notes belong to
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    
+    /// Change the text of a note
+    Edit {
+        /// Note ID, or the prefix shown by `memory list`
+        id: String,
+        
+        /// New text
+        text: String,
+    },
+    
+    /// Forget a note
+    Forget {
+        /// Note ID, or the prefix shown by `memory list`
+        id: String,
+    },
+}
+
+/// Environment subcommands
+#[derive(Subcommand)]
+pub enum EnvCommands {
+    /// List the variables of a workspace or conversation
+    List {
+        /// Conversation whose variables to list
+        #[arg(short, long, conflicts_with = "workspace", required_unless_present = "workspace")]
+        conversation: Option<String>,
+        
+        /// Workspace whose variables to list
+        #[arg(short, long)]
+        workspace: Option<String>,
+        
+        /// List everything the conversation sees, its workspace's variables included
+        #[arg(long, requires = "conversation")]
+        resolved: bool,
+    },
+    
+    /// Set a variable
+    Set {
+        /// Variable name; letters, digits and underscores
+        name: String,
+        
+        /// Value; asked for without echo when left out for a secret
+        #[arg(required_unless_present = "secret")]
+        value: Option<String>,
+        
+        /// Keep the value in the vault and mask it in logs and exports
+        #[arg(short, long)]
+        secret: bool,
+        
+        /// Conversation the variable belongs to
+        #[arg(short, long, conflicts_with = "workspace", required_unless_present = "workspace")]
+        conversation: Option<String>,
+        
+        /// Workspace the variable belongs to
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    
+    /// Remove a variable
+    Unset {
+        /// Variable name
+        name: String,
+        
+        /// Conversation the variable belongs to
+        #[arg(short, long, conflicts_with = "workspace", required_unless_present = "workspace")]
+        conversation: Option<String>,
+        
+        /// Workspace the variable belongs to
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+}
+
+/// Tools subcommands
+#[derive(Subcommand)]
+pub enum ToolsCommands {
+    /// Show, or turn on or off, dry runs: tool calls are recorded with what they would do instead of executed
+    DryRun {
+        /// `on` or `off`; shows the current state when left out
+        #[arg(value_parser = ["on", "off"])]
+        state: Option<String>,
+    },
+    
+    /// Show the tool calls of a conversation recorded during dry runs
+    Audit {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// Print the calls as JSON
+        #[arg(long)]
+        json: bool,
+        
+        /// Forget the recorded calls afterwards
+        #[arg(long)]
+        clear: bool,
+    },
+    
+    /// Show the tool policy of a workspace, or the global one
+    Policy {
+        /// Workspace; the global policy when left out
+        #[arg(short, long)]
+        workspace: Option<String>,
+        
+        /// Print the policy as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Add a rule allowing, asking about or denying tool calls
+    AddRule {
+        /// What the rule does with matching calls
+        #[arg(value_parser = ["allow", "ask", "deny"])]
+        effect: String,
+        
+        /// Tool name; `*` matches any run of characters
+        #[arg(long)]
+        tool: Option<String>,
+        
+        /// Regex over the call's arguments, as JSON
+        #[arg(long = "args")]
+        arguments: Option<String>,
+        
+        /// Directory the call's paths are checked against; repeat for several
+        #[arg(long = "path")]
+        paths: Vec<String>,
+        
+        /// Host the call's URLs are checked against, such as `*.example.com`; repeat for several
+        #[arg(long = "host")]
+        hosts: Vec<String>,
+        
+        /// Why the rule exists, told to the model when it denies a call
+        #[arg(long)]
+        reason: Option<String>,
+        
+        /// Workspace the rule applies in; everywhere when left out
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    
+    /// Remove a rule from a tool policy
+    RemoveRule {
+        /// Rule ID
+        id: String,
+        
+        /// Workspace the rule applies in; the global policy when left out
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    
+    /// Set what happens to tool calls no rule matches
+    PolicyDefault {
+        /// `allow`, `ask` or `deny`; `unset` leaves it to the global policy, and to allowing them
+        #[arg(value_parser = ["allow", "ask", "deny", "unset"])]
+        effect: String,
+        
+        /// Workspace; everywhere when left out
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    
+    /// Show what the tool policies would do with a call, without making it
+    TestPolicy {
+        /// Tool name
+        tool: String,
+        
+        /// Arguments, as JSON
+        #[arg(default_value = "{}")]
+        arguments: String,
+        
+        /// Workspace the call would be made in
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+}
+
+/// Agent subcommands
+#[derive(Subcommand)]
+pub enum AgentCommands {
+    /// Let an agent work on a task in a conversation, showing its plan and steps; Ctrl+C aborts it
+    Run {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// Task to work on
+        task: String,
+        
+        /// Most replies of the model
+        #[arg(long, default_value_t = AgentBudget::default().max_steps)]
+        max_steps: u32,
+        
+        /// Longest the run may take, in seconds
+        #[arg(long, default_value_t = AgentBudget::default().max_seconds)]
+        max_seconds: u64,
+        
+        /// Most tokens sent and received, estimated
+        #[arg(long, default_value_t = AgentBudget::default().max_tokens)]
+        max_tokens: u64,
+        
+        /// Give the task to the agent team instead; its agents have budgets of their own
+        #[arg(long)]
+        team: bool,
+    },
+    
+    /// Show the transcript of the latest agent run in a conversation
+    Transcript {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// Print the transcript as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// List the agents of the team and the rules routing tasks to them
+    List,
+    
+    /// Add an agent to the team, or replace the one of its name
+    Add {
+        /// Name; letters, digits, '-' and '_'
+        name: String,
+        
+        /// System prompt the agent works under
+        #[arg(long)]
+        prompt: Option<String>,
+        
+        /// Tool the agent may call; repeat for several, leave out for every tool
+        #[arg(long = "tool")]
+        tools: Vec<String>,
+        
+        /// Most replies of the model per run
+        #[arg(long, default_value_t = AgentBudget::default().max_steps)]
+        max_steps: u32,
+        
+        /// Longest a run may take, in seconds
+        #[arg(long, default_value_t = AgentBudget::default().max_seconds)]
+        max_seconds: u64,
+        
+        /// Most tokens sent and received per run, estimated
+        #[arg(long, default_value_t = AgentBudget::default().max_tokens)]
+        max_tokens: u64,
+    },
+    
+    /// Remove an agent from the team, with the rules routing to it
+    Remove {
+        /// Agent name
+        name: String,
+    },
+    
+    /// Start tasks matching a pattern with an agent
+    Route {
+        /// Regular expression matched against the task, ignoring case
+        pattern: String,
+        
+        /// Agent name
+        agent: String,
+    },
+    
+    /// Remove a routing rule
+    Unroute {
+        /// Pattern of the rule
+        pattern: String,
+    },
+    
+    /// Export the agent runs of a conversation and the handoffs between them
+    Graph {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// Print the graph in Graphviz's DOT language instead of JSON
+        #[arg(long)]
+        dot: bool,
+    },
+    
+    /// List the agent runs kept for replays, newest first
+    Runs {
+        /// Only the runs of this conversation
+        conversation_id: Option<String>,
+    },
+    
+    /// Step through a kept run: what the model was given, replied and got back from its tools
+    Replay {
+        /// Run ID or unique prefix
+        run_id: String,
+        
+        /// Show only this step
+        #[arg(long)]
+        step: Option<u32>,
+        
+        /// Print the steps as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Run a kept run again from one of its steps, in a copy of its conversation
+    Rerun {
+        /// Run ID or unique prefix
+        run_id: String,
+        
+        /// Step to run again from; the steps before it are kept
+        #[arg(long, default_value_t = 1)]
+        from: u32,
+        
+        /// Task to work on instead; only with `--from 1`
+        #[arg(long)]
+        task: Option<String>,
+        
+        /// Model to ask instead
+        #[arg(short, long)]
+        model: Option<String>,
+        
+        /// System prompt to work under instead
+        #[arg(long)]
+        system_prompt: Option<String>,
+        
+        /// Most replies of the model, counting the steps kept
+        #[arg(long)]
+        max_steps: Option<u32>,
+        
+        /// Longest the run may take, in seconds
+        #[arg(long)]
+        max_seconds: Option<u64>,
+        
+        /// Most tokens sent and received, estimated
+        #[arg(long)]
+        max_tokens: Option<u64>,
+    },
+    
+    /// Compare a kept run with another, by default the run it replays or the one before it
+    Diff {
+        /// Run ID or unique prefix
+        run_id: String,
+        
+        /// Run to compare with
+        other: Option<String>,
+        
+        /// Print the comparison as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Knowledge base subcommands
+#[derive(Subcommand)]
+pub enum RagCommands {
+    /// List knowledge bases
+    List,
+    
+    /// Create a knowledge base
+    Create {
+        /// Name; letters, digits, '-' and '_'
+        name: String,
+        
+        /// Embedding model, e.g. hashing-512
+        #[arg(short, long)]
+        model: Option<String>,
+    },
+    
+    /// Add text files to a knowledge base
+    Add {
+        /// Knowledge base name
+        name: String,
+        
+        /// Files to add
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    
+    /// Remove a document from a knowledge base
+    Remove {
+        /// Knowledge base name
+        name: String,
+        
+        /// Document ID or title
+        document: String,
+    },
+    
+    /// Show index size and health
+    Stats {
+        /// Knowledge base name
+        name: String,
+        
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Show or change how a knowledge base chunks documents and picks chunks
+    Config {
+        /// Knowledge base name
+        name: String,
+        
+        /// `fixed_tokens`, `sentences`, `markdown_headers` or `code_symbols`
+        #[arg(long)]
+        strategy: Option<String>,
+        
+        /// Largest chunk, in tokens
+        #[arg(long)]
+        max_tokens: Option<usize>,
+        
+        /// Tokens a chunk repeats from the one before it
+        #[arg(long)]
+        overlap: Option<usize>,
+        
+        /// Most chunks returned for a query
+        #[arg(short)]
+        k: Option<usize>,
+        
+        /// Lowest similarity of a returned chunk
+        #[arg(long)]
+        min_score: Option<f32>,
+        
+        /// Pick diverse chunks with maximal marginal relevance; 1 is pure relevance
+        #[arg(long, conflicts_with = "no_mmr")]
+        mmr: Option<f32>,
+        
+        /// Rank chunks by similarity alone
+        #[arg(long)]
+        no_mmr: bool,
+    },
+    
+    /// Show the chunks a query finds
+    Search {
+        /// Knowledge base name
+        name: String,
+        
+        /// Query
+        query: String,
+    },
+    
+    /// Compare chunking strategies on sample queries
+    Eval {
+        /// Knowledge base name
+        name: String,
+        
+        /// JSON array or JSON lines of {"query", "documents", "contains"}
+        queries: PathBuf,
+        
+        /// Strategy to compare; repeat for several, omit for all
+        #[arg(short, long = "strategy")]
+        strategies: Vec<String>,
+        
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Search knowledge bases for every message of a conversation and cite
+    /// the passages used
+    Use {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// Knowledge base names; none stops searching
+        names: Vec<String>,
+    },
+    
+    /// Rebuild the index, dropping removed documents, optionally with another embedding model
+    Reindex {
+        /// Knowledge base name
+        name: String,
+        
+        /// Embedding model to switch to
+        #[arg(short, long)]
+        model: Option<String>,
+    },
+}
+
+/// Jobs subcommands
+#[derive(Subcommand)]
+pub enum JobsCommands {
+    /// List running and interrupted jobs
+    List {
+        /// Include finished jobs
+        #[arg(short, long)]
+        all: bool,
+        
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Stop a job
+    Cancel {
+        /// Job ID or a unique prefix of it
+        id: String,
+    },
+}
+
+/// Debug subcommands
+#[derive(Subcommand)]
+pub enum DebugCommands {
+    /// Show the most recent captured API requests
+    LastRequests {
+        /// Number of requests to show
+        #[arg(short = 'n', long, default_value_t = 10)]
+        count: usize,
+        
+        /// Show headers and bodies
+        #[arg(long)]
+        full: bool,
+        
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Show one captured request with headers and bodies
+    Show {
+        /// ID shown by `debug last-requests`
+        id: u64,
+    },
+    
+    /// Turn the request inspector on or off
+    Inspector {
+        /// `on` or `off`
+        #[arg(value_parser = ["on", "off"])]
+        state: String,
+    },
+    
+    /// Forget all captured requests
+    Clear,
+}
+
+/// Logs subcommands
+#[derive(Subcommand)]
+pub enum LogsCommands {
+    /// Zip recent logs, redacted settings and diagnostics to share with support
+    Bundle {
+        /// Include logs written in the last this many days
+        #[arg(long, default_value_t = 3)]
+        days: u32,
+        
+        /// Archive to write; defaults to a timestamped file in the current directory
+        #[arg(short, long)]
+        output: Option<String>,
+        
+        /// Print every file before asking
+        #[arg(long)]
+        show: bool,
+        
+        /// Write the whole bundle without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+    
+    /// Show where log files are kept
+    Path,
+}
+
+/// GitHub subcommands
+#[derive(Subcommand)]
+pub enum GithubCommands {
+    /// Store the GitHub token; asks for it when not given
+    Token {
+        /// Personal access token
+        token: Option<String>,
+        
+        /// Remove the stored token
+        #[arg(long, conflicts_with = "token")]
+        remove: bool,
+    },
+    
+    /// Print lines of a file, e.g. `owner/repo/src/lib.rs@main#L10-L20` or a blob link
+    Snippet {
+        /// File reference
+        reference: String,
+    },
+    
+    /// Add a file, issue or pull request to a conversation as context
+    Import {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// GitHub link, `owner/repo#12` or `owner/repo/path`
+        reference: String,
+    },
+    
+    /// Show the API rate limit
+    Rate,
+}
+
+/// Issues subcommands
+#[derive(Subcommand)]
+pub enum IssuesCommands {
+    /// Show the settings and which trackers are ready
+    Show,
+    
+    /// Change the settings
+    Config {
+        /// Jira site, e.g. https://example.atlassian.net
+        #[arg(long)]
+        jira_url: Option<String>,
+        
+        /// Account email for Jira Cloud; leave empty for a Data Center token
+        #[arg(long)]
+        jira_email: Option<String>,
+        
+        /// Tracker for bare keys like ENG-42 (jira or linear)
+        #[arg(long)]
+        default: Option<String>,
+        
+        /// Fields imported by default, e.g. status,description,comments
+        #[arg(long)]
+        fields: Option<String>,
+    },
+    
+    /// Store the token of Jira or Linear; asks for it when not given
+    Token {
+        /// Tracker name
+        tracker: String,
+        
+        /// API token or key
+        token: Option<String>,
+        
+        /// Remove the stored token
+        #[arg(long, conflicts_with = "token")]
+        remove: bool,
+    },
+    
+    /// Print an issue
+    Get {
+        /// Issue link or key
+        reference: String,
+        
+        /// Fields to show, e.g. description,comments (default: the configured ones)
+        #[arg(long)]
+        fields: Option<String>,
+        
+        /// Fetch again instead of using the cache
+        #[arg(long)]
+        refresh: bool,
+    },
+    
+    /// Find issues by text
+    Search {
+        /// Text to look for
+        query: String,
+        
+        /// Tracker to search (default: the default tracker)
+        #[arg(long)]
+        tracker: Option<String>,
+        
+        /// Most results
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+    },
+    
+    /// Add an issue to a conversation as context
+    Import {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// Issue link or key
+        reference: String,
+        
+        /// Fields to import (default: the configured ones)
+        #[arg(long)]
+        fields: Option<String>,
+    },
+}
+
+/// Feeds subcommands
+#[derive(Subcommand)]
+pub enum FeedsCommands {
+    /// List subscribed feeds
+    List,
+    
+    /// Subscribe to a feed
+    Add {
+        /// URL of the RSS or Atom feed
+        url: String,
+        
+        /// Conversation the digests are posted to
+        #[arg(long, conflicts_with = "notify")]
+        conversation: Option<String>,
+        
+        /// Raise digests as notifications instead
+        #[arg(long)]
+        notify: bool,
+        
+        /// Title to show instead of the feed's own
+        #[arg(long)]
+        title: Option<String>,
+        
+        /// Only digest items mentioning one of these words (repeatable)
+        #[arg(long)]
+        include: Vec<String>,
+        
+        /// Skip items mentioning any of these words (repeatable)
+        #[arg(long)]
+        exclude: Vec<String>,
+        
+        /// Minutes between checks
+        #[arg(long, default_value = "60")]
+        every: u32,
+        
+        /// Model that writes the digests, local or cloud (default: the default model)
+        #[arg(long)]
+        model: Option<String>,
+    },
+    
+    /// Unsubscribe from a feed
+    Remove {
+        /// Feed ID
+        id: String,
+    },
+    
+    /// Change the words a feed's items are filtered by
+    Filter {
+        /// Feed ID
+        id: String,
+        
+        /// Only digest items mentioning one of these words (repeatable)
+        #[arg(long)]
+        include: Vec<String>,
+        
+        /// Skip items mentioning any of these words (repeatable)
+        #[arg(long)]
+        exclude: Vec<String>,
+    },
+    
+    /// Check feeds now and deliver their digests
+    Run {
+        /// Feed ID (default: every enabled feed)
+        id: Option<String>,
+    },
+    
+    /// Mark a feed's current items read so they are left out of digests
+    Read {
+        /// Feed ID
+        id: String,
+    },
+    
+    /// Resume checking a feed
+    Enable {
+        /// Feed ID
+        id: String,
+    },
+    
+    /// Pause checking a feed
+    Disable {
+        /// Feed ID
+        id: String,
+    },
+}
+
+/// Reminders subcommands
+#[derive(Subcommand)]
+pub enum RemindersCommands {
+    /// Remind me about a conversation later
+    Add {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// When: a delay like `3d`, `2h` or `in 3 days`, or an RFC 3339 time
+        when: String,
+        
+        /// What to follow up on
+        #[arg(long)]
+        note: Option<String>,
+    },
+    
+    /// List reminders, soonest first
+    List {
+        /// Only reminders of this conversation
+        conversation_id: Option<String>,
+        
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Set a reminder again for later
+    Snooze {
+        /// Reminder ID or prefix
+        id: String,
+        
+        /// How long, like `1h` or `tomorrow`
+        #[arg(default_value = "1d")]
+        delay: String,
+    },
+    
+    /// Cancel a reminder
+    Cancel {
+        /// Reminder ID or prefix
+        id: String,
+    },
+}
+
+/// Companion subcommands
+#[derive(Subcommand)]
+pub enum CompanionCommands {
+    /// List paired browser extensions
+    List,
+    
+    /// Unpair a browser extension
+    Revoke {
+        /// Extension ID
+        id: String,
+    },
+}
+
+/// Share subcommands
+#[derive(Subcommand)]
+pub enum ShareCommands {
+    /// Write a conversation to a self-contained HTML file
+    Export {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// Output file (default: the title as a file name)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        
+        /// Keep API keys, emails and paths as they are
+        #[arg(long)]
+        no_redact: bool,
+        
+        /// Include system messages
+        #[arg(long)]
+        include_system: bool,
+    },
+    
+    /// Upload a conversation snapshot and print its link
+    Publish {
+        /// Conversation ID
+        conversation_id: String,
+        
+        /// Where to upload: gist or s3 (default: the configured target)
+        #[arg(long)]
+        to: Option<String>,
+        
+        /// Revoke the link after this many days (default: the configured expiry)
+        #[arg(long)]
+        expires_days: Option<u32>,
+        
+        /// Keep API keys, emails and paths as they are
+        #[arg(long)]
+        no_redact: bool,
+        
+        /// Include system messages
+        #[arg(long)]
+        include_system: bool,
+    },
+    
+    /// List uploaded snapshots, revoked ones included
+    List,
+    
+    /// Delete an uploaded snapshot so its link stops working
+    Revoke {
+        /// Share ID
+        id: Option<String>,
+        
+        /// Revoke every share past its expiry
+        #[arg(long, conflicts_with = "id")]
+        expired: bool,
+    },
+    
+    /// Change where and how snapshots are shared
+    Config {
+        /// Default target: gist or s3
+        #[arg(long)]
+        target: Option<String>,
+        
+        /// Days until uploads are revoked
+        #[arg(long)]
+        expiry_days: Option<u32>,
+        
+        /// Keep uploads until revoked by hand
+        #[arg(long, conflicts_with = "expiry_days")]
+        no_expiry: bool,
+        
+        /// Redact snapshots unless --no-redact is given
+        #[arg(long)]
+        redact: Option<bool>,
+        
+        /// S3 bucket
+        #[arg(long)]
+        bucket: Option<String>,
+        
+        /// S3 region
+        #[arg(long)]
+        region: Option<String>,
+        
+        /// Endpoint of an S3-compatible store
+        #[arg(long)]
+        endpoint: Option<String>,
+        
+        /// Public base URL of the bucket; links are presigned when unset
+        #[arg(long)]
+        public_url: Option<String>,
+    },
+    
+    /// Store the S3 access keys; asks for the secret key
+    S3Keys {
+        /// Access key ID
+        access_key_id: Option<String>,
+        
+        /// Remove the stored keys
+        #[arg(long, conflicts_with = "access_key_id")]
+        remove: bool,
+    },
+}
+
+/// Templates subcommands
+#[derive(Subcommand)]
+pub enum TemplatesCommands {
+    /// List installed templates
+    List {
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Show an installed template and where it came from
+    Show {
+        /// Template ID
+        id: String,
+    },
+    
+    /// Print a template's prompt with its placeholders filled in
+    Render {
+        /// Template ID
+        id: String,
+        
+        /// Placeholder value as name=value; repeatable
+        #[arg(long = "set")]
+        values: Vec<String>,
+    },
+    
+    /// Remove an installed template
+    Remove {
+        /// Template ID
+        id: String,
+    },
+    
+    /// Browse the template gallery
+    Gallery {
+        /// Only templates mentioning this in their name, description or tags
+        #[arg(long)]
+        search: Option<String>,
+        
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Show a gallery template without installing it
+    Preview {
+        /// Template ID in the gallery
+        id: String,
+    },
+    
+    /// Install a template from the gallery
+    Install {
+        /// Template ID in the gallery
+        id: String,
+    },
+    
+    /// List installed templates with a newer version in the gallery
+    Updates,
+    
+    /// Update installed templates from the gallery
+    Update {
+        /// Template ID; all with updates when omitted
+        id: Option<String>,
+    },
+    
+    /// Show or set the URL of the gallery's index
+    SetGallery {
+        /// Index URL, over http or https
+        url: Option<String>,
+        
+        /// Stop using a gallery
+        #[arg(long, conflicts_with = "url")]
+        off: bool,
+    },
 }
@@ -1,7 +1,8 @@
 use std::sync::Arc;
 
 use crate::display::{print_error, print_info, print_success, print_table, TableColumn};
-use crate::error::CliResult;
+use crate::error::{CliError, CliResult};
+use mcp_common::service::routing::{get_routing_table, ModelAlias, RouteCondition, RouteTarget};
 use mcp_common::service::ChatService;
 
 /// List available models
@@ -100,3 +101,88 @@ pub async fn set_for_conversation(
         }
     }
 }
+
+/// Describe a route condition for display
+fn describe_condition(condition: &RouteCondition) -> String {
+    match condition {
+        RouteCondition::Offline => "offline".to_string(),
+        RouteCondition::Online => "online".to_string(),
+        RouteCondition::MaxPromptTokens(n) => format!("prompt <= {} tokens", n),
+        RouteCondition::MinPromptTokens(n) => format!("prompt >= {} tokens", n),
+        RouteCondition::MinBudgetRemaining(usd) => format!("budget >= ${:.2}", usd),
+    }
+}
+
+/// List model aliases
+pub fn list_aliases() -> CliResult<()> {
+    let table = get_routing_table().read().unwrap().clone();
+    if table.aliases.is_empty() {
+        print_info("No model aliases defined");
+        return Ok(());
+    }
+    
+    let columns = vec![
+        TableColumn {
+            title: "Alias".to_string(),
+            width: 18,
+            style: None,
+        },
+        TableColumn {
+            title: "Fallback chain".to_string(),
+            width: 70,
+            style: None,
+        },
+    ];
+    
+    let rows: Vec<Vec<String>> = table
+        .aliases
+        .values()
+        .map(|alias| {
+            let chain = alias
+                .targets
+                .iter()
+                .map(|target| {
+                    if target.conditions.is_empty() {
+                        target.model.clone()
+                    } else {
+                        let conditions: Vec<String> = target.conditions.iter().map(describe_condition).collect();
+                        format!("{} [{}]", target.model, conditions.join(", "))
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            vec![alias.name.clone(), chain]
+        })
+        .collect();
+    
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Define or replace an alias with an unconditional fallback chain
+pub fn set_alias(name: &str, models: Vec<String>, description: Option<String>) -> CliResult<()> {
+    let table = get_routing_table();
+    let mut table = table.write().unwrap();
+    table.set(ModelAlias {
+        name: name.to_string(),
+        description: description.unwrap_or_default(),
+        targets: models.into_iter().map(RouteTarget::new).collect(),
+    });
+    table.save()?;
+    
+    print_success(&format!("Alias '{}' saved", name));
+    Ok(())
+}
+
+/// Remove an alias
+pub fn remove_alias(name: &str) -> CliResult<()> {
+    let table = get_routing_table();
+    let mut table = table.write().unwrap();
+    if !table.remove(name) {
+        return Err(CliError::InvalidArgument(format!("No alias named '{}'", name)));
+    }
+    table.save()?;
+    
+    print_success(&format!("Alias '{}' removed", name));
+    Ok(())
+}
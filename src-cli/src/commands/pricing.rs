@@ -0,0 +1,129 @@
+use crate::display::{print_info, print_success, print_table, show_spinner_with_message, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::config::get_settings;
+use mcp_common::service::pricing::{ModelPrice, PriceSource, PriceTable};
+
+/// List model prices and where they come from
+pub fn list(json: bool) -> CliResult<()> {
+    let table = PriceTable::new();
+    let entries = table.entries();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let columns = vec![
+        column("Model prefix", 24),
+        column("Input $/Mtok", 13),
+        column("Output $/Mtok", 14),
+        column("Source", 8),
+    ];
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .map(|e| {
+            vec![
+                e.model.clone(),
+                format!("{:.2}", e.price.input_per_mtok),
+                format!("{:.2}", e.price.output_per_mtok),
+                match e.source {
+                    PriceSource::Bundled => "bundled",
+                    PriceSource::Remote => "remote",
+                    PriceSource::User => "user",
+                }
+                .to_string(),
+            ]
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+
+    if let Some((url, at)) = table.last_refresh() {
+        print_info(&format!("Price list fetched from {} at {}", url, at.format("%Y-%m-%d %H:%M")));
+    }
+    Ok(())
+}
+
+/// Set the price of models whose ID starts with `model`
+pub fn set(model: &str, input: f64, output: f64) -> CliResult<()> {
+    PriceTable::new().set_price(
+        model,
+        ModelPrice {
+            input_per_mtok: input,
+            output_per_mtok: output,
+        },
+    )?;
+    print_success(&format!("{}: ${:.2} in, ${:.2} out per million tokens", model, input, output));
+    Ok(())
+}
+
+/// Go back to the listed price of a model
+pub fn reset(model: &str) -> CliResult<()> {
+    PriceTable::new().reset_price(model)?;
+    print_success(&format!("{} uses the listed price again", model));
+    Ok(())
+}
+
+/// Fetch a price list; with a URL, also remember it for daily refreshes
+pub async fn refresh(url: Option<String>) -> CliResult<()> {
+    let settings = get_settings();
+    let url = match url {
+        Some(url) => {
+            let mut settings = settings.lock().unwrap();
+            settings.cost.price_list_url = Some(url.clone());
+            settings.save()?;
+            url
+        }
+        None => settings
+            .lock()
+            .unwrap()
+            .cost
+            .price_list_url
+            .clone()
+            .ok_or_else(|| CliError::InvalidArgument("No price list URL; pass one to set it".to_string()))?,
+    };
+
+    let spinner = show_spinner_with_message(&format!("Fetching prices from {}...", url));
+    match PriceTable::new().refresh(&url).await {
+        Ok(count) => {
+            spinner.success(&format!("Fetched {} price(s)", count));
+            Ok(())
+        }
+        Err(e) => {
+            spinner.error(&format!("Failed to fetch prices: {}", e));
+            Err(e.into())
+        }
+    }
+}
+
+/// Show or set the currency costs are shown in, optionally with its rate
+pub fn currency(code: Option<String>, rate: Option<f64>) -> CliResult<()> {
+    let table = PriceTable::new();
+    let settings = get_settings();
+    let Some(code) = code else {
+        let current = settings.lock().unwrap().cost.currency.clone();
+        print_info(&format!("Costs are shown in {}", current));
+        print_info(&format!("Known currencies: {}", table.currencies().join(", ")));
+        return Ok(());
+    };
+
+    let code = code.to_uppercase();
+    if let Some(rate) = rate {
+        table.set_rate(&code, rate)?;
+    }
+    if table.rate(&code).is_none() {
+        return Err(CliError::InvalidArgument(format!(
+            "No exchange rate for {}; pass one with --rate",
+            code
+        )));
+    }
+
+    let mut settings = settings.lock().unwrap();
+    settings.cost.currency = code.clone();
+    settings.save()?;
+    print_success(&format!("Costs are shown in {}", code));
+    Ok(())
+}
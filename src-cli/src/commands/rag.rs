@@ -0,0 +1,358 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::display::{print_info, print_success, print_table, print_warning, show_spinner_with_message, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::jobs::{get_job_manager, JobState};
+use mcp_common::memory::embedding::DEFAULT_EMBEDDER;
+use mcp_common::rag::chunking::ChunkingStrategy;
+use mcp_common::rag::evaluate;
+use mcp_common::rag::maintenance::{self, IndexStats};
+use mcp_common::rag::{get_knowledge_bases, KnowledgeBase};
+use mcp_common::service::ChatService;
+
+/// How often a reindex is checked on
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Format a byte count for display
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// List knowledge bases
+pub fn list() -> CliResult<()> {
+    let store = get_knowledge_bases();
+    let bases = store.list();
+    if bases.is_empty() {
+        print_info("No knowledge bases; create one with `mcp rag create`");
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "Name".to_string(),
+            width: 24,
+            style: None,
+        },
+        TableColumn {
+            title: "Documents".to_string(),
+            width: 10,
+            style: None,
+        },
+        TableColumn {
+            title: "Embedding".to_string(),
+            width: 16,
+            style: None,
+        },
+        TableColumn {
+            title: "Updated".to_string(),
+            width: 18,
+            style: None,
+        },
+    ];
+    let rows: Vec<Vec<String>> = bases
+        .iter()
+        .map(|kb| {
+            vec![
+                kb.name.clone(),
+                store.documents(&kb.name).map(|d| d.len()).unwrap_or_default().to_string(),
+                kb.embedding_model.clone(),
+                kb.updated_at.format("%Y-%m-%d %H:%M").to_string(),
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Create a knowledge base
+pub fn create(name: &str, model: Option<String>) -> CliResult<()> {
+    let kb = get_knowledge_bases().create(name, model.as_deref().unwrap_or(DEFAULT_EMBEDDER))?;
+    print_success(&format!("Created knowledge base {} ({})", kb.name, kb.embedding_model));
+    Ok(())
+}
+
+/// Add files to a knowledge base
+pub fn add(name: &str, files: Vec<PathBuf>) -> CliResult<()> {
+    let store = get_knowledge_bases();
+    for file in files {
+        let content = std::fs::read_to_string(&file)?;
+        let title = file.file_name().map_or_else(|| file.display().to_string(), |n| n.to_string_lossy().to_string());
+        store.add_document(name, &title, Some(&file.display().to_string()), &content)?;
+        print_success(&format!("Added {}", title));
+    }
+    Ok(())
+}
+
+/// Remove a document from a knowledge base
+pub fn remove(name: &str, document: &str) -> CliResult<()> {
+    let removed = get_knowledge_bases().remove_document(name, document)?;
+    print_success(&format!(
+        "Removed {}; its chunks are dropped on the next `mcp rag reindex {}`",
+        removed.title, name
+    ));
+    Ok(())
+}
+
+fn print_stats(stats: &IndexStats) {
+    println!("  Generation: {}", stats.generation);
+    println!("  Embedding: {} ({} dimensions)", stats.embedding_model, stats.dimensions);
+    println!("  Documents: {}", stats.documents);
+    println!(
+        "  Chunks: {} ({} of removed documents, {} repeated)",
+        stats.chunks, stats.orphaned_chunks, stats.duplicate_chunks
+    );
+    println!("  Index size: {}", format_size(stats.bytes));
+}
+
+/// Show the index stats of a knowledge base and its last reindex
+pub fn stats(name: &str, json: bool) -> CliResult<()> {
+    let store = get_knowledge_bases();
+    let stats = maintenance::stats(&store, name)?;
+    let report = maintenance::last_report(&store, name);
+    if json {
+        let value = serde_json::json!({ "index": stats, "last_reindex": report });
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+
+    print_info(&format!("Knowledge base {}", name));
+    print_stats(&stats);
+    if stats.reclaimable_chunks() > 0 {
+        print_warning(&format!(
+            "{} chunks can be reclaimed with `mcp rag reindex {}`",
+            stats.reclaimable_chunks(),
+            name
+        ));
+    }
+    if let Some(report) = report {
+        println!(
+            "  Last reindex: {}, {:.0}% self-retrieval",
+            report.finished_at.format("%Y-%m-%d %H:%M"),
+            report.self_retrieval * 100.0
+        );
+    }
+    Ok(())
+}
+
+/// Chunking settings given on the command line
+pub struct ChunkingArgs {
+    pub strategy: Option<String>,
+    pub max_tokens: Option<usize>,
+    pub overlap: Option<usize>,
+}
+
+/// Retrieval settings given on the command line
+pub struct RetrievalArgs {
+    pub k: Option<usize>,
+    pub min_score: Option<f32>,
+    pub mmr: Option<f32>,
+    pub no_mmr: bool,
+}
+
+fn print_config(kb: &KnowledgeBase) {
+    println!(
+        "  Chunking: {}, up to {} tokens, {} overlapping",
+        kb.chunking.strategy, kb.chunking.max_tokens, kb.chunking.overlap_tokens
+    );
+    let mmr = match kb.retrieval.mmr_lambda {
+        Some(lambda) => format!(", MMR lambda {}", lambda),
+        None => String::new(),
+    };
+    println!(
+        "  Retrieval: {} chunks scoring at least {}{}",
+        kb.retrieval.k, kb.retrieval.min_score, mmr
+    );
+}
+
+/// Show or change the chunking and retrieval settings of a knowledge base
+pub fn config(name: &str, chunking: ChunkingArgs, retrieval: RetrievalArgs) -> CliResult<()> {
+    let store = get_knowledge_bases();
+    let kb = store.get(name)?;
+
+    let chunking_changed = chunking.strategy.is_some() || chunking.max_tokens.is_some() || chunking.overlap.is_some();
+    let mut new_chunking = kb.chunking.clone();
+    if let Some(strategy) = chunking.strategy {
+        new_chunking.strategy = strategy.parse()?;
+    }
+    new_chunking.max_tokens = chunking.max_tokens.unwrap_or(new_chunking.max_tokens);
+    new_chunking.overlap_tokens = chunking.overlap.unwrap_or(new_chunking.overlap_tokens);
+
+    let retrieval_changed =
+        retrieval.k.is_some() || retrieval.min_score.is_some() || retrieval.mmr.is_some() || retrieval.no_mmr;
+    let mut new_retrieval = kb.retrieval.clone();
+    new_retrieval.k = retrieval.k.unwrap_or(new_retrieval.k);
+    new_retrieval.min_score = retrieval.min_score.unwrap_or(new_retrieval.min_score);
+    if retrieval.no_mmr {
+        new_retrieval.mmr_lambda = None;
+    } else if retrieval.mmr.is_some() {
+        new_retrieval.mmr_lambda = retrieval.mmr;
+    }
+
+    if !chunking_changed && !retrieval_changed {
+        print_info(&format!("Knowledge base {}", name));
+        print_config(&kb);
+        return Ok(());
+    }
+
+    let kb = store.configure(
+        name,
+        chunking_changed.then_some(new_chunking),
+        retrieval_changed.then_some(new_retrieval),
+    )?;
+    print_success(&format!("Updated knowledge base {}", name));
+    print_config(&kb);
+    if chunking_changed {
+        print_info(&format!(
+            "New chunking applies to documents added from now on; run `mcp rag reindex {}` for the others",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Show the chunks a query finds
+pub fn search(name: &str, query: &str) -> CliResult<()> {
+    let hits = get_knowledge_bases().search(name, query)?;
+    if hits.is_empty() {
+        print_info("Nothing found");
+        return Ok(());
+    }
+    for (i, hit) in hits.iter().enumerate() {
+        print_info(&format!("[{}] {} ({:.2})", i + 1, hit.document_title, hit.score));
+        println!("{}\n", hit.text);
+    }
+    Ok(())
+}
+
+/// Compare chunking strategies on sample queries
+pub fn eval(name: &str, queries: PathBuf, strategies: Vec<String>, json: bool) -> CliResult<()> {
+    let queries = evaluate::parse_queries(&std::fs::read_to_string(&queries)?)?;
+    let strategies = strategies
+        .iter()
+        .map(|s| s.parse::<ChunkingStrategy>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let scores = evaluate::evaluate(&get_knowledge_bases(), name, &queries, &strategies)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&scores)?);
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "Strategy".to_string(),
+            width: 18,
+            style: None,
+        },
+        TableColumn {
+            title: "Chunks".to_string(),
+            width: 8,
+            style: None,
+        },
+        TableColumn {
+            title: "Avg tokens".to_string(),
+            width: 10,
+            style: None,
+        },
+        TableColumn {
+            title: "Hit rate".to_string(),
+            width: 10,
+            style: None,
+        },
+        TableColumn {
+            title: "MRR".to_string(),
+            width: 8,
+            style: None,
+        },
+    ];
+    let rows: Vec<Vec<String>> = scores
+        .iter()
+        .map(|s| {
+            vec![
+                s.chunking.strategy.to_string(),
+                s.chunks.to_string(),
+                format!("{:.0}", s.average_chunk_tokens),
+                format!("{:.0}%", s.hit_rate * 100.0),
+                format!("{:.2}", s.mrr),
+            ]
+        })
+        .collect();
+
+    print_info(&format!("{} sample queries on {}", queries.len(), name));
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Set the knowledge bases a conversation searches
+pub async fn use_knowledge_bases(
+    chat_service: Arc<ChatService>,
+    conversation_id: &str,
+    names: Vec<String>,
+) -> CliResult<()> {
+    chat_service.set_knowledge_bases(conversation_id, &names).await?;
+    if names.is_empty() {
+        print_success("The conversation no longer searches knowledge bases");
+    } else {
+        print_success(&format!(
+            "Searching {} for every message; replies cite the passages they use",
+            names.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Reindex a knowledge base as a background job and wait for it
+pub async fn reindex(name: &str, model: Option<String>) -> CliResult<()> {
+    let job = maintenance::spawn_reindex(name, model.as_deref())?;
+    let spinner = show_spinner_with_message(&format!("Reindexing {}...", name));
+
+    let manager = get_job_manager();
+    let job = loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let job = manager.get(&job.id)?;
+        if job.state.is_finished() {
+            break job;
+        }
+        if let Some(fraction) = job.progress.fraction() {
+            spinner.set_message(&format!("Reindexing {}... {:.0}%", name, fraction * 100.0));
+        }
+    };
+
+    match job.state {
+        JobState::Completed => spinner.success(&format!("Reindexed {}", name)),
+        JobState::Cancelled => {
+            spinner.warning("Reindex canceled; the old index is still in use");
+            return Ok(());
+        }
+        _ => {
+            let error = job.error.unwrap_or_else(|| "Reindex failed".to_string());
+            spinner.error(&error);
+            return Err(CliError::Unknown(error));
+        }
+    }
+
+    if let Some(report) = maintenance::last_report(&get_knowledge_bases(), name) {
+        print_info("Before");
+        print_stats(&report.before);
+        print_info("After");
+        print_stats(&report.after);
+        println!("  Self-retrieval: {:.0}%", report.self_retrieval * 100.0);
+        if let Some(agreement) = report.neighbour_agreement {
+            println!("  Neighbours kept: {:.0}%", agreement * 100.0);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_table, TableColumn};
+use crate::error::CliResult;
+use mcp_common::reminders::{get_reminder_store, parse_delay, parse_when, Reminder};
+use mcp_common::service::ChatService;
+use mcp_common::utils::clock;
+
+fn describe_due(reminder: &Reminder) -> String {
+    let due = reminder.due_at.format("%Y-%m-%d %H:%M").to_string();
+    match reminder.delivered_at {
+        Some(_) => format!("{} (done)", due),
+        None => due,
+    }
+}
+
+/// Set a reminder on a conversation
+pub async fn add(
+    chat_service: Arc<ChatService>,
+    conversation_id: &str,
+    when: &str,
+    note: Option<String>,
+) -> CliResult<()> {
+    let conversation = chat_service.get_conversation(conversation_id).await?;
+    let due_at = parse_when(when, clock::now())?;
+    let reminder = get_reminder_store().add(&conversation.id, &conversation.title, due_at, note)?;
+    print_success(&format!(
+        "Reminder {} set for {}; it is announced while `mcp daemon` or the app runs",
+        reminder.id.chars().take(8).collect::<String>(),
+        describe_due(&reminder)
+    ));
+    Ok(())
+}
+
+/// List reminders, optionally of one conversation
+pub fn list(conversation_id: Option<String>, json: bool) -> CliResult<()> {
+    let reminders = get_reminder_store().list(conversation_id.as_deref());
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reminders)?);
+        return Ok(());
+    }
+    if reminders.is_empty() {
+        print_info("No reminders; set one with `mcp reminders add <conversation> 3d`");
+        return Ok(());
+    }
+
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let columns = vec![
+        column("ID", 10),
+        column("Due", 24),
+        column("Conversation", 30),
+        column("Note", 30),
+        column("Link", 48),
+    ];
+    let rows: Vec<Vec<String>> = reminders
+        .iter()
+        .map(|r| {
+            vec![
+                r.id.chars().take(8).collect(),
+                describe_due(r),
+                r.title.clone(),
+                r.note.clone().unwrap_or_default(),
+                r.link(),
+            ]
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Set a reminder again for later
+pub fn snooze(id: &str, delay: &str) -> CliResult<()> {
+    let until = clock::now() + parse_delay(delay)?;
+    let reminder = get_reminder_store().snooze(id, until)?;
+    print_success(&format!(
+        "Snoozed \"{}\" until {}",
+        reminder.title,
+        describe_due(&reminder)
+    ));
+    Ok(())
+}
+
+/// Cancel a reminder
+pub fn cancel(id: &str) -> CliResult<()> {
+    let reminder = get_reminder_store().cancel(id)?;
+    print_success(&format!("Canceled the reminder on \"{}\"", reminder.title));
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::display::{print_error, print_info, print_success, print_warning};
+use crate::error::{CliError, CliResult};
+use mcp_common::service::script::{load_script, ScriptRunner, StepResult};
+use mcp_common::service::ChatService;
+
+/// Run a scripted session; fails when any step fails so CI can gate on it
+pub async fn run(chat_service: Arc<ChatService>, script: PathBuf, junit: Option<String>) -> CliResult<()> {
+    let script = load_script(&script)?;
+
+    // With the report on stdout, keep the step lines off it
+    let to_stdout = junit.as_deref() == Some("-");
+    if !to_stdout {
+        print_info(&format!("Running {} ({} step(s))", script.name, script.steps.len()));
+    }
+
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+    let runner = ScriptRunner::new(chat_service).with_progress(progress_tx);
+    let run = runner.run(&script);
+    tokio::pin!(run);
+
+    // Print each step as it finishes
+    let report = loop {
+        tokio::select! {
+            report = &mut run => break report,
+            Some(step) = progress_rx.recv() => {
+                if !to_stdout {
+                    print_step(&step);
+                }
+            }
+        }
+    };
+    while let Ok(step) = progress_rx.try_recv() {
+        if !to_stdout {
+            print_step(&step);
+        }
+    }
+
+    match junit.as_deref() {
+        Some("-") => print!("{}", report.to_junit()),
+        Some(path) => {
+            std::fs::write(path, report.to_junit())?;
+            print_success(&format!("JUnit report written to {}", path));
+        }
+        None => {}
+    }
+
+    if !report.all_passed() {
+        return Err(CliError::Unknown(format!(
+            "{} step(s) failed, {} skipped",
+            report.failed(),
+            report.skipped()
+        )));
+    }
+    if !to_stdout {
+        print_success(&format!("All {} step(s) passed", report.passed()));
+    }
+    Ok(())
+}
+
+/// One line per step, with the failed checks below it
+fn print_step(step: &StepResult) {
+    let line = format!("{}. {} ({} ms)", step.index, step.name, step.duration_ms);
+    if step.skipped {
+        print_warning(&format!("{} - skipped", line));
+    } else if step.passed {
+        print_success(&line);
+    } else {
+        print_error(&line);
+        if let Some(error) = &step.error {
+            println!("    {}", error);
+        }
+        for failure in &step.failures {
+            println!("    {}", failure);
+        }
+    }
+}
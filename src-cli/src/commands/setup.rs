@@ -1,145 +1,200 @@
-use dialoguer::{Confirm, Input, Select};
-use std::sync::Arc;
+use dialoguer::{Confirm, Input, Password, Select};
 
-use crate::display::{print_error, print_info, print_success, show_spinner};
+use crate::display::{print_error, print_info, print_success, print_warning, show_spinner_with_message};
 use crate::error::CliResult;
-use mcp_common::config::{get_settings, get_storage_manager};
+use mcp_common::config::get_settings;
+use mcp_common::service::onboarding::{
+    check_api_key, complete_onboarding, onboarding_state, scan_hardware, ApiKeyCheck, MODEL_CHOICES,
+};
+use mcp_common::tr;
 
-/// Run the setup command
-pub async fn run() -> CliResult<()> {
-    print_info("MCP Client Setup");
+/// Number of wizard steps, for the step headers
+const STEPS: usize = 5;
+
+/// Print a step header
+fn step(number: usize, title: &str) {
     println!();
-    
-    // Get settings
-    let settings = get_settings();
-    let mut settings_guard = settings.lock().unwrap();
-    
-    // API key setup
-    let current_api_key = settings_guard.get_api_key().unwrap_or(Ok(None)).unwrap_or(None);
-    
-    if current_api_key.is_some() {
-        print_info("An API key is already configured");
-        
-        if !Confirm::new()
-            .with_prompt("Do you want to replace it?")
-            .default(false)
-            .interact()?
-        {
-            print_info("Keeping existing API key");
-        } else {
-            // Replace API key
-            let new_api_key: String = Input::new()
-                .with_prompt("Enter your Anthropic API key")
-                .interact_text()?;
-            
-            if new_api_key.is_empty() {
-                print_error("API key cannot be empty");
-            } else {
-                settings_guard.set_api_key(&new_api_key)?;
-                print_success("API key updated");
-            }
-        }
-    } else {
-        // No API key configured
-        print_info("No API key configured");
-        
-        let new_api_key: String = Input::new()
-            .with_prompt("Enter your Anthropic API key")
-            .interact_text()?;
-        
-        if new_api_key.is_empty() {
-            print_error("API key cannot be empty");
-        } else {
-            settings_guard.set_api_key(&new_api_key)?;
-            print_success("API key saved");
-        }
+    print_info(&tr!("setup-step", number = number, total = STEPS, title = title));
+}
+
+/// Run the setup wizard
+pub async fn run() -> CliResult<()> {
+    print_info(&tr!("setup-title"));
+    let state = onboarding_state();
+    if state.completed {
+        print_info(&tr!("setup-rerun"));
     }
-    
-    // Model settings
-    print_info("\nModel Settings");
-    
+    let mut answers = state.answers;
+
+    // Profile
+    step(1, &tr!("setup-step-profile"));
+    answers.profile_name = Input::new()
+        .with_prompt(tr!("setup-profile-name"))
+        .with_initial_text(answers.profile_name.clone())
+        .interact_text()?;
+    let email: String = Input::new()
+        .with_prompt(tr!("setup-profile-email"))
+        .with_initial_text(answers.email.clone().unwrap_or_default())
+        .allow_empty(true)
+        .interact_text()?;
+    answers.email = Some(email).filter(|e| !e.trim().is_empty());
+
+    // API key
+    step(2, &tr!("setup-step-api-key"));
+    answers.api_key = ask_api_key(state.has_api_key).await?;
+
     // Default model
-    let model_options = [
-        "claude-3-opus-20240229",
-        "claude-3-sonnet-20240229",
-        "claude-3-haiku-20240307",
-    ];
-    
-    let default_model_index = model_options
+    step(3, &tr!("setup-step-model"));
+    let default_model_index = MODEL_CHOICES
         .iter()
-        .position(|&m| m == settings_guard.api.model)
+        .position(|&m| m == answers.model)
         .unwrap_or(1); // Default to sonnet
-    
     let model_selection = Select::new()
-        .with_prompt("Select default model")
-        .items(&model_options)
+        .with_prompt(tr!("setup-model-prompt"))
+        .items(MODEL_CHOICES)
         .default(default_model_index)
         .interact()?;
-    
-    settings_guard.api.model = model_options[model_selection].to_string();
-    
-    // Temperature
-    let temperature: f32 = Input::new()
-        .with_prompt("Default temperature (0.0-1.0)")
-        .default(settings_guard.model.temperature)
-        .interact_text()?;
-    
-    settings_guard.model.temperature = temperature.clamp(0.0, 1.0);
-    
-    // Max tokens
-    let max_tokens: u32 = Input::new()
-        .with_prompt("Default max tokens")
-        .default(settings_guard.model.max_tokens)
-        .interact_text()?;
-    
-    settings_guard.model.max_tokens = max_tokens;
-    
-    // System prompt
-    print_info("\nSystem Prompt");
-    
-    if let Some(prompt) = &settings_guard.model.system_prompt {
-        println!("Current system prompt: {}", prompt);
-        
-        if Confirm::new()
-            .with_prompt("Do you want to change it?")
+    answers.model = MODEL_CHOICES[model_selection].to_string();
+
+    // Offline mode
+    step(4, &tr!("setup-step-offline"));
+    let spinner = show_spinner_with_message(&tr!("setup-scanning"));
+    let scan = scan_hardware();
+    spinner.info(&tr!(
+        "setup-scan-result",
+        cores = scan.cpu_cores,
+        memory = format!("{:.1}", scan.memory_bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    ));
+    if scan.can_run_local() {
+        answers.offline_enabled = Confirm::new()
+            .with_prompt(tr!("setup-offline-prompt"))
+            .default(answers.offline_enabled)
+            .interact()?;
+        if answers.offline_enabled {
+            let default_index = answers
+                .local_model
+                .as_ref()
+                .or(scan.recommended_local_model.as_ref())
+                .and_then(|model| scan.local_models.iter().position(|m| m == model))
+                .unwrap_or(0);
+            let selection = Select::new()
+                .with_prompt(tr!("setup-local-model-prompt"))
+                .items(&scan.local_models)
+                .default(default_index)
+                .interact()?;
+            answers.local_model = Some(scan.local_models[selection].clone());
+        }
+    } else {
+        print_info(&tr!("setup-offline-unsupported"));
+        answers.offline_enabled = false;
+    }
+
+    // Telemetry consent
+    step(5, &tr!("setup-step-telemetry"));
+    println!("{}", tr!("setup-telemetry-explanation"));
+    answers.telemetry = Confirm::new()
+        .with_prompt(tr!("setup-telemetry-prompt"))
+        .default(answers.telemetry)
+        .interact()?;
+
+    // Validate and write the configuration
+    println!();
+    let spinner = show_spinner_with_message(&tr!("setup-saving"));
+    match complete_onboarding(answers).await {
+        Ok(_) => spinner.success(&tr!("setup-saved")),
+        Err(e) => {
+            spinner.error(&tr!("setup-save-failed", error = e.to_string()));
+            return Err(e.into());
+        }
+    }
+
+    // Generation settings are optional
+    if Confirm::new()
+        .with_prompt(tr!("setup-advanced-prompt"))
+        .default(false)
+        .interact()?
+    {
+        configure_generation()?;
+    }
+
+    print_success(&tr!("setup-done"));
+    Ok(())
+}
+
+/// Ask for an API key until it checks out or the user moves on. Returns
+/// `None` to keep the stored key, or when skipped.
+async fn ask_api_key(has_api_key: bool) -> CliResult<Option<String>> {
+    if has_api_key {
+        print_info(&tr!("setup-api-key-present"));
+        if !Confirm::new()
+            .with_prompt(tr!("setup-api-key-replace"))
             .default(false)
             .interact()?
         {
-            let new_prompt: String = Input::new()
-                .with_prompt("Enter new system prompt (leave empty to clear)")
-                .interact_text()?;
-            
-            if new_prompt.is_empty() {
-                settings_guard.model.system_prompt = None;
-                print_info("System prompt cleared");
-            } else {
-                settings_guard.model.system_prompt = Some(new_prompt);
-                print_success("System prompt updated");
+            return Ok(None);
+        }
+    }
+
+    let api_url = get_settings().lock().unwrap().api.url.clone();
+    loop {
+        let api_key: String = Password::new()
+            .with_prompt(tr!("setup-api-key-prompt"))
+            .allow_empty_password(true)
+            .interact()?;
+        if api_key.trim().is_empty() {
+            print_error(&tr!("setup-api-key-empty"));
+        } else {
+            let spinner = show_spinner_with_message(&tr!("setup-api-key-checking"));
+            match check_api_key(&api_url, &api_key).await {
+                ApiKeyCheck::Valid => {
+                    spinner.success(&tr!("setup-api-key-valid"));
+                    return Ok(Some(api_key));
+                }
+                ApiKeyCheck::Unreachable { message } => {
+                    spinner.warning(&tr!("setup-api-key-unverified", error = message));
+                    return Ok(Some(api_key));
+                }
+                ApiKeyCheck::Invalid { message } => {
+                    spinner.error(&tr!("setup-api-key-invalid", error = message));
+                }
             }
         }
-    } else {
-        print_info("No system prompt configured");
-        
-        if Confirm::new()
-            .with_prompt("Do you want to set a system prompt?")
-            .default(false)
+
+        if !Confirm::new()
+            .with_prompt(tr!("setup-api-key-retry"))
+            .default(true)
             .interact()?
         {
-            let new_prompt: String = Input::new()
-                .with_prompt("Enter system prompt")
-                .interact_text()?;
-            
-            if !new_prompt.is_empty() {
-                settings_guard.model.system_prompt = Some(new_prompt);
-                print_success("System prompt set");
-            }
+            // Without a key, setup only succeeds with offline mode on
+            print_warning(&tr!("setup-api-key-skipped"));
+            return Ok(None);
         }
     }
-    
-    // Save settings
-    settings_guard.save()?;
-    print_success("\nSettings saved");
-    
-    // Return success
+}
+
+/// Temperature, max tokens and system prompt
+fn configure_generation() -> CliResult<()> {
+    let settings = get_settings();
+    let mut settings = settings.lock().unwrap();
+
+    let temperature: f32 = Input::new()
+        .with_prompt(tr!("setup-temperature-prompt"))
+        .default(settings.model.temperature)
+        .interact_text()?;
+    settings.model.temperature = temperature.clamp(0.0, 1.0);
+
+    settings.model.max_tokens = Input::new()
+        .with_prompt(tr!("setup-max-tokens-prompt"))
+        .default(settings.model.max_tokens)
+        .interact_text()?;
+
+    let system_prompt: String = Input::new()
+        .with_prompt(tr!("setup-system-prompt-prompt"))
+        .with_initial_text(settings.model.system_prompt.clone().unwrap_or_default())
+        .allow_empty(true)
+        .interact_text()?;
+    settings.model.system_prompt = Some(system_prompt).filter(|p| !p.trim().is_empty());
+
+    settings.save()?;
     Ok(())
 }
@@ -0,0 +1,236 @@
+use chrono::Duration;
+use dialoguer::{Input, Password};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_table, print_warning, show_spinner_with_message, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::service::ChatService;
+use mcp_common::snapshots::{
+    file_name, get_share_settings, get_share_store, render_html, S3Config, S3Credentials, ShareTarget, Sharer,
+    SnapshotOptions,
+};
+use mcp_common::utils::clock;
+
+fn options(redact: bool, include_system: bool) -> SnapshotOptions {
+    SnapshotOptions {
+        redact,
+        include_system,
+        ..Default::default()
+    }
+}
+
+/// Write a conversation to a local HTML file
+pub async fn export(
+    chat_service: Arc<ChatService>,
+    conversation_id: &str,
+    output: Option<PathBuf>,
+    no_redact: bool,
+    include_system: bool,
+) -> CliResult<()> {
+    let conversation = chat_service.get_conversation(conversation_id).await?;
+    let redact = !no_redact && get_share_settings().read().unwrap().redact;
+    let html = render_html(&conversation, &options(redact, include_system));
+    let output = output.unwrap_or_else(|| PathBuf::from(file_name(&conversation.title)));
+    std::fs::write(&output, html)?;
+    print_success(&format!("Wrote {}", output.display()));
+    if !redact {
+        print_warning("The snapshot is not redacted");
+    }
+    Ok(())
+}
+
+/// Upload a conversation and print its link
+pub async fn publish(
+    chat_service: Arc<ChatService>,
+    conversation_id: &str,
+    target: Option<String>,
+    expires_days: Option<u32>,
+    no_redact: bool,
+    include_system: bool,
+) -> CliResult<()> {
+    let target = target.map(|t| t.parse::<ShareTarget>()).transpose()?;
+    let conversation = chat_service.get_conversation(conversation_id).await?;
+    let mut options = options(!no_redact && get_share_settings().read().unwrap().redact, include_system);
+    options.expires_at = expires_days.map(|days| clock::now() + Duration::days(days as i64));
+
+    let spinner = show_spinner_with_message("Uploading snapshot...");
+    match Sharer::new().publish(&conversation, target, &options).await {
+        Ok(share) => {
+            spinner.success(&format!("Shared to {}", share.target.as_str()));
+            println!("{}", share.url);
+            match share.expires_at {
+                Some(at) => print_info(&format!(
+                    "Revoked on {}; `mcp share revoke {}` revokes it now",
+                    at.format("%Y-%m-%d"),
+                    &share.id[..8]
+                )),
+                None => print_info(&format!("Kept until `mcp share revoke {}`", &share.id[..8])),
+            }
+            Ok(())
+        }
+        Err(e) => {
+            spinner.error("Failed to share");
+            Err(e.into())
+        }
+    }
+}
+
+/// List uploaded snapshots
+pub fn list() -> CliResult<()> {
+    let shares = get_share_store().list();
+    if shares.is_empty() {
+        print_info("Nothing shared; share a conversation with `mcp share publish <id>`");
+        return Ok(());
+    }
+
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let columns = vec![
+        column("ID", 10),
+        column("Title", 28),
+        column("Target", 7),
+        column("Status", 20),
+        column("Link", 50),
+    ];
+    let rows: Vec<Vec<String>> = shares
+        .iter()
+        .rev()
+        .map(|s| {
+            let status = match (s.revoked_at, s.expires_at) {
+                (Some(at), _) => format!("revoked {}", at.format("%Y-%m-%d")),
+                (None, Some(at)) => format!("until {}", at.format("%Y-%m-%d")),
+                (None, None) => "no expiry".to_string(),
+            };
+            vec![
+                s.id.chars().take(8).collect(),
+                s.title.clone(),
+                s.target.as_str().to_string(),
+                status,
+                s.url.clone(),
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Revoke one share, or every expired one
+pub async fn revoke(id: Option<String>, expired: bool) -> CliResult<()> {
+    let sharer = Sharer::new();
+    match (id, expired) {
+        (Some(id), _) => {
+            let share = sharer.revoke(&id).await?;
+            print_success(&format!("Revoked {}", share.url));
+        }
+        (None, true) => {
+            let revoked = sharer.revoke_expired().await;
+            if revoked.is_empty() {
+                print_info("No expired shares");
+            } else {
+                print_success(&format!("Revoked {} expired share(s)", revoked.len()));
+            }
+        }
+        (None, false) => {
+            return Err(CliError::InvalidArgument("Give a share ID or --expired".to_string()))
+        }
+    }
+    Ok(())
+}
+
+/// Change the sharing settings
+#[allow(clippy::too_many_arguments)]
+pub fn configure(
+    target: Option<String>,
+    expiry_days: Option<u32>,
+    no_expiry: bool,
+    redact: Option<bool>,
+    bucket: Option<String>,
+    region: Option<String>,
+    endpoint: Option<String>,
+    public_url: Option<String>,
+) -> CliResult<()> {
+    let settings = get_share_settings();
+    let mut settings = settings.write().unwrap();
+
+    if let Some(target) = target {
+        settings.target = Some(target.parse()?);
+    }
+    if no_expiry {
+        settings.expiry_days = None;
+    } else if let Some(days) = expiry_days {
+        settings.expiry_days = Some(days.max(1));
+    }
+    if let Some(redact) = redact {
+        settings.redact = redact;
+    }
+    if let Some(bucket) = bucket {
+        let region = region
+            .clone()
+            .or_else(|| settings.s3.as_ref().map(|s3| s3.region.clone()))
+            .ok_or_else(|| CliError::InvalidArgument("Give the bucket's --region".to_string()))?;
+        settings.s3 = Some(S3Config {
+            bucket,
+            region,
+            endpoint: None,
+            prefix: settings.s3.as_ref().map(|s3| s3.prefix.clone()).unwrap_or_else(|| "papin-shares/".to_string()),
+            public_url: None,
+        });
+    }
+    if region.is_some() || endpoint.is_some() || public_url.is_some() {
+        let s3 = settings
+            .s3
+            .as_mut()
+            .ok_or_else(|| CliError::InvalidArgument("Set a --bucket first".to_string()))?;
+        if let Some(region) = region {
+            s3.region = region;
+        }
+        if let Some(endpoint) = endpoint {
+            s3.endpoint = Some(endpoint).filter(|e| !e.is_empty());
+        }
+        if let Some(public_url) = public_url {
+            s3.public_url = Some(public_url).filter(|u| !u.is_empty());
+        }
+    }
+    settings.save()?;
+
+    print_success("Sharing settings updated");
+    print_info(&format!(
+        "Target: {} · Expiry: {} · Redact: {}",
+        settings.target.map(|t| t.as_str()).unwrap_or("unset"),
+        settings
+            .expiry_days
+            .map(|d| format!("{} days", d))
+            .unwrap_or_else(|| "none".to_string()),
+        if settings.redact { "yes" } else { "no" }
+    ));
+    if let Some(s3) = &settings.s3 {
+        print_info(&format!("Bucket: {} ({})", s3.bucket, s3.region));
+    }
+    Ok(())
+}
+
+/// Store the S3 access keys, asking for them when not given
+pub fn set_s3_keys(access_key_id: Option<String>, remove: bool) -> CliResult<()> {
+    if remove {
+        S3Credentials::store(None)?;
+        print_success("Removed the S3 keys");
+        return Ok(());
+    }
+
+    let access_key_id = match access_key_id {
+        Some(id) => id,
+        None => Input::new().with_prompt("Access key ID").interact_text()?,
+    };
+    let secret_access_key = Password::new().with_prompt("Secret access key").interact()?;
+    S3Credentials::store(Some(&S3Credentials {
+        access_key_id,
+        secret_access_key,
+    }))?;
+    print_success("Stored the S3 keys");
+    Ok(())
+}
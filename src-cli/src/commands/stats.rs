@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use crate::display::{print_info, print_table, TableColumn};
+use crate::error::CliResult;
+use mcp_common::config::get_settings;
+use mcp_common::service::analytics::{self, UsageAnalytics};
+use mcp_common::service::pricing::{format_amount, PriceTable};
+use mcp_common::service::ChatService;
+use mcp_common::utils::clock;
+
+/// Run the stats command
+pub async fn run(chat_service: Arc<ChatService>, weeks: usize, json: bool) -> CliResult<()> {
+    let conversations = chat_service.list_conversations().await?;
+    let currency = get_settings().lock().unwrap().cost.currency.clone();
+    let usage = analytics::compute(&conversations, weeks, clock::now(), &PriceTable::new(), &currency);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&usage)?);
+        return Ok(());
+    }
+
+    if usage.total_messages() == 0 {
+        print_info(&format!("No messages in the last {} week(s)", weeks.max(1)));
+        return Ok(());
+    }
+
+    print_weeks(&usage)?;
+    println!();
+    print_models(&usage)?;
+    println!();
+    print_workspaces(&usage)?;
+    Ok(())
+}
+
+fn column(title: &str, width: usize) -> TableColumn {
+    TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    }
+}
+
+/// Print message counts, latency and tokens per week
+fn print_weeks(usage: &UsageAnalytics) -> CliResult<()> {
+    let columns = vec![
+        column("Week of", 12),
+        column("Messages", 10),
+        column("Avg latency (ms)", 17),
+        column("Input tokens", 13),
+        column("Output tokens", 14),
+        column("Spend", 10),
+        column("Top model", 30),
+    ];
+
+    let rows: Vec<Vec<String>> = usage
+        .weeks
+        .iter()
+        .enumerate()
+        .map(|(i, week)| {
+            let top_model = usage
+                .model_mix
+                .iter()
+                .filter(|s| s.values[i] > 0.0)
+                .max_by(|a, b| a.values[i].partial_cmp(&b.values[i]).unwrap())
+                .map(|s| s.name.clone())
+                .unwrap_or_else(|| "-".to_string());
+            vec![
+                week.format("%Y-%m-%d").to_string(),
+                format!("{:.0}", usage.messages.values[i]),
+                match usage.avg_latency_ms.values[i] {
+                    latency if latency > 0.0 => format!("{:.0}", latency),
+                    _ => "-".to_string(),
+                },
+                format!("{:.0}", usage.input_tokens.values[i]),
+                format!("{:.0}", usage.output_tokens.values[i]),
+                format_amount(usage.spend.values[i], &usage.currency),
+                top_model,
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Print each model's share of responses
+fn print_models(usage: &UsageAnalytics) -> CliResult<()> {
+    let columns = vec![column("Model", 30), column("Responses", 10), column("Share", 8)];
+
+    let rows: Vec<Vec<String>> = usage
+        .models
+        .iter()
+        .map(|m| vec![m.model.clone(), m.responses.to_string(), format!("{:.0}%", m.share * 100.0)])
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Print the busiest workspaces
+fn print_workspaces(usage: &UsageAnalytics) -> CliResult<()> {
+    let columns = vec![
+        column("Workspace", 24),
+        column("Conversations", 14),
+        column("Messages", 10),
+        column("Tokens", 10),
+        column("Spend", 10),
+    ];
+
+    let rows: Vec<Vec<String>> = usage
+        .top_workspaces
+        .iter()
+        .map(|w| {
+            vec![
+                w.workspace.clone(),
+                w.conversations.to_string(),
+                w.messages.to_string(),
+                w.tokens.to_string(),
+                format_amount(w.spend, &usage.currency),
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use crate::display::{print_error, print_info, print_success, print_warning, show_spinner_with_message};
+use crate::error::CliResult;
+use mcp_common::service::credentials::{self, CredentialStatus};
+use mcp_common::tr;
+
+/// Run the status command; a recent check is reused unless `refresh`
+pub async fn run(refresh: bool, json: bool) -> CliResult<()> {
+    let spinner = (!json).then(|| show_spinner_with_message(&tr!("status-checking")));
+    let check = if refresh {
+        credentials::revalidate().await
+    } else {
+        credentials::validate().await
+    };
+    if let Some(spinner) = spinner {
+        spinner.abandon();
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "health": check.health(),
+                "check": check,
+            }))?
+        );
+        return Ok(());
+    }
+
+    let error = check.message.clone();
+    match check.status {
+        CredentialStatus::Valid => print_success(&tr!("status-valid")),
+        CredentialStatus::Missing => print_error(&tr!("status-missing")),
+        CredentialStatus::InvalidKey => print_error(&tr!("status-invalid-key", error = error)),
+        CredentialStatus::QuotaExhausted => print_error(&tr!("status-quota-exhausted", error = error)),
+        CredentialStatus::RateLimited => print_warning(&tr!("status-rate-limited", error = error)),
+        CredentialStatus::Network => print_warning(&tr!("status-network", error = error)),
+        CredentialStatus::ServiceError => print_warning(&tr!("status-service-error", error = error)),
+    }
+
+    let time = check.checked_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string();
+    print_info(&tr!("status-checked", time = time));
+    if let Some(ms) = check.latency_ms {
+        print_info(&tr!("status-latency", ms = ms));
+    }
+    if let Some(id) = check.organization_id.as_ref() {
+        print_info(&tr!("status-organization", id = id.as_str()));
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use crate::display::{print_info, print_success, print_table, show_spinner_with_message, TableColumn};
+use crate::error::CliResult;
+use mcp_common::config::get_storage_manager;
+
+/// Format a byte count for display
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Deduplicate large message parts and remove unreferenced blobs
+pub fn compact() -> CliResult<()> {
+    let spinner = show_spinner_with_message("Compacting conversation storage...");
+
+    let report = match get_storage_manager().compact() {
+        Ok(report) => {
+            spinner.success("Storage compacted");
+            report
+        }
+        Err(e) => {
+            spinner.error(&format!("Compaction failed: {}", e));
+            return Err(e.into());
+        }
+    };
+
+    print_info(&format!(
+        "{} conversation(s) moved to shared blobs, {} unused blob(s) and {} orphaned attachment(s) removed",
+        report.conversations_rewritten, report.blobs_removed, report.attachments_removed
+    ));
+    if report.trash_purged > 0 {
+        print_info(&format!("{} expired conversation(s) purged from the trash", report.trash_purged));
+    }
+    print_info(&format!(
+        "{} blob(s): {} of content stored in {}",
+        report.blobs.blobs,
+        format_size(report.blobs.size),
+        format_size(report.blobs.stored_size)
+    ));
+    print_success(&format!(
+        "{} -> {} ({} reclaimed)",
+        format_size(report.bytes_before),
+        format_size(report.bytes_after),
+        format_size(report.reclaimed())
+    ));
+
+    Ok(())
+}
+
+/// Show disk usage by category
+pub fn usage() -> CliResult<()> {
+    let usage = get_storage_manager().usage();
+
+    let columns = vec![
+        TableColumn {
+            title: "Category".to_string(),
+            width: 16,
+            style: None,
+        },
+        TableColumn {
+            title: "Items".to_string(),
+            width: 8,
+            style: None,
+        },
+        TableColumn {
+            title: "Size".to_string(),
+            width: 12,
+            style: None,
+        },
+    ];
+    let categories = [
+        ("Conversations", &usage.conversations),
+        ("Trash", &usage.trash),
+        ("Blobs", &usage.blobs),
+        ("Attachments", &usage.attachments),
+        ("Undo journal", &usage.journal),
+    ];
+    let rows: Vec<Vec<String>> = categories
+        .iter()
+        .map(|(name, entry)| vec![name.to_string(), entry.count.to_string(), format_size(entry.bytes)])
+        .collect();
+    print_table(&columns, &rows)?;
+
+    print_success(&format!("Total: {}", format_size(usage.total())));
+    Ok(())
+}
@@ -0,0 +1,274 @@
+use dialoguer::Password;
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_table, print_warning, show_spinner_with_message, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::error::McpError;
+use mcp_common::service::ChatService;
+use mcp_common::sync::{get_team_workspace, TeamRole, TeamSyncReport};
+
+fn parse_role(role: &str) -> CliResult<TeamRole> {
+    role.parse().map_err(|e: McpError| CliError::InvalidArgument(e.to_string()))
+}
+
+fn print_report(report: &TeamSyncReport) {
+    print_success(&format!(
+        "Synced: {} change(s) pushed, {} received",
+        report.pushed, report.pulled
+    ));
+    if report.conflicts > 0 {
+        print_warning(&format!(
+            "{} edit(s) collided with a teammate's; review them in the sync conflicts view",
+            report.conflicts
+        ));
+    }
+    for refused in &report.refused {
+        print_warning(&format!("Refused by the server: {}", refused));
+    }
+}
+
+/// Join a team server
+pub async fn join(url: &str, token: Option<String>) -> CliResult<()> {
+    let token = match token {
+        Some(token) => token,
+        None => Password::new().with_prompt("Team access token").interact()?,
+    };
+
+    let spinner = show_spinner_with_message("Connecting to the team server...");
+    match get_team_workspace().join(url, &token).await {
+        Ok(team) => {
+            spinner.success(&format!("Joined team '{}' as {:?}", team.name, team.me.role));
+            Ok(())
+        }
+        Err(e) => {
+            spinner.error(&format!("Failed to join the team: {}", e));
+            Err(e.into())
+        }
+    }
+}
+
+/// Leave the team and forget its data
+pub fn leave() -> CliResult<()> {
+    get_team_workspace().leave()?;
+    print_success("Left the team; shared prompts and conversations were removed from this device");
+    Ok(())
+}
+
+/// Show team mode status
+pub fn status() -> CliResult<()> {
+    let status = get_team_workspace().status();
+    let Some(team) = status.team.filter(|_| status.enabled) else {
+        print_info("Team mode is off; join a team with `mcp team join <url>`");
+        return Ok(());
+    };
+
+    println!("Team:          {} ({})", team.name, status.url.unwrap_or_default());
+    println!("You:           {} ({:?})", team.me.name, team.me.role);
+    println!("Prompts:       {}", status.prompts);
+    println!("Shared:        {} conversation(s)", status.shared_conversations);
+    println!("Pending:       {} change(s)", status.pending_changes);
+    println!(
+        "Last sync:     {}",
+        status
+            .last_sync
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "never".to_string())
+    );
+    Ok(())
+}
+
+/// Push local changes and pull the team's
+pub async fn sync() -> CliResult<()> {
+    let spinner = show_spinner_with_message("Syncing with the team server...");
+    match get_team_workspace().sync().await {
+        Ok(report) => {
+            spinner.abandon();
+            print_report(&report);
+            Ok(())
+        }
+        Err(e) => {
+            spinner.error(&format!("Team sync failed: {}", e));
+            Err(e.into())
+        }
+    }
+}
+
+/// List the team prompt library
+pub fn prompts() -> CliResult<()> {
+    let prompts = get_team_workspace().prompts();
+    if prompts.is_empty() {
+        print_info("The team prompt library is empty");
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "Name".to_string(),
+            width: 24,
+            style: None,
+        },
+        TableColumn {
+            title: "Description".to_string(),
+            width: 40,
+            style: None,
+        },
+        TableColumn {
+            title: "Tags".to_string(),
+            width: 20,
+            style: None,
+        },
+    ];
+    let rows: Vec<Vec<String>> = prompts
+        .iter()
+        .map(|p| vec![p.name.clone(), p.description.clone().unwrap_or_default(), p.tags.join(", ")])
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Print a prompt from the library
+pub fn show_prompt(name: &str) -> CliResult<()> {
+    let prompt = get_team_workspace()
+        .prompt(name)
+        .ok_or_else(|| CliError::InvalidArgument(format!("No team prompt '{}'", name)))?;
+    println!("{}", prompt.content);
+    Ok(())
+}
+
+/// Add or update a prompt in the library
+pub fn add_prompt(name: &str, content: &str, description: Option<String>, tags: Vec<String>) -> CliResult<()> {
+    let prompt = get_team_workspace().save_prompt(name, content, description, tags)?;
+    print_success(&format!(
+        "Saved team prompt '{}'; it is shared on the next `mcp team sync`",
+        prompt.name
+    ));
+    Ok(())
+}
+
+/// Remove a prompt from the library
+pub fn remove_prompt(name: &str) -> CliResult<()> {
+    get_team_workspace().delete_prompt(name)?;
+    print_success(&format!("Removed team prompt '{}'", name));
+    Ok(())
+}
+
+/// Share a conversation's metadata with the team
+pub async fn share(chat_service: Arc<ChatService>, conversation_id: &str) -> CliResult<()> {
+    let conversation = chat_service.get_conversation(conversation_id).await?;
+    get_team_workspace().share_conversation(&conversation)?;
+    print_success(&format!(
+        "Conversation '{}' will be shared with the team on the next sync",
+        conversation.title
+    ));
+    Ok(())
+}
+
+/// Stop sharing a conversation
+pub fn unshare(conversation_id: &str) -> CliResult<()> {
+    get_team_workspace().unshare_conversation(conversation_id)?;
+    print_success(&format!("Conversation {} is no longer shared", conversation_id));
+    Ok(())
+}
+
+/// List conversations shared with the team
+pub fn shared() -> CliResult<()> {
+    let conversations = get_team_workspace().shared_conversations();
+    if conversations.is_empty() {
+        print_info("No conversations are shared with the team");
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: "ID".to_string(),
+            width: 38,
+            style: None,
+        },
+        TableColumn {
+            title: "Title".to_string(),
+            width: 30,
+            style: None,
+        },
+        TableColumn {
+            title: "Messages".to_string(),
+            width: 10,
+            style: None,
+        },
+        TableColumn {
+            title: "Shared by".to_string(),
+            width: 20,
+            style: None,
+        },
+    ];
+    let rows: Vec<Vec<String>> = conversations
+        .iter()
+        .map(|c| vec![c.id.clone(), c.title.clone(), c.message_count.to_string(), c.shared_by.clone()])
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// List team members
+pub async fn members() -> CliResult<()> {
+    let members = get_team_workspace().members().await?;
+
+    let columns = vec![
+        TableColumn {
+            title: "ID".to_string(),
+            width: 38,
+            style: None,
+        },
+        TableColumn {
+            title: "Name".to_string(),
+            width: 20,
+            style: None,
+        },
+        TableColumn {
+            title: "Email".to_string(),
+            width: 30,
+            style: None,
+        },
+        TableColumn {
+            title: "Role".to_string(),
+            width: 8,
+            style: None,
+        },
+    ];
+    let rows: Vec<Vec<String>> = members
+        .iter()
+        .map(|m| {
+            vec![
+                m.id.clone(),
+                m.name.clone(),
+                m.email.clone().unwrap_or_default(),
+                format!("{:?}", m.role).to_lowercase(),
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Invite someone to the team (admins only)
+pub async fn invite(email: &str, role: &str) -> CliResult<()> {
+    let member = get_team_workspace().invite(email, parse_role(role)?).await?;
+    print_success(&format!("Invited {} as {:?}", email, member.role));
+    Ok(())
+}
+
+/// Change a member's role (admins only)
+pub async fn set_role(member_id: &str, role: &str) -> CliResult<()> {
+    let member = get_team_workspace().set_role(member_id, parse_role(role)?).await?;
+    print_success(&format!("{} is now {:?}", member.name, member.role));
+    Ok(())
+}
+
+/// Remove a member from the team (admins only)
+pub async fn remove_member(member_id: &str) -> CliResult<()> {
+    get_team_workspace().remove_member(member_id).await?;
+    print_success(&format!("Removed member {}", member_id));
+    Ok(())
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+use crate::display::{print_info, print_success, print_table, print_warning, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::templates::{get_gallery_settings, get_template_library, Template};
+
+fn column(title: &str, width: usize) -> TableColumn {
+    TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    }
+}
+
+/// Where a template came from, for tables
+fn describe_source(template: &Template) -> String {
+    match &template.source {
+        Some(source) => format!("gallery {}", source.version),
+        None => "local".to_string(),
+    }
+}
+
+/// Print a template with its provenance
+fn print_template(template: &Template) {
+    println!("{} ({}, {})", template.name, template.id, template.kind);
+    if !template.description.is_empty() {
+        println!("{}", template.description);
+    }
+    if !template.tags.is_empty() {
+        println!("Tags: {}", template.tags.join(", "));
+    }
+    if let Some(model) = &template.model {
+        println!("Model: {}", model);
+    }
+    if let Some(source) = &template.source {
+        println!("Version: {}", source.version);
+        println!("From: {}", source.source_url);
+        if let Some(author) = &source.author {
+            println!("Author: {}", author);
+        }
+        if let Some(license) = &source.license {
+            println!("License: {}", license);
+        }
+        println!("SHA-256: {}", source.sha256);
+    }
+    if let Some(system_prompt) = &template.system_prompt {
+        println!("\nSystem prompt:\n{}", system_prompt);
+    }
+    if !template.prompt.is_empty() {
+        println!("\nPrompt:\n{}", template.prompt);
+    }
+}
+
+/// List installed templates
+pub fn list(json: bool) -> CliResult<()> {
+    let templates = get_template_library().list();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&templates)?);
+        return Ok(());
+    }
+    if templates.is_empty() {
+        print_info("No templates; browse the gallery with `mcp templates gallery`");
+        return Ok(());
+    }
+
+    let columns = vec![
+        column("ID", 24),
+        column("Name", 30),
+        column("Kind", 10),
+        column("Source", 16),
+        column("Placeholders", 30),
+    ];
+    let rows: Vec<Vec<String>> = templates
+        .iter()
+        .map(|t| {
+            vec![
+                t.id.clone(),
+                t.name.clone(),
+                t.kind.to_string(),
+                describe_source(t),
+                t.placeholders().join(", "),
+            ]
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Show an installed template
+pub fn show(id: &str) -> CliResult<()> {
+    print_template(&get_template_library().get(id)?);
+    Ok(())
+}
+
+/// Print a template's prompt with its placeholders filled in
+pub fn render(id: &str, values: Vec<String>) -> CliResult<()> {
+    let template = get_template_library().get(id)?;
+    let mut parsed = HashMap::new();
+    for arg in &values {
+        let (name, value) = arg
+            .split_once('=')
+            .filter(|(name, _)| !name.trim().is_empty())
+            .ok_or_else(|| CliError::InvalidArgument(format!("Expected name=value, got '{}'", arg)))?;
+        parsed.insert(name.trim().to_string(), value.to_string());
+    }
+    println!("{}", template.render(&parsed)?);
+    Ok(())
+}
+
+/// Remove an installed template
+pub fn remove(id: &str) -> CliResult<()> {
+    let template = get_template_library().remove(id)?;
+    print_success(&format!("Removed template {}", template.name));
+    Ok(())
+}
+
+/// Browse the gallery, optionally only templates matching a term
+pub async fn gallery(search: Option<String>, json: bool) -> CliResult<()> {
+    let gallery = get_gallery_settings().gallery()?;
+    let mut index = gallery.index().await?;
+    if let Some(term) = &search {
+        index.templates.retain(|entry| entry.matches(term));
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&index)?);
+        return Ok(());
+    }
+    if index.templates.is_empty() {
+        print_info("No templates found in the gallery");
+        return Ok(());
+    }
+
+    let library = get_template_library();
+    let columns = vec![
+        column("ID", 24),
+        column("Name", 30),
+        column("Kind", 10),
+        column("Version", 10),
+        column("Author", 16),
+        column("Installed", 10),
+    ];
+    let rows: Vec<Vec<String>> = index
+        .templates
+        .iter()
+        .map(|entry| {
+            let installed = match library.get(&entry.id) {
+                Ok(template) => template
+                    .source
+                    .map_or_else(|| "local".to_string(), |source| source.version),
+                Err(_) => String::new(),
+            };
+            vec![
+                entry.id.clone(),
+                entry.name.clone(),
+                entry.kind.to_string(),
+                entry.version.clone(),
+                entry.author.clone().unwrap_or_default(),
+                installed,
+            ]
+        })
+        .collect();
+    if !index.name.is_empty() {
+        print_info(&index.name);
+    }
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Download a gallery template and show it without installing it
+pub async fn preview(id: &str) -> CliResult<()> {
+    let template = get_gallery_settings().gallery()?.preview(id).await?;
+    print_template(&template);
+    Ok(())
+}
+
+/// Install a template from the gallery
+pub async fn install(id: &str) -> CliResult<()> {
+    let gallery = get_gallery_settings().gallery()?;
+    let template = gallery.install(&get_template_library(), id).await?;
+    print_success(&format!(
+        "Installed {} {}",
+        template.name,
+        template.source.as_ref().map_or("", |s| s.version.as_str())
+    ));
+    Ok(())
+}
+
+/// List installed templates with another version in the gallery
+pub async fn updates() -> CliResult<()> {
+    let settings = get_gallery_settings();
+    let updates = settings.gallery()?.updates(&get_template_library()).await?;
+    settings.unannounced(&updates)?;
+    if updates.is_empty() {
+        print_info("Installed templates are up to date");
+        return Ok(());
+    }
+
+    let columns = vec![
+        column("ID", 24),
+        column("Name", 30),
+        column("Installed", 12),
+        column("Available", 12),
+    ];
+    let rows: Vec<Vec<String>> = updates
+        .iter()
+        .map(|u| {
+            vec![
+                u.id.clone(),
+                u.name.clone(),
+                u.installed_version.clone(),
+                u.available_version.clone(),
+            ]
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+    print_info("Update with `mcp templates update [<id>]`");
+    Ok(())
+}
+
+/// Update one installed template, or all with updates
+pub async fn update(id: Option<String>) -> CliResult<()> {
+    let gallery = get_gallery_settings().gallery()?;
+    let library = get_template_library();
+    let ids = match id {
+        Some(id) => vec![id],
+        None => gallery.updates(&library).await?.into_iter().map(|u| u.id).collect(),
+    };
+    if ids.is_empty() {
+        print_info("Installed templates are up to date");
+        return Ok(());
+    }
+    for id in ids {
+        match gallery.install(&library, &id).await {
+            Ok(template) => print_success(&format!(
+                "Updated {} to {}",
+                template.name,
+                template.source.as_ref().map_or("", |s| s.version.as_str())
+            )),
+            Err(e) => print_warning(&format!("Failed to update {}: {}", id, e)),
+        }
+    }
+    Ok(())
+}
+
+/// Use a gallery, or none
+pub fn set_gallery(url: Option<String>, off: bool) -> CliResult<()> {
+    let settings = get_gallery_settings();
+    if off {
+        settings.set_url(None)?;
+        print_success("Template gallery turned off");
+        return Ok(());
+    }
+    match url {
+        Some(url) => {
+            settings.set_url(Some(&url))?;
+            print_success(&format!("Using the template gallery at {}", url.trim()));
+        }
+        None => match settings.url() {
+            Some(url) => print_info(&format!("Template gallery: {}", url)),
+            None => print_info("No template gallery is set"),
+        },
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use crate::display::{print_error, print_info, print_success, print_warning};
+use crate::error::{CliError, CliResult};
+use mcp_common::models::ToolCall;
+use mcp_common::service::dry_run::{self, get_dry_run_log};
+use mcp_common::service::policy::{get_policies, Effect, PolicyRule};
+
+/// Show, or turn on or off, dry runs of tool calls
+pub fn dry_run(enabled: Option<bool>) -> CliResult<()> {
+    match enabled {
+        Some(true) => {
+            dry_run::set_enabled(true)?;
+            print_success("Tool calls are now dry runs: recorded with what they would do, not executed");
+        }
+        Some(false) => {
+            dry_run::set_enabled(false)?;
+            print_warning("Tool calls are executed again");
+        }
+        None if dry_run::is_enabled() => print_info("Tool calls are dry runs"),
+        None => print_info("Tool calls are executed; turn dry runs on with `mcp tools dry-run on`"),
+    }
+    Ok(())
+}
+
+/// Show the tool calls of a conversation recorded during dry runs
+pub fn audit(conversation_id: &str, json: bool, clear: bool) -> CliResult<()> {
+    let log = get_dry_run_log();
+    let calls = log.list(conversation_id);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&calls)?);
+    } else if calls.is_empty() {
+        print_info("No tool calls recorded for this conversation");
+    } else {
+        for call in &calls {
+            print_info(&format!(
+                "{} {} {}",
+                call.recorded_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"),
+                call.tool,
+                call.arguments
+            ));
+            for effect in &call.effects {
+                println!("  - {}", effect);
+            }
+        }
+    }
+
+    if clear && !calls.is_empty() {
+        let cleared = log.clear(conversation_id)?;
+        print_success(&format!("Forgot {} recorded calls", cleared));
+    }
+    Ok(())
+}
+
+/// Where a policy applies, for messages
+fn policy_scope(workspace: Option<&str>) -> String {
+    match workspace {
+        Some(name) => format!("workspace '{}'", name),
+        None => "everywhere".to_string(),
+    }
+}
+
+/// Show the tool policy of a workspace, or the global one
+pub fn policy(workspace: Option<&str>, json: bool) -> CliResult<()> {
+    let policy = get_policies().get(workspace);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&policy)?);
+        return Ok(());
+    }
+
+    match policy.default {
+        Some(effect) => print_info(&format!("Calls no rule matches {}: {}", policy_scope(workspace), effect)),
+        None if workspace.is_some() => print_info("Calls no rule matches follow the global policy"),
+        None => print_info("Calls no rule matches are allowed"),
+    }
+    if policy.rules.is_empty() {
+        print_info(&format!("No rules apply {}", policy_scope(workspace)));
+    }
+    for rule in &policy.rules {
+        match &rule.reason {
+            Some(reason) => println!("  {}  {} ({})", rule.id, rule.describe(), reason),
+            None => println!("  {}  {}", rule.id, rule.describe()),
+        }
+    }
+    if workspace.is_none() {
+        let workspaces = get_policies().workspaces();
+        if !workspaces.is_empty() {
+            print_info(&format!("Workspaces with rules of their own: {}", workspaces.join(", ")));
+        }
+    }
+    Ok(())
+}
+
+/// Add a rule to the tool policy of a workspace, or the global one
+pub fn add_rule(workspace: Option<&str>, rule: PolicyRule) -> CliResult<()> {
+    let rule = get_policies().add_rule(workspace, rule)?;
+    print_success(&format!("Added rule {} {}: {}", rule.id, policy_scope(workspace), rule.describe()));
+    Ok(())
+}
+
+/// Remove a rule from the tool policy of a workspace, or the global one
+pub fn remove_rule(workspace: Option<&str>, id: &str) -> CliResult<()> {
+    if get_policies().remove_rule(workspace, id)? {
+        print_success(&format!("Removed rule {}", id));
+    } else {
+        print_info(&format!("There is no rule {} {}", id, policy_scope(workspace)));
+    }
+    Ok(())
+}
+
+/// Set what happens to tool calls no rule matches
+pub fn policy_default(workspace: Option<&str>, effect: Option<Effect>) -> CliResult<()> {
+    get_policies().set_default(workspace, effect)?;
+    match effect {
+        Some(effect) => print_success(&format!("Calls no rule matches {}: {}", policy_scope(workspace), effect)),
+        None => print_success(&format!("Cleared the default {}", policy_scope(workspace))),
+    }
+    Ok(())
+}
+
+/// Show what the tool policies would do with a call, without making it
+pub fn test_policy(workspace: Option<&str>, tool: &str, arguments: &str) -> CliResult<()> {
+    let arguments: serde_json::Value = serde_json::from_str(arguments)
+        .map_err(|e| CliError::InvalidArgument(format!("The arguments aren't valid JSON: {}", e)))?;
+    let call = ToolCall::new("policy-test", tool, arguments);
+    let decision = get_policies().effective(workspace).evaluate(&call);
+    match decision.effect {
+        Effect::Allow => print_success(&decision.explain()),
+        Effect::Ask => print_warning(&decision.explain()),
+        Effect::Deny => {
+            print_error(&decision.explain());
+            println!("{}", serde_json::to_string_pretty(&decision.denial(&call).result)?);
+        }
+    }
+    Ok(())
+}
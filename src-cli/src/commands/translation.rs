@@ -0,0 +1,162 @@
+use dialoguer::Password;
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_table, TableColumn};
+use crate::error::CliResult;
+use mcp_common::service::translation::{
+    self, get_translation_settings, translator_for, LanguagePreference, TranslationProvider, AUTO_LANGUAGE,
+};
+use mcp_common::service::ChatService;
+
+const PROVIDERS: [TranslationProvider; 3] = [
+    TranslationProvider::Deepl,
+    TranslationProvider::Google,
+    TranslationProvider::Local,
+];
+
+/// Show the settings and which providers are ready
+pub fn show() -> CliResult<()> {
+    let settings = get_translation_settings().read().unwrap().clone();
+    if settings.enabled {
+        print_info(&format!(
+            "Translating all conversations: {} ↔ {} with {}",
+            settings.defaults.user,
+            settings.defaults.model,
+            settings.provider.as_str()
+        ));
+    } else {
+        print_info("Translating only conversations with a language set (`mcp translation language`)");
+    }
+
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let columns = vec![column("Provider", 10), column("Ready", 6), column("Default", 8)];
+    let rows: Vec<Vec<String>> = PROVIDERS
+        .iter()
+        .map(|&provider| {
+            vec![
+                provider.as_str().to_string(),
+                if translation::is_configured(provider) { "yes" } else { "no" }.to_string(),
+                if provider == settings.provider { "*" } else { "" }.to_string(),
+            ]
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+    if let Some(model) = &settings.local_model {
+        print_info(&format!("Local model: {}", model));
+    }
+    Ok(())
+}
+
+/// Translate every conversation without a language of its own
+pub fn enable(user: String, model: String) -> CliResult<()> {
+    let defaults = LanguagePreference {
+        user,
+        model,
+        provider: None,
+    }
+    .validated()?;
+
+    let settings = get_translation_settings();
+    let mut settings = settings.write().unwrap();
+    settings.enabled = true;
+    settings.defaults = defaults;
+    settings.save()?;
+    print_success(&format!(
+        "Translating all conversations: {} ↔ {}",
+        settings.defaults.user, settings.defaults.model
+    ));
+    Ok(())
+}
+
+/// Translate only conversations with a language of their own
+pub fn disable() -> CliResult<()> {
+    let settings = get_translation_settings();
+    let mut settings = settings.write().unwrap();
+    settings.enabled = false;
+    settings.save()?;
+    print_success("Translating only conversations with a language set");
+    Ok(())
+}
+
+/// Choose the default provider, and the registry model for local translation
+pub fn set_provider(provider: &str, model: Option<String>) -> CliResult<()> {
+    let provider: TranslationProvider = provider.parse()?;
+    let settings = get_translation_settings();
+    let mut settings = settings.write().unwrap();
+    settings.provider = provider;
+    if model.is_some() {
+        settings.local_model = model;
+    }
+    settings.save()?;
+    print_success(&format!("Translating with {}", provider.as_str()));
+    if !translation::is_configured(provider) {
+        match provider {
+            TranslationProvider::Local => print_info("No translation model in the registry yet; add a catalog that offers one"),
+            _ => print_info(&format!("Set its API key with `mcp translation key {}`", provider.as_str())),
+        }
+    }
+    Ok(())
+}
+
+/// Store an API key in the secret store, asking for it when not given
+pub fn set_key(provider: &str, key: Option<String>, remove: bool) -> CliResult<()> {
+    let provider: TranslationProvider = provider.parse()?;
+    if remove {
+        translation::set_api_key(provider, None)?;
+        print_success(&format!("Removed the {} API key", provider.as_str()));
+        return Ok(());
+    }
+
+    let key = match key {
+        Some(key) => key,
+        None => Password::new()
+            .with_prompt(format!("{} API key", provider.as_str()))
+            .interact()?,
+    };
+    translation::set_api_key(provider, Some(&key))?;
+    print_success(&format!("Stored the {} API key", provider.as_str()));
+    Ok(())
+}
+
+/// Set or clear the languages of a conversation
+pub async fn set_language(
+    chat_service: Arc<ChatService>,
+    conversation_id: &str,
+    user: Option<String>,
+    model: String,
+    provider: Option<String>,
+) -> CliResult<()> {
+    let Some(user) = user else {
+        chat_service.set_conversation_language(conversation_id, None).await?;
+        print_success("The conversation is no longer translated");
+        return Ok(());
+    };
+
+    let preference = LanguagePreference {
+        user,
+        model,
+        provider: provider.map(|p| p.parse()).transpose()?,
+    };
+    let detected = preference.user == AUTO_LANGUAGE;
+    let summary = format!("{} ↔ {}", preference.user, preference.model);
+    chat_service.set_conversation_language(conversation_id, Some(preference)).await?;
+    if detected {
+        print_success(&format!("Translating {} (your language is detected from each message)", summary));
+    } else {
+        print_success(&format!("Translating {}", summary));
+    }
+    Ok(())
+}
+
+/// Translate a text with the default provider
+pub async fn translate(text: &str, to: &str, from: Option<String>) -> CliResult<()> {
+    let settings = get_translation_settings().read().unwrap().clone();
+    let translator = translator_for(settings.provider, &settings)?;
+    let translated = translation::translate_text(translator.as_ref(), text, from.as_deref(), to).await?;
+    println!("{}", translated);
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use dialoguer::Confirm;
+use std::sync::Arc;
+
+use crate::display::{print_error, print_info, print_success};
+use crate::error::CliResult;
+use mcp_common::service::ChatService;
+use mcp_common::tr;
+
+/// Move a conversation out of the trash
+pub async fn restore(chat_service: Arc<ChatService>, conversation_id: &str) -> CliResult<()> {
+    let conversation = chat_service.restore_conversation(conversation_id).await?;
+    print_success(&tr!("conversation-restored", title = conversation.title));
+    Ok(())
+}
+
+/// Permanently delete trashed conversations, or the whole trash
+pub async fn purge(chat_service: Arc<ChatService>, conversation_ids: Vec<String>, all: bool) -> CliResult<()> {
+    let prompt = if all {
+        tr!("trash-purge-all-confirm")
+    } else {
+        tr!("trash-purge-confirm", count = conversation_ids.len())
+    };
+    if !Confirm::new().with_prompt(prompt).default(false).interact()? {
+        print_error(&tr!("error-cancelled"));
+        return Ok(());
+    }
+
+    if all {
+        let purged = chat_service.empty_trash().await?;
+        if purged == 0 {
+            print_info(&tr!("trash-empty"));
+        } else {
+            print_success(&tr!("trash-purged", count = purged));
+        }
+        return Ok(());
+    }
+
+    for id in &conversation_ids {
+        chat_service.purge_conversation(id).await?;
+    }
+    print_success(&tr!("trash-purged", count = conversation_ids.len()));
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use chrono::Local;
+use std::sync::Arc;
+
+use crate::display::{print_info, print_success, print_table, TableColumn};
+use crate::error::CliResult;
+use mcp_common::config::get_journal;
+use mcp_common::service::ChatService;
+use mcp_common::tr;
+
+/// Undo the latest deletion, archive or edit, or list what can be undone
+pub async fn undo(chat_service: Arc<ChatService>, list: bool) -> CliResult<()> {
+    if list {
+        return show_history();
+    }
+
+    match chat_service.undo().await? {
+        Some(entry) => print_success(&tr!("undo-done", description = entry.description)),
+        None => print_info(&tr!("undo-nothing")),
+    }
+    Ok(())
+}
+
+/// Redo the most recently undone operation
+pub async fn redo(chat_service: Arc<ChatService>) -> CliResult<()> {
+    match chat_service.redo().await? {
+        Some(entry) => print_success(&tr!("redo-done", description = entry.description)),
+        None => print_info(&tr!("redo-nothing")),
+    }
+    Ok(())
+}
+
+/// List journaled operations, newest first
+fn show_history() -> CliResult<()> {
+    let entries = get_journal().list();
+    if entries.is_empty() {
+        print_info(&tr!("undo-nothing"));
+        return Ok(());
+    }
+
+    let columns = vec![
+        TableColumn {
+            title: tr!("undo-column-when"),
+            width: 20,
+            style: None,
+        },
+        TableColumn {
+            title: tr!("undo-column-operation"),
+            width: 40,
+            style: None,
+        },
+        TableColumn {
+            title: tr!("undo-column-state"),
+            width: 10,
+            style: None,
+        },
+    ];
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .map(|entry| {
+            vec![
+                entry.created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string(),
+                entry.description.clone(),
+                tr!(if entry.is_undone() { "undo-state-undone" } else { "undo-state-done" }),
+            ]
+        })
+        .collect();
+    print_table(&columns, &rows)?;
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use crate::display::{print_info, print_success, print_table, print_warning, show_spinner_with_message, TableColumn};
+use crate::error::{CliError, CliResult};
+use mcp_common::error::McpError;
+use mcp_common::webhooks::{get_webhook_store, WebhookEvent};
+
+fn parse_events(events: &[String]) -> CliResult<Vec<WebhookEvent>> {
+    events
+        .iter()
+        .map(|e| e.parse().map_err(|e: McpError| CliError::InvalidArgument(e.to_string())))
+        .collect()
+}
+
+fn describe_events(events: &[WebhookEvent]) -> String {
+    if events.is_empty() {
+        "all".to_string()
+    } else {
+        events.iter().map(|e| e.as_str()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// List registered webhooks
+pub fn list() -> CliResult<()> {
+    let webhooks = get_webhook_store().list();
+    if webhooks.is_empty() {
+        print_info("No webhooks; register one with `mcp webhook add <url>`");
+        return Ok(());
+    }
+
+    let column = |title: &str, width: usize| TableColumn {
+        title: title.to_string(),
+        width,
+        style: None,
+    };
+    let columns = vec![
+        column("ID", 10),
+        column("URL", 40),
+        column("Events", 24),
+        column("State", 10),
+        column("Last error", 30),
+    ];
+    let rows: Vec<Vec<String>> = webhooks
+        .iter()
+        .map(|w| {
+            vec![
+                w.id.chars().take(8).collect(),
+                w.url.clone(),
+                describe_events(&w.events),
+                if w.enabled { "enabled" } else { "disabled" }.to_string(),
+                w.last_error.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    print_table(&columns, &rows)?;
+    Ok(())
+}
+
+/// Register a webhook and print its secret
+pub fn add(url: &str, events: &[String], description: Option<String>) -> CliResult<()> {
+    let webhook = get_webhook_store().add(url, parse_events(events)?, description)?;
+    print_success(&format!(
+        "Webhook {} will be called on {} event(s)",
+        webhook.id,
+        describe_events(&webhook.events)
+    ));
+    println!("Signing secret: {}", webhook.secret);
+    print_info("Payloads are delivered while the desktop app runs");
+    Ok(())
+}
+
+/// Remove a webhook
+pub fn remove(id: &str) -> CliResult<()> {
+    get_webhook_store().remove(id)?;
+    print_success("Webhook removed");
+    Ok(())
+}
+
+/// Resume or pause a webhook
+pub fn set_enabled(id: &str, enabled: bool) -> CliResult<()> {
+    let webhook = get_webhook_store().set_enabled(id, enabled)?;
+    print_success(&format!(
+        "Webhook {} {}",
+        webhook.url,
+        if enabled { "enabled" } else { "disabled" }
+    ));
+    Ok(())
+}
+
+/// Change the events a webhook is called on
+pub fn set_events(id: &str, events: &[String]) -> CliResult<()> {
+    let webhook = get_webhook_store().set_events(id, parse_events(events)?)?;
+    print_success(&format!("Webhook {} is called on {} event(s)", webhook.url, describe_events(&webhook.events)));
+    Ok(())
+}
+
+/// Replace a webhook's secret and print the new one
+pub fn rotate_secret(id: &str) -> CliResult<()> {
+    let webhook = get_webhook_store().rotate_secret(id)?;
+    print_success(&format!("New signing secret for {}", webhook.url));
+    println!("{}", webhook.secret);
+    Ok(())
+}
+
+/// Send a test ping
+pub async fn test(id: &str) -> CliResult<()> {
+    let spinner = show_spinner_with_message("Sending a test ping...");
+    match get_webhook_store().test(id).await {
+        Ok(()) => {
+            spinner.success("The webhook answered");
+            Ok(())
+        }
+        Err(e) => {
+            spinner.error(&format!("Test ping failed: {}", e));
+            Err(e.into())
+        }
+    }
+}
+
+/// Show deliveries waiting to be retried
+pub fn queue() -> CliResult<()> {
+    let queue = get_webhook_store().queue();
+    if queue.is_empty() {
+        print_info("No deliveries waiting");
+        return Ok(());
+    }
+    for delivery in &queue {
+        println!(
+            "{}  {} to {}, {} attempt(s), next at {}",
+            &delivery.id[..8],
+            delivery.event.as_str(),
+            &delivery.webhook_id[..8],
+            delivery.attempts,
+            delivery.next_attempt_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S")
+        );
+        if let Some(error) = &delivery.last_error {
+            println!("    {}", error);
+        }
+    }
+    Ok(())
+}
+
+/// Retry the deliveries that are due
+pub async fn retry() -> CliResult<()> {
+    let report = get_webhook_store().deliver_due().await?;
+    print_success(&format!("{} delivered", report.delivered));
+    if report.retrying > 0 {
+        print_warning(&format!("{} failed and will be retried", report.retrying));
+    }
+    if report.dropped > 0 {
+        print_warning(&format!("{} failed too often and were dropped", report.dropped));
+    }
+    Ok(())
+}
@@ -1,5 +1,9 @@
 use console::{style, Style};
+use mcp_common::models::message::EDIT_HISTORY_METADATA_KEY;
 use mcp_common::models::{Conversation, Message, MessageRole};
+use mcp_common::rag::citations::Citations;
+use mcp_common::service::capabilities::Degradation;
+use mcp_common::service::performance::ResponsePerformance;
 
 /// Message format options
 pub enum MessageFormat {
@@ -11,11 +15,20 @@ pub enum MessageFormat {
 
 /// Format a conversation based on the selected format
 pub fn format_conversation(conversation: &Conversation, format: MessageFormat) -> String {
+    format_conversation_with(conversation, format, false)
+}
+
+/// Format a conversation including the earlier versions of edited messages
+pub fn format_conversation_with_history(conversation: &Conversation, format: MessageFormat) -> String {
+    format_conversation_with(conversation, format, true)
+}
+
+fn format_conversation_with(conversation: &Conversation, format: MessageFormat, history: bool) -> String {
     match format {
-        MessageFormat::Plain => format_conversation_plain(conversation),
+        MessageFormat::Plain => format_conversation_plain(conversation, history),
         MessageFormat::Colored => format_conversation_colored(conversation),
-        MessageFormat::Markdown => format_conversation_markdown(conversation),
-        MessageFormat::Json => format_conversation_json(conversation),
+        MessageFormat::Markdown => format_conversation_markdown(conversation, history),
+        MessageFormat::Json => format_conversation_json(conversation, history),
     }
 }
 
@@ -41,7 +54,7 @@ pub fn format_metadata(metadata: &serde_json::Value) -> String {
 }
 
 // Format a conversation in plain text
-fn format_conversation_plain(conversation: &Conversation) -> String {
+fn format_conversation_plain(conversation: &Conversation, history: bool) -> String {
     let mut result = String::new();
     
     result.push_str(&format!("Conversation: {}\n", conversation.title));
@@ -51,6 +64,15 @@ fn format_conversation_plain(conversation: &Conversation) -> String {
     
     for message in &conversation.messages {
         result.push_str(&format_message_plain(message));
+        if history {
+            for version in message.edit_history() {
+                result.push_str(&format!(
+                    "\n  [Earlier version, {}] {}",
+                    version.written_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                    version.text.replace('\n', "\n  ")
+                ));
+            }
+        }
         result.push_str("\n\n");
     }
     
@@ -79,7 +101,7 @@ fn format_conversation_colored(conversation: &Conversation) -> String {
 }
 
 // Format a conversation in markdown
-fn format_conversation_markdown(conversation: &Conversation) -> String {
+fn format_conversation_markdown(conversation: &Conversation, history: bool) -> String {
     let mut result = String::new();
     
     result.push_str(&format!("# {}\n\n", conversation.title));
@@ -89,15 +111,33 @@ fn format_conversation_markdown(conversation: &Conversation) -> String {
     
     for message in &conversation.messages {
         result.push_str(&format_message_markdown(message));
+        if history {
+            for version in message.edit_history() {
+                result.push_str(&format!(
+                    "\n\n> *Earlier version, {}:*\n> {}",
+                    version.written_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                    version.text.replace('\n', "\n> ")
+                ));
+            }
+        }
         result.push_str("\n\n");
     }
     
     result
 }
 
-// Format a conversation as JSON
-fn format_conversation_json(conversation: &Conversation) -> String {
-    match serde_json::to_string_pretty(conversation) {
+// Format a conversation as JSON; earlier versions of edited messages are
+// left out unless asked for
+fn format_conversation_json(conversation: &Conversation, history: bool) -> String {
+    let mut conversation = conversation.clone();
+    if !history {
+        for message in &mut conversation.messages {
+            if let Some(metadata) = message.metadata.as_mut() {
+                metadata.remove(EDIT_HISTORY_METADATA_KEY);
+            }
+        }
+    }
+    match serde_json::to_string_pretty(&conversation) {
         Ok(json) => json,
         Err(_) => String::from("Error: Could not serialize conversation to JSON"),
     }
@@ -109,9 +149,21 @@ fn format_message_plain(message: &Message) -> String {
         MessageRole::User => "User",
         MessageRole::Assistant => "Assistant",
         MessageRole::System => "System",
+        MessageRole::Tool => "Tool",
     };
     
-    format!("[{}] {}\n{}", role, message.timestamp(), message.text())
+    let mut text = format!("[{}] {}{}\n{}", role, message.timestamp(), edited_marker(message), message.text());
+    let footnotes = footnotes(message);
+    if !footnotes.is_empty() {
+        text.push_str(&format!("\nSources:\n{}", footnotes.join("\n")));
+    }
+    if let Some(performance) = ResponsePerformance::of(message) {
+        text.push_str(&format!("\n({})", performance.summary()));
+    }
+    if let Some(degraded) = Degradation::summary(&Degradation::of(message)) {
+        text.push_str(&format!("\n({})", degraded));
+    }
+    text
 }
 
 // Format a message with colors
@@ -120,16 +172,28 @@ fn format_message_colored(message: &Message) -> String {
         MessageRole::User => ("User", Style::new().green().bold()),
         MessageRole::Assistant => ("Assistant", Style::new().blue().bold()),
         MessageRole::System => ("System", Style::new().yellow().bold()),
+        MessageRole::Tool => ("Tool", Style::new().magenta().bold()),
     };
     
     let timestamp = Style::new().dim().apply_to(message.timestamp());
     
-    format!(
-        "[{}] {}\n{}",
+    let mut text = format!(
+        "[{}] {}{}\n{}",
         style.apply_to(role),
         timestamp,
+        Style::new().dim().apply_to(edited_marker(message)),
         message.text()
-    )
+    );
+    for footnote in footnotes(message) {
+        text.push_str(&format!("\n{}", Style::new().dim().apply_to(footnote)));
+    }
+    if let Some(performance) = ResponsePerformance::of(message) {
+        text.push_str(&format!("\n{}", Style::new().dim().apply_to(performance.summary())));
+    }
+    if let Some(degraded) = Degradation::summary(&Degradation::of(message)) {
+        text.push_str(&format!("\n{}", Style::new().yellow().dim().apply_to(degraded)));
+    }
+    text
 }
 
 // Format a message in markdown
@@ -138,14 +202,44 @@ fn format_message_markdown(message: &Message) -> String {
         MessageRole::User => "## 👤 User",
         MessageRole::Assistant => "## 🤖 Assistant",
         MessageRole::System => "## ⚙️ System",
+        MessageRole::Tool => "## 🔧 Tool",
     };
     
-    format!(
-        "{} ({})\n\n{}",
+    let mut text = format!(
+        "{} ({}){}\n\n{}",
         heading,
         message.timestamp(),
+        edited_marker(message),
         message.text()
-    )
+    );
+    let footnotes = footnotes(message);
+    if !footnotes.is_empty() {
+        text.push_str("\n\n**Sources**\n");
+        for footnote in footnotes {
+            text.push_str(&format!("\n- {}", footnote));
+        }
+    }
+    if let Some(performance) = ResponsePerformance::of(message) {
+        text.push_str(&format!("\n\n*{}*", performance.summary()));
+    }
+    if let Some(degraded) = Degradation::summary(&Degradation::of(message)) {
+        text.push_str(&format!("\n\n*{}*", degraded));
+    }
+    text
+}
+
+// Marker after the timestamp of an edited message
+fn edited_marker(message: &Message) -> &'static str {
+    if message.is_edited() {
+        " (edited)"
+    } else {
+        ""
+    }
+}
+
+// Footnotes of the knowledge base passages a reply cites
+fn footnotes(message: &Message) -> Vec<String> {
+    Citations::of(message).map(|c| c.footnotes()).unwrap_or_default()
 }
 
 // Format a message as JSON
@@ -3,7 +3,9 @@ mod printer;
 mod spinner;
 mod table;
 
-pub use formatter::{format_conversation, format_message, format_metadata, MessageFormat};
+pub use formatter::{
+    format_conversation, format_conversation_with_history, format_message, format_metadata, MessageFormat,
+};
 pub use printer::{print_error, print_info, print_success, print_warning};
 pub use spinner::{show_spinner, show_spinner_with_message, SpinnerHandle};
 pub use table::{print_table, TableColumn};
@@ -1,41 +1,58 @@
 use indicatif::{ProgressBar, ProgressStyle};
+use mcp_common::config::get_settings;
 use std::time::Duration;
 
 /// Spinner handle
 pub struct SpinnerHandle {
     bar: ProgressBar,
+    
+    /// Print progress as plain lines instead of animating
+    plain: bool,
 }
 
 impl SpinnerHandle {
     /// Set a new message for the spinner
     pub fn set_message(&self, message: &str) {
+        if self.plain {
+            println!("{}", message);
+        }
         self.bar.set_message(message.to_string());
     }
     
     /// Finish with a success message
     pub fn success(self, message: &str) {
-        self.bar.finish_with_message(format!("✓ {}", message));
+        self.finish("✓", "Done:", message);
     }
     
     /// Finish with an error message
     pub fn error(self, message: &str) {
-        self.bar.finish_with_message(format!("✗ {}", message));
+        self.finish("✗", "Error:", message);
     }
     
     /// Finish with a warning message
     pub fn warning(self, message: &str) {
-        self.bar.finish_with_message(format!("⚠ {}", message));
+        self.finish("⚠", "Warning:", message);
     }
     
     /// Finish with an info message
     pub fn info(self, message: &str) {
-        self.bar.finish_with_message(format!("ℹ {}", message));
+        self.finish("ℹ", "Note:", message);
     }
     
     /// Abandon the spinner (finish without message)
     pub fn abandon(self) {
         self.bar.finish_and_clear();
     }
+    
+    fn finish(self, symbol: &str, label: &str, message: &str) {
+        if self.plain {
+            // Words read better than symbols in screen readers
+            self.bar.finish_and_clear();
+            println!("{} {}", label, message);
+        } else {
+            self.bar.finish_with_message(format!("{} {}", symbol, message));
+        }
+    }
 }
 
 /// Show a spinner with default message "Processing..."
@@ -43,8 +60,21 @@ pub fn show_spinner() -> SpinnerHandle {
     show_spinner_with_message("Processing...")
 }
 
-/// Show a spinner with a custom message
+/// Show a spinner with a custom message; with reduced motion or screen
+/// reader mode on, progress is printed as plain lines instead
 pub fn show_spinner_with_message(message: &str) -> SpinnerHandle {
+    let plain = get_settings()
+        .lock()
+        .map(|s| !s.accessibility.allows_motion(&s.ui))
+        .unwrap_or(false);
+    if plain {
+        println!("{}", message);
+        return SpinnerHandle {
+            bar: ProgressBar::hidden(),
+            plain,
+        };
+    }
+    
     let spinner_style = ProgressStyle::default_spinner()
         .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏")
         .template("{spinner} {msg}")
@@ -55,5 +85,5 @@ pub fn show_spinner_with_message(message: &str) -> SpinnerHandle {
     bar.set_message(message.to_string());
     bar.enable_steady_tick(Duration::from_millis(100));
     
-    SpinnerHandle { bar }
+    SpinnerHandle { bar, plain }
 }
@@ -1,32 +1,38 @@
 use mcp_common::error::McpError;
+use mcp_common::tr;
 use thiserror::Error;
 use anyhow::Result;
 
 /// CLI error type
 #[derive(Error, Debug)]
 pub enum CliError {
-    #[error("MCP error: {0}")]
+    #[error("{}", localized("error-mcp", .0))]
     McpError(#[from] McpError),
     
-    #[error("I/O error: {0}")]
+    #[error("{}", localized("error-io", .0))]
     IoError(#[from] std::io::Error),
     
-    #[error("Serialization error: {0}")]
+    #[error("{}", localized("error-serialization", .0))]
     SerializationError(#[from] serde_json::Error),
     
-    #[error("Input error: {0}")]
+    #[error("{}", localized("error-input", .0))]
     InputError(String),
     
-    #[error("Invalid argument: {0}")]
+    #[error("{}", localized("error-invalid-argument", .0))]
     InvalidArgument(String),
     
-    #[error("Operation cancelled")]
+    #[error("{}", tr!("error-cancelled"))]
     Cancelled,
     
-    #[error("Unknown error: {0}")]
+    #[error("{}", localized("error-unknown", .0))]
     Unknown(String),
 }
 
+/// Error message in the current locale
+fn localized(id: &str, details: &dyn std::fmt::Display) -> String {
+    tr!(id, details = details.to_string())
+}
+
 /// Result type for CLI operations
 pub type CliResult<T> = Result<T, CliError>;
 
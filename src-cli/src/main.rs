@@ -2,22 +2,36 @@ mod commands;
 mod display;
 mod error;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use log::LevelFilter;
 use std::sync::Arc;
 
-use commands::{Cli, Commands, ModelCommands};
-use error::CliResult;
-use mcp_common::{get_mcp_service, init_mcp_service, service::ChatService};
+use commands::{
+    AgentCommands, AttachmentCommands, BatchCommands, Cli, Commands, CompanionCommands, DebugCommands, EnvCommands,
+    EvalsCommands, ExperimentCommands, FeedbackCommands, FeedsCommands, FilterCommands, GithubCommands,
+    InboundCommands, IssuesCommands, JobsCommands, LogsCommands, MeetingCommands, MemoryCommands, ModelCommands,
+    RagCommands, RemindersCommands, ShareCommands, StorageCommands, TeamCommands, TemplatesCommands, ToolsCommands,
+    TranslationCommands, WebhookCommands,
+};
+use error::{CliError, CliResult};
+use mcp_common::agent::replay::ReplayOverrides;
+use mcp_common::agent::AgentBudget;
+use mcp_common::service::limits::Fit;
+use mcp_common::service::policy::PolicyRule;
+use mcp_common::{i18n, init_mcp_service, logs, platform, service::ChatService};
 
 #[tokio::main]
 async fn main() -> CliResult<()> {
-    // Initialize logging
-    env_logger::Builder::new()
+    // Portable mode has to be settled before anything opens a file
+    platform::fs::init_from_args()?;
+    
+    // Initialize logging; lines are redacted before they are printed or kept
+    let console = env_logger::Builder::new()
         .filter_level(LevelFilter::Info)
         .format_timestamp(None)
         .format_target(false)
-        .init();
+        .build();
+    let _ = logs::init_logging(console);
     
     // Parse command line arguments
     let cli = Cli::parse();
@@ -31,12 +45,30 @@ async fn main() -> CliResult<()> {
         log::set_max_level(LevelFilter::Info);
     }
     
+    // Override the detected language
+    if let Some(lang) = &cli.lang {
+        i18n::set_locale(lang);
+    }
+    
     // Initialize MCP service
     let mcp_service = init_mcp_service();
     let chat_service = Arc::new(ChatService::new(mcp_service));
     
+    // A script runs in place of a subcommand
+    let command = match (cli.script, cli.command) {
+        (Some(script), None) => return commands::script::run(chat_service, script, cli.junit).await,
+        (Some(_), Some(_)) => {
+            return Err(CliError::InvalidArgument("--script can't be combined with a subcommand".to_string()));
+        }
+        (None, Some(command)) => command,
+        (None, None) => {
+            Cli::command().print_help()?;
+            return Ok(());
+        }
+    };
+    
     // Process command
-    match cli.command {
+    match command {
         Commands::Chat {
             conversation_id,
             message,
@@ -44,8 +76,8 @@ async fn main() -> CliResult<()> {
         } => {
             commands::chat::run(chat_service, conversation_id, message, !no_stream).await?;
         }
-        Commands::List => {
-            commands::list::run(chat_service).await?;
+        Commands::List { all, trashed } => {
+            commands::list::run(chat_service, all, trashed).await?;
         }
         Commands::New { title, model } => {
             commands::new::run(chat_service, title, model).await?;
@@ -53,14 +85,45 @@ async fn main() -> CliResult<()> {
         Commands::Delete { conversation_id } => {
             commands::delete::run(chat_service, conversation_id).await?;
         }
+        Commands::Archive { conversation_ids, unarchive } => {
+            commands::archive::run(chat_service, conversation_ids, unarchive).await?;
+        }
+        Commands::Undo { list } => {
+            commands::undo::undo(chat_service, list).await?;
+        }
+        Commands::Redo => {
+            commands::undo::redo(chat_service).await?;
+        }
+        Commands::Restore { conversation_id } => {
+            commands::trash::restore(chat_service, &conversation_id).await?;
+        }
+        Commands::Purge { conversation_ids, all } => {
+            commands::trash::purge(chat_service, conversation_ids, all).await?;
+        }
         Commands::Show { conversation_id } => {
             commands::show::run(chat_service, conversation_id).await?;
         }
         Commands::Setup => {
             commands::setup::run().await?;
         }
-        Commands::Export { conversation_id, format, output } => {
-            commands::export::run(chat_service, conversation_id, format, output).await?;
+        Commands::Login { device_url, token_url, client_id, scopes, audience } => {
+            let gateway = commands::login::GatewayArgs {
+                device_url,
+                token_url,
+                client_id,
+                scopes,
+                audience,
+            };
+            commands::login::login(gateway).await?;
+        }
+        Commands::Logout => {
+            commands::login::logout()?;
+        }
+        Commands::Status { refresh, json } => {
+            commands::status::run(refresh, json).await?;
+        }
+        Commands::Export { conversation_id, format, output, history } => {
+            commands::export::run(chat_service, conversation_id, format, output, history).await?;
         }
         Commands::System { conversation_id, message } => {
             commands::system::run(chat_service, conversation_id, message).await?;
@@ -68,6 +131,61 @@ async fn main() -> CliResult<()> {
         Commands::Interactive { conversation_id } => {
             commands::interactive::run(chat_service, conversation_id).await?;
         }
+        Commands::Stats { weeks, json } => {
+            commands::stats::run(chat_service, weeks, json).await?;
+        }
+        Commands::Bench { model, json, no_save } => {
+            commands::bench::run(chat_service, model, json, no_save).await?;
+        }
+        Commands::Apply {
+            conversation_id,
+            message_id,
+            repo,
+            hunks,
+            dry_run,
+            yes,
+        } => {
+            commands::apply::run(chat_service, conversation_id, message_id, repo, hunks, dry_run, yes).await?;
+        }
+        Commands::Evals { command } => {
+            match command {
+                EvalsCommands::Run {
+                    suite,
+                    model,
+                    concurrency,
+                    junit,
+                    json,
+                    no_save,
+                } => {
+                    commands::evals::run(chat_service, suite, model, concurrency, junit, json, no_save).await?;
+                }
+            }
+        }
+        Commands::Batch { command } => {
+            match command {
+                BatchCommands::Run {
+                    input,
+                    output,
+                    provider,
+                    model,
+                    concurrency,
+                    poll_interval,
+                    experiment,
+                } => {
+                    commands::batch::run(
+                        chat_service,
+                        input,
+                        output,
+                        provider,
+                        model,
+                        concurrency,
+                        poll_interval,
+                        experiment,
+                    )
+                    .await?;
+                }
+            }
+        }
         Commands::Model { command } => {
             match command {
                 ModelCommands::List => {
@@ -79,6 +197,645 @@ async fn main() -> CliResult<()> {
                 ModelCommands::SetForConversation { conversation_id, model } => {
                     commands::model::set_for_conversation(chat_service, &conversation_id, &model).await?;
                 }
+                ModelCommands::Aliases => {
+                    commands::model::list_aliases()?;
+                }
+                ModelCommands::Alias { name, models, description } => {
+                    commands::model::set_alias(&name, models, description)?;
+                }
+                ModelCommands::RemoveAlias { name } => {
+                    commands::model::remove_alias(&name)?;
+                }
+                ModelCommands::Catalogs => {
+                    commands::catalog::list()?;
+                }
+                ModelCommands::AddCatalog { url, name, format, trust, token } => {
+                    commands::catalog::add(&url, name, &format, &trust, token).await?;
+                }
+                ModelCommands::RemoveCatalog { id } => {
+                    commands::catalog::remove(&id)?;
+                }
+                ModelCommands::TrustCatalog { id, trust } => {
+                    commands::catalog::set_trust(&id, &trust)?;
+                }
+                ModelCommands::CatalogToken { id, token } => {
+                    commands::catalog::set_token(&id, token)?;
+                }
+                ModelCommands::CatalogInterval { id, hours } => {
+                    commands::catalog::set_refresh_hours(&id, hours)?;
+                }
+                ModelCommands::RefreshCatalogs { id } => {
+                    commands::catalog::refresh(id).await?;
+                }
+                ModelCommands::SearchHf { query, limit, max_size, all_sizes, json } => {
+                    commands::catalog::search_hf(&query, limit, max_size, all_sizes, json).await?;
+                }
+                ModelCommands::AddHf { repo, file, chat_template } => {
+                    commands::catalog::add_hf(&repo, file, chat_template.as_deref()).await?;
+                }
+                ModelCommands::HfToken { token } => {
+                    commands::catalog::set_hf_token(token)?;
+                }
+                ModelCommands::Prices { json } => {
+                    commands::pricing::list(json)?;
+                }
+                ModelCommands::SetPrice { model, input, output } => {
+                    commands::pricing::set(&model, input, output)?;
+                }
+                ModelCommands::ResetPrice { model } => {
+                    commands::pricing::reset(&model)?;
+                }
+                ModelCommands::RefreshPrices { url } => {
+                    commands::pricing::refresh(url).await?;
+                }
+                ModelCommands::Currency { code, rate } => {
+                    commands::pricing::currency(code, rate)?;
+                }
+                ModelCommands::Capabilities { model, json } => {
+                    commands::capabilities::list(chat_service, model, json).await?;
+                }
+                ModelCommands::SetCapability { target, feature, state } => {
+                    commands::capabilities::set(&target, &feature, &state)?;
+                }
+                ModelCommands::ResetCapability { target, feature } => {
+                    commands::capabilities::reset(&target, feature)?;
+                }
+                ModelCommands::JsonMode { conversation_id, state } => {
+                    commands::capabilities::json_mode(chat_service, &conversation_id, &state).await?;
+                }
+                ModelCommands::Limits { provider, json } => {
+                    commands::limits::show(provider, json)?;
+                }
+                ModelCommands::SetLimits {
+                    provider,
+                    request_mb,
+                    image_mb,
+                    dimension,
+                    images,
+                } => {
+                    commands::limits::set(&provider, request_mb, image_mb, dimension, images)?;
+                }
+                ModelCommands::ResetLimits { provider } => {
+                    commands::limits::reset(&provider)?;
+                }
+            }
+        }
+        Commands::Attachment { command } => {
+            match command {
+                AttachmentCommands::Add { conversation_id, path } => {
+                    commands::attachment::add(&conversation_id, path)?;
+                }
+                AttachmentCommands::List { conversation_id } => {
+                    commands::attachment::list(conversation_id)?;
+                }
+                AttachmentCommands::Open { id } => {
+                    commands::attachment::open(&id)?;
+                }
+                AttachmentCommands::Delete { id } => {
+                    commands::attachment::delete(&id)?;
+                }
+                AttachmentCommands::Downscale { id } => {
+                    commands::limits::downscale(chat_service, &id).await?;
+                }
+                AttachmentCommands::Check {
+                    conversation_id,
+                    message,
+                    attach,
+                } => {
+                    commands::limits::check(chat_service, &conversation_id, &message, &attach).await?;
+                }
+                AttachmentCommands::Send {
+                    conversation_id,
+                    message,
+                    attach,
+                    downscale,
+                    split,
+                } => {
+                    let fit = Fit { downscale, split };
+                    commands::limits::send(chat_service, &conversation_id, &message, &attach, fit).await?;
+                }
+            }
+        }
+        Commands::Storage { command } => {
+            match command {
+                StorageCommands::Compact => {
+                    commands::storage::compact()?;
+                }
+                StorageCommands::Usage => {
+                    commands::storage::usage()?;
+                }
+            }
+        }
+        Commands::Feedback { command } => {
+            match command {
+                FeedbackCommands::Rate { message_id, rating, comment } => {
+                    commands::feedback::rate(chat_service, &message_id, &rating, comment).await?;
+                }
+                FeedbackCommands::Export { output } => {
+                    commands::feedback::export(chat_service, output).await?;
+                }
+            }
+        }
+        Commands::Experiment { command } => {
+            match command {
+                ExperimentCommands::Create { name, variants, templates } => {
+                    commands::experiment::create(&name, variants, templates)?;
+                }
+                ExperimentCommands::List => {
+                    commands::experiment::list()?;
+                }
+                ExperimentCommands::Report { experiment, json } => {
+                    commands::experiment::report(&experiment, json)?;
+                }
+                ExperimentCommands::Stop { experiment } => {
+                    commands::experiment::stop(&experiment)?;
+                }
+            }
+        }
+        Commands::Filter { command } => {
+            match command {
+                FilterCommands::Show { workspace } => {
+                    commands::filter::show(workspace)?;
+                }
+                FilterCommands::Set { filter, value, workspace } => {
+                    commands::filter::set(&filter, &value, workspace)?;
+                }
+                FilterCommands::AddRule { pattern, replacement, description, workspace } => {
+                    commands::filter::add_rule(&pattern, &replacement, description, workspace)?;
+                }
+                FilterCommands::RemoveRule { number, workspace } => {
+                    commands::filter::remove_rule(number, workspace)?;
+                }
+            }
+        }
+        Commands::Memory { command } => {
+            match command {
+                MemoryCommands::List { workspace } => {
+                    commands::memory::list(workspace)?;
+                }
+                MemoryCommands::Remember { text, conversation, workspace } => {
+                    commands::memory::remember(chat_service, &text, conversation, workspace).await?;
+                }
+                MemoryCommands::Edit { id, text } => {
+                    commands::memory::edit(&id, &text)?;
+                }
+                MemoryCommands::Forget { id } => {
+                    commands::memory::forget(&id)?;
+                }
+            }
+        }
+        Commands::Env { command } => {
+            match command {
+                EnvCommands::List { conversation, workspace, resolved } => {
+                    commands::env::list(chat_service, conversation, workspace, resolved).await?;
+                }
+                EnvCommands::Set { name, value, secret, conversation, workspace } => {
+                    commands::env::set(&name, value, secret, conversation, workspace)?;
+                }
+                EnvCommands::Unset { name, conversation, workspace } => {
+                    commands::env::unset(&name, conversation, workspace)?;
+                }
+            }
+        }
+        Commands::Tools { command } => {
+            match command {
+                ToolsCommands::DryRun { state } => {
+                    commands::tools::dry_run(state.map(|s| s == "on"))?;
+                }
+                ToolsCommands::Audit { conversation_id, json, clear } => {
+                    commands::tools::audit(&conversation_id, json, clear)?;
+                }
+                ToolsCommands::Policy { workspace, json } => {
+                    commands::tools::policy(workspace.as_deref(), json)?;
+                }
+                ToolsCommands::AddRule { effect, tool, arguments, paths, hosts, reason, workspace } => {
+                    let rule = PolicyRule {
+                        tool,
+                        arguments,
+                        paths,
+                        hosts,
+                        reason,
+                        ..PolicyRule::new(effect.parse()?)
+                    };
+                    commands::tools::add_rule(workspace.as_deref(), rule)?;
+                }
+                ToolsCommands::RemoveRule { id, workspace } => {
+                    commands::tools::remove_rule(workspace.as_deref(), &id)?;
+                }
+                ToolsCommands::PolicyDefault { effect, workspace } => {
+                    let effect = if effect == "unset" { None } else { Some(effect.parse()?) };
+                    commands::tools::policy_default(workspace.as_deref(), effect)?;
+                }
+                ToolsCommands::TestPolicy { tool, arguments, workspace } => {
+                    commands::tools::test_policy(workspace.as_deref(), &tool, &arguments)?;
+                }
+            }
+        }
+        Commands::Agent { command } => {
+            match command {
+                AgentCommands::Run { conversation_id, task, max_steps, max_seconds, max_tokens, team } => {
+                    let budget = AgentBudget { max_steps, max_seconds, max_tokens };
+                    commands::agent::run(chat_service, &conversation_id, &task, budget, team).await?;
+                }
+                AgentCommands::Transcript { conversation_id, json } => {
+                    commands::agent::transcript(chat_service, &conversation_id, json).await?;
+                }
+                AgentCommands::List => {
+                    commands::agent::list()?;
+                }
+                AgentCommands::Add { name, prompt, tools, max_steps, max_seconds, max_tokens } => {
+                    let budget = AgentBudget { max_steps, max_seconds, max_tokens };
+                    commands::agent::add(name, prompt, tools, budget)?;
+                }
+                AgentCommands::Remove { name } => {
+                    commands::agent::remove(&name)?;
+                }
+                AgentCommands::Route { pattern, agent } => {
+                    commands::agent::route(&pattern, &agent)?;
+                }
+                AgentCommands::Unroute { pattern } => {
+                    commands::agent::unroute(&pattern)?;
+                }
+                AgentCommands::Graph { conversation_id, dot } => {
+                    commands::agent::graph(chat_service, &conversation_id, dot).await?;
+                }
+                AgentCommands::Runs { conversation_id } => {
+                    commands::agent::runs(conversation_id.as_deref())?;
+                }
+                AgentCommands::Replay { run_id, step, json } => {
+                    commands::agent::replay(&run_id, step, json)?;
+                }
+                AgentCommands::Rerun {
+                    run_id,
+                    from,
+                    task,
+                    model,
+                    system_prompt,
+                    max_steps,
+                    max_seconds,
+                    max_tokens,
+                } => {
+                    let overrides = ReplayOverrides { task, budget: None, model, system_prompt };
+                    commands::agent::rerun(chat_service, &run_id, from, overrides, max_steps, max_seconds, max_tokens)
+                        .await?;
+                }
+                AgentCommands::Diff { run_id, other, json } => {
+                    commands::agent::diff(&run_id, other.as_deref(), json)?;
+                }
+            }
+        }
+        Commands::Rag { command } => {
+            match command {
+                RagCommands::List => {
+                    commands::rag::list()?;
+                }
+                RagCommands::Create { name, model } => {
+                    commands::rag::create(&name, model)?;
+                }
+                RagCommands::Add { name, files } => {
+                    commands::rag::add(&name, files)?;
+                }
+                RagCommands::Remove { name, document } => {
+                    commands::rag::remove(&name, &document)?;
+                }
+                RagCommands::Stats { name, json } => {
+                    commands::rag::stats(&name, json)?;
+                }
+                RagCommands::Config { name, strategy, max_tokens, overlap, k, min_score, mmr, no_mmr } => {
+                    let chunking = commands::rag::ChunkingArgs { strategy, max_tokens, overlap };
+                    let retrieval = commands::rag::RetrievalArgs { k, min_score, mmr, no_mmr };
+                    commands::rag::config(&name, chunking, retrieval)?;
+                }
+                RagCommands::Search { name, query } => {
+                    commands::rag::search(&name, &query)?;
+                }
+                RagCommands::Eval { name, queries, strategies, json } => {
+                    commands::rag::eval(&name, queries, strategies, json)?;
+                }
+                RagCommands::Use { conversation_id, names } => {
+                    commands::rag::use_knowledge_bases(chat_service, &conversation_id, names).await?;
+                }
+                RagCommands::Reindex { name, model } => {
+                    commands::rag::reindex(&name, model).await?;
+                }
+            }
+        }
+        Commands::Team { command } => {
+            match command {
+                TeamCommands::Join { url, token } => {
+                    commands::team::join(&url, token).await?;
+                }
+                TeamCommands::Leave => {
+                    commands::team::leave()?;
+                }
+                TeamCommands::Status => {
+                    commands::team::status()?;
+                }
+                TeamCommands::Sync => {
+                    commands::team::sync().await?;
+                }
+                TeamCommands::Prompts => {
+                    commands::team::prompts()?;
+                }
+                TeamCommands::ShowPrompt { name } => {
+                    commands::team::show_prompt(&name)?;
+                }
+                TeamCommands::AddPrompt { name, content, description, tags } => {
+                    commands::team::add_prompt(&name, &content, description, tags)?;
+                }
+                TeamCommands::RemovePrompt { name } => {
+                    commands::team::remove_prompt(&name)?;
+                }
+                TeamCommands::Share { conversation_id } => {
+                    commands::team::share(chat_service, &conversation_id).await?;
+                }
+                TeamCommands::Unshare { conversation_id } => {
+                    commands::team::unshare(&conversation_id)?;
+                }
+                TeamCommands::Shared => {
+                    commands::team::shared()?;
+                }
+                TeamCommands::Members => {
+                    commands::team::members().await?;
+                }
+                TeamCommands::Invite { email, role } => {
+                    commands::team::invite(&email, &role).await?;
+                }
+                TeamCommands::SetRole { member_id, role } => {
+                    commands::team::set_role(&member_id, &role).await?;
+                }
+                TeamCommands::RemoveMember { member_id } => {
+                    commands::team::remove_member(&member_id).await?;
+                }
+            }
+        }
+        Commands::Webhook { command } => {
+            match command {
+                WebhookCommands::List => {
+                    commands::webhook::list()?;
+                }
+                WebhookCommands::Add { url, events, description } => {
+                    commands::webhook::add(&url, &events, description)?;
+                }
+                WebhookCommands::Remove { id } => {
+                    commands::webhook::remove(&id)?;
+                }
+                WebhookCommands::Enable { id } => {
+                    commands::webhook::set_enabled(&id, true)?;
+                }
+                WebhookCommands::Disable { id } => {
+                    commands::webhook::set_enabled(&id, false)?;
+                }
+                WebhookCommands::Events { id, events } => {
+                    commands::webhook::set_events(&id, &events)?;
+                }
+                WebhookCommands::RotateSecret { id } => {
+                    commands::webhook::rotate_secret(&id)?;
+                }
+                WebhookCommands::Test { id } => {
+                    commands::webhook::test(&id).await?;
+                }
+                WebhookCommands::Queue => {
+                    commands::webhook::queue()?;
+                }
+                WebhookCommands::Retry => {
+                    commands::webhook::retry().await?;
+                }
+            }
+        }
+        Commands::Jobs { command } => {
+            match command {
+                JobsCommands::List { all, json } => {
+                    commands::jobs::list(all, json)?;
+                }
+                JobsCommands::Cancel { id } => {
+                    commands::jobs::cancel(&id)?;
+                }
+            }
+        }
+        Commands::Debug { command } => {
+            match command {
+                DebugCommands::LastRequests { count, full, json } => {
+                    commands::debug::last_requests(count, full, json)?;
+                }
+                DebugCommands::Show { id } => {
+                    commands::debug::show(id)?;
+                }
+                DebugCommands::Inspector { state } => {
+                    commands::debug::set_inspector(state == "on")?;
+                }
+                DebugCommands::Clear => {
+                    commands::debug::clear()?;
+                }
+            }
+        }
+        Commands::Logs { command } => {
+            match command {
+                LogsCommands::Bundle { days, output, show, yes } => {
+                    commands::logs::bundle(days, output, show, yes)?;
+                }
+                LogsCommands::Path => {
+                    commands::logs::path();
+                }
+            }
+        }
+        Commands::Daemon { listen, companion_port } => {
+            commands::daemon::run(chat_service, listen, companion_port).await?;
+        }
+        Commands::Inbound { command } => {
+            match command {
+                InboundCommands::List => {
+                    commands::inbound::list()?;
+                }
+                InboundCommands::Add { name, conversation_id, reply } => {
+                    commands::inbound::add(chat_service, &name, &conversation_id, reply).await?;
+                }
+                InboundCommands::Remove { name } => {
+                    commands::inbound::remove(&name)?;
+                }
+                InboundCommands::RotateToken { name } => {
+                    commands::inbound::rotate_token(&name)?;
+                }
+            }
+        }
+        Commands::Meeting { command } => {
+            match command {
+                MeetingCommands::Summarize { file, format, title, date, model, markdown, ics } => {
+                    commands::meeting::summarize(chat_service, file, format, title, date, model, markdown, ics).await?;
+                }
+            }
+        }
+        Commands::Translation { command } => {
+            match command {
+                TranslationCommands::Show => {
+                    commands::translation::show()?;
+                }
+                TranslationCommands::Enable { user, model } => {
+                    commands::translation::enable(user, model)?;
+                }
+                TranslationCommands::Disable => {
+                    commands::translation::disable()?;
+                }
+                TranslationCommands::Provider { provider, model } => {
+                    commands::translation::set_provider(&provider, model)?;
+                }
+                TranslationCommands::Key { provider, key, remove } => {
+                    commands::translation::set_key(&provider, key, remove)?;
+                }
+                TranslationCommands::Language { conversation_id, language, model, provider } => {
+                    commands::translation::set_language(chat_service, &conversation_id, language, model, provider).await?;
+                }
+                TranslationCommands::Text { text, to, from } => {
+                    commands::translation::translate(&text, &to, from).await?;
+                }
+            }
+        }
+        Commands::Github { command } => {
+            match command {
+                GithubCommands::Token { token, remove } => {
+                    commands::github::set_token(token, remove)?;
+                }
+                GithubCommands::Snippet { reference } => {
+                    commands::github::snippet(&reference).await?;
+                }
+                GithubCommands::Import { conversation_id, reference } => {
+                    commands::github::import(chat_service, &conversation_id, &reference).await?;
+                }
+                GithubCommands::Rate => {
+                    commands::github::rate().await?;
+                }
+            }
+        }
+        Commands::Issues { command } => {
+            match command {
+                IssuesCommands::Show => {
+                    commands::issues::show()?;
+                }
+                IssuesCommands::Config { jira_url, jira_email, default, fields } => {
+                    commands::issues::configure(jira_url, jira_email, default, fields)?;
+                }
+                IssuesCommands::Token { tracker, token, remove } => {
+                    commands::issues::set_token(&tracker, token, remove)?;
+                }
+                IssuesCommands::Get { reference, fields, refresh } => {
+                    commands::issues::get(&reference, fields, refresh).await?;
+                }
+                IssuesCommands::Search { query, tracker, limit } => {
+                    commands::issues::search(&query, tracker, limit).await?;
+                }
+                IssuesCommands::Import { conversation_id, reference, fields } => {
+                    commands::issues::import(chat_service, &conversation_id, &reference, fields).await?;
+                }
+            }
+        }
+        Commands::Feeds { command } => {
+            match command {
+                FeedsCommands::List => {
+                    commands::feeds::list()?;
+                }
+                FeedsCommands::Add { url, conversation, notify, title, include, exclude, every, model } => {
+                    commands::feeds::add(chat_service, url, conversation, notify, title, include, exclude, every, model).await?;
+                }
+                FeedsCommands::Remove { id } => {
+                    commands::feeds::remove(&id)?;
+                }
+                FeedsCommands::Filter { id, include, exclude } => {
+                    commands::feeds::set_filter(&id, include, exclude)?;
+                }
+                FeedsCommands::Run { id } => {
+                    commands::feeds::run(chat_service, id).await?;
+                }
+                FeedsCommands::Read { id } => {
+                    commands::feeds::mark_read(chat_service, &id).await?;
+                }
+                FeedsCommands::Enable { id } => {
+                    commands::feeds::set_enabled(&id, true)?;
+                }
+                FeedsCommands::Disable { id } => {
+                    commands::feeds::set_enabled(&id, false)?;
+                }
+            }
+        }
+        Commands::Reminders { command } => {
+            match command {
+                RemindersCommands::Add { conversation_id, when, note } => {
+                    commands::reminders::add(chat_service, &conversation_id, &when, note).await?;
+                }
+                RemindersCommands::List { conversation_id, json } => {
+                    commands::reminders::list(conversation_id, json)?;
+                }
+                RemindersCommands::Snooze { id, delay } => {
+                    commands::reminders::snooze(&id, &delay)?;
+                }
+                RemindersCommands::Cancel { id } => {
+                    commands::reminders::cancel(&id)?;
+                }
+            }
+        }
+        Commands::Companion { command } => {
+            match command {
+                CompanionCommands::List => {
+                    commands::companion::list()?;
+                }
+                CompanionCommands::Revoke { id } => {
+                    commands::companion::revoke(&id)?;
+                }
+            }
+        }
+        Commands::Share { command } => {
+            match command {
+                ShareCommands::Export { conversation_id, output, no_redact, include_system } => {
+                    commands::share::export(chat_service, &conversation_id, output, no_redact, include_system).await?;
+                }
+                ShareCommands::Publish { conversation_id, to, expires_days, no_redact, include_system } => {
+                    commands::share::publish(chat_service, &conversation_id, to, expires_days, no_redact, include_system).await?;
+                }
+                ShareCommands::List => {
+                    commands::share::list()?;
+                }
+                ShareCommands::Revoke { id, expired } => {
+                    commands::share::revoke(id, expired).await?;
+                }
+                ShareCommands::Config { target, expiry_days, no_expiry, redact, bucket, region, endpoint, public_url } => {
+                    commands::share::configure(target, expiry_days, no_expiry, redact, bucket, region, endpoint, public_url)?;
+                }
+                ShareCommands::S3Keys { access_key_id, remove } => {
+                    commands::share::set_s3_keys(access_key_id, remove)?;
+                }
+            }
+        }
+        Commands::Templates { command } => {
+            match command {
+                TemplatesCommands::List { json } => {
+                    commands::templates::list(json)?;
+                }
+                TemplatesCommands::Show { id } => {
+                    commands::templates::show(&id)?;
+                }
+                TemplatesCommands::Render { id, values } => {
+                    commands::templates::render(&id, values)?;
+                }
+                TemplatesCommands::Remove { id } => {
+                    commands::templates::remove(&id)?;
+                }
+                TemplatesCommands::Gallery { search, json } => {
+                    commands::templates::gallery(search, json).await?;
+                }
+                TemplatesCommands::Preview { id } => {
+                    commands::templates::preview(&id).await?;
+                }
+                TemplatesCommands::Install { id } => {
+                    commands::templates::install(&id).await?;
+                }
+                TemplatesCommands::Updates => {
+                    commands::templates::updates().await?;
+                }
+                TemplatesCommands::Update { id } => {
+                    commands::templates::update(id).await?;
+                }
+                TemplatesCommands::SetGallery { url, off } => {
+                    commands::templates::set_gallery(url, off)?;
+                }
             }
         }
     }
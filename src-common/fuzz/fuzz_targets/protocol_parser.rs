@@ -0,0 +1,30 @@
+//! Feeds arbitrary bytes to the protocol parsers; any panic is a bug.
+//!
+//! Run with `cargo fuzz run protocol_parser` from `src-common`. Seeds are in
+//! `fuzz/corpus/protocol_parser`, which `tests/protocol_corpus.rs` also replays.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mcp_common::protocol::parse;
+use mcp_common::protocol::SseDecoder;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        if let Ok(message) = parse::parse_message(text) {
+            let _ = parse::content_text(&message.payload);
+            let _ = parse::error_message(&message.payload, "Unknown error");
+        }
+    }
+
+    // Split the input to exercise events spanning chunks
+    let mut decoder = SseDecoder::new();
+    for chunk in data.chunks(7) {
+        if let Ok(events) = decoder.push(chunk) {
+            for event in events {
+                let _ = event.parse::<serde_json::Value>();
+            }
+        }
+    }
+    let _ = decoder.finish();
+});
@@ -0,0 +1,168 @@
+# Meldungen der CLI, der TUI und der Desktop-Benachrichtigungen.
+
+## Errors
+
+error-mcp = MCP-Fehler: { $details }
+error-io = E/A-Fehler: { $details }
+error-serialization = Serialisierungsfehler: { $details }
+error-input = Eingabefehler: { $details }
+error-invalid-argument = Ungültiges Argument: { $details }
+error-cancelled = Vorgang abgebrochen
+error-unknown = Unbekannter Fehler: { $details }
+
+## Conversations
+
+conversation-delete-confirm = Unterhaltung { $id } wirklich löschen?
+conversation-deleting = Unterhaltung { $id } wird gelöscht...
+conversation-trashed = Unterhaltung in den Papierkorb verschoben
+conversation-deleted = Unterhaltung { $id } gelöscht; `mcp restore { $id }` stellt sie wieder her
+conversation-delete-failed = Unterhaltung konnte nicht gelöscht werden: { $error }
+conversation-restored = '{ $title }' wiederhergestellt
+conversation-archived = { $count ->
+        [one] Eine Unterhaltung
+       *[other] { $count } Unterhaltungen
+    } archiviert; `mcp undo` macht das rückgängig
+conversation-unarchived = { $count ->
+        [one] Eine Unterhaltung
+       *[other] { $count } Unterhaltungen
+    } aus dem Archiv geholt
+conversation-nothing-changed = Nichts zu ändern
+
+## Trash
+
+trash-purge-all-confirm = Alles im Papierkorb endgültig löschen?
+trash-purge-confirm = { $count ->
+        [one] Eine Unterhaltung
+       *[other] { $count } Unterhaltungen
+    } endgültig löschen? Das kann nicht rückgängig gemacht werden
+trash-empty = Der Papierkorb ist leer
+trash-purged = { $count ->
+        [one] Eine Unterhaltung
+       *[other] { $count } Unterhaltungen
+    } endgültig gelöscht
+
+## Undo
+
+undo-done = Rückgängig gemacht: { $description }
+undo-nothing = Nichts rückgängig zu machen
+redo-done = Wiederholt: { $description }
+redo-nothing = Nichts zu wiederholen
+undo-column-when = Wann
+undo-column-operation = Vorgang
+undo-column-state = Status
+undo-state-done = ausgeführt
+undo-state-undone = rückgängig
+
+## Setup
+
+setup-title = MCP-Client einrichten
+setup-rerun = Die Einrichtung wurde schon einmal abgeschlossen; Ihre bisherigen Antworten sind vorausgefüllt
+setup-step = Schritt { $number } von { $total }: { $title }
+setup-step-profile = Profil
+setup-step-api-key = API-Schlüssel
+setup-step-model = Standardmodell
+setup-step-offline = Offline-Modus
+setup-step-telemetry = Nutzungsdaten
+setup-profile-name = Ihr Name
+setup-profile-email = E-Mail (optional)
+setup-api-key-present = Es ist bereits ein API-Schlüssel eingerichtet
+setup-api-key-replace = Möchten Sie ihn ersetzen?
+setup-api-key-prompt = Anthropic-API-Schlüssel eingeben
+setup-api-key-empty = Der API-Schlüssel darf nicht leer sein
+setup-api-key-checking = API-Schlüssel wird geprüft...
+setup-api-key-valid = API-Schlüssel akzeptiert
+setup-api-key-unverified = Die API war zur Prüfung nicht erreichbar ({ $error }); der Schlüssel wird trotzdem gespeichert
+setup-api-key-invalid = API-Schlüssel abgelehnt: { $error }
+setup-api-key-retry = Einen anderen Schlüssel versuchen?
+setup-api-key-skipped = Kein API-Schlüssel gespeichert; nur der Offline-Modus funktioniert
+setup-model-prompt = Standardmodell wählen
+setup-scanning = Dieser Rechner wird geprüft...
+setup-scan-result = { $cores } CPU-Kerne, { $memory } GiB Arbeitsspeicher
+setup-offline-prompt = Ein lokales Modell verwenden, wenn die API nicht erreichbar ist?
+setup-local-model-prompt = Lokales Modell wählen
+setup-offline-unsupported = Dieser Rechner hat nicht genug Arbeitsspeicher oder Kerne für lokale Modelle
+setup-telemetry-explanation = Papin kann anonyme Nutzungsdaten (verwendete Funktionen, Fehler, Leistung) senden, um die Anwendung zu verbessern. Unterhaltungen werden nie gesendet.
+setup-telemetry-prompt = Anonyme Nutzungsdaten teilen?
+setup-saving = Konfiguration wird gespeichert...
+setup-saved = Konfiguration gespeichert
+setup-save-failed = Einrichtung fehlgeschlagen: { $error }
+setup-advanced-prompt = Temperatur, maximale Tokens und Systemprompt anpassen?
+setup-temperature-prompt = Standardtemperatur (0.0-1.0)
+setup-max-tokens-prompt = Maximale Tokens
+setup-system-prompt-prompt = Systemprompt (leer lassen für keinen)
+setup-done = Einrichtung abgeschlossen
+
+## Connection status
+
+status-checking = API-Schlüssel wird geprüft...
+status-valid = Verbunden; der API-Schlüssel ist gültig
+status-missing = Kein API-Schlüssel eingerichtet; führen Sie `mcp setup` aus
+status-invalid-key = Die API hat den Schlüssel abgelehnt: { $error }
+status-quota-exhausted = Das Konto hat kein Guthaben mehr: { $error }
+status-rate-limited = Der Schlüssel funktioniert, aber Anfragen werden gedrosselt: { $error }
+status-network = Die API ist nicht erreichbar: { $error }
+status-service-error = Die API hat Probleme: { $error }
+status-checked = Geprüft um { $time }
+status-latency = Latenz: { $ms } ms
+status-organization = Organisation: { $id }
+
+## Terminal UI
+
+tui-welcome = Willkommen bei Claude MCP TUI
+tui-error = Fehler: { $error }
+tui-conflicts-pending = { $count ->
+        [one] Ein Synchronisierungskonflikt muss
+       *[other] { $count } Synchronisierungskonflikte müssen
+    } geprüft werden ({ $key } drücken)
+tui-load-conversations-failed = Unterhaltungen konnten nicht geladen werden: { $error }
+tui-load-conversation-failed = Unterhaltung konnte nicht geladen werden: { $error }
+tui-send-failed = Nachricht konnte nicht gesendet werden: { $error }
+tui-conversation-created = Unterhaltung erstellt: { $title }
+tui-create-failed = Unterhaltung konnte nicht erstellt werden: { $error }
+tui-conversation-deleted = Unterhaltung gelöscht: { $title }
+tui-no-reply-to-rate = Keine Antwort zum Bewerten
+tui-no-unread = Keine ungelesenen Nachrichten
+tui-rated-up = Antwort positiv bewertet
+tui-rated-down = Antwort negativ bewertet
+tui-rating-cleared = Bewertung entfernt
+tui-rate-failed = Antwort konnte nicht bewertet werden: { $error }
+tui-rate-usage = Verwendung: rate up|down|clear [Grund]
+tui-conflict-resolved = Konflikt für '{ $key }' gelöst
+tui-conflict-resolve-failed = Konflikt konnte nicht gelöst werden: { $error }
+tui-theme-active = Farbschema { $name } aktiv
+tui-theme-error = Fehler beim Farbschema: { $error }
+tui-themes = Farbschemata: { $names }
+tui-keymap-active = Tastenbelegung { $name } aktiv
+tui-keymap-exported = Tastenbelegung nach { $path } exportiert
+tui-keymap-imported = Tastenbelegung aus { $path } importiert
+tui-keymap-usage = Verwendung: keymap default|vim|emacs, keymap export|import <Pfad>
+tui-keymap-error = Fehler bei der Tastenbelegung: { $error }
+tui-unknown-command = Unbekannter Befehl: { $command }
+
+## Accessibility
+
+tui-a11y-usage = Verwendung: a11y [on|off]
+tui-a11y-on = Bildschirmleser-Ansicht an
+tui-a11y-off = Bildschirmleser-Ansicht aus
+tui-settings-save-failed = Einstellungen konnten nicht gespeichert werden: { $error }
+tui-mode-normal = Unterhaltungsliste
+tui-mode-chat = Nachrichteneingabe
+tui-mode-command = Befehl
+tui-mode-help = Hilfe
+tui-mode-settings = Einstellungen
+tui-mode-conflicts = Konfliktprüfung
+tui-a11y-mode = Modus { $mode }.
+tui-a11y-conversation = Unterhaltung: { $title }.
+tui-a11y-model = Modell: { $model }.
+tui-a11y-streaming = Antwort wird empfangen.
+tui-a11y-error = Fehler: { $message }
+tui-a11y-position = { $position } von { $total }
+tui-a11y-no-conversations = keine
+
+## Desktop notifications
+
+notification-captive-portal-title = Anmeldung im Netzwerk erforderlich
+notification-captive-portal-body = In diesem Netzwerk müssen Sie sich anmelden, bevor Papin das Internet erreichen kann.
+notification-captive-portal-action = Anmeldeseite öffnen
+notification-dns-only-title = Eingeschränkte Verbindung
+notification-dns-only-body = Das Netzwerk ist verbunden, aber Internetverbindungen werden blockiert. Lokale Modelle bleiben verfügbar.
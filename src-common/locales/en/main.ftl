@@ -0,0 +1,169 @@
+# Messages shown by the CLI, the TUI and desktop notifications.
+# Keep IDs in sync with the other locales; missing IDs fall back to English.
+
+## Errors
+
+error-mcp = MCP error: { $details }
+error-io = I/O error: { $details }
+error-serialization = Serialization error: { $details }
+error-input = Input error: { $details }
+error-invalid-argument = Invalid argument: { $details }
+error-cancelled = Operation cancelled
+error-unknown = Unknown error: { $details }
+
+## Conversations
+
+conversation-delete-confirm = Are you sure you want to delete conversation { $id }?
+conversation-deleting = Deleting conversation { $id }...
+conversation-trashed = Conversation moved to the trash
+conversation-deleted = Deleted conversation { $id }; `mcp restore { $id }` brings it back
+conversation-delete-failed = Failed to delete conversation: { $error }
+conversation-restored = Restored '{ $title }'
+conversation-archived = Archived { $count ->
+        [one] one conversation
+       *[other] { $count } conversations
+    }; `mcp undo` restores them
+conversation-unarchived = Unarchived { $count ->
+        [one] one conversation
+       *[other] { $count } conversations
+    }
+conversation-nothing-changed = Nothing to change
+
+## Trash
+
+trash-purge-all-confirm = Permanently delete everything in the trash?
+trash-purge-confirm = Permanently delete { $count ->
+        [one] one conversation
+       *[other] { $count } conversations
+    }? This can't be undone
+trash-empty = Trash is empty
+trash-purged = Purged { $count ->
+        [one] one conversation
+       *[other] { $count } conversations
+    }
+
+## Undo
+
+undo-done = Undid: { $description }
+undo-nothing = Nothing to undo
+redo-done = Redid: { $description }
+redo-nothing = Nothing to redo
+undo-column-when = When
+undo-column-operation = Operation
+undo-column-state = State
+undo-state-done = done
+undo-state-undone = undone
+
+## Setup
+
+setup-title = MCP Client Setup
+setup-rerun = Setup was completed before; your current answers are filled in
+setup-step = Step { $number } of { $total }: { $title }
+setup-step-profile = Profile
+setup-step-api-key = API key
+setup-step-model = Default model
+setup-step-offline = Offline mode
+setup-step-telemetry = Usage data
+setup-profile-name = Your name
+setup-profile-email = Email (optional)
+setup-api-key-present = An API key is already configured
+setup-api-key-replace = Do you want to replace it?
+setup-api-key-prompt = Enter your Anthropic API key
+setup-api-key-empty = API key cannot be empty
+setup-api-key-checking = Checking the API key...
+setup-api-key-valid = API key accepted
+setup-api-key-unverified = Couldn't reach the API to check the key ({ $error }); it will be saved anyway
+setup-api-key-invalid = API key rejected: { $error }
+setup-api-key-retry = Try another key?
+setup-api-key-skipped = No API key saved; only offline mode will work
+setup-model-prompt = Select default model
+setup-scanning = Checking this machine...
+setup-scan-result = { $cores } CPU cores, { $memory } GiB memory
+setup-offline-prompt = Use a local model when the API can't be reached?
+setup-local-model-prompt = Select local model
+setup-offline-unsupported = This machine doesn't have enough memory or cores for local models
+setup-telemetry-explanation = Papin can send anonymous usage data (features used, errors, performance) to help improve it. Conversations are never sent.
+setup-telemetry-prompt = Share anonymous usage data?
+setup-saving = Saving configuration...
+setup-saved = Configuration saved
+setup-save-failed = Setup failed: { $error }
+setup-advanced-prompt = Customize temperature, max tokens and system prompt?
+setup-temperature-prompt = Default temperature (0.0-1.0)
+setup-max-tokens-prompt = Default max tokens
+setup-system-prompt-prompt = System prompt (leave empty for none)
+setup-done = Setup complete
+
+## Connection status
+
+status-checking = Checking the API key...
+status-valid = Connected; the API key is valid
+status-missing = No API key configured; run `mcp setup`
+status-invalid-key = The API rejected the key: { $error }
+status-quota-exhausted = The account is out of credit: { $error }
+status-rate-limited = The key works but requests are rate limited: { $error }
+status-network = The API can't be reached: { $error }
+status-service-error = The API is having problems: { $error }
+status-checked = Checked at { $time }
+status-latency = Latency: { $ms } ms
+status-organization = Organization: { $id }
+
+## Terminal UI
+
+tui-welcome = Welcome to Claude MCP TUI
+tui-error = Error: { $error }
+tui-conflicts-pending = { $count ->
+        [one] One sync conflict needs review
+       *[other] { $count } sync conflicts need review
+    } (press { $key })
+tui-load-conversations-failed = Failed to load conversations: { $error }
+tui-load-conversation-failed = Failed to load conversation: { $error }
+tui-send-failed = Failed to send message: { $error }
+tui-conversation-created = Created conversation: { $title }
+tui-create-failed = Failed to create conversation: { $error }
+tui-conversation-deleted = Deleted conversation: { $title }
+tui-no-reply-to-rate = No reply to rate
+tui-no-unread = No unread messages
+tui-rated-up = Rated reply up
+tui-rated-down = Rated reply down
+tui-rating-cleared = Rating cleared
+tui-rate-failed = Failed to rate reply: { $error }
+tui-rate-usage = Usage: rate up|down|clear [reason]
+tui-conflict-resolved = Resolved conflict for '{ $key }'
+tui-conflict-resolve-failed = Failed to resolve conflict: { $error }
+tui-theme-active = Using the { $name } theme
+tui-theme-error = Theme error: { $error }
+tui-themes = Themes: { $names }
+tui-keymap-active = Using the { $name } keymap
+tui-keymap-exported = Keymap exported to { $path }
+tui-keymap-imported = Keymap imported from { $path }
+tui-keymap-usage = Usage: keymap default|vim|emacs, keymap export|import <path>
+tui-keymap-error = Keymap error: { $error }
+tui-unknown-command = Unknown command: { $command }
+
+## Accessibility
+
+tui-a11y-usage = Usage: a11y [on|off]
+tui-a11y-on = Screen reader layout on
+tui-a11y-off = Screen reader layout off
+tui-settings-save-failed = Failed to save settings: { $error }
+tui-mode-normal = Conversation list
+tui-mode-chat = Message input
+tui-mode-command = Command
+tui-mode-help = Help
+tui-mode-settings = Settings
+tui-mode-conflicts = Conflict review
+tui-a11y-mode = { $mode } mode.
+tui-a11y-conversation = Conversation: { $title }.
+tui-a11y-model = Model: { $model }.
+tui-a11y-streaming = Receiving reply.
+tui-a11y-error = Error: { $message }
+tui-a11y-position = { $position } of { $total }
+tui-a11y-no-conversations = none
+
+## Desktop notifications
+
+notification-captive-portal-title = Network login required
+notification-captive-portal-body = This network requires you to sign in before Papin can reach the internet.
+notification-captive-portal-action = Open login page
+notification-dns-only-title = Limited connectivity
+notification-dns-only-body = The network is up but internet connections are blocked. Local models remain available.
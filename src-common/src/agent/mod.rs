@@ -0,0 +1,809 @@
+//! Agent runs: a conversation working through a task on its own.
+//!
+//! The model is asked for a short numbered plan, then replies, calls tools
+//! and sees their results step by step until it replies without calling a
+//! tool, or until the run has used its budget of steps, time or tokens.
+//! Every step is published on the event bus as it happens, and the whole
+//! transcript is kept in the metadata of the run's last reply. Runs can be
+//! paused, resumed and aborted; the controls take effect between steps.
+//!
+//! Named agents can hand a task on to each other; see [`team`]. Every run is
+//! also kept step by step for replays; see [`replay`].
+
+pub mod replay;
+pub mod team;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+use crate::models::{Conversation, Message, MessageRole, Tool, ToolCall, ToolResult};
+use crate::service::estimate::estimate_tokens;
+use crate::service::ChatService;
+use crate::utils::clock;
+use replay::{get_agent_run_store, ReplayOrigin, RunRecord};
+
+/// Message metadata key holding the transcript of an agent run
+pub const AGENT_METADATA_KEY: &str = "agent";
+
+/// Tool an agent calls to hand a task on to another agent
+pub const HANDOFF_TOOL: &str = "handoff";
+
+/// Numbered lines of a reply, such as `2. Run the tests`
+static PLAN_ITEM: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*\d+[.)]\s+(.+)$").unwrap());
+
+/// How much a run may do before it is stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentBudget {
+    /// Most replies of the model
+    pub max_steps: u32,
+
+    /// Longest the run may take in seconds, not counting pauses
+    pub max_seconds: u64,
+
+    /// Most tokens sent and received, estimated
+    pub max_tokens: u64,
+}
+
+impl Default for AgentBudget {
+    fn default() -> Self {
+        Self {
+            max_steps: 20,
+            max_seconds: 600,
+            max_tokens: 100_000,
+        }
+    }
+}
+
+impl AgentBudget {
+    /// What of the budget has run out, if anything
+    fn exceeded(&self, steps: u32, elapsed: Duration, tokens: u64) -> Option<String> {
+        if steps >= self.max_steps {
+            Some(format!("Used all {} steps", self.max_steps))
+        } else if elapsed.as_secs() >= self.max_seconds {
+            Some(format!("Ran for the {} seconds allowed", self.max_seconds))
+        } else if tokens >= self.max_tokens {
+            Some(format!("Used {} of {} tokens", tokens, self.max_tokens))
+        } else {
+            None
+        }
+    }
+}
+
+/// Executes the tool calls of agent runs
+#[async_trait]
+pub trait ToolExecutor: Send + Sync {
+    /// Tools the model may call
+    fn tools(&self) -> Vec<Tool>;
+
+    /// Execute a call; `None` if no tool has its name
+    async fn execute(&self, conversation_id: &str, call: &ToolCall) -> Option<ToolResult>;
+}
+
+/// Offers no tools; runs with it can only think
+pub struct NoTools;
+
+#[async_trait]
+impl ToolExecutor for NoTools {
+    fn tools(&self) -> Vec<Tool> {
+        Vec::new()
+    }
+
+    async fn execute(&self, _conversation_id: &str, _call: &ToolCall) -> Option<ToolResult> {
+        None
+    }
+}
+
+/// Where a run stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentStatus {
+    /// Taking steps
+    Running,
+
+    /// Waiting to be resumed
+    Paused,
+
+    /// The model replied without calling a tool
+    Finished,
+
+    /// Stopped by the user
+    Aborted,
+
+    /// Stopped when its budget ran out
+    OutOfBudget,
+
+    /// Stopped by an error
+    Failed,
+
+    /// Handed the task on to another agent
+    HandedOff,
+}
+
+impl AgentStatus {
+    /// Whether the run has stopped for good
+    pub fn is_done(&self) -> bool {
+        !matches!(self, AgentStatus::Running | AgentStatus::Paused)
+    }
+}
+
+impl fmt::Display for AgentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            AgentStatus::Running => "running",
+            AgentStatus::Paused => "paused",
+            AgentStatus::Finished => "finished",
+            AgentStatus::Aborted => "aborted",
+            AgentStatus::OutOfBudget => "out of budget",
+            AgentStatus::Failed => "failed",
+            AgentStatus::HandedOff => "handed off",
+        };
+        f.write_str(label)
+    }
+}
+
+/// What happened in a step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StepKind {
+    /// The model replied
+    Think { text: String },
+
+    /// The model called a tool
+    ToolCall { call: ToolCall },
+
+    /// The result of a tool call came back
+    Observe { result: ToolResult },
+}
+
+/// One step of a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentStep {
+    /// Reply of the model the step belongs to, from 1
+    pub number: u32,
+
+    /// What happened
+    #[serde(flatten)]
+    pub kind: StepKind,
+
+    /// Tokens the step sent or received, estimated
+    pub tokens: u64,
+
+    /// When the step was taken
+    pub at: DateTime<Utc>,
+}
+
+/// A task handed on to another agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Handoff {
+    /// Agent taking the task over
+    pub agent: String,
+
+    /// What it is asked to do
+    pub task: String,
+}
+
+/// Everything an agent run did
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentTranscript {
+    /// ID of the run
+    #[serde(default)]
+    pub run_id: String,
+
+    /// Agent that did the run, if it was a named one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+
+    /// Run that handed the task over, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+
+    /// Routing rule that picked the agent, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routed_by: Option<String>,
+
+    /// Task the run worked on
+    pub task: String,
+
+    /// The model's latest plan, one item per entry
+    #[serde(default)]
+    pub plan: Vec<String>,
+
+    /// Budget the run had
+    pub budget: AgentBudget,
+
+    /// Steps, oldest first
+    pub steps: Vec<AgentStep>,
+
+    /// Where the run stands
+    pub status: AgentStatus,
+
+    /// Why the run stopped, unless it finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stopped_because: Option<String>,
+
+    /// Tokens the run used, estimated
+    pub tokens: u64,
+
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+
+    /// When the run stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+
+    /// Where the task went, if the run handed it on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handoff: Option<Handoff>,
+}
+
+impl AgentTranscript {
+    fn new(task: &str, budget: AgentBudget) -> Self {
+        Self {
+            run_id: Uuid::new_v4().to_string(),
+            agent: None,
+            parent: None,
+            routed_by: None,
+            task: task.to_string(),
+            plan: Vec::new(),
+            budget,
+            steps: Vec::new(),
+            status: AgentStatus::Running,
+            stopped_because: None,
+            tokens: 0,
+            started_at: clock::now(),
+            finished_at: None,
+            handoff: None,
+        }
+    }
+
+    /// Replies of the model so far
+    pub fn replies(&self) -> u32 {
+        self.steps.last().map(|s| s.number).unwrap_or(0)
+    }
+
+    /// Transcript kept in a message's metadata
+    pub fn of(message: &Message) -> Option<Self> {
+        let value = message.metadata.as_ref()?.get(AGENT_METADATA_KEY)?;
+        serde_json::from_value(value.clone()).ok()
+    }
+
+    /// Keep the transcript in a message's metadata
+    pub fn attach(&self, message: &mut Message) {
+        message.metadata.get_or_insert_with(Default::default).insert(
+            AGENT_METADATA_KEY.to_string(),
+            serde_json::to_value(self).unwrap_or_default(),
+        );
+    }
+
+    /// Transcript of the latest run in a conversation
+    pub fn latest(conversation: &Conversation) -> Option<Self> {
+        conversation.messages.iter().rev().find_map(Self::of)
+    }
+
+    /// Transcripts of every run in a conversation, oldest first
+    pub fn all(conversation: &Conversation) -> Vec<Self> {
+        conversation.messages.iter().filter_map(Self::of).collect()
+    }
+}
+
+/// Numbered items of a reply, the plan it lays out
+pub fn plan_of(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| PLAN_ITEM.captures(line))
+        .map(|caps| caps[1].trim().to_string())
+        .collect()
+}
+
+/// What the user asked a run to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Signal {
+    Run,
+    Pause,
+    Abort,
+}
+
+/// Pauses, resumes and aborts a run
+#[derive(Clone)]
+pub struct AgentControl {
+    conversation_id: String,
+    signal: Arc<watch::Sender<Signal>>,
+}
+
+impl AgentControl {
+    fn new(conversation_id: &str) -> Self {
+        Self {
+            conversation_id: conversation_id.to_string(),
+            signal: Arc::new(watch::channel(Signal::Run).0),
+        }
+    }
+
+    /// Conversation the run works in
+    pub fn conversation_id(&self) -> &str {
+        &self.conversation_id
+    }
+
+    /// Stop taking steps until resumed
+    pub fn pause(&self) {
+        if self
+            .signal
+            .send_if_modified(|s| Self::shift(s, Signal::Run, Signal::Pause))
+        {
+            emit_status(&self.conversation_id, AgentStatus::Paused, None);
+        }
+    }
+
+    /// Take steps again after a pause
+    pub fn resume(&self) {
+        if self
+            .signal
+            .send_if_modified(|s| Self::shift(s, Signal::Pause, Signal::Run))
+        {
+            emit_status(&self.conversation_id, AgentStatus::Running, None);
+        }
+    }
+
+    /// Stop the run after the current step
+    pub fn abort(&self) {
+        self.signal.send_replace(Signal::Abort);
+    }
+
+    /// Whether the run is paused
+    pub fn is_paused(&self) -> bool {
+        *self.signal.borrow() == Signal::Pause
+    }
+
+    /// Move from one signal to another; false if the run wasn't at the first
+    fn shift(signal: &mut Signal, from: Signal, to: Signal) -> bool {
+        if *signal != from {
+            return false;
+        }
+        *signal = to;
+        true
+    }
+
+    /// Wait while the run is paused; false once it is aborted
+    async fn proceed(&self) -> bool {
+        let mut signal = self.signal.subscribe();
+        loop {
+            let current = *signal.borrow_and_update();
+            match current {
+                Signal::Run => return true,
+                Signal::Abort => return false,
+                Signal::Pause => {}
+            }
+            if signal.changed().await.is_err() {
+                return false;
+            }
+        }
+    }
+}
+
+/// Runs in progress, by conversation
+#[derive(Default)]
+pub struct AgentRuns {
+    runs: Mutex<HashMap<String, AgentControl>>,
+}
+
+impl AgentRuns {
+    /// Controls of a conversation's run, if one is in progress
+    pub fn get(&self, conversation_id: &str) -> Option<AgentControl> {
+        self.runs.lock().unwrap().get(conversation_id).cloned()
+    }
+
+    /// Conversations with a run in progress
+    pub fn running(&self) -> Vec<String> {
+        self.runs.lock().unwrap().keys().cloned().collect()
+    }
+
+    fn start(&self, conversation_id: &str) -> McpResult<AgentControl> {
+        let mut runs = self.runs.lock().unwrap();
+        if runs.contains_key(conversation_id) {
+            return Err(McpError::InvalidRequest(format!(
+                "An agent is already running in conversation {}",
+                conversation_id
+            )));
+        }
+        let control = AgentControl::new(conversation_id);
+        runs.insert(conversation_id.to_string(), control.clone());
+        Ok(control)
+    }
+
+    fn finish(&self, conversation_id: &str) {
+        self.runs.lock().unwrap().remove(conversation_id);
+    }
+}
+
+static AGENT_RUNS: Lazy<Arc<AgentRuns>> = Lazy::new(|| Arc::new(AgentRuns::default()));
+
+/// Get the runs in progress
+pub fn get_agent_runs() -> Arc<AgentRuns> {
+    AGENT_RUNS.clone()
+}
+
+fn emit_status(conversation_id: &str, status: AgentStatus, transcript: Option<&AgentTranscript>) {
+    get_event_bus().emit(
+        Topic::Conversation,
+        names::AGENT_STATUS_CHANGED,
+        serde_json::json!({
+            "conversation_id": conversation_id,
+            "agent": transcript.and_then(|t| t.agent.clone()),
+            "status": status,
+            "stopped_because": transcript.and_then(|t| t.stopped_because.clone()),
+            "plan": transcript.map(|t| t.plan.clone()),
+        }),
+    );
+}
+
+/// First message of a run: the task, with how to go about it
+fn instructions(task: &str, tools: &[Tool]) -> String {
+    let tools = if tools.is_empty() {
+        "No tools are available.".to_string()
+    } else {
+        let list: Vec<String> = tools
+            .iter()
+            .map(|t| format!("- {}: {}", t.name, t.description))
+            .collect();
+        format!("Tools you can call:\n{}", list.join("\n"))
+    };
+    format!(
+        "Work on the task below on your own. Start with a short numbered plan, then carry it out \
+         step by step, calling tools where they help. Reply without calling a tool once the task \
+         is done.\n\n{}\n\nTask: {}",
+        tools, task
+    )
+}
+
+/// A run in progress
+struct Run<'a> {
+    conversation_id: &'a str,
+    control: AgentControl,
+    transcript: AgentTranscript,
+    prompt: String,
+    tools: Vec<String>,
+    replies: Vec<String>,
+    replay_of: Option<ReplayOrigin>,
+    started: Instant,
+    paused: Duration,
+}
+
+impl Run<'_> {
+    /// Keep the run as it stands for replays
+    fn persist(&self) {
+        let record = RunRecord {
+            conversation_id: self.conversation_id.to_string(),
+            prompt: self.prompt.clone(),
+            tools: self.tools.clone(),
+            replies: self.replies.clone(),
+            replay_of: self.replay_of.clone(),
+            transcript: self.transcript.clone(),
+        };
+        if let Err(e) = get_agent_run_store().save(&record) {
+            warn!("Failed to keep agent run {}: {}", self.transcript.run_id, e);
+        }
+    }
+
+    fn record(&mut self, number: u32, kind: StepKind, tokens: u64) {
+        if let StepKind::Think { text } = &kind {
+            let plan = plan_of(text);
+            if !plan.is_empty() {
+                self.transcript.plan = plan;
+            }
+        }
+        let step = AgentStep {
+            number,
+            kind,
+            tokens,
+            at: clock::now(),
+        };
+        self.transcript.tokens += tokens;
+        get_event_bus().emit(
+            Topic::Conversation,
+            names::AGENT_STEP,
+            serde_json::json!({
+                "conversation_id": self.conversation_id,
+                "agent": self.transcript.agent,
+                "step": step,
+                "plan": self.transcript.plan,
+            }),
+        );
+        self.transcript.steps.push(step);
+        self.persist();
+    }
+
+    /// Wait out a pause; false if the run is aborted
+    async fn proceed(&mut self) -> bool {
+        let waited = Instant::now();
+        let proceed = self.control.proceed().await;
+        self.paused += waited.elapsed();
+        proceed
+    }
+
+    fn stop(&mut self, status: AgentStatus, reason: Option<String>) {
+        self.transcript.status = status;
+        self.transcript.stopped_because = reason;
+        self.transcript.finished_at = Some(clock::now());
+        self.persist();
+    }
+}
+
+/// The tool agents hand tasks on with
+fn handoff_tool(agents: &[String]) -> Tool {
+    Tool::new(
+        HANDOFF_TOOL,
+        format!(
+            "Hand the task, or a part of it, to another agent: {}. Your run ends with the call.",
+            agents.join(", ")
+        ),
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "agent": { "type": "string", "enum": agents },
+                "task": { "type": "string", "description": "What the agent should do" }
+            },
+            "required": ["agent", "task"]
+        }),
+    )
+}
+
+/// Where a replay picks up an earlier run
+struct Resume {
+    /// Run and step replayed
+    origin: ReplayOrigin,
+
+    /// Steps taken before the one run again
+    steps: Vec<AgentStep>,
+
+    /// IDs of the replies to those steps, in the replay's conversation
+    replies: Vec<String>,
+
+    /// First message of the earlier run; `None` asks afresh
+    prompt: Option<String>,
+
+    /// Results the step run again gets; `None` sends the prompt instead
+    results: Option<Vec<ToolResult>>,
+}
+
+/// Runs tasks in conversations within a budget
+pub struct AgentRunner {
+    budget: AgentBudget,
+    name: Option<String>,
+    handoffs: Vec<String>,
+    parent: Option<String>,
+    routed_by: Option<String>,
+    resume: Option<Resume>,
+}
+
+impl AgentRunner {
+    /// Create a runner giving every run a budget
+    pub fn new(budget: AgentBudget) -> Self {
+        Self {
+            budget,
+            name: None,
+            handoffs: Vec::new(),
+            parent: None,
+            routed_by: None,
+            resume: None,
+        }
+    }
+
+    /// Run as a named agent
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Let runs hand their task on to these agents
+    pub fn with_handoffs(mut self, agents: Vec<String>) -> Self {
+        self.handoffs = agents;
+        self
+    }
+
+    /// Record runs as picking up a task another run handed over
+    pub fn after(mut self, parent_run_id: &str) -> Self {
+        self.parent = Some(parent_run_id.to_string());
+        self
+    }
+
+    /// Record the routing rule that picked the agent
+    pub fn routed_by(mut self, pattern: &str) -> Self {
+        self.routed_by = Some(pattern.to_string());
+        self
+    }
+
+    /// Work on a task in a conversation until the model is done, the budget
+    /// runs out, the run is aborted or a request fails. The transcript is
+    /// returned and kept in the metadata of the run's last reply.
+    pub async fn run(
+        &self,
+        chat: &ChatService,
+        conversation_id: &str,
+        task: &str,
+        tools: &dyn ToolExecutor,
+    ) -> McpResult<AgentTranscript> {
+        let control = get_agent_runs().start(conversation_id)?;
+        let mut offered = tools.tools();
+        if !self.handoffs.is_empty() {
+            offered.push(handoff_tool(&self.handoffs));
+        }
+        let mut transcript = AgentTranscript::new(task, self.budget);
+        transcript.agent = self.name.clone();
+        transcript.parent = self.parent.clone();
+        transcript.routed_by = self.routed_by.clone();
+        let mut run = Run {
+            conversation_id,
+            control,
+            transcript,
+            prompt: instructions(task, &offered),
+            tools: offered.iter().map(|t| t.name.clone()).collect(),
+            replies: Vec::new(),
+            replay_of: None,
+            started: Instant::now(),
+            paused: Duration::ZERO,
+        };
+        let mut results = None;
+        if let Some(resume) = &self.resume {
+            run.transcript.tokens = resume.steps.iter().map(|s| s.tokens).sum();
+            run.transcript.steps = resume.steps.clone();
+            if let Some(plan) = resume.steps.iter().rev().find_map(|s| match &s.kind {
+                StepKind::Think { text } => Some(plan_of(text)).filter(|plan| !plan.is_empty()),
+                _ => None,
+            }) {
+                run.transcript.plan = plan;
+            }
+            if let Some(prompt) = &resume.prompt {
+                run.prompt = prompt.clone();
+            }
+            run.replies = resume.replies.clone();
+            run.replay_of = Some(resume.origin.clone());
+            results = resume.results.clone();
+        }
+        // Models without native tools get the offered ones described in the prompt
+        if let Err(e) = chat.offer_tools(conversation_id, &offered).await {
+            warn!("Failed to offer tools in {}: {}", conversation_id, e);
+        }
+        info!("Agent run started in {}", conversation_id);
+        emit_status(conversation_id, AgentStatus::Running, Some(&run.transcript));
+
+        self.drive(chat, &mut run, tools, results).await;
+        get_agent_runs().finish(conversation_id);
+        if let Err(e) = chat.offer_tools(conversation_id, &[]).await {
+            warn!("Failed to withdraw tools in {}: {}", conversation_id, e);
+        }
+
+        let transcript = run.transcript;
+        // Without a reply of its own, the run has nowhere to keep its transcript
+        if transcript.replies() > 0 {
+            if let Err(e) = Self::keep_transcript(chat, conversation_id, &transcript).await {
+                warn!("Failed to keep the agent transcript of {}: {}", conversation_id, e);
+            }
+        }
+        info!("Agent run in {} stopped: {}", conversation_id, transcript.status);
+        emit_status(conversation_id, transcript.status, Some(&transcript));
+        Ok(transcript)
+    }
+
+    /// Take steps until the run stops, starting with the prompt, or with
+    /// tool results when a replay picks up after a step
+    async fn drive(
+        &self,
+        chat: &ChatService,
+        run: &mut Run<'_>,
+        tools: &dyn ToolExecutor,
+        results: Option<Vec<ToolResult>>,
+    ) {
+        let mut prompt = results.is_none().then(|| run.prompt.clone());
+        let mut results = results.unwrap_or_default();
+        loop {
+            if !run.proceed().await {
+                return run.stop(AgentStatus::Aborted, Some("Aborted by the user".to_string()));
+            }
+            let elapsed = run.started.elapsed().saturating_sub(run.paused);
+            let spent = self
+                .budget
+                .exceeded(run.transcript.replies(), elapsed, run.transcript.tokens);
+            if let Some(reason) = spent {
+                return run.stop(AgentStatus::OutOfBudget, Some(reason));
+            }
+
+            let (sent, reply) = match prompt.take() {
+                Some(prompt) => (
+                    estimate_tokens(&prompt),
+                    chat.send_message(run.conversation_id, &prompt).await,
+                ),
+                None => (0, chat.send_tool_results(run.conversation_id, &results).await),
+            };
+            let reply = match reply {
+                Ok(reply) => reply,
+                Err(e) => return run.stop(AgentStatus::Failed, Some(e.to_string())),
+            };
+            let number = run.transcript.replies() + 1;
+            run.replies.push(reply.id.clone());
+            let text = reply.text();
+            let tokens = sent + estimate_tokens(&text);
+            run.record(number, StepKind::Think { text }, tokens);
+
+            let calls = reply.tool_calls();
+            if calls.is_empty() {
+                return run.stop(AgentStatus::Finished, None);
+            }
+            results.clear();
+            for call in calls {
+                if !run.proceed().await {
+                    return run.stop(AgentStatus::Aborted, Some("Aborted by the user".to_string()));
+                }
+                run.record(number, StepKind::ToolCall { call: call.clone() }, 0);
+                let result = if call.name == HANDOFF_TOOL && !self.handoffs.is_empty() {
+                    match self.handoff_of(&call) {
+                        Ok(handoff) => {
+                            let reason = format!("Handed off to {}", handoff.agent);
+                            run.transcript.handoff = Some(handoff);
+                            return run.stop(AgentStatus::HandedOff, Some(reason));
+                        }
+                        Err(error) => ToolResult::new(
+                            call.id.clone(),
+                            call.name.clone(),
+                            serde_json::json!({ "error": error }),
+                        ),
+                    }
+                } else {
+                    tools.execute(run.conversation_id, &call).await.unwrap_or_else(|| {
+                        ToolResult::new(
+                            call.id.clone(),
+                            call.name.clone(),
+                            serde_json::json!({ "error": format!("There is no tool named {}", call.name) }),
+                        )
+                    })
+                };
+                let tokens = estimate_tokens(&result.result.to_string());
+                run.record(number, StepKind::Observe { result: result.clone() }, tokens);
+                results.push(result);
+            }
+        }
+    }
+
+    /// Task a handoff call passes on, or why it can't be
+    fn handoff_of(&self, call: &ToolCall) -> Result<Handoff, String> {
+        let agent = call.arguments["agent"].as_str().unwrap_or_default();
+        if !self.handoffs.iter().any(|a| a == agent) {
+            return Err(format!(
+                "No agent named '{}'; hand off to one of: {}",
+                agent,
+                self.handoffs.join(", ")
+            ));
+        }
+        match call.arguments["task"].as_str().map(str::trim) {
+            Some(task) if !task.is_empty() => Ok(Handoff {
+                agent: agent.to_string(),
+                task: task.to_string(),
+            }),
+            _ => Err("Say what the agent should do in `task`".to_string()),
+        }
+    }
+
+    /// Keep the transcript in the metadata of the conversation's last reply
+    async fn keep_transcript(chat: &ChatService, conversation_id: &str, transcript: &AgentTranscript) -> McpResult<()> {
+        let mut conversation = chat.get_conversation(conversation_id).await?;
+        let Some(reply) = conversation
+            .messages
+            .iter_mut()
+            .rev()
+            .find(|m| m.role == MessageRole::Assistant)
+        else {
+            return Ok(());
+        };
+        transcript.attach(reply);
+        chat.update_conversation(conversation).await
+    }
+}
@@ -0,0 +1,553 @@
+//! Replays of agent runs.
+//!
+//! Every run is kept in its own file under `agent_runs` in the app data
+//! directory, saved again after each step, with the prompt that started it
+//! and the IDs of the replies it got. A kept run can be stepped through one
+//! reply of the model at a time, run again from any step with a different
+//! task, budget, model or system prompt, and compared with another run.
+
+use chrono::{DateTime, Utc};
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use super::{AgentBudget, AgentRunner, AgentStatus, AgentTranscript, Resume, StepKind, ToolExecutor};
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::models::{MessageRole, ToolCall, ToolResult};
+use crate::service::ChatService;
+
+/// Most runs kept; the oldest are dropped
+const MAX_KEPT_RUNS: usize = 500;
+
+/// The run a replay ran again, and from where
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayOrigin {
+    /// Run replayed
+    pub run_id: String,
+
+    /// Step the replay ran again from
+    pub from_step: u32,
+}
+
+/// An agent run as kept for replays
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Conversation the run worked in
+    pub conversation_id: String,
+
+    /// First message of the run: the task, with how to go about it
+    pub prompt: String,
+
+    /// Tools the run was offered
+    #[serde(default)]
+    pub tools: Vec<String>,
+
+    /// IDs of the model's replies, by step
+    #[serde(default)]
+    pub replies: Vec<String>,
+
+    /// Run this one replays, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay_of: Option<ReplayOrigin>,
+
+    /// Everything the run did
+    pub transcript: AgentTranscript,
+}
+
+impl RunRecord {
+    /// Every reply of the model, with what it was given and what its tool
+    /// calls returned
+    pub fn frames(&self) -> Vec<ReplayFrame> {
+        let mut frames: Vec<ReplayFrame> = Vec::new();
+        for step in &self.transcript.steps {
+            if frames.last().is_none_or(|f| f.number != step.number) {
+                let input = match frames.last() {
+                    Some(previous) => FrameInput::ToolResults {
+                        results: previous.results.clone(),
+                    },
+                    None => FrameInput::Prompt {
+                        text: self.prompt.clone(),
+                    },
+                };
+                frames.push(ReplayFrame {
+                    number: step.number,
+                    input,
+                    response: String::new(),
+                    calls: Vec::new(),
+                    results: Vec::new(),
+                    tokens: 0,
+                    at: step.at,
+                });
+            }
+            let frame = frames.last_mut().unwrap();
+            frame.tokens += step.tokens;
+            match &step.kind {
+                StepKind::Think { text } => frame.response = text.clone(),
+                StepKind::ToolCall { call } => frame.calls.push(call.clone()),
+                StepKind::Observe { result } => frame.results.push(result.clone()),
+            }
+        }
+        frames
+    }
+
+    /// One reply of the model, from 1
+    pub fn frame(&self, number: u32) -> Option<ReplayFrame> {
+        self.frames().into_iter().find(|f| f.number == number)
+    }
+
+    /// What the run was and how it went
+    pub fn summary(&self) -> RunSummary {
+        RunSummary {
+            run_id: self.transcript.run_id.clone(),
+            conversation_id: self.conversation_id.clone(),
+            agent: self.transcript.agent.clone(),
+            task: self.transcript.task.clone(),
+            status: self.transcript.status,
+            replies: self.transcript.replies(),
+            tokens: self.transcript.tokens,
+            started_at: self.transcript.started_at,
+            replay_of: self.replay_of.clone(),
+        }
+    }
+}
+
+/// A kept run at a glance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    /// ID of the run
+    pub run_id: String,
+
+    /// Conversation the run worked in
+    pub conversation_id: String,
+
+    /// Agent that did the run, if it was a named one
+    pub agent: Option<String>,
+
+    /// Task the run worked on
+    pub task: String,
+
+    /// Where the run stands
+    pub status: AgentStatus,
+
+    /// Replies of the model
+    pub replies: u32,
+
+    /// Tokens the run used, estimated
+    pub tokens: u64,
+
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+
+    /// Run this one replays, if any
+    pub replay_of: Option<ReplayOrigin>,
+}
+
+/// What the model was given for a reply
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FrameInput {
+    /// The first message of the run
+    Prompt { text: String },
+
+    /// The results of the previous reply's tool calls
+    ToolResults { results: Vec<ToolResult> },
+}
+
+/// One reply of the model in a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    /// Step of the run, from 1
+    pub number: u32,
+
+    /// What the model was given
+    pub input: FrameInput,
+
+    /// What the model replied
+    pub response: String,
+
+    /// Tools it called
+    pub calls: Vec<ToolCall>,
+
+    /// What the calls returned
+    pub results: Vec<ToolResult>,
+
+    /// Tokens the step sent and received, estimated
+    pub tokens: u64,
+
+    /// When the model replied
+    pub at: DateTime<Utc>,
+}
+
+impl ReplayFrame {
+    /// Whether two replies got the same input and did the same, call IDs
+    /// and times aside
+    pub fn same_as(&self, other: &ReplayFrame) -> bool {
+        let calls = |frame: &ReplayFrame| -> Vec<(String, serde_json::Value)> {
+            frame
+                .calls
+                .iter()
+                .map(|c| (c.name.clone(), c.arguments.clone()))
+                .collect()
+        };
+        let results = |results: &[ToolResult]| -> Vec<(String, serde_json::Value)> {
+            results.iter().map(|r| (r.name.clone(), r.result.clone())).collect()
+        };
+        let same_input = match (&self.input, &other.input) {
+            (FrameInput::Prompt { text: a }, FrameInput::Prompt { text: b }) => a == b,
+            (FrameInput::ToolResults { results: a }, FrameInput::ToolResults { results: b }) => {
+                results(a) == results(b)
+            }
+            _ => false,
+        };
+        same_input
+            && self.response == other.response
+            && calls(self) == calls(other)
+            && results(&self.results) == results(&other.results)
+    }
+}
+
+/// How a reply differs between two runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameChange {
+    /// Both runs did the same
+    Same,
+
+    /// The runs did different things
+    Changed,
+
+    /// Only the second run got this far
+    Added,
+
+    /// Only the first run got this far
+    Removed,
+}
+
+/// A reply of two runs side by side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameDiff {
+    /// Step of the runs, from 1
+    pub number: u32,
+
+    /// How the reply differs
+    pub change: FrameChange,
+
+    /// The first run's reply
+    pub before: Option<ReplayFrame>,
+
+    /// The second run's reply
+    pub after: Option<ReplayFrame>,
+}
+
+/// Two runs compared reply by reply
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunDiff {
+    /// The first run
+    pub before: RunSummary,
+
+    /// The second run
+    pub after: RunSummary,
+
+    /// First step where the runs part ways, if they do
+    pub diverged_at: Option<u32>,
+
+    /// Every reply of either run
+    pub frames: Vec<FrameDiff>,
+}
+
+impl RunDiff {
+    /// Compare a run with a later one
+    pub fn between(before: &RunRecord, after: &RunRecord) -> Self {
+        let mut old = before.frames().into_iter();
+        let mut new = after.frames().into_iter();
+        let mut frames = Vec::new();
+        loop {
+            let (before, after) = match (old.next(), new.next()) {
+                (None, None) => break,
+                pair => pair,
+            };
+            let change = match (&before, &after) {
+                (Some(b), Some(a)) if b.same_as(a) => FrameChange::Same,
+                (Some(_), Some(_)) => FrameChange::Changed,
+                (None, _) => FrameChange::Added,
+                (_, None) => FrameChange::Removed,
+            };
+            let number = before.as_ref().or(after.as_ref()).map(|f| f.number).unwrap_or_default();
+            frames.push(FrameDiff {
+                number,
+                change,
+                before,
+                after,
+            });
+        }
+        let diverged_at = frames.iter().find(|f| f.change != FrameChange::Same).map(|f| f.number);
+        Self {
+            before: before.summary(),
+            after: after.summary(),
+            diverged_at,
+            frames,
+        }
+    }
+}
+
+/// Kept agent runs, one file each
+pub struct AgentRunStore {
+    dir: PathBuf,
+}
+
+impl AgentRunStore {
+    /// Runs kept in a directory
+    pub fn at(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path(&self, run_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", run_id))
+    }
+
+    fn read(&self, run_id: &str) -> Option<RunRecord> {
+        let json = fs::read_to_string(self.path(run_id)).ok()?;
+        serde_json::from_str(&json)
+            .map_err(|e| warn!("Ignoring unreadable agent run {}: {}", run_id, e))
+            .ok()
+    }
+
+    fn records(&self) -> Vec<RunRecord> {
+        let mut records: Vec<RunRecord> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    self.read(&path.file_stem()?.to_string_lossy())
+                } else {
+                    None
+                }
+            })
+            .collect();
+        records.sort_by_key(|r| std::cmp::Reverse(r.transcript.started_at));
+        records
+    }
+
+    /// Keep a run as it stands; the oldest runs are dropped once there are
+    /// too many
+    pub fn save(&self, record: &RunRecord) -> McpResult<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(&record.transcript.run_id);
+        let new = !path.exists();
+        fs::write(&path, serde_json::to_string_pretty(record)?)?;
+
+        if new {
+            for old in self.records().iter().skip(MAX_KEPT_RUNS) {
+                let _ = fs::remove_file(self.path(&old.transcript.run_id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Kept runs, newest first; only a conversation's with one given
+    pub fn list(&self, conversation_id: Option<&str>) -> Vec<RunSummary> {
+        self.records()
+            .iter()
+            .filter(|r| conversation_id.is_none_or(|id| r.conversation_id == id))
+            .map(RunRecord::summary)
+            .collect()
+    }
+
+    /// A run by ID or unique ID prefix
+    pub fn get(&self, run_id: &str) -> McpResult<RunRecord> {
+        if let Some(record) = self.read(run_id) {
+            return Ok(record);
+        }
+        let mut matches: Vec<RunRecord> = self
+            .records()
+            .into_iter()
+            .filter(|r| r.transcript.run_id.starts_with(run_id))
+            .collect();
+        match matches.len() {
+            1 => Ok(matches.remove(0)),
+            0 => Err(McpError::InvalidRequest(format!("Agent run {} not found", run_id))),
+            _ => Err(McpError::InvalidRequest(format!(
+                "Agent run ID {} is ambiguous",
+                run_id
+            ))),
+        }
+    }
+
+    /// The run to compare a run with: the one it replays, or else the one
+    /// before it in its conversation
+    pub fn previous(&self, record: &RunRecord) -> Option<RunRecord> {
+        if let Some(origin) = &record.replay_of {
+            return self.read(&origin.run_id);
+        }
+        self.records().into_iter().find(|r| {
+            r.conversation_id == record.conversation_id && r.transcript.started_at < record.transcript.started_at
+        })
+    }
+
+    /// Forget a run; returns whether it was kept
+    pub fn remove(&self, run_id: &str) -> McpResult<bool> {
+        let record = match self.get(run_id) {
+            Ok(record) => record,
+            Err(_) => return Ok(false),
+        };
+        fs::remove_file(self.path(&record.transcript.run_id))?;
+        Ok(true)
+    }
+}
+
+static AGENT_RUN_STORE: Lazy<Arc<AgentRunStore>> = Lazy::new(|| Arc::new(AgentRunStore::at(data_path("agent_runs"))));
+
+/// Get the global store of kept agent runs
+pub fn get_agent_run_store() -> Arc<AgentRunStore> {
+    AGENT_RUN_STORE.clone()
+}
+
+/// What to change when running a kept run again
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplayOverrides {
+    /// Task to work on instead; only when running again from step 1
+    #[serde(default)]
+    pub task: Option<String>,
+
+    /// Budget instead of the run's, counting the steps replayed
+    #[serde(default)]
+    pub budget: Option<AgentBudget>,
+
+    /// Model to ask instead of the conversation's
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// System prompt instead of the conversation's
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+}
+
+/// A kept run set up to run again from one of its steps, in a copy of its
+/// conversation as it stood before that step
+pub struct Rerun {
+    conversation_id: String,
+    task: String,
+    runner: AgentRunner,
+}
+
+impl Rerun {
+    /// Copy the run's conversation up to a step and apply the overrides.
+    /// The steps before it are kept as they were; the model is asked again
+    /// from it on, and its tool calls are made again.
+    pub async fn prepare(
+        chat: &ChatService,
+        record: &RunRecord,
+        from_step: u32,
+        overrides: ReplayOverrides,
+    ) -> McpResult<Self> {
+        let replies = record.transcript.replies();
+        if from_step == 0 || from_step > replies {
+            return Err(McpError::InvalidRequest(format!(
+                "Run {} has steps 1 to {}",
+                record.transcript.run_id, replies
+            )));
+        }
+        if overrides.task.is_some() && from_step > 1 {
+            return Err(McpError::InvalidRequest(
+                "The task can only change when running again from step 1".to_string(),
+            ));
+        }
+
+        let original = chat.get_conversation(&record.conversation_id).await?;
+        let missing = || {
+            McpError::InvalidRequest(format!(
+                "Conversation {} no longer holds the replies of run {}",
+                record.conversation_id, record.transcript.run_id
+            ))
+        };
+        let position = |id: &String| original.messages.iter().position(|m| &m.id == id);
+        // Up to the prompt of the run, or the reply before the step
+        let cut = if from_step == 1 {
+            let first = record.replies.first().and_then(position).ok_or_else(missing)?;
+            original.messages[..first]
+                .iter()
+                .rposition(|m| m.role == MessageRole::User)
+                .ok_or_else(missing)?
+        } else {
+            let before = record.replies.get(from_step as usize - 2).ok_or_else(missing)?;
+            position(before).ok_or_else(missing)? + 1
+        };
+
+        let mut conversation = chat
+            .create_conversation(&format!("Replay of {}", original.title), Some(original.model.clone()))
+            .await?;
+        let mut ids = HashMap::new();
+        conversation.messages = original.messages[..cut]
+            .iter()
+            .map(|message| {
+                let mut copy = message.clone();
+                copy.id = Uuid::new_v4().to_string();
+                ids.insert(message.id.clone(), copy.id.clone());
+                copy
+            })
+            .collect();
+        conversation.metadata = original.metadata.clone();
+        let conversation_id = conversation.id.clone();
+        chat.update_conversation(conversation).await?;
+        if let Some(model) = &overrides.model {
+            chat.set_conversation_model(&conversation_id, model).await?;
+        }
+        if let Some(system_prompt) = &overrides.system_prompt {
+            chat.set_system_message(&conversation_id, system_prompt).await?;
+        }
+
+        let resume = Resume {
+            origin: ReplayOrigin {
+                run_id: record.transcript.run_id.clone(),
+                from_step,
+            },
+            steps: record
+                .transcript
+                .steps
+                .iter()
+                .filter(|s| s.number < from_step)
+                .cloned()
+                .collect(),
+            replies: record.replies[..from_step as usize - 1]
+                .iter()
+                .filter_map(|id| ids.get(id).cloned())
+                .collect(),
+            prompt: (from_step > 1).then(|| record.prompt.clone()),
+            results: (from_step > 1).then(|| {
+                record
+                    .frame(from_step - 1)
+                    .map(|frame| frame.results)
+                    .unwrap_or_default()
+            }),
+        };
+        let mut runner = AgentRunner::new(overrides.budget.unwrap_or(record.transcript.budget));
+        if let Some(agent) = &record.transcript.agent {
+            runner = runner.named(agent);
+        }
+        runner.resume = Some(resume);
+
+        Ok(Self {
+            conversation_id,
+            task: overrides.task.unwrap_or_else(|| record.transcript.task.clone()),
+            runner,
+        })
+    }
+
+    /// Conversation the run goes on in
+    pub fn conversation_id(&self) -> &str {
+        &self.conversation_id
+    }
+
+    /// Run from the step on, as an agent run of its own
+    pub async fn run(&self, chat: &ChatService, tools: &dyn ToolExecutor) -> McpResult<AgentTranscript> {
+        self.runner.run(chat, &self.conversation_id, &self.task, tools).await
+    }
+}
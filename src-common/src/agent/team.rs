@@ -0,0 +1,455 @@
+//! Teams of named agents handing tasks to each other.
+//!
+//! Each agent of the team has its own system prompt, tools and budget.
+//! Routing rules pick the agent a task starts with; an agent hands the task,
+//! or a part of it, on by calling the `handoff` tool, and the agent it names
+//! picks it up in the same conversation. The runs of a conversation form an
+//! execution graph that can be exported for debugging.
+
+use async_trait::async_trait;
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use super::{AgentBudget, AgentRunner, AgentStatus, AgentTranscript, StepKind, ToolExecutor};
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::models::{Conversation, Tool, ToolCall, ToolResult};
+use crate::service::ChatService;
+
+/// Handoffs a team run allows before the last agent has to finish on its own
+const DEFAULT_MAX_HANDOFFS: u32 = 5;
+
+fn default_max_handoffs() -> u32 {
+    DEFAULT_MAX_HANDOFFS
+}
+
+/// A named agent of a team
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentProfile {
+    /// Name, unique in the team
+    pub name: String,
+
+    /// System prompt the agent works under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+
+    /// Tools the agent may call; none means every tool
+    #[serde(default)]
+    pub tools: Vec<String>,
+
+    /// Budget of each of the agent's runs
+    #[serde(default)]
+    pub budget: AgentBudget,
+}
+
+/// Sends tasks matching a pattern to an agent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutingRule {
+    /// Regular expression matched against the task, ignoring case
+    pub pattern: String,
+
+    /// Agent the task starts with
+    pub agent: String,
+}
+
+impl RoutingRule {
+    fn regex(pattern: &str) -> McpResult<Regex> {
+        RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| McpError::InvalidRequest(format!("Invalid routing pattern '{}': {}", pattern, e)))
+    }
+
+    /// Whether a task matches the rule
+    pub fn matches(&self, task: &str) -> bool {
+        Self::regex(&self.pattern).map(|r| r.is_match(task)).unwrap_or(false)
+    }
+}
+
+/// Agents that hand tasks to each other, and the rules routing tasks to them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentTeam {
+    /// The agents; the first takes tasks no rule routes
+    #[serde(default)]
+    pub agents: Vec<AgentProfile>,
+
+    /// Routing rules, tried in order
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
+
+    /// Handoffs a run of the team allows
+    #[serde(default = "default_max_handoffs")]
+    pub max_handoffs: u32,
+}
+
+impl Default for AgentTeam {
+    fn default() -> Self {
+        Self {
+            agents: Vec::new(),
+            rules: Vec::new(),
+            max_handoffs: DEFAULT_MAX_HANDOFFS,
+        }
+    }
+}
+
+impl AgentTeam {
+    /// Agent of a name
+    pub fn agent(&self, name: &str) -> Option<&AgentProfile> {
+        self.agents.iter().find(|a| a.name == name)
+    }
+
+    /// Agent a task starts with, and the rule that picked it; the first
+    /// agent when no rule matches
+    pub fn route(&self, task: &str) -> Option<(&AgentProfile, Option<&RoutingRule>)> {
+        let routed = self
+            .rules
+            .iter()
+            .filter(|rule| rule.matches(task))
+            .find_map(|rule| self.agent(&rule.agent).map(|agent| (agent, Some(rule))));
+        routed.or_else(|| self.agents.first().map(|agent| (agent, None)))
+    }
+}
+
+fn validate_name(name: &str) -> McpResult<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(McpError::InvalidRequest(format!(
+            "Invalid agent name '{}'; use letters, digits, '-' and '_'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// The team, kept in a file
+pub struct AgentTeamStore {
+    path: PathBuf,
+    team: Mutex<AgentTeam>,
+}
+
+impl AgentTeamStore {
+    /// Team kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        let team = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable agent team {:?}: {}", path, e);
+                AgentTeam::default()
+            }),
+            Err(_) => AgentTeam::default(),
+        };
+        Self {
+            path,
+            team: Mutex::new(team),
+        }
+    }
+
+    fn save(&self, team: &AgentTeam) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(team)?)?;
+        Ok(())
+    }
+
+    /// The team
+    pub fn get(&self) -> AgentTeam {
+        self.team.lock().unwrap().clone()
+    }
+
+    /// Add an agent, or replace the one of its name
+    pub fn set_agent(&self, profile: AgentProfile) -> McpResult<()> {
+        validate_name(&profile.name)?;
+        let mut team = self.team.lock().unwrap();
+        match team.agents.iter_mut().find(|a| a.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => team.agents.push(profile),
+        }
+        self.save(&team)
+    }
+
+    /// Remove an agent and the rules routing to it; returns whether it was
+    /// in the team
+    pub fn remove_agent(&self, name: &str) -> McpResult<bool> {
+        let mut team = self.team.lock().unwrap();
+        let before = team.agents.len();
+        team.agents.retain(|a| a.name != name);
+        if team.agents.len() == before {
+            return Ok(false);
+        }
+        team.rules.retain(|r| r.agent != name);
+        self.save(&team)?;
+        Ok(true)
+    }
+
+    /// Route tasks matching a pattern to an agent, in place of an earlier
+    /// rule of the same pattern
+    pub fn add_rule(&self, pattern: &str, agent: &str) -> McpResult<()> {
+        RoutingRule::regex(pattern)?;
+        let mut team = self.team.lock().unwrap();
+        if team.agent(agent).is_none() {
+            return Err(McpError::InvalidRequest(format!("No agent named '{}'", agent)));
+        }
+        team.rules.retain(|r| r.pattern != pattern);
+        team.rules.push(RoutingRule {
+            pattern: pattern.to_string(),
+            agent: agent.to_string(),
+        });
+        self.save(&team)
+    }
+
+    /// Remove the rule of a pattern; returns whether there was one
+    pub fn remove_rule(&self, pattern: &str) -> McpResult<bool> {
+        let mut team = self.team.lock().unwrap();
+        let before = team.rules.len();
+        team.rules.retain(|r| r.pattern != pattern);
+        if team.rules.len() == before {
+            return Ok(false);
+        }
+        self.save(&team)?;
+        Ok(true)
+    }
+}
+
+static AGENT_TEAM: Lazy<Arc<AgentTeamStore>> = Lazy::new(|| Arc::new(AgentTeamStore::at(data_path("agents.json"))));
+
+/// Get the global agent team
+pub fn get_agent_team() -> Arc<AgentTeamStore> {
+    AGENT_TEAM.clone()
+}
+
+/// The tools an agent may call, out of all of them
+struct AllowedTools<'a> {
+    inner: &'a dyn ToolExecutor,
+    allowed: &'a [String],
+}
+
+impl<'a> AllowedTools<'a> {
+    fn allows(&self, name: &str) -> bool {
+        self.allowed.is_empty() || self.allowed.iter().any(|a| a == name)
+    }
+}
+
+#[async_trait]
+impl<'a> ToolExecutor for AllowedTools<'a> {
+    fn tools(&self) -> Vec<Tool> {
+        self.inner
+            .tools()
+            .into_iter()
+            .filter(|t| self.allows(&t.name))
+            .collect()
+    }
+
+    async fn execute(&self, conversation_id: &str, call: &ToolCall) -> Option<ToolResult> {
+        if !self.allows(&call.name) {
+            return None;
+        }
+        self.inner.execute(conversation_id, call).await
+    }
+}
+
+/// Runs a task through a team, one agent at a time
+pub struct TeamRunner {
+    team: AgentTeam,
+}
+
+impl TeamRunner {
+    /// Create a runner for a team
+    pub fn new(team: AgentTeam) -> Self {
+        Self { team }
+    }
+
+    /// Work on a task in a conversation, starting with the agent the routing
+    /// rules pick and following handoffs until an agent stops without one.
+    /// Returns the transcript of every run, in order.
+    pub async fn run(
+        &self,
+        chat: &ChatService,
+        conversation_id: &str,
+        task: &str,
+        tools: &dyn ToolExecutor,
+    ) -> McpResult<Vec<AgentTranscript>> {
+        let (mut agent, rule) = self
+            .team
+            .route(task)
+            .ok_or_else(|| McpError::InvalidRequest("The team has no agents".to_string()))?;
+        let mut task = task.to_string();
+        let mut runs: Vec<AgentTranscript> = Vec::new();
+
+        loop {
+            if let Some(prompt) = &agent.system_prompt {
+                chat.set_system_message(conversation_id, prompt).await?;
+            }
+            // The last handoff allowed leaves the agent to finish on its own
+            let handoffs = if runs.len() as u32 >= self.team.max_handoffs {
+                Vec::new()
+            } else {
+                self.team
+                    .agents
+                    .iter()
+                    .filter(|a| a.name != agent.name)
+                    .map(|a| a.name.clone())
+                    .collect()
+            };
+
+            let mut runner = AgentRunner::new(agent.budget)
+                .named(&agent.name)
+                .with_handoffs(handoffs);
+            match runs.last() {
+                Some(parent) => runner = runner.after(&parent.run_id),
+                None => {
+                    if let Some(rule) = rule {
+                        runner = runner.routed_by(&rule.pattern);
+                    }
+                }
+            }
+            let allowed = AllowedTools {
+                inner: tools,
+                allowed: &agent.tools,
+            };
+            let transcript = runner.run(chat, conversation_id, &task, &allowed).await?;
+            let handoff = transcript.handoff.clone();
+            runs.push(transcript);
+
+            let Some(handoff) = handoff else {
+                return Ok(runs);
+            };
+            info!(
+                "Agent {} handed a task to {} in {}",
+                agent.name, handoff.agent, conversation_id
+            );
+            agent = self
+                .team
+                .agent(&handoff.agent)
+                .ok_or_else(|| McpError::InvalidRequest(format!("No agent named '{}' in the team", handoff.agent)))?;
+            task = handoff.task;
+        }
+    }
+}
+
+/// A run in an execution graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphNode {
+    /// ID of the run
+    pub id: String,
+
+    /// Agent that did the run
+    pub agent: Option<String>,
+
+    /// Task of the run
+    pub task: String,
+
+    /// How the run ended
+    pub status: AgentStatus,
+
+    /// Replies of the model
+    pub replies: u32,
+
+    /// Tokens used, estimated
+    pub tokens: u64,
+
+    /// Tools called, in order
+    pub tools: Vec<String>,
+
+    /// Routing rule that picked the agent
+    pub routed_by: Option<String>,
+}
+
+/// A task handed from one run to another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    /// Run that handed the task over
+    pub from: String,
+
+    /// Run that picked it up
+    pub to: String,
+
+    /// The task handed over
+    pub task: String,
+}
+
+/// The agent runs of a conversation and the handoffs between them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionGraph {
+    /// Runs, oldest first
+    pub nodes: Vec<GraphNode>,
+
+    /// Handoffs
+    pub edges: Vec<GraphEdge>,
+}
+
+impl ExecutionGraph {
+    /// Graph of runs
+    pub fn from_runs(runs: &[AgentTranscript]) -> Self {
+        let nodes = runs
+            .iter()
+            .map(|run| GraphNode {
+                id: run.run_id.clone(),
+                agent: run.agent.clone(),
+                task: run.task.clone(),
+                status: run.status,
+                replies: run.replies(),
+                tokens: run.tokens,
+                tools: run
+                    .steps
+                    .iter()
+                    .filter_map(|step| match &step.kind {
+                        StepKind::ToolCall { call } => Some(call.name.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+                routed_by: run.routed_by.clone(),
+            })
+            .collect();
+        let edges = runs
+            .iter()
+            .filter_map(|run| {
+                let parent = run.parent.as_ref()?;
+                Some(GraphEdge {
+                    from: parent.clone(),
+                    to: run.run_id.clone(),
+                    task: run.task.clone(),
+                })
+            })
+            .collect();
+        Self { nodes, edges }
+    }
+
+    /// Graph of the agent runs kept in a conversation
+    pub fn of(conversation: &Conversation) -> Self {
+        Self::from_runs(&AgentTranscript::all(conversation))
+    }
+
+    /// The graph in Graphviz's DOT language
+    pub fn to_dot(&self) -> String {
+        let quote = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut dot = String::from("digraph agents {\n    rankdir=LR;\n    node [shape=box];\n");
+        for node in &self.nodes {
+            let mut label = format!(
+                "{}\\n{} · {} replies · {} tokens",
+                quote(node.agent.as_deref().unwrap_or("agent")),
+                node.status,
+                node.replies,
+                node.tokens
+            );
+            if let Some(pattern) = &node.routed_by {
+                label.push_str(&format!("\\nrouted by /{}/", quote(pattern)));
+            }
+            dot.push_str(&format!("    \"{}\" [label=\"{}\"];\n", node.id, label));
+        }
+        for edge in &self.edges {
+            let task: String = edge.task.chars().take(40).collect();
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                edge.from,
+                edge.to,
+                quote(&task)
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -0,0 +1,342 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use super::vault::secret_store;
+use crate::config::{get_settings, AuthMethod, OAuthSettings};
+use crate::error::{McpError, McpResult};
+use crate::protocol::inspector;
+
+/// Secret store key of the OAuth tokens
+const TOKENS_SECRET: &str = "oauth_tokens";
+
+/// Grant type of the device authorization grant (RFC 8628)
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Tokens are refreshed this long before they expire
+const REFRESH_MARGIN_SECS: i64 = 120;
+
+/// Tokens issued by the gateway's identity provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthTokens {
+    /// Bearer token sent with requests
+    pub access_token: String,
+
+    /// Token used to get a new access token
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+
+    /// When the access token expires; `None` if the provider didn't say
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// Granted scopes
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+impl OAuthTokens {
+    /// Whether the access token expires within the refresh margin
+    pub fn needs_refresh(&self) -> bool {
+        self.expires_at
+            .is_some_and(|at| at - Utc::now() < ChronoDuration::seconds(REFRESH_MARGIN_SECS))
+    }
+}
+
+/// What the user has to do to approve a device login
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAuthorization {
+    /// Code the client polls with
+    pub device_code: String,
+
+    /// Code the user enters
+    pub user_code: String,
+
+    /// Page where the user enters the code
+    pub verification_uri: String,
+
+    /// Page with the code already filled in
+    #[serde(default)]
+    pub verification_uri_complete: Option<String>,
+
+    /// Seconds until the codes expire
+    pub expires_in: u64,
+
+    /// Seconds to wait between polls
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+/// OAuth login state, for status displays
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthStatus {
+    /// Whether requests use OAuth rather than the API key
+    pub enabled: bool,
+
+    /// Whether tokens are stored
+    pub logged_in: bool,
+
+    /// When the access token expires
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// Granted scopes
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<i64>,
+    #[serde(default)]
+    scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+impl ErrorResponse {
+    fn message(&self) -> String {
+        match &self.error_description {
+            Some(description) => format!("{} ({})", description, self.error),
+            None => self.error.clone(),
+        }
+    }
+}
+
+/// Serializes refreshes so concurrent requests don't spend the refresh token twice
+static REFRESH_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn oauth_settings() -> McpResult<(OAuthSettings, String, String)> {
+    let oauth = get_settings().lock().unwrap().auth.oauth.clone();
+    let missing = |what: &str| McpError::Config(format!("OAuth login needs a {}; configure the gateway first", what));
+    let token_url = oauth.token_url.clone().ok_or_else(|| missing("token URL"))?;
+    let client_id = oauth.client_id.clone().ok_or_else(|| missing("client ID"))?;
+    Ok((oauth, token_url, client_id))
+}
+
+fn load_tokens() -> McpResult<Option<OAuthTokens>> {
+    match secret_store().get(TOKENS_SECRET)? {
+        Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+        None => Ok(None),
+    }
+}
+
+fn store_tokens(response: TokenResponse, previous_refresh_token: Option<String>) -> McpResult<OAuthTokens> {
+    let tokens = OAuthTokens {
+        access_token: response.access_token,
+        // Providers may keep the refresh token and not send it again
+        refresh_token: response.refresh_token.or(previous_refresh_token),
+        expires_at: response.expires_in.map(|secs| Utc::now() + ChronoDuration::seconds(secs)),
+        scope: response.scope,
+    };
+    secret_store().set(TOKENS_SECRET, &serde_json::to_string(&tokens)?)?;
+    Ok(tokens)
+}
+
+async fn post_form(url: &str, form: &[(&str, &str)]) -> McpResult<Result<reqwest::Response, ErrorResponse>> {
+    let request = reqwest::Client::new()
+        .post(url)
+        .header("Accept", "application/json")
+        .form(form)
+        .timeout(Duration::from_secs(30));
+    let response = inspector::send(request)
+        .await
+        .map_err(|e| McpError::Connection(format!("Identity provider unreachable: {}", e)))?;
+    if response.status().is_success() {
+        return Ok(Ok(response));
+    }
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    serde_json::from_str::<ErrorResponse>(&body)
+        .map(Err)
+        .map_err(|_| McpError::Authentication(format!("Identity provider answered with {}", status)))
+}
+
+/// Start a device-code login; show the user code and verification page to
+/// the user, then call [`poll_device_login`]
+pub async fn start_device_login() -> McpResult<DeviceAuthorization> {
+    let (oauth, _, client_id) = oauth_settings()?;
+    let url = oauth.device_authorization_url.clone().ok_or_else(|| {
+        McpError::Config("OAuth login needs a device authorization URL; configure the gateway first".to_string())
+    })?;
+
+    let scope = oauth.scopes.join(" ");
+    let mut form = vec![("client_id", client_id.as_str())];
+    if !scope.is_empty() {
+        form.push(("scope", scope.as_str()));
+    }
+    if let Some(audience) = oauth.audience.as_deref() {
+        form.push(("audience", audience));
+    }
+
+    match post_form(&url, &form).await? {
+        Ok(response) => response
+            .json()
+            .await
+            .map_err(|e| McpError::Protocol(format!("Invalid device authorization response: {}", e))),
+        Err(error) => Err(McpError::Authentication(format!("Device login refused: {}", error.message()))),
+    }
+}
+
+/// Poll until the user approves the login, then store the tokens and switch
+/// requests to OAuth
+pub async fn poll_device_login(authorization: &DeviceAuthorization) -> McpResult<OAuthTokens> {
+    let (_, token_url, client_id) = oauth_settings()?;
+    let deadline = Utc::now() + ChronoDuration::seconds(authorization.expires_in as i64);
+    let mut interval = authorization.interval.max(1);
+
+    loop {
+        if Utc::now() > deadline {
+            return Err(McpError::Timeout(Duration::from_secs(authorization.expires_in)));
+        }
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+
+        let form = [
+            ("grant_type", DEVICE_CODE_GRANT),
+            ("device_code", authorization.device_code.as_str()),
+            ("client_id", client_id.as_str()),
+        ];
+        match post_form(&token_url, &form).await? {
+            Ok(response) => {
+                let response: TokenResponse = response
+                    .json()
+                    .await
+                    .map_err(|e| McpError::Protocol(format!("Invalid token response: {}", e)))?;
+                let tokens = store_tokens(response, None)?;
+
+                let settings = get_settings();
+                let mut settings = settings.lock().unwrap();
+                settings.auth.method = AuthMethod::OAuth;
+                settings.save()?;
+                crate::service::credentials::invalidate();
+
+                info!("OAuth device login completed");
+                return Ok(tokens);
+            }
+            Err(error) => match error.error.as_str() {
+                "authorization_pending" => debug!("Waiting for the user to approve the device login"),
+                "slow_down" => interval += 5,
+                "access_denied" => {
+                    return Err(McpError::Authentication("The login was denied".to_string()))
+                }
+                "expired_token" => {
+                    return Err(McpError::Authentication("The login code expired; start again".to_string()))
+                }
+                _ => return Err(McpError::Authentication(format!("Device login failed: {}", error.message()))),
+            },
+        }
+    }
+}
+
+/// Exchange the refresh token for a new access token
+pub async fn refresh_tokens() -> McpResult<OAuthTokens> {
+    let _guard = REFRESH_LOCK.lock().await;
+    let tokens = load_tokens()?.ok_or_else(|| McpError::Authentication("Not logged in".to_string()))?;
+    // Another request may have refreshed while this one waited
+    if !tokens.needs_refresh() {
+        return Ok(tokens);
+    }
+    let refresh_token = tokens
+        .refresh_token
+        .clone()
+        .ok_or_else(|| McpError::Authentication("The login expired; log in again".to_string()))?;
+    let (_, token_url, client_id) = oauth_settings()?;
+
+    let form = [
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token.as_str()),
+        ("client_id", client_id.as_str()),
+    ];
+    match post_form(&token_url, &form).await? {
+        Ok(response) => {
+            let response: TokenResponse = response
+                .json()
+                .await
+                .map_err(|e| McpError::Protocol(format!("Invalid token response: {}", e)))?;
+            debug!("Refreshed the OAuth access token");
+            store_tokens(response, Some(refresh_token))
+        }
+        Err(error) if error.error == "invalid_grant" => {
+            warn!("OAuth refresh token was rejected; a new login is needed");
+            secret_store().delete(TOKENS_SECRET)?;
+            Err(McpError::Authentication("The login expired; log in again".to_string()))
+        }
+        Err(error) => Err(McpError::Authentication(format!("Token refresh failed: {}", error.message()))),
+    }
+}
+
+/// A valid access token, refreshed first if it is about to expire
+pub async fn access_token() -> McpResult<String> {
+    let tokens = load_tokens()?
+        .ok_or_else(|| McpError::Authentication("Not logged in; run the OAuth login".to_string()))?;
+    if tokens.needs_refresh() {
+        return refresh_tokens().await.map(|t| t.access_token);
+    }
+    Ok(tokens.access_token)
+}
+
+/// Forget the tokens and go back to the API key
+pub fn logout() -> McpResult<()> {
+    secret_store().delete(TOKENS_SECRET)?;
+
+    let settings = get_settings();
+    let mut settings = settings.lock().unwrap();
+    settings.auth.method = AuthMethod::ApiKey;
+    settings.save()?;
+    crate::service::credentials::invalidate();
+    info!("Logged out of OAuth");
+    Ok(())
+}
+
+/// Current OAuth login state
+pub fn oauth_status() -> OAuthStatus {
+    let enabled = get_settings().lock().unwrap().auth.method == AuthMethod::OAuth;
+    let tokens = load_tokens().unwrap_or_else(|e| {
+        warn!("Failed to read OAuth tokens: {}", e);
+        None
+    });
+    OAuthStatus {
+        enabled,
+        logged_in: tokens.is_some(),
+        expires_at: tokens.as_ref().and_then(|t| t.expires_at),
+        scope: tokens.and_then(|t| t.scope),
+    }
+}
+
+/// Refresh the access token ahead of expiry while OAuth is in use, so
+/// requests don't wait for a refresh
+pub fn spawn_token_refresher(interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if get_settings().lock().unwrap().auth.method != AuthMethod::OAuth {
+                continue;
+            }
+            match load_tokens() {
+                Ok(Some(tokens)) if tokens.needs_refresh() => {
+                    if let Err(e) = refresh_tokens().await {
+                        warn!("Background token refresh failed: {}", e);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to read OAuth tokens: {}", e),
+            }
+        }
+    })
+}
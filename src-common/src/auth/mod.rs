@@ -0,0 +1,60 @@
+mod device;
+mod vault;
+
+use crate::config::{get_settings, AuthMethod};
+use crate::error::{McpError, McpResult};
+
+pub use device::{
+    access_token, logout, oauth_status, poll_device_login, refresh_tokens, spawn_token_refresher,
+    start_device_login, DeviceAuthorization, OAuthStatus, OAuthTokens,
+};
+pub use vault::{secret_store, set_secret_store, FileSecretStore, SecretStore};
+
+/// Credentials attached to an API request
+#[derive(Clone)]
+pub enum Authorization {
+    /// Anthropic API key, sent as `x-api-key`
+    ApiKey(String),
+    /// OAuth access token for a gateway, sent as `Authorization: Bearer`
+    Bearer(String),
+}
+
+impl std::fmt::Debug for Authorization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the secret itself
+        match self {
+            Authorization::ApiKey(_) => write!(f, "ApiKey(..)"),
+            Authorization::Bearer(_) => write!(f, "Bearer(..)"),
+        }
+    }
+}
+
+impl Authorization {
+    /// Header carrying the credentials
+    pub fn header(&self) -> (String, String) {
+        match self {
+            Authorization::ApiKey(key) => ("x-api-key".to_string(), key.trim().to_string()),
+            Authorization::Bearer(token) => ("Authorization".to_string(), format!("Bearer {}", token)),
+        }
+    }
+
+    /// Attach the credentials to an HTTP request
+    pub fn apply(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let (name, value) = self.header();
+        request.header(name, value)
+    }
+}
+
+/// Credentials for the configured authentication method; OAuth tokens are
+/// refreshed first if they are about to expire
+pub async fn authorization() -> McpResult<Authorization> {
+    let settings = get_settings().lock().unwrap().clone();
+    match settings.auth.method {
+        AuthMethod::ApiKey => settings
+            .get_api_key()?
+            .filter(|key| !key.trim().is_empty())
+            .map(Authorization::ApiKey)
+            .ok_or_else(|| McpError::Authentication("No API key configured".to_string())),
+        AuthMethod::OAuth => access_token().await.map(Authorization::Bearer),
+    }
+}
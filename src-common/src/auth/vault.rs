@@ -0,0 +1,72 @@
+use once_cell::sync::Lazy;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use crate::config::config_path;
+use crate::error::{McpError, McpResult};
+use crate::utils::security;
+
+/// Storage for secrets such as OAuth tokens
+pub trait SecretStore: Send + Sync {
+    /// Read a secret; `None` when it isn't stored
+    fn get(&self, key: &str) -> McpResult<Option<String>>;
+
+    /// Store a secret, replacing any previous value
+    fn set(&self, key: &str, value: &str) -> McpResult<()>;
+
+    /// Remove a secret; removing a missing secret is not an error
+    fn delete(&self, key: &str) -> McpResult<()>;
+}
+
+/// Secrets encrypted in files in the configuration directory, like the API
+/// key. Used unless the application registers the platform vault.
+pub struct FileSecretStore;
+
+impl FileSecretStore {
+    fn path(key: &str) -> McpResult<PathBuf> {
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(McpError::InvalidRequest(format!("Invalid secret name '{}'", key)));
+        }
+        Ok(config_path(&format!("secret_{}.enc", key)))
+    }
+}
+
+impl SecretStore for FileSecretStore {
+    fn get(&self, key: &str) -> McpResult<Option<String>> {
+        let path = Self::path(key)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let value = security::decrypt(&fs::read(path)?)
+            .map_err(|e| McpError::Config(format!("Failed to decrypt secret '{}': {}", key, e)))?;
+        Ok(Some(value))
+    }
+
+    fn set(&self, key: &str, value: &str) -> McpResult<()> {
+        let encrypted = security::encrypt(value)
+            .map_err(|e| McpError::Config(format!("Failed to encrypt secret '{}': {}", key, e)))?;
+        fs::write(Self::path(key)?, encrypted)?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> McpResult<()> {
+        let path = Self::path(key)?;
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+static SECRET_STORE: Lazy<RwLock<Arc<dyn SecretStore>>> = Lazy::new(|| RwLock::new(Arc::new(FileSecretStore)));
+
+/// Use another secret store, e.g. the platform keychain of the desktop app
+pub fn set_secret_store(store: Arc<dyn SecretStore>) {
+    *SECRET_STORE.write().unwrap() = store;
+}
+
+/// Get the secret store in use
+pub fn secret_store() -> Arc<dyn SecretStore> {
+    SECRET_STORE.read().unwrap().clone()
+}
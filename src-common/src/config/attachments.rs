@@ -0,0 +1,384 @@
+use chrono::{DateTime, Utc};
+use image::imageops::FilterType;
+use image::ImageOutputFormat;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use crate::error::{McpError, McpResult};
+
+/// URL scheme used by message parts that point at an attachment
+pub const ATTACHMENT_URL_SCHEME: &str = "attachment://";
+
+/// Longest side of generated thumbnails, in pixels
+const THUMBNAIL_SIZE: u32 = 256;
+
+/// JPEG quality of downscaled images
+const DOWNSCALE_QUALITY: u8 = 85;
+
+/// Characters kept for text previews
+const TEXT_PREVIEW_CHARS: usize = 2000;
+
+/// A file stored for a conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    /// Attachment ID
+    pub id: String,
+
+    /// Conversation the file belongs to
+    pub conversation_id: String,
+
+    /// Message referencing the file, once sent
+    #[serde(default)]
+    pub message_id: Option<String>,
+
+    /// Original file name
+    pub file_name: String,
+
+    /// Media type guessed from the file name
+    pub media_type: String,
+
+    /// Size in bytes
+    pub size: u64,
+
+    /// When the file was added
+    pub created_at: DateTime<Utc>,
+
+    /// Whether a thumbnail was generated
+    #[serde(default)]
+    pub has_thumbnail: bool,
+
+    /// Start of the file's text, for text files
+    #[serde(default)]
+    pub text_preview: Option<String>,
+}
+
+impl Attachment {
+    /// URL to use in an image part
+    pub fn url(&self) -> String {
+        format!("{}{}", ATTACHMENT_URL_SCHEME, self.id)
+    }
+
+    /// Whether the attachment is an image
+    pub fn is_image(&self) -> bool {
+        self.media_type.starts_with("image/")
+    }
+}
+
+/// Guess a media type from a file name
+pub fn media_type_for(file_name: &str) -> &'static str {
+    let extension = Path::new(file_name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "txt" | "log" | "rs" | "py" | "js" | "ts" | "toml" | "yaml" | "yml" | "xml" | "sh" | "c" | "h" | "cpp"
+        | "go" | "java" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Stores files referenced by messages under the data directory, with
+/// thumbnails for images and text previews for text files
+pub struct AttachmentStore {
+    dir: PathBuf,
+    index: Mutex<HashMap<String, Attachment>>,
+}
+
+impl AttachmentStore {
+    /// Open the store in a directory
+    pub fn new(dir: PathBuf) -> Self {
+        if let Err(e) = fs::create_dir_all(dir.join("files")).and_then(|_| fs::create_dir_all(dir.join("thumbnails"))) {
+            warn!("Failed to create attachment directories: {}", e);
+        }
+
+        let index = fs::read_to_string(dir.join("index.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            dir,
+            index: Mutex::new(index),
+        }
+    }
+
+    fn save_index(&self, index: &HashMap<String, Attachment>) -> McpResult<()> {
+        fs::write(self.dir.join("index.json"), serde_json::to_string_pretty(index)?)?;
+        Ok(())
+    }
+
+    /// Path of an attachment's file
+    pub fn file_path(&self, id: &str) -> PathBuf {
+        self.dir.join("files").join(id)
+    }
+
+    /// Path of an attachment's thumbnail (PNG)
+    pub fn thumbnail_path(&self, id: &str) -> PathBuf {
+        self.dir.join("thumbnails").join(format!("{}.png", id))
+    }
+
+    /// Copy a file into the store for a conversation
+    pub fn add_file(&self, conversation_id: &str, source: &Path) -> McpResult<Attachment> {
+        let file_name = source
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .ok_or_else(|| McpError::InvalidRequest(format!("{} is not a file", source.display())))?;
+        let content = fs::read(source)?;
+        self.add_bytes(conversation_id, &file_name, &content)
+    }
+
+    /// Store file content for a conversation
+    pub fn add_bytes(&self, conversation_id: &str, file_name: &str, content: &[u8]) -> McpResult<Attachment> {
+        let id = uuid::Uuid::new_v4().to_string();
+        fs::write(self.file_path(&id), content)?;
+
+        let mut attachment = Attachment {
+            id: id.clone(),
+            conversation_id: conversation_id.to_string(),
+            message_id: None,
+            file_name: file_name.to_string(),
+            media_type: media_type_for(file_name).to_string(),
+            size: content.len() as u64,
+            created_at: Utc::now(),
+            has_thumbnail: false,
+            text_preview: None,
+        };
+
+        if attachment.is_image() {
+            match self.write_thumbnail(&id, content) {
+                Ok(()) => attachment.has_thumbnail = true,
+                Err(e) => warn!("Failed to create thumbnail for {}: {}", file_name, e),
+            }
+        } else if attachment.media_type.starts_with("text/") || attachment.media_type == "application/json" {
+            let text = String::from_utf8_lossy(content);
+            attachment.text_preview = Some(text.chars().take(TEXT_PREVIEW_CHARS).collect());
+        }
+
+        let mut index = self.index.lock().unwrap();
+        index.insert(id, attachment.clone());
+        self.save_index(&index)?;
+
+        debug!("Stored attachment {} ({} bytes)", attachment.file_name, attachment.size);
+        Ok(attachment)
+    }
+
+    fn write_thumbnail(&self, id: &str, content: &[u8]) -> Result<(), String> {
+        let image = image::load_from_memory(content).map_err(|e| e.to_string())?;
+        image
+            .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+            .save_with_format(self.thumbnail_path(id), image::ImageFormat::Png)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Store a smaller JPEG copy of an image attachment, at most
+    /// `max_dimension` pixels on its longest side and `max_bytes` large
+    pub fn downscale(&self, id: &str, max_dimension: u32, max_bytes: u64) -> McpResult<Attachment> {
+        let attachment = self
+            .get(id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("Attachment {} not found", id)))?;
+        if !attachment.is_image() {
+            return Err(McpError::InvalidRequest(format!("{} is not an image", attachment.file_name)));
+        }
+        let image = image::load_from_memory(&self.read(id)?)
+            .map_err(|e| McpError::InvalidRequest(format!("Can't read {}: {}", attachment.file_name, e)))?;
+
+        // Shrink by a quarter at a time until the file is small enough
+        let mut side = max_dimension.min(image.width().max(image.height()));
+        let content = loop {
+            let resized = image::DynamicImage::ImageRgb8(image.resize(side, side, FilterType::Lanczos3).to_rgb8());
+            let mut content = Vec::new();
+            resized
+                .write_to(&mut Cursor::new(&mut content), ImageOutputFormat::Jpeg(DOWNSCALE_QUALITY))
+                .map_err(|e| McpError::InvalidRequest(format!("Can't encode {}: {}", attachment.file_name, e)))?;
+            if content.len() as u64 <= max_bytes {
+                break content;
+            }
+            if side <= THUMBNAIL_SIZE {
+                return Err(McpError::InvalidRequest(format!(
+                    "{} can't be made smaller than {} bytes",
+                    attachment.file_name, max_bytes
+                )));
+            }
+            side = side * 3 / 4;
+        };
+
+        let stem = Path::new(&attachment.file_name)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| attachment.id.clone());
+        let smaller = self.add_bytes(&attachment.conversation_id, &format!("{}-small.jpg", stem), &content)?;
+        debug!(
+            "Downscaled {} from {} to {} bytes",
+            attachment.file_name, attachment.size, smaller.size
+        );
+        Ok(smaller)
+    }
+
+    /// Link an attachment to the message that sent it
+    pub fn attach_to_message(&self, id: &str, message_id: &str) -> McpResult<()> {
+        let mut index = self.index.lock().unwrap();
+        let attachment = index
+            .get_mut(id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("Attachment {} not found", id)))?;
+        attachment.message_id = Some(message_id.to_string());
+        self.save_index(&index)
+    }
+
+    /// Get an attachment
+    pub fn get(&self, id: &str) -> Option<Attachment> {
+        self.index.lock().unwrap().get(id).cloned()
+    }
+
+    /// Resolve an `attachment://` URL
+    pub fn resolve_url(&self, url: &str) -> Option<Attachment> {
+        url.strip_prefix(ATTACHMENT_URL_SCHEME).and_then(|id| self.get(id))
+    }
+
+    /// Read an attachment's content
+    pub fn read(&self, id: &str) -> McpResult<Vec<u8>> {
+        if self.get(id).is_none() {
+            return Err(McpError::InvalidRequest(format!("Attachment {} not found", id)));
+        }
+        Ok(fs::read(self.file_path(id))?)
+    }
+
+    /// Read an attachment's thumbnail, if it has one
+    pub fn thumbnail(&self, id: &str) -> Option<Vec<u8>> {
+        self.get(id)
+            .filter(|a| a.has_thumbnail)
+            .and_then(|_| fs::read(self.thumbnail_path(id)).ok())
+    }
+
+    /// Attachments of a conversation, or all of them, newest first
+    pub fn list(&self, conversation_id: Option<&str>) -> Vec<Attachment> {
+        let mut attachments: Vec<Attachment> = self
+            .index
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|a| conversation_id.is_none_or(|id| a.conversation_id == id))
+            .cloned()
+            .collect();
+        attachments.sort_by_key(|a| std::cmp::Reverse(a.created_at));
+        attachments
+    }
+
+    /// Total attachment size per conversation
+    pub fn usage(&self) -> HashMap<String, u64> {
+        let mut usage = HashMap::new();
+        for attachment in self.index.lock().unwrap().values() {
+            *usage.entry(attachment.conversation_id.clone()).or_insert(0) += attachment.size;
+        }
+        usage
+    }
+
+    /// Open an attachment with the system's default application
+    pub fn open(&self, id: &str) -> McpResult<()> {
+        if self.get(id).is_none() {
+            return Err(McpError::InvalidRequest(format!("Attachment {} not found", id)));
+        }
+        let path = self.file_path(id);
+
+        #[cfg(target_os = "windows")]
+        let result = Command::new("cmd").args(["/C", "start", ""]).arg(&path).spawn();
+        #[cfg(target_os = "macos")]
+        let result = Command::new("open").arg(&path).spawn();
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        let result = Command::new("xdg-open").arg(&path).spawn();
+
+        result.map(|_| ()).map_err(McpError::from)
+    }
+
+    fn remove_files(&self, id: &str) {
+        for path in [self.file_path(id), self.thumbnail_path(id)] {
+            if path.exists() {
+                if let Err(e) = fs::remove_file(&path) {
+                    warn!("Failed to remove {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+
+    /// Delete an attachment
+    pub fn delete(&self, id: &str) -> McpResult<()> {
+        let mut index = self.index.lock().unwrap();
+        if index.remove(id).is_none() {
+            return Err(McpError::InvalidRequest(format!("Attachment {} not found", id)));
+        }
+        self.remove_files(id);
+        self.save_index(&index)
+    }
+
+    /// Delete every attachment of a conversation; returns how many were removed
+    pub fn delete_for_conversation(&self, conversation_id: &str) -> McpResult<usize> {
+        let mut index = self.index.lock().unwrap();
+        let ids: Vec<String> = index
+            .values()
+            .filter(|a| a.conversation_id == conversation_id)
+            .map(|a| a.id.clone())
+            .collect();
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        for id in &ids {
+            index.remove(id);
+            self.remove_files(id);
+        }
+        self.save_index(&index)?;
+        Ok(ids.len())
+    }
+
+    /// Remove attachments of conversations that no longer exist and files the
+    /// index doesn't know. Returns the number removed and the bytes freed.
+    pub fn cleanup_orphans(&self, conversations: &HashSet<String>) -> McpResult<(usize, u64)> {
+        let mut index = self.index.lock().unwrap();
+        let mut removed = 0;
+        let mut freed = 0;
+
+        let orphans: Vec<Attachment> = index
+            .values()
+            .filter(|a| !conversations.contains(&a.conversation_id) || !self.file_path(&a.id).exists())
+            .cloned()
+            .collect();
+        for attachment in orphans {
+            index.remove(&attachment.id);
+            self.remove_files(&attachment.id);
+            removed += 1;
+            freed += attachment.size;
+        }
+
+        for dir in ["files", "thumbnails"] {
+            for entry in fs::read_dir(self.dir.join(dir))?.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let id = name.trim_end_matches(".png");
+                if !index.contains_key(id) {
+                    freed += entry.metadata().map(|m| m.len()).unwrap_or(0);
+                    let _ = fs::remove_file(entry.path());
+                    if dir == "files" {
+                        removed += 1;
+                    }
+                }
+            }
+        }
+
+        self.save_index(&index)?;
+        Ok((removed, freed))
+    }
+}
@@ -0,0 +1,307 @@
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::{McpError, McpResult};
+
+/// Message part fields at least this long are moved out of conversation files
+pub const BLOB_THRESHOLD: usize = 16 * 1024;
+
+/// Key marking a field replaced by a blob reference
+const BLOB_REF_KEY: &str = "$blob";
+
+/// zstd compression level for stored blobs
+const COMPRESSION_LEVEL: i32 = 9;
+
+/// Index entry for a stored blob
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BlobEntry {
+    /// Conversations referencing the blob
+    refs: usize,
+
+    /// Uncompressed size in bytes
+    size: u64,
+
+    /// Size on disk in bytes
+    stored_size: u64,
+}
+
+/// Blob storage statistics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlobStats {
+    /// Stored blobs
+    pub blobs: usize,
+
+    /// Uncompressed size of all blobs
+    pub size: u64,
+
+    /// Size of all blobs on disk
+    pub stored_size: u64,
+}
+
+/// Content-addressed, zstd-compressed storage for large message parts.
+///
+/// Blobs are keyed by the SHA-256 of their content, so a context pasted into
+/// many conversations is stored once. Each blob counts the conversations that
+/// reference it and is removed when the last one lets go.
+pub struct BlobStore {
+    dir: PathBuf,
+    index: Mutex<HashMap<String, BlobEntry>>,
+}
+
+impl BlobStore {
+    /// Open the store in a directory
+    pub fn new(dir: PathBuf) -> Self {
+        if !dir.exists() {
+            fs::create_dir_all(&dir).expect("Failed to create blob directory");
+        }
+
+        let index = fs::read_to_string(dir.join("index.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            dir,
+            index: Mutex::new(index),
+        }
+    }
+
+    fn blob_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(&hash[..2]).join(format!("{}.zst", &hash[2..]))
+    }
+
+    fn save_index(&self, index: &HashMap<String, BlobEntry>) -> McpResult<()> {
+        fs::write(self.dir.join("index.json"), serde_json::to_string(index)?)?;
+        Ok(())
+    }
+
+    /// Hash content the way blobs are keyed
+    pub fn hash(content: &[u8]) -> String {
+        ring::digest::digest(&ring::digest::SHA256, content)
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Write content without taking a reference; returns its hash
+    fn write(&self, content: &[u8]) -> McpResult<String> {
+        let hash = Self::hash(content);
+        let mut index = self.index.lock().unwrap();
+        if index.contains_key(&hash) {
+            return Ok(hash);
+        }
+
+        let path = self.blob_path(&hash);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let compressed = zstd::encode_all(content, COMPRESSION_LEVEL)?;
+        fs::write(&path, &compressed)?;
+
+        index.insert(
+            hash.clone(),
+            BlobEntry {
+                refs: 0,
+                size: content.len() as u64,
+                stored_size: compressed.len() as u64,
+            },
+        );
+        debug!("Stored blob {} ({} -> {} bytes)", hash, content.len(), compressed.len());
+        Ok(hash)
+    }
+
+    /// Read a blob's content
+    pub fn get(&self, hash: &str) -> McpResult<Vec<u8>> {
+        if hash.len() < 3 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(McpError::InvalidRequest(format!("Invalid blob hash '{}'", hash)));
+        }
+        let compressed = fs::read(self.blob_path(hash))?;
+        Ok(zstd::decode_all(compressed.as_slice())?)
+    }
+
+    /// Adjust reference counts after a conversation's references changed
+    fn update_refs(&self, added: &HashSet<String>, removed: &HashSet<String>) -> McpResult<()> {
+        if added.is_empty() && removed.is_empty() {
+            return Ok(());
+        }
+
+        let mut index = self.index.lock().unwrap();
+        for hash in added {
+            if let Some(entry) = index.get_mut(hash) {
+                entry.refs += 1;
+            }
+        }
+        for hash in removed {
+            let unreferenced = match index.get_mut(hash) {
+                Some(entry) => {
+                    entry.refs = entry.refs.saturating_sub(1);
+                    entry.refs == 0
+                }
+                None => false,
+            };
+            if unreferenced {
+                index.remove(hash);
+                if let Err(e) = fs::remove_file(self.blob_path(hash)) {
+                    warn!("Failed to remove blob {}: {}", hash, e);
+                }
+            }
+        }
+        self.save_index(&index)
+    }
+
+    /// Move large message part fields of a serialized conversation into blobs.
+    ///
+    /// Returns the hashes the conversation now references.
+    pub fn externalize(&self, conversation: &mut serde_json::Value) -> McpResult<HashSet<String>> {
+        let mut refs = HashSet::new();
+        let Some(messages) = conversation.get_mut("messages").and_then(|m| m.as_array_mut()) else {
+            return Ok(refs);
+        };
+
+        for message in messages {
+            let Some(parts) = message.pointer_mut("/content/parts").and_then(|p| p.as_array_mut()) else {
+                continue;
+            };
+            for part in parts {
+                let Some(fields) = part.get_mut("value").and_then(|v| v.as_object_mut()) else {
+                    continue;
+                };
+                for value in fields.values_mut() {
+                    match value {
+                        serde_json::Value::String(s) if s.len() >= BLOB_THRESHOLD => {
+                            let hash = self.write(s.as_bytes())?;
+                            *value = serde_json::json!({ BLOB_REF_KEY: hash });
+                            refs.insert(hash);
+                        }
+                        other => {
+                            if let Some(hash) = blob_ref(other) {
+                                refs.insert(hash.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(refs)
+    }
+
+    /// Replace blob references in a serialized conversation with their content
+    pub fn internalize(&self, conversation: &mut serde_json::Value) -> McpResult<()> {
+        let Some(messages) = conversation.get_mut("messages").and_then(|m| m.as_array_mut()) else {
+            return Ok(());
+        };
+
+        for message in messages {
+            let Some(parts) = message.pointer_mut("/content/parts").and_then(|p| p.as_array_mut()) else {
+                continue;
+            };
+            for part in parts {
+                let Some(fields) = part.get_mut("value").and_then(|v| v.as_object_mut()) else {
+                    continue;
+                };
+                for value in fields.values_mut() {
+                    if let Some(hash) = blob_ref(value).map(str::to_string) {
+                        let content = self.get(&hash)?;
+                        *value = serde_json::Value::String(String::from_utf8_lossy(&content).into_owned());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record that a conversation now references `current` instead of `previous`
+    pub fn replace_refs(&self, previous: &HashSet<String>, current: &HashSet<String>) -> McpResult<()> {
+        let added = current.difference(previous).cloned().collect();
+        let removed = previous.difference(current).cloned().collect();
+        self.update_refs(&added, &removed)
+    }
+
+    /// Reset reference counts to the given totals and delete unreferenced blobs.
+    ///
+    /// Returns the number of blobs removed.
+    pub fn reconcile(&self, refs: &HashMap<String, usize>) -> McpResult<usize> {
+        let mut index = self.index.lock().unwrap();
+        let mut removed = 0;
+
+        index.retain(|hash, entry| match refs.get(hash) {
+            Some(count) => {
+                entry.refs = *count;
+                true
+            }
+            None => {
+                removed += 1;
+                if let Err(e) = fs::remove_file(self.blob_path(hash)) {
+                    warn!("Failed to remove blob {}: {}", hash, e);
+                }
+                false
+            }
+        });
+
+        // Files the index lost track of
+        for shard in fs::read_dir(&self.dir)?.flatten() {
+            if !shard.path().is_dir() {
+                continue;
+            }
+            let prefix = shard.file_name().to_string_lossy().into_owned();
+            for file in fs::read_dir(shard.path())?.flatten() {
+                let name = file.file_name().to_string_lossy().into_owned();
+                let hash = format!("{}{}", prefix, name.trim_end_matches(".zst"));
+                if !index.contains_key(&hash) {
+                    removed += 1;
+                    let _ = fs::remove_file(file.path());
+                }
+            }
+        }
+
+        self.save_index(&index)?;
+        Ok(removed)
+    }
+
+    /// Current statistics
+    pub fn stats(&self) -> BlobStats {
+        let index = self.index.lock().unwrap();
+        BlobStats {
+            blobs: index.len(),
+            size: index.values().map(|e| e.size).sum(),
+            stored_size: index.values().map(|e| e.stored_size).sum(),
+        }
+    }
+}
+
+/// The hash of a blob reference value, if it is one
+fn blob_ref(value: &serde_json::Value) -> Option<&str> {
+    let object = value.as_object()?;
+    if object.len() != 1 {
+        return None;
+    }
+    object.get(BLOB_REF_KEY)?.as_str()
+}
+
+/// Hashes referenced by a serialized conversation
+pub fn referenced_blobs(conversation: &serde_json::Value) -> HashSet<String> {
+    let mut refs = HashSet::new();
+    let Some(messages) = conversation.get("messages").and_then(|m| m.as_array()) else {
+        return refs;
+    };
+
+    for message in messages {
+        let Some(parts) = message.pointer("/content/parts").and_then(|p| p.as_array()) else {
+            continue;
+        };
+        for part in parts {
+            if let Some(fields) = part.get("value").and_then(|v| v.as_object()) {
+                refs.extend(fields.values().filter_map(blob_ref).map(str::to_string));
+            }
+        }
+    }
+
+    refs
+}
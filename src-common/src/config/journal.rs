@@ -0,0 +1,263 @@
+use chrono::{DateTime, Duration, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::error::McpResult;
+use crate::models::Conversation;
+use crate::utils::clock;
+use super::get_settings;
+
+/// Kind of operation recorded in the journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    /// A conversation was deleted
+    DeleteConversation,
+    /// Conversations were archived or unarchived
+    ArchiveConversations,
+    /// A message was edited
+    EditMessage,
+}
+
+/// State of one conversation before or after an operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Conversation ID
+    pub conversation_id: String,
+
+    /// The conversation, or `None` when it didn't exist
+    pub conversation: Option<Conversation>,
+}
+
+/// An undoable operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Entry ID
+    pub id: String,
+
+    /// What was done
+    pub kind: OperationKind,
+
+    /// Human-readable summary, e.g. "Delete 'Trip planning'"
+    pub description: String,
+
+    /// When the operation ran
+    pub created_at: DateTime<Utc>,
+
+    /// When the operation was undone, while it is
+    #[serde(default)]
+    pub undone_at: Option<DateTime<Utc>>,
+
+    /// Conversations before the operation
+    pub before: Vec<Snapshot>,
+
+    /// Conversations after the operation
+    pub after: Vec<Snapshot>,
+}
+
+impl JournalEntry {
+    /// Whether the entry has been undone (and not redone)
+    pub fn is_undone(&self) -> bool {
+        self.undone_at.is_some()
+    }
+}
+
+/// How long operations can be undone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoSettings {
+    /// Seconds an operation stays undoable
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+
+    /// Most operations kept
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_window_secs() -> u64 {
+    30 * 60
+}
+
+fn default_max_entries() -> usize {
+    50
+}
+
+impl Default for UndoSettings {
+    fn default() -> Self {
+        Self {
+            window_secs: default_window_secs(),
+            max_entries: default_max_entries(),
+        }
+    }
+}
+
+/// Journal of destructive operations, one file per entry, so they can be
+/// undone and redone within the configured window
+pub struct Journal {
+    dir: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl Journal {
+    /// Open the journal in a directory
+    pub fn new(dir: PathBuf) -> Self {
+        if let Err(e) = fs::create_dir_all(&dir) {
+            warn!("Failed to create journal directory: {}", e);
+        }
+        Self {
+            dir,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn entry_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn write(&self, entry: &JournalEntry) -> McpResult<()> {
+        fs::write(self.entry_path(&entry.id), serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    /// Every entry, oldest first
+    fn read_all(&self) -> Vec<JournalEntry> {
+        let mut entries: Vec<JournalEntry> = fs::read_dir(&self.dir)
+            .map(|dir| {
+                dir.flatten()
+                    .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+                    .filter_map(|e| fs::read_to_string(e.path()).ok())
+                    .filter_map(|content| serde_json::from_str(&content).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        entries.sort_by_key(|a| a.created_at);
+        entries
+    }
+
+    fn remove(&self, entry: &JournalEntry) {
+        if let Err(e) = fs::remove_file(self.entry_path(&entry.id)) {
+            warn!("Failed to remove journal entry {}: {}", entry.id, e);
+        }
+    }
+
+    /// Remove entries older than the undo window or beyond the entry limit.
+    /// Returns how many were removed.
+    pub fn gc(&self) -> usize {
+        let _guard = self.lock.lock().unwrap();
+        self.gc_locked()
+    }
+
+    fn gc_locked(&self) -> usize {
+        let settings = get_settings().lock().unwrap().undo.clone();
+        let cutoff = clock::now() - Duration::seconds(settings.window_secs as i64);
+        let entries = self.read_all();
+        let excess = entries.len().saturating_sub(settings.max_entries);
+
+        let mut removed = 0;
+        for (i, entry) in entries.iter().enumerate() {
+            if i < excess || entry.created_at < cutoff {
+                self.remove(entry);
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            debug!("Expired {} journal entries", removed);
+        }
+        removed
+    }
+
+    /// Record an operation. Operations undone before it can no longer be redone.
+    pub fn record(
+        &self,
+        kind: OperationKind,
+        description: &str,
+        before: Vec<Snapshot>,
+        after: Vec<Snapshot>,
+    ) -> McpResult<JournalEntry> {
+        let _guard = self.lock.lock().unwrap();
+        for entry in self.read_all().iter().filter(|e| e.is_undone()) {
+            self.remove(entry);
+        }
+
+        let entry = JournalEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            description: description.to_string(),
+            created_at: clock::now(),
+            undone_at: None,
+            before,
+            after,
+        };
+        self.write(&entry)?;
+        self.gc_locked();
+
+        Ok(entry)
+    }
+
+    /// Undoable and redoable entries, newest first
+    pub fn list(&self) -> Vec<JournalEntry> {
+        let _guard = self.lock.lock().unwrap();
+        self.gc_locked();
+        let mut entries = self.read_all();
+        entries.reverse();
+        entries
+    }
+
+    /// Undo the latest operation by handing its `before` snapshots to `restore`.
+    /// Returns `None` when there is nothing to undo.
+    pub fn undo(&self, restore: impl Fn(&Snapshot) -> McpResult<()>) -> McpResult<Option<JournalEntry>> {
+        let _guard = self.lock.lock().unwrap();
+        self.gc_locked();
+        let Some(mut entry) = self.read_all().into_iter().rev().find(|e| !e.is_undone()) else {
+            return Ok(None);
+        };
+
+        for snapshot in &entry.before {
+            restore(snapshot)?;
+        }
+        entry.undone_at = Some(clock::now());
+        self.write(&entry)?;
+
+        Ok(Some(entry))
+    }
+
+    /// Redo the most recently undone operation by handing its `after`
+    /// snapshots to `restore`. Returns `None` when there is nothing to redo.
+    pub fn redo(&self, restore: impl Fn(&Snapshot) -> McpResult<()>) -> McpResult<Option<JournalEntry>> {
+        let _guard = self.lock.lock().unwrap();
+        self.gc_locked();
+        let Some(mut entry) = self
+            .read_all()
+            .into_iter()
+            .filter(|e| e.is_undone())
+            .max_by_key(|e| e.undone_at)
+        else {
+            return Ok(None);
+        };
+
+        for snapshot in &entry.after {
+            restore(snapshot)?;
+        }
+        entry.undone_at = None;
+        self.write(&entry)?;
+
+        Ok(Some(entry))
+    }
+
+    /// Drop every entry touching a conversation, e.g. once it is purged
+    pub fn forget(&self, conversation_id: &str) {
+        let _guard = self.lock.lock().unwrap();
+        let touching = self.read_all().into_iter().filter(|e| {
+            e.before
+                .iter()
+                .chain(e.after.iter())
+                .any(|s| s.conversation_id == conversation_id)
+        });
+        for entry in touching {
+            self.remove(&entry);
+        }
+    }
+}
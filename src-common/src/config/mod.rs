@@ -1,3 +1,6 @@
+mod attachments;
+mod blobs;
+mod journal;
 mod settings;
 mod storage;
 
@@ -5,8 +8,18 @@ use once_cell::sync::OnceCell;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-pub use settings::Settings;
-pub use storage::StorageManager;
+use crate::platform::fs::{app_dir, AppDir};
+
+pub use settings::{
+    AccessibilitySettings, AuthMethod, AuthSettings, CostSettings, DebugSettings, DraftSettings, LinkSettings, LoggingSettings, OAuthSettings,
+    OfflineSettings, Settings, TeamSettings, TelemetryConsent, ToolSettings, TrashSettings, UserProfile,
+};
+pub use attachments::{media_type_for, Attachment, AttachmentStore, ATTACHMENT_URL_SCHEME};
+pub use blobs::{BlobStats, BlobStore, BLOB_THRESHOLD};
+pub use journal::{Journal, JournalEntry, OperationKind, Snapshot, UndoSettings};
+pub use storage::{
+    CompactReport, Draft, StorageManager, StorageUsage, TrashedConversation, UsageEntry, TRASHED_AT_METADATA_KEY,
+};
 
 /// Global settings instance
 static SETTINGS: OnceCell<Arc<Mutex<Settings>>> = OnceCell::new();
@@ -14,6 +27,12 @@ static SETTINGS: OnceCell<Arc<Mutex<Settings>>> = OnceCell::new();
 /// Global storage manager instance
 static STORAGE_MANAGER: OnceCell<Arc<StorageManager>> = OnceCell::new();
 
+/// Global attachment store instance
+static ATTACHMENT_STORE: OnceCell<Arc<AttachmentStore>> = OnceCell::new();
+
+/// Global undo journal instance
+static JOURNAL: OnceCell<Arc<Journal>> = OnceCell::new();
+
 /// Get the global settings instance
 pub fn get_settings() -> Arc<Mutex<Settings>> {
     SETTINGS.get_or_init(|| {
@@ -28,34 +47,28 @@ pub fn get_storage_manager() -> Arc<StorageManager> {
     }).clone()
 }
 
-/// Get the application config directory
+/// Get the global attachment store instance
+pub fn get_attachment_store() -> Arc<AttachmentStore> {
+    ATTACHMENT_STORE.get_or_init(|| {
+        Arc::new(AttachmentStore::new(data_path("attachments")))
+    }).clone()
+}
+
+/// Get the global undo journal
+pub fn get_journal() -> Arc<Journal> {
+    JOURNAL.get_or_init(|| {
+        Arc::new(Journal::new(data_path("journal")))
+    }).clone()
+}
+
+/// Get the application config directory; next to the executable in portable mode
 pub fn get_config_dir() -> PathBuf {
-    let proj_dirs = directories::ProjectDirs::from("com", "anthropic", "mcp-client")
-        .expect("Failed to determine config directory");
-    
-    let config_dir = proj_dirs.config_dir().to_path_buf();
-    
-    // Create if it doesn't exist
-    if !config_dir.exists() {
-        std::fs::create_dir_all(&config_dir).expect("Failed to create config directory");
-    }
-    
-    config_dir
+    app_dir(AppDir::Config)
 }
 
-/// Get the application data directory
+/// Get the application data directory; next to the executable in portable mode
 pub fn get_data_dir() -> PathBuf {
-    let proj_dirs = directories::ProjectDirs::from("com", "anthropic", "mcp-client")
-        .expect("Failed to determine data directory");
-    
-    let data_dir = proj_dirs.data_dir().to_path_buf();
-    
-    // Create if it doesn't exist
-    if !data_dir.exists() {
-        std::fs::create_dir_all(&data_dir).expect("Failed to create data directory");
-    }
-    
-    data_dir
+    app_dir(AppDir::Data)
 }
 
 /// Get a path within the config directory
@@ -1,13 +1,16 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 
 use super::config_path;
+use super::journal::UndoSettings;
 use crate::error::{McpError, McpResult};
 use crate::utils::security;
 
 const SETTINGS_FILE: &str = "settings.json";
 const API_KEY_FILE: &str = "credentials.enc";
+const TEAM_TOKEN_FILE: &str = "team_token.enc";
 
 /// Application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +23,66 @@ pub struct Settings {
     
     /// Model configuration
     pub model: ModelSettings,
+    
+    /// Link preview configuration
+    #[serde(default)]
+    pub links: LinkSettings,
+    
+    /// Undo journal configuration
+    #[serde(default)]
+    pub undo: UndoSettings,
+    
+    /// Trash configuration
+    #[serde(default)]
+    pub trash: TrashSettings,
+    
+    /// Accessibility configuration
+    #[serde(default)]
+    pub accessibility: AccessibilitySettings,
+    
+    /// Offline mode and local model configuration
+    #[serde(default)]
+    pub offline: OfflineSettings,
+    
+    /// Unsent message drafts
+    #[serde(default)]
+    pub drafts: DraftSettings,
+    
+    /// Usage data sharing consent
+    #[serde(default)]
+    pub telemetry: TelemetryConsent,
+    
+    /// Who uses this installation
+    #[serde(default)]
+    pub profile: Option<UserProfile>,
+    
+    /// When first-run setup was completed
+    #[serde(default)]
+    pub onboarded_at: Option<DateTime<Utc>>,
+    
+    /// Team workspace configuration
+    #[serde(default)]
+    pub team: TeamSettings,
+    
+    /// How requests are authenticated
+    #[serde(default)]
+    pub auth: AuthSettings,
+    
+    /// Developer diagnostics
+    #[serde(default)]
+    pub debug: DebugSettings,
+    
+    /// Cost display, prices and warnings before sending
+    #[serde(default)]
+    pub cost: CostSettings,
+    
+    /// Log files and what is redacted from them
+    #[serde(default)]
+    pub logging: LoggingSettings,
+    
+    /// How tool calls are handled
+    #[serde(default)]
+    pub tools: ToolSettings,
 }
 
 /// API settings
@@ -49,6 +112,10 @@ pub struct UiSettings {
     
     /// Use system theme
     pub system_theme: bool,
+    
+    /// Language for messages, e.g. `de`; detected from the system when unset
+    #[serde(default)]
+    pub language: Option<String>,
 }
 
 /// Model settings
@@ -67,6 +134,304 @@ pub struct ModelSettings {
     pub streaming: bool,
 }
 
+/// Link preview settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LinkSettings {
+    /// Fetch titles and previews for links in messages; off by default
+    /// because it contacts the linked sites
+    #[serde(default)]
+    pub unfurl: bool,
+    
+    /// Domains previews may be fetched from (subdomains included); empty allows all
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+}
+
+impl LinkSettings {
+    /// Whether a preview may be fetched for a URL
+    pub fn allows(&self, url: &url::Url) -> bool {
+        if !self.unfurl || !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        if self.allowed_domains.is_empty() {
+            return true;
+        }
+        
+        let host = url.host_str().unwrap_or_default().to_lowercase();
+        self.allowed_domains.iter().any(|domain| {
+            let domain = domain.trim().trim_start_matches("*.").to_lowercase();
+            host == domain || host.ends_with(&format!(".{}", domain))
+        })
+    }
+}
+
+/// Trash settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashSettings {
+    /// Days deleted conversations are kept before they are purged
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_retention_days() -> u32 {
+    30
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self {
+            retention_days: default_retention_days(),
+        }
+    }
+}
+
+/// Accessibility settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccessibilitySettings {
+    /// Simplified TUI for terminal screen readers: one pane at a time, no
+    /// decorations and spelled-out status lines
+    #[serde(default)]
+    pub screen_reader: bool,
+    
+    /// No spinners, transitions or other motion, in the TUI, CLI and GUI
+    #[serde(default)]
+    pub reduced_motion: bool,
+    
+    /// Hint for the desktop app to enlarge text and controls
+    #[serde(default)]
+    pub large_text: bool,
+}
+
+impl AccessibilitySettings {
+    /// Whether animations may run, given the UI animation setting
+    pub fn allows_motion(&self, ui: &UiSettings) -> bool {
+        ui.animations && !self.reduced_motion && !self.screen_reader
+    }
+}
+
+/// Offline mode settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OfflineSettings {
+    /// Use a local model when the API can't be reached
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Local model to use, e.g. `tinyllama`
+    #[serde(default)]
+    pub local_model: Option<String>,
+}
+
+/// Draft settings; drafts are always kept on this device
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DraftSettings {
+    /// Send drafts to other devices with sync
+    #[serde(default)]
+    pub sync: bool,
+}
+
+/// Consent to share anonymous usage data
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelemetryConsent {
+    /// Usage data may be sent; off until the user opts in
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// When the user answered; `None` means they haven't been asked
+    #[serde(default)]
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+/// The user of this installation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserProfile {
+    /// Display name
+    pub name: String,
+    
+    /// Contact email, for feedback and sharing
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+/// Team workspace settings; the access token is stored encrypted, like the
+/// API key
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TeamSettings {
+    /// Sync prompts and shared conversations with the team server
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// Base URL of the self-hosted team server
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// How requests to the API are authenticated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    /// An Anthropic API key
+    #[default]
+    ApiKey,
+    
+    /// OAuth access tokens from an enterprise gateway in front of the API
+    OAuth,
+}
+
+/// Authentication settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthSettings {
+    /// Credentials sent with requests
+    #[serde(default)]
+    pub method: AuthMethod,
+    
+    /// OAuth device-code login configuration
+    #[serde(default)]
+    pub oauth: OAuthSettings,
+}
+
+/// OAuth device authorization grant (RFC 8628) configuration of the gateway
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OAuthSettings {
+    /// Endpoint issuing device and user codes
+    #[serde(default)]
+    pub device_authorization_url: Option<String>,
+    
+    /// Endpoint issuing and refreshing tokens
+    #[serde(default)]
+    pub token_url: Option<String>,
+    
+    /// Client ID registered with the identity provider
+    #[serde(default)]
+    pub client_id: Option<String>,
+    
+    /// Scopes to request
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    
+    /// Audience (resource) to request tokens for, if the provider needs one
+    #[serde(default)]
+    pub audience: Option<String>,
+}
+
+/// Developer diagnostics settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugSettings {
+    /// Capture sanitized copies of API requests and responses
+    #[serde(default)]
+    pub inspector: bool,
+    
+    /// Exchanges kept by the inspector; older ones are dropped
+    #[serde(default = "default_inspector_capacity")]
+    pub inspector_capacity: usize,
+    
+    /// Bytes of each request or response body the inspector keeps
+    #[serde(default = "default_inspector_max_body")]
+    pub inspector_max_body: usize,
+}
+
+fn default_inspector_capacity() -> usize {
+    50
+}
+
+fn default_inspector_max_body() -> usize {
+    16 * 1024
+}
+
+impl Default for DebugSettings {
+    fn default() -> Self {
+        Self {
+            inspector: false,
+            inspector_capacity: default_inspector_capacity(),
+            inspector_max_body: default_inspector_max_body(),
+        }
+    }
+}
+
+/// Cost display and warning settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostSettings {
+    /// Ask before sending a message projected to cost more than this much,
+    /// in `currency`; never ask when unset
+    #[serde(default = "default_confirm_above")]
+    pub confirm_above: Option<f64>,
+    
+    /// Currency costs are shown in, e.g. `EUR`
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    
+    /// Price list fetched daily on top of the bundled prices
+    #[serde(default)]
+    pub price_list_url: Option<String>,
+    
+    /// Spend allowed per calendar month, in `currency`; alias routes with a
+    /// budget condition always pass when unset
+    #[serde(default)]
+    pub monthly_budget: Option<f64>,
+}
+
+fn default_confirm_above() -> Option<f64> {
+    Some(0.5)
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+impl Default for CostSettings {
+    fn default() -> Self {
+        Self {
+            confirm_above: default_confirm_above(),
+            currency: default_currency(),
+            price_list_url: None,
+            monthly_budget: None,
+        }
+    }
+}
+
+/// Log file and redaction settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingSettings {
+    /// Keep logs in files in the data directory, for support bundles
+    #[serde(default = "default_true")]
+    pub file: bool,
+    
+    /// Replace API keys, tokens and passwords
+    #[serde(default = "default_true")]
+    pub redact_api_keys: bool,
+    
+    /// Replace prompt and response text
+    #[serde(default)]
+    pub redact_prompts: bool,
+    
+    /// Shorten file paths to their file name
+    #[serde(default = "default_true")]
+    pub redact_paths: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            file: true,
+            redact_api_keys: true,
+            redact_prompts: false,
+            redact_paths: true,
+        }
+    }
+}
+
+/// Tool call settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolSettings {
+    /// Record tool calls and describe what they would do instead of
+    /// executing them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
 impl Settings {
     /// Load settings from file
     pub fn load() -> McpResult<Self> {
@@ -130,6 +495,36 @@ impl Settings {
             
         Ok(())
     }
+    
+    /// Get the team server access token (will be decrypted)
+    pub fn get_team_token(&self) -> McpResult<Option<String>> {
+        let path = config_path(TEAM_TOKEN_FILE);
+        
+        if path.exists() {
+            let encrypted = fs::read(&path)?;
+            let token = security::decrypt(&encrypted)
+                .map_err(|e| McpError::Config(format!("Failed to decrypt team token: {}", e)))?;
+            Ok(Some(token))
+        } else {
+            Ok(None)
+        }
+    }
+    
+    /// Set the team server access token (will be encrypted); `None` removes it
+    pub fn set_team_token(&self, token: Option<&str>) -> McpResult<()> {
+        let path = config_path(TEAM_TOKEN_FILE);
+        
+        match token {
+            Some(token) => {
+                let encrypted = security::encrypt(token)
+                    .map_err(|e| McpError::Config(format!("Failed to encrypt team token: {}", e)))?;
+                fs::write(path, encrypted)?;
+            }
+            None if path.exists() => fs::remove_file(path)?,
+            None => {}
+        }
+        Ok(())
+    }
 }
 
 impl Default for Settings {
@@ -145,6 +540,7 @@ impl Default for Settings {
                 font_size: 14,
                 animations: true,
                 system_theme: true,
+                language: None,
             },
             model: ModelSettings {
                 temperature: 0.7,
@@ -152,6 +548,21 @@ impl Default for Settings {
                 system_prompt: None,
                 streaming: true,
             },
+            links: LinkSettings::default(),
+            undo: UndoSettings::default(),
+            trash: TrashSettings::default(),
+            accessibility: AccessibilitySettings::default(),
+            offline: OfflineSettings::default(),
+            drafts: DraftSettings::default(),
+            telemetry: TelemetryConsent::default(),
+            profile: None,
+            onboarded_at: None,
+            team: TeamSettings::default(),
+            auth: AuthSettings::default(),
+            debug: DebugSettings::default(),
+            cost: CostSettings::default(),
+            logging: LoggingSettings::default(),
+            tools: ToolSettings::default(),
         }
     }
 }
@@ -1,33 +1,237 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 use crate::error::{McpError, McpResult};
 use crate::models::Conversation;
-use super::{data_path, get_data_dir};
+use crate::reminders::get_reminder_store;
+use crate::utils::clock;
+use super::blobs::{referenced_blobs, BlobStats, BlobStore};
+use super::journal::{Journal, JournalEntry, OperationKind, Snapshot};
+use super::{data_path, get_attachment_store, get_data_dir, get_journal, get_settings};
+
+/// Conversation metadata key holding when a trashed conversation was deleted
+pub const TRASHED_AT_METADATA_KEY: &str = "trashed_at";
+
+/// Result of compacting conversation storage
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompactReport {
+    /// Conversation files rewritten to reference blobs
+    pub conversations_rewritten: usize,
+
+    /// Unreferenced blobs deleted
+    pub blobs_removed: usize,
+
+    /// Attachments of deleted conversations removed
+    pub attachments_removed: usize,
+
+    /// Trashed conversations purged after their retention period
+    #[serde(default)]
+    pub trash_purged: usize,
+
+    /// Bytes used by conversations, blobs and attachments before compacting
+    pub bytes_before: u64,
+
+    /// Bytes used by conversations, blobs and attachments after compacting
+    pub bytes_after: u64,
+
+    /// Blob statistics after compacting
+    pub blobs: BlobStats,
+}
+
+impl CompactReport {
+    /// Bytes freed by compacting
+    pub fn reclaimed(&self) -> u64 {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+}
+
+/// A conversation in the trash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedConversation {
+    /// The conversation
+    pub conversation: Conversation,
+
+    /// When it was deleted
+    pub deleted_at: DateTime<Utc>,
+
+    /// When it will be purged
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Disk usage of one kind of stored data
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageEntry {
+    /// Number of items
+    pub count: usize,
+
+    /// Bytes on disk
+    pub bytes: u64,
+}
+
+/// Disk usage of conversation storage by category
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageUsage {
+    /// Live conversations
+    pub conversations: UsageEntry,
+
+    /// Conversations in the trash
+    pub trash: UsageEntry,
+
+    /// Shared blobs
+    pub blobs: UsageEntry,
+
+    /// Attachments
+    pub attachments: UsageEntry,
+
+    /// Undo journal entries
+    pub journal: UsageEntry,
+}
+
+impl StorageUsage {
+    /// Bytes used in total
+    pub fn total(&self) -> u64 {
+        self.conversations.bytes + self.trash.bytes + self.blobs.bytes + self.attachments.bytes + self.journal.bytes
+    }
+}
+
+/// Number and total size of the JSON files directly in a directory
+fn json_files_usage(dir: &Path) -> UsageEntry {
+    let mut usage = UsageEntry::default();
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+            usage.count += 1;
+            usage.bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+        }
+    }
+    usage
+}
+
+/// Total size of the files in a directory tree
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| {
+                    let path = entry.path();
+                    if path.is_dir() {
+                        dir_size(&path)
+                    } else {
+                        entry.metadata().map(|m| m.len()).unwrap_or(0)
+                    }
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Unsent input of a conversation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Draft {
+    /// Conversation the draft is written in
+    pub conversation_id: String,
+    
+    /// Text typed so far
+    pub text: String,
+    
+    /// When the text was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Draft {
+    /// Suffix of the sync key of a conversation's draft
+    pub const SYNC_KEY_SUFFIX: &'static str = "/draft";
+    
+    /// Sync key of the draft, e.g. `conversation:<id>/draft`
+    pub fn sync_key(&self) -> String {
+        format!("conversation:{}{}", self.conversation_id, Self::SYNC_KEY_SUFFIX)
+    }
+    
+    /// Whether a sync key is that of a draft
+    pub fn is_sync_key(key: &str) -> bool {
+        key.starts_with("conversation:") && key.ends_with(Self::SYNC_KEY_SUFFIX)
+    }
+}
 
 /// Storage manager
 pub struct StorageManager {
     /// Conversations directory
     conversations_dir: PathBuf,
+    
+    /// Unsent drafts, one file per conversation
+    drafts_dir: PathBuf,
+    
+    /// Deleted conversations kept until their retention period ends
+    trash_dir: PathBuf,
+    
+    /// Large message parts shared between conversations
+    blobs: BlobStore,
+    
+    /// Undo journal of operations on these conversations
+    journal: Arc<Journal>,
 }
 
 impl StorageManager {
     /// Create a new storage manager
     pub fn new() -> Self {
-        let mut conversations_dir = get_data_dir();
-        conversations_dir.push("conversations");
+        Self::with_journal(get_data_dir(), get_journal())
+    }
+    
+    /// Create a storage manager keeping everything, including its undo
+    /// journal, under another directory; used to isolate tests
+    pub fn at(root: PathBuf) -> Self {
+        let journal = Arc::new(Journal::new(root.join("journal")));
+        Self::with_journal(root, journal)
+    }
+    
+    fn with_journal(root: PathBuf, journal: Arc<Journal>) -> Self {
+        let conversations_dir = root.join("conversations");
         
         // Create if it doesn't exist
         if !conversations_dir.exists() {
             fs::create_dir_all(&conversations_dir).expect("Failed to create conversations directory");
         }
         
+        let trash_dir = root.join("trash");
+        if !trash_dir.exists() {
+            fs::create_dir_all(&trash_dir).expect("Failed to create trash directory");
+        }
+        
         Self {
             conversations_dir,
+            drafts_dir: root.join("drafts"),
+            trash_dir,
+            blobs: BlobStore::new(root.join("blobs")),
+            journal,
         }
     }
     
+    /// Blobs referenced by a stored conversation file
+    fn file_refs(path: &Path) -> HashSet<String> {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            .map(|value| referenced_blobs(&value))
+            .unwrap_or_default()
+    }
+    
+    /// Blobs referenced by the stored copy of a conversation
+    fn stored_refs(&self, conversation_id: &str) -> HashSet<String> {
+        Self::file_refs(&self.conversation_path(conversation_id))
+    }
+    
+    /// Parse a conversation file, resolving blob references
+    fn read_conversation(&self, content: &str) -> McpResult<Conversation> {
+        let mut value: serde_json::Value = serde_json::from_str(content)?;
+        self.blobs.internalize(&mut value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+    
     /// Get path for a conversation file
     pub fn conversation_path(&self, conversation_id: &str) -> PathBuf {
         let mut path = self.conversations_dir.clone();
@@ -35,17 +239,30 @@ impl StorageManager {
         path
     }
     
+    /// Get path for a trashed conversation file
+    pub fn trash_path(&self, conversation_id: &str) -> PathBuf {
+        self.trash_dir.join(format!("{}.json", conversation_id))
+    }
+    
     /// Save a conversation
     pub fn save_conversation(&self, conversation: &Conversation) -> McpResult<()> {
+        // Saving a trashed conversation (e.g. undoing its deletion) takes it out of the trash
+        self.remove_trash_file(&conversation.id)?;
+        
         let path = self.conversation_path(&conversation.id);
+        let previous = self.stored_refs(&conversation.id);
+        
+        // Large parts go to the blob store so repeated pastes are kept once
+        let mut value = serde_json::to_value(conversation)?;
+        let current = self.blobs.externalize(&mut value)?;
         
-        let content = serde_json::to_string_pretty(conversation)
+        let content = serde_json::to_string_pretty(&value)
             .map_err(|e| McpError::Serialization(e))?;
             
         fs::write(path, content)
             .map_err(|e| McpError::Io(e))?;
-            
-        Ok(())
+        
+        self.blobs.replace_refs(&previous, &current)
     }
     
     /// Load a conversation
@@ -59,17 +276,71 @@ impl StorageManager {
         let content = fs::read_to_string(&path)
             .map_err(|e| McpError::Io(e))?;
             
-        let conversation = serde_json::from_str(&content)
-            .map_err(|e| McpError::Serialization(e))?;
-            
-        Ok(conversation)
+        self.read_conversation(&content)
+    }
+    
+    /// Get path for a draft file
+    fn draft_path(&self, conversation_id: &str) -> PathBuf {
+        self.drafts_dir.join(format!("{}.json", conversation_id))
+    }
+    
+    /// Save the draft of a conversation
+    pub fn save_draft(&self, draft: &Draft) -> McpResult<()> {
+        fs::create_dir_all(&self.drafts_dir)?;
+        fs::write(self.draft_path(&draft.conversation_id), serde_json::to_string_pretty(draft)?)?;
+        Ok(())
+    }
+    
+    /// Load the draft of a conversation, if there is one
+    pub fn load_draft(&self, conversation_id: &str) -> McpResult<Option<Draft>> {
+        let path = self.draft_path(conversation_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+    
+    /// Delete the draft of a conversation
+    pub fn delete_draft(&self, conversation_id: &str) -> McpResult<()> {
+        let path = self.draft_path(conversation_id);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
     }
     
-    /// Delete a conversation
+    /// Apply a draft synced from another device. It replaces the local one
+    /// only if it is newer; blank text deletes it. Returns whether it was
+    /// applied.
+    pub fn apply_draft(&self, draft: &Draft) -> McpResult<bool> {
+        if let Some(local) = self.load_draft(&draft.conversation_id)? {
+            if local.updated_at >= draft.updated_at {
+                return Ok(false);
+            }
+        }
+        
+        if draft.text.trim().is_empty() {
+            self.delete_draft(&draft.conversation_id)?;
+        } else {
+            self.save_draft(draft)?;
+        }
+        Ok(true)
+    }
+    
+    /// Delete a conversation by moving it to the trash. Its blobs stay
+    /// referenced until it is purged.
     pub fn delete_conversation(&self, conversation_id: &str) -> McpResult<()> {
         let path = self.conversation_path(conversation_id);
         
         if path.exists() {
+            let mut value: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            if let Some(metadata) = value.get_mut("metadata").and_then(|m| m.as_object_mut()) {
+                metadata.insert(TRASHED_AT_METADATA_KEY.to_string(), serde_json::json!(clock::now()));
+            }
+            
+            // A conversation deleted again replaces its older trashed copy
+            self.remove_trash_file(conversation_id)?;
+            fs::write(self.trash_path(conversation_id), serde_json::to_string_pretty(&value)?)?;
             fs::remove_file(path)
                 .map_err(|e| McpError::Io(e))?;
         }
@@ -77,6 +348,107 @@ impl StorageManager {
         Ok(())
     }
     
+    /// Remove a trashed copy and release its blobs
+    fn remove_trash_file(&self, conversation_id: &str) -> McpResult<()> {
+        let path = self.trash_path(conversation_id);
+        if path.exists() {
+            let previous = Self::file_refs(&path);
+            fs::remove_file(&path)?;
+            self.blobs.replace_refs(&previous, &HashSet::new())?;
+        }
+        Ok(())
+    }
+    
+    /// Conversations in the trash, most recently deleted first
+    pub fn list_trashed(&self) -> McpResult<Vec<TrashedConversation>> {
+        let retention = Duration::days(get_settings().lock().unwrap().trash.retention_days as i64);
+        let mut trashed = Vec::new();
+        
+        for entry in fs::read_dir(&self.trash_dir)?.flatten() {
+            let path = entry.path();
+            if !path.is_file() || path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let Ok(mut conversation) = fs::read_to_string(&path)
+                .map_err(McpError::from)
+                .and_then(|content| self.read_conversation(&content))
+            else {
+                log::warn!("Skipping unreadable trash file {}", path.display());
+                continue;
+            };
+            
+            let deleted_at = conversation
+                .metadata
+                .as_object_mut()
+                .and_then(|m| m.remove(TRASHED_AT_METADATA_KEY))
+                .and_then(|v| serde_json::from_value::<DateTime<Utc>>(v).ok())
+                .unwrap_or_else(clock::now);
+            trashed.push(TrashedConversation {
+                conversation,
+                deleted_at,
+                expires_at: deleted_at + retention,
+            });
+        }
+        
+        trashed.sort_by_key(|t| std::cmp::Reverse(t.deleted_at));
+        Ok(trashed)
+    }
+    
+    /// Move a conversation out of the trash
+    pub fn restore_from_trash(&self, conversation_id: &str) -> McpResult<Conversation> {
+        let path = self.trash_path(conversation_id);
+        if !path.exists() {
+            return Err(McpError::InvalidRequest(format!("Conversation {} is not in the trash", conversation_id)));
+        }
+        
+        let mut conversation = self.read_conversation(&fs::read_to_string(&path)?)?;
+        if let Some(metadata) = conversation.metadata.as_object_mut() {
+            metadata.remove(TRASHED_AT_METADATA_KEY);
+        }
+        self.save_conversation(&conversation)?;
+        
+        Ok(conversation)
+    }
+    
+    /// Permanently delete a trashed conversation with its attachments
+    pub fn purge_conversation(&self, conversation_id: &str) -> McpResult<()> {
+        if !self.trash_path(conversation_id).exists() {
+            return Err(McpError::InvalidRequest(format!("Conversation {} is not in the trash", conversation_id)));
+        }
+        
+        self.remove_trash_file(conversation_id)?;
+        self.delete_draft(conversation_id)?;
+        get_attachment_store().delete_for_conversation(conversation_id)?;
+        get_reminder_store().cancel_for_conversation(conversation_id)?;
+        // Its deletion can't be undone any more
+        self.journal.forget(conversation_id);
+        
+        Ok(())
+    }
+    
+    /// Purge trashed conversations older than the retention period; returns
+    /// how many were purged
+    pub fn purge_expired_trash(&self) -> McpResult<usize> {
+        let now = clock::now();
+        let mut purged = 0;
+        for trashed in self.list_trashed()? {
+            if trashed.expires_at <= now {
+                self.purge_conversation(&trashed.conversation.id)?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+    
+    /// Permanently delete everything in the trash; returns how many were purged
+    pub fn empty_trash(&self) -> McpResult<usize> {
+        let trashed = self.list_trashed()?;
+        for entry in &trashed {
+            self.purge_conversation(&entry.conversation.id)?;
+        }
+        Ok(trashed.len())
+    }
+    
     /// List all conversations
     pub fn list_conversations(&self) -> McpResult<Vec<Conversation>> {
         let mut conversations = Vec::new();
@@ -90,7 +462,7 @@ impl StorageManager {
             if path.is_file() && path.extension().map_or(false, |ext| ext == "json") {
                 // Read the conversation file
                 if let Ok(content) = fs::read_to_string(&path) {
-                    if let Ok(conversation) = serde_json::from_str::<Conversation>(&content) {
+                    if let Ok(conversation) = self.read_conversation(&content) {
                         conversations.push(conversation);
                     }
                 }
@@ -102,6 +474,135 @@ impl StorageManager {
         
         Ok(conversations)
     }
+    
+    /// Current stored state of a conversation
+    fn snapshot(&self, conversation_id: &str) -> Snapshot {
+        Snapshot {
+            conversation_id: conversation_id.to_string(),
+            conversation: self.load_conversation(conversation_id).ok(),
+        }
+    }
+    
+    /// Write a snapshot back to disk
+    pub fn restore(&self, snapshot: &Snapshot) -> McpResult<()> {
+        match &snapshot.conversation {
+            Some(conversation) => self.save_conversation(conversation),
+            None => self.delete_conversation(&snapshot.conversation_id),
+        }
+    }
+    
+    /// Run an operation on some conversations as one journal entry.
+    ///
+    /// If the operation fails, the conversations are put back the way they
+    /// were and nothing is recorded.
+    pub fn journaled(
+        &self,
+        kind: OperationKind,
+        description: &str,
+        conversation_ids: &[String],
+        operation: impl FnOnce() -> McpResult<()>,
+    ) -> McpResult<JournalEntry> {
+        let before: Vec<Snapshot> = conversation_ids.iter().map(|id| self.snapshot(id)).collect();
+        
+        if let Err(e) = operation() {
+            for snapshot in &before {
+                if let Err(rollback) = self.restore(snapshot) {
+                    log::error!("Failed to roll back {}: {}", snapshot.conversation_id, rollback);
+                }
+            }
+            return Err(e);
+        }
+        
+        let after = conversation_ids.iter().map(|id| self.snapshot(id)).collect();
+        self.journal.record(kind, description, before, after)
+    }
+    
+    /// Undo the latest journaled operation on disk
+    pub fn undo(&self) -> McpResult<Option<JournalEntry>> {
+        self.journal.undo(|snapshot| self.restore(snapshot))
+    }
+    
+    /// Redo the most recently undone operation on disk
+    pub fn redo(&self) -> McpResult<Option<JournalEntry>> {
+        self.journal.redo(|snapshot| self.restore(snapshot))
+    }
+    
+    /// Disk usage by category
+    pub fn usage(&self) -> StorageUsage {
+        let blobs = self.blobs.stats();
+        let attachments = get_attachment_store().list(None);
+        
+        StorageUsage {
+            conversations: json_files_usage(&self.conversations_dir),
+            trash: json_files_usage(&self.trash_dir),
+            blobs: UsageEntry {
+                count: blobs.blobs,
+                bytes: blobs.stored_size,
+            },
+            attachments: UsageEntry {
+                count: attachments.len(),
+                bytes: dir_size(&data_path("attachments")),
+            },
+            journal: json_files_usage(&data_path("journal")),
+        }
+    }
+    
+    /// Bytes used by conversation storage
+    fn storage_size(&self) -> u64 {
+        self.usage().total()
+    }
+    
+    /// Move large inline parts into blobs, recount references and delete
+    /// blobs and attachments no conversation uses any more
+    pub fn compact(&self) -> McpResult<CompactReport> {
+        let mut report = CompactReport {
+            bytes_before: self.storage_size(),
+            ..CompactReport::default()
+        };
+        
+        report.trash_purged = self.purge_expired_trash()?;
+        
+        // Trashed conversations keep their blobs and attachments until purged
+        let mut refs: HashMap<String, usize> = HashMap::new();
+        let mut conversation_ids = HashSet::new();
+        let files = fs::read_dir(&self.conversations_dir)?
+            .chain(fs::read_dir(&self.trash_dir)?)
+            .flatten();
+        for entry in files {
+            let path = entry.path();
+            if !path.is_file() || path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            
+            let parsed = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok());
+            let Some(mut value) = parsed else {
+                log::warn!("Skipping unreadable conversation file {}", path.display());
+                continue;
+            };
+            if let Some(id) = value.get("id").and_then(|id| id.as_str()) {
+                conversation_ids.insert(id.to_string());
+            }
+            
+            let before = referenced_blobs(&value);
+            let current = self.blobs.externalize(&mut value)?;
+            if current != before {
+                fs::write(&path, serde_json::to_string_pretty(&value)?)?;
+                report.conversations_rewritten += 1;
+            }
+            for hash in current {
+                *refs.entry(hash).or_default() += 1;
+            }
+        }
+        
+        report.blobs_removed = self.blobs.reconcile(&refs)?;
+        report.attachments_removed = get_attachment_store().cleanup_orphans(&conversation_ids)?.0;
+        report.bytes_after = self.storage_size();
+        report.blobs = self.blobs.stats();
+        
+        Ok(report)
+    }
 }
 
 impl Default for StorageManager {
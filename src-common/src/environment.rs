@@ -0,0 +1,336 @@
+//! Environment variables of workspaces and conversations.
+//!
+//! Values such as an API base URL, a repository path or a dataset path are
+//! set for a workspace or a single conversation; a conversation sees its
+//! workspace's variables with its own on top. Tools get them filled into
+//! their arguments and the processes they start, and prompt templates
+//! reference them as `{env.NAME}`. Secret values are kept in the vault, with
+//! only a key in the environment file, and are masked in logs and exports.
+
+use log::warn;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::auth::secret_store;
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::logs::redaction::register_secret;
+use crate::models::Conversation;
+use crate::service::filters::WORKSPACE_CONTEXT_KEY;
+
+/// Stands in for secret values shown to the user
+pub const MASK: &str = "********";
+
+/// References to variables, such as `{env.API_BASE}`
+static REFERENCE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{env\.([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+
+/// Valid variable names
+static NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap());
+
+/// What variables apply to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    /// Every conversation of a workspace
+    Workspace(String),
+
+    /// One conversation
+    Conversation(String),
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scope::Workspace(name) => write!(f, "workspace '{}'", name),
+            Scope::Conversation(id) => write!(f, "conversation {}", id),
+        }
+    }
+}
+
+/// A stored variable value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EnvValue {
+    /// Kept in the environment file
+    Plain { value: String },
+
+    /// Kept in the vault under a key
+    Secret { key: String },
+}
+
+impl EnvValue {
+    /// Whether the value is kept in the vault
+    pub fn is_secret(&self) -> bool {
+        matches!(self, EnvValue::Secret { .. })
+    }
+}
+
+/// A variable as a conversation sees it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Variable {
+    /// The value
+    pub value: String,
+
+    /// Whether the value is a secret
+    pub secret: bool,
+}
+
+/// Variables a conversation sees, by name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Environment {
+    variables: BTreeMap<String, Variable>,
+}
+
+impl Environment {
+    /// Whether there are no variables
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty()
+    }
+
+    /// Value of a variable
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.variables.get(name).map(|v| v.value.as_str())
+    }
+
+    /// Variables by name
+    pub fn variables(&self) -> &BTreeMap<String, Variable> {
+        &self.variables
+    }
+
+    /// Values for a process's environment
+    pub fn vars(&self) -> HashMap<String, String> {
+        self.variables
+            .iter()
+            .map(|(name, v)| (name.clone(), v.value.clone()))
+            .collect()
+    }
+
+    /// Values for display, with secrets masked
+    pub fn masked(&self) -> BTreeMap<String, String> {
+        self.variables
+            .iter()
+            .map(|(name, v)| {
+                let value = if v.secret { MASK.to_string() } else { v.value.clone() };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+
+    /// Fill in the `{env.NAME}` references of a text; references to unknown
+    /// variables are left as they are
+    pub fn expand(&self, text: &str) -> String {
+        REFERENCE
+            .replace_all(text, |caps: &regex::Captures| match self.get(&caps[1]) {
+                Some(value) => value.to_string(),
+                None => caps[0].to_string(),
+            })
+            .into_owned()
+    }
+
+    /// Fill in the references of every string in a JSON value
+    pub fn expand_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(text) => *text = self.expand(text),
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|item| self.expand_json(item)),
+            serde_json::Value::Object(map) => map.values_mut().for_each(|item| self.expand_json(item)),
+            _ => {}
+        }
+    }
+
+    /// Mask the secret values in a text
+    pub fn mask(&self, text: &str) -> String {
+        self.variables
+            .values()
+            .filter(|v| v.secret && !v.value.is_empty())
+            .fold(text.to_string(), |text, v| text.replace(&v.value, MASK))
+    }
+
+    /// Mask the secret values in every string of a JSON value
+    pub fn mask_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(text) => *text = self.mask(text),
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|item| self.mask_json(item)),
+            serde_json::Value::Object(map) => map.values_mut().for_each(|item| self.mask_json(item)),
+            _ => {}
+        }
+    }
+}
+
+/// Variables of every workspace and conversation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct EnvironmentFile {
+    #[serde(default)]
+    workspaces: BTreeMap<String, BTreeMap<String, EnvValue>>,
+
+    #[serde(default)]
+    conversations: BTreeMap<String, BTreeMap<String, EnvValue>>,
+}
+
+impl EnvironmentFile {
+    fn scope_mut(&mut self, scope: &Scope) -> &mut BTreeMap<String, EnvValue> {
+        match scope {
+            Scope::Workspace(name) => self.workspaces.entry(name.clone()).or_default(),
+            Scope::Conversation(id) => self.conversations.entry(id.clone()).or_default(),
+        }
+    }
+
+    fn scope(&self, scope: &Scope) -> Option<&BTreeMap<String, EnvValue>> {
+        match scope {
+            Scope::Workspace(name) => self.workspaces.get(name),
+            Scope::Conversation(id) => self.conversations.get(id),
+        }
+    }
+
+    /// Drop scopes left without variables
+    fn prune(&mut self) {
+        self.workspaces.retain(|_, vars| !vars.is_empty());
+        self.conversations.retain(|_, vars| !vars.is_empty());
+    }
+}
+
+/// The variables of every workspace and conversation, kept in a file
+pub struct EnvironmentStore {
+    path: PathBuf,
+    file: Mutex<EnvironmentFile>,
+}
+
+impl EnvironmentStore {
+    /// Variables kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        let file = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable environment {:?}: {}", path, e);
+                EnvironmentFile::default()
+            }),
+            Err(_) => EnvironmentFile::default(),
+        };
+        Self {
+            path,
+            file: Mutex::new(file),
+        }
+    }
+
+    fn save(&self, file: &EnvironmentFile) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(file)?)?;
+        Ok(())
+    }
+
+    /// Set a variable; a secret value goes to the vault
+    pub fn set(&self, scope: &Scope, name: &str, value: &str, secret: bool) -> McpResult<()> {
+        if !NAME.is_match(name) {
+            return Err(McpError::InvalidRequest(format!(
+                "Invalid variable name '{}': use letters, digits and underscores",
+                name
+            )));
+        }
+
+        let mut file = self.file.lock().unwrap();
+        let vars = file.scope_mut(scope);
+        let previous = vars.get(name).cloned();
+        let stored = if secret {
+            // A secret replacing a secret keeps its key
+            let key = match &previous {
+                Some(EnvValue::Secret { key }) => key.clone(),
+                _ => format!("env_{}", Uuid::new_v4().simple()),
+            };
+            secret_store().set(&key, value)?;
+            EnvValue::Secret { key }
+        } else {
+            if let Some(EnvValue::Secret { key }) = &previous {
+                secret_store().delete(key)?;
+            }
+            EnvValue::Plain {
+                value: value.to_string(),
+            }
+        };
+        vars.insert(name.to_string(), stored);
+        self.save(&file)
+    }
+
+    /// Remove a variable; returns whether it was set
+    pub fn unset(&self, scope: &Scope, name: &str) -> McpResult<bool> {
+        let mut file = self.file.lock().unwrap();
+        let removed = file.scope_mut(scope).remove(name);
+        file.prune();
+        let Some(removed) = removed else {
+            return Ok(false);
+        };
+        if let EnvValue::Secret { key } = &removed {
+            secret_store().delete(key)?;
+        }
+        self.save(&file)?;
+        Ok(true)
+    }
+
+    /// Remove every variable of a scope; returns how many were set
+    pub fn clear(&self, scope: &Scope) -> McpResult<usize> {
+        let mut file = self.file.lock().unwrap();
+        let removed = std::mem::take(file.scope_mut(scope));
+        file.prune();
+        if removed.is_empty() {
+            return Ok(0);
+        }
+        for value in removed.values() {
+            if let EnvValue::Secret { key } = value {
+                secret_store().delete(key)?;
+            }
+        }
+        self.save(&file)?;
+        Ok(removed.len())
+    }
+
+    /// Variables set for a scope, by name
+    pub fn list(&self, scope: &Scope) -> BTreeMap<String, EnvValue> {
+        self.file.lock().unwrap().scope(scope).cloned().unwrap_or_default()
+    }
+
+    /// Variables a conversation sees: its workspace's, with its own on top.
+    /// Secret values are read from the vault and masked in logs from then on.
+    pub fn resolve(&self, workspace: Option<&str>, conversation_id: &str) -> McpResult<Environment> {
+        let file = self.file.lock().unwrap().clone();
+        let workspace_vars = workspace.and_then(|w| file.workspaces.get(w));
+        let conversation_vars = file.conversations.get(conversation_id);
+
+        let mut variables = BTreeMap::new();
+        for (name, value) in workspace_vars.into_iter().chain(conversation_vars).flatten() {
+            let variable = match value {
+                EnvValue::Plain { value } => Variable {
+                    value: value.clone(),
+                    secret: false,
+                },
+                EnvValue::Secret { key } => {
+                    let value = secret_store().get(key)?.ok_or_else(|| {
+                        McpError::Config(format!("The secret value of {} is missing from the vault", name))
+                    })?;
+                    register_secret(&value);
+                    Variable { value, secret: true }
+                }
+            };
+            variables.insert(name.clone(), variable);
+        }
+        Ok(Environment { variables })
+    }
+
+    /// Variables a conversation sees, in the workspace its metadata names
+    pub fn for_conversation(&self, conversation: &Conversation) -> McpResult<Environment> {
+        let workspace = conversation.metadata.get(WORKSPACE_CONTEXT_KEY).and_then(|w| w.as_str());
+        self.resolve(workspace, &conversation.id)
+    }
+}
+
+static ENVIRONMENT_STORE: Lazy<Arc<EnvironmentStore>> =
+    Lazy::new(|| Arc::new(EnvironmentStore::at(data_path("environment.json"))));
+
+/// Get the global environment store
+pub fn get_environments() -> Arc<EnvironmentStore> {
+    ENVIRONMENT_STORE.clone()
+}
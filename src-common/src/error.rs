@@ -30,6 +30,12 @@ pub enum McpError {
     #[error("Rate limited: {0}")]
     RateLimit(String),
     
+    #[error("Request cancelled")]
+    Cancelled,
+    
+    #[error("Request timed out after {0:?}")]
+    Timeout(std::time::Duration),
+    
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
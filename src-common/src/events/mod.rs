@@ -0,0 +1,332 @@
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::sync::mpsc;
+
+/// Event topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Topic {
+    /// Conversations and messages
+    Conversation,
+    /// Model loading, unloading and routing
+    Model,
+    /// Synchronization
+    Sync,
+    /// Plugin lifecycle and plugin-defined events
+    Plugin,
+    /// Connectivity, auth, notifications and other app-wide state
+    System,
+}
+
+impl Topic {
+    /// All topics
+    pub fn all() -> Vec<Topic> {
+        vec![
+            Topic::Conversation,
+            Topic::Model,
+            Topic::Sync,
+            Topic::Plugin,
+            Topic::System,
+        ]
+    }
+
+    /// Best-effort topic for a legacy event name such as `conversation_created`
+    pub fn for_event_name(name: &str) -> Topic {
+        if name.starts_with("conversation") || name.starts_with("message") {
+            Topic::Conversation
+        } else if name.starts_with("model") {
+            Topic::Model
+        } else if name.starts_with("sync") {
+            Topic::Sync
+        } else if name.starts_with("plugin") {
+            Topic::Plugin
+        } else {
+            Topic::System
+        }
+    }
+}
+
+/// An event published on the bus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    /// Topic the event belongs to
+    pub topic: Topic,
+
+    /// Event name, also used as the Tauri event name
+    pub name: String,
+
+    /// Event payload
+    pub payload: serde_json::Value,
+
+    /// When the event was published
+    pub timestamp: SystemTime,
+}
+
+impl Event {
+    /// Create a new event
+    pub fn new(topic: Topic, name: impl Into<String>, payload: serde_json::Value) -> Self {
+        Self {
+            topic,
+            name: name.into(),
+            payload,
+            timestamp: SystemTime::now(),
+        }
+    }
+}
+
+/// What happens when a subscriber's queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Drop the new event and count it
+    DropNewest,
+    /// Remove the subscriber; it sees the end of the stream
+    Disconnect,
+}
+
+struct Subscriber {
+    id: u64,
+    topics: HashSet<Topic>,
+    tx: mpsc::Sender<Event>,
+    policy: Backpressure,
+    dropped: Arc<AtomicU64>,
+}
+
+/// Receiving end of a bus subscription
+pub struct Subscription {
+    id: u64,
+    rx: mpsc::Receiver<Event>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl Subscription {
+    /// Subscriber ID, used to unsubscribe
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Wait for the next event; `None` once the subscriber is removed
+    pub async fn recv(&mut self) -> Option<Event> {
+        self.rx.recv().await
+    }
+
+    /// Take every queued event without waiting, for poll-driven loops
+    pub fn drain(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        while let Ok(event) = self.rx.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    /// Number of events dropped because this subscriber fell behind
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Typed publish/subscribe bus shared by the desktop app, CLI and TUI
+pub struct EventBus {
+    subscribers: Mutex<Vec<Subscriber>>,
+    next_id: AtomicU64,
+}
+
+impl EventBus {
+    /// Create an empty bus
+    pub fn new() -> Self {
+        Self {
+            subscribers: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Subscribe to a set of topics with a bounded queue
+    pub fn subscribe(&self, topics: &[Topic], capacity: usize, policy: Backpressure) -> Subscription {
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let dropped = Arc::new(AtomicU64::new(0));
+
+        self.subscribers.lock().unwrap().push(Subscriber {
+            id,
+            topics: topics.iter().copied().collect(),
+            tx,
+            policy,
+            dropped: dropped.clone(),
+        });
+
+        Subscription { id, rx, dropped }
+    }
+
+    /// Subscribe to every topic
+    pub fn subscribe_all(&self, capacity: usize, policy: Backpressure) -> Subscription {
+        self.subscribe(&Topic::all(), capacity, policy)
+    }
+
+    /// Remove a subscriber
+    pub fn unsubscribe(&self, id: u64) {
+        self.subscribers.lock().unwrap().retain(|s| s.id != id);
+    }
+
+    /// Publish an event without blocking.
+    ///
+    /// Slow subscribers never hold up the publisher: depending on their policy
+    /// the event is dropped for them or they are disconnected.
+    pub fn publish(&self, event: Event) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+
+        subscribers.retain(|subscriber| {
+            if !subscriber.topics.contains(&event.topic) {
+                return true;
+            }
+
+            match subscriber.tx.try_send(event.clone()) {
+                Ok(()) => true,
+                Err(mpsc::error::TrySendError::Closed(_)) => false,
+                Err(mpsc::error::TrySendError::Full(_)) => match subscriber.policy {
+                    Backpressure::DropNewest => {
+                        subscriber.dropped.fetch_add(1, Ordering::Relaxed);
+                        debug!("Subscriber {} is behind, dropped {}", subscriber.id, event.name);
+                        true
+                    }
+                    Backpressure::Disconnect => {
+                        warn!("Disconnecting slow event subscriber {}", subscriber.id);
+                        false
+                    }
+                },
+            }
+        });
+    }
+
+    /// Publish an event built from its parts
+    pub fn emit(&self, topic: Topic, name: &str, payload: serde_json::Value) {
+        self.publish(Event::new(topic, name, payload));
+    }
+
+    /// Number of active subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global event bus instance
+static EVENT_BUS: Lazy<Arc<EventBus>> = Lazy::new(|| Arc::new(EventBus::new()));
+
+/// Get the global event bus
+pub fn get_event_bus() -> Arc<EventBus> {
+    EVENT_BUS.clone()
+}
+
+/// Event names published by shared services
+pub mod names {
+    /// Conversation created
+    pub const CONVERSATION_CREATED: &str = "conversation_created";
+
+    /// Conversation deleted
+    pub const CONVERSATION_DELETED: &str = "conversation_deleted";
+
+    /// Conversation restored from the trash
+    pub const CONVERSATION_RESTORED: &str = "conversation_restored";
+
+    /// Trashed conversation permanently deleted
+    pub const CONVERSATION_PURGED: &str = "conversation_purged";
+
+    /// Message sent
+    pub const MESSAGE_SENT: &str = "message_sent";
+
+    /// Message received
+    pub const MESSAGE_RECEIVED: &str = "message_received";
+
+    /// A link preview for a message became available
+    pub const LINK_UNFURLED: &str = "link_unfurled";
+
+    /// A conversation was read further, here or on another device
+    pub const READ_MARKER_CHANGED: &str = "read_marker_changed";
+
+    /// The unsent draft of a conversation changed or was cleared
+    pub const DRAFT_CHANGED: &str = "draft_changed";
+
+    /// Feedback on a message was given or cleared
+    pub const MESSAGE_RATED: &str = "message_rated";
+
+    /// A journaled operation was undone
+    pub const OPERATION_UNDONE: &str = "operation_undone";
+
+    /// An undone operation was redone
+    pub const OPERATION_REDONE: &str = "operation_redone";
+
+    /// Sync conflicts waiting for review changed
+    pub const SYNC_CONFLICTS_CHANGED: &str = "sync_conflicts_changed";
+
+    /// The team workspace synced with its server
+    pub const TEAM_SYNCED: &str = "team_synced";
+
+    /// Background sync was paused or resumed
+    pub const SYNC_PAUSED_CHANGED: &str = "sync_paused_changed";
+
+    /// Accessibility settings changed
+    pub const ACCESSIBILITY_CHANGED: &str = "accessibility_changed";
+
+    /// The theme of a profile, or the default theme, changed
+    pub const THEME_CHANGED: &str = "theme_changed";
+
+    /// API key validity or connection health changed
+    pub const CONNECTION_HEALTH_CHANGED: &str = "connection_health_changed";
+
+    /// The request inspector captured an exchange
+    pub const REQUEST_CAPTURED: &str = "request_captured";
+
+    /// An app subsystem started, degraded or failed
+    pub const SERVICE_HEALTH_CHANGED: &str = "service_health_changed";
+
+    /// A background job started or made progress
+    pub const JOB_PROGRESS: &str = "job_progress";
+
+    /// A background job completed, failed or was canceled
+    pub const JOB_FINISHED: &str = "job_finished";
+
+    /// The tracked budget ran out
+    pub const BUDGET_EXCEEDED: &str = "budget_exceeded";
+
+    /// A model catalog was added, removed, changed or refreshed
+    pub const MODEL_CATALOGS_CHANGED: &str = "model_catalogs_changed";
+
+    /// A conversation's model alias now resolves to a different model
+    pub const MODEL_ROUTED: &str = "model_routed";
+
+    /// New items of a subscribed feed were summarized into a digest
+    pub const FEED_DIGEST: &str = "feed_digest";
+
+    /// A follow-up reminder on a conversation came due
+    pub const REMINDER_DUE: &str = "reminder_due";
+
+    /// Installed templates have new versions in the gallery
+    pub const TEMPLATE_UPDATES_AVAILABLE: &str = "template_updates_available";
+
+    /// A remembered note was added, edited or forgotten
+    pub const MEMORIES_CHANGED: &str = "memories_changed";
+
+    /// A knowledge base switched to a new index
+    pub const KNOWLEDGE_BASE_REINDEXED: &str = "knowledge_base_reindexed";
+
+    /// A tool call was recorded instead of executed
+    pub const TOOL_CALL_SIMULATED: &str = "tool_call_simulated";
+
+    /// A tool policy asked about or denied a call
+    pub const TOOL_CALL_CHECKED: &str = "tool_call_checked";
+
+    /// An agent run took a step
+    pub const AGENT_STEP: &str = "agent_step";
+
+    /// An agent run was paused, resumed or stopped
+    pub const AGENT_STATUS_CHANGED: &str = "agent_status_changed";
+}
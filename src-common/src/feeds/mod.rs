@@ -0,0 +1,580 @@
+//! Feed digests
+//!
+//! Users subscribe to RSS and Atom feeds. A scheduler checks each feed on its
+//! own interval, keeps the items it hasn't seen that pass the feed's filter,
+//! and has a model summarize them into a digest. The digest is posted into a
+//! chosen conversation or raised as a notification. Items are remembered
+//! once digested, or when the user marks a feed read, so nothing is
+//! summarized twice.
+
+pub mod parser;
+
+pub use parser::{parse_feed, FeedItem, ParsedFeed};
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+use crate::models::{Message, Model};
+use crate::service::chat::ChatService;
+use crate::utils::clock;
+
+/// Metadata key of digest messages
+pub const DIGEST_METADATA_KEY: &str = "feed_digest";
+
+/// How often a feed is checked unless it says otherwise
+pub const DEFAULT_INTERVAL_MINUTES: u32 = 60;
+
+/// Shortest interval a feed can be checked on
+const MIN_INTERVAL_MINUTES: u32 = 5;
+
+/// Most items in one digest; the rest wait for the next one
+const MAX_DIGEST_ITEMS: usize = 20;
+
+/// Item IDs remembered per feed; the oldest are forgotten beyond this
+const MAX_SEEN: usize = 1000;
+
+/// How long a feed has to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+const DIGEST_SYSTEM_PROMPT: &str = "You write short digests of new articles from a news feed. \
+For each article write one bullet starting with \"- \": the title as a Markdown link when it has one, \
+then one or two sentences on what it says. Only use what the articles say. Reply with the bullets only.";
+
+/// Keywords an item's title and summary are checked against
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedFilter {
+    /// Items must mention one of these; any item when empty
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    /// Items mentioning any of these are skipped
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl FeedFilter {
+    /// Whether an item passes, ignoring case
+    pub fn matches(&self, item: &FeedItem) -> bool {
+        let text = format!("{} {}", item.title, item.summary).to_lowercase();
+        let mentions = |keyword: &String| text.contains(&keyword.to_lowercase());
+        (self.include.is_empty() || self.include.iter().any(mentions)) && !self.exclude.iter().any(mentions)
+    }
+}
+
+/// Where a feed's digests go
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DigestTarget {
+    /// Posted as a reply in a conversation
+    Conversation {
+        /// Conversation ID
+        conversation_id: String,
+    },
+
+    /// Raised as a notification
+    Notification,
+}
+
+/// A subscribed feed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Feed {
+    /// Feed ID
+    pub id: String,
+
+    /// URL of the RSS or Atom document
+    pub url: String,
+
+    /// Title, from the feed unless the user named it
+    pub title: String,
+
+    /// Which items make it into digests
+    #[serde(default)]
+    pub filter: FeedFilter,
+
+    /// Where digests go
+    pub target: DigestTarget,
+
+    /// Model that writes the digests, by ID or name; the default model when unset
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Minutes between checks
+    pub interval_minutes: u32,
+
+    /// Whether the feed is checked
+    pub enabled: bool,
+
+    /// IDs of items already digested or marked read, oldest first
+    #[serde(default)]
+    pub seen: Vec<String>,
+
+    /// When the feed was subscribed to
+    pub created_at: DateTime<Utc>,
+
+    /// When the feed was last checked
+    #[serde(default)]
+    pub last_checked_at: Option<DateTime<Utc>>,
+
+    /// When the last digest was made
+    #[serde(default)]
+    pub last_digest_at: Option<DateTime<Utc>>,
+
+    /// Error of the last check, cleared by a successful one
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl Feed {
+    /// Whether the feed should be checked now
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.enabled
+            && self
+                .last_checked_at
+                .is_none_or(|at| now - at >= ChronoDuration::minutes(self.interval_minutes as i64))
+    }
+
+    /// Items not seen yet that pass the filter, newest first
+    pub fn unread<'a>(&self, items: &'a [FeedItem]) -> Vec<&'a FeedItem> {
+        items
+            .iter()
+            .filter(|item| !self.seen.contains(&item.id) && self.filter.matches(item))
+            .collect()
+    }
+
+    fn remember(&mut self, ids: impl IntoIterator<Item = String>) {
+        for id in ids {
+            if !self.seen.contains(&id) {
+                self.seen.push(id);
+            }
+        }
+        if self.seen.len() > MAX_SEEN {
+            let excess = self.seen.len() - MAX_SEEN;
+            self.seen.drain(..excess);
+        }
+    }
+}
+
+/// Settings of a new subscription
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewFeed {
+    /// URL of the RSS or Atom document
+    pub url: String,
+
+    /// Title; taken from the feed when unset
+    #[serde(default)]
+    pub title: Option<String>,
+
+    /// Which items make it into digests
+    #[serde(default)]
+    pub filter: FeedFilter,
+
+    /// Where digests go
+    pub target: DigestTarget,
+
+    /// Model that writes the digests
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Minutes between checks
+    #[serde(default)]
+    pub interval_minutes: Option<u32>,
+}
+
+/// A summary of a feed's new items
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Digest {
+    /// Feed the items came from
+    pub feed_id: String,
+
+    /// Feed title
+    pub feed_title: String,
+
+    /// Items summarized
+    pub items: Vec<FeedItem>,
+
+    /// The model's summary
+    pub summary: String,
+
+    /// Where it went
+    pub target: DigestTarget,
+
+    /// When it was made
+    pub created_at: DateTime<Utc>,
+}
+
+impl Digest {
+    /// Digest as Markdown, headed by the feed title
+    pub fn to_markdown(&self) -> String {
+        format!(
+            "## {} — {} new {}\n\n{}",
+            self.feed_title,
+            self.items.len(),
+            if self.items.len() == 1 { "item" } else { "items" },
+            self.summary.trim()
+        )
+    }
+}
+
+/// Only http and https feeds
+fn validate_url(url: &str) -> McpResult<()> {
+    let parsed = url::Url::parse(url).map_err(|e| McpError::InvalidRequest(format!("Invalid feed URL: {}", e)))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(()),
+        scheme => Err(McpError::InvalidRequest(format!("Feeds can't be read over {}", scheme))),
+    }
+}
+
+/// Subscribed feeds and what has been read in them
+pub struct FeedStore {
+    path: PathBuf,
+    feeds: Mutex<Vec<Feed>>,
+}
+
+impl FeedStore {
+    /// Store kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        let feeds = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            feeds: Mutex::new(feeds),
+        }
+    }
+
+    fn save(&self, feeds: &[Feed]) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(feeds)?)?;
+        Ok(())
+    }
+
+    /// Index of the feed with an ID, or the only one starting with it
+    fn find(feeds: &[Feed], id: &str) -> McpResult<usize> {
+        if let Some(index) = feeds.iter().position(|f| f.id == id) {
+            return Ok(index);
+        }
+        let matches: Vec<_> = (0..feeds.len())
+            .filter(|&i| !id.is_empty() && feeds[i].id.starts_with(id))
+            .collect();
+        match matches.as_slice() {
+            [index] => Ok(*index),
+            [] => Err(McpError::InvalidRequest(format!("No feed {}", id))),
+            _ => Err(McpError::InvalidRequest(format!("More than one feed starts with {}", id))),
+        }
+    }
+
+    /// Subscribed feeds
+    pub fn list(&self) -> Vec<Feed> {
+        self.feeds.lock().unwrap().clone()
+    }
+
+    /// A feed by ID or unique ID prefix
+    pub fn get(&self, id: &str) -> McpResult<Feed> {
+        let feeds = self.feeds.lock().unwrap();
+        Ok(feeds[Self::find(&feeds, id)?].clone())
+    }
+
+    /// Subscribe to a feed
+    pub fn add(&self, new: NewFeed) -> McpResult<Feed> {
+        validate_url(&new.url)?;
+        let feed = Feed {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: new.title.unwrap_or_else(|| new.url.clone()),
+            url: new.url,
+            filter: new.filter,
+            target: new.target,
+            model: new.model,
+            interval_minutes: new
+                .interval_minutes
+                .unwrap_or(DEFAULT_INTERVAL_MINUTES)
+                .max(MIN_INTERVAL_MINUTES),
+            enabled: true,
+            seen: Vec::new(),
+            created_at: clock::now(),
+            last_checked_at: None,
+            last_digest_at: None,
+            last_error: None,
+        };
+
+        let mut feeds = self.feeds.lock().unwrap();
+        feeds.push(feed.clone());
+        self.save(&feeds)?;
+        info!("Subscribed to feed {} at {}", feed.id, feed.url);
+        Ok(feed)
+    }
+
+    /// Unsubscribe from a feed
+    pub fn remove(&self, id: &str) -> McpResult<()> {
+        let mut feeds = self.feeds.lock().unwrap();
+        let index = Self::find(&feeds, id)?;
+        feeds.remove(index);
+        self.save(&feeds)
+    }
+
+    /// Pause or resume checking a feed
+    pub fn set_enabled(&self, id: &str, enabled: bool) -> McpResult<Feed> {
+        self.update(id, |feed| feed.enabled = enabled)
+    }
+
+    /// Replace a feed's filter
+    pub fn set_filter(&self, id: &str, filter: FeedFilter) -> McpResult<Feed> {
+        self.update(id, |feed| feed.filter = filter)
+    }
+
+    /// Change a feed's settings
+    pub fn update(&self, id: &str, change: impl FnOnce(&mut Feed)) -> McpResult<Feed> {
+        let mut feeds = self.feeds.lock().unwrap();
+        let index = Self::find(&feeds, id)?;
+        change(&mut feeds[index]);
+        let feed = &mut feeds[index];
+        feed.interval_minutes = feed.interval_minutes.max(MIN_INTERVAL_MINUTES);
+        let feed = feed.clone();
+        self.save(&feeds)?;
+        Ok(feed)
+    }
+
+    /// Remember items as read so they stay out of digests
+    pub fn mark_read(&self, id: &str, item_ids: impl IntoIterator<Item = String>) -> McpResult<Feed> {
+        let mut feeds = self.feeds.lock().unwrap();
+        let index = Self::find(&feeds, id)?;
+        feeds[index].remember(item_ids);
+        let feed = feeds[index].clone();
+        self.save(&feeds)?;
+        Ok(feed)
+    }
+
+    /// Record the outcome of a check
+    fn record_check(&self, id: &str, title: Option<String>, digested: bool, error: Option<String>) -> McpResult<()> {
+        let now = clock::now();
+        let mut feeds = self.feeds.lock().unwrap();
+        let index = Self::find(&feeds, id)?;
+        let feed = &mut feeds[index];
+        feed.last_checked_at = Some(now);
+        if digested {
+            feed.last_digest_at = Some(now);
+        }
+        // A feed still named after its URL takes the title it gives itself
+        if let Some(title) = title.filter(|t| !t.is_empty()) {
+            if feed.title == feed.url {
+                feed.title = title;
+            }
+        }
+        feed.last_error = error;
+        self.save(&feeds)
+    }
+}
+
+static FEED_STORE: Lazy<Arc<FeedStore>> = Lazy::new(|| Arc::new(FeedStore::at(data_path("feeds.json"))));
+
+/// Get the global feed store
+pub fn get_feed_store() -> Arc<FeedStore> {
+    FEED_STORE.clone()
+}
+
+/// Fetches feeds and turns their new items into digests
+pub struct FeedDigester {
+    chat_service: Arc<ChatService>,
+    store: Arc<FeedStore>,
+    client: reqwest::Client,
+}
+
+impl FeedDigester {
+    /// Digester over the global feed store
+    pub fn new(chat_service: Arc<ChatService>) -> Self {
+        Self::with_store(chat_service, get_feed_store())
+    }
+
+    /// Digester over a given store
+    pub fn with_store(chat_service: Arc<ChatService>, store: Arc<FeedStore>) -> Self {
+        Self {
+            chat_service,
+            store,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Download and parse a feed
+    pub async fn fetch(&self, url: &str) -> McpResult<ParsedFeed> {
+        let response = self
+            .client
+            .get(url)
+            .timeout(REQUEST_TIMEOUT)
+            .header(
+                reqwest::header::ACCEPT,
+                "application/rss+xml, application/atom+xml, application/xml;q=0.9, text/xml;q=0.8",
+            )
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(format!("Failed to fetch feed {}: {}", url, e)))?;
+        if !response.status().is_success() {
+            return Err(McpError::Connection(format!(
+                "Feed {} answered {}",
+                url,
+                response.status()
+            )));
+        }
+        let body = response
+            .text()
+            .await
+            .map_err(|e| McpError::Connection(format!("Failed to read feed {}: {}", url, e)))?;
+        parse_feed(&body)
+    }
+
+    /// Check a feed now and deliver a digest of its new items, if any
+    pub async fn run(&self, id: &str) -> McpResult<Option<Digest>> {
+        let feed = self.store.get(id)?;
+        let result = self.digest(&feed).await;
+        let (title, digested, error) = match &result {
+            Ok((title, digest)) => (Some(title.clone()), digest.is_some(), None),
+            Err(e) => (None, false, Some(e.to_string())),
+        };
+        self.store.record_check(&feed.id, title, digested, error)?;
+        result.map(|(_, digest)| digest)
+    }
+
+    /// Check every enabled feed whose interval has passed; returns the
+    /// digests delivered
+    pub async fn run_due(&self) -> Vec<Digest> {
+        let now = clock::now();
+        let mut digests = Vec::new();
+        for feed in self.store.list().into_iter().filter(|f| f.is_due(now)) {
+            match self.run(&feed.id).await {
+                Ok(Some(digest)) => digests.push(digest),
+                Ok(None) => debug!("No new items in feed {}", feed.title),
+                Err(e) => warn!("Failed to check feed {}: {}", feed.title, e),
+            }
+        }
+        digests
+    }
+
+    /// Fetch, summarize and deliver; returns the feed's own title too
+    async fn digest(&self, feed: &Feed) -> McpResult<(String, Option<Digest>)> {
+        let parsed = self.fetch(&feed.url).await?;
+        let items: Vec<FeedItem> = feed
+            .unread(&parsed.items)
+            .into_iter()
+            .take(MAX_DIGEST_ITEMS)
+            .cloned()
+            .collect();
+        if items.is_empty() {
+            return Ok((parsed.title, None));
+        }
+
+        let title = if feed.title == feed.url && !parsed.title.is_empty() {
+            parsed.title.clone()
+        } else {
+            feed.title.clone()
+        };
+        let summary = self.summarize(feed, &items).await?;
+        let digest = Digest {
+            feed_id: feed.id.clone(),
+            feed_title: title,
+            items,
+            summary,
+            target: feed.target.clone(),
+            created_at: clock::now(),
+        };
+
+        if let DigestTarget::Conversation { conversation_id } = &digest.target {
+            let mut message = Message::assistant(digest.to_markdown());
+            message.metadata = Some(HashMap::from([(
+                DIGEST_METADATA_KEY.to_string(),
+                serde_json::json!({
+                    "feed_id": digest.feed_id,
+                    "items": digest.items.iter().map(|i| &i.id).collect::<Vec<_>>(),
+                }),
+            )]));
+            let mut conversation = self.chat_service.get_conversation(conversation_id).await?;
+            conversation.add_message(message);
+            self.chat_service.update_conversation(conversation).await?;
+        }
+        get_event_bus().emit(Topic::System, names::FEED_DIGEST, serde_json::to_value(&digest)?);
+
+        // Only now that the digest went out are its items done with
+        self.store
+            .mark_read(&feed.id, digest.items.iter().map(|i| i.id.clone()))?;
+        info!("Delivered a digest of {} items from feed {}", digest.items.len(), feed.title);
+        Ok((parsed.title, Some(digest)))
+    }
+
+    async fn model(&self, name: &str) -> McpResult<Model> {
+        let models = self.chat_service.available_models().await?;
+        models
+            .into_iter()
+            .find(|m| m.id == name || m.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| McpError::Config(format!("Model {} is not available", name)))
+    }
+
+    /// One request in a throwaway conversation
+    async fn summarize(&self, feed: &Feed, items: &[FeedItem]) -> McpResult<String> {
+        let model = match &feed.model {
+            Some(name) => Some(self.model(name).await?),
+            None => None,
+        };
+        let prompt = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let mut entry = format!("{}. {}", i + 1, item.title);
+                if let Some(link) = &item.link {
+                    entry.push_str(&format!("\nLink: {}", link));
+                }
+                if let Some(published) = item.published {
+                    entry.push_str(&format!("\nPublished: {}", published.format("%Y-%m-%d")));
+                }
+                if !item.summary.is_empty() {
+                    entry.push_str(&format!("\n{}", item.summary));
+                }
+                entry
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let conversation = self.chat_service.create_conversation("Feed digest", model).await?;
+        let result = async {
+            self.chat_service
+                .set_system_message(&conversation.id, DIGEST_SYSTEM_PROMPT)
+                .await?;
+            self.chat_service
+                .send_message(&conversation.id, &format!("New articles from {}:\n\n{}", feed.title, prompt))
+                .await
+        }
+        .await;
+
+        let cleanup = match self.chat_service.delete_conversation(&conversation.id).await {
+            Ok(()) => self.chat_service.purge_conversation(&conversation.id).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = cleanup {
+            debug!("Failed to delete the digest conversation: {}", e);
+        }
+
+        Ok(result?.text())
+    }
+}
+
+/// Check due feeds on an interval and deliver their digests
+pub fn spawn_feed_scheduler(chat_service: Arc<ChatService>, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let digester = FeedDigester::new(chat_service);
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            digester.run_due().await;
+        }
+    })
+}
@@ -0,0 +1,176 @@
+//! RSS and Atom parsing
+//!
+//! Feeds are small and loosely formed, so they are read with patterns for
+//! the few elements a digest needs rather than a full XML parser. Both RSS
+//! 2.0 (`<item>`) and Atom (`<entry>`) are understood, along with the common
+//! `content:encoded` and `dc:date` extensions.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use ring::digest;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{McpError, McpResult};
+
+/// Longest summary kept for an item
+const MAX_SUMMARY_CHARS: usize = 2000;
+
+static ITEM: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<(item|entry)(?:\s[^>]*)?>(.*?)</(?:item|entry)>").unwrap());
+static CDATA: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<!\[CDATA\[(.*?)\]\]>").unwrap());
+static COMMENT: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<!--.*?-->").unwrap());
+static TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+static ATOM_LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r"<link\b([^>]*)/?>").unwrap());
+static ENTITY: Lazy<Regex> = Lazy::new(|| Regex::new(r"&(#x[0-9a-fA-F]+|#[0-9]+|[a-zA-Z]+);").unwrap());
+
+/// An article in a feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedItem {
+    /// Stable ID: the guid or Atom ID, else the link, else a hash of the title
+    pub id: String,
+
+    /// Title
+    pub title: String,
+
+    /// Link to the article
+    pub link: Option<String>,
+
+    /// When it was published
+    pub published: Option<DateTime<Utc>>,
+
+    /// Plain-text summary or content
+    pub summary: String,
+}
+
+/// A parsed feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParsedFeed {
+    /// Feed title
+    pub title: String,
+
+    /// Items, newest first
+    pub items: Vec<FeedItem>,
+}
+
+/// Read an RSS or Atom document
+pub fn parse_feed(xml: &str) -> McpResult<ParsedFeed> {
+    let xml = COMMENT.replace_all(xml, "");
+    if !(xml.contains("<rss") || xml.contains("<feed") || xml.contains("<rdf:RDF")) {
+        return Err(McpError::InvalidRequest("Not an RSS or Atom feed".to_string()));
+    }
+
+    let head_end = ITEM.find(&xml).map_or(xml.len(), |m| m.start());
+    let title = element(&xml[..head_end], &["title"]).unwrap_or_default();
+
+    let mut items: Vec<FeedItem> = ITEM
+        .captures_iter(&xml)
+        .filter_map(|c| {
+            let atom = &c[1] == "entry";
+            item(c.get(2)?.as_str(), atom)
+        })
+        .collect();
+    // Feeds are usually newest first already; undated items keep their place
+    items.sort_by(|a, b| match (a.published, b.published) {
+        (Some(a), Some(b)) => b.cmp(&a),
+        _ => std::cmp::Ordering::Equal,
+    });
+
+    Ok(ParsedFeed {
+        title: plain_text(&title),
+        items,
+    })
+}
+
+fn item(block: &str, atom: bool) -> Option<FeedItem> {
+    let title = element(block, &["title"]).map(|t| plain_text(&t)).unwrap_or_default();
+    let link = if atom { atom_link(block) } else { element(block, &["link"]).map(|l| decode(&l).trim().to_string()) }
+        .filter(|l| !l.is_empty());
+    let summary = element(block, &["content:encoded", "content", "description", "summary"])
+        .map(|s| plain_text(&s))
+        .unwrap_or_default();
+    if title.is_empty() && summary.is_empty() {
+        return None;
+    }
+
+    let published = element(block, &["pubDate", "published", "updated", "dc:date"]).and_then(|date| {
+        let date = date.trim();
+        DateTime::parse_from_rfc2822(date)
+            .or_else(|_| DateTime::parse_from_rfc3339(date))
+            .ok()
+            .map(|d| d.with_timezone(&Utc))
+    });
+    let id = element(block, &["guid", "id"])
+        .map(|id| decode(&id).trim().to_string())
+        .filter(|id| !id.is_empty())
+        .or_else(|| link.clone())
+        .unwrap_or_else(|| {
+            let hash = digest::digest(&digest::SHA256, title.as_bytes());
+            hash.as_ref()[..12].iter().map(|b| format!("{:02x}", b)).collect()
+        });
+
+    let summary = if summary.chars().count() > MAX_SUMMARY_CHARS {
+        format!("{}…", summary.chars().take(MAX_SUMMARY_CHARS).collect::<String>())
+    } else {
+        summary
+    };
+    Some(FeedItem {
+        id,
+        title,
+        link,
+        published,
+        summary,
+    })
+}
+
+/// Raw content of the first of these elements found, CDATA unwrapped
+fn element(block: &str, names: &[&str]) -> Option<String> {
+    names.iter().find_map(|name| {
+        let pattern = format!(r"(?s)<{}(?:\s[^>]*)?>(.*?)</{}>", regex::escape(name), regex::escape(name));
+        let re = Regex::new(&pattern).ok()?;
+        let content = re.captures(block)?.get(1)?.as_str();
+        Some(CDATA.replace_all(content, "$1").into_owned())
+    })
+}
+
+/// Atom's page link: the `alternate` one, or the first without a `rel`
+fn atom_link(block: &str) -> Option<String> {
+    let attribute = |attrs: &str, name: &str| {
+        let re = Regex::new(&format!(r#"\b{}\s*=\s*["']([^"']*)["']"#, name)).ok()?;
+        re.captures(attrs).map(|c| decode(&c[1]))
+    };
+    ATOM_LINK
+        .captures_iter(block)
+        .map(|c| c[1].to_string())
+        .filter(|attrs| matches!(attribute(attrs, "rel").as_deref(), None | Some("alternate")))
+        .find_map(|attrs| attribute(&attrs, "href"))
+}
+
+/// Replace character and the common named entities
+fn decode(text: &str) -> String {
+    ENTITY
+        .replace_all(text, |c: &regex::Captures| {
+            let entity = &c[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                    None => entity.strip_prefix('#').and_then(|n| n.parse().ok()).and_then(char::from_u32),
+                },
+            };
+            decoded.map_or_else(|| c[0].to_string(), |ch| ch.to_string())
+        })
+        .into_owned()
+}
+
+/// Text of possibly escaped HTML, on one line
+fn plain_text(content: &str) -> String {
+    // Escaped HTML only turns into tags once decoded
+    let decoded = decode(&TAG.replace_all(content, " "));
+    let text = decode(&TAG.replace_all(&decoded, " "));
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,173 @@
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::FluentResource;
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+use unic_langid::LanguageIdentifier;
+
+use crate::config::get_settings;
+
+pub use fluent_bundle::{FluentArgs, FluentValue};
+
+/// Locale used when the requested one has no catalog, and for messages a
+/// catalog lacks
+pub const FALLBACK_LOCALE: &str = "en";
+
+/// Environment variable overriding the detected locale
+pub const LANG_ENV: &str = "MCP_LANG";
+
+/// Message catalogs shipped with the application
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../../locales/en/main.ftl")),
+    ("de", include_str!("../../locales/de/main.ftl")),
+];
+
+/// Locales with a catalog
+pub fn available_locales() -> Vec<&'static str> {
+    CATALOGS.iter().map(|(locale, _)| *locale).collect()
+}
+
+/// Catalog locale best matching a requested one such as `de-AT` or
+/// `de_DE.UTF-8`
+fn negotiate(requested: &str) -> Option<&'static str> {
+    // POSIX locales look like `de_DE.UTF-8@euro`
+    let tag = requested.split(['.', '@']).next().unwrap_or_default().replace('_', "-");
+    let langid: LanguageIdentifier = tag.parse().ok()?;
+    let canonical = langid.to_string();
+    CATALOGS
+        .iter()
+        .map(|(locale, _)| *locale)
+        .find(|locale| *locale == canonical)
+        .or_else(|| {
+            CATALOGS
+                .iter()
+                .map(|(locale, _)| *locale)
+                .find(|locale| *locale == langid.language.as_str())
+        })
+}
+
+/// Pick the locale from, in order: `MCP_LANG`, the UI language setting, the
+/// POSIX locale variables and the operating system
+pub fn detect_locale() -> String {
+    let setting = get_settings().lock().ok().and_then(|s| s.ui.language.clone());
+    let posix = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX");
+
+    let candidates = std::env::var(LANG_ENV)
+        .ok()
+        .into_iter()
+        .chain(setting)
+        .chain(posix)
+        .chain(sys_locale::get_locale());
+    for candidate in candidates {
+        if let Some(locale) = negotiate(&candidate) {
+            return locale.to_string();
+        }
+    }
+    FALLBACK_LOCALE.to_string()
+}
+
+fn bundle(locale: &str) -> Option<FluentBundle<FluentResource>> {
+    let (_, source) = CATALOGS.iter().find(|(l, _)| *l == locale)?;
+    let langid: LanguageIdentifier = locale.parse().ok()?;
+    let resource = FluentResource::try_new(source.to_string())
+        .unwrap_or_else(|(resource, errors)| {
+            warn!("Errors in the {} message catalog: {:?}", locale, errors);
+            resource
+        });
+
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Isolation marks show up as garbage in terminals
+    bundle.set_use_isolating(false);
+    if let Err(errors) = bundle.add_resource(resource) {
+        warn!("Duplicate messages in the {} catalog: {:?}", locale, errors);
+    }
+    Some(bundle)
+}
+
+/// Formats messages in one locale, falling back to English
+pub struct Localizer {
+    locale: String,
+    bundles: Vec<FluentBundle<FluentResource>>,
+}
+
+impl Localizer {
+    /// Localizer for a requested locale; unknown locales use English
+    pub fn new(requested: &str) -> Self {
+        let locale = negotiate(requested).unwrap_or(FALLBACK_LOCALE).to_string();
+        let mut locales = vec![locale.as_str()];
+        if locale != FALLBACK_LOCALE {
+            locales.push(FALLBACK_LOCALE);
+        }
+        let bundles = locales.into_iter().filter_map(bundle).collect();
+
+        Self { locale, bundles }
+    }
+
+    /// Catalog locale in use
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Format a message; unknown IDs are returned as-is
+    pub fn message(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        for bundle in &self.bundles {
+            let Some(pattern) = bundle.get_message(id).and_then(|m| m.value()) else {
+                continue;
+            };
+            let mut errors = Vec::new();
+            let text = bundle.format_pattern(pattern, args, &mut errors);
+            if !errors.is_empty() {
+                debug!("Errors formatting message {}: {:?}", id, errors);
+            }
+            return text.into_owned();
+        }
+
+        warn!("Missing message {}", id);
+        id.to_string()
+    }
+}
+
+static LOCALIZER: Lazy<RwLock<Localizer>> = Lazy::new(|| RwLock::new(Localizer::new(&detect_locale())));
+
+/// Switch the locale, e.g. for a `--lang` flag. Returns the catalog locale used.
+pub fn set_locale(requested: &str) -> String {
+    let localizer = Localizer::new(requested);
+    if negotiate(requested).is_none() {
+        warn!("No messages for locale '{}', using {}", requested, localizer.locale());
+    }
+    let locale = localizer.locale().to_string();
+    *LOCALIZER.write().unwrap() = localizer;
+    locale
+}
+
+/// Locale messages are formatted in
+pub fn current_locale() -> String {
+    LOCALIZER.read().unwrap().locale().to_string()
+}
+
+/// Format a message without arguments
+pub fn tr(id: &str) -> String {
+    LOCALIZER.read().unwrap().message(id, None)
+}
+
+/// Format a message with arguments
+pub fn tr_args(id: &str, args: &FluentArgs) -> String {
+    LOCALIZER.read().unwrap().message(id, Some(args))
+}
+
+/// Format a user-facing message in the current locale:
+/// `tr!("trash-purged", count = purged)`
+#[macro_export]
+macro_rules! tr {
+    ($id:expr) => {
+        $crate::i18n::tr($id)
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = $crate::i18n::FluentArgs::new();
+        $(args.set(stringify!($name), $crate::i18n::FluentValue::from($value));)+
+        $crate::i18n::tr_args($id, &args)
+    }};
+}
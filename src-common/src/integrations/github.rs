@@ -0,0 +1,958 @@
+//! GitHub
+//!
+//! Reads files, issues and pull requests through the REST and GraphQL APIs,
+//! with the token from the secret store when there is one. Public
+//! repositories work without a token, at GitHub's much lower anonymous rate
+//! limit.
+//!
+//! Responses are cached. A cached answer is used as is for a few minutes,
+//! then revalidated with its ETag, which GitHub doesn't count against the
+//! rate limit when unchanged. Close to the limit, cached answers are used
+//! however old they are, and once it is used up nothing is requested until
+//! it resets.
+
+use base64::Engine;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::auth::secret_store;
+use crate::error::{McpError, McpResult};
+use crate::integrations::ContextItem;
+use crate::utils::clock;
+
+/// Secret store entry of the GitHub token
+pub const TOKEN_SECRET: &str = "github_token";
+
+/// GitHub's API
+const API_URL: &str = "https://api.github.com";
+
+/// How long a cached answer is used without asking GitHub
+const FRESH_SECS: i64 = 300;
+
+/// Requests left below which cached answers are used however old
+const LOW_RATE_LIMIT: u64 = 10;
+
+/// Cached responses kept
+const MAX_CACHE_ENTRIES: usize = 256;
+
+/// Most lines a snippet has
+pub const MAX_SNIPPET_LINES: usize = 400;
+
+/// Most comments imported with an issue or pull request
+const MAX_COMMENTS: usize = 50;
+
+/// Most changed files listed for a pull request
+const MAX_FILES: usize = 100;
+
+/// Request timeout
+const TIMEOUT_SECS: u64 = 15;
+
+static FILE_URL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^https?://github\.com/([\w.-]+)/([\w.-]+)/blob/([^/]+)/([^#?]+)(?:\?[^#]*)?(?:#L(\d+)(?:-L?(\d+))?)?$").unwrap()
+});
+static THREAD_URL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^https?://github\.com/([\w.-]+)/([\w.-]+)/(issues|pull)/(\d+)").unwrap());
+static THREAD_SHORT: Lazy<Regex> = Lazy::new(|| Regex::new(r"^([\w.-]+)/([\w.-]+)#(\d+)$").unwrap());
+static FILE_SHORT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([\w.-]+)/([\w.-]+)/([^@#\s]+)(?:@([^#\s]+))?(?:#L?(\d+)(?:-L?(\d+))?)?$").unwrap());
+
+/// Something on GitHub
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GitHubRef {
+    /// A file, or some of its lines
+    File {
+        owner: String,
+        repo: String,
+        path: String,
+        /// Branch, tag or commit; the default branch when unset
+        reference: Option<String>,
+        /// First and last line, 1-based
+        lines: Option<(usize, usize)>,
+    },
+    /// An issue; resolves to a pull request when it is one
+    Issue { owner: String, repo: String, number: u64 },
+    /// A pull request
+    PullRequest { owner: String, repo: String, number: u64 },
+}
+
+impl std::str::FromStr for GitHubRef {
+    type Err = McpError;
+
+    /// Read a GitHub URL, `owner/repo#12`, or `owner/repo/path[@ref][#L10-L20]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let lines = |start: Option<regex::Match>, end: Option<regex::Match>| {
+            start.map(|start| {
+                let start: usize = start.as_str().parse().unwrap_or(1).max(1);
+                let end = end.and_then(|e| e.as_str().parse().ok()).unwrap_or(start);
+                (start, end.max(start))
+            })
+        };
+
+        if let Some(c) = THREAD_URL.captures(s) {
+            let number = c[4].parse().map_err(|_| invalid(s))?;
+            let (owner, repo) = (c[1].to_string(), c[2].to_string());
+            return Ok(if &c[3] == "pull" {
+                GitHubRef::PullRequest { owner, repo, number }
+            } else {
+                GitHubRef::Issue { owner, repo, number }
+            });
+        }
+        if let Some(c) = FILE_URL.captures(s) {
+            return Ok(GitHubRef::File {
+                owner: c[1].to_string(),
+                repo: c[2].to_string(),
+                path: c[4].to_string(),
+                reference: Some(c[3].to_string()),
+                lines: lines(c.get(5), c.get(6)),
+            });
+        }
+        if let Some(c) = THREAD_SHORT.captures(s) {
+            return Ok(GitHubRef::Issue {
+                owner: c[1].to_string(),
+                repo: c[2].to_string(),
+                number: c[3].parse().map_err(|_| invalid(s))?,
+            });
+        }
+        if let Some(c) = FILE_SHORT.captures(s) {
+            return Ok(GitHubRef::File {
+                owner: c[1].to_string(),
+                repo: c[2].to_string(),
+                path: c[3].to_string(),
+                reference: c.get(4).map(|r| r.as_str().to_string()),
+                lines: lines(c.get(5), c.get(6)),
+            });
+        }
+        Err(invalid(s))
+    }
+}
+
+fn invalid(reference: &str) -> McpError {
+    McpError::InvalidRequest(format!(
+        "'{}' is not a GitHub link, owner/repo#number or owner/repo/path",
+        reference
+    ))
+}
+
+/// Lines of a file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    /// `owner/repo`
+    pub repository: String,
+
+    /// File path
+    pub path: String,
+
+    /// Branch, tag or commit, if one was asked for
+    pub reference: Option<String>,
+
+    /// First line, 1-based
+    pub start: usize,
+
+    /// Last line
+    pub end: usize,
+
+    /// Lines in the file
+    pub total_lines: usize,
+
+    /// Code block language
+    pub language: Option<String>,
+
+    /// The lines
+    pub content: String,
+
+    /// Link to the lines
+    pub url: String,
+}
+
+impl Snippet {
+    /// The snippet as a titled code block
+    pub fn to_markdown(&self) -> String {
+        let mut heading = format!("`{}/{}`", self.repository, self.path);
+        if self.start > 1 || self.end < self.total_lines {
+            heading.push_str(&format!(", lines {}-{} of {}", self.start, self.end, self.total_lines));
+        }
+        if let Some(reference) = &self.reference {
+            heading.push_str(&format!(" at `{}`", reference));
+        }
+        let fence = if self.content.contains("```") { "````" } else { "```" };
+        format!(
+            "{}\n\n{}{}\n{}\n{}",
+            heading,
+            fence,
+            self.language.as_deref().unwrap_or(""),
+            self.content.trim_end_matches('\n'),
+            fence
+        )
+    }
+
+    /// The snippet as conversation context
+    pub fn to_context(&self) -> ContextItem {
+        ContextItem {
+            source: "github".to_string(),
+            reference: format!("{}/{}#L{}-L{}", self.repository, self.path, self.start, self.end),
+            url: self.url.clone(),
+            title: self.path.clone(),
+            text: self.to_markdown(),
+        }
+    }
+}
+
+/// Whether a thread is an issue or a pull request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadKind {
+    Issue,
+    PullRequest,
+}
+
+/// A comment or review on an issue or pull request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment {
+    /// Login of the author
+    pub author: String,
+
+    /// Text
+    pub body: String,
+
+    /// When it was written
+    pub created_at: Option<String>,
+
+    /// Review verdict (`APPROVED`, `CHANGES_REQUESTED`, ...) for reviews
+    pub review_state: Option<String>,
+}
+
+/// A file changed by a pull request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedFile {
+    /// Path
+    pub path: String,
+
+    /// Lines added
+    pub additions: u64,
+
+    /// Lines removed
+    pub deletions: u64,
+}
+
+/// An issue or pull request with its discussion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Thread {
+    /// Issue or pull request
+    pub kind: ThreadKind,
+
+    /// `owner/repo`
+    pub repository: String,
+
+    /// Number
+    pub number: u64,
+
+    /// Title
+    pub title: String,
+
+    /// `open`, `closed` or `merged`
+    pub state: String,
+
+    /// Login of the author
+    pub author: String,
+
+    /// Link
+    pub url: String,
+
+    /// Description
+    pub body: String,
+
+    /// Labels
+    pub labels: Vec<String>,
+
+    /// Comments and reviews, oldest first
+    pub comments: Vec<Comment>,
+
+    /// Changed files of a pull request
+    pub files: Vec<ChangedFile>,
+
+    /// Base and head branch of a pull request
+    pub branches: Option<(String, String)>,
+}
+
+impl Thread {
+    /// The thread as Markdown
+    pub fn to_markdown(&self) -> String {
+        let kind = match self.kind {
+            ThreadKind::Issue => "Issue",
+            ThreadKind::PullRequest => "Pull request",
+        };
+        let mut md = format!("## {} {}#{}: {}\n\n", kind, self.repository, self.number, self.title);
+        md.push_str(&format!("State: {} · Author: @{}", self.state.to_lowercase(), self.author));
+        if let Some((base, head)) = &self.branches {
+            md.push_str(&format!(" · `{}` ← `{}`", base, head));
+        }
+        if !self.labels.is_empty() {
+            md.push_str(&format!(" · Labels: {}", self.labels.join(", ")));
+        }
+        md.push_str(&format!("\n{}\n", self.url));
+
+        if !self.body.trim().is_empty() {
+            md.push_str(&format!("\n{}\n", self.body.trim()));
+        }
+        if !self.files.is_empty() {
+            md.push_str("\n### Changed files\n\n");
+            for file in &self.files {
+                md.push_str(&format!("- `{}` (+{} -{})\n", file.path, file.additions, file.deletions));
+            }
+        }
+        if !self.comments.is_empty() {
+            md.push_str("\n### Discussion\n");
+            for comment in &self.comments {
+                let verdict = comment
+                    .review_state
+                    .as_ref()
+                    .map(|state| format!(" reviewed ({})", state.to_lowercase().replace('_', " ")))
+                    .unwrap_or_default();
+                let date = comment
+                    .created_at
+                    .as_deref()
+                    .map(|d| format!(" on {}", &d[..d.len().min(10)]))
+                    .unwrap_or_default();
+                md.push_str(&format!("\n**@{}**{}{}:\n{}\n", comment.author, verdict, date, comment.body.trim()));
+            }
+        }
+        md
+    }
+
+    /// The thread as conversation context
+    pub fn to_context(&self) -> ContextItem {
+        ContextItem {
+            source: "github".to_string(),
+            reference: format!("{}#{}", self.repository, self.number),
+            url: self.url.clone(),
+            title: self.title.clone(),
+            text: self.to_markdown(),
+        }
+    }
+}
+
+/// GitHub's rate limit as of the last response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Requests allowed per window
+    pub limit: u64,
+
+    /// Requests left in the window
+    pub remaining: u64,
+
+    /// When the window resets
+    pub reset_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    etag: Option<String>,
+    body: Value,
+    fetched_at: DateTime<Utc>,
+}
+
+const PULL_REQUEST_QUERY: &str = r#"
+query($owner: String!, $repo: String!, $number: Int!) {
+  repository(owner: $owner, name: $repo) {
+    pullRequest(number: $number) {
+      title body state url baseRefName headRefName
+      author { login }
+      labels(first: 20) { nodes { name } }
+      files(first: 100) { nodes { path additions deletions } }
+      comments(first: 50) { nodes { author { login } body createdAt } }
+      reviews(first: 50) { nodes { author { login } body state submittedAt } }
+    }
+  }
+}"#;
+
+/// GitHub API client
+pub struct GitHubClient {
+    api_url: String,
+    token: RwLock<Option<String>>,
+    client: reqwest::Client,
+    cache: Mutex<HashMap<String, CachedResponse>>,
+    rate_limit: Mutex<Option<RateLimit>>,
+}
+
+impl GitHubClient {
+    /// Create a client; without a token only public repositories are readable
+    pub fn new(token: Option<String>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(TIMEOUT_SECS))
+            .user_agent(concat!("papin/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+        Self {
+            api_url: API_URL.to_string(),
+            token: RwLock::new(token),
+            client,
+            cache: Mutex::new(HashMap::new()),
+            rate_limit: Mutex::new(None),
+        }
+    }
+
+    /// Create a client with the token from the secret store
+    pub fn from_secret_store() -> Self {
+        let token = secret_store().get(TOKEN_SECRET).unwrap_or_else(|e| {
+            warn!("Failed to read the GitHub token: {}", e);
+            None
+        });
+        Self::new(token)
+    }
+
+    /// Talk to another API, e.g. GitHub Enterprise at `https://host/api/v3`
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Replace the token; cached answers are dropped since access changed
+    pub fn set_token(&self, token: Option<String>) {
+        *self.token.write().unwrap() = token;
+        self.cache.lock().unwrap().clear();
+        *self.rate_limit.lock().unwrap() = None;
+    }
+
+    /// Whether requests are authenticated
+    pub fn has_token(&self) -> bool {
+        self.token.read().unwrap().is_some()
+    }
+
+    /// Rate limit reported with the last response
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    /// Ask GitHub for the rate limit; this request doesn't count against it
+    pub async fn check_rate_limit(&self) -> McpResult<Option<RateLimit>> {
+        let response = self
+            .request(self.client.get(format!("{}/rate_limit", self.api_url)))
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(format!("GitHub request failed: {}", e)))?;
+        self.record_rate_limit(response.headers());
+        let status = response.status();
+        if !status.is_success() {
+            return Err(self.error(status, response, "rate_limit").await);
+        }
+        Ok(self.rate_limit())
+    }
+
+    /// Requests left before the limit resets, when known
+    fn remaining(&self) -> Option<(u64, DateTime<Utc>)> {
+        let limit = (*self.rate_limit.lock().unwrap())?;
+        (limit.reset_at > clock::now()).then_some((limit.remaining, limit.reset_at))
+    }
+
+    fn record_rate_limit(&self, headers: &reqwest::header::HeaderMap) {
+        let number = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<i64>().ok())
+        };
+        if let (Some(limit), Some(remaining), Some(reset)) = (
+            number("x-ratelimit-limit"),
+            number("x-ratelimit-remaining"),
+            number("x-ratelimit-reset"),
+        ) {
+            if let Some(reset_at) = Utc.timestamp_opt(reset, 0).single() {
+                *self.rate_limit.lock().unwrap() = Some(RateLimit {
+                    limit: limit.max(0) as u64,
+                    remaining: remaining.max(0) as u64,
+                    reset_at,
+                });
+            }
+        }
+    }
+
+    fn cached(&self, key: &str) -> Option<CachedResponse> {
+        self.cache.lock().unwrap().get(key).cloned()
+    }
+
+    fn store(&self, key: &str, etag: Option<String>, body: &Value) {
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHE_ENTRIES && !cache.contains_key(key) {
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, c)| c.fetched_at)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(
+            key.to_string(),
+            CachedResponse {
+                etag,
+                body: body.clone(),
+                fetched_at: clock::now(),
+            },
+        );
+    }
+
+    /// A cached answer that can stand in for a request, or an error when
+    /// the rate limit is used up and there is none
+    fn without_request(&self, key: &str) -> McpResult<Option<Value>> {
+        let cached = self.cached(key);
+        let remaining = self.remaining();
+        if let Some(cached) = &cached {
+            let fresh = clock::now() - cached.fetched_at < Duration::seconds(FRESH_SECS);
+            let low = remaining.is_some_and(|(left, _)| left < LOW_RATE_LIMIT);
+            if fresh || low {
+                return Ok(Some(cached.body.clone()));
+            }
+        }
+        if let Some((0, reset_at)) = remaining {
+            return Err(McpError::RateLimit(format!(
+                "GitHub's rate limit is used up until {}{}",
+                reset_at.format("%H:%M UTC"),
+                if self.has_token() { "" } else { "; a token raises it" }
+            )));
+        }
+        Ok(None)
+    }
+
+    fn request(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let request = request
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28");
+        match self.token.read().unwrap().as_deref() {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// GET a REST endpoint, e.g. `/repos/owner/repo/issues/1`
+    pub async fn get(&self, path: &str) -> McpResult<Value> {
+        let url = format!("{}{}", self.api_url, path);
+        if let Some(body) = self.without_request(&url)? {
+            debug!("GitHub cache hit for {}", path);
+            return Ok(body);
+        }
+
+        let mut request = self.request(self.client.get(&url));
+        let cached = self.cached(&url);
+        if let Some(etag) = cached.as_ref().and_then(|c| c.etag.as_ref()) {
+            request = request.header("If-None-Match", etag);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(format!("GitHub request failed: {}", e)))?;
+        self.record_rate_limit(response.headers());
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                self.store(&url, cached.etag, &cached.body);
+                return Ok(cached.body);
+            }
+        }
+        if !status.is_success() {
+            return Err(self.error(status, response, path).await);
+        }
+
+        let etag = response
+            .headers()
+            .get("etag")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| McpError::Protocol(format!("Unexpected GitHub response: {}", e)))?;
+        self.store(&url, etag, &body);
+        Ok(body)
+    }
+
+    /// Run a GraphQL query; GitHub only answers these with a token
+    pub async fn graphql(&self, query: &str, variables: Value) -> McpResult<Value> {
+        if !self.has_token() {
+            return Err(McpError::Authentication("GitHub's GraphQL API needs a token".to_string()));
+        }
+        let key = format!("graphql:{}:{}", query, variables);
+        if let Some(body) = self.without_request(&key)? {
+            return Ok(body);
+        }
+
+        let response = self
+            .request(self.client.post(format!("{}/graphql", self.api_url)))
+            .json(&serde_json::json!({ "query": query, "variables": variables }))
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(format!("GitHub request failed: {}", e)))?;
+        self.record_rate_limit(response.headers());
+        let status = response.status();
+        if !status.is_success() {
+            return Err(self.error(status, response, "graphql").await);
+        }
+
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| McpError::Protocol(format!("Unexpected GitHub response: {}", e)))?;
+        if let Some(error) = body["errors"].as_array().and_then(|e| e.first()) {
+            let message = error["message"].as_str().unwrap_or("unknown error");
+            return Err(McpError::InvalidRequest(format!("GitHub: {}", message)));
+        }
+        let data = body["data"].clone();
+        self.store(&key, None, &data);
+        Ok(data)
+    }
+
+    async fn error(&self, status: reqwest::StatusCode, response: reqwest::Response, what: &str) -> McpError {
+        let message = response
+            .json::<Value>()
+            .await
+            .ok()
+            .and_then(|body| body["message"].as_str().map(str::to_string))
+            .unwrap_or_else(|| status.to_string());
+        let exhausted = self.remaining().is_some_and(|(left, _)| left == 0);
+        match status.as_u16() {
+            403 | 429 if exhausted => McpError::RateLimit(format!("GitHub's rate limit is used up: {}", message)),
+            401 => McpError::Authentication("GitHub rejected the token".to_string()),
+            403 => McpError::Authentication(format!("GitHub denied access: {}", message)),
+            404 if self.has_token() => McpError::InvalidRequest(format!("Not found on GitHub: {}", what)),
+            404 => McpError::InvalidRequest(format!(
+                "Not found on GitHub: {} (private repositories need a token)",
+                what
+            )),
+            _ => McpError::Protocol(format!("GitHub answered {}: {}", status, message)),
+        }
+    }
+
+    /// Lines of a file; the whole file, up to [`MAX_SNIPPET_LINES`], when no
+    /// range is given
+    pub async fn snippet(
+        &self,
+        owner: &str,
+        repo: &str,
+        path: &str,
+        reference: Option<&str>,
+        lines: Option<(usize, usize)>,
+    ) -> McpResult<Snippet> {
+        let mut endpoint = format!("/repos/{}/{}/contents/{}", owner, repo, encode_path(path.trim_matches('/')));
+        if let Some(reference) = reference {
+            endpoint.push_str(&format!("?ref={}", encode_path(reference)));
+        }
+        let file = self.get(&endpoint).await?;
+
+        if file.is_array() || file["type"].as_str() != Some("file") {
+            return Err(McpError::InvalidRequest(format!("{} is not a file", path)));
+        }
+        if file["encoding"].as_str() != Some("base64") {
+            return Err(McpError::InvalidRequest(format!("{} is too large to show", path)));
+        }
+        let encoded: String = file["content"].as_str().unwrap_or_default().split_whitespace().collect();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| McpError::Protocol(format!("Invalid file content from GitHub: {}", e)))?;
+        let text = String::from_utf8(bytes).map_err(|_| McpError::InvalidRequest(format!("{} is not a text file", path)))?;
+
+        let all: Vec<&str> = text.lines().collect();
+        let total_lines = all.len();
+        let (start, end) = match lines {
+            Some((start, _)) if start > total_lines.max(1) => {
+                return Err(McpError::InvalidRequest(format!(
+                    "{} has only {} lines",
+                    path, total_lines
+                )));
+            }
+            Some((start, end)) => (start, end.min(total_lines)),
+            None => (1, total_lines),
+        };
+        let end = end.min(start + MAX_SNIPPET_LINES - 1).max(start.min(total_lines));
+        let content = all
+            .get(start.saturating_sub(1)..end)
+            .unwrap_or_default()
+            .join("\n");
+
+        let html_url = file["html_url"].as_str().unwrap_or_default();
+        let url = if start > 1 || end < total_lines {
+            format!("{}#L{}-L{}", html_url, start, end)
+        } else {
+            html_url.to_string()
+        };
+        Ok(Snippet {
+            repository: format!("{}/{}", owner, repo),
+            path: path.trim_matches('/').to_string(),
+            reference: reference.map(str::to_string),
+            start,
+            end,
+            total_lines,
+            language: language_of(path).map(str::to_string),
+            content,
+            url,
+        })
+    }
+
+    /// An issue with its comments; pull requests found this way are read as such
+    pub async fn issue(&self, owner: &str, repo: &str, number: u64) -> McpResult<Thread> {
+        let issue = self.get(&format!("/repos/{}/{}/issues/{}", owner, repo, number)).await?;
+        if !issue["pull_request"].is_null() {
+            return self.pull_request(owner, repo, number).await;
+        }
+        let comments = if issue["comments"].as_u64().unwrap_or(0) > 0 {
+            self.rest_comments(owner, repo, number).await?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Thread {
+            kind: ThreadKind::Issue,
+            repository: format!("{}/{}", owner, repo),
+            number,
+            title: string(&issue["title"]),
+            state: string(&issue["state"]),
+            author: string(&issue["user"]["login"]),
+            url: string(&issue["html_url"]),
+            body: string(&issue["body"]),
+            labels: names(&issue["labels"], "name"),
+            comments,
+            files: Vec::new(),
+            branches: None,
+        })
+    }
+
+    /// A pull request with its changed files, comments and reviews
+    pub async fn pull_request(&self, owner: &str, repo: &str, number: u64) -> McpResult<Thread> {
+        if self.has_token() {
+            self.pull_request_graphql(owner, repo, number).await
+        } else {
+            self.pull_request_rest(owner, repo, number).await
+        }
+    }
+
+    /// One query for everything
+    async fn pull_request_graphql(&self, owner: &str, repo: &str, number: u64) -> McpResult<Thread> {
+        let data = self
+            .graphql(
+                PULL_REQUEST_QUERY,
+                serde_json::json!({ "owner": owner, "repo": repo, "number": number }),
+            )
+            .await?;
+        let pr = &data["repository"]["pullRequest"];
+        if pr.is_null() {
+            return Err(McpError::InvalidRequest(format!("Not found on GitHub: {}/{}#{}", owner, repo, number)));
+        }
+
+        let mut comments: Vec<Comment> = nodes(&pr["comments"])
+            .iter()
+            .map(|c| Comment {
+                author: login(&c["author"]),
+                body: string(&c["body"]),
+                created_at: c["createdAt"].as_str().map(str::to_string),
+                review_state: None,
+            })
+            .collect();
+        // Approvals without a text still say something
+        comments.extend(nodes(&pr["reviews"]).iter().map(|r| Comment {
+            author: login(&r["author"]),
+            body: string(&r["body"]),
+            created_at: r["submittedAt"].as_str().map(str::to_string),
+            review_state: r["state"].as_str().map(str::to_string),
+        }));
+        comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+        Ok(Thread {
+            kind: ThreadKind::PullRequest,
+            repository: format!("{}/{}", owner, repo),
+            number,
+            title: string(&pr["title"]),
+            state: string(&pr["state"]).to_lowercase(),
+            author: login(&pr["author"]),
+            url: string(&pr["url"]),
+            body: string(&pr["body"]),
+            labels: nodes(&pr["labels"]).iter().map(|l| string(&l["name"])).collect(),
+            comments,
+            files: nodes(&pr["files"]).iter().map(changed_file).collect(),
+            branches: Some((string(&pr["baseRefName"]), string(&pr["headRefName"]))),
+        })
+    }
+
+    /// The anonymous way: the pull request, its files and its comments
+    async fn pull_request_rest(&self, owner: &str, repo: &str, number: u64) -> McpResult<Thread> {
+        let pr = self.get(&format!("/repos/{}/{}/pulls/{}", owner, repo, number)).await?;
+        let files = self
+            .get(&format!("/repos/{}/{}/pulls/{}/files?per_page={}", owner, repo, number, MAX_FILES))
+            .await?;
+        let comments = self.rest_comments(owner, repo, number).await?;
+
+        let state = if pr["merged"].as_bool().unwrap_or(false) {
+            "merged".to_string()
+        } else {
+            string(&pr["state"])
+        };
+        Ok(Thread {
+            kind: ThreadKind::PullRequest,
+            repository: format!("{}/{}", owner, repo),
+            number,
+            title: string(&pr["title"]),
+            state,
+            author: string(&pr["user"]["login"]),
+            url: string(&pr["html_url"]),
+            body: string(&pr["body"]),
+            labels: names(&pr["labels"], "name"),
+            comments,
+            files: files
+                .as_array()
+                .map(|files| {
+                    files
+                        .iter()
+                        .map(|f| ChangedFile {
+                            path: string(&f["filename"]),
+                            additions: f["additions"].as_u64().unwrap_or(0),
+                            deletions: f["deletions"].as_u64().unwrap_or(0),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            branches: Some((string(&pr["base"]["ref"]), string(&pr["head"]["ref"]))),
+        })
+    }
+
+    async fn rest_comments(&self, owner: &str, repo: &str, number: u64) -> McpResult<Vec<Comment>> {
+        let comments = self
+            .get(&format!(
+                "/repos/{}/{}/issues/{}/comments?per_page={}",
+                owner, repo, number, MAX_COMMENTS
+            ))
+            .await?;
+        Ok(comments
+            .as_array()
+            .map(|comments| {
+                comments
+                    .iter()
+                    .map(|c| Comment {
+                        author: string(&c["user"]["login"]),
+                        body: string(&c["body"]),
+                        created_at: c["created_at"].as_str().map(str::to_string),
+                        review_state: None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Fetch anything a [`GitHubRef`] points at, as conversation context
+    pub async fn fetch(&self, reference: &GitHubRef) -> McpResult<ContextItem> {
+        match reference {
+            GitHubRef::File {
+                owner,
+                repo,
+                path,
+                reference,
+                lines,
+            } => Ok(self
+                .snippet(owner, repo, path, reference.as_deref(), *lines)
+                .await?
+                .to_context()),
+            GitHubRef::Issue { owner, repo, number } => Ok(self.issue(owner, repo, *number).await?.to_context()),
+            GitHubRef::PullRequest { owner, repo, number } => {
+                Ok(self.pull_request(owner, repo, *number).await?.to_context())
+            }
+        }
+    }
+}
+
+fn string(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+fn login(author: &Value) -> String {
+    // Deleted accounts show up as null authors
+    author["login"].as_str().unwrap_or("ghost").to_string()
+}
+
+fn names(list: &Value, field: &str) -> Vec<String> {
+    list.as_array()
+        .map(|items| items.iter().filter_map(|i| i[field].as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+fn nodes(connection: &Value) -> Vec<Value> {
+    connection["nodes"].as_array().cloned().unwrap_or_default()
+}
+
+fn changed_file(file: &Value) -> ChangedFile {
+    ChangedFile {
+        path: string(&file["path"]),
+        additions: file["additions"].as_u64().unwrap_or(0),
+        deletions: file["deletions"].as_u64().unwrap_or(0),
+    }
+}
+
+/// Percent-encode a repository path, keeping its slashes
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Code block language for a file name
+fn language_of(path: &str) -> Option<&'static str> {
+    let extension = path.rsplit('.').next()?.to_lowercase();
+    let language = match extension.as_str() {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "ts" => "typescript",
+        "tsx" => "tsx",
+        "jsx" => "jsx",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" => "cpp",
+        "cs" => "csharp",
+        "rb" => "ruby",
+        "php" => "php",
+        "swift" => "swift",
+        "sh" | "bash" => "bash",
+        "md" => "markdown",
+        "json" => "json",
+        "yml" | "yaml" => "yaml",
+        "toml" => "toml",
+        "html" => "html",
+        "css" => "css",
+        "sql" => "sql",
+        _ => return None,
+    };
+    Some(language)
+}
+
+static GITHUB_CLIENT: Lazy<Arc<GitHubClient>> = Lazy::new(|| Arc::new(GitHubClient::from_secret_store()));
+
+/// Get the global GitHub client
+pub fn get_github_client() -> Arc<GitHubClient> {
+    GITHUB_CLIENT.clone()
+}
+
+/// Store or remove the GitHub token, and use it from now on
+pub fn set_token(token: Option<&str>) -> McpResult<()> {
+    match token.map(str::trim).filter(|t| !t.is_empty()) {
+        Some(token) => {
+            secret_store().set(TOKEN_SECRET, token)?;
+            get_github_client().set_token(Some(token.to_string()));
+        }
+        None => {
+            secret_store().delete(TOKEN_SECRET)?;
+            get_github_client().set_token(None);
+        }
+    }
+    Ok(())
+}
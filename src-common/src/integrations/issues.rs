@@ -0,0 +1,913 @@
+//! Jira and Linear issues
+//!
+//! Reads an issue from its link or key, with its description, comments and
+//! the pull requests linked to it, and turns it into conversation context.
+//! Which of those go into the context is chosen per import, falling back to
+//! the configured fields. Jira is reached at the configured site with an API
+//! token (and the account's email on Jira Cloud), Linear with a personal API
+//! key; both are kept in the secret store.
+//!
+//! Issues are cached for a few minutes, so a model that looks at the same
+//! issue several times in a conversation doesn't refetch it each time.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::auth::secret_store;
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::integrations::ContextItem;
+use crate::utils::clock;
+
+/// Linear's GraphQL API
+const LINEAR_API_URL: &str = "https://api.linear.app/graphql";
+
+/// How long a fetched issue is used without refetching it
+const FRESH_SECS: i64 = 300;
+
+/// Issues kept in the cache
+const MAX_CACHE_ENTRIES: usize = 128;
+
+/// Most comments read with an issue
+const MAX_COMMENTS: usize = 50;
+
+/// Most results of a search
+pub const MAX_SEARCH_RESULTS: usize = 20;
+
+/// Request timeout
+const TIMEOUT_SECS: u64 = 15;
+
+static KEY: Lazy<Regex> = Lazy::new(|| Regex::new(r"^([A-Za-z][A-Za-z0-9_]*)-(\d+)$").unwrap());
+static JIRA_URL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(https?://[^/]+)/(?:browse/|.*[?&]selectedIssue=)([A-Za-z][A-Za-z0-9_]*-\d+)").unwrap()
+});
+static LINEAR_URL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^https?://linear\.app/[^/]+/issue/([A-Za-z][A-Za-z0-9_]*-\d+)").unwrap());
+
+/// An issue tracker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueTracker {
+    Jira,
+    Linear,
+}
+
+impl IssueTracker {
+    /// Stable identifier
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueTracker::Jira => "jira",
+            IssueTracker::Linear => "linear",
+        }
+    }
+
+    /// Name shown to people
+    pub fn name(&self) -> &'static str {
+        match self {
+            IssueTracker::Jira => "Jira",
+            IssueTracker::Linear => "Linear",
+        }
+    }
+
+    /// Secret store entry of the tracker's token
+    fn secret_key(&self) -> &'static str {
+        match self {
+            IssueTracker::Jira => "jira_token",
+            IssueTracker::Linear => "linear_api_key",
+        }
+    }
+}
+
+impl std::str::FromStr for IssueTracker {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "jira" => Ok(IssueTracker::Jira),
+            "linear" => Ok(IssueTracker::Linear),
+            other => Err(McpError::InvalidRequest(format!(
+                "Unknown issue tracker '{}'; expected jira or linear",
+                other
+            ))),
+        }
+    }
+}
+
+/// Part of an issue that can go into a conversation; the title and key
+/// always do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueField {
+    Status,
+    Assignee,
+    Labels,
+    Description,
+    Comments,
+    PullRequests,
+}
+
+impl IssueField {
+    /// Every field, in the order they are shown
+    pub const ALL: [IssueField; 6] = [
+        IssueField::Status,
+        IssueField::Assignee,
+        IssueField::Labels,
+        IssueField::Description,
+        IssueField::Comments,
+        IssueField::PullRequests,
+    ];
+
+    /// Stable identifier
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueField::Status => "status",
+            IssueField::Assignee => "assignee",
+            IssueField::Labels => "labels",
+            IssueField::Description => "description",
+            IssueField::Comments => "comments",
+            IssueField::PullRequests => "pull_requests",
+        }
+    }
+
+    /// Read a comma-separated list such as `description,comments`
+    pub fn parse_list(list: &str) -> McpResult<Vec<IssueField>> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+}
+
+impl std::str::FromStr for IssueField {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase().replace('-', "_");
+        IssueField::ALL
+            .into_iter()
+            .find(|f| f.as_str() == s || (s == "prs" && *f == IssueField::PullRequests))
+            .ok_or_else(|| {
+                McpError::InvalidRequest(format!(
+                    "Unknown issue field '{}'; expected one of {}",
+                    s,
+                    IssueField::ALL.map(|f| f.as_str()).join(", ")
+                ))
+            })
+    }
+}
+
+fn all_fields() -> Vec<IssueField> {
+    IssueField::ALL.to_vec()
+}
+
+/// Issue tracker settings; tokens are kept in the secret store
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssueSettings {
+    /// Jira site, e.g. `https://example.atlassian.net`
+    #[serde(default)]
+    pub jira_url: Option<String>,
+
+    /// Account email for Jira Cloud; without it the token is sent as a
+    /// Data Center personal access token
+    #[serde(default)]
+    pub jira_email: Option<String>,
+
+    /// Tracker that bare keys like `ENG-42` are looked up in when both are
+    /// set up
+    #[serde(default)]
+    pub default_tracker: Option<IssueTracker>,
+
+    /// Fields imported when none are asked for
+    #[serde(default = "all_fields")]
+    pub fields: Vec<IssueField>,
+}
+
+impl Default for IssueSettings {
+    fn default() -> Self {
+        Self {
+            jira_url: None,
+            jira_email: None,
+            default_tracker: None,
+            fields: all_fields(),
+        }
+    }
+}
+
+impl IssueSettings {
+    fn path() -> PathBuf {
+        data_path("issues.json")
+    }
+
+    /// Load the settings, falling back to the defaults
+    pub fn load() -> Self {
+        match fs::read_to_string(Self::path()) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Invalid issue tracker settings, using defaults: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Persist the settings
+    pub fn save(&self) -> McpResult<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+static ISSUE_SETTINGS: Lazy<Arc<RwLock<IssueSettings>>> = Lazy::new(|| Arc::new(RwLock::new(IssueSettings::load())));
+
+/// Get the global issue tracker settings
+pub fn get_issue_settings() -> Arc<RwLock<IssueSettings>> {
+    ISSUE_SETTINGS.clone()
+}
+
+/// Store or remove the token of a tracker
+pub fn set_token(tracker: IssueTracker, token: Option<&str>) -> McpResult<()> {
+    let result = match token.map(str::trim).filter(|t| !t.is_empty()) {
+        Some(token) => secret_store().set(tracker.secret_key(), token),
+        None => secret_store().delete(tracker.secret_key()),
+    };
+    get_issue_importer().clear_cache();
+    result
+}
+
+/// Whether a tracker has its token, and Jira its site
+pub fn is_configured(tracker: IssueTracker) -> bool {
+    let has_token = matches!(secret_store().get(tracker.secret_key()), Ok(Some(_)));
+    match tracker {
+        IssueTracker::Jira => has_token && get_issue_settings().read().unwrap().jira_url.is_some(),
+        IssueTracker::Linear => has_token,
+    }
+}
+
+/// Where an issue is
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssueRef {
+    /// Tracker, when the reference says; bare keys leave it open
+    pub tracker: Option<IssueTracker>,
+
+    /// Key, e.g. `ENG-42`
+    pub key: String,
+
+    /// Site of a Jira link
+    pub site: Option<String>,
+}
+
+impl std::str::FromStr for IssueRef {
+    type Err = McpError;
+
+    /// Read an issue link or a key such as `ENG-42`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(c) = LINEAR_URL.captures(s) {
+            return Ok(IssueRef {
+                tracker: Some(IssueTracker::Linear),
+                key: c[1].to_uppercase(),
+                site: None,
+            });
+        }
+        if let Some(c) = JIRA_URL.captures(s) {
+            return Ok(IssueRef {
+                tracker: Some(IssueTracker::Jira),
+                key: c[2].to_uppercase(),
+                site: Some(c[1].to_string()),
+            });
+        }
+        if KEY.is_match(s) {
+            return Ok(IssueRef {
+                tracker: None,
+                key: s.to_uppercase(),
+                site: None,
+            });
+        }
+        Err(McpError::InvalidRequest(format!(
+            "'{}' is not a Jira or Linear issue link or key",
+            s
+        )))
+    }
+}
+
+/// A comment on an issue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssueComment {
+    /// Name of the author
+    pub author: String,
+
+    /// Text
+    pub body: String,
+
+    /// When it was written
+    pub created_at: Option<String>,
+}
+
+/// A pull request linked to an issue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkedPullRequest {
+    /// Title
+    pub title: String,
+
+    /// Link
+    pub url: String,
+}
+
+/// An issue with its discussion
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Issue {
+    /// Tracker it is in
+    pub tracker: IssueTracker,
+
+    /// Key
+    pub key: String,
+
+    /// Title
+    pub title: String,
+
+    /// Link
+    pub url: String,
+
+    /// Workflow status, e.g. `In Progress`
+    pub status: Option<String>,
+
+    /// Name of the assignee
+    pub assignee: Option<String>,
+
+    /// Labels
+    pub labels: Vec<String>,
+
+    /// Description
+    pub description: String,
+
+    /// Comments, oldest first
+    pub comments: Vec<IssueComment>,
+
+    /// Linked pull requests
+    pub pull_requests: Vec<LinkedPullRequest>,
+}
+
+impl Issue {
+    /// The issue as Markdown, with only the given fields
+    pub fn to_markdown(&self, fields: &[IssueField]) -> String {
+        let mut md = format!("## {} {}: {}\n{}\n", self.tracker.name(), self.key, self.title, self.url);
+
+        let mut facts = Vec::new();
+        for field in IssueField::ALL.iter().filter(|f| fields.contains(f)) {
+            match field {
+                IssueField::Status => facts.extend(self.status.as_ref().map(|s| format!("Status: {}", s))),
+                IssueField::Assignee => facts.push(format!(
+                    "Assignee: {}",
+                    self.assignee.as_deref().unwrap_or("unassigned")
+                )),
+                IssueField::Labels if !self.labels.is_empty() => {
+                    facts.push(format!("Labels: {}", self.labels.join(", ")))
+                }
+                _ => {}
+            }
+        }
+        if !facts.is_empty() {
+            md.push_str(&format!("{}\n", facts.join(" · ")));
+        }
+
+        if fields.contains(&IssueField::Description) && !self.description.trim().is_empty() {
+            md.push_str(&format!("\n{}\n", self.description.trim()));
+        }
+        if fields.contains(&IssueField::PullRequests) && !self.pull_requests.is_empty() {
+            md.push_str("\n### Pull requests\n\n");
+            for pr in &self.pull_requests {
+                md.push_str(&format!("- [{}]({})\n", pr.title, pr.url));
+            }
+        }
+        if fields.contains(&IssueField::Comments) && !self.comments.is_empty() {
+            md.push_str("\n### Comments\n");
+            for comment in &self.comments {
+                let date = comment
+                    .created_at
+                    .as_deref()
+                    .map(|d| format!(" on {}", &d[..d.len().min(10)]))
+                    .unwrap_or_default();
+                md.push_str(&format!("\n**{}**{}:\n{}\n", comment.author, date, comment.body.trim()));
+            }
+        }
+        md
+    }
+
+    /// The issue as conversation context, with only the given fields
+    pub fn to_context(&self, fields: &[IssueField]) -> ContextItem {
+        ContextItem {
+            source: self.tracker.as_str().to_string(),
+            reference: self.key.clone(),
+            url: self.url.clone(),
+            title: self.title.clone(),
+            text: self.to_markdown(fields),
+        }
+    }
+}
+
+/// An issue found by a search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssueSummary {
+    /// Key
+    pub key: String,
+
+    /// Title
+    pub title: String,
+
+    /// Workflow status
+    pub status: Option<String>,
+
+    /// Link
+    pub url: String,
+}
+
+/// A place issues are read from
+#[async_trait]
+pub trait IssueSource: Send + Sync {
+    /// Tracker the source reads
+    fn tracker(&self) -> IssueTracker;
+
+    /// Read an issue by key
+    async fn issue(&self, key: &str) -> McpResult<Issue>;
+
+    /// Find issues matching a text
+    async fn search(&self, query: &str, limit: usize) -> McpResult<Vec<IssueSummary>>;
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(TIMEOUT_SECS))
+        .user_agent(concat!("papin/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap_or_default()
+}
+
+/// Read a JSON answer, mapping failures to errors
+async fn read_response(tracker: IssueTracker, response: reqwest::Response, what: &str) -> McpResult<Value> {
+    let status = response.status();
+    if status.is_success() {
+        return response
+            .json()
+            .await
+            .map_err(|e| McpError::Protocol(format!("Unexpected {} response: {}", tracker.name(), e)));
+    }
+    let body = response.text().await.unwrap_or_default();
+    debug!("{} answered {}: {}", tracker.name(), status, body);
+    Err(match status.as_u16() {
+        401 | 403 => McpError::Authentication(format!("{} rejected the token", tracker.name())),
+        404 => McpError::InvalidRequest(format!("{} not found in {}", what, tracker.name())),
+        429 => McpError::RateLimit(format!("{} is limiting requests", tracker.name())),
+        _ => McpError::Protocol(format!("{} answered {}", tracker.name(), status)),
+    })
+}
+
+fn string(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+fn optional(value: &Value) -> Option<String> {
+    value.as_str().filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+/// Links to pull and merge requests on the common forges
+fn is_pull_request_url(url: &str) -> bool {
+    ["/pull/", "/pulls/", "/merge_requests/", "/pull-requests/"]
+        .iter()
+        .any(|part| url.contains(part))
+}
+
+/// Jira Cloud or Data Center
+pub struct JiraClient {
+    base_url: String,
+    email: Option<String>,
+    token: String,
+    client: reqwest::Client,
+}
+
+impl JiraClient {
+    /// Create a client for a site; with an email the token is an Atlassian
+    /// API token, without one a personal access token
+    pub fn new(base_url: &str, email: Option<String>, token: String) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            email,
+            token,
+            client: http_client(),
+        }
+    }
+
+    async fn get(&self, path: &str, query: &[(&str, String)], what: &str) -> McpResult<Value> {
+        let request = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(query)
+            .header("Accept", "application/json");
+        let request = match &self.email {
+            Some(email) => request.basic_auth(email, Some(&self.token)),
+            None => request.bearer_auth(&self.token),
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(format!("Jira request failed: {}", e)))?;
+        read_response(IssueTracker::Jira, response, what).await
+    }
+}
+
+#[async_trait]
+impl IssueSource for JiraClient {
+    fn tracker(&self) -> IssueTracker {
+        IssueTracker::Jira
+    }
+
+    async fn issue(&self, key: &str) -> McpResult<Issue> {
+        let issue = self
+            .get(
+                &format!("/rest/api/2/issue/{}", key),
+                &[("fields", "summary,description,status,assignee,labels,comment".to_string())],
+                key,
+            )
+            .await?;
+        let fields = &issue["fields"];
+
+        let mut comments: Vec<IssueComment> = fields["comment"]["comments"]
+            .as_array()
+            .map(|comments| {
+                comments
+                    .iter()
+                    .map(|c| IssueComment {
+                        author: optional(&c["author"]["displayName"]).unwrap_or_else(|| "Unknown".to_string()),
+                        body: string(&c["body"]),
+                        created_at: optional(&c["created"]),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        // The latest comments matter most
+        if comments.len() > MAX_COMMENTS {
+            comments.drain(..comments.len() - MAX_COMMENTS);
+        }
+
+        // Development panel links come in as remote links; they are a bonus
+        let pull_requests = match self.get(&format!("/rest/api/2/issue/{}/remotelink", key), &[], key).await {
+            Ok(links) => links
+                .as_array()
+                .map(|links| {
+                    links
+                        .iter()
+                        .map(|l| &l["object"])
+                        .filter(|o| is_pull_request_url(o["url"].as_str().unwrap_or_default()))
+                        .map(|o| LinkedPullRequest {
+                            title: optional(&o["title"]).unwrap_or_else(|| string(&o["url"])),
+                            url: string(&o["url"]),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Err(e) => {
+                debug!("No remote links for {}: {}", key, e);
+                Vec::new()
+            }
+        };
+
+        let key = optional(&issue["key"]).unwrap_or_else(|| key.to_string());
+        Ok(Issue {
+            tracker: IssueTracker::Jira,
+            url: format!("{}/browse/{}", self.base_url, key),
+            key,
+            title: string(&fields["summary"]),
+            status: optional(&fields["status"]["name"]),
+            assignee: optional(&fields["assignee"]["displayName"]),
+            labels: fields["labels"]
+                .as_array()
+                .map(|labels| labels.iter().filter_map(optional).collect())
+                .unwrap_or_default(),
+            description: string(&fields["description"]),
+            comments,
+            pull_requests,
+        })
+    }
+
+    async fn search(&self, query: &str, limit: usize) -> McpResult<Vec<IssueSummary>> {
+        let jql = format!("text ~ \"{}\" ORDER BY updated DESC", query.replace('\\', "\\\\").replace('"', "\\\""));
+        let results = self
+            .get(
+                "/rest/api/2/search",
+                &[
+                    ("jql", jql),
+                    ("maxResults", limit.min(MAX_SEARCH_RESULTS).to_string()),
+                    ("fields", "summary,status".to_string()),
+                ],
+                "search",
+            )
+            .await?;
+        Ok(results["issues"]
+            .as_array()
+            .map(|issues| {
+                issues
+                    .iter()
+                    .map(|i| IssueSummary {
+                        key: string(&i["key"]),
+                        title: string(&i["fields"]["summary"]),
+                        status: optional(&i["fields"]["status"]["name"]),
+                        url: format!("{}/browse/{}", self.base_url, string(&i["key"])),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+const LINEAR_ISSUE_QUERY: &str = r#"
+query($id: String!) {
+  issue(id: $id) {
+    identifier title description url
+    state { name }
+    assignee { name }
+    labels { nodes { name } }
+    comments(first: 50) { nodes { body createdAt user { name } } }
+    attachments { nodes { title url } }
+  }
+}"#;
+
+const LINEAR_SEARCH_QUERY: &str = r#"
+query($term: String!, $first: Int) {
+  searchIssues(term: $term, first: $first) {
+    nodes { identifier title url state { name } }
+  }
+}"#;
+
+/// Linear
+pub struct LinearClient {
+    endpoint: String,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+impl LinearClient {
+    /// Create a client with a personal API key
+    pub fn new(api_key: String) -> Self {
+        Self {
+            endpoint: LINEAR_API_URL.to_string(),
+            api_key,
+            client: http_client(),
+        }
+    }
+
+    /// Send requests somewhere else, e.g. a proxy
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+
+    async fn query(&self, query: &str, variables: Value, what: &str) -> McpResult<Value> {
+        let response = self
+            .client
+            .post(&self.endpoint)
+            // Personal API keys go without a scheme
+            .header("Authorization", &self.api_key)
+            .json(&serde_json::json!({ "query": query, "variables": variables }))
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(format!("Linear request failed: {}", e)))?;
+        let body = read_response(IssueTracker::Linear, response, what).await?;
+        if let Some(error) = body["errors"].as_array().and_then(|e| e.first()) {
+            let message = error["message"].as_str().unwrap_or("unknown error");
+            return Err(McpError::InvalidRequest(format!("Linear: {} ({})", message, what)));
+        }
+        Ok(body["data"].clone())
+    }
+}
+
+#[async_trait]
+impl IssueSource for LinearClient {
+    fn tracker(&self) -> IssueTracker {
+        IssueTracker::Linear
+    }
+
+    async fn issue(&self, key: &str) -> McpResult<Issue> {
+        let data = self
+            .query(LINEAR_ISSUE_QUERY, serde_json::json!({ "id": key }), key)
+            .await?;
+        let issue = &data["issue"];
+        if issue.is_null() {
+            return Err(McpError::InvalidRequest(format!("{} not found in Linear", key)));
+        }
+        let nodes = |connection: &Value| connection["nodes"].as_array().cloned().unwrap_or_default();
+
+        let mut comments: Vec<IssueComment> = nodes(&issue["comments"])
+            .iter()
+            .map(|c| IssueComment {
+                author: optional(&c["user"]["name"]).unwrap_or_else(|| "Unknown".to_string()),
+                body: string(&c["body"]),
+                created_at: optional(&c["createdAt"]),
+            })
+            .collect();
+        comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+        Ok(Issue {
+            tracker: IssueTracker::Linear,
+            key: optional(&issue["identifier"]).unwrap_or_else(|| key.to_string()),
+            title: string(&issue["title"]),
+            url: string(&issue["url"]),
+            status: optional(&issue["state"]["name"]),
+            assignee: optional(&issue["assignee"]["name"]),
+            labels: nodes(&issue["labels"]).iter().filter_map(|l| optional(&l["name"])).collect(),
+            description: string(&issue["description"]),
+            comments,
+            pull_requests: nodes(&issue["attachments"])
+                .iter()
+                .filter(|a| is_pull_request_url(a["url"].as_str().unwrap_or_default()))
+                .map(|a| LinkedPullRequest {
+                    title: optional(&a["title"]).unwrap_or_else(|| string(&a["url"])),
+                    url: string(&a["url"]),
+                })
+                .collect(),
+        })
+    }
+
+    async fn search(&self, query: &str, limit: usize) -> McpResult<Vec<IssueSummary>> {
+        let data = self
+            .query(
+                LINEAR_SEARCH_QUERY,
+                serde_json::json!({ "term": query, "first": limit.min(MAX_SEARCH_RESULTS) }),
+                "search",
+            )
+            .await?;
+        Ok(data["searchIssues"]["nodes"]
+            .as_array()
+            .map(|issues| {
+                issues
+                    .iter()
+                    .map(|i| IssueSummary {
+                        key: string(&i["identifier"]),
+                        title: string(&i["title"]),
+                        status: optional(&i["state"]["name"]),
+                        url: string(&i["url"]),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+/// Tracker and issue key of a cached issue
+type CacheKey = (IssueTracker, String);
+
+/// Reads issues from whichever trackers are set up, with a cache
+pub struct IssueImporter {
+    /// Fixed sources; when empty they are built from the settings and
+    /// secret store on each request, so changes apply right away
+    sources: Vec<Arc<dyn IssueSource>>,
+    cache: Mutex<HashMap<CacheKey, (Issue, DateTime<Utc>)>>,
+}
+
+impl Default for IssueImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IssueImporter {
+    /// Create an importer for the configured trackers
+    pub fn new() -> Self {
+        Self::with_sources(Vec::new())
+    }
+
+    /// Create an importer reading from these sources only
+    pub fn with_sources(sources: Vec<Arc<dyn IssueSource>>) -> Self {
+        Self {
+            sources,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Forget cached issues
+    pub fn clear_cache(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    fn source(&self, tracker: IssueTracker) -> McpResult<Arc<dyn IssueSource>> {
+        if !self.sources.is_empty() {
+            return self
+                .sources
+                .iter()
+                .find(|s| s.tracker() == tracker)
+                .cloned()
+                .ok_or_else(|| McpError::Config(format!("{} is not set up", tracker.name())));
+        }
+
+        let token = secret_store()
+            .get(tracker.secret_key())?
+            .ok_or_else(|| McpError::Config(format!("No {} token; set one first", tracker.name())))?;
+        match tracker {
+            IssueTracker::Jira => {
+                let settings = get_issue_settings().read().unwrap().clone();
+                let url = settings
+                    .jira_url
+                    .ok_or_else(|| McpError::Config("No Jira site configured".to_string()))?;
+                Ok(Arc::new(JiraClient::new(&url, settings.jira_email, token)))
+            }
+            IssueTracker::Linear => Ok(Arc::new(LinearClient::new(token))),
+        }
+    }
+
+    /// Trackers that can be asked
+    fn available(&self) -> Vec<IssueTracker> {
+        if !self.sources.is_empty() {
+            return self.sources.iter().map(|s| s.tracker()).collect();
+        }
+        [IssueTracker::Jira, IssueTracker::Linear]
+            .into_iter()
+            .filter(|&t| is_configured(t))
+            .collect()
+    }
+
+    /// Tracker a reference is in: its own, or else the default one
+    fn resolve(&self, reference: &IssueRef) -> McpResult<IssueTracker> {
+        let Some(tracker) = reference.tracker else {
+            return self.default_tracker(&reference.key);
+        };
+        if let Some(site) = &reference.site {
+            let configured = get_issue_settings().read().unwrap().jira_url.clone();
+            if self.sources.is_empty() && configured.as_deref().map(|u| u.trim_end_matches('/')) != Some(site.as_str()) {
+                return Err(McpError::Config(format!("{} is not the configured Jira site", site)));
+            }
+        }
+        Ok(tracker)
+    }
+
+    /// The configured default tracker, or the only one set up
+    fn default_tracker(&self, key: &str) -> McpResult<IssueTracker> {
+        let available = self.available();
+        if let Some(default) = get_issue_settings().read().unwrap().default_tracker {
+            if available.contains(&default) {
+                return Ok(default);
+            }
+        }
+        match available.as_slice() {
+            [only] => Ok(*only),
+            [] => Err(McpError::Config("No issue tracker is set up".to_string())),
+            _ => Err(McpError::InvalidRequest(format!(
+                "{} could be in Jira or Linear; use a link or set a default tracker",
+                key
+            ))),
+        }
+    }
+
+    /// Read an issue from a link or key; `refresh` skips the cache
+    pub async fn fetch(&self, reference: &str, refresh: bool) -> McpResult<Issue> {
+        let reference: IssueRef = reference.parse()?;
+        let tracker = self.resolve(&reference)?;
+        let cache_key = (tracker, reference.key.clone());
+
+        if !refresh {
+            if let Some((issue, fetched_at)) = self.cache.lock().unwrap().get(&cache_key) {
+                if clock::now() - *fetched_at < Duration::seconds(FRESH_SECS) {
+                    debug!("Issue cache hit for {}", reference.key);
+                    return Ok(issue.clone());
+                }
+            }
+        }
+
+        let issue = self.source(tracker)?.issue(&reference.key).await?;
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHE_ENTRIES && !cache.contains_key(&cache_key) {
+            let oldest = cache.iter().min_by_key(|(_, (_, at))| *at).map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(cache_key, (issue.clone(), clock::now()));
+        Ok(issue)
+    }
+
+    /// Find issues in a tracker, or in the default one
+    pub async fn search(&self, tracker: Option<IssueTracker>, query: &str, limit: usize) -> McpResult<Vec<IssueSummary>> {
+        let tracker = match tracker {
+            Some(tracker) => tracker,
+            None => self.default_tracker("The search")?,
+        };
+        self.source(tracker)?.search(query, limit.max(1)).await
+    }
+}
+
+static ISSUE_IMPORTER: Lazy<Arc<IssueImporter>> = Lazy::new(|| Arc::new(IssueImporter::new()));
+
+/// Get the global issue importer
+pub fn get_issue_importer() -> Arc<IssueImporter> {
+    ISSUE_IMPORTER.clone()
+}
@@ -0,0 +1,62 @@
+//! Integrations with developer services
+//!
+//! Each integration reads from a service's API with a token kept in the
+//! secret store and turns what it finds (code, issues, pull requests) into
+//! a [`ContextItem`] that can be imported into a conversation.
+
+pub mod github;
+pub mod issues;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::error::McpResult;
+use crate::models::Message;
+use crate::service::ChatService;
+
+/// Message metadata key marking imported context, with where it came from
+pub const CONTEXT_METADATA_KEY: &str = "imported_context";
+
+/// Something fetched from a service, ready to go into a conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContextItem {
+    /// Service it came from, e.g. `github`
+    pub source: String,
+
+    /// Short reference, e.g. `owner/repo#12`
+    pub reference: String,
+
+    /// Link to it
+    pub url: String,
+
+    /// Title
+    pub title: String,
+
+    /// Markdown text given to the model
+    pub text: String,
+}
+
+impl ContextItem {
+    /// What the imported message records under [`CONTEXT_METADATA_KEY`]
+    pub fn metadata(&self) -> serde_json::Value {
+        serde_json::json!({
+            "source": self.source,
+            "reference": self.reference,
+            "url": self.url,
+            "title": self.title,
+        })
+    }
+}
+
+/// Add fetched context to a conversation as a user message, without asking
+/// for a reply. The message records its source so it can be refreshed or
+/// shown differently.
+pub async fn import_context(chat_service: &ChatService, conversation_id: &str, item: &ContextItem) -> McpResult<Message> {
+    let mut message = Message::user(item.text.clone());
+    message.metadata = Some(HashMap::from([(CONTEXT_METADATA_KEY.to_string(), item.metadata())]));
+
+    let mut conversation = chat_service.get_conversation(conversation_id).await?;
+    conversation.add_message(message.clone());
+    chat_service.update_conversation(conversation).await?;
+    Ok(message)
+}
@@ -0,0 +1,510 @@
+//! Background jobs for long-running operations such as model downloads,
+//! imports, exports, indexing and backups.
+//!
+//! Every job is stored as its own file in the data directory, so the CLI can
+//! list jobs run by the desktop app and cancel them: a cancel request from
+//! another process is a marker file the owning process picks up on its next
+//! progress update. Resumable jobs save a checkpoint and are picked up again
+//! after a restart by the resumer registered for their kind.
+
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+use crate::utils::clock;
+
+/// Directory in the data directory holding job files
+pub const JOBS_DIR: &str = "jobs";
+
+/// Shortest time between progress updates written to disk and published
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Kind of long-running operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Downloading a local model
+    ModelDownload,
+
+    /// Importing conversations or settings
+    Import,
+
+    /// Exporting conversations or settings
+    Export,
+
+    /// Building a search index
+    Indexing,
+
+    /// Backing up app data
+    Backup,
+
+    /// Fine-tuning a local model
+    FineTune,
+}
+
+impl fmt::Display for JobKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            JobKind::ModelDownload => "model download",
+            JobKind::Import => "import",
+            JobKind::Export => "export",
+            JobKind::Indexing => "indexing",
+            JobKind::Backup => "backup",
+            JobKind::FineTune => "fine-tune",
+        };
+        f.write_str(name)
+    }
+}
+
+/// State of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// Created, not started yet
+    Queued,
+
+    /// Running
+    Running,
+
+    /// Stopped by a shutdown; resumable jobs can be resumed
+    Interrupted,
+
+    /// Finished successfully
+    Completed,
+
+    /// Finished with an error
+    Failed,
+
+    /// Canceled by the user
+    Cancelled,
+}
+
+impl JobState {
+    /// Whether the job has ended for good
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobState::Completed | JobState::Failed | JobState::Cancelled)
+    }
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Interrupted => "interrupted",
+            JobState::Completed => "completed",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Progress of a job
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobProgress {
+    /// Units done, e.g. bytes downloaded
+    pub done: u64,
+
+    /// Units in total, if known
+    pub total: Option<u64>,
+
+    /// What the job is doing right now
+    pub message: Option<String>,
+}
+
+impl JobProgress {
+    /// Share of the work done, between 0 and 1, if the total is known
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(1.0),
+            Some(total) => Some((self.done as f64 / total as f64).min(1.0)),
+            None => None,
+        }
+    }
+}
+
+/// A long-running operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    /// Job ID
+    pub id: String,
+
+    /// Kind of operation
+    pub kind: JobKind,
+
+    /// What the job works on, e.g. the model name
+    pub title: String,
+
+    /// Current state
+    pub state: JobState,
+
+    /// Current progress
+    pub progress: JobProgress,
+
+    /// Whether the job can continue from its checkpoint after a restart
+    pub resumable: bool,
+
+    /// Data the job needs to resume, saved by the job itself
+    #[serde(default)]
+    pub checkpoint: Option<serde_json::Value>,
+
+    /// Why the job failed
+    pub error: Option<String>,
+
+    /// Process running the job
+    pub owner_pid: u32,
+
+    /// When the job was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the job last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Future running a job
+pub type JobFuture = Pin<Box<dyn Future<Output = McpResult<()>> + Send>>;
+
+/// Continues an interrupted job of one kind from its checkpoint
+pub type Resumer = Arc<dyn Fn(JobHandle) -> JobFuture + Send + Sync>;
+
+/// Handle given to a running job to report progress and notice cancellation
+#[derive(Clone)]
+pub struct JobHandle {
+    job: Arc<Mutex<Job>>,
+    manager: Arc<JobManager>,
+    token: CancellationToken,
+    last_saved: Arc<Mutex<Instant>>,
+}
+
+impl JobHandle {
+    /// Job ID
+    pub fn id(&self) -> String {
+        self.job.lock().unwrap().id.clone()
+    }
+
+    /// Checkpoint saved by an earlier run, when resuming
+    pub fn checkpoint(&self) -> Option<serde_json::Value> {
+        self.job.lock().unwrap().checkpoint.clone()
+    }
+
+    /// Report progress
+    pub fn progress(&self, done: u64, total: Option<u64>) {
+        self.update(false, |job| {
+            job.progress.done = done;
+            job.progress.total = total;
+        });
+    }
+
+    /// Describe what the job is doing
+    pub fn message(&self, message: impl Into<String>) {
+        let message = message.into();
+        self.update(true, |job| job.progress.message = Some(message));
+    }
+
+    /// Save the data needed to resume the job
+    pub fn save_checkpoint(&self, checkpoint: serde_json::Value) {
+        self.update(true, |job| job.checkpoint = Some(checkpoint));
+    }
+
+    /// Whether the job was asked to stop
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Resolves when the job is asked to stop
+    pub async fn cancelled(&self) {
+        self.token.cancelled().await
+    }
+
+    fn update(&self, force: bool, change: impl FnOnce(&mut Job)) {
+        let job = {
+            let mut job = self.job.lock().unwrap();
+            change(&mut job);
+            job.updated_at = clock::now();
+
+            let mut last_saved = self.last_saved.lock().unwrap();
+            if !force && last_saved.elapsed() < PROGRESS_INTERVAL {
+                return;
+            }
+            *last_saved = Instant::now();
+            job.clone()
+        };
+
+        // Cancel requests from other processes arrive as marker files
+        if self.manager.cancel_path(&job.id).exists() {
+            info!("Job {} canceled from another process", job.id);
+            self.token.cancel();
+        }
+        self.manager.save(&job);
+        self.manager.publish(names::JOB_PROGRESS, &job);
+    }
+}
+
+/// Runs jobs and keeps track of jobs run by any process
+pub struct JobManager {
+    dir: PathBuf,
+    running: Mutex<HashMap<String, CancellationToken>>,
+    resumers: RwLock<HashMap<JobKind, Resumer>>,
+}
+
+impl JobManager {
+    /// Create a manager keeping job files in a directory
+    pub fn new(dir: PathBuf) -> Self {
+        if let Err(e) = fs::create_dir_all(&dir) {
+            warn!("Failed to create jobs directory {}: {}", dir.display(), e);
+        }
+        Self {
+            dir,
+            running: Mutex::new(HashMap::new()),
+            resumers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn job_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    fn cancel_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.cancel", id))
+    }
+
+    fn save(&self, job: &Job) {
+        let result = serde_json::to_string_pretty(job)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(self.job_path(&job.id), json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            warn!("Failed to save job {}: {}", job.id, e);
+        }
+    }
+
+    fn read(&self, id: &str) -> Option<Job> {
+        let json = fs::read_to_string(self.job_path(id)).ok()?;
+        serde_json::from_str(&json)
+            .map_err(|e| warn!("Ignoring unreadable job {}: {}", id, e))
+            .ok()
+    }
+
+    fn publish(&self, name: &str, job: &Job) {
+        get_event_bus().emit(
+            Topic::System,
+            name,
+            serde_json::to_value(job).unwrap_or_default(),
+        );
+    }
+
+    /// Register how interrupted jobs of a kind are resumed
+    pub fn register_resumer(
+        &self,
+        kind: JobKind,
+        resumer: impl Fn(JobHandle) -> JobFuture + Send + Sync + 'static,
+    ) {
+        self.resumers.write().unwrap().insert(kind, Arc::new(resumer));
+    }
+
+    /// Start a job in the background. The job should report progress through
+    /// its handle and stop when it is canceled; it is dropped at its next
+    /// await point otherwise.
+    pub fn spawn<F, Fut>(self: &Arc<Self>, kind: JobKind, title: &str, resumable: bool, run: F) -> Job
+    where
+        F: FnOnce(JobHandle) -> Fut + Send + 'static,
+        Fut: Future<Output = McpResult<()>> + Send + 'static,
+    {
+        let now = clock::now();
+        let job = Job {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            title: title.to_string(),
+            state: JobState::Queued,
+            progress: JobProgress::default(),
+            resumable,
+            checkpoint: None,
+            error: None,
+            owner_pid: std::process::id(),
+            created_at: now,
+            updated_at: now,
+        };
+        self.run(job, move |handle| Box::pin(run(handle)))
+    }
+
+    fn run(self: &Arc<Self>, mut job: Job, run: impl FnOnce(JobHandle) -> JobFuture) -> Job {
+        job.state = JobState::Running;
+        job.owner_pid = std::process::id();
+        job.error = None;
+        job.updated_at = clock::now();
+        self.save(&job);
+        self.publish(names::JOB_PROGRESS, &job);
+
+        let token = CancellationToken::new();
+        self.running.lock().unwrap().insert(job.id.clone(), token.clone());
+        let handle = JobHandle {
+            job: Arc::new(Mutex::new(job.clone())),
+            manager: self.clone(),
+            token: token.clone(),
+            last_saved: Arc::new(Mutex::new(Instant::now())),
+        };
+        let future = run(handle.clone());
+        let manager = self.clone();
+
+        tokio::spawn(async move {
+            let result = tokio::select! {
+                result = future => result,
+                _ = token.cancelled() => Err(McpError::Cancelled),
+            };
+
+            let job = {
+                let mut job = handle.job.lock().unwrap();
+                // A job that notices the cancellation may still return Ok
+                job.state = match &result {
+                    _ if token.is_cancelled() => JobState::Cancelled,
+                    Ok(()) => JobState::Completed,
+                    Err(_) => JobState::Failed,
+                };
+                job.error = result.err().filter(|_| !token.is_cancelled()).map(|e| e.to_string());
+                job.updated_at = clock::now();
+                job.clone()
+            };
+            debug!("Job {} ({}) {}", job.id, job.kind, job.state);
+
+            manager.running.lock().unwrap().remove(&job.id);
+            let _ = fs::remove_file(manager.cancel_path(&job.id));
+            manager.save(&job);
+            manager.publish(names::JOB_FINISHED, &job);
+        });
+
+        job
+    }
+
+    /// All jobs, newest first
+    pub fn list(&self) -> Vec<Job> {
+        let mut jobs: Vec<Job> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    self.read(&path.file_stem()?.to_string_lossy())
+                } else {
+                    None
+                }
+            })
+            .collect();
+        jobs.sort_by_key(|j| std::cmp::Reverse(j.created_at));
+        jobs
+    }
+
+    /// A job by ID or unique ID prefix
+    pub fn get(&self, id: &str) -> McpResult<Job> {
+        if let Some(job) = self.read(id) {
+            return Ok(job);
+        }
+        let mut matches: Vec<Job> = self.list().into_iter().filter(|j| j.id.starts_with(id)).collect();
+        match matches.len() {
+            1 => Ok(matches.remove(0)),
+            0 => Err(McpError::InvalidRequest(format!("Job {} not found", id))),
+            _ => Err(McpError::InvalidRequest(format!("Job ID {} is ambiguous", id))),
+        }
+    }
+
+    /// Ask a job to stop. Jobs run by another process stop at their next
+    /// progress update.
+    pub fn cancel(&self, id: &str) -> McpResult<Job> {
+        let mut job = self.get(id)?;
+        if job.state.is_finished() {
+            return Err(McpError::InvalidRequest(format!("Job {} has already {}", job.id, job.state)));
+        }
+
+        if let Some(token) = self.running.lock().unwrap().get(&job.id) {
+            token.cancel();
+            return Ok(job);
+        }
+
+        if job.state == JobState::Interrupted {
+            // Nothing is running it, so it ends here
+            job.state = JobState::Cancelled;
+            job.updated_at = clock::now();
+            self.save(&job);
+            self.publish(names::JOB_FINISHED, &job);
+        } else {
+            fs::write(self.cancel_path(&job.id), b"")?;
+        }
+        Ok(job)
+    }
+
+    /// Continue an interrupted job
+    pub fn resume(self: &Arc<Self>, id: &str) -> McpResult<Job> {
+        let job = self.get(id)?;
+        if job.state != JobState::Interrupted {
+            return Err(McpError::InvalidRequest(format!("Job {} is {}, not interrupted", job.id, job.state)));
+        }
+        let resumer = self
+            .resumers
+            .read()
+            .unwrap()
+            .get(&job.kind)
+            .cloned()
+            .ok_or_else(|| McpError::InvalidRequest(format!("Jobs of kind {} can't be resumed", job.kind)))?;
+
+        info!("Resuming job {} ({})", job.id, job.title);
+        Ok(self.run(job, move |handle| resumer(handle)))
+    }
+
+    /// Take over jobs left running by a previous run of the app: resumable
+    /// jobs continue, the others are marked failed. Call once at app startup,
+    /// after registering resumers; returns how many jobs were resumed.
+    pub fn recover(self: &Arc<Self>) -> usize {
+        let pid = std::process::id();
+        let mut resumed = 0;
+        for mut job in self.list() {
+            if !matches!(job.state, JobState::Queued | JobState::Running) || job.owner_pid == pid {
+                continue;
+            }
+
+            job.updated_at = clock::now();
+            if job.resumable {
+                job.state = JobState::Interrupted;
+                self.save(&job);
+                if self.resumers.read().unwrap().contains_key(&job.kind) && self.resume(&job.id).is_ok() {
+                    resumed += 1;
+                }
+            } else {
+                job.state = JobState::Failed;
+                job.error = Some("Interrupted by shutdown".to_string());
+                self.save(&job);
+                self.publish(names::JOB_FINISHED, &job);
+            }
+        }
+        resumed
+    }
+
+    /// Forget finished jobs; returns how many were removed
+    pub fn clear_finished(&self) -> usize {
+        let finished: Vec<Job> = self.list().into_iter().filter(|j| j.state.is_finished()).collect();
+        for job in &finished {
+            let _ = fs::remove_file(self.job_path(&job.id));
+            let _ = fs::remove_file(self.cancel_path(&job.id));
+        }
+        finished.len()
+    }
+}
+
+static JOB_MANAGER: Lazy<Arc<JobManager>> = Lazy::new(|| Arc::new(JobManager::new(data_path(JOBS_DIR))));
+
+/// Get the job manager
+pub fn get_job_manager() -> Arc<JobManager> {
+    JOB_MANAGER.clone()
+}
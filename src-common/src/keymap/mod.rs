@@ -0,0 +1,332 @@
+mod presets;
+
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use crate::config::config_path;
+use crate::error::{McpError, McpResult};
+
+pub use presets::{ActionInfo, ACTIONS, PRESETS};
+
+const KEYMAP_FILE: &str = "keymap.json";
+
+/// Scopes where unmodified printable keys go to a text input
+const TEXT_INPUT_SCOPES: &[&str] = &["tui.chat", "tui.command"];
+
+/// Named keys understood besides single characters
+const NAMED_KEYS: &[&str] = &[
+    "enter", "esc", "tab", "backtab", "backspace", "delete", "insert", "up", "down", "left", "right", "pageup",
+    "pagedown", "home", "end", "space", "f1", "f2", "f3", "f4", "f5", "f6", "f7", "f8", "f9", "f10", "f11", "f12",
+];
+
+/// A key with modifiers, written like `ctrl+shift+z`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KeyCombo {
+    /// Control held
+    pub ctrl: bool,
+
+    /// Alt/Option held
+    pub alt: bool,
+
+    /// Shift held; folded into the character for printable keys
+    pub shift: bool,
+
+    /// Super/Command held
+    pub meta: bool,
+
+    /// Lowercase key name or the character itself
+    pub key: String,
+}
+
+impl KeyCombo {
+    /// A key without modifiers
+    pub fn key(key: &str) -> Self {
+        Self {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            meta: false,
+            key: key.to_string(),
+        }
+    }
+
+    /// Whether the combo types a character into a text field
+    pub fn is_printable(&self) -> bool {
+        !self.ctrl && !self.alt && !self.meta && (self.key.chars().count() == 1 || self.key == "space")
+    }
+}
+
+impl FromStr for KeyCombo {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| McpError::InvalidRequest(format!("Invalid key '{}': {}", s, reason));
+        let s = s.trim();
+        // "+" alone or at the end is the plus key, not a separator
+        let (modifiers, key) = match s.strip_suffix("++") {
+            Some(rest) => (rest, "+"),
+            None if s == "+" => ("", "+"),
+            None => s.rsplit_once('+').unwrap_or(("", s)),
+        };
+
+        let mut combo = KeyCombo::key("");
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => combo.ctrl = true,
+                "alt" | "option" | "opt" => combo.alt = true,
+                "shift" => combo.shift = true,
+                "meta" | "super" | "cmd" | "command" | "win" => combo.meta = true,
+                other => return Err(invalid(&format!("unknown modifier '{}'", other))),
+            }
+        }
+
+        let lower = key.to_lowercase();
+        combo.key = if key.chars().count() == 1 {
+            // Shift is part of the character itself, e.g. "shift+a" is "A"
+            let shifted = std::mem::take(&mut combo.shift);
+            if shifted {
+                key.to_uppercase()
+            } else {
+                key.to_string()
+            }
+        } else if lower == "escape" {
+            "esc".to_string()
+        } else if lower == "return" {
+            "enter".to_string()
+        } else if NAMED_KEYS.contains(&lower.as_str()) {
+            lower
+        } else {
+            return Err(invalid("unknown key name"));
+        };
+
+        if combo.key.is_empty() {
+            return Err(invalid("no key"));
+        }
+        Ok(combo)
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [(self.ctrl, "ctrl+"), (self.alt, "alt+"), (self.shift, "shift+"), (self.meta, "meta+")] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        f.write_str(&self.key)
+    }
+}
+
+impl Serialize for KeyCombo {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyCombo {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Scope of an action ID: everything before the last dot
+pub fn scope_of(action: &str) -> &str {
+    action.rsplit_once('.').map(|(scope, _)| scope).unwrap_or(action)
+}
+
+/// Look up an action
+pub fn action_info(action: &str) -> Option<&'static ActionInfo> {
+    ACTIONS.iter().find(|a| a.id == action)
+}
+
+/// A key bound to more than one action in the same scope, or a binding
+/// that would swallow typed text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyConflict {
+    /// Scope the conflict is in
+    pub scope: String,
+
+    /// The key
+    pub key: KeyCombo,
+
+    /// Actions bound to it
+    pub actions: Vec<String>,
+
+    /// What is wrong
+    pub reason: String,
+}
+
+/// User keybindings for the TUI and the desktop app
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keymap {
+    /// Preset the bindings started from
+    #[serde(default = "default_preset")]
+    pub preset: String,
+
+    /// Keys per action ID; actions missing here use the preset's keys
+    #[serde(default)]
+    pub bindings: BTreeMap<String, Vec<KeyCombo>>,
+}
+
+fn default_preset() -> String {
+    "default".to_string()
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::preset("default").expect("default preset exists")
+    }
+}
+
+impl Keymap {
+    /// A built-in preset with every action bound
+    pub fn preset(name: &str) -> McpResult<Self> {
+        let overrides = presets::preset_overrides(name).ok_or_else(|| {
+            McpError::InvalidRequest(format!("Unknown keymap preset '{}'; use {}", name, PRESETS.join(", ")))
+        })?;
+
+        let parse = |keys: &[&str]| -> Vec<KeyCombo> { keys.iter().filter_map(|k| k.parse().ok()).collect() };
+        let mut bindings: BTreeMap<String, Vec<KeyCombo>> =
+            ACTIONS.iter().map(|a| (a.id.to_string(), parse(a.default_keys))).collect();
+        for (action, keys) in overrides {
+            bindings.insert(action.to_string(), parse(keys));
+        }
+
+        Ok(Self {
+            preset: name.to_string(),
+            bindings,
+        })
+    }
+
+    /// Load the user keymap, falling back to the default preset
+    pub fn load() -> Self {
+        match fs::read_to_string(config_path(KEYMAP_FILE)) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Invalid keymap file, using defaults: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Persist the keymap after validating it
+    pub fn save(&self) -> McpResult<()> {
+        self.ensure_valid()?;
+        fs::write(config_path(KEYMAP_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Write the keymap to a file for sharing
+    pub fn export(&self, path: &Path) -> McpResult<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Read a keymap exported with [`Keymap::export`]; bindings it lacks come
+    /// from its preset
+    pub fn import(path: &Path) -> McpResult<Self> {
+        let imported: Keymap = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let mut keymap = Self::preset(&imported.preset).unwrap_or_default();
+        for (action, keys) in imported.bindings {
+            if action_info(&action).is_none() {
+                warn!("Ignoring unknown action '{}' in imported keymap", action);
+                continue;
+            }
+            keymap.bindings.insert(action, keys);
+        }
+        keymap.ensure_valid()?;
+        Ok(keymap)
+    }
+
+    /// Keys bound to an action
+    pub fn keys(&self, action: &str) -> Vec<KeyCombo> {
+        match self.bindings.get(action) {
+            Some(keys) => keys.clone(),
+            None => action_info(action)
+                .map(|a| a.default_keys.iter().filter_map(|k| k.parse().ok()).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Bind keys to an action, replacing its current keys
+    pub fn bind(&mut self, action: &str, keys: &[String]) -> McpResult<()> {
+        if action_info(action).is_none() {
+            return Err(McpError::InvalidRequest(format!("Unknown action '{}'", action)));
+        }
+        let keys = keys.iter().map(|k| k.parse()).collect::<McpResult<Vec<KeyCombo>>>()?;
+        self.bindings.insert(action.to_string(), keys);
+        Ok(())
+    }
+
+    /// Action a key triggers in a scope
+    pub fn action(&self, scope: &str, key: &KeyCombo) -> Option<&'static str> {
+        ACTIONS
+            .iter()
+            .filter(|a| scope_of(a.id) == scope)
+            .find(|a| self.keys(a.id).contains(key))
+            .map(|a| a.id)
+    }
+
+    /// Keys bound to several actions of one scope, and printable keys bound in
+    /// text input scopes
+    pub fn conflicts(&self) -> Vec<KeyConflict> {
+        let mut by_key: BTreeMap<(String, KeyCombo), Vec<String>> = BTreeMap::new();
+        for action in ACTIONS {
+            for key in self.keys(action.id) {
+                by_key
+                    .entry((scope_of(action.id).to_string(), key))
+                    .or_default()
+                    .push(action.id.to_string());
+            }
+        }
+
+        let mut conflicts = Vec::new();
+        for ((scope, key), actions) in by_key {
+            if actions.len() > 1 {
+                conflicts.push(KeyConflict {
+                    scope,
+                    key,
+                    actions,
+                    reason: "bound to more than one action".to_string(),
+                });
+            } else if TEXT_INPUT_SCOPES.contains(&scope.as_str()) && key.is_printable() {
+                conflicts.push(KeyConflict {
+                    scope,
+                    key,
+                    actions,
+                    reason: "would stop the key from being typed".to_string(),
+                });
+            }
+        }
+        conflicts
+    }
+
+    /// Fail with a description of the first conflict, if any
+    pub fn ensure_valid(&self) -> McpResult<()> {
+        match self.conflicts().first() {
+            Some(conflict) => Err(McpError::InvalidRequest(format!(
+                "'{}' in {} is {}: {}",
+                conflict.key,
+                conflict.scope,
+                conflict.reason,
+                conflict.actions.join(", ")
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+static KEYMAP: Lazy<Arc<RwLock<Keymap>>> = Lazy::new(|| Arc::new(RwLock::new(Keymap::load())));
+
+/// Get the global keymap
+pub fn get_keymap() -> Arc<RwLock<Keymap>> {
+    KEYMAP.clone()
+}
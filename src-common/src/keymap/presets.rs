@@ -0,0 +1,108 @@
+/// A bindable action
+#[derive(Debug, Clone, Copy)]
+pub struct ActionInfo {
+    /// Action ID; everything before the last dot is its scope
+    pub id: &'static str,
+
+    /// What the action does
+    pub description: &'static str,
+
+    /// Keys bound by the default preset
+    pub default_keys: &'static [&'static str],
+}
+
+/// Every action that can be bound, grouped by scope
+pub const ACTIONS: &[ActionInfo] = &[
+    // TUI conversation list
+    ActionInfo { id: "tui.normal.quit", description: "Quit", default_keys: &["q"] },
+    ActionInfo { id: "tui.normal.help", description: "Show help", default_keys: &["?", "f1"] },
+    ActionInfo { id: "tui.normal.settings", description: "Open settings", default_keys: &["s"] },
+    ActionInfo { id: "tui.normal.conflicts", description: "Review sync conflicts", default_keys: &["c"] },
+    ActionInfo { id: "tui.normal.up", description: "Previous conversation", default_keys: &["up", "k"] },
+    ActionInfo { id: "tui.normal.down", description: "Next conversation", default_keys: &["down", "j"] },
+    ActionInfo { id: "tui.normal.open", description: "Open conversation", default_keys: &["enter"] },
+    ActionInfo { id: "tui.normal.new", description: "New conversation", default_keys: &["n"] },
+    ActionInfo { id: "tui.normal.delete", description: "Delete conversation", default_keys: &["d"] },
+    ActionInfo { id: "tui.normal.command", description: "Command prompt", default_keys: &[":"] },
+    ActionInfo { id: "tui.normal.page_up", description: "Scroll messages up", default_keys: &["pageup"] },
+    ActionInfo { id: "tui.normal.page_down", description: "Scroll messages down", default_keys: &["pagedown"] },
+    ActionInfo { id: "tui.normal.first_unread", description: "Jump to first unread message", default_keys: &["u"] },
+    ActionInfo { id: "tui.normal.reload", description: "Reload conversations", default_keys: &["r"] },
+    ActionInfo { id: "tui.normal.rate_up", description: "Rate latest reply up", default_keys: &["+"] },
+    ActionInfo { id: "tui.normal.rate_down", description: "Rate latest reply down", default_keys: &["-"] },
+    // TUI message input
+    ActionInfo { id: "tui.chat.send", description: "Send message", default_keys: &["ctrl+enter"] },
+    ActionInfo { id: "tui.chat.leave", description: "Leave input", default_keys: &["esc"] },
+    // TUI command prompt
+    ActionInfo { id: "tui.command.run", description: "Run command", default_keys: &["enter"] },
+    ActionInfo { id: "tui.command.cancel", description: "Cancel command", default_keys: &["esc"] },
+    // TUI help screen
+    ActionInfo { id: "tui.help.close", description: "Close help", default_keys: &["esc", "q"] },
+    // TUI settings screen
+    ActionInfo { id: "tui.settings.close", description: "Close settings", default_keys: &["esc"] },
+    ActionInfo { id: "tui.settings.up", description: "Previous setting", default_keys: &["up", "k"] },
+    ActionInfo { id: "tui.settings.down", description: "Next setting", default_keys: &["down", "j"] },
+    ActionInfo { id: "tui.settings.toggle", description: "Change setting", default_keys: &["enter", "space"] },
+    // TUI sync conflict review
+    ActionInfo { id: "tui.conflicts.close", description: "Close conflict review", default_keys: &["esc", "q"] },
+    ActionInfo { id: "tui.conflicts.up", description: "Previous conflict", default_keys: &["up", "k"] },
+    ActionInfo { id: "tui.conflicts.down", description: "Next conflict", default_keys: &["down", "j"] },
+    ActionInfo { id: "tui.conflicts.keep_local", description: "Keep local version", default_keys: &["l"] },
+    ActionInfo { id: "tui.conflicts.keep_remote", description: "Keep remote version", default_keys: &["r"] },
+    ActionInfo { id: "tui.conflicts.merge", description: "Keep both versions", default_keys: &["m"] },
+    // Desktop app
+    ActionInfo { id: "gui.new_conversation", description: "New conversation", default_keys: &["ctrl+n"] },
+    ActionInfo { id: "gui.send", description: "Send message", default_keys: &["ctrl+enter"] },
+    ActionInfo { id: "gui.undo", description: "Undo", default_keys: &["ctrl+z"] },
+    ActionInfo { id: "gui.redo", description: "Redo", default_keys: &["ctrl+shift+z", "ctrl+y"] },
+    ActionInfo { id: "gui.search", description: "Search conversations", default_keys: &["ctrl+k"] },
+    ActionInfo { id: "gui.settings", description: "Open settings", default_keys: &["ctrl+,"] },
+    ActionInfo { id: "gui.toggle_sidebar", description: "Toggle sidebar", default_keys: &["ctrl+b"] },
+    ActionInfo { id: "gui.previous_conversation", description: "Previous conversation", default_keys: &["alt+up"] },
+    ActionInfo { id: "gui.next_conversation", description: "Next conversation", default_keys: &["alt+down"] },
+    ActionInfo { id: "gui.cancel", description: "Stop generating", default_keys: &["esc"] },
+];
+
+/// Built-in presets
+pub const PRESETS: &[&str] = &["default", "vim", "emacs"];
+
+/// Bindings a preset changes relative to the default one
+pub fn preset_overrides(name: &str) -> Option<&'static [(&'static str, &'static [&'static str])]> {
+    match name {
+        "default" => Some(&[]),
+        "vim" => Some(&[
+            ("tui.normal.quit", &["q", "ctrl+c"]),
+            ("tui.normal.up", &["k", "up"]),
+            ("tui.normal.down", &["j", "down"]),
+            ("tui.normal.open", &["enter", "l", "i"]),
+            ("tui.normal.delete", &["x"]),
+            ("tui.normal.page_up", &["ctrl+b", "ctrl+u", "pageup"]),
+            ("tui.normal.page_down", &["ctrl+f", "ctrl+d", "pagedown"]),
+            ("tui.chat.send", &["ctrl+enter", "ctrl+s"]),
+            ("tui.chat.leave", &["esc", "ctrl+["]),
+            ("tui.command.cancel", &["esc", "ctrl+["]),
+        ]),
+        "emacs" => Some(&[
+            ("tui.normal.quit", &["ctrl+q"]),
+            ("tui.normal.up", &["ctrl+p", "up"]),
+            ("tui.normal.down", &["ctrl+n", "down"]),
+            ("tui.normal.new", &["ctrl+o"]),
+            ("tui.normal.delete", &["ctrl+k"]),
+            ("tui.normal.command", &["alt+x", ":"]),
+            ("tui.normal.page_up", &["alt+v", "pageup"]),
+            ("tui.normal.page_down", &["ctrl+v", "pagedown"]),
+            ("tui.normal.help", &["ctrl+h", "f1"]),
+            ("tui.chat.send", &["ctrl+enter", "ctrl+j"]),
+            ("tui.chat.leave", &["ctrl+g", "esc"]),
+            ("tui.command.cancel", &["ctrl+g", "esc"]),
+            ("tui.help.close", &["ctrl+g", "esc", "q"]),
+            ("tui.settings.close", &["ctrl+g", "esc"]),
+            ("tui.settings.up", &["ctrl+p", "up"]),
+            ("tui.settings.down", &["ctrl+n", "down"]),
+            ("tui.conflicts.close", &["ctrl+g", "esc"]),
+            ("tui.conflicts.up", &["ctrl+p", "up"]),
+            ("tui.conflicts.down", &["ctrl+n", "down"]),
+        ]),
+        _ => None,
+    }
+}
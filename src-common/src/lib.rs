@@ -1,9 +1,30 @@
+pub mod agent;
+pub mod auth;
 pub mod config;
+pub mod environment;
 pub mod error;
+pub mod events;
+pub mod feeds;
+pub mod i18n;
+pub mod integrations;
+pub mod jobs;
+pub mod keymap;
+pub mod logs;
+pub mod memory;
 pub mod models;
+pub mod platform;
 pub mod protocol;
+pub mod rag;
+pub mod reminders;
 pub mod service;
+pub mod snapshots;
+pub mod sync;
+pub mod templates;
+#[cfg(feature = "test-support")]
+pub mod testing;
+pub mod theme;
 pub mod utils;
+pub mod webhooks;
 
 use once_cell::sync::OnceCell;
 use std::sync::Arc;
@@ -18,10 +39,9 @@ pub fn init_mcp_service() -> Arc<McpService> {
     // Create a shared MCP service instance
     let service = Arc::new(McpService::new());
     
-    // Store in global cell if not already set
-    if let Err(_) = MCP_SERVICE.set(service.clone()) {
-        // Already initialized, just return the new instance
-    }
+    // Store in global cell if not already set; when already initialized,
+    // just return the new instance
+    let _ = MCP_SERVICE.set(service.clone());
     
     service
 }
@@ -0,0 +1,163 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::redaction::Redactor;
+use super::sink::{log_files, logs_dir};
+use crate::config::{get_settings, Settings};
+use crate::error::{McpError, McpResult};
+use crate::service::onboarding::{scan_hardware, HardwareScan};
+use crate::utils::{app_version, clock};
+
+/// A file in a support bundle, already redacted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleItem {
+    /// Path inside the archive
+    pub name: String,
+
+    /// What the file holds
+    pub description: String,
+
+    /// File contents
+    pub content: String,
+}
+
+impl BundleItem {
+    fn new(name: &str, description: &str, content: String) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            content,
+        }
+    }
+
+    /// Size of the contents in bytes
+    pub fn size(&self) -> usize {
+        self.content.len()
+    }
+}
+
+/// Environment the logs were written in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostics {
+    /// When the bundle was collected
+    pub collected_at: DateTime<Utc>,
+
+    /// Application version
+    pub app_version: String,
+
+    /// Operating system and architecture
+    pub os: String,
+
+    /// Operating system release, when known
+    pub os_release: Option<String>,
+
+    /// CPU cores and memory
+    pub hardware: HardwareScan,
+
+    /// Log files found, including ones too old to include
+    pub log_files: usize,
+}
+
+/// Recent logs, settings and diagnostics to share with support. Everything
+/// is redacted when collected, so the items are exactly what gets written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogBundle {
+    /// When the bundle was collected
+    pub created_at: DateTime<Utc>,
+
+    /// Files to write; drop any the user doesn't want to share
+    pub items: Vec<BundleItem>,
+}
+
+impl LogBundle {
+    /// Collect log files written in the last `days` days from the log
+    /// directory, with the current settings
+    pub fn collect(days: u32) -> McpResult<Self> {
+        let settings = get_settings().lock().unwrap().clone();
+        Self::collect_from(&logs_dir(), &settings, days, clock::now())
+    }
+
+    /// Collect log files written since `days` days before `now` from a
+    /// directory. Logs are redacted as configured, and secrets always.
+    pub fn collect_from(logs_dir: &Path, settings: &Settings, days: u32, now: DateTime<Utc>) -> McpResult<Self> {
+        let redactor = Redactor::new(&settings.logging).with_secrets_redacted();
+        let since = now - Duration::days(days.max(1) as i64);
+        let files = log_files(logs_dir);
+        let mut items = Vec::new();
+
+        for path in &files {
+            let modified: DateTime<Utc> = fs::metadata(path)?.modified()?.into();
+            if modified < since {
+                continue;
+            }
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let content = String::from_utf8_lossy(&fs::read(path)?).into_owned();
+            items.push(BundleItem::new(
+                &format!("logs/{}", name),
+                &format!("Log lines until {}", modified.format("%Y-%m-%d %H:%M")),
+                redactor.redact(&content),
+            ));
+        }
+
+        // Who uses the installation stays out of the bundle entirely
+        let mut config = serde_json::to_value(settings)?;
+        if let Some(map) = config.as_object_mut() {
+            map.remove("profile");
+        }
+        redactor.redact_json(&mut config);
+        items.push(BundleItem::new(
+            "settings.json",
+            "Settings, without secrets or your profile",
+            serde_json::to_string_pretty(&config)?,
+        ));
+
+        let diagnostics = Diagnostics {
+            collected_at: now,
+            app_version: app_version(),
+            os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            os_release: sys_info::os_release().ok(),
+            hardware: scan_hardware(),
+            log_files: files.len(),
+        };
+        items.push(BundleItem::new(
+            "diagnostics.json",
+            "App version, operating system and hardware",
+            serde_json::to_string_pretty(&diagnostics)?,
+        ));
+
+        Ok(Self { created_at: now, items })
+    }
+
+    /// File name to suggest for the archive
+    pub fn file_name(&self) -> String {
+        format!("mcp-logs-{}.zip", self.created_at.format("%Y%m%d-%H%M%S"))
+    }
+
+    /// Total size of the items in bytes
+    pub fn size(&self) -> usize {
+        self.items.iter().map(BundleItem::size).sum()
+    }
+
+    /// Write the items to a zip archive; returns the archive size in bytes
+    pub fn write_zip(&self, path: &Path) -> McpResult<u64> {
+        if self.items.is_empty() {
+            return Err(McpError::InvalidRequest("Nothing to put in the bundle".to_string()));
+        }
+
+        let zip_error = |e: zip::result::ZipError| McpError::Io(e.into());
+        let mut zip = ZipWriter::new(File::create(path)?);
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        for item in &self.items {
+            zip.start_file(item.name.as_str(), options).map_err(zip_error)?;
+            zip.write_all(item.content.as_bytes())?;
+        }
+        zip.finish().map_err(zip_error)?;
+
+        Ok(fs::metadata(path)?.len())
+    }
+}
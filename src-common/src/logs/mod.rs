@@ -0,0 +1,11 @@
+//! Application logs with API keys, file paths and optionally prompts
+//! redacted before they are printed or written, and support bundles of
+//! recent logs, settings and diagnostics.
+
+pub mod bundle;
+pub mod redaction;
+pub mod sink;
+
+pub use bundle::{BundleItem, Diagnostics, LogBundle};
+pub use redaction::Redactor;
+pub use sink::{init_logging, log_files, logs_dir, redact, set_redaction};
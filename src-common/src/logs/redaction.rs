@@ -0,0 +1,152 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+
+use crate::config::LoggingSettings;
+
+/// Replaces secrets in log lines
+pub const REDACTED: &str = "[redacted]";
+
+/// Replaces prompt and response text in log lines
+pub const REDACTED_PROMPT: &str = "[prompt]";
+
+/// Stands in for the directories of a shortened path
+const PATH_PREFIX: &str = "[path]";
+
+/// Settings fields whose values never leave the machine
+const SENSITIVE_FIELDS: &[&str] = &[
+    "api_key",
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "client_secret",
+    "password",
+    "secret",
+    "token",
+];
+
+/// Anthropic and OpenAI style keys
+static KEY_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"\bsk-[A-Za-z0-9_\-]{16,}").unwrap());
+
+/// Bearer tokens in authorization headers
+static BEARER_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\b(bearer)\s+[A-Za-z0-9._~+/=\-]+").unwrap());
+
+/// `name=value` and `"name": "value"` pairs with a secret value
+static ASSIGNMENT_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?i)\b(api[_-]?key|x-api-key|access_token|refresh_token|id_token|client_secret|password|secret|token)(["']?\s*[:=]\s*["']?)[^\s"'&,;}]+"#,
+    )
+    .unwrap()
+});
+
+/// JSON fields holding conversation text
+static PROMPT_FIELD_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)"(prompt|content|text|system)"\s*:\s*"(?:[^"\\]|\\.)*""#).unwrap());
+
+/// `prompt: ...` and `response: ...` up to the end of the line
+static PROMPT_LINE_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?im)\b(prompt|response)\s*[:=]\s*.+$").unwrap());
+
+/// Absolute Unix and Windows paths, after the start of the text, a space,
+/// a quote, `=` or an opening bracket so URLs and relative paths are kept
+static PATH_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(^|[\s"'=(\[])(~?(?:/[^\s/"':,;()\[\]]+){2,}/?|[A-Za-z]:\\[^\s"']+)"#).unwrap()
+});
+
+/// Secret values known to the app, such as secret environment variables
+static KNOWN_SECRETS: Lazy<RwLock<BTreeSet<String>>> = Lazy::new(|| RwLock::new(BTreeSet::new()));
+
+/// Redact a secret value wherever it appears from now on
+pub fn register_secret(value: &str) {
+    if !value.is_empty() {
+        KNOWN_SECRETS.write().unwrap().insert(value.to_string());
+    }
+}
+
+/// Scrubs secrets, file paths and optionally prompts from text
+#[derive(Debug, Clone, Copy)]
+pub struct Redactor {
+    api_keys: bool,
+    prompts: bool,
+    paths: bool,
+}
+
+impl Redactor {
+    /// Redact what the settings ask for
+    pub fn new(settings: &LoggingSettings) -> Self {
+        Self {
+            api_keys: settings.redact_api_keys,
+            prompts: settings.redact_prompts,
+            paths: settings.redact_paths,
+        }
+    }
+
+    /// Same redactions, with secrets always replaced
+    pub fn with_secrets_redacted(self) -> Self {
+        Self { api_keys: true, ..self }
+    }
+
+    /// Redact a log line or file
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+
+        if self.api_keys {
+            for secret in KNOWN_SECRETS.read().unwrap().iter() {
+                text = text.replace(secret.as_str(), REDACTED);
+            }
+            text = KEY_PATTERN.replace_all(&text, REDACTED).into_owned();
+            text = BEARER_PATTERN.replace_all(&text, format!("$1 {}", REDACTED)).into_owned();
+            text = ASSIGNMENT_PATTERN
+                .replace_all(&text, format!("$1$2{}", REDACTED))
+                .into_owned();
+        }
+
+        if self.prompts {
+            text = PROMPT_FIELD_PATTERN
+                .replace_all(&text, format!(r#""$1": "{}""#, REDACTED_PROMPT))
+                .into_owned();
+            text = PROMPT_LINE_PATTERN
+                .replace_all(&text, format!("$1: {}", REDACTED_PROMPT))
+                .into_owned();
+        }
+
+        if self.paths {
+            text = PATH_PATTERN
+                .replace_all(&text, |caps: &Captures| {
+                    let path = caps[2].trim_end_matches(['/', '\\']);
+                    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+                    format!("{}{}/{}", &caps[1], PATH_PREFIX, name)
+                })
+                .into_owned();
+        }
+
+        text
+    }
+
+    /// Redact a JSON document: secret fields are replaced whatever the
+    /// settings, and every string goes through `redact`
+    pub fn redact_json(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (name, value) in map.iter_mut() {
+                    let name = name.to_ascii_lowercase();
+                    if SENSITIVE_FIELDS.contains(&name.as_str()) && !value.is_null() {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_json(value);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_json(item)),
+            Value::String(text) => *text = self.redact(text),
+            _ => {}
+        }
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(&LoggingSettings::default())
+    }
+}
@@ -0,0 +1,196 @@
+use log::{Log, Metadata, Record, SetLoggerError};
+use once_cell::sync::Lazy;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+use super::redaction::Redactor;
+use crate::config::{data_path, get_settings, LoggingSettings};
+use crate::utils::clock;
+
+/// Current log file; rotated ones get a `.1`, `.2`, ... suffix
+pub const LOG_FILE: &str = "mcp.log";
+
+/// Size at which the log file is rotated
+const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Rotated log files kept next to the current one
+const KEPT_FILES: usize = 5;
+
+static REDACTOR: Lazy<RwLock<Redactor>> = Lazy::new(|| RwLock::new(Redactor::default()));
+
+/// Directory the log files are kept in
+pub fn logs_dir() -> PathBuf {
+    data_path("logs")
+}
+
+/// Log files in a directory, the current one first and older ones after
+pub fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(usize, PathBuf)> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            let index = match name.strip_prefix(LOG_FILE)? {
+                "" => 0,
+                suffix => suffix.strip_prefix('.')?.parse().ok()?,
+            };
+            Some((index, path))
+        })
+        .collect();
+    files.sort();
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Change what is redacted from log lines from now on
+pub fn set_redaction(settings: &LoggingSettings) {
+    *REDACTOR.write().unwrap() = Redactor::new(settings);
+}
+
+/// Redact text the way log lines currently are
+pub fn redact(text: &str) -> String {
+    REDACTOR.read().unwrap().redact(text)
+}
+
+/// Append-only log file rotated by size
+pub struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    kept: usize,
+}
+
+impl LogFile {
+    /// Open or create a log file
+    pub fn open(path: PathBuf, max_size: u64, kept: usize) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_size,
+            kept,
+        })
+    }
+
+    /// Append a line, rotating first when the file is full
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 + 1 > self.max_size {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}", index));
+        self.path.with_file_name(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let _ = fs::remove_file(self.rotated(self.kept));
+        for index in (1..self.kept).rev() {
+            let from = self.rotated(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated(index + 1))?;
+            }
+        }
+        if self.kept > 0 {
+            fs::rename(&self.path, self.rotated(1))?;
+        }
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        let _ = self.file.flush();
+    }
+}
+
+/// Redacts every record, then prints it and appends it to the log file
+struct RedactingLogger {
+    console: env_logger::Logger,
+    file: Option<Mutex<LogFile>>,
+}
+
+impl Log for RedactingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.console.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.console.matches(record) {
+            return;
+        }
+
+        let message = redact(&record.args().to_string());
+        // Source file and line are left out; they can contain paths
+        self.console.log(
+            &Record::builder()
+                .args(format_args!("{}", message))
+                .metadata(record.metadata().clone())
+                .module_path(record.module_path())
+                .build(),
+        );
+
+        if let Some(file) = &self.file {
+            let line = format!(
+                "{} {:<5} {} {}",
+                clock::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                record.level(),
+                record.target(),
+                message
+            );
+            if let Err(e) = file.lock().unwrap().write_line(&line) {
+                eprintln!("Failed to write log file: {}", e);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+        if let Some(file) = &self.file {
+            file.lock().unwrap().flush();
+        }
+    }
+}
+
+/// Install the redacting log sink in front of a console logger. Log lines
+/// also go to `logs_dir()` unless file logging is turned off.
+pub fn init_logging(console: env_logger::Logger) -> Result<(), SetLoggerError> {
+    let settings = get_settings().lock().unwrap().logging.clone();
+    set_redaction(&settings);
+
+    let file = if settings.file {
+        match LogFile::open(logs_dir().join(LOG_FILE), MAX_FILE_SIZE, KEPT_FILES) {
+            Ok(file) => Some(Mutex::new(file)),
+            Err(e) => {
+                eprintln!("Logging to the console only; can't open the log file: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    log::set_max_level(console.filter());
+    log::set_boxed_logger(Box::new(RedactingLogger { console, file }))
+}
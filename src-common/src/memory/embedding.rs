@@ -0,0 +1,127 @@
+//! Text embeddings for similarity search.
+//!
+//! The built-in embedder hashes words and word pairs into a fixed number of
+//! buckets. It needs no model, so it works offline and on every device, and
+//! is good enough to find notes that share vocabulary with a message. A
+//! local embedding model can take its place through [`Embedder`].
+
+use std::sync::Arc;
+
+/// Turns text into vectors whose cosine similarity reflects related meaning
+pub trait Embedder: Send + Sync {
+    /// Identifier stored with each vector, so vectors of different models
+    /// are never compared
+    fn id(&self) -> &str;
+
+    /// Embed a text
+    fn embed(&self, text: &str) -> Vec<f32>;
+}
+
+/// Words too common to say anything about a text
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "do", "for", "from", "has", "have", "i", "in", "is", "it",
+    "its", "me", "my", "of", "on", "or", "our", "so", "that", "the", "this", "to", "was", "we", "were", "with", "you",
+    "your",
+];
+
+/// Feature-hashing embedder over words and adjacent word pairs
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    id: String,
+    dimensions: usize,
+}
+
+impl HashingEmbedder {
+    /// Embedder with a number of buckets
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            id: format!("hashing-{}", dimensions),
+            dimensions: dimensions.max(1),
+        }
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+/// Lowercased words of a text, without stop words and crude plural endings
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|w| !w.is_empty() && !STOP_WORDS.contains(&w.as_str()))
+        .map(|w| match w.strip_suffix('s') {
+            Some(stem) if stem.len() > 3 && !stem.ends_with('s') => stem.to_string(),
+            _ => w,
+        })
+        .collect()
+}
+
+/// FNV-1a; unlike the standard hasher it never changes, and stored vectors
+/// have to stay comparable with new ones
+fn fnv1a(text: &str) -> u64 {
+    text.bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+fn bucket(feature: &str, dimensions: usize) -> (usize, f32) {
+    let hash = fnv1a(feature);
+    // One bit picks the sign, so unrelated features cancel out on average
+    let sign = if hash & 1 == 0 { 1.0 } else { -1.0 };
+    ((hash >> 1) as usize % dimensions, sign)
+}
+
+impl Embedder for HashingEmbedder {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0; self.dimensions];
+        let words = words(text);
+        for word in &words {
+            let (index, sign) = bucket(word, self.dimensions);
+            vector[index] += sign;
+        }
+        for pair in words.windows(2) {
+            let (index, sign) = bucket(&format!("{} {}", pair[0], pair[1]), self.dimensions);
+            vector[index] += sign * 0.5;
+        }
+        normalize(&mut vector);
+        vector
+    }
+}
+
+/// Embedder used when none is chosen
+pub const DEFAULT_EMBEDDER: &str = "hashing-256";
+
+/// Built-in embedder with a stored ID, e.g. `hashing-512`
+pub fn embedder_for(id: &str) -> Option<Arc<dyn Embedder>> {
+    let dimensions = id.strip_prefix("hashing-")?.parse::<usize>().ok().filter(|d| *d > 0)?;
+    Some(Arc::new(HashingEmbedder::new(dimensions)))
+}
+
+/// Scale a vector to unit length; zero vectors stay zero
+pub fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+/// Cosine similarity of two vectors; 0 when their lengths differ
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
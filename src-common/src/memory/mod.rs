@@ -0,0 +1,326 @@
+//! Notes the user asked to remember, per workspace.
+//!
+//! `remember` splits a text into short notes and stores each with an
+//! embedding. When a new conversation starts in the workspace, the notes most
+//! similar to its first message are put in front of it as a system message.
+//! Notes outside any workspace are shared by conversations outside any
+//! workspace. Everything is kept on this device.
+
+pub mod embedding;
+
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+use crate::utils::clock;
+use embedding::{cosine, Embedder, HashingEmbedder};
+
+/// Conversation metadata key listing the notes put in front of it
+pub const MEMORIES_METADATA_KEY: &str = "memories";
+
+/// Most notes put in front of a new conversation
+pub const RECALL_LIMIT: usize = 5;
+
+/// Similarity below which a note is not considered relevant
+pub const MIN_SIMILARITY: f32 = 0.2;
+
+/// Similarity above which a new note replaces an existing one
+const DUPLICATE_SIMILARITY: f32 = 0.95;
+
+/// Longest note kept, in characters
+const MAX_NOTE_CHARS: usize = 500;
+
+/// A remembered fact
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryNote {
+    /// Note ID
+    pub id: String,
+
+    /// Workspace the note belongs to; None for conversations outside any
+    pub workspace: Option<String>,
+
+    /// The fact
+    pub text: String,
+
+    /// Conversation the note was taken from
+    #[serde(default)]
+    pub source_conversation: Option<String>,
+
+    /// When the note was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the note was last edited
+    pub updated_at: DateTime<Utc>,
+
+    /// Embedder the vector was made with
+    pub embedding_model: String,
+
+    /// Embedding of the text
+    #[serde(default)]
+    pub embedding: Vec<f32>,
+}
+
+/// A note found for a query, with how similar it is
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecalledNote {
+    /// The note
+    pub note: MemoryNote,
+
+    /// Cosine similarity to the query
+    pub similarity: f32,
+}
+
+/// Notes in a text: a leading "remember (that)" is dropped, and each
+/// sentence or list item becomes a note
+pub fn extract_notes(text: &str) -> Vec<String> {
+    let trimmed = text.trim();
+    let lower = trimmed.to_lowercase();
+    let body = ["please remember that", "please remember", "remember that", "remember:", "remember"]
+        .iter()
+        .find(|prefix| {
+            lower.starts_with(*prefix)
+                && !lower[prefix.len()..].chars().next().is_some_and(char::is_alphanumeric)
+        })
+        .map(|prefix| &trimmed[prefix.len()..])
+        .unwrap_or(trimmed);
+
+    let mut notes = Vec::new();
+    for line in body.lines() {
+        let line = line.trim().trim_start_matches(['-', '*', '•']).trim();
+        let mut sentence = String::new();
+        for (i, c) in line.char_indices() {
+            sentence.push(c);
+            let at_end = line[i + c.len_utf8()..].chars().next().is_none_or(char::is_whitespace);
+            if matches!(c, '.' | '!' | '?' | ';') && at_end {
+                notes.push(std::mem::take(&mut sentence));
+            }
+        }
+        notes.push(sentence);
+    }
+    notes
+        .into_iter()
+        .map(|note| {
+            let note = note.trim().trim_start_matches(':').trim();
+            let mut chars = note.chars();
+            let first = chars.next().map(|c| c.to_uppercase().collect::<String>()).unwrap_or_default();
+            format!("{}{}", first, chars.as_str()).chars().take(MAX_NOTE_CHARS).collect::<String>()
+        })
+        .filter(|note| note.chars().filter(|c| c.is_alphanumeric()).count() >= 3)
+        .collect()
+}
+
+/// Text put in front of a new conversation for recalled notes
+pub fn format_context(notes: &[RecalledNote]) -> String {
+    let mut text = String::from("Things the user asked you to remember:\n");
+    for recalled in notes {
+        text.push_str(&format!("- {}\n", recalled.note.text));
+    }
+    text
+}
+
+/// The notes of every workspace, kept in a file
+pub struct MemoryStore {
+    path: PathBuf,
+    embedder: Arc<dyn Embedder>,
+    notes: Mutex<Vec<MemoryNote>>,
+}
+
+impl MemoryStore {
+    /// Notes kept in a file, embedded with an embedder
+    pub fn at(path: PathBuf, embedder: Arc<dyn Embedder>) -> Self {
+        let notes = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable memories {:?}: {}", path, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+
+        let store = Self {
+            path,
+            embedder,
+            notes: Mutex::new(notes),
+        };
+        store.reembed_stale();
+        store
+    }
+
+    /// Embed again the notes made with another embedder
+    fn reembed_stale(&self) {
+        let mut notes = self.notes.lock().unwrap();
+        let mut changed = false;
+        for note in notes.iter_mut().filter(|n| n.embedding_model != self.embedder.id()) {
+            note.embedding = self.embedder.embed(&note.text);
+            note.embedding_model = self.embedder.id().to_string();
+            changed = true;
+        }
+        if changed {
+            if let Err(e) = self.save(&notes) {
+                warn!("Failed to save re-embedded memories: {}", e);
+            }
+        }
+    }
+
+    fn save(&self, notes: &[MemoryNote]) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(notes)?)?;
+        Ok(())
+    }
+
+    fn changed(workspace: Option<&str>) {
+        get_event_bus().emit(
+            Topic::System,
+            names::MEMORIES_CHANGED,
+            serde_json::json!({ "workspace": workspace }),
+        );
+    }
+
+    /// Remember the facts in a text; returns the notes stored. A fact close
+    /// to one already remembered replaces it.
+    pub fn remember(
+        &self,
+        workspace: Option<&str>,
+        text: &str,
+        source_conversation: Option<&str>,
+    ) -> McpResult<Vec<MemoryNote>> {
+        let facts = extract_notes(text);
+        if facts.is_empty() {
+            return Err(McpError::InvalidRequest("Nothing to remember".to_string()));
+        }
+
+        let now = clock::now();
+        let mut notes = self.notes.lock().unwrap();
+        let mut stored = Vec::new();
+        for fact in facts {
+            let embedding = self.embedder.embed(&fact);
+            let duplicate = notes.iter_mut().find(|n| {
+                n.workspace.as_deref() == workspace && cosine(&n.embedding, &embedding) >= DUPLICATE_SIMILARITY
+            });
+            let note = match duplicate {
+                Some(existing) => {
+                    existing.text = fact;
+                    existing.embedding = embedding;
+                    existing.updated_at = now;
+                    existing.clone()
+                }
+                None => {
+                    let note = MemoryNote {
+                        id: Uuid::new_v4().to_string(),
+                        workspace: workspace.map(str::to_string),
+                        text: fact,
+                        source_conversation: source_conversation.map(str::to_string),
+                        created_at: now,
+                        updated_at: now,
+                        embedding_model: self.embedder.id().to_string(),
+                        embedding,
+                    };
+                    notes.push(note.clone());
+                    note
+                }
+            };
+            stored.push(note);
+        }
+        self.save(&notes)?;
+        drop(notes);
+
+        debug!("Remembered {} notes in {:?}", stored.len(), workspace);
+        Self::changed(workspace);
+        Ok(stored)
+    }
+
+    /// Notes of a workspace, newest first
+    pub fn list(&self, workspace: Option<&str>) -> Vec<MemoryNote> {
+        let mut notes: Vec<MemoryNote> = self
+            .notes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|n| n.workspace.as_deref() == workspace)
+            .cloned()
+            .collect();
+        notes.sort_by_key(|n| std::cmp::Reverse(n.updated_at));
+        notes
+    }
+
+    /// Notes of every workspace
+    pub fn all(&self) -> Vec<MemoryNote> {
+        self.notes.lock().unwrap().clone()
+    }
+
+    /// Change the text of a note
+    pub fn update(&self, id: &str, text: &str) -> McpResult<MemoryNote> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(McpError::InvalidRequest("A note can't be empty".to_string()));
+        }
+
+        let mut notes = self.notes.lock().unwrap();
+        let note = notes
+            .iter_mut()
+            .find(|n| n.id == id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("No memory {}", id)))?;
+        note.text = text.chars().take(MAX_NOTE_CHARS).collect();
+        note.embedding = self.embedder.embed(&note.text);
+        note.embedding_model = self.embedder.id().to_string();
+        note.updated_at = clock::now();
+        let note = note.clone();
+        self.save(&notes)?;
+        drop(notes);
+
+        Self::changed(note.workspace.as_deref());
+        Ok(note)
+    }
+
+    /// Forget a note
+    pub fn delete(&self, id: &str) -> McpResult<()> {
+        let mut notes = self.notes.lock().unwrap();
+        let index = notes
+            .iter()
+            .position(|n| n.id == id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("No memory {}", id)))?;
+        let note = notes.remove(index);
+        self.save(&notes)?;
+        drop(notes);
+
+        Self::changed(note.workspace.as_deref());
+        Ok(())
+    }
+
+    /// Notes of a workspace relevant to a text, most similar first
+    pub fn recall(&self, workspace: Option<&str>, query: &str, limit: usize) -> Vec<RecalledNote> {
+        let query = self.embedder.embed(query);
+        let mut recalled: Vec<RecalledNote> = self
+            .notes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|n| n.workspace.as_deref() == workspace)
+            .map(|n| RecalledNote {
+                similarity: cosine(&n.embedding, &query),
+                note: n.clone(),
+            })
+            .filter(|r| r.similarity >= MIN_SIMILARITY)
+            .collect();
+        recalled.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+        recalled.truncate(limit);
+        recalled
+    }
+}
+
+static MEMORY_STORE: Lazy<Arc<MemoryStore>> =
+    Lazy::new(|| Arc::new(MemoryStore::at(data_path("memories.json"), Arc::new(HashingEmbedder::default()))));
+
+/// Get the global memory store
+pub fn get_memory_store() -> Arc<MemoryStore> {
+    MEMORY_STORE.clone()
+}
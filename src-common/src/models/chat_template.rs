@@ -0,0 +1,297 @@
+//! Chat templates for local models.
+//!
+//! A GGUF chat model was trained on conversations laid out in one particular
+//! way, with its own role markers and end-of-turn tokens; prompted any other
+//! way it rambles or answers for the user. The template of a model comes
+//! from, in order: the `chat_template` override in its catalog entry, the
+//! `tokenizer.chat_template` in its GGUF header, its architecture, and
+//! finally its name. Models nothing is known about get a plain transcript.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use super::message::MessageRole;
+
+/// Conversation layout a model expects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatTemplate {
+    /// `<|im_start|>role ... <|im_end|>`: Qwen, Hermes, Yi, Phi-3.5 and many fine-tunes
+    ChatMl,
+
+    /// `<|start_header_id|>role<|end_header_id|> ... <|eot_id|>`: Llama 3
+    Llama3,
+
+    /// `[INST] ... [/INST]`: Mistral, Mixtral and Llama 2
+    Mistral,
+
+    /// `<|role|> ... </s>`: Zephyr and TinyLlama chat
+    Zephyr,
+
+    /// `<start_of_turn>role ... <end_of_turn>`: Gemma
+    Gemma,
+
+    /// `User: ... Assistant:` transcript, for models without a known template
+    #[default]
+    Plain,
+}
+
+impl ChatTemplate {
+    /// All templates, in the order they are offered
+    pub const ALL: [ChatTemplate; 6] = [
+        ChatTemplate::ChatMl,
+        ChatTemplate::Llama3,
+        ChatTemplate::Mistral,
+        ChatTemplate::Zephyr,
+        ChatTemplate::Gemma,
+        ChatTemplate::Plain,
+    ];
+
+    /// Stable identifier used in catalogs and settings
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatTemplate::ChatMl => "chatml",
+            ChatTemplate::Llama3 => "llama3",
+            ChatTemplate::Mistral => "mistral",
+            ChatTemplate::Zephyr => "zephyr",
+            ChatTemplate::Gemma => "gemma",
+            ChatTemplate::Plain => "plain",
+        }
+    }
+
+    /// Template of a Jinja chat template as found in a GGUF header or a
+    /// `tokenizer_config.json`, recognized by its markers
+    pub fn detect(jinja: &str) -> Option<ChatTemplate> {
+        if jinja.contains("<|start_header_id|>") {
+            Some(ChatTemplate::Llama3)
+        } else if jinja.contains("<|im_start|>") {
+            Some(ChatTemplate::ChatMl)
+        } else if jinja.contains("<start_of_turn>") {
+            Some(ChatTemplate::Gemma)
+        } else if jinja.contains("[INST]") {
+            Some(ChatTemplate::Mistral)
+        } else if jinja.contains("<|user|>") && jinja.contains("<|assistant|>") {
+            Some(ChatTemplate::Zephyr)
+        } else {
+            None
+        }
+    }
+
+    /// Template usually used by models of a GGUF architecture. Architectures
+    /// shared by families with different templates, like `llama`, give none.
+    pub fn for_architecture(architecture: &str) -> Option<ChatTemplate> {
+        match architecture.to_lowercase().as_str() {
+            "qwen" | "qwen2" | "qwen2moe" | "qwen3" | "phi3" | "internlm2" | "starcoder2" => Some(ChatTemplate::ChatMl),
+            "gemma" | "gemma2" | "gemma3" => Some(ChatTemplate::Gemma),
+            _ => None,
+        }
+    }
+
+    /// Template guessed from a model's ID or name
+    pub fn for_name(name: &str) -> Option<ChatTemplate> {
+        let name = name.to_lowercase().replace(['-', '_', ' ', '.'], "");
+        if name.contains("llama3") {
+            Some(ChatTemplate::Llama3)
+        } else if name.contains("mistral") || name.contains("mixtral") || name.contains("llama2") {
+            Some(ChatTemplate::Mistral)
+        } else if name.contains("tinyllama") || name.contains("zephyr") {
+            Some(ChatTemplate::Zephyr)
+        } else if name.contains("gemma") {
+            Some(ChatTemplate::Gemma)
+        } else if ["qwen", "hermes", "chatml", "phi3"].iter().any(|n| name.contains(n)) {
+            Some(ChatTemplate::ChatMl)
+        } else {
+            None
+        }
+    }
+
+    /// Template of a model from what is known about it, most specific first
+    pub fn resolve(
+        configured: Option<&str>,
+        tokenizer_template: Option<&str>,
+        architecture: Option<&str>,
+        names: &[&str],
+    ) -> ChatTemplate {
+        configured
+            .and_then(|name| name.parse().ok())
+            .or_else(|| tokenizer_template.and_then(ChatTemplate::detect))
+            .or_else(|| architecture.and_then(ChatTemplate::for_architecture))
+            .or_else(|| names.iter().find_map(|name| ChatTemplate::for_name(name)))
+            .unwrap_or(ChatTemplate::Plain)
+    }
+
+    /// Lay out a conversation, ending where the model writes the next reply
+    pub fn render(&self, turns: &[ChatTurn]) -> String {
+        match self {
+            ChatTemplate::ChatMl => {
+                let mut out = String::new();
+                for turn in turns {
+                    let role = match turn.role {
+                        MessageRole::System => "system",
+                        MessageRole::User => "user",
+                        MessageRole::Assistant => "assistant",
+                        MessageRole::Tool => "tool",
+                    };
+                    out.push_str(&format!("<|im_start|>{}\n{}<|im_end|>\n", role, turn.text));
+                }
+                out.push_str("<|im_start|>assistant\n");
+                out
+            }
+            ChatTemplate::Llama3 => {
+                let mut out = String::from("<|begin_of_text|>");
+                for turn in turns {
+                    let role = match turn.role {
+                        MessageRole::System => "system",
+                        MessageRole::User => "user",
+                        MessageRole::Assistant => "assistant",
+                        MessageRole::Tool => "ipython",
+                    };
+                    out.push_str(&format!(
+                        "<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>",
+                        role, turn.text
+                    ));
+                }
+                out.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+                out
+            }
+            ChatTemplate::Mistral => {
+                // No system role: the system prompt opens the first instruction
+                let system = system_prompt(turns);
+                let mut out = String::from("<s>");
+                let mut pending_system = system.as_deref();
+                for turn in turns.iter().filter(|t| t.role != MessageRole::System) {
+                    match turn.role {
+                        MessageRole::Assistant => out.push_str(&format!(" {}</s>", turn.text)),
+                        _ => {
+                            let text = match pending_system.take() {
+                                Some(system) => format!("{}\n\n{}", system, turn.text),
+                                None => turn.text.clone(),
+                            };
+                            out.push_str(&format!("[INST] {} [/INST]", text));
+                        }
+                    }
+                }
+                out
+            }
+            ChatTemplate::Zephyr => {
+                let mut out = String::new();
+                for turn in turns {
+                    let role = match turn.role {
+                        MessageRole::System => "system",
+                        MessageRole::Assistant => "assistant",
+                        MessageRole::User | MessageRole::Tool => "user",
+                    };
+                    out.push_str(&format!("<|{}|>\n{}</s>\n", role, turn.text));
+                }
+                out.push_str("<|assistant|>\n");
+                out
+            }
+            ChatTemplate::Gemma => {
+                // No system role either
+                let system = system_prompt(turns);
+                let mut out = String::from("<bos>");
+                let mut pending_system = system.as_deref();
+                for turn in turns.iter().filter(|t| t.role != MessageRole::System) {
+                    let (role, text) = match turn.role {
+                        MessageRole::Assistant => ("model", turn.text.clone()),
+                        _ => match pending_system.take() {
+                            Some(system) => ("user", format!("{}\n\n{}", system, turn.text)),
+                            None => ("user", turn.text.clone()),
+                        },
+                    };
+                    out.push_str(&format!("<start_of_turn>{}\n{}<end_of_turn>\n", role, text));
+                }
+                out.push_str("<start_of_turn>model\n");
+                out
+            }
+            ChatTemplate::Plain => {
+                let mut out = String::new();
+                for turn in turns {
+                    let role = match turn.role {
+                        MessageRole::System => "System",
+                        MessageRole::User => "User",
+                        MessageRole::Assistant => "Assistant",
+                        MessageRole::Tool => "Tool",
+                    };
+                    out.push_str(&format!("{}: {}\n\n", role, turn.text));
+                }
+                out.push_str("Assistant:");
+                out
+            }
+        }
+    }
+
+    /// Sequences that end the model's turn; generation stops at any of them
+    pub fn stop_sequences(&self) -> &'static [&'static str] {
+        match self {
+            ChatTemplate::ChatMl => &["<|im_end|>", "<|im_start|>"],
+            ChatTemplate::Llama3 => &["<|eot_id|>", "<|start_header_id|>"],
+            ChatTemplate::Mistral => &["</s>", "[INST]"],
+            ChatTemplate::Zephyr => &["</s>", "<|user|>"],
+            ChatTemplate::Gemma => &["<end_of_turn>", "<start_of_turn>"],
+            ChatTemplate::Plain => &["\nUser:", "\nSystem:"],
+        }
+    }
+}
+
+/// System messages of a conversation joined into one prompt
+fn system_prompt(turns: &[ChatTurn]) -> Option<String> {
+    let system: Vec<&str> = turns
+        .iter()
+        .filter(|t| t.role == MessageRole::System)
+        .map(|t| t.text.as_str())
+        .collect();
+    if system.is_empty() {
+        None
+    } else {
+        Some(system.join("\n\n"))
+    }
+}
+
+impl fmt::Display for ChatTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ChatTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase().replace(['-', '_'], "");
+        match name.as_str() {
+            "chatml" => Ok(ChatTemplate::ChatMl),
+            "llama3" => Ok(ChatTemplate::Llama3),
+            "mistral" | "llama2" => Ok(ChatTemplate::Mistral),
+            "zephyr" => Ok(ChatTemplate::Zephyr),
+            "gemma" => Ok(ChatTemplate::Gemma),
+            "plain" => Ok(ChatTemplate::Plain),
+            _ => Err(format!(
+                "Unknown chat template '{}'; expected one of: {}",
+                s,
+                ChatTemplate::ALL.iter().map(|t| t.as_str()).collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+}
+
+/// One turn of a conversation as a local model sees it: a role and its text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatTurn {
+    /// Who wrote the turn
+    pub role: MessageRole,
+
+    /// Text of the turn; other content is left out
+    pub text: String,
+}
+
+impl ChatTurn {
+    /// Create a turn
+    pub fn new(role: MessageRole, text: impl Into<String>) -> Self {
+        Self {
+            role,
+            text: text.into(),
+        }
+    }
+}
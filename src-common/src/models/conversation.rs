@@ -4,6 +4,7 @@ use uuid::Uuid;
 
 use super::model::Model;
 use super::message::Message;
+use crate::utils::clock;
 
 /// Represents a conversation with a model
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,13 +30,17 @@ pub struct Conversation {
     /// Messages in this conversation
     #[serde(default)]
     pub messages: Vec<Message>,
+    
+    /// Replies not read yet on any device; set when conversations are listed
+    #[serde(default)]
+    pub unread: usize,
 }
 
 /// Implementation for Conversation
 impl Conversation {
     /// Create a new conversation
     pub fn new(title: impl Into<String>, model: Model) -> Self {
-        let now = SystemTime::now();
+        let now = clock::system_now();
         Self {
             id: Uuid::new_v4().to_string(),
             title: title.into(),
@@ -44,24 +49,25 @@ impl Conversation {
             model,
             metadata: serde_json::Value::Object(serde_json::Map::new()),
             messages: Vec::new(),
+            unread: 0,
         }
     }
     
     /// Set conversation title
     pub fn set_title(&mut self, title: impl Into<String>) {
         self.title = title.into();
-        self.updated_at = SystemTime::now();
+        self.updated_at = clock::system_now();
     }
     
     /// Add a message to the conversation
     pub fn add_message(&mut self, message: Message) {
         self.messages.push(message);
-        self.updated_at = SystemTime::now();
+        self.updated_at = clock::system_now();
     }
     
     /// Calculate conversation age
     pub fn age(&self) -> Duration {
-        SystemTime::now()
+        clock::system_now()
             .duration_since(self.created_at)
             .unwrap_or(Duration::from_secs(0))
     }
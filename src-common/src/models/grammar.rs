@@ -0,0 +1,1019 @@
+//! Constrained output for local models.
+//!
+//! Local models can be held to a GBNF grammar while they decode, so only
+//! text the grammar accepts comes out; that is how structured output works
+//! offline. A JSON schema is turned into a grammar first. The grammar can't
+//! enforce everything a schema says (string patterns, numeric bounds), so
+//! results are checked against the schema afterwards as well. Object
+//! members are expected in the order of the schema's `properties`.
+//!
+//! The GBNF dialect is llama.cpp's: `name ::= alternatives`, string
+//! literals, character classes (`[a-z]`, `[^"]`), `.`, groups, and the
+//! repetitions `*`, `+`, `?` and `{m}`, `{m,}`, `{m,n}`. Comments start
+//! with `#`.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use crate::error::{McpError, McpResult};
+
+/// Deepest rule nesting followed when sampling or matching
+const MAX_DEPTH: usize = 64;
+
+/// What a model's output must look like
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputFormat {
+    /// Free text
+    #[default]
+    Text,
+
+    /// Text accepted by a GBNF grammar
+    Grammar { gbnf: String },
+
+    /// JSON matching a JSON schema
+    JsonSchema { schema: Value },
+}
+
+impl OutputFormat {
+    /// Grammar to constrain decoding with, if any
+    pub fn grammar(&self) -> McpResult<Option<Grammar>> {
+        match self {
+            OutputFormat::Text => Ok(None),
+            OutputFormat::Grammar { gbnf } => Grammar::parse(gbnf).map(Some),
+            OutputFormat::JsonSchema { schema } => Grammar::from_json_schema(schema).map(Some),
+        }
+    }
+
+    /// Check a model's output; returns the parsed value for JSON schemas
+    pub fn check(&self, output: &str) -> McpResult<Option<Value>> {
+        match self {
+            OutputFormat::Text => Ok(None),
+            OutputFormat::Grammar { gbnf } => {
+                if Grammar::parse(gbnf)?.matches(output) {
+                    Ok(None)
+                } else {
+                    Err(McpError::InvalidRequest("Output doesn't match the grammar".to_string()))
+                }
+            }
+            OutputFormat::JsonSchema { schema } => {
+                let value: Value = serde_json::from_str(output.trim())
+                    .map_err(|e| McpError::InvalidRequest(format!("Output isn't JSON: {}", e)))?;
+                let errors = validate(schema, &value);
+                if errors.is_empty() {
+                    Ok(Some(value))
+                } else {
+                    Err(McpError::InvalidRequest(format!(
+                        "Output doesn't match the schema: {}",
+                        errors.join("; ")
+                    )))
+                }
+            }
+        }
+    }
+}
+
+/// One element of a grammar rule
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// Exact text
+    Literal(Vec<char>),
+
+    /// One character in (or, negated, not in) a set of ranges
+    Class { ranges: Vec<(char, char)>, negated: bool },
+
+    /// Any one character
+    Any,
+
+    /// Another rule
+    Rule(String),
+
+    /// Parenthesized alternatives
+    Group(Vec<Vec<Node>>),
+
+    /// A repeated element
+    Repeat { node: Box<Node>, min: usize, max: Option<usize> },
+}
+
+/// A parsed GBNF grammar
+#[derive(Debug, Clone)]
+pub struct Grammar {
+    source: String,
+    rules: HashMap<String, Vec<Vec<Node>>>,
+}
+
+impl Grammar {
+    /// Parse and check a GBNF grammar: it needs a `root` rule, and every
+    /// rule it refers to must be defined
+    pub fn parse(gbnf: &str) -> McpResult<Grammar> {
+        let mut parser = Parser { chars: gbnf.chars().collect(), pos: 0 };
+        let mut rules = HashMap::new();
+        let mut order = Vec::new();
+
+        parser.skip_space(true);
+        while !parser.at_end() {
+            let name = parser.name().ok_or_else(|| parser.error("expected a rule name"))?;
+            parser.skip_space(false);
+            if !parser.eat_str("::=") {
+                return Err(parser.error(&format!("expected ::= after {}", name)));
+            }
+            let alternatives = parser.alternatives(false)?;
+            if rules.insert(name.clone(), alternatives).is_some() {
+                return Err(grammar_error(&format!("rule {} is defined twice", name)));
+            }
+            order.push(name);
+            parser.skip_space(true);
+        }
+
+        if !rules.contains_key("root") {
+            return Err(grammar_error("no root rule"));
+        }
+        for name in &order {
+            for missing in references(&rules[name]) {
+                if !rules.contains_key(&missing) {
+                    return Err(grammar_error(&format!("rule {} refers to undefined rule {}", name, missing)));
+                }
+            }
+        }
+
+        Ok(Grammar {
+            source: gbnf.to_string(),
+            rules,
+        })
+    }
+
+    /// Grammar accepting JSON that matches a schema, as far as a grammar
+    /// can express it
+    pub fn from_json_schema(schema: &Value) -> McpResult<Grammar> {
+        let mut converter = SchemaConverter::default();
+        let root = converter.visit(schema, "root")?;
+        if root != "root" {
+            converter.add("root", root);
+        }
+        converter.add_used_primitives();
+        Grammar::parse(&converter.finish())
+    }
+
+    /// GBNF source of the grammar
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the grammar accepts a text as a whole
+    pub fn matches(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        let mut matcher = Matcher {
+            grammar: self,
+            input: &chars,
+            memo: HashMap::new(),
+            active: HashSet::new(),
+        };
+        matcher.rule("root", 0, 0).contains(&chars.len())
+    }
+
+    /// A short text the grammar accepts, or None if it only accepts texts
+    /// nested too deep to build
+    pub fn sample(&self) -> Option<String> {
+        let mut out = String::new();
+        if sample_rule(self, "root", 0, &mut out) {
+            Some(out)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Grammar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn grammar_error(message: &str) -> McpError {
+    McpError::InvalidRequest(format!("Invalid grammar: {}", message))
+}
+
+/// Rules referred to by a rule body
+fn references(alternatives: &[Vec<Node>]) -> Vec<String> {
+    fn walk(node: &Node, out: &mut Vec<String>) {
+        match node {
+            Node::Rule(name) => out.push(name.clone()),
+            Node::Group(alternatives) => alternatives.iter().flatten().for_each(|n| walk(n, out)),
+            Node::Repeat { node, .. } => walk(node, out),
+            _ => {}
+        }
+    }
+    let mut out = Vec::new();
+    alternatives.iter().flatten().for_each(|n| walk(n, &mut out));
+    out
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, message: &str) -> McpError {
+        let line = self.chars[..self.pos.min(self.chars.len())].iter().filter(|&&c| c == '\n').count() + 1;
+        grammar_error(&format!("{} on line {}", message, line))
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let expected: Vec<char> = s.chars().collect();
+        if self.chars[self.pos..].starts_with(&expected) {
+            self.pos += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Skip blanks and comments; newlines only where a rule can't end
+    fn skip_space(&mut self, newlines: bool) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+            } else if c == ' ' || c == '\t' || c == '\r' || (newlines && c == '\n') {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn name(&mut self) -> Option<String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            self.pos += 1;
+        }
+        if self.pos > start {
+            Some(self.chars[start..self.pos].iter().collect())
+        } else {
+            None
+        }
+    }
+
+    /// Whether a rule definition (`name ::=`) starts here
+    fn at_rule_start(&self) -> bool {
+        let mut pos = self.pos;
+        while self.chars.get(pos).is_some_and(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_') {
+            pos += 1;
+        }
+        if pos == self.pos {
+            return false;
+        }
+        while self.chars.get(pos).is_some_and(|c| *c == ' ' || *c == '\t') {
+            pos += 1;
+        }
+        self.chars[pos..].starts_with(&[':', ':', '='])
+    }
+
+    /// Alternatives up to the end of a rule, or of a group when nested
+    fn alternatives(&mut self, nested: bool) -> McpResult<Vec<Vec<Node>>> {
+        let mut alternatives = vec![self.sequence(nested)?];
+        loop {
+            self.skip_space(nested);
+            // A rule continues on the next line when that line starts with |
+            let saved = self.pos;
+            if !nested {
+                self.skip_space(true);
+            }
+            if self.peek() == Some('|') {
+                self.pos += 1;
+                alternatives.push(self.sequence(nested)?);
+            } else {
+                self.pos = saved;
+                return Ok(alternatives);
+            }
+        }
+    }
+
+    fn sequence(&mut self, nested: bool) -> McpResult<Vec<Node>> {
+        let mut sequence = Vec::new();
+        loop {
+            self.skip_space(nested || sequence.is_empty());
+            let node = match self.peek() {
+                None | Some('|') | Some(')') | Some('\n') => return Ok(sequence),
+                Some(_) if !nested && self.at_rule_start() => return Ok(sequence),
+                Some('"') => {
+                    self.pos += 1;
+                    let mut text = Vec::new();
+                    loop {
+                        match self.peek() {
+                            None => return Err(self.error("unterminated string")),
+                            Some('"') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            Some(_) => text.push(self.char_in_literal()?),
+                        }
+                    }
+                    Node::Literal(text)
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    let negated = self.peek() == Some('^');
+                    if negated {
+                        self.pos += 1;
+                    }
+                    let mut ranges = Vec::new();
+                    loop {
+                        match self.peek() {
+                            None => return Err(self.error("unterminated character class")),
+                            Some(']') => {
+                                self.pos += 1;
+                                break;
+                            }
+                            Some(_) => {
+                                let start = self.char_in_literal()?;
+                                let end = if self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']') {
+                                    self.pos += 1;
+                                    self.char_in_literal()?
+                                } else {
+                                    start
+                                };
+                                ranges.push((start, end));
+                            }
+                        }
+                    }
+                    Node::Class { ranges, negated }
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    Node::Any
+                }
+                Some('(') => {
+                    self.pos += 1;
+                    let alternatives = self.alternatives(true)?;
+                    self.skip_space(true);
+                    if self.peek() != Some(')') {
+                        return Err(self.error("expected )"));
+                    }
+                    self.pos += 1;
+                    Node::Group(alternatives)
+                }
+                Some(_) => match self.name() {
+                    Some(name) => Node::Rule(name),
+                    None => return Err(self.error(&format!("unexpected {:?}", self.peek().unwrap()))),
+                },
+            };
+            sequence.push(self.repetition(node)?);
+        }
+    }
+
+    fn repetition(&mut self, node: Node) -> McpResult<Node> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.pos += 1;
+                let min = self.number().ok_or_else(|| self.error("expected a count"))?;
+                let max = if self.peek() == Some(',') {
+                    self.pos += 1;
+                    self.number()
+                } else {
+                    Some(min)
+                };
+                if self.peek() != Some('}') {
+                    return Err(self.error("expected }"));
+                }
+                if max.is_some_and(|max| max < min) {
+                    return Err(self.error("repetition maximum is below its minimum"));
+                }
+                (min, max)
+            }
+            _ => return Ok(node),
+        };
+        self.pos += 1;
+        Ok(Node::Repeat { node: Box::new(node), min, max })
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect::<String>().parse().ok()
+    }
+
+    /// A character of a string literal or class, with escapes
+    fn char_in_literal(&mut self) -> McpResult<char> {
+        let c = self.peek().ok_or_else(|| self.error("unexpected end"))?;
+        self.pos += 1;
+        if c != '\\' {
+            return Ok(c);
+        }
+        let escaped = self.peek().ok_or_else(|| self.error("unexpected end"))?;
+        self.pos += 1;
+        let digits = match escaped {
+            'n' => return Ok('\n'),
+            't' => return Ok('\t'),
+            'r' => return Ok('\r'),
+            'x' => 2,
+            'u' => 4,
+            'U' => 8,
+            other => return Ok(other),
+        };
+        let end = (self.pos + digits).min(self.chars.len());
+        let hex: String = self.chars[self.pos..end].iter().collect();
+        self.pos = end;
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(&format!("bad escape \\{}{}", escaped, hex)))
+    }
+}
+
+/// Backtracking matcher; each step returns every position the input can
+/// be matched up to
+struct Matcher<'a> {
+    grammar: &'a Grammar,
+    input: &'a [char],
+    memo: HashMap<(String, usize), BTreeSet<usize>>,
+    active: HashSet<(String, usize)>,
+}
+
+impl<'a> Matcher<'a> {
+    fn rule(&mut self, name: &str, pos: usize, depth: usize) -> BTreeSet<usize> {
+        let key = (name.to_string(), pos);
+        if let Some(ends) = self.memo.get(&key) {
+            return ends.clone();
+        }
+        // Left recursion or runaway nesting matches nothing
+        if depth > MAX_DEPTH || !self.active.insert(key.clone()) {
+            return BTreeSet::new();
+        }
+        let grammar = self.grammar;
+        let ends = self.alternatives(&grammar.rules[name], pos, depth + 1);
+        self.active.remove(&key);
+        self.memo.insert(key, ends.clone());
+        ends
+    }
+
+    fn alternatives(&mut self, alternatives: &[Vec<Node>], pos: usize, depth: usize) -> BTreeSet<usize> {
+        let mut ends = BTreeSet::new();
+        for sequence in alternatives {
+            let mut positions = BTreeSet::from([pos]);
+            for node in sequence {
+                let mut next = BTreeSet::new();
+                for &p in &positions {
+                    next.extend(self.node(node, p, depth));
+                }
+                positions = next;
+                if positions.is_empty() {
+                    break;
+                }
+            }
+            ends.extend(positions);
+        }
+        ends
+    }
+
+    fn node(&mut self, node: &Node, pos: usize, depth: usize) -> BTreeSet<usize> {
+        match node {
+            Node::Literal(text) => {
+                if self.input[pos..].starts_with(text) {
+                    BTreeSet::from([pos + text.len()])
+                } else {
+                    BTreeSet::new()
+                }
+            }
+            Node::Class { ranges, negated } => match self.input.get(pos) {
+                Some(c) if ranges.iter().any(|(lo, hi)| lo <= c && c <= hi) != *negated => BTreeSet::from([pos + 1]),
+                _ => BTreeSet::new(),
+            },
+            Node::Any => {
+                if pos < self.input.len() {
+                    BTreeSet::from([pos + 1])
+                } else {
+                    BTreeSet::new()
+                }
+            }
+            Node::Rule(name) => self.rule(name, pos, depth),
+            Node::Group(alternatives) => self.alternatives(alternatives, pos, depth),
+            Node::Repeat { node, min, max } => {
+                let mut ends = BTreeSet::new();
+                let mut frontier = BTreeSet::from([pos]);
+                let mut seen = BTreeSet::new();
+                let mut count = 0;
+                loop {
+                    if count >= *min {
+                        ends.extend(frontier.iter().copied());
+                    }
+                    if max.is_some_and(|max| count >= max) || frontier.is_empty() {
+                        break;
+                    }
+                    let mut next = BTreeSet::new();
+                    for &p in &frontier {
+                        next.extend(self.node(node, p, depth));
+                    }
+                    count += 1;
+                    // Past the minimum, positions already reached add nothing
+                    if count > *min {
+                        next.retain(|p| !seen.contains(p));
+                    }
+                    seen.extend(next.iter().copied());
+                    frontier = next;
+                }
+                ends
+            }
+        }
+    }
+}
+
+fn sample_rule(grammar: &Grammar, name: &str, depth: usize, out: &mut String) -> bool {
+    depth <= MAX_DEPTH && sample_alternatives(grammar, &grammar.rules[name], depth + 1, out)
+}
+
+fn sample_alternatives(grammar: &Grammar, alternatives: &[Vec<Node>], depth: usize, out: &mut String) -> bool {
+    for sequence in alternatives {
+        let start = out.len();
+        if sequence.iter().all(|node| sample_node(grammar, node, depth, out)) {
+            return true;
+        }
+        out.truncate(start);
+    }
+    false
+}
+
+fn sample_node(grammar: &Grammar, node: &Node, depth: usize, out: &mut String) -> bool {
+    match node {
+        Node::Literal(text) => {
+            out.extend(text);
+            true
+        }
+        Node::Class { ranges, negated: false } => match ranges.first() {
+            Some((lo, _)) => {
+                out.push(*lo);
+                true
+            }
+            None => false,
+        },
+        Node::Class { ranges, negated: true } => {
+            match ('a'..='z').chain('0'..='9').find(|c| !ranges.iter().any(|(lo, hi)| lo <= c && c <= hi)) {
+                Some(c) => {
+                    out.push(c);
+                    true
+                }
+                None => false,
+            }
+        }
+        Node::Any => {
+            out.push('a');
+            true
+        }
+        Node::Rule(name) => sample_rule(grammar, name, depth, out),
+        Node::Group(alternatives) => sample_alternatives(grammar, alternatives, depth, out),
+        Node::Repeat { node, min, .. } => (0..*min).all(|_| sample_node(grammar, node, depth, out)),
+    }
+}
+
+/// JSON primitives the converter builds on, in GBNF
+const PRIMITIVES: &[(&str, &str)] = &[
+    ("space", r#"" "?"#),
+    ("value", "object | array | string | number | boolean | null"),
+    ("object", r#""{" space ( string ":" space value ( "," space string ":" space value )* )? "}" space"#),
+    ("array", r#""[" space ( value ( "," space value )* )? "]" space"#),
+    ("string", r#""\"" char* "\"" space"#),
+    ("char", r#"[^"\\\x00-\x1F\x7F] | "\\" ( ["\\/bfnrt] | "u" [0-9a-fA-F]{4} )"#),
+    ("number", r#"integer-part ( "." [0-9]+ )? ( [eE] [-+]? [0-9]+ )? space"#),
+    ("integer", "integer-part space"),
+    ("integer-part", r#""-"? ( "0" | [1-9] [0-9]* )"#),
+    ("boolean", r#"( "true" | "false" ) space"#),
+    ("null", r#""null" space"#),
+];
+
+/// Rules primitives refer to
+fn primitive_dependencies(name: &str) -> &'static [&'static str] {
+    match name {
+        "value" => &["object", "array", "string", "number", "boolean", "null"],
+        "object" => &["space", "string", "value"],
+        "array" => &["space", "value"],
+        "string" => &["char", "space"],
+        "number" | "integer" => &["integer-part", "space"],
+        "boolean" | "null" => &["space"],
+        _ => &[],
+    }
+}
+
+#[derive(Default)]
+struct SchemaConverter {
+    rules: Vec<(String, String)>,
+    names: HashSet<String>,
+    primitives: BTreeSet<&'static str>,
+}
+
+impl SchemaConverter {
+    fn add(&mut self, name: &str, body: String) -> String {
+        let mut unique = name.to_string();
+        let mut n = 1;
+        while !self.names.insert(unique.clone()) {
+            n += 1;
+            unique = format!("{}-{}", name, n);
+        }
+        self.rules.push((unique.clone(), body));
+        unique
+    }
+
+    fn primitive(&mut self, name: &'static str) -> String {
+        self.primitives.insert(name);
+        name.to_string()
+    }
+
+    fn add_used_primitives(&mut self) {
+        let mut pending: Vec<&'static str> = self.primitives.iter().copied().collect();
+        while let Some(name) = pending.pop() {
+            for dependency in primitive_dependencies(name) {
+                if self.primitives.insert(*dependency) {
+                    pending.push(*dependency);
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut out: Vec<String> = self.rules.iter().map(|(name, body)| format!("{} ::= {}", name, body)).collect();
+        for (name, body) in PRIMITIVES {
+            if self.primitives.contains(name) {
+                out.push(format!("{} ::= {}", name, body));
+            }
+        }
+        out.join("\n") + "\n"
+    }
+
+    /// Rule name for a schema, adding the rules it needs
+    fn visit(&mut self, schema: &Value, name: &str) -> McpResult<String> {
+        let schema = match schema {
+            Value::Bool(true) => return Ok(self.primitive("value")),
+            Value::Bool(false) => return Err(schema_error("a schema that accepts nothing")),
+            Value::Object(schema) => schema,
+            _ => return Err(schema_error("schemas must be objects")),
+        };
+
+        if schema.contains_key("$ref") {
+            return Err(schema_error("$ref isn't supported"));
+        }
+        if let Some(value) = schema.get("const") {
+            return Ok(self.add(name, json_literal(value)));
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            let body = values.iter().map(json_literal).collect::<Vec<_>>().join(" | ");
+            return Ok(self.add(name, body));
+        }
+        for key in ["anyOf", "oneOf"] {
+            if let Some(options) = schema.get(key).and_then(Value::as_array) {
+                let mut alternatives = Vec::new();
+                for (i, option) in options.iter().enumerate() {
+                    alternatives.push(self.visit(option, &format!("{}-{}", name, i))?);
+                }
+                return Ok(self.add(name, alternatives.join(" | ")));
+            }
+        }
+
+        match schema.get("type") {
+            Some(Value::Array(types)) => {
+                let mut alternatives = Vec::new();
+                for t in types {
+                    let mut single = schema.clone();
+                    single.insert("type".to_string(), t.clone());
+                    let t = t.as_str().unwrap_or("value");
+                    alternatives.push(self.visit(&Value::Object(single), &format!("{}-{}", name, t))?);
+                }
+                Ok(self.add(name, alternatives.join(" | ")))
+            }
+            Some(Value::String(t)) => match t.as_str() {
+                "object" => self.object(schema, name),
+                "array" => self.array(schema, name),
+                "string" => {
+                    let min = schema.get("minLength").and_then(Value::as_u64);
+                    let max = schema.get("maxLength").and_then(Value::as_u64);
+                    if min.is_none() && max.is_none() {
+                        return Ok(self.primitive("string"));
+                    }
+                    self.primitive("char");
+                    self.primitive("space");
+                    let repeat = match (min.unwrap_or(0), max) {
+                        (min, Some(max)) => format!("{{{},{}}}", min, max),
+                        (min, None) => format!("{{{},}}", min),
+                    };
+                    Ok(self.add(name, format!(r#""\"" char{} "\"" space"#, repeat)))
+                }
+                "number" => Ok(self.primitive("number")),
+                "integer" => Ok(self.primitive("integer")),
+                "boolean" => Ok(self.primitive("boolean")),
+                "null" => Ok(self.primitive("null")),
+                other => Err(schema_error(&format!("unknown type {}", other))),
+            },
+            Some(_) => Err(schema_error("type must be a string or a list")),
+            None if schema.contains_key("properties") => self.object(schema, name),
+            None if schema.contains_key("items") => self.array(schema, name),
+            None => Ok(self.primitive("value")),
+        }
+    }
+
+    fn object(&mut self, schema: &serde_json::Map<String, Value>, name: &str) -> McpResult<String> {
+        let properties = match schema.get("properties").and_then(Value::as_object) {
+            Some(properties) if !properties.is_empty() => properties,
+            _ => return Ok(self.primitive("object")),
+        };
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        self.primitive("space");
+
+        let mut pairs = Vec::new();
+        for (key, property) in properties {
+            let rule = self.visit(property, &format!("{}-{}", name, rule_name(key)))?;
+            let pair = format!(r#"{} ":" space {}"#, json_literal(&Value::String(key.clone())), rule);
+            pairs.push((required.contains(&key.as_str()), pair));
+        }
+        let (required_pairs, optional_pairs): (Vec<_>, Vec<_>) = pairs.into_iter().partition(|(r, _)| *r);
+        let required_pairs: Vec<String> = required_pairs.into_iter().map(|(_, p)| p).collect();
+        let optional_pairs: Vec<String> = optional_pairs.into_iter().map(|(_, p)| p).collect();
+
+        let optional_after = |from: usize| -> String {
+            optional_pairs[from..]
+                .iter()
+                .map(|p| format!(r#" ( "," space {} )?"#, p))
+                .collect()
+        };
+        let members = if !required_pairs.is_empty() {
+            format!("{}{}", required_pairs.join(r#" "," space "#), optional_after(0))
+        } else {
+            // Any optional member can come first
+            let firsts: Vec<String> = (0..optional_pairs.len())
+                .map(|i| format!("{}{}", optional_pairs[i], optional_after(i + 1)))
+                .collect();
+            format!("( {} )?", firsts.join(" | "))
+        };
+        Ok(self.add(name, format!(r#""{{" space {} "}}" space"#, members)))
+    }
+
+    fn array(&mut self, schema: &serde_json::Map<String, Value>, name: &str) -> McpResult<String> {
+        let item = match schema.get("items") {
+            Some(items) => self.visit(items, &format!("{}-item", name))?,
+            None => self.primitive("value"),
+        };
+        self.primitive("space");
+        let min = schema.get("minItems").and_then(Value::as_u64).unwrap_or(0);
+        let max = schema.get("maxItems").and_then(Value::as_u64);
+        let more = |n: u64| -> String {
+            let max = max.map(|max| max.saturating_sub(1).to_string()).unwrap_or_default();
+            format!(r#"( "," space {} ){{{},{}}}"#, item, n, max)
+        };
+        let items = match (min, max) {
+            (_, Some(0)) => String::new(),
+            (0, _) => format!("( {} {} )?", item, more(0)),
+            (min, _) => format!("{} {}", item, more(min - 1)),
+        };
+        Ok(self.add(name, format!(r#""[" space {} "]" space"#, items)))
+    }
+}
+
+fn schema_error(message: &str) -> McpError {
+    McpError::InvalidRequest(format!("Unsupported JSON schema: {}", message))
+}
+
+/// Rule name made from a property name
+fn rule_name(key: &str) -> String {
+    let name: String = key.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect();
+    if name.is_empty() {
+        "property".to_string()
+    } else {
+        name
+    }
+}
+
+/// GBNF literal for a JSON value, followed by optional space
+fn json_literal(value: &Value) -> String {
+    let json = value.to_string();
+    let escaped = json.replace('\\', "\\\\").replace('"', "\\\"");
+    format!(r#""{}" space"#, escaped)
+}
+
+/// Check a value against a JSON schema; returns what doesn't match, each
+/// prefixed with the JSON pointer of the offending value
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(schema, value, "", &mut errors);
+    errors
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            errors.push(format!("{}: not allowed", display_path(path)));
+            return;
+        }
+        Value::Object(schema) => schema,
+        _ => return,
+    };
+    let mut fail = |message: String| errors.push(format!("{}: {}", display_path(path), message));
+
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            fail(format!("expected {}", expected));
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            fail(format!("expected one of {}", Value::Array(options.clone())));
+        }
+    }
+    match schema.get("type") {
+        Some(Value::String(t)) if !has_type(value, t) => fail(format!("expected {}", t)),
+        Some(Value::Array(types)) if !types.iter().filter_map(Value::as_str).any(|t| has_type(value, t)) => {
+            fail(format!("expected one of {}", Value::Array(types.clone())))
+        }
+        _ => {}
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(options) = schema.get(key).and_then(Value::as_array) {
+            let matching = options.iter().filter(|o| validate(o, value).is_empty()).count();
+            if matching == 0 || (key == "oneOf" && matching > 1) {
+                fail(format!("doesn't match {} of the {} options", if key == "oneOf" { "exactly one" } else { "any" }, key));
+            }
+        }
+    }
+
+    match value {
+        Value::String(s) => {
+            let length = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if length < min {
+                    fail(format!("shorter than {} characters", min));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if length > max {
+                    fail(format!("longer than {} characters", max));
+                }
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                match Regex::new(pattern) {
+                    Ok(re) if !re.is_match(s) => fail(format!("doesn't match {}", pattern)),
+                    Err(_) => fail(format!("invalid pattern {}", pattern)),
+                    _ => {}
+                }
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or(0.0);
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if n < min {
+                    fail(format!("less than {}", min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if n > max {
+                    fail(format!("more than {}", max));
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if (items.len() as u64) < min {
+                    fail(format!("fewer than {} items", min));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if (items.len() as u64) > max {
+                    fail(format!("more than {} items", max));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(item_schema, item, &format!("{}/{}", path, i), errors);
+                }
+            }
+        }
+        Value::Object(members) => {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !members.contains_key(key) {
+                        fail(format!("missing {}", key));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, member) in members {
+                let member_path = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                match properties.and_then(|p| p.get(key)) {
+                    Some(property) => validate_at(property, member, &member_path, errors),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => errors.push(format!("{}: not allowed", member_path)),
+                        Some(extra @ Value::Object(_)) => validate_at(extra, member, &member_path, errors),
+                        _ => {}
+                    },
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn has_type(value: &Value, t: &str) -> bool {
+    match t {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.as_i64().is_some() || value.as_u64().is_some() || value.as_f64().is_some_and(|f| f.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() {
+        "/"
+    } else {
+        path
+    }
+}
+
+/// Example value matching a JSON schema, used when decoding is simulated
+pub fn example(schema: &Value) -> McpResult<Value> {
+    let schema = match schema.as_object() {
+        Some(schema) => schema,
+        None => return Ok(Value::Null),
+    };
+    if let Some(value) = schema.get("const") {
+        return Ok(value.clone());
+    }
+    if let Some(first) = schema.get("enum").and_then(Value::as_array).and_then(|e| e.first()) {
+        return Ok(first.clone());
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(first) = schema.get(key).and_then(Value::as_array).and_then(|o| o.first()) {
+            return example(first);
+        }
+    }
+    let t = match schema.get("type") {
+        Some(Value::String(t)) => t.as_str(),
+        Some(Value::Array(types)) => types.first().and_then(Value::as_str).unwrap_or("null"),
+        None if schema.contains_key("properties") => "object",
+        None if schema.contains_key("items") => "array",
+        Some(_) => return Err(schema_error("type must be a string or a list")),
+        None => "null",
+    };
+    Ok(match t {
+        "object" => {
+            let required: Vec<&str> = schema
+                .get("required")
+                .and_then(Value::as_array)
+                .map(|r| r.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            let mut out = serde_json::Map::new();
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (key, property) in properties {
+                    if required.is_empty() || required.contains(&key.as_str()) {
+                        out.insert(key.clone(), example(property)?);
+                    }
+                }
+            }
+            Value::Object(out)
+        }
+        "array" => {
+            let min = schema.get("minItems").and_then(Value::as_u64).unwrap_or(1).max(1);
+            let max = schema.get("maxItems").and_then(Value::as_u64).unwrap_or(u64::MAX);
+            let item = schema.get("items").map(example).transpose()?.unwrap_or(Value::Null);
+            Value::Array(vec![item; min.min(max) as usize])
+        }
+        "string" => {
+            let min = schema.get("minLength").and_then(Value::as_u64).unwrap_or(0) as usize;
+            let max = schema.get("maxLength").and_then(Value::as_u64).map_or(usize::MAX, |m| m as usize);
+            let length = 7.min(max).max(min);
+            Value::String("example".chars().cycle().take(length).collect())
+        }
+        "integer" | "number" => {
+            let bound = schema
+                .get("minimum")
+                .or_else(|| schema.get("maximum"))
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0);
+            serde_json::json!(bound.ceil() as i64)
+        }
+        "boolean" => Value::Bool(true),
+        _ => Value::Null,
+    })
+}
@@ -0,0 +1,556 @@
+//! Hugging Face Hub integration for finding and registering GGUF models.
+//!
+//! Searches return the GGUF files of matching repositories with their size,
+//! checksum and license, and whether they fit in this machine's memory.
+//! Registering a file reads the metadata in its GGUF header (architecture,
+//! context length, chat template) and adds it to the `huggingface` catalog of the model
+//! registry, from where the local provider downloads it. Downloads from the
+//! Hub send the Hugging Face token, for gated and private repositories.
+
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use super::chat_template::ChatTemplate;
+use super::registry::{
+    connection_error, family_key, get, get_model_registry, parameters_from_name, quantization_from_name, CatalogEntry,
+    RegistryEntry,
+};
+use crate::config::config_path;
+use crate::error::{McpError, McpResult};
+use crate::service::onboarding::scan_hardware;
+use crate::utils::security;
+
+/// Hugging Face Hub API base URL
+pub const HF_API: &str = "https://huggingface.co/api";
+
+/// Hugging Face file download base URL
+pub const HF_BASE: &str = "https://huggingface.co";
+
+/// Registry catalog holding models registered from the Hub
+pub const HF_CATALOG: &str = "huggingface";
+
+/// Encrypted Hugging Face token in the config directory
+const TOKEN_FILE: &str = "huggingface.token";
+
+/// Bytes of a GGUF file fetched to read its metadata
+const GGUF_HEADER_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Split GGUF files (`model-00001-of-00003.gguf`) need every part, which
+/// the local provider can't download yet
+static SPLIT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"-\d{5}-of-\d{5}\.gguf$").unwrap());
+
+/// Hugging Face token: `HF_TOKEN`, or the one stored with [`set_token`]
+pub fn token() -> Option<String> {
+    if let Ok(token) = std::env::var("HF_TOKEN") {
+        if !token.trim().is_empty() {
+            return Some(token.trim().to_string());
+        }
+    }
+    let encrypted = fs::read(config_path(TOKEN_FILE)).ok()?;
+    security::decrypt(&encrypted)
+        .map_err(|e| warn!("Failed to decrypt Hugging Face token: {}", e))
+        .ok()
+}
+
+/// Store the Hugging Face token (encrypted, like the API key); `None`
+/// removes it
+pub fn set_token(token: Option<&str>) -> McpResult<()> {
+    let path = config_path(TOKEN_FILE);
+    match token.map(str::trim).filter(|t| !t.is_empty()) {
+        Some(token) => {
+            let encrypted = security::encrypt(token)
+                .map_err(|e| McpError::Config(format!("Failed to encrypt Hugging Face token: {}", e)))?;
+            fs::write(path, encrypted)?;
+        }
+        None if path.exists() => fs::remove_file(path)?,
+        None => {}
+    }
+    Ok(())
+}
+
+/// Whether a URL points at the Hub
+pub fn is_hub_url(url: &str) -> bool {
+    url.starts_with(&format!("{}/", HF_BASE))
+}
+
+/// Largest model file this machine is expected to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HardwareLimits {
+    /// Largest file size in bytes
+    pub max_file_bytes: u64,
+}
+
+impl HardwareLimits {
+    /// Limits for this machine: a model may take three quarters of memory
+    pub fn detect() -> Self {
+        let memory = scan_hardware().memory_bytes;
+        Self {
+            max_file_bytes: if memory == 0 { u64::MAX } else { memory / 4 * 3 },
+        }
+    }
+
+    /// No limit
+    pub fn unlimited() -> Self {
+        Self { max_file_bytes: u64::MAX }
+    }
+
+    /// Whether a file of this size fits; files of unknown size do
+    pub fn fits(&self, size: Option<u64>) -> bool {
+        size.is_none_or(|size| size <= self.max_file_bytes)
+    }
+}
+
+/// A GGUF file in a Hub repository
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HfFile {
+    /// Path in the repository
+    pub path: String,
+
+    /// Size in bytes, if the Hub reports it
+    pub size: Option<u64>,
+
+    /// Hex SHA-256 of the file, for files stored with LFS
+    pub sha256: Option<String>,
+
+    /// Quantization type parsed from the file name
+    pub quantization: Option<String>,
+
+    /// Whether the file fits the hardware limits of the search
+    pub fits: bool,
+}
+
+/// A Hub repository with GGUF files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HfModel {
+    /// Repository ID, e.g. "TheBloke/Llama-2-7B-Chat-GGUF"
+    pub repo: String,
+
+    /// Downloads in the last month
+    pub downloads: u64,
+
+    /// Likes
+    pub likes: u64,
+
+    /// License identifier, e.g. "apache-2.0"
+    pub license: Option<String>,
+
+    /// Whether access must be requested on the Hub first
+    pub gated: bool,
+
+    /// Parameter count parsed from the repository name
+    pub parameters: Option<u64>,
+
+    /// GGUF files, smallest first
+    pub files: Vec<HfFile>,
+}
+
+impl HfModel {
+    /// The largest file that fits, which usually has the best quality
+    pub fn best_file(&self) -> Option<&HfFile> {
+        self.files.iter().rfind(|f| f.fits)
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiModel {
+    id: String,
+    #[serde(default)]
+    downloads: u64,
+    #[serde(default)]
+    likes: u64,
+    #[serde(default)]
+    tags: Vec<String>,
+    // `false`, or "auto" / "manual"
+    #[serde(default)]
+    gated: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct ApiTreeEntry {
+    #[serde(rename = "type")]
+    entry_type: String,
+    path: String,
+    #[serde(default)]
+    size: Option<u64>,
+    #[serde(default)]
+    lfs: Option<ApiLfs>,
+}
+
+#[derive(Deserialize)]
+struct ApiLfs {
+    oid: String,
+    #[serde(default)]
+    size: Option<u64>,
+}
+
+fn license_of(tags: &[String]) -> Option<String> {
+    tags.iter().find_map(|t| t.strip_prefix("license:").map(String::from))
+}
+
+/// GGUF files of a repository, smallest first
+async fn model_files(repo: &str, limits: &HardwareLimits, token: Option<&str>) -> McpResult<Vec<HfFile>> {
+    let tree: Vec<ApiTreeEntry> = get(&format!("{}/models/{}/tree/main?recursive=true", HF_API, repo), token)
+        .await?
+        .json()
+        .await
+        .map_err(connection_error)?;
+
+    let mut files: Vec<HfFile> = tree
+        .into_iter()
+        .filter(|e| e.entry_type == "file" && e.path.ends_with(".gguf") && !SPLIT_RE.is_match(&e.path))
+        .map(|e| {
+            let size = e.lfs.as_ref().and_then(|l| l.size).or(e.size);
+            let stem = e.path.rsplit('/').next().unwrap_or(&e.path).trim_end_matches(".gguf").to_string();
+            HfFile {
+                fits: limits.fits(size),
+                sha256: e.lfs.map(|l| l.oid),
+                quantization: quantization_from_name(&stem),
+                path: e.path,
+                size,
+            }
+        })
+        .collect();
+    files.sort_by_key(|f| f.size.unwrap_or(u64::MAX));
+    Ok(files)
+}
+
+fn to_model(api: ApiModel, files: Vec<HfFile>) -> HfModel {
+    HfModel {
+        parameters: parameters_from_name(&api.id),
+        license: license_of(&api.tags),
+        gated: !matches!(api.gated, serde_json::Value::Bool(false) | serde_json::Value::Null),
+        repo: api.id,
+        downloads: api.downloads,
+        likes: api.likes,
+        files,
+    }
+}
+
+/// Search the Hub for GGUF models, most downloaded first. Repositories
+/// without a file that fits the limits are left out.
+pub async fn search(query: &str, limits: HardwareLimits, limit: usize) -> McpResult<Vec<HfModel>> {
+    let token = token();
+    let limit = limit.to_string();
+    let url = reqwest::Url::parse_with_params(
+        &format!("{}/models", HF_API),
+        &[
+            ("search", query),
+            ("filter", "gguf"),
+            ("sort", "downloads"),
+            ("direction", "-1"),
+            ("limit", limit.as_str()),
+        ],
+    )
+    .map_err(|e| McpError::InvalidRequest(e.to_string()))?;
+    let results: Vec<ApiModel> = get(url.as_str(), token.as_deref())
+        .await?
+        .json()
+        .await
+        .map_err(connection_error)?;
+
+    let mut models = Vec::new();
+    for api in results {
+        let files = match model_files(&api.id, &limits, token.as_deref()).await {
+            Ok(files) => files,
+            Err(e) => {
+                debug!("Skipping {}: {}", api.id, e);
+                continue;
+            }
+        };
+        if files.iter().any(|f| f.fits) {
+            models.push(to_model(api, files));
+        }
+    }
+    Ok(models)
+}
+
+/// A repository and its GGUF files
+pub async fn model_info(repo: &str) -> McpResult<HfModel> {
+    let token = token();
+    let api: ApiModel = get(&format!("{}/models/{}", HF_API, repo), token.as_deref())
+        .await?
+        .json()
+        .await
+        .map_err(connection_error)?;
+    let files = model_files(repo, &HardwareLimits::detect(), token.as_deref()).await?;
+    Ok(to_model(api, files))
+}
+
+/// Download URL of a file in a repository
+pub fn file_url(repo: &str, path: &str) -> String {
+    format!("{}/{}/resolve/main/{}", HF_BASE, repo, path)
+}
+
+/// Catalog entry for a GGUF file, without reading its header
+pub fn entry(model: &HfModel, file: &HfFile) -> CatalogEntry {
+    let file_name = file.path.rsplit('/').next().unwrap_or(&file.path).to_string();
+    let stem = file_name.trim_end_matches(".gguf").to_string();
+    CatalogEntry {
+        id: format!("hf:{}/{}", model.repo, stem).to_lowercase(),
+        name: stem.clone(),
+        kind: "chat".to_string(),
+        download_url: file_url(&model.repo, &file.path),
+        parameters: parameters_from_name(&stem).or(model.parameters).unwrap_or(0),
+        quantization: file.quantization.clone().unwrap_or_default(),
+        context_size: 2048,
+        version: "1.0".to_string(),
+        size: file.size,
+        sha256: file.sha256.clone(),
+        license: model.license.clone(),
+        description: Some(format!("{} on Hugging Face", model.repo)),
+        family: Some(family_key(&stem)),
+        changelog: None,
+        architecture: None,
+        chat_template: None,
+        file_name,
+    }
+}
+
+/// Register a GGUF file of a repository in the model registry, with
+/// metadata read from its header. A chat template given here overrides the
+/// one the header names.
+pub async fn register(repo: &str, path: &str, chat_template: Option<ChatTemplate>) -> McpResult<RegistryEntry> {
+    let model = model_info(repo).await?;
+    let file = model
+        .files
+        .iter()
+        .find(|f| f.path == path)
+        .ok_or_else(|| McpError::InvalidRequest(format!("{} has no GGUF file {}", repo, path)))?;
+    let mut entry = entry(&model, file);
+
+    match fetch_metadata(&entry.download_url).await {
+        Ok(metadata) => metadata.apply(&mut entry),
+        Err(e) => warn!("Could not read the GGUF header of {}: {}", entry.download_url, e),
+    }
+    if let Some(template) = chat_template {
+        entry.chat_template = Some(template.to_string());
+    }
+    get_model_registry().register(HF_CATALOG, "Hugging Face", entry)
+}
+
+/// Read the metadata at the start of a remote GGUF file
+async fn fetch_metadata(url: &str) -> McpResult<GgufMetadata> {
+    let token = token();
+    let mut request = reqwest::Client::new()
+        .get(url)
+        .header(reqwest::header::RANGE, format!("bytes=0-{}", GGUF_HEADER_BYTES - 1));
+    if let Some(token) = &token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.map_err(connection_error)?;
+    if !response.status().is_success() {
+        return Err(McpError::Connection(format!("{} returned {}", url, response.status())));
+    }
+
+    // Servers that ignore the range send the whole file; stop reading early
+    let mut bytes = Vec::new();
+    let mut response = response;
+    while let Some(chunk) = response.chunk().await.map_err(connection_error)? {
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() as u64 >= GGUF_HEADER_BYTES {
+            break;
+        }
+    }
+    parse_gguf_metadata(&bytes)
+}
+
+/// Model metadata from a GGUF header
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GgufMetadata {
+    /// Model architecture, e.g. "llama"
+    pub architecture: Option<String>,
+
+    /// Model name given by its author
+    pub name: Option<String>,
+
+    /// Context length the model was trained with
+    pub context_length: Option<u64>,
+
+    /// Parameter count, from `general.size_label` or similar
+    pub size_label: Option<String>,
+
+    /// Jinja chat template from `tokenizer.chat_template`, if it came
+    /// before the header was cut off
+    #[serde(default)]
+    pub chat_template: Option<String>,
+}
+
+impl GgufMetadata {
+    /// Fill in a catalog entry with what the header says
+    pub fn apply(&self, entry: &mut CatalogEntry) {
+        if let Some(context_length) = self.context_length {
+            entry.context_size = context_length as usize;
+        }
+        if let Some(parameters) = self.size_label.as_deref().and_then(parameters_from_name) {
+            entry.parameters = parameters;
+        }
+        if let Some(architecture) = &self.architecture {
+            // Encoder-only models embed text rather than chat
+            if architecture.contains("bert") {
+                entry.kind = "embedding".to_string();
+            }
+            entry.architecture = Some(architecture.clone());
+        }
+        if entry.chat_template.is_none() {
+            if let Some(template) = self.chat_template.as_deref().and_then(ChatTemplate::detect) {
+                entry.chat_template = Some(template.to_string());
+            }
+        }
+    }
+}
+
+/// Reader over a GGUF header that may be cut off anywhere
+struct GgufReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> GgufReader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(n)?;
+        let slice = self.bytes.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = usize::try_from(self.u64()?).ok()?;
+        Some(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    /// Read a value of a GGUF type; numbers are returned as u64 when they
+    /// fit, strings as text, anything else is skipped
+    fn value(&mut self, value_type: u32) -> Option<GgufValue> {
+        Some(match value_type {
+            0 | 1 | 7 => GgufValue::Number(self.take(1)?[0] as u64),
+            2 | 3 => GgufValue::Number(u16::from_le_bytes(self.take(2)?.try_into().ok()?) as u64),
+            4 | 5 => GgufValue::Number(self.u32()? as u64),
+            6 => {
+                self.take(4)?;
+                GgufValue::Other
+            }
+            8 => GgufValue::Text(self.string()?),
+            9 => {
+                let item_type = self.u32()?;
+                // Nested arrays don't hold anything of interest; refusing
+                // them also bounds the recursion
+                if item_type == 9 {
+                    return None;
+                }
+                let count = self.u64()?;
+                for _ in 0..count {
+                    self.value(item_type)?;
+                }
+                GgufValue::Other
+            }
+            10 | 11 => GgufValue::Number(self.u64()?),
+            12 => {
+                self.take(8)?;
+                GgufValue::Other
+            }
+            _ => return None,
+        })
+    }
+}
+
+enum GgufValue {
+    Number(u64),
+    Text(String),
+    Other,
+}
+
+/// Parse the key-value metadata at the start of a GGUF file. The bytes may
+/// be cut off; whatever was read before the cut is returned.
+pub fn parse_gguf_metadata(bytes: &[u8]) -> McpResult<GgufMetadata> {
+    let mut reader = GgufReader { bytes, pos: 0 };
+    if reader.take(4) != Some(b"GGUF".as_slice()) {
+        return Err(McpError::Protocol("Not a GGUF file".to_string()));
+    }
+    let version = reader.u32().unwrap_or(0);
+    if version < 2 {
+        return Err(McpError::Protocol(format!("Unsupported GGUF version {}", version)));
+    }
+    let _tensor_count = reader.u64();
+    let kv_count = reader.u64().unwrap_or(0);
+
+    let mut metadata = GgufMetadata::default();
+    let mut context_lengths = Vec::new();
+    for _ in 0..kv_count {
+        let Some(key) = reader.string() else { break };
+        let Some(value_type) = reader.u32() else { break };
+        let Some(value) = reader.value(value_type) else { break };
+        match (key.as_str(), value) {
+            ("general.architecture", GgufValue::Text(v)) => metadata.architecture = Some(v),
+            ("general.name", GgufValue::Text(v)) => metadata.name = Some(v),
+            ("general.size_label", GgufValue::Text(v)) => metadata.size_label = Some(v),
+            ("tokenizer.chat_template", GgufValue::Text(v)) => metadata.chat_template = Some(v),
+            (key, GgufValue::Number(v)) if key.ends_with(".context_length") => {
+                context_lengths.push((key.to_string(), v));
+            }
+            _ => {}
+        }
+    }
+
+    // Prefer the context length of the model's own architecture
+    let own = metadata.architecture.as_ref().map(|a| format!("{}.context_length", a));
+    metadata.context_length = context_lengths
+        .iter()
+        .find(|(key, _)| Some(key) == own.as_ref())
+        .or(context_lengths.first())
+        .map(|(_, v)| *v);
+    Ok(metadata)
+}
+
+#[derive(Deserialize)]
+struct ApiCollection {
+    #[serde(default)]
+    items: Vec<ApiCollectionItem>,
+}
+
+#[derive(Deserialize)]
+struct ApiCollectionItem {
+    #[serde(rename = "type")]
+    item_type: String,
+    id: String,
+}
+
+/// Catalog entries for the GGUF files of the models in a collection
+pub(crate) async fn fetch_collection(url: &str, token: Option<&str>) -> McpResult<Vec<CatalogEntry>> {
+    let slug = url
+        .trim_end_matches('/')
+        .split("/collections/")
+        .nth(1)
+        .ok_or_else(|| McpError::InvalidRequest(format!("Not a Hugging Face collection URL: {}", url)))?;
+    let collection: ApiCollection = get(&format!("{}/collections/{}", HF_API, slug), token)
+        .await?
+        .json()
+        .await
+        .map_err(connection_error)?;
+
+    let limits = HardwareLimits::unlimited();
+    let mut entries = Vec::new();
+    for item in collection.items.iter().filter(|i| i.item_type == "model") {
+        let api: ApiModel = match get(&format!("{}/models/{}", HF_API, item.id), token).await {
+            Ok(response) => response.json().await.map_err(connection_error)?,
+            Err(e) => {
+                warn!("Skipping {} in collection {}: {}", item.id, slug, e);
+                continue;
+            }
+        };
+        let files = model_files(&item.id, &limits, token).await?;
+        let model = to_model(api, files);
+        entries.extend(model.files.iter().map(|file| entry(&model, file)));
+    }
+    Ok(entries)
+}
@@ -4,7 +4,8 @@ use std::time::{Duration, SystemTime};
 use thiserror::Error;
 use uuid::Uuid;
 
-use super::tool::ToolCall;
+use super::tool::{ToolCall, ToolResult};
+use crate::utils::clock;
 
 /// Message role
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -13,6 +14,7 @@ pub enum MessageRole {
     User,
     Assistant,
     System,
+    Tool,
 }
 
 /// Message content type
@@ -31,6 +33,48 @@ pub struct MessageContent {
     pub parts: Vec<ContentType>,
 }
 
+/// Thumbs up or down on a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rating {
+    Up,
+    Down,
+}
+
+/// User feedback on a message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageFeedback {
+    /// The rating
+    pub rating: Rating,
+    
+    /// Why the user rated it this way
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    
+    /// When the feedback was given
+    pub rated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Metadata key holding the earlier versions of an edited message
+pub const EDIT_HISTORY_METADATA_KEY: &str = "edit_history";
+
+/// Metadata key on the last message of a request asking the provider for a
+/// response format, e.g. "json"
+pub const RESPONSE_FORMAT_METADATA_KEY: &str = "response_format";
+
+/// An earlier version of an edited message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageVersion {
+    /// Text of the message at the time
+    pub text: String,
+    
+    /// When this version was written
+    pub written_at: chrono::DateTime<chrono::Utc>,
+    
+    /// When an edit replaced it
+    pub replaced_at: chrono::DateTime<chrono::Utc>,
+}
+
 /// Message structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
@@ -47,6 +91,10 @@ pub struct Message {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<HashMap<String, serde_json::Value>>,
     
+    /// User feedback, if the message was rated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback: Option<MessageFeedback>,
+    
     /// When the message was created
     pub created_at: SystemTime,
 }
@@ -83,7 +131,8 @@ impl Message {
                 parts: vec![ContentType::Text { text: text.into() }],
             },
             metadata: None,
-            created_at: SystemTime::now(),
+            feedback: None,
+            created_at: clock::system_now(),
         }
     }
     
@@ -96,7 +145,8 @@ impl Message {
                 parts: vec![ContentType::Text { text: text.into() }],
             },
             metadata: None,
-            created_at: SystemTime::now(),
+            feedback: None,
+            created_at: clock::system_now(),
         }
     }
     
@@ -109,7 +159,26 @@ impl Message {
                 parts: vec![ContentType::Text { text: text.into() }],
             },
             metadata: None,
-            created_at: SystemTime::now(),
+            feedback: None,
+            created_at: clock::system_now(),
+        }
+    }
+    
+    /// Create a message handing tool results back to the model
+    pub fn tool_results(results: &[ToolResult]) -> Self {
+        let results = results
+            .iter()
+            .map(|r| serde_json::to_value(r).unwrap_or_default())
+            .collect();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            role: MessageRole::Tool,
+            content: MessageContent {
+                parts: vec![ContentType::ToolResults { results }],
+            },
+            metadata: None,
+            feedback: None,
+            created_at: clock::system_now(),
         }
     }
     
@@ -133,6 +202,49 @@ impl Message {
             .to_string()
     }
     
+    /// Earlier versions of the message, oldest first
+    pub fn edit_history(&self) -> Vec<MessageVersion> {
+        self.metadata
+            .as_ref()
+            .and_then(|m| m.get(EDIT_HISTORY_METADATA_KEY))
+            .and_then(|h| serde_json::from_value(h.clone()).ok())
+            .unwrap_or_default()
+    }
+    
+    /// Whether the message was edited after it was written
+    pub fn is_edited(&self) -> bool {
+        !self.edit_history().is_empty()
+    }
+    
+    /// Replace the text of the message, keeping the current text in its
+    /// edit history; non-text parts such as images are kept. Returns false
+    /// if the text didn't change.
+    pub fn edit_text(&mut self, text: &str) -> bool {
+        let previous = self.text();
+        if previous == text {
+            return false;
+        }
+        
+        let mut history = self.edit_history();
+        let written_at = history
+            .last()
+            .map(|v| v.replaced_at)
+            .unwrap_or_else(|| self.created_at.into());
+        history.push(MessageVersion {
+            text: previous,
+            written_at,
+            replaced_at: clock::now(),
+        });
+        self.metadata.get_or_insert_with(HashMap::new).insert(
+            EDIT_HISTORY_METADATA_KEY.to_string(),
+            serde_json::to_value(history).unwrap_or_default(),
+        );
+        
+        self.content.parts.retain(|p| !matches!(p, ContentType::Text { .. }));
+        self.content.parts.insert(0, ContentType::Text { text: text.to_string() });
+        true
+    }
+    
     /// Check if this message has tool calls
     pub fn has_tool_calls(&self) -> bool {
         self.content.parts.iter().any(|part| {
@@ -143,4 +255,18 @@ impl Message {
             }
         })
     }
+    
+    /// Tool calls the message makes, in order
+    pub fn tool_calls(&self) -> Vec<ToolCall> {
+        self.content
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                ContentType::ToolCalls { calls } => Some(calls),
+                _ => None,
+            })
+            .flatten()
+            .cloned()
+            .collect()
+    }
 }
@@ -1,9 +1,15 @@
+pub mod chat_template;
 pub mod conversation;
+pub mod grammar;
+pub mod huggingface;
 pub mod message;
 pub mod model;
+pub mod registry;
 pub mod tool;
 
+pub use chat_template::{ChatTemplate, ChatTurn};
 pub use conversation::Conversation;
-pub use message::{Message, MessageContent, MessageError, MessageRole};
+pub use grammar::{Grammar, OutputFormat};
+pub use message::{Message, MessageContent, MessageError, MessageFeedback, MessageRole, MessageVersion, Rating};
 pub use model::{Model, ModelCapabilities};
 pub use tool::{Tool, ToolCall, ToolResult};
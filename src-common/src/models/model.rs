@@ -33,6 +33,10 @@ pub struct ModelCapabilities {
     
     /// Supports streamed responses
     pub streaming: bool,
+
+    /// Can be held to replying with JSON
+    #[serde(default)]
+    pub json_mode: bool,
 }
 
 /// Implementation for Model
@@ -40,10 +44,10 @@ impl Model {
     /// Create a new Claude model
     pub fn claude(variant: &str, version: &str) -> Self {
         let (name, display_name) = match variant {
-            "opus" => ("claude-3-opus", "Claude 3 Opus"),
-            "sonnet" => ("claude-3-sonnet", "Claude 3 Sonnet"),
-            "haiku" => ("claude-3-haiku", "Claude 3 Haiku"),
-            _ => (format!("claude-3-{}", variant).as_str(), format!("Claude 3 {}", variant.to_string()).as_str()),
+            "opus" => ("claude-3-opus".to_string(), "Claude 3 Opus".to_string()),
+            "sonnet" => ("claude-3-sonnet".to_string(), "Claude 3 Sonnet".to_string()),
+            "haiku" => ("claude-3-haiku".to_string(), "Claude 3 Haiku".to_string()),
+            _ => (format!("claude-3-{}", variant), format!("Claude 3 {}", variant)),
         };
         
         let capabilities = match variant {
@@ -52,31 +56,35 @@ impl Model {
                 max_context_length: 200_000,
                 functions: true,
                 streaming: true,
+                json_mode: false,
             },
             "sonnet" => ModelCapabilities {
                 vision: true,
                 max_context_length: 180_000,
                 functions: true,
                 streaming: true,
+                json_mode: false,
             },
             "haiku" => ModelCapabilities {
                 vision: true,
                 max_context_length: 150_000,
                 functions: true,
                 streaming: true,
+                json_mode: false,
             },
             _ => ModelCapabilities {
                 vision: false,
                 max_context_length: 100_000,
                 functions: false,
                 streaming: true,
+                json_mode: false,
             },
         };
         
         Self {
             id: format!("{}-{}", name, version),
             provider: "anthropic".to_string(),
-            name: display_name.to_string(),
+            name: display_name,
             version: version.to_string(),
             capabilities,
         }
@@ -0,0 +1,919 @@
+//! Remote model catalogs.
+//!
+//! Besides the built-in local models, users can add catalogs: JSON manifests
+//! served over HTTP (or read from a `file://` URL) and Hugging Face
+//! collections. Catalogs are refreshed periodically; their entries are merged
+//! into one list in catalog order, so an entry offered by several catalogs
+//! comes from the first one and records the others. Each catalog has a trust
+//! level and may have an access token that downloads from it send along.
+//!
+//! The registry lives on disk and is re-read on every call, so catalogs added
+//! with the CLI show up in the running app. It also records the LoRA
+//! adapters the user imported or downloaded, and which base models they fit.
+
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::config::{config_path, data_path};
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+use super::huggingface;
+use crate::utils::{clock, security};
+
+/// How a catalog is published
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatalogFormat {
+    /// A JSON manifest listing models (see [`CatalogManifest`])
+    Manifest,
+
+    /// A Hugging Face collection; its GGUF files become entries
+    HuggingFaceCollection,
+
+    /// Models registered one by one, e.g. from a Hub search; never fetched
+    Local,
+}
+
+impl std::str::FromStr for CatalogFormat {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "manifest" | "json" => Ok(CatalogFormat::Manifest),
+            "huggingface" | "hf" | "hugging_face_collection" => Ok(CatalogFormat::HuggingFaceCollection),
+            other => Err(McpError::InvalidRequest(format!(
+                "Unknown catalog format '{}'; use manifest or huggingface",
+                other
+            ))),
+        }
+    }
+}
+
+/// How far models from a catalog are trusted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatalogTrust {
+    /// Entries are offered; downloads are checked against a checksum when
+    /// the entry has one
+    Trusted,
+
+    /// Only entries with a SHA-256 checksum are offered, and downloads must
+    /// match it
+    #[default]
+    Verify,
+
+    /// The catalog is kept but none of its entries are offered
+    Blocked,
+}
+
+impl std::str::FromStr for CatalogTrust {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "trusted" => Ok(CatalogTrust::Trusted),
+            "verify" => Ok(CatalogTrust::Verify),
+            "blocked" => Ok(CatalogTrust::Blocked),
+            other => Err(McpError::InvalidRequest(format!(
+                "Unknown trust level '{}'; use trusted, verify or blocked",
+                other
+            ))),
+        }
+    }
+}
+
+/// A catalog the user added
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogSource {
+    /// Short ID, derived from the name
+    pub id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// Manifest or collection URL
+    pub url: String,
+
+    /// How the catalog is published
+    pub format: CatalogFormat,
+
+    /// How far its models are trusted
+    #[serde(default)]
+    pub trust: CatalogTrust,
+
+    /// Hours between refreshes
+    #[serde(default = "default_refresh_hours")]
+    pub refresh_hours: u32,
+
+    /// Whether an access token is stored for the catalog
+    #[serde(default)]
+    pub has_token: bool,
+
+    /// When the catalog was last fetched successfully
+    #[serde(default)]
+    pub refreshed_at: Option<DateTime<Utc>>,
+
+    /// Why the last refresh failed
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+fn default_refresh_hours() -> u32 {
+    24
+}
+
+impl CatalogSource {
+    /// Whether the catalog should be fetched again
+    pub fn is_due(&self) -> bool {
+        match self.refreshed_at {
+            Some(at) => clock::now() - at >= chrono::Duration::hours(self.refresh_hours as i64),
+            None => true,
+        }
+    }
+}
+
+/// A model listed by a catalog
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    /// Model ID, unique across catalogs
+    pub id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// What the model is used for: chat, embedding, reranker, tts or stt
+    #[serde(default = "default_kind")]
+    pub kind: String,
+
+    /// File name the model is saved under
+    pub file_name: String,
+
+    /// Download URL
+    pub download_url: String,
+
+    /// Number of parameters, if known
+    #[serde(default)]
+    pub parameters: u64,
+
+    /// Quantization type, e.g. "Q4_K_M"
+    #[serde(default)]
+    pub quantization: String,
+
+    /// Context size (max tokens)
+    #[serde(default = "default_context_size")]
+    pub context_size: usize,
+
+    /// Model version
+    #[serde(default = "default_version")]
+    pub version: String,
+
+    /// File size in bytes, if known
+    #[serde(default)]
+    pub size: Option<u64>,
+
+    /// Hex SHA-256 of the file
+    #[serde(default)]
+    pub sha256: Option<String>,
+
+    /// License identifier, e.g. "apache-2.0"
+    #[serde(default)]
+    pub license: Option<String>,
+
+    /// Short description
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Model family the entry is a variant of; by default the name without
+    /// its quantization (see [`family_key`])
+    #[serde(default)]
+    pub family: Option<String>,
+
+    /// What changed in this version, shown when offering an update
+    #[serde(default)]
+    pub changelog: Option<String>,
+
+    /// GGUF architecture, e.g. "llama" or "qwen2"
+    #[serde(default)]
+    pub architecture: Option<String>,
+
+    /// Chat template the model expects, e.g. "chatml" or "llama3" (see
+    /// [`ChatTemplate`](super::ChatTemplate)); detected when unset
+    #[serde(default)]
+    pub chat_template: Option<String>,
+}
+
+impl CatalogEntry {
+    /// Family the entry belongs to
+    pub fn family(&self) -> String {
+        self.family.clone().unwrap_or_else(|| family_key(&self.name))
+    }
+}
+
+fn default_kind() -> String {
+    "chat".to_string()
+}
+
+fn default_context_size() -> usize {
+    2048
+}
+
+fn default_version() -> String {
+    "1.0".to_string()
+}
+
+/// A LoRA adapter trained on top of a base model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdapterEntry {
+    /// Unique adapter ID
+    pub id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// ID or family of the model the adapter was trained on
+    pub base_model: String,
+
+    /// File name in the adapter directory
+    pub file_name: String,
+
+    /// Where the adapter was downloaded from; `None` when imported
+    #[serde(default)]
+    pub download_url: Option<String>,
+
+    /// Hex SHA-256 of the file
+    #[serde(default)]
+    pub sha256: Option<String>,
+
+    /// File size in bytes, if known
+    #[serde(default)]
+    pub size: Option<u64>,
+
+    /// Scaling factor used unless a conversation picks another
+    #[serde(default = "default_adapter_scale")]
+    pub default_scale: f32,
+
+    /// Short description
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// When the adapter was added
+    pub added_at: DateTime<Utc>,
+}
+
+impl AdapterEntry {
+    /// Whether the adapter can be applied to a model of a family
+    pub fn applies_to(&self, model_id: &str, family: &str) -> bool {
+        self.base_model == model_id || self.base_model == family
+    }
+}
+
+fn default_adapter_scale() -> f32 {
+    1.0
+}
+
+/// Format of a JSON catalog manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogManifest {
+    /// Catalog name
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Models in the catalog
+    pub models: Vec<CatalogEntry>,
+}
+
+/// A merged entry with where it came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    /// The entry as listed by its catalog
+    #[serde(flatten)]
+    pub entry: CatalogEntry,
+
+    /// ID of the catalog the entry comes from
+    pub catalog: String,
+
+    /// Trust level of that catalog
+    pub trust: CatalogTrust,
+
+    /// Other catalogs listing the same model
+    #[serde(default)]
+    pub also_in: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RegistryFile {
+    #[serde(default)]
+    catalogs: Vec<CatalogSource>,
+
+    #[serde(default)]
+    entries: BTreeMap<String, Vec<CatalogEntry>>,
+
+    /// Preferred variant of each model family
+    #[serde(default)]
+    preferred: BTreeMap<String, String>,
+
+    /// LoRA adapters by ID
+    #[serde(default)]
+    adapters: BTreeMap<String, AdapterEntry>,
+}
+
+/// Catalogs the user added and their cached entries
+pub struct ModelRegistry {
+    path: PathBuf,
+    token_dir: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl ModelRegistry {
+    /// Open a registry stored in a file, with catalog tokens in a directory
+    pub fn open(path: PathBuf, token_dir: PathBuf) -> Self {
+        Self {
+            path,
+            token_dir,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn load(&self) -> RegistryFile {
+        match fs::read_to_string(&self.path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable model registry {}: {}", self.path.display(), e);
+                RegistryFile::default()
+            }),
+            Err(_) => RegistryFile::default(),
+        }
+    }
+
+    fn save(&self, file: &RegistryFile) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(file)?)?;
+        get_event_bus().emit(Topic::Model, names::MODEL_CATALOGS_CHANGED, serde_json::json!({}));
+        Ok(())
+    }
+
+    fn token_path(&self, id: &str) -> PathBuf {
+        self.token_dir.join(format!("{}.token", id))
+    }
+
+    /// Catalogs in priority order
+    pub fn catalogs(&self) -> Vec<CatalogSource> {
+        self.load().catalogs
+    }
+
+    /// A catalog by ID
+    pub fn catalog(&self, id: &str) -> McpResult<CatalogSource> {
+        self.catalogs()
+            .into_iter()
+            .find(|c| c.id == id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("Model catalog {} not found", id)))
+    }
+
+    /// Add a catalog after the existing ones; it's fetched on the next refresh
+    pub fn add_catalog(&self, name: &str, url: &str, format: CatalogFormat, trust: CatalogTrust) -> McpResult<CatalogSource> {
+        let url = url.trim();
+        if format == CatalogFormat::Local {
+            return Err(McpError::InvalidRequest("Local catalogs are created by registering models".to_string()));
+        }
+        if !(url.starts_with("https://") || url.starts_with("http://") || url.starts_with("file://")) {
+            return Err(McpError::InvalidRequest(format!("Catalog URL must be http(s) or file: {}", url)));
+        }
+
+        let _guard = self.lock.lock().unwrap();
+        let mut file = self.load();
+        if file.catalogs.iter().any(|c| c.url == url) {
+            return Err(McpError::InvalidRequest(format!("Catalog {} was already added", url)));
+        }
+
+        let base = slug(name);
+        let mut id = base.clone();
+        let mut n = 2;
+        while file.catalogs.iter().any(|c| c.id == id) {
+            id = format!("{}-{}", base, n);
+            n += 1;
+        }
+
+        let catalog = CatalogSource {
+            id,
+            name: name.trim().to_string(),
+            url: url.to_string(),
+            format,
+            trust,
+            refresh_hours: default_refresh_hours(),
+            has_token: false,
+            refreshed_at: None,
+            last_error: None,
+        };
+        file.catalogs.push(catalog.clone());
+        self.save(&file)?;
+        info!("Added model catalog {} ({})", catalog.id, catalog.url);
+        Ok(catalog)
+    }
+
+    /// Remove a catalog, its cached entries and its token
+    pub fn remove_catalog(&self, id: &str) -> McpResult<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut file = self.load();
+        let before = file.catalogs.len();
+        file.catalogs.retain(|c| c.id != id);
+        if file.catalogs.len() == before {
+            return Err(McpError::InvalidRequest(format!("Model catalog {} not found", id)));
+        }
+        file.entries.remove(id);
+        let _ = fs::remove_file(self.token_path(id));
+        self.save(&file)
+    }
+
+    /// Change how far a catalog is trusted
+    pub fn set_trust(&self, id: &str, trust: CatalogTrust) -> McpResult<CatalogSource> {
+        self.update_catalog(id, |c| c.trust = trust)
+    }
+
+    /// Change how often a catalog is refreshed
+    pub fn set_refresh_hours(&self, id: &str, hours: u32) -> McpResult<CatalogSource> {
+        self.update_catalog(id, |c| c.refresh_hours = hours.max(1))
+    }
+
+    /// Store the access token sent to a catalog and its downloads (encrypted,
+    /// like the API key); `None` removes it
+    pub fn set_token(&self, id: &str, token: Option<&str>) -> McpResult<CatalogSource> {
+        let path = self.token_path(id);
+        self.catalog(id)?;
+        match token.map(str::trim).filter(|t| !t.is_empty()) {
+            Some(token) => {
+                fs::create_dir_all(&self.token_dir)?;
+                let encrypted = security::encrypt(token)
+                    .map_err(|e| McpError::Config(format!("Failed to encrypt catalog token: {}", e)))?;
+                fs::write(&path, encrypted)?;
+            }
+            None if path.exists() => fs::remove_file(&path)?,
+            None => {}
+        }
+        let has_token = path.exists();
+        self.update_catalog(id, |c| c.has_token = has_token)
+    }
+
+    /// Access token of a catalog, if one is stored
+    pub fn token(&self, id: &str) -> Option<String> {
+        let encrypted = fs::read(self.token_path(id)).ok()?;
+        security::decrypt(&encrypted)
+            .map_err(|e| warn!("Failed to decrypt token of catalog {}: {}", id, e))
+            .ok()
+    }
+
+    fn update_catalog(&self, id: &str, change: impl FnOnce(&mut CatalogSource)) -> McpResult<CatalogSource> {
+        let _guard = self.lock.lock().unwrap();
+        let mut file = self.load();
+        let catalog = file
+            .catalogs
+            .iter_mut()
+            .find(|c| c.id == id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("Model catalog {} not found", id)))?;
+        change(catalog);
+        let catalog = catalog.clone();
+        self.save(&file)?;
+        Ok(catalog)
+    }
+
+    /// Entries of all catalogs that aren't blocked, merged in catalog order.
+    /// Catalogs set to verify only offer entries with a checksum.
+    pub fn entries(&self) -> Vec<RegistryEntry> {
+        let file = self.load();
+        let mut merged: Vec<RegistryEntry> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+
+        for catalog in file.catalogs.iter().filter(|c| c.trust != CatalogTrust::Blocked) {
+            let entries = file.entries.get(&catalog.id).into_iter().flatten();
+            for entry in entries {
+                if catalog.trust == CatalogTrust::Verify && entry.sha256.is_none() {
+                    continue;
+                }
+                match index.get(&entry.id) {
+                    Some(&i) => {
+                        if !merged[i].also_in.contains(&catalog.id) && merged[i].catalog != catalog.id {
+                            merged[i].also_in.push(catalog.id.clone());
+                        }
+                    }
+                    None => {
+                        index.insert(entry.id.clone(), merged.len());
+                        merged.push(RegistryEntry {
+                            entry: entry.clone(),
+                            catalog: catalog.id.clone(),
+                            trust: catalog.trust,
+                            also_in: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+        merged
+    }
+
+    /// Fetch one catalog and replace its cached entries. A failed fetch keeps
+    /// the entries of the last successful one.
+    pub async fn refresh(&self, id: &str) -> McpResult<usize> {
+        let catalog = self.catalog(id)?;
+        if catalog.format == CatalogFormat::Local {
+            return Ok(self.load().entries.get(id).map_or(0, Vec::len));
+        }
+        let token = self.token(id);
+        let result = fetch_catalog(&catalog, token.as_deref()).await;
+
+        let _guard = self.lock.lock().unwrap();
+        let mut file = self.load();
+        let Some(stored) = file.catalogs.iter_mut().find(|c| c.id == id) else {
+            // Removed while fetching
+            return Ok(0);
+        };
+        match result {
+            Ok(entries) => {
+                let count = entries.len();
+                stored.refreshed_at = Some(clock::now());
+                stored.last_error = None;
+                file.entries.insert(id.to_string(), entries);
+                self.save(&file)?;
+                debug!("Refreshed model catalog {}: {} models", id, count);
+                Ok(count)
+            }
+            Err(e) => {
+                warn!("Failed to refresh model catalog {}: {}", id, e);
+                stored.last_error = Some(e.to_string());
+                self.save(&file)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Refresh catalogs that are due, or all of them; returns how many failed
+    pub async fn refresh_all(&self, force: bool) -> usize {
+        let mut failed = 0;
+        let fetched = self.catalogs().into_iter().filter(|c| c.format != CatalogFormat::Local);
+        for catalog in fetched.filter(|c| force || c.is_due()) {
+            if self.refresh(&catalog.id).await.is_err() {
+                failed += 1;
+            }
+        }
+        failed
+    }
+
+    /// Preferred variant of a model family, if the user chose one
+    pub fn preferred_variant(&self, family: &str) -> Option<String> {
+        self.load().preferred.get(family).cloned()
+    }
+
+    /// Choose the variant used for a model family; `None` forgets the choice
+    pub fn set_preferred_variant(&self, family: &str, model_id: Option<&str>) -> McpResult<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut file = self.load();
+        match model_id {
+            Some(id) => file.preferred.insert(family.to_string(), id.to_string()),
+            None => file.preferred.remove(family),
+        };
+        self.save(&file)
+    }
+
+    /// Add a model to a catalog of hand-picked models, creating the catalog
+    /// if needed. The entry replaces one with the same ID.
+    pub fn register(&self, catalog_id: &str, catalog_name: &str, entry: CatalogEntry) -> McpResult<RegistryEntry> {
+        let _guard = self.lock.lock().unwrap();
+        let mut file = self.load();
+        match file.catalogs.iter().find(|c| c.id == catalog_id) {
+            Some(c) if c.format != CatalogFormat::Local => {
+                return Err(McpError::InvalidRequest(format!("Catalog {} is fetched from {}", c.id, c.url)));
+            }
+            Some(_) => {}
+            None => file.catalogs.push(CatalogSource {
+                id: catalog_id.to_string(),
+                name: catalog_name.to_string(),
+                url: format!("local:{}", catalog_id),
+                format: CatalogFormat::Local,
+                // The user picked these models themselves
+                trust: CatalogTrust::Trusted,
+                refresh_hours: default_refresh_hours(),
+                has_token: false,
+                refreshed_at: None,
+                last_error: None,
+            }),
+        }
+
+        let entries = file.entries.entry(catalog_id.to_string()).or_default();
+        entries.retain(|e| e.id != entry.id);
+        entries.push(entry.clone());
+        self.save(&file)?;
+        info!("Registered model {} in catalog {}", entry.id, catalog_id);
+
+        let catalog = file.catalogs.iter().find(|c| c.id == catalog_id).unwrap();
+        Ok(RegistryEntry {
+            entry,
+            catalog: catalog.id.clone(),
+            trust: catalog.trust,
+            also_in: Vec::new(),
+        })
+    }
+
+    /// LoRA adapters, by ID
+    pub fn adapters(&self) -> Vec<AdapterEntry> {
+        self.load().adapters.into_values().collect()
+    }
+
+    /// A LoRA adapter by ID
+    pub fn adapter(&self, id: &str) -> McpResult<AdapterEntry> {
+        self.load()
+            .adapters
+            .remove(id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("No adapter {}", id)))
+    }
+
+    /// LoRA adapters that can be applied to a model of a family
+    pub fn adapters_for(&self, model_id: &str, family: &str) -> Vec<AdapterEntry> {
+        self.adapters()
+            .into_iter()
+            .filter(|a| a.applies_to(model_id, family))
+            .collect()
+    }
+
+    /// Record a LoRA adapter, replacing one with the same ID
+    pub fn add_adapter(&self, adapter: AdapterEntry) -> McpResult<()> {
+        if adapter.base_model.trim().is_empty() {
+            return Err(McpError::InvalidRequest("Adapter needs a base model".to_string()));
+        }
+        if adapter.default_scale.is_nan() || adapter.default_scale <= 0.0 {
+            return Err(McpError::InvalidRequest("Adapter scale must be positive".to_string()));
+        }
+        let _guard = self.lock.lock().unwrap();
+        let mut file = self.load();
+        info!("Added adapter {} for {}", adapter.id, adapter.base_model);
+        file.adapters.insert(adapter.id.clone(), adapter);
+        self.save(&file)
+    }
+
+    /// Forget a LoRA adapter; its file is left to the caller
+    pub fn remove_adapter(&self, id: &str) -> McpResult<AdapterEntry> {
+        let _guard = self.lock.lock().unwrap();
+        let mut file = self.load();
+        let adapter = file
+            .adapters
+            .remove(id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("No adapter {}", id)))?;
+        self.save(&file)?;
+        Ok(adapter)
+    }
+
+    /// Access token to send with a model download: the token of the model's
+    /// catalog, or the Hugging Face token for files on the Hub
+    pub fn download_token(&self, catalog_id: Option<&str>, url: &str) -> Option<String> {
+        let catalog_token = catalog_id.and_then(|id| {
+            self.catalogs()
+                .iter()
+                .find(|c| c.id == id && c.has_token)
+                .and_then(|c| self.token(&c.id))
+        });
+        catalog_token.or_else(|| huggingface::is_hub_url(url).then(huggingface::token).flatten())
+    }
+}
+
+/// Fetch and parse the entries of a catalog
+async fn fetch_catalog(catalog: &CatalogSource, token: Option<&str>) -> McpResult<Vec<CatalogEntry>> {
+    match catalog.format {
+        CatalogFormat::Manifest => {
+            let text = if let Some(path) = catalog.url.strip_prefix("file://") {
+                fs::read_to_string(path)?
+            } else {
+                get(&catalog.url, token).await?.text().await.map_err(connection_error)?
+            };
+            Ok(parse_manifest(&text)?.models)
+        }
+        CatalogFormat::HuggingFaceCollection => huggingface::fetch_collection(&catalog.url, token).await,
+        CatalogFormat::Local => Err(McpError::InvalidRequest(format!("Catalog {} is not fetched", catalog.id))),
+    }
+}
+
+pub(crate) fn connection_error(e: reqwest::Error) -> McpError {
+    McpError::Connection(e.to_string())
+}
+
+/// GET a URL, with a bearer token if there is one
+pub(crate) async fn get(url: &str, token: Option<&str>) -> McpResult<reqwest::Response> {
+    let mut request = reqwest::Client::new().get(url).timeout(Duration::from_secs(30));
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.map_err(connection_error)?;
+    match response.status() {
+        status if status.is_success() => Ok(response),
+        status if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN => {
+            Err(McpError::Authentication(format!("{} refused access ({})", url, status)))
+        }
+        status => Err(McpError::Connection(format!("{} returned {}", url, status))),
+    }
+}
+
+/// Parse a JSON catalog manifest, checking that entries are usable
+pub fn parse_manifest(text: &str) -> McpResult<CatalogManifest> {
+    let manifest: CatalogManifest = serde_json::from_str(text)
+        .map_err(|e| McpError::Protocol(format!("Invalid catalog manifest: {}", e)))?;
+    for entry in &manifest.models {
+        if entry.id.trim().is_empty() || entry.download_url.trim().is_empty() {
+            return Err(McpError::Protocol("Catalog entries need an id and a download_url".to_string()));
+        }
+        if entry.file_name.contains(['/', '\\']) || entry.file_name.starts_with('.') {
+            return Err(McpError::Protocol(format!("Invalid file name in catalog: {}", entry.file_name)));
+        }
+    }
+    Ok(manifest)
+}
+
+/// A model available in one or more quantizations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelFamily {
+    /// Family key shared by the variants
+    pub key: String,
+
+    /// Display name
+    pub name: String,
+
+    /// Variants, smallest quantization first
+    pub variants: Vec<FamilyVariant>,
+
+    /// ID of the variant used for the family
+    pub preferred: Option<String>,
+
+    /// Bytes taken by downloaded variants
+    pub disk_usage: u64,
+}
+
+/// One quantization of a model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FamilyVariant {
+    /// Model ID
+    pub id: String,
+
+    /// Quantization type
+    pub quantization: String,
+
+    /// Whether the variant is downloaded
+    pub downloaded: bool,
+
+    /// Bytes on disk, when downloaded
+    pub size_on_disk: u64,
+}
+
+impl ModelFamily {
+    /// IDs of downloaded variants
+    pub fn downloaded(&self) -> impl Iterator<Item = &str> {
+        self.variants.iter().filter(|v| v.downloaded).map(|v| v.id.as_str())
+    }
+}
+
+/// Group models into families. The preferred variant is the one chosen in
+/// the registry if it's still there, else the largest downloaded one.
+pub fn group_families(
+    registry: &ModelRegistry,
+    models: impl IntoIterator<Item = (String, String, FamilyVariant)>,
+) -> Vec<ModelFamily> {
+    let preferred = registry.load().preferred;
+    let mut families: Vec<ModelFamily> = Vec::new();
+    for (key, name, variant) in models {
+        let family = match families.iter_mut().position(|f| f.key == key) {
+            Some(i) => &mut families[i],
+            None => {
+                families.push(ModelFamily {
+                    key: key.clone(),
+                    name,
+                    variants: Vec::new(),
+                    preferred: None,
+                    disk_usage: 0,
+                });
+                families.last_mut().unwrap()
+            }
+        };
+        family.disk_usage += variant.size_on_disk;
+        family.variants.push(variant);
+    }
+
+    for family in &mut families {
+        family.variants.sort_by_key(|v| quantization_bits(&v.quantization));
+        family.preferred = preferred
+            .get(&family.key)
+            .filter(|id| family.variants.iter().any(|v| &v.id == *id))
+            .cloned()
+            .or_else(|| family.downloaded().last().map(String::from));
+    }
+    families
+}
+
+/// Bits per weight of a quantization type, for ordering variants;
+/// unknown types sort last
+pub fn quantization_bits(quantization: &str) -> u32 {
+    let q = quantization.to_uppercase();
+    match q.as_str() {
+        "F32" => 32,
+        "F16" | "BF16" => 16,
+        _ => q
+            .trim_start_matches('I')
+            .strip_prefix('Q')
+            .and_then(|rest| rest.chars().next())
+            .and_then(|c| c.to_digit(10))
+            .unwrap_or(u32::MAX),
+    }
+}
+
+/// Family of a model name: the name without its quantization and file
+/// extension, lowercased, e.g. "llama-2-7b-chat" for
+/// "Llama-2-7B-Chat.Q4_K_M.gguf"
+pub fn family_key(name: &str) -> String {
+    let name = name.trim_end_matches(".gguf").trim_end_matches(".bin");
+    let stripped = match QUANT_RE.find(name) {
+        Some(m) => format!("{}-{}", &name[..m.start()], &name[m.end()..]),
+        None => name.to_string(),
+    };
+    stripped
+        .trim_matches(|c: char| c == '-' || c == '_' || c == '.')
+        .to_lowercase()
+}
+
+static QUANT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)(?:^|[-_.])((?:I?Q\d(?:_[A-Z0-9]+)*)|BF16|F16|F32)(?:$|[-_.])").unwrap());
+
+static PARAMS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)(?:^|[-_.])(\d+(?:\.\d+)?)([BM])(?:$|[-_.])").unwrap());
+
+/// Quantization type in a GGUF file name, e.g. "Q4_K_M" in
+/// "llama-2-7b.Q4_K_M.gguf"
+pub fn quantization_from_name(name: &str) -> Option<String> {
+    QUANT_RE.captures(name).map(|c| c[1].to_uppercase())
+}
+
+/// Parameter count in a model name, e.g. 7 billion in "Llama-2-7B-Chat"
+pub fn parameters_from_name(name: &str) -> Option<u64> {
+    let captures = PARAMS_RE.captures(name)?;
+    let count: f64 = captures[1].parse().ok()?;
+    let unit = if captures[2].eq_ignore_ascii_case("b") { 1e9 } else { 1e6 };
+    Some((count * unit) as u64)
+}
+
+/// Hex SHA-256 of a file, to check a download against its catalog entry
+pub fn file_sha256(path: &Path) -> McpResult<String> {
+    use std::io::Read;
+
+    let mut file = fs::File::open(path)?;
+    let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+    let mut buffer = vec![0; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        context.update(&buffer[..read]);
+    }
+    Ok(context.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Lowercase ID made of the alphanumeric runs of a name
+pub fn slug(name: &str) -> String {
+    let slug: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "catalog".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Refresh catalogs that are due periodically
+pub fn spawn_catalog_refresher(interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let failed = get_model_registry().refresh_all(false).await;
+            if failed > 0 {
+                warn!("{} model catalogs could not be refreshed", failed);
+            }
+        }
+    })
+}
+
+static MODEL_REGISTRY: Lazy<Arc<ModelRegistry>> =
+    Lazy::new(|| Arc::new(ModelRegistry::open(data_path("model_catalogs.json"), config_path("catalog_tokens"))));
+
+/// Get the model registry
+pub fn get_model_registry() -> Arc<ModelRegistry> {
+    MODEL_REGISTRY.clone()
+}
@@ -13,6 +13,7 @@ use uuid::Uuid;
 use crate::observability::telemetry::TelemetryClient;
 use crate::observability::metrics::ObservabilityConfig;
 use crate::error::Result;
+use crate::platform::fs::{app_dir, AppDir};
 
 // Define log levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -256,31 +257,7 @@ impl Logger {
     }
     
     fn default_log_path() -> PathBuf {
-        let mut path = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        
-        // Platform-specific log directory
-        #[cfg(target_os = "windows")]
-        {
-            path.push("AppData");
-            path.push("Roaming");
-            path.push("MCP");
-            path.push("logs");
-        }
-        
-        #[cfg(target_os = "macos")]
-        {
-            path.push("Library");
-            path.push("Logs");
-            path.push("MCP");
-        }
-        
-        #[cfg(target_os = "linux")]
-        {
-            path.push(".local");
-            path.push("share");
-            path.push("mcp");
-            path.push("logs");
-        }
+        let mut path = app_dir(AppDir::Data).join("logs");
         
         // Ensure directory exists
         if !path.exists() {
@@ -0,0 +1,151 @@
+//! Where the app keeps its files
+//!
+//! Normally config, data and caches live in the per-user directories of the
+//! platform. In portable mode, for running from a USB stick, they all live
+//! in one directory next to the executable instead. Every subsystem gets its
+//! directories from here, so portable mode only has to be decided once.
+
+use once_cell::sync::OnceCell;
+use std::path::{Path, PathBuf};
+
+use crate::error::{McpError, McpResult};
+
+/// File next to the executable that turns portable mode on
+pub const PORTABLE_MARKER: &str = "portable";
+
+/// Environment variable that turns portable mode on: `1` or `true` for the
+/// default directory, or the directory to use
+pub const PORTABLE_ENV: &str = "PAPIN_PORTABLE";
+
+/// Launch argument that turns portable mode on: `--portable` for the
+/// default directory, or `--portable=<dir>`
+pub const PORTABLE_ARG: &str = "--portable";
+
+/// Directory created next to the executable in portable mode
+pub const PORTABLE_DIR: &str = "PapinData";
+
+/// Portable directory, decided on first use
+static PORTABLE: OnceCell<Option<PathBuf>> = OnceCell::new();
+
+/// Kinds of app directories
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppDir {
+    /// Settings and other configuration
+    Config,
+    /// Conversations, models, logs and other data
+    Data,
+    /// Files that can be rebuilt
+    Cache,
+}
+
+impl AppDir {
+    fn name(self) -> &'static str {
+        match self {
+            AppDir::Config => "config",
+            AppDir::Data => "data",
+            AppDir::Cache => "cache",
+        }
+    }
+}
+
+/// Directory holding the executable, or the `.app` bundle on macOS, so a
+/// portable copy keeps its data next to what the user sees
+pub fn executable_dir(exe: &Path) -> Option<PathBuf> {
+    let dir = exe.parent()?;
+    let bundle = dir
+        .ancestors()
+        .find(|ancestor| ancestor.extension().is_some_and(|ext| ext == "app"));
+    match bundle {
+        Some(bundle) => bundle.parent().map(Path::to_path_buf),
+        None => Some(dir.to_path_buf()),
+    }
+}
+
+/// Decide the portable directory from the executable's location and the
+/// value of `PAPIN_PORTABLE`; `None` when not portable
+pub fn resolve_portable(exe: &Path, env: Option<&str>) -> Option<PathBuf> {
+    let exe_dir = executable_dir(exe)?;
+    match env.map(str::trim) {
+        Some("0") | Some("false") => None,
+        Some("1") | Some("true") => Some(exe_dir.join(PORTABLE_DIR)),
+        Some(dir) if !dir.is_empty() => {
+            let dir = PathBuf::from(dir);
+            // Relative directories are relative to the executable, not wherever it was started from
+            Some(if dir.is_absolute() { dir } else { exe_dir.join(dir) })
+        }
+        _ if exe_dir.join(PORTABLE_MARKER).exists() => Some(exe_dir.join(PORTABLE_DIR)),
+        _ => None,
+    }
+}
+
+fn portable() -> &'static Option<PathBuf> {
+    PORTABLE.get_or_init(|| {
+        let exe = std::env::current_exe().ok()?;
+        resolve_portable(&exe, std::env::var(PORTABLE_ENV).ok().as_deref())
+    })
+}
+
+/// Turn portable mode on with a directory, e.g. from a `--portable` flag.
+/// Must be called before any directory is used.
+pub fn set_portable_dir(dir: PathBuf) -> McpResult<()> {
+    PORTABLE
+        .set(Some(dir))
+        .map_err(|_| McpError::Config("Portable mode must be set before any files are opened".to_string()))
+}
+
+/// Directory a `--portable` launch argument asks for, if any
+pub fn portable_arg<I, S>(exe: &Path, args: I) -> Option<PathBuf>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    args.into_iter().find_map(|arg| {
+        let arg = arg.as_ref();
+        if arg == PORTABLE_ARG {
+            resolve_portable(exe, Some("1"))
+        } else {
+            let dir = arg.strip_prefix(PORTABLE_ARG)?.strip_prefix('=')?;
+            resolve_portable(exe, Some(dir))
+        }
+    })
+}
+
+/// Honor a `--portable` launch argument. Call first thing in `main`, before
+/// logging or settings open any file.
+pub fn init_from_args() -> McpResult<()> {
+    let exe = std::env::current_exe()?;
+    match portable_arg(&exe, std::env::args().skip(1)) {
+        Some(dir) => set_portable_dir(dir),
+        None => Ok(()),
+    }
+}
+
+/// Whether the app runs in portable mode
+pub fn is_portable() -> bool {
+    portable().is_some()
+}
+
+/// Portable directory of a kind, when in portable mode. Subsystems with
+/// their own default locations use this to honor portable mode.
+pub fn portable_dir(kind: AppDir) -> Option<PathBuf> {
+    portable().as_ref().map(|root| root.join(kind.name()))
+}
+
+/// Directory of a kind, created if missing
+pub fn app_dir(kind: AppDir) -> PathBuf {
+    let dir = portable_dir(kind).unwrap_or_else(|| {
+        let dirs = directories::ProjectDirs::from("com", "anthropic", "mcp-client")
+            .expect("Failed to determine the app directories");
+        match kind {
+            AppDir::Config => dirs.config_dir().to_path_buf(),
+            AppDir::Data => dirs.data_dir().to_path_buf(),
+            AppDir::Cache => dirs.cache_dir().to_path_buf(),
+        }
+    });
+
+    if !dir.exists() {
+        std::fs::create_dir_all(&dir)
+            .unwrap_or_else(|e| panic!("Failed to create {} directory {}: {}", kind.name(), dir.display(), e));
+    }
+    dir
+}
@@ -0,0 +1,2 @@
+//! Platform-specific behavior shared by the desktop app, CLI and TUI
+pub mod fs;
@@ -0,0 +1,248 @@
+use log::debug;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Beta header value enabling prompt caching on the Messages API
+pub const PROMPT_CACHING_BETA: &str = "prompt-caching-2024-07-31";
+
+/// Message metadata key marking a message as a stable, cacheable prefix (e.g. RAG context)
+pub const CACHE_METADATA_KEY: &str = "cache_prefix";
+
+/// Maximum number of cache breakpoints the API accepts per request
+pub const MAX_BREAKPOINTS: usize = 4;
+
+/// Prefixes shorter than this (in characters, roughly 1024 tokens) are below the
+/// provider's minimum cacheable length and are sent unmarked
+pub const MIN_CACHEABLE_CHARS: usize = 4096;
+
+/// Cache-control marker for a content block
+fn cache_control() -> Value {
+    serde_json::json!({ "type": "ephemeral" })
+}
+
+/// Build the `system` field of a Messages API request as content blocks
+pub fn system_blocks(system_prompt: &str) -> Value {
+    serde_json::json!([{ "type": "text", "text": system_prompt }])
+}
+
+/// Characters of text in a list of content blocks
+fn blocks_chars(blocks: &Value) -> usize {
+    match blocks {
+        Value::String(text) => text.len(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .map(str::len)
+            .sum(),
+        _ => 0,
+    }
+}
+
+/// Put cache-control on the last block of a list of content blocks
+fn mark_last_block(blocks: &mut Value) -> bool {
+    match blocks.as_array_mut().and_then(|b| b.last_mut()) {
+        Some(last) if last.is_object() => {
+            last["cache_control"] = cache_control();
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Mark cache breakpoints on a request's system prompt and messages.
+///
+/// Candidates are the system prompt, leading system messages, messages
+/// flagged in `flagged` (e.g. carrying `CACHE_METADATA_KEY`) and the end of
+/// the history, i.e. the message before the newest. A breakpoint caches
+/// everything before it, so a candidate is only marked once the prefix up to
+/// it is long enough to be cached, and the latest `MAX_BREAKPOINTS` are kept.
+/// Returns the number of breakpoints marked.
+pub fn mark_breakpoints(system: Option<&mut Value>, messages: &mut [Value], flagged: &[bool]) -> usize {
+    let mut prefix = system.as_deref().map(blocks_chars).unwrap_or(0);
+    let system_eligible = prefix >= MIN_CACHEABLE_CHARS;
+
+    let history_end = messages.len().checked_sub(2);
+    let mut leading_system = true;
+    let mut candidates = Vec::new();
+    for (i, message) in messages.iter().enumerate() {
+        prefix += message.get("content").map(blocks_chars).unwrap_or(0);
+        let is_system = message.get("role").and_then(|r| r.as_str()) == Some("system");
+        leading_system &= is_system;
+
+        let candidate = leading_system || flagged.get(i).copied().unwrap_or(false) || Some(i) == history_end;
+        if candidate && prefix >= MIN_CACHEABLE_CHARS {
+            candidates.push(i);
+        }
+    }
+
+    // Later breakpoints cover longer prefixes, so the system prompt only gets
+    // one when the messages leave room for it
+    let skip = candidates.len().saturating_sub(MAX_BREAKPOINTS);
+    let mut marked = 0;
+    for i in candidates.into_iter().skip(skip) {
+        if messages[i].get_mut("content").is_some_and(mark_last_block) {
+            marked += 1;
+        }
+    }
+    if let Some(system) = system {
+        if system_eligible && marked < MAX_BREAKPOINTS && mark_last_block(system) {
+            marked += 1;
+        }
+    }
+    marked
+}
+
+/// Cache-related token counts reported in a response's usage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheUsage {
+    /// Uncached input tokens
+    pub input_tokens: u64,
+
+    /// Output tokens
+    pub output_tokens: u64,
+
+    /// Input tokens written to the cache
+    pub cache_creation_input_tokens: u64,
+
+    /// Input tokens served from the cache
+    pub cache_read_input_tokens: u64,
+}
+
+impl CacheUsage {
+    /// Parse the usage object of a response
+    pub fn from_usage(usage: &Value) -> Self {
+        let mut parsed = Self::default();
+        parsed.update(usage);
+        parsed
+    }
+
+    /// Overwrite the counts a usage object reports, keeping the others
+    pub fn update(&mut self, usage: &Value) {
+        let field = |name: &str| usage.get(name).and_then(|v| v.as_u64());
+        if let Some(tokens) = field("input_tokens") {
+            self.input_tokens = tokens;
+        }
+        if let Some(tokens) = field("output_tokens") {
+            self.output_tokens = tokens;
+        }
+        if let Some(tokens) = field("cache_creation_input_tokens") {
+            self.cache_creation_input_tokens = tokens;
+        }
+        if let Some(tokens) = field("cache_read_input_tokens") {
+            self.cache_read_input_tokens = tokens;
+        }
+    }
+
+    /// Take the usage out of a streaming event. Input and cache counts come
+    /// with `message_start`; `message_delta` carries the running output count.
+    pub fn observe(&mut self, event: &Value) {
+        if let Some(usage) = event.get("message").and_then(|m| m.get("usage")) {
+            self.update(usage);
+        }
+        if let Some(usage) = event.get("usage") {
+            self.update(usage);
+        }
+    }
+
+    /// Whether any cache activity was reported
+    pub fn touched_cache(&self) -> bool {
+        self.cache_creation_input_tokens > 0 || self.cache_read_input_tokens > 0
+    }
+}
+
+/// Input price per million tokens for a model, used to estimate savings
+fn input_price_per_mtok(model_id: &str) -> f64 {
+    if model_id.contains("opus") {
+        15.0
+    } else if model_id.contains("haiku") {
+        0.25
+    } else {
+        3.0
+    }
+}
+
+/// Prompt cache statistics for one model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelCacheStats {
+    /// Requests that used caching
+    pub requests: u64,
+
+    /// Requests that read from the cache
+    pub hits: u64,
+
+    /// Requests that only wrote to the cache
+    pub misses: u64,
+
+    /// Tokens written to the cache
+    pub tokens_written: u64,
+
+    /// Tokens read from the cache
+    pub tokens_read: u64,
+
+    /// Estimated savings in USD compared to sending the prefix uncached
+    pub estimated_savings_usd: f64,
+}
+
+/// Prompt cache statistics across models
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromptCacheStats {
+    /// Statistics per model ID
+    pub models: HashMap<String, ModelCacheStats>,
+}
+
+impl PromptCacheStats {
+    /// Record the usage of a response
+    pub fn record(&mut self, model_id: &str, usage: &CacheUsage) {
+        if !usage.touched_cache() {
+            return;
+        }
+
+        let stats = self.models.entry(model_id.to_string()).or_default();
+        stats.requests += 1;
+        if usage.cache_read_input_tokens > 0 {
+            stats.hits += 1;
+        } else {
+            stats.misses += 1;
+        }
+        stats.tokens_written += usage.cache_creation_input_tokens;
+        stats.tokens_read += usage.cache_read_input_tokens;
+
+        // Cache reads cost 10% of the base input price, writes 125%
+        let price = input_price_per_mtok(model_id) / 1_000_000.0;
+        stats.estimated_savings_usd += usage.cache_read_input_tokens as f64 * price * 0.9
+            - usage.cache_creation_input_tokens as f64 * price * 0.25;
+
+        debug!(
+            "Prompt cache for {}: read {} tokens, wrote {} tokens",
+            model_id, usage.cache_read_input_tokens, usage.cache_creation_input_tokens
+        );
+    }
+
+    /// Fraction of caching requests that hit the cache
+    pub fn hit_rate(&self) -> f64 {
+        let (hits, requests) = self
+            .models
+            .values()
+            .fold((0, 0), |(h, r), s| (h + s.hits, r + s.requests));
+        if requests == 0 {
+            0.0
+        } else {
+            hits as f64 / requests as f64
+        }
+    }
+}
+
+static PROMPT_CACHE_STATS: Lazy<Mutex<PromptCacheStats>> = Lazy::new(Default::default);
+
+/// Record a response's usage in the global statistics
+pub fn record_usage(model_id: &str, usage: &CacheUsage) {
+    PROMPT_CACHE_STATS.lock().unwrap().record(model_id, usage);
+}
+
+/// Snapshot of the global prompt cache statistics
+pub fn get_prompt_cache_stats() -> PromptCacheStats {
+    PROMPT_CACHE_STATS.lock().unwrap().clone()
+}
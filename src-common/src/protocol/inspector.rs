@@ -0,0 +1,415 @@
+use chrono::{DateTime, Utc};
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::config::{data_path, get_settings};
+use crate::events::{get_event_bus, names, Topic};
+
+const INSPECTOR_FILE: &str = "inspector.json";
+
+/// Replaces secrets in captured headers, URLs and bodies
+const REDACTED: &str = "[redacted]";
+
+/// Headers whose values are never captured
+const SENSITIVE_HEADERS: &[&str] = &["authorization", "proxy-authorization", "x-api-key", "cookie", "set-cookie"];
+
+/// Body fields and query parameters whose values are never captured
+const SENSITIVE_FIELDS: &[&str] = &[
+    "api_key",
+    "access_token",
+    "refresh_token",
+    "id_token",
+    "device_code",
+    "client_secret",
+    "password",
+    "token",
+];
+
+/// How an exchange travelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    /// HTTP request and response
+    Http,
+    /// Single MCP WebSocket frame
+    WebSocket,
+}
+
+/// A captured body, redacted and capped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedBody {
+    /// Body text, cut at the configured size
+    pub text: String,
+
+    /// Size of the full body in bytes
+    pub size: usize,
+
+    /// Whether `text` was cut
+    pub truncated: bool,
+}
+
+/// One side of an exchange
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CapturedMessage {
+    /// Headers, with credentials redacted
+    pub headers: Vec<(String, String)>,
+
+    /// Body, if there was one and it was captured
+    pub body: Option<CapturedBody>,
+}
+
+/// A sanitized request/response pair, or a single WebSocket frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    /// Sequence number, increasing
+    pub id: u64,
+
+    /// When the request was sent or the frame passed
+    pub at: DateTime<Utc>,
+
+    /// How the exchange travelled
+    pub transport: Transport,
+
+    /// HTTP method, or `SEND`/`RECEIVE` for WebSocket frames
+    pub method: String,
+
+    /// Target URL, with credentials in the query redacted
+    pub url: String,
+
+    /// Outgoing side; `None` for received frames
+    pub request: Option<CapturedMessage>,
+
+    /// HTTP status code
+    pub status: Option<u16>,
+
+    /// Incoming side; `None` for sent frames and failed requests
+    pub response: Option<CapturedMessage>,
+
+    /// Time until the response headers arrived
+    pub duration_ms: Option<u64>,
+
+    /// Transport error, if the request failed
+    pub error: Option<String>,
+}
+
+/// Ring buffer of the most recent exchanges.
+///
+/// The buffer is kept in the data directory so `mcp debug last-requests` can
+/// show exchanges captured by the desktop app or the TUI.
+pub struct Inspector {
+    exchanges: Mutex<Option<VecDeque<Exchange>>>,
+}
+
+impl Inspector {
+    fn new() -> Self {
+        Self {
+            exchanges: Mutex::new(None),
+        }
+    }
+
+    fn load() -> VecDeque<Exchange> {
+        let path = data_path(INSPECTOR_FILE);
+        if !path.exists() {
+            return VecDeque::new();
+        }
+        fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_else(|| {
+                warn!("Discarding unreadable request inspector buffer");
+                VecDeque::new()
+            })
+    }
+
+    fn with_exchanges<R>(&self, f: impl FnOnce(&mut VecDeque<Exchange>) -> R) -> R {
+        let mut exchanges = self.exchanges.lock().unwrap();
+        f(exchanges.get_or_insert_with(Self::load))
+    }
+
+    fn save(exchanges: &VecDeque<Exchange>) {
+        let result = serde_json::to_string(exchanges)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(data_path(INSPECTOR_FILE), json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            warn!("Failed to save the request inspector buffer: {}", e);
+        }
+    }
+
+    /// Whether capturing is turned on
+    pub fn is_enabled(&self) -> bool {
+        get_settings().lock().unwrap().debug.inspector
+    }
+
+    fn record(&self, mut exchange: Exchange) {
+        let capacity = get_settings().lock().unwrap().debug.inspector_capacity.max(1);
+        let exchange = self.with_exchanges(|exchanges| {
+            exchange.id = exchanges.back().map_or(1, |last| last.id + 1);
+            exchanges.push_back(exchange.clone());
+            while exchanges.len() > capacity {
+                exchanges.pop_front();
+            }
+            Self::save(exchanges);
+            exchange
+        });
+
+        get_event_bus().emit(
+            Topic::System,
+            names::REQUEST_CAPTURED,
+            serde_json::json!({
+                "id": exchange.id,
+                "method": exchange.method,
+                "url": exchange.url,
+                "status": exchange.status,
+            }),
+        );
+    }
+
+    /// The most recent exchanges, newest first
+    pub fn recent(&self, limit: usize) -> Vec<Exchange> {
+        self.with_exchanges(|exchanges| exchanges.iter().rev().take(limit).cloned().collect())
+    }
+
+    /// A captured exchange by ID
+    pub fn get(&self, id: u64) -> Option<Exchange> {
+        self.with_exchanges(|exchanges| exchanges.iter().find(|e| e.id == id).cloned())
+    }
+
+    /// Drop all captured exchanges
+    pub fn clear(&self) {
+        self.with_exchanges(|exchanges| {
+            exchanges.clear();
+            Self::save(exchanges);
+        });
+    }
+}
+
+static INSPECTOR: Lazy<Inspector> = Lazy::new(Inspector::new);
+
+/// Get the request inspector
+pub fn get_inspector() -> &'static Inspector {
+    &INSPECTOR
+}
+
+fn is_sensitive_field(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SENSITIVE_FIELDS.iter().any(|field| name == *field)
+}
+
+fn capture_headers<'a>(headers: impl Iterator<Item = (&'a str, String)>) -> Vec<(String, String)> {
+    headers
+        .map(|(name, value)| {
+            if SENSITIVE_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                (name.to_string(), REDACTED.to_string())
+            } else {
+                (name.to_string(), value)
+            }
+        })
+        .collect()
+}
+
+fn redact_url(raw: &str) -> String {
+    let Ok(mut url) = url::Url::parse(raw) else {
+        return raw.to_string();
+    };
+    if url.query().is_none() {
+        return raw.to_string();
+    }
+
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if is_sensitive_field(&name) {
+                REDACTED.to_string()
+            } else {
+                value.into_owned()
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+    url.to_string()
+}
+
+fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (name, value) in map.iter_mut() {
+                if is_sensitive_field(name) && !value.is_null() {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+fn redact_body(text: &str) -> String {
+    if let Ok(mut json) = serde_json::from_str::<serde_json::Value>(text) {
+        redact_json(&mut json);
+        return json.to_string();
+    }
+
+    // Form bodies, as sent to OAuth token endpoints
+    let looks_like_form = text.contains('=') && !text.contains(char::is_whitespace);
+    if looks_like_form {
+        let pairs: Vec<(String, String)> = url::form_urlencoded::parse(text.as_bytes())
+            .map(|(name, value)| {
+                let value = if is_sensitive_field(&name) {
+                    REDACTED.to_string()
+                } else {
+                    value.into_owned()
+                };
+                (name.into_owned(), value)
+            })
+            .collect();
+        return url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(pairs)
+            .finish();
+    }
+
+    text.to_string()
+}
+
+fn capture_body(bytes: &[u8]) -> Option<CapturedBody> {
+    if bytes.is_empty() {
+        return None;
+    }
+
+    let max = get_settings().lock().unwrap().debug.inspector_max_body;
+    let mut text = match std::str::from_utf8(bytes) {
+        Ok(text) => redact_body(text),
+        Err(_) => format!("[{} bytes of binary data]", bytes.len()),
+    };
+    let truncated = text.len() > max;
+    if truncated {
+        let mut cut = max;
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        text.truncate(cut);
+    }
+
+    Some(CapturedBody {
+        text,
+        size: bytes.len(),
+        truncated,
+    })
+}
+
+fn header_pairs(headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+    capture_headers(
+        headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.to_str().unwrap_or("[binary]").to_string())),
+    )
+}
+
+/// Send an HTTP request, capturing it when the inspector is enabled.
+///
+/// A drop-in replacement for `RequestBuilder::send`. Streamed (`text/event-stream`)
+/// responses are passed through without capturing their body.
+pub async fn send(request: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+    let inspector = get_inspector();
+    if !inspector.is_enabled() {
+        return request.send().await;
+    }
+
+    let (client, request) = request.build_split();
+    let request = request?;
+    let mut exchange = Exchange {
+        id: 0,
+        at: Utc::now(),
+        transport: Transport::Http,
+        method: request.method().to_string(),
+        url: redact_url(request.url().as_str()),
+        request: Some(CapturedMessage {
+            headers: header_pairs(request.headers()),
+            body: request.body().and_then(|b| b.as_bytes()).and_then(capture_body),
+        }),
+        status: None,
+        response: None,
+        duration_ms: None,
+        error: None,
+    };
+
+    let started = Instant::now();
+    let response = match client.execute(request).await {
+        Ok(response) => response,
+        Err(e) => {
+            exchange.duration_ms = Some(started.elapsed().as_millis() as u64);
+            exchange.error = Some(e.to_string());
+            inspector.record(exchange);
+            return Err(e);
+        }
+    };
+    exchange.duration_ms = Some(started.elapsed().as_millis() as u64);
+    exchange.status = Some(response.status().as_u16());
+
+    let headers = header_pairs(response.headers());
+    let streamed = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if streamed {
+        exchange.response = Some(CapturedMessage { headers, body: None });
+        inspector.record(exchange);
+        return Ok(response);
+    }
+
+    // Read the body to capture it, then hand the caller an identical response
+    let status = response.status();
+    let version = response.version();
+    let response_headers = response.headers().clone();
+    let bytes = response.bytes().await?;
+    exchange.response = Some(CapturedMessage {
+        headers,
+        body: capture_body(&bytes),
+    });
+    inspector.record(exchange);
+
+    let mut rebuilt = http::Response::builder().status(status).version(version);
+    if let Some(headers) = rebuilt.headers_mut() {
+        *headers = response_headers;
+    }
+    let rebuilt = rebuilt.body(bytes).expect("status and headers come from a valid response");
+    Ok(reqwest::Response::from(rebuilt))
+}
+
+/// Record an MCP WebSocket frame when the inspector is enabled
+pub fn record_frame(outgoing: bool, url: &str, text: &str) {
+    let inspector = get_inspector();
+    if !inspector.is_enabled() {
+        return;
+    }
+
+    let message = CapturedMessage {
+        headers: Vec::new(),
+        body: capture_body(text.as_bytes()),
+    };
+    let (method, request, response) = if outgoing {
+        ("SEND", Some(message), None)
+    } else {
+        ("RECEIVE", None, Some(message))
+    };
+    inspector.record(Exchange {
+        id: 0,
+        at: Utc::now(),
+        transport: Transport::WebSocket,
+        method: method.to_string(),
+        url: redact_url(url),
+        request,
+        status: None,
+        response,
+        duration_ms: None,
+        error: None,
+    });
+}
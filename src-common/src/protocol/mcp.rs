@@ -8,8 +8,11 @@ use tokio::sync::{mpsc, Mutex, RwLock};
 use tokio_tungstenite::tungstenite::protocol::Message as WsMessage;
 use uuid::Uuid;
 
-use super::{ConnectionStatus, ProtocolConfig, ProtocolHandler, WebSocketClient, WebSocketConfig};
+use super::{cache, inspector, parse, ConnectionStatus, ProtocolConfig, ProtocolHandler, WebSocketClient, WebSocketConfig};
+use crate::auth::{self, Authorization};
+use crate::config::AuthMethod;
 use crate::error::{McpError, McpResult};
+use crate::models::message::RESPONSE_FORMAT_METADATA_KEY;
 use crate::models::{ContentType, Message, MessageContent, MessageRole};
 
 /// MCP message types
@@ -60,6 +63,9 @@ pub struct McpConfig {
     
     /// Default model ID
     pub model: String,
+    
+    /// Whether to send the API key or an OAuth access token
+    pub auth_method: AuthMethod,
 }
 
 /// MCP client
@@ -74,7 +80,7 @@ pub struct McpClient {
     status: Arc<RwLock<ConnectionStatus>>,
     
     /// Active streaming sessions
-    streaming_sessions: Arc<Mutex<HashMap<String, mpsc::Sender<Message>>>>,
+    streaming_sessions: Arc<Mutex<HashMap<String, mpsc::Sender<McpResult<Message>>>>>,
 }
 
 /// MCP protocol handler implementation
@@ -98,13 +104,12 @@ impl McpMessage {
     }
     
     /// Create an authentication request message
-    pub fn auth_request(api_key: &str) -> Self {
-        Self::new(
-            McpMessageType::AuthRequest,
-            serde_json::json!({
-                "api_key": api_key,
-            }),
-        )
+    pub fn auth_request(authorization: &Authorization) -> Self {
+        let payload = match authorization {
+            Authorization::ApiKey(api_key) => serde_json::json!({ "api_key": api_key }),
+            Authorization::Bearer(token) => serde_json::json!({ "access_token": token }),
+        };
+        Self::new(McpMessageType::AuthRequest, payload)
     }
     
     /// Create a completion request message
@@ -116,7 +121,7 @@ impl McpMessage {
         stream: bool,
     ) -> Self {
         // Convert messages to MCP format
-        let mcp_messages = messages
+        let mut mcp_messages = messages
             .iter()
             .map(|msg| {
                 let content = msg.content.parts.iter().map(|part| {
@@ -146,22 +151,44 @@ impl McpMessage {
                         MessageRole::User => "user",
                         MessageRole::Assistant => "assistant",
                         MessageRole::System => "system",
+                        MessageRole::Tool => "tool",
                     },
                     "content": content
                 })
             })
             .collect::<Vec<_>>();
         
-        Self::new(
-            McpMessageType::CompletionRequest,
-            serde_json::json!({
-                "model": model,
-                "messages": mcp_messages,
-                "max_tokens": max_tokens,
-                "temperature": temperature,
-                "stream": stream,
-            }),
-        )
+        // Let the stable prefix, system prompt and history, be cached
+        let flagged: Vec<bool> = messages
+            .iter()
+            .map(|m| {
+                m.metadata
+                    .as_ref()
+                    .and_then(|m| m.get(cache::CACHE_METADATA_KEY))
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false)
+            })
+            .collect();
+        cache::mark_breakpoints(None, &mut mcp_messages, &flagged);
+        
+        let mut payload = serde_json::json!({
+            "model": model,
+            "messages": mcp_messages,
+            "max_tokens": max_tokens,
+            "temperature": temperature,
+            "stream": stream,
+        });
+        
+        // Ask for a response format when the request wants one
+        let format = messages
+            .last()
+            .and_then(|m| m.metadata.as_ref())
+            .and_then(|m| m.get(RESPONSE_FORMAT_METADATA_KEY));
+        if let Some(format) = format {
+            payload["response_format"] = serde_json::json!({ "type": format });
+        }
+        
+        Self::new(McpMessageType::CompletionRequest, payload)
     }
     
     /// Create a cancel stream message
@@ -188,9 +215,16 @@ impl McpConfig {
             url: "wss://api.anthropic.com/v1/messages".to_string(),
             version: "v1".to_string(),
             model: "claude-3-sonnet-20240229".to_string(),
+            auth_method: AuthMethod::ApiKey,
         }
     }
     
+    /// Set how requests are authenticated
+    pub fn with_auth_method(mut self, auth_method: AuthMethod) -> Self {
+        self.auth_method = auth_method;
+        self
+    }
+    
     /// Set the server URL
     pub fn with_url(mut self, url: String) -> Self {
         self.url = url;
@@ -213,14 +247,10 @@ impl McpConfig {
 impl McpClient {
     /// Create a new MCP client
     pub fn new(config: McpConfig) -> Self {
-        // Create websocket configuration
+        // Create websocket configuration; credentials are added on connect
         let ws_config = WebSocketConfig {
             url: config.url.clone(),
-            headers: vec![
-                ("X-API-Key".to_string(), config.api_key.clone()),
-                ("Content-Type".to_string(), "application/json".to_string()),
-                ("Accept".to_string(), "application/json".to_string()),
-            ],
+            headers: Self::base_headers(),
             ..Default::default()
         };
         
@@ -241,13 +271,40 @@ impl McpClient {
         self.ws_client.status()
     }
     
+    fn base_headers() -> Vec<(String, String)> {
+        vec![
+            ("Content-Type".to_string(), "application/json".to_string()),
+            ("Accept".to_string(), "application/json".to_string()),
+        ]
+    }
+    
+    /// Credentials for the configured method; OAuth tokens are refreshed
+    /// if they are about to expire
+    async fn authorization(&self) -> McpResult<Authorization> {
+        match self.config.auth_method {
+            AuthMethod::ApiKey => Ok(Authorization::ApiKey(self.config.api_key.clone())),
+            AuthMethod::OAuth => auth::access_token().await.map(Authorization::Bearer),
+        }
+    }
+    
     /// Connect to the MCP server
     pub async fn connect(&self) -> McpResult<()> {
+        let authorization = match self.authorization().await {
+            Ok(authorization) => authorization,
+            Err(e) => {
+                *self.status.write().await = ConnectionStatus::AuthFailed;
+                return Err(e);
+            }
+        };
+        let mut headers = Self::base_headers();
+        headers.push(authorization.header());
+        self.ws_client.set_headers(headers);
+        
         // Connect WebSocket
         self.ws_client.connect().await?;
         
         // Send authentication message
-        let auth_message = McpMessage::auth_request(&self.config.api_key);
+        let auth_message = McpMessage::auth_request(&authorization);
         self.send_message(&auth_message).await?;
         
         // Wait for authentication response
@@ -255,29 +312,27 @@ impl McpClient {
         
         if response.message_type == McpMessageType::AuthResponse {
             // Check if authentication was successful
-            if let Some(success) = response.payload.get("success") {
-                if success.as_bool().unwrap_or(false) {
+            match parse::parse_payload::<parse::AuthPayload>(&response.payload, "authentication") {
+                Ok(auth) if auth.success => {
                     *self.status.write().await = ConnectionStatus::Connected;
                     Ok(())
-                } else {
+                }
+                Ok(_) => {
                     *self.status.write().await = ConnectionStatus::AuthFailed;
                     Err(McpError::Authentication("Authentication failed".to_string()))
                 }
-            } else {
-                *self.status.write().await = ConnectionStatus::Error("Invalid auth response".to_string());
-                Err(McpError::Protocol("Invalid authentication response".to_string()))
+                Err(e) => {
+                    *self.status.write().await = ConnectionStatus::Error("Invalid auth response".to_string());
+                    Err(e)
+                }
             }
         } else if response.message_type == McpMessageType::Error {
             // Authentication error
             *self.status.write().await = ConnectionStatus::AuthFailed;
-            Err(McpError::Authentication(
-                response
-                    .payload
-                    .get("message")
-                    .and_then(|m| m.as_str())
-                    .unwrap_or("Authentication failed")
-                    .to_string(),
-            ))
+            Err(McpError::Authentication(parse::error_message(
+                &response.payload,
+                "Authentication failed",
+            )))
         } else {
             // Unexpected response
             *self.status.write().await = ConnectionStatus::Error("Unexpected response".to_string());
@@ -297,6 +352,7 @@ impl McpClient {
         // Serialize message
         let json = serde_json::to_string(message)
             .map_err(|e| McpError::Serialization(e))?;
+        inspector::record_frame(true, &self.config.url, &json);
             
         // Send via websocket
         self.ws_client
@@ -316,10 +372,8 @@ impl McpClient {
             
         // Parse message
         if let WsMessage::Text(text) = message {
-            let mcp_message: McpMessage = serde_json::from_str(&text)
-                .map_err(|e| McpError::Serialization(e))?;
-                
-            Ok(mcp_message)
+            inspector::record_frame(false, &self.config.url, &text);
+            parse::parse_message(&text)
         } else {
             Err(McpError::Protocol("Unexpected message type".to_string()))
         }
@@ -354,41 +408,27 @@ impl McpClient {
         let response = self.receive_message().await?;
         
         if response.message_type == McpMessageType::CompletionResponse {
+            cache::record_usage(model, &cache::CacheUsage::from_usage(&response.payload["usage"]));
+            
             // Parse response
-            let content = response
-                .payload
-                .get("content")
-                .ok_or_else(|| McpError::Protocol("Missing content in response".to_string()))?;
+            let text = parse::content_text(&response.payload)?;
                 
             // Convert to Message format
             let message = Message {
                 id: response.id,
                 role: MessageRole::Assistant,
                 content: MessageContent {
-                    parts: vec![ContentType::Text {
-                        text: content
-                            .as_str()
-                            .ok_or_else(|| {
-                                McpError::Protocol("Invalid content type in response".to_string())
-                            })?
-                            .to_string(),
-                    }],
+                    parts: vec![ContentType::Text { text }],
                 },
                 metadata: None,
+                feedback: None,
                 created_at: std::time::SystemTime::now(),
             };
             
             Ok(message)
         } else if response.message_type == McpMessageType::Error {
             // Error response
-            Err(McpError::Protocol(
-                response
-                    .payload
-                    .get("message")
-                    .and_then(|m| m.as_str())
-                    .unwrap_or("Unknown error")
-                    .to_string(),
-            ))
+            Err(McpError::Protocol(parse::error_message(&response.payload, "Unknown error")))
         } else {
             // Unexpected response
             Err(McpError::Protocol("Unexpected response type".to_string()))
@@ -402,7 +442,7 @@ impl McpClient {
         messages: &[Message],
         max_tokens: u32,
         temperature: f32,
-    ) -> McpResult<mpsc::Receiver<Message>> {
+    ) -> McpResult<mpsc::Receiver<McpResult<Message>>> {
         // Check if connected
         if !matches!(self.connection_status(), ConnectionStatus::Connected) {
             return Err(McpError::Connection("Not connected".to_string()));
@@ -418,7 +458,7 @@ impl McpClient {
         );
         
         // Create channel for streaming
-        let (tx, rx) = mpsc::channel::<Message>(32);
+        let (tx, rx) = mpsc::channel::<McpResult<Message>>(32);
         
         // Store streaming session
         {
@@ -432,40 +472,40 @@ impl McpClient {
         // Start streaming task
         let client_clone = Arc::new(self.clone());
         let request_id = request.id.clone();
+        let model = model.to_string();
         
         tokio::spawn(async move {
+            let mut usage = cache::CacheUsage::default();
+            
             // Process streaming messages
             loop {
                 match client_clone.receive_message().await {
                     Ok(message) => {
+                        usage.observe(&message.payload);
                         match message.message_type {
                             McpMessageType::StreamingStart => {
                                 // Stream started - just log it
                                 debug!("Streaming started for {}", request_id);
                             }
                             McpMessageType::StreamingMessage => {
-                                // Process streaming message
-                                if let Some(content) = message.payload.get("content") {
-                                    if let Some(text) = content.as_str() {
-                                        // Create message
-                                        let chunk = Message {
-                                            id: request_id.clone(),
-                                            role: MessageRole::Assistant,
-                                            content: MessageContent {
-                                                parts: vec![ContentType::Text {
-                                                    text: text.to_string(),
-                                                }],
-                                            },
-                                            metadata: None,
-                                            created_at: std::time::SystemTime::now(),
-                                        };
-                                        
-                                        // Send to receiver
-                                        if tx.send(chunk).await.is_err() {
-                                            // Receiver dropped, stop streaming
-                                            break;
-                                        }
-                                    }
+                                // Process streaming message; a chunk without text ends the
+                                // stream with an error, as the reply would be incomplete
+                                let chunk = parse::content_text(&message.payload).map(|text| Message {
+                                    id: request_id.clone(),
+                                    role: MessageRole::Assistant,
+                                    content: MessageContent {
+                                        parts: vec![ContentType::Text { text }],
+                                    },
+                                    metadata: None,
+                                    feedback: None,
+                                    created_at: std::time::SystemTime::now(),
+                                });
+                                let failed = chunk.is_err();
+                                
+                                // Send to receiver
+                                if tx.send(chunk).await.is_err() || failed {
+                                    // Receiver dropped or the stream broke
+                                    break;
                                 }
                             }
                             McpMessageType::StreamingEnd => {
@@ -475,14 +515,9 @@ impl McpClient {
                             }
                             McpMessageType::Error => {
                                 // Error occurred
-                                error!(
-                                    "Streaming error: {}",
-                                    message
-                                        .payload
-                                        .get("message")
-                                        .and_then(|m| m.as_str())
-                                        .unwrap_or("Unknown error")
-                                );
+                                let error = parse::error_message(&message.payload, "Unknown error");
+                                error!("Streaming error: {}", error);
+                                let _ = tx.send(Err(McpError::Protocol(error))).await;
                                 break;
                             }
                             _ => {
@@ -493,11 +528,14 @@ impl McpClient {
                     Err(e) => {
                         // Error receiving message
                         error!("Error receiving streaming message: {}", e);
+                        let _ = tx.send(Err(e)).await;
                         break;
                     }
                 }
             }
             
+            cache::record_usage(&model, &usage);
+            
             // Remove streaming session
             let client = client_clone.as_ref();
             let mut sessions = client.streaming_sessions.lock().await;
@@ -613,7 +651,8 @@ impl ProtocolHandler for McpProtocolHandler {
 
 impl ProtocolConfig for McpConfig {
     fn validate(&self) -> McpResult<()> {
-        if self.api_key.is_empty() {
+        // OAuth tokens are fetched when connecting
+        if self.auth_method == AuthMethod::ApiKey && self.api_key.is_empty() {
             return Err(McpError::Config("API key is required".to_string()));
         }
         
@@ -636,6 +675,7 @@ impl Default for McpConfig {
             url: "wss://api.anthropic.com/v1/messages".to_string(),
             version: "v1".to_string(),
             model: "claude-3-sonnet-20240229".to_string(),
+            auth_method: AuthMethod::ApiKey,
         }
     }
 }
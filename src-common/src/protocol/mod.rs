@@ -1,7 +1,13 @@
+pub mod cache;
+pub mod inspector;
 mod mcp;
+pub mod parse;
+mod transport;
 mod websocket;
 
 pub use mcp::{McpClient, McpConfig, McpMessage, McpMessageType};
+pub use parse::{SseDecoder, SseEvent};
+pub use transport::{backoff_delay, PersistentTransport, TransportConfig, TransportEvent};
 pub use websocket::{ConnectionStatus, WebSocketClient};
 
 use async_trait::async_trait;
@@ -0,0 +1,262 @@
+//! Typed parsing of server frames and streamed events.
+//!
+//! Frames are parsed strictly first. When that fails, known fields are
+//! recovered from whatever JSON is there and the frame is logged, so a server
+//! adding or mangling a field doesn't take the connection down. Input that
+//! can't be recovered becomes an [`McpError`]; nothing in here panics.
+
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::mcp::{McpMessage, McpMessageType};
+use crate::error::{McpError, McpResult};
+
+/// Longest part of a malformed frame written to the log
+const LOGGED_FRAME_CHARS: usize = 200;
+
+/// Start of a frame for logging, cut at a character boundary
+fn excerpt(text: &str) -> String {
+    let mut chars = text.chars();
+    let excerpt: String = chars.by_ref().take(LOGGED_FRAME_CHARS).collect();
+    if chars.next().is_some() {
+        format!("{}...", excerpt)
+    } else {
+        excerpt
+    }
+}
+
+/// Payload of completion responses and streamed chunks
+#[derive(Debug, Deserialize)]
+struct ContentPayload {
+    content: Content,
+}
+
+/// Content as a plain string or as a list of content blocks
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Content {
+    Text(String),
+    Blocks(Vec<ContentBlock>),
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+
+    #[serde(default)]
+    text: Option<String>,
+}
+
+impl Content {
+    /// Text of the content; non-text blocks are skipped
+    fn into_text(self) -> Option<String> {
+        match self {
+            Content::Text(text) => Some(text),
+            Content::Blocks(blocks) => {
+                let texts: Vec<String> = blocks
+                    .into_iter()
+                    .filter(|b| b.kind.as_deref().is_none_or(|kind| kind == "text"))
+                    .filter_map(|b| b.text)
+                    .collect();
+                (!texts.is_empty()).then(|| texts.concat())
+            }
+        }
+    }
+}
+
+/// Payload of error frames
+#[derive(Debug, Default, Deserialize)]
+pub struct ErrorPayload {
+    /// Human readable message
+    #[serde(default)]
+    pub message: Option<String>,
+
+    /// Machine readable error code
+    #[serde(default)]
+    pub code: Option<String>,
+}
+
+/// Payload of authentication responses
+#[derive(Debug, Deserialize)]
+pub struct AuthPayload {
+    /// Whether the credentials were accepted
+    pub success: bool,
+}
+
+/// Parse a frame received from the server
+pub fn parse_message(text: &str) -> McpResult<McpMessage> {
+    let error = match serde_json::from_str::<McpMessage>(text) {
+        Ok(message) => return Ok(message),
+        Err(e) => e,
+    };
+
+    match recover_message(text) {
+        Some(message) => {
+            warn!("Recovered malformed frame ({}): {}", error, excerpt(text));
+            Ok(message)
+        }
+        None => {
+            warn!("Dropped malformed frame ({}): {}", error, excerpt(text));
+            Err(McpError::Protocol(format!("Malformed frame: {}", error)))
+        }
+    }
+}
+
+/// Rebuild a frame from loosely typed JSON; only the type is required
+fn recover_message(text: &str) -> Option<McpMessage> {
+    let value: Value = serde_json::from_str(text).ok()?;
+    let object = value.as_object()?;
+    let message_type: McpMessageType = serde_json::from_value(object.get("type")?.clone()).ok()?;
+
+    let string = |key: &str| match object.get(key) {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    };
+
+    Some(McpMessage {
+        id: string("id").unwrap_or_default(),
+        version: string("version").unwrap_or_else(|| "v1".to_string()),
+        message_type,
+        payload: object.get("payload").cloned().unwrap_or(Value::Null),
+    })
+}
+
+/// Parse a payload into a typed model
+pub fn parse_payload<T: DeserializeOwned>(payload: &Value, what: &str) -> McpResult<T> {
+    T::deserialize(payload).map_err(|e| {
+        warn!("Invalid {} payload ({}): {}", what, e, excerpt(&payload.to_string()));
+        McpError::Protocol(format!("Invalid {} payload: {}", what, e))
+    })
+}
+
+/// Text of a completion response or a streamed chunk
+pub fn content_text(payload: &Value) -> McpResult<String> {
+    parse_payload::<ContentPayload>(payload, "content")?
+        .content
+        .into_text()
+        .ok_or_else(|| McpError::Protocol("Content has no text".to_string()))
+}
+
+/// Message of an error frame, with a fallback for payloads without one
+pub fn error_message(payload: &Value, fallback: &str) -> String {
+    let error = ErrorPayload::deserialize(payload).unwrap_or_default();
+    match (error.message, error.code) {
+        (Some(message), _) if !message.is_empty() => message,
+        (_, Some(code)) => format!("{} ({})", fallback, code),
+        _ => fallback.to_string(),
+    }
+}
+
+/// A server-sent event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Event name, if the server sent one
+    pub event: Option<String>,
+
+    /// Data lines joined with newlines
+    pub data: String,
+}
+
+impl SseEvent {
+    /// Whether this is the end-of-stream marker
+    pub fn is_done(&self) -> bool {
+        self.data.trim() == "[DONE]"
+    }
+
+    /// Parse the event data as JSON; `None` for the end-of-stream marker
+    pub fn parse<T: DeserializeOwned>(&self) -> McpResult<Option<T>> {
+        if self.is_done() {
+            return Ok(None);
+        }
+        serde_json::from_str(&self.data).map(Some).map_err(|e| {
+            warn!("Invalid stream event ({}): {}", e, excerpt(&self.data));
+            McpError::Protocol(format!("Invalid stream event: {}", e))
+        })
+    }
+}
+
+/// Incremental decoder for `text/event-stream` bodies.
+///
+/// Network chunks may split lines and even UTF-8 sequences, so bytes are
+/// buffered until a line is complete and events are emitted at blank lines.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+/// Longest unterminated line buffered before the stream is treated as broken
+const MAX_SSE_LINE: usize = 1024 * 1024;
+
+impl SseDecoder {
+    /// Create an empty decoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of the body; returns the events it completed
+    pub fn push(&mut self, chunk: &[u8]) -> McpResult<Vec<SseEvent>> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if let Some(event) = self.line(line) {
+                events.push(event);
+            }
+        }
+
+        if self.buffer.len() > MAX_SSE_LINE {
+            self.buffer.clear();
+            return Err(McpError::Protocol("Stream line too long".to_string()));
+        }
+        Ok(events)
+    }
+
+    /// Flush the last event of a body that didn't end with a blank line
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        if !self.buffer.is_empty() {
+            let line = String::from_utf8_lossy(&std::mem::take(&mut self.buffer)).into_owned();
+            if let Some(event) = self.line(line.trim_end_matches('\r')) {
+                return Some(event);
+            }
+        }
+        self.line("")
+    }
+
+    fn line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            if self.data.is_empty() {
+                self.event = None;
+                return None;
+            }
+            return Some(SseEvent {
+                event: self.event.take(),
+                data: std::mem::take(&mut self.data).join("\n"),
+            });
+        }
+
+        // Comments keep the connection alive
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+}
@@ -0,0 +1,481 @@
+use futures::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex, RwLock};
+use tokio::time::{interval, sleep, timeout};
+use tokio_tungstenite::{connect_async, tungstenite::client::IntoClientRequest, tungstenite::protocol::Message as WsMessage};
+
+use super::websocket::ConnectionStatus;
+use crate::error::{McpError, McpResult};
+
+/// Persistent transport configuration
+#[derive(Clone, Debug)]
+pub struct TransportConfig {
+    /// Server URL
+    pub url: String,
+
+    /// Extra request headers (e.g. API key)
+    pub headers: Vec<(String, String)>,
+
+    /// Timeout for a single connection attempt
+    pub connect_timeout: Duration,
+
+    /// Interval between heartbeat pings
+    pub heartbeat_interval: Duration,
+
+    /// Connection is considered dead if no frame arrives within this time
+    pub heartbeat_timeout: Duration,
+
+    /// Initial reconnect delay
+    pub initial_backoff: Duration,
+
+    /// Maximum reconnect delay
+    pub max_backoff: Duration,
+
+    /// Maximum consecutive reconnect attempts (0 = unlimited)
+    pub max_reconnect_attempts: u32,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            url: "wss://api.anthropic.com/v1/messages".to_string(),
+            headers: Vec::new(),
+            connect_timeout: Duration::from_secs(15),
+            heartbeat_interval: Duration::from_secs(20),
+            heartbeat_timeout: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(60),
+            max_reconnect_attempts: 0,
+        }
+    }
+}
+
+/// Connection-state events published by the transport
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransportEvent {
+    /// Connection established
+    Connected,
+
+    /// Connection lost
+    Disconnected { reason: String },
+
+    /// Waiting before the next reconnect attempt
+    Reconnecting { attempt: u32, delay_ms: u64 },
+
+    /// In-flight requests were re-sent after reconnecting
+    Resumed { requests: usize },
+
+    /// Reconnection abandoned after too many attempts
+    GaveUp { attempts: u32 },
+}
+
+/// A request that may be re-sent after a reconnect
+struct InFlightRequest {
+    /// Serialized request frame
+    payload: String,
+
+    /// Whether the API supports resuming this request
+    resumable: bool,
+
+    /// Connection the frame was last handed to; a request is sent at most
+    /// once per connection
+    sent_on: Option<u64>,
+
+    /// Channel for the response frame
+    responder: Option<oneshot::Sender<McpResult<String>>>,
+}
+
+/// Sequence number and outgoing frames of the active connection
+type Connection = (u64, mpsc::Sender<WsMessage>);
+
+/// Compute a jittered exponential backoff delay ("full jitter")
+pub fn backoff_delay(attempt: u32, initial: Duration, max: Duration) -> Duration {
+    let exp = initial.as_millis() as u64 * 2u64.saturating_pow(attempt.min(16));
+    let capped = exp.min(max.as_millis() as u64).max(1);
+    Duration::from_millis(rand::thread_rng().gen_range(capped / 2..=capped))
+}
+
+/// Persistent WebSocket transport with heartbeat and automatic reconnection
+pub struct PersistentTransport {
+    /// Configuration
+    config: TransportConfig,
+
+    /// Current connection status
+    status: Arc<RwLock<ConnectionStatus>>,
+
+    /// Connection-state event publisher
+    events: broadcast::Sender<TransportEvent>,
+
+    /// Outgoing frames for the active connection, with its sequence number
+    outgoing: Arc<Mutex<Option<Connection>>>,
+
+    /// Number of connections made so far
+    connections: Arc<AtomicU64>,
+
+    /// Requests awaiting a response, keyed by request ID
+    in_flight: Arc<Mutex<HashMap<String, InFlightRequest>>>,
+
+    /// Frames that did not match an in-flight request
+    unsolicited: broadcast::Sender<String>,
+
+    /// Shutdown signal for the connection loop
+    shutdown: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+}
+
+impl PersistentTransport {
+    /// Create a new transport (not yet connected)
+    pub fn new(config: TransportConfig) -> Self {
+        let (events, _) = broadcast::channel(64);
+        let (unsolicited, _) = broadcast::channel(256);
+
+        Self {
+            config,
+            status: Arc::new(RwLock::new(ConnectionStatus::Disconnected)),
+            events,
+            outgoing: Arc::new(Mutex::new(None)),
+            connections: Arc::new(AtomicU64::new(0)),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            unsolicited,
+            shutdown: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Subscribe to connection-state events
+    pub fn subscribe_events(&self) -> broadcast::Receiver<TransportEvent> {
+        self.events.subscribe()
+    }
+
+    /// Subscribe to frames not tied to a request (e.g. server pushes)
+    pub fn subscribe_messages(&self) -> broadcast::Receiver<String> {
+        self.unsolicited.subscribe()
+    }
+
+    /// Get current connection status
+    pub async fn status(&self) -> ConnectionStatus {
+        self.status.read().await.clone()
+    }
+
+    /// Start the connection loop; reconnects automatically until `stop` is called
+    pub async fn start(&self) {
+        let mut shutdown = self.shutdown.lock().await;
+        if shutdown.is_some() {
+            return;
+        }
+
+        let (tx, rx) = oneshot::channel();
+        *shutdown = Some(tx);
+
+        let worker = TransportWorker {
+            config: self.config.clone(),
+            status: self.status.clone(),
+            events: self.events.clone(),
+            outgoing: self.outgoing.clone(),
+            connections: self.connections.clone(),
+            in_flight: self.in_flight.clone(),
+            unsolicited: self.unsolicited.clone(),
+        };
+
+        tokio::spawn(async move {
+            worker.run(rx).await;
+        });
+    }
+
+    /// Stop the connection loop and close the connection
+    pub async fn stop(&self) {
+        if let Some(tx) = self.shutdown.lock().await.take() {
+            let _ = tx.send(());
+        }
+        *self.outgoing.lock().await = None;
+        *self.status.write().await = ConnectionStatus::Disconnected;
+    }
+
+    /// Send a frame that expects no response (e.g. a stream cancel)
+    pub async fn send(&self, payload: String) -> McpResult<()> {
+        let sender = self.outgoing.lock().await.as_ref().map(|(_, sender)| sender.clone());
+        match sender {
+            Some(sender) => sender
+                .send(WsMessage::Text(payload))
+                .await
+                .map_err(|_| McpError::Connection("Connection lost".to_string())),
+            None => Err(McpError::Connection("Not connected".to_string())),
+        }
+    }
+
+    /// Complete a waiting request with a frame routed by the caller, e.g.
+    /// an error that names the request in its payload rather than its ID.
+    /// Returns whether a request was waiting.
+    pub async fn resolve(&self, request_id: &str, result: McpResult<String>) -> bool {
+        match self.in_flight.lock().await.remove(request_id) {
+            Some(mut request) => {
+                if let Some(responder) = request.responder.take() {
+                    let _ = responder.send(result);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Send a request frame and wait for the response with the same ID.
+    ///
+    /// Resumable requests survive reconnects and are re-sent once the
+    /// connection is back; others fail when the connection drops. A request
+    /// is handed to each connection at most once, so a reconnect racing
+    /// with the first send does not put it on the wire twice.
+    pub async fn request(
+        &self,
+        request_id: &str,
+        payload: String,
+        resumable: bool,
+        response_timeout: Duration,
+    ) -> McpResult<String> {
+        let (tx, rx) = oneshot::channel();
+
+        // Claim the current connection while registering, so the resume
+        // after a reconnect sees whether this request still has to go out
+        let sender = {
+            let mut in_flight = self.in_flight.lock().await;
+            let connection = self.outgoing.lock().await.clone();
+            in_flight.insert(
+                request_id.to_string(),
+                InFlightRequest {
+                    payload: payload.clone(),
+                    resumable,
+                    sent_on: connection.as_ref().map(|(id, _)| *id),
+                    responder: Some(tx),
+                },
+            );
+            connection.map(|(_, sender)| sender)
+        };
+
+        // Send now if connected; otherwise resumable requests wait for reconnect
+        let sent = match sender {
+            Some(sender) => sender.send(WsMessage::Text(payload)).await.is_ok(),
+            None => false,
+        };
+
+        if !sent && !resumable {
+            self.in_flight.lock().await.remove(request_id);
+            return Err(McpError::Connection("Not connected".to_string()));
+        }
+
+        match timeout(response_timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(McpError::Connection("Request dropped".to_string())),
+            Err(_) => {
+                self.in_flight.lock().await.remove(request_id);
+                Err(McpError::Connection(format!("Request {} timed out", request_id)))
+            }
+        }
+    }
+}
+
+/// State shared with the background connection loop
+struct TransportWorker {
+    config: TransportConfig,
+    status: Arc<RwLock<ConnectionStatus>>,
+    events: broadcast::Sender<TransportEvent>,
+    outgoing: Arc<Mutex<Option<Connection>>>,
+    connections: Arc<AtomicU64>,
+    in_flight: Arc<Mutex<HashMap<String, InFlightRequest>>>,
+    unsolicited: broadcast::Sender<String>,
+}
+
+impl TransportWorker {
+    /// Connect, serve, and reconnect with backoff until shut down
+    async fn run(self, mut shutdown: oneshot::Receiver<()>) {
+        let mut attempt: u32 = 0;
+
+        loop {
+            *self.status.write().await = ConnectionStatus::Connecting;
+            let connection = self.connections.fetch_add(1, Ordering::SeqCst) + 1;
+
+            let session = tokio::select! {
+                result = self.connect_and_serve(connection, &mut attempt) => result,
+                _ = &mut shutdown => break,
+            };
+
+            let reason = match session {
+                Ok(()) => "Connection closed by server".to_string(),
+                Err(e) => e.to_string(),
+            };
+
+            *self.outgoing.lock().await = None;
+            *self.status.write().await = ConnectionStatus::Error(reason.clone());
+            let _ = self.events.send(TransportEvent::Disconnected { reason });
+            self.fail_non_resumable().await;
+
+            attempt += 1;
+            if self.config.max_reconnect_attempts > 0 && attempt > self.config.max_reconnect_attempts {
+                warn!("Giving up after {} reconnect attempts", attempt - 1);
+                let _ = self.events.send(TransportEvent::GaveUp { attempts: attempt - 1 });
+                break;
+            }
+
+            let delay = backoff_delay(attempt - 1, self.config.initial_backoff, self.config.max_backoff);
+            debug!("Reconnecting in {:?} (attempt {})", delay, attempt);
+            let _ = self.events.send(TransportEvent::Reconnecting {
+                attempt,
+                delay_ms: delay.as_millis() as u64,
+            });
+
+            tokio::select! {
+                _ = sleep(delay) => {}
+                _ = &mut shutdown => break,
+            }
+        }
+
+        *self.status.write().await = ConnectionStatus::Disconnected;
+    }
+
+    /// Establish one connection and pump frames until it ends
+    async fn connect_and_serve(&self, connection: u64, attempt: &mut u32) -> McpResult<()> {
+        let mut request = self
+            .config
+            .url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| McpError::Connection(format!("Invalid URL: {}", e)))?;
+
+        for (name, value) in &self.config.headers {
+            if let (Ok(name), Ok(value)) = (
+                tokio_tungstenite::tungstenite::http::HeaderName::from_bytes(name.as_bytes()),
+                value.parse(),
+            ) {
+                request.headers_mut().insert(name, value);
+            }
+        }
+
+        let (stream, _) = timeout(self.config.connect_timeout, connect_async(request))
+            .await
+            .map_err(|_| McpError::Connection("Connection timed out".to_string()))?
+            .map_err(|e| McpError::Connection(format!("WebSocket connect error: {}", e)))?;
+
+        let (mut sink, mut source) = stream.split();
+        let (tx, mut rx) = mpsc::channel::<WsMessage>(64);
+
+        *self.status.write().await = ConnectionStatus::Connected;
+        *attempt = 0;
+        info!("Transport connected to {}", self.config.url);
+        let _ = self.events.send(TransportEvent::Connected);
+
+        // Re-send requests that survived the previous connection
+        let resumed = self.resume_in_flight(connection, &tx).await;
+        if resumed > 0 {
+            let _ = self.events.send(TransportEvent::Resumed { requests: resumed });
+        }
+
+        let mut heartbeat = interval(self.config.heartbeat_interval);
+        let mut last_frame = Instant::now();
+
+        loop {
+            tokio::select! {
+                frame = source.next() => {
+                    let frame = match frame {
+                        Some(Ok(frame)) => frame,
+                        Some(Err(e)) => return Err(McpError::Connection(format!("WebSocket error: {}", e))),
+                        None => return Ok(()),
+                    };
+                    last_frame = Instant::now();
+
+                    match frame {
+                        WsMessage::Text(text) => self.dispatch(text).await,
+                        WsMessage::Ping(data) => {
+                            let _ = tx.send(WsMessage::Pong(data)).await;
+                        }
+                        WsMessage::Close(_) => return Ok(()),
+                        _ => {}
+                    }
+                }
+
+                Some(frame) = rx.recv() => {
+                    sink.send(frame)
+                        .await
+                        .map_err(|e| McpError::Connection(format!("Send error: {}", e)))?;
+                }
+
+                _ = heartbeat.tick() => {
+                    if last_frame.elapsed() > self.config.heartbeat_timeout {
+                        return Err(McpError::Connection("Heartbeat timed out".to_string()));
+                    }
+                    sink.send(WsMessage::Ping(Vec::new()))
+                        .await
+                        .map_err(|e| McpError::Connection(format!("Heartbeat failed: {}", e)))?;
+                }
+            }
+        }
+    }
+
+    /// Route an incoming frame to its waiting request, if any
+    async fn dispatch(&self, text: String) {
+        let request_id = serde_json::from_str::<serde_json::Value>(&text)
+            .ok()
+            .and_then(|v| v.get("id").and_then(|id| id.as_str()).map(String::from));
+
+        if let Some(id) = request_id {
+            if let Some(mut request) = self.in_flight.lock().await.remove(&id) {
+                if let Some(responder) = request.responder.take() {
+                    let _ = responder.send(Ok(text));
+                }
+                return;
+            }
+        }
+
+        let _ = self.unsolicited.send(text);
+    }
+
+    /// Publish a new connection and re-send the resumable requests not yet
+    /// handed to it. Requests answered in the meantime are no longer in
+    /// flight, and those sent while this runs already carry its number.
+    async fn resume_in_flight(&self, connection: u64, tx: &mpsc::Sender<WsMessage>) -> usize {
+        let payloads: Vec<String> = {
+            let mut in_flight = self.in_flight.lock().await;
+            *self.outgoing.lock().await = Some((connection, tx.clone()));
+            in_flight
+                .values_mut()
+                .filter(|r| r.resumable && r.sent_on != Some(connection))
+                .map(|r| {
+                    r.sent_on = Some(connection);
+                    r.payload.clone()
+                })
+                .collect()
+        };
+
+        let mut resumed = 0;
+        for payload in payloads {
+            if tx.send(WsMessage::Text(payload)).await.is_ok() {
+                resumed += 1;
+            }
+        }
+        resumed
+    }
+
+    /// Fail requests that cannot be resumed after a disconnect
+    async fn fail_non_resumable(&self) {
+        let mut in_flight = self.in_flight.lock().await;
+        let failed: Vec<String> = in_flight
+            .iter()
+            .filter(|(_, r)| !r.resumable)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in failed {
+            if let Some(mut request) = in_flight.remove(&id) {
+                if let Some(responder) = request.responder.take() {
+                    let _ = responder.send(Err(McpError::Connection("Connection lost".to_string())));
+                }
+            }
+        }
+
+        if !in_flight.is_empty() {
+            debug!("{} resumable request(s) waiting for reconnect", in_flight.len());
+        }
+    }
+}
@@ -7,6 +7,8 @@ use std::time::Duration;
 use tokio::net::TcpStream;
 use tokio::sync::{mpsc, Mutex, RwLock};
 use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
 use tokio_tungstenite::{
     connect_async, tungstenite::protocol::Message as WsMessage, MaybeTlsStream, WebSocketStream,
 };
@@ -71,6 +73,9 @@ pub struct WebSocketClient {
     
     /// Message sender for the connection task
     connection_sender: mpsc::Sender<WsMessage>,
+    
+    /// Headers sent when connecting; credentials change when tokens refresh
+    headers: Arc<std::sync::RwLock<Vec<(String, String)>>>,
 }
 
 impl WebSocketClient {
@@ -81,6 +86,7 @@ impl WebSocketClient {
         let (message_sender, message_receiver) = mpsc::channel::<WsMessage>(32);
         
         let client = Self {
+            headers: Arc::new(std::sync::RwLock::new(config.headers.clone())),
             config,
             status: Arc::new(RwLock::new(ConnectionStatus::Disconnected)),
             sender: Arc::new(Mutex::new(None)),
@@ -92,6 +98,7 @@ impl WebSocketClient {
         let status_clone = client.status.clone();
         let sender_clone = client.sender.clone();
         let config_clone = client.config.clone();
+        let headers_clone = client.headers.clone();
         
         tokio::spawn(async move {
             Self::connection_task(
@@ -100,6 +107,7 @@ impl WebSocketClient {
                 message_sender,
                 connection_receiver,
                 config_clone,
+                headers_clone,
             )
             .await;
         });
@@ -107,6 +115,11 @@ impl WebSocketClient {
         client
     }
     
+    /// Replace the headers sent on the next connect
+    pub fn set_headers(&self, headers: Vec<(String, String)>) {
+        *self.headers.write().unwrap() = headers;
+    }
+    
     /// Get current connection status
     pub fn status(&self) -> ConnectionStatus {
         tokio::task::block_in_place(|| {
@@ -210,6 +223,7 @@ impl WebSocketClient {
         message_sender: mpsc::Sender<WsMessage>,
         mut control_receiver: mpsc::Receiver<WsMessage>,
         config: WebSocketConfig,
+        headers: Arc<std::sync::RwLock<Vec<(String, String)>>>,
     ) {
         // Websocket connection
         let mut ws_stream: Option<WebSocketStream<MaybeTlsStream<TcpStream>>> = None;
@@ -221,7 +235,11 @@ impl WebSocketClient {
                 if let WsMessage::Text(text) = &msg {
                     match text.as_str() {
                         "CONNECT" => {
-                            // Try to connect
+                            // Try to connect with the current headers
+                            let config = WebSocketConfig {
+                                headers: headers.read().unwrap().clone(),
+                                ..config.clone()
+                            };
                             match Self::do_connect(&config).await {
                                 Ok(stream) => {
                                     ws_stream = Some(stream);
@@ -281,8 +299,20 @@ impl WebSocketClient {
         let url = Url::parse(&config.url)
             .map_err(|e| McpError::Connection(format!("Invalid URL: {}", e)))?;
             
+        let mut request = url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| McpError::Connection(format!("Invalid URL: {}", e)))?;
+        for (name, value) in &config.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| McpError::Connection(format!("Invalid header name '{}': {}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| McpError::Connection(format!("Invalid value for header '{}': {}", name, e)))?;
+            request.headers_mut().insert(name, value);
+        }
+        
         // Connect with timeout
-        let result = timeout(config.connect_timeout, connect_async(url)).await;
+        let result = timeout(config.connect_timeout, connect_async(request)).await;
         
         match result {
             Ok(Ok((ws_stream, _))) => Ok(ws_stream),
@@ -0,0 +1,393 @@
+//! Splitting documents into chunks for a knowledge base's index.
+//!
+//! Every strategy cuts a text into units that belong together, such as
+//! words, sentences or lines, within sections a chunk never crosses: the
+//! whole text, a markdown section, or a code symbol. Units are packed into
+//! chunks of up to `max_tokens`, and each chunk repeats the last units of the
+//! one before it, up to `overlap_tokens`. A unit longer than a chunk is cut
+//! between words. Tokens are estimated at four characters each.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{McpError, McpResult};
+
+/// Characters per estimated token
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Start of a top-level function, type or module in common languages
+static SYMBOL_START: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"^(?:export\s+)?(?:pub(?:\([^)]*\))?\s+)?",
+        r"(?:(?:async|unsafe|static|default|abstract|public|private|final)\s+)*",
+        r"(?:(?:fn|struct|enum|trait|impl|mod|union|class|def|function|interface|type|func)\b|macro_rules!)",
+    ))
+    .unwrap()
+});
+
+/// How documents are cut into chunks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkingStrategy {
+    /// Runs of words of the same size, wherever they fall
+    FixedTokens,
+
+    /// Whole sentences; a blank line also ends one
+    #[default]
+    Sentences,
+
+    /// Sections under markdown headings; every chunk of a section starts
+    /// with its heading
+    MarkdownHeaders,
+
+    /// Top-level functions, types and modules of source code, with their
+    /// doc comments and attributes
+    CodeSymbols,
+}
+
+impl ChunkingStrategy {
+    /// Every strategy
+    pub const ALL: [ChunkingStrategy; 4] = [
+        ChunkingStrategy::FixedTokens,
+        ChunkingStrategy::Sentences,
+        ChunkingStrategy::MarkdownHeaders,
+        ChunkingStrategy::CodeSymbols,
+    ];
+}
+
+impl fmt::Display for ChunkingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ChunkingStrategy::FixedTokens => "fixed_tokens",
+            ChunkingStrategy::Sentences => "sentences",
+            ChunkingStrategy::MarkdownHeaders => "markdown_headers",
+            ChunkingStrategy::CodeSymbols => "code_symbols",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for ChunkingStrategy {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "fixed_tokens" | "fixed" | "tokens" => Ok(ChunkingStrategy::FixedTokens),
+            "sentences" | "sentence" => Ok(ChunkingStrategy::Sentences),
+            "markdown_headers" | "markdown" | "headers" => Ok(ChunkingStrategy::MarkdownHeaders),
+            "code_symbols" | "code" | "symbols" => Ok(ChunkingStrategy::CodeSymbols),
+            other => Err(McpError::InvalidRequest(format!(
+                "Unknown chunking strategy '{}'; use fixed_tokens, sentences, markdown_headers or code_symbols",
+                other
+            ))),
+        }
+    }
+}
+
+/// How a knowledge base cuts its documents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChunkingConfig {
+    /// Where chunks may end
+    pub strategy: ChunkingStrategy,
+
+    /// Largest chunk, in estimated tokens
+    pub max_tokens: usize,
+
+    /// Tokens a chunk repeats from the one before it
+    pub overlap_tokens: usize,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            strategy: ChunkingStrategy::default(),
+            max_tokens: 256,
+            overlap_tokens: 32,
+        }
+    }
+}
+
+impl ChunkingConfig {
+    /// Check the sizes make sense
+    pub fn validate(&self) -> McpResult<()> {
+        if !(16..=8192).contains(&self.max_tokens) {
+            return Err(McpError::InvalidRequest(format!(
+                "Chunks hold 16 to 8192 tokens, not {}",
+                self.max_tokens
+            )));
+        }
+        if self.overlap_tokens * 2 > self.max_tokens {
+            return Err(McpError::InvalidRequest(format!(
+                "An overlap of {} tokens is more than half of a {}-token chunk",
+                self.overlap_tokens, self.max_tokens
+            )));
+        }
+        Ok(())
+    }
+
+    /// The same sizes with another strategy
+    pub fn with_strategy(&self, strategy: ChunkingStrategy) -> Self {
+        Self {
+            strategy,
+            ..self.clone()
+        }
+    }
+}
+
+/// A run of units no chunk crosses
+struct Section<'a> {
+    /// Heading repeated at the start of every chunk after the first
+    heading: Option<&'a str>,
+
+    /// Consecutive slices of the text
+    units: Vec<&'a str>,
+}
+
+fn len(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Words with the whitespace after them
+fn words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut in_space = false;
+    for (i, c) in text.char_indices() {
+        if !c.is_whitespace() && in_space {
+            words.push(&text[start..i]);
+            start = i;
+        }
+        in_space = c.is_whitespace();
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// Sentences with the whitespace after them; a blank line ends a sentence too
+pub(crate) fn sentences(text: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut units = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        let next_is_space = chars.get(i + 1).is_none_or(|(_, n)| n.is_whitespace());
+        let ends_sentence = matches!(c, '.' | '!' | '?') && next_is_space;
+        let blank_line = c == '\n' && {
+            let mut k = i + 1;
+            while k < chars.len() && matches!(chars[k].1, ' ' | '\t' | '\r') {
+                k += 1;
+            }
+            k < chars.len() && chars[k].1 == '\n'
+        };
+        if ends_sentence || blank_line {
+            let mut j = i + 1;
+            while j < chars.len() && chars[j].1.is_whitespace() {
+                j += 1;
+            }
+            let end = chars.get(j).map_or(text.len(), |(pos, _)| *pos);
+            units.push(&text[start..end]);
+            start = end;
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+    if start < text.len() {
+        units.push(&text[start..]);
+    }
+    units
+}
+
+/// Markdown sections, each starting at a heading outside code fences
+fn markdown_sections(text: &str) -> Vec<Section<'_>> {
+    let mut sections = Vec::new();
+    let mut heading: Option<&str> = None;
+    let mut start = 0;
+    let mut offset = 0;
+    let mut in_fence = false;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        let hashes = line.chars().take_while(|c| *c == '#').count();
+        let is_heading = !in_fence && (1..=6).contains(&hashes) && line[hashes..].starts_with(' ');
+        if is_heading {
+            if offset > start {
+                sections.push(markdown_section(heading, &text[start..offset]));
+            }
+            heading = Some(line.trim());
+            start = offset;
+        }
+        offset += line.len();
+    }
+    if offset > start {
+        sections.push(markdown_section(heading, &text[start..offset]));
+    }
+    sections
+}
+
+fn markdown_section<'a>(heading: Option<&'a str>, text: &'a str) -> Section<'a> {
+    let mut units = Vec::new();
+    let body = match heading {
+        Some(_) => {
+            // The heading line is a unit of its own
+            let end = text.find('\n').map_or(text.len(), |i| i + 1);
+            units.push(&text[..end]);
+            &text[end..]
+        }
+        None => text,
+    };
+    units.extend(sentences(body));
+    Section { heading, units }
+}
+
+/// Sections of source code, each starting at a top-level symbol or the
+/// comments and attributes right above it
+fn code_sections(text: &str) -> Vec<Section<'_>> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let is_preamble = |line: &str| {
+        let trimmed = line.trim_start();
+        !trimmed.is_empty()
+            && ["//", "#[", "#!", "@", "/*", "*", "#"].iter().any(|p| trimmed.starts_with(p))
+    };
+
+    let mut starts = vec![0];
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 && SYMBOL_START.is_match(line) {
+            let mut start = i;
+            while start > 0 && is_preamble(lines[start - 1]) {
+                start -= 1;
+            }
+            if start > *starts.last().unwrap() {
+                starts.push(start);
+            }
+        }
+    }
+    starts.push(lines.len());
+
+    starts
+        .windows(2)
+        .map(|w| Section {
+            heading: None,
+            units: lines[w[0]..w[1]].to_vec(),
+        })
+        .collect()
+}
+
+/// Cut a unit too long for a chunk between words, or within a word that is
+fn split_unit(unit: &str, max_chars: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut size = 0;
+    for word in words(unit) {
+        let word_start = word.as_ptr() as usize - unit.as_ptr() as usize;
+        let word_len = len(word);
+        if size > 0 && size + word_len > max_chars {
+            pieces.push(&unit[start..word_start]);
+            start = word_start;
+            size = 0;
+        }
+        if word_len > max_chars {
+            // A single word longer than a chunk is cut where it has to be
+            let mut rest = word;
+            while len(rest) > max_chars {
+                let cut = rest.char_indices().nth(max_chars).map_or(rest.len(), |(i, _)| i);
+                pieces.push(&rest[..cut]);
+                rest = &rest[cut..];
+            }
+            start = word_start + word.len() - rest.len();
+            size = len(rest);
+            continue;
+        }
+        size += word_len;
+    }
+    if start < unit.len() {
+        pieces.push(&unit[start..]);
+    }
+    pieces
+}
+
+/// Pack the units of a section into chunks
+fn pack(section: &Section<'_>, max_chars: usize, overlap_chars: usize, chunks: &mut Vec<String>) {
+    let heading_chars = section.heading.map_or(0, |h| len(h) + 2);
+    // Continuation chunks make room for the repeated heading
+    let budget = max_chars.saturating_sub(heading_chars).max(max_chars / 2);
+
+    let units: Vec<&str> = section.units.iter().flat_map(|u| split_unit(u, budget)).collect();
+    let mut current: Vec<&str> = Vec::new();
+    let mut size = 0;
+    let mut fresh = 0;
+    let mut first = true;
+    let mut emit = |current: &[&str], first: bool| {
+        let text = current.concat();
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        match section.heading {
+            Some(heading) if !first => chunks.push(format!("{}\n\n{}", heading, text)),
+            _ => chunks.push(text.to_string()),
+        }
+    };
+
+    for unit in units {
+        let unit_len = len(unit);
+        if fresh > 0 && size + unit_len > budget {
+            emit(&current, first);
+            first = false;
+
+            // Carry the last units over, as long as the next one still fits
+            let mut keep = 0;
+            let mut kept = 0;
+            for previous in current.iter().rev() {
+                if kept + len(previous) > overlap_chars || kept + len(previous) + unit_len > budget {
+                    break;
+                }
+                kept += len(previous);
+                keep += 1;
+            }
+            let dropped = current.len() - keep;
+            current.drain(..dropped);
+            size = kept;
+            fresh = 0;
+        }
+        current.push(unit);
+        size += unit_len;
+        fresh += 1;
+    }
+    if fresh > 0 {
+        emit(&current, first);
+    }
+}
+
+/// Cut a text into chunks
+pub fn chunk(text: &str, config: &ChunkingConfig) -> Vec<String> {
+    let max_chars = config.max_tokens.max(1) * CHARS_PER_TOKEN;
+    let overlap_chars = config.overlap_tokens * CHARS_PER_TOKEN;
+    let sections = match config.strategy {
+        ChunkingStrategy::FixedTokens => vec![Section {
+            heading: None,
+            units: words(text),
+        }],
+        ChunkingStrategy::Sentences => vec![Section {
+            heading: None,
+            units: sentences(text),
+        }],
+        ChunkingStrategy::MarkdownHeaders => markdown_sections(text),
+        ChunkingStrategy::CodeSymbols => code_sections(text),
+    };
+
+    let mut chunks = Vec::new();
+    for section in &sections {
+        pack(section, max_chars, overlap_chars, &mut chunks);
+    }
+    chunks
+}
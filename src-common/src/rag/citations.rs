@@ -0,0 +1,279 @@
+//! Citing knowledge base passages in replies.
+//!
+//! A conversation can search knowledge bases for every message it sends. The
+//! passages found are numbered and put in front of the message as a system
+//! message asking the model to cite them as `[n]`; those found for earlier
+//! messages make way. Each statement of the reply is then mapped to the
+//! passages it cites or, where the model cited none, to the passage it is
+//! most like, and the mapping is kept in the reply's metadata for the UIs to
+//! show as numbered footnotes.
+
+use log::warn;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use super::chunking::sentences;
+use super::KnowledgeBaseStore;
+use crate::memory::embedding::{cosine, embedder_for, DEFAULT_EMBEDDER};
+use crate::models::Message;
+use crate::protocol::cache::CACHE_METADATA_KEY;
+
+/// Conversation metadata key listing the knowledge bases it searches
+pub const KNOWLEDGE_BASES_METADATA_KEY: &str = "knowledge_bases";
+
+/// Metadata key of the system message holding the passages found for a message
+pub const SOURCES_METADATA_KEY: &str = "rag_sources";
+
+/// Metadata key of a reply's citations
+pub const CITATIONS_METADATA_KEY: &str = "citations";
+
+/// Most passages put in front of a message, across knowledge bases
+pub const MAX_SOURCES: usize = 8;
+
+/// Similarity above which an uncited statement is taken to come from a passage
+const INFERRED_SIMILARITY: f32 = 0.4;
+
+/// Citation markers such as `[1]` or `[2, 3]`
+static MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[(\d+(?:\s*,\s*\d+)*)\]").unwrap());
+
+/// A passage put in front of a message, numbered for citing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Source {
+    /// Number the passage is cited by, from 1
+    pub number: usize,
+
+    /// Knowledge base the passage was found in
+    pub knowledge_base: String,
+
+    /// Document the passage was taken from
+    pub document_id: String,
+
+    /// Title of that document
+    pub document_title: String,
+
+    /// Path or URL that document was read from
+    #[serde(default)]
+    pub document_source: Option<String>,
+
+    /// Chunk ID of the passage
+    pub chunk_id: String,
+
+    /// The passage
+    pub text: String,
+
+    /// Similarity to the message
+    pub score: f32,
+}
+
+/// A statement of a reply and the passages it is taken from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Statement {
+    /// Byte offset in the reply's text where the statement starts
+    pub start: usize,
+
+    /// Byte offset where it ends
+    pub end: usize,
+
+    /// Numbers of the passages
+    pub sources: Vec<usize>,
+
+    /// Whether the model cited nothing and the passage was picked by
+    /// similarity
+    #[serde(default)]
+    pub inferred: bool,
+}
+
+/// Passages put in front of the message a reply answers, and the reply's
+/// statements taken from them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Citations {
+    /// Every passage put in front of the message
+    pub sources: Vec<Source>,
+
+    /// Statements taken from a passage, in order
+    pub statements: Vec<Statement>,
+}
+
+impl Citations {
+    /// Citations kept in a message's metadata
+    pub fn of(message: &Message) -> Option<Self> {
+        message
+            .metadata
+            .as_ref()?
+            .get(CITATIONS_METADATA_KEY)
+            .and_then(|c| serde_json::from_value(c.clone()).ok())
+    }
+
+    /// Keep the citations in a message's metadata
+    pub fn attach(&self, message: &mut Message) {
+        message.metadata.get_or_insert_with(Default::default).insert(
+            CITATIONS_METADATA_KEY.to_string(),
+            serde_json::to_value(self).unwrap_or_default(),
+        );
+    }
+
+    /// Passages some statement is taken from, by number
+    pub fn cited(&self) -> Vec<&Source> {
+        let numbers: BTreeSet<usize> = self.statements.iter().flat_map(|s| s.sources.iter().copied()).collect();
+        self.sources.iter().filter(|s| numbers.contains(&s.number)).collect()
+    }
+
+    /// A footnote per cited passage: its number, document and where the
+    /// document was read from
+    pub fn footnotes(&self) -> Vec<String> {
+        self.cited()
+            .into_iter()
+            .map(|s| match &s.document_source {
+                Some(source) => format!("[{}] {} ({})", s.number, s.document_title, source),
+                None => format!("[{}] {}", s.number, s.document_title),
+            })
+            .collect()
+    }
+}
+
+/// Knowledge bases named in a conversation's metadata
+pub fn knowledge_bases(metadata: &serde_json::Value) -> Vec<String> {
+    metadata
+        .get(KNOWLEDGE_BASES_METADATA_KEY)
+        .and_then(|k| serde_json::from_value(k.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Passages of knowledge bases for a message, most similar first and
+/// numbered from 1. A knowledge base that can't be searched is skipped.
+pub fn find_sources(store: &KnowledgeBaseStore, names: &[String], query: &str) -> Vec<Source> {
+    let mut sources = Vec::new();
+    for name in names {
+        let hits = match store.search(name, query) {
+            Ok(hits) => hits,
+            Err(e) => {
+                warn!("Skipping knowledge base {}: {}", name, e);
+                continue;
+            }
+        };
+        sources.extend(hits.into_iter().map(|hit| Source {
+            number: 0,
+            knowledge_base: name.clone(),
+            document_id: hit.document_id,
+            document_title: hit.document_title,
+            document_source: hit.document_source,
+            chunk_id: hit.chunk_id,
+            text: hit.text,
+            score: hit.score,
+        }));
+    }
+
+    sources.sort_by(|a, b| b.score.total_cmp(&a.score));
+    sources.truncate(MAX_SOURCES);
+    for (i, source) in sources.iter_mut().enumerate() {
+        source.number = i + 1;
+    }
+    sources
+}
+
+/// System message putting numbered passages in front of a message
+pub fn sources_message(sources: &[Source]) -> Message {
+    let mut text = String::from(
+        "Answer from these sources where they apply, and cite the source of each statement taken from one by its \
+         number, like [1]:\n",
+    );
+    for source in sources {
+        text.push_str(&format!("\n[{}] {}\n{}\n", source.number, source.document_title, source.text));
+    }
+
+    let mut message = Message::system(text);
+    let metadata = message.metadata.get_or_insert_with(Default::default);
+    metadata.insert(
+        SOURCES_METADATA_KEY.to_string(),
+        serde_json::to_value(sources).unwrap_or_default(),
+    );
+    // The passages stay the same for the rest of the turn, so they can be cached
+    metadata.insert(CACHE_METADATA_KEY.to_string(), serde_json::json!(true));
+    message
+}
+
+/// Whether a message holds the passages found for a message
+pub fn is_sources_message(message: &Message) -> bool {
+    message
+        .metadata
+        .as_ref()
+        .is_some_and(|m| m.contains_key(SOURCES_METADATA_KEY))
+}
+
+/// Map the statements of a reply to the passages they are taken from; None
+/// when no passages were put in front of the message
+pub fn cite(text: &str, sources: &[Source]) -> Option<Citations> {
+    if sources.is_empty() {
+        return None;
+    }
+
+    // Statements with the passages they cite; a marker opening a statement
+    // closes the one before it, as in "Deploys run on Fridays. [1]"
+    let mut spans: Vec<(usize, usize, BTreeSet<usize>)> = Vec::new();
+    let mut offset = 0;
+    for sentence in sentences(text) {
+        let start = offset;
+        offset += sentence.len();
+        let mut body_start = 0;
+        let mut numbers = BTreeSet::new();
+        for marker in MARKER.captures_iter(sentence) {
+            let whole = marker.get(0).unwrap();
+            let cited = marker[1]
+                .split(',')
+                .filter_map(|n| n.trim().parse::<usize>().ok())
+                .filter(|n| sources.iter().any(|s| s.number == *n));
+            let leading = sentence[body_start..whole.start()].trim().is_empty() && numbers.is_empty();
+            match spans.last_mut() {
+                Some((_, _, previous)) if leading => {
+                    previous.extend(cited);
+                    body_start = whole.end();
+                }
+                _ => numbers.extend(cited),
+            }
+        }
+
+        let rest = &sentence[body_start..];
+        let body = rest.trim();
+        if !body.is_empty() {
+            let body_start = start + body_start + (rest.len() - rest.trim_start().len());
+            spans.push((body_start, body_start + body.len(), numbers));
+        }
+    }
+
+    // Uncited statements come from the passage they are most like, if any
+    let embedder = embedder_for(DEFAULT_EMBEDDER)?;
+    let vectors: Vec<Vec<f32>> = sources.iter().map(|s| embedder.embed(&s.text)).collect();
+    let statements = spans
+        .into_iter()
+        .filter_map(|(start, end, numbers)| {
+            if !numbers.is_empty() {
+                return Some(Statement {
+                    start,
+                    end,
+                    sources: numbers.into_iter().collect(),
+                    inferred: false,
+                });
+            }
+            let statement = MARKER.replace_all(&text[start..end], "");
+            let vector = embedder.embed(&statement);
+            let (best, similarity) = vectors
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i, cosine(&vector, v)))
+                .max_by(|a, b| a.1.total_cmp(&b.1))?;
+            (similarity >= INFERRED_SIMILARITY).then(|| Statement {
+                start,
+                end,
+                sources: vec![sources[best].number],
+                inferred: true,
+            })
+        })
+        .collect();
+
+    Some(Citations {
+        sources: sources.to_vec(),
+        statements,
+    })
+}
@@ -0,0 +1,136 @@
+//! Comparing chunking strategies on a knowledge base.
+//!
+//! Each strategy chunks the knowledge base's documents into a throwaway
+//! index with the knowledge base's chunk sizes and embedder, and the sample
+//! queries are run against it with its retrieval settings. A query is
+//! answered by a chunk from one of its expected documents that contains its
+//! expected text, if it names one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::chunking::{ChunkingConfig, ChunkingStrategy};
+use super::index::{Chunk, VectorIndex};
+use super::{embed_document, embedder, Document, KnowledgeBaseStore};
+use crate::error::{McpError, McpResult};
+use crate::service::estimate::estimate_tokens;
+
+/// A query with the documents that answer it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SampleQuery {
+    /// The query
+    pub query: String,
+
+    /// Titles or IDs of the documents that answer it
+    #[serde(default)]
+    pub documents: Vec<String>,
+
+    /// Text an answering chunk contains
+    #[serde(default)]
+    pub contains: Option<String>,
+}
+
+/// How well one chunking did on the sample queries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StrategyScore {
+    /// Chunking evaluated
+    pub chunking: ChunkingConfig,
+
+    /// Chunks it made
+    pub chunks: usize,
+
+    /// Average size of a chunk, in estimated tokens
+    pub average_chunk_tokens: f32,
+
+    /// Share of queries answered by a returned chunk
+    pub hit_rate: f32,
+
+    /// Mean reciprocal rank of the first answering chunk; 0 for a query
+    /// nothing answered
+    pub mrr: f32,
+}
+
+/// Sample queries from a JSON array or JSON lines
+pub fn parse_queries(content: &str) -> McpResult<Vec<SampleQuery>> {
+    let queries: Vec<SampleQuery> = if content.trim_start().starts_with('[') {
+        serde_json::from_str(content)?
+    } else {
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str::<SampleQuery>)
+            .collect::<Result<_, _>>()?
+    };
+
+    if queries.is_empty() {
+        return Err(McpError::InvalidRequest("No sample queries".to_string()));
+    }
+    if let Some(query) = queries.iter().find(|q| q.documents.is_empty() && q.contains.is_none()) {
+        return Err(McpError::InvalidRequest(format!(
+            "'{}' names no documents or text that answer it",
+            query.query
+        )));
+    }
+    Ok(queries)
+}
+
+fn answers(chunk: &Chunk, query: &SampleQuery, documents: &[Document]) -> bool {
+    let from_expected = query.documents.is_empty()
+        || documents
+            .iter()
+            .any(|d| d.id == chunk.document_id && query.documents.iter().any(|e| *e == d.id || *e == d.title));
+    let has_text = query
+        .contains
+        .as_ref()
+        .is_none_or(|text| chunk.text.to_lowercase().contains(&text.to_lowercase()));
+    from_expected && has_text
+}
+
+/// Score strategies on a knowledge base with sample queries; every strategy
+/// when none are given
+pub fn evaluate(
+    store: &KnowledgeBaseStore,
+    name: &str,
+    queries: &[SampleQuery],
+    strategies: &[ChunkingStrategy],
+) -> McpResult<Vec<StrategyScore>> {
+    let kb = store.get(name)?;
+    let embedder = embedder(&kb.embedding_model)?;
+    let documents = store.documents(name)?;
+    let live: HashSet<&str> = documents.iter().map(|d| d.id.as_str()).collect();
+    let strategies = if strategies.is_empty() {
+        &ChunkingStrategy::ALL[..]
+    } else {
+        strategies
+    };
+    let vectors: Vec<Vec<f32>> = queries.iter().map(|q| embedder.embed(&q.query)).collect();
+
+    let mut scores = Vec::new();
+    for strategy in strategies {
+        let chunking = kb.chunking.with_strategy(*strategy);
+        let mut index = VectorIndex::new(&kb.embedding_model, 0);
+        for document in &documents {
+            index.chunks.extend(embed_document(embedder.as_ref(), document, &chunking));
+        }
+
+        let mut hits = 0;
+        let mut reciprocal_ranks = 0.0;
+        for (query, vector) in queries.iter().zip(&vectors) {
+            let retrieved = index.retrieve(vector, &kb.retrieval, |c| live.contains(c.document_id.as_str()));
+            if let Some(rank) = retrieved.iter().position(|(chunk, _)| answers(chunk, query, &documents)) {
+                hits += 1;
+                reciprocal_ranks += 1.0 / (rank + 1) as f32;
+            }
+        }
+
+        let tokens: u64 = index.chunks.iter().map(|c| estimate_tokens(&c.text)).sum();
+        scores.push(StrategyScore {
+            chunks: index.chunks.len(),
+            average_chunk_tokens: tokens as f32 / index.chunks.len().max(1) as f32,
+            hit_rate: hits as f32 / queries.len().max(1) as f32,
+            mrr: reciprocal_ranks / queries.len().max(1) as f32,
+            chunking,
+        });
+    }
+    Ok(scores)
+}
@@ -0,0 +1,185 @@
+//! Vector index of a knowledge base: its chunks with their embeddings.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::error::{McpError, McpResult};
+use crate::memory::embedding::cosine;
+
+/// Candidates per returned chunk considered by maximal marginal relevance
+const MMR_CANDIDATES: usize = 4;
+
+/// A passage of a document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chunk {
+    /// Chunk ID, the document ID and the chunk's position in it
+    pub id: String,
+
+    /// Document the chunk was taken from
+    pub document_id: String,
+
+    /// Position of the chunk in its document
+    pub ordinal: usize,
+
+    /// The passage
+    pub text: String,
+
+    /// Embedding of the text
+    #[serde(default)]
+    pub vector: Vec<f32>,
+}
+
+impl Chunk {
+    /// ID of the chunk at a position of a document
+    pub fn id_for(document_id: &str, ordinal: usize) -> String {
+        format!("{}:{}", document_id, ordinal)
+    }
+}
+
+/// How a knowledge base picks chunks for a query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrievalConfig {
+    /// Most chunks returned
+    pub k: usize,
+
+    /// Lowest similarity of a returned chunk
+    pub min_score: f32,
+
+    /// Maximal marginal relevance: how much relevance (1.0) outweighs
+    /// difference from chunks already picked (0.0); None ranks by
+    /// similarity alone
+    pub mmr_lambda: Option<f32>,
+}
+
+impl Default for RetrievalConfig {
+    fn default() -> Self {
+        Self {
+            k: 4,
+            min_score: 0.1,
+            mmr_lambda: None,
+        }
+    }
+}
+
+impl RetrievalConfig {
+    /// Check the parameters are in range
+    pub fn validate(&self) -> McpResult<()> {
+        if !(1..=100).contains(&self.k) {
+            return Err(McpError::InvalidRequest(format!("k must be 1 to 100, not {}", self.k)));
+        }
+        if !(-1.0..=1.0).contains(&self.min_score) {
+            return Err(McpError::InvalidRequest(format!(
+                "The score threshold must be -1 to 1, not {}",
+                self.min_score
+            )));
+        }
+        if let Some(lambda) = self.mmr_lambda.filter(|l| !(0.0..=1.0).contains(l)) {
+            return Err(McpError::InvalidRequest(format!("The MMR lambda must be 0 to 1, not {}", lambda)));
+        }
+        Ok(())
+    }
+}
+
+/// Chunks of one generation of a knowledge base's index
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VectorIndex {
+    /// Embedder the vectors were made with
+    pub embedding_model: String,
+
+    /// Generation of the index
+    pub generation: u64,
+
+    /// Indexed chunks
+    pub chunks: Vec<Chunk>,
+}
+
+impl VectorIndex {
+    /// Empty index
+    pub fn new(embedding_model: &str, generation: u64) -> Self {
+        Self {
+            embedding_model: embedding_model.to_string(),
+            generation,
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Read an index; a missing file is an empty index
+    pub fn load(path: &Path, embedding_model: &str, generation: u64) -> McpResult<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::new(embedding_model, generation)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the index; returns its size in bytes
+    pub fn save(&self, path: &Path) -> McpResult<u64> {
+        let json = serde_json::to_vec(self)?;
+        super::write_atomic(path, &json)?;
+        Ok(json.len() as u64)
+    }
+
+    /// Length of the vectors
+    pub fn dimensions(&self) -> usize {
+        self.chunks.first().map_or(0, |c| c.vector.len())
+    }
+
+    /// Chunks most similar to a query vector, most similar first, among the
+    /// chunks a filter keeps
+    pub fn search(&self, query: &[f32], k: usize, keep: impl Fn(&Chunk) -> bool) -> Vec<(&Chunk, f32)> {
+        let mut hits: Vec<(&Chunk, f32)> = self
+            .chunks
+            .iter()
+            .filter(|c| keep(c))
+            .map(|c| (c, cosine(&c.vector, query)))
+            .collect();
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1));
+        hits.truncate(k);
+        hits
+    }
+
+    /// Chunks for a query vector as a knowledge base's retrieval settings
+    /// pick them, among the chunks a filter keeps
+    pub fn retrieve(
+        &self,
+        query: &[f32],
+        config: &RetrievalConfig,
+        keep: impl Fn(&Chunk) -> bool,
+    ) -> Vec<(&Chunk, f32)> {
+        let candidates = match config.mmr_lambda {
+            Some(_) => config.k * MMR_CANDIDATES,
+            None => config.k,
+        };
+        let mut remaining: Vec<(&Chunk, f32)> = self
+            .search(query, candidates, keep)
+            .into_iter()
+            .filter(|(_, score)| *score >= config.min_score)
+            .collect();
+        let lambda = match config.mmr_lambda {
+            Some(lambda) => lambda,
+            None => return remaining,
+        };
+
+        // Pick the candidate most relevant and least like those already picked
+        let mut picked: Vec<(&Chunk, f32)> = Vec::new();
+        while picked.len() < config.k && !remaining.is_empty() {
+            let best = remaining
+                .iter()
+                .enumerate()
+                .map(|(i, (chunk, score))| {
+                    let redundancy = picked
+                        .iter()
+                        .map(|(p, _)| cosine(&chunk.vector, &p.vector))
+                        .fold(0.0, f32::max);
+                    (i, lambda * score - (1.0 - lambda) * redundancy)
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(i, _)| i)
+                .unwrap();
+            picked.push(remaining.remove(best));
+        }
+        picked
+    }
+}
@@ -0,0 +1,300 @@
+//! Reindexing knowledge bases.
+//!
+//! A reindex chunks every document again, embeds the chunks with the
+//! knowledge base's embedder or a new one, and writes the next generation of
+//! the index. Searches keep using the old generation until the manifest is
+//! switched to the new one, so a failed or canceled run changes nothing.
+//! Chunks of removed documents and repeated chunks are left out, which
+//! compacts the index. Each run leaves a report with the size of the index
+//! before and after, and how well the new index retrieves its own chunks.
+
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+
+use super::index::{Chunk, VectorIndex};
+use super::{embed_document, embedder, get_knowledge_bases, write_atomic, KnowledgeBaseStore};
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+use crate::jobs::{get_job_manager, Job, JobKind};
+use crate::utils::clock;
+
+/// Chunks sampled to measure retrieval quality
+const QUALITY_SAMPLE: usize = 50;
+
+/// Nearest neighbours compared between the old and the new index
+const AGREEMENT_K: usize = 5;
+
+/// Report of the last reindex, in the knowledge base's directory
+const REPORT_FILE: &str = "reindex.json";
+
+/// Size and health of a knowledge base's index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexStats {
+    /// Generation of the index
+    pub generation: u64,
+
+    /// Embedder the vectors were made with
+    pub embedding_model: String,
+
+    /// Length of the vectors
+    pub dimensions: usize,
+
+    /// Documents in the knowledge base
+    pub documents: usize,
+
+    /// Chunks in the index
+    pub chunks: usize,
+
+    /// Chunks of removed documents
+    pub orphaned_chunks: usize,
+
+    /// Chunks repeating an earlier chunk's text
+    pub duplicate_chunks: usize,
+
+    /// Size of the index file
+    pub bytes: u64,
+}
+
+impl IndexStats {
+    /// Chunks a compaction would drop
+    pub fn reclaimable_chunks(&self) -> usize {
+        self.orphaned_chunks + self.duplicate_chunks
+    }
+}
+
+/// Outcome of a reindex
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReindexReport {
+    /// Knowledge base reindexed
+    pub knowledge_base: String,
+
+    /// Index replaced
+    pub before: IndexStats,
+
+    /// Index now in use
+    pub after: IndexStats,
+
+    /// Share of sampled chunks that are their own best match in the new index
+    pub self_retrieval: f32,
+
+    /// Average share of nearest neighbours the old and the new index agree
+    /// on for sampled chunks; None when the old index had none of them
+    pub neighbour_agreement: Option<f32>,
+
+    /// When the reindex started
+    pub started_at: DateTime<Utc>,
+
+    /// When the new index took over
+    pub finished_at: DateTime<Utc>,
+}
+
+/// Stats of the index in use of a knowledge base
+pub fn stats(store: &KnowledgeBaseStore, name: &str) -> McpResult<IndexStats> {
+    let kb = store.get(name)?;
+    let index = store.index(&kb)?;
+    let documents = store.documents(name)?;
+    let live: HashSet<&str> = documents.iter().map(|d| d.id.as_str()).collect();
+
+    let mut seen = HashSet::new();
+    let mut orphaned_chunks = 0;
+    let mut duplicate_chunks = 0;
+    for chunk in &index.chunks {
+        if !live.contains(chunk.document_id.as_str()) {
+            orphaned_chunks += 1;
+        } else if !seen.insert(chunk.text.as_str()) {
+            duplicate_chunks += 1;
+        }
+    }
+
+    Ok(IndexStats {
+        generation: kb.generation,
+        embedding_model: index.embedding_model.clone(),
+        dimensions: index.dimensions(),
+        documents: documents.len(),
+        chunks: index.chunks.len(),
+        orphaned_chunks,
+        duplicate_chunks,
+        bytes: fs::metadata(store.index_path(name, kb.generation)).map_or(0, |m| m.len()),
+    })
+}
+
+/// Report of the last reindex of a knowledge base
+pub fn last_report(store: &KnowledgeBaseStore, name: &str) -> Option<ReindexReport> {
+    let path = store.kb_dir(name).join(REPORT_FILE);
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+/// Chunks spread evenly over an index
+fn sample(chunks: &[Chunk]) -> impl Iterator<Item = &Chunk> {
+    let step = (chunks.len() / QUALITY_SAMPLE).max(1);
+    chunks.iter().step_by(step).take(QUALITY_SAMPLE)
+}
+
+/// Share of sampled chunks retrieved first by their own vector
+fn self_retrieval(index: &VectorIndex) -> f32 {
+    let mut sampled = 0;
+    let mut found = 0;
+    for chunk in sample(&index.chunks) {
+        sampled += 1;
+        let best = index.search(&chunk.vector, 1, |_| true);
+        // Another chunk with the same text is as good a match
+        if best.first().is_some_and(|(hit, _)| hit.id == chunk.id || hit.text == chunk.text) {
+            found += 1;
+        }
+    }
+    if sampled == 0 {
+        1.0
+    } else {
+        found as f32 / sampled as f32
+    }
+}
+
+/// Average overlap of sampled chunks' nearest neighbours in two indexes
+fn neighbour_agreement(old: &VectorIndex, new: &VectorIndex, live: &HashSet<&str>) -> Option<f32> {
+    let mut total = 0.0;
+    let mut sampled = 0;
+    for chunk in sample(&new.chunks) {
+        let previous = match old.chunks.iter().find(|c| c.id == chunk.id) {
+            Some(previous) => previous,
+            None => continue,
+        };
+        let neighbours = |index: &VectorIndex, vector: &[f32]| -> HashSet<String> {
+            index
+                .search(vector, AGREEMENT_K, |c| live.contains(c.document_id.as_str()))
+                .into_iter()
+                .map(|(c, _)| c.id.clone())
+                .collect()
+        };
+        let before = neighbours(old, &previous.vector);
+        let after = neighbours(new, &chunk.vector);
+        let k = before.len().max(after.len()).max(1);
+        total += before.intersection(&after).count() as f32 / k as f32;
+        sampled += 1;
+    }
+    (sampled > 0).then(|| total / sampled as f32)
+}
+
+/// Build the next generation of a knowledge base's index, with another
+/// embedder if given, and switch to it.
+///
+/// `progress` is told how many of the documents are embedded; returning false
+/// stops the reindex and keeps the old index.
+pub fn reindex(
+    store: &KnowledgeBaseStore,
+    name: &str,
+    model: Option<&str>,
+    mut progress: impl FnMut(u64, u64) -> bool,
+) -> McpResult<ReindexReport> {
+    let started_at = clock::now();
+    let kb = store.get(name)?;
+    let model = model.unwrap_or(&kb.embedding_model).to_string();
+    let embedder = embedder(&model)?;
+    let chunking = kb.chunking.clone();
+    let before = stats(store, name)?;
+    let old = store.index(&kb)?;
+    let generation = kb.generation + 1;
+    info!("Reindexing {} with {} into generation {}", name, model, generation);
+
+    // Embedding runs without the lock, so documents can still be added
+    let documents = store.documents(name)?;
+    let total = documents.len() as u64;
+    let mut index = VectorIndex::new(&model, generation);
+    let mut seen = HashSet::new();
+    for (done, document) in documents.iter().enumerate() {
+        if !progress(done as u64, total) {
+            return Err(McpError::Cancelled);
+        }
+        let chunks = embed_document(embedder.as_ref(), document, &chunking);
+        index.chunks.extend(chunks.into_iter().filter(|c| seen.insert(c.text.clone())));
+    }
+    progress(total, total);
+
+    let report = {
+        let _guard = store.lock();
+        let mut kb = store.get(name)?;
+        if kb.generation >= generation {
+            return Err(McpError::InvalidRequest(format!("{} was reindexed by another run", name)));
+        }
+
+        // Documents added while embedding are embedded now; removed ones drop out
+        let documents = store.documents(name)?;
+        let indexed: HashSet<&str> = documents.iter().map(|d| d.id.as_str()).collect();
+        index.chunks.retain(|c| indexed.contains(c.document_id.as_str()));
+        let mut seen: HashSet<String> = index.chunks.iter().map(|c| c.text.clone()).collect();
+        let covered: HashSet<String> = index.chunks.iter().map(|c| c.document_id.clone()).collect();
+        for document in documents.iter().filter(|d| !covered.contains(&d.id)) {
+            let chunks = embed_document(embedder.as_ref(), document, &chunking);
+            index.chunks.extend(chunks.into_iter().filter(|c| seen.insert(c.text.clone())));
+        }
+
+        index.save(&store.index_path(name, generation))?;
+        let old_generation = kb.generation;
+        kb.generation = generation;
+        kb.embedding_model = model.clone();
+        kb.updated_at = clock::now();
+        store.save_manifest(&kb)?;
+        if let Err(e) = fs::remove_file(store.index_path(name, old_generation)) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove old index of {}: {}", name, e);
+            }
+        }
+
+        ReindexReport {
+            knowledge_base: name.to_string(),
+            before,
+            after: stats(store, name)?,
+            self_retrieval: self_retrieval(&index),
+            neighbour_agreement: neighbour_agreement(&old, &index, &indexed),
+            started_at,
+            finished_at: clock::now(),
+        }
+    };
+
+    let path = store.kb_dir(name).join(REPORT_FILE);
+    if let Err(e) = write_atomic(&path, &serde_json::to_vec_pretty(&report)?) {
+        warn!("Failed to save reindex report of {}: {}", name, e);
+    }
+    get_event_bus().emit(
+        Topic::System,
+        names::KNOWLEDGE_BASE_REINDEXED,
+        serde_json::to_value(&report).unwrap_or_default(),
+    );
+    info!(
+        "Reindexed {}: {} -> {} chunks, {} -> {} bytes",
+        name, report.before.chunks, report.after.chunks, report.before.bytes, report.after.bytes
+    );
+    Ok(report)
+}
+
+/// Reindex a knowledge base of this device as a background job
+pub fn spawn_reindex(name: &str, model: Option<&str>) -> McpResult<Job> {
+    let store = get_knowledge_bases();
+    let kb = store.get(name)?;
+    if let Some(model) = model {
+        embedder(model)?;
+    }
+    let model = model.map(str::to_string);
+
+    Ok(get_job_manager().spawn(JobKind::Indexing, &format!("Reindex {}", kb.name), false, move |job| async move {
+        let handle = job.clone();
+        let report = tokio::task::spawn_blocking(move || {
+            reindex(&store, &kb.name, model.as_deref(), |done, total| {
+                handle.progress(done, Some(total));
+                !handle.is_cancelled()
+            })
+        })
+        .await
+        .map_err(|e| McpError::Unknown(e.to_string()))??;
+
+        job.message(format!(
+            "{} chunks, {} reclaimed, {:.0}% self-retrieval",
+            report.after.chunks,
+            report.before.chunks.saturating_sub(report.after.chunks),
+            report.self_retrieval * 100.0
+        ));
+        Ok(())
+    }))
+}
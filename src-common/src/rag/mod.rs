@@ -0,0 +1,363 @@
+//! Knowledge bases for retrieval-augmented generation.
+//!
+//! Each knowledge base is a directory under `rag` in the data directory with
+//! its manifest, its documents and its vector index. Indexes are numbered by
+//! generation and the manifest names the one in use, so a new index is
+//! written next to the old one and takes over when the manifest is replaced.
+//! Removing a document leaves its chunks in the index, unused, until
+//! [`maintenance::reindex`] compacts it.
+
+pub mod chunking;
+pub mod citations;
+pub mod evaluate;
+pub mod index;
+pub mod maintenance;
+
+use chrono::{DateTime, Utc};
+use log::debug;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::memory::embedding::{embedder_for, Embedder};
+use crate::utils::clock;
+use chunking::{chunk, ChunkingConfig};
+use index::{Chunk, RetrievalConfig, VectorIndex};
+
+/// Directory in the data directory holding knowledge bases
+pub const RAG_DIR: &str = "rag";
+
+const MANIFEST_FILE: &str = "kb.json";
+const DOCUMENTS_FILE: &str = "documents.json";
+
+/// A collection of documents searched for context
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnowledgeBase {
+    /// Name, also the directory name
+    pub name: String,
+
+    /// Embedder of the index in use
+    pub embedding_model: String,
+
+    /// Generation of the index in use
+    pub generation: u64,
+
+    /// How documents are cut into chunks
+    #[serde(default)]
+    pub chunking: ChunkingConfig,
+
+    /// How chunks are picked for a query
+    #[serde(default)]
+    pub retrieval: RetrievalConfig,
+
+    /// When the knowledge base was created
+    pub created_at: DateTime<Utc>,
+
+    /// When a document was last added or removed, or the index replaced
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A document of a knowledge base
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    /// Document ID
+    pub id: String,
+
+    /// Title shown for the document
+    pub title: String,
+
+    /// Path or URL the document was read from
+    #[serde(default)]
+    pub source: Option<String>,
+
+    /// Full text
+    pub content: String,
+
+    /// When the document was added
+    pub added_at: DateTime<Utc>,
+}
+
+/// A chunk found for a query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// Chunk ID
+    pub chunk_id: String,
+
+    /// Document the chunk was taken from
+    pub document_id: String,
+
+    /// Title of that document
+    pub document_title: String,
+
+    /// Path or URL that document was read from
+    #[serde(default)]
+    pub document_source: Option<String>,
+
+    /// The passage
+    pub text: String,
+
+    /// Cosine similarity to the query
+    pub score: f32,
+}
+
+/// Write a file so readers see either the old or the new content
+pub(crate) fn write_atomic(path: &Path, content: &[u8]) -> McpResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Embedder for a stored model ID
+pub(crate) fn embedder(model: &str) -> McpResult<Arc<dyn Embedder>> {
+    embedder_for(model).ok_or_else(|| McpError::InvalidRequest(format!("Unknown embedding model {}", model)))
+}
+
+/// Chunks of a document, embedded
+pub(crate) fn embed_document(embedder: &dyn Embedder, document: &Document, chunking: &ChunkingConfig) -> Vec<Chunk> {
+    chunk(&document.content, chunking)
+        .into_iter()
+        .enumerate()
+        .map(|(ordinal, text)| Chunk {
+            id: Chunk::id_for(&document.id, ordinal),
+            document_id: document.id.clone(),
+            ordinal,
+            vector: embedder.embed(&text),
+            text,
+        })
+        .collect()
+}
+
+/// Every knowledge base on this device
+pub struct KnowledgeBaseStore {
+    dir: PathBuf,
+    // Held while files of a knowledge base change
+    lock: Mutex<()>,
+}
+
+impl KnowledgeBaseStore {
+    /// Knowledge bases kept in a directory
+    pub fn at(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().unwrap()
+    }
+
+    pub(crate) fn kb_dir(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    pub(crate) fn index_path(&self, name: &str, generation: u64) -> PathBuf {
+        self.kb_dir(name).join(format!("index-{}.json", generation))
+    }
+
+    pub(crate) fn save_manifest(&self, kb: &KnowledgeBase) -> McpResult<()> {
+        write_atomic(&self.kb_dir(&kb.name).join(MANIFEST_FILE), &serde_json::to_vec_pretty(kb)?)
+    }
+
+    fn save_documents(&self, name: &str, documents: &[Document]) -> McpResult<()> {
+        write_atomic(&self.kb_dir(name).join(DOCUMENTS_FILE), &serde_json::to_vec(documents)?)
+    }
+
+    /// All knowledge bases, by name
+    pub fn list(&self) -> Vec<KnowledgeBase> {
+        let mut bases: Vec<KnowledgeBase> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| self.get(&entry.file_name().to_string_lossy()).ok())
+            .collect();
+        bases.sort_by(|a, b| a.name.cmp(&b.name));
+        bases
+    }
+
+    /// A knowledge base by name
+    pub fn get(&self, name: &str) -> McpResult<KnowledgeBase> {
+        let content = fs::read_to_string(self.kb_dir(name).join(MANIFEST_FILE))
+            .map_err(|_| McpError::InvalidRequest(format!("No knowledge base '{}'", name)))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Create an empty knowledge base
+    pub fn create(&self, name: &str, embedding_model: &str) -> McpResult<KnowledgeBase> {
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if name.is_empty() || !name.chars().all(valid) {
+            return Err(McpError::InvalidRequest(format!(
+                "Knowledge base names use letters, digits, '-' and '_': '{}'",
+                name
+            )));
+        }
+        embedder(embedding_model)?;
+
+        let _guard = self.lock();
+        if self.get(name).is_ok() {
+            return Err(McpError::InvalidRequest(format!("Knowledge base '{}' already exists", name)));
+        }
+        let now = clock::now();
+        let kb = KnowledgeBase {
+            name: name.to_string(),
+            embedding_model: embedding_model.to_string(),
+            generation: 1,
+            chunking: ChunkingConfig::default(),
+            retrieval: RetrievalConfig::default(),
+            created_at: now,
+            updated_at: now,
+        };
+        self.save_documents(name, &[])?;
+        self.save_manifest(&kb)?;
+        Ok(kb)
+    }
+
+    /// Change how a knowledge base chunks documents and picks chunks. New
+    /// chunking applies to documents added from now on, and to the others
+    /// once the knowledge base is reindexed.
+    pub fn configure(
+        &self,
+        name: &str,
+        chunking: Option<ChunkingConfig>,
+        retrieval: Option<RetrievalConfig>,
+    ) -> McpResult<KnowledgeBase> {
+        if let Some(chunking) = &chunking {
+            chunking.validate()?;
+        }
+        if let Some(retrieval) = &retrieval {
+            retrieval.validate()?;
+        }
+
+        let _guard = self.lock();
+        let mut kb = self.get(name)?;
+        if let Some(chunking) = chunking {
+            kb.chunking = chunking;
+        }
+        if let Some(retrieval) = retrieval {
+            kb.retrieval = retrieval;
+        }
+        kb.updated_at = clock::now();
+        self.save_manifest(&kb)?;
+        Ok(kb)
+    }
+
+    /// Delete a knowledge base with its documents and index
+    pub fn delete(&self, name: &str) -> McpResult<()> {
+        let _guard = self.lock();
+        self.get(name)?;
+        fs::remove_dir_all(self.kb_dir(name))?;
+        Ok(())
+    }
+
+    /// Documents of a knowledge base, oldest first
+    pub fn documents(&self, name: &str) -> McpResult<Vec<Document>> {
+        match fs::read_to_string(self.kb_dir(name).join(DOCUMENTS_FILE)) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Index in use of a knowledge base
+    pub fn index(&self, kb: &KnowledgeBase) -> McpResult<VectorIndex> {
+        VectorIndex::load(&self.index_path(&kb.name, kb.generation), &kb.embedding_model, kb.generation)
+    }
+
+    /// Add a document and index its chunks
+    pub fn add_document(&self, name: &str, title: &str, source: Option<&str>, content: &str) -> McpResult<Document> {
+        if content.trim().is_empty() {
+            return Err(McpError::InvalidRequest(format!("'{}' has no text", title)));
+        }
+
+        let _guard = self.lock();
+        let mut kb = self.get(name)?;
+        let embedder = embedder(&kb.embedding_model)?;
+        let document = Document {
+            id: Uuid::new_v4().to_string(),
+            title: title.to_string(),
+            source: source.map(str::to_string),
+            content: content.to_string(),
+            added_at: clock::now(),
+        };
+
+        let mut index = self.index(&kb)?;
+        index.chunks.extend(embed_document(embedder.as_ref(), &document, &kb.chunking));
+        index.save(&self.index_path(name, kb.generation))?;
+        let mut documents = self.documents(name)?;
+        documents.push(document.clone());
+        self.save_documents(name, &documents)?;
+        kb.updated_at = clock::now();
+        self.save_manifest(&kb)?;
+
+        debug!("Added '{}' to knowledge base {}", title, name);
+        Ok(document)
+    }
+
+    /// Remove a document; its chunks stay in the index until it is compacted
+    pub fn remove_document(&self, name: &str, id: &str) -> McpResult<Document> {
+        let _guard = self.lock();
+        let mut kb = self.get(name)?;
+        let mut documents = self.documents(name)?;
+        let position = documents
+            .iter()
+            .position(|d| d.id == id || d.title == id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("No document '{}' in {}", id, name)))?;
+        let document = documents.remove(position);
+        self.save_documents(name, &documents)?;
+        kb.updated_at = clock::now();
+        self.save_manifest(&kb)?;
+        Ok(document)
+    }
+
+    /// Chunks of a knowledge base's documents for a query, picked by its
+    /// retrieval settings
+    pub fn search(&self, name: &str, query: &str) -> McpResult<Vec<SearchHit>> {
+        let kb = self.get(name)?;
+        self.search_with(name, query, &kb.retrieval)
+    }
+
+    /// Chunks of a knowledge base's documents for a query, picked by other
+    /// retrieval settings
+    pub fn search_with(&self, name: &str, query: &str, retrieval: &RetrievalConfig) -> McpResult<Vec<SearchHit>> {
+        let kb = self.get(name)?;
+        let embedder = embedder(&kb.embedding_model)?;
+        let index = self.index(&kb)?;
+        let documents = self.documents(name)?;
+        let live: HashSet<&str> = documents.iter().map(|d| d.id.as_str()).collect();
+
+        let query = embedder.embed(query);
+        Ok(index
+            .retrieve(&query, retrieval, |c| live.contains(c.document_id.as_str()))
+            .into_iter()
+            .map(|(chunk, score)| {
+                let document = documents.iter().find(|d| d.id == chunk.document_id);
+                SearchHit {
+                    chunk_id: chunk.id.clone(),
+                    document_id: chunk.document_id.clone(),
+                    document_title: document.map(|d| d.title.clone()).unwrap_or_default(),
+                    document_source: document.and_then(|d| d.source.clone()),
+                    text: chunk.text.clone(),
+                    score,
+                }
+            })
+            .collect())
+    }
+}
+
+static KNOWLEDGE_BASES: Lazy<Arc<KnowledgeBaseStore>> =
+    Lazy::new(|| Arc::new(KnowledgeBaseStore::at(data_path(RAG_DIR))));
+
+/// Get the knowledge bases of this device
+pub fn get_knowledge_bases() -> Arc<KnowledgeBaseStore> {
+    KNOWLEDGE_BASES.clone()
+}
@@ -0,0 +1,321 @@
+//! Follow-up reminders on conversations
+//!
+//! A reminder is set on a conversation for later ("remind me about this in
+//! 3 days"). A scheduler checks for reminders that came due and announces
+//! each one once with a link back to its conversation; the desktop app
+//! raises it as a notification. Reminders can be snoozed, which sets them
+//! again for later, or canceled.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+use crate::utils::clock;
+
+/// Links that open a conversation in the desktop app
+pub const CONVERSATION_LINK_PREFIX: &str = "papin://conversation/";
+
+/// Furthest ahead a reminder can be set
+const MAX_DELAY_DAYS: i64 = 365;
+
+/// Longest note kept with a reminder, in characters
+const MAX_NOTE_CHARS: usize = 500;
+
+/// Link that opens a conversation
+pub fn conversation_link(conversation_id: &str) -> String {
+    format!("{}{}", CONVERSATION_LINK_PREFIX, conversation_id)
+}
+
+/// Parse a delay like "3d", "2 hours", "in 3 days" or "tomorrow"
+pub fn parse_delay(text: &str) -> McpResult<ChronoDuration> {
+    let text = text.trim().to_lowercase();
+    let text = text.strip_prefix("in ").unwrap_or(&text).trim();
+    if text == "tomorrow" {
+        return Ok(ChronoDuration::days(1));
+    }
+    let invalid = || {
+        McpError::InvalidRequest(format!(
+            "Can't tell when '{}' is; use something like 30m, 2h, 3 days or 1w",
+            text
+        ))
+    };
+
+    let split = text.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let amount: i64 = text[..split].parse().map_err(|_| invalid())?;
+    let minutes_per_unit = match text[split..].trim() {
+        "m" | "min" | "mins" | "minute" | "minutes" => 1,
+        "h" | "hr" | "hrs" | "hour" | "hours" => 60,
+        "d" | "day" | "days" => 24 * 60,
+        "w" | "week" | "weeks" => 7 * 24 * 60,
+        _ => return Err(invalid()),
+    };
+    if amount == 0 {
+        return Err(McpError::InvalidRequest("Reminders are for later, not now".to_string()));
+    }
+    // Checked before building the duration, which panics when out of range
+    let too_far = || {
+        McpError::InvalidRequest(format!(
+            "Reminders can be set at most {} days ahead",
+            MAX_DELAY_DAYS
+        ))
+    };
+    let minutes = amount.checked_mul(minutes_per_unit).ok_or_else(too_far)?;
+    if minutes > MAX_DELAY_DAYS * 24 * 60 {
+        return Err(too_far());
+    }
+    Ok(ChronoDuration::minutes(minutes))
+}
+
+/// When a reminder is for: a delay from now, or an RFC 3339 time
+pub fn parse_when(text: &str, now: DateTime<Utc>) -> McpResult<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(text.trim()) {
+        let at = at.with_timezone(&Utc);
+        if at <= now {
+            return Err(McpError::InvalidRequest(format!("{} has already passed", text.trim())));
+        }
+        return Ok(at);
+    }
+    Ok(now + parse_delay(text)?)
+}
+
+/// A follow-up reminder on a conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reminder {
+    /// Reminder ID
+    pub id: String,
+
+    /// Conversation to come back to
+    pub conversation_id: String,
+
+    /// Conversation title when the reminder was set
+    pub title: String,
+
+    /// What to follow up on
+    #[serde(default)]
+    pub note: Option<String>,
+
+    /// When the reminder is due
+    pub due_at: DateTime<Utc>,
+
+    /// When it was set
+    pub created_at: DateTime<Utc>,
+
+    /// Times it was snoozed
+    #[serde(default)]
+    pub snoozes: u32,
+
+    /// When it was announced; it stays listed until canceled or snoozed
+    #[serde(default)]
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl Reminder {
+    /// Whether the reminder should be announced now
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.delivered_at.is_none() && self.due_at <= now
+    }
+
+    /// Link back to the conversation
+    pub fn link(&self) -> String {
+        conversation_link(&self.conversation_id)
+    }
+
+    /// Text to show when the reminder comes due
+    pub fn body(&self) -> String {
+        match &self.note {
+            Some(note) => format!("Follow up on \"{}\": {}", self.title, note),
+            None => format!("Follow up on \"{}\"", self.title),
+        }
+    }
+}
+
+/// Reminders waiting or announced
+pub struct ReminderStore {
+    path: PathBuf,
+    reminders: Mutex<Vec<Reminder>>,
+}
+
+impl ReminderStore {
+    /// Store kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        let reminders = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            reminders: Mutex::new(reminders),
+        }
+    }
+
+    fn save(&self, reminders: &[Reminder]) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(reminders)?)?;
+        Ok(())
+    }
+
+    /// Index of the reminder with an ID, or the only one starting with it
+    fn find(reminders: &[Reminder], id: &str) -> McpResult<usize> {
+        if let Some(index) = reminders.iter().position(|r| r.id == id) {
+            return Ok(index);
+        }
+        let matches: Vec<_> = (0..reminders.len())
+            .filter(|&i| !id.is_empty() && reminders[i].id.starts_with(id))
+            .collect();
+        match matches.as_slice() {
+            [index] => Ok(*index),
+            [] => Err(McpError::InvalidRequest(format!("No reminder {}", id))),
+            _ => Err(McpError::InvalidRequest(format!(
+                "More than one reminder starts with {}",
+                id
+            ))),
+        }
+    }
+
+    /// Reminders soonest first, optionally of one conversation
+    pub fn list(&self, conversation_id: Option<&str>) -> Vec<Reminder> {
+        let mut reminders: Vec<Reminder> = self
+            .reminders
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| conversation_id.is_none_or(|id| r.conversation_id == id))
+            .cloned()
+            .collect();
+        reminders.sort_by_key(|r| r.due_at);
+        reminders
+    }
+
+    /// A reminder by ID or unique ID prefix
+    pub fn get(&self, id: &str) -> McpResult<Reminder> {
+        let reminders = self.reminders.lock().unwrap();
+        Ok(reminders[Self::find(&reminders, id)?].clone())
+    }
+
+    /// Set a reminder on a conversation
+    pub fn add(
+        &self,
+        conversation_id: &str,
+        title: &str,
+        due_at: DateTime<Utc>,
+        note: Option<String>,
+    ) -> McpResult<Reminder> {
+        let now = clock::now();
+        if due_at <= now {
+            return Err(McpError::InvalidRequest("Reminders are for later, not now".to_string()));
+        }
+        let reminder = Reminder {
+            id: uuid::Uuid::new_v4().to_string(),
+            conversation_id: conversation_id.to_string(),
+            title: title.to_string(),
+            note: note
+                .map(|n| n.trim().chars().take(MAX_NOTE_CHARS).collect::<String>())
+                .filter(|n| !n.is_empty()),
+            due_at,
+            created_at: now,
+            snoozes: 0,
+            delivered_at: None,
+        };
+
+        let mut reminders = self.reminders.lock().unwrap();
+        reminders.push(reminder.clone());
+        self.save(&reminders)?;
+        info!("Reminder {} set on {} for {}", reminder.id, conversation_id, due_at);
+        Ok(reminder)
+    }
+
+    /// Set a reminder again for later, whether or not it was announced
+    pub fn snooze(&self, id: &str, until: DateTime<Utc>) -> McpResult<Reminder> {
+        if until <= clock::now() {
+            return Err(McpError::InvalidRequest("Reminders are for later, not now".to_string()));
+        }
+        let mut reminders = self.reminders.lock().unwrap();
+        let index = Self::find(&reminders, id)?;
+        let reminder = &mut reminders[index];
+        reminder.due_at = until;
+        reminder.delivered_at = None;
+        reminder.snoozes += 1;
+        let reminder = reminder.clone();
+        self.save(&reminders)?;
+        Ok(reminder)
+    }
+
+    /// Cancel a reminder
+    pub fn cancel(&self, id: &str) -> McpResult<Reminder> {
+        let mut reminders = self.reminders.lock().unwrap();
+        let index = Self::find(&reminders, id)?;
+        let reminder = reminders.remove(index);
+        self.save(&reminders)?;
+        Ok(reminder)
+    }
+
+    /// Cancel every reminder of a conversation; returns how many there were
+    pub fn cancel_for_conversation(&self, conversation_id: &str) -> McpResult<usize> {
+        let mut reminders = self.reminders.lock().unwrap();
+        let before = reminders.len();
+        reminders.retain(|r| r.conversation_id != conversation_id);
+        let removed = before - reminders.len();
+        if removed > 0 {
+            self.save(&reminders)?;
+        }
+        Ok(removed)
+    }
+
+    /// Announce the reminders that came due, each once; returns them
+    pub fn deliver_due(&self) -> McpResult<Vec<Reminder>> {
+        let now = clock::now();
+        let mut reminders = self.reminders.lock().unwrap();
+        let mut due = Vec::new();
+        for reminder in reminders.iter_mut().filter(|r| r.is_due(now)) {
+            reminder.delivered_at = Some(now);
+            due.push(reminder.clone());
+        }
+        if due.is_empty() {
+            return Ok(due);
+        }
+        self.save(&reminders)?;
+        drop(reminders);
+
+        let bus = get_event_bus();
+        for reminder in &due {
+            debug!("Reminder {} on {} is due", reminder.id, reminder.conversation_id);
+            let mut payload = serde_json::to_value(reminder)?;
+            payload["link"] = serde_json::json!(reminder.link());
+            bus.emit(Topic::System, names::REMINDER_DUE, payload);
+        }
+        Ok(due)
+    }
+}
+
+static REMINDER_STORE: Lazy<Arc<ReminderStore>> =
+    Lazy::new(|| Arc::new(ReminderStore::at(data_path("reminders.json"))));
+
+/// Get the global reminder store
+pub fn get_reminder_store() -> Arc<ReminderStore> {
+    REMINDER_STORE.clone()
+}
+
+/// Announce due reminders on an interval
+pub fn spawn_reminder_scheduler(interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let store = get_reminder_store();
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = store.deliver_due() {
+                warn!("Failed to deliver reminders: {}", e);
+            }
+        }
+    })
+}
@@ -0,0 +1,294 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::{Conversation, Message, MessageRole};
+use crate::service::filters::WORKSPACE_CONTEXT_KEY;
+use crate::service::performance::ResponsePerformance;
+use crate::service::pricing::{lookup, PriceTable, BASE_CURRENCY};
+use crate::service::routing::SERVED_BY_METADATA_KEY;
+
+/// Workspace name for conversations that don't belong to one
+pub const NO_WORKSPACE: &str = "(none)";
+
+/// A named series of values, one per week of the report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Series {
+    /// Series name
+    pub name: String,
+
+    /// Values aligned with `UsageAnalytics::weeks`
+    pub values: Vec<f64>,
+}
+
+/// Share of responses served by a model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelShare {
+    /// Model identifier
+    pub model: String,
+
+    /// Responses served
+    pub responses: u64,
+
+    /// Share of all responses, from 0 to 1
+    pub share: f64,
+}
+
+/// Activity of a workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceUsage {
+    /// Workspace name
+    pub workspace: String,
+
+    /// Conversations with messages in the period
+    pub conversations: u64,
+
+    /// Messages in the period
+    pub messages: u64,
+
+    /// Input and output tokens reported by providers
+    pub tokens: u64,
+
+    /// Cost of those tokens in `UsageAnalytics::currency`
+    pub spend: f64,
+}
+
+/// Usage over recent weeks, ready to chart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageAnalytics {
+    /// First day (Monday) of each week, oldest first
+    pub weeks: Vec<NaiveDate>,
+
+    /// User and assistant messages per week
+    pub messages: Series,
+
+    /// Responses per week for each model, busiest first
+    pub model_mix: Vec<Series>,
+
+    /// Average time from prompt to response per week, in milliseconds, as
+    /// recorded on the response or else between the two messages; zero in
+    /// weeks without responses
+    pub avg_latency_ms: Series,
+
+    /// Input tokens per week
+    pub input_tokens: Series,
+
+    /// Output tokens per week
+    pub output_tokens: Series,
+
+    /// Cost of the tokens per week, for models with a price
+    pub spend: Series,
+
+    /// Currency of the spend
+    pub currency: String,
+
+    /// Models over the whole period, busiest first
+    pub models: Vec<ModelShare>,
+
+    /// Busiest workspaces over the whole period
+    pub top_workspaces: Vec<WorkspaceUsage>,
+}
+
+impl UsageAnalytics {
+    /// Total messages over the period
+    pub fn total_messages(&self) -> u64 {
+        self.messages.values.iter().sum::<f64>() as u64
+    }
+
+    /// Total input and output tokens over the period
+    pub fn total_tokens(&self) -> u64 {
+        (self.input_tokens.values.iter().sum::<f64>() + self.output_tokens.values.iter().sum::<f64>()) as u64
+    }
+
+    /// Total spend over the period
+    pub fn total_spend(&self) -> f64 {
+        self.spend.values.iter().sum()
+    }
+}
+
+/// Monday of the week a time falls in
+pub fn week_start(time: DateTime<Utc>) -> NaiveDate {
+    let date = time.date_naive();
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Model that served a response
+fn served_by(conversation: &Conversation, message: &Message) -> String {
+    message
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get(SERVED_BY_METADATA_KEY))
+        .and_then(|v| v.as_str())
+        .unwrap_or(&conversation.model.id)
+        .to_string()
+}
+
+/// Input and output tokens a provider reported for a response
+fn reported_tokens(message: &Message) -> (u64, u64) {
+    let usage = message.metadata.as_ref().and_then(|m| m.get("usage"));
+    let field = |name: &str| usage.and_then(|u| u.get(name)).and_then(|v| v.as_u64()).unwrap_or(0);
+    (field("input_tokens"), field("output_tokens"))
+}
+
+fn series(name: &str, values: Vec<f64>) -> Series {
+    Series {
+        name: name.to_string(),
+        values,
+    }
+}
+
+/// Cost of the responses given in the calendar month of `now`, in
+/// `currency` (US dollars when it has no exchange rate)
+pub fn month_spend(conversations: &[Conversation], now: DateTime<Utc>, prices: &PriceTable, currency: &str) -> f64 {
+    let rate = prices.rate(currency).unwrap_or(1.0);
+    let price_list = prices.prices();
+    let this_month = |time: DateTime<Utc>| time.year() == now.year() && time.month() == now.month();
+
+    conversations
+        .iter()
+        .flat_map(|c| c.messages.iter().map(move |m| (c, m)))
+        .filter(|(_, m)| m.role == MessageRole::Assistant && this_month(m.created_at.into()))
+        .filter_map(|(c, m)| {
+            let (input, output) = reported_tokens(m);
+            lookup(&price_list, &served_by(c, m)).map(|p| p.cost(input, output) * rate)
+        })
+        .sum()
+}
+
+/// Compute usage over the `weeks` weeks up to and including the week of
+/// `now`, with spend in `currency` (US dollars when it has no exchange rate)
+pub fn compute(
+    conversations: &[Conversation],
+    weeks: usize,
+    now: DateTime<Utc>,
+    prices: &PriceTable,
+    currency: &str,
+) -> UsageAnalytics {
+    let weeks = weeks.max(1);
+    let (currency, rate) = match prices.rate(currency) {
+        Some(rate) => (currency.to_uppercase(), rate),
+        None => (BASE_CURRENCY.to_string(), 1.0),
+    };
+    let price_list = prices.prices();
+    let last = week_start(now);
+    let labels: Vec<NaiveDate> = (0..weeks)
+        .rev()
+        .map(|ago| last - Duration::weeks(ago as i64))
+        .collect();
+    let index_of = |time: DateTime<Utc>| labels.iter().position(|week| *week == week_start(time));
+
+    let mut messages = vec![0.0; weeks];
+    let mut input_tokens = vec![0.0; weeks];
+    let mut output_tokens = vec![0.0; weeks];
+    let mut spend = vec![0.0; weeks];
+    let mut latency_total = vec![0.0; weeks];
+    let mut latency_count = vec![0.0; weeks];
+    let mut model_weeks: HashMap<String, Vec<f64>> = HashMap::new();
+    let mut workspaces: HashMap<String, WorkspaceUsage> = HashMap::new();
+
+    for conversation in conversations {
+        let workspace = conversation
+            .metadata
+            .get(WORKSPACE_CONTEXT_KEY)
+            .and_then(|w| w.as_str())
+            .unwrap_or(NO_WORKSPACE)
+            .to_string();
+        let mut active = false;
+        let mut prompted_at: Option<DateTime<Utc>> = None;
+
+        for message in &conversation.messages {
+            let time = DateTime::<Utc>::from(message.created_at);
+            let week = index_of(time);
+            match message.role {
+                MessageRole::User => prompted_at = Some(time),
+                MessageRole::Assistant => {}
+                _ => continue,
+            }
+            let Some(week) = week else { continue };
+
+            messages[week] += 1.0;
+            active = true;
+            let usage = workspaces.entry(workspace.clone()).or_insert_with(|| WorkspaceUsage {
+                workspace: workspace.clone(),
+                conversations: 0,
+                messages: 0,
+                tokens: 0,
+                spend: 0.0,
+            });
+            usage.messages += 1;
+
+            if message.role == MessageRole::Assistant {
+                let model = served_by(conversation, message);
+                let (input, output) = reported_tokens(message);
+                input_tokens[week] += input as f64;
+                output_tokens[week] += output as f64;
+                usage.tokens += input + output;
+                if let Some(cost) = lookup(&price_list, &model).map(|p| p.cost(input, output)) {
+                    spend[week] += cost * rate;
+                    usage.spend += cost * rate;
+                }
+                model_weeks.entry(model).or_insert_with(|| vec![0.0; weeks])[week] += 1.0;
+
+                let prompted_at = prompted_at.take();
+                let latency = ResponsePerformance::of(message)
+                    .map(|p| p.duration_ms as i64)
+                    .or_else(|| prompted_at.map(|at| (time - at).num_milliseconds()));
+                if let Some(latency) = latency.filter(|l| *l >= 0) {
+                    latency_total[week] += latency as f64;
+                    latency_count[week] += 1.0;
+                }
+            }
+        }
+
+        if active {
+            if let Some(usage) = workspaces.get_mut(&workspace) {
+                usage.conversations += 1;
+            }
+        }
+    }
+
+    let mut model_mix: Vec<Series> = model_weeks
+        .into_iter()
+        .map(|(model, values)| series(&model, values))
+        .collect();
+    model_mix.sort_by(|a, b| {
+        let total = |s: &Series| s.values.iter().sum::<f64>();
+        total(b).partial_cmp(&total(a)).unwrap().then_with(|| a.name.cmp(&b.name))
+    });
+
+    let responses: f64 = model_mix.iter().flat_map(|s| s.values.iter()).sum();
+    let models = model_mix
+        .iter()
+        .map(|s| {
+            let count = s.values.iter().sum::<f64>();
+            ModelShare {
+                model: s.name.clone(),
+                responses: count as u64,
+                share: if responses > 0.0 { count / responses } else { 0.0 },
+            }
+        })
+        .collect();
+
+    let mut top_workspaces: Vec<WorkspaceUsage> = workspaces.into_values().collect();
+    top_workspaces.sort_by(|a, b| b.messages.cmp(&a.messages).then_with(|| a.workspace.cmp(&b.workspace)));
+    top_workspaces.truncate(10);
+
+    let avg_latency = latency_total
+        .iter()
+        .zip(&latency_count)
+        .map(|(total, count)| if *count > 0.0 { total / count } else { 0.0 })
+        .collect();
+
+    UsageAnalytics {
+        weeks: labels,
+        messages: series("messages", messages),
+        model_mix,
+        avg_latency_ms: series("avg_latency_ms", avg_latency),
+        input_tokens: series("input_tokens", input_tokens),
+        output_tokens: series("output_tokens", output_tokens),
+        spend: series("spend", spend),
+        currency,
+        models,
+        top_workspaces,
+    }
+}
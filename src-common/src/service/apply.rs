@@ -0,0 +1,621 @@
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::service::chat::ChatService;
+
+/// Conversation metadata key holding the list of applied patches
+pub const APPLICATIONS_METADATA_KEY: &str = "patch_applications";
+
+/// How far (in lines) a hunk may have drifted from its stated position
+const MAX_FUZZ_LINES: usize = 200;
+
+/// A line in a hunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "text", rename_all = "snake_case")]
+pub enum HunkLine {
+    /// Unchanged line
+    Context(String),
+    /// Line added by the patch
+    Add(String),
+    /// Line removed by the patch
+    Remove(String),
+}
+
+/// A contiguous change within a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hunk {
+    /// 1-based line the hunk starts at in the original file
+    pub old_start: usize,
+
+    /// Lines of the hunk
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// Lines the hunk expects to find in the original file
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    /// Lines the hunk leaves in the file
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(s) | HunkLine::Add(s) => Some(s.as_str()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect()
+    }
+
+    /// Render the hunk body as unified diff text
+    pub fn to_text(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| match line {
+                HunkLine::Context(s) => format!(" {}\n", s),
+                HunkLine::Add(s) => format!("+{}\n", s),
+                HunkLine::Remove(s) => format!("-{}\n", s),
+            })
+            .collect()
+    }
+}
+
+/// Change to a single file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileChange {
+    /// Unified diff hunks against the existing file
+    Hunks(Vec<Hunk>),
+    /// Complete new contents from a file block
+    Replace(String),
+    /// The file is removed
+    Delete,
+}
+
+/// A patch to one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilePatch {
+    /// Path relative to the repository root
+    pub path: String,
+
+    /// The change
+    pub change: FileChange,
+}
+
+impl FilePatch {
+    /// Number of selectable hunks; replacements and deletions count as one
+    fn hunk_count(&self) -> usize {
+        match &self.change {
+            FileChange::Hunks(hunks) => hunks.len(),
+            _ => 1,
+        }
+    }
+}
+
+/// Changes extracted from an assistant message
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Patch {
+    /// Per-file changes in the order they appeared
+    pub files: Vec<FilePatch>,
+}
+
+impl Patch {
+    /// Whether the message contained any changes
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Total number of selectable hunks
+    pub fn hunk_count(&self) -> usize {
+        self.files.iter().map(|f| f.hunk_count()).sum()
+    }
+}
+
+/// Extract unified diffs and file blocks from message text.
+///
+/// Diffs are read from ```diff/```patch fences or bare `---`/`+++` headers.
+/// File blocks are fences whose info string names a path, e.g. ```rust src/main.rs
+/// or ```src/main.rs.
+pub fn parse_patch(text: &str) -> Patch {
+    let mut patch = Patch::default();
+    let lines: Vec<&str> = text.lines().collect();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if let Some(info) = line.trim_start().strip_prefix("```") {
+            let info = info.trim();
+            let end = lines[i + 1..]
+                .iter()
+                .position(|l| l.trim_start().starts_with("```"))
+                .map(|p| i + 1 + p)
+                .unwrap_or(lines.len());
+            let body = &lines[(i + 1).min(end)..end];
+
+            if info == "diff" || info == "patch" || body.first().map(|l| l.starts_with("--- ")).unwrap_or(false) {
+                patch.files.extend(parse_unified_diff(body));
+            } else if let Some(path) = info_string_path(info) {
+                let mut content = body.join("\n");
+                content.push('\n');
+                patch.files.push(FilePatch {
+                    path,
+                    change: FileChange::Replace(content),
+                });
+            }
+
+            i = end + 1;
+        } else if line.starts_with("--- ") && lines.get(i + 1).map(|l| l.starts_with("+++ ")).unwrap_or(false) {
+            let end = lines[i..]
+                .iter()
+                .position(|l| l.trim_start().starts_with("```"))
+                .map(|p| i + p)
+                .unwrap_or(lines.len());
+            patch.files.extend(parse_unified_diff(&lines[i..end]));
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+
+    patch
+}
+
+/// Path named in a code fence info string, if any
+fn info_string_path(info: &str) -> Option<String> {
+    let candidate = info
+        .split_whitespace()
+        .map(|token| token.trim_start_matches("path=").trim_start_matches("file=")).rfind(|token| token.contains('/') || token.contains('.'))?;
+    Some(candidate.to_string())
+}
+
+/// Strip the `a/` and `b/` prefixes git adds to diff paths
+fn diff_path(header: &str) -> Option<String> {
+    let path = header.split('\t').next()?.trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    Some(
+        path.strip_prefix("a/")
+            .or_else(|| path.strip_prefix("b/"))
+            .unwrap_or(path)
+            .to_string(),
+    )
+}
+
+/// Parse the `@@ -a,b +c,d @@` header and return the old start line
+fn hunk_old_start(header: &str) -> Option<usize> {
+    let old = header.strip_prefix("@@ -")?.split_whitespace().next()?;
+    old.split(',').next()?.parse().ok()
+}
+
+fn parse_unified_diff(lines: &[&str]) -> Vec<FilePatch> {
+    let mut files = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let (Some(old), Some(new)) = (
+            lines[i].strip_prefix("--- "),
+            lines.get(i + 1).and_then(|l| l.strip_prefix("+++ ")),
+        ) else {
+            i += 1;
+            continue;
+        };
+        i += 2;
+
+        let old_path = diff_path(old);
+        let new_path = diff_path(new);
+        let mut hunks = Vec::new();
+
+        while i < lines.len() && !lines[i].starts_with("--- ") {
+            let Some(old_start) = hunk_old_start(lines[i]) else {
+                i += 1;
+                continue;
+            };
+            i += 1;
+
+            let mut hunk = Hunk { old_start, lines: Vec::new() };
+            while i < lines.len() && !lines[i].starts_with("@@") && !lines[i].starts_with("--- ") {
+                let line = lines[i];
+                match line.chars().next() {
+                    Some('+') => hunk.lines.push(HunkLine::Add(line[1..].to_string())),
+                    Some('-') => hunk.lines.push(HunkLine::Remove(line[1..].to_string())),
+                    Some(' ') => hunk.lines.push(HunkLine::Context(line[1..].to_string())),
+                    // Models often drop the leading space on blank context lines
+                    None => hunk.lines.push(HunkLine::Context(String::new())),
+                    _ => {}
+                }
+                i += 1;
+            }
+            hunks.push(hunk);
+        }
+
+        match (old_path, new_path) {
+            (Some(path), None) => files.push(FilePatch {
+                path,
+                change: FileChange::Delete,
+            }),
+            (_, Some(path)) => files.push(FilePatch {
+                path,
+                change: FileChange::Hunks(hunks),
+            }),
+            (None, None) => {}
+        }
+    }
+
+    files
+}
+
+/// Resolve a patch path inside the repository, rejecting anything that escapes it.
+///
+/// Besides absolute paths and parent components, the deepest part of the path
+/// that exists is resolved, so symlinks pointing out of the repository are
+/// rejected too.
+pub fn scope_path(root: &Path, path: &str) -> McpResult<PathBuf> {
+    let outside = || McpError::InvalidRequest(format!("Path '{}' is outside the repository", path));
+    let relative = Path::new(path);
+    let escapes = relative.is_absolute()
+        || relative
+            .components()
+            .any(|c| matches!(c, Component::ParentDir | Component::Prefix(_) | Component::RootDir))
+        || relative.components().next().map(|c| c.as_os_str() == ".git").unwrap_or(false);
+
+    if escapes {
+        return Err(outside());
+    }
+
+    let canonical_root = root.canonicalize()?;
+    let full = root.join(relative);
+    // A dangling symlink exists but can't be resolved, and writing through it
+    // would create its target, so it is rejected as well
+    if let Some(existing) = full.ancestors().find(|a| a.symlink_metadata().is_ok()) {
+        let canonical = existing.canonicalize().map_err(|_| outside())?;
+        if !canonical.starts_with(&canonical_root) {
+            return Err(outside());
+        }
+    }
+
+    Ok(full)
+}
+
+/// Line endings and final newline of a file, kept when it is rewritten
+struct LineFormat {
+    ending: &'static str,
+    final_newline: bool,
+}
+
+impl LineFormat {
+    /// Format of a file's current contents; new and empty files get `\n` and a final newline
+    fn of(existing: Option<&str>) -> Self {
+        match existing {
+            Some(text) if !text.is_empty() => Self {
+                ending: if text.contains("\r\n") { "\r\n" } else { "\n" },
+                final_newline: text.ends_with('\n'),
+            },
+            _ => Self {
+                ending: "\n",
+                final_newline: true,
+            },
+        }
+    }
+
+    /// Join lines back into file contents
+    fn join<S: AsRef<str>>(&self, lines: &[S]) -> String {
+        let lines: Vec<&str> = lines.iter().map(|l| l.as_ref()).collect();
+        let mut joined = lines.join(self.ending);
+        if self.final_newline && !joined.is_empty() {
+            joined.push_str(self.ending);
+        }
+        joined
+    }
+}
+
+/// Find where a hunk's original lines are in the file, preferring the stated position
+fn locate_hunk(file: &[String], expected: &[&str], hint: usize) -> Option<usize> {
+    let matches_at = |start: usize| {
+        start + expected.len() <= file.len()
+            && file[start..start + expected.len()]
+                .iter()
+                .zip(expected)
+                .all(|(a, b)| a.trim_end() == b.trim_end())
+    };
+
+    // Pure additions to an empty region apply at the hint
+    if expected.is_empty() {
+        return Some(hint.min(file.len()));
+    }
+
+    (0..=MAX_FUZZ_LINES).find_map(|offset| {
+        if hint >= offset && matches_at(hint - offset) {
+            Some(hint - offset)
+        } else if matches_at(hint + offset) {
+            Some(hint + offset)
+        } else {
+            None
+        }
+    })
+}
+
+/// Outcome of checking one hunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HunkPreview {
+    /// 1-based index across the whole patch, used for selection
+    pub index: usize,
+
+    /// Whether the hunk applies cleanly
+    pub applies: bool,
+
+    /// Line the hunk was found at, when it applies
+    pub line: Option<usize>,
+
+    /// Diff text of the hunk
+    pub text: String,
+}
+
+/// Preview of one file's changes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilePreview {
+    /// Path relative to the repository root
+    pub path: String,
+
+    /// "modify", "create", "replace" or "delete"
+    pub action: String,
+
+    /// Hunks in the file
+    pub hunks: Vec<HunkPreview>,
+}
+
+/// Check every hunk against the repository without writing anything
+pub fn preview(patch: &Patch, root: &Path) -> McpResult<Vec<FilePreview>> {
+    let mut previews = Vec::new();
+    let mut index = 0;
+
+    for file in &patch.files {
+        let full = scope_path(root, &file.path)?;
+        let existing = fs::read_to_string(&full).ok();
+
+        let (action, hunks) = match &file.change {
+            FileChange::Hunks(hunks) => {
+                let mut content: Vec<String> = existing
+                    .as_deref()
+                    .unwrap_or_default()
+                    .lines()
+                    .map(String::from)
+                    .collect();
+
+                let previews = hunks
+                    .iter()
+                    .map(|hunk| {
+                        index += 1;
+                        let applied = apply_hunk(&mut content, hunk);
+                        HunkPreview {
+                            index,
+                            applies: applied.is_some(),
+                            line: applied.map(|line| line + 1),
+                            text: hunk.to_text(),
+                        }
+                    })
+                    .collect();
+
+                let action = if existing.is_some() { "modify" } else { "create" };
+                (action, previews)
+            }
+            FileChange::Replace(content) => {
+                index += 1;
+                let action = if existing.is_some() { "replace" } else { "create" };
+                let preview = HunkPreview {
+                    index,
+                    applies: true,
+                    line: Some(1),
+                    text: content.lines().map(|l| format!("+{}\n", l)).collect(),
+                };
+                (action, vec![preview])
+            }
+            FileChange::Delete => {
+                index += 1;
+                let preview = HunkPreview {
+                    index,
+                    applies: existing.is_some(),
+                    line: None,
+                    text: String::new(),
+                };
+                ("delete", vec![preview])
+            }
+        };
+
+        previews.push(FilePreview {
+            path: file.path.clone(),
+            action: action.to_string(),
+            hunks,
+        });
+    }
+
+    Ok(previews)
+}
+
+/// Apply one hunk in memory, returning the 0-based line it was applied at
+fn apply_hunk(content: &mut Vec<String>, hunk: &Hunk) -> Option<usize> {
+    let old = hunk.old_lines();
+    let start = locate_hunk(content, &old, hunk.old_start.saturating_sub(1))?;
+    let new: Vec<String> = hunk.new_lines().into_iter().map(String::from).collect();
+    content.splice(start..start + old.len(), new);
+    Some(start)
+}
+
+/// Result of applying a patch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApplyReport {
+    /// Files written, created or deleted
+    pub files: Vec<String>,
+
+    /// Indices of the hunks applied
+    pub applied_hunks: Vec<usize>,
+
+    /// Whether nothing was written
+    pub dry_run: bool,
+}
+
+/// Apply the selected hunks (all when `selection` is `None`).
+///
+/// Every selected hunk is applied in memory first; if any fails, nothing is
+/// written. With `dry_run` the check runs but files are left untouched.
+pub fn apply(patch: &Patch, root: &Path, selection: Option<&[usize]>, dry_run: bool) -> McpResult<ApplyReport> {
+    let selected = |index: usize| selection.map(|s| s.contains(&index)).unwrap_or(true);
+
+    // Stage every file's new contents before touching the disk
+    let mut staged: Vec<(PathBuf, Option<String>)> = Vec::new();
+    let mut report = ApplyReport {
+        dry_run,
+        ..Default::default()
+    };
+    let mut index = 0;
+
+    for file in &patch.files {
+        let full = scope_path(root, &file.path)?;
+        let existing = fs::read_to_string(&full).ok();
+        let format = LineFormat::of(existing.as_deref());
+        let mut touched = false;
+
+        let new_content = match &file.change {
+            FileChange::Hunks(hunks) => {
+                let mut content: Vec<String> = existing
+                    .as_deref()
+                    .unwrap_or_default()
+                    .lines()
+                    .map(String::from)
+                    .collect();
+
+                for hunk in hunks {
+                    index += 1;
+                    if !selected(index) {
+                        continue;
+                    }
+                    apply_hunk(&mut content, hunk).ok_or_else(|| {
+                        McpError::InvalidRequest(format!(
+                            "Hunk {} does not apply to {}",
+                            index, file.path
+                        ))
+                    })?;
+                    report.applied_hunks.push(index);
+                    touched = true;
+                }
+
+                Some(format.join(&content))
+            }
+            FileChange::Replace(content) => {
+                index += 1;
+                if selected(index) {
+                    report.applied_hunks.push(index);
+                    touched = true;
+                }
+                Some(format.join(&content.lines().collect::<Vec<_>>()))
+            }
+            FileChange::Delete => {
+                index += 1;
+                if selected(index) {
+                    if existing.is_none() {
+                        return Err(McpError::InvalidRequest(format!("{} does not exist", file.path)));
+                    }
+                    report.applied_hunks.push(index);
+                    touched = true;
+                }
+                None
+            }
+        };
+
+        if touched {
+            report.files.push(file.path.clone());
+            staged.push((full, new_content));
+        }
+    }
+
+    if dry_run {
+        debug!("Dry run: {} hunk(s) would apply", report.applied_hunks.len());
+        return Ok(report);
+    }
+
+    for (path, content) in staged {
+        match content {
+            Some(content) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, content)?;
+            }
+            None => fs::remove_file(&path)?,
+        }
+    }
+
+    info!(
+        "Applied {} hunk(s) to {} file(s) in {}",
+        report.applied_hunks.len(),
+        report.files.len(),
+        root.display()
+    );
+    Ok(report)
+}
+
+/// Record of a patch applied from a message, stored in conversation metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplicationRecord {
+    /// Message the patch came from
+    pub message_id: String,
+
+    /// Repository it was applied to
+    pub repository: PathBuf,
+
+    /// Files changed
+    pub files: Vec<String>,
+
+    /// Hunks applied
+    pub hunks: Vec<usize>,
+
+    /// When it was applied
+    pub applied_at: DateTime<Utc>,
+}
+
+/// Append an application record to a conversation's metadata
+pub fn add_record(metadata: &mut serde_json::Value, record: &ApplicationRecord) {
+    if !metadata.is_object() {
+        *metadata = serde_json::Value::Object(serde_json::Map::new());
+    }
+    let entry = metadata
+        .as_object_mut()
+        .unwrap()
+        .entry(APPLICATIONS_METADATA_KEY)
+        .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+
+    if let (Some(list), Ok(value)) = (entry.as_array_mut(), serde_json::to_value(record)) {
+        list.push(value);
+    }
+}
+
+/// Record an application in a conversation managed by a chat service
+pub async fn record_application(
+    chat_service: &ChatService,
+    conversation_id: &str,
+    record: &ApplicationRecord,
+) -> McpResult<()> {
+    let mut conversation = chat_service.get_conversation(conversation_id).await?;
+    add_record(&mut conversation.metadata, record);
+    chat_service.update_conversation(conversation).await
+}
+
+/// Repository bound to a conversation by the desktop app's git tool, if any
+pub fn bound_repository(conversation_id: &str) -> Option<PathBuf> {
+    let content = fs::read_to_string(data_path("repo_bindings.json")).ok()?;
+    let bindings: HashMap<String, PathBuf> = serde_json::from_str(&content).ok()?;
+    bindings.get(conversation_id).cloned()
+}
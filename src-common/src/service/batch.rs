@@ -0,0 +1,552 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use log::{debug, info, warn};
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::auth::{self, Authorization};
+use crate::config::get_settings;
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, Topic};
+use crate::protocol::inspector;
+use crate::service::chat::ChatService;
+
+/// Event published on the bus as a batch makes progress
+pub const BATCH_PROGRESS_EVENT: &str = "batch_progress";
+
+/// Anthropic API version used for the batch endpoint
+const BATCH_API_VERSION: &str = "2023-06-01";
+
+/// One prompt in a batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItem {
+    /// Caller-supplied identifier, echoed in the results
+    #[serde(default, alias = "id")]
+    pub custom_id: String,
+
+    /// Prompt text
+    pub prompt: String,
+
+    /// Model override for this item
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// System prompt for this item
+    #[serde(default)]
+    pub system: Option<String>,
+}
+
+/// Outcome of one batch item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResult {
+    /// Identifier of the item
+    pub custom_id: String,
+
+    /// Prompt that was sent
+    pub prompt: String,
+
+    /// Response text, if the item succeeded
+    pub output: Option<String>,
+
+    /// Error, if the item failed
+    pub error: Option<String>,
+
+    /// Conversation the exchange was stored in, if any
+    pub conversation_id: Option<String>,
+}
+
+/// Where prompts are executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchBackend {
+    /// The provider's batch endpoint; cheaper, completes within hours
+    Provider,
+
+    /// A local queue sending prompts through the chat service
+    Local,
+}
+
+/// Where results are written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOutput {
+    /// JSONL file, one result per line
+    File(PathBuf),
+
+    /// One new conversation per item
+    Conversations,
+}
+
+/// Batch job options
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Backend to use
+    pub backend: BatchBackend,
+
+    /// Default model for items without one
+    pub model: String,
+
+    /// Maximum tokens per response
+    pub max_tokens: u32,
+
+    /// Concurrent requests for the local backend
+    pub concurrency: usize,
+
+    /// Interval between status checks for the provider backend
+    pub poll_interval: Duration,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            backend: BatchBackend::Local,
+            model: "claude-3-sonnet-20240229".to_string(),
+            max_tokens: 4096,
+            concurrency: 4,
+            poll_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Progress of a running batch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchProgress {
+    /// Batch identifier
+    pub batch_id: String,
+
+    /// Number of items
+    pub total: usize,
+
+    /// Items that succeeded
+    pub succeeded: usize,
+
+    /// Items that failed
+    pub failed: usize,
+}
+
+impl BatchProgress {
+    /// Whether every item has finished
+    pub fn is_done(&self) -> bool {
+        self.succeeded + self.failed >= self.total
+    }
+}
+
+/// Load batch items from a JSONL or CSV file.
+///
+/// JSONL lines are objects with a `prompt` and optional `custom_id`, `model`
+/// and `system`. CSV files need a header row with a `prompt` column; `id`,
+/// `model` and `system` columns are optional. Items without an ID are numbered.
+pub fn load_items(path: &Path) -> McpResult<Vec<BatchItem>> {
+    let content = fs::read_to_string(path)?;
+    let is_csv = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("csv"))
+        .unwrap_or(false);
+
+    let mut items = if is_csv {
+        parse_csv(&content)?
+    } else {
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str::<BatchItem>)
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    for (index, item) in items.iter_mut().enumerate() {
+        if item.custom_id.is_empty() {
+            item.custom_id = format!("item-{}", index + 1);
+        }
+    }
+
+    Ok(items)
+}
+
+/// Parse CSV content into batch items
+fn parse_csv(content: &str) -> McpResult<Vec<BatchItem>> {
+    let mut rows = split_csv_rows(content).into_iter();
+    let header = rows
+        .next()
+        .ok_or_else(|| McpError::InvalidRequest("CSV file is empty".to_string()))?;
+
+    let column = |name: &str| header.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+    let prompt_col = column("prompt")
+        .ok_or_else(|| McpError::InvalidRequest("CSV file has no 'prompt' column".to_string()))?;
+    let id_col = column("id").or_else(|| column("custom_id"));
+    let model_col = column("model");
+    let system_col = column("system");
+
+    let field = |row: &Vec<String>, col: Option<usize>| {
+        col.and_then(|c| row.get(c))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    Ok(rows
+        .filter(|row| row.iter().any(|f| !f.trim().is_empty()))
+        .map(|row| BatchItem {
+            custom_id: field(&row, id_col).unwrap_or_default(),
+            prompt: row.get(prompt_col).cloned().unwrap_or_default(),
+            model: field(&row, model_col),
+            system: field(&row, system_col),
+        })
+        .collect())
+}
+
+/// Split CSV content into rows of fields, honoring quoted fields with
+/// embedded commas, newlines and doubled quotes
+fn split_csv_rows(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows
+}
+
+/// Runs batches of prompts and writes the results
+pub struct BatchRunner {
+    /// Chat service for the local backend and conversation output
+    chat_service: Arc<ChatService>,
+
+    /// HTTP client for the provider backend
+    client: reqwest::Client,
+
+    /// Progress listener
+    progress_tx: Option<mpsc::UnboundedSender<BatchProgress>>,
+}
+
+impl BatchRunner {
+    /// Create a new batch runner
+    pub fn new(chat_service: Arc<ChatService>) -> Self {
+        Self {
+            chat_service,
+            client: reqwest::Client::new(),
+            progress_tx: None,
+        }
+    }
+
+    /// Receive progress updates in addition to the bus events
+    pub fn with_progress(mut self, tx: mpsc::UnboundedSender<BatchProgress>) -> Self {
+        self.progress_tx = Some(tx);
+        self
+    }
+
+    /// Run a batch to completion and write its results
+    pub async fn run(
+        &self,
+        items: Vec<BatchItem>,
+        options: &BatchOptions,
+        output: &BatchOutput,
+    ) -> McpResult<Vec<BatchResult>> {
+        if items.is_empty() {
+            return Err(McpError::InvalidRequest("Batch has no items".to_string()));
+        }
+
+        info!("Running batch of {} item(s) on the {:?} backend", items.len(), options.backend);
+
+        let mut results = match options.backend {
+            BatchBackend::Local => self.run_local(items, options).await,
+            BatchBackend::Provider => self.run_provider(items, options).await?,
+        };
+
+        self.write_results(&mut results, output).await?;
+        Ok(results)
+    }
+
+    /// Send prompts through the chat service with bounded concurrency
+    async fn run_local(&self, items: Vec<BatchItem>, options: &BatchOptions) -> Vec<BatchResult> {
+        let mut progress = BatchProgress {
+            batch_id: uuid::Uuid::new_v4().to_string(),
+            total: items.len(),
+            ..Default::default()
+        };
+        self.report(&progress);
+
+        let semaphore = Arc::new(Semaphore::new(options.concurrency.max(1)));
+        let (tx, mut rx) = mpsc::channel(items.len());
+
+        for (index, item) in items.into_iter().enumerate() {
+            let permit = semaphore.clone().acquire_owned().await;
+            let chat_service = self.chat_service.clone();
+            let model = options.model.clone();
+            let tx = tx.clone();
+
+            tokio::spawn(async move {
+                let _permit = permit;
+                let result = run_local_item(&chat_service, &item, &model).await;
+                let _ = tx.send((index, result)).await;
+            });
+        }
+        drop(tx);
+
+        let mut results: Vec<Option<BatchResult>> = vec![None; progress.total];
+        while let Some((index, result)) = rx.recv().await {
+            if result.error.is_some() {
+                progress.failed += 1;
+            } else {
+                progress.succeeded += 1;
+            }
+            self.report(&progress);
+            results[index] = Some(result);
+        }
+
+        results.into_iter().flatten().collect()
+    }
+
+    /// Submit to the provider's batch endpoint and poll until it ends
+    async fn run_provider(&self, items: Vec<BatchItem>, options: &BatchOptions) -> McpResult<Vec<BatchResult>> {
+        let settings = get_settings().lock().unwrap().clone();
+        let authorization = auth::authorization().await?;
+        let base_url = api_base_url(&settings.api.url)?;
+
+        let requests: Vec<_> = items
+            .iter()
+            .map(|item| {
+                let mut params = serde_json::json!({
+                    "model": item.model.as_deref().unwrap_or(&options.model),
+                    "max_tokens": options.max_tokens,
+                    "messages": [{ "role": "user", "content": item.prompt }],
+                });
+                if let Some(system) = &item.system {
+                    params["system"] = serde_json::json!(system);
+                }
+                serde_json::json!({ "custom_id": item.custom_id, "params": params })
+            })
+            .collect();
+
+        let created: serde_json::Value = inspector::send(
+            self.request(reqwest::Method::POST, &format!("{}/v1/messages/batches", base_url), &authorization)
+                .json(&serde_json::json!({ "requests": requests })),
+        )
+        .await
+            .map_err(|e| McpError::Connection(e.to_string()))?
+            .error_for_status()
+            .map_err(|e| McpError::Protocol(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| McpError::Protocol(e.to_string()))?;
+
+        let batch_id = created["id"]
+            .as_str()
+            .ok_or_else(|| McpError::Protocol("Batch response has no ID".to_string()))?
+            .to_string();
+        info!("Submitted batch {}", batch_id);
+
+        // Poll until the provider reports the batch has ended
+        let status_url = format!("{}/v1/messages/batches/{}", base_url, batch_id);
+        let results_url = loop {
+            let status: serde_json::Value = inspector::send(self.request(reqwest::Method::GET, &status_url, &authorization))
+                .await
+                .map_err(|e| McpError::Connection(e.to_string()))?
+                .json()
+                .await
+                .map_err(|e| McpError::Protocol(e.to_string()))?;
+
+            let counts = &status["request_counts"];
+            let count = |name: &str| counts[name].as_u64().unwrap_or(0) as usize;
+            self.report(&BatchProgress {
+                batch_id: batch_id.clone(),
+                total: items.len(),
+                succeeded: count("succeeded"),
+                failed: count("errored") + count("canceled") + count("expired"),
+            });
+
+            if status["processing_status"] == "ended" {
+                break status["results_url"]
+                    .as_str()
+                    .ok_or_else(|| McpError::Protocol("Ended batch has no results URL".to_string()))?
+                    .to_string();
+            }
+
+            debug!("Batch {} still processing", batch_id);
+            tokio::time::sleep(options.poll_interval).await;
+        };
+
+        let body = inspector::send(self.request(reqwest::Method::GET, &results_url, &authorization))
+            .await
+            .map_err(|e| McpError::Connection(e.to_string()))?
+            .text()
+            .await
+            .map_err(|e| McpError::Protocol(e.to_string()))?;
+
+        let mut results: Vec<BatchResult> = items
+            .iter()
+            .map(|item| BatchResult {
+                custom_id: item.custom_id.clone(),
+                prompt: item.prompt.clone(),
+                output: None,
+                error: Some("No result returned".to_string()),
+                conversation_id: None,
+            })
+            .collect();
+
+        for line in body.lines().filter(|l| !l.trim().is_empty()) {
+            let entry: serde_json::Value = serde_json::from_str(line)?;
+            let Some(result) = entry["custom_id"]
+                .as_str()
+                .and_then(|id| results.iter_mut().find(|r| r.custom_id == id))
+            else {
+                continue;
+            };
+
+            if entry["result"]["type"] == "succeeded" {
+                let text = entry["result"]["message"]["content"]
+                    .as_array()
+                    .map(|blocks| {
+                        blocks
+                            .iter()
+                            .filter_map(|b| b["text"].as_str())
+                            .collect::<Vec<_>>()
+                            .join("")
+                    })
+                    .unwrap_or_default();
+                result.output = Some(text);
+                result.error = None;
+            } else {
+                result.error = Some(
+                    entry["result"]["error"]["message"]
+                        .as_str()
+                        .or_else(|| entry["result"]["type"].as_str())
+                        .unwrap_or("unknown error")
+                        .to_string(),
+                );
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Build an authenticated request to the provider API
+    fn request(&self, method: reqwest::Method, url: &str, authorization: &Authorization) -> reqwest::RequestBuilder {
+        authorization
+            .apply(self.client.request(method, url))
+            .header("anthropic-version", BATCH_API_VERSION)
+    }
+
+    /// Write results to a file or into conversations
+    async fn write_results(&self, results: &mut [BatchResult], output: &BatchOutput) -> McpResult<()> {
+        match output {
+            BatchOutput::File(path) => {
+                let mut file = fs::File::create(path)?;
+                for result in results.iter() {
+                    writeln!(file, "{}", serde_json::to_string(result)?)?;
+                }
+                info!("Wrote {} batch result(s) to {}", results.len(), path.display());
+            }
+            BatchOutput::Conversations => {
+                for result in results.iter_mut() {
+                    // Local results already live in the conversation they were sent in
+                    if result.conversation_id.is_some() {
+                        continue;
+                    }
+                    let Some(output) = &result.output else {
+                        continue;
+                    };
+
+                    let mut conversation = self
+                        .chat_service
+                        .create_conversation(&format!("Batch: {}", result.custom_id), None)
+                        .await?;
+                    conversation.add_message(crate::models::Message::user(result.prompt.clone()));
+                    conversation.add_message(crate::models::Message::assistant(output.clone()));
+                    result.conversation_id = Some(conversation.id.clone());
+
+                    if let Err(e) = crate::config::get_storage_manager().save_conversation(&conversation) {
+                        warn!("Failed to store batch conversation {}: {}", conversation.id, e);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Publish progress on the bus and to the listener
+    fn report(&self, progress: &BatchProgress) {
+        if let Ok(payload) = serde_json::to_value(progress) {
+            get_event_bus().emit(Topic::System, BATCH_PROGRESS_EVENT, payload);
+        }
+        if let Some(tx) = &self.progress_tx {
+            let _ = tx.send(progress.clone());
+        }
+    }
+}
+
+/// Run one item through a fresh conversation
+async fn run_local_item(chat_service: &ChatService, item: &BatchItem, default_model: &str) -> BatchResult {
+    let model_id = item.model.as_deref().unwrap_or(default_model);
+    let model = chat_service
+        .available_models()
+        .await
+        .ok()
+        .and_then(|models| models.into_iter().find(|m| m.id == model_id));
+
+    let outcome = async {
+        let conversation = chat_service
+            .create_conversation(&format!("Batch: {}", item.custom_id), model)
+            .await?;
+        if let Some(system) = &item.system {
+            chat_service.set_system_message(&conversation.id, system).await?;
+        }
+        let response = chat_service.send_message(&conversation.id, &item.prompt).await?;
+        Ok::<_, McpError>((conversation.id, response.text()))
+    }
+    .await;
+
+    match outcome {
+        Ok((conversation_id, output)) => BatchResult {
+            custom_id: item.custom_id.clone(),
+            prompt: item.prompt.clone(),
+            output: Some(output),
+            error: None,
+            conversation_id: Some(conversation_id),
+        },
+        Err(e) => BatchResult {
+            custom_id: item.custom_id.clone(),
+            prompt: item.prompt.clone(),
+            output: None,
+            error: Some(e.to_string()),
+            conversation_id: None,
+        },
+    }
+}
+
+/// Derive the HTTPS API origin from the configured endpoint
+pub(crate) fn api_base_url(api_url: &str) -> McpResult<String> {
+    let url = url::Url::parse(api_url)
+        .map_err(|e| McpError::Config(format!("Invalid API URL {}: {}", api_url, e)))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| McpError::Config(format!("API URL {} has no host", api_url)))?;
+
+    Ok(match url.port() {
+        Some(port) => format!("https://{}:{}", host, port),
+        None => format!("https://{}", host),
+    })
+}
@@ -0,0 +1,330 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+use log::{debug, info, warn};
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::models::Model;
+use crate::service::chat::ChatService;
+
+/// Standardized prompts used for every benchmark run
+pub const STANDARD_PROMPTS: &[(&str, &str)] = &[
+    ("short", "Reply with the single word: ready."),
+    ("reasoning", "List the prime numbers below 50 and explain in two sentences how you found them."),
+    ("long_output", "Write a 300 word description of how a compiler turns source code into machine code."),
+];
+
+/// Maximum number of runs kept in the history file
+const MAX_HISTORY_RUNS: usize = 200;
+
+/// Measurements for a single prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptMeasurement {
+    /// Prompt identifier from `STANDARD_PROMPTS`
+    pub prompt_id: String,
+
+    /// Estimated prompt tokens
+    pub prompt_tokens: usize,
+
+    /// Estimated output tokens
+    pub output_tokens: usize,
+
+    /// Time to first token in milliseconds
+    pub time_to_first_token_ms: u64,
+
+    /// Total time in milliseconds
+    pub total_ms: u64,
+
+    /// Prefill throughput (prompt tokens / time to first token)
+    pub prefill_tokens_per_sec: f64,
+
+    /// Decode throughput (output tokens / time after first token)
+    pub decode_tokens_per_sec: f64,
+
+    /// Error if the prompt failed
+    pub error: Option<String>,
+}
+
+/// Benchmark result for one model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelBenchmark {
+    /// Model identifier
+    pub model_id: String,
+
+    /// Provider name
+    pub provider: String,
+
+    /// Per-prompt measurements
+    pub measurements: Vec<PromptMeasurement>,
+
+    /// Peak resident memory of the process in bytes, if available
+    pub peak_memory_bytes: Option<u64>,
+}
+
+impl ModelBenchmark {
+    /// Average time to first token over successful prompts
+    pub fn avg_time_to_first_token_ms(&self) -> f64 {
+        average(self.successful().map(|m| m.time_to_first_token_ms as f64))
+    }
+
+    /// Average prefill throughput over successful prompts
+    pub fn avg_prefill_tokens_per_sec(&self) -> f64 {
+        average(self.successful().map(|m| m.prefill_tokens_per_sec))
+    }
+
+    /// Average decode throughput over successful prompts
+    pub fn avg_decode_tokens_per_sec(&self) -> f64 {
+        average(self.successful().map(|m| m.decode_tokens_per_sec))
+    }
+
+    fn successful(&self) -> impl Iterator<Item = &PromptMeasurement> {
+        self.measurements.iter().filter(|m| m.error.is_none())
+    }
+}
+
+/// A complete benchmark run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    /// Run identifier
+    pub id: String,
+
+    /// When the run started
+    pub started_at: SystemTime,
+
+    /// Application version that produced the report
+    pub app_version: String,
+
+    /// Results per model
+    pub models: Vec<ModelBenchmark>,
+}
+
+/// Comparison of a model's latest result with its previous run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkRegression {
+    /// Model identifier
+    pub model_id: String,
+
+    /// Decode throughput change in percent (negative = slower)
+    pub decode_change_percent: f64,
+
+    /// Time to first token change in percent (positive = slower)
+    pub ttft_change_percent: f64,
+}
+
+fn average(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = values.fold((0.0, 0usize), |(s, c), v| (s + v, c + 1));
+    if count == 0 { 0.0 } else { sum / count as f64 }
+}
+
+/// Rough token estimate (about four characters per token)
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Read the peak resident set size of the current process
+fn peak_memory_bytes() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find(|line| line.starts_with("VmHWM:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+/// Runs standardized prompts against models and keeps a history of results
+pub struct BenchmarkRunner {
+    /// Chat service used to run prompts
+    chat_service: Arc<ChatService>,
+
+    /// Path to the history file
+    history_path: PathBuf,
+}
+
+impl BenchmarkRunner {
+    /// Create a new benchmark runner
+    pub fn new(chat_service: Arc<ChatService>) -> Self {
+        Self {
+            chat_service,
+            history_path: data_path("bench_history.json"),
+        }
+    }
+
+    /// Run the benchmark for the given models
+    pub async fn run(&self, models: &[Model]) -> McpResult<BenchmarkReport> {
+        let started_at = SystemTime::now();
+        let mut results = Vec::new();
+
+        for model in models {
+            info!("Benchmarking model {}", model.id);
+            results.push(self.run_model(model).await);
+        }
+
+        Ok(BenchmarkReport {
+            id: uuid::Uuid::new_v4().to_string(),
+            started_at,
+            app_version: crate::utils::app_version(),
+            models: results,
+        })
+    }
+
+    /// Run every standard prompt against a single model
+    async fn run_model(&self, model: &Model) -> ModelBenchmark {
+        let mut measurements = Vec::new();
+
+        for (prompt_id, prompt) in STANDARD_PROMPTS {
+            let measurement = match self.measure_prompt(model, prompt).await {
+                Ok(mut m) => {
+                    m.prompt_id = prompt_id.to_string();
+                    m
+                }
+                Err(e) => {
+                    warn!("Benchmark prompt {} failed on {}: {}", prompt_id, model.id, e);
+                    PromptMeasurement {
+                        prompt_id: prompt_id.to_string(),
+                        prompt_tokens: estimate_tokens(prompt),
+                        output_tokens: 0,
+                        time_to_first_token_ms: 0,
+                        total_ms: 0,
+                        prefill_tokens_per_sec: 0.0,
+                        decode_tokens_per_sec: 0.0,
+                        error: Some(e.to_string()),
+                    }
+                }
+            };
+            measurements.push(measurement);
+        }
+
+        ModelBenchmark {
+            model_id: model.id.clone(),
+            provider: model.provider.clone(),
+            measurements,
+            peak_memory_bytes: peak_memory_bytes(),
+        }
+    }
+
+    /// Stream a single prompt in a throwaway conversation and time it
+    async fn measure_prompt(&self, model: &Model, prompt: &str) -> McpResult<PromptMeasurement> {
+        let conversation = self
+            .chat_service
+            .create_conversation("Benchmark", Some(model.clone()))
+            .await?;
+
+        let start = Instant::now();
+        let result = self.stream_and_time(&conversation.id, prompt, start).await;
+
+        // Always clean up the benchmark conversation, bypassing the trash
+        let cleanup = match self.chat_service.delete_conversation(&conversation.id).await {
+            Ok(()) => self.chat_service.purge_conversation(&conversation.id).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = cleanup {
+            debug!("Failed to delete benchmark conversation: {}", e);
+        }
+
+        let (first_token, output) = result?;
+        let total = start.elapsed();
+
+        let prompt_tokens = estimate_tokens(prompt);
+        let output_tokens = estimate_tokens(&output);
+        let ttft_secs = first_token.as_secs_f64();
+        let decode_secs = (total.as_secs_f64() - ttft_secs).max(f64::EPSILON);
+
+        Ok(PromptMeasurement {
+            prompt_id: String::new(),
+            prompt_tokens,
+            output_tokens,
+            time_to_first_token_ms: first_token.as_millis() as u64,
+            total_ms: total.as_millis() as u64,
+            prefill_tokens_per_sec: if ttft_secs > 0.0 { prompt_tokens as f64 / ttft_secs } else { 0.0 },
+            decode_tokens_per_sec: output_tokens as f64 / decode_secs,
+            error: None,
+        })
+    }
+
+    async fn stream_and_time(
+        &self,
+        conversation_id: &str,
+        prompt: &str,
+        start: Instant,
+    ) -> McpResult<(std::time::Duration, String)> {
+        let mut rx = self
+            .chat_service
+            .send_message_streaming(conversation_id, prompt)
+            .await?;
+
+        let mut first_token = None;
+        let mut output = String::new();
+
+        while let Some(chunk) = rx.recv().await {
+            let message = chunk?;
+            let text = message.text();
+            if first_token.is_none() && !text.is_empty() {
+                first_token = Some(start.elapsed());
+            }
+            // Chunks carry the accumulated text so far
+            output = text;
+        }
+
+        Ok((first_token.unwrap_or_else(|| start.elapsed()), output))
+    }
+
+    /// Load previous benchmark runs
+    pub fn load_history(&self) -> McpResult<Vec<BenchmarkReport>> {
+        if !self.history_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.history_path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Append a report to the history file
+    pub fn save_to_history(&self, report: &BenchmarkReport) -> McpResult<()> {
+        let mut history = self.load_history().unwrap_or_default();
+        history.push(report.clone());
+
+        if history.len() > MAX_HISTORY_RUNS {
+            let excess = history.len() - MAX_HISTORY_RUNS;
+            history.drain(0..excess);
+        }
+
+        let content = serde_json::to_string_pretty(&history)?;
+        fs::write(&self.history_path, content).map_err(McpError::Io)
+    }
+
+    /// Compare a report with the most recent previous run of each model
+    pub fn compare_with_history(&self, report: &BenchmarkReport) -> Vec<BenchmarkRegression> {
+        let history = self.load_history().unwrap_or_default();
+
+        report
+            .models
+            .iter()
+            .filter_map(|current| {
+                let previous = history
+                    .iter()
+                    .rev()
+                    .filter(|run| run.id != report.id)
+                    .flat_map(|run| run.models.iter())
+                    .find(|m| m.model_id == current.model_id)?;
+
+                let change = |new: f64, old: f64| if old > 0.0 { (new - old) / old * 100.0 } else { 0.0 };
+
+                Some(BenchmarkRegression {
+                    model_id: current.model_id.clone(),
+                    decode_change_percent: change(
+                        current.avg_decode_tokens_per_sec(),
+                        previous.avg_decode_tokens_per_sec(),
+                    ),
+                    ttft_change_percent: change(
+                        current.avg_time_to_first_token_ms(),
+                        previous.avg_time_to_first_token_ms(),
+                    ),
+                })
+            })
+            .collect()
+    }
+}
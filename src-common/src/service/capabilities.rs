@@ -0,0 +1,494 @@
+//! What models can do, and how requests are adapted to models that can't do
+//! everything a conversation asks of them.
+//!
+//! Every model carries a capability descriptor, which users can correct for
+//! a whole provider or for models by ID prefix. Before a message goes out,
+//! the conversation is negotiated against its model: images are left out
+//! with a note, tool calls are emulated in the prompt, JSON is asked for in
+//! words and streamed replies come back whole. The stored conversation keeps
+//! what was written; what was given up is kept in the reply's metadata.
+
+use log::warn;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::models::message::{ContentType, RESPONSE_FORMAT_METADATA_KEY};
+use crate::models::{Message, MessageRole, Model, ModelCapabilities, Tool, ToolCall};
+
+/// Metadata key of the features a reply was produced without
+pub const DEGRADED_METADATA_KEY: &str = "degraded";
+
+/// Conversation metadata key asking for replies in JSON
+pub const JSON_MODE_METADATA_KEY: &str = "json_mode";
+
+/// Conversation metadata key of the tools offered to the model
+pub const TOOLS_METADATA_KEY: &str = "offered_tools";
+
+/// Fenced blocks a model without native tools calls tools with
+static TOOL_CALL_BLOCK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)```tool_call[ \t]*\r?\n(.*?)```").unwrap());
+
+/// A fenced JSON reply
+static JSON_BLOCK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)^```(?:json)?[ \t]*\r?\n(.*?)\r?\n?```$").unwrap());
+
+/// A feature a model may lack
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// Seeing images
+    Vision,
+
+    /// Calling tools natively
+    Tools,
+
+    /// Streaming replies
+    Streaming,
+
+    /// Being held to replying with JSON
+    JsonMode,
+}
+
+impl Feature {
+    /// Every feature, in display order
+    pub const ALL: [Feature; 4] = [Feature::Vision, Feature::Tools, Feature::Streaming, Feature::JsonMode];
+
+    /// Whether a model with these capabilities has the feature
+    pub fn supported_by(self, capabilities: &ModelCapabilities) -> bool {
+        match self {
+            Feature::Vision => capabilities.vision,
+            Feature::Tools => capabilities.functions,
+            Feature::Streaming => capabilities.streaming,
+            Feature::JsonMode => capabilities.json_mode,
+        }
+    }
+
+    fn set(self, capabilities: &mut ModelCapabilities, supported: bool) {
+        match self {
+            Feature::Vision => capabilities.vision = supported,
+            Feature::Tools => capabilities.functions = supported,
+            Feature::Streaming => capabilities.streaming = supported,
+            Feature::JsonMode => capabilities.json_mode = supported,
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Feature::Vision => "vision",
+            Feature::Tools => "tools",
+            Feature::Streaming => "streaming",
+            Feature::JsonMode => "json_mode",
+        })
+    }
+}
+
+impl FromStr for Feature {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> McpResult<Self> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "vision" | "images" => Ok(Feature::Vision),
+            "tools" | "functions" => Ok(Feature::Tools),
+            "streaming" => Ok(Feature::Streaming),
+            "json_mode" | "json" => Ok(Feature::JsonMode),
+            _ => Err(McpError::InvalidRequest(format!(
+                "Unknown feature '{}'; use vision, tools, streaming or json_mode",
+                s
+            ))),
+        }
+    }
+}
+
+/// Corrections by provider name or model ID prefix
+pub type CapabilityOverrides = BTreeMap<String, BTreeMap<Feature, bool>>;
+
+/// The user's corrections to what models say they can do
+pub struct CapabilityTable {
+    /// File holding the corrections
+    path: PathBuf,
+}
+
+impl CapabilityTable {
+    /// Table backed by `capabilities.json` in the data directory
+    pub fn new() -> Self {
+        Self::at(data_path("capabilities.json"))
+    }
+
+    /// Table backed by the given file
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn load(&self) -> CapabilityOverrides {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, overrides: &CapabilityOverrides) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(overrides)?)?;
+        Ok(())
+    }
+
+    /// All corrections
+    pub fn overrides(&self) -> CapabilityOverrides {
+        self.load()
+    }
+
+    /// Say whether a provider, or every model whose ID starts with `target`,
+    /// has a feature
+    pub fn set(&self, target: &str, feature: Feature, supported: bool) -> McpResult<()> {
+        let target = target.trim();
+        if target.is_empty() {
+            return Err(McpError::InvalidRequest(
+                "Name a provider or a model ID prefix".to_string(),
+            ));
+        }
+        let mut overrides = self.load();
+        overrides
+            .entry(target.to_string())
+            .or_default()
+            .insert(feature, supported);
+        self.save(&overrides)
+    }
+
+    /// Drop a correction, or all of a target's with no feature; returns
+    /// false if there was none
+    pub fn reset(&self, target: &str, feature: Option<Feature>) -> McpResult<bool> {
+        let mut overrides = self.load();
+        let removed = match (overrides.get_mut(target), feature) {
+            (None, _) => false,
+            (Some(_), None) => overrides.remove(target).is_some(),
+            (Some(features), Some(feature)) => {
+                let removed = features.remove(&feature).is_some();
+                if features.is_empty() {
+                    overrides.remove(target);
+                }
+                removed
+            }
+        };
+        if removed {
+            self.save(&overrides)?;
+        }
+        Ok(removed)
+    }
+
+    /// What a model can do: what it says, corrected for its provider and
+    /// then by model ID prefix, the longest prefix last
+    pub fn for_model(&self, model: &Model) -> ModelCapabilities {
+        let overrides = self.load();
+        let mut capabilities = model.capabilities.clone();
+        let mut prefixes: Vec<&String> = overrides
+            .keys()
+            .filter(|target| **target != model.provider && model.id.starts_with(target.as_str()))
+            .collect();
+        prefixes.sort_by_key(|prefix| prefix.len());
+
+        let layers = overrides
+            .get(&model.provider)
+            .into_iter()
+            .chain(prefixes.into_iter().map(|p| &overrides[p]));
+        for features in layers {
+            for (feature, supported) in features {
+                feature.set(&mut capabilities, *supported);
+            }
+        }
+        capabilities
+    }
+}
+
+impl Default for CapabilityTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A feature a reply was produced without, and what was done instead
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Degradation {
+    /// The feature the model lacks
+    pub feature: Feature,
+
+    /// What was done instead
+    pub detail: String,
+}
+
+impl Degradation {
+    /// A feature given up
+    pub fn new(feature: Feature, detail: impl Into<String>) -> Self {
+        Self {
+            feature,
+            detail: detail.into(),
+        }
+    }
+
+    /// Features a message's reply was produced without
+    pub fn of(message: &Message) -> Vec<Self> {
+        message
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get(DEGRADED_METADATA_KEY))
+            .and_then(|d| serde_json::from_value(d.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Keep features given up in a message's metadata, next to those kept
+    /// already
+    pub fn attach(degraded: &[Self], message: &mut Message) {
+        if degraded.is_empty() {
+            return;
+        }
+        let mut all = Self::of(message);
+        for degradation in degraded {
+            all.retain(|d| d.feature != degradation.feature);
+            all.push(degradation.clone());
+        }
+        message.metadata.get_or_insert_with(Default::default).insert(
+            DEGRADED_METADATA_KEY.to_string(),
+            serde_json::to_value(all).unwrap_or_default(),
+        );
+    }
+
+    /// One line for display, like "Degraded: vision (left out 1 image)"
+    pub fn summary(degraded: &[Self]) -> Option<String> {
+        if degraded.is_empty() {
+            return None;
+        }
+        let parts: Vec<String> = degraded
+            .iter()
+            .map(|d| format!("{} ({})", d.feature, d.detail))
+            .collect();
+        Some(format!("Degraded: {}", parts.join(", ")))
+    }
+}
+
+/// Tools a conversation offers the model
+pub fn offered_tools(metadata: &serde_json::Value) -> Vec<Tool> {
+    metadata
+        .get(TOOLS_METADATA_KEY)
+        .and_then(|t| serde_json::from_value(t.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Whether a conversation asks for replies in JSON
+pub fn wants_json(metadata: &serde_json::Value) -> bool {
+    metadata
+        .get(JSON_MODE_METADATA_KEY)
+        .and_then(|j| j.as_bool())
+        .unwrap_or(false)
+}
+
+/// A conversation adapted to what its model can do
+#[derive(Debug, Clone)]
+pub struct Negotiation {
+    /// Messages to send in place of the conversation
+    pub messages: Vec<Message>,
+
+    /// Features given up
+    pub degraded: Vec<Degradation>,
+
+    /// Tool calls are emulated and have to be read from the reply's text
+    emulated_tools: bool,
+
+    /// JSON was asked for in words and may come back fenced
+    emulated_json: bool,
+}
+
+impl Negotiation {
+    /// Adapt a conversation, the new message last, to a model's capabilities
+    pub fn new(capabilities: &ModelCapabilities, metadata: &serde_json::Value, mut messages: Vec<Message>) -> Self {
+        let mut degraded = Vec::new();
+
+        if !capabilities.vision {
+            let left_out = strip_images(&mut messages);
+            if left_out > 0 {
+                degraded.push(Degradation::new(
+                    Feature::Vision,
+                    format!("left out {} image{} the model can't see", left_out, plural(left_out)),
+                ));
+            }
+        }
+
+        let tools = offered_tools(metadata);
+        let uses_tools = messages.iter().any(|m| {
+            m.role == MessageRole::Tool
+                || m.content
+                    .parts
+                    .iter()
+                    .any(|p| matches!(p, ContentType::ToolCalls { .. } | ContentType::ToolResults { .. }))
+        });
+        let emulated_tools = !capabilities.functions && (uses_tools || !tools.is_empty());
+        if emulated_tools {
+            emulate_tools(&mut messages, &tools);
+            degraded.push(Degradation::new(Feature::Tools, "tool calls emulated in the prompt"));
+        }
+
+        let emulated_json = wants_json(metadata) && !capabilities.json_mode;
+        if let Some(last) = messages.last_mut() {
+            if emulated_json {
+                last.content.parts.push(ContentType::Text {
+                    text: "\n\nReply with a single valid JSON value and nothing else.".to_string(),
+                });
+                degraded.push(Degradation::new(Feature::JsonMode, "JSON asked for in the prompt"));
+            } else if wants_json(metadata) {
+                last.metadata
+                    .get_or_insert_with(Default::default)
+                    .insert(RESPONSE_FORMAT_METADATA_KEY.to_string(), serde_json::json!("json"));
+            }
+        }
+
+        Self {
+            messages,
+            degraded,
+            emulated_tools,
+            emulated_json,
+        }
+    }
+
+    /// Whether anything was given up
+    pub fn is_degraded(&self) -> bool {
+        !self.degraded.is_empty()
+    }
+
+    /// Bring a reply to what the model would have sent with the features it
+    /// lacks, and keep what was given up in its metadata
+    pub fn finish(&self, reply: &mut Message) {
+        if self.emulated_tools && !reply.has_tool_calls() {
+            let text = reply.text();
+            let calls = parse_tool_calls(&text);
+            if !calls.is_empty() {
+                let rest = TOOL_CALL_BLOCK.replace_all(&text, "").trim().to_string();
+                replace_text(reply, rest);
+                reply.content.parts.push(ContentType::ToolCalls { calls });
+            }
+        }
+        if self.emulated_json {
+            let text = reply.text();
+            let trimmed = text.trim();
+            let json = JSON_BLOCK
+                .captures(trimmed)
+                .and_then(|c| c.get(1))
+                .map_or(trimmed, |m| m.as_str().trim());
+            if serde_json::from_str::<serde_json::Value>(json).is_ok() {
+                if json != text {
+                    replace_text(reply, json.to_string());
+                }
+            } else {
+                warn!("Reply {} was asked for JSON but isn't JSON", reply.id);
+            }
+        }
+        Degradation::attach(&self.degraded, reply);
+    }
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// Replace images with a note saying what they showed; returns how many
+fn strip_images(messages: &mut [Message]) -> usize {
+    let mut left_out = 0;
+    for message in messages {
+        for part in &mut message.content.parts {
+            if let ContentType::Image { url, alt_text } = part {
+                let shown = alt_text.clone().unwrap_or_else(|| url.clone());
+                *part = ContentType::Text {
+                    text: format!("[Image left out: {}]", shown),
+                };
+                left_out += 1;
+            }
+        }
+    }
+    left_out
+}
+
+/// Describe the tools in a system message and turn tool calls and results
+/// into text
+fn emulate_tools(messages: &mut Vec<Message>, tools: &[Tool]) {
+    for message in messages.iter_mut() {
+        for part in &mut message.content.parts {
+            let text = match part {
+                ContentType::ToolCalls { calls } => calls
+                    .iter()
+                    .map(|c| {
+                        let call = serde_json::json!({ "name": c.name, "arguments": c.arguments });
+                        format!("```tool_call\n{}\n```", call)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                ContentType::ToolResults { results } => format!(
+                    "Tool results:\n```json\n{}\n```",
+                    serde_json::to_string_pretty(results).unwrap_or_default()
+                ),
+                _ => continue,
+            };
+            *part = ContentType::Text { text };
+        }
+        if message.role == MessageRole::Tool {
+            message.role = MessageRole::User;
+        }
+    }
+
+    let after_system = messages
+        .iter()
+        .position(|m| m.role != MessageRole::System)
+        .unwrap_or(messages.len());
+    messages.insert(after_system, Message::system(tool_instructions(tools)));
+}
+
+/// How to call tools without native support
+fn tool_instructions(tools: &[Tool]) -> String {
+    let mut text = "To call a tool, reply with one fenced block per call and wait for the results:\n\n\
+                    ```tool_call\n{\"name\": \"tool name\", \"arguments\": {}}\n```"
+        .to_string();
+    if !tools.is_empty() {
+        text.push_str("\n\nTools:");
+        for tool in tools {
+            text.push_str(&format!("\n- {}: {}", tool.name, tool.description));
+            if !tool.schema.is_null() {
+                text.push_str(&format!(" Arguments: {}", tool.schema));
+            }
+        }
+    }
+    text
+}
+
+/// Tool calls in fenced blocks of a reply's text
+fn parse_tool_calls(text: &str) -> Vec<ToolCall> {
+    TOOL_CALL_BLOCK
+        .captures_iter(text)
+        .filter_map(|c| serde_json::from_str::<serde_json::Value>(c[1].trim()).ok())
+        .filter_map(|call| {
+            let name = call.get("name")?.as_str()?.to_string();
+            let arguments = call.get("arguments").cloned().unwrap_or_else(|| serde_json::json!({}));
+            Some(ToolCall::new(
+                format!("call_{}", Uuid::new_v4().simple()),
+                name,
+                arguments,
+            ))
+        })
+        .collect()
+}
+
+/// Replace the text of a reply, keeping its other parts
+fn replace_text(reply: &mut Message, text: String) {
+    reply.content.parts.retain(|p| !matches!(p, ContentType::Text { .. }));
+    reply.content.parts.insert(0, ContentType::Text { text });
+}
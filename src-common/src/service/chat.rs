@@ -1,21 +1,109 @@
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::mpsc;
 use log::{debug, error, info, warn};
 
+use crate::config::{
+    get_attachment_store, get_settings, Attachment, Draft, JournalEntry, OperationKind, TrashedConversation,
+};
+use crate::environment::{get_environments, Environment, Scope};
 use crate::error::{McpError, McpResult};
-use crate::models::{Conversation, Message, Model};
+use crate::events::{get_event_bus, names, Topic};
+use crate::memory::{self, get_memory_store, MemoryNote, MEMORIES_METADATA_KEY, RECALL_LIMIT};
+use crate::models::message::ContentType;
+use crate::models::{
+    Conversation, Message, MessageFeedback, MessageRole, MessageVersion, Model, ModelCapabilities, Rating, Tool,
+    ToolResult,
+};
+use crate::protocol::ConnectionStatus;
+use crate::rag::citations::{self, Source, KNOWLEDGE_BASES_METADATA_KEY};
+use crate::rag::get_knowledge_bases;
+use crate::service::capabilities::{
+    CapabilityTable, Degradation, Feature, Negotiation, JSON_MODE_METADATA_KEY, TOOLS_METADATA_KEY,
+};
+use crate::service::analytics;
+use crate::service::estimate::{self, CostEstimate};
+use crate::service::experiments::{get_experiment_store, ExperimentMiddleware};
+use crate::service::filters::{ProfanityFilter, ReplacementFilter, SecretFilter, WORKSPACE_CONTEXT_KEY};
+use crate::service::limits::{
+    encoded_size, leave_out_older_images, measure_image, Fit, LimitsTable, Preflight, RequestLimits,
+};
 use crate::service::mcp::McpService;
+use crate::service::performance::{self, ResponsePerformance};
+use crate::service::pricing::PriceTable;
+use crate::service::middleware::{MessagePipeline, MiddlewareContext, MiddlewareOptions};
+use crate::service::routing::{
+    budget_remaining, get_routing_table, set_alias, set_budget_remaining, RouteDecision, RoutingContext,
+    ALIAS_METADATA_KEY, SERVED_BY_METADATA_KEY,
+};
+use crate::service::stream::{self, CostMeter, EventEncoder, StreamEvent};
+use crate::service::translation::{LanguagePreference, TranslationMiddleware, LANGUAGE_METADATA_KEY};
+use crate::service::unfurl::get_unfurler;
+use crate::sync::read::{get_read_markers, ReadMarker};
+use crate::utils::cancellation::RequestContext;
+use crate::utils::clock;
+
+/// Conversation metadata key marking archived conversations
+pub const ARCHIVED_METADATA_KEY: &str = "archived";
 
 /// Service for managing chat interactions
 pub struct ChatService {
     /// MCP service for communication
     mcp_service: Arc<McpService>,
+    
+    /// Middleware chain applied to outgoing and incoming messages
+    pipeline: Arc<MessagePipeline>,
 }
 
 impl ChatService {
     /// Create a new chat service
     pub fn new(mcp_service: Arc<McpService>) -> Self {
-        Self { mcp_service }
+        let pipeline = Arc::new(MessagePipeline::new());
+        // Translation comes first so every other middleware sees the model's language
+        pipeline.register(
+            Arc::new(TranslationMiddleware::default()),
+            MiddlewareOptions {
+                priority: 5,
+                required: false,
+            },
+        );
+        // Experiment templates wrap the prompt before other middlewares see it
+        pipeline.register(
+            Arc::new(ExperimentMiddleware),
+            MiddlewareOptions {
+                priority: 10,
+                required: false,
+            },
+        );
+        // Content filters run last so they see the final response text
+        pipeline.register(
+            Arc::new(ReplacementFilter),
+            MiddlewareOptions {
+                priority: 900,
+                required: false,
+            },
+        );
+        pipeline.register(
+            Arc::new(ProfanityFilter),
+            MiddlewareOptions {
+                priority: 910,
+                required: false,
+            },
+        );
+        pipeline.register(
+            Arc::new(SecretFilter),
+            MiddlewareOptions {
+                priority: 920,
+                required: false,
+            },
+        );
+        
+        Self { mcp_service, pipeline }
+    }
+    
+    /// Get the middleware pipeline for registering hooks
+    pub fn pipeline(&self) -> Arc<MessagePipeline> {
+        self.pipeline.clone()
     }
     
     /// Create a new conversation
@@ -29,7 +117,23 @@ impl ChatService {
             }
         };
         
-        self.mcp_service.create_conversation(title, &model).await
+        let conversation = self.mcp_service.create_conversation(title, &model).await?;
+        
+        // Enroll in the running prompt experiment, if any
+        if let Some(variant) = get_experiment_store().enroll(&conversation.id) {
+            debug!("Conversation {} enrolled in variant {}", conversation.id, variant.name);
+            if let Some(system_prompt) = &variant.system_prompt {
+                self.set_system_message(&conversation.id, system_prompt).await?;
+            }
+        }
+        
+        get_event_bus().emit(
+            Topic::Conversation,
+            names::CONVERSATION_CREATED,
+            serde_json::json!({ "conversation_id": conversation.id }),
+        );
+        
+        Ok(conversation)
     }
     
     /// Get a conversation by ID
@@ -37,23 +141,847 @@ impl ChatService {
         self.mcp_service.get_conversation(id).await
     }
     
-    /// List all conversations
+    /// List all conversations, with their unread counts
     pub async fn list_conversations(&self) -> McpResult<Vec<Conversation>> {
-        Ok(self.mcp_service.active_conversations().await)
+        let markers = get_read_markers();
+        let mut conversations = self.mcp_service.active_conversations().await;
+        for conversation in &mut conversations {
+            conversation.unread = markers.unread_count(conversation);
+        }
+        Ok(conversations)
+    }
+    
+    /// Mark a conversation read up to its last message
+    pub async fn mark_read(&self, conversation_id: &str) -> McpResult<Option<ReadMarker>> {
+        let conversation = self.get_conversation(conversation_id).await?;
+        get_read_markers().mark_read(&conversation)
+    }
+    
+    /// Unsent draft of a conversation
+    pub async fn get_draft(&self, conversation_id: &str) -> McpResult<Option<Draft>> {
+        self.mcp_service.load_draft(conversation_id).await
+    }
+    
+    /// Save the unsent input of a conversation; blank text deletes the draft
+    pub async fn set_draft(&self, conversation_id: &str, text: &str) -> McpResult<Option<Draft>> {
+        if text.trim().is_empty() {
+            self.mcp_service.delete_draft(conversation_id).await?;
+            Self::emit_draft_changed(conversation_id);
+            return Ok(None);
+        }
+        
+        let draft = Draft {
+            conversation_id: conversation_id.to_string(),
+            text: text.to_string(),
+            updated_at: clock::now(),
+        };
+        self.mcp_service.save_draft(&draft).await?;
+        Self::emit_draft_changed(conversation_id);
+        Ok(Some(draft))
+    }
+    
+    /// Apply a draft synced from another device; it replaces the local one
+    /// only if it is newer. Returns whether it was applied.
+    pub async fn apply_draft(&self, draft: &Draft) -> McpResult<bool> {
+        let applied = self.mcp_service.apply_draft(draft).await?;
+        if applied {
+            Self::emit_draft_changed(&draft.conversation_id);
+        }
+        Ok(applied)
+    }
+    
+    /// Tell the UIs that a draft changed
+    pub fn emit_draft_changed(conversation_id: &str) {
+        get_event_bus().emit(
+            Topic::Conversation,
+            names::DRAFT_CHANGED,
+            serde_json::json!({ "conversation_id": conversation_id }),
+        );
+    }
+    
+    /// Delete the draft of a conversation once its message was sent
+    async fn clear_sent_draft(&self, conversation_id: &str) {
+        if let Err(e) = self.mcp_service.delete_draft(conversation_id).await {
+            warn!("Failed to clear the draft of {}: {}", conversation_id, e);
+        }
     }
     
-    /// Delete a conversation
+    /// Update a stored conversation (title, metadata or messages)
+    pub async fn update_conversation(&self, conversation: Conversation) -> McpResult<()> {
+        self.mcp_service.update_conversation(conversation).await
+    }
+    
+    /// Delete a conversation by moving it to the trash
     pub async fn delete_conversation(&self, id: &str) -> McpResult<()> {
-        self.mcp_service.delete_conversation(id).await
+        // The conversation goes to the trash; attachments are removed when it is purged
+        self.mcp_service.delete_conversation(id).await?;
+        get_event_bus().emit(
+            Topic::Conversation,
+            names::CONVERSATION_DELETED,
+            serde_json::json!({ "conversation_id": id }),
+        );
+        
+        Ok(())
+    }
+    
+    /// Conversations in the trash, most recently deleted first
+    pub async fn list_trashed(&self) -> McpResult<Vec<TrashedConversation>> {
+        self.mcp_service.list_trashed().await
+    }
+    
+    /// Move a conversation out of the trash
+    pub async fn restore_conversation(&self, id: &str) -> McpResult<Conversation> {
+        let conversation = self.mcp_service.restore_conversation(id).await?;
+        get_event_bus().emit(
+            Topic::Conversation,
+            names::CONVERSATION_RESTORED,
+            serde_json::json!({ "conversation_id": id }),
+        );
+        Ok(conversation)
+    }
+    
+    /// Permanently delete a trashed conversation and its attachments
+    pub async fn purge_conversation(&self, id: &str) -> McpResult<()> {
+        self.mcp_service.purge_conversation(id).await?;
+        get_read_markers().remove(id)?;
+        get_environments().clear(&Scope::Conversation(id.to_string()))?;
+        get_event_bus().emit(
+            Topic::Conversation,
+            names::CONVERSATION_PURGED,
+            serde_json::json!({ "conversation_id": id }),
+        );
+        Ok(())
+    }
+    
+    /// Permanently delete everything in the trash; returns how many were purged
+    pub async fn empty_trash(&self) -> McpResult<usize> {
+        let purged = self.mcp_service.empty_trash().await?;
+        if purged > 0 {
+            get_event_bus().emit(
+                Topic::Conversation,
+                names::CONVERSATION_PURGED,
+                serde_json::json!({ "count": purged }),
+            );
+        }
+        Ok(purged)
+    }
+    
+    /// Archive or unarchive conversations as one undoable operation.
+    /// Returns how many changed.
+    pub async fn archive_conversations(&self, ids: &[String], archived: bool) -> McpResult<usize> {
+        let mut changed = Vec::new();
+        for id in ids {
+            let mut conversation = self.mcp_service.get_conversation(id).await?;
+            if Self::is_archived(&conversation) == archived {
+                continue;
+            }
+            if let Some(metadata) = conversation.metadata.as_object_mut() {
+                metadata.insert(ARCHIVED_METADATA_KEY.to_string(), serde_json::json!(archived));
+            }
+            changed.push(conversation);
+        }
+        if changed.is_empty() {
+            return Ok(0);
+        }
+        
+        let count = changed.len();
+        let verb = if archived { "Archive" } else { "Unarchive" };
+        self.mcp_service
+            .update_conversations_journaled(
+                OperationKind::ArchiveConversations,
+                &format!("{} {} conversation(s)", verb, count),
+                changed,
+            )
+            .await?;
+        Ok(count)
+    }
+    
+    /// Whether a conversation is archived
+    pub fn is_archived(conversation: &Conversation) -> bool {
+        conversation
+            .metadata
+            .get(ARCHIVED_METADATA_KEY)
+            .and_then(|a| a.as_bool())
+            .unwrap_or(false)
+    }
+    
+    /// Replace the text of a message as an undoable operation; the text it
+    /// replaces is kept in the message's edit history
+    pub async fn edit_message(&self, conversation_id: &str, message_id: &str, text: &str) -> McpResult<Message> {
+        let mut conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        let message = conversation
+            .messages
+            .iter_mut()
+            .find(|m| m.id == message_id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("Message {} not found", message_id)))?;
+        
+        if !message.edit_text(text) {
+            return Ok(message.clone());
+        }
+        let edited = message.clone();
+        
+        self.mcp_service
+            .update_conversations_journaled(
+                OperationKind::EditMessage,
+                &format!("Edit message in '{}'", conversation.title),
+                vec![conversation],
+            )
+            .await?;
+        Ok(edited)
+    }
+    
+    /// Earlier versions of a message, oldest first
+    pub async fn get_message_history(&self, message_id: &str) -> McpResult<Vec<MessageVersion>> {
+        self.mcp_service
+            .active_conversations()
+            .await
+            .iter()
+            .flat_map(|c| c.messages.iter())
+            .find(|m| m.id == message_id)
+            .map(Message::edit_history)
+            .ok_or_else(|| McpError::InvalidRequest(format!("Message {} not found", message_id)))
+    }
+    
+    /// Undo the latest deletion, archive or edit
+    pub async fn undo(&self) -> McpResult<Option<JournalEntry>> {
+        let entry = self.mcp_service.undo().await?;
+        if let Some(entry) = &entry {
+            get_event_bus().emit(
+                Topic::Conversation,
+                names::OPERATION_UNDONE,
+                serde_json::json!({ "entry_id": entry.id, "description": entry.description }),
+            );
+        }
+        Ok(entry)
+    }
+    
+    /// Redo the most recently undone operation
+    pub async fn redo(&self) -> McpResult<Option<JournalEntry>> {
+        let entry = self.mcp_service.redo().await?;
+        if let Some(entry) = &entry {
+            get_event_bus().emit(
+                Topic::Conversation,
+                names::OPERATION_REDONE,
+                serde_json::json!({ "entry_id": entry.id, "description": entry.description }),
+            );
+        }
+        Ok(entry)
+    }
+    
+    /// Use a model or alias for a conversation; aliases are resolved at send time
+    pub async fn set_conversation_model(&self, conversation_id: &str, model_name: &str) -> McpResult<()> {
+        let mut conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        
+        if get_routing_table().read().unwrap().is_alias(model_name) {
+            set_alias(&mut conversation.metadata, model_name);
+        } else {
+            let model = self
+                .mcp_service
+                .available_models()
+                .await
+                .into_iter()
+                .find(|m| m.id == model_name || m.name == model_name)
+                .ok_or_else(|| McpError::InvalidRequest(format!("Unknown model '{}'", model_name)))?;
+            conversation.model = model;
+            if let Some(metadata) = conversation.metadata.as_object_mut() {
+                metadata.remove(ALIAS_METADATA_KEY);
+            }
+        }
+        
+        self.mcp_service.update_conversation(conversation).await
+    }
+    
+    /// Translate a conversation between the user's language and the model's,
+    /// or stop translating it with `None`
+    pub async fn set_conversation_language(
+        &self,
+        conversation_id: &str,
+        preference: Option<LanguagePreference>,
+    ) -> McpResult<()> {
+        let mut conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        match preference {
+            Some(preference) => {
+                conversation.metadata[LANGUAGE_METADATA_KEY] = serde_json::to_value(preference.validated()?)?;
+            }
+            None => {
+                if let Some(metadata) = conversation.metadata.as_object_mut() {
+                    metadata.remove(LANGUAGE_METADATA_KEY);
+                }
+            }
+        }
+        self.mcp_service.update_conversation(conversation).await
+    }
+    
+    /// Tell routing how much of the monthly budget is left, from what this
+    /// month's replies cost
+    async fn refresh_budget(&self) {
+        let (budget, currency) = {
+            let settings = get_settings();
+            let settings = settings.lock().unwrap();
+            (settings.cost.monthly_budget, settings.cost.currency.clone())
+        };
+        let remaining = match budget {
+            Some(budget) => {
+                let conversations = self.mcp_service.active_conversations().await;
+                Some(budget - analytics::month_spend(&conversations, clock::now(), &PriceTable::new(), &currency))
+            }
+            None => None,
+        };
+        set_budget_remaining(remaining);
+    }
+    
+    /// Resolve the conversation's alias, if it uses one, and switch it to the chosen model
+    async fn route(&self, conversation_id: &str, message: &Message) -> McpResult<Option<RouteDecision>> {
+        let mut conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        let table = get_routing_table().read().unwrap().clone();
+        
+        let alias = match conversation.metadata.get(ALIAS_METADATA_KEY).and_then(|a| a.as_str()) {
+            Some(alias) => alias.to_string(),
+            None if table.is_alias(&conversation.model.id) => conversation.model.id.clone(),
+            None => return Ok(None),
+        };
+        
+        self.refresh_budget().await;
+        let models = self.mcp_service.available_models().await;
+        // Rough estimate: about four characters per token
+        let prompt_chars: usize = conversation
+            .messages
+            .iter()
+            .chain(std::iter::once(message))
+            .map(|m| m.text().chars().count())
+            .sum();
+        let ctx = RoutingContext {
+            online: !matches!(
+                self.mcp_service.connection_status(),
+                ConnectionStatus::Error(_) | ConnectionStatus::AuthFailed
+            ),
+            prompt_tokens: prompt_chars / 4,
+            budget_remaining: budget_remaining(),
+            available_models: models.iter().map(|m| m.id.clone()).collect(),
+        };
+        
+        let decision = table.resolve(&alias, &ctx)?;
+        if conversation.model.id != decision.model {
+            conversation.model = models
+                .into_iter()
+                .find(|m| m.id == decision.model)
+                .unwrap_or_else(|| Model {
+                    id: decision.model.clone(),
+                    name: decision.model.clone(),
+                    ..conversation.model.clone()
+                });
+            set_alias(&mut conversation.metadata, &alias);
+            self.mcp_service.update_conversation(conversation).await?;
+            get_event_bus().emit(
+                Topic::Model,
+                names::MODEL_ROUTED,
+                serde_json::json!({
+                    "conversation_id": conversation_id,
+                    "alias": decision.alias,
+                    "model": decision.model,
+                    "target_index": decision.target_index,
+                }),
+            );
+        }
+        
+        info!("Routing alias {} to {}", decision.alias, decision.model);
+        Ok(Some(decision))
+    }
+    
+    /// Record which concrete model served a response
+    fn mark_served_by(message: &mut Message, decision: &RouteDecision) {
+        message.metadata.get_or_insert_with(Default::default).insert(
+            SERVED_BY_METADATA_KEY.to_string(),
+            serde_json::json!({ "model": decision.model, "alias": decision.alias }),
+        );
+    }
+    
+    /// Model a message goes to, and the messages sent right before it that got
+    /// no reply
+    async fn attempt(&self, conversation_id: &str) -> McpResult<(Model, u32)> {
+        let conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        let retries = performance::unanswered(&conversation);
+        Ok((conversation.model, retries))
+    }
+    
+    /// Middleware context for a conversation, carrying its workspace
+    async fn middleware_context(&self, conversation_id: &str) -> MiddlewareContext {
+        let mut ctx = MiddlewareContext::new(conversation_id);
+        if let Ok(conversation) = self.mcp_service.get_conversation(conversation_id).await {
+            if let Some(workspace) = conversation.metadata.get(WORKSPACE_CONTEXT_KEY) {
+                ctx.values.insert(WORKSPACE_CONTEXT_KEY.to_string(), workspace.clone());
+            }
+            if let Some(language) = conversation.metadata.get(LANGUAGE_METADATA_KEY) {
+                ctx.values.insert(LANGUAGE_METADATA_KEY.to_string(), language.clone());
+            }
+        }
+        ctx
+    }
+    
+    /// Replace the stored copy of a response with its post-processed version.
+    ///
+    /// Streamed replies are stored under a different ID than their chunks, so
+    /// the last assistant message is used when the ID isn't found.
+    async fn store_processed(mcp_service: &McpService, conversation_id: &str, response: &Message) -> McpResult<()> {
+        let mut conversation = mcp_service.get_conversation(conversation_id).await?;
+        let stored = match conversation.messages.iter().position(|m| m.id == response.id) {
+            Some(index) => Some(index),
+            None => conversation
+                .messages
+                .iter()
+                .rposition(|m| m.role == MessageRole::Assistant),
+        };
+        let Some(index) = stored else {
+            return Ok(());
+        };
+        
+        let message = &mut conversation.messages[index];
+        if message.content == response.content && message.metadata == response.metadata {
+            return Ok(());
+        }
+        message.content = response.content.clone();
+        message.metadata = response.metadata.clone();
+        mcp_service.update_conversation(conversation).await
+    }
+    
+    /// Workspace a conversation belongs to
+    fn workspace_of(conversation: &Conversation) -> Option<String> {
+        conversation
+            .metadata
+            .get(WORKSPACE_CONTEXT_KEY)
+            .and_then(|w| w.as_str())
+            .map(str::to_string)
+    }
+    
+    /// Workspace of a conversation, if it belongs to one
+    pub async fn workspace(&self, conversation_id: &str) -> McpResult<Option<String>> {
+        let conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        Ok(Self::workspace_of(&conversation))
+    }
+    
+    /// Environment variables a conversation sees, its workspace's with its own on top
+    pub async fn environment(&self, conversation_id: &str) -> McpResult<Environment> {
+        let conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        get_environments().for_conversation(&conversation)
+    }
+    
+    /// Remember the facts in a text for later conversations in the
+    /// conversation's workspace
+    pub async fn remember(&self, conversation_id: &str, text: &str) -> McpResult<Vec<MemoryNote>> {
+        let conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        let workspace = Self::workspace_of(&conversation);
+        get_memory_store().remember(workspace.as_deref(), text, Some(conversation_id))
+    }
+    
+    /// Put the notes relevant to the first message of a new conversation in
+    /// front of it, as a system message
+    async fn recall_memories(&self, conversation_id: &str, content: &str) -> McpResult<()> {
+        let mut conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        let started = conversation.messages.iter().any(|m| m.role != MessageRole::System);
+        if started || conversation.metadata.get(MEMORIES_METADATA_KEY).is_some() {
+            return Ok(());
+        }
+        
+        let workspace = Self::workspace_of(&conversation);
+        let recalled = get_memory_store().recall(workspace.as_deref(), content, RECALL_LIMIT);
+        if recalled.is_empty() {
+            return Ok(());
+        }
+        
+        debug!("Recalled {} memories for {}", recalled.len(), conversation_id);
+        let ids: Vec<&str> = recalled.iter().map(|r| r.note.id.as_str()).collect();
+        conversation.metadata[MEMORIES_METADATA_KEY] = serde_json::json!(ids);
+        let after_system = conversation
+            .messages
+            .iter()
+            .position(|m| m.role != MessageRole::System)
+            .unwrap_or(conversation.messages.len());
+        conversation
+            .messages
+            .insert(after_system, Message::system(memory::format_context(&recalled)));
+        self.mcp_service.update_conversation(conversation).await
+    }
+    
+    /// Set the knowledge bases searched for every message of a conversation;
+    /// none stops searching
+    pub async fn set_knowledge_bases(&self, conversation_id: &str, names: &[String]) -> McpResult<()> {
+        let store = get_knowledge_bases();
+        for name in names {
+            store.get(name)?;
+        }
+        
+        let mut conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        if names.is_empty() {
+            if let Some(metadata) = conversation.metadata.as_object_mut() {
+                metadata.remove(KNOWLEDGE_BASES_METADATA_KEY);
+            }
+        } else {
+            conversation.metadata[KNOWLEDGE_BASES_METADATA_KEY] = serde_json::json!(names);
+        }
+        self.mcp_service.update_conversation(conversation).await
+    }
+    
+    /// Put the passages of the conversation's knowledge bases found for a
+    /// message in front of it, in place of those found for the last one
+    async fn add_sources(&self, conversation_id: &str, content: &str) -> McpResult<Vec<Source>> {
+        let mut conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        let names = citations::knowledge_bases(&conversation.metadata);
+        let had_sources = conversation.messages.iter().any(citations::is_sources_message);
+        if names.is_empty() && !had_sources {
+            return Ok(Vec::new());
+        }
+        
+        conversation.messages.retain(|m| !citations::is_sources_message(m));
+        let sources = citations::find_sources(&get_knowledge_bases(), &names, content);
+        if !sources.is_empty() {
+            debug!("Found {} passages for {}", sources.len(), conversation_id);
+            conversation.messages.push(citations::sources_message(&sources));
+        }
+        self.mcp_service.update_conversation(conversation).await?;
+        Ok(sources)
+    }
+    
+    /// Keep which passages a reply's statements are taken from in its metadata
+    fn add_citations(reply: &mut Message, sources: &[Source]) {
+        if let Some(citations) = citations::cite(&reply.text(), sources) {
+            citations.attach(reply);
+        }
+    }
+    
+    /// Passages found for a message, or none if searching failed
+    async fn sources_for(&self, conversation_id: &str, content: &str) -> Vec<Source> {
+        self.add_sources(conversation_id, content).await.unwrap_or_else(|e| {
+            warn!("Failed to search knowledge bases for {}: {}", conversation_id, e);
+            Vec::new()
+        })
+    }
+    
+    /// What the model of a conversation can do, with the user's corrections
+    pub async fn capabilities(&self, conversation_id: &str) -> McpResult<ModelCapabilities> {
+        let conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        Ok(CapabilityTable::new().for_model(&conversation.model))
+    }
+    
+    /// Offer tools to the model of a conversation; none stops offering them.
+    /// Models without native tools get them described in the prompt.
+    pub async fn offer_tools(&self, conversation_id: &str, tools: &[Tool]) -> McpResult<()> {
+        let mut conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        if tools.is_empty() {
+            if let Some(metadata) = conversation.metadata.as_object_mut() {
+                metadata.remove(TOOLS_METADATA_KEY);
+            }
+        } else {
+            conversation.metadata[TOOLS_METADATA_KEY] = serde_json::to_value(tools)?;
+        }
+        self.mcp_service.update_conversation(conversation).await
+    }
+    
+    /// Ask for replies in JSON in a conversation, or stop asking
+    pub async fn set_json_mode(&self, conversation_id: &str, enabled: bool) -> McpResult<()> {
+        let mut conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        if enabled {
+            conversation.metadata[JSON_MODE_METADATA_KEY] = serde_json::json!(true);
+        } else if let Some(metadata) = conversation.metadata.as_object_mut() {
+            metadata.remove(JSON_MODE_METADATA_KEY);
+        }
+        self.mcp_service.update_conversation(conversation).await
+    }
+    
+    /// Adapt a conversation with a new message to what its model can do
+    async fn negotiate(&self, conversation_id: &str, message: &Message) -> McpResult<Negotiation> {
+        let conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        let capabilities = CapabilityTable::new().for_model(&conversation.model);
+        let mut messages = conversation.messages;
+        messages.push(message.clone());
+        let negotiation = Negotiation::new(&capabilities, &conversation.metadata, messages);
+        if negotiation.is_degraded() {
+            let given_up: Vec<String> = negotiation
+                .degraded
+                .iter()
+                .map(|d| format!("{} ({})", d.feature, d.detail))
+                .collect();
+            debug!(
+                "{} can't do everything asked in {}: {}",
+                conversation.model.id,
+                conversation_id,
+                given_up.join(", ")
+            );
+        }
+        Ok(negotiation)
+    }
+    
+    /// Send a message to a model that can't stream, handing the whole reply
+    /// back as the only chunk
+    async fn send_unstreamed(
+        &self,
+        conversation_id: &str,
+        content: &str,
+    ) -> McpResult<mpsc::Receiver<McpResult<Message>>> {
+        let mut reply = self.send_message(conversation_id, content).await?;
+        Degradation::attach(&[Degradation::new(Feature::Streaming, "sent as a whole reply")], &mut reply);
+        Self::store_processed(&self.mcp_service, conversation_id, &reply).await?;
+        let (tx, rx) = mpsc::channel(1);
+        let _ = tx.try_send(Ok(reply));
+        Ok(rx)
+    }
+    
+    /// Check a request against the provider's limits before it goes out,
+    /// leaving the images of earlier messages out when splitting is allowed
+    fn check_limits(model: &Model, messages: &mut [Message], fit: Fit) -> McpResult<()> {
+        let store = get_attachment_store();
+        let limits = LimitsTable::new().for_provider(&model.provider);
+        if fit.split {
+            let left_out = leave_out_older_images(&store, &limits, messages);
+            if left_out > 0 {
+                debug!("Left {} earlier image(s) out of the request to {}", left_out, model.id);
+            }
+        }
+        Preflight::check_with(&store, &limits, messages).into_result().map(|_| ())
+    }
+    
+    /// Measure a message with attachments against the limits of the
+    /// conversation's provider without sending it
+    pub async fn preflight(
+        &self,
+        conversation_id: &str,
+        content: &str,
+        attachment_ids: &[String],
+    ) -> McpResult<Preflight> {
+        let conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        let limits = LimitsTable::new().for_provider(&conversation.model.provider);
+        let attachments = Self::load_attachments(attachment_ids)?;
+        let message = Self::attachment_message(content, &attachments)?;
+        let negotiation = self.negotiate(conversation_id, &message).await?;
+        Ok(Preflight::check(&limits, &negotiation.messages))
+    }
+    
+    /// Look attachments up by ID
+    fn load_attachments(attachment_ids: &[String]) -> McpResult<Vec<Attachment>> {
+        let store = get_attachment_store();
+        attachment_ids
+            .iter()
+            .map(|id| {
+                store
+                    .get(id)
+                    .ok_or_else(|| McpError::InvalidRequest(format!("Attachment {} not found", id)))
+            })
+            .collect()
+    }
+    
+    /// A user message with text files inlined and images as image parts
+    fn attachment_message(content: &str, attachments: &[Attachment]) -> McpResult<Message> {
+        let store = get_attachment_store();
+        let mut text = content.to_string();
+        let mut images = Vec::new();
+        for attachment in attachments {
+            if attachment.is_image() {
+                images.push(ContentType::Image {
+                    url: attachment.url(),
+                    alt_text: Some(attachment.file_name.clone()),
+                });
+            } else if attachment.text_preview.is_some() {
+                let file = store.read(&attachment.id)?;
+                text.push_str(&format!(
+                    "\n\n{}:\n```\n{}\n```",
+                    attachment.file_name,
+                    String::from_utf8_lossy(&file)
+                ));
+            } else {
+                return Err(McpError::InvalidRequest(format!(
+                    "{} can't be sent; only images and text files can",
+                    attachment.file_name
+                )));
+            }
+        }
+        let mut message = Message::user(text);
+        message.content.parts.extend(images);
+        Ok(message)
+    }
+    
+    /// Group images into batches that each fit in a request next to the text
+    fn batch_images(attachments: Vec<Attachment>, limits: &RequestLimits, text_bytes: u64) -> Vec<Vec<Attachment>> {
+        let budget = limits.max_request_bytes.saturating_sub(text_bytes);
+        let mut batches: Vec<Vec<Attachment>> = Vec::new();
+        let mut batch_bytes = 0;
+        for attachment in attachments {
+            let bytes = encoded_size(attachment.size);
+            let full = batches
+                .last()
+                .is_none_or(|b| b.len() >= limits.max_images.max(1) || batch_bytes + bytes > budget);
+            if full {
+                batches.push(Vec::new());
+                batch_bytes = 0;
+            }
+            batch_bytes += bytes;
+            batches.last_mut().unwrap().push(attachment);
+        }
+        batches
+    }
+    
+    /// Send a message with attachments. Text files are inlined and images
+    /// sent as images; with `fit`, images over the provider's limits are
+    /// downscaled and images that don't fit in one request are sent over
+    /// several, the text going with the last. Returns every reply.
+    pub async fn send_message_with_attachments(
+        &self,
+        conversation_id: &str,
+        content: &str,
+        attachment_ids: &[String],
+        fit: Fit,
+    ) -> McpResult<Vec<Message>> {
+        let conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        let limits = LimitsTable::new().for_provider(&conversation.model.provider);
+        let store = get_attachment_store();
+        let mut attachments = Self::load_attachments(attachment_ids)?;
+        
+        if fit.downscale {
+            for attachment in attachments.iter_mut().filter(|a| a.is_image()) {
+                let size = measure_image(&store, &attachment.url());
+                let too_wide = size
+                    .dimensions
+                    .is_some_and(|(w, h)| w.max(h) > limits.max_image_dimension);
+                if attachment.size > limits.max_image_bytes || too_wide {
+                    *attachment = store.downscale(&attachment.id, limits.max_image_dimension, limits.max_image_bytes)?;
+                }
+            }
+        }
+        
+        let (images, others): (Vec<Attachment>, Vec<Attachment>) = attachments.into_iter().partition(|a| a.is_image());
+        let text_bytes = Self::attachment_message(content, &others)?.text().len() as u64;
+        let batches = if fit.split && !images.is_empty() {
+            Self::batch_images(images, &limits, text_bytes)
+        } else {
+            vec![images]
+        };
+        
+        let total: usize = batches.iter().map(Vec::len).sum();
+        let mut first = 1;
+        let mut replies = Vec::new();
+        for (i, batch) in batches.iter().enumerate() {
+            let last = first + batch.len().max(1) - 1;
+            let mut message = if i + 1 == batches.len() {
+                Self::attachment_message(content, &others)?
+            } else {
+                Message::user(format!(
+                    "Images {}–{} of {}; more follow, so wait for the rest before answering.",
+                    first, last, total
+                ))
+            };
+            if batches.len() > 1 && i + 1 == batches.len() {
+                message
+                    .content
+                    .parts
+                    .insert(0, ContentType::Text { text: format!("Images {}–{} of {}.\n\n", first, last, total) });
+            }
+            message.content.parts.extend(batch.iter().map(|a| ContentType::Image {
+                url: a.url(),
+                alt_text: Some(a.file_name.clone()),
+            }));
+            for attachment in batch.iter().chain(others.iter()) {
+                store.attach_to_message(&attachment.id, &message.id)?;
+            }
+            replies.push(self.send_prepared(conversation_id, message, fit).await?);
+            first = last + 1;
+        }
+        Ok(replies)
     }
     
     /// Send a message in a conversation
     pub async fn send_message(&self, conversation_id: &str, content: &str) -> McpResult<Message> {
-        // Create user message
-        let message = Message::user(content);
+        self.send_prepared(conversation_id, Message::user(content), Fit::default()).await
+    }
+    
+    /// Send a user message, checking the request against the provider's
+    /// limits with the fixes allowed by `fit`
+    async fn send_prepared(&self, conversation_id: &str, mut message: Message, fit: Fit) -> McpResult<Message> {
+        let content = message.text();
+        if let Err(e) = self.recall_memories(conversation_id, &content).await {
+            warn!("Failed to recall memories for {}: {}", conversation_id, e);
+        }
+        let sources = self.sources_for(conversation_id, &content).await;
+        
+        let mut ctx = self.middleware_context(conversation_id).await;
+        self.pipeline.pre_send(&mut ctx, &mut message).await?;
+        let route = self.route(conversation_id, &message).await?;
+        let (model, retries) = self.attempt(conversation_id).await?;
+        let mut negotiation = self.negotiate(conversation_id, &message).await?;
+        Self::check_limits(&model, &mut negotiation.messages, fit)?;
         
         // Send via MCP service
-        self.mcp_service.send_message(conversation_id, message).await
+        let message_id = message.id.clone();
+        get_unfurler().unfurl_message(conversation_id, &message);
+        let started = Instant::now();
+        let sent = std::mem::take(&mut negotiation.messages);
+        let mut response = self.mcp_service.send_message_as(conversation_id, message, sent).await?;
+        let elapsed = started.elapsed();
+        negotiation.finish(&mut response);
+        self.clear_sent_draft(conversation_id).await;
+        if let Some(decision) = &route {
+            Self::mark_served_by(&mut response, decision);
+        }
+        ResponsePerformance::new(&model, elapsed, elapsed, retries).attach(&mut response);
+        let bus = get_event_bus();
+        bus.emit(
+            Topic::Conversation,
+            names::MESSAGE_SENT,
+            serde_json::json!({ "conversation_id": conversation_id, "message_id": message_id }),
+        );
+        
+        self.pipeline.post_receive(&mut ctx, &mut response).await?;
+        Self::add_citations(&mut response, &sources);
+        Self::store_processed(&self.mcp_service, conversation_id, &response).await?;
+        bus.emit(
+            Topic::Conversation,
+            names::MESSAGE_RECEIVED,
+            serde_json::json!({ "conversation_id": conversation_id, "message_id": response.id }),
+        );
+        get_unfurler().unfurl_message(conversation_id, &response);
+        
+        Ok(response)
+    }
+    
+    /// Send a message, aborting when the context is canceled or its deadline passes
+    pub async fn send_message_with_context(
+        &self,
+        conversation_id: &str,
+        content: &str,
+        ctx: &RequestContext,
+    ) -> McpResult<Message> {
+        ctx.run(self.send_message(conversation_id, content)).await
+    }
+    
+    /// Hand the results of a reply's tool calls back to the model and get its
+    /// next reply
+    pub async fn send_tool_results(&self, conversation_id: &str, results: &[ToolResult]) -> McpResult<Message> {
+        let message = Message::tool_results(results);
+        let mut ctx = self.middleware_context(conversation_id).await;
+        let (model, retries) = self.attempt(conversation_id).await?;
+        let mut negotiation = self.negotiate(conversation_id, &message).await?;
+        Self::check_limits(&model, &mut negotiation.messages, Fit::default())?;
+        
+        let message_id = message.id.clone();
+        let started = Instant::now();
+        let sent = std::mem::take(&mut negotiation.messages);
+        let mut response = self.mcp_service.send_message_as(conversation_id, message, sent).await?;
+        let elapsed = started.elapsed();
+        negotiation.finish(&mut response);
+        ResponsePerformance::new(&model, elapsed, elapsed, retries).attach(&mut response);
+        let bus = get_event_bus();
+        bus.emit(
+            Topic::Conversation,
+            names::MESSAGE_SENT,
+            serde_json::json!({ "conversation_id": conversation_id, "message_id": message_id }),
+        );
+        
+        self.pipeline.post_receive(&mut ctx, &mut response).await?;
+        Self::store_processed(&self.mcp_service, conversation_id, &response).await?;
+        bus.emit(
+            Topic::Conversation,
+            names::MESSAGE_RECEIVED,
+            serde_json::json!({ "conversation_id": conversation_id, "message_id": response.id }),
+        );
+        
+        Ok(response)
     }
     
     /// Send a message with streaming response
@@ -62,11 +990,149 @@ impl ChatService {
         conversation_id: &str,
         content: &str,
     ) -> McpResult<mpsc::Receiver<McpResult<Message>>> {
+        if !self.capabilities(conversation_id).await?.streaming {
+            return self.send_unstreamed(conversation_id, content).await;
+        }
+        if let Err(e) = self.recall_memories(conversation_id, content).await {
+            warn!("Failed to recall memories for {}: {}", conversation_id, e);
+        }
+        let sources = self.sources_for(conversation_id, content).await;
+        
         // Create user message
-        let message = Message::user(content);
+        let mut message = Message::user(content);
+        let mut ctx = self.middleware_context(conversation_id).await;
+        self.pipeline.pre_send(&mut ctx, &mut message).await?;
+        let route = self.route(conversation_id, &message).await?;
+        let (model, retries) = self.attempt(conversation_id).await?;
+        let mut negotiation = self.negotiate(conversation_id, &message).await?;
+        Self::check_limits(&model, &mut negotiation.messages, Fit::default())?;
         
         // Send via MCP service with streaming
-        self.mcp_service.stream_message(conversation_id, message).await
+        get_unfurler().unfurl_message(conversation_id, &message);
+        let started = Instant::now();
+        let sent = std::mem::take(&mut negotiation.messages);
+        let mut upstream = self.mcp_service.stream_message_as(conversation_id, message, sent).await?;
+        self.clear_sent_draft(conversation_id).await;
+        let (tx, rx) = mpsc::channel(32);
+        let pipeline = self.pipeline.clone();
+        let mcp_service = self.mcp_service.clone();
+        let conversation_id = conversation_id.to_string();
+        
+        // Run every chunk through the pipeline before handing it to the caller
+        tokio::spawn(async move {
+            let mut reply: Option<Message> = None;
+            let mut completed = true;
+            let mut first_token = None;
+            while let Some(chunk) = upstream.recv().await {
+                let chunk = match chunk {
+                    Ok(mut chunk) => {
+                        if let Some(decision) = &route {
+                            Self::mark_served_by(&mut chunk, decision);
+                        }
+                        if first_token.is_none() && !chunk.text().is_empty() {
+                            first_token = Some(started.elapsed());
+                        }
+                        // The stored reply is built from the unprocessed chunks and
+                        // goes through post-receive as a whole once complete
+                        match reply.as_mut() {
+                            Some(reply) => Self::append_chunk(reply, &chunk),
+                            None => reply = Some(chunk.clone()),
+                        }
+                        pipeline.on_stream_chunk(&mut ctx, &mut chunk).await.map(|_| chunk)
+                    }
+                    Err(e) => {
+                        // Text held back by the filters comes before the error
+                        if !Self::flush_stream(&pipeline, &mut ctx, &tx).await {
+                            completed = false;
+                            break;
+                        }
+                        Err(e)
+                    }
+                };
+                if tx.send(chunk).await.is_err() {
+                    completed = false;
+                    break;
+                }
+            }
+            if completed && !Self::flush_stream(&pipeline, &mut ctx, &tx).await {
+                completed = false;
+            }
+            let duration = started.elapsed();
+            
+            // Links are only complete once the reply is
+            if let Some(mut reply) = reply {
+                // The upstream closes only after the raw reply was stored
+                if completed {
+                    negotiation.finish(&mut reply);
+                    // Same post-receive pass as unstreamed replies: translation,
+                    // then filters over the whole text
+                    match pipeline.post_receive(&mut ctx, &mut reply).await {
+                        Ok(()) => {
+                            Self::add_citations(&mut reply, &sources);
+                            ResponsePerformance::new(&model, first_token.unwrap_or(duration), duration, retries)
+                                .attach(&mut reply);
+                            if let Err(e) = Self::store_processed(&mcp_service, &conversation_id, &reply).await {
+                                warn!("Failed to store filtered reply: {}", e);
+                            }
+                        }
+                        Err(e) => warn!("Streamed reply rejected by middleware: {}", e),
+                    }
+                }
+                get_unfurler().unfurl_message(&conversation_id, &reply);
+            }
+        });
+        
+        Ok(rx)
+    }
+    
+    /// Add a streamed chunk to the reply being assembled. Consecutive text
+    /// stays in one part so filters see matches that span chunks.
+    fn append_chunk(reply: &mut Message, chunk: &Message) {
+        for part in &chunk.content.parts {
+            match (reply.content.parts.last_mut(), part) {
+                (Some(ContentType::Text { text }), ContentType::Text { text: more }) => text.push_str(more),
+                _ => reply.content.parts.push(part.clone()),
+            }
+        }
+    }
+    
+    /// Send the text middlewares held back from earlier chunks as a final
+    /// chunk. Returns false when the receiver is gone.
+    async fn flush_stream(
+        pipeline: &MessagePipeline,
+        ctx: &mut MiddlewareContext,
+        tx: &mpsc::Sender<McpResult<Message>>,
+    ) -> bool {
+        let mut chunk = Message::assistant("");
+        let chunk = pipeline.on_stream_end(ctx, &mut chunk).await.map(|_| chunk);
+        match chunk {
+            Ok(chunk) if chunk.text().is_empty() => true,
+            chunk => tx.send(chunk).await.is_ok(),
+        }
+    }
+    
+    /// Send a message and stream the reply as typed events: content blocks,
+    /// text deltas, tool calls, running usage and cost, and the whole reply
+    /// when done
+    pub async fn send_message_events(
+        &self,
+        conversation_id: &str,
+        content: &str,
+    ) -> McpResult<mpsc::Receiver<McpResult<StreamEvent>>> {
+        let conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        let prompt_tokens = conversation
+            .messages
+            .iter()
+            .map(|m| estimate::estimate_tokens(&m.text()))
+            .sum::<u64>()
+            + estimate::estimate_tokens(content);
+        let currency = get_settings().lock().unwrap().cost.currency.clone();
+        let prices = PriceTable::new();
+        let meter = CostMeter::new(prices.price_for_model(&conversation.model), &prices, &currency);
+        
+        let chunks = self.send_message_streaming(conversation_id, content).await?;
+        let encoder = EventEncoder::new().with_prompt_tokens(prompt_tokens).with_meter(meter);
+        Ok(stream::encode(chunks, encoder))
     }
     
     /// Set a system message for a conversation
@@ -104,6 +1170,82 @@ impl ChatService {
         self.mcp_service.update_conversation(conversation).await
     }
     
+    /// Add a user message to a conversation without asking for a reply
+    pub async fn append_message(&self, conversation_id: &str, content: &str) -> McpResult<Message> {
+        let mut conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        let message = Message::user(content);
+        conversation.add_message(message.clone());
+        self.mcp_service.update_conversation(conversation).await?;
+        
+        get_event_bus().emit(
+            Topic::Conversation,
+            names::MESSAGE_SENT,
+            serde_json::json!({ "conversation_id": conversation_id, "message_id": message.id }),
+        );
+        Ok(message)
+    }
+    
+    /// Projected tokens and cost of sending `draft` in a conversation, for
+    /// showing next to the send button before it is pressed
+    pub async fn estimate_cost(&self, conversation_id: &str, draft: &str) -> McpResult<CostEstimate> {
+        let conversation = self.mcp_service.get_conversation(conversation_id).await?;
+        let (max_tokens, cost) = {
+            let settings = get_settings();
+            let settings = settings.lock().unwrap();
+            (settings.model.max_tokens as u64, settings.cost.clone())
+        };
+        
+        Ok(estimate::estimate(&conversation, draft, &PriceTable::new(), max_tokens, &cost))
+    }
+    
+    /// Rate a message, or clear its rating with `None`
+    pub async fn rate_message(
+        &self,
+        message_id: &str,
+        rating: Option<Rating>,
+        comment: Option<String>,
+    ) -> McpResult<Message> {
+        let mut conversation = self
+            .mcp_service
+            .active_conversations()
+            .await
+            .into_iter()
+            .find(|c| c.messages.iter().any(|m| m.id == message_id))
+            .ok_or_else(|| McpError::InvalidRequest(format!("Message {} not found", message_id)))?;
+        
+        let message = conversation
+            .messages
+            .iter_mut()
+            .find(|m| m.id == message_id)
+            .unwrap();
+        message.feedback = rating.map(|rating| MessageFeedback {
+            rating,
+            comment,
+            rated_at: clock::now(),
+        });
+        let message = message.clone();
+        let conversation_id = conversation.id.clone();
+        
+        self.mcp_service.update_conversation(conversation).await?;
+        
+        // Ratings of enrolled conversations feed the experiment report
+        if let Err(e) = get_experiment_store().record_rating(&conversation_id, message_id, rating) {
+            warn!("Failed to record rating for experiment: {}", e);
+        }
+        
+        get_event_bus().emit(
+            Topic::Conversation,
+            names::MESSAGE_RATED,
+            serde_json::json!({
+                "conversation_id": conversation_id,
+                "message_id": message_id,
+                "feedback": message.feedback,
+            }),
+        );
+        
+        Ok(message)
+    }
+    
     /// Get available models
     pub async fn available_models(&self) -> McpResult<Vec<Model>> {
         Ok(self.mcp_service.available_models().await)
@@ -0,0 +1,291 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use super::batch::api_base_url;
+use crate::auth::{self, Authorization};
+use crate::config::{data_path, get_settings};
+use crate::error::McpError;
+use crate::events::{get_event_bus, names, Topic};
+use crate::protocol::inspector;
+
+const HEALTH_FILE: &str = "connection_health.json";
+
+/// API version sent with checks
+const API_VERSION: &str = "2023-06-01";
+
+/// How long a successful check is trusted
+const VALID_TTL_SECS: i64 = 10 * 60;
+
+/// How long a failed check is trusted before it is retried
+const FAILED_TTL_SECS: i64 = 60;
+
+/// Outcome of a credential check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialStatus {
+    /// The API accepted the key
+    Valid,
+    /// No API key is configured
+    Missing,
+    /// The API rejected the key
+    InvalidKey,
+    /// The account is out of credit or over its spend limit
+    QuotaExhausted,
+    /// The key works but requests are being rate limited
+    RateLimited,
+    /// The API couldn't be reached
+    Network,
+    /// The API answered with a server error or overload
+    ServiceError,
+}
+
+impl CredentialStatus {
+    /// Whether the API recognized the key, even if it can't be used right now
+    pub fn key_accepted(&self) -> bool {
+        matches!(
+            self,
+            CredentialStatus::Valid | CredentialStatus::RateLimited | CredentialStatus::QuotaExhausted
+        )
+    }
+
+    /// Connection health this status amounts to
+    pub fn health(&self) -> ConnectionHealth {
+        match self {
+            CredentialStatus::Valid => ConnectionHealth::Healthy,
+            CredentialStatus::RateLimited | CredentialStatus::ServiceError => ConnectionHealth::Degraded,
+            CredentialStatus::Network => ConnectionHealth::Offline,
+            CredentialStatus::Missing | CredentialStatus::InvalidKey | CredentialStatus::QuotaExhausted => {
+                ConnectionHealth::Unhealthy
+            }
+        }
+    }
+}
+
+/// Connection health shown in status bars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionHealth {
+    /// Not checked yet
+    Unknown,
+    /// Requests go through
+    Healthy,
+    /// Requests go through slowly or intermittently
+    Degraded,
+    /// Requests fail until the user acts (new key, more credit)
+    Unhealthy,
+    /// The API is unreachable
+    Offline,
+}
+
+/// Result of checking the API key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialCheck {
+    /// Outcome
+    pub status: CredentialStatus,
+
+    /// Details from the API or the network error
+    pub message: String,
+
+    /// When the check ran
+    pub checked_at: DateTime<Utc>,
+
+    /// Round trip of the check, when the API answered
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+
+    /// Organization the key belongs to, when the API says
+    #[serde(default)]
+    pub organization_id: Option<String>,
+}
+
+impl CredentialCheck {
+    fn new(status: CredentialStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+            checked_at: Utc::now(),
+            latency_ms: None,
+            organization_id: None,
+        }
+    }
+
+    /// Connection health this check amounts to
+    pub fn health(&self) -> ConnectionHealth {
+        self.status.health()
+    }
+
+    /// Whether the result is recent enough to reuse
+    pub fn is_fresh(&self) -> bool {
+        let ttl = if self.status == CredentialStatus::Valid {
+            VALID_TTL_SECS
+        } else {
+            FAILED_TTL_SECS
+        };
+        Utc::now() - self.checked_at < ChronoDuration::seconds(ttl)
+    }
+}
+
+/// Classify an API error response by its status code and error body
+pub fn classify(status: u16, body: &str) -> (CredentialStatus, String) {
+    let error = serde_json::from_str::<serde_json::Value>(body).ok();
+    let error_type = error
+        .as_ref()
+        .and_then(|e| e.pointer("/error/type"))
+        .and_then(|t| t.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let message = error
+        .as_ref()
+        .and_then(|e| e.pointer("/error/message"))
+        .and_then(|m| m.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("HTTP {}", status));
+    let lower = message.to_lowercase();
+
+    let kind = match (status, error_type.as_str()) {
+        (_, "billing_error") | (402, _) => CredentialStatus::QuotaExhausted,
+        // Running out of credit is reported as a bad request
+        (400, _) if lower.contains("credit balance") || lower.contains("spend limit") => {
+            CredentialStatus::QuotaExhausted
+        }
+        (401, _) | (403, _) | (_, "authentication_error") | (_, "permission_error") => CredentialStatus::InvalidKey,
+        (429, _) | (_, "rate_limit_error") => CredentialStatus::RateLimited,
+        _ => CredentialStatus::ServiceError,
+    };
+    (kind, message)
+}
+
+/// Check a key with a cheap authenticated call (listing models), without
+/// touching the cache
+pub async fn validate_key(api_url: &str, api_key: &str) -> CredentialCheck {
+    if api_key.trim().is_empty() {
+        return CredentialCheck::new(CredentialStatus::Missing, "No API key configured");
+    }
+    validate_authorization(api_url, &Authorization::ApiKey(api_key.to_string())).await
+}
+
+/// Check an API key or OAuth access token, without touching the cache
+pub async fn validate_authorization(api_url: &str, authorization: &Authorization) -> CredentialCheck {
+    let base_url = match api_base_url(api_url) {
+        Ok(url) => url,
+        Err(e) => return CredentialCheck::new(CredentialStatus::Network, e.to_string()),
+    };
+
+    let started = Instant::now();
+    let response = inspector::send(
+        authorization
+            .apply(reqwest::Client::new().get(format!("{}/v1/models?limit=1", base_url)))
+            .header("anthropic-version", API_VERSION)
+            .timeout(Duration::from_secs(10)),
+    )
+    .await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => return CredentialCheck::new(CredentialStatus::Network, e.to_string()),
+    };
+    let organization_id = response
+        .headers()
+        .get("anthropic-organization-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let status = response.status();
+
+    let mut check = if status.is_success() {
+        CredentialCheck::new(CredentialStatus::Valid, "API key accepted")
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        let (kind, message) = classify(status.as_u16(), &body);
+        CredentialCheck::new(kind, message)
+    };
+    check.latency_ms = Some(latency_ms);
+    check.organization_id = organization_id;
+    check
+}
+
+/// Last check, loaded from disk so the indicator survives restarts
+static LAST_CHECK: Lazy<RwLock<Option<CredentialCheck>>> = Lazy::new(|| {
+    let check = fs::read_to_string(data_path(HEALTH_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok());
+    RwLock::new(check)
+});
+
+/// Remember a check, persist it and announce status changes
+fn record(check: &CredentialCheck) {
+    let previous = LAST_CHECK.write().unwrap().replace(check.clone());
+    if let Err(e) = serde_json::to_string(check)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(data_path(HEALTH_FILE), json).map_err(|e| e.to_string()))
+    {
+        warn!("Failed to save connection health: {}", e);
+    }
+
+    let previous_health = previous.as_ref().map(|c| c.health()).unwrap_or(ConnectionHealth::Unknown);
+    if previous.map(|c| c.status) != Some(check.status) {
+        info!("Connection health is now {:?} ({:?})", check.health(), check.status);
+        get_event_bus().emit(
+            Topic::System,
+            names::CONNECTION_HEALTH_CHANGED,
+            serde_json::json!({
+                "health": check.health(),
+                "previous": previous_health,
+                "check": check,
+            }),
+        );
+    }
+}
+
+/// Check the configured API key, reusing a recent result
+pub async fn validate() -> CredentialCheck {
+    if let Some(check) = LAST_CHECK.read().unwrap().clone().filter(|c| c.is_fresh()) {
+        return check;
+    }
+    revalidate().await
+}
+
+/// Check the configured API key now, ignoring cached results
+pub async fn revalidate() -> CredentialCheck {
+    let api_url = get_settings().lock().unwrap().api.url.clone();
+    let check = match auth::authorization().await {
+        Ok(authorization) => validate_authorization(&api_url, &authorization).await,
+        // Refreshing an OAuth token needs the identity provider
+        Err(McpError::Connection(message)) => CredentialCheck::new(CredentialStatus::Network, message),
+        Err(e) => CredentialCheck::new(CredentialStatus::Missing, e.to_string()),
+    };
+    debug!("Credential check: {:?}", check.status);
+    record(&check);
+    check
+}
+
+/// Forget the cached result, e.g. after the key changed
+pub fn invalidate() {
+    if let Some(check) = LAST_CHECK.write().unwrap().as_mut() {
+        check.checked_at = DateTime::<Utc>::MIN_UTC;
+    }
+}
+
+/// Latest known connection health and the check behind it, without any
+/// network call
+pub fn connection_health() -> (ConnectionHealth, Option<CredentialCheck>) {
+    let check = LAST_CHECK.read().unwrap().clone();
+    (check.as_ref().map(|c| c.health()).unwrap_or(ConnectionHealth::Unknown), check)
+}
+
+/// Keep the health indicator current by revalidating whenever the cached
+/// result expires
+pub fn spawn_health_monitor(interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            validate().await;
+        }
+    })
+}
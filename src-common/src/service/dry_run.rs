@@ -0,0 +1,171 @@
+//! Dry runs of tool calls.
+//!
+//! With `tools.dry_run` on, tool calls are recorded instead of executed, and
+//! the model gets a description of what each would have done in place of its
+//! result. The record lets users audit what an agentic conversation would do
+//! before letting it act.
+
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::config::{data_path, get_settings};
+use crate::error::McpResult;
+use crate::events::{get_event_bus, names, Topic};
+use crate::models::{ToolCall, ToolResult};
+use crate::utils::clock;
+
+/// Most calls kept per conversation; older ones are dropped
+const MAX_CALLS_PER_CONVERSATION: usize = 200;
+
+/// A tool call recorded instead of executed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulatedCall {
+    /// ID of the tool call
+    pub call_id: String,
+
+    /// Conversation the call was made in
+    pub conversation_id: String,
+
+    /// Tool called
+    pub tool: String,
+
+    /// Arguments, with environment references filled in and secrets masked
+    pub arguments: serde_json::Value,
+
+    /// What the call would have done, one effect per entry
+    pub effects: Vec<String>,
+
+    /// When the call was recorded
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl SimulatedCall {
+    /// Result handed to the model in place of the real one
+    pub fn result(&self) -> ToolResult {
+        ToolResult::new(
+            self.call_id.clone(),
+            self.tool.clone(),
+            serde_json::json!({
+                "simulated": true,
+                "note": "Tool calls are dry runs; nothing was executed",
+                "would": self.effects,
+            }),
+        )
+    }
+}
+
+/// Whether tool calls are dry runs
+pub fn is_enabled() -> bool {
+    get_settings().lock().unwrap().tools.dry_run
+}
+
+/// Turn dry runs on or off
+pub fn set_enabled(enabled: bool) -> McpResult<()> {
+    let settings = get_settings();
+    let mut settings = settings.lock().unwrap();
+    settings.tools.dry_run = enabled;
+    settings.save()?;
+    info!("Tool dry runs {}", if enabled { "on" } else { "off" });
+    Ok(())
+}
+
+/// Tool calls recorded instead of executed, kept in a file
+pub struct DryRunLog {
+    path: PathBuf,
+    calls: Mutex<Vec<SimulatedCall>>,
+}
+
+impl DryRunLog {
+    /// Calls kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        let calls = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable dry run log {:?}: {}", path, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self {
+            path,
+            calls: Mutex::new(calls),
+        }
+    }
+
+    fn save(&self, calls: &[SimulatedCall]) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(calls)?)?;
+        Ok(())
+    }
+
+    /// Record a call with what it would have done
+    pub fn record(&self, conversation_id: &str, call: &ToolCall, effects: Vec<String>) -> McpResult<SimulatedCall> {
+        let simulated = SimulatedCall {
+            call_id: call.id.clone(),
+            conversation_id: conversation_id.to_string(),
+            tool: call.name.clone(),
+            arguments: call.arguments.clone(),
+            effects,
+            recorded_at: clock::now(),
+        };
+
+        let mut calls = self.calls.lock().unwrap();
+        calls.push(simulated.clone());
+        let kept = calls.iter().filter(|c| c.conversation_id == conversation_id).count();
+        if kept > MAX_CALLS_PER_CONVERSATION {
+            let mut excess = kept - MAX_CALLS_PER_CONVERSATION;
+            calls.retain(|c| {
+                let drop = excess > 0 && c.conversation_id == conversation_id;
+                if drop {
+                    excess -= 1;
+                }
+                !drop
+            });
+        }
+        self.save(&calls)?;
+        drop(calls);
+
+        get_event_bus().emit(
+            Topic::Conversation,
+            names::TOOL_CALL_SIMULATED,
+            serde_json::to_value(&simulated).unwrap_or_default(),
+        );
+        Ok(simulated)
+    }
+
+    /// Calls recorded in a conversation, oldest first
+    pub fn list(&self, conversation_id: &str) -> Vec<SimulatedCall> {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|c| c.conversation_id == conversation_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Forget the calls recorded in a conversation; returns how many there were
+    pub fn clear(&self, conversation_id: &str) -> McpResult<usize> {
+        let mut calls = self.calls.lock().unwrap();
+        let before = calls.len();
+        calls.retain(|c| c.conversation_id != conversation_id);
+        let removed = before - calls.len();
+        if removed > 0 {
+            self.save(&calls)?;
+        }
+        Ok(removed)
+    }
+}
+
+static DRY_RUN_LOG: Lazy<Arc<DryRunLog>> = Lazy::new(|| Arc::new(DryRunLog::at(data_path("dry_runs.json"))));
+
+/// Get the global dry run log
+pub fn get_dry_run_log() -> Arc<DryRunLog> {
+    DRY_RUN_LOG.clone()
+}
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::CostSettings;
+use crate::models::{Conversation, MessageRole};
+use crate::service::pricing::{format_amount, PriceTable, BASE_CURRENCY};
+
+/// Response length assumed for conversations without earlier responses
+const DEFAULT_RESPONSE_TOKENS: u64 = 500;
+
+/// Projected size and cost of sending a message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostEstimate {
+    /// Model the message goes to
+    pub model: String,
+
+    /// Prompt tokens, history included
+    pub input_tokens: u64,
+
+    /// Expected response tokens
+    pub output_tokens: u64,
+
+    /// Projected cost in `currency`; unknown without a price for the model
+    pub cost: Option<f64>,
+
+    /// Cost if the response uses every token allowed
+    pub max_cost: Option<f64>,
+
+    /// Currency of the costs
+    pub currency: String,
+
+    /// Short label for the send button, e.g. "≈ $0.03"
+    pub label: String,
+
+    /// Whether the cost is over the confirmation threshold
+    pub needs_confirmation: bool,
+}
+
+/// Rough token estimate (about four characters per token)
+pub fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+/// Short label for a cost, e.g. "≈ $0.03"
+pub fn format_cost(cost: Option<f64>, currency: &str) -> String {
+    match cost {
+        None => "≈ ?".to_string(),
+        Some(0.0) => "free".to_string(),
+        Some(cost) if cost < 0.01 => format!("< {}", format_amount(0.01, currency)),
+        Some(cost) => format!("≈ {}", format_amount(cost, currency)),
+    }
+}
+
+/// Estimate the cost of sending `draft` in a conversation. The response is
+/// expected to be as long as earlier responses, within `max_output_tokens`.
+/// Costs are in the configured currency, or US dollars when it has no
+/// exchange rate, and sends above the threshold need confirmation.
+pub fn estimate(
+    conversation: &Conversation,
+    draft: &str,
+    prices: &PriceTable,
+    max_output_tokens: u64,
+    settings: &CostSettings,
+) -> CostEstimate {
+    let history: u64 = conversation.messages.iter().map(|m| estimate_tokens(&m.text())).sum();
+    let input_tokens = history + estimate_tokens(draft);
+
+    let responses: Vec<u64> = conversation
+        .messages
+        .iter()
+        .filter(|m| m.role == MessageRole::Assistant)
+        .map(|m| estimate_tokens(&m.text()))
+        .collect();
+    let typical = match responses.len() {
+        0 => DEFAULT_RESPONSE_TOKENS,
+        n => responses.iter().sum::<u64>() / n as u64,
+    };
+    let output_tokens = typical.min(max_output_tokens);
+
+    let (currency, rate) = match prices.rate(&settings.currency) {
+        Some(rate) => (settings.currency.to_uppercase(), rate),
+        None => (BASE_CURRENCY.to_string(), 1.0),
+    };
+    let price = prices.price_for_model(&conversation.model);
+    let cost = price.map(|p| p.cost(input_tokens, output_tokens) * rate);
+
+    CostEstimate {
+        model: conversation.model.id.clone(),
+        input_tokens,
+        output_tokens,
+        cost,
+        max_cost: price.map(|p| p.cost(input_tokens, max_output_tokens) * rate),
+        label: format_cost(cost, &currency),
+        currency,
+        needs_confirmation: matches!((cost, settings.confirm_above), (Some(cost), Some(limit)) if cost > limit),
+    }
+}
@@ -0,0 +1,556 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+use log::{debug, info, warn};
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::models::Model;
+use crate::service::chat::ChatService;
+
+/// Maximum number of runs kept in the history file
+const MAX_HISTORY_RUNS: usize = 200;
+
+/// Instructions for the judge model; the rubric and response are appended
+const JUDGE_PROMPT: &str = "You are grading a response against a rubric. \
+Reply with PASS or FAIL on the first line, then one sentence explaining why.";
+
+/// A check on a response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expectation {
+    /// The response contains the text, ignoring case
+    Contains(String),
+
+    /// The response doesn't contain the text, ignoring case
+    NotContains(String),
+
+    /// The response matches the regular expression
+    Regex(String),
+}
+
+/// One test case of a suite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalCase {
+    /// Case identifier, unique in the suite
+    pub id: String,
+
+    /// Prompt sent to the model
+    pub prompt: String,
+
+    /// System prompt for the case
+    #[serde(default)]
+    pub system: Option<String>,
+
+    /// Checks the response must pass
+    #[serde(default)]
+    pub expect: Vec<Expectation>,
+
+    /// Rubric a judge model grades the response against
+    #[serde(default)]
+    pub judge: Option<String>,
+}
+
+/// A named set of test cases
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalSuite {
+    /// Suite name; runs are compared with earlier runs of the same suite
+    pub name: String,
+
+    /// Model grading rubric cases; each model judges itself when unset
+    #[serde(default)]
+    pub judge_model: Option<String>,
+
+    /// Test cases
+    pub cases: Vec<EvalCase>,
+}
+
+/// Outcome of one case on one model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseResult {
+    /// Case identifier
+    pub case_id: String,
+
+    /// Whether every check passed
+    pub passed: bool,
+
+    /// Response text, if the model answered
+    pub output: Option<String>,
+
+    /// Checks that failed
+    pub failures: Vec<String>,
+
+    /// Error if the prompt failed
+    pub error: Option<String>,
+
+    /// Time taken in milliseconds, grading included
+    pub duration_ms: u64,
+}
+
+/// Results of a suite on one model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelEvaluation {
+    /// Model identifier
+    pub model_id: String,
+
+    /// Provider name
+    pub provider: String,
+
+    /// Per-case results in suite order
+    pub cases: Vec<CaseResult>,
+}
+
+impl ModelEvaluation {
+    /// Cases that passed
+    pub fn passed(&self) -> usize {
+        self.cases.iter().filter(|c| c.passed).count()
+    }
+
+    /// Cases that failed or errored
+    pub fn failed(&self) -> usize {
+        self.cases.len() - self.passed()
+    }
+
+    /// Share of cases that passed, from 0 to 1
+    pub fn pass_rate(&self) -> f64 {
+        if self.cases.is_empty() {
+            0.0
+        } else {
+            self.passed() as f64 / self.cases.len() as f64
+        }
+    }
+}
+
+/// A complete evaluation run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    /// Run identifier
+    pub id: String,
+
+    /// Suite that was run
+    pub suite: String,
+
+    /// When the run started
+    pub started_at: SystemTime,
+
+    /// Application version that produced the report
+    pub app_version: String,
+
+    /// Results per model
+    pub models: Vec<ModelEvaluation>,
+}
+
+impl EvalReport {
+    /// Whether every case passed on every model
+    pub fn all_passed(&self) -> bool {
+        self.models.iter().all(|m| m.failed() == 0)
+    }
+
+    /// JUnit XML with one test suite per model, for CI systems
+    pub fn to_junit(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let tests: usize = self.models.iter().map(|m| m.cases.len()).sum();
+        let failures: usize = self.models.iter().map(|m| m.failed()).sum();
+        xml.push_str(&format!(
+            "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+            xml_escape(&self.suite),
+            tests,
+            failures
+        ));
+
+        for model in &self.models {
+            let time: u64 = model.cases.iter().map(|c| c.duration_ms).sum();
+            xml.push_str(&format!(
+                "  <testsuite name=\"{}/{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+                xml_escape(&self.suite),
+                xml_escape(&model.model_id),
+                model.cases.len(),
+                model.failed(),
+                time as f64 / 1000.0
+            ));
+            for case in &model.cases {
+                xml.push_str(&format!(
+                    "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                    xml_escape(&model.model_id),
+                    xml_escape(&case.case_id),
+                    case.duration_ms as f64 / 1000.0
+                ));
+                if case.passed {
+                    xml.push_str("/>\n");
+                    continue;
+                }
+                xml.push_str(">\n");
+                let message = case.error.clone().unwrap_or_else(|| case.failures.join("; "));
+                xml.push_str(&format!(
+                    "      <failure message=\"{}\">{}</failure>\n",
+                    xml_escape(&message),
+                    xml_escape(case.output.as_deref().unwrap_or(""))
+                ));
+                xml.push_str("    </testcase>\n");
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+/// Comparison of a model's result with its previous run of the suite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalComparison {
+    /// Model identifier
+    pub model_id: String,
+
+    /// Pass rate of the previous run
+    pub previous_pass_rate: f64,
+
+    /// Pass rate of this run
+    pub pass_rate: f64,
+
+    /// Cases that passed before and fail now
+    pub regressions: Vec<String>,
+
+    /// Cases that failed before and pass now
+    pub fixed: Vec<String>,
+}
+
+/// Progress of a running evaluation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EvalProgress {
+    /// Cases times models
+    pub total: usize,
+
+    /// Cases that passed
+    pub passed: usize,
+
+    /// Cases that failed
+    pub failed: usize,
+}
+
+/// Load a suite from a JSON file
+pub fn load_suite(path: &Path) -> McpResult<EvalSuite> {
+    let suite: EvalSuite = serde_json::from_str(&fs::read_to_string(path)?)?;
+    if suite.cases.is_empty() {
+        return Err(McpError::InvalidRequest(format!("{} has no cases", path.display())));
+    }
+    let mut seen = std::collections::HashSet::new();
+    for case in &suite.cases {
+        if !seen.insert(case.id.as_str()) {
+            return Err(McpError::InvalidRequest(format!("Case {} appears twice", case.id)));
+        }
+        if case.expect.is_empty() && case.judge.is_none() {
+            return Err(McpError::InvalidRequest(format!("Case {} has no checks", case.id)));
+        }
+        for expectation in &case.expect {
+            if let Expectation::Regex(pattern) = expectation {
+                Regex::new(pattern)
+                    .map_err(|e| McpError::InvalidRequest(format!("Case {}: invalid regex: {}", case.id, e)))?;
+            }
+        }
+    }
+    Ok(suite)
+}
+
+/// Checks a response fails, described for the report
+pub fn check_expectations(expectations: &[Expectation], output: &str) -> Vec<String> {
+    let lower = output.to_lowercase();
+    expectations
+        .iter()
+        .filter_map(|expectation| match expectation {
+            Expectation::Contains(text) if !lower.contains(&text.to_lowercase()) => {
+                Some(format!("expected to contain \"{}\"", text))
+            }
+            Expectation::NotContains(text) if lower.contains(&text.to_lowercase()) => {
+                Some(format!("expected not to contain \"{}\"", text))
+            }
+            Expectation::Regex(pattern) => match Regex::new(pattern) {
+                Ok(re) if re.is_match(output) => None,
+                Ok(_) => Some(format!("expected to match /{}/", pattern)),
+                Err(e) => Some(format!("invalid regex /{}/: {}", pattern, e)),
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Read a judge's verdict: `Some(true)` for PASS, `Some(false)` for FAIL
+pub fn parse_verdict(reply: &str) -> Option<bool> {
+    let first = reply.split_whitespace().next()?;
+    let word: String = first.chars().filter(|c| c.is_ascii_alphabetic()).collect();
+    match word.to_uppercase().as_str() {
+        "PASS" => Some(true),
+        "FAIL" => Some(false),
+        _ => None,
+    }
+}
+
+/// Compare a report with the most recent earlier run of the same suite on
+/// each model
+pub fn compare(report: &EvalReport, history: &[EvalReport]) -> Vec<EvalComparison> {
+    report
+        .models
+        .iter()
+        .filter_map(|current| {
+            let previous = history
+                .iter()
+                .rev()
+                .filter(|run| run.id != report.id && run.suite == report.suite)
+                .flat_map(|run| run.models.iter())
+                .find(|m| m.model_id == current.model_id)?;
+
+            let passed_before = |case_id: &str| previous.cases.iter().find(|c| c.case_id == case_id).map(|c| c.passed);
+            let changed = |now: bool| -> Vec<String> {
+                current
+                    .cases
+                    .iter()
+                    .filter(|c| c.passed == now && passed_before(&c.case_id) == Some(!now))
+                    .map(|c| c.case_id.clone())
+                    .collect()
+            };
+
+            Some(EvalComparison {
+                model_id: current.model_id.clone(),
+                previous_pass_rate: previous.pass_rate(),
+                pass_rate: current.pass_rate(),
+                regressions: changed(false),
+                fixed: changed(true),
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn xml_escape(text: &str) -> String {
+    text.chars()
+        .filter(|c| matches!(c, '\t' | '\n' | '\r') || !c.is_control())
+        .fold(String::new(), |mut out, c| {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                '"' => out.push_str("&quot;"),
+                '\'' => out.push_str("&apos;"),
+                c => out.push(c),
+            }
+            out
+        })
+}
+
+/// Runs evaluation suites against models and keeps a history of results
+pub struct EvalRunner {
+    /// Chat service used to run prompts
+    chat_service: Arc<ChatService>,
+
+    /// Path to the history file
+    history_path: PathBuf,
+
+    /// Cases run at the same time
+    concurrency: usize,
+
+    /// Progress listener
+    progress_tx: Option<mpsc::UnboundedSender<EvalProgress>>,
+}
+
+impl EvalRunner {
+    /// Create a new evaluation runner
+    pub fn new(chat_service: Arc<ChatService>) -> Self {
+        Self {
+            chat_service,
+            history_path: data_path("eval_history.json"),
+            concurrency: 4,
+            progress_tx: None,
+        }
+    }
+
+    /// Run this many cases at the same time
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Receive progress updates
+    pub fn with_progress(mut self, tx: mpsc::UnboundedSender<EvalProgress>) -> Self {
+        self.progress_tx = Some(tx);
+        self
+    }
+
+    /// Run every case of a suite on every model as one batch
+    pub async fn run(&self, suite: &EvalSuite, models: &[Model]) -> McpResult<EvalReport> {
+        if models.is_empty() {
+            return Err(McpError::InvalidRequest("No models to evaluate".to_string()));
+        }
+        let started_at = SystemTime::now();
+        let judge = match &suite.judge_model {
+            Some(id) => Some(
+                self.chat_service
+                    .available_models()
+                    .await?
+                    .into_iter()
+                    .find(|m| &m.id == id || &m.name == id)
+                    .ok_or_else(|| McpError::InvalidRequest(format!("Unknown judge model: {}", id)))?,
+            ),
+            None => None,
+        };
+        info!("Evaluating suite {} on {} model(s)", suite.name, models.len());
+
+        let mut progress = EvalProgress {
+            total: suite.cases.len() * models.len(),
+            ..Default::default()
+        };
+        self.report(&progress);
+
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let (tx, mut rx) = mpsc::channel(progress.total.max(1));
+        for (model_index, model) in models.iter().enumerate() {
+            for (case_index, case) in suite.cases.iter().enumerate() {
+                let permit = semaphore.clone().acquire_owned().await;
+                let chat_service = self.chat_service.clone();
+                let model = model.clone();
+                let judge = judge.clone().unwrap_or_else(|| model.clone());
+                let case = case.clone();
+                let tx = tx.clone();
+
+                tokio::spawn(async move {
+                    let _permit = permit;
+                    let result = run_case(&chat_service, &model, &judge, &case).await;
+                    let _ = tx.send((model_index, case_index, result)).await;
+                });
+            }
+        }
+        drop(tx);
+
+        let mut results: Vec<Vec<Option<CaseResult>>> = vec![vec![None; suite.cases.len()]; models.len()];
+        while let Some((model_index, case_index, result)) = rx.recv().await {
+            if result.passed {
+                progress.passed += 1;
+            } else {
+                progress.failed += 1;
+            }
+            self.report(&progress);
+            results[model_index][case_index] = Some(result);
+        }
+
+        Ok(EvalReport {
+            id: uuid::Uuid::new_v4().to_string(),
+            suite: suite.name.clone(),
+            started_at,
+            app_version: crate::utils::app_version(),
+            models: models
+                .iter()
+                .zip(results)
+                .map(|(model, cases)| ModelEvaluation {
+                    model_id: model.id.clone(),
+                    provider: model.provider.clone(),
+                    cases: cases.into_iter().flatten().collect(),
+                })
+                .collect(),
+        })
+    }
+
+    fn report(&self, progress: &EvalProgress) {
+        if let Some(tx) = &self.progress_tx {
+            let _ = tx.send(progress.clone());
+        }
+    }
+
+    /// Load previous evaluation runs
+    pub fn load_history(&self) -> McpResult<Vec<EvalReport>> {
+        if !self.history_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.history_path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Append a report to the history file
+    pub fn save_to_history(&self, report: &EvalReport) -> McpResult<()> {
+        let mut history = self.load_history().unwrap_or_default();
+        history.push(report.clone());
+
+        if history.len() > MAX_HISTORY_RUNS {
+            let excess = history.len() - MAX_HISTORY_RUNS;
+            history.drain(0..excess);
+        }
+
+        let content = serde_json::to_string_pretty(&history)?;
+        fs::write(&self.history_path, content).map_err(McpError::Io)
+    }
+
+    /// Compare a report with the previous run of its suite on each model
+    pub fn compare_with_history(&self, report: &EvalReport) -> Vec<EvalComparison> {
+        compare(report, &self.load_history().unwrap_or_default())
+    }
+}
+
+/// Ask a model in a throwaway conversation
+async fn ask(chat_service: &ChatService, model: &Model, system: Option<&str>, prompt: &str) -> McpResult<String> {
+    let conversation = chat_service
+        .create_conversation("Evaluation", Some(model.clone()))
+        .await?;
+
+    let result = async {
+        if let Some(system) = system {
+            chat_service.set_system_message(&conversation.id, system).await?;
+        }
+        chat_service.send_message(&conversation.id, prompt).await
+    }
+    .await;
+
+    // Always clean up the conversation, bypassing the trash
+    let cleanup = match chat_service.delete_conversation(&conversation.id).await {
+        Ok(()) => chat_service.purge_conversation(&conversation.id).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = cleanup {
+        debug!("Failed to delete evaluation conversation: {}", e);
+    }
+
+    Ok(result?.text())
+}
+
+/// Run one case on a model and grade the response
+async fn run_case(chat_service: &ChatService, model: &Model, judge: &Model, case: &EvalCase) -> CaseResult {
+    let start = Instant::now();
+    let output = match ask(chat_service, model, case.system.as_deref(), &case.prompt).await {
+        Ok(output) => output,
+        Err(e) => {
+            warn!("Evaluation case {} failed on {}: {}", case.id, model.id, e);
+            return CaseResult {
+                case_id: case.id.clone(),
+                passed: false,
+                output: None,
+                failures: Vec::new(),
+                error: Some(e.to_string()),
+                duration_ms: start.elapsed().as_millis() as u64,
+            };
+        }
+    };
+
+    let mut failures = check_expectations(&case.expect, &output);
+    let mut error = None;
+    if let Some(rubric) = &case.judge {
+        let prompt = format!("Rubric:\n{}\n\nResponse:\n{}", rubric, output);
+        match ask(chat_service, judge, Some(JUDGE_PROMPT), &prompt).await {
+            Ok(reply) => match parse_verdict(&reply) {
+                Some(true) => {}
+                Some(false) => failures.push(format!("judge: {}", reply.trim())),
+                None => failures.push(format!("judge gave no verdict: {}", reply.trim())),
+            },
+            Err(e) => error = Some(format!("Judge {} failed: {}", judge.id, e)),
+        }
+    }
+
+    CaseResult {
+        case_id: case.id.clone(),
+        passed: failures.is_empty() && error.is_none(),
+        output: Some(output),
+        failures,
+        error,
+        duration_ms: start.elapsed().as_millis() as u64,
+    }
+}
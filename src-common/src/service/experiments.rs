@@ -0,0 +1,480 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::config::data_path;
+use crate::environment::{get_environments, Environment};
+use crate::error::{McpError, McpResult};
+use crate::models::message::ContentType;
+use crate::models::{Message, MessageRole, Rating};
+use crate::service::batch::{BatchItem, BatchResult};
+use crate::service::filters::WORKSPACE_CONTEXT_KEY;
+use crate::service::middleware::{MessageMiddleware, MiddlewareContext};
+
+/// Placeholder replaced by the user's text in a variant's prompt template
+pub const PROMPT_PLACEHOLDER: &str = "{prompt}";
+
+/// One arm of an experiment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Variant {
+    /// Variant name, unique within the experiment
+    pub name: String,
+
+    /// System prompt used by this variant
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+
+    /// Template wrapping each user prompt; must contain `{prompt}`
+    #[serde(default)]
+    pub prompt_template: Option<String>,
+}
+
+impl Variant {
+    /// Apply the prompt template to a user prompt
+    pub fn render_prompt(&self, prompt: &str) -> String {
+        match &self.prompt_template {
+            Some(template) => template.replace(PROMPT_PLACEHOLDER, prompt),
+            None => prompt.to_string(),
+        }
+    }
+
+    /// Apply the prompt template to a user prompt, with the template's
+    /// `{env.NAME}` references filled in from an environment
+    pub fn render_prompt_in(&self, prompt: &str, environment: &Environment) -> String {
+        match &self.prompt_template {
+            Some(template) => environment.expand(template).replace(PROMPT_PLACEHOLDER, prompt),
+            None => prompt.to_string(),
+        }
+    }
+}
+
+/// A prompt experiment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Experiment {
+    /// Experiment ID
+    pub id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// Variants alternated across conversations
+    pub variants: Vec<Variant>,
+
+    /// Whether new conversations and batches are enrolled
+    pub active: bool,
+
+    /// When the experiment was created
+    pub created_at: DateTime<Utc>,
+
+    /// Number of enrollments so far, used for round-robin assignment
+    #[serde(default)]
+    pub enrollments: usize,
+}
+
+/// Which experiment and variant a conversation belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Assignment {
+    /// Experiment ID
+    pub experiment_id: String,
+
+    /// Index of the variant
+    pub variant: usize,
+}
+
+/// A rating recorded for an enrolled conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RatingRecord {
+    /// Conversation the message belongs to
+    pub conversation_id: String,
+
+    /// The rating
+    pub rating: Rating,
+
+    /// When it was recorded
+    pub rated_at: DateTime<Utc>,
+}
+
+/// Results for one variant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariantReport {
+    /// Variant name
+    pub name: String,
+
+    /// Conversations enrolled
+    pub conversations: usize,
+
+    /// Thumbs up
+    pub up: usize,
+
+    /// Thumbs down
+    pub down: usize,
+
+    /// Share of ratings that were positive
+    pub approval_rate: f64,
+}
+
+/// Comparison of an experiment's variants
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentReport {
+    /// Experiment ID
+    pub experiment_id: String,
+
+    /// Experiment name
+    pub name: String,
+
+    /// Per-variant results in definition order
+    pub variants: Vec<VariantReport>,
+
+    /// Variant with the highest approval rate, if any were rated
+    pub leader: Option<String>,
+
+    /// Two-proportion z-score of the best variant against the runner-up
+    pub z_score: Option<f64>,
+}
+
+/// Persisted experiment state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ExperimentData {
+    experiments: Vec<Experiment>,
+    assignments: HashMap<String, Assignment>,
+    ratings: HashMap<String, RatingRecord>,
+}
+
+/// Stores experiments, conversation assignments and ratings
+pub struct ExperimentStore {
+    path: PathBuf,
+    data: Mutex<ExperimentData>,
+}
+
+impl ExperimentStore {
+    /// Experiments kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        let data = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            data: Mutex::new(data),
+        }
+    }
+
+    fn save(&self, data: &ExperimentData) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(data)?)?;
+        Ok(())
+    }
+
+    /// Create an experiment; it becomes the active one
+    pub fn create(&self, name: &str, variants: Vec<Variant>) -> McpResult<Experiment> {
+        if variants.len() < 2 {
+            return Err(McpError::InvalidRequest("An experiment needs at least two variants".to_string()));
+        }
+        if let Some(variant) = variants.iter().find(|v| {
+            v.prompt_template
+                .as_ref()
+                .map(|t| !t.contains(PROMPT_PLACEHOLDER))
+                .unwrap_or(false)
+        }) {
+            return Err(McpError::InvalidRequest(format!(
+                "Prompt template of variant '{}' must contain {}",
+                variant.name, PROMPT_PLACEHOLDER
+            )));
+        }
+
+        let experiment = Experiment {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            variants,
+            active: true,
+            created_at: Utc::now(),
+            enrollments: 0,
+        };
+
+        let mut data = self.data.lock().unwrap();
+        // Only one experiment enrolls at a time so variants aren't confounded
+        for existing in data.experiments.iter_mut() {
+            existing.active = false;
+        }
+        data.experiments.push(experiment.clone());
+        self.save(&data)?;
+
+        info!("Started experiment {} with {} variants", name, experiment.variants.len());
+        Ok(experiment)
+    }
+
+    /// All experiments
+    pub fn list(&self) -> Vec<Experiment> {
+        self.data.lock().unwrap().experiments.clone()
+    }
+
+    /// Find an experiment by ID or name
+    pub fn get(&self, id_or_name: &str) -> Option<Experiment> {
+        self.data
+            .lock()
+            .unwrap()
+            .experiments
+            .iter()
+            .find(|e| e.id == id_or_name || e.name == id_or_name)
+            .cloned()
+    }
+
+    /// Stop enrolling into an experiment
+    pub fn stop(&self, id_or_name: &str) -> McpResult<()> {
+        let mut data = self.data.lock().unwrap();
+        let experiment = data
+            .experiments
+            .iter_mut()
+            .find(|e| e.id == id_or_name || e.name == id_or_name)
+            .ok_or_else(|| McpError::InvalidRequest(format!("Unknown experiment '{}'", id_or_name)))?;
+        experiment.active = false;
+        self.save(&data)
+    }
+
+    /// Pick the next variant of an experiment, alternating round-robin
+    fn next_variant(data: &mut ExperimentData, experiment_id: &str) -> Option<(usize, Variant)> {
+        let experiment = data.experiments.iter_mut().find(|e| e.id == experiment_id)?;
+        let index = experiment.enrollments % experiment.variants.len();
+        experiment.enrollments += 1;
+        Some((index, experiment.variants[index].clone()))
+    }
+
+    /// Enroll a new conversation in the active experiment, if there is one
+    pub fn enroll(&self, conversation_id: &str) -> Option<Variant> {
+        let mut data = self.data.lock().unwrap();
+        let experiment_id = data.experiments.iter().find(|e| e.active)?.id.clone();
+        let (index, variant) = Self::next_variant(&mut data, &experiment_id)?;
+
+        data.assignments.insert(
+            conversation_id.to_string(),
+            Assignment {
+                experiment_id,
+                variant: index,
+            },
+        );
+        if let Err(e) = self.save(&data) {
+            warn!("Failed to save experiment assignment: {}", e);
+        }
+        Some(variant)
+    }
+
+    /// Variant a conversation was enrolled in
+    pub fn variant_for(&self, conversation_id: &str) -> Option<Variant> {
+        let data = self.data.lock().unwrap();
+        let assignment = data.assignments.get(conversation_id)?;
+        data.experiments
+            .iter()
+            .find(|e| e.id == assignment.experiment_id)
+            .and_then(|e| e.variants.get(assignment.variant))
+            .cloned()
+    }
+
+    /// Alternate an experiment's variants across batch items, rewriting their
+    /// system prompt and prompt. Returns the variant index per item.
+    pub fn apply_to_batch(&self, id_or_name: &str, items: &mut [BatchItem]) -> McpResult<Vec<usize>> {
+        let mut data = self.data.lock().unwrap();
+        let experiment_id = data
+            .experiments
+            .iter()
+            .find(|e| e.id == id_or_name || e.name == id_or_name)
+            .map(|e| e.id.clone())
+            .ok_or_else(|| McpError::InvalidRequest(format!("Unknown experiment '{}'", id_or_name)))?;
+
+        let mut indices = Vec::with_capacity(items.len());
+        for item in items.iter_mut() {
+            let (index, variant) = Self::next_variant(&mut data, &experiment_id).unwrap();
+            if variant.system_prompt.is_some() {
+                item.system = variant.system_prompt.clone();
+            }
+            item.prompt = variant.render_prompt(&item.prompt);
+            indices.push(index);
+        }
+
+        self.save(&data)?;
+        Ok(indices)
+    }
+
+    /// Record which variant produced the conversations of a finished batch
+    pub fn record_batch(&self, id_or_name: &str, results: &[BatchResult], variants: &[usize]) -> McpResult<()> {
+        let mut data = self.data.lock().unwrap();
+        let experiment_id = data
+            .experiments
+            .iter()
+            .find(|e| e.id == id_or_name || e.name == id_or_name)
+            .map(|e| e.id.clone())
+            .ok_or_else(|| McpError::InvalidRequest(format!("Unknown experiment '{}'", id_or_name)))?;
+
+        for (result, variant) in results.iter().zip(variants) {
+            if let Some(conversation_id) = &result.conversation_id {
+                data.assignments.insert(
+                    conversation_id.clone(),
+                    Assignment {
+                        experiment_id: experiment_id.clone(),
+                        variant: *variant,
+                    },
+                );
+            }
+        }
+        self.save(&data)
+    }
+
+    /// Record a rating; ignored unless the conversation is enrolled
+    pub fn record_rating(&self, conversation_id: &str, message_id: &str, rating: Option<Rating>) -> McpResult<()> {
+        let mut data = self.data.lock().unwrap();
+        if !data.assignments.contains_key(conversation_id) {
+            return Ok(());
+        }
+
+        match rating {
+            Some(rating) => {
+                data.ratings.insert(
+                    message_id.to_string(),
+                    RatingRecord {
+                        conversation_id: conversation_id.to_string(),
+                        rating,
+                        rated_at: Utc::now(),
+                    },
+                );
+            }
+            None => {
+                data.ratings.remove(message_id);
+            }
+        }
+        self.save(&data)
+    }
+
+    /// Compare the variants of an experiment
+    pub fn report(&self, id_or_name: &str) -> McpResult<ExperimentReport> {
+        let data = self.data.lock().unwrap();
+        let experiment = data
+            .experiments
+            .iter()
+            .find(|e| e.id == id_or_name || e.name == id_or_name)
+            .ok_or_else(|| McpError::InvalidRequest(format!("Unknown experiment '{}'", id_or_name)))?;
+
+        let mut variants: Vec<VariantReport> = experiment
+            .variants
+            .iter()
+            .map(|v| VariantReport {
+                name: v.name.clone(),
+                conversations: 0,
+                up: 0,
+                down: 0,
+                approval_rate: 0.0,
+            })
+            .collect();
+
+        for assignment in data.assignments.values().filter(|a| a.experiment_id == experiment.id) {
+            if let Some(report) = variants.get_mut(assignment.variant) {
+                report.conversations += 1;
+            }
+        }
+
+        for record in data.ratings.values() {
+            let Some(assignment) = data.assignments.get(&record.conversation_id) else {
+                continue;
+            };
+            if assignment.experiment_id != experiment.id {
+                continue;
+            }
+            if let Some(report) = variants.get_mut(assignment.variant) {
+                match record.rating {
+                    Rating::Up => report.up += 1,
+                    Rating::Down => report.down += 1,
+                }
+            }
+        }
+
+        for report in variants.iter_mut() {
+            let total = report.up + report.down;
+            if total > 0 {
+                report.approval_rate = report.up as f64 / total as f64;
+            }
+        }
+
+        let mut ranked: Vec<&VariantReport> = variants.iter().filter(|v| v.up + v.down > 0).collect();
+        ranked.sort_by(|a, b| b.approval_rate.partial_cmp(&a.approval_rate).unwrap_or(std::cmp::Ordering::Equal));
+        let leader = ranked.first().map(|v| v.name.clone());
+        let z_score = match (ranked.first(), ranked.get(1)) {
+            (Some(a), Some(b)) => two_proportion_z(a, b),
+            _ => None,
+        };
+
+        Ok(ExperimentReport {
+            experiment_id: experiment.id.clone(),
+            name: experiment.name.clone(),
+            variants,
+            leader,
+            z_score,
+        })
+    }
+}
+
+/// Two-proportion z-test of approval rates; |z| > 1.96 is significant at 95%
+fn two_proportion_z(a: &VariantReport, b: &VariantReport) -> Option<f64> {
+    let (n1, n2) = ((a.up + a.down) as f64, (b.up + b.down) as f64);
+    let pooled = (a.up + b.up) as f64 / (n1 + n2);
+    let se = (pooled * (1.0 - pooled) * (1.0 / n1 + 1.0 / n2)).sqrt();
+    if se == 0.0 {
+        None
+    } else {
+        Some((a.approval_rate - b.approval_rate) / se)
+    }
+}
+
+static EXPERIMENT_STORE: Lazy<Arc<ExperimentStore>> =
+    Lazy::new(|| Arc::new(ExperimentStore::at(data_path("experiments.json"))));
+
+/// Get the global experiment store
+pub fn get_experiment_store() -> Arc<ExperimentStore> {
+    EXPERIMENT_STORE.clone()
+}
+
+/// Middleware applying the prompt template of a conversation's variant
+pub struct ExperimentMiddleware;
+
+#[async_trait]
+impl MessageMiddleware for ExperimentMiddleware {
+    fn name(&self) -> &str {
+        "experiments"
+    }
+
+    async fn pre_send(&self, ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        if message.role != MessageRole::User {
+            return Ok(());
+        }
+        let Some(variant) = get_experiment_store().variant_for(&ctx.conversation_id) else {
+            return Ok(());
+        };
+
+        if variant.prompt_template.is_some() {
+            // Wrap the combined text in the template, keeping images and tool parts
+            let workspace = ctx.values.get(WORKSPACE_CONTEXT_KEY).and_then(|w| w.as_str());
+            let environment = get_environments().resolve(workspace, &ctx.conversation_id)?;
+            let rendered = variant.render_prompt_in(&message.text(), &environment);
+            let mut parts = vec![ContentType::Text { text: rendered }];
+            parts.extend(
+                message
+                    .content
+                    .parts
+                    .drain(..)
+                    .filter(|part| !matches!(part, ContentType::Text { .. })),
+            );
+            message.content.parts = parts;
+        }
+        message
+            .metadata
+            .get_or_insert_with(HashMap::new)
+            .insert("experiment_variant".to_string(), serde_json::json!(variant.name));
+        Ok(())
+    }
+}
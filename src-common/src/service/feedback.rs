@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::error::McpResult;
+use crate::models::{Conversation, Message, MessageRole, Rating};
+
+/// A rated response with the prompt that produced it, one line of the export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackRecord {
+    /// Conversation the message belongs to
+    pub conversation_id: String,
+
+    /// Rated message
+    pub message_id: String,
+
+    /// Model that served the message
+    pub model: String,
+
+    /// System prompt in effect, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+
+    /// User message the rated response answered
+    pub prompt: String,
+
+    /// Text of the rated message
+    pub response: String,
+
+    /// The rating
+    pub rating: Rating,
+
+    /// Reason given with the rating
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    /// When the rating was given
+    pub rated_at: DateTime<Utc>,
+}
+
+/// Collect the rated messages of a conversation
+pub fn collect(conversation: &Conversation) -> Vec<FeedbackRecord> {
+    let system = conversation
+        .messages
+        .iter()
+        .find(|m| m.role == MessageRole::System)
+        .map(Message::text);
+
+    conversation
+        .messages
+        .iter()
+        .enumerate()
+        .filter_map(|(index, message)| {
+            let feedback = message.feedback.as_ref()?;
+            let prompt = conversation.messages[..index]
+                .iter()
+                .rev()
+                .find(|m| m.role == MessageRole::User)
+                .map(Message::text)
+                .unwrap_or_default();
+            let model = message
+                .metadata
+                .as_ref()
+                .and_then(|m| m.get(crate::service::routing::SERVED_BY_METADATA_KEY))
+                .and_then(|v| v.as_str())
+                .unwrap_or(&conversation.model.id)
+                .to_string();
+
+            Some(FeedbackRecord {
+                conversation_id: conversation.id.clone(),
+                message_id: message.id.clone(),
+                model,
+                system: system.clone(),
+                prompt,
+                response: message.text(),
+                rating: feedback.rating,
+                comment: feedback.comment.clone(),
+                rated_at: feedback.rated_at,
+            })
+        })
+        .collect()
+}
+
+/// Write the feedback of all conversations as JSONL; returns the number of records
+pub fn export_jsonl(conversations: &[Conversation], path: &Path) -> McpResult<usize> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let mut count = 0;
+
+    for conversation in conversations {
+        for record in collect(conversation) {
+            writeln!(writer, "{}", serde_json::to_string(&record)?)?;
+            count += 1;
+        }
+    }
+
+    writer.flush()?;
+    Ok(count)
+}
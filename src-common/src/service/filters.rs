@@ -0,0 +1,589 @@
+use async_trait::async_trait;
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::models::message::ContentType;
+use crate::models::{Message, MessageRole};
+use crate::service::middleware::{MessageMiddleware, MiddlewareContext};
+
+/// Context value naming the workspace whose filter profile applies
+pub const WORKSPACE_CONTEXT_KEY: &str = "workspace";
+
+/// Message metadata key recording what the filters changed
+pub const FILTERS_METADATA_KEY: &str = "content_filters";
+
+/// Words masked by the profanity filter in addition to the configured ones
+const BUILTIN_PROFANITY: &[&str] = &[
+    "fuck", "fucking", "shit", "bullshit", "bitch", "asshole", "bastard", "dick", "cunt", "motherfucker",
+];
+
+/// Credential formats detected in responses, with the label used when redacting
+static SECRET_PATTERNS: Lazy<Vec<(&'static str, Regex)>> = Lazy::new(|| {
+    [
+        ("AWS access key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+        ("GitHub token", r"\bgh[pousr]_[A-Za-z0-9]{36,}\b"),
+        ("Slack token", r"\bxox[abprs]-[A-Za-z0-9-]{10,}\b"),
+        ("Anthropic API key", r"\bsk-ant-[A-Za-z0-9_-]{20,}\b"),
+        ("OpenAI API key", r"\bsk-(?:proj-)?[A-Za-z0-9]{32,}\b"),
+        ("Google API key", r"\bAIza[0-9A-Za-z_-]{35}\b"),
+        ("Private key", r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----"),
+        (
+            "Hard-coded credential",
+            r#"(?i)\b(?:api[_-]?key|secret|password|passwd|access[_-]?token)\b\s*[:=]\s*["'][^"'\s]{8,}["']"#,
+        ),
+    ]
+    .into_iter()
+    .map(|(label, pattern)| (label, Regex::new(pattern).unwrap()))
+    .collect()
+});
+
+/// Credentials whose end hasn't been streamed yet: a private key before its
+/// footer, or an assignment before its closing quote
+static UNFINISHED_SECRETS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        r"-----BEGIN [A-Z ]*PRIVATE KEY-----[^-]*(?:-+[A-Z ]*)?$",
+        r#"(?i)\b(?:api[_-]?key|secret|password|passwd|access[_-]?token)\b\s*[:=]?\s*["']?[^"'\s]*$"#,
+    ]
+    .into_iter()
+    .map(|pattern| Regex::new(pattern).unwrap())
+    .collect()
+});
+
+/// Context value prefix for the stream text a filter holds back
+const PENDING_CONTEXT_PREFIX: &str = "filter_pending:";
+
+/// Bytes at the end of streamed text held back in case a match continues in
+/// the next chunk
+const STREAM_HOLDBACK_BYTES: usize = 32;
+
+/// Most streamed text held back waiting for a match to complete
+const MAX_PENDING_BYTES: usize = 8192;
+
+/// What the secret filter does with detected credentials
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretAction {
+    /// Don't scan
+    Off,
+    /// Record findings in the message metadata, leave the text alone
+    Flag,
+    /// Replace findings with a placeholder
+    #[default]
+    Redact,
+}
+
+/// A custom regex replacement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplacementRule {
+    /// Regular expression to match
+    pub pattern: String,
+
+    /// Replacement text; `$1` etc. refer to capture groups
+    pub replacement: String,
+
+    /// What the rule is for
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Filters applied to assistant messages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterProfile {
+    /// Mask profanity
+    #[serde(default)]
+    pub profanity: bool,
+
+    /// Extra words to mask
+    #[serde(default)]
+    pub profanity_words: Vec<String>,
+
+    /// Secret detection in generated text and code
+    #[serde(default)]
+    pub secrets: SecretAction,
+
+    /// Custom replacements, applied in order
+    #[serde(default)]
+    pub replacements: Vec<ReplacementRule>,
+}
+
+impl Default for FilterProfile {
+    fn default() -> Self {
+        Self {
+            profanity: false,
+            profanity_words: Vec::new(),
+            secrets: SecretAction::Redact,
+            replacements: Vec::new(),
+        }
+    }
+}
+
+/// Filter profiles: one default plus overrides per workspace
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FilterSettings {
+    /// Profile for conversations outside any workspace
+    #[serde(default)]
+    pub default: FilterProfile,
+
+    /// Profiles by workspace name
+    #[serde(default)]
+    pub workspaces: HashMap<String, FilterProfile>,
+}
+
+impl FilterSettings {
+    fn path() -> PathBuf {
+        data_path("content_filters.json")
+    }
+
+    /// Load the settings, falling back to the defaults
+    pub fn load() -> Self {
+        match fs::read_to_string(Self::path()) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Invalid content filter file, using defaults: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Persist the settings
+    pub fn save(&self) -> McpResult<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Profile for a workspace, or the default one
+    pub fn profile(&self, workspace: Option<&str>) -> &FilterProfile {
+        workspace
+            .and_then(|w| self.workspaces.get(w))
+            .unwrap_or(&self.default)
+    }
+
+    /// Mutable profile for a workspace, created from the default when missing
+    pub fn profile_mut(&mut self, workspace: Option<&str>) -> &mut FilterProfile {
+        match workspace {
+            Some(w) => {
+                let default = self.default.clone();
+                self.workspaces.entry(w.to_string()).or_insert(default)
+            }
+            None => &mut self.default,
+        }
+    }
+
+    /// Check that every custom rule compiles
+    pub fn validate(&self) -> McpResult<()> {
+        for profile in std::iter::once(&self.default).chain(self.workspaces.values()) {
+            for rule in &profile.replacements {
+                Regex::new(&rule.pattern).map_err(|e| {
+                    McpError::InvalidRequest(format!("Invalid filter pattern '{}': {}", rule.pattern, e))
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+static FILTER_SETTINGS: Lazy<Arc<RwLock<FilterSettings>>> =
+    Lazy::new(|| Arc::new(RwLock::new(FilterSettings::load())));
+
+/// Get the global content filter settings
+pub fn get_filter_settings() -> Arc<RwLock<FilterSettings>> {
+    FILTER_SETTINGS.clone()
+}
+
+/// Profile that applies to a request
+fn profile_for(ctx: &MiddlewareContext) -> FilterProfile {
+    let workspace = ctx.values.get(WORKSPACE_CONTEXT_KEY).and_then(|w| w.as_str());
+    get_filter_settings().read().unwrap().profile(workspace).clone()
+}
+
+/// Run every content filter over a message outside the middleware pipeline
+pub fn filter_message(workspace: Option<&str>, message: &mut Message) -> McpResult<()> {
+    let profile = get_filter_settings().read().unwrap().profile(workspace).clone();
+    ReplacementFilter::apply(&profile, message)?;
+    ProfanityFilter::apply(&profile, message);
+    SecretFilter::apply(&profile, message);
+    Ok(())
+}
+
+/// Apply `f` to every text part of a message
+fn map_text(message: &mut Message, mut f: impl FnMut(&str) -> String) {
+    for part in message.content.parts.iter_mut() {
+        if let ContentType::Text { text } = part {
+            *text = f(text);
+        }
+    }
+}
+
+/// Add a count to the filter report in the message metadata
+fn report(message: &mut Message, key: &str, value: serde_json::Value) {
+    let metadata = message.metadata.get_or_insert_with(HashMap::new);
+    let entry = metadata
+        .entry(FILTERS_METADATA_KEY.to_string())
+        .or_insert_with(|| serde_json::json!({}));
+    entry[key] = value;
+}
+
+/// Mask a word, keeping its first letter
+fn mask(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}", first, "*".repeat(chars.count())),
+        None => String::new(),
+    }
+}
+
+/// Replace the text parts of a message with a single one
+fn set_text(message: &mut Message, text: String) {
+    let parts = &mut message.content.parts;
+    // Parts before the first text part aren't text, so its index survives the retain
+    let first = parts.iter().position(|p| matches!(p, ContentType::Text { .. }));
+    parts.retain(|p| !matches!(p, ContentType::Text { .. }));
+    if first.is_some() || !text.is_empty() {
+        parts.insert(first.unwrap_or(0), ContentType::Text { text });
+    }
+}
+
+/// Where pending stream text can be cut: everything before the cut is
+/// filtered and sent, the rest waits for the next chunk. The last
+/// `STREAM_HOLDBACK_BYTES` and the word they start in are kept back, as is
+/// any match of `patterns` the cut would split.
+fn stream_cut(pending: &str, patterns: &[&Regex]) -> usize {
+    if pending.len() <= STREAM_HOLDBACK_BYTES {
+        return 0;
+    }
+    let mut limit = pending.len() - STREAM_HOLDBACK_BYTES;
+    while !pending.is_char_boundary(limit) {
+        limit -= 1;
+    }
+    if pending.len() > MAX_PENDING_BYTES {
+        // Nothing that long is worth waiting for
+        return limit;
+    }
+
+    let mut cut = pending[..limit]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map(|(i, c)| i + c.len_utf8())
+        .unwrap_or(0);
+    while let Some(start) = patterns
+        .iter()
+        .flat_map(|re| re.find_iter(pending))
+        .filter(|m| m.start() < cut && m.end() > cut)
+        .map(|m| m.start())
+        .min()
+    {
+        cut = start;
+    }
+    cut
+}
+
+/// Filter a streamed chunk with `apply`. The end of the text is held back in
+/// the context until a later chunk, or the end of the stream, shows whether it
+/// completes one of `patterns`, so matches split across chunks are still
+/// caught. Without patterns the filter is off and held text is released as is.
+fn filter_chunk(
+    ctx: &mut MiddlewareContext,
+    name: &str,
+    chunk: &mut Message,
+    patterns: &[&Regex],
+    end: bool,
+    apply: impl FnOnce(&mut Message),
+) {
+    let key = format!("{}{}", PENDING_CONTEXT_PREFIX, name);
+    let held = ctx.values.remove(&key).and_then(|v| v.as_str().map(str::to_string));
+    if patterns.is_empty() && held.is_none() {
+        return;
+    }
+
+    let mut text = held.unwrap_or_default();
+    text.push_str(&chunk.text());
+    let cut = if end || patterns.is_empty() {
+        text.len()
+    } else {
+        stream_cut(&text, patterns)
+    };
+    let rest = text.split_off(cut);
+    if !rest.is_empty() {
+        ctx.values.insert(key, serde_json::json!(rest));
+    }
+
+    set_text(chunk, text);
+    if !patterns.is_empty() {
+        apply(chunk);
+    }
+}
+
+/// Built-in middleware masking profanity in assistant messages
+pub struct ProfanityFilter;
+
+/// A profanity regex with the extra words it was built from
+type CachedProfanity = Option<(Vec<String>, Arc<Regex>)>;
+
+/// The last profanity regex built, reused while the words stay the same
+static PROFANITY_REGEX: Lazy<Mutex<CachedProfanity>> = Lazy::new(Default::default);
+
+impl ProfanityFilter {
+    /// Regex matching the profile's words, or `None` when the filter is off
+    /// for the message
+    fn regex(profile: &FilterProfile, message: &Message) -> Option<Arc<Regex>> {
+        if !profile.profanity || message.role != MessageRole::Assistant {
+            return None;
+        }
+
+        let mut cached = PROFANITY_REGEX.lock().unwrap();
+        if let Some((words, re)) = cached.as_ref() {
+            if *words == profile.profanity_words {
+                return Some(re.clone());
+            }
+        }
+
+        let words: Vec<String> = BUILTIN_PROFANITY
+            .iter()
+            .map(|w| w.to_string())
+            .chain(profile.profanity_words.iter().cloned())
+            .map(|w| regex::escape(w.trim()))
+            .filter(|w| !w.is_empty())
+            .collect();
+        let pattern = format!(r"\b(?:{})\b", words.join("|"));
+        let re = Arc::new(RegexBuilder::new(&pattern).case_insensitive(true).build().ok()?);
+        *cached = Some((profile.profanity_words.clone(), re.clone()));
+        Some(re)
+    }
+
+    fn mask_all(re: &Regex, message: &mut Message) {
+        let mut masked = 0;
+        map_text(message, |text| {
+            re.replace_all(text, |caps: &regex::Captures| {
+                masked += 1;
+                mask(&caps[0])
+            })
+            .into_owned()
+        });
+        if masked > 0 {
+            report(message, "profanity", serde_json::json!(masked));
+        }
+    }
+
+    fn apply(profile: &FilterProfile, message: &mut Message) {
+        if let Some(re) = Self::regex(profile, message) {
+            Self::mask_all(&re, message);
+        }
+    }
+
+    fn stream(&self, ctx: &mut MiddlewareContext, chunk: &mut Message, end: bool) {
+        let re = Self::regex(&profile_for(ctx), chunk);
+        let patterns: Vec<&Regex> = re.iter().map(|re| re.as_ref()).collect();
+        filter_chunk(ctx, self.name(), chunk, &patterns, end, |chunk| {
+            if let Some(re) = &re {
+                Self::mask_all(re, chunk);
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl MessageMiddleware for ProfanityFilter {
+    fn name(&self) -> &str {
+        "profanity_filter"
+    }
+
+    async fn post_receive(&self, ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        Self::apply(&profile_for(ctx), message);
+        Ok(())
+    }
+
+    async fn on_stream_chunk(&self, ctx: &mut MiddlewareContext, chunk: &mut Message) -> McpResult<()> {
+        self.stream(ctx, chunk, false);
+        Ok(())
+    }
+
+    async fn on_stream_end(&self, ctx: &mut MiddlewareContext, chunk: &mut Message) -> McpResult<()> {
+        self.stream(ctx, chunk, true);
+        Ok(())
+    }
+}
+
+/// Built-in middleware detecting credentials in generated text and code
+pub struct SecretFilter;
+
+impl SecretFilter {
+    /// Whether the filter scans the message
+    fn active(profile: &FilterProfile, message: &Message) -> bool {
+        profile.secrets != SecretAction::Off && message.role == MessageRole::Assistant
+    }
+
+    fn scan(redact: bool, message: &mut Message) {
+        let mut found: Vec<&str> = Vec::new();
+        map_text(message, |text| {
+            let mut text = text.to_string();
+            for (label, re) in SECRET_PATTERNS.iter() {
+                if !re.is_match(&text) {
+                    continue;
+                }
+                found.push(label);
+                if redact {
+                    text = re.replace_all(&text, format!("[REDACTED {}]", label).as_str()).into_owned();
+                }
+            }
+            text
+        });
+
+        if !found.is_empty() {
+            debug!("Secret filter found {:?} in message {}", found, message.id);
+            found.dedup();
+            report(message, "secrets", serde_json::json!(found));
+        }
+    }
+
+    fn apply(profile: &FilterProfile, message: &mut Message) {
+        if Self::active(profile, message) {
+            Self::scan(profile.secrets == SecretAction::Redact, message);
+        }
+    }
+
+    fn stream(&self, ctx: &mut MiddlewareContext, chunk: &mut Message, end: bool) {
+        let profile = profile_for(ctx);
+        let patterns: Vec<&Regex> = if Self::active(&profile, chunk) {
+            SECRET_PATTERNS
+                .iter()
+                .map(|(_, re)| re)
+                .chain(UNFINISHED_SECRETS.iter())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        filter_chunk(ctx, self.name(), chunk, &patterns, end, |chunk| {
+            Self::scan(profile.secrets == SecretAction::Redact, chunk)
+        });
+    }
+}
+
+#[async_trait]
+impl MessageMiddleware for SecretFilter {
+    fn name(&self) -> &str {
+        "secret_filter"
+    }
+
+    async fn post_receive(&self, ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        Self::apply(&profile_for(ctx), message);
+        Ok(())
+    }
+
+    async fn on_stream_chunk(&self, ctx: &mut MiddlewareContext, chunk: &mut Message) -> McpResult<()> {
+        self.stream(ctx, chunk, false);
+        Ok(())
+    }
+
+    async fn on_stream_end(&self, ctx: &mut MiddlewareContext, chunk: &mut Message) -> McpResult<()> {
+        self.stream(ctx, chunk, true);
+        Ok(())
+    }
+}
+
+/// Compiled replacement rules
+type CompiledRules = Arc<Vec<(Regex, String)>>;
+
+/// Compiled replacement rules with the rules they were compiled from
+type CachedRules = Option<(Vec<ReplacementRule>, CompiledRules)>;
+
+/// The last rules compiled, reused while the rules stay the same
+static REPLACEMENT_RULES: Lazy<Mutex<CachedRules>> = Lazy::new(Default::default);
+
+/// Built-in middleware applying custom regex replacements
+pub struct ReplacementFilter;
+
+impl ReplacementFilter {
+    /// Compiled rules of the profile, or `None` when the filter is off for the
+    /// message
+    fn rules(profile: &FilterProfile, message: &Message) -> McpResult<Option<CompiledRules>> {
+        if profile.replacements.is_empty() || message.role != MessageRole::Assistant {
+            return Ok(None);
+        }
+
+        let mut cached = REPLACEMENT_RULES.lock().unwrap();
+        if let Some((rules, compiled)) = cached.as_ref() {
+            if *rules == profile.replacements {
+                return Ok(Some(compiled.clone()));
+            }
+        }
+
+        let compiled = profile
+            .replacements
+            .iter()
+            .map(|rule| {
+                Regex::new(&rule.pattern)
+                    .map(|re| (re, rule.replacement.clone()))
+                    .map_err(|e| McpError::InvalidRequest(format!("Invalid filter pattern '{}': {}", rule.pattern, e)))
+            })
+            .collect::<McpResult<Vec<_>>>()?;
+        let compiled = Arc::new(compiled);
+        *cached = Some((profile.replacements.clone(), compiled.clone()));
+        Ok(Some(compiled))
+    }
+
+    fn replace_all(rules: &[(Regex, String)], message: &mut Message) {
+        let mut replaced = 0;
+        map_text(message, |text| {
+            let mut text = text.to_string();
+            for (re, replacement) in rules {
+                replaced += re.find_iter(&text).count();
+                text = re.replace_all(&text, replacement.as_str()).into_owned();
+            }
+            text
+        });
+        if replaced > 0 {
+            report(message, "replacements", serde_json::json!(replaced));
+        }
+    }
+
+    fn apply(profile: &FilterProfile, message: &mut Message) -> McpResult<()> {
+        if let Some(rules) = Self::rules(profile, message)? {
+            Self::replace_all(&rules, message);
+        }
+        Ok(())
+    }
+
+    fn stream(&self, ctx: &mut MiddlewareContext, chunk: &mut Message, end: bool) -> McpResult<()> {
+        // Compiled before any text is held back, so an invalid rule leaves the chunk as it was
+        let rules = Self::rules(&profile_for(ctx), chunk)?;
+        let patterns: Vec<&Regex> = rules.iter().flat_map(|rules| rules.iter().map(|(re, _)| re)).collect();
+        filter_chunk(ctx, self.name(), chunk, &patterns, end, |chunk| {
+            if let Some(rules) = &rules {
+                Self::replace_all(rules, chunk);
+            }
+        });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MessageMiddleware for ReplacementFilter {
+    fn name(&self) -> &str {
+        "replacement_filter"
+    }
+
+    async fn post_receive(&self, ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        Self::apply(&profile_for(ctx), message)
+    }
+
+    async fn on_stream_chunk(&self, ctx: &mut MiddlewareContext, chunk: &mut Message) -> McpResult<()> {
+        self.stream(ctx, chunk, false)
+    }
+
+    async fn on_stream_end(&self, ctx: &mut MiddlewareContext, chunk: &mut Message) -> McpResult<()> {
+        self.stream(ctx, chunk, true)
+    }
+}
@@ -0,0 +1,469 @@
+//! Pre-flight checks of a request against the provider's size limits.
+//!
+//! Before a request goes out, its text and images are measured against what
+//! the provider accepts: the whole body, each image's size and dimensions,
+//! and the number of images. A request over a limit is refused with an error
+//! saying what is too large and how to fix it, instead of the provider's
+//! opaque 4xx. Two fixes can be asked for: images are downscaled, and the
+//! images of a message are split over several requests, leaving the images
+//! of earlier messages out.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::{data_path, get_attachment_store, AttachmentStore};
+use crate::error::{McpError, McpResult};
+use crate::models::message::ContentType;
+use crate::models::Message;
+
+/// Bytes in a megabyte, as limits are given
+const MB: u64 = 1024 * 1024;
+
+/// What a provider accepts in one request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestLimits {
+    /// Largest request body, in bytes
+    pub max_request_bytes: u64,
+
+    /// Largest image, in bytes
+    pub max_image_bytes: u64,
+
+    /// Longest side of an image, in pixels
+    pub max_image_dimension: u32,
+
+    /// Most images in one request
+    pub max_images: usize,
+}
+
+impl RequestLimits {
+    /// Limits a provider documents, or conservative ones for providers
+    /// without documented limits
+    pub fn for_provider(provider: &str) -> Self {
+        match provider {
+            "anthropic" => Self {
+                max_request_bytes: 32 * MB,
+                max_image_bytes: 5 * MB,
+                max_image_dimension: 8000,
+                max_images: 100,
+            },
+            "local" => Self {
+                max_request_bytes: 64 * MB,
+                max_image_bytes: 20 * MB,
+                max_image_dimension: 4096,
+                max_images: 16,
+            },
+            _ => Self {
+                max_request_bytes: 20 * MB,
+                max_image_bytes: 5 * MB,
+                max_image_dimension: 4096,
+                max_images: 20,
+            },
+        }
+    }
+}
+
+/// The user's limits by provider, over the documented ones
+pub struct LimitsTable {
+    /// File holding the user's limits
+    path: PathBuf,
+}
+
+impl LimitsTable {
+    /// Table backed by `request_limits.json` in the data directory
+    pub fn new() -> Self {
+        Self::at(data_path("request_limits.json"))
+    }
+
+    /// Table backed by the given file
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn load(&self) -> BTreeMap<String, RequestLimits> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, overrides: &BTreeMap<String, RequestLimits>) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(overrides)?)?;
+        Ok(())
+    }
+
+    /// Limits of a provider: the user's, or the documented ones
+    pub fn for_provider(&self, provider: &str) -> RequestLimits {
+        self.load()
+            .get(provider)
+            .copied()
+            .unwrap_or_else(|| RequestLimits::for_provider(provider))
+    }
+
+    /// Providers with limits of the user's
+    pub fn overrides(&self) -> BTreeMap<String, RequestLimits> {
+        self.load()
+    }
+
+    /// Set a provider's limits
+    pub fn set(&self, provider: &str, limits: RequestLimits) -> McpResult<()> {
+        if limits.max_request_bytes == 0 || limits.max_image_bytes == 0 || limits.max_image_dimension == 0 {
+            return Err(McpError::InvalidRequest("Limits must be above zero".to_string()));
+        }
+        let mut overrides = self.load();
+        overrides.insert(provider.to_string(), limits);
+        self.save(&overrides)
+    }
+
+    /// Go back to a provider's documented limits; returns false if it had
+    /// none of the user's
+    pub fn reset(&self, provider: &str) -> McpResult<bool> {
+        let mut overrides = self.load();
+        let removed = overrides.remove(provider).is_some();
+        if removed {
+            self.save(&overrides)?;
+        }
+        Ok(removed)
+    }
+}
+
+impl Default for LimitsTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What can be done about a problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fix {
+    /// Store smaller copies of the images
+    Downscale,
+
+    /// Send the message's images over several requests
+    Split,
+}
+
+/// Fixes to apply before sending
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fit {
+    /// Downscale images over the size or dimension limit
+    #[serde(default)]
+    pub downscale: bool,
+
+    /// Split a message's images over several requests, and leave the images
+    /// of earlier messages out when a request would be too large
+    #[serde(default)]
+    pub split: bool,
+}
+
+/// A limit a request goes over
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    /// The whole request is too large
+    RequestTooLarge { bytes: u64, limit: u64, image_bytes: u64 },
+
+    /// An image is too large
+    ImageTooLarge { image: String, bytes: u64, limit: u64 },
+
+    /// An image has too many pixels on a side
+    ImageTooWide {
+        image: String,
+        width: u32,
+        height: u32,
+        limit: u32,
+    },
+
+    /// The request has too many images
+    TooManyImages { count: usize, limit: usize },
+}
+
+impl Problem {
+    /// What would fix the problem, if anything can be done automatically
+    pub fn fix(&self) -> Option<Fix> {
+        match self {
+            Problem::ImageTooLarge { .. } | Problem::ImageTooWide { .. } => Some(Fix::Downscale),
+            Problem::TooManyImages { .. } => Some(Fix::Split),
+            Problem::RequestTooLarge {
+                bytes,
+                limit,
+                image_bytes,
+            } => {
+                // Splitting only helps when the text fits on its own
+                (bytes.saturating_sub(*image_bytes) < *limit && *image_bytes > 0).then_some(Fix::Split)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::RequestTooLarge { bytes, limit, .. } => match self.fix() {
+                Some(_) => write!(
+                    f,
+                    "the request is {}, over the {} limit; split its images over several requests?",
+                    format_bytes(*bytes),
+                    format_bytes(*limit)
+                ),
+                None => write!(
+                    f,
+                    "the request is {}, over the {} limit; shorten the message or start a new conversation",
+                    format_bytes(*bytes),
+                    format_bytes(*limit)
+                ),
+            },
+            Problem::ImageTooLarge { image, bytes, limit } => write!(
+                f,
+                "{} is {}, over the {} limit; resize it?",
+                image,
+                format_bytes(*bytes),
+                format_bytes(*limit)
+            ),
+            Problem::ImageTooWide {
+                image,
+                width,
+                height,
+                limit,
+            } => write!(
+                f,
+                "{} is {}x{} pixels, over the {} pixel limit; resize it?",
+                image, width, height, limit
+            ),
+            Problem::TooManyImages { count, limit } => write!(
+                f,
+                "the request has {} images, over the limit of {}; split them over several requests?",
+                count, limit
+            ),
+        }
+    }
+}
+
+/// Bytes for display, like "7.2 MB"
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else if bytes >= 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// Bytes of content once base64 encoded, as images travel in a request
+pub fn encoded_size(bytes: u64) -> u64 {
+    bytes.div_ceil(3) * 4
+}
+
+/// An image in a request, as measured
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageSize {
+    /// Attachment ID, for stored images
+    pub attachment_id: Option<String>,
+
+    /// File name or URL
+    pub name: String,
+
+    /// Size in bytes, if known
+    pub bytes: u64,
+
+    /// Width and height in pixels, if known
+    pub dimensions: Option<(u32, u32)>,
+}
+
+/// Measure an image part
+pub fn measure_image(store: &AttachmentStore, url: &str) -> ImageSize {
+    if let Some(attachment) = store.resolve_url(url) {
+        // Stored files have no extension, so the format comes from the bytes
+        let dimensions = image::io::Reader::open(store.file_path(&attachment.id))
+            .and_then(|reader| reader.with_guessed_format())
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok());
+        return ImageSize {
+            attachment_id: Some(attachment.id),
+            name: attachment.file_name,
+            bytes: attachment.size,
+            dimensions,
+        };
+    }
+    // Inline images carry their bytes base64 encoded after the comma
+    let bytes = match url.strip_prefix("data:").and_then(|d| d.split_once(',')) {
+        Some((_, data)) => data.len() as u64 / 4 * 3,
+        None => 0,
+    };
+    ImageSize {
+        attachment_id: None,
+        name: if url.starts_with("data:") {
+            "an inline image".to_string()
+        } else {
+            url.to_string()
+        },
+        bytes,
+        dimensions: None,
+    }
+}
+
+/// Bytes a message adds to a request: its text and its images, encoded
+fn message_bytes(store: &AttachmentStore, message: &Message) -> u64 {
+    message
+        .content
+        .parts
+        .iter()
+        .map(|part| match part {
+            ContentType::Text { text } => text.len() as u64,
+            ContentType::Image { url, .. } => encoded_size(measure_image(store, url).bytes).max(url.len() as u64),
+            other => serde_json::to_string(other).map(|s| s.len() as u64).unwrap_or(0),
+        })
+        .sum()
+}
+
+/// A request measured against a provider's limits
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preflight {
+    /// Limits checked against
+    pub limits: RequestLimits,
+
+    /// Size of the request body, roughly
+    pub request_bytes: u64,
+
+    /// Images in the request
+    pub images: Vec<ImageSize>,
+
+    /// Limits the request goes over
+    pub problems: Vec<Problem>,
+}
+
+impl Preflight {
+    /// Measure the messages of a request
+    pub fn check(limits: &RequestLimits, messages: &[Message]) -> Self {
+        Self::check_with(&get_attachment_store(), limits, messages)
+    }
+
+    /// Measure the messages of a request, with images from a store
+    pub fn check_with(store: &AttachmentStore, limits: &RequestLimits, messages: &[Message]) -> Self {
+        let images: Vec<ImageSize> = messages
+            .iter()
+            .flat_map(|m| m.content.parts.iter())
+            .filter_map(|part| match part {
+                ContentType::Image { url, .. } => Some(measure_image(store, url)),
+                _ => None,
+            })
+            .collect();
+        let request_bytes = messages.iter().map(|m| message_bytes(store, m)).sum();
+        let image_bytes = images.iter().map(|i| encoded_size(i.bytes)).sum();
+
+        let mut problems = Vec::new();
+        for image in &images {
+            if image.bytes > limits.max_image_bytes {
+                problems.push(Problem::ImageTooLarge {
+                    image: image.name.clone(),
+                    bytes: image.bytes,
+                    limit: limits.max_image_bytes,
+                });
+            }
+            if let Some((width, height)) = image.dimensions {
+                if width.max(height) > limits.max_image_dimension {
+                    problems.push(Problem::ImageTooWide {
+                        image: image.name.clone(),
+                        width,
+                        height,
+                        limit: limits.max_image_dimension,
+                    });
+                }
+            }
+        }
+        if images.len() > limits.max_images {
+            problems.push(Problem::TooManyImages {
+                count: images.len(),
+                limit: limits.max_images,
+            });
+        }
+        if request_bytes > limits.max_request_bytes {
+            problems.push(Problem::RequestTooLarge {
+                bytes: request_bytes,
+                limit: limits.max_request_bytes,
+                image_bytes,
+            });
+        }
+
+        Self {
+            limits: *limits,
+            request_bytes,
+            images,
+            problems,
+        }
+    }
+
+    /// Whether the request is within every limit
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Fixes that would help, each once
+    pub fn fixes(&self) -> Vec<Fix> {
+        let mut fixes: Vec<Fix> = Vec::new();
+        for fix in self.problems.iter().filter_map(Problem::fix) {
+            if !fixes.contains(&fix) {
+                fixes.push(fix);
+            }
+        }
+        fixes
+    }
+
+    /// The request, or an error saying what is too large and what would fix it
+    pub fn into_result(self) -> McpResult<Self> {
+        if self.passed() {
+            return Ok(self);
+        }
+        let problems: Vec<String> = self.problems.iter().map(|p| p.to_string()).collect();
+        let mut error = format!("Request over the provider's limits: {}", problems.join("; "));
+        let fixes = self.fixes();
+        if !fixes.is_empty() {
+            let options: Vec<&str> = fixes
+                .iter()
+                .map(|f| match f {
+                    Fix::Downscale => "downscaling",
+                    Fix::Split => "splitting",
+                })
+                .collect();
+            error.push_str(&format!(". Send again with {} to fix this", options.join(" and ")));
+        }
+        Err(McpError::InvalidRequest(error))
+    }
+}
+
+/// Leave the images of every message but the last out of a request, oldest
+/// first, until it has few enough images and is small enough; returns how
+/// many were left out
+pub fn leave_out_older_images(store: &AttachmentStore, limits: &RequestLimits, messages: &mut [Message]) -> usize {
+    let check = Preflight::check_with(store, limits, messages);
+    let mut images = check.images.len();
+    let mut bytes = check.request_bytes;
+    let mut left_out = 0;
+
+    let Some((_, earlier)) = messages.split_last_mut() else {
+        return 0;
+    };
+    for message in earlier {
+        for part in &mut message.content.parts {
+            if images <= limits.max_images && bytes <= limits.max_request_bytes {
+                return left_out;
+            }
+            if let ContentType::Image { url, alt_text } = part {
+                let size = measure_image(store, url);
+                let note = format!("[Image sent earlier: {}]", alt_text.clone().unwrap_or(size.name));
+                bytes = bytes.saturating_sub(encoded_size(size.bytes).max(url.len() as u64)) + note.len() as u64;
+                images -= 1;
+                *part = ContentType::Text { text: note };
+                left_out += 1;
+            }
+        }
+    }
+    left_out
+}
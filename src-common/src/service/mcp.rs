@@ -1,18 +1,98 @@
+use async_trait::async_trait;
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
 use tokio::sync::{mpsc, Mutex, RwLock};
 use log::{debug, error, info, warn};
 
-use crate::config::{get_settings, get_storage_manager};
+use crate::config::{
+    get_settings, get_storage_manager, Draft, JournalEntry, OperationKind, Snapshot, StorageManager, TrashedConversation,
+};
 use crate::error::{McpError, McpResult};
 use crate::models::{Conversation, Message, Model};
 use crate::protocol::{ConnectionStatus, McpClient, McpConfig};
+use crate::utils::clock;
+
+/// Something that completes conversations: the MCP client, or a scripted
+/// provider in tests
+#[async_trait]
+pub trait CompletionProvider: Send + Sync {
+    /// Current connection status
+    fn connection_status(&self) -> ConnectionStatus;
+    
+    /// Connect to the provider
+    async fn connect(&self) -> McpResult<()>;
+    
+    /// Disconnect from the provider
+    async fn disconnect(&self) -> McpResult<()>;
+    
+    /// Complete a conversation
+    async fn send_completion(
+        &self,
+        model: &str,
+        messages: &[Message],
+        max_tokens: u32,
+        temperature: f32,
+    ) -> McpResult<Message>;
+    
+    /// Complete a conversation, streaming text deltas
+    async fn stream_completion(
+        &self,
+        model: &str,
+        messages: &[Message],
+        max_tokens: u32,
+        temperature: f32,
+    ) -> McpResult<mpsc::Receiver<McpResult<Message>>>;
+    
+    /// Cancel a streaming completion
+    async fn cancel_streaming(&self, stream_id: &str) -> McpResult<()>;
+}
+
+#[async_trait]
+impl CompletionProvider for McpClient {
+    fn connection_status(&self) -> ConnectionStatus {
+        McpClient::connection_status(self)
+    }
+    
+    async fn connect(&self) -> McpResult<()> {
+        McpClient::connect(self).await
+    }
+    
+    async fn disconnect(&self) -> McpResult<()> {
+        McpClient::disconnect(self).await
+    }
+    
+    async fn send_completion(
+        &self,
+        model: &str,
+        messages: &[Message],
+        max_tokens: u32,
+        temperature: f32,
+    ) -> McpResult<Message> {
+        McpClient::send_completion(self, model, messages, max_tokens, temperature).await
+    }
+    
+    async fn stream_completion(
+        &self,
+        model: &str,
+        messages: &[Message],
+        max_tokens: u32,
+        temperature: f32,
+    ) -> McpResult<mpsc::Receiver<McpResult<Message>>> {
+        McpClient::stream_completion(self, model, messages, max_tokens, temperature).await
+    }
+    
+    async fn cancel_streaming(&self, stream_id: &str) -> McpResult<()> {
+        McpClient::cancel_streaming(self, stream_id).await
+    }
+}
 
 /// Service for interacting with the MCP protocol
 pub struct McpService {
-    /// MCP client
-    client: Arc<McpClient>,
+    /// Provider completing conversations
+    client: Arc<dyn CompletionProvider>,
+    
+    /// Conversation storage
+    storage: Arc<StorageManager>,
     
     /// Available models
     models: Arc<RwLock<Vec<Model>>>,
@@ -41,16 +121,23 @@ impl McpService {
         // Create MCP configuration
         let mcp_config = McpConfig::with_api_key(api_key)
             .with_url(settings_guard.api.url.clone())
-            .with_model(settings_guard.api.model.clone());
+            .with_model(settings_guard.api.model.clone())
+            .with_auth_method(settings_guard.auth.method);
         
         // Create MCP client
         let client = Arc::new(McpClient::new(mcp_config));
         
+        Self::with_backends(client, get_storage_manager())
+    }
+    
+    /// Create a service on another provider and storage, e.g. in tests
+    pub fn with_backends(client: Arc<dyn CompletionProvider>, storage: Arc<StorageManager>) -> Self {
         // Define available models
         let models = Model::available_claude_models();
         
         Self {
             client,
+            storage,
             models: Arc::new(RwLock::new(models)),
             conversations: Arc::new(RwLock::new(HashMap::new())),
             streaming_sessions: Arc::new(Mutex::new(HashMap::new())),
@@ -60,7 +147,7 @@ impl McpService {
     /// Initialize the service - load saved data
     pub async fn initialize(&self) -> McpResult<()> {
         // Load saved conversations
-        let storage = get_storage_manager();
+        let storage = &self.storage;
         let conversations = storage.list_conversations()?;
         
         // Store in memory
@@ -115,7 +202,7 @@ impl McpService {
         }
         
         // Save to storage
-        let storage = get_storage_manager();
+        let storage = &self.storage;
         storage.save_conversation(&conversation)?;
         
         Ok(conversation)
@@ -132,7 +219,7 @@ impl McpService {
         }
         
         // Try to load from storage
-        let storage = get_storage_manager();
+        let storage = &self.storage;
         let conversation = storage.load_conversation(id)?;
         
         // Store in memory
@@ -153,29 +240,159 @@ impl McpService {
         }
         
         // Save to storage
-        let storage = get_storage_manager();
+        let storage = &self.storage;
         storage.save_conversation(&conversation)?;
         
         Ok(())
     }
     
-    /// Delete a conversation
+    /// Move a conversation to the trash; the deletion can be undone within the undo window
     pub async fn delete_conversation(&self, id: &str) -> McpResult<()> {
+        let title = match self.get_conversation(id).await {
+            Ok(conversation) => conversation.title,
+            Err(_) => id.to_string(),
+        };
+        
+        // Remove from storage
+        let storage = &self.storage;
+        storage.journaled(
+            OperationKind::DeleteConversation,
+            &format!("Delete '{}'", title),
+            &[id.to_string()],
+            || storage.delete_conversation(id),
+        )?;
+        
         // Remove from memory
         {
             let mut conversations = self.conversations.write().await;
             conversations.remove(id);
         }
         
-        // Remove from storage
-        let storage = get_storage_manager();
-        storage.delete_conversation(id)?;
-        
         Ok(())
     }
     
+    /// Conversations in the trash, most recently deleted first
+    pub async fn list_trashed(&self) -> McpResult<Vec<TrashedConversation>> {
+        self.storage.list_trashed()
+    }
+    
+    /// Move a conversation out of the trash
+    pub async fn restore_conversation(&self, id: &str) -> McpResult<Conversation> {
+        let conversation = self.storage.restore_from_trash(id)?;
+        
+        // Store in memory
+        {
+            let mut conversations = self.conversations.write().await;
+            conversations.insert(conversation.id.clone(), conversation.clone());
+        }
+        
+        Ok(conversation)
+    }
+    
+    /// Permanently delete a trashed conversation
+    pub async fn purge_conversation(&self, id: &str) -> McpResult<()> {
+        self.storage.purge_conversation(id)
+    }
+    
+    /// Permanently delete everything in the trash
+    pub async fn empty_trash(&self) -> McpResult<usize> {
+        self.storage.empty_trash()
+    }
+    
+    /// Draft of a conversation, if there is one
+    pub async fn load_draft(&self, conversation_id: &str) -> McpResult<Option<Draft>> {
+        self.storage.load_draft(conversation_id)
+    }
+    
+    /// Save the draft of a conversation
+    pub async fn save_draft(&self, draft: &Draft) -> McpResult<()> {
+        self.storage.save_draft(draft)
+    }
+    
+    /// Delete the draft of a conversation
+    pub async fn delete_draft(&self, conversation_id: &str) -> McpResult<()> {
+        self.storage.delete_draft(conversation_id)
+    }
+    
+    /// Apply a draft synced from another device if it is newer
+    pub async fn apply_draft(&self, draft: &Draft) -> McpResult<bool> {
+        self.storage.apply_draft(draft)
+    }
+    
+    /// Save changed conversations as one undoable operation
+    pub async fn update_conversations_journaled(
+        &self,
+        kind: OperationKind,
+        description: &str,
+        changed: Vec<Conversation>,
+    ) -> McpResult<JournalEntry> {
+        let ids: Vec<String> = changed.iter().map(|c| c.id.clone()).collect();
+        let storage = &self.storage;
+        let entry = storage.journaled(kind, description, &ids, || {
+            changed.iter().try_for_each(|c| storage.save_conversation(c))
+        })?;
+        
+        // Store in memory
+        {
+            let mut conversations = self.conversations.write().await;
+            for conversation in changed {
+                conversations.insert(conversation.id.clone(), conversation);
+            }
+        }
+        
+        Ok(entry)
+    }
+    
+    /// Bring the in-memory conversations in line with restored snapshots
+    async fn apply_snapshots(&self, snapshots: &[Snapshot]) {
+        let mut conversations = self.conversations.write().await;
+        for snapshot in snapshots {
+            match &snapshot.conversation {
+                Some(conversation) => {
+                    conversations.insert(snapshot.conversation_id.clone(), conversation.clone());
+                }
+                None => {
+                    conversations.remove(&snapshot.conversation_id);
+                }
+            }
+        }
+    }
+    
+    /// Undo the latest journaled operation
+    pub async fn undo(&self) -> McpResult<Option<JournalEntry>> {
+        let entry = self.storage.undo()?;
+        if let Some(entry) = &entry {
+            self.apply_snapshots(&entry.before).await;
+        }
+        Ok(entry)
+    }
+    
+    /// Redo the most recently undone operation
+    pub async fn redo(&self) -> McpResult<Option<JournalEntry>> {
+        let entry = self.storage.redo()?;
+        if let Some(entry) = &entry {
+            self.apply_snapshots(&entry.after).await;
+        }
+        Ok(entry)
+    }
+    
     /// Send a message in a conversation
     pub async fn send_message(&self, conversation_id: &str, message: Message) -> McpResult<Message> {
+        self.send(conversation_id, message, None).await
+    }
+    
+    /// Send a message in a conversation, handing the provider `sent` in place
+    /// of the stored conversation; what is stored stays as it was written
+    pub async fn send_message_as(
+        &self,
+        conversation_id: &str,
+        message: Message,
+        sent: Vec<Message>,
+    ) -> McpResult<Message> {
+        self.send(conversation_id, message, Some(sent)).await
+    }
+    
+    async fn send(&self, conversation_id: &str, message: Message, sent: Option<Vec<Message>>) -> McpResult<Message> {
         // Get conversation
         let mut conversation = self.get_conversation(conversation_id).await?;
         
@@ -190,18 +407,21 @@ impl McpService {
             self.connect().await?;
         }
         
-        // Get settings
-        let settings = get_settings();
-        let settings_guard = settings.lock().unwrap();
+        // Get settings; the lock is released before awaiting so the future stays Send
+        let (max_tokens, temperature) = {
+            let settings = get_settings();
+            let settings_guard = settings.lock().unwrap();
+            (settings_guard.model.max_tokens, settings_guard.model.temperature)
+        };
         
         // Send message to MCP server
         let response = self
             .client
             .send_completion(
                 &conversation.model.id,
-                &conversation.messages,
-                settings_guard.model.max_tokens,
-                settings_guard.model.temperature,
+                sent.as_deref().unwrap_or(&conversation.messages),
+                max_tokens,
+                temperature,
             )
             .await?;
         
@@ -219,6 +439,26 @@ impl McpService {
         &self,
         conversation_id: &str,
         message: Message,
+    ) -> McpResult<mpsc::Receiver<McpResult<Message>>> {
+        self.stream(conversation_id, message, None).await
+    }
+    
+    /// Start a streaming message in a conversation, handing the provider
+    /// `sent` in place of the stored conversation
+    pub async fn stream_message_as(
+        &self,
+        conversation_id: &str,
+        message: Message,
+        sent: Vec<Message>,
+    ) -> McpResult<mpsc::Receiver<McpResult<Message>>> {
+        self.stream(conversation_id, message, Some(sent)).await
+    }
+    
+    async fn stream(
+        &self,
+        conversation_id: &str,
+        message: Message,
+        sent: Option<Vec<Message>>,
     ) -> McpResult<mpsc::Receiver<McpResult<Message>>> {
         // Get conversation
         let mut conversation = self.get_conversation(conversation_id).await?;
@@ -234,9 +474,12 @@ impl McpService {
             self.connect().await?;
         }
         
-        // Get settings
-        let settings = get_settings();
-        let settings_guard = settings.lock().unwrap();
+        // Get settings; the lock is released before awaiting so the future stays Send
+        let (max_tokens, temperature) = {
+            let settings = get_settings();
+            let settings_guard = settings.lock().unwrap();
+            (settings_guard.model.max_tokens, settings_guard.model.temperature)
+        };
         
         // Create streaming channel
         let (tx, rx) = mpsc::channel(32);
@@ -250,9 +493,7 @@ impl McpService {
         // Start streaming
         let client_clone = self.client.clone();
         let model_id = conversation.model.id.clone();
-        let messages = conversation.messages.clone();
-        let max_tokens = settings_guard.model.max_tokens;
-        let temperature = settings_guard.model.temperature;
+        let messages = sent.unwrap_or_else(|| conversation.messages.clone());
         let session_id = message.id.clone();
         let conversation_id = conversation_id.to_string();
         let service = Arc::new(self.clone());
@@ -273,15 +514,24 @@ impl McpService {
                             }],
                         },
                         metadata: None,
-                        created_at: SystemTime::now(),
+                        feedback: None,
+                        created_at: clock::system_now(),
                     };
                     
                     // Process streaming chunks
                     while let Some(chunk) = receiver.recv().await {
+                        let chunk = match chunk {
+                            Ok(chunk) => chunk,
+                            Err(e) => {
+                                let _ = tx.send(Err(e)).await;
+                                break;
+                            }
+                        };
+                        
                         // Accumulate text
-                        if let crate::models::ContentType::Text { ref text } = chunk.content.parts[0] {
+                        if let Some(crate::models::ContentType::Text { text: delta }) = chunk.content.parts.first() {
                             if let crate::models::ContentType::Text { ref mut text } = full_response.content.parts[0] {
-                                *text = text.clone();
+                                text.push_str(delta);
                             }
                         }
                         
@@ -335,6 +585,7 @@ impl Clone for McpService {
     fn clone(&self) -> Self {
         Self {
             client: self.client.clone(),
+            storage: self.storage.clone(),
             models: self.models.clone(),
             conversations: self.conversations.clone(),
             streaming_sessions: self.streaming_sessions.clone(),
@@ -0,0 +1,491 @@
+//! Meeting summaries
+//!
+//! A transcript, read from a file or collected while the meeting runs, is
+//! summarized by a model into a summary, the decisions taken and action items
+//! with owners and due dates. Due dates are resolved against the meeting date,
+//! so "by Monday" lands on a calendar day. The notes export to Markdown, and
+//! the action items to an ICS file of tasks calendar apps import.
+
+use chrono::{Datelike, NaiveDate};
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::error::{McpError, McpResult};
+use crate::models::Model;
+use crate::service::chat::ChatService;
+use crate::utils::clock;
+
+/// Transcript characters sent in one request; longer meetings are
+/// summarized in parts and the parts combined
+const CHUNK_CHARS: usize = 24_000;
+
+/// Longest text before a colon that still counts as a speaker name
+const MAX_SPEAKER_CHARS: usize = 40;
+
+/// Leading timestamps such as `[00:01:02]` or `12:30 -`
+static TIMESTAMP: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[?\(?\d{1,2}:\d{2}(:\d{2})?([.,]\d+)?\)?\]?\s*-?\s*").unwrap());
+
+/// WebVTT voice tags: `<v Ada>text</v>`
+static VOICE_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"^<v(?:\.[^ >]*)?\s+([^>]+)>(.*?)(?:</v>)?$").unwrap());
+
+/// How long and in what shape the summary is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryFormat {
+    /// A few paragraphs
+    #[default]
+    Detailed,
+    /// Two or three sentences
+    Concise,
+    /// Bullet points
+    Bullet,
+}
+
+impl SummaryFormat {
+    fn instructions(&self) -> &'static str {
+        match self {
+            SummaryFormat::Detailed => "a few paragraphs covering each topic discussed",
+            SummaryFormat::Concise => "two or three sentences",
+            SummaryFormat::Bullet => "bullet points, one per topic, each starting with \"- \"",
+        }
+    }
+}
+
+impl std::str::FromStr for SummaryFormat {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "detailed" => Ok(SummaryFormat::Detailed),
+            "concise" => Ok(SummaryFormat::Concise),
+            "bullet" | "bullets" => Ok(SummaryFormat::Bullet),
+            other => Err(McpError::InvalidRequest(format!(
+                "Unknown summary format '{}'; expected detailed, concise or bullet",
+                other
+            ))),
+        }
+    }
+}
+
+/// One utterance of a transcript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptLine {
+    /// Who spoke, when the transcript says
+    pub speaker: Option<String>,
+
+    /// What was said
+    pub text: String,
+}
+
+/// A meeting transcript
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Transcript {
+    /// Utterances in order
+    pub lines: Vec<TranscriptLine>,
+}
+
+impl Transcript {
+    /// Read a transcript: `Speaker: text` lines, optionally timestamped, or
+    /// WebVTT and SRT captions
+    pub fn parse(text: &str) -> Self {
+        let mut transcript = Self::default();
+        transcript.push_text(text);
+        transcript
+    }
+
+    /// Add text as it comes in during a live meeting
+    pub fn push_text(&mut self, text: &str) {
+        for line in text.lines() {
+            if let Some(line) = parse_line(line) {
+                self.lines.push(line);
+            }
+        }
+    }
+
+    /// Whether nothing was said
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Speakers in order of first appearance
+    pub fn participants(&self) -> Vec<String> {
+        let mut participants: Vec<String> = Vec::new();
+        for speaker in self.lines.iter().filter_map(|l| l.speaker.as_ref()) {
+            if !participants.contains(speaker) {
+                participants.push(speaker.clone());
+            }
+        }
+        participants
+    }
+
+    /// The transcript as `Speaker: text` lines
+    pub fn to_text(&self) -> String {
+        self.lines.iter().map(format_line).collect::<Vec<_>>().join("\n")
+    }
+
+    /// The transcript in parts of at most `max_chars`, split between lines
+    fn chunks(&self, max_chars: usize) -> Vec<String> {
+        let mut chunks = Vec::new();
+        let mut current = String::new();
+        for line in self.lines.iter().map(format_line) {
+            if !current.is_empty() && current.len() + line.len() + 1 > max_chars {
+                chunks.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push('\n');
+            }
+            current.push_str(&line);
+        }
+        if !current.is_empty() {
+            chunks.push(current);
+        }
+        chunks
+    }
+}
+
+fn format_line(line: &TranscriptLine) -> String {
+    match &line.speaker {
+        Some(speaker) => format!("{}: {}", speaker, line.text),
+        None => line.text.clone(),
+    }
+}
+
+/// One transcript line, or `None` for caption numbering, timings and blanks
+fn parse_line(line: &str) -> Option<TranscriptLine> {
+    let line = line.trim();
+    let is_numbering = line.chars().all(|c| c.is_ascii_digit());
+    if line.is_empty() || is_numbering || line == "WEBVTT" || line.contains("-->") {
+        return None;
+    }
+
+    let line = TIMESTAMP.replace(line, "");
+    let line = line.trim();
+    if let Some(captures) = VOICE_TAG.captures(line) {
+        return Some(TranscriptLine {
+            speaker: Some(captures[1].trim().to_string()),
+            text: captures[2].trim().to_string(),
+        });
+    }
+
+    if let Some((name, text)) = line.split_once(':') {
+        let name = name.trim();
+        let is_speaker = !name.is_empty()
+            && name.chars().count() <= MAX_SPEAKER_CHARS
+            && name.split_whitespace().count() <= 3
+            && name.chars().next().is_some_and(char::is_alphabetic)
+            && !text.starts_with("//");
+        if is_speaker && !text.trim().is_empty() {
+            return Some(TranscriptLine {
+                speaker: Some(name.to_string()),
+                text: text.trim().to_string(),
+            });
+        }
+    }
+    Some(TranscriptLine {
+        speaker: None,
+        text: line.to_string(),
+    })
+}
+
+/// A task agreed in the meeting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionItem {
+    /// What has to be done
+    pub task: String,
+
+    /// Who does it
+    #[serde(default)]
+    pub owner: Option<String>,
+
+    /// When it is due
+    #[serde(default)]
+    pub due: Option<NaiveDate>,
+}
+
+/// What came out of a meeting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeetingNotes {
+    /// Meeting title
+    pub title: String,
+
+    /// Day of the meeting
+    pub date: NaiveDate,
+
+    /// Who spoke
+    pub participants: Vec<String>,
+
+    /// Summary in the requested format
+    pub summary: String,
+
+    /// Decisions taken
+    pub decisions: Vec<String>,
+
+    /// Tasks agreed
+    pub action_items: Vec<ActionItem>,
+}
+
+/// Structured reply asked of the model
+#[derive(Debug, Deserialize)]
+struct NotesReply {
+    #[serde(default)]
+    title: Option<String>,
+    summary: String,
+    #[serde(default)]
+    decisions: Vec<String>,
+    #[serde(default)]
+    action_items: Vec<ActionItemReply>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ActionItemReply {
+    task: String,
+    #[serde(default)]
+    owner: Option<String>,
+    #[serde(default)]
+    due: Option<String>,
+}
+
+impl MeetingNotes {
+    /// Read the model's JSON reply, tolerating code fences and text around
+    /// the object. Due dates that aren't `YYYY-MM-DD` are dropped.
+    pub fn from_reply(reply: &str, date: NaiveDate, participants: Vec<String>) -> McpResult<Self> {
+        let start = reply.find('{');
+        let end = reply.rfind('}');
+        let json = match (start, end) {
+            (Some(start), Some(end)) if start < end => &reply[start..=end],
+            _ => return Err(McpError::Protocol("The model didn't reply with meeting notes".to_string())),
+        };
+        let parsed: NotesReply = serde_json::from_str(json)
+            .map_err(|e| McpError::Protocol(format!("The model's meeting notes are malformed: {}", e)))?;
+
+        let present = |text: Option<String>| text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty() && t != "null");
+        Ok(Self {
+            title: present(parsed.title).unwrap_or_else(|| "Meeting".to_string()),
+            date,
+            participants,
+            summary: parsed.summary.trim().to_string(),
+            decisions: parsed.decisions.into_iter().filter(|d| !d.trim().is_empty()).collect(),
+            action_items: parsed
+                .action_items
+                .into_iter()
+                .filter(|item| !item.task.trim().is_empty())
+                .map(|item| ActionItem {
+                    task: item.task.trim().to_string(),
+                    owner: present(item.owner),
+                    due: item.due.and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok()),
+                })
+                .collect(),
+        })
+    }
+
+    /// Notes as a Markdown document, action items as a task list
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# {}\n\n*{}*", self.title, self.date.format("%A, %B %-d, %Y"));
+        if !self.participants.is_empty() {
+            md.push_str(&format!(" · {}", self.participants.join(", ")));
+        }
+        md.push_str(&format!("\n\n## Summary\n\n{}\n", self.summary));
+
+        if !self.decisions.is_empty() {
+            md.push_str("\n## Decisions\n\n");
+            for decision in &self.decisions {
+                md.push_str(&format!("- {}\n", decision));
+            }
+        }
+        if !self.action_items.is_empty() {
+            md.push_str("\n## Action items\n\n");
+            for item in &self.action_items {
+                let mut details = Vec::new();
+                if let Some(owner) = &item.owner {
+                    details.push(owner.clone());
+                }
+                if let Some(due) = item.due {
+                    details.push(format!("due {}", due));
+                }
+                if details.is_empty() {
+                    md.push_str(&format!("- [ ] {}\n", item.task));
+                } else {
+                    md.push_str(&format!("- [ ] {} ({})\n", item.task, details.join(", ")));
+                }
+            }
+        }
+        md
+    }
+
+    /// Action items as an iCalendar file of tasks. Each task keeps its UID
+    /// when exported again, so importing twice updates instead of duplicating.
+    pub fn to_ics(&self) -> String {
+        let stamp = clock::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//Papin//Meeting notes//EN".to_string(),
+        ];
+        for item in &self.action_items {
+            let key = format!("{}|{}|{}", self.title, self.date, item.task);
+            let hash = digest::digest(&digest::SHA256, key.as_bytes());
+            let uid: String = hash.as_ref()[..12].iter().map(|b| format!("{:02x}", b)).collect();
+
+            let mut description = format!("From \"{}\" on {}", self.title, self.date);
+            if let Some(owner) = &item.owner {
+                description.push_str(&format!("\nOwner: {}", owner));
+            }
+
+            lines.push("BEGIN:VTODO".to_string());
+            lines.push(format!("UID:{}@papin", uid));
+            lines.push(format!("DTSTAMP:{}", stamp));
+            lines.push(format!("SUMMARY:{}", ics_escape(&item.task)));
+            lines.push(format!("DESCRIPTION:{}", ics_escape(&description)));
+            if let Some(due) = item.due {
+                lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+            }
+            lines.push("STATUS:NEEDS-ACTION".to_string());
+            lines.push("END:VTODO".to_string());
+        }
+        lines.push("END:VCALENDAR".to_string());
+
+        lines.iter().map(|line| ics_fold(line) + "\r\n").collect()
+    }
+}
+
+/// Escape text for an iCalendar property value
+fn ics_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line at 75 octets without splitting a character
+fn ics_fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+/// Instructions for the summarizing model
+fn system_prompt(format: SummaryFormat, date: NaiveDate) -> String {
+    format!(
+        "You write meeting notes from transcripts. Reply with only a JSON object: \
+{{\"title\": a short title, \"summary\": {}, \"decisions\": [each decision taken, as a sentence], \
+\"action_items\": [{{\"task\": what to do, \"owner\": who does it or null, \"due\": \"YYYY-MM-DD\" or null}}]}}. \
+The meeting took place on {}, a {}; resolve relative dates such as \"by Monday\" from it. \
+List only decisions and tasks the transcript states.",
+        format.instructions(),
+        date,
+        date.weekday()
+    )
+}
+
+/// Summarizes transcripts with a model
+pub struct MeetingSummarizer {
+    /// Chat service used for the model calls
+    chat_service: Arc<ChatService>,
+
+    /// Model to use; the default model when unset
+    model: Option<Model>,
+
+    /// Summary format
+    format: SummaryFormat,
+}
+
+impl MeetingSummarizer {
+    /// Create a summarizer on the default model
+    pub fn new(chat_service: Arc<ChatService>) -> Self {
+        Self {
+            chat_service,
+            model: None,
+            format: SummaryFormat::default(),
+        }
+    }
+
+    /// Use a specific model
+    pub fn with_model(mut self, model: Model) -> Self {
+        self.model = Some(model);
+        self
+    }
+
+    /// Write the summary in a format
+    pub fn with_format(mut self, format: SummaryFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Summarize a transcript of a meeting held on `date`
+    pub async fn summarize(&self, transcript: &Transcript, title: Option<&str>, date: NaiveDate) -> McpResult<MeetingNotes> {
+        if transcript.is_empty() {
+            return Err(McpError::InvalidRequest("The transcript is empty".to_string()));
+        }
+        let system = system_prompt(self.format, date);
+        let chunks = transcript.chunks(CHUNK_CHARS);
+        info!("Summarizing a meeting transcript in {} part(s)", chunks.len());
+
+        let reply = if chunks.len() == 1 {
+            self.ask(&system, &format!("Transcript:\n{}", chunks[0])).await?
+        } else {
+            // Notes of each part, then notes of the notes
+            let mut parts = Vec::with_capacity(chunks.len());
+            for (i, chunk) in chunks.iter().enumerate() {
+                let prompt = format!("Transcript, part {} of {}:\n{}", i + 1, chunks.len(), chunk);
+                let reply = self.ask(&system, &prompt).await?;
+                let notes = MeetingNotes::from_reply(&reply, date, Vec::new())?;
+                parts.push(serde_json::to_string(&serde_json::json!({
+                    "summary": notes.summary,
+                    "decisions": notes.decisions,
+                    "action_items": notes.action_items,
+                }))?);
+            }
+            let prompt = format!(
+                "These are notes of consecutive parts of one meeting. Combine them into notes of the whole \
+meeting, merging duplicate decisions and tasks:\n{}",
+                parts.join("\n")
+            );
+            self.ask(&system, &prompt).await?
+        };
+
+        let mut notes = MeetingNotes::from_reply(&reply, date, transcript.participants())?;
+        if let Some(title) = title {
+            notes.title = title.to_string();
+        }
+        Ok(notes)
+    }
+
+    /// One request in a throwaway conversation
+    async fn ask(&self, system: &str, prompt: &str) -> McpResult<String> {
+        let conversation = self
+            .chat_service
+            .create_conversation("Meeting summary", self.model.clone())
+            .await?;
+
+        let result = async {
+            self.chat_service.set_system_message(&conversation.id, system).await?;
+            self.chat_service.send_message(&conversation.id, prompt).await
+        }
+        .await;
+
+        // The transcript shouldn't linger in the conversation list or the trash
+        let cleanup = match self.chat_service.delete_conversation(&conversation.id).await {
+            Ok(()) => self.chat_service.purge_conversation(&conversation.id).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = cleanup {
+            debug!("Failed to delete the summary conversation: {}", e);
+        }
+
+        Ok(result?.text())
+    }
+}
@@ -0,0 +1,288 @@
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::error::{McpError, McpResult};
+use crate::models::Message;
+
+/// Stage of the message pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStage {
+    /// Before a user message is sent
+    PreSend,
+    /// After a complete response is received
+    PostReceive,
+    /// For each streamed chunk
+    StreamChunk,
+    /// After the last streamed chunk
+    StreamEnd,
+}
+
+/// Per-message state shared by the middlewares of one request
+#[derive(Debug, Clone, Default)]
+pub struct MiddlewareContext {
+    /// Conversation the message belongs to
+    pub conversation_id: String,
+
+    /// Values middlewares pass to each other (e.g. detected language, token counts)
+    pub values: HashMap<String, serde_json::Value>,
+}
+
+impl MiddlewareContext {
+    /// Create a context for a conversation
+    pub fn new(conversation_id: &str) -> Self {
+        Self {
+            conversation_id: conversation_id.to_string(),
+            values: HashMap::new(),
+        }
+    }
+}
+
+/// A hook into the message flow.
+///
+/// Every stage has a no-op default so middlewares only implement what they need.
+#[async_trait]
+pub trait MessageMiddleware: Send + Sync {
+    /// Unique middleware name
+    fn name(&self) -> &str;
+
+    /// Called before a user message is sent
+    async fn pre_send(&self, _ctx: &mut MiddlewareContext, _message: &mut Message) -> McpResult<()> {
+        Ok(())
+    }
+
+    /// Called after a complete response is received
+    async fn post_receive(&self, _ctx: &mut MiddlewareContext, _message: &mut Message) -> McpResult<()> {
+        Ok(())
+    }
+
+    /// Called for each streamed chunk
+    async fn on_stream_chunk(&self, _ctx: &mut MiddlewareContext, _chunk: &mut Message) -> McpResult<()> {
+        Ok(())
+    }
+
+    /// Called once after the last streamed chunk with an empty chunk, to send
+    /// text held back from earlier chunks
+    async fn on_stream_end(&self, _ctx: &mut MiddlewareContext, _chunk: &mut Message) -> McpResult<()> {
+        Ok(())
+    }
+}
+
+/// Registration options for a middleware
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MiddlewareOptions {
+    /// Lower values run first
+    pub priority: i32,
+
+    /// Whether a failure aborts the request; otherwise the error is logged and the
+    /// message continues unchanged by this middleware
+    pub required: bool,
+}
+
+impl Default for MiddlewareOptions {
+    fn default() -> Self {
+        Self {
+            priority: 100,
+            required: false,
+        }
+    }
+}
+
+/// Timing and error counters for one middleware
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MiddlewareMetrics {
+    /// Middleware name
+    pub name: String,
+
+    /// Invocations per stage
+    pub calls: HashMap<PipelineStage, u64>,
+
+    /// Total time spent per stage in microseconds
+    pub total_micros: HashMap<PipelineStage, u64>,
+
+    /// Failures across all stages
+    pub errors: u64,
+}
+
+impl MiddlewareMetrics {
+    /// Average time per call for a stage
+    pub fn average(&self, stage: PipelineStage) -> Duration {
+        let calls = self.calls.get(&stage).copied().unwrap_or(0);
+        if calls == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_micros(self.total_micros.get(&stage).copied().unwrap_or(0) / calls)
+    }
+}
+
+struct RegisteredMiddleware {
+    middleware: Arc<dyn MessageMiddleware>,
+    options: MiddlewareOptions,
+    metrics: Mutex<MiddlewareMetrics>,
+}
+
+/// Ordered chain of middlewares applied to every message
+#[derive(Default)]
+pub struct MessagePipeline {
+    middlewares: RwLock<Vec<Arc<RegisteredMiddleware>>>,
+}
+
+impl MessagePipeline {
+    /// Create an empty pipeline
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a middleware; replaces an existing one with the same name
+    pub fn register(&self, middleware: Arc<dyn MessageMiddleware>, options: MiddlewareOptions) {
+        let name = middleware.name().to_string();
+        let registered = Arc::new(RegisteredMiddleware {
+            metrics: Mutex::new(MiddlewareMetrics {
+                name: name.clone(),
+                ..Default::default()
+            }),
+            middleware,
+            options,
+        });
+
+        let mut middlewares = self.middlewares.write().unwrap();
+        middlewares.retain(|m| m.middleware.name() != name);
+        middlewares.push(registered);
+        // Stable sort keeps registration order among equal priorities
+        middlewares.sort_by_key(|m| m.options.priority);
+
+        debug!("Registered message middleware {}", name);
+    }
+
+    /// Remove a middleware by name
+    pub fn unregister(&self, name: &str) -> bool {
+        let mut middlewares = self.middlewares.write().unwrap();
+        let before = middlewares.len();
+        middlewares.retain(|m| m.middleware.name() != name);
+        middlewares.len() != before
+    }
+
+    /// Names of registered middlewares in execution order
+    pub fn names(&self) -> Vec<String> {
+        self.middlewares
+            .read()
+            .unwrap()
+            .iter()
+            .map(|m| m.middleware.name().to_string())
+            .collect()
+    }
+
+    /// Timing and error metrics per middleware
+    pub fn metrics(&self) -> Vec<MiddlewareMetrics> {
+        self.middlewares
+            .read()
+            .unwrap()
+            .iter()
+            .map(|m| m.metrics.lock().unwrap().clone())
+            .collect()
+    }
+
+    /// Run the pre-send stage
+    pub async fn pre_send(&self, ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        self.run(PipelineStage::PreSend, ctx, message).await
+    }
+
+    /// Run the post-receive stage
+    pub async fn post_receive(&self, ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        self.run(PipelineStage::PostReceive, ctx, message).await
+    }
+
+    /// Run the stream-chunk stage
+    pub async fn on_stream_chunk(&self, ctx: &mut MiddlewareContext, chunk: &mut Message) -> McpResult<()> {
+        self.run(PipelineStage::StreamChunk, ctx, chunk).await
+    }
+
+    /// Run the stream-end stage
+    pub async fn on_stream_end(&self, ctx: &mut MiddlewareContext, chunk: &mut Message) -> McpResult<()> {
+        self.run(PipelineStage::StreamEnd, ctx, chunk).await
+    }
+
+    async fn run(&self, stage: PipelineStage, ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        // Snapshot the chain so registration changes don't block in-flight messages
+        let middlewares: Vec<_> = self.middlewares.read().unwrap().clone();
+
+        for registered in middlewares {
+            // Work on a copy so a failing optional middleware leaves the message untouched
+            let mut candidate = message.clone();
+            let started = Instant::now();
+
+            let result = match stage {
+                PipelineStage::PreSend => registered.middleware.pre_send(ctx, &mut candidate).await,
+                PipelineStage::PostReceive => registered.middleware.post_receive(ctx, &mut candidate).await,
+                PipelineStage::StreamChunk => registered.middleware.on_stream_chunk(ctx, &mut candidate).await,
+                PipelineStage::StreamEnd => registered.middleware.on_stream_end(ctx, &mut candidate).await,
+            };
+
+            let elapsed = started.elapsed().as_micros() as u64;
+            {
+                let mut metrics = registered.metrics.lock().unwrap();
+                *metrics.calls.entry(stage).or_insert(0) += 1;
+                *metrics.total_micros.entry(stage).or_insert(0) += elapsed;
+                if result.is_err() {
+                    metrics.errors += 1;
+                }
+            }
+
+            match result {
+                Ok(()) => *message = candidate,
+                Err(e) if registered.options.required => {
+                    return Err(McpError::InvalidRequest(format!(
+                        "Middleware {} failed: {}",
+                        registered.middleware.name(),
+                        e
+                    )));
+                }
+                Err(e) => {
+                    warn!(
+                        "Middleware {} failed during {:?}, skipping: {}",
+                        registered.middleware.name(),
+                        stage,
+                        e
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Built-in middleware that records estimated token counts in the message metadata
+pub struct TokenCountMiddleware;
+
+impl TokenCountMiddleware {
+    fn annotate(message: &mut Message) {
+        // Rough estimate: about four characters per token
+        let tokens = message.text().chars().count().div_ceil(4);
+        message
+            .metadata
+            .get_or_insert_with(HashMap::new)
+            .insert("estimated_tokens".to_string(), serde_json::json!(tokens));
+    }
+}
+
+#[async_trait]
+impl MessageMiddleware for TokenCountMiddleware {
+    fn name(&self) -> &str {
+        "token_count"
+    }
+
+    async fn pre_send(&self, _ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        Self::annotate(message);
+        Ok(())
+    }
+
+    async fn post_receive(&self, _ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        Self::annotate(message);
+        Ok(())
+    }
+}
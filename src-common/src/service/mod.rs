@@ -1,6 +1,31 @@
+pub mod analytics;
+pub mod apply;
+pub mod batch;
+pub mod bench;
+pub mod capabilities;
 pub mod chat;
+pub mod credentials;
+pub mod dry_run;
+pub mod estimate;
+pub mod evals;
+pub mod experiments;
+pub mod feedback;
+pub mod filters;
+pub mod limits;
 pub mod mcp;
+pub mod meetings;
+pub mod middleware;
+pub mod onboarding;
+pub mod performance;
+pub mod policy;
+pub mod pricing;
+pub mod routing;
+pub mod script;
+pub mod stream;
+pub mod translation;
+pub mod unfurl;
 
 // Re-export main services
 pub use chat::ChatService;
 pub use mcp::McpService;
+pub use middleware::{MessageMiddleware, MessagePipeline, MiddlewareContext, MiddlewareOptions};
@@ -0,0 +1,243 @@
+use chrono::Utc;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::credentials::{self, validate_key, CredentialStatus};
+use crate::config::{get_settings, OfflineSettings, Settings, TelemetryConsent, UserProfile};
+use crate::error::{McpError, McpResult};
+
+/// Models offered as the default
+pub const MODEL_CHOICES: &[&str] = &[
+    "claude-3-opus-20240229",
+    "claude-3-sonnet-20240229",
+    "claude-3-haiku-20240307",
+];
+
+/// Local models offered for offline use, smallest first, with the memory
+/// they need in GiB
+pub const LOCAL_MODEL_CHOICES: &[(&str, u64)] = &[("tinyllama", 4), ("redpajama-mini", 6)];
+
+/// Minimum CPU cores for local inference to be usable
+const MIN_LOCAL_CORES: usize = 2;
+
+/// Outcome of checking an API key against the API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ApiKeyCheck {
+    /// The API accepted the key
+    Valid,
+    /// The API rejected the key
+    Invalid { message: String },
+    /// The API couldn't be reached, so the key is unverified
+    Unreachable { message: String },
+}
+
+/// Check an API key before saving it
+pub async fn check_api_key(api_url: &str, api_key: &str) -> ApiKeyCheck {
+    if api_key.trim().is_empty() {
+        return ApiKeyCheck::Invalid {
+            message: "API key is empty".to_string(),
+        };
+    }
+
+    let check = validate_key(api_url, api_key).await;
+    match check.status {
+        // Rate limits and exhausted credit still mean the key was accepted
+        status if status.key_accepted() => ApiKeyCheck::Valid,
+        CredentialStatus::InvalidKey | CredentialStatus::Missing => ApiKeyCheck::Invalid { message: check.message },
+        _ => ApiKeyCheck::Unreachable { message: check.message },
+    }
+}
+
+/// What this machine can run locally
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardwareScan {
+    /// Logical CPU cores
+    pub cpu_cores: usize,
+
+    /// Total memory in bytes; 0 when unknown
+    pub memory_bytes: u64,
+
+    /// Local models that fit, smallest first
+    pub local_models: Vec<String>,
+
+    /// Local model suggested for offline use
+    pub recommended_local_model: Option<String>,
+}
+
+impl HardwareScan {
+    /// Whether any local model can run
+    pub fn can_run_local(&self) -> bool {
+        !self.local_models.is_empty()
+    }
+}
+
+/// Scan CPU and memory to decide which local models can run
+pub fn scan_hardware() -> HardwareScan {
+    let cpu_cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let memory_bytes = sys_info::mem_info().map(|m| m.total * 1024).unwrap_or(0);
+    let memory_gib = memory_bytes / (1024 * 1024 * 1024);
+
+    let local_models: Vec<String> = if cpu_cores >= MIN_LOCAL_CORES {
+        LOCAL_MODEL_CHOICES
+            .iter()
+            .filter(|(_, needed)| memory_gib >= *needed)
+            .map(|(model, _)| model.to_string())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    HardwareScan {
+        cpu_cores,
+        memory_bytes,
+        recommended_local_model: local_models.last().cloned(),
+        local_models,
+    }
+}
+
+/// Everything the setup wizard asks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnboardingAnswers {
+    /// New API key; `None` keeps the configured one
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// Default model
+    pub model: String,
+
+    /// Use a local model when offline
+    #[serde(default)]
+    pub offline_enabled: bool,
+
+    /// Local model for offline use
+    #[serde(default)]
+    pub local_model: Option<String>,
+
+    /// Share anonymous usage data
+    #[serde(default)]
+    pub telemetry: bool,
+
+    /// Profile display name
+    pub profile_name: String,
+
+    /// Profile email
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+/// Current configuration, to prefill the wizard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnboardingState {
+    /// Whether setup has been completed before
+    pub completed: bool,
+
+    /// Whether an API key is stored
+    pub has_api_key: bool,
+
+    /// Models to choose from
+    pub models: Vec<String>,
+
+    /// Answers matching the current settings
+    pub answers: OnboardingAnswers,
+}
+
+/// Current configuration as wizard answers
+pub fn onboarding_state() -> OnboardingState {
+    let settings = get_settings().lock().unwrap().clone();
+    let has_api_key = matches!(settings.get_api_key(), Ok(Some(_)));
+
+    OnboardingState {
+        completed: settings.onboarded_at.is_some(),
+        has_api_key,
+        models: MODEL_CHOICES.iter().map(|m| m.to_string()).collect(),
+        answers: OnboardingAnswers {
+            api_key: None,
+            model: settings.api.model.clone(),
+            offline_enabled: settings.offline.enabled,
+            local_model: settings.offline.local_model.clone(),
+            telemetry: settings.telemetry.enabled,
+            profile_name: settings.profile.as_ref().map(|p| p.name.clone()).unwrap_or_default(),
+            email: settings.profile.as_ref().and_then(|p| p.email.clone()),
+        },
+    }
+}
+
+/// Check answers for problems that don't need the network
+pub fn validate_answers(answers: &OnboardingAnswers, has_api_key: bool) -> McpResult<()> {
+    let invalid = |message: &str| Err(McpError::InvalidRequest(message.to_string()));
+
+    let new_key = answers.api_key.as_deref().map(str::trim);
+    if new_key == Some("") {
+        return invalid("API key cannot be empty");
+    }
+    if new_key.is_none() && !has_api_key && !answers.offline_enabled {
+        return invalid("An API key is required unless offline mode is enabled");
+    }
+    if answers.model.trim().is_empty() {
+        return invalid("Choose a default model");
+    }
+    if answers.offline_enabled && answers.local_model.as_deref().is_none_or(|m| m.trim().is_empty()) {
+        return invalid("Choose a local model for offline mode");
+    }
+    if answers.profile_name.trim().is_empty() {
+        return invalid("Profile name cannot be empty");
+    }
+    if let Some(email) = answers.email.as_deref().filter(|e| !e.trim().is_empty()) {
+        let valid = email
+            .split_once('@')
+            .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'));
+        if !valid {
+            return invalid("Email address is not valid");
+        }
+    }
+    Ok(())
+}
+
+/// Validate the answers, verify a new API key and write the complete
+/// configuration. An API that can't be reached doesn't block setup.
+pub async fn complete_onboarding(answers: OnboardingAnswers) -> McpResult<Settings> {
+    let (api_url, has_api_key) = {
+        let settings = get_settings().lock().unwrap().clone();
+        (settings.api.url.clone(), matches!(settings.get_api_key(), Ok(Some(_))))
+    };
+    validate_answers(&answers, has_api_key)?;
+
+    if let Some(api_key) = answers.api_key.as_deref() {
+        match check_api_key(&api_url, api_key).await {
+            ApiKeyCheck::Valid => {}
+            ApiKeyCheck::Invalid { message } => return Err(McpError::Authentication(message)),
+            ApiKeyCheck::Unreachable { message } => {
+                warn!("Couldn't verify the API key, saving it anyway: {}", message)
+            }
+        }
+    }
+
+    let key_changed = answers.api_key.is_some();
+    let settings = get_settings();
+    let mut settings = settings.lock().unwrap();
+    if let Some(api_key) = answers.api_key.as_deref() {
+        settings.set_api_key(api_key.trim())?;
+    }
+    settings.api.model = answers.model.trim().to_string();
+    settings.offline = OfflineSettings {
+        enabled: answers.offline_enabled,
+        local_model: answers.local_model.filter(|_| answers.offline_enabled),
+    };
+    settings.telemetry = TelemetryConsent {
+        enabled: answers.telemetry,
+        decided_at: Some(Utc::now()),
+    };
+    settings.profile = Some(UserProfile {
+        name: answers.profile_name.trim().to_string(),
+        email: answers.email.map(|e| e.trim().to_string()).filter(|e| !e.is_empty()),
+    });
+    settings.onboarded_at = Some(Utc::now());
+    settings.save()?;
+    if key_changed {
+        credentials::invalidate();
+    }
+
+    info!("Onboarding completed");
+    Ok(settings.clone())
+}
@@ -0,0 +1,110 @@
+//! How a reply was produced: the provider and model that served it, how long
+//! it took and how many failed attempts came before it. Kept in the reply's
+//! metadata so setups can be compared later.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::models::{Conversation, Message, MessageRole, Model};
+
+/// Metadata key of a reply's performance
+pub const PERFORMANCE_METADATA_KEY: &str = "performance";
+
+/// Provider, model and timings of a reply
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponsePerformance {
+    /// Provider that served the reply
+    pub provider: String,
+
+    /// Model that served the reply
+    pub model: String,
+
+    /// Version of that model
+    #[serde(default)]
+    pub model_version: String,
+
+    /// Time from sending the message to the first text of the reply, in
+    /// milliseconds; the whole duration for replies that aren't streamed
+    pub time_to_first_token_ms: u64,
+
+    /// Time from sending the message to the end of the reply, in milliseconds
+    pub duration_ms: u64,
+
+    /// Messages sent right before this one that got no reply
+    #[serde(default)]
+    pub retries: u32,
+}
+
+impl ResponsePerformance {
+    /// Performance of a reply served by a model
+    pub fn new(model: &Model, first_token: Duration, duration: Duration, retries: u32) -> Self {
+        Self {
+            provider: model.provider.clone(),
+            model: model.id.clone(),
+            model_version: model.version.clone(),
+            time_to_first_token_ms: first_token.as_millis() as u64,
+            duration_ms: duration.as_millis() as u64,
+            retries,
+        }
+    }
+
+    /// Performance kept in a message's metadata
+    pub fn of(message: &Message) -> Option<Self> {
+        message
+            .metadata
+            .as_ref()?
+            .get(PERFORMANCE_METADATA_KEY)
+            .and_then(|p| serde_json::from_value(p.clone()).ok())
+    }
+
+    /// Keep the performance in a message's metadata
+    pub fn attach(&self, message: &mut Message) {
+        message.metadata.get_or_insert_with(Default::default).insert(
+            PERFORMANCE_METADATA_KEY.to_string(),
+            serde_json::to_value(self).unwrap_or_default(),
+        );
+    }
+
+    /// One line for display, like
+    /// "claude-3-opus 20240229 (anthropic) · first token 420 ms · 2.1 s · 1 retry"
+    pub fn summary(&self) -> String {
+        let model = if self.model_version.is_empty() {
+            self.model.clone()
+        } else {
+            format!("{} {}", self.model, self.model_version)
+        };
+        let mut summary = format!(
+            "{} ({}) · first token {} · {}",
+            model,
+            self.provider,
+            format_millis(self.time_to_first_token_ms),
+            format_millis(self.duration_ms)
+        );
+        match self.retries {
+            0 => {}
+            1 => summary.push_str(" · 1 retry"),
+            n => summary.push_str(&format!(" · {} retries", n)),
+        }
+        summary
+    }
+}
+
+/// Milliseconds under a second as they are, longer times in seconds
+fn format_millis(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{} ms", ms)
+    } else {
+        format!("{:.1} s", ms as f64 / 1000.0)
+    }
+}
+
+/// Messages at the end of a conversation that were sent and got no reply
+pub fn unanswered(conversation: &Conversation) -> u32 {
+    conversation
+        .messages
+        .iter()
+        .rev()
+        .take_while(|m| m.role != MessageRole::Assistant)
+        .filter(|m| m.role == MessageRole::User)
+        .count() as u32
+}
@@ -0,0 +1,510 @@
+//! Guardrail policies for tool calls.
+//!
+//! Before a tool runs, its call is checked against rules that allow it, deny
+//! it or ask the user about it. A rule can name the tool, a regex over the
+//! arguments, path scopes the call touches and network hosts it reaches.
+//! Rules apply everywhere or in one workspace. When several rules match, the
+//! strictest one decides. The model gets a structured denial in place of the
+//! result of a call it may not make.
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Component, PathBuf};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+use crate::models::{ToolCall, ToolResult};
+
+/// Hosts of the URLs in a text
+static URL_HOST: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b[a-z][a-z0-9+.-]*://(?:[^/\s@]+@)?(\[[^\]]+\]|[^/\s:?#]+)").unwrap());
+
+/// Argument names whose whole value is a path
+const PATH_KEYS: &[&str] = &["path", "file", "dir", "cwd", "repo"];
+
+/// What a policy does with a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    /// Run the call
+    Allow,
+
+    /// Run the call once the user agrees
+    Ask,
+
+    /// Refuse the call
+    Deny,
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Effect::Allow => write!(f, "allow"),
+            Effect::Ask => write!(f, "ask"),
+            Effect::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+impl std::str::FromStr for Effect {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "allow" => Ok(Effect::Allow),
+            "ask" => Ok(Effect::Ask),
+            "deny" => Ok(Effect::Deny),
+            _ => Err(McpError::InvalidRequest(format!(
+                "Unknown policy effect '{}': use allow, ask or deny",
+                s
+            ))),
+        }
+    }
+}
+
+/// A rule over tool calls. Every condition given must hold for the rule to
+/// match. Path and host conditions need the call to touch a path or reach a
+/// host: an allow rule matches when all of them are in its scopes, an ask or
+/// deny rule when any of them is.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyRule {
+    /// Rule ID
+    #[serde(default)]
+    pub id: String,
+
+    /// What the rule does with matching calls
+    pub effect: Effect,
+
+    /// Tool name; `*` matches any run of characters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+
+    /// Regex over the arguments, as JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+
+    /// Directories the call's paths are checked against
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+
+    /// Hosts the call's URLs are checked against; `*.example.com` matches
+    /// its subdomains
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<String>,
+
+    /// Why the rule exists, told to the model when it denies a call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl PolicyRule {
+    /// A rule with an effect and no conditions, matching every call
+    pub fn new(effect: Effect) -> Self {
+        Self {
+            id: String::new(),
+            effect,
+            tool: None,
+            arguments: None,
+            paths: Vec::new(),
+            hosts: Vec::new(),
+            reason: None,
+        }
+    }
+
+    /// Check the rule's patterns
+    fn validate(&self) -> McpResult<()> {
+        if let Some(pattern) = &self.arguments {
+            Regex::new(pattern)
+                .map_err(|e| McpError::InvalidRequest(format!("Invalid arguments regex '{}': {}", pattern, e)))?;
+        }
+        let empty = |values: &[String]| values.iter().any(|v| v.trim().is_empty());
+        if self.tool.as_deref().map(str::trim) == Some("") || empty(&self.paths) || empty(&self.hosts) {
+            return Err(McpError::InvalidRequest(
+                "Policy rules can't have empty tool, path or host patterns".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the rule applies to a call
+    pub fn matches(&self, call: &ToolCall) -> bool {
+        if let Some(tool) = &self.tool {
+            if !wildcard(tool, &call.name) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.arguments {
+            let arguments = call.arguments.to_string();
+            match Regex::new(pattern) {
+                Ok(regex) if regex.is_match(&arguments) => {}
+                _ => return false,
+            }
+        }
+        if !self.paths.is_empty() {
+            let scopes: Vec<PathBuf> = self.paths.iter().map(|p| normalize(p)).collect();
+            let inside = |path: &PathBuf| scopes.iter().any(|scope| path.starts_with(scope));
+            if !self.covers(&paths_of(&call.arguments), inside) {
+                return false;
+            }
+        }
+        if !self.hosts.is_empty() {
+            let known = |host: &String| self.hosts.iter().any(|pattern| wildcard(pattern, host));
+            if !self.covers(&hosts_of(&call.arguments), known) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Whether what a call touches falls in the rule's scopes: all of it for
+    /// allow rules, any of it for the others
+    fn covers<T>(&self, touched: &[T], inside: impl Fn(&T) -> bool) -> bool {
+        if touched.is_empty() {
+            return false;
+        }
+        match self.effect {
+            Effect::Allow => touched.iter().all(inside),
+            Effect::Ask | Effect::Deny => touched.iter().any(inside),
+        }
+    }
+
+    /// The rule's conditions, for people
+    pub fn describe(&self) -> String {
+        let mut conditions = Vec::new();
+        if let Some(tool) = &self.tool {
+            conditions.push(format!("tool {}", tool));
+        }
+        if let Some(pattern) = &self.arguments {
+            conditions.push(format!("arguments /{}/", pattern));
+        }
+        if !self.paths.is_empty() {
+            conditions.push(format!("paths {}", self.paths.join(", ")));
+        }
+        if !self.hosts.is_empty() {
+            conditions.push(format!("hosts {}", self.hosts.join(", ")));
+        }
+        if conditions.is_empty() {
+            format!("{} every call", self.effect)
+        } else {
+            format!("{} {}", self.effect, conditions.join(" and "))
+        }
+    }
+}
+
+/// Whether a text matches a pattern where `*` stands for any run of characters
+fn wildcard(pattern: &str, text: &str) -> bool {
+    let regex = format!("(?i)^{}$", regex::escape(pattern).replace(r"\*", ".*"));
+    Regex::new(&regex).map(|r| r.is_match(text)).unwrap_or(false)
+}
+
+/// A path with `~` expanded and `.` and `..` resolved, without touching the
+/// file system
+fn normalize(path: &str) -> PathBuf {
+    let expanded = match path.strip_prefix("~") {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') => {
+            match directories::BaseDirs::new() {
+                Some(dirs) => dirs.home_dir().join(rest.trim_start_matches(['/', '\\'])),
+                None => PathBuf::from(path),
+            }
+        }
+        _ => PathBuf::from(path),
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in expanded.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Whether a word of an argument looks like a path
+fn looks_like_path(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    let drive =
+        bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && matches!(bytes[2], b'\\' | b'/');
+    word.starts_with('/') || word.starts_with("~/") || word.starts_with("./") || word.starts_with("../") || drive
+}
+
+/// Paths a call's arguments touch: the values of path-like arguments and
+/// the path-like words of every other string
+fn paths_of(arguments: &serde_json::Value) -> Vec<PathBuf> {
+    fn walk(key: Option<&str>, value: &serde_json::Value, paths: &mut Vec<PathBuf>) {
+        match value {
+            serde_json::Value::String(text) => {
+                let whole = key.is_some_and(|k| {
+                    let k = k.to_lowercase();
+                    PATH_KEYS.iter().any(|p| k.contains(p))
+                });
+                if whole && !text.contains("://") {
+                    paths.push(normalize(text));
+                } else {
+                    let words = text.split_whitespace().map(|w| w.trim_matches(['"', '\'']));
+                    paths.extend(words.filter(|w| looks_like_path(w)).map(normalize));
+                }
+            }
+            serde_json::Value::Array(items) => items.iter().for_each(|item| walk(key, item, paths)),
+            serde_json::Value::Object(map) => map.iter().for_each(|(k, item)| walk(Some(k), item, paths)),
+            _ => {}
+        }
+    }
+    let mut paths = Vec::new();
+    walk(None, arguments, &mut paths);
+    paths
+}
+
+/// Hosts of the URLs in a call's arguments
+fn hosts_of(arguments: &serde_json::Value) -> Vec<String> {
+    let mut hosts = Vec::new();
+    let mut strings = vec![arguments];
+    while let Some(value) = strings.pop() {
+        match value {
+            serde_json::Value::String(text) => hosts.extend(
+                URL_HOST
+                    .captures_iter(text)
+                    .map(|caps| caps[1].trim_matches(['[', ']']).to_lowercase()),
+            ),
+            serde_json::Value::Array(items) => strings.extend(items),
+            serde_json::Value::Object(map) => strings.extend(map.values()),
+            _ => {}
+        }
+    }
+    hosts
+}
+
+/// Rules applied in one place, and what happens to calls none of them match
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Policy {
+    /// Effect for calls no rule matches; unset leaves it to the global
+    /// policy, and to allowing them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Effect>,
+
+    /// Rules, in the order they were added
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+impl Policy {
+    /// What the policy does with a call
+    pub fn evaluate(&self, call: &ToolCall) -> Decision {
+        // The strictest matching rule decides; the first of equals is named
+        let mut decided: Option<&PolicyRule> = None;
+        for rule in self.rules.iter().filter(|rule| rule.matches(call)) {
+            if decided.is_none_or(|d| rule.effect > d.effect) {
+                decided = Some(rule);
+            }
+        }
+        Decision {
+            tool: call.name.clone(),
+            effect: decided.map_or(self.default.unwrap_or(Effect::Allow), |rule| rule.effect),
+            rule: decided.cloned(),
+        }
+    }
+}
+
+/// What a policy decided about a call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Decision {
+    /// Tool called
+    pub tool: String,
+
+    /// What happens to the call
+    pub effect: Effect,
+
+    /// Rule that decided; `None` when no rule matched
+    pub rule: Option<PolicyRule>,
+}
+
+impl Decision {
+    /// Why the call was decided this way, for people
+    pub fn explain(&self) -> String {
+        match &self.rule {
+            Some(rule) => {
+                let mut explanation = format!("{}: rule {} ({})", self.effect, rule.id, rule.describe());
+                if let Some(reason) = &rule.reason {
+                    explanation.push_str(&format!(" — {}", reason));
+                }
+                explanation
+            }
+            None => format!("{}: no rule matches, so the default applies", self.effect),
+        }
+    }
+
+    /// Result handed to the model in place of the real one when the call is
+    /// denied, or the user declines a call the policy asked about
+    pub fn denial(&self, call: &ToolCall) -> ToolResult {
+        let error = match self.effect {
+            Effect::Ask => format!("The user declined the call to {}", call.name),
+            _ => format!("Policy does not allow calls to {} like this one", call.name),
+        };
+        ToolResult::new(
+            call.id.clone(),
+            call.name.clone(),
+            serde_json::json!({
+                "error": error,
+                "denied": {
+                    "tool": call.name,
+                    "decision": self.effect,
+                    "rule": self.rule.as_ref().map(|rule| rule.id.clone()),
+                    "reason": self.rule.as_ref().and_then(|rule| rule.reason.clone()),
+                },
+                "note": "Do not retry this call; find another way or tell the user what was blocked",
+            }),
+        )
+    }
+}
+
+/// Global and workspace policies
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PolicyFile {
+    #[serde(default)]
+    global: Policy,
+
+    #[serde(default)]
+    workspaces: BTreeMap<String, Policy>,
+}
+
+impl PolicyFile {
+    fn policy_mut(&mut self, workspace: Option<&str>) -> &mut Policy {
+        match workspace {
+            Some(name) => self.workspaces.entry(name.to_string()).or_default(),
+            None => &mut self.global,
+        }
+    }
+}
+
+/// The guardrail policies, kept in a file
+pub struct PolicyStore {
+    path: PathBuf,
+    file: Mutex<PolicyFile>,
+}
+
+impl PolicyStore {
+    /// Policies kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        let file = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable tool policies {:?}: {}", path, e);
+                PolicyFile::default()
+            }),
+            Err(_) => PolicyFile::default(),
+        };
+        Self {
+            path,
+            file: Mutex::new(file),
+        }
+    }
+
+    fn save(&self, file: &PolicyFile) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(file)?)?;
+        Ok(())
+    }
+
+    /// The policy of a workspace, or the global one, as set
+    pub fn get(&self, workspace: Option<&str>) -> Policy {
+        let file = self.file.lock().unwrap();
+        match workspace {
+            Some(name) => file.workspaces.get(name).cloned().unwrap_or_default(),
+            None => file.global.clone(),
+        }
+    }
+
+    /// Workspaces with a policy of their own
+    pub fn workspaces(&self) -> Vec<String> {
+        self.file.lock().unwrap().workspaces.keys().cloned().collect()
+    }
+
+    /// The policy calls in a workspace are checked against: the global rules
+    /// and the workspace's, with the workspace's default taking precedence
+    pub fn effective(&self, workspace: Option<&str>) -> Policy {
+        let file = self.file.lock().unwrap();
+        let mut policy = file.global.clone();
+        if let Some(own) = workspace.and_then(|name| file.workspaces.get(name)) {
+            policy.rules.extend(own.rules.iter().cloned());
+            policy.default = own.default.or(policy.default);
+        }
+        policy
+    }
+
+    /// Check a call made in a workspace. Calls that aren't allowed outright
+    /// are logged and published as a `tool_call_checked` event.
+    pub fn check(&self, workspace: Option<&str>, conversation_id: &str, call: &ToolCall) -> Decision {
+        let decision = self.effective(workspace).evaluate(call);
+        if decision.effect != Effect::Allow {
+            info!("Tool call {} in {}: {}", call.name, conversation_id, decision.explain());
+            get_event_bus().emit(
+                Topic::Conversation,
+                names::TOOL_CALL_CHECKED,
+                serde_json::json!({
+                    "conversation_id": conversation_id,
+                    "call_id": call.id,
+                    "workspace": workspace,
+                    "decision": decision,
+                }),
+            );
+        }
+        decision
+    }
+
+    /// Add a rule to a workspace's policy, or the global one; returns it with
+    /// its ID
+    pub fn add_rule(&self, workspace: Option<&str>, mut rule: PolicyRule) -> McpResult<PolicyRule> {
+        rule.validate()?;
+        rule.id = Uuid::new_v4().simple().to_string()[..8].to_string();
+
+        let mut file = self.file.lock().unwrap();
+        file.policy_mut(workspace).rules.push(rule.clone());
+        self.save(&file)?;
+        Ok(rule)
+    }
+
+    /// Remove a rule from a workspace's policy, or the global one; returns
+    /// whether it was there
+    pub fn remove_rule(&self, workspace: Option<&str>, id: &str) -> McpResult<bool> {
+        let mut file = self.file.lock().unwrap();
+        let policy = file.policy_mut(workspace);
+        let before = policy.rules.len();
+        policy.rules.retain(|rule| rule.id != id);
+        let removed = policy.rules.len() < before;
+        file.workspaces.retain(|_, policy| *policy != Policy::default());
+        if removed {
+            self.save(&file)?;
+        }
+        Ok(removed)
+    }
+
+    /// Set what happens to calls no rule matches in a workspace, or
+    /// everywhere; `None` clears it
+    pub fn set_default(&self, workspace: Option<&str>, effect: Option<Effect>) -> McpResult<()> {
+        let mut file = self.file.lock().unwrap();
+        file.policy_mut(workspace).default = effect;
+        file.workspaces.retain(|_, policy| *policy != Policy::default());
+        self.save(&file)
+    }
+}
+
+static POLICY_STORE: Lazy<Arc<PolicyStore>> = Lazy::new(|| Arc::new(PolicyStore::at(data_path("policies.json"))));
+
+/// Get the global tool policy store
+pub fn get_policies() -> Arc<PolicyStore> {
+    POLICY_STORE.clone()
+}
@@ -0,0 +1,320 @@
+use chrono::{DateTime, Duration, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::models::Model;
+use crate::utils::clock;
+
+/// Currency prices are listed in
+pub const BASE_CURRENCY: &str = "USD";
+
+/// Price list shipped with the application
+const BUNDLED_PRICES: &str = include_str!("../../pricing/prices.json");
+
+/// How long a fetched price list is used before it is fetched again
+const REFRESH_INTERVAL_HOURS: i64 = 24;
+
+/// Price of a model in US dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    /// Price of a million prompt tokens
+    pub input_per_mtok: f64,
+
+    /// Price of a million response tokens
+    pub output_per_mtok: f64,
+}
+
+impl ModelPrice {
+    /// Price of models that run on this machine
+    pub const FREE: ModelPrice = ModelPrice {
+        input_per_mtok: 0.0,
+        output_per_mtok: 0.0,
+    };
+
+    /// Cost of a request in US dollars
+    pub fn cost(&self, input_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.input_per_mtok + output_tokens as f64 * self.output_per_mtok) / 1_000_000.0
+    }
+}
+
+/// Prices by model ID prefix and exchange rates, as bundled or served by a
+/// price list URL
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriceList {
+    /// Prices by model ID prefix
+    #[serde(default)]
+    pub prices: BTreeMap<String, ModelPrice>,
+
+    /// Units of each currency per US dollar
+    #[serde(default)]
+    pub rates: BTreeMap<String, f64>,
+}
+
+/// Where a price comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSource {
+    /// The list shipped with the application
+    Bundled,
+
+    /// The fetched price list
+    Remote,
+
+    /// Set by the user
+    User,
+}
+
+/// A price with its origin, for listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceEntry {
+    /// Model ID prefix
+    pub model: String,
+
+    /// The price
+    #[serde(flatten)]
+    pub price: ModelPrice,
+
+    /// Where it comes from
+    pub source: PriceSource,
+}
+
+/// User prices and the last fetched list, as stored on disk
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PricingState {
+    /// Prices set by the user
+    #[serde(default)]
+    overrides: BTreeMap<String, ModelPrice>,
+
+    /// Exchange rates set by the user
+    #[serde(default)]
+    rates: BTreeMap<String, f64>,
+
+    /// Last fetched price list
+    #[serde(default)]
+    remote: Option<PriceList>,
+
+    /// URL the list was fetched from
+    #[serde(default)]
+    remote_url: Option<String>,
+
+    /// When the list was fetched
+    #[serde(default)]
+    fetched_at: Option<DateTime<Utc>>,
+}
+
+/// Model prices and exchange rates: the bundled list, overlaid by the last
+/// fetched list, overlaid by the user's own prices
+pub struct PriceTable {
+    /// File holding the user's prices and the fetched list
+    path: PathBuf,
+}
+
+impl PriceTable {
+    /// Price table backed by `pricing.json` in the data directory
+    pub fn new() -> Self {
+        Self::at(data_path("pricing.json"))
+    }
+
+    /// Price table backed by the given file
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn bundled() -> PriceList {
+        serde_json::from_str(BUNDLED_PRICES).expect("bundled price list is valid")
+    }
+
+    fn load(&self) -> PricingState {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, state: &PricingState) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(state)?)?;
+        Ok(())
+    }
+
+    /// All prices by model ID prefix with where they come from
+    pub fn entries(&self) -> Vec<PriceEntry> {
+        let state = self.load();
+        let mut entries: BTreeMap<String, PriceEntry> = BTreeMap::new();
+        let layers = [
+            (PriceSource::Bundled, Self::bundled().prices),
+            (PriceSource::Remote, state.remote.map(|r| r.prices).unwrap_or_default()),
+            (PriceSource::User, state.overrides),
+        ];
+        for (source, prices) in layers {
+            for (model, price) in prices {
+                entries.insert(model.clone(), PriceEntry { model, price, source });
+            }
+        }
+        entries.into_values().collect()
+    }
+
+    /// All prices by model ID prefix
+    pub fn prices(&self) -> BTreeMap<String, ModelPrice> {
+        self.entries().into_iter().map(|e| (e.model, e.price)).collect()
+    }
+
+    /// Price of a model ID, from the longest prefix with a price
+    pub fn price_for(&self, model_id: &str) -> Option<ModelPrice> {
+        lookup(&self.prices(), model_id)
+    }
+
+    /// Price of a model; local models are free unless priced otherwise
+    pub fn price_for_model(&self, model: &Model) -> Option<ModelPrice> {
+        self.price_for(&model.id)
+            .or_else(|| (model.provider == "local").then_some(ModelPrice::FREE))
+    }
+
+    /// Cost of a request in US dollars, if the model has a price
+    pub fn cost(&self, model_id: &str, input_tokens: u64, output_tokens: u64) -> Option<f64> {
+        self.price_for(model_id).map(|p| p.cost(input_tokens, output_tokens))
+    }
+
+    /// Set the price of a model or of every model whose ID starts with `model`
+    pub fn set_price(&self, model: &str, price: ModelPrice) -> McpResult<()> {
+        if price.input_per_mtok < 0.0 || price.output_per_mtok < 0.0 {
+            return Err(McpError::InvalidRequest("Prices can't be negative".to_string()));
+        }
+        let mut state = self.load();
+        state.overrides.insert(model.to_string(), price);
+        self.save(&state)
+    }
+
+    /// Drop the user's price of a model, going back to the listed one
+    pub fn reset_price(&self, model: &str) -> McpResult<()> {
+        let mut state = self.load();
+        state.overrides.remove(model);
+        self.save(&state)
+    }
+
+    /// Units of a currency per US dollar
+    pub fn rate(&self, currency: &str) -> Option<f64> {
+        let currency = currency.to_uppercase();
+        if currency == BASE_CURRENCY {
+            return Some(1.0);
+        }
+        let state = self.load();
+        state
+            .rates
+            .get(&currency)
+            .or_else(|| state.remote.as_ref().and_then(|r| r.rates.get(&currency)))
+            .copied()
+            .or_else(|| Self::bundled().rates.get(&currency).copied())
+    }
+
+    /// Currencies with a known rate
+    pub fn currencies(&self) -> Vec<String> {
+        let state = self.load();
+        let mut currencies: Vec<String> = Self::bundled()
+            .rates
+            .into_keys()
+            .chain(state.remote.into_iter().flat_map(|r| r.rates.into_keys()))
+            .chain(state.rates.into_keys())
+            .collect();
+        currencies.sort();
+        currencies.dedup();
+        currencies
+    }
+
+    /// Set the units of a currency per US dollar
+    pub fn set_rate(&self, currency: &str, rate: f64) -> McpResult<()> {
+        if rate.is_nan() || rate <= 0.0 {
+            return Err(McpError::InvalidRequest("Exchange rates must be positive".to_string()));
+        }
+        let mut state = self.load();
+        state.rates.insert(currency.to_uppercase(), rate);
+        self.save(&state)
+    }
+
+    /// Convert US dollars to a currency
+    pub fn convert(&self, usd: f64, currency: &str) -> Option<f64> {
+        self.rate(currency).map(|rate| usd * rate)
+    }
+
+    /// When the price list was last fetched, and from where
+    pub fn last_refresh(&self) -> Option<(String, DateTime<Utc>)> {
+        let state = self.load();
+        state.remote_url.zip(state.fetched_at)
+    }
+
+    /// Fetch a price list (`{"prices": {...}, "rates": {...}}`) from an http,
+    /// https or file URL; returns the number of prices it lists
+    pub async fn refresh(&self, url: &str) -> McpResult<usize> {
+        let content = match url.strip_prefix("file://") {
+            Some(path) => fs::read_to_string(path)?,
+            None => {
+                let response = reqwest::get(url)
+                    .await
+                    .map_err(|e| McpError::Connection(format!("Failed to fetch {}: {}", url, e)))?;
+                if !response.status().is_success() {
+                    return Err(McpError::Connection(format!("{} returned {}", url, response.status())));
+                }
+                response
+                    .text()
+                    .await
+                    .map_err(|e| McpError::Connection(format!("Failed to read {}: {}", url, e)))?
+            }
+        };
+        let list: PriceList = serde_json::from_str(&content)?;
+        let count = list.prices.len();
+
+        let mut state = self.load();
+        state.remote = Some(list);
+        state.remote_url = Some(url.to_string());
+        state.fetched_at = Some(clock::now());
+        self.save(&state)?;
+        info!("Fetched {} prices from {}", count, url);
+        Ok(count)
+    }
+
+    /// Fetch the price list if it was never fetched from `url` or is a day old
+    pub async fn refresh_if_stale(&self, url: &str) -> McpResult<bool> {
+        let fresh = matches!(
+            self.last_refresh(),
+            Some((last_url, at)) if last_url == url && clock::now() - at < Duration::hours(REFRESH_INTERVAL_HOURS)
+        );
+        if fresh {
+            return Ok(false);
+        }
+        self.refresh(url).await.map(|_| true)
+    }
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Price of a model ID in a set of prices by prefix, from the longest
+/// matching prefix
+pub fn lookup(prices: &BTreeMap<String, ModelPrice>, model_id: &str) -> Option<ModelPrice> {
+    prices
+        .iter()
+        .filter(|(prefix, _)| model_id.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, price)| *price)
+}
+
+/// An amount of money for display, e.g. "$0.03" or "CHF 1.20"
+pub fn format_amount(amount: f64, currency: &str) -> String {
+    match currency.to_uppercase().as_str() {
+        "USD" => format!("${:.2}", amount),
+        "EUR" => format!("€{:.2}", amount),
+        "GBP" => format!("£{:.2}", amount),
+        "JPY" => format!("¥{:.0}", amount),
+        other => format!("{} {:.2}", other, amount),
+    }
+}
@@ -0,0 +1,264 @@
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+
+/// Conversation metadata key holding the alias a conversation uses
+pub const ALIAS_METADATA_KEY: &str = "model_alias";
+
+/// Message metadata key recording which concrete model served a message
+pub const SERVED_BY_METADATA_KEY: &str = "served_by";
+
+/// Record the alias a conversation uses in its metadata, replacing metadata
+/// that isn't an object
+pub fn set_alias(metadata: &mut serde_json::Value, alias: &str) {
+    if !metadata.is_object() {
+        *metadata = serde_json::Value::Object(serde_json::Map::new());
+    }
+    if let Some(metadata) = metadata.as_object_mut() {
+        metadata.insert(ALIAS_METADATA_KEY.to_string(), serde_json::json!(alias));
+    }
+}
+
+/// Condition a route target requires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum RouteCondition {
+    /// The API is unreachable
+    Offline,
+    /// The API is reachable
+    Online,
+    /// The prompt is at most this many estimated tokens
+    MaxPromptTokens(usize),
+    /// The prompt is at least this many estimated tokens
+    MinPromptTokens(usize),
+    /// At least this much budget (USD) remains; passes when no budget is tracked
+    MinBudgetRemaining(f64),
+}
+
+impl RouteCondition {
+    fn holds(&self, ctx: &RoutingContext) -> bool {
+        match self {
+            RouteCondition::Offline => !ctx.online,
+            RouteCondition::Online => ctx.online,
+            RouteCondition::MaxPromptTokens(max) => ctx.prompt_tokens <= *max,
+            RouteCondition::MinPromptTokens(min) => ctx.prompt_tokens >= *min,
+            RouteCondition::MinBudgetRemaining(min) => ctx.budget_remaining.map(|b| b >= *min).unwrap_or(true),
+        }
+    }
+}
+
+/// One step of an alias's fallback chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteTarget {
+    /// Concrete model ID
+    pub model: String,
+
+    /// Conditions that must all hold for this target to be used
+    #[serde(default)]
+    pub conditions: Vec<RouteCondition>,
+}
+
+impl RouteTarget {
+    /// A target without conditions
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            conditions: Vec::new(),
+        }
+    }
+
+    /// Add a condition
+    pub fn when(mut self, condition: RouteCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+}
+
+/// A user-defined model name resolved at send time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelAlias {
+    /// Alias name, e.g. "fast"
+    pub name: String,
+
+    /// What the alias is for
+    #[serde(default)]
+    pub description: String,
+
+    /// Targets tried in order
+    pub targets: Vec<RouteTarget>,
+}
+
+/// Facts the routing decision is based on
+#[derive(Debug, Clone, Default)]
+pub struct RoutingContext {
+    /// Whether the API is reachable
+    pub online: bool,
+
+    /// Estimated tokens in the prompt
+    pub prompt_tokens: usize,
+
+    /// Remaining budget in USD, if one is tracked
+    pub budget_remaining: Option<f64>,
+
+    /// IDs of models that can currently be used; empty means "don't check"
+    pub available_models: Vec<String>,
+}
+
+/// Outcome of resolving an alias
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteDecision {
+    /// Alias that was resolved
+    pub alias: String,
+
+    /// Concrete model chosen
+    pub model: String,
+
+    /// Position of the chosen target in the fallback chain
+    pub target_index: usize,
+}
+
+/// Aliases keyed by name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoutingTable {
+    /// Defined aliases
+    pub aliases: BTreeMap<String, ModelAlias>,
+}
+
+impl RoutingTable {
+    /// Built-in aliases used until the user defines their own
+    pub fn defaults() -> Self {
+        let mut table = Self::default();
+        table.set(ModelAlias {
+            name: "fast".to_string(),
+            description: "Quick answers at low cost".to_string(),
+            targets: vec![
+                RouteTarget::new("claude-3-haiku-20240307").when(RouteCondition::Online),
+                RouteTarget::new("tinyllama").when(RouteCondition::Offline),
+            ],
+        });
+        table.set(ModelAlias {
+            name: "smart".to_string(),
+            description: "Best quality while the budget allows".to_string(),
+            targets: vec![
+                RouteTarget::new("claude-3-opus-20240229")
+                    .when(RouteCondition::Online)
+                    .when(RouteCondition::MinBudgetRemaining(1.0)),
+                RouteTarget::new("claude-3-sonnet-20240229").when(RouteCondition::Online),
+                RouteTarget::new("tinyllama"),
+            ],
+        });
+        table.set(ModelAlias {
+            name: "offline-default".to_string(),
+            description: "Local model when offline, cloud otherwise".to_string(),
+            targets: vec![
+                RouteTarget::new("tinyllama").when(RouteCondition::Offline),
+                RouteTarget::new("claude-3-haiku-20240307").when(RouteCondition::MaxPromptTokens(4000)),
+                RouteTarget::new("claude-3-sonnet-20240229"),
+            ],
+        });
+        table
+    }
+
+    fn path() -> PathBuf {
+        data_path("model_aliases.json")
+    }
+
+    /// Load the table, falling back to the defaults
+    pub fn load() -> Self {
+        match fs::read_to_string(Self::path()) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Invalid model alias file, using defaults: {}", e);
+                Self::defaults()
+            }),
+            Err(_) => Self::defaults(),
+        }
+    }
+
+    /// Persist the table
+    pub fn save(&self) -> McpResult<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Add or replace an alias
+    pub fn set(&mut self, alias: ModelAlias) {
+        self.aliases.insert(alias.name.clone(), alias);
+    }
+
+    /// Remove an alias
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.aliases.remove(name).is_some()
+    }
+
+    /// Whether a name refers to an alias
+    pub fn is_alias(&self, name: &str) -> bool {
+        self.aliases.contains_key(name)
+    }
+
+    /// Resolve an alias to the first target whose conditions hold and whose model is available
+    pub fn resolve(&self, name: &str, ctx: &RoutingContext) -> McpResult<RouteDecision> {
+        let alias = self
+            .aliases
+            .get(name)
+            .ok_or_else(|| McpError::InvalidRequest(format!("Unknown model alias '{}'", name)))?;
+
+        alias
+            .targets
+            .iter()
+            .enumerate()
+            .find(|(_, target)| {
+                target.conditions.iter().all(|c| c.holds(ctx))
+                    && (ctx.available_models.is_empty() || ctx.available_models.contains(&target.model))
+            })
+            .map(|(index, target)| {
+                debug!("Alias {} resolved to {} (target {})", name, target.model, index);
+                RouteDecision {
+                    alias: name.to_string(),
+                    model: target.model.clone(),
+                    target_index: index,
+                }
+            })
+            .ok_or_else(|| McpError::InvalidRequest(format!("No model of alias '{}' is usable right now", name)))
+    }
+}
+
+static ROUTING_TABLE: Lazy<Arc<RwLock<RoutingTable>>> = Lazy::new(|| Arc::new(RwLock::new(RoutingTable::load())));
+
+static BUDGET_REMAINING: Lazy<Mutex<Option<f64>>> = Lazy::new(|| Mutex::new(None));
+
+/// Get the global routing table
+pub fn get_routing_table() -> Arc<RwLock<RoutingTable>> {
+    ROUTING_TABLE.clone()
+}
+
+/// Report the remaining budget used by budget conditions; `None` disables them
+pub fn set_budget_remaining(budget: Option<f64>) {
+    let previous = std::mem::replace(&mut *BUDGET_REMAINING.lock().unwrap(), budget);
+
+    // Announce the budget running out once, not on every later report
+    let exhausted = |b: Option<f64>| b.is_some_and(|b| b <= 0.0);
+    if exhausted(budget) && !exhausted(previous) {
+        get_event_bus().emit(
+            Topic::System,
+            names::BUDGET_EXCEEDED,
+            serde_json::json!({ "remaining": budget }),
+        );
+    }
+}
+
+/// Remaining budget, if one is tracked
+pub fn budget_remaining() -> Option<f64> {
+    *BUDGET_REMAINING.lock().unwrap()
+}
@@ -0,0 +1,405 @@
+//! Scripted sessions
+//!
+//! A script is a YAML file of steps run against the chat service without
+//! any UI: start conversations, set system prompts, send prompts and check
+//! the responses. It smoke-tests an installation end to end and doubles as
+//! a repeatable demo.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+use log::{debug, info, warn};
+use tokio::sync::mpsc;
+
+use crate::error::{McpError, McpResult};
+use crate::service::chat::ChatService;
+use crate::service::evals::{check_expectations, xml_escape, Expectation};
+
+/// Longest step text used as a step name
+const MAX_NAME_CHARS: usize = 48;
+
+/// A conversation to start
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewConversation {
+    /// Conversation title
+    #[serde(default)]
+    pub title: Option<String>,
+
+    /// Model for this conversation; the script's model when unset
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// What a step does
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptAction {
+    /// Start a conversation; later steps use it
+    New(NewConversation),
+
+    /// Set the system prompt of the current conversation
+    System(String),
+
+    /// Send a prompt to the current conversation, starting one if needed
+    Send(String),
+}
+
+/// One step of a script
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptStep {
+    /// Name shown in reports; derived from the action when unset
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// What the step does
+    #[serde(flatten)]
+    pub action: ScriptAction,
+
+    /// Checks the response must pass, for `send` steps, written as
+    /// `- contains: text` rather than YAML tags
+    #[serde(default, with = "serde_yaml::with::singleton_map_recursive")]
+    pub expect: Vec<Expectation>,
+}
+
+impl ScriptStep {
+    /// Name of the step for reports
+    pub fn display_name(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        let (action, text) = match &self.action {
+            ScriptAction::New(new) => ("new", new.title.clone().unwrap_or_default()),
+            ScriptAction::System(text) => ("system", text.clone()),
+            ScriptAction::Send(text) => ("send", text.clone()),
+        };
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            action.to_string()
+        } else if text.chars().count() > MAX_NAME_CHARS {
+            format!("{}: {}...", action, text.chars().take(MAX_NAME_CHARS).collect::<String>())
+        } else {
+            format!("{}: {}", action, text)
+        }
+    }
+}
+
+/// A scripted session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Script {
+    /// Script name, used for the report
+    pub name: String,
+
+    /// Model for conversations that don't name one; the default model when unset
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Keep the conversations the script started instead of deleting them
+    #[serde(default)]
+    pub keep: bool,
+
+    /// Steps in order
+    pub steps: Vec<ScriptStep>,
+}
+
+/// Outcome of one step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepResult {
+    /// Position of the step, from 1
+    pub index: usize,
+
+    /// Step name
+    pub name: String,
+
+    /// Whether the step ran and every check passed
+    pub passed: bool,
+
+    /// Whether the step didn't run because an earlier step errored
+    pub skipped: bool,
+
+    /// Response text, for `send` steps that got an answer
+    pub output: Option<String>,
+
+    /// Checks that failed
+    pub failures: Vec<String>,
+
+    /// Error if the step couldn't run
+    pub error: Option<String>,
+
+    /// Time taken in milliseconds
+    pub duration_ms: u64,
+}
+
+/// Results of a script run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptReport {
+    /// Script name
+    pub script: String,
+
+    /// When the run started
+    pub started_at: SystemTime,
+
+    /// Per-step results in script order
+    pub steps: Vec<StepResult>,
+}
+
+impl ScriptReport {
+    /// Steps that passed
+    pub fn passed(&self) -> usize {
+        self.steps.iter().filter(|s| s.passed).count()
+    }
+
+    /// Steps that were skipped
+    pub fn skipped(&self) -> usize {
+        self.steps.iter().filter(|s| s.skipped).count()
+    }
+
+    /// Steps that failed or errored
+    pub fn failed(&self) -> usize {
+        self.steps.len() - self.passed() - self.skipped()
+    }
+
+    /// Whether every step ran and passed
+    pub fn all_passed(&self) -> bool {
+        self.steps.iter().all(|s| s.passed)
+    }
+
+    /// JUnit XML with one test case per step, for CI systems
+    pub fn to_junit(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let time: u64 = self.steps.iter().map(|s| s.duration_ms).sum();
+        xml.push_str(&format!(
+            "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">\n",
+            xml_escape(&self.script),
+            self.steps.len(),
+            self.failed(),
+            self.skipped()
+        ));
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+            xml_escape(&self.script),
+            self.steps.len(),
+            self.failed(),
+            self.skipped(),
+            time as f64 / 1000.0
+        ));
+
+        for step in &self.steps {
+            xml.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}. {}\" time=\"{:.3}\"",
+                xml_escape(&self.script),
+                step.index,
+                xml_escape(&step.name),
+                step.duration_ms as f64 / 1000.0
+            ));
+            if step.passed {
+                xml.push_str("/>\n");
+                continue;
+            }
+            xml.push_str(">\n");
+            if step.skipped {
+                xml.push_str("      <skipped/>\n");
+            } else {
+                let message = step.error.clone().unwrap_or_else(|| step.failures.join("; "));
+                xml.push_str(&format!(
+                    "      <failure message=\"{}\">{}</failure>\n",
+                    xml_escape(&message),
+                    xml_escape(step.output.as_deref().unwrap_or(""))
+                ));
+            }
+            xml.push_str("    </testcase>\n");
+        }
+
+        xml.push_str("  </testsuite>\n");
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+/// Parse a script from YAML, or JSON, which YAML reads as well
+pub fn parse_script(text: &str) -> McpResult<Script> {
+    let script: Script =
+        serde_yaml::from_str(text).map_err(|e| McpError::InvalidRequest(format!("Invalid script: {}", e)))?;
+    if script.steps.is_empty() {
+        return Err(McpError::InvalidRequest(format!("Script {} has no steps", script.name)));
+    }
+    for (i, step) in script.steps.iter().enumerate() {
+        if !step.expect.is_empty() && !matches!(step.action, ScriptAction::Send(_)) {
+            return Err(McpError::InvalidRequest(format!(
+                "Step {}: only send steps have a response to check",
+                i + 1
+            )));
+        }
+        for expectation in &step.expect {
+            if let Expectation::Regex(pattern) = expectation {
+                Regex::new(pattern)
+                    .map_err(|e| McpError::InvalidRequest(format!("Step {}: invalid regex: {}", i + 1, e)))?;
+            }
+        }
+    }
+    Ok(script)
+}
+
+/// Load a script from a file
+pub fn load_script(path: &Path) -> McpResult<Script> {
+    parse_script(&fs::read_to_string(path)?)
+        .map_err(|e| McpError::InvalidRequest(format!("{}: {}", path.display(), e)))
+}
+
+/// Runs scripts against the chat service
+pub struct ScriptRunner {
+    /// Chat service used to run the steps
+    chat_service: Arc<ChatService>,
+
+    /// Listener for each step's result as it finishes
+    progress_tx: Option<mpsc::UnboundedSender<StepResult>>,
+}
+
+impl ScriptRunner {
+    /// Create a new script runner
+    pub fn new(chat_service: Arc<ChatService>) -> Self {
+        Self {
+            chat_service,
+            progress_tx: None,
+        }
+    }
+
+    /// Receive each step's result as it finishes
+    pub fn with_progress(mut self, tx: mpsc::UnboundedSender<StepResult>) -> Self {
+        self.progress_tx = Some(tx);
+        self
+    }
+
+    /// Run a script. Failed checks don't stop it; a step that errors skips
+    /// the steps after it, since they depend on its conversation.
+    pub async fn run(&self, script: &Script) -> ScriptReport {
+        let started_at = SystemTime::now();
+        info!("Running script {} ({} step(s))", script.name, script.steps.len());
+
+        let mut current: Option<String> = None;
+        let mut created = Vec::new();
+        let mut steps = Vec::with_capacity(script.steps.len());
+        let mut aborted = false;
+
+        for (i, step) in script.steps.iter().enumerate() {
+            let start = Instant::now();
+            let mut result = StepResult {
+                index: i + 1,
+                name: step.display_name(),
+                passed: false,
+                skipped: aborted,
+                output: None,
+                failures: Vec::new(),
+                error: None,
+                duration_ms: 0,
+            };
+
+            if !aborted {
+                match self.run_step(script, step, &mut current, &mut created).await {
+                    Ok(output) => {
+                        if let Some(output) = &output {
+                            result.failures = check_expectations(&step.expect, output);
+                        }
+                        result.passed = result.failures.is_empty();
+                        result.output = output;
+                    }
+                    Err(e) => {
+                        warn!("Script step {} failed: {}", result.index, e);
+                        result.error = Some(e.to_string());
+                        aborted = true;
+                    }
+                }
+                result.duration_ms = start.elapsed().as_millis() as u64;
+            }
+
+            if let Some(tx) = &self.progress_tx {
+                let _ = tx.send(result.clone());
+            }
+            steps.push(result);
+        }
+
+        if !script.keep {
+            self.clean_up(&created).await;
+        }
+
+        ScriptReport {
+            script: script.name.clone(),
+            started_at,
+            steps,
+        }
+    }
+
+    /// Run one step; returns the response for `send` steps
+    async fn run_step(
+        &self,
+        script: &Script,
+        step: &ScriptStep,
+        current: &mut Option<String>,
+        created: &mut Vec<String>,
+    ) -> McpResult<Option<String>> {
+        match &step.action {
+            ScriptAction::New(new) => {
+                let id = self.start_conversation(script, new, created).await?;
+                *current = Some(id);
+                Ok(None)
+            }
+            ScriptAction::System(text) => {
+                let id = match current {
+                    Some(id) => id.clone(),
+                    None => self.start_conversation(script, &NewConversation::default(), created).await?,
+                };
+                self.chat_service.set_system_message(&id, text).await?;
+                *current = Some(id);
+                Ok(None)
+            }
+            ScriptAction::Send(text) => {
+                let id = match current {
+                    Some(id) => id.clone(),
+                    None => self.start_conversation(script, &NewConversation::default(), created).await?,
+                };
+                let message = self.chat_service.send_message(&id, text).await?;
+                *current = Some(id);
+                Ok(Some(message.text()))
+            }
+        }
+    }
+
+    /// Start a conversation on the step's or the script's model
+    async fn start_conversation(
+        &self,
+        script: &Script,
+        new: &NewConversation,
+        created: &mut Vec<String>,
+    ) -> McpResult<String> {
+        let model = match new.model.as_ref().or(script.model.as_ref()) {
+            Some(name) => Some(
+                self.chat_service
+                    .available_models()
+                    .await?
+                    .into_iter()
+                    .find(|m| &m.id == name || &m.name == name)
+                    .ok_or_else(|| McpError::InvalidRequest(format!("Unknown model: {}", name)))?,
+            ),
+            None => None,
+        };
+        let title = new.title.clone().unwrap_or_else(|| script.name.clone());
+        let conversation = self.chat_service.create_conversation(&title, model).await?;
+        created.push(conversation.id.clone());
+        Ok(conversation.id)
+    }
+
+    /// Delete the conversations a run started, bypassing the trash
+    async fn clean_up(&self, created: &[String]) {
+        for id in created {
+            let cleanup = match self.chat_service.delete_conversation(id).await {
+                Ok(()) => self.chat_service.purge_conversation(id).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = cleanup {
+                debug!("Failed to delete script conversation {}: {}", id, e);
+            }
+        }
+    }
+}
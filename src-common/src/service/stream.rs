@@ -0,0 +1,543 @@
+//! Typed streaming events
+//!
+//! Providers stream replies as message chunks. Frontends that render as the
+//! reply arrives get typed events instead: where content blocks (prose,
+//! fenced code, tool calls) start and stop, the text added to each, tool
+//! calls, token usage, and the finished reply. [`into_snapshots`] turns the
+//! events back into the growing message snapshots of the older interface.
+//!
+//! Token usage and its cost are reported as the reply grows, so frontends
+//! can show a running counter: from what the provider reports as it streams,
+//! or estimated from the text when it reports nothing, as with local models.
+//! The `Usage` event ending the reply has the final figures.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+
+use crate::error::McpResult;
+use crate::models::message::ContentType;
+use crate::models::{Message, ToolCall};
+use crate::service::estimate::estimate_tokens;
+use crate::service::pricing::{ModelPrice, PriceTable, BASE_CURRENCY};
+
+/// Message metadata key of the token usage a provider reports
+pub const USAGE_METADATA_KEY: &str = "usage";
+
+const FENCE: &str = "```";
+
+/// Kind of a content block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum BlockKind {
+    /// Prose
+    Text,
+
+    /// Fenced code; the fence lines are not part of its text
+    Code { language: Option<String> },
+
+    /// A call of a tool
+    ToolCall { id: String, name: String },
+}
+
+/// Tokens used by a reply, and what they cost
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Prompt tokens, history included
+    pub input_tokens: u64,
+
+    /// Reply tokens
+    pub output_tokens: u64,
+
+    /// Whether the counts are estimated rather than reported by the provider
+    pub estimated: bool,
+
+    /// Cost in `currency`; unknown without a price for the model
+    #[serde(default)]
+    pub cost: Option<f64>,
+
+    /// Currency of the cost
+    #[serde(default)]
+    pub currency: String,
+}
+
+/// Prices the tokens of a reply as it streams
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostMeter {
+    /// Price of the model; None when unknown
+    pub price: Option<ModelPrice>,
+
+    /// Currency costs are given in
+    pub currency: String,
+
+    /// Units of that currency per US dollar
+    pub rate: f64,
+}
+
+impl CostMeter {
+    /// Meter at a model's price, in a currency with an exchange rate or else
+    /// in US dollars
+    pub fn new(price: Option<ModelPrice>, prices: &PriceTable, currency: &str) -> Self {
+        let (currency, rate) = match prices.rate(currency) {
+            Some(rate) => (currency.to_uppercase(), rate),
+            None => (BASE_CURRENCY.to_string(), 1.0),
+        };
+        Self { price, currency, rate }
+    }
+
+    /// Cost of a number of tokens, if the model has a price
+    pub fn cost(&self, input_tokens: u64, output_tokens: u64) -> Option<f64> {
+        self.price.map(|p| p.cost(input_tokens, output_tokens) * self.rate)
+    }
+}
+
+impl Default for CostMeter {
+    fn default() -> Self {
+        Self {
+            price: None,
+            currency: BASE_CURRENCY.to_string(),
+            rate: 1.0,
+        }
+    }
+}
+
+/// Something that happened in a streamed reply
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum StreamEvent {
+    /// The reply started
+    MessageStart { message_id: String },
+
+    /// A content block started; blocks are numbered from 0 in order
+    ContentBlockStart { index: usize, block: BlockKind },
+
+    /// Text was added to a block
+    TextDelta { index: usize, text: String },
+
+    /// A content block ended
+    ContentBlockStop { index: usize },
+
+    /// The model called a tool; sent between the start and stop of its block
+    ToolCall { index: usize, call: ToolCall },
+
+    /// Tokens used and their cost so far, sent after every chunk that adds
+    /// to the reply or reports usage
+    UsageDelta(Usage),
+
+    /// Tokens used and their cost, sent once before `Done`
+    Usage(Usage),
+
+    /// The reply is complete; `message` holds all of it
+    Done { message: Message },
+}
+
+/// Turns the chunks of a reply into events, one chunk at a time.
+///
+/// A fence may arrive split over chunks, so text at the start of a line is
+/// held back until it's clear whether it opens or closes a code block.
+pub struct EventEncoder {
+    reply: Option<Message>,
+    latest: Option<Message>,
+    text: String,
+    calls: Vec<ToolCall>,
+    pending: String,
+    at_line_start: bool,
+    in_code: bool,
+    open: Option<usize>,
+    next_index: usize,
+    prompt_tokens: Option<u64>,
+    reported_input: Option<u64>,
+    reported_output: Option<u64>,
+    meter: CostMeter,
+    finished: bool,
+}
+
+impl EventEncoder {
+    /// Encoder for a new reply
+    pub fn new() -> Self {
+        Self {
+            reply: None,
+            latest: None,
+            text: String::new(),
+            calls: Vec::new(),
+            pending: String::new(),
+            at_line_start: true,
+            in_code: false,
+            open: None,
+            next_index: 0,
+            prompt_tokens: None,
+            reported_input: None,
+            reported_output: None,
+            meter: CostMeter::default(),
+            finished: false,
+        }
+    }
+
+    /// Estimate the prompt at a number of tokens until the provider reports
+    /// usage
+    pub fn with_prompt_tokens(mut self, prompt_tokens: u64) -> Self {
+        self.prompt_tokens = Some(prompt_tokens);
+        self
+    }
+
+    /// Price usage with a meter
+    pub fn with_meter(mut self, meter: CostMeter) -> Self {
+        self.meter = meter;
+        self
+    }
+
+    /// Events for a chunk holding what was added to the reply
+    pub fn push(&mut self, chunk: &Message) -> Vec<StreamEvent> {
+        let mut events = Vec::new();
+        let seen = self.text.len();
+        self.begin(Some(chunk), &mut events);
+        for part in &chunk.content.parts {
+            match part {
+                ContentType::Text { text } => self.push_text(text, &mut events),
+                ContentType::ToolCalls { calls } => {
+                    for call in calls {
+                        self.push_call(call, &mut events);
+                    }
+                }
+                ContentType::Image { .. } | ContentType::ToolResults { .. } => {}
+            }
+        }
+
+        // Providers report running totals, some the prompt first and the
+        // reply's tokens as they grow
+        let usage = chunk.metadata.as_ref().and_then(|m| m.get(USAGE_METADATA_KEY));
+        let field = |name: &str| usage.and_then(|u| u.get(name)).and_then(|v| v.as_u64());
+        let reported = (field("input_tokens"), field("output_tokens"));
+        self.reported_input = reported.0.or(self.reported_input);
+        self.reported_output = reported.1.or(self.reported_output);
+
+        if self.text.len() != seen || reported != (None, None) {
+            events.push(StreamEvent::UsageDelta(self.usage()));
+        }
+        events
+    }
+
+    /// Tokens used and their cost so far; what the provider didn't report is
+    /// estimated
+    pub fn usage(&self) -> Usage {
+        let input_tokens = self.reported_input.or(self.prompt_tokens).unwrap_or(0);
+        let output_tokens = self.reported_output.unwrap_or_else(|| estimate_tokens(&self.text));
+        Usage {
+            input_tokens,
+            output_tokens,
+            estimated: self.reported_input.is_none() || self.reported_output.is_none(),
+            cost: self.meter.cost(input_tokens, output_tokens),
+            currency: self.meter.currency.clone(),
+        }
+    }
+
+    /// Events for a snapshot holding the whole reply so far, as the older
+    /// interface sends. Only text extending what was seen is new; text
+    /// rewritten earlier on, e.g. by a filter, shows in the message of `Done`.
+    pub fn push_snapshot(&mut self, snapshot: &Message) -> Vec<StreamEvent> {
+        let text = snapshot.text();
+        let added = text.strip_prefix(self.text.as_str()).unwrap_or_default().to_string();
+        let calls: Vec<ToolCall> = snapshot
+            .content
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                ContentType::ToolCalls { calls } => Some(calls),
+                _ => None,
+            })
+            .flatten()
+            .skip(self.calls.len())
+            .cloned()
+            .collect();
+
+        let mut chunk = snapshot.clone();
+        chunk.content.parts = vec![ContentType::Text { text: added }];
+        if !calls.is_empty() {
+            chunk.content.parts.push(ContentType::ToolCalls { calls });
+        }
+        let events = self.push(&chunk);
+        // Later snapshots extend this one, rewritten or not
+        self.text = text;
+        self.latest = Some(snapshot.clone());
+        events
+    }
+
+    /// Events ending the reply: open blocks stop, then `Usage` and `Done`.
+    /// Usage the provider didn't report is estimated, with `prompt_tokens`
+    /// as the input when given.
+    pub fn finish(&mut self, prompt_tokens: Option<u64>) -> Vec<StreamEvent> {
+        let mut events = Vec::new();
+        if self.finished {
+            return events;
+        }
+        self.finished = true;
+        self.begin(None, &mut events);
+
+        // A closing fence needn't end with a newline
+        let pending = std::mem::take(&mut self.pending);
+        if self.at_line_start && self.in_code && pending.starts_with(FENCE) {
+            self.fence("", &mut events);
+        } else {
+            self.emit_text(&pending, &mut events);
+        }
+        self.close(&mut events);
+        self.in_code = false;
+
+        if prompt_tokens.is_some() {
+            self.prompt_tokens = prompt_tokens;
+        }
+        events.push(StreamEvent::Usage(self.usage()));
+        events.push(StreamEvent::Done { message: self.message() });
+        events
+    }
+
+    /// The reply as received so far
+    pub fn message(&self) -> Message {
+        if let Some(latest) = &self.latest {
+            return latest.clone();
+        }
+        let mut message = self.reply.clone().unwrap_or_else(|| Message::assistant(""));
+        message.content.parts = vec![ContentType::Text { text: self.text.clone() }];
+        if !self.calls.is_empty() {
+            message.content.parts.push(ContentType::ToolCalls {
+                calls: self.calls.clone(),
+            });
+        }
+        message
+    }
+
+    /// Start the reply with the first chunk, or merge a later chunk's metadata
+    fn begin(&mut self, chunk: Option<&Message>, events: &mut Vec<StreamEvent>) {
+        if let Some(reply) = self.reply.as_mut() {
+            if let Some(metadata) = chunk.and_then(|c| c.metadata.as_ref()) {
+                reply
+                    .metadata
+                    .get_or_insert_with(HashMap::new)
+                    .extend(metadata.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+            return;
+        }
+
+        let mut reply = chunk.cloned().unwrap_or_else(|| Message::assistant(""));
+        reply.content.parts.clear();
+        events.push(StreamEvent::MessageStart {
+            message_id: reply.id.clone(),
+        });
+        self.reply = Some(reply);
+    }
+
+    fn push_text(&mut self, added: &str, events: &mut Vec<StreamEvent>) {
+        self.text.push_str(added);
+        self.pending.push_str(added);
+
+        loop {
+            if self.at_line_start {
+                if self.pending.starts_with(FENCE) {
+                    // The language is only known once the line is complete
+                    let Some(end) = self.pending.find('\n') else { break };
+                    let line: String = self.pending.drain(..=end).collect();
+                    self.fence(&line[FENCE.len()..], events);
+                    continue;
+                }
+                if FENCE.starts_with(self.pending.as_str()) {
+                    break;
+                }
+            }
+
+            match self.pending.find('\n') {
+                Some(end) => {
+                    let line: String = self.pending.drain(..=end).collect();
+                    self.emit_text(&line, events);
+                    self.at_line_start = true;
+                }
+                None => {
+                    let rest = std::mem::take(&mut self.pending);
+                    self.emit_text(&rest, events);
+                    self.at_line_start = false;
+                    break;
+                }
+            }
+        }
+    }
+
+    fn push_call(&mut self, call: &ToolCall, events: &mut Vec<StreamEvent>) {
+        let pending = std::mem::take(&mut self.pending);
+        if !pending.is_empty() {
+            self.emit_text(&pending, events);
+            self.at_line_start = false;
+        }
+        self.in_code = false;
+
+        let index = self.start(
+            BlockKind::ToolCall {
+                id: call.id.clone(),
+                name: call.name.clone(),
+            },
+            events,
+        );
+        events.push(StreamEvent::ToolCall {
+            index,
+            call: call.clone(),
+        });
+        self.close(events);
+        self.calls.push(call.clone());
+    }
+
+    /// A fence line: opens a code block, or closes the open one
+    fn fence(&mut self, info: &str, events: &mut Vec<StreamEvent>) {
+        if self.in_code {
+            self.close(events);
+            self.in_code = false;
+        } else {
+            let language = info.split_whitespace().next().map(str::to_string);
+            self.start(BlockKind::Code { language }, events);
+            self.in_code = true;
+        }
+    }
+
+    fn start(&mut self, block: BlockKind, events: &mut Vec<StreamEvent>) -> usize {
+        self.close(events);
+        let index = self.next_index;
+        self.next_index += 1;
+        events.push(StreamEvent::ContentBlockStart { index, block });
+        self.open = Some(index);
+        index
+    }
+
+    fn close(&mut self, events: &mut Vec<StreamEvent>) {
+        if let Some(index) = self.open.take() {
+            events.push(StreamEvent::ContentBlockStop { index });
+        }
+    }
+
+    fn emit_text(&mut self, text: &str, events: &mut Vec<StreamEvent>) {
+        if text.is_empty() {
+            return;
+        }
+        let index = match self.open {
+            Some(index) => index,
+            None => self.start(BlockKind::Text, events),
+        };
+        // One delta per block per chunk
+        if let Some(StreamEvent::TextDelta { index: last, text: previous }) = events.last_mut() {
+            if *last == index {
+                previous.push_str(text);
+                return;
+            }
+        }
+        events.push(StreamEvent::TextDelta {
+            index,
+            text: text.to_string(),
+        });
+    }
+}
+
+impl Default for EventEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Events of a stream of reply chunks, encoded by an encoder
+pub fn encode(
+    mut chunks: mpsc::Receiver<McpResult<Message>>,
+    mut encoder: EventEncoder,
+) -> mpsc::Receiver<McpResult<StreamEvent>> {
+    let (tx, rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        while let Some(chunk) = chunks.recv().await {
+            let events = match chunk {
+                Ok(chunk) => encoder.push(&chunk),
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            };
+            for event in events {
+                if tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+        }
+        for event in encoder.finish(None) {
+            if tx.send(Ok(event)).await.is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+/// Snapshots of an event stream, each holding the whole reply so far, for
+/// consumers of the older interface. Fences are put back around code; the
+/// last snapshot is the message of `Done`.
+pub fn into_snapshots(mut events: mpsc::Receiver<McpResult<StreamEvent>>) -> mpsc::Receiver<McpResult<Message>> {
+    let (tx, rx) = mpsc::channel(32);
+    tokio::spawn(async move {
+        let mut snapshot = Message::assistant("");
+        let mut text = String::new();
+        let mut calls: Vec<ToolCall> = Vec::new();
+        let mut code_block: Option<usize> = None;
+
+        while let Some(event) = events.recv().await {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            };
+            let changed = match event {
+                StreamEvent::MessageStart { message_id } => {
+                    snapshot.id = message_id;
+                    false
+                }
+                StreamEvent::ContentBlockStart {
+                    index,
+                    block: BlockKind::Code { language },
+                } => {
+                    code_block = Some(index);
+                    text.push_str(&format!("{}{}\n", FENCE, language.unwrap_or_default()));
+                    true
+                }
+                StreamEvent::ContentBlockStop { index } if code_block == Some(index) => {
+                    code_block = None;
+                    if !text.is_empty() && !text.ends_with('\n') {
+                        text.push('\n');
+                    }
+                    text.push_str(FENCE);
+                    text.push('\n');
+                    true
+                }
+                StreamEvent::TextDelta { text: added, .. } => {
+                    text.push_str(&added);
+                    true
+                }
+                StreamEvent::ToolCall { call, .. } => {
+                    calls.push(call);
+                    true
+                }
+                StreamEvent::Done { message } => {
+                    let _ = tx.send(Ok(message)).await;
+                    return;
+                }
+                StreamEvent::ContentBlockStart { .. }
+                | StreamEvent::ContentBlockStop { .. }
+                | StreamEvent::UsageDelta(_)
+                | StreamEvent::Usage(_) => false,
+            };
+            if !changed {
+                continue;
+            }
+
+            snapshot.content.parts = vec![ContentType::Text { text: text.clone() }];
+            if !calls.is_empty() {
+                snapshot.content.parts.push(ContentType::ToolCalls { calls: calls.clone() });
+            }
+            if tx.send(Ok(snapshot.clone())).await.is_err() {
+                return;
+            }
+        }
+    });
+    rx
+}
@@ -0,0 +1,798 @@
+//! Message translation
+//!
+//! Users can write and read in their own language while the model works in
+//! another one, usually English. Outgoing messages are translated into the
+//! model's language and replies back into the user's, by a local translation
+//! model from the registry (NLLB, M2M-100) or by DeepL or Google with keys
+//! kept in the secret store. The user's language is set per conversation or
+//! detected from each message. Code blocks are never translated.
+
+use async_trait::async_trait;
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use crate::auth::secret_store;
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::models::message::ContentType;
+use crate::models::registry::{get_model_registry, RegistryEntry};
+use crate::models::{Message, MessageRole};
+use crate::service::middleware::{MessageMiddleware, MiddlewareContext};
+
+/// Conversation metadata key, and context value, holding the conversation's
+/// [`LanguagePreference`]
+pub const LANGUAGE_METADATA_KEY: &str = "language";
+
+/// Message metadata key recording the original text of a translated message
+pub const TRANSLATION_METADATA_KEY: &str = "translation";
+
+/// Context value with the language the reply is translated into
+const USER_LANGUAGE_CONTEXT_KEY: &str = "user_language";
+
+/// User language that is detected from each message
+pub const AUTO_LANGUAGE: &str = "auto";
+
+/// Registry model kind of local translation models
+pub const LOCAL_MODEL_KIND: &str = "translation";
+
+/// Time allowed for one request to a translation API
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Fewest letters a text needs for its language to be detected
+const MIN_DETECT_LETTERS: usize = 12;
+
+/// Where translations come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranslationProvider {
+    /// A translation model from the registry, run on this device
+    Local,
+    /// The DeepL API
+    #[default]
+    Deepl,
+    /// Google Cloud Translation
+    Google,
+}
+
+impl TranslationProvider {
+    /// Stable identifier
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TranslationProvider::Local => "local",
+            TranslationProvider::Deepl => "deepl",
+            TranslationProvider::Google => "google",
+        }
+    }
+
+    /// Secret store entry of the provider's API key
+    fn secret_key(&self) -> Option<&'static str> {
+        match self {
+            TranslationProvider::Local => None,
+            TranslationProvider::Deepl => Some("translation_deepl"),
+            TranslationProvider::Google => Some("translation_google"),
+        }
+    }
+}
+
+impl std::str::FromStr for TranslationProvider {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "local" => Ok(TranslationProvider::Local),
+            "deepl" => Ok(TranslationProvider::Deepl),
+            "google" => Ok(TranslationProvider::Google),
+            other => Err(McpError::InvalidRequest(format!(
+                "Unknown translation provider '{}'; expected local, deepl or google",
+                other
+            ))),
+        }
+    }
+}
+
+/// Languages of a conversation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguagePreference {
+    /// Language the user writes and reads, or `auto` to detect it from
+    /// each message
+    #[serde(default = "default_user_language")]
+    pub user: String,
+
+    /// Language the model is addressed in
+    #[serde(default = "default_model_language")]
+    pub model: String,
+
+    /// Provider for this conversation instead of the configured one
+    #[serde(default)]
+    pub provider: Option<TranslationProvider>,
+}
+
+fn default_user_language() -> String {
+    AUTO_LANGUAGE.to_string()
+}
+
+fn default_model_language() -> String {
+    "en".to_string()
+}
+
+impl Default for LanguagePreference {
+    fn default() -> Self {
+        Self {
+            user: default_user_language(),
+            model: default_model_language(),
+            provider: None,
+        }
+    }
+}
+
+impl LanguagePreference {
+    /// Check the language codes, normalizing them to lower case
+    pub fn validated(mut self) -> McpResult<Self> {
+        self.user = self.user.trim().to_lowercase();
+        self.model = self.model.trim().to_lowercase();
+        for code in [&self.user, &self.model] {
+            let valid = code.len() >= 2
+                && code.len() <= 7
+                && code.chars().all(|c| c.is_ascii_alphabetic() || c == '-');
+            if !valid {
+                return Err(McpError::InvalidRequest(format!("Invalid language code '{}'", code)));
+            }
+        }
+        if self.model == AUTO_LANGUAGE {
+            return Err(McpError::InvalidRequest("The model language can't be detected; name one".to_string()));
+        }
+        Ok(self)
+    }
+}
+
+/// Translation settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TranslationSettings {
+    /// Translate conversations that have no language preference of their own
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Provider used unless a conversation names another
+    #[serde(default)]
+    pub provider: TranslationProvider,
+
+    /// Registry model for local translation; the first translation model in
+    /// the registry when unset
+    #[serde(default)]
+    pub local_model: Option<String>,
+
+    /// Languages of conversations without a preference of their own
+    #[serde(default)]
+    pub defaults: LanguagePreference,
+}
+
+impl TranslationSettings {
+    fn path() -> PathBuf {
+        data_path("translation.json")
+    }
+
+    /// Load the settings, falling back to the defaults
+    pub fn load() -> Self {
+        match fs::read_to_string(Self::path()) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Invalid translation settings, using defaults: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Persist the settings
+    pub fn save(&self) -> McpResult<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+static TRANSLATION_SETTINGS: Lazy<Arc<RwLock<TranslationSettings>>> =
+    Lazy::new(|| Arc::new(RwLock::new(TranslationSettings::load())));
+
+/// Get the global translation settings
+pub fn get_translation_settings() -> Arc<RwLock<TranslationSettings>> {
+    TRANSLATION_SETTINGS.clone()
+}
+
+/// Store or remove the API key of a translation provider
+pub fn set_api_key(provider: TranslationProvider, key: Option<&str>) -> McpResult<()> {
+    let secret = provider
+        .secret_key()
+        .ok_or_else(|| McpError::InvalidRequest("Local translation needs no API key".to_string()))?;
+    match key.map(str::trim).filter(|k| !k.is_empty()) {
+        Some(key) => secret_store().set(secret, key),
+        None => secret_store().delete(secret),
+    }
+}
+
+/// Whether a provider has what it needs: an API key, or a local model
+pub fn is_configured(provider: TranslationProvider) -> bool {
+    match provider.secret_key() {
+        Some(secret) => matches!(secret_store().get(secret), Ok(Some(_))),
+        None => local_model(None).is_some(),
+    }
+}
+
+/// A translation backend
+#[async_trait]
+pub trait Translator: Send + Sync {
+    /// Backend name recorded with each translation
+    fn name(&self) -> &str;
+
+    /// Translate text into `target`; `source` is `None` when unknown
+    async fn translate(&self, text: &str, source: Option<&str>, target: &str) -> McpResult<String>;
+}
+
+/// Runs local translation models. The application that can load models
+/// registers one with [`set_local_engine`].
+#[async_trait]
+pub trait LocalTranslationEngine: Send + Sync {
+    /// Translate text with a registry model. NLLB models take the codes
+    /// from [`nllb_code`]; M2M-100 models take the ISO codes as given.
+    async fn translate(&self, model: &RegistryEntry, text: &str, source: &str, target: &str) -> McpResult<String>;
+}
+
+static LOCAL_ENGINE: Lazy<RwLock<Option<Arc<dyn LocalTranslationEngine>>>> = Lazy::new(|| RwLock::new(None));
+
+/// Make local translation models usable
+pub fn set_local_engine(engine: Arc<dyn LocalTranslationEngine>) {
+    *LOCAL_ENGINE.write().unwrap() = Some(engine);
+}
+
+/// Registry translation model by ID, or the first one
+fn local_model(model_id: Option<&str>) -> Option<RegistryEntry> {
+    get_model_registry()
+        .entries()
+        .into_iter()
+        .filter(|e| e.entry.kind == LOCAL_MODEL_KIND)
+        .find(|e| model_id.is_none_or(|id| e.entry.id == id))
+}
+
+/// FLORES-200 code NLLB models use for an ISO 639-1 language
+pub fn nllb_code(language: &str) -> Option<&'static str> {
+    let code = match language.split('-').next().unwrap_or(language) {
+        "ar" => "arb_Arab",
+        "cs" => "ces_Latn",
+        "da" => "dan_Latn",
+        "de" => "deu_Latn",
+        "el" => "ell_Grek",
+        "en" => "eng_Latn",
+        "es" => "spa_Latn",
+        "fi" => "fin_Latn",
+        "fr" => "fra_Latn",
+        "he" => "heb_Hebr",
+        "hi" => "hin_Deva",
+        "hu" => "hun_Latn",
+        "id" => "ind_Latn",
+        "it" => "ita_Latn",
+        "ja" => "jpn_Jpan",
+        "ko" => "kor_Hang",
+        "nl" => "nld_Latn",
+        "no" | "nb" => "nob_Latn",
+        "pl" => "pol_Latn",
+        "pt" => "por_Latn",
+        "ro" => "ron_Latn",
+        "ru" => "rus_Cyrl",
+        "sv" => "swe_Latn",
+        "th" => "tha_Thai",
+        "tr" => "tur_Latn",
+        "uk" => "ukr_Cyrl",
+        "vi" => "vie_Latn",
+        "zh" => "zho_Hans",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Translation with a local registry model
+pub struct LocalTranslator {
+    model: RegistryEntry,
+    engine: Arc<dyn LocalTranslationEngine>,
+}
+
+impl LocalTranslator {
+    /// Use a registry model, or the first translation model when `None`
+    pub fn new(model_id: Option<&str>) -> McpResult<Self> {
+        let model = local_model(model_id).ok_or_else(|| match model_id {
+            Some(id) => McpError::Config(format!("'{}' is not a translation model in the registry", id)),
+            None => McpError::Config("No translation model in the registry; add a catalog that offers one".to_string()),
+        })?;
+        let engine = LOCAL_ENGINE
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| McpError::Config("Local translation models can't be run here; use DeepL or Google".to_string()))?;
+        Ok(Self { model, engine })
+    }
+}
+
+#[async_trait]
+impl Translator for LocalTranslator {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn translate(&self, text: &str, source: Option<&str>, target: &str) -> McpResult<String> {
+        // Unlike the APIs, the models need to be told the source language
+        let source = source
+            .or_else(|| detect_language(text))
+            .ok_or_else(|| McpError::InvalidRequest("The source language couldn't be detected".to_string()))?;
+        self.engine.translate(&self.model, text, source, target).await
+    }
+}
+
+/// Translation with the DeepL API
+pub struct DeepLTranslator {
+    api_key: String,
+    endpoint: String,
+}
+
+impl DeepLTranslator {
+    /// Use an API key; free-plan keys (ending in `:fx`) go to the free endpoint
+    pub fn new(api_key: &str) -> Self {
+        let endpoint = if api_key.ends_with(":fx") {
+            "https://api-free.deepl.com/v2/translate"
+        } else {
+            "https://api.deepl.com/v2/translate"
+        };
+        Self {
+            api_key: api_key.to_string(),
+            endpoint: endpoint.to_string(),
+        }
+    }
+
+    /// Send requests to another endpoint, e.g. a proxy
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+
+    /// DeepL's code for a target language; English and Portuguese need a variant
+    fn target_code(language: &str) -> String {
+        match language {
+            "en" => "EN-US".to_string(),
+            "pt" => "PT-PT".to_string(),
+            other => other.to_uppercase(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Deserialize)]
+struct DeepLTranslation {
+    text: String,
+}
+
+#[async_trait]
+impl Translator for DeepLTranslator {
+    fn name(&self) -> &str {
+        "deepl"
+    }
+
+    async fn translate(&self, text: &str, source: Option<&str>, target: &str) -> McpResult<String> {
+        let mut body = serde_json::json!({
+            "text": [text],
+            "target_lang": Self::target_code(target),
+        });
+        if let Some(source) = source {
+            // Source codes never carry a variant
+            body["source_lang"] = serde_json::json!(source.split('-').next().unwrap_or(source).to_uppercase());
+        }
+
+        let response = http_client()?
+            .post(&self.endpoint)
+            .header("Authorization", format!("DeepL-Auth-Key {}", self.api_key))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(format!("DeepL request failed: {}", e)))?;
+        let parsed: DeepLResponse = read_response("DeepL", response).await?;
+        parsed
+            .translations
+            .into_iter()
+            .next()
+            .map(|t| t.text)
+            .ok_or_else(|| McpError::Protocol("DeepL returned no translation".to_string()))
+    }
+}
+
+/// Translation with Google Cloud Translation
+pub struct GoogleTranslator {
+    api_key: String,
+    endpoint: String,
+}
+
+impl GoogleTranslator {
+    /// Use an API key
+    pub fn new(api_key: &str) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            endpoint: "https://translation.googleapis.com/language/translate/v2".to_string(),
+        }
+    }
+
+    /// Send requests to another endpoint, e.g. a proxy
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+}
+
+#[derive(Deserialize)]
+struct GoogleResponse {
+    data: GoogleData,
+}
+
+#[derive(Deserialize)]
+struct GoogleData {
+    translations: Vec<GoogleTranslation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GoogleTranslation {
+    translated_text: String,
+}
+
+#[async_trait]
+impl Translator for GoogleTranslator {
+    fn name(&self) -> &str {
+        "google"
+    }
+
+    async fn translate(&self, text: &str, source: Option<&str>, target: &str) -> McpResult<String> {
+        let mut body = serde_json::json!({
+            "q": [text],
+            "target": target,
+            "format": "text",
+        });
+        if let Some(source) = source {
+            body["source"] = serde_json::json!(source);
+        }
+
+        let response = http_client()?
+            .post(&self.endpoint)
+            .query(&[("key", &self.api_key)])
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(format!("Google Translate request failed: {}", e)))?;
+        let parsed: GoogleResponse = read_response("Google Translate", response).await?;
+        parsed
+            .data
+            .translations
+            .into_iter()
+            .next()
+            .map(|t| t.translated_text)
+            .ok_or_else(|| McpError::Protocol("Google Translate returned no translation".to_string()))
+    }
+}
+
+fn http_client() -> McpResult<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| McpError::Connection(e.to_string()))
+}
+
+/// Decode a successful API response, mapping failures to errors
+async fn read_response<T: serde::de::DeserializeOwned>(service: &str, response: reqwest::Response) -> McpResult<T> {
+    let status = response.status();
+    if !status.is_success() {
+        let detail = response.text().await.unwrap_or_default();
+        let detail = detail.chars().take(200).collect::<String>();
+        return Err(match status.as_u16() {
+            401 | 403 => McpError::Authentication(format!("{} rejected the API key", service)),
+            // DeepL answers 456 when the character quota is used up
+            429 | 456 => McpError::RateLimit(format!("{} quota or rate limit reached", service)),
+            code => McpError::Protocol(format!("{} answered {}: {}", service, code, detail)),
+        });
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| McpError::Protocol(format!("Unexpected {} response: {}", service, e)))
+}
+
+/// Build the translator for a provider from the settings and the secret store
+pub fn translator_for(provider: TranslationProvider, settings: &TranslationSettings) -> McpResult<Arc<dyn Translator>> {
+    if provider == TranslationProvider::Local {
+        return Ok(Arc::new(LocalTranslator::new(settings.local_model.as_deref())?));
+    }
+
+    let secret = provider.secret_key().unwrap_or_default();
+    let key = secret_store().get(secret)?.ok_or_else(|| {
+        McpError::Config(format!(
+            "No {} API key; set one with `mcp translation key {}`",
+            provider.as_str(),
+            provider.as_str()
+        ))
+    })?;
+    Ok(match provider {
+        TranslationProvider::Google => Arc::new(GoogleTranslator::new(&key)),
+        _ => Arc::new(DeepLTranslator::new(&key)),
+    })
+}
+
+/// Words frequent in one language and rare in the others
+const STOPWORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "is", "are", "of", "to", "in", "that", "it", "you", "with", "for", "this", "what", "how", "can", "not", "have"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "ich", "ein", "eine", "mit", "zu", "den", "auf", "für", "wie", "sie", "es", "kann"]),
+    ("fr", &["le", "la", "les", "et", "est", "un", "une", "des", "je", "vous", "que", "pas", "pour", "dans", "qui", "sur", "avec", "ce"]),
+    ("es", &["el", "la", "los", "las", "y", "es", "un", "una", "que", "no", "por", "para", "con", "del", "como", "pero", "está", "qué"]),
+    ("it", &["il", "la", "di", "e", "è", "che", "non", "un", "una", "per", "con", "sono", "come", "del", "della", "questo", "gli", "mi"]),
+    ("pt", &["o", "a", "os", "as", "e", "é", "um", "uma", "que", "não", "com", "para", "do", "da", "em", "como", "você", "isso"]),
+    ("nl", &["de", "het", "een", "en", "is", "niet", "ik", "van", "dat", "met", "voor", "op", "zijn", "je", "wat", "hoe", "er", "maar"]),
+];
+
+/// Detect the language of a text as an ISO 639-1 code. Returns `None` when
+/// the text is too short or too mixed to tell.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut scripts: HashMap<&'static str, usize> = HashMap::new();
+    let mut letters = 0;
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        let script = match c as u32 {
+            0x3040..=0x30FF => "ja",
+            0xAC00..=0xD7AF | 0x1100..=0x11FF => "ko",
+            0x4E00..=0x9FFF | 0x3400..=0x4DBF => "zh",
+            0x0400..=0x04FF => "cyrillic",
+            0x0370..=0x03FF => "el",
+            0x0600..=0x06FF => "ar",
+            0x0590..=0x05FF => "he",
+            0x0900..=0x097F => "hi",
+            0x0E00..=0x0E7F => "th",
+            _ => "latin",
+        };
+        *scripts.entry(script).or_insert(0) += 1;
+    }
+    if letters == 0 {
+        return None;
+    }
+
+    // Japanese mixes kana with Han characters
+    if scripts.get("ja").copied().unwrap_or(0) > 0 && scripts.contains_key("zh") {
+        return Some("ja");
+    }
+    let (&script, &count) = scripts.iter().max_by_key(|(_, &count)| count)?;
+    if count * 2 < letters {
+        return None;
+    }
+    match script {
+        "cyrillic" => {
+            let ukrainian = text.chars().any(|c| matches!(c, 'і' | 'ї' | 'є' | 'ґ'));
+            Some(if ukrainian { "uk" } else { "ru" })
+        }
+        "latin" => detect_latin(text, letters),
+        other => Some(other),
+    }
+}
+
+/// Pick among Latin-script languages by their most frequent words
+fn detect_latin(text: &str, letters: usize) -> Option<&'static str> {
+    if letters < MIN_DETECT_LETTERS {
+        return None;
+    }
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let mut scores: Vec<(&'static str, usize)> = STOPWORDS
+        .iter()
+        .map(|(language, stopwords)| (*language, words.iter().filter(|w| stopwords.contains(w)).count()))
+        .collect();
+    // Letters only one of the languages uses settle close calls
+    for (language, score) in scores.iter_mut() {
+        let marker = match *language {
+            "de" => lower.contains(&['ß', 'ä', 'ö', 'ü'][..]),
+            "es" => lower.contains(&['ñ', '¿', '¡'][..]),
+            "fr" => lower.contains(&['ç', 'ê', 'è', 'œ'][..]),
+            "pt" => lower.contains(&['ã', 'õ'][..]),
+            _ => false,
+        };
+        if marker {
+            *score += 2;
+        }
+    }
+    scores.sort_by_key(|s| std::cmp::Reverse(s.1));
+
+    let (best, score) = scores[0];
+    let runner_up = scores[1].1;
+    (score >= 2 && score > runner_up).then_some(best)
+}
+
+/// Translate text, leaving fenced code blocks and blank lines as they are
+pub async fn translate_text(translator: &dyn Translator, text: &str, source: Option<&str>, target: &str) -> McpResult<String> {
+    let mut translated = String::with_capacity(text.len());
+    let mut prose = String::new();
+    let mut in_code = false;
+
+    for line in text.split_inclusive('\n') {
+        let fence = line.trim_start().starts_with("```");
+        if in_code || fence {
+            if !prose.is_empty() {
+                translated.push_str(&translate_prose(translator, &prose, source, target).await?);
+                prose.clear();
+            }
+            translated.push_str(line);
+            if fence {
+                in_code = !in_code;
+            }
+        } else {
+            prose.push_str(line);
+        }
+    }
+    if !prose.is_empty() {
+        translated.push_str(&translate_prose(translator, &prose, source, target).await?);
+    }
+    Ok(translated)
+}
+
+/// Translate a stretch of prose, keeping its surrounding whitespace
+async fn translate_prose(translator: &dyn Translator, text: &str, source: Option<&str>, target: &str) -> McpResult<String> {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return Ok(text.to_string());
+    }
+    let start = text.len() - text.trim_start().len();
+    let end = text.trim_end().len();
+    let result = translator.translate(trimmed, source, target).await?;
+    Ok(format!("{}{}{}", &text[..start], result.trim(), &text[end..]))
+}
+
+/// Built-in middleware translating messages of conversations that have a
+/// language preference, or of all conversations when translation is enabled
+#[derive(Default)]
+pub struct TranslationMiddleware {
+    /// Backend to use instead of the configured one
+    translator: Option<Arc<dyn Translator>>,
+}
+
+impl TranslationMiddleware {
+    /// Translate with a specific backend
+    pub fn with_translator(translator: Arc<dyn Translator>) -> Self {
+        Self {
+            translator: Some(translator),
+        }
+    }
+
+    /// Languages that apply to a request, if it's translated at all
+    fn preference(ctx: &MiddlewareContext) -> Option<LanguagePreference> {
+        if let Some(value) = ctx.values.get(LANGUAGE_METADATA_KEY) {
+            return serde_json::from_value(value.clone()).ok();
+        }
+        let settings = get_translation_settings().read().unwrap().clone();
+        settings.enabled.then_some(settings.defaults)
+    }
+
+    fn translator(&self, preference: &LanguagePreference) -> McpResult<Arc<dyn Translator>> {
+        if let Some(translator) = &self.translator {
+            return Ok(translator.clone());
+        }
+        let settings = get_translation_settings().read().unwrap().clone();
+        translator_for(preference.provider.unwrap_or(settings.provider), &settings)
+    }
+
+    /// Translate a user message into the model's language, remembering the
+    /// user's language for the reply
+    pub async fn translate_request(&self, ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        let Some(preference) = Self::preference(ctx) else {
+            return Ok(());
+        };
+        if message.role != MessageRole::User {
+            return Ok(());
+        }
+
+        let text = message.text();
+        let source = if preference.user == AUTO_LANGUAGE {
+            detect_language(&text).map(str::to_string)
+        } else {
+            Some(preference.user.clone())
+        };
+        let Some(source) = source else {
+            debug!("Couldn't detect the language of message {}; sending it as is", message.id);
+            return Ok(());
+        };
+        ctx.values
+            .insert(USER_LANGUAGE_CONTEXT_KEY.to_string(), serde_json::json!(source));
+        if same_language(&source, &preference.model) {
+            return Ok(());
+        }
+
+        let translator = self.translator(&preference)?;
+        translate_message(translator.as_ref(), message, &source, &preference.model).await
+    }
+
+    /// Translate a reply into the user's language
+    pub async fn translate_reply(&self, ctx: &MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        let Some(preference) = Self::preference(ctx) else {
+            return Ok(());
+        };
+        if message.role != MessageRole::Assistant {
+            return Ok(());
+        }
+
+        let target = match ctx.values.get(USER_LANGUAGE_CONTEXT_KEY).and_then(|l| l.as_str()) {
+            Some(language) => language.to_string(),
+            None if preference.user != AUTO_LANGUAGE => preference.user.clone(),
+            None => return Ok(()),
+        };
+        if same_language(&target, &preference.model) {
+            return Ok(());
+        }
+
+        let translator = self.translator(&preference)?;
+        translate_message(translator.as_ref(), message, &preference.model, &target).await
+    }
+}
+
+#[async_trait]
+impl MessageMiddleware for TranslationMiddleware {
+    fn name(&self) -> &str {
+        "translation"
+    }
+
+    async fn pre_send(&self, ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        self.translate_request(ctx, message).await
+    }
+
+    async fn post_receive(&self, ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        self.translate_reply(ctx, message).await
+    }
+
+    // Chunks are partial sentences; streamed replies are translated once complete
+}
+
+/// Whether two codes name the same language, ignoring regional variants
+fn same_language(a: &str, b: &str) -> bool {
+    let base = |code: &str| code.split('-').next().unwrap_or(code).to_lowercase();
+    base(a) == base(b)
+}
+
+/// Translate the text of a message, keeping the original in its metadata.
+/// Text parts are joined first, since a streamed reply has one per chunk.
+async fn translate_message(translator: &dyn Translator, message: &mut Message, source: &str, target: &str) -> McpResult<()> {
+    let original = message.text();
+    let translated = translate_text(translator, &original, Some(source), target).await?;
+
+    let mut translated = Some(translated);
+    message.content.parts.retain_mut(|part| match part {
+        ContentType::Text { text } => match translated.take() {
+            Some(translation) => {
+                *text = translation;
+                true
+            }
+            None => false,
+        },
+        _ => true,
+    });
+    message.metadata.get_or_insert_with(HashMap::new).insert(
+        TRANSLATION_METADATA_KEY.to_string(),
+        serde_json::json!({
+            "original": original,
+            "from": source,
+            "to": target,
+            "provider": translator.name(),
+        }),
+    );
+    Ok(())
+}
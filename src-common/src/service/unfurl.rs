@@ -0,0 +1,285 @@
+use chrono::{DateTime, Duration, Utc};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::config::{data_path, get_settings};
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+use crate::models::Message;
+
+/// How long a cached preview is used before it is fetched again
+const CACHE_TTL_DAYS: i64 = 7;
+
+/// Most of a page that is read looking for metadata
+const MAX_PAGE_BYTES: usize = 512 * 1024;
+
+/// Request timeout
+const FETCH_TIMEOUT_SECS: u64 = 5;
+
+static URL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"https?://[^\s<>"'`)\]]+"#).unwrap());
+static TITLE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap());
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<(meta|link)\s[^>]*>").unwrap());
+static ATTR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?is)([a-z][a-z0-9:_-]*)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+/// Title and preview metadata for a link
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LinkPreview {
+    /// The link
+    pub url: String,
+
+    /// Page title
+    pub title: Option<String>,
+
+    /// Short description
+    pub description: Option<String>,
+
+    /// Site name
+    pub site_name: Option<String>,
+
+    /// Preview image URL
+    pub image: Option<String>,
+
+    /// Favicon URL
+    pub favicon: Option<String>,
+
+    /// When the metadata was fetched
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Links appearing in a text, in order and without duplicates
+pub fn extract_urls(text: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for m in URL_RE.find_iter(text) {
+        // Trailing punctuation usually ends the sentence, not the URL
+        let url = m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        if !urls.iter().any(|u| u == url) {
+            urls.push(url.to_string());
+        }
+    }
+    urls
+}
+
+/// Decode the HTML entities that commonly appear in titles
+fn decode_entities(text: &str) -> String {
+    text.replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parse OpenGraph, Twitter card and plain HTML metadata from a page
+pub fn parse_preview(page_url: &url::Url, html: &str) -> LinkPreview {
+    let mut meta: HashMap<String, String> = HashMap::new();
+    let mut favicon = None;
+
+    for tag in TAG_RE.captures_iter(html) {
+        let attrs: HashMap<String, String> = ATTR_RE
+            .captures_iter(&tag[0])
+            .map(|c| {
+                let value = c.get(2).or_else(|| c.get(3)).map(|v| v.as_str()).unwrap_or_default();
+                (c[1].to_lowercase(), decode_entities(value))
+            })
+            .collect();
+
+        if tag[1].eq_ignore_ascii_case("link") {
+            let is_icon = attrs
+                .get("rel")
+                .map(|rel| rel.to_lowercase().split_whitespace().any(|r| r == "icon"))
+                .unwrap_or(false);
+            if is_icon && favicon.is_none() {
+                favicon = attrs.get("href").cloned();
+            }
+            continue;
+        }
+
+        let key = attrs.get("property").or_else(|| attrs.get("name"));
+        if let (Some(key), Some(content)) = (key, attrs.get("content")) {
+            meta.entry(key.to_lowercase()).or_insert_with(|| content.clone());
+        }
+    }
+
+    let pick = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|k| meta.get(*k))
+            .filter(|v| !v.is_empty())
+            .cloned()
+    };
+    let resolve = |href: String| page_url.join(&href).map(|u| u.to_string()).unwrap_or(href);
+
+    LinkPreview {
+        url: page_url.to_string(),
+        title: pick(&["og:title", "twitter:title"]).or_else(|| {
+            TITLE_RE
+                .captures(html)
+                .map(|c| decode_entities(&c[1]))
+                .filter(|t| !t.is_empty())
+        }),
+        description: pick(&["og:description", "twitter:description", "description"]),
+        site_name: pick(&["og:site_name"]),
+        image: pick(&["og:image", "twitter:image"]).map(resolve),
+        favicon: Some(resolve(favicon.unwrap_or_else(|| "/favicon.ico".to_string()))),
+        fetched_at: Utc::now(),
+    }
+}
+
+/// Fetches and caches link previews.
+///
+/// Nothing is fetched unless link previews are enabled in the settings, and
+/// only from allowed domains.
+pub struct Unfurler {
+    path: PathBuf,
+    cache: Mutex<HashMap<String, LinkPreview>>,
+    client: reqwest::Client,
+}
+
+impl Unfurler {
+    fn new(path: PathBuf) -> Self {
+        let cache = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(FETCH_TIMEOUT_SECS))
+            .user_agent(concat!("papin-link-preview/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            path,
+            cache: Mutex::new(cache),
+            client,
+        }
+    }
+
+    fn save(&self, cache: &HashMap<String, LinkPreview>) {
+        let result = serde_json::to_string(cache)
+            .map_err(McpError::from)
+            .and_then(|content| Ok(fs::write(&self.path, content)?));
+        if let Err(e) = result {
+            warn!("Failed to save link preview cache: {}", e);
+        }
+    }
+
+    /// A cached preview that hasn't expired
+    pub fn cached(&self, url: &str) -> Option<LinkPreview> {
+        self.cache
+            .lock()
+            .unwrap()
+            .get(url)
+            .filter(|p| Utc::now() - p.fetched_at < Duration::days(CACHE_TTL_DAYS))
+            .cloned()
+    }
+
+    /// Get a preview, fetching it if it isn't cached and the settings allow it
+    pub async fn unfurl(&self, url: &str) -> McpResult<Option<LinkPreview>> {
+        if let Some(preview) = self.cached(url) {
+            return Ok(Some(preview));
+        }
+
+        let parsed = url::Url::parse(url).map_err(|e| McpError::InvalidRequest(format!("Invalid URL {}: {}", url, e)))?;
+        let links = get_settings().lock().unwrap().links.clone();
+        if !links.allows(&parsed) {
+            return Ok(None);
+        }
+
+        let mut response = self
+            .client
+            .get(parsed.clone())
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(e.to_string()))?;
+        let is_html = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.contains("html"))
+            .unwrap_or(true);
+
+        let mut preview = if response.status().is_success() && is_html {
+            let mut body = Vec::new();
+            while let Some(chunk) = response.chunk().await.map_err(|e| McpError::Connection(e.to_string()))? {
+                body.extend_from_slice(&chunk);
+                if body.len() >= MAX_PAGE_BYTES {
+                    break;
+                }
+            }
+            parse_preview(response.url(), &String::from_utf8_lossy(&body))
+        } else {
+            // Still worth caching so the link isn't fetched on every render
+            LinkPreview {
+                url: parsed.to_string(),
+                fetched_at: Utc::now(),
+                ..LinkPreview::default()
+            }
+        };
+        preview.url = url.to_string();
+
+        debug!("Unfurled {}: {:?}", url, preview.title);
+        let mut cache = self.cache.lock().unwrap();
+        cache.insert(url.to_string(), preview.clone());
+        self.save(&cache);
+
+        Ok(Some(preview))
+    }
+
+    /// Unfurl the links of a message in the background, announcing each
+    /// preview on the event bus as it arrives
+    pub fn unfurl_message(self: &Arc<Self>, conversation_id: &str, message: &Message) {
+        if !get_settings().lock().unwrap().links.unfurl {
+            return;
+        }
+        let urls = extract_urls(&message.text());
+        if urls.is_empty() {
+            return;
+        }
+
+        let unfurler = self.clone();
+        let conversation_id = conversation_id.to_string();
+        let message_id = message.id.clone();
+        tokio::spawn(async move {
+            for url in urls {
+                match unfurler.unfurl(&url).await {
+                    Ok(Some(preview)) => get_event_bus().emit(
+                        Topic::Conversation,
+                        names::LINK_UNFURLED,
+                        serde_json::json!({
+                            "conversation_id": conversation_id,
+                            "message_id": message_id,
+                            "preview": preview,
+                        }),
+                    ),
+                    Ok(None) => {}
+                    Err(e) => debug!("No preview for {}: {}", url, e),
+                }
+            }
+        });
+    }
+
+    /// Drop all cached previews
+    pub fn clear(&self) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.clear();
+        self.save(&cache);
+    }
+}
+
+static UNFURLER: Lazy<Arc<Unfurler>> = Lazy::new(|| Arc::new(Unfurler::new(data_path("link_previews.json"))));
+
+/// Get the global link unfurler
+pub fn get_unfurler() -> Arc<Unfurler> {
+    UNFURLER.clone()
+}
@@ -0,0 +1,362 @@
+//! Conversation snapshots
+//!
+//! A conversation is rendered to one self-contained HTML file, optionally
+//! with secrets and personal details redacted. The file can be kept locally
+//! or uploaded to a secret gist or an S3 bucket, which returns a link to
+//! share. Uploaded shares are recorded with their expiry, so they can be
+//! listed, revoked by hand, and revoked once they expire.
+
+pub mod render;
+pub mod upload;
+
+pub use render::{render_html, SnapshotOptions};
+pub use upload::{GistUploader, S3Config, S3Credentials, S3Uploader, ShareTarget, ShareUploader};
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::models::Conversation;
+use crate::utils::clock;
+
+/// Settings of shared snapshots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShareSettings {
+    /// Where snapshots are uploaded unless asked otherwise
+    #[serde(default)]
+    pub target: Option<ShareTarget>,
+
+    /// Bucket for S3 uploads
+    #[serde(default)]
+    pub s3: Option<S3Config>,
+
+    /// Days until uploaded snapshots are revoked; kept until revoked by
+    /// hand when unset
+    #[serde(default)]
+    pub expiry_days: Option<u32>,
+
+    /// Redact snapshots unless asked otherwise
+    #[serde(default = "default_true")]
+    pub redact: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ShareSettings {
+    fn default() -> Self {
+        Self {
+            target: None,
+            s3: None,
+            expiry_days: Some(30),
+            redact: true,
+        }
+    }
+}
+
+impl ShareSettings {
+    fn path() -> PathBuf {
+        data_path("sharing.json")
+    }
+
+    /// Load the settings, falling back to the defaults
+    pub fn load() -> Self {
+        match fs::read_to_string(Self::path()) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Invalid sharing settings, using defaults: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Persist the settings
+    pub fn save(&self) -> McpResult<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+static SHARE_SETTINGS: Lazy<Arc<RwLock<ShareSettings>>> = Lazy::new(|| Arc::new(RwLock::new(ShareSettings::load())));
+
+/// Get the global sharing settings
+pub fn get_share_settings() -> Arc<RwLock<ShareSettings>> {
+    SHARE_SETTINGS.clone()
+}
+
+/// An uploaded snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Share {
+    /// Share ID
+    pub id: String,
+
+    /// Conversation the snapshot is of
+    pub conversation_id: String,
+
+    /// Title shown in the snapshot
+    pub title: String,
+
+    /// Where it was uploaded
+    pub target: ShareTarget,
+
+    /// What the target knows it by
+    pub remote_id: String,
+
+    /// Link to the snapshot
+    pub url: String,
+
+    /// Whether secrets and personal details were redacted
+    pub redacted: bool,
+
+    /// When it was shared
+    pub created_at: DateTime<Utc>,
+
+    /// When it is revoked on its own
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// When it was revoked
+    #[serde(default)]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Share {
+    /// Whether the link still works
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+
+    /// Whether it has expired and not been revoked yet
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.is_active() && self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+/// Uploaded snapshots, including revoked ones
+pub struct ShareStore {
+    path: PathBuf,
+    shares: Mutex<Vec<Share>>,
+}
+
+impl ShareStore {
+    /// Store kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        let shares = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            shares: Mutex::new(shares),
+        }
+    }
+
+    fn save(&self, shares: &[Share]) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(shares)?)?;
+        Ok(())
+    }
+
+    /// Shares, newest last
+    pub fn list(&self) -> Vec<Share> {
+        self.shares.lock().unwrap().clone()
+    }
+
+    /// A share by ID or unique ID prefix
+    pub fn get(&self, id: &str) -> McpResult<Share> {
+        let shares = self.shares.lock().unwrap();
+        if let Some(share) = shares.iter().find(|s| s.id == id) {
+            return Ok(share.clone());
+        }
+        let matches: Vec<&Share> = shares.iter().filter(|s| !id.is_empty() && s.id.starts_with(id)).collect();
+        match matches.as_slice() {
+            [share] => Ok((*share).clone()),
+            [] => Err(McpError::InvalidRequest(format!("No share {}", id))),
+            _ => Err(McpError::InvalidRequest(format!("More than one share starts with {}", id))),
+        }
+    }
+
+    fn add(&self, share: Share) -> McpResult<()> {
+        let mut shares = self.shares.lock().unwrap();
+        shares.push(share);
+        self.save(&shares)
+    }
+
+    fn mark_revoked(&self, id: &str) -> McpResult<Share> {
+        let mut shares = self.shares.lock().unwrap();
+        let share = shares
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("No share {}", id)))?;
+        share.revoked_at = Some(clock::now());
+        let share = share.clone();
+        self.save(&shares)?;
+        Ok(share)
+    }
+}
+
+static SHARE_STORE: Lazy<Arc<ShareStore>> = Lazy::new(|| Arc::new(ShareStore::at(data_path("shares.json"))));
+
+/// Get the global list of uploaded snapshots
+pub fn get_share_store() -> Arc<ShareStore> {
+    SHARE_STORE.clone()
+}
+
+/// File name of a snapshot: the title, lowercased, with dashes
+pub fn file_name(title: &str) -> String {
+    let slug: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let slug = slug.split('-').filter(|s| !s.is_empty()).take(8).collect::<Vec<_>>().join("-");
+    let slug: String = slug.chars().filter(|c| c.is_ascii()).collect();
+    if slug.is_empty() {
+        "conversation.html".to_string()
+    } else {
+        format!("{}.html", slug)
+    }
+}
+
+/// Uploads snapshots and revokes them
+pub struct Sharer {
+    store: Arc<ShareStore>,
+    uploaders: Vec<Arc<dyn ShareUploader>>,
+}
+
+impl Sharer {
+    /// Sharer over the global list, with the targets that are set up
+    pub fn new() -> Self {
+        let settings = get_share_settings().read().unwrap().clone();
+        let mut uploaders: Vec<Arc<dyn ShareUploader>> = Vec::new();
+        if let Ok(gist) = GistUploader::from_secret_store() {
+            uploaders.push(Arc::new(gist));
+        }
+        if let Some(config) = settings.s3 {
+            match S3Credentials::from_secret_store() {
+                Ok(credentials) => uploaders.push(Arc::new(S3Uploader::new(config, credentials))),
+                Err(e) => warn!("S3 sharing is set up without keys: {}", e),
+            }
+        }
+        Self::with_uploaders(get_share_store(), uploaders)
+    }
+
+    /// Sharer over a given list and targets
+    pub fn with_uploaders(store: Arc<ShareStore>, uploaders: Vec<Arc<dyn ShareUploader>>) -> Self {
+        Self { store, uploaders }
+    }
+
+    fn uploader(&self, target: ShareTarget) -> McpResult<&Arc<dyn ShareUploader>> {
+        self.uploaders.iter().find(|u| u.target() == target).ok_or_else(|| {
+            let what = match target {
+                ShareTarget::Gist => "a GitHub token",
+                ShareTarget::S3 => "a bucket and access keys",
+            };
+            McpError::Config(format!("Sharing to {} needs {}", target.as_str(), what))
+        })
+    }
+
+    /// Expiry of a share made now: the options' own, else the configured one
+    pub fn expiry(options: &SnapshotOptions) -> Option<DateTime<Utc>> {
+        options.expires_at.or_else(|| {
+            let days = get_share_settings().read().unwrap().expiry_days?;
+            Some(clock::now() + ChronoDuration::days(days as i64))
+        })
+    }
+
+    /// Render a conversation and upload it; the default target is used
+    /// when none is given
+    pub async fn publish(
+        &self,
+        conversation: &Conversation,
+        target: Option<ShareTarget>,
+        options: &SnapshotOptions,
+    ) -> McpResult<Share> {
+        let target = match target.or_else(|| get_share_settings().read().unwrap().target) {
+            Some(target) => target,
+            None => match self.uploaders.as_slice() {
+                [only] => only.target(),
+                _ => return Err(McpError::Config("Choose where to share: gist or s3".to_string())),
+            },
+        };
+        let uploader = self.uploader(target)?;
+
+        let mut options = options.clone();
+        options.expires_at = Self::expiry(&options);
+        let title = options.title.clone().unwrap_or_else(|| conversation.title.clone());
+        let html = render_html(conversation, &options);
+        let upload = uploader.upload(&file_name(&title), &html, options.expires_at).await?;
+
+        let share = Share {
+            id: uuid::Uuid::new_v4().to_string(),
+            conversation_id: conversation.id.clone(),
+            title,
+            target,
+            remote_id: upload.remote_id,
+            url: upload.url,
+            redacted: options.redact,
+            created_at: clock::now(),
+            expires_at: options.expires_at,
+            revoked_at: None,
+        };
+        self.store.add(share.clone())?;
+        info!("Shared conversation {} to {} as {}", conversation.id, target.as_str(), share.id);
+        Ok(share)
+    }
+
+    /// Delete an upload so its link stops working
+    pub async fn revoke(&self, id: &str) -> McpResult<Share> {
+        let share = self.store.get(id)?;
+        if !share.is_active() {
+            return Ok(share);
+        }
+        self.uploader(share.target)?.delete(&share.remote_id).await?;
+        let share = self.store.mark_revoked(&share.id)?;
+        info!("Revoked share {}", share.id);
+        Ok(share)
+    }
+
+    /// Revoke every share past its expiry; returns those revoked
+    pub async fn revoke_expired(&self) -> Vec<Share> {
+        let now = clock::now();
+        let mut revoked = Vec::new();
+        for share in self.store.list().into_iter().filter(|s| s.is_expired(now)) {
+            match self.revoke(&share.id).await {
+                Ok(share) => revoked.push(share),
+                Err(e) => warn!("Failed to revoke expired share {}: {}", share.id, e),
+            }
+        }
+        revoked
+    }
+}
+
+impl Default for Sharer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Revoke expired shares on an interval
+pub fn spawn_share_expiry(interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            Sharer::new().revoke_expired().await;
+        }
+    })
+}
@@ -0,0 +1,209 @@
+//! Conversations as single HTML files
+//!
+//! Everything from the conversation is escaped, and a content security
+//! policy keeps the page from loading or running anything, so a snapshot is
+//! safe to open whatever the messages contain. Styles are inline; the file
+//! needs nothing else.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::config::LoggingSettings;
+use crate::logs::Redactor;
+use crate::models::{Conversation, MessageRole};
+use crate::utils::clock;
+
+/// Replaces email addresses in redacted snapshots
+pub const REDACTED_EMAIL: &str = "[email]";
+
+/// Name of the meta tag holding the expiry time
+pub const EXPIRES_META: &str = "papin-snapshot-expires";
+
+static EMAIL: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}\b").unwrap());
+static INLINE_CODE: Lazy<Regex> = Lazy::new(|| Regex::new(r"`([^`\n]+)`").unwrap());
+static BOLD: Lazy<Regex> = Lazy::new(|| Regex::new(r"\*\*([^*\n]+)\*\*").unwrap());
+
+const STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;max-width:760px;\
+margin:2rem auto;padding:0 1rem;color:#1f2328;background:#fff;line-height:1.55}\
+header{border-bottom:1px solid #d0d7de;margin-bottom:1.5rem}header p{color:#656d76;font-size:.9rem}\
+.message{margin:1rem 0;padding:.75rem 1rem;border-radius:8px}.user{background:#f6f8fa}\
+.assistant{background:#fff;border:1px solid #d0d7de}.system{background:#fff8c5;font-size:.9rem}\
+.role{font-weight:600;font-size:.8rem;text-transform:uppercase;color:#656d76;margin-bottom:.25rem}.edited{font-weight:400;text-transform:none}\
+pre{background:#f6f8fa;padding:.75rem;border-radius:6px;overflow-x:auto}code{font-family:ui-monospace,monospace;font-size:.9em}\
+footer{color:#656d76;font-size:.8rem;margin-top:2rem}\
+@media (prefers-color-scheme:dark){body{background:#0d1117;color:#e6edf3}.user,pre{background:#161b22}\
+.assistant{background:#0d1117;border-color:#30363d}.system{background:#272115}}";
+
+/// What goes into a snapshot
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotOptions {
+    /// Replace API keys, tokens, passwords, email addresses and file paths
+    #[serde(default)]
+    pub redact: bool,
+
+    /// Include system messages
+    #[serde(default)]
+    pub include_system: bool,
+
+    /// Title to show instead of the conversation's
+    #[serde(default)]
+    pub title: Option<String>,
+
+    /// When the snapshot should stop being available
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Escape text for HTML content and attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Secrets, emails and paths replaced
+pub fn redact(text: &str) -> String {
+    let redactor = Redactor::new(&LoggingSettings {
+        redact_api_keys: true,
+        redact_prompts: false,
+        redact_paths: true,
+        ..LoggingSettings::default()
+    });
+    EMAIL.replace_all(&redactor.redact(text), REDACTED_EMAIL).into_owned()
+}
+
+/// Inline formatting of escaped text: code spans and bold
+fn inline(escaped: &str) -> String {
+    let text = INLINE_CODE.replace_all(escaped, "<code>$1</code>");
+    BOLD.replace_all(&text, "<strong>$1</strong>").into_owned()
+}
+
+/// Message text as HTML: fenced code blocks, paragraphs and line breaks.
+/// The text is escaped before any markup is added.
+fn message_html(text: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<String> = Vec::new();
+    let mut code: Option<Vec<String>> = None;
+
+    let flush = |paragraph: &mut Vec<String>, html: &mut String| {
+        if !paragraph.is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", paragraph.join("<br>\n")));
+            paragraph.clear();
+        }
+    };
+
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            match code.take() {
+                Some(lines) => html.push_str(&format!("<pre><code>{}</code></pre>\n", lines.join("\n"))),
+                None => {
+                    flush(&mut paragraph, &mut html);
+                    code = Some(Vec::new());
+                }
+            }
+        } else if let Some(lines) = code.as_mut() {
+            lines.push(escape_html(line));
+        } else if line.trim().is_empty() {
+            flush(&mut paragraph, &mut html);
+        } else {
+            paragraph.push(inline(&escape_html(line)));
+        }
+    }
+    // An unclosed fence still shows as code
+    if let Some(lines) = code {
+        html.push_str(&format!("<pre><code>{}</code></pre>\n", lines.join("\n")));
+    }
+    flush(&mut paragraph, &mut html);
+    html
+}
+
+/// Render a conversation to a self-contained HTML document
+pub fn render_html(conversation: &Conversation, options: &SnapshotOptions) -> String {
+    let clean = |text: &str| if options.redact { redact(text) } else { text.to_string() };
+    let title = escape_html(&clean(options.title.as_deref().unwrap_or(&conversation.title)));
+    let created_at = clock::now();
+
+    let mut messages = String::new();
+    let mut shown = 0;
+    for message in &conversation.messages {
+        let (class, role) = match message.role {
+            MessageRole::User => ("user", "You".to_string()),
+            MessageRole::Assistant => ("assistant", conversation.model.name.clone()),
+            MessageRole::System if options.include_system => ("system", "System".to_string()),
+            MessageRole::System | MessageRole::Tool => continue,
+        };
+        let text = message.text();
+        if text.trim().is_empty() {
+            continue;
+        }
+        shown += 1;
+        let edited = if message.is_edited() { " <span class=\"edited\">edited</span>" } else { "" };
+        messages.push_str(&format!(
+            "<section class=\"message {}\">\n<div class=\"role\">{}{}</div>\n{}</section>\n",
+            class,
+            escape_html(&role),
+            edited,
+            message_html(&clean(&text))
+        ));
+    }
+
+    let expires = options
+        .expires_at
+        .map(|at| {
+            format!(
+                "<meta name=\"{}\" content=\"{}\">\n",
+                EXPIRES_META,
+                at.to_rfc3339()
+            )
+        })
+        .unwrap_or_default();
+    let expiry_note = options
+        .expires_at
+        .map(|at| format!(" · Available until {}", at.format("%B %-d, %Y")))
+        .unwrap_or_default();
+
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<meta http-equiv=\"Content-Security-Policy\" content=\"default-src 'none'; style-src 'unsafe-inline'; img-src data:\">
+<meta name=\"referrer\" content=\"no-referrer\">
+<meta name=\"generator\" content=\"Papin\">
+<meta name=\"papin-snapshot-created\" content=\"{created}\">
+{expires}<title>{title}</title>
+<style>{style}</style>
+</head>
+<body>
+<header>
+<h1>{title}</h1>
+<p>{count} messages · Shared {shared}{expiry_note}</p>
+</header>
+<main>
+{messages}</main>
+<footer>Snapshot of a Papin conversation</footer>
+</body>
+</html>
+",
+        created = created_at.to_rfc3339(),
+        expires = expires,
+        title = title,
+        style = STYLE,
+        count = shown,
+        shared = created_at.format("%B %-d, %Y"),
+        expiry_note = expiry_note,
+        messages = messages,
+    )
+}
@@ -0,0 +1,485 @@
+//! Where shared snapshots are uploaded
+//!
+//! A secret gist on GitHub, using the GitHub token, or an S3 bucket (or an
+//! S3-compatible store), with requests signed with AWS Signature Version 4.
+//! Private buckets get presigned links, which S3 caps at seven days.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ring::{digest, hmac};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::auth::secret_store;
+use crate::error::{McpError, McpResult};
+use crate::integrations::github;
+use crate::utils::clock;
+
+/// Secret holding the S3 access key ID
+pub const S3_ACCESS_KEY_SECRET: &str = "s3_access_key_id";
+
+/// Secret holding the S3 secret access key
+pub const S3_SECRET_KEY_SECRET: &str = "s3_secret_access_key";
+
+/// Longest a presigned S3 link works
+pub const MAX_PRESIGN_SECS: i64 = 7 * 24 * 3600;
+
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Kinds of upload target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareTarget {
+    /// A secret GitHub gist
+    Gist,
+    /// An S3 bucket
+    S3,
+}
+
+impl ShareTarget {
+    /// Name used in settings and on the command line
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShareTarget::Gist => "gist",
+            ShareTarget::S3 => "s3",
+        }
+    }
+}
+
+impl std::str::FromStr for ShareTarget {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "gist" | "github" => Ok(ShareTarget::Gist),
+            "s3" => Ok(ShareTarget::S3),
+            other => Err(McpError::InvalidRequest(format!(
+                "Unknown share target '{}'; expected gist or s3",
+                other
+            ))),
+        }
+    }
+}
+
+/// An uploaded file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Upload {
+    /// What the target knows it by: the gist ID or the object key
+    pub remote_id: String,
+
+    /// Link to share
+    pub url: String,
+}
+
+/// Somewhere snapshots can be uploaded to and deleted from
+#[async_trait]
+pub trait ShareUploader: Send + Sync {
+    /// Target kind
+    fn target(&self) -> ShareTarget;
+
+    /// Upload an HTML file; the link should stop working at `expires_at`
+    /// where the target can do that
+    async fn upload(&self, name: &str, html: &str, expires_at: Option<DateTime<Utc>>) -> McpResult<Upload>;
+
+    /// Delete an upload; one that is already gone counts as deleted
+    async fn delete(&self, remote_id: &str) -> McpResult<()>;
+}
+
+/// Uploads to secret gists
+pub struct GistUploader {
+    token: String,
+    api_url: String,
+    client: reqwest::Client,
+}
+
+impl GistUploader {
+    /// Uploader with a GitHub token allowed to create gists
+    pub fn new(token: String) -> Self {
+        Self {
+            token,
+            api_url: GITHUB_API_URL.to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Uploader with the GitHub token from the secret store
+    pub fn from_secret_store() -> McpResult<Self> {
+        let token = secret_store()
+            .get(github::TOKEN_SECRET)?
+            .ok_or_else(|| McpError::Config("Set a GitHub token to share to gists".to_string()))?;
+        Ok(Self::new(token))
+    }
+
+    /// Talk to another API, e.g. GitHub Enterprise
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.api_url, path))
+            .timeout(REQUEST_TIMEOUT)
+            .bearer_auth(&self.token)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header(reqwest::header::USER_AGENT, "papin")
+    }
+}
+
+#[async_trait]
+impl ShareUploader for GistUploader {
+    fn target(&self) -> ShareTarget {
+        ShareTarget::Gist
+    }
+
+    async fn upload(&self, name: &str, html: &str, _expires_at: Option<DateTime<Utc>>) -> McpResult<Upload> {
+        let body = serde_json::json!({
+            "description": "Papin conversation snapshot",
+            "public": false,
+            "files": { name: { "content": html } },
+        });
+        let response = self
+            .request(reqwest::Method::POST, "/gists")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(format!("Failed to create the gist: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(McpError::Connection(format!("GitHub refused the gist: {}", response.status())));
+        }
+        let gist: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| McpError::Connection(format!("Unexpected gist response: {}", e)))?;
+        match (gist["id"].as_str(), gist["html_url"].as_str()) {
+            (Some(id), Some(url)) => Ok(Upload {
+                remote_id: id.to_string(),
+                url: url.to_string(),
+            }),
+            _ => Err(McpError::Connection("The gist response has no ID or link".to_string())),
+        }
+    }
+
+    async fn delete(&self, remote_id: &str) -> McpResult<()> {
+        let response = self
+            .request(reqwest::Method::DELETE, &format!("/gists/{}", remote_id))
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(format!("Failed to delete the gist: {}", e)))?;
+        let status = response.status();
+        if status.is_success() || status == reqwest::StatusCode::NOT_FOUND {
+            Ok(())
+        } else {
+            Err(McpError::Connection(format!("GitHub refused to delete the gist: {}", status)))
+        }
+    }
+}
+
+/// Where in S3 snapshots go
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct S3Config {
+    /// Bucket name
+    pub bucket: String,
+
+    /// Region, e.g. `eu-west-1`
+    pub region: String,
+
+    /// Endpoint of an S3-compatible store; objects are then addressed as
+    /// `<endpoint>/<bucket>/<key>`
+    #[serde(default)]
+    pub endpoint: Option<String>,
+
+    /// Key prefix of uploads
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+
+    /// Public base URL of the bucket; links are presigned when unset
+    #[serde(default)]
+    pub public_url: Option<String>,
+}
+
+fn default_prefix() -> String {
+    "papin-shares/".to_string()
+}
+
+/// Access key pair for S3
+#[derive(Debug, Clone)]
+pub struct S3Credentials {
+    /// Access key ID
+    pub access_key_id: String,
+
+    /// Secret access key
+    pub secret_access_key: String,
+}
+
+impl S3Credentials {
+    /// Keys from the secret store
+    pub fn from_secret_store() -> McpResult<Self> {
+        let store = secret_store();
+        match (store.get(S3_ACCESS_KEY_SECRET)?, store.get(S3_SECRET_KEY_SECRET)?) {
+            (Some(access_key_id), Some(secret_access_key)) => Ok(Self {
+                access_key_id,
+                secret_access_key,
+            }),
+            _ => Err(McpError::Config("Set the S3 access keys to share to S3".to_string())),
+        }
+    }
+
+    /// Store the keys in the secret store, or remove them
+    pub fn store(credentials: Option<&S3Credentials>) -> McpResult<()> {
+        let store = secret_store();
+        match credentials {
+            Some(credentials) => {
+                store.set(S3_ACCESS_KEY_SECRET, credentials.access_key_id.trim())?;
+                store.set(S3_SECRET_KEY_SECRET, credentials.secret_access_key.trim())?;
+            }
+            None => {
+                store.delete(S3_ACCESS_KEY_SECRET)?;
+                store.delete(S3_SECRET_KEY_SECRET)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex(digest::digest(&digest::SHA256, data).as_ref())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), data.as_bytes())
+        .as_ref()
+        .to_vec()
+}
+
+/// Percent-encode all but the unreserved characters, as SigV4 wants;
+/// `/` is kept in paths. Object URLs are built with this encoding, so their
+/// paths are already canonical.
+fn uri_encode(text: &str, keep_slash: bool) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            b'/' if keep_slash => "/".to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Signs S3 requests with AWS Signature Version 4
+struct Signer<'a> {
+    credentials: &'a S3Credentials,
+    region: &'a str,
+    now: DateTime<Utc>,
+}
+
+impl Signer<'_> {
+    fn amz_date(&self) -> String {
+        self.now.format("%Y%m%dT%H%M%SZ").to_string()
+    }
+
+    fn scope(&self) -> String {
+        format!("{}/{}/s3/aws4_request", self.now.format("%Y%m%d"), self.region)
+    }
+
+    /// Signature over a canonical request
+    fn signature(&self, canonical_request: &str) -> String {
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            self.amz_date(),
+            self.scope(),
+            sha256_hex(canonical_request.as_bytes())
+        );
+        let secret = format!("AWS4{}", self.credentials.secret_access_key);
+        let key = hmac_sha256(secret.as_bytes(), &self.now.format("%Y%m%d").to_string());
+        let key = hmac_sha256(&key, self.region);
+        let key = hmac_sha256(&key, "s3");
+        let key = hmac_sha256(&key, "aws4_request");
+        hex(&hmac_sha256(&key, &string_to_sign))
+    }
+
+    fn canonical_query(params: &[(String, String)]) -> String {
+        let mut params: Vec<(String, String)> = params
+            .iter()
+            .map(|(k, v)| (uri_encode(k, false), uri_encode(v, false)))
+            .collect();
+        params.sort();
+        params.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join("&")
+    }
+
+    /// Headers for a request whose body hashes to `payload_hash`, including
+    /// `Authorization`; `headers` are the other headers to sign
+    fn headers(&self, method: &str, url: &url::Url, headers: &[(&str, String)], payload_hash: &str) -> Vec<(String, String)> {
+        let mut signed: Vec<(String, String)> = headers
+            .iter()
+            .map(|(name, value)| (name.to_lowercase(), value.trim().to_string()))
+            .collect();
+        signed.push(("host".to_string(), host_of(url)));
+        signed.push(("x-amz-content-sha256".to_string(), payload_hash.to_string()));
+        signed.push(("x-amz-date".to_string(), self.amz_date()));
+        signed.sort();
+
+        let names = signed.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>().join(";");
+        let query: Vec<(String, String)> = url.query_pairs().map(|(k, v)| (k.into_owned(), v.into_owned())).collect();
+        let canonical = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method,
+            url.path(),
+            Self::canonical_query(&query),
+            signed.iter().map(|(n, v)| format!("{}:{}\n", n, v)).collect::<String>(),
+            names,
+            payload_hash
+        );
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.credentials.access_key_id,
+            self.scope(),
+            names,
+            self.signature(&canonical)
+        );
+        signed.push(("authorization".to_string(), authorization));
+        signed.retain(|(name, _)| name != "host");
+        signed
+    }
+}
+
+fn host_of(url: &url::Url) -> String {
+    match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    }
+}
+
+/// A presigned `GET` link to an object, valid for `expires_secs`
+pub fn presign_url(
+    url: &url::Url,
+    credentials: &S3Credentials,
+    region: &str,
+    now: DateTime<Utc>,
+    expires_secs: i64,
+) -> String {
+    let signer = Signer {
+        credentials,
+        region,
+        now,
+    };
+    let params = vec![
+        ("X-Amz-Algorithm".to_string(), "AWS4-HMAC-SHA256".to_string()),
+        (
+            "X-Amz-Credential".to_string(),
+            format!("{}/{}", credentials.access_key_id, signer.scope()),
+        ),
+        ("X-Amz-Date".to_string(), signer.amz_date()),
+        ("X-Amz-Expires".to_string(), expires_secs.clamp(1, MAX_PRESIGN_SECS).to_string()),
+        ("X-Amz-SignedHeaders".to_string(), "host".to_string()),
+    ];
+    let query = Signer::canonical_query(&params);
+    let path = url.path();
+    let canonical = format!("GET\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD", path, query, host_of(url));
+    format!(
+        "{}://{}{}?{}&X-Amz-Signature={}",
+        url.scheme(),
+        host_of(url),
+        path,
+        query,
+        signer.signature(&canonical)
+    )
+}
+
+/// Uploads to an S3 bucket
+pub struct S3Uploader {
+    config: S3Config,
+    credentials: S3Credentials,
+    client: reqwest::Client,
+}
+
+impl S3Uploader {
+    /// Uploader to a bucket with these keys
+    pub fn new(config: S3Config, credentials: S3Credentials) -> Self {
+        Self {
+            config,
+            credentials,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// URL of an object
+    fn object_url(&self, key: &str) -> McpResult<url::Url> {
+        let base = match &self.config.endpoint {
+            Some(endpoint) => format!("{}/{}", endpoint.trim_end_matches('/'), self.config.bucket),
+            None => format!("https://{}.s3.{}.amazonaws.com", self.config.bucket, self.config.region),
+        };
+        url::Url::parse(&format!("{}/{}", base, uri_encode(key, true)))
+            .map_err(|e| McpError::Config(format!("Invalid S3 endpoint: {}", e)))
+    }
+
+    async fn send(&self, method: reqwest::Method, key: &str, body: Vec<u8>, content_type: Option<&str>) -> McpResult<reqwest::Response> {
+        let url = self.object_url(key)?;
+        let signer = Signer {
+            credentials: &self.credentials,
+            region: &self.config.region,
+            now: clock::now(),
+        };
+        let extra: Vec<(&str, String)> = content_type.map(|t| ("content-type", t.to_string())).into_iter().collect();
+        let headers = signer.headers(method.as_str(), &url, &extra, &sha256_hex(&body));
+
+        let mut request = self.client.request(method, url).timeout(REQUEST_TIMEOUT).body(body);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        request
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(format!("Failed to reach S3: {}", e)))
+    }
+}
+
+#[async_trait]
+impl ShareUploader for S3Uploader {
+    fn target(&self) -> ShareTarget {
+        ShareTarget::S3
+    }
+
+    async fn upload(&self, name: &str, html: &str, expires_at: Option<DateTime<Utc>>) -> McpResult<Upload> {
+        // A random key, so links can't be guessed from one another
+        let key = format!("{}{}/{}", self.config.prefix, uuid::Uuid::new_v4(), name);
+        let response = self
+            .send(
+                reqwest::Method::PUT,
+                &key,
+                html.as_bytes().to_vec(),
+                Some("text/html; charset=utf-8"),
+            )
+            .await?;
+        if !response.status().is_success() {
+            return Err(McpError::Connection(format!("S3 refused the upload: {}", response.status())));
+        }
+
+        let url = match &self.config.public_url {
+            Some(base) => format!("{}/{}", base.trim_end_matches('/'), uri_encode(&key, true)),
+            None => {
+                let now = clock::now();
+                let lifetime = expires_at
+                    .map(|at| (at - now).num_seconds())
+                    .unwrap_or(MAX_PRESIGN_SECS);
+                presign_url(&self.object_url(&key)?, &self.credentials, &self.config.region, now, lifetime)
+            }
+        };
+        Ok(Upload { remote_id: key, url })
+    }
+
+    async fn delete(&self, remote_id: &str) -> McpResult<()> {
+        let response = self.send(reqwest::Method::DELETE, remote_id, Vec::new(), None).await?;
+        let status = response.status();
+        if status.is_success() || status == reqwest::StatusCode::NOT_FOUND {
+            Ok(())
+        } else {
+            Err(McpError::Connection(format!("S3 refused to delete the snapshot: {}", status)))
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+use crate::utils::clock;
+
+/// One side of a conflicting edit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ConflictVersion {
+    /// Value of the item (None if the item was deleted)
+    pub value: Option<String>,
+
+    /// Device that made the edit
+    pub device_id: String,
+
+    /// When the edit was made
+    pub timestamp: DateTime<Utc>,
+}
+
+/// How the user chose to resolve a conflict
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// Keep the local version
+    KeepLocal,
+
+    /// Keep the remote version
+    KeepRemote,
+
+    /// Use a merged value supplied by the user
+    Merge { value: String },
+}
+
+/// A conflict waiting for review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingConflict {
+    /// Conflict ID
+    pub id: String,
+
+    /// Key of the conflicted item
+    pub key: String,
+
+    /// Local version
+    pub local: ConflictVersion,
+
+    /// Remote version
+    pub remote: ConflictVersion,
+
+    /// When the conflict was detected
+    pub detected_at: DateTime<Utc>,
+}
+
+/// A reviewed conflict whose value still has to be applied by sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedConflict {
+    /// Conflict ID
+    pub id: String,
+
+    /// Key of the item
+    pub key: String,
+
+    /// Resolution chosen by the user
+    pub resolution: ConflictResolution,
+
+    /// Value to apply (None deletes the item)
+    pub value: Option<String>,
+
+    /// When the conflict was resolved
+    pub resolved_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct QueueState {
+    pending: Vec<PendingConflict>,
+    resolved: Vec<ResolvedConflict>,
+}
+
+/// Persistent queue of sync conflicts that could not be merged automatically.
+///
+/// Sync pushes conflicts in; the desktop app and TUI list and resolve them;
+/// sync then takes the resolved values and applies them on its next run.
+pub struct ConflictQueue {
+    path: PathBuf,
+    state: Mutex<QueueState>,
+}
+
+impl ConflictQueue {
+    /// Open a queue backed by the given file
+    pub fn open(path: PathBuf) -> Self {
+        let state = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable conflict queue {:?}: {}", path, e);
+                QueueState::default()
+            }),
+            Err(_) => QueueState::default(),
+        };
+
+        Self {
+            path,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Add a conflict; replaces an unreviewed conflict for the same key
+    pub fn enqueue(&self, key: &str, local: ConflictVersion, remote: ConflictVersion) -> McpResult<String> {
+        let conflict = PendingConflict {
+            id: uuid::Uuid::new_v4().to_string(),
+            key: key.to_string(),
+            local,
+            remote,
+            detected_at: clock::now(),
+        };
+        let id = conflict.id.clone();
+
+        let mut state = self.state.lock().unwrap();
+        state.pending.retain(|c| c.key != key);
+        state.pending.push(conflict);
+        debug!("Queued sync conflict for key '{}'", key);
+
+        self.save(&state)?;
+        Self::publish_changed(&state);
+        Ok(id)
+    }
+
+    /// List conflicts waiting for review, oldest first
+    pub fn list(&self) -> Vec<PendingConflict> {
+        self.state.lock().unwrap().pending.clone()
+    }
+
+    /// Number of conflicts waiting for review
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().pending.len()
+    }
+
+    /// Whether no conflicts are waiting for review
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get a conflict with both versions
+    pub fn get(&self, id: &str) -> Option<PendingConflict> {
+        self.state
+            .lock()
+            .unwrap()
+            .pending
+            .iter()
+            .find(|c| c.id == id)
+            .cloned()
+    }
+
+    /// Resolve a conflict; the chosen value is applied on the next sync
+    pub fn resolve(&self, id: &str, resolution: ConflictResolution) -> McpResult<ResolvedConflict> {
+        let mut state = self.state.lock().unwrap();
+
+        let index = state
+            .pending
+            .iter()
+            .position(|c| c.id == id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("Conflict {} not found", id)))?;
+        let conflict = state.pending.remove(index);
+
+        let value = match &resolution {
+            ConflictResolution::KeepLocal => conflict.local.value,
+            ConflictResolution::KeepRemote => conflict.remote.value,
+            ConflictResolution::Merge { value } => Some(value.clone()),
+        };
+
+        let resolved = ResolvedConflict {
+            id: conflict.id,
+            key: conflict.key,
+            resolution,
+            value,
+            resolved_at: clock::now(),
+        };
+        state.resolved.push(resolved.clone());
+
+        self.save(&state)?;
+        Self::publish_changed(&state);
+        Ok(resolved)
+    }
+
+    /// Take resolved conflicts so sync can apply them
+    pub fn take_resolved(&self) -> Vec<ResolvedConflict> {
+        self.take_resolved_where(|_| true)
+    }
+
+    /// Take the resolved conflicts whose key matches, leaving the rest for
+    /// the sync that owns them
+    pub fn take_resolved_where(&self, matches: impl Fn(&str) -> bool) -> Vec<ResolvedConflict> {
+        let mut state = self.state.lock().unwrap();
+        let (resolved, rest) = std::mem::take(&mut state.resolved)
+            .into_iter()
+            .partition::<Vec<_>, _>(|c| matches(&c.key));
+        state.resolved = rest;
+
+        if !resolved.is_empty() {
+            if let Err(e) = self.save(&state) {
+                warn!("Failed to save conflict queue: {}", e);
+            }
+        }
+
+        resolved
+    }
+
+    fn publish_changed(state: &QueueState) {
+        get_event_bus().emit(
+            Topic::Sync,
+            names::SYNC_CONFLICTS_CHANGED,
+            serde_json::json!({ "pending": state.pending.len() }),
+        );
+    }
+
+    fn save(&self, state: &QueueState) -> McpResult<()> {
+        let content = serde_json::to_string_pretty(state)?;
+        fs::write(&self.path, content).map_err(McpError::Io)
+    }
+}
+
+/// Global conflict queue shared by sync and the review UIs
+static CONFLICT_QUEUE: OnceCell<Arc<ConflictQueue>> = OnceCell::new();
+
+/// Get the global conflict queue
+pub fn get_conflict_queue() -> Arc<ConflictQueue> {
+    CONFLICT_QUEUE
+        .get_or_init(|| Arc::new(ConflictQueue::open(data_path("sync_conflicts.json"))))
+        .clone()
+}
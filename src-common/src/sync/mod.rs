@@ -0,0 +1,13 @@
+pub mod conflicts;
+pub mod read;
+pub mod team;
+
+pub use conflicts::{
+    get_conflict_queue, ConflictQueue, ConflictResolution, ConflictVersion, PendingConflict,
+    ResolvedConflict,
+};
+pub use read::{get_read_markers, ReadMarker, ReadMarkers};
+pub use team::{
+    get_team_workspace, is_sync_paused, set_sync_paused, spawn_team_sync, SharedConversation, SharedPrompt, TeamInfo, TeamMember, TeamRole, TeamStatus,
+    TeamSyncReport, TeamWorkspace, TEAM_KEY_PREFIX,
+};
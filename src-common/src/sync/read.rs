@@ -0,0 +1,195 @@
+//! Read positions of conversations, kept in step across devices
+//!
+//! A marker records the last message read in a conversation. Markers travel
+//! as sync changes under `conversation:<id>/read`, so they follow the same
+//! local-only and device-scope rules as the conversation itself. When two
+//! devices disagree, the marker furthest into the conversation wins: reading
+//! on one device never makes messages unread on another.
+
+use chrono::{DateTime, Utc};
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::config::data_path;
+use crate::error::McpResult;
+use crate::events::{get_event_bus, names, Topic};
+use crate::models::{Conversation, MessageRole};
+use crate::utils::clock;
+
+/// Suffix of the sync key of a conversation's read marker
+pub const READ_KEY_SUFFIX: &str = "/read";
+
+/// How far a conversation has been read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadMarker {
+    /// Conversation the marker belongs to
+    pub conversation_id: String,
+
+    /// Last message read
+    pub message_id: String,
+
+    /// Number of messages up to and including the last one read
+    pub position: usize,
+
+    /// When the conversation was read
+    pub read_at: DateTime<Utc>,
+}
+
+impl ReadMarker {
+    /// Sync key of the marker, e.g. `conversation:<id>/read`
+    pub fn sync_key(&self) -> String {
+        sync_key(&self.conversation_id)
+    }
+
+    /// Whether this marker is further into the conversation than another
+    pub fn is_ahead_of(&self, other: &ReadMarker) -> bool {
+        (self.position, self.read_at) > (other.position, other.read_at)
+    }
+}
+
+/// Sync key of a conversation's read marker
+pub fn sync_key(conversation_id: &str) -> String {
+    format!("conversation:{}{}", conversation_id, READ_KEY_SUFFIX)
+}
+
+/// Conversation of a read marker sync key
+pub fn conversation_for_key(key: &str) -> Option<&str> {
+    key.strip_prefix("conversation:")?.strip_suffix(READ_KEY_SUFFIX)
+}
+
+/// Index of the first unread message of a conversation.
+///
+/// Messages up to the marker are read, and so is everything before the
+/// user's own last message. Only replies count as unread.
+pub fn first_unread(conversation: &Conversation, marker: Option<&ReadMarker>) -> Option<usize> {
+    let messages = &conversation.messages;
+    let marked = marker.map(|marker| {
+        messages
+            .iter()
+            .position(|m| m.id == marker.message_id)
+            .map(|index| index + 1)
+            // The message is gone, e.g. edited away; fall back to the count
+            .unwrap_or_else(|| marker.position.min(messages.len()))
+    });
+    let answered = messages.iter().rposition(|m| m.role == MessageRole::User).map(|index| index + 1);
+    let read = marked.into_iter().chain(answered).max().unwrap_or(0);
+
+    (read..messages.len()).find(|&index| messages[index].role == MessageRole::Assistant)
+}
+
+/// Number of unread replies in a conversation
+pub fn unread_count(conversation: &Conversation, marker: Option<&ReadMarker>) -> usize {
+    match first_unread(conversation, marker) {
+        Some(first) => conversation.messages[first..]
+            .iter()
+            .filter(|m| m.role == MessageRole::Assistant)
+            .count(),
+        None => 0,
+    }
+}
+
+/// Read markers of all conversations
+pub struct ReadMarkers {
+    path: PathBuf,
+    markers: Mutex<HashMap<String, ReadMarker>>,
+}
+
+impl ReadMarkers {
+    /// Markers kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        let markers = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable read markers {:?}: {}", path, e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            path,
+            markers: Mutex::new(markers),
+        }
+    }
+
+    fn save(&self, markers: &HashMap<String, ReadMarker>) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(markers)?)?;
+        Ok(())
+    }
+
+    /// Marker of a conversation, if it was ever read
+    pub fn get(&self, conversation_id: &str) -> Option<ReadMarker> {
+        self.markers.lock().unwrap().get(conversation_id).cloned()
+    }
+
+    /// Mark a conversation read up to its last message; returns the new
+    /// marker, or None if it had no messages or was read that far already
+    pub fn mark_read(&self, conversation: &Conversation) -> McpResult<Option<ReadMarker>> {
+        let Some(last) = conversation.messages.last() else {
+            return Ok(None);
+        };
+        self.apply(ReadMarker {
+            conversation_id: conversation.id.clone(),
+            message_id: last.id.clone(),
+            position: conversation.messages.len(),
+            read_at: clock::now(),
+        })
+    }
+
+    /// Apply a marker from this or another device; returns it if it moved
+    /// the read position forward
+    pub fn apply(&self, marker: ReadMarker) -> McpResult<Option<ReadMarker>> {
+        let mut markers = self.markers.lock().unwrap();
+        if let Some(current) = markers.get(&marker.conversation_id) {
+            if current.message_id == marker.message_id || !marker.is_ahead_of(current) {
+                return Ok(None);
+            }
+        }
+        markers.insert(marker.conversation_id.clone(), marker.clone());
+        self.save(&markers)?;
+        drop(markers);
+
+        get_event_bus().emit(
+            Topic::Conversation,
+            names::READ_MARKER_CHANGED,
+            serde_json::json!({
+                "conversation_id": marker.conversation_id,
+                "message_id": marker.message_id,
+            }),
+        );
+        Ok(Some(marker))
+    }
+
+    /// Forget the marker of a conversation
+    pub fn remove(&self, conversation_id: &str) -> McpResult<()> {
+        let mut markers = self.markers.lock().unwrap();
+        if markers.remove(conversation_id).is_some() {
+            self.save(&markers)?;
+        }
+        Ok(())
+    }
+
+    /// Number of unread replies in a conversation
+    pub fn unread_count(&self, conversation: &Conversation) -> usize {
+        unread_count(conversation, self.get(&conversation.id).as_ref())
+    }
+
+    /// Index of the first unread message of a conversation
+    pub fn first_unread(&self, conversation: &Conversation) -> Option<usize> {
+        first_unread(conversation, self.get(&conversation.id).as_ref())
+    }
+}
+
+static READ_MARKERS: Lazy<Arc<ReadMarkers>> = Lazy::new(|| Arc::new(ReadMarkers::at(data_path("read_markers.json"))));
+
+/// Get the global read markers
+pub fn get_read_markers() -> Arc<ReadMarkers> {
+    READ_MARKERS.clone()
+}
@@ -0,0 +1,838 @@
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use once_cell::sync::OnceCell;
+use reqwest::{Method, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::conflicts::{get_conflict_queue, ConflictVersion};
+use crate::config::{data_path, get_settings, TeamSettings};
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+use crate::models::Conversation;
+use crate::protocol::inspector;
+
+/// Prefix of conflict queue keys owned by the team sync
+/// (`team:prompt:<id>`, `team:conversation:<id>`)
+pub const TEAM_KEY_PREFIX: &str = "team:";
+
+/// Version of the team sync protocol this client speaks
+const PROTOCOL_VERSION: &str = "1";
+
+/// What a team member may do; the server decides and enforces it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TeamRole {
+    /// Read shared prompts and conversations
+    Viewer,
+    /// Also edit prompts and share conversations
+    Member,
+    /// Also manage members and remove anything
+    Admin,
+}
+
+impl TeamRole {
+    /// Whether prompts and shared conversations may be changed
+    pub fn can_edit(&self) -> bool {
+        *self >= TeamRole::Member
+    }
+
+    /// Whether members may be managed
+    pub fn can_admin(&self) -> bool {
+        *self == TeamRole::Admin
+    }
+}
+
+impl std::str::FromStr for TeamRole {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "viewer" => Ok(TeamRole::Viewer),
+            "member" => Ok(TeamRole::Member),
+            "admin" => Ok(TeamRole::Admin),
+            other => Err(McpError::InvalidRequest(format!(
+                "Unknown role '{}'; use viewer, member or admin",
+                other
+            ))),
+        }
+    }
+}
+
+/// A member of the team
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeamMember {
+    /// Member ID
+    pub id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// Email address
+    #[serde(default)]
+    pub email: Option<String>,
+
+    /// Role
+    pub role: TeamRole,
+}
+
+/// The team this installation belongs to, as the server sees it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeamInfo {
+    /// Team ID
+    pub id: String,
+
+    /// Team name
+    pub name: String,
+
+    /// The member the access token belongs to
+    pub me: TeamMember,
+}
+
+/// A prompt in the team library
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedPrompt {
+    /// Prompt ID
+    pub id: String,
+
+    /// Unique name
+    pub name: String,
+
+    /// Prompt text
+    pub content: String,
+
+    /// What the prompt is for
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Tags for filtering
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Member who last changed it
+    pub updated_by: String,
+
+    /// When it was last changed
+    pub updated_at: DateTime<Utc>,
+
+    /// Server revision the item is based on; 0 for items the server hasn't seen
+    #[serde(default)]
+    pub revision: u64,
+
+    /// Removed from the library
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+/// Metadata of a conversation shared with the team; messages stay local
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedConversation {
+    /// Conversation ID
+    pub id: String,
+
+    /// Title
+    pub title: String,
+
+    /// Model used
+    pub model: String,
+
+    /// Number of messages
+    pub message_count: usize,
+
+    /// Member who shared it
+    pub shared_by: String,
+
+    /// When the conversation was last updated
+    pub updated_at: DateTime<Utc>,
+
+    /// Server revision the item is based on; 0 for items the server hasn't seen
+    #[serde(default)]
+    pub revision: u64,
+
+    /// No longer shared
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+/// A change exchanged with the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "item", rename_all = "snake_case")]
+pub enum TeamChange {
+    /// A prompt was created, edited or deleted
+    Prompt(SharedPrompt),
+    /// A conversation was shared, updated or unshared
+    Conversation(SharedConversation),
+}
+
+impl TeamChange {
+    fn key(&self) -> String {
+        match self {
+            TeamChange::Prompt(p) => format!("{}prompt:{}", TEAM_KEY_PREFIX, p.id),
+            TeamChange::Conversation(c) => format!("{}conversation:{}", TEAM_KEY_PREFIX, c.id),
+        }
+    }
+
+    fn updated(&self) -> (&str, DateTime<Utc>) {
+        match self {
+            TeamChange::Prompt(p) => (&p.updated_by, p.updated_at),
+            TeamChange::Conversation(c) => (&c.shared_by, c.updated_at),
+        }
+    }
+
+    fn deleted(&self) -> bool {
+        match self {
+            TeamChange::Prompt(p) => p.deleted,
+            TeamChange::Conversation(c) => c.deleted,
+        }
+    }
+
+    fn conflict_version(&self) -> ConflictVersion {
+        let (member, timestamp) = self.updated();
+        ConflictVersion {
+            value: if self.deleted() { None } else { serde_json::to_string(self).ok() },
+            device_id: member.to_string(),
+            timestamp,
+        }
+    }
+}
+
+/// Body of `POST /api/v1/sync`
+#[derive(Debug, Serialize)]
+struct SyncRequest<'a> {
+    /// Cursor from the previous sync; `None` fetches everything
+    cursor: Option<&'a str>,
+
+    /// Local changes, each based on the revision it was edited from
+    changes: &'a [TeamChange],
+}
+
+/// A pushed change the server refused
+#[derive(Debug, Deserialize)]
+struct RejectedChange {
+    /// ID of the prompt or conversation
+    id: String,
+
+    /// Why it was refused
+    reason: String,
+
+    /// Server version, when the change was based on an old revision
+    #[serde(default)]
+    current: Option<TeamChange>,
+}
+
+/// Response of `POST /api/v1/sync`
+#[derive(Debug, Deserialize)]
+struct SyncResponse {
+    /// Cursor for the next sync
+    cursor: String,
+
+    /// Changes made by others (and accepted local ones, with new revisions)
+    #[serde(default)]
+    changes: Vec<TeamChange>,
+
+    /// Local changes the server refused
+    #[serde(default)]
+    rejected: Vec<RejectedChange>,
+}
+
+/// Outcome of a sync
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TeamSyncReport {
+    /// Local changes the server accepted
+    pub pushed: usize,
+
+    /// Changes received from the server
+    pub pulled: usize,
+
+    /// Edits that collided with someone else's and wait for review
+    pub conflicts: usize,
+
+    /// Changes refused for other reasons, such as missing permissions
+    pub refused: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TeamState {
+    team: Option<TeamInfo>,
+    cursor: Option<String>,
+    last_sync: Option<DateTime<Utc>>,
+    prompts: BTreeMap<String, SharedPrompt>,
+    conversations: BTreeMap<String, SharedConversation>,
+    /// Local changes not pushed yet
+    outbox: Vec<TeamChange>,
+}
+
+impl TeamState {
+    fn apply(&mut self, change: TeamChange) {
+        match change {
+            TeamChange::Prompt(prompt) => {
+                self.prompts.insert(prompt.id.clone(), prompt);
+            }
+            TeamChange::Conversation(conversation) => {
+                self.conversations.insert(conversation.id.clone(), conversation);
+            }
+        }
+    }
+
+    /// Base a change on the latest server revision of its item
+    fn rebase(&self, change: &mut TeamChange) {
+        match change {
+            TeamChange::Prompt(p) => p.revision = self.prompts.get(&p.id).map_or(0, |c| c.revision),
+            TeamChange::Conversation(c) => {
+                c.revision = self.conversations.get(&c.id).map_or(0, |current| current.revision)
+            }
+        }
+    }
+
+    /// Queue a local change, replacing an unpushed change to the same item
+    fn queue(&mut self, change: TeamChange) {
+        let key = change.key();
+        self.outbox.retain(|c| c.key() != key);
+        self.apply(change.clone());
+        self.outbox.push(change);
+    }
+}
+
+/// Current state of the team workspace, for status displays
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamStatus {
+    /// Whether team mode is on
+    pub enabled: bool,
+
+    /// Team server URL
+    pub url: Option<String>,
+
+    /// Team and own membership, once joined
+    pub team: Option<TeamInfo>,
+
+    /// When the last sync finished
+    pub last_sync: Option<DateTime<Utc>>,
+
+    /// Local changes waiting to be pushed
+    pub pending_changes: usize,
+
+    /// Prompts in the library
+    pub prompts: usize,
+
+    /// Conversations shared with the team
+    pub shared_conversations: usize,
+}
+
+/// Prompt library and shared conversations of a team, synced with a
+/// self-hosted team server.
+///
+/// Edits are recorded locally and pushed on the next sync together with the
+/// server revision they are based on; edits the server finds stale land in
+/// the conflict queue and are pushed again once resolved.
+pub struct TeamWorkspace {
+    path: PathBuf,
+    state: Mutex<TeamState>,
+    client: reqwest::Client,
+}
+
+impl TeamWorkspace {
+    /// Open a workspace backed by the given file
+    pub fn open(path: PathBuf) -> Self {
+        let state = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable team workspace {:?}: {}", path, e);
+                TeamState::default()
+            }),
+            Err(_) => TeamState::default(),
+        };
+
+        Self {
+            path,
+            state: Mutex::new(state),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn save(&self, state: &TeamState) -> McpResult<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(state)?)?;
+        Ok(())
+    }
+
+    /// Team settings and token, or an error when team mode is off
+    fn connection() -> McpResult<(String, String)> {
+        let settings = get_settings().lock().unwrap().clone();
+        let url = settings
+            .team
+            .url
+            .clone()
+            .filter(|_| settings.team.enabled)
+            .ok_or_else(|| McpError::Config("Team mode is not set up; join a team first".to_string()))?;
+        let token = settings
+            .get_team_token()?
+            .ok_or_else(|| McpError::Authentication("No team access token configured".to_string()))?;
+        Ok((url, token))
+    }
+
+    fn request(&self, method: Method, url: &str, token: &str, path: &str) -> RequestBuilder {
+        self.client
+            .request(method, format!("{}/api/v1/{}", url.trim_end_matches('/'), path))
+            .bearer_auth(token)
+            .header("x-team-protocol", PROTOCOL_VERSION)
+            .timeout(Duration::from_secs(30))
+    }
+
+    async fn execute(request: RequestBuilder) -> McpResult<reqwest::Response> {
+        let response = inspector::send(request)
+            .await
+            .map_err(|e| McpError::Connection(format!("Team server unreachable: {}", e)))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(str::to_string))
+            .unwrap_or_else(|| format!("HTTP {}", status));
+        match status.as_u16() {
+            401 => Err(McpError::Authentication(format!("Team server rejected the token: {}", message))),
+            403 => Err(McpError::Authentication(format!("Not allowed for your team role: {}", message))),
+            _ => Err(McpError::Protocol(format!("Team server error: {}", message))),
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(request: RequestBuilder) -> McpResult<T> {
+        Self::execute(request)
+            .await?
+            .json()
+            .await
+            .map_err(|e| McpError::Protocol(format!("Invalid team server response: {}", e)))
+    }
+
+    /// Role of this installation's member, as last reported by the server
+    pub fn role(&self) -> Option<TeamRole> {
+        self.state.lock().unwrap().team.as_ref().map(|t| t.me.role)
+    }
+
+    fn require(&self, allowed: fn(&TeamRole) -> bool, action: &str) -> McpResult<TeamMember> {
+        let state = self.state.lock().unwrap();
+        let me = state
+            .team
+            .as_ref()
+            .map(|t| t.me.clone())
+            .ok_or_else(|| McpError::Config("Team mode is not set up; join a team first".to_string()))?;
+        if !allowed(&me.role) {
+            return Err(McpError::Authentication(format!(
+                "Your team role ({:?}) doesn't allow you to {}",
+                me.role, action
+            )));
+        }
+        Ok(me)
+    }
+
+    /// Join a team: check the token with the server, turn team mode on and
+    /// fetch the shared data
+    pub async fn join(&self, url: &str, token: &str) -> McpResult<TeamInfo> {
+        let url = url.trim().trim_end_matches('/').to_string();
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(McpError::InvalidRequest("Team server URL must start with https://".to_string()));
+        }
+        let team: TeamInfo = Self::send(self.request(Method::GET, &url, token.trim(), "team")).await?;
+
+        {
+            let settings = get_settings();
+            let mut settings = settings.lock().unwrap();
+            settings.set_team_token(Some(token.trim()))?;
+            settings.team = TeamSettings {
+                enabled: true,
+                url: Some(url),
+            };
+            settings.save()?;
+        }
+
+        {
+            let mut state = self.state.lock().unwrap();
+            // A different team starts from scratch
+            if state.team.as_ref().map(|t| &t.id) != Some(&team.id) {
+                *state = TeamState::default();
+            }
+            state.team = Some(team.clone());
+            self.save(&state)?;
+        }
+        info!("Joined team '{}' as {:?}", team.name, team.me.role);
+
+        self.sync().await?;
+        Ok(team)
+    }
+
+    /// Leave the team: turn team mode off and forget the token and the
+    /// shared data
+    pub fn leave(&self) -> McpResult<()> {
+        {
+            let settings = get_settings();
+            let mut settings = settings.lock().unwrap();
+            settings.set_team_token(None)?;
+            settings.team = TeamSettings::default();
+            settings.save()?;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        *state = TeamState::default();
+        self.save(&state)?;
+        info!("Left the team workspace");
+        Ok(())
+    }
+
+    /// Team mode settings and local sync state
+    pub fn status(&self) -> TeamStatus {
+        let team = get_settings().lock().unwrap().team.clone();
+        let state = self.state.lock().unwrap();
+        TeamStatus {
+            enabled: team.enabled,
+            url: team.url,
+            team: state.team.clone(),
+            last_sync: state.last_sync,
+            pending_changes: state.outbox.len(),
+            prompts: state.prompts.values().filter(|p| !p.deleted).count(),
+            shared_conversations: state.conversations.values().filter(|c| !c.deleted).count(),
+        }
+    }
+
+    /// Push local changes, pull everyone else's and refresh the own role
+    pub async fn sync(&self) -> McpResult<TeamSyncReport> {
+        let (url, token) = Self::connection()?;
+        self.sync_with(&url, &token).await
+    }
+
+    /// Sync with a team server other than the configured one
+    pub async fn sync_with(&self, url: &str, token: &str) -> McpResult<TeamSyncReport> {
+        // Roles change on the server; refresh before pushing
+        let team: TeamInfo = Self::send(self.request(Method::GET, url, token, "team")).await?;
+
+        let (cursor, mut outbox) = {
+            let mut state = self.state.lock().unwrap();
+            state.team = Some(team);
+            (state.cursor.clone(), state.outbox.clone())
+        };
+
+        // Conflicts resolved since the last sync go out with the other changes
+        for resolved in get_conflict_queue().take_resolved_where(|key| key.starts_with(TEAM_KEY_PREFIX)) {
+            let mut change = match resolved.value.as_deref().map(serde_json::from_str::<TeamChange>) {
+                Some(Ok(change)) => change,
+                Some(Err(e)) => {
+                    warn!("Dropping unreadable team conflict resolution for {}: {}", resolved.key, e);
+                    continue;
+                }
+                None => match self.deleted_version(&resolved.key) {
+                    Some(change) => change,
+                    None => continue,
+                },
+            };
+            // The resolution supersedes the server version it was compared with
+            self.state.lock().unwrap().rebase(&mut change);
+            outbox.retain(|c| c.key() != change.key());
+            outbox.push(change);
+        }
+
+        let request = SyncRequest {
+            cursor: cursor.as_deref(),
+            changes: &outbox,
+        };
+        let response: SyncResponse =
+            Self::send(self.request(Method::POST, url, token, "sync").json(&request)).await?;
+
+        let mut report = TeamSyncReport {
+            pushed: outbox.len() - response.rejected.len().min(outbox.len()),
+            pulled: response.changes.len(),
+            ..Default::default()
+        };
+
+        let mut state = self.state.lock().unwrap();
+        // Changes queued while the request ran stay in the outbox
+        let sent: Vec<TeamChange> = outbox;
+        state.outbox.retain(|c| !sent.contains(c));
+        for change in response.changes {
+            state.apply(change);
+        }
+
+        for rejected in response.rejected {
+            let local = sent.iter().find(|c| match c {
+                TeamChange::Prompt(p) => p.id == rejected.id,
+                TeamChange::Conversation(c) => c.id == rejected.id,
+            });
+            match (local, rejected.current) {
+                // Someone else changed the item first; let the user pick
+                (Some(local), Some(current)) => {
+                    get_conflict_queue().enqueue(&local.key(), local.conflict_version(), current.conflict_version())?;
+                    state.apply(current);
+                    report.conflicts += 1;
+                }
+                _ => {
+                    warn!("Team server refused change to {}: {}", rejected.id, rejected.reason);
+                    report.refused.push(format!("{}: {}", rejected.id, rejected.reason));
+                }
+            }
+        }
+
+        state.cursor = Some(response.cursor);
+        state.last_sync = Some(Utc::now());
+        self.save(&state)?;
+        drop(state);
+
+        debug!(
+            "Team sync: {} pushed, {} pulled, {} conflicts",
+            report.pushed, report.pulled, report.conflicts
+        );
+        get_event_bus().emit(Topic::Sync, names::TEAM_SYNCED, serde_json::to_value(&report)?);
+        Ok(report)
+    }
+
+    /// The local item for a conflict key, marked deleted
+    fn deleted_version(&self, key: &str) -> Option<TeamChange> {
+        let state = self.state.lock().unwrap();
+        let rest = key.strip_prefix(TEAM_KEY_PREFIX)?;
+        let change = if let Some(id) = rest.strip_prefix("prompt:") {
+            let mut prompt = state.prompts.get(id)?.clone();
+            prompt.deleted = true;
+            TeamChange::Prompt(prompt)
+        } else {
+            let id = rest.strip_prefix("conversation:")?;
+            let mut conversation = state.conversations.get(id)?.clone();
+            conversation.deleted = true;
+            TeamChange::Conversation(conversation)
+        };
+        Some(change)
+    }
+
+    /// Prompts in the library, by name
+    pub fn prompts(&self) -> Vec<SharedPrompt> {
+        let mut prompts: Vec<SharedPrompt> = self
+            .state
+            .lock()
+            .unwrap()
+            .prompts
+            .values()
+            .filter(|p| !p.deleted)
+            .cloned()
+            .collect();
+        prompts.sort_by_key(|a| a.name.to_lowercase());
+        prompts
+    }
+
+    /// Find a prompt by ID or name
+    pub fn prompt(&self, id_or_name: &str) -> Option<SharedPrompt> {
+        self.state
+            .lock()
+            .unwrap()
+            .prompts
+            .values()
+            .filter(|p| !p.deleted)
+            .find(|p| p.id == id_or_name || p.name == id_or_name)
+            .cloned()
+    }
+
+    /// Add a prompt to the library, or update the prompt with that name;
+    /// pushed on the next sync
+    pub fn save_prompt(
+        &self,
+        name: &str,
+        content: &str,
+        description: Option<String>,
+        tags: Vec<String>,
+    ) -> McpResult<SharedPrompt> {
+        let me = self.require(TeamRole::can_edit, "edit team prompts")?;
+        if name.trim().is_empty() || content.trim().is_empty() {
+            return Err(McpError::InvalidRequest("A prompt needs a name and content".to_string()));
+        }
+
+        let existing = self.prompt(name.trim());
+        let prompt = SharedPrompt {
+            id: existing
+                .as_ref()
+                .map(|p| p.id.clone())
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            name: name.trim().to_string(),
+            content: content.to_string(),
+            description: description.filter(|d| !d.trim().is_empty()),
+            tags,
+            updated_by: me.id,
+            updated_at: Utc::now(),
+            revision: existing.map(|p| p.revision).unwrap_or(0),
+            deleted: false,
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.queue(TeamChange::Prompt(prompt.clone()));
+        self.save(&state)?;
+        Ok(prompt)
+    }
+
+    /// Remove a prompt from the library; pushed on the next sync
+    pub fn delete_prompt(&self, id_or_name: &str) -> McpResult<()> {
+        let me = self.require(TeamRole::can_edit, "edit team prompts")?;
+        let mut prompt = self
+            .prompt(id_or_name)
+            .ok_or_else(|| McpError::InvalidRequest(format!("No team prompt '{}'", id_or_name)))?;
+        prompt.deleted = true;
+        prompt.updated_by = me.id;
+        prompt.updated_at = Utc::now();
+
+        let mut state = self.state.lock().unwrap();
+        state.queue(TeamChange::Prompt(prompt));
+        self.save(&state)
+    }
+
+    /// Conversations shared with the team, most recently updated first
+    pub fn shared_conversations(&self) -> Vec<SharedConversation> {
+        let mut conversations: Vec<SharedConversation> = self
+            .state
+            .lock()
+            .unwrap()
+            .conversations
+            .values()
+            .filter(|c| !c.deleted)
+            .cloned()
+            .collect();
+        conversations.sort_by_key(|c| std::cmp::Reverse(c.updated_at));
+        conversations
+    }
+
+    /// Share a conversation's metadata with the team, or update it if
+    /// already shared; pushed on the next sync
+    pub fn share_conversation(&self, conversation: &Conversation) -> McpResult<SharedConversation> {
+        let me = self.require(TeamRole::can_edit, "share conversations")?;
+        let revision = self
+            .state
+            .lock()
+            .unwrap()
+            .conversations
+            .get(&conversation.id)
+            .map(|c| c.revision)
+            .unwrap_or(0);
+
+        let shared = SharedConversation {
+            id: conversation.id.clone(),
+            title: conversation.title.clone(),
+            model: conversation.model.id.clone(),
+            message_count: conversation.messages.len(),
+            shared_by: me.id,
+            updated_at: DateTime::<Utc>::from(conversation.updated_at),
+            revision,
+            deleted: false,
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.queue(TeamChange::Conversation(shared.clone()));
+        self.save(&state)?;
+        Ok(shared)
+    }
+
+    /// Stop sharing a conversation; members may only unshare their own
+    pub fn unshare_conversation(&self, id: &str) -> McpResult<()> {
+        let me = self.require(TeamRole::can_edit, "unshare conversations")?;
+        let mut state = self.state.lock().unwrap();
+        let mut shared = state
+            .conversations
+            .get(id)
+            .filter(|c| !c.deleted)
+            .cloned()
+            .ok_or_else(|| McpError::InvalidRequest(format!("Conversation {} is not shared", id)))?;
+        if shared.shared_by != me.id && !me.role.can_admin() {
+            return Err(McpError::Authentication(
+                "Only admins can unshare conversations shared by others".to_string(),
+            ));
+        }
+        shared.deleted = true;
+        shared.updated_at = Utc::now();
+
+        state.queue(TeamChange::Conversation(shared));
+        self.save(&state)
+    }
+
+    /// Members of the team
+    pub async fn members(&self) -> McpResult<Vec<TeamMember>> {
+        let (url, token) = Self::connection()?;
+        Self::send(self.request(Method::GET, &url, &token, "members")).await
+    }
+
+    /// Invite someone by email (admins only)
+    pub async fn invite(&self, email: &str, role: TeamRole) -> McpResult<TeamMember> {
+        self.require(TeamRole::can_admin, "invite members")?;
+        let (url, token) = Self::connection()?;
+        let body = serde_json::json!({ "email": email.trim(), "role": role });
+        Self::send(self.request(Method::POST, &url, &token, "members").json(&body)).await
+    }
+
+    /// Change a member's role (admins only)
+    pub async fn set_role(&self, member_id: &str, role: TeamRole) -> McpResult<TeamMember> {
+        self.require(TeamRole::can_admin, "change roles")?;
+        let (url, token) = Self::connection()?;
+        let body = serde_json::json!({ "role": role });
+        let path = format!("members/{}", member_id);
+        Self::send(self.request(Method::PATCH, &url, &token, &path).json(&body)).await
+    }
+
+    /// Remove a member from the team (admins only)
+    pub async fn remove_member(&self, member_id: &str) -> McpResult<()> {
+        let me = self.require(TeamRole::can_admin, "remove members")?;
+        if me.id == member_id {
+            return Err(McpError::InvalidRequest(
+                "Admins can't remove themselves; leave the team instead".to_string(),
+            ));
+        }
+        let (url, token) = Self::connection()?;
+        let path = format!("members/{}", member_id);
+        Self::execute(self.request(Method::DELETE, &url, &token, &path)).await?;
+        Ok(())
+    }
+}
+
+/// Whether background team sync is paused for this session
+static SYNC_PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Pause or resume background team sync until the app restarts. Manual
+/// syncs still run.
+pub fn set_sync_paused(paused: bool) {
+    if SYNC_PAUSED.swap(paused, Ordering::SeqCst) != paused {
+        info!("Background team sync {}", if paused { "paused" } else { "resumed" });
+        get_event_bus().emit(
+            Topic::Sync,
+            names::SYNC_PAUSED_CHANGED,
+            serde_json::json!({ "paused": paused }),
+        );
+    }
+}
+
+/// Whether background team sync is paused
+pub fn is_sync_paused() -> bool {
+    SYNC_PAUSED.load(Ordering::SeqCst)
+}
+
+/// Sync the team workspace periodically while team mode is on and sync
+/// isn't paused
+pub fn spawn_team_sync(interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if is_sync_paused() || !get_settings().lock().unwrap().team.enabled {
+                continue;
+            }
+            if let Err(e) = get_team_workspace().sync().await {
+                warn!("Background team sync failed: {}", e);
+            }
+        }
+    })
+}
+
+/// Global team workspace
+static TEAM_WORKSPACE: OnceCell<Arc<TeamWorkspace>> = OnceCell::new();
+
+/// Get the global team workspace
+pub fn get_team_workspace() -> Arc<TeamWorkspace> {
+    TEAM_WORKSPACE
+        .get_or_init(|| Arc::new(TeamWorkspace::open(data_path("team.json"))))
+        .clone()
+}
@@ -0,0 +1,475 @@
+//! Community template gallery
+//!
+//! A gallery is a curated JSON index of templates, each entry naming a file
+//! next to the index that holds the template's prompts:
+//!
+//! ```json
+//! {"name": "Community templates", "templates": [
+//!   {"id": "code-review", "name": "Code review", "kind": "prompt", "version": "1.2.0",
+//!    "url": "templates/code-review.json", "sha256": "…", "author": "…", "license": "MIT"}
+//! ]}
+//! ```
+//!
+//! Nothing is fetched until the user sets a gallery URL. Installed templates
+//! keep the gallery, version and checksum they came from; a checker compares
+//! them with the index and announces each new upstream version once.
+
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use url::Url;
+
+use super::{validate_id, Provenance, Template, TemplateKind, TemplateLibrary};
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+use crate::utils::clock;
+
+/// Largest index accepted
+const MAX_INDEX_BYTES: usize = 2 * 1024 * 1024;
+
+/// Largest template file accepted
+const MAX_TEMPLATE_BYTES: usize = 256 * 1024;
+
+/// How long the gallery has to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// A template as the gallery lists it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GalleryEntry {
+    /// Template ID
+    pub id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// What it sets up
+    #[serde(default)]
+    pub kind: TemplateKind,
+
+    /// What it is for
+    #[serde(default)]
+    pub description: String,
+
+    /// Tags to find it by
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Version, changed whenever the template is
+    pub version: String,
+
+    /// Template file, absolute or relative to the index
+    pub url: String,
+
+    /// SHA-256 of the template file, checked when given
+    #[serde(default)]
+    pub sha256: Option<String>,
+
+    /// Who wrote it
+    #[serde(default)]
+    pub author: Option<String>,
+
+    /// License it is shared under
+    #[serde(default)]
+    pub license: Option<String>,
+}
+
+impl GalleryEntry {
+    /// Whether the entry mentions a search term in its ID, name,
+    /// description or tags, ignoring case
+    pub fn matches(&self, term: &str) -> bool {
+        let term = term.to_lowercase();
+        self.id.to_lowercase().contains(&term)
+            || self.name.to_lowercase().contains(&term)
+            || self.description.to_lowercase().contains(&term)
+            || self.tags.iter().any(|t| t.to_lowercase().contains(&term))
+    }
+}
+
+/// A gallery's index
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GalleryIndex {
+    /// Gallery name
+    #[serde(default)]
+    pub name: String,
+
+    /// Templates on offer
+    #[serde(default)]
+    pub templates: Vec<GalleryEntry>,
+}
+
+/// Prompts of a template file
+#[derive(Debug, Deserialize)]
+struct TemplateFile {
+    #[serde(default)]
+    prompt: String,
+
+    #[serde(default)]
+    system_prompt: Option<String>,
+
+    #[serde(default)]
+    model: Option<String>,
+}
+
+/// An installed template with a newer version upstream
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateUpdate {
+    /// Template ID
+    pub id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// Version installed
+    pub installed_version: String,
+
+    /// Version in the gallery
+    pub available_version: String,
+}
+
+/// SHA-256 of content, in hex
+fn sha256(content: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, content)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Only http and https galleries
+fn parse_url(url: &str) -> McpResult<Url> {
+    let parsed = Url::parse(url.trim()).map_err(|e| McpError::InvalidRequest(format!("Invalid gallery URL: {}", e)))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed),
+        scheme => Err(McpError::InvalidRequest(format!(
+            "Galleries can't be read over {}",
+            scheme
+        ))),
+    }
+}
+
+/// A template gallery, read over HTTP
+pub struct Gallery {
+    index_url: Url,
+    client: reqwest::Client,
+}
+
+impl Gallery {
+    /// Gallery with its index at a URL
+    pub fn new(index_url: &str) -> McpResult<Self> {
+        Ok(Self {
+            index_url: parse_url(index_url)?,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// URL of the index
+    pub fn index_url(&self) -> &str {
+        self.index_url.as_str()
+    }
+
+    /// Download a file of at most `limit` bytes
+    async fn fetch(&self, url: &Url, limit: usize) -> McpResult<Vec<u8>> {
+        let response = self
+            .client
+            .get(url.clone())
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(format!("Failed to fetch {}: {}", url, e)))?;
+        if !response.status().is_success() {
+            return Err(McpError::Connection(format!("{} answered {}", url, response.status())));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| McpError::Connection(format!("Failed to read {}: {}", url, e)))?;
+        if body.len() > limit {
+            return Err(McpError::InvalidRequest(format!(
+                "{} is larger than the {} bytes allowed",
+                url, limit
+            )));
+        }
+        Ok(body.to_vec())
+    }
+
+    /// Fetch the index; entries with unusable IDs are dropped
+    pub async fn index(&self) -> McpResult<GalleryIndex> {
+        let body = self.fetch(&self.index_url, MAX_INDEX_BYTES).await?;
+        let mut index: GalleryIndex = serde_json::from_slice(&body)
+            .map_err(|e| McpError::InvalidRequest(format!("Unreadable gallery index: {}", e)))?;
+        index.templates.retain(|entry| match validate_id(&entry.id) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Skipping gallery entry: {}", e);
+                false
+            }
+        });
+        Ok(index)
+    }
+
+    /// A template's entry in the index
+    pub async fn entry(&self, id: &str) -> McpResult<GalleryEntry> {
+        self.index()
+            .await?
+            .templates
+            .into_iter()
+            .find(|entry| entry.id == id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("The gallery has no template {}", id)))
+    }
+
+    /// Download a template without installing it
+    pub async fn preview(&self, id: &str) -> McpResult<Template> {
+        let entry = self.entry(id).await?;
+        self.download(&entry).await
+    }
+
+    /// Download and check a template, with where it came from
+    async fn download(&self, entry: &GalleryEntry) -> McpResult<Template> {
+        let source_url = self
+            .index_url
+            .join(&entry.url)
+            .map_err(|e| McpError::InvalidRequest(format!("Invalid URL for template {}: {}", entry.id, e)))?;
+        let source_url = parse_url(source_url.as_str())?;
+        let body = self.fetch(&source_url, MAX_TEMPLATE_BYTES).await?;
+        let checksum = sha256(&body);
+        if let Some(expected) = &entry.sha256 {
+            if !expected.eq_ignore_ascii_case(&checksum) {
+                return Err(McpError::InvalidRequest(format!(
+                    "Template {} doesn't match the checksum the gallery lists; not installing it",
+                    entry.id
+                )));
+            }
+        }
+        let file: TemplateFile = serde_json::from_slice(&body)
+            .map_err(|e| McpError::InvalidRequest(format!("Unreadable template {}: {}", entry.id, e)))?;
+        let empty = match entry.kind {
+            TemplateKind::Prompt => file.prompt.trim().is_empty(),
+            TemplateKind::Workspace => file.system_prompt.as_deref().is_none_or(|p| p.trim().is_empty()),
+        };
+        if empty {
+            return Err(McpError::InvalidRequest(format!("Template {} is empty", entry.id)));
+        }
+
+        Ok(Template {
+            id: entry.id.clone(),
+            name: entry.name.clone(),
+            kind: entry.kind,
+            description: entry.description.clone(),
+            tags: entry.tags.clone(),
+            prompt: file.prompt,
+            system_prompt: file.system_prompt,
+            model: file.model,
+            source: Some(Provenance {
+                gallery: self.index_url.to_string(),
+                source_url: source_url.to_string(),
+                version: entry.version.clone(),
+                sha256: checksum,
+                author: entry.author.clone(),
+                license: entry.license.clone(),
+                installed_at: clock::now(),
+            }),
+        })
+    }
+
+    /// Install a template into the library, or update it if it came from
+    /// this gallery; templates of the user's own are never replaced
+    pub async fn install(&self, library: &TemplateLibrary, id: &str) -> McpResult<Template> {
+        if let Ok(existing) = library.get(id) {
+            let from_here = existing
+                .source
+                .as_ref()
+                .is_some_and(|s| s.gallery == self.index_url.as_str());
+            if !from_here {
+                return Err(McpError::InvalidRequest(format!(
+                    "You already have a template called {}; remove it first",
+                    id
+                )));
+            }
+        }
+        let template = self.preview(id).await?;
+        let template = library.save(template)?;
+        info!(
+            "Installed template {} {} from {}",
+            template.id,
+            template.source.as_ref().map_or("", |s| s.version.as_str()),
+            self.index_url
+        );
+        Ok(template)
+    }
+
+    /// Installed templates from this gallery with another version upstream
+    pub async fn updates(&self, library: &TemplateLibrary) -> McpResult<Vec<TemplateUpdate>> {
+        let index = self.index().await?;
+        let updates = library
+            .list()
+            .into_iter()
+            .filter_map(|template| {
+                let source = template.source.as_ref()?;
+                if source.gallery != self.index_url.as_str() {
+                    return None;
+                }
+                let entry = index.templates.iter().find(|e| e.id == template.id)?;
+                let changed = entry.version != source.version
+                    || entry
+                        .sha256
+                        .as_ref()
+                        .is_some_and(|sha| !sha.eq_ignore_ascii_case(&source.sha256));
+                changed.then(|| TemplateUpdate {
+                    id: template.id.clone(),
+                    name: template.name.clone(),
+                    installed_version: source.version.clone(),
+                    available_version: entry.version.clone(),
+                })
+            })
+            .collect();
+        Ok(updates)
+    }
+}
+
+/// What is kept about the gallery
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct GalleryState {
+    #[serde(default)]
+    url: Option<String>,
+
+    /// Version last announced per template
+    #[serde(default)]
+    announced: BTreeMap<String, String>,
+
+    #[serde(default)]
+    last_checked_at: Option<DateTime<Utc>>,
+}
+
+/// Which gallery to use, if any, and which updates were announced
+pub struct GallerySettings {
+    /// File holding the settings
+    path: PathBuf,
+}
+
+impl GallerySettings {
+    /// Settings kept in `template_gallery.json` in the data directory
+    pub fn new() -> Self {
+        Self::at(data_path("template_gallery.json"))
+    }
+
+    /// Settings kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn load(&self) -> GalleryState {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, state: &GalleryState) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(state)?)?;
+        Ok(())
+    }
+
+    /// Index URL of the gallery, if one is set
+    pub fn url(&self) -> Option<String> {
+        self.load().url
+    }
+
+    /// Use a gallery, or none
+    pub fn set_url(&self, url: Option<&str>) -> McpResult<()> {
+        let url = match url {
+            Some(url) => Some(parse_url(url)?.to_string()),
+            None => None,
+        };
+        let mut state = self.load();
+        if state.url != url {
+            state.announced.clear();
+        }
+        state.url = url;
+        self.save(&state)
+    }
+
+    /// When updates were last checked for
+    pub fn last_checked_at(&self) -> Option<DateTime<Utc>> {
+        self.load().last_checked_at
+    }
+
+    /// The gallery set, or an error saying how to set one
+    pub fn gallery(&self) -> McpResult<Gallery> {
+        match self.url() {
+            Some(url) => Gallery::new(&url),
+            None => Err(McpError::Config(
+                "No template gallery is set; set the URL of its index first".to_string(),
+            )),
+        }
+    }
+
+    /// Of updates found now, those not announced before; they count as
+    /// announced from here on
+    pub fn unannounced(&self, updates: &[TemplateUpdate]) -> McpResult<Vec<TemplateUpdate>> {
+        let mut state = self.load();
+        let fresh: Vec<TemplateUpdate> = updates
+            .iter()
+            .filter(|u| state.announced.get(&u.id) != Some(&u.available_version))
+            .cloned()
+            .collect();
+        for update in &fresh {
+            state
+                .announced
+                .insert(update.id.clone(), update.available_version.clone());
+        }
+        state.last_checked_at = Some(clock::now());
+        self.save(&state)?;
+        Ok(fresh)
+    }
+}
+
+impl Default for GallerySettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Gallery settings in the data directory
+pub fn get_gallery_settings() -> GallerySettings {
+    GallerySettings::new()
+}
+
+/// Check the gallery for updates of installed templates on an interval,
+/// announcing each new version once
+pub fn spawn_template_update_checker(interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let settings = get_gallery_settings();
+            let Ok(gallery) = settings.gallery() else {
+                continue;
+            };
+            let library = super::get_template_library();
+            let updates = match gallery.updates(&library).await {
+                Ok(updates) => updates,
+                Err(e) => {
+                    debug!("Failed to check the template gallery: {}", e);
+                    continue;
+                }
+            };
+            match settings.unannounced(&updates) {
+                Ok(fresh) if !fresh.is_empty() => get_event_bus().emit(
+                    Topic::System,
+                    names::TEMPLATE_UPDATES_AVAILABLE,
+                    serde_json::json!({ "updates": fresh }),
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("Failed to record template updates: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,242 @@
+//! Prompt and workspace templates
+//!
+//! The library keeps templates the user installed: prompts with
+//! `{placeholders}` filled in when used, and workspace templates that set up
+//! a conversation with a system prompt and a model. Templates installed from
+//! a community gallery remember where they came from, so they can be
+//! updated when they change upstream.
+
+pub mod gallery;
+
+pub use gallery::{
+    get_gallery_settings, spawn_template_update_checker, Gallery, GalleryEntry, GalleryIndex, GallerySettings,
+    TemplateUpdate,
+};
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+
+/// Placeholders such as `{language}`; `{env.NAME}` references are left to
+/// the environment
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+
+/// What a template sets up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateKind {
+    /// A prompt to send
+    #[default]
+    Prompt,
+
+    /// A conversation's system prompt and model
+    Workspace,
+}
+
+impl std::fmt::Display for TemplateKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateKind::Prompt => write!(f, "prompt"),
+            TemplateKind::Workspace => write!(f, "workspace"),
+        }
+    }
+}
+
+/// Where an installed template came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Index URL of the gallery
+    pub gallery: String,
+
+    /// URL the template was downloaded from
+    pub source_url: String,
+
+    /// Version installed
+    pub version: String,
+
+    /// SHA-256 of the downloaded file
+    pub sha256: String,
+
+    /// Author, as the gallery lists them
+    #[serde(default)]
+    pub author: Option<String>,
+
+    /// License, as the gallery lists it
+    #[serde(default)]
+    pub license: Option<String>,
+
+    /// When it was installed or last updated
+    pub installed_at: DateTime<Utc>,
+}
+
+/// A prompt or workspace template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Template {
+    /// Template ID, a short slug
+    pub id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// What it sets up
+    #[serde(default)]
+    pub kind: TemplateKind,
+
+    /// What it is for
+    #[serde(default)]
+    pub description: String,
+
+    /// Tags to find it by
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Prompt text with `{placeholders}`
+    #[serde(default)]
+    pub prompt: String,
+
+    /// System prompt of conversations it sets up
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+
+    /// Model it was written for, by ID or name
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Where it came from, for templates installed from a gallery
+    #[serde(default)]
+    pub source: Option<Provenance>,
+}
+
+impl Template {
+    /// Names of the placeholders in the prompt, each once, in order
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for captures in PLACEHOLDER.captures_iter(&self.prompt) {
+            let name = captures[1].to_string();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// The prompt with its placeholders filled in; every one needs a value
+    pub fn render(&self, values: &HashMap<String, String>) -> McpResult<String> {
+        let missing: Vec<String> = self
+            .placeholders()
+            .into_iter()
+            .filter(|name| !values.contains_key(name))
+            .collect();
+        if !missing.is_empty() {
+            return Err(McpError::InvalidRequest(format!(
+                "Template {} needs a value for {}",
+                self.id,
+                missing.join(", ")
+            )));
+        }
+        Ok(PLACEHOLDER
+            .replace_all(&self.prompt, |captures: &regex::Captures| values[&captures[1]].clone())
+            .into_owned())
+    }
+}
+
+/// IDs are short slugs, as they end up in file names and commands
+pub(crate) fn validate_id(id: &str) -> McpResult<()> {
+    let valid =
+        !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(McpError::InvalidRequest(format!(
+            "Template IDs are letters, digits, '-' and '_': '{}'",
+            id
+        )))
+    }
+}
+
+/// Templates kept by the user
+pub struct TemplateLibrary {
+    path: PathBuf,
+    templates: Mutex<Vec<Template>>,
+}
+
+impl TemplateLibrary {
+    /// Library kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        let templates = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            templates: Mutex::new(templates),
+        }
+    }
+
+    fn save_all(&self, templates: &[Template]) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(templates)?)?;
+        Ok(())
+    }
+
+    /// Templates by name
+    pub fn list(&self) -> Vec<Template> {
+        let mut templates = self.templates.lock().unwrap().clone();
+        templates.sort_by_key(|a| a.name.to_lowercase());
+        templates
+    }
+
+    /// A template by ID
+    pub fn get(&self, id: &str) -> McpResult<Template> {
+        self.templates
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|t| t.id == id)
+            .cloned()
+            .ok_or_else(|| McpError::InvalidRequest(format!("No template {}", id)))
+    }
+
+    /// Add a template, or replace the one with its ID
+    pub fn save(&self, template: Template) -> McpResult<Template> {
+        validate_id(&template.id)?;
+        let mut templates = self.templates.lock().unwrap();
+        match templates.iter_mut().find(|t| t.id == template.id) {
+            Some(existing) => *existing = template.clone(),
+            None => templates.push(template.clone()),
+        }
+        self.save_all(&templates)?;
+        Ok(template)
+    }
+
+    /// Remove a template
+    pub fn remove(&self, id: &str) -> McpResult<Template> {
+        let mut templates = self.templates.lock().unwrap();
+        let index = templates
+            .iter()
+            .position(|t| t.id == id)
+            .ok_or_else(|| McpError::InvalidRequest(format!("No template {}", id)))?;
+        let template = templates.remove(index);
+        self.save_all(&templates)?;
+        Ok(template)
+    }
+}
+
+static TEMPLATE_LIBRARY: Lazy<Arc<TemplateLibrary>> =
+    Lazy::new(|| Arc::new(TemplateLibrary::at(data_path("templates.json"))));
+
+/// Get the global template library
+pub fn get_template_library() -> Arc<TemplateLibrary> {
+    TEMPLATE_LIBRARY.clone()
+}
@@ -0,0 +1,353 @@
+//! Deterministic test support for code built on [`ChatService`].
+//!
+//! Enabled with the `test-support` feature. A [`TestHarness`] wires a chat
+//! service to a scripted [`MockProvider`], storage in a throwaway directory and
+//! a [`ManualClock`], so streaming, retries, undo and sync flows can be
+//! exercised without a network, an API key or the user's data.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::mpsc;
+
+use crate::config::StorageManager;
+use crate::error::{McpError, McpResult};
+use crate::models::message::ContentType;
+use crate::models::{Message, MessageRole, ToolCall};
+use crate::protocol::ConnectionStatus;
+use crate::service::mcp::{CompletionProvider, McpService};
+use crate::service::ChatService;
+use crate::sync::ConflictQueue;
+use crate::utils::clock::{self, Clock};
+
+/// A clock that only moves when told to.
+///
+/// Every reading also moves it forward by one millisecond, so timestamps taken
+/// in a row stay distinct and ordered.
+pub struct ManualClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    /// Start the clock at a point in time
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(start),
+        }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+
+    /// Set the clock to a point in time
+    pub fn set(&self, to: DateTime<Utc>) {
+        *self.now.lock().unwrap() = to;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        let mut now = self.now.lock().unwrap();
+        let reading = *now;
+        *now += Duration::milliseconds(1);
+        reading
+    }
+}
+
+/// What the mock provider does for one request
+#[derive(Debug, Clone)]
+pub enum Turn {
+    /// Answer with this text
+    Reply(String),
+
+    /// Stream these text deltas; a complete request gets them joined
+    Stream(Vec<String>),
+
+    /// Stream these deltas, then fail with a connection error
+    StreamThenFail(Vec<String>, String),
+
+    /// Fail with a connection error
+    Fail(String),
+
+    /// Answer with this text and these tool calls
+    CallTools(String, Vec<ToolCall>),
+}
+
+/// Scripted completion provider.
+///
+/// Requests take turns from the script in order; once it runs out, replies
+/// echo the last user message.
+pub struct MockProvider {
+    script: Mutex<VecDeque<Turn>>,
+    requests: Mutex<Vec<Vec<Message>>>,
+    status: Mutex<ConnectionStatus>,
+    offline: AtomicBool,
+    connects: AtomicUsize,
+    cancelled: Mutex<Vec<String>>,
+}
+
+impl MockProvider {
+    /// Create a provider with an empty script
+    pub fn new() -> Self {
+        Self {
+            script: Mutex::new(VecDeque::new()),
+            requests: Mutex::new(Vec::new()),
+            status: Mutex::new(ConnectionStatus::Disconnected),
+            offline: AtomicBool::new(false),
+            connects: AtomicUsize::new(0),
+            cancelled: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add a turn to the script
+    pub fn push(&self, turn: Turn) -> &Self {
+        self.script.lock().unwrap().push_back(turn);
+        self
+    }
+
+    /// Answer the next request with a text
+    pub fn reply(&self, text: &str) -> &Self {
+        self.push(Turn::Reply(text.to_string()))
+    }
+
+    /// Stream the next reply in these deltas
+    pub fn stream(&self, deltas: &[&str]) -> &Self {
+        self.push(Turn::Stream(deltas.iter().map(|d| d.to_string()).collect()))
+    }
+
+    /// Fail the next request
+    pub fn fail(&self, error: &str) -> &Self {
+        self.push(Turn::Fail(error.to_string()))
+    }
+
+    /// Answer the next request with a text and tool calls
+    pub fn call_tools(&self, text: &str, calls: Vec<ToolCall>) -> &Self {
+        self.push(Turn::CallTools(text.to_string(), calls))
+    }
+
+    /// Refuse connections, as if the network were down
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::SeqCst);
+        if offline {
+            *self.status.lock().unwrap() = ConnectionStatus::Error("offline".to_string());
+        }
+    }
+
+    /// Conversations sent with each request, oldest first
+    pub fn requests(&self) -> Vec<Vec<Message>> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Number of successful connects
+    pub fn connects(&self) -> usize {
+        self.connects.load(Ordering::SeqCst)
+    }
+
+    /// Streams the caller canceled
+    pub fn cancelled(&self) -> Vec<String> {
+        self.cancelled.lock().unwrap().clone()
+    }
+
+    fn next_turn(&self, messages: &[Message]) -> Turn {
+        self.requests.lock().unwrap().push(messages.to_vec());
+        self.script.lock().unwrap().pop_front().unwrap_or_else(|| {
+            let prompt = messages
+                .iter()
+                .rev()
+                .find(|m| m.role == MessageRole::User)
+                .map(|m| m.text())
+                .unwrap_or_default();
+            Turn::Reply(format!("Echo: {}", prompt))
+        })
+    }
+
+    fn calling(text: String, calls: Vec<ToolCall>) -> Message {
+        let mut message = Message::assistant(text);
+        message.content.parts.push(ContentType::ToolCalls { calls });
+        message
+    }
+
+    fn require_online(&self) -> McpResult<()> {
+        if self.offline.load(Ordering::SeqCst) {
+            return Err(McpError::Connection("offline".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for MockProvider {
+    fn connection_status(&self) -> ConnectionStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    async fn connect(&self) -> McpResult<()> {
+        self.require_online()?;
+        *self.status.lock().unwrap() = ConnectionStatus::Connected;
+        self.connects.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> McpResult<()> {
+        *self.status.lock().unwrap() = ConnectionStatus::Disconnected;
+        Ok(())
+    }
+
+    async fn send_completion(
+        &self,
+        _model: &str,
+        messages: &[Message],
+        _max_tokens: u32,
+        _temperature: f32,
+    ) -> McpResult<Message> {
+        self.require_online()?;
+        match self.next_turn(messages) {
+            Turn::Reply(text) => Ok(Message::assistant(text)),
+            Turn::Stream(deltas) => Ok(Message::assistant(deltas.concat())),
+            Turn::StreamThenFail(_, error) | Turn::Fail(error) => Err(McpError::Connection(error)),
+            Turn::CallTools(text, calls) => Ok(Self::calling(text, calls)),
+        }
+    }
+
+    async fn stream_completion(
+        &self,
+        _model: &str,
+        messages: &[Message],
+        _max_tokens: u32,
+        _temperature: f32,
+    ) -> McpResult<mpsc::Receiver<McpResult<Message>>> {
+        self.require_online()?;
+        let (deltas, error) = match self.next_turn(messages) {
+            Turn::Reply(text) => (vec![text], None),
+            Turn::Stream(deltas) => (deltas, None),
+            Turn::StreamThenFail(deltas, error) => (deltas, Some(error)),
+            Turn::Fail(error) => return Err(McpError::Connection(error)),
+            Turn::CallTools(text, calls) => {
+                let (tx, rx) = mpsc::channel(1);
+                let _ = tx.try_send(Ok(Self::calling(text, calls)));
+                return Ok(rx);
+            }
+        };
+
+        // Everything is queued up front, so the stream doesn't depend on timing
+        let (tx, rx) = mpsc::channel(deltas.len() + 1);
+        for delta in deltas {
+            let _ = tx.try_send(Ok(Message::assistant(delta)));
+        }
+        if let Some(error) = error {
+            let _ = tx.try_send(Err(McpError::Connection(error)));
+        }
+        Ok(rx)
+    }
+
+    async fn cancel_streaming(&self, stream_id: &str) -> McpResult<()> {
+        self.cancelled.lock().unwrap().push(stream_id.to_string());
+        Ok(())
+    }
+}
+
+/// Harnesses share the global clock, so only one runs at a time
+static HARNESS_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// A chat service on a mock provider, throwaway storage and a manual clock.
+///
+/// The clock is installed globally while the harness lives; harnesses in
+/// parallel tests wait for each other.
+pub struct TestHarness {
+    /// Scripted provider behind the service
+    pub provider: Arc<MockProvider>,
+
+    /// Clock used for timestamps, trash retention and undo windows
+    pub clock: Arc<ManualClock>,
+
+    /// Storage of the service
+    pub storage: Arc<StorageManager>,
+
+    /// MCP service on the mock provider
+    pub service: Arc<McpService>,
+
+    /// Chat service under test
+    pub chat: ChatService,
+
+    dir: tempfile::TempDir,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl TestHarness {
+    /// Time the clock starts at
+    pub fn epoch() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    /// Create a harness with an empty script
+    pub fn new() -> Self {
+        // A test that panicked while holding the lock doesn't affect the next one
+        let guard = HARNESS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let clock = Arc::new(ManualClock::new(Self::epoch()));
+        clock::set_clock(clock.clone());
+
+        let dir = tempfile::tempdir().expect("Failed to create a temporary directory");
+        let storage = Arc::new(StorageManager::at(dir.path().to_path_buf()));
+        let provider = Arc::new(MockProvider::new());
+        let service = Arc::new(McpService::with_backends(provider.clone(), storage.clone()));
+        let chat = ChatService::new(service.clone());
+
+        Self {
+            provider,
+            clock,
+            storage,
+            service,
+            chat,
+            dir,
+            _guard: guard,
+        }
+    }
+
+    /// Directory holding the harness's data
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// A sync conflict queue stored with the harness's data
+    pub fn conflict_queue(&self) -> ConflictQueue {
+        ConflictQueue::open(self.dir.path().join("sync_conflicts.json"))
+    }
+
+    /// Read a stream to its end; returns the chunks and the error that ended
+    /// it, if any
+    pub async fn drain(mut stream: mpsc::Receiver<McpResult<Message>>) -> (Vec<Message>, Option<McpError>) {
+        let mut chunks = Vec::new();
+        while let Some(chunk) = stream.recv().await {
+            match chunk {
+                Ok(chunk) => chunks.push(chunk),
+                Err(e) => return (chunks, Some(e)),
+            }
+        }
+        (chunks, None)
+    }
+}
+
+impl Default for TestHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TestHarness {
+    fn drop(&mut self) {
+        clock::reset_clock();
+    }
+}
@@ -0,0 +1,85 @@
+use super::{Palette, Rgb, Theme};
+
+/// Names of the built-in themes
+pub const BUILTIN_THEMES: &[&str] = &["dark", "light", "high-contrast"];
+
+const fn rgb(hex: u32) -> Rgb {
+    Rgb {
+        r: (hex >> 16) as u8,
+        g: (hex >> 8) as u8,
+        b: hex as u8,
+    }
+}
+
+/// A built-in theme by name
+pub fn builtin(name: &str) -> Option<Theme> {
+    let (dark, palette) = match name {
+        "dark" => (
+            true,
+            Palette {
+                background: rgb(0x1e1f22),
+                surface: rgb(0x2b2d31),
+                foreground: rgb(0xe3e5e8),
+                muted: rgb(0x8e9297),
+                accent: rgb(0x3b82f6),
+                accent_foreground: rgb(0xffffff),
+                selection: rgb(0x3b82f6),
+                selection_foreground: rgb(0xffffff),
+                border: rgb(0x3f4147),
+                success: rgb(0x22c55e),
+                warning: rgb(0xeab308),
+                error: rgb(0xef4444),
+                user: rgb(0x4ade80),
+                assistant: rgb(0x60a5fa),
+                system: rgb(0xfacc15),
+            },
+        ),
+        "light" => (
+            false,
+            Palette {
+                background: rgb(0xffffff),
+                surface: rgb(0xf3f4f6),
+                foreground: rgb(0x111827),
+                muted: rgb(0x6b7280),
+                accent: rgb(0x2563eb),
+                accent_foreground: rgb(0xffffff),
+                selection: rgb(0xdbeafe),
+                selection_foreground: rgb(0x111827),
+                border: rgb(0xd1d5db),
+                success: rgb(0x15803d),
+                warning: rgb(0xa16207),
+                error: rgb(0xb91c1c),
+                user: rgb(0x15803d),
+                assistant: rgb(0x1d4ed8),
+                system: rgb(0xa16207),
+            },
+        ),
+        "high-contrast" => (
+            true,
+            Palette {
+                background: rgb(0x000000),
+                surface: rgb(0x000000),
+                foreground: rgb(0xffffff),
+                muted: rgb(0xd0d0d0),
+                accent: rgb(0xffff00),
+                accent_foreground: rgb(0x000000),
+                selection: rgb(0xffff00),
+                selection_foreground: rgb(0x000000),
+                border: rgb(0xffffff),
+                success: rgb(0x00ff00),
+                warning: rgb(0xffff00),
+                error: rgb(0xff4040),
+                user: rgb(0x00ff00),
+                assistant: rgb(0x00ffff),
+                system: rgb(0xffff00),
+            },
+        ),
+        _ => return None,
+    };
+
+    Some(Theme {
+        name: name.to_string(),
+        dark,
+        palette,
+    })
+}
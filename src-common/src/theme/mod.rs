@@ -0,0 +1,358 @@
+mod builtin;
+
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use crate::config::{config_path, get_settings};
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Topic};
+
+pub use builtin::{builtin, BUILTIN_THEMES};
+
+const THEME_SETTINGS_FILE: &str = "theme.json";
+const CUSTOM_THEMES_DIR: &str = "themes";
+
+/// An RGB color, written as `#rrggbb`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl FromStr for Rgb {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || McpError::InvalidRequest(format!("Invalid color '{}'; use #rrggbb", s));
+        let hex = s.trim().strip_prefix('#').ok_or_else(invalid)?;
+        if hex.len() != 6 {
+            return Err(invalid());
+        }
+        let value = u32::from_str_radix(hex, 16).map_err(|_| invalid())?;
+        Ok(Rgb {
+            r: (value >> 16) as u8,
+            g: (value >> 8) as u8,
+            b: value as u8,
+        })
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+impl Serialize for Rgb {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Colors of a theme by role
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    /// Window background
+    pub background: Rgb,
+
+    /// Panels, popups and input boxes
+    pub surface: Rgb,
+
+    /// Body text
+    pub foreground: Rgb,
+
+    /// Secondary text such as timestamps and hints
+    pub muted: Rgb,
+
+    /// Title bars, buttons and focus
+    pub accent: Rgb,
+
+    /// Text on the accent color
+    pub accent_foreground: Rgb,
+
+    /// Selected list items
+    pub selection: Rgb,
+
+    /// Text on the selection color
+    pub selection_foreground: Rgb,
+
+    /// Borders and separators
+    pub border: Rgb,
+
+    /// Success messages
+    pub success: Rgb,
+
+    /// Warnings and pending states
+    pub warning: Rgb,
+
+    /// Errors
+    pub error: Rgb,
+
+    /// User messages
+    pub user: Rgb,
+
+    /// Assistant messages
+    pub assistant: Rgb,
+
+    /// System messages
+    pub system: Rgb,
+}
+
+impl Palette {
+    /// Every color with its role name
+    pub fn colors(&self) -> [(&'static str, Rgb); 15] {
+        [
+            ("background", self.background),
+            ("surface", self.surface),
+            ("foreground", self.foreground),
+            ("muted", self.muted),
+            ("accent", self.accent),
+            ("accent-foreground", self.accent_foreground),
+            ("selection", self.selection),
+            ("selection-foreground", self.selection_foreground),
+            ("border", self.border),
+            ("success", self.success),
+            ("warning", self.warning),
+            ("error", self.error),
+            ("user", self.user),
+            ("assistant", self.assistant),
+            ("system", self.system),
+        ]
+    }
+}
+
+/// A named color scheme
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    /// Theme name
+    pub name: String,
+
+    /// Whether the theme is dark, for widgets that only have light and dark styles
+    #[serde(default)]
+    pub dark: bool,
+
+    /// Colors
+    pub palette: Palette,
+}
+
+impl Theme {
+    /// CSS custom properties for the desktop frontend, e.g. `--color-accent`
+    pub fn css_variables(&self) -> BTreeMap<String, String> {
+        self.palette
+            .colors()
+            .iter()
+            .map(|(role, color)| (format!("--color-{}", role), color.to_string()))
+            .collect()
+    }
+
+    /// A `:root` rule setting [`Theme::css_variables`]
+    pub fn css(&self) -> String {
+        let mut css = String::from(":root {\n");
+        css.push_str(&format!("  color-scheme: {};\n", if self.dark { "dark" } else { "light" }));
+        for (name, value) in self.css_variables() {
+            css.push_str(&format!("  {}: {};\n", name, value));
+        }
+        css.push_str("}\n");
+        css
+    }
+}
+
+/// Which theme is used, overall and per profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThemeSettings {
+    /// Theme used by profiles without their own
+    pub active: String,
+
+    /// Theme per profile, e.g. `tui` or `desktop`
+    #[serde(default)]
+    pub profiles: BTreeMap<String, String>,
+}
+
+impl Default for ThemeSettings {
+    fn default() -> Self {
+        let dark = get_settings().lock().map(|s| s.ui.dark_mode).unwrap_or(true);
+        Self {
+            active: if dark { "dark" } else { "light" }.to_string(),
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+impl ThemeSettings {
+    /// Load theme settings, falling back to the UI dark mode setting
+    pub fn load() -> Self {
+        match fs::read_to_string(config_path(THEME_SETTINGS_FILE)) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Invalid theme settings, using defaults: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Persist theme settings
+    pub fn save(&self) -> McpResult<()> {
+        fs::write(config_path(THEME_SETTINGS_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Name of the theme a profile uses
+    pub fn theme_name(&self, profile: Option<&str>) -> &str {
+        profile
+            .and_then(|p| self.profiles.get(p))
+            .unwrap_or(&self.active)
+    }
+}
+
+fn custom_theme_path(name: &str) -> PathBuf {
+    config_path(CUSTOM_THEMES_DIR).join(format!("{}.json", name))
+}
+
+fn validate_name(name: &str) -> McpResult<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(McpError::InvalidRequest(format!(
+            "Invalid theme name '{}'; use letters, digits, '-' and '_'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Load a built-in or custom theme
+pub fn load_theme(name: &str) -> McpResult<Theme> {
+    if let Some(theme) = builtin(name) {
+        return Ok(theme);
+    }
+    validate_name(name)?;
+    let content = fs::read_to_string(custom_theme_path(name))
+        .map_err(|_| McpError::InvalidRequest(format!("Unknown theme '{}'", name)))?;
+    let mut theme: Theme = serde_json::from_str(&content)?;
+    theme.name = name.to_string();
+    Ok(theme)
+}
+
+/// Built-in themes followed by custom ones
+pub fn list_themes() -> Vec<String> {
+    let mut custom: Vec<String> = fs::read_dir(config_path(CUSTOM_THEMES_DIR))
+        .map(|dir| {
+            dir.flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+                .filter(|name| !BUILTIN_THEMES.contains(&name.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    custom.sort();
+
+    BUILTIN_THEMES.iter().map(|s| s.to_string()).chain(custom).collect()
+}
+
+/// Save a custom theme; built-in themes can't be overwritten
+pub fn save_custom_theme(theme: &Theme) -> McpResult<()> {
+    validate_name(&theme.name)?;
+    if BUILTIN_THEMES.contains(&theme.name.as_str()) {
+        return Err(McpError::InvalidRequest(format!(
+            "'{}' is a built-in theme; save it under another name",
+            theme.name
+        )));
+    }
+    fs::create_dir_all(config_path(CUSTOM_THEMES_DIR))?;
+    fs::write(custom_theme_path(&theme.name), serde_json::to_string_pretty(theme)?)?;
+
+    // Profiles using the theme pick up the new colors
+    let settings = get_theme_settings();
+    let settings = settings.read().unwrap();
+    if settings.active == theme.name || settings.profiles.values().any(|t| *t == theme.name) {
+        emit_theme_changed(theme, None);
+    }
+    Ok(())
+}
+
+/// Delete a custom theme; profiles using it fall back to the default
+pub fn delete_custom_theme(name: &str) -> McpResult<()> {
+    validate_name(name)?;
+    if BUILTIN_THEMES.contains(&name) {
+        return Err(McpError::InvalidRequest(format!("'{}' is a built-in theme", name)));
+    }
+    fs::remove_file(custom_theme_path(name))
+        .map_err(|_| McpError::InvalidRequest(format!("Unknown theme '{}'", name)))?;
+
+    let settings = get_theme_settings();
+    let mut settings = settings.write().unwrap();
+    if settings.active == name || settings.profiles.values().any(|t| t == name) {
+        let fallback = ThemeSettings::default().active;
+        if settings.active == name {
+            settings.active = fallback.clone();
+        }
+        settings.profiles.retain(|_, theme| theme != name);
+        settings.save()?;
+        if let Some(theme) = builtin(&fallback) {
+            emit_theme_changed(&theme, None);
+        }
+    }
+    Ok(())
+}
+
+/// Theme a profile uses; an unknown theme falls back to the default one
+pub fn active_theme(profile: Option<&str>) -> Theme {
+    let name = get_theme_settings().read().unwrap().theme_name(profile).to_string();
+    load_theme(&name).unwrap_or_else(|e| {
+        warn!("Failed to load theme '{}', using the default: {}", name, e);
+        builtin(&ThemeSettings::default().active).expect("default theme exists")
+    })
+}
+
+/// Switch the theme of a profile, or the overall theme without one, and
+/// notify running front ends
+pub fn set_active_theme(name: &str, profile: Option<&str>) -> McpResult<Theme> {
+    let theme = load_theme(name)?;
+
+    let settings = get_theme_settings();
+    let mut settings = settings.write().unwrap();
+    match profile {
+        Some(profile) => {
+            settings.profiles.insert(profile.to_string(), name.to_string());
+        }
+        None => settings.active = name.to_string(),
+    }
+    settings.save()?;
+
+    emit_theme_changed(&theme, profile);
+    Ok(theme)
+}
+
+fn emit_theme_changed(theme: &Theme, profile: Option<&str>) {
+    get_event_bus().emit(
+        Topic::System,
+        names::THEME_CHANGED,
+        serde_json::json!({
+            "theme": theme,
+            "profile": profile,
+            "css_variables": theme.css_variables(),
+        }),
+    );
+}
+
+static THEME_SETTINGS: Lazy<Arc<RwLock<ThemeSettings>>> =
+    Lazy::new(|| Arc::new(RwLock::new(ThemeSettings::load())));
+
+/// Get the global theme settings
+pub fn get_theme_settings() -> Arc<RwLock<ThemeSettings>> {
+    THEME_SETTINGS.clone()
+}
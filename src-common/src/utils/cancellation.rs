@@ -0,0 +1,88 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+pub use tokio_util::sync::CancellationToken;
+
+use crate::error::{McpError, McpResult};
+
+/// Cancellation token and optional deadline carried by a request
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    /// Token canceled when the caller aborts
+    pub token: CancellationToken,
+
+    /// Point in time after which the request fails with a timeout
+    pub deadline: Option<Instant>,
+
+    /// Total time budget, reported in timeout errors
+    budget: Option<Duration>,
+}
+
+impl RequestContext {
+    /// Create a context without a deadline
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a context that expires after the given duration
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            token: CancellationToken::new(),
+            deadline: Some(Instant::now() + timeout),
+            budget: Some(timeout),
+        }
+    }
+
+    /// Create a child context; canceling the parent cancels the child
+    pub fn child(&self) -> Self {
+        Self {
+            token: self.token.child_token(),
+            deadline: self.deadline,
+            budget: self.budget,
+        }
+    }
+
+    /// Cancel the request
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Time left until the deadline
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Return an error if the request should stop
+    pub fn check(&self) -> McpResult<()> {
+        if self.token.is_cancelled() {
+            return Err(McpError::Cancelled);
+        }
+        if self.remaining() == Some(Duration::ZERO) {
+            return Err(McpError::Timeout(self.budget.unwrap_or_default()));
+        }
+        Ok(())
+    }
+
+    /// Run a future until it completes, the token is canceled, or the deadline passes
+    pub async fn run<T, E, F>(&self, future: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: From<McpError>,
+    {
+        self.check()?;
+
+        let deadline = async {
+            match self.remaining() {
+                Some(remaining) => tokio::time::sleep(remaining).await,
+                None => std::future::pending::<()>().await,
+            }
+        };
+
+        tokio::select! {
+            result = future => result,
+            _ = self.token.cancelled() => Err(McpError::Cancelled.into()),
+            _ = deadline => Err(McpError::Timeout(self.budget.unwrap_or_default()).into()),
+        }
+    }
+}
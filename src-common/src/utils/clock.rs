@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+/// Source of the current time for timestamps, retention and undo windows
+pub trait Clock: Send + Sync {
+    /// The current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+static CLOCK: Lazy<RwLock<Arc<dyn Clock>>> = Lazy::new(|| RwLock::new(Arc::new(SystemClock)));
+
+/// Use another clock, e.g. a controllable one in tests
+pub fn set_clock(clock: Arc<dyn Clock>) {
+    *CLOCK.write().unwrap() = clock;
+}
+
+/// Go back to the system clock
+pub fn reset_clock() {
+    set_clock(Arc::new(SystemClock));
+}
+
+/// The current time
+pub fn now() -> DateTime<Utc> {
+    CLOCK.read().unwrap().now()
+}
+
+/// The current time as a `SystemTime`, for message and conversation timestamps
+pub fn system_now() -> SystemTime {
+    now().into()
+}
@@ -0,0 +1,351 @@
+//! Markdown that renders safely while it streams in
+//!
+//! A reply arrives a few characters at a time, so the last line is usually
+//! incomplete: two backticks may become a code fence, `1` a list item, and
+//! `**bo` bold text. Rendering that as-is makes the screen flicker and
+//! highlights half a code block as prose. [`MarkdownStream`] parses complete
+//! lines into blocks, keeps track of open fences and lists across chunks,
+//! and only adds as much of the last line as can't change meaning.
+
+use serde::{Deserialize, Serialize};
+
+/// An item of a list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListItem {
+    /// Nesting depth, from 0
+    pub depth: usize,
+
+    /// Marker as written, e.g. `-` or `3.`
+    pub marker: String,
+
+    /// Text of the item
+    pub text: String,
+}
+
+/// A block of rendered markdown
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Block {
+    /// Lines of prose
+    Paragraph { text: String },
+
+    /// A heading, levels 1 to 6
+    Heading { level: u8, text: String },
+
+    /// Consecutive list items
+    List { ordered: bool, items: Vec<ListItem> },
+
+    /// Quoted lines, without the `>`
+    Quote { text: String },
+
+    /// Fenced code, without the fences; `closed` is false while the
+    /// closing fence hasn't arrived
+    Code {
+        language: Option<String>,
+        text: String,
+        closed: bool,
+    },
+
+    /// A horizontal rule
+    Rule,
+}
+
+/// Incremental markdown parser for streamed text
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownStream {
+    finished: Vec<Block>,
+    open: Option<Block>,
+    fence: Option<(char, usize)>,
+    after_blank: bool,
+    partial: String,
+}
+
+impl MarkdownStream {
+    /// Parser for a new stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Blocks of a complete text
+    pub fn parse(text: &str) -> Vec<Block> {
+        let mut stream = Self::new();
+        stream.push(text);
+        stream.finish();
+        stream.finished
+    }
+
+    /// Add streamed text; returns the blocks it finished
+    pub fn push(&mut self, text: &str) -> Vec<Block> {
+        let before = self.finished.len();
+        self.partial.push_str(text);
+        while let Some(end) = self.partial.find('\n') {
+            let line: String = self.partial.drain(..=end).collect();
+            self.feed_line(line.trim_end_matches(['\n', '\r']));
+        }
+        self.finished[before..].to_vec()
+    }
+
+    /// End the stream; returns the blocks this finished. A fence left open
+    /// stays a code block with `closed` false.
+    pub fn finish(&mut self) -> Vec<Block> {
+        let before = self.finished.len();
+        let partial = std::mem::take(&mut self.partial);
+        if !partial.is_empty() {
+            self.feed_line(partial.trim_end_matches('\r'));
+        }
+        self.close();
+        self.fence = None;
+        self.finished[before..].to_vec()
+    }
+
+    /// Blocks that are complete; they no longer change
+    pub fn finished(&self) -> &[Block] {
+        &self.finished
+    }
+
+    /// Everything safe to render now: the finished blocks, then the block
+    /// in progress with as much of the last line as can't change meaning
+    pub fn blocks(&self) -> Vec<Block> {
+        let mut view = self.clone();
+        view.partial.clear();
+        if let Some(line) = self.safe_partial() {
+            view.feed_line(&line);
+        }
+        let mut blocks = view.finished;
+        blocks.extend(view.open);
+        blocks
+    }
+
+    /// Whether a code fence is open
+    pub fn in_code(&self) -> bool {
+        self.fence.is_some()
+    }
+
+    fn close(&mut self) {
+        if let Some(block) = self.open.take() {
+            self.finished.push(block);
+        }
+    }
+
+    fn feed_line(&mut self, line: &str) {
+        if let Some((fence_char, fence_len)) = self.fence {
+            if is_closing_fence(line, fence_char, fence_len) {
+                if let Some(Block::Code { closed, .. }) = self.open.as_mut() {
+                    *closed = true;
+                }
+                self.fence = None;
+                self.close();
+            } else if let Some(Block::Code { text, .. }) = self.open.as_mut() {
+                text.push_str(line);
+                text.push('\n');
+            }
+            return;
+        }
+
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            // Lists carry on over blank lines between items
+            if !matches!(self.open, Some(Block::List { .. })) {
+                self.close();
+            }
+            self.after_blank = true;
+            return;
+        }
+        let after_blank = std::mem::replace(&mut self.after_blank, false);
+        let indent = line.len() - trimmed.len();
+
+        if let Some((fence_char, fence_len, info)) = opening_fence(trimmed) {
+            self.close();
+            self.open = Some(Block::Code {
+                language: info.split_whitespace().next().map(str::to_string),
+                text: String::new(),
+                closed: false,
+            });
+            self.fence = Some((fence_char, fence_len));
+        } else if let Some((level, text)) = heading(trimmed) {
+            self.close();
+            self.finished.push(Block::Heading {
+                level,
+                text: text.to_string(),
+            });
+        } else if is_rule(trimmed) {
+            self.close();
+            self.finished.push(Block::Rule);
+        } else if let Some((ordered, marker, text)) = list_item(trimmed) {
+            let item = ListItem {
+                depth: indent / 2,
+                marker: marker.to_string(),
+                text: text.to_string(),
+            };
+            match self.open.as_mut() {
+                Some(Block::List { ordered: list_ordered, items }) if *list_ordered == ordered || item.depth > 0 => {
+                    items.push(item)
+                }
+                _ => {
+                    self.close();
+                    self.open = Some(Block::List {
+                        ordered,
+                        items: vec![item],
+                    });
+                }
+            }
+        } else if let Some(text) = trimmed.strip_prefix('>') {
+            let text = text.strip_prefix(' ').unwrap_or(text);
+            match self.open.as_mut() {
+                Some(Block::Quote { text: quote }) => {
+                    quote.push('\n');
+                    quote.push_str(text);
+                }
+                _ => {
+                    self.close();
+                    self.open = Some(Block::Quote { text: text.to_string() });
+                }
+            }
+        } else {
+            match self.open.as_mut() {
+                // A line right under an item continues it
+                Some(Block::List { items, .. }) if !after_blank => {
+                    if let Some(item) = items.last_mut() {
+                        item.text.push(' ');
+                        item.text.push_str(trimmed);
+                    }
+                }
+                Some(Block::Paragraph { text }) | Some(Block::Quote { text }) => {
+                    text.push('\n');
+                    text.push_str(trimmed);
+                }
+                _ => {
+                    self.close();
+                    self.open = Some(Block::Paragraph {
+                        text: trimmed.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    /// The incomplete last line, if any of it is safe to render
+    fn safe_partial(&self) -> Option<String> {
+        let line = self.partial.trim_end_matches('\r');
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            return None;
+        }
+
+        if let Some((fence_char, _)) = self.fence {
+            // Could still be the closing fence
+            if trimmed.chars().all(|c| c == fence_char) {
+                return None;
+            }
+            return Some(line.to_string());
+        }
+
+        // Could still become a fence, heading, rule or list item
+        let first = trimmed.chars().next().unwrap_or_default();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            return None;
+        }
+        if trimmed.chars().all(|c| c == first) && "`~#-*_+".contains(first) {
+            return None;
+        }
+        if trimmed.trim_end_matches(['.', ')']).chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        let safe = safe_inline(line);
+        if safe.trim().is_empty() {
+            None
+        } else {
+            Some(safe)
+        }
+    }
+}
+
+/// A line cut before inline markup that hasn't been closed yet: a code
+/// span, strong emphasis, or a link
+pub fn safe_inline(line: &str) -> String {
+    let mut cut = line.len();
+
+    let ticks: Vec<usize> = line.match_indices('`').map(|(i, _)| i).collect();
+    if ticks.len() % 2 == 1 {
+        cut = ticks[ticks.len() - 1];
+    }
+    for marker in ["**", "__"] {
+        let hits: Vec<usize> = line[..cut].match_indices(marker).map(|(i, _)| i).collect();
+        if hits.len() % 2 == 1 {
+            cut = hits[hits.len() - 1];
+        }
+    }
+    if let Some(open) = line[..cut].rfind('[') {
+        let rest = &line[open..cut];
+        let complete = match rest.find("](") {
+            Some(target) => rest[target..].contains(')'),
+            // A bracket without a link target is just text once it's closed
+            None => rest.contains(']') && !rest.ends_with(']'),
+        };
+        if !complete {
+            cut = open;
+        }
+    }
+
+    line[..cut].to_string()
+}
+
+/// Fence character, fence length and info string of an opening fence
+fn opening_fence(line: &str) -> Option<(char, usize, &str)> {
+    let fence_char = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.chars().take_while(|c| *c == fence_char).count();
+    if len < 3 {
+        return None;
+    }
+    let info = &line[len..];
+    // Backtick fences can't have backticks in their info string
+    if fence_char == '`' && info.contains('`') {
+        return None;
+    }
+    Some((fence_char, len, info.trim()))
+}
+
+fn is_closing_fence(line: &str, fence_char: char, fence_len: usize) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= fence_len && trimmed.chars().all(|c| c == fence_char)
+}
+
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if rest.is_empty() {
+        return Some((level as u8, ""));
+    }
+    rest.strip_prefix(' ')
+        .map(|text| (level as u8, text.trim().trim_end_matches('#').trim_end()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    let Some(first) = compact.chars().next() else {
+        return false;
+    };
+    "-*_".contains(first) && compact.len() >= 3 && compact.chars().all(|c| c == first)
+}
+
+/// Whether the list is ordered, the marker and the text of a list item
+fn list_item(line: &str) -> Option<(bool, &str, &str)> {
+    if let Some(first) = line.chars().next().filter(|c| "-*+".contains(*c)) {
+        let rest = &line[first.len_utf8()..];
+        return rest.strip_prefix(' ').map(|text| (false, &line[..1], text.trim_start()));
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+    let rest = &line[digits..];
+    let delimiter = rest.chars().next().filter(|c| *c == '.' || *c == ')')?;
+    rest[1..]
+        .strip_prefix(' ')
+        .map(|text| (true, &line[..digits + delimiter.len_utf8()], text.trim_start()))
+}
@@ -1,3 +1,6 @@
+pub mod cancellation;
+pub mod clock;
+pub mod markdown;
 pub mod security;
 pub mod text;
 
@@ -0,0 +1,442 @@
+//! Browser extension companion
+//!
+//! A browser extension sends the page the user is on, or the text selected
+//! in it, to the daemon and gets back a summary, an answer, or a new
+//! conversation holding the page. Only extension origins may talk to it:
+//! an extension starts pairing, the daemon shows a short code, and the user
+//! types that code into the extension, which then gets a token tied to its
+//! origin. Requests are rate limited per paired extension.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use log::{debug, info};
+use once_cell::sync::Lazy;
+use rand::{Rng, RngCore};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::models::Message;
+use crate::service::chat::ChatService;
+use crate::utils::clock;
+
+/// Largest request body accepted
+pub const MAX_BODY_BYTES: usize = 512 * 1024;
+
+/// Page characters passed to the model; the rest is cut off
+const MAX_PAGE_CHARS: usize = 48_000;
+
+/// How long a pairing code can be entered
+const PAIRING_TTL_MINUTES: i64 = 5;
+
+/// Wrong codes before a pairing is dropped
+const MAX_PAIRING_ATTEMPTS: u32 = 5;
+
+/// Pairings waiting for their code at once
+const MAX_PENDING_PAIRINGS: usize = 3;
+
+/// URL schemes of browser extension origins
+const EXTENSION_SCHEMES: [&str; 3] = ["chrome-extension", "moz-extension", "safari-web-extension"];
+
+const SUMMARY_SYSTEM_PROMPT: &str = "You summarize web pages the user is reading. \
+Write a short summary of the page: its main point first, then the key details as bullets starting with \"- \". \
+Only use what the page says.";
+
+const ANSWER_SYSTEM_PROMPT: &str = "You answer questions about the web page the user is reading. \
+Answer from the page; when it doesn't say, answer briefly from what you know and say so.";
+
+/// Whether an `Origin` header is a browser extension's, e.g.
+/// `chrome-extension://<id>`; web pages can't pair
+pub fn is_extension_origin(origin: &str) -> bool {
+    origin
+        .split_once("://")
+        .is_some_and(|(scheme, id)| {
+            EXTENSION_SCHEMES.contains(&scheme)
+                && !id.is_empty()
+                && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+        })
+}
+
+fn hash_token(token: &str) -> String {
+    digest::digest(&digest::SHA256, token.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("ext_{}", bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+/// A paired browser extension
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairedExtension {
+    /// Pairing ID
+    pub id: String,
+
+    /// Origin every request must come from
+    pub origin: String,
+
+    /// Name the extension gave itself
+    pub name: String,
+
+    /// SHA-256 of the token; the token itself is only shown to the extension
+    pub token_hash: String,
+
+    /// When it was paired
+    pub created_at: DateTime<Utc>,
+
+    /// When it last made a request
+    #[serde(default)]
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// A pairing waiting for the user to enter its code in the extension
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingPairing {
+    /// Pairing ID, sent back with the code
+    pub id: String,
+
+    /// Origin that asked to pair
+    pub origin: String,
+
+    /// Name the extension gave itself
+    pub name: String,
+
+    /// Six-digit code shown to the user
+    pub code: String,
+
+    /// When the code stops working
+    pub expires_at: DateTime<Utc>,
+
+    #[serde(skip)]
+    attempts: u32,
+}
+
+/// Result of a completed pairing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pairing {
+    /// The paired extension
+    pub extension: PairedExtension,
+
+    /// Bearer token for its requests
+    pub token: String,
+}
+
+/// Paired extensions and pairings in progress
+pub struct CompanionStore {
+    path: PathBuf,
+    paired: Mutex<Vec<PairedExtension>>,
+    pending: Mutex<Vec<PendingPairing>>,
+}
+
+impl CompanionStore {
+    /// Store kept in a file; pairings in progress are kept in memory only
+    pub fn at(path: PathBuf) -> Self {
+        let paired = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            paired: Mutex::new(paired),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn save(&self, paired: &[PairedExtension]) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(paired)?)?;
+        Ok(())
+    }
+
+    /// Paired extensions
+    pub fn list(&self) -> Vec<PairedExtension> {
+        self.paired.lock().unwrap().clone()
+    }
+
+    /// Start pairing an extension origin; the code in the result is for the
+    /// user's eyes and must not be sent back to the extension
+    pub fn start_pairing(&self, origin: &str, name: &str) -> McpResult<PendingPairing> {
+        if !is_extension_origin(origin) {
+            return Err(McpError::Authentication(format!("{} is not a browser extension", origin)));
+        }
+        let now = clock::now();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|p| p.expires_at > now && p.origin != origin);
+        if pending.len() >= MAX_PENDING_PAIRINGS {
+            return Err(McpError::RateLimit("Too many pairings in progress".to_string()));
+        }
+
+        let pairing = PendingPairing {
+            id: uuid::Uuid::new_v4().to_string(),
+            origin: origin.to_string(),
+            name: name.trim().chars().take(64).collect(),
+            code: format!("{:06}", rand::thread_rng().gen_range(0..1_000_000)),
+            expires_at: now + ChronoDuration::minutes(PAIRING_TTL_MINUTES),
+            attempts: 0,
+        };
+        pending.push(pairing.clone());
+        Ok(pairing)
+    }
+
+    /// Finish pairing with the code the user entered; the origin must be
+    /// the one that started it
+    pub fn complete_pairing(&self, id: &str, origin: &str, code: &str) -> McpResult<Pairing> {
+        let now = clock::now();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|p| p.expires_at > now);
+        let index = pending
+            .iter()
+            .position(|p| p.id == id && p.origin == origin)
+            .ok_or_else(|| McpError::Authentication("No such pairing, or it expired".to_string()))?;
+
+        if pending[index].code != code.trim() {
+            pending[index].attempts += 1;
+            if pending[index].attempts >= MAX_PAIRING_ATTEMPTS {
+                pending.remove(index);
+                return Err(McpError::Authentication("Wrong code; start pairing again".to_string()));
+            }
+            return Err(McpError::Authentication("Wrong code".to_string()));
+        }
+        let request = pending.remove(index);
+        drop(pending);
+
+        let token = new_token();
+        let extension = PairedExtension {
+            id: uuid::Uuid::new_v4().to_string(),
+            origin: request.origin,
+            name: request.name,
+            token_hash: hash_token(&token),
+            created_at: now,
+            last_used_at: None,
+        };
+        let mut paired = self.paired.lock().unwrap();
+        // Pairing again replaces the old token
+        paired.retain(|p| p.origin != extension.origin);
+        paired.push(extension.clone());
+        self.save(&paired)?;
+        info!("Paired browser extension {} ({})", extension.name, extension.origin);
+        Ok(Pairing { extension, token })
+    }
+
+    /// The extension a token belongs to, if the request comes from its origin
+    pub fn authenticate(&self, token: &str, origin: &str) -> Option<PairedExtension> {
+        let hash = hash_token(token);
+        let mut paired = self.paired.lock().unwrap();
+        let extension = paired.iter_mut().find(|p| p.token_hash == hash && p.origin == origin)?;
+        extension.last_used_at = Some(clock::now());
+        let extension = extension.clone();
+        if let Err(e) = self.save(&paired) {
+            debug!("Failed to record extension use: {}", e);
+        }
+        Some(extension)
+    }
+
+    /// Whether an origin is paired, for answering CORS preflights
+    pub fn is_paired(&self, origin: &str) -> bool {
+        self.paired.lock().unwrap().iter().any(|p| p.origin == origin)
+    }
+
+    /// Unpair an extension by ID or unique ID prefix
+    pub fn revoke(&self, id: &str) -> McpResult<PairedExtension> {
+        let mut paired = self.paired.lock().unwrap();
+        let matches: Vec<usize> = (0..paired.len())
+            .filter(|&i| paired[i].id == id || (!id.is_empty() && paired[i].id.starts_with(id)))
+            .collect();
+        let index = match matches.as_slice() {
+            [index] => *index,
+            [] => return Err(McpError::InvalidRequest(format!("No paired extension {}", id))),
+            _ => return Err(McpError::InvalidRequest(format!("More than one extension starts with {}", id))),
+        };
+        let extension = paired.remove(index);
+        self.save(&paired)?;
+        Ok(extension)
+    }
+}
+
+static COMPANION_STORE: Lazy<Arc<CompanionStore>> =
+    Lazy::new(|| Arc::new(CompanionStore::at(data_path("companion.json"))));
+
+/// Get the global store of paired extensions
+pub fn get_companion_store() -> Arc<CompanionStore> {
+    COMPANION_STORE.clone()
+}
+
+/// Requests allowed per key within a sliding window
+pub struct RateLimiter {
+    limit: usize,
+    window: ChronoDuration,
+    hits: Mutex<HashMap<String, VecDeque<DateTime<Utc>>>>,
+}
+
+impl RateLimiter {
+    /// Allow `limit` requests per key in any `window`
+    pub fn new(limit: usize, window: std::time::Duration) -> Self {
+        Self {
+            limit,
+            window: ChronoDuration::from_std(window).unwrap_or_else(|_| ChronoDuration::minutes(1)),
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request; when over the limit, returns how long until the
+    /// next one is allowed, rounded up to whole seconds for Retry-After
+    pub fn check(&self, key: &str) -> Result<(), std::time::Duration> {
+        let now = clock::now();
+        let mut hits = self.hits.lock().unwrap();
+        hits.retain(|_, times| times.back().is_some_and(|&t| now - t < self.window));
+        let times = hits.entry(key.to_string()).or_default();
+        while times.front().is_some_and(|&t| now - t >= self.window) {
+            times.pop_front();
+        }
+        if times.len() >= self.limit {
+            let wait = (times[0] + self.window - now).to_std().unwrap_or_default();
+            let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            return Err(std::time::Duration::from_secs(seconds));
+        }
+        times.push_back(now);
+        Ok(())
+    }
+}
+
+/// The page, or part of it, an extension sends
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageSelection {
+    /// Page URL
+    pub url: String,
+
+    /// Page title
+    #[serde(default)]
+    pub title: Option<String>,
+
+    /// Selected text, or the page's text when nothing is selected
+    pub text: String,
+}
+
+impl PageSelection {
+    fn validate(&self) -> McpResult<()> {
+        if self.text.trim().is_empty() {
+            return Err(McpError::InvalidRequest("The page has no text".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The page as a prompt: where it's from, then its text
+    pub fn to_prompt(&self) -> String {
+        let text = self.text.trim();
+        let text = if text.chars().count() > MAX_PAGE_CHARS {
+            format!("{}\n[…cut off]", text.chars().take(MAX_PAGE_CHARS).collect::<String>())
+        } else {
+            text.to_string()
+        };
+        match &self.title {
+            Some(title) => format!("Page: {}\nURL: {}\n\n{}", title, self.url, text),
+            None => format!("URL: {}\n\n{}", self.url, text),
+        }
+    }
+}
+
+/// Reply to an extension
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompanionReply {
+    /// Summary or answer
+    pub text: String,
+
+    /// Conversation created, for `open`
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+}
+
+/// What the extension can ask for
+pub struct Companion {
+    chat_service: Arc<ChatService>,
+}
+
+impl Companion {
+    /// Companion answering with a chat service
+    pub fn new(chat_service: Arc<ChatService>) -> Self {
+        Self { chat_service }
+    }
+
+    /// Summarize a page
+    pub async fn summarize(&self, page: &PageSelection) -> McpResult<CompanionReply> {
+        page.validate()?;
+        let text = self.ask(SUMMARY_SYSTEM_PROMPT, &page.to_prompt()).await?;
+        Ok(CompanionReply {
+            text,
+            conversation_id: None,
+        })
+    }
+
+    /// Answer a question about a page
+    pub async fn answer(&self, page: &PageSelection, question: &str) -> McpResult<CompanionReply> {
+        page.validate()?;
+        if question.trim().is_empty() {
+            return Err(McpError::InvalidRequest("The question is empty".to_string()));
+        }
+        let prompt = format!("{}\n\nQuestion: {}", page.to_prompt(), question.trim());
+        let text = self.ask(ANSWER_SYSTEM_PROMPT, &prompt).await?;
+        Ok(CompanionReply {
+            text,
+            conversation_id: None,
+        })
+    }
+
+    /// Start a conversation holding the page, answering a first question
+    /// when there is one
+    pub async fn open(&self, page: &PageSelection, question: Option<&str>) -> McpResult<CompanionReply> {
+        page.validate()?;
+        let title = page.title.clone().unwrap_or_else(|| page.url.clone());
+        let conversation = self.chat_service.create_conversation(&title, None).await?;
+
+        let mut context = Message::user(page.to_prompt());
+        context.metadata = Some(HashMap::from([(
+            "browser_page".to_string(),
+            serde_json::json!({ "url": page.url, "title": page.title }),
+        )]));
+        let mut stored = self.chat_service.get_conversation(&conversation.id).await?;
+        stored.add_message(context);
+        self.chat_service.update_conversation(stored).await?;
+
+        let text = match question.map(str::trim).filter(|q| !q.is_empty()) {
+            Some(question) => self.chat_service.send_message(&conversation.id, question).await?.text(),
+            None => String::new(),
+        };
+        Ok(CompanionReply {
+            text,
+            conversation_id: Some(conversation.id),
+        })
+    }
+
+    /// One request in a throwaway conversation
+    async fn ask(&self, system: &str, prompt: &str) -> McpResult<String> {
+        let conversation = self.chat_service.create_conversation("Browser page", None).await?;
+        let result = async {
+            self.chat_service.set_system_message(&conversation.id, system).await?;
+            self.chat_service.send_message(&conversation.id, prompt).await
+        }
+        .await;
+
+        // Pages read in passing shouldn't fill the conversation list
+        let cleanup = match self.chat_service.delete_conversation(&conversation.id).await {
+            Ok(()) => self.chat_service.purge_conversation(&conversation.id).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = cleanup {
+            debug!("Failed to delete the page conversation: {}", e);
+        }
+
+        Ok(result?.text())
+    }
+}
@@ -0,0 +1,401 @@
+//! Incoming messages
+//!
+//! A route ties a name and a secret token to a conversation. Automations
+//! POST text, JSON or a forwarded email to the route, the message is added
+//! to the conversation as if the user typed it, and when the route asks for
+//! it the reply is generated and returned.
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use log::info;
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use regex::Regex;
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::service::chat::ChatService;
+use crate::utils::clock;
+
+/// Largest request body accepted
+pub const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Longest route name
+const MAX_NAME_CHARS: usize = 64;
+
+static HTML_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+
+/// Where messages sent to a name go
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboundRoute {
+    /// Name in the URL, e.g. `/inbound/alerts`
+    pub name: String,
+
+    /// Bearer token requests must carry
+    pub token: String,
+
+    /// Conversation the messages are added to
+    pub conversation_id: String,
+
+    /// Generate a reply to each message and return it
+    pub reply: bool,
+
+    /// When the route was created
+    pub created_at: DateTime<Utc>,
+}
+
+/// A message received by a route
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InboundMessage {
+    /// Message text
+    pub text: String,
+
+    /// Sender, for forwarded emails
+    #[serde(default)]
+    pub from: Option<String>,
+
+    /// Subject, for forwarded emails
+    #[serde(default)]
+    pub subject: Option<String>,
+}
+
+impl InboundMessage {
+    /// Text added to the conversation, with the sender and subject on top
+    pub fn to_prompt(&self) -> String {
+        let mut prompt = String::new();
+        if let Some(from) = &self.from {
+            prompt.push_str(&format!("From: {}\n", from));
+        }
+        if let Some(subject) = &self.subject {
+            prompt.push_str(&format!("Subject: {}\n", subject));
+        }
+        if !prompt.is_empty() {
+            prompt.push('\n');
+        }
+        prompt.push_str(self.text.trim());
+        prompt
+    }
+}
+
+/// What happened to a delivered message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboundReceipt {
+    /// Conversation the message was added to
+    pub conversation_id: String,
+
+    /// Last message stored: the reply, or the message itself without one
+    pub message_id: String,
+
+    /// Generated reply, when the route asks for one
+    pub reply: Option<String>,
+}
+
+/// Read a request body by its content type: JSON (`{"text", "from",
+/// "subject"}`), a forwarded email (`message/rfc822`) or plain text
+pub fn parse_body(content_type: &str, body: &[u8]) -> McpResult<InboundMessage> {
+    if body.len() > MAX_BODY_BYTES {
+        return Err(McpError::InvalidRequest("Message is too large".to_string()));
+    }
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+    let text = String::from_utf8_lossy(body);
+    let message = match mime.as_str() {
+        "application/json" => serde_json::from_str::<InboundMessage>(&text)
+            .map_err(|e| McpError::InvalidRequest(format!("Invalid message: {}", e)))?,
+        "message/rfc822" => parse_email(&text)?,
+        "text/plain" | "" => InboundMessage {
+            text: text.into_owned(),
+            ..Default::default()
+        },
+        other => return Err(McpError::InvalidRequest(format!("Unsupported content type {}", other))),
+    };
+    if message.text.trim().is_empty() {
+        return Err(McpError::InvalidRequest("Message has no text".to_string()));
+    }
+    Ok(message)
+}
+
+/// Headers of a message or MIME part, names lowercased, folded lines joined
+fn parse_headers(head: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in head.lines() {
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+}
+
+/// A parameter of a header value, e.g. `boundary` of a content type
+fn header_param(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        (key.trim().eq_ignore_ascii_case(name)).then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+fn decode_quoted_printable(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'=' {
+            // Soft line break
+            if bytes.get(i + 1) == Some(&b'\n') {
+                i += 2;
+                continue;
+            }
+            if let Some(byte) = text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Body of a part, undoing its transfer encoding
+fn decode_body(headers: &[(String, String)], body: &str) -> String {
+    let encoding = header(headers, "content-transfer-encoding").unwrap_or("").to_lowercase();
+    match encoding.as_str() {
+        "quoted-printable" => String::from_utf8_lossy(&decode_quoted_printable(body)).into_owned(),
+        "base64" => {
+            let compact: String = body.split_whitespace().collect();
+            match base64::engine::general_purpose::STANDARD.decode(compact) {
+                Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Err(_) => body.to_string(),
+            }
+        }
+        _ => body.to_string(),
+    }
+}
+
+/// Text of a part: the first plain-text part of a multipart message, or
+/// an HTML part with the tags removed when there is no plain one
+fn extract_text(headers: &[(String, String)], body: &str) -> Option<String> {
+    let content_type = header(headers, "content-type").unwrap_or("text/plain");
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+
+    if mime.starts_with("multipart/") {
+        let boundary = header_param(content_type, "boundary")?;
+        let delimiter = format!("--{}", boundary);
+        let parts: Vec<(Vec<(String, String)>, &str)> = body
+            .split(delimiter.as_str())
+            .skip(1)
+            .take_while(|part| !part.starts_with("--"))
+            .map(|part| {
+                let part = part.strip_prefix('\n').unwrap_or(part);
+                let (head, body) = part.split_once("\n\n").unwrap_or(("", part));
+                (parse_headers(head), body)
+            })
+            .collect();
+
+        let is_html = |headers: &[(String, String)]| {
+            header(headers, "content-type").is_some_and(|t| t.to_lowercase().starts_with("text/html"))
+        };
+        return parts
+            .iter()
+            .filter(|(headers, _)| !is_html(headers))
+            .chain(parts.iter().filter(|(headers, _)| is_html(headers)))
+            .find_map(|(headers, body)| extract_text(headers, body).filter(|text| !text.trim().is_empty()));
+    }
+
+    match mime.as_str() {
+        "text/plain" => Some(decode_body(headers, body)),
+        "text/html" => Some(HTML_TAG.replace_all(&decode_body(headers, body), "").into_owned()),
+        _ => None,
+    }
+}
+
+/// Read a forwarded email: sender, subject and the plain-text body, without
+/// the signature
+pub fn parse_email(raw: &str) -> McpResult<InboundMessage> {
+    let raw = raw.replace("\r\n", "\n");
+    let (head, body) = raw
+        .split_once("\n\n")
+        .ok_or_else(|| McpError::InvalidRequest("Email has no body".to_string()))?;
+    let headers = parse_headers(head);
+
+    let text = extract_text(&headers, body)
+        .ok_or_else(|| McpError::InvalidRequest("Email has no text part".to_string()))?;
+    let text = match text.find("\n-- \n") {
+        Some(signature) => text[..signature].to_string(),
+        None => text,
+    };
+
+    Ok(InboundMessage {
+        text: text.trim().to_string(),
+        from: header(&headers, "from").map(str::to_string),
+        subject: header(&headers, "subject").map(str::to_string),
+    })
+}
+
+fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("inb_{}", bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+/// Compare tokens by their digests, so the time taken says nothing about them
+fn same_token(a: &str, b: &str) -> bool {
+    digest::digest(&digest::SHA256, a.as_bytes()).as_ref() == digest::digest(&digest::SHA256, b.as_bytes()).as_ref()
+}
+
+/// Routes for incoming messages
+pub struct InboundStore {
+    path: PathBuf,
+    routes: Mutex<Vec<InboundRoute>>,
+}
+
+impl InboundStore {
+    /// Store kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        let routes = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            routes: Mutex::new(routes),
+        }
+    }
+
+    fn save(&self, routes: &[InboundRoute]) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(routes)?)?;
+        Ok(())
+    }
+
+    /// Routes by name
+    pub fn list(&self) -> Vec<InboundRoute> {
+        self.routes.lock().unwrap().clone()
+    }
+
+    /// Create a route to a conversation; the returned route carries its token
+    pub fn add(&self, name: &str, conversation_id: &str, reply: bool) -> McpResult<InboundRoute> {
+        let valid = !name.is_empty()
+            && name.chars().count() <= MAX_NAME_CHARS
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(McpError::InvalidRequest(
+                "Route names use letters, digits, '-' and '_'".to_string(),
+            ));
+        }
+
+        let mut routes = self.routes.lock().unwrap();
+        if routes.iter().any(|r| r.name == name) {
+            return Err(McpError::InvalidRequest(format!("Route {} already exists", name)));
+        }
+        let route = InboundRoute {
+            name: name.to_string(),
+            token: new_token(),
+            conversation_id: conversation_id.to_string(),
+            reply,
+            created_at: clock::now(),
+        };
+        routes.push(route.clone());
+        self.save(&routes)?;
+        info!("Created inbound route {} to conversation {}", name, conversation_id);
+        Ok(route)
+    }
+
+    /// Remove a route
+    pub fn remove(&self, name: &str) -> McpResult<()> {
+        let mut routes = self.routes.lock().unwrap();
+        let before = routes.len();
+        routes.retain(|r| r.name != name);
+        if routes.len() == before {
+            return Err(McpError::InvalidRequest(format!("No route {}", name)));
+        }
+        self.save(&routes)
+    }
+
+    /// Replace a route's token
+    pub fn rotate_token(&self, name: &str) -> McpResult<InboundRoute> {
+        let mut routes = self.routes.lock().unwrap();
+        let route = routes
+            .iter_mut()
+            .find(|r| r.name == name)
+            .ok_or_else(|| McpError::InvalidRequest(format!("No route {}", name)))?;
+        route.token = new_token();
+        let route = route.clone();
+        self.save(&routes)?;
+        Ok(route)
+    }
+
+    /// The route, if the token is its own
+    pub fn authenticate(&self, name: &str, token: &str) -> Option<InboundRoute> {
+        self.routes
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|r| r.name == name && same_token(&r.token, token))
+            .cloned()
+    }
+}
+
+static INBOUND_STORE: Lazy<Arc<InboundStore>> = Lazy::new(|| Arc::new(InboundStore::at(data_path("inbound.json"))));
+
+/// Get the global inbound route store
+pub fn get_inbound_store() -> Arc<InboundStore> {
+    INBOUND_STORE.clone()
+}
+
+/// Adds incoming messages to their conversations
+pub struct InboundGateway {
+    store: Arc<InboundStore>,
+    chat_service: Arc<ChatService>,
+}
+
+impl InboundGateway {
+    /// Create a gateway over a route store
+    pub fn new(store: Arc<InboundStore>, chat_service: Arc<ChatService>) -> Self {
+        Self { store, chat_service }
+    }
+
+    /// Add a message to a route's conversation, generating the reply when
+    /// the route asks for one. Fails with an authentication error for an
+    /// unknown route or a wrong token.
+    pub async fn deliver(&self, route: &str, token: &str, message: &InboundMessage) -> McpResult<InboundReceipt> {
+        let route = self
+            .store
+            .authenticate(route, token)
+            .ok_or_else(|| McpError::Authentication("Unknown route or wrong token".to_string()))?;
+        let prompt = message.to_prompt();
+
+        if route.reply {
+            let reply = self.chat_service.send_message(&route.conversation_id, &prompt).await?;
+            Ok(InboundReceipt {
+                conversation_id: route.conversation_id,
+                message_id: reply.id.clone(),
+                reply: Some(reply.text()),
+            })
+        } else {
+            let message = self.chat_service.append_message(&route.conversation_id, &prompt).await?;
+            Ok(InboundReceipt {
+                conversation_id: route.conversation_id,
+                message_id: message.id,
+                reply: None,
+            })
+        }
+    }
+}
@@ -0,0 +1,628 @@
+//! Outgoing webhooks
+//!
+//! Users register URLs to be called when something happens: a reply
+//! completed, a conversation was created, the budget ran out. Each call is a
+//! JSON payload signed with the webhook's secret, so receivers can check it
+//! came from this app. Calls that fail wait in a persisted queue and are
+//! retried with growing delays. Messages coming the other way go through
+//! [`inbound`], and pages from a browser extension through [`companion`].
+
+pub mod companion;
+pub mod inbound;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::auth::secret_store;
+use crate::config::data_path;
+use crate::error::{McpError, McpResult};
+use crate::events::{get_event_bus, names, Backpressure, Topic};
+use crate::utils::clock;
+
+/// Header carrying `sha256=<hex>`, the HMAC-SHA256 of `<timestamp>.<body>`
+pub const SIGNATURE_HEADER: &str = "X-Papin-Signature";
+
+/// Header carrying the Unix time the payload was signed at
+pub const TIMESTAMP_HEADER: &str = "X-Papin-Timestamp";
+
+/// Header carrying the event name
+pub const EVENT_HEADER: &str = "X-Papin-Event";
+
+/// Header carrying the delivery ID, the same across retries
+pub const DELIVERY_HEADER: &str = "X-Papin-Delivery";
+
+/// Event name of test deliveries
+pub const PING_EVENT: &str = "ping";
+
+/// Delays before each retry; a delivery is dropped after the last
+const RETRY_DELAYS_SECS: [i64; 6] = [30, 120, 600, 1800, 3600, 6 * 3600];
+
+/// Most deliveries kept waiting; the oldest are dropped beyond this
+const MAX_QUEUED: usize = 1000;
+
+/// How long a receiver has to answer
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Events a webhook can be called on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// A reply finished arriving
+    MessageCompleted,
+    /// A conversation was created
+    ConversationCreated,
+    /// The tracked budget ran out
+    BudgetExceeded,
+}
+
+impl WebhookEvent {
+    /// All events
+    pub fn all() -> Vec<WebhookEvent> {
+        vec![
+            WebhookEvent::MessageCompleted,
+            WebhookEvent::ConversationCreated,
+            WebhookEvent::BudgetExceeded,
+        ]
+    }
+
+    /// Name used in payloads and the event header
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::MessageCompleted => "message_completed",
+            WebhookEvent::ConversationCreated => "conversation_created",
+            WebhookEvent::BudgetExceeded => "budget_exceeded",
+        }
+    }
+
+    /// Webhook event for an event published on the bus
+    pub fn from_bus(name: &str) -> Option<WebhookEvent> {
+        match name {
+            names::MESSAGE_RECEIVED => Some(WebhookEvent::MessageCompleted),
+            names::CONVERSATION_CREATED => Some(WebhookEvent::ConversationCreated),
+            names::BUDGET_EXCEEDED => Some(WebhookEvent::BudgetExceeded),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for WebhookEvent {
+    type Err = McpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase().replace('-', "_");
+        WebhookEvent::all()
+            .into_iter()
+            .find(|event| event.as_str() == name)
+            .ok_or_else(|| {
+                let known: Vec<_> = WebhookEvent::all().iter().map(|e| e.as_str()).collect();
+                McpError::InvalidRequest(format!("Unknown webhook event '{}'; expected one of {}", s, known.join(", ")))
+            })
+    }
+}
+
+/// A registered webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    /// Webhook ID
+    pub id: String,
+
+    /// URL called with a POST for each event
+    pub url: String,
+
+    /// Secret the payloads are signed with. It lives in the secret store,
+    /// so it is only filled in when the webhook is added or rotated.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub secret: String,
+
+    /// Events the webhook is called on; all of them when empty
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+
+    /// What the webhook is for
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Whether events are delivered
+    pub enabled: bool,
+
+    /// When the webhook was registered
+    pub created_at: DateTime<Utc>,
+
+    /// When a delivery last succeeded
+    #[serde(default)]
+    pub last_success_at: Option<DateTime<Utc>>,
+
+    /// Error of the last failed delivery, cleared by a successful one
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl Webhook {
+    /// Whether the webhook is called on an event
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        self.enabled && (self.events.is_empty() || self.events.contains(&event))
+    }
+}
+
+/// A call waiting to be made or retried
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    /// Delivery ID, sent in the delivery header
+    pub id: String,
+
+    /// Webhook to call
+    pub webhook_id: String,
+
+    /// Event being delivered
+    pub event: WebhookEvent,
+
+    /// JSON body
+    pub payload: serde_json::Value,
+
+    /// Failed attempts so far
+    pub attempts: u32,
+
+    /// When to try next
+    pub next_attempt_at: DateTime<Utc>,
+
+    /// Error of the last attempt
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+/// What a delivery run did
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeliveryReport {
+    /// Deliveries that succeeded
+    pub delivered: usize,
+
+    /// Deliveries that failed and will be retried
+    pub retrying: usize,
+
+    /// Deliveries that failed for the last time, or whose webhook was
+    /// removed or paused, and were dropped
+    pub dropped: usize,
+}
+
+/// Signature of a payload: `sha256=` and the hex HMAC-SHA256 of
+/// `<timestamp>.<body>` keyed with the secret
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let mut context = hmac::Context::with_key(&key);
+    context.update(timestamp.to_string().as_bytes());
+    context.update(b".");
+    context.update(body);
+    format!("sha256={}", to_hex(context.sign().as_ref()))
+}
+
+/// Check a signature made by [`sign`], in constant time
+pub fn verify(secret: &str, timestamp: i64, body: &[u8], signature: &str) -> bool {
+    let Some(tag) = signature.strip_prefix("sha256=").and_then(from_hex) else {
+        return false;
+    };
+    let mut message = format!("{}.", timestamp).into_bytes();
+    message.extend_from_slice(body);
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::verify(&key, &message, &tag).is_ok()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Only HTTPS, except to this machine where plain HTTP is fine
+fn validate_url(url: &str) -> McpResult<()> {
+    let parsed = url::Url::parse(url).map_err(|e| McpError::InvalidRequest(format!("Invalid webhook URL: {}", e)))?;
+    let loopback = matches!(parsed.host_str(), Some("localhost") | Some("127.0.0.1") | Some("[::1]"));
+    match parsed.scheme() {
+        "https" => Ok(()),
+        "http" if loopback => Ok(()),
+        _ => Err(McpError::InvalidRequest(
+            "Webhook URLs must use https, or http to localhost".to_string(),
+        )),
+    }
+}
+
+fn new_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("whsec_{}", to_hex(&bytes))
+}
+
+/// Secret store key of a webhook's signing secret
+fn secret_key(webhook_id: &str) -> String {
+    format!("webhook_{}", webhook_id)
+}
+
+/// Persisted webhooks and the delivery queue
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct WebhookData {
+    webhooks: Vec<Webhook>,
+    queue: Vec<Delivery>,
+}
+
+/// Registered webhooks and deliveries waiting to be retried
+pub struct WebhookStore {
+    path: PathBuf,
+    data: Mutex<WebhookData>,
+    client: reqwest::Client,
+}
+
+impl WebhookStore {
+    /// Store kept in a file
+    pub fn at(path: PathBuf) -> Self {
+        let mut data: WebhookData = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        // Secrets written by earlier versions move to the secret store
+        let mut migrated = false;
+        for webhook in data.webhooks.iter_mut().filter(|w| !w.secret.is_empty()) {
+            match secret_store().set(&secret_key(&webhook.id), &webhook.secret) {
+                Ok(()) => {
+                    webhook.secret.clear();
+                    migrated = true;
+                }
+                Err(e) => warn!("Failed to move the secret of webhook {} to the secret store: {}", webhook.id, e),
+            }
+        }
+
+        let store = Self {
+            path,
+            data: Mutex::new(data),
+            client: reqwest::Client::new(),
+        };
+        if migrated {
+            if let Err(e) = store.save(&store.data.lock().unwrap()) {
+                warn!("Failed to save webhooks after moving their secrets: {}", e);
+            }
+        }
+        store
+    }
+
+    fn save(&self, data: &WebhookData) -> McpResult<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(data)?)?;
+        Ok(())
+    }
+
+    /// Index of the webhook with an ID, or the only one starting with it
+    fn find(data: &WebhookData, id: &str) -> McpResult<usize> {
+        if let Some(index) = data.webhooks.iter().position(|w| w.id == id) {
+            return Ok(index);
+        }
+        let matches: Vec<_> = (0..data.webhooks.len())
+            .filter(|&i| !id.is_empty() && data.webhooks[i].id.starts_with(id))
+            .collect();
+        match matches.as_slice() {
+            [index] => Ok(*index),
+            [] => Err(McpError::InvalidRequest(format!("No webhook {}", id))),
+            _ => Err(McpError::InvalidRequest(format!("More than one webhook starts with {}", id))),
+        }
+    }
+
+    /// Registered webhooks
+    pub fn list(&self) -> Vec<Webhook> {
+        self.data.lock().unwrap().webhooks.clone()
+    }
+
+    /// A webhook by ID or unique ID prefix
+    pub fn get(&self, id: &str) -> McpResult<Webhook> {
+        let data = self.data.lock().unwrap();
+        Ok(data.webhooks[Self::find(&data, id)?].clone())
+    }
+
+    /// Register a URL for events, all of them when `events` is empty. The
+    /// returned webhook carries the new signing secret.
+    pub fn add(&self, url: &str, events: Vec<WebhookEvent>, description: Option<String>) -> McpResult<Webhook> {
+        validate_url(url)?;
+        let mut webhook = Webhook {
+            id: uuid::Uuid::new_v4().to_string(),
+            url: url.to_string(),
+            secret: String::new(),
+            events,
+            description,
+            enabled: true,
+            created_at: clock::now(),
+            last_success_at: None,
+            last_error: None,
+        };
+
+        let secret = new_secret();
+        secret_store().set(&secret_key(&webhook.id), &secret)?;
+        let mut data = self.data.lock().unwrap();
+        data.webhooks.push(webhook.clone());
+        self.save(&data)?;
+        info!("Registered webhook {} for {}", webhook.id, webhook.url);
+        webhook.secret = secret;
+        Ok(webhook)
+    }
+
+    /// Remove a webhook and its waiting deliveries
+    pub fn remove(&self, id: &str) -> McpResult<()> {
+        let mut data = self.data.lock().unwrap();
+        let index = Self::find(&data, id)?;
+        let webhook = data.webhooks.remove(index);
+        data.queue.retain(|d| d.webhook_id != webhook.id);
+        self.save(&data)?;
+        if let Err(e) = secret_store().delete(&secret_key(&webhook.id)) {
+            warn!("Failed to remove the secret of webhook {}: {}", webhook.id, e);
+        }
+        Ok(())
+    }
+
+    /// Pause or resume a webhook; pausing drops its waiting deliveries
+    pub fn set_enabled(&self, id: &str, enabled: bool) -> McpResult<Webhook> {
+        let mut data = self.data.lock().unwrap();
+        let index = Self::find(&data, id)?;
+        data.webhooks[index].enabled = enabled;
+        let webhook = data.webhooks[index].clone();
+        if !enabled {
+            data.queue.retain(|d| d.webhook_id != webhook.id);
+        }
+        self.save(&data)?;
+        Ok(webhook)
+    }
+
+    /// Change the events a webhook is called on; all of them when empty
+    pub fn set_events(&self, id: &str, events: Vec<WebhookEvent>) -> McpResult<Webhook> {
+        self.update(id, |webhook| webhook.events = events)
+    }
+
+    /// Replace a webhook's signing secret; the returned webhook carries it
+    pub fn rotate_secret(&self, id: &str) -> McpResult<Webhook> {
+        let mut webhook = self.get(id)?;
+        let secret = new_secret();
+        secret_store().set(&secret_key(&webhook.id), &secret)?;
+        webhook.secret = secret;
+        Ok(webhook)
+    }
+
+    /// A webhook's signing secret, from the secret store
+    fn secret(webhook: &Webhook) -> McpResult<String> {
+        secret_store().get(&secret_key(&webhook.id))?.ok_or_else(|| {
+            McpError::Config(format!("Webhook {} has no signing secret; rotate it to make one", webhook.id))
+        })
+    }
+
+    fn update(&self, id: &str, change: impl FnOnce(&mut Webhook)) -> McpResult<Webhook> {
+        let mut data = self.data.lock().unwrap();
+        let index = Self::find(&data, id)?;
+        change(&mut data.webhooks[index]);
+        let webhook = data.webhooks[index].clone();
+        self.save(&data)?;
+        Ok(webhook)
+    }
+
+    /// Deliveries waiting to be retried
+    pub fn queue(&self) -> Vec<Delivery> {
+        self.data.lock().unwrap().queue.clone()
+    }
+
+    /// Queue an event for every webhook that wants it; returns how many
+    pub fn enqueue(&self, event: WebhookEvent, data: serde_json::Value) -> McpResult<usize> {
+        let now = clock::now();
+        let mut store = self.data.lock().unwrap();
+        let targets: Vec<String> = store
+            .webhooks
+            .iter()
+            .filter(|w| w.wants(event))
+            .map(|w| w.id.clone())
+            .collect();
+        if targets.is_empty() {
+            return Ok(0);
+        }
+
+        for webhook_id in &targets {
+            let id = uuid::Uuid::new_v4().to_string();
+            store.queue.push(Delivery {
+                payload: serde_json::json!({
+                    "id": id,
+                    "event": event.as_str(),
+                    "created_at": now,
+                    "data": data,
+                }),
+                id,
+                webhook_id: webhook_id.clone(),
+                event,
+                attempts: 0,
+                next_attempt_at: now,
+                last_error: None,
+            });
+        }
+        if store.queue.len() > MAX_QUEUED {
+            let excess = store.queue.len() - MAX_QUEUED;
+            warn!("Webhook queue is full, dropping {} old deliveries", excess);
+            store.queue.drain(..excess);
+        }
+        self.save(&store)?;
+        Ok(targets.len())
+    }
+
+    /// POST a signed payload
+    async fn send(&self, webhook: &Webhook, event: &str, delivery_id: &str, payload: &serde_json::Value) -> McpResult<()> {
+        let secret = Self::secret(webhook)?;
+        let body = serde_json::to_vec(payload)?;
+        let timestamp = clock::now().timestamp();
+        let response = self
+            .client
+            .post(&webhook.url)
+            .timeout(REQUEST_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event)
+            .header(DELIVERY_HEADER, delivery_id)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, sign(&secret, timestamp, &body))
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| McpError::Connection(e.to_string()))?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(McpError::Connection(format!("{} answered {}", webhook.url, status)))
+        }
+    }
+
+    /// Make every delivery that is due, rescheduling the ones that fail.
+    /// Deliveries for webhooks that are gone or paused are dropped.
+    pub async fn deliver_due(&self) -> McpResult<DeliveryReport> {
+        let now = clock::now();
+        let mut report = DeliveryReport::default();
+        let due: Vec<(Delivery, Webhook)> = {
+            let mut data = self.data.lock().unwrap();
+            let WebhookData { webhooks, queue } = &mut *data;
+            let before = queue.len();
+            queue.retain(|d| webhooks.iter().any(|w| w.id == d.webhook_id && w.enabled));
+            report.dropped = before - queue.len();
+            if report.dropped > 0 {
+                warn!("Dropped {} webhook deliveries for removed or paused webhooks", report.dropped);
+                self.save(&data)?;
+            }
+
+            data.queue
+                .iter()
+                .filter(|d| d.next_attempt_at <= now)
+                .filter_map(|d| {
+                    let webhook = data.webhooks.iter().find(|w| w.id == d.webhook_id)?;
+                    Some((d.clone(), webhook.clone()))
+                })
+                .collect()
+        };
+        if due.is_empty() {
+            return Ok(report);
+        }
+
+        // Send without holding the lock, then record the outcomes
+        let mut outcomes = Vec::with_capacity(due.len());
+        for (delivery, webhook) in &due {
+            let result = self.send(webhook, delivery.event.as_str(), &delivery.id, &delivery.payload).await;
+            outcomes.push((delivery.id.clone(), webhook.id.clone(), result));
+        }
+
+        let now = clock::now();
+        let mut data = self.data.lock().unwrap();
+        for (delivery_id, webhook_id, result) in outcomes {
+            let error = result.err().map(|e| e.to_string());
+            if let Some(webhook) = data.webhooks.iter_mut().find(|w| w.id == webhook_id) {
+                match &error {
+                    None => {
+                        webhook.last_success_at = Some(now);
+                        webhook.last_error = None;
+                    }
+                    Some(error) => webhook.last_error = Some(error.clone()),
+                }
+            }
+
+            let Some(index) = data.queue.iter().position(|d| d.id == delivery_id) else {
+                continue;
+            };
+            let Some(error) = error else {
+                data.queue.remove(index);
+                report.delivered += 1;
+                continue;
+            };
+
+            let delivery = &mut data.queue[index];
+            delivery.attempts += 1;
+            delivery.last_error = Some(error.clone());
+            match RETRY_DELAYS_SECS.get(delivery.attempts as usize - 1) {
+                Some(delay) => {
+                    delivery.next_attempt_at = now + ChronoDuration::seconds(*delay);
+                    debug!("Webhook delivery {} failed, retrying in {}s: {}", delivery_id, delay, error);
+                    report.retrying += 1;
+                }
+                None => {
+                    warn!("Giving up on webhook delivery {} after {} attempts: {}", delivery_id, delivery.attempts, error);
+                    data.queue.remove(index);
+                    report.dropped += 1;
+                }
+            }
+        }
+        self.save(&data)?;
+        Ok(report)
+    }
+
+    /// Send a `ping` right away, outside the queue, to check a webhook works
+    pub async fn test(&self, id: &str) -> McpResult<()> {
+        let webhook = self.get(id)?;
+        let delivery_id = uuid::Uuid::new_v4().to_string();
+        let payload = serde_json::json!({
+            "id": delivery_id,
+            "event": PING_EVENT,
+            "created_at": clock::now(),
+            "data": { "webhook_id": webhook.id },
+        });
+        let result = self.send(&webhook, PING_EVENT, &delivery_id, &payload).await;
+
+        let now = clock::now();
+        self.update(&webhook.id, |webhook| match &result {
+            Ok(()) => {
+                webhook.last_success_at = Some(now);
+                webhook.last_error = None;
+            }
+            Err(e) => webhook.last_error = Some(e.to_string()),
+        })?;
+        result
+    }
+}
+
+static WEBHOOK_STORE: Lazy<Arc<WebhookStore>> = Lazy::new(|| Arc::new(WebhookStore::at(data_path("webhooks.json"))));
+
+/// Get the global webhook store
+pub fn get_webhook_store() -> Arc<WebhookStore> {
+    WEBHOOK_STORE.clone()
+}
+
+/// Queue webhook deliveries for events on the bus and send them, retrying
+/// failed ones every `interval`
+pub fn spawn_webhook_dispatcher(interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let store = get_webhook_store();
+        let mut events = get_event_bus().subscribe(&[Topic::Conversation, Topic::System], 256, Backpressure::DropNewest);
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                event = events.recv() => {
+                    let Some(event) = event else { break };
+                    let Some(kind) = WebhookEvent::from_bus(&event.name) else { continue };
+                    match store.enqueue(kind, event.payload) {
+                        Ok(0) => continue,
+                        Ok(_) => {}
+                        Err(e) => {
+                            warn!("Failed to queue webhook deliveries: {}", e);
+                            continue;
+                        }
+                    }
+                }
+                _ = ticker.tick() => {}
+            }
+            if let Err(e) = store.deliver_due().await {
+                warn!("Failed to record webhook deliveries: {}", e);
+            }
+        }
+    })
+}
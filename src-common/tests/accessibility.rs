@@ -0,0 +1,35 @@
+//! Accessibility settings: motion is off whenever reduced motion or screen
+//! reader mode is on, and settings files without them still load.
+
+use mcp_common::config::{AccessibilitySettings, Settings};
+
+#[test]
+fn reduced_motion_and_screen_readers_stop_animations() {
+    let mut settings = Settings::default();
+    assert!(settings.accessibility.allows_motion(&settings.ui));
+
+    for (screen_reader, reduced_motion) in [(true, false), (false, true), (true, true)] {
+        let accessibility = AccessibilitySettings {
+            screen_reader,
+            reduced_motion,
+            large_text: false,
+        };
+        assert!(!accessibility.allows_motion(&settings.ui));
+    }
+
+    settings.ui.animations = false;
+    assert!(!AccessibilitySettings::default().allows_motion(&settings.ui));
+}
+
+#[test]
+fn settings_without_accessibility_load_with_defaults() {
+    let mut value = serde_json::to_value(Settings::default()).unwrap();
+    value.as_object_mut().unwrap().remove("accessibility");
+    let settings: Settings = serde_json::from_value(value).unwrap();
+    assert!(!settings.accessibility.screen_reader);
+    assert!(!settings.accessibility.reduced_motion);
+    assert!(!settings.accessibility.large_text);
+
+    let partial: AccessibilitySettings = serde_json::from_str(r#"{ "large_text": true }"#).unwrap();
+    assert!(partial.large_text && !partial.screen_reader);
+}
@@ -0,0 +1,290 @@
+//! Agent runs on the test harness: plans, tool calls and their results,
+//! budgets, controls, the transcript kept with the last reply, teams of
+//! agents handing tasks to each other, and replays of kept runs.
+
+use async_trait::async_trait;
+use mcp_common::agent::replay::{get_agent_run_store, FrameChange, FrameInput, ReplayOverrides, Rerun, RunDiff};
+use mcp_common::agent::team::{AgentProfile, AgentTeamStore, ExecutionGraph, TeamRunner};
+use mcp_common::agent::{
+    get_agent_runs, AgentBudget, AgentRunner, AgentStatus, AgentTranscript, StepKind, ToolExecutor, HANDOFF_TOOL,
+};
+use mcp_common::models::{MessageRole, Tool, ToolCall, ToolResult};
+use mcp_common::testing::TestHarness;
+use std::sync::Mutex;
+
+/// Adds numbers; pauses, resumes and then aborts the run on its call number
+/// `abort_on`, if set
+#[derive(Default)]
+struct Adder {
+    calls: Mutex<Vec<String>>,
+    abort_on: Option<usize>,
+}
+
+#[async_trait]
+impl ToolExecutor for Adder {
+    fn tools(&self) -> Vec<Tool> {
+        vec![Tool::simple_function("add", "Add two numbers")]
+    }
+
+    async fn execute(&self, conversation_id: &str, call: &ToolCall) -> Option<ToolResult> {
+        if call.name != "add" {
+            return None;
+        }
+        let mut calls = self.calls.lock().unwrap();
+        calls.push(call.id.clone());
+        if self.abort_on == Some(calls.len()) {
+            let control = get_agent_runs().get(conversation_id).unwrap();
+            control.pause();
+            assert!(control.is_paused());
+            control.resume();
+            assert!(!control.is_paused());
+            control.abort();
+        }
+        let sum = call.arguments["a"].as_i64()? + call.arguments["b"].as_i64()?;
+        Some(ToolResult::new(
+            call.id.clone(),
+            call.name.clone(),
+            serde_json::json!(sum),
+        ))
+    }
+}
+
+fn add(id: &str, a: i64, b: i64) -> ToolCall {
+    ToolCall::new(id, "add", serde_json::json!({ "a": a, "b": b }))
+}
+
+#[tokio::test]
+async fn runs_think_call_tools_and_observe_until_the_model_is_done() {
+    let h = TestHarness::new();
+    h.provider
+        .call_tools("1. Add 2 and 3\n2. Report the sum", vec![add("call-1", 2, 3)])
+        .call_tools(
+            "Checking a tool that isn't there",
+            vec![ToolCall::new("call-2", "search", serde_json::json!({}))],
+        )
+        .reply("The sum is 5");
+    let conversation = h.chat.create_conversation("Agent", None).await.unwrap();
+    let tools = Adder::default();
+
+    let transcript = AgentRunner::new(AgentBudget::default())
+        .run(&h.chat, &conversation.id, "Add 2 and 3", &tools)
+        .await
+        .unwrap();
+
+    assert_eq!(transcript.status, AgentStatus::Finished);
+    assert_eq!(transcript.plan, vec!["Add 2 and 3", "Report the sum"]);
+    assert_eq!(transcript.replies(), 3);
+    let kinds: Vec<&str> = transcript
+        .steps
+        .iter()
+        .map(|s| match &s.kind {
+            StepKind::Think { .. } => "think",
+            StepKind::ToolCall { .. } => "call",
+            StepKind::Observe { .. } => "observe",
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec!["think", "call", "observe", "think", "call", "observe", "think"]
+    );
+    match &transcript.steps[2].kind {
+        StepKind::Observe { result } => assert_eq!(result.result, 5),
+        other => panic!("Expected an observation, got {:?}", other),
+    }
+    match &transcript.steps[5].kind {
+        StepKind::Observe { result } => assert!(result.result["error"].as_str().unwrap().contains("search")),
+        other => panic!("Expected an observation, got {:?}", other),
+    }
+
+    // The model was asked for a plan and saw the results of its calls
+    let requests = h.provider.requests();
+    assert!(requests[0].last().unwrap().text().contains("numbered plan"));
+    assert_eq!(requests[1].last().unwrap().role, MessageRole::Tool);
+
+    // The transcript is kept with the last reply, and the run is over
+    let stored = h.storage.load_conversation(&conversation.id).unwrap();
+    let kept = AgentTranscript::latest(&stored).unwrap();
+    assert_eq!(stored.messages.last().unwrap().text(), "The sum is 5");
+    assert_eq!(kept.steps.len(), 7);
+    assert!(get_agent_runs().get(&conversation.id).is_none());
+}
+
+#[tokio::test]
+async fn runs_stop_when_their_budget_runs_out_or_they_are_aborted() {
+    let h = TestHarness::new();
+    for n in 0..2 {
+        h.provider.call_tools("Adding", vec![add(&format!("call-{}", n), n, 1)]);
+    }
+    let conversation = h.chat.create_conversation("Budget", None).await.unwrap();
+    let budget = AgentBudget {
+        max_steps: 2,
+        ..AgentBudget::default()
+    };
+
+    let transcript = AgentRunner::new(budget)
+        .run(&h.chat, &conversation.id, "Keep adding", &Adder::default())
+        .await
+        .unwrap();
+    assert_eq!(transcript.status, AgentStatus::OutOfBudget);
+    assert_eq!(transcript.replies(), 2);
+    assert_eq!(transcript.stopped_because.as_deref(), Some("Used all 2 steps"));
+
+    // Aborting during a tool call stops the run before the next step
+    let conversation = h.chat.create_conversation("Abort", None).await.unwrap();
+    h.provider
+        .call_tools("Adding twice", vec![add("call-a", 1, 1), add("call-b", 2, 2)])
+        .reply("Never sent");
+    let tools = Adder {
+        abort_on: Some(1),
+        ..Adder::default()
+    };
+    let transcript = AgentRunner::new(AgentBudget::default())
+        .run(&h.chat, &conversation.id, "Add twice", &tools)
+        .await
+        .unwrap();
+    assert_eq!(transcript.status, AgentStatus::Aborted);
+    assert_eq!(*tools.calls.lock().unwrap(), vec!["call-a"]);
+    assert_eq!(h.provider.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn team_agents_hand_tasks_to_each_other_under_their_own_prompts_and_tools() {
+    let h = TestHarness::new();
+    let store = AgentTeamStore::at(h.dir().join("agents.json"));
+    let profile = |name: &str, prompt: &str, tools: &[&str]| AgentProfile {
+        name: name.to_string(),
+        system_prompt: Some(prompt.to_string()),
+        tools: tools.iter().map(|t| t.to_string()).collect(),
+        budget: AgentBudget::default(),
+    };
+    store
+        .set_agent(profile("planner", "You plan work.", &["none"]))
+        .unwrap();
+    store.set_agent(profile("coder", "You write code.", &["add"])).unwrap();
+    store.add_rule("fix|bug", "coder").unwrap();
+    assert!(store.add_rule("sum", "nobody").is_err());
+    assert!(store.add_rule("(", "coder").is_err());
+    assert!(store.set_agent(profile("two words", "", &[])).is_err());
+
+    let team = AgentTeamStore::at(h.dir().join("agents.json")).get();
+    assert_eq!(team.route("Fix the BUG in the parser").unwrap().0.name, "coder");
+    assert_eq!(team.route("Add 2 and 3").unwrap().0.name, "planner");
+
+    let handoff = ToolCall::new(
+        "call-1",
+        HANDOFF_TOOL,
+        serde_json::json!({ "agent": "coder", "task": "Add 2 and 3" }),
+    );
+    h.provider
+        .call_tools("1. Have the coder add the numbers", vec![handoff])
+        .call_tools("", vec![add("call-2", 2, 3)])
+        .reply("The sum is 5");
+    let conversation = h.chat.create_conversation("Team", None).await.unwrap();
+
+    let runs = TeamRunner::new(team)
+        .run(&h.chat, &conversation.id, "Add 2 and 3", &Adder::default())
+        .await
+        .unwrap();
+
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0].agent.as_deref(), Some("planner"));
+    assert_eq!(runs[0].status, AgentStatus::HandedOff);
+    assert_eq!(runs[1].agent.as_deref(), Some("coder"));
+    assert_eq!(runs[1].status, AgentStatus::Finished);
+    assert_eq!(runs[1].parent.as_deref(), Some(runs[0].run_id.as_str()));
+
+    // Each agent worked under its own prompt, offered only its own tools
+    let requests = h.provider.requests();
+    assert_eq!(requests[0][0].text(), "You plan work.");
+    assert!(!requests[0].last().unwrap().text().contains("- add:"));
+    assert!(requests[0].last().unwrap().text().contains("- handoff:"));
+    assert_eq!(requests[1][0].text(), "You write code.");
+    assert!(requests[1].last().unwrap().text().contains("- add:"));
+
+    // The graph of the runs is rebuilt from the conversation
+    let stored = h.storage.load_conversation(&conversation.id).unwrap();
+    let graph = ExecutionGraph::of(&stored);
+    assert_eq!(graph.nodes.len(), 2);
+    assert_eq!(graph.nodes[1].tools, vec!["add"]);
+    assert_eq!(graph.edges.len(), 1);
+    assert_eq!(graph.edges[0].from, runs[0].run_id);
+    assert_eq!(graph.edges[0].task, "Add 2 and 3");
+    let dot = graph.to_dot();
+    assert!(dot.contains(&format!("\"{}\" -> \"{}\"", runs[0].run_id, runs[1].run_id)));
+
+    assert!(store.remove_agent("coder").unwrap());
+    assert!(store.get().rules.is_empty());
+}
+
+#[tokio::test]
+async fn kept_runs_replay_step_by_step_and_run_again_from_any_step() {
+    let h = TestHarness::new();
+    h.provider
+        .call_tools("1. Add 2 and 3\n2. Report the sum", vec![add("call-1", 2, 3)])
+        .reply("The sum is 5");
+    let conversation = h.chat.create_conversation("Replays", None).await.unwrap();
+    let transcript = AgentRunner::new(AgentBudget::default())
+        .run(&h.chat, &conversation.id, "Add 2 and 3", &Adder::default())
+        .await
+        .unwrap();
+
+    // Every reply is kept with what the model was given and what its calls returned
+    let store = get_agent_run_store();
+    let record = store.get(&transcript.run_id[..8]).unwrap();
+    assert_eq!(record.conversation_id, conversation.id);
+    assert_eq!(record.tools, vec!["add"]);
+    let frames = record.frames();
+    assert_eq!(frames.len(), 2);
+    match &frames[0].input {
+        FrameInput::Prompt { text } => assert!(text.ends_with("Task: Add 2 and 3")),
+        other => panic!("Expected the prompt, got {:?}", other),
+    }
+    assert_eq!(frames[0].calls[0].name, "add");
+    assert_eq!(frames[0].results[0].result, 5);
+    match &frames[1].input {
+        FrameInput::ToolResults { results } => assert_eq!(results[0].result, 5),
+        other => panic!("Expected tool results, got {:?}", other),
+    }
+    assert_eq!(frames[1].response, "The sum is 5");
+
+    // Running again from step 2 keeps step 1 and hands the model its results again
+    h.provider.reply("It is five");
+    let rerun = Rerun::prepare(&h.chat, &record, 2, ReplayOverrides::default())
+        .await
+        .unwrap();
+    assert_ne!(rerun.conversation_id(), conversation.id);
+    let replayed = rerun.run(&h.chat, &Adder::default()).await.unwrap();
+    assert_eq!(replayed.status, AgentStatus::Finished);
+    assert_eq!(replayed.replies(), 2);
+    assert_eq!(replayed.steps.len(), 4);
+    assert_eq!(h.provider.requests().last().unwrap().last().unwrap().role, MessageRole::Tool);
+    let copy = h.storage.load_conversation(rerun.conversation_id()).unwrap();
+    assert_eq!(copy.messages.len(), 4);
+    assert_eq!(copy.messages.last().unwrap().text(), "It is five");
+
+    let again = store.get(&replayed.run_id).unwrap();
+    assert_eq!(again.replay_of.as_ref().unwrap().from_step, 2);
+    assert_eq!(store.previous(&again).unwrap().transcript.run_id, transcript.run_id);
+    let diff = RunDiff::between(&record, &again);
+    assert_eq!(diff.diverged_at, Some(2));
+    let changes: Vec<FrameChange> = diff.frames.iter().map(|f| f.change).collect();
+    assert_eq!(changes, vec![FrameChange::Same, FrameChange::Changed]);
+
+    // From step 1 the task can change too
+    h.provider.reply("Nothing to add");
+    let overrides = ReplayOverrides {
+        task: Some("Add nothing".to_string()),
+        ..ReplayOverrides::default()
+    };
+    let rerun = Rerun::prepare(&h.chat, &record, 1, overrides.clone()).await.unwrap();
+    let replayed = rerun.run(&h.chat, &Adder::default()).await.unwrap();
+    let diff = RunDiff::between(&record, &store.get(&replayed.run_id).unwrap());
+    let changes: Vec<FrameChange> = diff.frames.iter().map(|f| f.change).collect();
+    assert_eq!(changes, vec![FrameChange::Changed, FrameChange::Removed]);
+
+    assert!(Rerun::prepare(&h.chat, &record, 2, overrides).await.is_err());
+    assert!(Rerun::prepare(&h.chat, &record, 3, ReplayOverrides::default())
+        .await
+        .is_err());
+    assert!(store.remove(&replayed.run_id).unwrap());
+}
@@ -0,0 +1,94 @@
+//! Usage analytics: weekly series, model mix and workspace rankings.
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use mcp_common::models::{Conversation, Message, Model};
+use mcp_common::service::analytics::{compute, month_spend, week_start, NO_WORKSPACE};
+use mcp_common::service::pricing::PriceTable;
+use serde_json::json;
+
+fn at(day: u32, hour: u32, second: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 5, day, hour, 0, second).unwrap()
+}
+
+fn exchange(conversation: &mut Conversation, prompted: DateTime<Utc>, answered: DateTime<Utc>, served_by: Option<&str>) {
+    let mut prompt = Message::user("Hello");
+    prompt.created_at = prompted.into();
+    let mut response = Message::assistant("Hi");
+    response.created_at = answered.into();
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert("usage".to_string(), json!({ "input_tokens": 100, "output_tokens": 20 }));
+    if let Some(model) = served_by {
+        metadata.insert("served_by".to_string(), json!(model));
+    }
+    response.metadata = Some(metadata);
+    conversation.messages.push(prompt);
+    conversation.messages.push(response);
+}
+
+#[test]
+fn weeks_start_on_monday() {
+    // 2024-05-15 is a Wednesday
+    assert_eq!(week_start(at(15, 12, 0)), NaiveDate::from_ymd_opt(2024, 5, 13).unwrap());
+    assert_eq!(week_start(at(13, 0, 0)), NaiveDate::from_ymd_opt(2024, 5, 13).unwrap());
+}
+
+#[test]
+fn usage_is_bucketed_by_week() {
+    let mut work = Conversation::new("Work", Model::default_claude());
+    work.metadata = json!({ "workspace": "acme" });
+    exchange(&mut work, at(8, 9, 0), at(8, 9, 2), None);
+    exchange(&mut work, at(15, 9, 0), at(15, 9, 4), Some("local-llama"));
+    exchange(&mut work, at(16, 9, 0), at(16, 9, 2), None);
+
+    let mut personal = Conversation::new("Personal", Model::default_claude());
+    exchange(&mut personal, at(16, 10, 0), at(16, 10, 6), None);
+    // Outside the period
+    exchange(&mut personal, at(1, 10, 0), at(1, 10, 1), None);
+
+    let claude = work.model.id.clone();
+    let dir = tempfile::tempdir().unwrap();
+    let prices = PriceTable::at(dir.path().join("pricing.json"));
+    let usage = compute(&[work, personal], 2, at(17, 12, 0), &prices, "USD");
+
+    assert_eq!(
+        usage.weeks,
+        vec![NaiveDate::from_ymd_opt(2024, 5, 6).unwrap(), NaiveDate::from_ymd_opt(2024, 5, 13).unwrap()]
+    );
+    assert_eq!(usage.messages.values, vec![2.0, 6.0]);
+    assert_eq!(usage.avg_latency_ms.values, vec![2000.0, 4000.0]);
+    assert_eq!(usage.input_tokens.values, vec![100.0, 300.0]);
+    assert_eq!(usage.output_tokens.values, vec![20.0, 60.0]);
+    assert_eq!(usage.total_tokens(), 480);
+    // Sonnet at $3 / $15 per million tokens; the local model has no price
+    assert!((usage.spend.values[1] - 2.0 * 0.0006).abs() < 1e-9);
+    assert!((usage.total_spend() - 3.0 * 0.0006).abs() < 1e-9);
+
+    assert_eq!(usage.model_mix[0].name, claude);
+    assert_eq!(usage.model_mix[0].values, vec![1.0, 2.0]);
+    assert_eq!(usage.model_mix[1].name, "local-llama");
+    assert_eq!(usage.models[0].responses, 3);
+    assert_eq!(usage.models[0].share, 0.75);
+
+    assert_eq!(usage.top_workspaces[0].workspace, "acme");
+    assert_eq!(usage.top_workspaces[0].messages, 6);
+    assert_eq!(usage.top_workspaces[0].conversations, 1);
+    assert_eq!(usage.top_workspaces[1].workspace, NO_WORKSPACE);
+    assert_eq!(usage.top_workspaces[1].tokens, 120);
+}
+
+#[test]
+fn month_spend_counts_this_months_priced_responses() {
+    let mut conversation = Conversation::new("Work", Model::default_claude());
+    exchange(&mut conversation, at(1, 9, 0), at(1, 9, 1), None);
+    exchange(&mut conversation, at(20, 9, 0), at(20, 9, 1), None);
+    // Unpriced models cost nothing
+    exchange(&mut conversation, at(20, 10, 0), at(20, 10, 1), Some("local-llama"));
+    // Last month
+    let april = Utc.with_ymd_and_hms(2024, 4, 30, 23, 0, 0).unwrap();
+    exchange(&mut conversation, april, april, None);
+
+    let dir = tempfile::tempdir().unwrap();
+    let prices = PriceTable::at(dir.path().join("pricing.json"));
+    let spent = month_spend(&[conversation], at(25, 12, 0), &prices, "USD");
+    assert!((spent - 2.0 * 0.0006).abs() < 1e-9);
+}
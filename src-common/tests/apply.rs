@@ -0,0 +1,153 @@
+//! Applying patches from messages: diffs and file blocks are found, hunks
+//! apply where the code drifted, and nothing is written unless all apply.
+
+use std::fs;
+
+use mcp_common::service::apply::{add_record, apply, parse_patch, preview, ApplicationRecord, FileChange};
+
+const MESSAGE: &str = "Rename the greeting and add a config file:
+
+```diff
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ pub fn greet() -> &'static str {
+-    \"hello\"
++    \"hi\"
+ }
+@@ -9,2 +9,3 @@
+ pub fn part() {}
++pub fn whole() {}
+```
+
+```toml config/app.toml
+name = \"demo\"
+```
+";
+
+fn repository() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    // Two lines were added on top since the diff was written
+    fs::write(
+        dir.path().join("src/lib.rs"),
+        "// Greetings\n\npub fn greet() -> &'static str {\n    \"hello\"\n}\n\npub fn part() {}\n",
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn diffs_and_file_blocks_are_found_in_messages() {
+    let patch = parse_patch(MESSAGE);
+    assert_eq!(patch.files.len(), 2);
+    assert_eq!(patch.files[0].path, "src/lib.rs");
+    assert!(matches!(&patch.files[0].change, FileChange::Hunks(hunks) if hunks.len() == 2));
+    assert_eq!(patch.files[1].path, "config/app.toml");
+    assert_eq!(patch.files[1].change, FileChange::Replace("name = \"demo\"\n".to_string()));
+    assert_eq!(patch.hunk_count(), 3);
+    assert!(parse_patch("No code here").is_empty());
+}
+
+#[test]
+fn hunks_apply_where_the_code_moved() {
+    let repo = repository();
+    let patch = parse_patch(MESSAGE);
+
+    let previews = preview(&patch, repo.path()).unwrap();
+    assert_eq!(previews[0].action, "modify");
+    assert_eq!(previews[0].hunks[0].line, Some(3));
+    assert!(previews[0].hunks.iter().all(|h| h.applies));
+    assert_eq!(previews[1].action, "create");
+
+    // A dry run checks everything and writes nothing
+    let report = apply(&patch, repo.path(), None, true).unwrap();
+    assert_eq!(report.applied_hunks, vec![1, 2, 3]);
+    assert!(!repo.path().join("config/app.toml").exists());
+
+    let report = apply(&patch, repo.path(), Some(&[1, 3]), false).unwrap();
+    assert_eq!(report.files, vec!["src/lib.rs", "config/app.toml"]);
+    assert_eq!(
+        fs::read_to_string(repo.path().join("src/lib.rs")).unwrap(),
+        "// Greetings\n\npub fn greet() -> &'static str {\n    \"hi\"\n}\n\npub fn part() {}\n"
+    );
+    assert_eq!(fs::read_to_string(repo.path().join("config/app.toml")).unwrap(), "name = \"demo\"\n");
+}
+
+#[test]
+fn nothing_is_written_when_a_hunk_does_not_apply() {
+    let repo = repository();
+    let stale = MESSAGE.replace("-    \"hello\"", "-    \"howdy\"");
+    let patch = parse_patch(&stale);
+    assert!(!preview(&patch, repo.path()).unwrap()[0].hunks[0].applies);
+
+    let error = apply(&patch, repo.path(), None, false).unwrap_err().to_string();
+    assert!(error.contains("Hunk 1 does not apply to src/lib.rs"));
+    assert!(fs::read_to_string(repo.path().join("src/lib.rs")).unwrap().contains("\"hello\""));
+    assert!(!repo.path().join("config/app.toml").exists());
+}
+
+#[test]
+fn patches_cannot_leave_the_repository() {
+    let repo = repository();
+    for path in ["../outside.txt", "/etc/passwd", ".git/config"] {
+        let patch = parse_patch(&format!("```text {}\nx\n```\n", path));
+        assert!(apply(&patch, repo.path(), None, false).is_err(), "{}", path);
+    }
+
+    let deletion = parse_patch("--- a/missing.rs\n+++ /dev/null\n@@ -1 +0,0 @@\n-gone\n");
+    assert!(apply(&deletion, repo.path(), None, false).unwrap_err().to_string().contains("does not exist"));
+}
+
+#[cfg(unix)]
+#[test]
+fn patches_cannot_follow_symlinks_out_of_the_repository() {
+    let repo = repository();
+    let outside = tempfile::tempdir().unwrap();
+    std::os::unix::fs::symlink(outside.path(), repo.path().join("linked")).unwrap();
+    std::os::unix::fs::symlink(outside.path().join("target.txt"), repo.path().join("dangling.txt")).unwrap();
+
+    for path in ["linked/new.txt", "linked/deeper/new.txt", "dangling.txt"] {
+        let patch = parse_patch(&format!("```text {}\nx\n```\n", path));
+        assert!(apply(&patch, repo.path(), None, false).is_err(), "{}", path);
+    }
+    assert_eq!(fs::read_dir(outside.path()).unwrap().count(), 0);
+
+    // Links that stay inside are fine
+    std::os::unix::fs::symlink(repo.path().join("src"), repo.path().join("sources")).unwrap();
+    let patch = parse_patch("```text sources/extra.rs\nx\n```\n");
+    apply(&patch, repo.path(), None, false).unwrap();
+    assert!(repo.path().join("src/extra.rs").exists());
+}
+
+#[test]
+fn line_endings_and_final_newline_are_kept() {
+    let repo = repository();
+    fs::write(repo.path().join("src/lib.rs"), "pub fn greet() -> &'static str {\r\n    \"hello\"\r\n}").unwrap();
+    fs::write(repo.path().join("config.toml"), "old\r\n").unwrap();
+    let message = format!("{}\n```toml config.toml\nname = 1\n```\n", MESSAGE.split("```toml").next().unwrap());
+    let patch = parse_patch(&message);
+
+    apply(&patch, repo.path(), Some(&[1, 3]), false).unwrap();
+    assert_eq!(
+        fs::read_to_string(repo.path().join("src/lib.rs")).unwrap(),
+        "pub fn greet() -> &'static str {\r\n    \"hi\"\r\n}"
+    );
+    assert_eq!(fs::read_to_string(repo.path().join("config.toml")).unwrap(), "name = 1\r\n");
+}
+
+#[test]
+fn applications_are_recorded_in_metadata() {
+    let mut metadata = serde_json::Value::Null;
+    let record = ApplicationRecord {
+        message_id: "m1".to_string(),
+        repository: "/work/demo".into(),
+        files: vec!["src/lib.rs".to_string()],
+        hunks: vec![1],
+        applied_at: chrono::Utc::now(),
+    };
+    add_record(&mut metadata, &record);
+    add_record(&mut metadata, &record);
+    assert_eq!(metadata["patch_applications"].as_array().unwrap().len(), 2);
+    assert_eq!(metadata["patch_applications"][0]["message_id"], "m1");
+}
@@ -0,0 +1,101 @@
+//! Attachment store: files are kept per conversation with thumbnails for
+//! images and previews for text, and orphans are cleaned up.
+
+use mcp_common::config::{media_type_for, AttachmentStore, ATTACHMENT_URL_SCHEME};
+use std::collections::HashSet;
+use std::io::Cursor;
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let image = image::RgbImage::from_fn(width, height, |x, y| image::Rgb([(x % 256) as u8, (y % 256) as u8, 128]));
+    let mut content = Vec::new();
+    image::DynamicImage::ImageRgb8(image)
+        .write_to(&mut Cursor::new(&mut content), image::ImageOutputFormat::Png)
+        .unwrap();
+    content
+}
+
+#[test]
+fn media_types_come_from_the_extension() {
+    assert_eq!(media_type_for("photo.JPG"), "image/jpeg");
+    assert_eq!(media_type_for("notes.md"), "text/markdown");
+    assert_eq!(media_type_for("main.rs"), "text/plain");
+    assert_eq!(media_type_for("archive"), "application/octet-stream");
+}
+
+#[test]
+fn images_get_thumbnails_and_text_gets_a_preview() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = AttachmentStore::new(dir.path().to_path_buf());
+
+    let image = store.add_bytes("c1", "photo.png", &png(600, 300)).unwrap();
+    assert!(image.is_image() && image.has_thumbnail);
+    let thumbnail = image::load_from_memory(&store.thumbnail(&image.id).unwrap()).unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (256, 128));
+    assert_eq!(image.url(), format!("{}{}", ATTACHMENT_URL_SCHEME, image.id));
+    assert_eq!(store.resolve_url(&image.url()).unwrap().file_name, "photo.png");
+
+    let text = store.add_bytes("c1", "notes.txt", "a".repeat(5000).as_bytes()).unwrap();
+    assert!(!text.has_thumbnail);
+    assert_eq!(text.text_preview.unwrap().len(), 2000);
+    assert_eq!(store.read(&text.id).unwrap().len(), 5000);
+
+    // A broken image is still stored, just without a thumbnail
+    let broken = store.add_bytes("c1", "broken.png", b"not an image").unwrap();
+    assert!(!broken.has_thumbnail);
+    assert!(store.thumbnail(&broken.id).is_none());
+}
+
+#[test]
+fn the_index_survives_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = AttachmentStore::new(dir.path().to_path_buf());
+    let attachment = store.add_bytes("c1", "notes.txt", b"hello").unwrap();
+    store.attach_to_message(&attachment.id, "m1").unwrap();
+    assert!(store.attach_to_message("missing", "m1").is_err());
+
+    let reopened = AttachmentStore::new(dir.path().to_path_buf());
+    assert_eq!(reopened.get(&attachment.id).unwrap().message_id.as_deref(), Some("m1"));
+}
+
+#[test]
+fn large_images_are_downscaled_into_a_new_attachment() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = AttachmentStore::new(dir.path().to_path_buf());
+    let original = store.add_bytes("c1", "scan.png", &png(1200, 800)).unwrap();
+
+    let smaller = store.downscale(&original.id, 600, 200_000).unwrap();
+    assert_eq!((smaller.file_name.as_str(), smaller.media_type.as_str()), ("scan-small.jpg", "image/jpeg"));
+    assert!(smaller.size <= 200_000);
+    let image = image::load_from_memory(&store.read(&smaller.id).unwrap()).unwrap();
+    assert_eq!(image.width().max(image.height()), 600);
+
+    let text = store.add_bytes("c1", "notes.txt", b"hello").unwrap();
+    assert!(store.downscale(&text.id, 600, 200_000).is_err());
+}
+
+#[test]
+fn usage_deletion_and_orphan_cleanup() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = AttachmentStore::new(dir.path().to_path_buf());
+    store.add_bytes("c1", "a.txt", b"12345").unwrap();
+    let b = store.add_bytes("c1", "b.txt", b"123").unwrap();
+    store.add_bytes("c2", "c.txt", b"1").unwrap();
+    store.add_bytes("gone", "d.txt", b"1234").unwrap();
+
+    let usage = store.usage();
+    assert_eq!((usage["c1"], usage["c2"]), (8, 1));
+    assert_eq!(store.list(Some("c1")).len(), 2);
+    assert_eq!(store.list(None).len(), 4);
+
+    store.delete(&b.id).unwrap();
+    assert!(store.delete(&b.id).is_err());
+    assert_eq!(store.delete_for_conversation("c2").unwrap(), 1);
+    assert_eq!(store.delete_for_conversation("c2").unwrap(), 0);
+
+    // Conversations that no longer exist and files the index doesn't know are removed
+    std::fs::write(dir.path().join("files").join("stray"), b"xy").unwrap();
+    let live: HashSet<String> = ["c1".to_string()].into_iter().collect();
+    assert_eq!(store.cleanup_orphans(&live).unwrap(), (2, 6));
+    assert_eq!(store.list(None).len(), 1);
+    assert!(!dir.path().join("files").join("stray").exists());
+}
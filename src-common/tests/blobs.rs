@@ -0,0 +1,90 @@
+//! Blob storage: large message parts are stored once, compressed, and
+//! removed when no conversation references them.
+
+use std::collections::{HashMap, HashSet};
+
+use mcp_common::config::{BlobStore, BLOB_THRESHOLD};
+use serde_json::{json, Value};
+
+/// A serialized conversation with one text part
+fn conversation(text: &str) -> Value {
+    json!({
+        "id": "c1",
+        "messages": [
+            {"content": {"parts": [{"type": "text", "value": {"text": text}}]}}
+        ]
+    })
+}
+
+fn text_of(conversation: &Value) -> &Value {
+    &conversation["messages"][0]["content"]["parts"][0]["value"]["text"]
+}
+
+#[test]
+fn large_parts_are_stored_once_and_read_back() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = BlobStore::new(dir.path().to_path_buf());
+    let pasted = "log line that repeats\n".repeat(BLOB_THRESHOLD / 10);
+
+    let mut small = conversation("short");
+    assert!(store.externalize(&mut small).unwrap().is_empty());
+    assert_eq!(text_of(&small), "short");
+
+    let mut first = conversation(&pasted);
+    let mut second = conversation(&pasted);
+    let refs = store.externalize(&mut first).unwrap();
+    assert_eq!(store.externalize(&mut second).unwrap(), refs);
+    let hash = refs.iter().next().unwrap().clone();
+    assert_eq!(hash, BlobStore::hash(pasted.as_bytes()));
+    assert_eq!(text_of(&first), &json!({ "$blob": hash }));
+
+    let stats = store.stats();
+    assert_eq!(stats.blobs, 1);
+    assert_eq!(stats.size, pasted.len() as u64);
+    assert!(stats.stored_size < stats.size / 10);
+
+    store.internalize(&mut first).unwrap();
+    assert_eq!(text_of(&first), pasted.as_str());
+    assert!(store.get("../../etc").is_err());
+}
+
+#[test]
+fn blobs_go_when_the_last_reference_does() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = BlobStore::new(dir.path().to_path_buf());
+    let pasted = "x".repeat(BLOB_THRESHOLD);
+    let none = HashSet::new();
+
+    // Two conversations take a reference each
+    let refs = store.externalize(&mut conversation(&pasted)).unwrap();
+    store.replace_refs(&none, &refs).unwrap();
+    store.replace_refs(&none, &refs).unwrap();
+    let hash = refs.iter().next().unwrap();
+
+    store.replace_refs(&refs, &none).unwrap();
+    assert!(store.get(hash).is_ok());
+    store.replace_refs(&refs, &none).unwrap();
+    assert!(store.get(hash).is_err());
+    assert_eq!(store.stats().blobs, 0);
+
+    // The index survives reopening
+    let refs = store.externalize(&mut conversation(&pasted)).unwrap();
+    store.replace_refs(&none, &refs).unwrap();
+    assert_eq!(BlobStore::new(dir.path().to_path_buf()).stats().blobs, 1);
+}
+
+#[test]
+fn reconciling_removes_unreferenced_and_stray_blobs() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = BlobStore::new(dir.path().to_path_buf());
+    let kept = store.externalize(&mut conversation(&"k".repeat(BLOB_THRESHOLD))).unwrap();
+    store.externalize(&mut conversation(&"d".repeat(BLOB_THRESHOLD))).unwrap();
+    std::fs::create_dir_all(dir.path().join("ab")).unwrap();
+    std::fs::write(dir.path().join("ab").join("cdef.zst"), b"stray").unwrap();
+
+    let counts: HashMap<String, usize> = kept.iter().map(|hash| (hash.clone(), 2)).collect();
+    assert_eq!(store.reconcile(&counts).unwrap(), 2);
+    assert_eq!(store.stats().blobs, 1);
+    assert!(store.get(kept.iter().next().unwrap()).is_ok());
+    assert!(!dir.path().join("ab").join("cdef.zst").exists());
+}
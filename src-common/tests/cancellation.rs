@@ -0,0 +1,74 @@
+//! Request contexts: canceling or passing the deadline aborts the work, and
+//! children follow their parent.
+
+use mcp_common::error::{McpError, McpResult};
+use mcp_common::utils::cancellation::RequestContext;
+use std::time::Duration;
+
+async fn slow() -> McpResult<()> {
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancel_aborts_the_future() {
+    let ctx = RequestContext::new();
+    let cancel = ctx.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        cancel.cancel();
+    });
+
+    assert!(matches!(ctx.run(slow()).await, Err(McpError::Cancelled)));
+}
+
+#[tokio::test]
+async fn deadline_times_out_with_the_budget() {
+    let ctx = RequestContext::with_timeout(Duration::from_millis(20));
+
+    match ctx.run(slow()).await {
+        Err(McpError::Timeout(budget)) => assert_eq!(budget, Duration::from_millis(20)),
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn canceled_context_does_not_start_work() {
+    let ctx = RequestContext::new();
+    ctx.cancel();
+
+    let result: McpResult<()> = ctx.run(async { panic!("work started after cancel") }).await;
+    assert!(matches!(result, Err(McpError::Cancelled)));
+}
+
+#[tokio::test]
+async fn errors_convert_into_the_caller_error_type() {
+    #[derive(Debug)]
+    struct CallerError(McpError);
+
+    impl From<McpError> for CallerError {
+        fn from(error: McpError) -> Self {
+            CallerError(error)
+        }
+    }
+
+    let ctx = RequestContext::new();
+    ctx.cancel();
+    let result: Result<(), CallerError> = ctx.run(async { Ok(()) }).await;
+    assert!(matches!(result, Err(CallerError(McpError::Cancelled))));
+}
+
+#[test]
+fn child_is_canceled_with_its_parent() {
+    let parent = RequestContext::with_timeout(Duration::from_secs(60));
+    let child = parent.child();
+    assert_eq!(child.deadline, parent.deadline);
+
+    parent.cancel();
+    assert!(matches!(child.check(), Err(McpError::Cancelled)));
+
+    // Canceling a child leaves the parent alone
+    let parent = RequestContext::new();
+    parent.child().cancel();
+    assert!(parent.check().is_ok());
+}
@@ -0,0 +1,167 @@
+//! Capability negotiation: corrections by provider and prefix, and requests
+//! adapted to a model without vision, tools, streaming or JSON mode.
+
+use mcp_common::models::message::{ContentType, RESPONSE_FORMAT_METADATA_KEY};
+use mcp_common::models::{Message, MessageRole, Model, ModelCapabilities, Tool, ToolResult};
+use mcp_common::service::capabilities::{CapabilityTable, Degradation, Feature};
+use mcp_common::testing::TestHarness;
+
+fn model(capabilities: ModelCapabilities) -> Model {
+    Model {
+        id: "capability-test-model".to_string(),
+        provider: "capability-test".to_string(),
+        name: "Capability test model".to_string(),
+        version: "1".to_string(),
+        capabilities,
+    }
+}
+
+fn plain() -> ModelCapabilities {
+    ModelCapabilities {
+        vision: false,
+        max_context_length: 8192,
+        functions: false,
+        streaming: false,
+        json_mode: false,
+    }
+}
+
+fn features(message: &Message) -> Vec<Feature> {
+    Degradation::of(message).iter().map(|d| d.feature).collect()
+}
+
+#[test]
+fn corrections_apply_by_provider_then_longest_prefix() {
+    let dir = tempfile::tempdir().unwrap();
+    let table = CapabilityTable::at(dir.path().join("capabilities.json"));
+    table.set("capability-test", Feature::Vision, true).unwrap();
+    table.set("capability-test", Feature::Tools, true).unwrap();
+    table.set("capability", Feature::Tools, false).unwrap();
+    table.set("capability-test-model", Feature::Streaming, true).unwrap();
+    assert!(table.set(" ", Feature::Vision, true).is_err());
+
+    let capabilities = table.for_model(&model(plain()));
+    assert!(capabilities.vision);
+    assert!(!capabilities.functions);
+    assert!(capabilities.streaming);
+    assert!(!capabilities.json_mode);
+
+    assert!(table.reset("capability", Some(Feature::Tools)).unwrap());
+    assert!(!table.reset("capability", None).unwrap());
+    assert!(table.for_model(&model(plain())).functions);
+    assert_eq!("json".parse::<Feature>().unwrap(), Feature::JsonMode);
+    assert!("telepathy".parse::<Feature>().is_err());
+}
+
+#[tokio::test]
+async fn requests_degrade_to_what_the_model_can_do() {
+    let h = TestHarness::new();
+    let conversation = h.chat.create_conversation("Plain", Some(model(plain()))).await.unwrap();
+    let id = conversation.id.clone();
+
+    // Images are left out of the request but kept in the conversation
+    let mut conversation = h.chat.get_conversation(&id).await.unwrap();
+    let mut picture = Message::user("Here is my cat");
+    picture.content.parts.push(ContentType::Image {
+        url: "https://example.com/cat.png".to_string(),
+        alt_text: Some("a cat on a sofa".to_string()),
+    });
+    conversation.add_message(picture);
+    h.chat.update_conversation(conversation).await.unwrap();
+
+    h.provider.reply("A fine cat.");
+    let reply = h.chat.send_message(&id, "What is it doing?").await.unwrap();
+    let sent = h.provider.requests().pop().unwrap();
+    assert!(sent
+        .iter()
+        .all(|m| m.content.parts.iter().all(|p| !matches!(p, ContentType::Image { .. }))));
+    assert!(sent
+        .iter()
+        .any(|m| m.text().contains("[Image left out: a cat on a sofa]")));
+    assert_eq!(features(&reply), vec![Feature::Vision]);
+    let stored = h.chat.get_conversation(&id).await.unwrap();
+    assert!(stored
+        .messages
+        .iter()
+        .any(|m| m.content.parts.iter().any(|p| matches!(p, ContentType::Image { .. }))));
+
+    // Offered tools are described in the prompt and called from fenced blocks
+    h.chat
+        .offer_tools(&id, &[Tool::simple_function("search", "Search the web")])
+        .await
+        .unwrap();
+    h.provider
+        .reply("Let me look.\n```tool_call\n{\"name\": \"search\", \"arguments\": {\"input\": \"cats\"}}\n```");
+    let reply = h.chat.send_message(&id, "Find out more").await.unwrap();
+    let calls = reply.tool_calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].name, "search");
+    assert_eq!(calls[0].arguments["input"], "cats");
+    assert_eq!(reply.text(), "Let me look.");
+    assert!(features(&reply).contains(&Feature::Tools));
+    let sent = h.provider.requests().pop().unwrap();
+    assert!(sent
+        .iter()
+        .any(|m| m.role == MessageRole::System && m.text().contains("- search: Search the web")));
+
+    // Results go back as text the model can read
+    h.provider.reply("Cats sleep a lot.");
+    let result = ToolResult::success(&calls[0].id, "search", "Cats sleep 16 hours a day");
+    h.chat.send_tool_results(&id, &[result]).await.unwrap();
+    let sent = h.provider.requests().pop().unwrap();
+    let last = sent.last().unwrap();
+    assert_eq!(last.role, MessageRole::User);
+    assert!(last.text().contains("Cats sleep 16 hours a day"));
+    assert!(sent.iter().all(|m| m.role != MessageRole::Tool && !m.has_tool_calls()));
+    h.chat.offer_tools(&id, &[]).await.unwrap();
+
+    // JSON is asked for in words and unfenced
+    h.chat.set_json_mode(&id, true).await.unwrap();
+    h.provider.reply("```json\n{\"sleeping\": true}\n```");
+    let reply = h.chat.send_message(&id, "Is it sleeping?").await.unwrap();
+    assert_eq!(reply.text(), "{\"sleeping\": true}");
+    assert!(features(&reply).contains(&Feature::JsonMode));
+    assert!(h
+        .provider
+        .requests()
+        .pop()
+        .unwrap()
+        .last()
+        .unwrap()
+        .text()
+        .contains("valid JSON"));
+
+    // Streaming falls back to one whole reply
+    h.provider.stream(&["All ", "done"]);
+    let (chunks, error) = TestHarness::drain(h.chat.send_message_streaming(&id, "Thanks").await.unwrap()).await;
+    assert!(error.is_none());
+    assert_eq!(chunks.len(), 1);
+    assert!(features(&chunks[0]).contains(&Feature::Streaming));
+    let stored = h.chat.get_conversation(&id).await.unwrap();
+    assert!(features(stored.messages.last().unwrap()).contains(&Feature::Streaming));
+}
+
+#[tokio::test]
+async fn capable_models_are_sent_the_conversation_as_is() {
+    let h = TestHarness::new();
+    let capable = ModelCapabilities {
+        vision: true,
+        functions: true,
+        streaming: true,
+        json_mode: true,
+        ..plain()
+    };
+    let conversation = h
+        .chat
+        .create_conversation("Capable", Some(model(capable)))
+        .await
+        .unwrap();
+    h.chat.set_json_mode(&conversation.id, true).await.unwrap();
+
+    h.provider.reply("{}");
+    let reply = h.chat.send_message(&conversation.id, "Anything").await.unwrap();
+    assert!(Degradation::of(&reply).is_empty());
+    let sent = h.provider.requests().pop().unwrap();
+    let format = sent.last().unwrap().metadata.as_ref().unwrap()[RESPONSE_FORMAT_METADATA_KEY].clone();
+    assert_eq!(format, "json");
+}
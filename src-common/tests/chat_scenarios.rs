@@ -0,0 +1,249 @@
+//! End-to-end chat scenarios on the test harness: scripted provider,
+//! throwaway storage and a manual clock.
+
+use chrono::Duration;
+use mcp_common::error::McpError;
+use mcp_common::models::MessageRole;
+use mcp_common::service::performance::ResponsePerformance;
+use mcp_common::sync::{ConflictResolution, ConflictVersion};
+use mcp_common::testing::{TestHarness, Turn};
+
+#[tokio::test]
+async fn reply_is_stored_after_the_prompt() {
+    let h = TestHarness::new();
+    h.provider.reply("Hi there");
+    let conversation = h.chat.create_conversation("Greeting", None).await.unwrap();
+
+    let reply = h.chat.send_message(&conversation.id, "Hello").await.unwrap();
+    assert_eq!(reply.text(), "Hi there");
+
+    let stored = h.storage.load_conversation(&conversation.id).unwrap();
+    let roles: Vec<_> = stored.messages.iter().map(|m| m.role.clone()).collect();
+    assert_eq!(roles, vec![MessageRole::User, MessageRole::Assistant]);
+    assert!(stored.messages[0].created_at < stored.messages[1].created_at);
+}
+
+#[tokio::test]
+async fn unscripted_requests_echo_the_prompt() {
+    let h = TestHarness::new();
+    let conversation = h.chat.create_conversation("Echo", None).await.unwrap();
+
+    let reply = h.chat.send_message(&conversation.id, "ping").await.unwrap();
+    assert_eq!(reply.text(), "Echo: ping");
+}
+
+#[tokio::test]
+async fn streamed_reply_is_stored_whole() {
+    let h = TestHarness::new();
+    h.provider.stream(&["Hel", "lo"]);
+    let conversation = h.chat.create_conversation("Streaming", None).await.unwrap();
+
+    let stream = h.chat.send_message_streaming(&conversation.id, "Say hello").await.unwrap();
+    let (chunks, error) = TestHarness::drain(stream).await;
+    assert!(error.is_none());
+    // The content filters hold back the end of the text until the stream ends
+    let deltas: Vec<_> = chunks.iter().map(|c| c.text()).collect();
+    assert_eq!(deltas, vec!["", "", "Hello"]);
+
+    let stored = h.chat.get_conversation(&conversation.id).await.unwrap();
+    let reply = stored.messages.last().unwrap();
+    assert_eq!(reply.role, MessageRole::Assistant);
+    assert_eq!(reply.text(), "Hello");
+}
+
+#[tokio::test]
+async fn stream_failing_midway_ends_with_the_error() {
+    let h = TestHarness::new();
+    h.provider
+        .push(Turn::StreamThenFail(vec!["Partial".to_string()], "connection reset".to_string()));
+    let conversation = h.chat.create_conversation("Flaky", None).await.unwrap();
+
+    let stream = h.chat.send_message_streaming(&conversation.id, "Tell me").await.unwrap();
+    let (chunks, error) = TestHarness::drain(stream).await;
+    // Text held back by the filters is sent before the error
+    let text: String = chunks.iter().map(|c| c.text()).collect();
+    assert_eq!(text, "Partial");
+    assert!(matches!(error, Some(McpError::Connection(_))));
+
+    // The prompt is kept so it can be retried
+    let stored = h.chat.get_conversation(&conversation.id).await.unwrap();
+    assert_eq!(stored.messages[0].text(), "Tell me");
+
+    // The conversation carries on after the broken stream
+    h.provider.reply("Complete answer");
+    let reply = h.chat.send_message(&conversation.id, "Try again").await.unwrap();
+    assert_eq!(reply.text(), "Complete answer");
+}
+
+#[tokio::test]
+async fn retry_after_a_failure_sends_the_whole_history() {
+    let h = TestHarness::new();
+    h.provider.fail("timed out").reply("Second time lucky");
+    let conversation = h.chat.create_conversation("Retry", None).await.unwrap();
+
+    let error = h.chat.send_message(&conversation.id, "First try").await.unwrap_err();
+    assert!(matches!(error, McpError::Connection(_)));
+
+    let reply = h.chat.send_message(&conversation.id, "Second try").await.unwrap();
+    assert_eq!(reply.text(), "Second time lucky");
+
+    let requests = h.provider.requests();
+    assert_eq!(requests.len(), 2);
+    let sent: Vec<_> = requests[1].iter().map(|m| m.text()).collect();
+    assert_eq!(sent, vec!["First try", "Second try"]);
+}
+
+#[tokio::test]
+async fn replies_record_who_served_them_and_after_how_many_retries() {
+    let h = TestHarness::new();
+    h.provider.fail("timed out");
+    h.provider.push(Turn::Stream(vec!["Got ".to_string(), "there".to_string()]));
+    let conversation = h.chat.create_conversation("Timing", None).await.unwrap();
+
+    h.chat.send_message(&conversation.id, "First try").await.unwrap_err();
+    let stream = h.chat.send_message_streaming(&conversation.id, "Second try").await.unwrap();
+    TestHarness::drain(stream).await;
+
+    let stored = h.chat.get_conversation(&conversation.id).await.unwrap();
+    let reply = stored.messages.last().unwrap();
+    let performance = ResponsePerformance::of(reply).unwrap();
+    assert_eq!(performance.provider, conversation.model.provider);
+    assert_eq!(performance.model, conversation.model.id);
+    assert_eq!(performance.model_version, conversation.model.version);
+    assert!(performance.time_to_first_token_ms <= performance.duration_ms);
+    assert_eq!(performance.retries, 1);
+
+    // A reply right after the last one took no retries
+    h.provider.reply("Again");
+    let reply = h.chat.send_message(&conversation.id, "Once more").await.unwrap();
+    assert_eq!(ResponsePerformance::of(&reply).unwrap().retries, 0);
+}
+
+#[tokio::test]
+async fn messages_go_out_once_back_online() {
+    let h = TestHarness::new();
+    h.provider.set_offline(true);
+    let conversation = h.chat.create_conversation("Offline", None).await.unwrap();
+
+    let error = h.chat.send_message(&conversation.id, "Anyone there?").await.unwrap_err();
+    assert!(matches!(error, McpError::Connection(_)));
+    assert_eq!(h.provider.connects(), 0);
+    assert!(h.provider.requests().is_empty());
+
+    h.provider.set_offline(false);
+    h.provider.reply("Back again");
+    let reply = h.chat.send_message(&conversation.id, "Now?").await.unwrap();
+    assert_eq!(reply.text(), "Back again");
+    assert_eq!(h.provider.connects(), 1);
+}
+
+#[tokio::test]
+async fn edits_can_be_undone_and_redone() {
+    let h = TestHarness::new();
+    let conversation = h.chat.create_conversation("Edits", None).await.unwrap();
+    h.chat.send_message(&conversation.id, "Original").await.unwrap();
+    let stored = h.chat.get_conversation(&conversation.id).await.unwrap();
+    let message_id = stored.messages[0].id.clone();
+
+    h.chat.edit_message(&conversation.id, &message_id, "First edit").await.unwrap();
+    h.chat.edit_message(&conversation.id, &message_id, "Second edit").await.unwrap();
+
+    let text = |c: mcp_common::models::Conversation| c.messages[0].text();
+    h.chat.undo().await.unwrap().unwrap();
+    assert_eq!(text(h.chat.get_conversation(&conversation.id).await.unwrap()), "First edit");
+    h.chat.undo().await.unwrap().unwrap();
+    assert_eq!(text(h.chat.get_conversation(&conversation.id).await.unwrap()), "Original");
+    h.chat.redo().await.unwrap().unwrap();
+    assert_eq!(text(h.chat.get_conversation(&conversation.id).await.unwrap()), "First edit");
+
+    // A new edit branches off, so the undone second edit can't be redone
+    h.chat.edit_message(&conversation.id, &message_id, "Other edit").await.unwrap();
+    assert!(h.chat.redo().await.unwrap().is_none());
+    assert_eq!(text(h.storage.load_conversation(&conversation.id).unwrap()), "Other edit");
+}
+
+#[tokio::test]
+async fn edits_keep_earlier_versions() {
+    let h = TestHarness::new();
+    let conversation = h.chat.create_conversation("History", None).await.unwrap();
+    h.chat.send_message(&conversation.id, "Deploy on Friday").await.unwrap();
+    let message_id = h.chat.get_conversation(&conversation.id).await.unwrap().messages[0].id.clone();
+    assert!(h.chat.get_message_history(&message_id).await.unwrap().is_empty());
+
+    h.chat.edit_message(&conversation.id, &message_id, "Deploy on Monday").await.unwrap();
+    let edited = h.chat.edit_message(&conversation.id, &message_id, "Deploy on Tuesday").await.unwrap();
+    assert!(edited.is_edited());
+    assert_eq!(edited.text(), "Deploy on Tuesday");
+
+    let history = h.chat.get_message_history(&message_id).await.unwrap();
+    let texts: Vec<_> = history.iter().map(|v| v.text.as_str()).collect();
+    assert_eq!(texts, vec!["Deploy on Friday", "Deploy on Monday"]);
+    assert_eq!(history[1].written_at, history[0].replaced_at);
+
+    // The history is stored with the message, and undo takes its version back
+    let stored = h.storage.load_conversation(&conversation.id).unwrap();
+    assert_eq!(stored.messages[0].edit_history(), history);
+    h.chat.undo().await.unwrap().unwrap();
+    assert_eq!(h.chat.get_message_history(&message_id).await.unwrap().len(), 1);
+
+    // Saving the same text isn't an edit
+    let unchanged = h.chat.edit_message(&conversation.id, &message_id, "Deploy on Monday").await.unwrap();
+    assert_eq!(unchanged.edit_history().len(), 1);
+    assert!(h.chat.get_message_history("no-such-message").await.is_err());
+}
+
+#[tokio::test]
+async fn undo_window_expires() {
+    let h = TestHarness::new();
+    let conversation = h.chat.create_conversation("Doomed", None).await.unwrap();
+    h.chat.delete_conversation(&conversation.id).await.unwrap();
+
+    h.clock.advance(Duration::hours(1));
+    assert!(h.chat.undo().await.unwrap().is_none());
+    assert!(h.chat.get_conversation(&conversation.id).await.is_err());
+}
+
+#[tokio::test]
+async fn deleted_conversations_can_be_restored_until_the_trash_expires() {
+    let h = TestHarness::new();
+    let kept = h.chat.create_conversation("Kept", None).await.unwrap();
+    let lost = h.chat.create_conversation("Lost", None).await.unwrap();
+    h.chat.delete_conversation(&kept.id).await.unwrap();
+    h.chat.delete_conversation(&lost.id).await.unwrap();
+
+    h.clock.advance(Duration::days(29));
+    assert_eq!(h.storage.purge_expired_trash().unwrap(), 0);
+    h.chat.restore_conversation(&kept.id).await.unwrap();
+
+    h.clock.advance(Duration::days(2));
+    assert_eq!(h.storage.purge_expired_trash().unwrap(), 1);
+    assert!(h.chat.list_trashed().await.unwrap().is_empty());
+    assert_eq!(h.chat.get_conversation(&kept.id).await.unwrap().title, "Kept");
+}
+
+#[tokio::test]
+async fn resolved_sync_conflicts_are_handed_to_sync_once() {
+    let h = TestHarness::new();
+    let queue = h.conflict_queue();
+    let version = |value: &str, device: &str| ConflictVersion {
+        value: Some(value.to_string()),
+        device_id: device.to_string(),
+        timestamp: mcp_common::utils::clock::now(),
+    };
+
+    let id = queue
+        .enqueue("settings.theme", version("dark", "laptop"), version("light", "desktop"))
+        .unwrap();
+    assert_eq!(queue.len(), 1);
+
+    h.clock.advance(Duration::minutes(5));
+    let resolved = queue.resolve(&id, ConflictResolution::KeepLocal).unwrap();
+    assert_eq!(resolved.value.as_deref(), Some("dark"));
+    assert!(resolved.resolved_at >= TestHarness::epoch() + Duration::minutes(5));
+    assert!(queue.is_empty());
+
+    let taken = queue.take_resolved();
+    assert_eq!(taken.len(), 1);
+    assert_eq!(taken[0].key, "settings.theme");
+    assert!(queue.take_resolved().is_empty());
+}
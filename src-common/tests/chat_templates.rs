@@ -0,0 +1,83 @@
+//! Chat templates: picking a local model's template and laying out
+//! conversations with it.
+
+use mcp_common::models::{ChatTemplate, ChatTurn, MessageRole};
+
+fn conversation() -> Vec<ChatTurn> {
+    vec![
+        ChatTurn::new(MessageRole::System, "Be brief."),
+        ChatTurn::new(MessageRole::User, "Hi"),
+        ChatTurn::new(MessageRole::Assistant, "Hello!"),
+        ChatTurn::new(MessageRole::User, "Bye"),
+    ]
+}
+
+#[test]
+fn conversations_are_laid_out_per_template() {
+    assert_eq!(
+        ChatTemplate::ChatMl.render(&conversation()),
+        "<|im_start|>system\nBe brief.<|im_end|>\n\
+         <|im_start|>user\nHi<|im_end|>\n\
+         <|im_start|>assistant\nHello!<|im_end|>\n\
+         <|im_start|>user\nBye<|im_end|>\n\
+         <|im_start|>assistant\n"
+    );
+    assert_eq!(
+        ChatTemplate::Llama3.render(&conversation()),
+        "<|begin_of_text|>\
+         <|start_header_id|>system<|end_header_id|>\n\nBe brief.<|eot_id|>\
+         <|start_header_id|>user<|end_header_id|>\n\nHi<|eot_id|>\
+         <|start_header_id|>assistant<|end_header_id|>\n\nHello!<|eot_id|>\
+         <|start_header_id|>user<|end_header_id|>\n\nBye<|eot_id|>\
+         <|start_header_id|>assistant<|end_header_id|>\n\n"
+    );
+    // Mistral has no system role; the system prompt opens the first instruction
+    assert_eq!(
+        ChatTemplate::Mistral.render(&conversation()),
+        "<s>[INST] Be brief.\n\nHi [/INST] Hello!</s>[INST] Bye [/INST]"
+    );
+    assert_eq!(
+        ChatTemplate::Gemma.render(&conversation()),
+        "<bos><start_of_turn>user\nBe brief.\n\nHi<end_of_turn>\n\
+         <start_of_turn>model\nHello!<end_of_turn>\n\
+         <start_of_turn>user\nBye<end_of_turn>\n\
+         <start_of_turn>model\n"
+    );
+    assert!(ChatTemplate::Plain.render(&conversation()).ends_with("User: Bye\n\nAssistant:"));
+}
+
+#[test]
+fn templates_come_from_the_most_specific_source() {
+    let llama3_jinja = "{{ '<|start_header_id|>' + message['role'] + '<|end_header_id|>' }}";
+
+    // The catalog's choice beats the file's own template
+    assert_eq!(
+        ChatTemplate::resolve(Some("chatml"), Some(llama3_jinja), Some("llama"), &["llama-3-8b"]),
+        ChatTemplate::ChatMl
+    );
+    assert_eq!(
+        ChatTemplate::resolve(None, Some(llama3_jinja), Some("qwen2"), &[]),
+        ChatTemplate::Llama3
+    );
+    assert_eq!(ChatTemplate::resolve(None, None, Some("qwen2"), &[]), ChatTemplate::ChatMl);
+    // "llama" is shared by too many families to say; the name decides
+    assert_eq!(
+        ChatTemplate::resolve(None, None, Some("llama"), &["Meta-Llama-3.1-8B-Instruct"]),
+        ChatTemplate::Llama3
+    );
+    assert_eq!(
+        ChatTemplate::resolve(None, None, Some("llama"), &["mistral-7b-instruct-v0.2"]),
+        ChatTemplate::Mistral
+    );
+    assert_eq!(ChatTemplate::resolve(Some("bogus"), None, None, &["mystery"]), ChatTemplate::Plain);
+}
+
+#[test]
+fn template_names_round_trip() {
+    for template in ChatTemplate::ALL {
+        assert_eq!(template.as_str().parse::<ChatTemplate>(), Ok(template));
+        assert!(!template.stop_sequences().is_empty());
+    }
+    assert_eq!("Llama-2".parse::<ChatTemplate>(), Ok(ChatTemplate::Mistral));
+    assert!("jinja".parse::<ChatTemplate>().is_err());
+}
@@ -0,0 +1,120 @@
+//! Browser extension companion: pairing, origin checks, rate limits and page requests.
+
+use chrono::Duration;
+use mcp_common::error::McpError;
+use mcp_common::models::MessageRole;
+use mcp_common::service::ChatService;
+use mcp_common::testing::TestHarness;
+use mcp_common::webhooks::companion::{is_extension_origin, Companion, CompanionStore, PageSelection, RateLimiter};
+use std::sync::Arc;
+
+const ORIGIN: &str = "chrome-extension://abcdefghijklmnop";
+
+fn page() -> PageSelection {
+    PageSelection {
+        url: "https://example.com/post".to_string(),
+        title: Some("A post".to_string()),
+        text: "Rust 2.0 is not happening; editions cover it.".to_string(),
+    }
+}
+
+#[test]
+fn only_extension_origins_pair_and_only_with_the_right_code() {
+    let h = TestHarness::new();
+    assert!(is_extension_origin("moz-extension://0f6e3b2a-1c4d-4e5f-8a9b-0c1d2e3f4a5b"));
+    assert!(!is_extension_origin("https://evil.example"));
+    assert!(!is_extension_origin("chrome-extension://"));
+
+    let store = CompanionStore::at(h.dir().join("companion.json"));
+    assert!(matches!(
+        store.start_pairing("https://evil.example", "Evil"),
+        Err(McpError::Authentication(_))
+    ));
+
+    let pending = store.start_pairing(ORIGIN, "Papin for Chrome").unwrap();
+    assert_eq!(pending.code.len(), 6);
+    // Another origin can't finish it, nor can a wrong code
+    assert!(store.complete_pairing(&pending.id, "chrome-extension://other", &pending.code).is_err());
+    assert!(store.complete_pairing(&pending.id, ORIGIN, "not-it").is_err());
+
+    let pairing = store.complete_pairing(&pending.id, ORIGIN, &pending.code).unwrap();
+    assert!(pairing.token.starts_with("ext_"));
+    assert_ne!(pairing.extension.token_hash, pairing.token);
+    // A code works once
+    assert!(store.complete_pairing(&pending.id, ORIGIN, &pending.code).is_err());
+
+    assert!(store.authenticate(&pairing.token, ORIGIN).is_some());
+    assert!(store.authenticate(&pairing.token, "chrome-extension://other").is_none());
+    assert!(store.authenticate("ext_wrong", ORIGIN).is_none());
+
+    // Paired extensions survive a restart; codes expire
+    let store = CompanionStore::at(h.dir().join("companion.json"));
+    assert!(store.is_paired(ORIGIN));
+    let late = store.start_pairing(ORIGIN, "Again").unwrap();
+    h.clock.advance(Duration::minutes(6));
+    assert!(store.complete_pairing(&late.id, ORIGIN, &late.code).is_err());
+
+    store.revoke(&pairing.extension.id[..8]).unwrap();
+    assert!(store.authenticate(&pairing.token, ORIGIN).is_none());
+}
+
+#[test]
+fn repeated_wrong_codes_drop_the_pairing() {
+    let h = TestHarness::new();
+    let store = CompanionStore::at(h.dir().join("companion.json"));
+    let pending = store.start_pairing(ORIGIN, "Guesser").unwrap();
+    for _ in 0..5 {
+        let _ = store.complete_pairing(&pending.id, ORIGIN, "000000x");
+    }
+    assert!(store.complete_pairing(&pending.id, ORIGIN, &pending.code).is_err());
+}
+
+#[test]
+fn requests_are_limited_per_key_in_a_sliding_window() {
+    let h = TestHarness::new();
+    let limiter = RateLimiter::new(2, std::time::Duration::from_secs(60));
+    assert!(limiter.check("a").is_ok());
+    h.clock.advance(Duration::seconds(20));
+    assert!(limiter.check("a").is_ok());
+    let wait = limiter.check("a").unwrap_err();
+    assert_eq!(wait.as_secs(), 40);
+    assert!(limiter.check("b").is_ok());
+
+    h.clock.advance(Duration::seconds(41));
+    assert!(limiter.check("a").is_ok());
+}
+
+#[tokio::test]
+async fn pages_are_summarized_answered_and_opened() {
+    let h = TestHarness::new();
+    let chat = Arc::new(ChatService::new(h.service.clone()));
+    let companion = Companion::new(chat.clone());
+
+    h.provider.reply("No Rust 2.0.");
+    let summary = companion.summarize(&page()).await.unwrap();
+    assert_eq!(summary.text, "No Rust 2.0.");
+    assert!(summary.conversation_id.is_none());
+    let prompt = h.provider.requests()[0].last().unwrap().text();
+    assert!(prompt.starts_with("Page: A post\nURL: https://example.com/post"));
+
+    h.provider.reply("Editions.");
+    let answer = companion.answer(&page(), "What replaces it?").await.unwrap();
+    assert_eq!(answer.text, "Editions.");
+    assert!(companion.answer(&page(), " ").await.is_err());
+    // Summaries and answers leave nothing behind
+    assert!(chat.list_conversations().await.unwrap().is_empty());
+
+    h.provider.reply("It's about editions.");
+    let opened = companion.open(&page(), Some("What is it about?")).await.unwrap();
+    let conversation = chat.get_conversation(opened.conversation_id.as_deref().unwrap()).await.unwrap();
+    assert_eq!(conversation.title, "A post");
+    let roles: Vec<_> = conversation.messages.iter().map(|m| m.role.clone()).collect();
+    assert_eq!(roles, vec![MessageRole::User, MessageRole::User, MessageRole::Assistant]);
+    assert_eq!(conversation.messages[0].metadata.as_ref().unwrap()["browser_page"]["url"], "https://example.com/post");
+
+    let empty = PageSelection {
+        text: "  ".to_string(),
+        ..page()
+    };
+    assert!(companion.summarize(&empty).await.is_err());
+}
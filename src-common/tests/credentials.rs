@@ -0,0 +1,83 @@
+//! Credential checks: API errors are classified into statuses, statuses map
+//! to connection health, and results are reused only while fresh.
+
+use chrono::{Duration, Utc};
+use mcp_common::service::credentials::{classify, validate_key, ConnectionHealth, CredentialCheck, CredentialStatus};
+
+fn error(kind: &str, message: &str) -> String {
+    serde_json::json!({ "type": "error", "error": { "type": kind, "message": message } }).to_string()
+}
+
+#[test]
+fn api_errors_are_classified() {
+    let cases = [
+        (401, error("authentication_error", "invalid x-api-key"), CredentialStatus::InvalidKey),
+        (403, error("permission_error", "not allowed"), CredentialStatus::InvalidKey),
+        (429, error("rate_limit_error", "slow down"), CredentialStatus::RateLimited),
+        (400, error("billing_error", "pay up"), CredentialStatus::QuotaExhausted),
+        (402, String::new(), CredentialStatus::QuotaExhausted),
+        (
+            400,
+            error("invalid_request_error", "Your credit balance is too low"),
+            CredentialStatus::QuotaExhausted,
+        ),
+        (400, error("invalid_request_error", "bad field"), CredentialStatus::ServiceError),
+        (529, error("overloaded_error", "Overloaded"), CredentialStatus::ServiceError),
+    ];
+    for (status, body, expected) in cases {
+        assert_eq!(classify(status, &body).0, expected, "{} {}", status, body);
+    }
+
+    assert_eq!(classify(401, &error("authentication_error", "invalid x-api-key")).1, "invalid x-api-key");
+    // Bodies that aren't API errors fall back to the status code
+    assert_eq!(classify(503, "<html>down</html>"), (CredentialStatus::ServiceError, "HTTP 503".to_string()));
+}
+
+#[test]
+fn statuses_map_to_connection_health() {
+    use CredentialStatus::*;
+    assert_eq!(Valid.health(), ConnectionHealth::Healthy);
+    assert_eq!(RateLimited.health(), ConnectionHealth::Degraded);
+    assert_eq!(ServiceError.health(), ConnectionHealth::Degraded);
+    assert_eq!(Network.health(), ConnectionHealth::Offline);
+    for status in [Missing, InvalidKey, QuotaExhausted] {
+        assert_eq!(status.health(), ConnectionHealth::Unhealthy);
+    }
+
+    // Limits and empty accounts still prove the key exists
+    let all = [Valid, Missing, InvalidKey, QuotaExhausted, RateLimited, Network, ServiceError];
+    let accepted: Vec<CredentialStatus> = all
+        .into_iter()
+        .filter(|s| s.key_accepted())
+        .collect();
+    assert_eq!(accepted, vec![Valid, QuotaExhausted, RateLimited]);
+}
+
+#[test]
+fn failed_checks_expire_sooner_than_successful_ones() {
+    let check = |status, minutes_ago| CredentialCheck {
+        status,
+        message: String::new(),
+        checked_at: Utc::now() - Duration::minutes(minutes_ago),
+        latency_ms: None,
+        organization_id: None,
+    };
+    assert!(check(CredentialStatus::Valid, 5).is_fresh());
+    assert!(!check(CredentialStatus::Valid, 11).is_fresh());
+    assert!(check(CredentialStatus::Network, 0).is_fresh());
+    assert!(!check(CredentialStatus::Network, 2).is_fresh());
+}
+
+#[tokio::test]
+async fn missing_keys_and_unreachable_apis_are_reported() {
+    let missing = validate_key("https://api.anthropic.com", "").await;
+    assert_eq!(missing.status, CredentialStatus::Missing);
+    assert!(missing.latency_ms.is_none());
+
+    let invalid_url = validate_key("not a url", "sk-ant-test").await;
+    assert_eq!(invalid_url.status, CredentialStatus::Network);
+
+    let unreachable = validate_key("https://127.0.0.1:1", "sk-ant-test").await;
+    assert_eq!(unreachable.status, CredentialStatus::Network);
+    assert_eq!(unreachable.health(), ConnectionHealth::Offline);
+}
@@ -0,0 +1,79 @@
+//! Drafts: saved per conversation, cleared on send, and merged from other
+//! devices by age.
+
+use chrono::Duration;
+use mcp_common::config::Draft;
+use mcp_common::testing::TestHarness;
+use mcp_common::utils::clock;
+
+#[tokio::test]
+async fn drafts_are_kept_per_conversation_until_sent() {
+    let h = TestHarness::new();
+    let first = h.chat.create_conversation("First", None).await.unwrap();
+    let second = h.chat.create_conversation("Second", None).await.unwrap();
+
+    let draft = h.chat.set_draft(&first.id, "Half a thought").await.unwrap().unwrap();
+    assert_eq!(draft.text, "Half a thought");
+    assert_eq!(h.chat.get_draft(&first.id).await.unwrap(), Some(draft));
+    assert!(h.chat.get_draft(&second.id).await.unwrap().is_none());
+
+    // Blank input deletes the draft
+    h.chat.set_draft(&second.id, "Other").await.unwrap();
+    assert!(h.chat.set_draft(&second.id, "  \n").await.unwrap().is_none());
+    assert!(h.chat.get_draft(&second.id).await.unwrap().is_none());
+
+    h.provider.reply("Noted");
+    h.chat.send_message(&first.id, "Half a thought, finished").await.unwrap();
+    assert!(h.chat.get_draft(&first.id).await.unwrap().is_none());
+
+    // Streaming clears it too
+    h.chat.set_draft(&first.id, "Next").await.unwrap();
+    h.provider.stream(&["Sure"]);
+    let rx = h.chat.send_message_streaming(&first.id, "Next").await.unwrap();
+    TestHarness::drain(rx).await;
+    assert!(h.chat.get_draft(&first.id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn newer_drafts_from_other_devices_win() {
+    let h = TestHarness::new();
+    let conversation = h.chat.create_conversation("Synced", None).await.unwrap();
+    let local = h.chat.set_draft(&conversation.id, "Typed here").await.unwrap().unwrap();
+
+    let older = Draft {
+        text: "Typed on the phone earlier".to_string(),
+        updated_at: local.updated_at - Duration::minutes(1),
+        ..local.clone()
+    };
+    assert!(!h.chat.apply_draft(&older).await.unwrap());
+    assert_eq!(h.chat.get_draft(&conversation.id).await.unwrap(), Some(local.clone()));
+
+    let newer = Draft {
+        text: "Typed on the phone later".to_string(),
+        updated_at: clock::now() + Duration::minutes(1),
+        ..local.clone()
+    };
+    assert!(h.chat.apply_draft(&newer).await.unwrap());
+    assert_eq!(h.chat.get_draft(&conversation.id).await.unwrap(), Some(newer.clone()));
+
+    // A message sent on the phone clears the draft here
+    let sent = Draft {
+        text: String::new(),
+        updated_at: newer.updated_at + Duration::minutes(1),
+        ..newer
+    };
+    assert!(h.chat.apply_draft(&sent).await.unwrap());
+    assert!(h.chat.get_draft(&conversation.id).await.unwrap().is_none());
+}
+
+#[test]
+fn drafts_sync_under_their_conversation() {
+    let draft = Draft {
+        conversation_id: "abc".to_string(),
+        text: "Hi".to_string(),
+        updated_at: clock::now(),
+    };
+    assert_eq!(draft.sync_key(), "conversation:abc/draft");
+    assert!(Draft::is_sync_key("conversation:abc/draft"));
+    assert!(!Draft::is_sync_key("conversation:abc/read"));
+}
@@ -0,0 +1,35 @@
+//! Dry runs of tool calls: recording what calls would do and handing that
+//! to the model in place of a result.
+
+use mcp_common::models::ToolCall;
+use mcp_common::service::dry_run::DryRunLog;
+
+#[test]
+fn calls_are_recorded_per_conversation_instead_of_executed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dry_runs.json");
+    let log = DryRunLog::at(path.clone());
+    let call = ToolCall::new("call-1", "shell", serde_json::json!({ "command": "rm -rf build" }));
+
+    let simulated = log
+        .record("c1", &call, vec!["Run `rm -rf build` in a new terminal in /repo".to_string()])
+        .unwrap();
+    log.record("c2", &ToolCall::new("call-2", "git", serde_json::json!({ "action": "log" })), vec![])
+        .unwrap();
+
+    let result = simulated.result();
+    assert_eq!(result.tool_call_id, "call-1");
+    assert_eq!(result.name, "shell");
+    assert_eq!(result.result["simulated"], true);
+    assert_eq!(result.result["would"][0], "Run `rm -rf build` in a new terminal in /repo");
+
+    // The record survives a restart
+    let reopened = DryRunLog::at(path);
+    let calls = reopened.list("c1");
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].arguments["command"], "rm -rf build");
+
+    assert_eq!(reopened.clear("c1").unwrap(), 1);
+    assert!(reopened.list("c1").is_empty());
+    assert_eq!(reopened.list("c2").len(), 1);
+}
@@ -0,0 +1,96 @@
+//! Environment variables: workspace and conversation scopes, references in
+//! tool arguments and templates, and secrets kept in the vault and masked.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use mcp_common::auth::{set_secret_store, SecretStore};
+use mcp_common::environment::{EnvValue, EnvironmentStore, Scope, MASK};
+use mcp_common::error::McpResult;
+use mcp_common::logs::Redactor;
+use mcp_common::service::experiments::Variant;
+
+#[derive(Default)]
+struct MemorySecrets(Mutex<HashMap<String, String>>);
+
+impl SecretStore for MemorySecrets {
+    fn get(&self, key: &str) -> McpResult<Option<String>> {
+        Ok(self.0.lock().unwrap().get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &str) -> McpResult<()> {
+        self.0.lock().unwrap().insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> McpResult<()> {
+        self.0.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+#[test]
+fn conversations_see_their_workspace_variables_with_their_own_on_top() {
+    let secrets = Arc::new(MemorySecrets::default());
+    set_secret_store(secrets.clone());
+    let dir = tempfile::tempdir().unwrap();
+    let store = EnvironmentStore::at(dir.path().join("environment.json"));
+    let workspace = Scope::Workspace("acme".to_string());
+    let conversation = Scope::Conversation("c1".to_string());
+
+    store.set(&workspace, "API_BASE", "https://api.acme.test", false).unwrap();
+    store.set(&workspace, "DATASET", "/data/full.csv", false).unwrap();
+    store.set(&conversation, "DATASET", "/data/sample.csv", false).unwrap();
+    store.set(&conversation, "API_TOKEN", "tok-7f3a9c", true).unwrap();
+    assert!(store.set(&conversation, "not valid", "x", false).is_err());
+
+    // Only the vault holds the secret
+    let file = std::fs::read_to_string(dir.path().join("environment.json")).unwrap();
+    assert!(!file.contains("tok-7f3a9c"));
+    assert!(store.list(&conversation)["API_TOKEN"].is_secret());
+    assert_eq!(secrets.0.lock().unwrap().len(), 1);
+
+    let environment = store.resolve(Some("acme"), "c1").unwrap();
+    assert_eq!(environment.get("API_BASE"), Some("https://api.acme.test"));
+    assert_eq!(environment.get("DATASET"), Some("/data/sample.csv"));
+    assert_eq!(environment.get("API_TOKEN"), Some("tok-7f3a9c"));
+    assert_eq!(store.resolve(None, "c1").unwrap().get("API_BASE"), None);
+
+    // Tool arguments and prompt templates reference variables
+    let mut arguments = serde_json::json!({ "url": "{env.API_BASE}/v1", "files": ["{env.DATASET}", "{env.MISSING}"] });
+    environment.expand_json(&mut arguments);
+    assert_eq!(
+        arguments,
+        serde_json::json!({ "url": "https://api.acme.test/v1", "files": ["/data/sample.csv", "{env.MISSING}"] })
+    );
+    let variant = Variant {
+        name: "a".to_string(),
+        system_prompt: None,
+        prompt_template: Some("Using {env.DATASET}: {prompt}".to_string()),
+    };
+    assert_eq!(
+        variant.render_prompt_in("count rows of {env.DATASET}", &environment),
+        "Using /data/sample.csv: count rows of {env.DATASET}"
+    );
+
+    // Secrets are masked in exports, and in logs once read
+    assert_eq!(environment.mask("Bearer tok-7f3a9c sent"), format!("Bearer {} sent", MASK));
+    assert_eq!(environment.masked()["API_TOKEN"], MASK);
+    assert!(!Redactor::default().redact("using tok-7f3a9c").contains("tok-7f3a9c"));
+
+    // A secret made plain leaves the vault, as do the secrets of a cleared scope
+    store.set(&conversation, "API_TOKEN", "public", false).unwrap();
+    assert_eq!(
+        store.list(&conversation)["API_TOKEN"],
+        EnvValue::Plain {
+            value: "public".to_string()
+        }
+    );
+    assert!(secrets.0.lock().unwrap().is_empty());
+    store.set(&conversation, "API_TOKEN", "tok-8e2b", true).unwrap();
+    assert_eq!(store.clear(&conversation).unwrap(), 2);
+    assert!(secrets.0.lock().unwrap().is_empty());
+    assert!(store.list(&conversation).is_empty());
+    assert!(store.unset(&workspace, "DATASET").unwrap());
+    assert!(!store.unset(&workspace, "DATASET").unwrap());
+}
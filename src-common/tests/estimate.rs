@@ -0,0 +1,68 @@
+//! Pre-send cost estimates.
+
+use mcp_common::config::CostSettings;
+use mcp_common::models::{Conversation, Message, Model};
+use mcp_common::service::estimate::{estimate, format_cost};
+use mcp_common::service::pricing::PriceTable;
+
+fn prices(dir: &std::path::Path) -> PriceTable {
+    PriceTable::at(dir.join("pricing.json"))
+}
+
+fn settings(confirm_above: Option<f64>, currency: &str) -> CostSettings {
+    CostSettings {
+        confirm_above,
+        currency: currency.to_string(),
+        price_list_url: None,
+        monthly_budget: None,
+    }
+}
+
+#[test]
+fn estimates_use_history_and_typical_response_length() {
+    let dir = tempfile::tempdir().unwrap();
+    let table = prices(dir.path());
+    let mut conversation = Conversation::new("Chat", Model::claude("opus", "20240229"));
+    conversation.messages.push(Message::user("a".repeat(4000)));
+    conversation.messages.push(Message::assistant("b".repeat(4000)));
+
+    let expensive = estimate(&conversation, &"c".repeat(4000), &table, 4096, &settings(Some(0.05), "USD"));
+    assert_eq!(expensive.input_tokens, 3000);
+    assert_eq!(expensive.output_tokens, 1000);
+    // 3000 * $15 + 1000 * $75 per million tokens
+    assert!((expensive.cost.unwrap() - 0.12).abs() < 1e-9);
+    assert_eq!(expensive.label, "≈ $0.12");
+    assert!(expensive.needs_confirmation);
+
+    let capped = estimate(&conversation, "hi", &table, 100, &settings(None, "USD"));
+    assert_eq!(capped.output_tokens, 100);
+    assert!(!capped.needs_confirmation);
+}
+
+#[test]
+fn estimates_are_shown_in_the_chosen_currency() {
+    let dir = tempfile::tempdir().unwrap();
+    let table = prices(dir.path());
+    table.set_rate("EUR", 0.5).unwrap();
+    let mut conversation = Conversation::new("Chat", Model::claude("opus", "20240229"));
+    conversation.messages.push(Message::assistant("b".repeat(4000)));
+
+    // 2000 * $15 + 1000 * $75 per million tokens, at 0.5 EUR per dollar
+    let euros = estimate(&conversation, &"c".repeat(4000), &table, 4096, &settings(Some(0.05), "eur"));
+    assert_eq!(euros.currency, "EUR");
+    assert!((euros.cost.unwrap() - 0.0525).abs() < 1e-9);
+    assert!(euros.needs_confirmation);
+
+    let unknown = estimate(&conversation, "hi", &table, 4096, &settings(None, "XYZ"));
+    assert_eq!(unknown.currency, "USD");
+
+    let mut local = Model::claude("opus", "20240229");
+    local.id = "llama-3-8b".to_string();
+    local.provider = "local".to_string();
+    conversation.model = local;
+    assert_eq!(estimate(&conversation, "hi", &table, 4096, &settings(None, "USD")).label, "free");
+
+    assert_eq!(format_cost(None, "USD"), "≈ ?");
+    assert_eq!(format_cost(Some(0.004), "USD"), "< $0.01");
+    assert_eq!(format_cost(Some(1.5), "CHF"), "≈ CHF 1.50");
+}
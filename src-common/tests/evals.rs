@@ -0,0 +1,97 @@
+//! Eval suites: loading, checking responses, comparing runs and JUnit output.
+
+use mcp_common::service::evals::{
+    check_expectations, compare, load_suite, parse_verdict, CaseResult, EvalReport, Expectation, ModelEvaluation,
+};
+use std::fs;
+use std::time::SystemTime;
+
+fn case(id: &str, passed: bool) -> CaseResult {
+    CaseResult {
+        case_id: id.to_string(),
+        passed,
+        output: Some(format!("answer to {}", id)),
+        failures: if passed { Vec::new() } else { vec!["expected to contain \"x\"".to_string()] },
+        error: None,
+        duration_ms: 1200,
+    }
+}
+
+fn report(id: &str, cases: Vec<CaseResult>) -> EvalReport {
+    EvalReport {
+        id: id.to_string(),
+        suite: "support".to_string(),
+        started_at: SystemTime::now(),
+        app_version: "1.0.0".to_string(),
+        models: vec![ModelEvaluation {
+            model_id: "claude-3-haiku".to_string(),
+            provider: "claude".to_string(),
+            cases,
+        }],
+    }
+}
+
+#[test]
+fn suites_load_and_reject_cases_without_checks() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("suite.json");
+    fs::write(
+        &path,
+        r#"{"name": "support", "cases": [
+            {"id": "refund", "prompt": "How do I get a refund?", "expect": [{"contains": "30 days"}, {"regex": "^\\w"}]},
+            {"id": "tone", "prompt": "Hi", "judge": "Friendly"}
+        ]}"#,
+    )
+    .unwrap();
+    let suite = load_suite(&path).unwrap();
+    assert_eq!(suite.cases.len(), 2);
+    assert_eq!(suite.cases[0].expect[0], Expectation::Contains("30 days".to_string()));
+
+    fs::write(&path, r#"{"name": "s", "cases": [{"id": "a", "prompt": "Hi"}]}"#).unwrap();
+    assert!(load_suite(&path).is_err());
+
+    fs::write(&path, r#"{"name": "s", "cases": [{"id": "a", "prompt": "Hi", "expect": [{"regex": "("}]}]}"#).unwrap();
+    assert!(load_suite(&path).is_err());
+}
+
+#[test]
+fn expectations_and_verdicts() {
+    let expect = vec![
+        Expectation::Contains("30 DAYS".to_string()),
+        Expectation::NotContains("sorry".to_string()),
+        Expectation::Regex(r"form \d+".to_string()),
+    ];
+    assert!(check_expectations(&expect, "Refunds within 30 days via form 12").is_empty());
+    assert_eq!(check_expectations(&expect, "Sorry, within 30 days").len(), 2);
+
+    assert_eq!(parse_verdict("PASS\nIt is polite."), Some(true));
+    assert_eq!(parse_verdict("  **Fail**: rude"), Some(false));
+    assert_eq!(parse_verdict("Maybe"), None);
+}
+
+#[test]
+fn regressions_compare_with_the_previous_run_of_the_suite() {
+    let previous = report("1", vec![case("refund", true), case("tone", false)]);
+    let current = report("2", vec![case("refund", false), case("tone", true)]);
+
+    let comparisons = compare(&current, &[previous, current.clone()]);
+    assert_eq!(comparisons.len(), 1);
+    assert_eq!(comparisons[0].regressions, vec!["refund".to_string()]);
+    assert_eq!(comparisons[0].fixed, vec!["tone".to_string()]);
+    assert_eq!(comparisons[0].previous_pass_rate, 0.5);
+
+    let mut other = report("3", vec![case("refund", true)]);
+    other.suite = "other".to_string();
+    assert!(compare(&current, &[other]).is_empty());
+}
+
+#[test]
+fn junit_report_escapes_output() {
+    let mut failing = case("refund", false);
+    failing.output = Some("<b>\"no\" & never</b>".to_string());
+    let xml = report("1", vec![case("tone", true), failing]).to_junit();
+
+    assert!(xml.contains(r#"<testsuites name="support" tests="2" failures="1">"#));
+    assert!(xml.contains(r#"<testcase classname="claude-3-haiku" name="tone" time="1.200"/>"#));
+    assert!(xml.contains("&lt;b&gt;&quot;no&quot; &amp; never&lt;/b&gt;</failure>"));
+}
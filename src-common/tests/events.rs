@@ -0,0 +1,51 @@
+//! Event bus topics: subscribers only get the topics they asked for, and
+//! model events are published on the model topic.
+
+use mcp_common::events::{get_event_bus, names, Backpressure, EventBus, Topic};
+use mcp_common::models::registry::{CatalogFormat, CatalogTrust, ModelRegistry};
+use std::fs;
+use std::time::Duration;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn subscribers_only_get_their_topics() {
+    let bus = EventBus::new();
+    let mut models = bus.subscribe(&[Topic::Model], 8, Backpressure::DropNewest);
+    let mut system = bus.subscribe(&[Topic::System], 8, Backpressure::DropNewest);
+    let mut everything = bus.subscribe_all(8, Backpressure::DropNewest);
+
+    bus.emit(Topic::System, "system_event", serde_json::json!({}));
+    bus.emit(Topic::Model, "model_event", serde_json::json!({}));
+
+    let names = |events: Vec<mcp_common::events::Event>| events.into_iter().map(|e| e.name).collect::<Vec<_>>();
+    assert_eq!(names(models.drain()), vec!["model_event"]);
+    assert_eq!(names(system.drain()), vec!["system_event"]);
+    assert_eq!(names(everything.drain()), vec!["system_event", "model_event"]);
+}
+
+#[tokio::test]
+async fn catalog_changes_are_model_events() {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest = dir.path().join("hub.json");
+    fs::write(&manifest, serde_json::json!({ "name": "Hub", "models": [] }).to_string()).unwrap();
+    let registry = ModelRegistry::open(dir.path().join("catalogs.json"), dir.path().join("tokens"));
+
+    let mut models = get_event_bus().subscribe(&[Topic::Model], 64, Backpressure::DropNewest);
+    let mut system = get_event_bus().subscribe(&[Topic::System], 64, Backpressure::DropNewest);
+    registry
+        .add_catalog("Hub", &format!("file://{}", manifest.display()), CatalogFormat::Manifest, CatalogTrust::Trusted)
+        .unwrap();
+
+    let event = timeout(Duration::from_secs(5), async {
+        loop {
+            let event = models.recv().await.unwrap();
+            if event.name == names::MODEL_CATALOGS_CHANGED {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("catalog change not published on the model topic");
+    assert_eq!(event.topic, Topic::Model);
+    assert!(system.drain().iter().all(|e| e.name != names::MODEL_CATALOGS_CHANGED));
+}
@@ -0,0 +1,132 @@
+//! Prompt experiments: variants alternate across conversations and batches,
+//! ratings of enrolled conversations are counted, and the report compares
+//! the variants.
+
+use mcp_common::models::Rating;
+use mcp_common::service::batch::{BatchItem, BatchResult};
+use mcp_common::service::experiments::{ExperimentStore, Variant};
+
+fn variant(name: &str, template: Option<&str>) -> Variant {
+    Variant {
+        name: name.to_string(),
+        system_prompt: Some(format!("You are {}", name)),
+        prompt_template: template.map(String::from),
+    }
+}
+
+fn open_store(dir: &tempfile::TempDir) -> ExperimentStore {
+    ExperimentStore::at(dir.path().join("experiments.json"))
+}
+
+#[test]
+fn experiments_need_two_variants_with_valid_templates() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open_store(&dir);
+
+    assert!(store.create("Solo", vec![variant("a", None)]).is_err());
+    let error = store
+        .create("Broken", vec![variant("a", None), variant("b", Some("no placeholder"))])
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("variant 'b'"));
+    assert!(store.list().is_empty());
+}
+
+#[test]
+fn variants_alternate_across_new_conversations() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open_store(&dir);
+    assert!(store.enroll("c0").is_none());
+
+    store.create("Tone", vec![variant("plain", None), variant("terse", Some("Briefly: {prompt}"))]).unwrap();
+    let names: Vec<String> = ["c1", "c2", "c3"].iter().map(|c| store.enroll(c).unwrap().name).collect();
+    assert_eq!(names, vec!["plain", "terse", "plain"]);
+    assert_eq!(store.variant_for("c2").unwrap().render_prompt("Why?"), "Briefly: Why?");
+    assert!(store.variant_for("c0").is_none());
+
+    // Assignments survive a restart
+    assert_eq!(open_store(&dir).variant_for("c2").unwrap().name, "terse");
+
+    store.stop("Tone").unwrap();
+    assert!(store.enroll("c4").is_none());
+}
+
+#[test]
+fn a_new_experiment_replaces_the_active_one() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open_store(&dir);
+    store.create("First", vec![variant("a", None), variant("b", None)]).unwrap();
+    store.create("Second", vec![variant("x", None), variant("y", None)]).unwrap();
+
+    assert!(!store.get("First").unwrap().active);
+    assert_eq!(store.enroll("c1").unwrap().name, "x");
+}
+
+#[test]
+fn batches_alternate_variants_per_item() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open_store(&dir);
+    store.create("Batch", vec![variant("a", None), variant("b", Some("Q: {prompt}"))]).unwrap();
+
+    let mut items: Vec<BatchItem> = ["one", "two", "three"]
+        .iter()
+        .map(|p| BatchItem {
+            custom_id: p.to_string(),
+            prompt: p.to_string(),
+            model: None,
+            system: None,
+        })
+        .collect();
+    let variants = store.apply_to_batch("Batch", &mut items).unwrap();
+    assert_eq!(variants, vec![0, 1, 0]);
+    assert_eq!(items[1].prompt, "Q: two");
+    assert_eq!(items[1].system.as_deref(), Some("You are b"));
+    assert_eq!(items[2].prompt, "three");
+
+    let results: Vec<BatchResult> = items
+        .iter()
+        .map(|item| BatchResult {
+            custom_id: item.custom_id.clone(),
+            prompt: item.prompt.clone(),
+            output: Some("ok".to_string()),
+            error: None,
+            conversation_id: Some(format!("conv-{}", item.custom_id)),
+        })
+        .collect();
+    store.record_batch("Batch", &results, &variants).unwrap();
+    assert_eq!(store.variant_for("conv-two").unwrap().name, "b");
+    assert!(store.apply_to_batch("Missing", &mut items).is_err());
+}
+
+#[test]
+fn report_compares_approval_of_rated_variants() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open_store(&dir);
+    store.create("Tone", vec![variant("plain", None), variant("terse", None)]).unwrap();
+    for conversation in ["p1", "t1", "p2", "t2"] {
+        store.enroll(conversation).unwrap();
+    }
+
+    for (message, conversation, rating) in [
+        ("m1", "p1", Rating::Up),
+        ("m2", "p1", Rating::Up),
+        ("m3", "p2", Rating::Down),
+        ("m4", "t1", Rating::Down),
+        ("m5", "t2", Rating::Down),
+    ] {
+        store.record_rating(conversation, message, Some(rating)).unwrap();
+    }
+    // Clearing a rating removes it, and unenrolled conversations are ignored
+    store.record_rating("t2", "m6", Some(Rating::Up)).unwrap();
+    store.record_rating("t2", "m6", None).unwrap();
+    store.record_rating("elsewhere", "m7", Some(Rating::Up)).unwrap();
+
+    let report = store.report("Tone").unwrap();
+    let plain = &report.variants[0];
+    assert_eq!((plain.conversations, plain.up, plain.down), (2, 2, 1));
+    assert!((plain.approval_rate - 2.0 / 3.0).abs() < 1e-9);
+    let terse = &report.variants[1];
+    assert_eq!((terse.conversations, terse.up, terse.down), (2, 0, 2));
+    assert_eq!(report.leader.as_deref(), Some("plain"));
+    assert!(report.z_score.unwrap() > 0.0);
+}
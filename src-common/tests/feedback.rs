@@ -0,0 +1,65 @@
+//! Message feedback: ratings are stored on the message and announced, and
+//! rated responses export with the prompt that produced them.
+
+use mcp_common::events::{get_event_bus, names, Backpressure, Topic};
+use mcp_common::models::{Message, MessageRole, Rating};
+use mcp_common::service::feedback::{collect, export_jsonl, FeedbackRecord};
+use mcp_common::testing::TestHarness;
+
+#[tokio::test]
+async fn ratings_are_stored_and_announced() {
+    let h = TestHarness::new();
+    let conversation = h.chat.create_conversation("Rated", None).await.unwrap();
+    h.provider.reply("Paris");
+    let reply = h.chat.send_message(&conversation.id, "Capital of France?").await.unwrap();
+
+    let mut events = get_event_bus().subscribe(&[Topic::Conversation], 64, Backpressure::DropNewest);
+    let rated = h
+        .chat
+        .rate_message(&reply.id, Some(Rating::Down), Some("Too short".to_string()))
+        .await
+        .unwrap();
+    let feedback = rated.feedback.unwrap();
+    assert_eq!((feedback.rating, feedback.comment.as_deref()), (Rating::Down, Some("Too short")));
+
+    let stored = h.chat.get_conversation(&conversation.id).await.unwrap();
+    let stored_reply = stored.messages.iter().find(|m| m.id == reply.id).unwrap();
+    assert_eq!(stored_reply.feedback.as_ref().unwrap().rating, Rating::Down);
+    assert!(events
+        .drain()
+        .iter()
+        .any(|e| e.name == names::MESSAGE_RATED && e.payload["message_id"] == reply.id.as_str()));
+
+    // No rating clears the feedback
+    assert!(h.chat.rate_message(&reply.id, None, None).await.unwrap().feedback.is_none());
+    assert!(h.chat.rate_message("missing", Some(Rating::Up), None).await.is_err());
+}
+
+#[tokio::test]
+async fn rated_responses_export_with_their_prompt() {
+    let h = TestHarness::new();
+    let conversation = h.chat.create_conversation("Export", None).await.unwrap();
+    h.provider.reply("Four");
+    h.provider.reply("Six");
+    let first = h.chat.send_message(&conversation.id, "2 + 2?").await.unwrap();
+    h.chat.send_message(&conversation.id, "3 + 3?").await.unwrap();
+    h.chat.rate_message(&first.id, Some(Rating::Up), None).await.unwrap();
+
+    let mut conversation = h.chat.get_conversation(&conversation.id).await.unwrap();
+    conversation.messages.insert(0, Message::system("Answer with words"));
+    let records = collect(&conversation);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].prompt, "2 + 2?");
+    assert_eq!(records[0].response, "Four");
+    assert_eq!(records[0].system.as_deref(), Some("Answer with words"));
+    assert!(conversation.messages.iter().any(|m| m.role == MessageRole::Assistant && m.feedback.is_none()));
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("feedback.jsonl");
+    assert_eq!(export_jsonl(&[conversation], &path).unwrap(), 1);
+    let line = std::fs::read_to_string(&path).unwrap();
+    let record: FeedbackRecord = serde_json::from_str(line.trim()).unwrap();
+    assert_eq!((record.message_id, record.rating), (first.id, Rating::Up));
+    // Absent comments are left out of the line
+    assert!(!line.contains("comment"));
+}
@@ -0,0 +1,203 @@
+//! Feed digests: RSS and Atom parsing, filters, read state and delivery.
+
+use chrono::Duration;
+use mcp_common::feeds::{
+    parse_feed, DigestTarget, FeedDigester, FeedFilter, FeedStore, NewFeed, DIGEST_METADATA_KEY,
+};
+use mcp_common::models::MessageRole;
+use mcp_common::service::ChatService;
+use mcp_common::testing::TestHarness;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/">
+<channel>
+  <title>Rust &amp; Friends</title>
+  <link>https://example.com</link>
+  <item>
+    <title>Older post</title>
+    <link>https://example.com/older</link>
+    <guid>older</guid>
+    <pubDate>Mon, 01 Jan 2024 08:00:00 GMT</pubDate>
+    <description>Nothing much happened.</description>
+  </item>
+  <item>
+    <title><![CDATA[Rust 2.0 & editions]]></title>
+    <link>https://example.com/rust</link>
+    <guid isPermaLink="false">rust-2</guid>
+    <pubDate>Tue, 02 Jan 2024 08:00:00 GMT</pubDate>
+    <description>&lt;p&gt;A &lt;b&gt;long&lt;/b&gt; look at editions &amp;amp; more.&lt;/p&gt;</description>
+  </item>
+  <item>
+    <title>Sponsored: buy crabs</title>
+    <link>https://example.com/ad</link>
+    <description>An advert.</description>
+  </item>
+</channel>
+</rss>"#;
+
+const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title type="text">Release notes</title>
+  <entry>
+    <title>v1.2</title>
+    <id>urn:uuid:1</id>
+    <link rel="self" href="https://example.com/feed/1"/>
+    <link rel="alternate" href="https://example.com/releases/1.2"/>
+    <updated>2024-03-01T10:00:00Z</updated>
+    <summary type="html">Faster &lt;em&gt;startup&lt;/em&gt;</summary>
+  </entry>
+</feed>"#;
+
+/// Subscription settings with nothing filled in
+fn new_feed() -> NewFeed {
+    NewFeed {
+        url: String::new(),
+        title: None,
+        filter: FeedFilter::default(),
+        target: DigestTarget::Notification,
+        model: None,
+        interval_minutes: None,
+    }
+}
+
+/// Serve this body to each of `count` requests
+async fn serve(body: &'static str, count: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/feed.xml", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        for _ in 0..count {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    url
+}
+
+#[test]
+fn rss_and_atom_are_read_into_plain_items() {
+    let rss = parse_feed(RSS).unwrap();
+    assert_eq!(rss.title, "Rust & Friends");
+    assert_eq!(rss.items.len(), 3);
+    // Newest first
+    assert_eq!(rss.items[0].id, "rust-2");
+    assert_eq!(rss.items[0].title, "Rust 2.0 & editions");
+    assert_eq!(rss.items[0].summary, "A long look at editions & more.");
+    assert_eq!(rss.items[1].id, "older");
+    // Without a guid the link identifies the item
+    assert_eq!(rss.items[2].id, "https://example.com/ad");
+
+    let atom = parse_feed(ATOM).unwrap();
+    assert_eq!(atom.title, "Release notes");
+    assert_eq!(atom.items[0].id, "urn:uuid:1");
+    assert_eq!(atom.items[0].link.as_deref(), Some("https://example.com/releases/1.2"));
+    assert_eq!(atom.items[0].summary, "Faster startup");
+    assert!(atom.items[0].published.is_some());
+
+    assert!(parse_feed("<html><body>Not a feed</body></html>").is_err());
+}
+
+#[test]
+fn filters_and_read_state_pick_the_unread_items() {
+    let h = TestHarness::new();
+    let store = FeedStore::at(h.dir().join("feeds.json"));
+    let feed = store
+        .add(NewFeed {
+            url: "https://example.com/feed.xml".to_string(),
+            title: None,
+            filter: FeedFilter {
+                include: Vec::new(),
+                exclude: vec!["SPONSORED".to_string()],
+            },
+            target: DigestTarget::Notification,
+            model: None,
+            interval_minutes: Some(1),
+        })
+        .unwrap();
+    // Intervals have a floor
+    assert_eq!(feed.interval_minutes, 5);
+    assert!(store.add(NewFeed { url: "file:///etc/passwd".to_string(), ..new_feed() }).is_err());
+
+    let items = parse_feed(RSS).unwrap().items;
+    let unread: Vec<_> = feed.unread(&items).iter().map(|i| i.id.clone()).collect();
+    assert_eq!(unread, vec!["rust-2", "older"]);
+
+    let feed = store.mark_read(&feed.id[..8], vec!["older".to_string()]).unwrap();
+    assert_eq!(feed.unread(&items).len(), 1);
+
+    let include = FeedFilter {
+        include: vec!["long LOOK".to_string()],
+        exclude: Vec::new(),
+    };
+    assert!(include.matches(&items[0]));
+    assert!(!include.matches(&items[1]));
+
+    // The read state survives a restart
+    let reopened = FeedStore::at(h.dir().join("feeds.json"));
+    assert_eq!(reopened.get(&feed.id).unwrap().seen, vec!["older"]);
+    assert!(reopened.get(&feed.id).unwrap().is_due(TestHarness::epoch()));
+}
+
+#[tokio::test]
+async fn new_items_are_digested_into_the_conversation_once() {
+    let h = TestHarness::new();
+    let chat = Arc::new(ChatService::new(h.service.clone()));
+    let conversation = chat.create_conversation("News", None).await.unwrap();
+    let store = Arc::new(FeedStore::at(h.dir().join("feeds.json")));
+    let url = serve(RSS, 2).await;
+    let feed = store
+        .add(NewFeed {
+            url,
+            filter: FeedFilter {
+                include: Vec::new(),
+                exclude: vec!["sponsored".to_string()],
+            },
+            target: DigestTarget::Conversation {
+                conversation_id: conversation.id.clone(),
+            },
+            ..new_feed()
+        })
+        .unwrap();
+    let digester = FeedDigester::with_store(chat.clone(), store.clone());
+
+    h.provider.reply("- [Rust 2.0 & editions](https://example.com/rust): a long look.\n- Older post: nothing.");
+    let digests = digester.run_due().await;
+    assert_eq!(digests.len(), 1);
+    assert_eq!(digests[0].feed_title, "Rust & Friends");
+    assert_eq!(digests[0].items.len(), 2);
+
+    let prompt = h.provider.requests()[0].last().unwrap().text();
+    assert!(prompt.contains("Link: https://example.com/rust"));
+    assert!(!prompt.contains("advert"));
+
+    let conversation = chat.get_conversation(&conversation.id).await.unwrap();
+    let posted = conversation.messages.last().unwrap();
+    assert_eq!(posted.role, MessageRole::Assistant);
+    assert!(posted.text().starts_with("## Rust & Friends — 2 new items"));
+    assert_eq!(posted.metadata.as_ref().unwrap()[DIGEST_METADATA_KEY]["items"][0], "rust-2");
+    // The summarizing conversation is gone
+    assert_eq!(chat.list_conversations().await.unwrap().len(), 1);
+
+    let feed = store.get(&feed.id).unwrap();
+    assert_eq!(feed.title, "Rust & Friends");
+    assert!(feed.last_error.is_none());
+
+    // Not due again until the interval has passed, and then nothing is new
+    assert!(digester.run_due().await.is_empty());
+    h.clock.advance(Duration::minutes(61));
+    assert!(digester.run(&feed.id).await.unwrap().is_none());
+    assert_eq!(h.provider.requests().len(), 1);
+}
@@ -0,0 +1,194 @@
+//! GitHub: references, snippets, caching, the rate limit and importing threads.
+
+use base64::Engine;
+use chrono::Duration;
+use mcp_common::error::McpError;
+use mcp_common::integrations::github::{GitHubClient, GitHubRef, ThreadKind};
+use mcp_common::integrations::{import_context, CONTEXT_METADATA_KEY};
+use mcp_common::testing::TestHarness;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A canned response: status, extra headers and JSON body
+type Answer = (&'static str, Vec<(&'static str, String)>, String);
+
+/// Answer requests in order from `answers`, recording each request's headers
+async fn serve(answers: Vec<Answer>) -> (String, Arc<Mutex<Vec<HashMap<String, String>>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let record = seen.clone();
+
+    tokio::spawn(async move {
+        for (status, headers, body) in answers {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let head = String::from_utf8_lossy(&request).to_string();
+            let head = head.split("\r\n\r\n").next().unwrap();
+            record.lock().unwrap().push(
+                head.lines()
+                    .map(|line| line.split_once(": ").map_or(("request-line", line), |(k, v)| (k, v)))
+                    .map(|(k, v)| (k.to_lowercase(), v.to_string()))
+                    .collect(),
+            );
+
+            let extra: String = headers.iter().map(|(k, v)| format!("{}: {}\r\n", k, v)).collect();
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                extra,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    (url, seen)
+}
+
+fn rate(remaining: u64) -> Vec<(&'static str, String)> {
+    let reset = (TestHarness::epoch() + Duration::hours(1)).timestamp();
+    vec![
+        ("X-RateLimit-Limit", "60".to_string()),
+        ("X-RateLimit-Remaining", remaining.to_string()),
+        ("X-RateLimit-Reset", reset.to_string()),
+    ]
+}
+
+fn file(text: &str) -> String {
+    serde_json::json!({
+        "type": "file",
+        "encoding": "base64",
+        "content": base64::engine::general_purpose::STANDARD.encode(text),
+        "html_url": "https://github.com/octo/app/blob/main/src/lib.rs",
+    })
+    .to_string()
+}
+
+#[test]
+fn references_are_read_from_links_and_shorthands() {
+    let file_link: GitHubRef = "https://github.com/octo/app/blob/main/src/lib.rs#L10-L20".parse().unwrap();
+    assert_eq!(
+        file_link,
+        GitHubRef::File {
+            owner: "octo".to_string(),
+            repo: "app".to_string(),
+            path: "src/lib.rs".to_string(),
+            reference: Some("main".to_string()),
+            lines: Some((10, 20)),
+        }
+    );
+    assert!(matches!(
+        "https://github.com/octo/app/pull/7/files".parse::<GitHubRef>().unwrap(),
+        GitHubRef::PullRequest { number: 7, .. }
+    ));
+    assert!(matches!("octo/app#12".parse::<GitHubRef>().unwrap(), GitHubRef::Issue { number: 12, .. }));
+
+    let short: GitHubRef = "octo/app/src/main.rs@v1.2#L5".parse().unwrap();
+    assert!(matches!(
+        short,
+        GitHubRef::File { ref path, ref reference, lines: Some((5, 5)), .. }
+            if path == "src/main.rs" && reference.as_deref() == Some("v1.2")
+    ));
+
+    assert!(matches!("not a reference".parse::<GitHubRef>(), Err(McpError::InvalidRequest(_))));
+}
+
+#[tokio::test]
+async fn snippets_are_cached_and_revalidated_with_etags() {
+    let h = TestHarness::new();
+    let text: String = (1..=30).map(|n| format!("line {}\n", n)).collect();
+    let mut first = rate(59);
+    first.push(("ETag", "\"abc\"".to_string()));
+    let (url, seen) = serve(vec![
+        ("200 OK", first, file(&text)),
+        ("304 Not Modified", rate(59), String::new()),
+    ])
+    .await;
+    let client = GitHubClient::new(Some("ghp_test".to_string())).with_api_url(&url);
+
+    let snippet = client.snippet("octo", "app", "src/lib.rs", Some("main"), Some((10, 12))).await.unwrap();
+    assert_eq!(snippet.content, "line 10\nline 11\nline 12");
+    assert_eq!(snippet.total_lines, 30);
+    assert_eq!(snippet.url, "https://github.com/octo/app/blob/main/src/lib.rs#L10-L12");
+    assert!(snippet.to_markdown().contains("```rust\nline 10"));
+
+    // Fresh answers don't need a request
+    client.snippet("octo", "app", "src/lib.rs", Some("main"), Some((1, 2))).await.unwrap();
+    assert_eq!(seen.lock().unwrap().len(), 1);
+
+    // Older ones are revalidated
+    h.clock.advance(Duration::minutes(10));
+    let again = client.snippet("octo", "app", "src/lib.rs", Some("main"), None).await.unwrap();
+    assert_eq!(again.end, 30);
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 2);
+    assert_eq!(seen[0]["authorization"], "Bearer ghp_test");
+    assert!(seen[0]["request-line"].contains("/repos/octo/app/contents/src/lib.rs?ref=main"));
+    assert_eq!(seen[1]["if-none-match"], "\"abc\"");
+    assert_eq!(client.rate_limit().unwrap().remaining, 59);
+}
+
+#[tokio::test]
+async fn requests_stop_when_the_rate_limit_is_used_up() {
+    let _h = TestHarness::new();
+    let (url, seen) = serve(vec![("200 OK", rate(0), file("fn main() {}\n"))]).await;
+    let client = GitHubClient::new(None).with_api_url(&url);
+
+    client.snippet("octo", "app", "src/main.rs", None, None).await.unwrap();
+    // Cached answers are still there
+    client.snippet("octo", "app", "src/main.rs", None, None).await.unwrap();
+
+    let result = client.snippet("octo", "app", "src/other.rs", None, None).await;
+    assert!(matches!(result, Err(McpError::RateLimit(_))));
+    assert_eq!(seen.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn issues_are_imported_into_conversations() {
+    let h = TestHarness::new();
+    let issue = serde_json::json!({
+        "title": "Crash on start",
+        "state": "open",
+        "user": { "login": "mona" },
+        "html_url": "https://github.com/octo/app/issues/3",
+        "body": "It crashes when the config is empty.",
+        "labels": [{ "name": "bug" }],
+        "comments": 1,
+    });
+    let comments = serde_json::json!([{
+        "user": { "login": "hubot" },
+        "body": "Reproduced on 1.2.",
+        "created_at": "2024-01-02T10:00:00Z",
+    }]);
+    let (url, _) = serve(vec![
+        ("200 OK", rate(58), issue.to_string()),
+        ("200 OK", rate(57), comments.to_string()),
+    ])
+    .await;
+    let client = GitHubClient::new(None).with_api_url(&url);
+
+    let thread = client.issue("octo", "app", 3).await.unwrap();
+    assert_eq!(thread.kind, ThreadKind::Issue);
+    assert_eq!(thread.labels, vec!["bug"]);
+    assert_eq!(thread.comments.len(), 1);
+
+    let conversation = h.chat.create_conversation("Crash", None).await.unwrap();
+    let message = import_context(&h.chat, &conversation.id, &thread.to_context()).await.unwrap();
+    assert!(message.text().contains("## Issue octo/app#3: Crash on start"));
+    assert!(message.text().contains("**@hubot** on 2024-01-02:\nReproduced on 1.2."));
+
+    let stored = h.chat.get_conversation(&conversation.id).await.unwrap();
+    let imported = stored.messages.last().unwrap();
+    assert_eq!(imported.metadata.as_ref().unwrap()[CONTEXT_METADATA_KEY]["reference"], "octo/app#3");
+    // Importing doesn't ask the model anything
+    assert!(h.provider.requests().is_empty());
+}
@@ -0,0 +1,104 @@
+//! Constrained output: GBNF grammars, JSON schemas turned into grammars,
+//! and checking model output against them.
+
+use mcp_common::models::grammar::{self, validate};
+use mcp_common::models::{Grammar, OutputFormat};
+use serde_json::json;
+
+fn person_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "minLength": 1 },
+            "age": { "type": "integer", "minimum": 0 },
+            "email": { "type": "string", "pattern": "^[^@]+@[^@]+$" },
+            "tags": { "type": "array", "items": { "enum": ["admin", "guest"] }, "maxItems": 2 }
+        },
+        "required": ["name", "age"],
+        "additionalProperties": false
+    })
+}
+
+#[test]
+fn grammars_accept_only_their_language() {
+    let grammar = Grammar::parse(
+        r#"
+        # A yes/no answer with an optional reason
+        root   ::= answer ( ": " reason )?
+        answer ::= "yes" | "no"
+        reason ::= [a-z ]{1,20}
+        "#,
+    )
+    .unwrap();
+
+    assert!(grammar.matches("yes"));
+    assert!(grammar.matches("no: too late"));
+    assert!(!grammar.matches("maybe"));
+    assert!(!grammar.matches("no: "));
+    assert!(!grammar.matches("yes: this reason is far too long to fit"));
+    assert!(grammar.matches(&grammar.sample().unwrap()));
+}
+
+#[test]
+fn broken_grammars_are_rejected() {
+    assert!(Grammar::parse(r#"answer ::= "yes""#).is_err());
+    assert!(Grammar::parse(r#"root ::= answer"#).is_err());
+    assert!(Grammar::parse(r#"root ::= "unterminated"#).is_err());
+    assert!(Grammar::parse("root ::= [a-z]{3,1}").is_err());
+}
+
+#[test]
+fn schemas_become_grammars() {
+    let grammar = Grammar::from_json_schema(&person_schema()).unwrap();
+
+    // Members come in the order of the schema's properties
+    assert!(grammar.matches(r#"{"age": 36, "name": "Ada"}"#));
+    assert!(grammar.matches(r#"{"age":36,"name":"Ada","tags":["admin","guest"]}"#));
+    assert!(!grammar.matches(r#"{"age": 36}"#));
+    assert!(!grammar.matches(r#"{"age": "old", "name": "Ada"}"#));
+    assert!(!grammar.matches(r#"{"age": 36, "name": "Ada", "tags": ["root"]}"#));
+    assert!(!grammar.matches(r#"{"age": 36, "name": "Ada", "tags": ["admin", "guest", "admin"]}"#));
+
+    // Simulated decoding produces an example of the schema
+    let example = grammar::example(&person_schema()).unwrap();
+    assert!(grammar.matches(&example.to_string()));
+    assert!(validate(&person_schema(), &example).is_empty());
+
+    assert!(Grammar::from_json_schema(&json!({ "$ref": "#/definitions/person" })).is_err());
+}
+
+#[test]
+fn schemas_with_a_numeric_type_are_rejected() {
+    let schema = json!({ "type": 5 });
+    assert!(grammar::example(&schema).unwrap_err().to_string().contains("type must be a string or a list"));
+    assert!(Grammar::from_json_schema(&schema).is_err());
+
+    let nested = json!({ "type": "object", "properties": { "age": { "type": 5 } } });
+    assert!(grammar::example(&nested).is_err());
+}
+
+#[test]
+fn results_are_validated_against_the_schema() {
+    let errors = validate(
+        &person_schema(),
+        &json!({ "name": "", "age": -1, "email": "nobody", "nickname": "A" }),
+    );
+    assert_eq!(
+        errors,
+        vec![
+            "/age: less than 0",
+            "/email: doesn't match ^[^@]+@[^@]+$",
+            "/name: shorter than 1 characters",
+            "/nickname: not allowed",
+        ]
+    );
+
+    let format = OutputFormat::JsonSchema { schema: person_schema() };
+    assert_eq!(
+        format.check(r#"{"name": "Ada", "age": 36}"#).unwrap(),
+        Some(json!({ "name": "Ada", "age": 36 }))
+    );
+    assert!(format.check(r#"{"name": "Ada", "age": 36.5}"#).is_err());
+    assert!(format.check("Sure! Here is the JSON you asked for").is_err());
+    assert!(OutputFormat::Text.check("anything").unwrap().is_none());
+}
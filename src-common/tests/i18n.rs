@@ -0,0 +1,62 @@
+//! Localization: requested locales are matched to a catalog, plurals and
+//! arguments are formatted, and every catalog has the same messages.
+
+use mcp_common::i18n::{available_locales, current_locale, set_locale, FluentArgs, Localizer, FALLBACK_LOCALE};
+use std::collections::BTreeSet;
+
+fn message_ids(locale: &str) -> BTreeSet<String> {
+    let path = format!("{}/locales/{}/main.ftl", env!("CARGO_MANIFEST_DIR"), locale);
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .filter(|line| line.starts_with(|c: char| c.is_ascii_lowercase()))
+        .filter_map(|line| line.split_once(" =").map(|(id, _)| id.to_string()))
+        .collect()
+}
+
+#[test]
+fn requested_locales_are_matched_to_a_catalog() {
+    assert_eq!(Localizer::new("de").locale(), "de");
+    assert_eq!(Localizer::new("de-AT").locale(), "de");
+    assert_eq!(Localizer::new("de_DE.UTF-8@euro").locale(), "de");
+    assert_eq!(Localizer::new("fr-FR").locale(), FALLBACK_LOCALE);
+    assert_eq!(Localizer::new("not a locale").locale(), FALLBACK_LOCALE);
+}
+
+#[test]
+fn messages_are_formatted_with_plurals_and_fall_back_to_english() {
+    let german = Localizer::new("de");
+    let mut args = FluentArgs::new();
+    args.set("count", 1);
+    assert_eq!(german.message("trash-purged", Some(&args)), "Eine Unterhaltung endgültig gelöscht");
+    args.set("count", 3);
+    assert_eq!(german.message("trash-purged", Some(&args)), "3 Unterhaltungen endgültig gelöscht");
+
+    let english = Localizer::new("en");
+    assert_eq!(english.message("trash-purged", Some(&args)), "Purged 3 conversations");
+    // Arguments are inserted without isolation marks
+    let mut args = FluentArgs::new();
+    args.set("details", "disk full");
+    assert_eq!(english.message("error-io", Some(&args)), "I/O error: disk full");
+
+    assert_eq!(german.message("no-such-message", None), "no-such-message");
+}
+
+#[test]
+fn catalogs_have_the_same_messages() {
+    let english = message_ids("en");
+    assert!(!english.is_empty());
+    for locale in available_locales() {
+        assert_eq!(message_ids(locale), english, "{}", locale);
+    }
+}
+
+#[test]
+fn the_locale_can_be_switched() {
+    assert_eq!(set_locale("de-CH"), "de");
+    assert_eq!(current_locale(), "de");
+    assert_eq!(mcp_common::tr!("trash-purged", count = 2), "2 Unterhaltungen endgültig gelöscht");
+
+    assert_eq!(set_locale("xx"), FALLBACK_LOCALE);
+    assert_eq!(mcp_common::tr!("conversation-trashed"), "Conversation moved to the trash");
+}
@@ -0,0 +1,105 @@
+//! Incoming messages: body and email parsing, route tokens and delivery.
+
+use mcp_common::error::McpError;
+use mcp_common::models::MessageRole;
+use mcp_common::service::ChatService;
+use mcp_common::testing::TestHarness;
+use mcp_common::webhooks::inbound::{parse_body, parse_email, InboundGateway, InboundStore};
+use std::sync::Arc;
+
+const FORWARDED: &str = "From: Ada <ada@example.com>\r\n\
+Subject: Server\r\n  is down\r\n\
+Content-Type: multipart/alternative; boundary=\"b1\"\r\n\
+\r\n\
+--b1\r\n\
+Content-Type: text/html\r\n\
+\r\n\
+<p>HTML version</p>\r\n\
+--b1\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+The API answers 503 since 10:00 =E2=80=93 please look=\r\n\
+\x20into it.\r\n\
+-- \r\n\
+Ada\r\n\
+--b1--\r\n";
+
+#[test]
+fn emails_yield_the_plain_text_without_signature() {
+    let message = parse_email(FORWARDED).unwrap();
+    assert_eq!(message.from.as_deref(), Some("Ada <ada@example.com>"));
+    assert_eq!(message.subject.as_deref(), Some("Server is down"));
+    assert_eq!(message.text, "The API answers 503 since 10:00 \u{2013} please look into it.");
+    assert!(message.to_prompt().starts_with("From: Ada <ada@example.com>\nSubject: Server is down\n\nThe API"));
+
+    // Without a plain part, the HTML one is used with its tags removed
+    let html = "Subject: Hi\nContent-Type: text/html\n\n<p>Hello <b>there</b></p>\n";
+    assert_eq!(parse_email(html).unwrap().text, "Hello there");
+}
+
+#[test]
+fn bodies_are_read_by_content_type() {
+    let json = parse_body("application/json", br#"{"text": "Deploy finished", "subject": "CI"}"#).unwrap();
+    assert_eq!(json.text, "Deploy finished");
+    assert_eq!(json.subject.as_deref(), Some("CI"));
+
+    let plain = parse_body("text/plain; charset=utf-8", b"Build 42 failed").unwrap();
+    assert_eq!(plain.to_prompt(), "Build 42 failed");
+
+    assert!(parse_body("message/rfc822", FORWARDED.as_bytes()).is_ok());
+    assert!(parse_body("image/png", b"\x89PNG").is_err());
+    assert!(parse_body("text/plain", b"   ").is_err());
+    assert!(parse_body("application/json", b"{}").is_err());
+}
+
+#[test]
+fn routes_check_their_tokens() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = InboundStore::at(dir.path().join("inbound.json"));
+
+    assert!(store.add("bad name", "c1", false).is_err());
+    let route = store.add("alerts", "c1", false).unwrap();
+    assert!(route.token.starts_with("inb_"));
+    assert!(store.add("alerts", "c2", false).is_err());
+
+    assert!(store.authenticate("alerts", &route.token).is_some());
+    assert!(store.authenticate("alerts", "inb_wrong").is_none());
+    assert!(store.authenticate("other", &route.token).is_none());
+
+    let rotated = store.rotate_token("alerts").unwrap();
+    assert!(store.authenticate("alerts", &route.token).is_none());
+    let store = InboundStore::at(dir.path().join("inbound.json"));
+    assert!(store.authenticate("alerts", &rotated.token).is_some());
+    store.remove("alerts").unwrap();
+    assert!(store.list().is_empty());
+}
+
+#[tokio::test]
+async fn messages_are_appended_or_answered() {
+    let h = TestHarness::new();
+    let chat = Arc::new(ChatService::new(h.service.clone()));
+    let conversation = chat.create_conversation("Alerts", None).await.unwrap();
+
+    let store = Arc::new(InboundStore::at(h.dir().join("inbound.json")));
+    let quiet = store.add("log", &conversation.id, false).unwrap();
+    let ask = store.add("ask", &conversation.id, true).unwrap();
+    let gateway = InboundGateway::new(store, chat.clone());
+
+    let message = parse_body("text/plain", b"Disk at 91%").unwrap();
+    let receipt = gateway.deliver("log", &quiet.token, &message).await.unwrap();
+    assert!(receipt.reply.is_none());
+    assert!(h.provider.requests().is_empty());
+
+    h.provider.reply("Clean up the logs");
+    let receipt = gateway.deliver("ask", &ask.token, &message).await.unwrap();
+    assert_eq!(receipt.reply.as_deref(), Some("Clean up the logs"));
+
+    let stored = chat.get_conversation(&conversation.id).await.unwrap();
+    let roles: Vec<_> = stored.messages.iter().map(|m| m.role.clone()).collect();
+    assert_eq!(roles, vec![MessageRole::User, MessageRole::User, MessageRole::Assistant]);
+    assert_eq!(stored.messages[0].text(), "Disk at 91%");
+
+    let refused = gateway.deliver("ask", &quiet.token, &message).await;
+    assert!(matches!(refused, Err(McpError::Authentication(_))));
+}
@@ -0,0 +1,94 @@
+//! Request inspector: exchanges are captured with credentials redacted,
+//! bodies are capped and only the most recent ones are kept.
+
+use mcp_common::config::get_settings;
+use mcp_common::platform::fs::set_portable_dir;
+use mcp_common::protocol::inspector::{self, get_inspector, Transport};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Answer every request with the same JSON body until the test ends
+async fn serve(body: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 8192];
+            let _ = stream.read(&mut buf).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nSet-Cookie: session=s3cr3t\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    base
+}
+
+// The inspector and settings are global, so the steps run in one test
+#[tokio::test]
+async fn exchanges_are_captured_redacted_and_capped() {
+    let dir = tempfile::tempdir().unwrap();
+    set_portable_dir(dir.path().to_path_buf()).unwrap();
+    let base = serve(r#"{"access_token":"at-123","model":"claude"}"#).await;
+    let client = reqwest::Client::new();
+
+    // Nothing is captured until the inspector is turned on
+    inspector::send(client.get(format!("{}/off", base))).await.unwrap();
+    assert!(get_inspector().recent(10).is_empty());
+    {
+        let settings = get_settings();
+        let mut settings = settings.lock().unwrap();
+        settings.debug.inspector = true;
+        settings.debug.inspector_capacity = 3;
+        settings.debug.inspector_max_body = 64;
+    }
+
+    let request = client
+        .post(format!("{}/v1/messages?token=q-456&beta=1", base))
+        .header("x-api-key", "sk-789")
+        .json(&serde_json::json!({ "api_key": "sk-789", "prompt": "x".repeat(100) }));
+    let response = inspector::send(request).await.unwrap();
+    // The caller still gets the whole response
+    assert_eq!(response.text().await.unwrap(), r#"{"access_token":"at-123","model":"claude"}"#);
+
+    let exchange = get_inspector().recent(1).remove(0);
+    assert_eq!(exchange.transport, Transport::Http);
+    assert_eq!((exchange.method.as_str(), exchange.status), ("POST", Some(200)));
+    assert_eq!(exchange.url, format!("{}/v1/messages?token=%5Bredacted%5D&beta=1", base));
+    let captured = serde_json::to_string(&exchange).unwrap();
+    for secret in ["sk-789", "q-456", "at-123", "s3cr3t"] {
+        assert!(!captured.contains(secret), "{} was captured", secret);
+    }
+    let body = exchange.request.unwrap().body.unwrap();
+    assert!(body.truncated);
+    assert_eq!(body.text.len(), 64);
+    assert!(body.size > 100);
+
+    // Form bodies, as sent to token endpoints, are redacted too
+    let form = client
+        .post(format!("{}/token", base))
+        .form(&[("grant_type", "refresh_token"), ("refresh_token", "rt-000")]);
+    inspector::send(form).await.unwrap();
+    let exchange = get_inspector().recent(1).remove(0);
+    assert_eq!(
+        exchange.request.unwrap().body.unwrap().text,
+        "grant_type=refresh_token&refresh_token=%5Bredacted%5D"
+    );
+
+    // Only the most recent exchanges are kept, numbered in order
+    inspector::record_frame(true, "wss://example.com/mcp", r#"{"type":"ping"}"#);
+    inspector::record_frame(false, "wss://example.com/mcp", r#"{"type":"pong"}"#);
+    let recent = get_inspector().recent(10);
+    assert_eq!(recent.iter().map(|e| e.id).collect::<Vec<_>>(), vec![4, 3, 2]);
+    assert_eq!(recent[0].method, "RECEIVE");
+    assert!(recent[0].request.is_none());
+    assert_eq!(get_inspector().get(3).unwrap().method, "SEND");
+    assert!(get_inspector().get(1).is_none());
+    assert!(dir.path().join("data").join("inspector.json").exists());
+
+    get_inspector().clear();
+    assert!(get_inspector().recent(10).is_empty());
+}
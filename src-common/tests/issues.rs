@@ -0,0 +1,222 @@
+//! Jira and Linear issues: references, the APIs, field selection and caching.
+
+use async_trait::async_trait;
+use chrono::Duration;
+use mcp_common::error::{McpError, McpResult};
+use mcp_common::integrations::issues::{
+    Issue, IssueField, IssueImporter, IssueRef, IssueSource, IssueSummary, IssueTracker, JiraClient, LinearClient,
+};
+use mcp_common::integrations::{import_context, CONTEXT_METADATA_KEY};
+use mcp_common::testing::TestHarness;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Answer requests in order with `200 OK` and these JSON bodies, recording
+/// each request line and `Authorization` header
+async fn serve(bodies: Vec<String>) -> (String, Arc<Mutex<Vec<(String, String)>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let record = seen.clone();
+
+    tokio::spawn(async move {
+        for body in bodies {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let end = request.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+            let head = String::from_utf8_lossy(&request[..end]).to_string();
+            let header = |name: &str| {
+                head.lines()
+                    .find_map(|l| l.split_once(':').filter(|(k, _)| k.eq_ignore_ascii_case(name)))
+                    .map(|(_, v)| v.trim().to_string())
+                    .unwrap_or_default()
+            };
+            // Read the body too, so closing doesn't reset the connection
+            let length: usize = header("content-length").parse().unwrap_or(0);
+            while request.len() < end + length {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request_line = head.lines().next().unwrap_or_default().to_string();
+            record.lock().unwrap().push((request_line, header("authorization")));
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    (url, seen)
+}
+
+/// Hands out one canned issue and counts how often it is asked
+struct FakeTracker {
+    fetches: AtomicUsize,
+}
+
+#[async_trait]
+impl IssueSource for FakeTracker {
+    fn tracker(&self) -> IssueTracker {
+        IssueTracker::Linear
+    }
+
+    async fn issue(&self, key: &str) -> McpResult<Issue> {
+        self.fetches.fetch_add(1, Ordering::SeqCst);
+        Ok(Issue {
+            tracker: IssueTracker::Linear,
+            key: key.to_string(),
+            title: "Login fails".to_string(),
+            url: format!("https://linear.app/acme/issue/{}", key),
+            status: Some("In Progress".to_string()),
+            assignee: None,
+            labels: vec!["bug".to_string()],
+            description: "Users on SSO can't log in.".to_string(),
+            comments: Vec::new(),
+            pull_requests: Vec::new(),
+        })
+    }
+
+    async fn search(&self, _query: &str, _limit: usize) -> McpResult<Vec<IssueSummary>> {
+        Ok(Vec::new())
+    }
+}
+
+#[test]
+fn references_are_read_from_links_and_keys() {
+    let jira: IssueRef = "https://acme.atlassian.net/browse/OPS-12".parse().unwrap();
+    assert_eq!(jira.tracker, Some(IssueTracker::Jira));
+    assert_eq!(jira.key, "OPS-12");
+    assert_eq!(jira.site.as_deref(), Some("https://acme.atlassian.net"));
+
+    let board: IssueRef = "https://acme.atlassian.net/jira/software/projects/OPS/boards/1?selectedIssue=OPS-7"
+        .parse()
+        .unwrap();
+    assert_eq!(board.key, "OPS-7");
+
+    let linear: IssueRef = "https://linear.app/acme/issue/ENG-42/login-fails".parse().unwrap();
+    assert_eq!(linear.tracker, Some(IssueTracker::Linear));
+    assert_eq!(linear.key, "ENG-42");
+
+    let bare: IssueRef = "eng-42".parse().unwrap();
+    assert_eq!(bare.tracker, None);
+    assert_eq!(bare.key, "ENG-42");
+
+    assert!("ENG42".parse::<IssueRef>().is_err());
+    assert_eq!(
+        IssueField::parse_list("description, prs").unwrap(),
+        vec![IssueField::Description, IssueField::PullRequests]
+    );
+    assert!(IssueField::parse_list("watchers").is_err());
+}
+
+#[tokio::test]
+async fn jira_issues_come_with_comments_and_linked_pull_requests() {
+    let issue = serde_json::json!({
+        "key": "OPS-12",
+        "fields": {
+            "summary": "Disk fills up",
+            "description": "The log volume fills up every night.",
+            "status": { "name": "To Do" },
+            "assignee": { "displayName": "Ana" },
+            "labels": ["infra"],
+            "comment": { "comments": [
+                { "author": { "displayName": "Bo" }, "body": "Rotation is off.", "created": "2024-01-03T09:00:00.000+0000" }
+            ]},
+        },
+    });
+    let links = serde_json::json!([
+        { "object": { "url": "https://github.com/acme/ops/pull/9", "title": "Enable log rotation" } },
+        { "object": { "url": "https://wiki.acme.com/runbook", "title": "Runbook" } },
+    ]);
+    let (url, seen) = serve(vec![issue.to_string(), links.to_string()]).await;
+
+    let jira = JiraClient::new(&url, Some("me@acme.com".to_string()), "secret".to_string());
+    let issue = jira.issue("OPS-12").await.unwrap();
+    assert_eq!(issue.title, "Disk fills up");
+    assert_eq!(issue.assignee.as_deref(), Some("Ana"));
+    assert_eq!(issue.comments[0].author, "Bo");
+    assert_eq!(issue.pull_requests.len(), 1);
+    assert_eq!(issue.pull_requests[0].title, "Enable log rotation");
+    assert_eq!(issue.url, format!("{}/browse/OPS-12", url));
+
+    let seen = seen.lock().unwrap();
+    assert!(seen[0].0.starts_with("GET /rest/api/2/issue/OPS-12?fields="));
+    assert!(seen[1].0.starts_with("GET /rest/api/2/issue/OPS-12/remotelink"));
+    // Email and API token as basic auth
+    assert_eq!(seen[0].1, "Basic bWVAYWNtZS5jb206c2VjcmV0");
+}
+
+#[tokio::test]
+async fn linear_issues_are_read_with_graphql() {
+    let body = serde_json::json!({ "data": { "issue": {
+        "identifier": "ENG-42",
+        "title": "Login fails",
+        "description": "SSO users can't log in.",
+        "url": "https://linear.app/acme/issue/ENG-42",
+        "state": { "name": "In Review" },
+        "assignee": null,
+        "labels": { "nodes": [{ "name": "bug" }] },
+        "comments": { "nodes": [
+            { "body": "Second", "createdAt": "2024-01-04T00:00:00Z", "user": { "name": "Cy" } },
+            { "body": "First", "createdAt": "2024-01-02T00:00:00Z", "user": null },
+        ]},
+        "attachments": { "nodes": [{ "title": "Fix SSO callback", "url": "https://github.com/acme/web/pull/3" }] },
+    }}});
+    let (url, seen) = serve(vec![body.to_string()]).await;
+
+    let linear = LinearClient::new("lin_api_key".to_string()).with_endpoint(&format!("{}/graphql", url));
+    let issue = linear.issue("ENG-42").await.unwrap();
+    assert_eq!(issue.status.as_deref(), Some("In Review"));
+    assert_eq!(issue.comments[0].body, "First");
+    assert_eq!(issue.comments[0].author, "Unknown");
+    assert_eq!(issue.pull_requests[0].url, "https://github.com/acme/web/pull/3");
+
+    let seen = seen.lock().unwrap();
+    assert!(seen[0].0.starts_with("POST /graphql"));
+    assert_eq!(seen[0].1, "lin_api_key");
+}
+
+#[tokio::test]
+async fn issues_are_cached_and_imported_with_the_chosen_fields() {
+    let h = TestHarness::new();
+    let tracker = Arc::new(FakeTracker {
+        fetches: AtomicUsize::new(0),
+    });
+    let importer = IssueImporter::with_sources(vec![tracker.clone() as Arc<dyn IssueSource>]);
+
+    let issue = importer.fetch("ENG-42", false).await.unwrap();
+    importer.fetch("https://linear.app/acme/issue/ENG-42", false).await.unwrap();
+    assert_eq!(tracker.fetches.load(Ordering::SeqCst), 1);
+
+    // Refreshing, or waiting, fetches again
+    importer.fetch("ENG-42", true).await.unwrap();
+    h.clock.advance(Duration::minutes(10));
+    importer.fetch("ENG-42", false).await.unwrap();
+    assert_eq!(tracker.fetches.load(Ordering::SeqCst), 3);
+
+    // Only Linear is set up here
+    let jira = importer.fetch("https://acme.atlassian.net/browse/OPS-1", false).await;
+    assert!(matches!(jira, Err(McpError::Config(_))));
+
+    let conversation = h.chat.create_conversation("Login", None).await.unwrap();
+    let item = issue.to_context(&[IssueField::Status]);
+    let message = import_context(&h.chat, &conversation.id, &item).await.unwrap();
+    assert!(message.text().starts_with("## Linear ENG-42: Login fails"));
+    assert!(message.text().contains("Status: In Progress"));
+    assert!(!message.text().contains("SSO"));
+    assert!(!message.text().contains("Labels"));
+    assert_eq!(message.metadata.as_ref().unwrap()[CONTEXT_METADATA_KEY]["source"], "linear");
+
+    let full = issue.to_markdown(&IssueField::ALL);
+    assert!(full.contains("Assignee: unassigned · Labels: bug"));
+    assert!(full.contains("Users on SSO can't log in."));
+}
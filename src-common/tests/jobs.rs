@@ -0,0 +1,117 @@
+//! Background jobs: progress, cancellation from another process and
+//! resuming after a restart.
+
+use mcp_common::jobs::{Job, JobKind, JobManager, JobState};
+use std::sync::Arc;
+use std::time::Duration;
+
+async fn wait_until_finished(manager: &JobManager, id: &str) -> Job {
+    for _ in 0..200 {
+        let job = manager.get(id).unwrap();
+        if job.state.is_finished() {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("job {} did not finish", id);
+}
+
+#[tokio::test]
+async fn completed_job_keeps_its_progress() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = Arc::new(JobManager::new(dir.path().to_path_buf()));
+
+    let job = manager.spawn(JobKind::Export, "Conversations", false, |job| async move {
+        job.message("Writing");
+        job.progress(10, Some(10));
+        Ok(())
+    });
+    assert_eq!(job.state, JobState::Running);
+
+    let job = wait_until_finished(&manager, &job.id).await;
+    assert_eq!(job.state, JobState::Completed);
+    assert_eq!(job.progress.message.as_deref(), Some("Writing"));
+    assert_eq!(job.progress.fraction(), Some(1.0));
+    assert_eq!(manager.clear_finished(), 1);
+    assert!(manager.list().is_empty());
+}
+
+#[tokio::test]
+async fn cancel_stops_a_job_run_elsewhere() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = Arc::new(JobManager::new(dir.path().to_path_buf()));
+    let cli = JobManager::new(dir.path().to_path_buf());
+
+    let job = app.spawn(JobKind::ModelDownload, "Model", true, |job| async move {
+        let mut done = 0;
+        while !job.is_cancelled() {
+            done += 1;
+            job.progress(done, None);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        Ok(())
+    });
+
+    // Another process only sees the job file; IDs may be shortened
+    cli.cancel(&job.id[..8]).unwrap();
+    let job = wait_until_finished(&app, &job.id).await;
+    assert_eq!(job.state, JobState::Cancelled);
+    assert!(job.error.is_none());
+    assert!(cli.cancel(&job.id).is_err());
+}
+
+#[tokio::test]
+async fn failed_job_records_the_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = Arc::new(JobManager::new(dir.path().to_path_buf()));
+
+    let job = manager.spawn(JobKind::Backup, "Backup", false, |_| async move {
+        Err(mcp_common::error::McpError::Unknown("disk full".to_string()))
+    });
+    let job = wait_until_finished(&manager, &job.id).await;
+    assert_eq!(job.state, JobState::Failed);
+    assert!(job.error.unwrap().contains("disk full"));
+}
+
+#[tokio::test]
+async fn interrupted_jobs_resume_from_their_checkpoint() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // A previous run of the app was shut down mid-job
+    let before = Arc::new(JobManager::new(dir.path().to_path_buf()));
+    let resumable = before.spawn(JobKind::ModelDownload, "Model", true, |job| async move {
+        job.save_checkpoint(serde_json::json!({ "offset": 42 }));
+        std::future::pending::<()>().await;
+        Ok(())
+    });
+    let other = before.spawn(JobKind::Import, "Import", false, |_| async move {
+        std::future::pending::<()>().await;
+        Ok(())
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // Pretend the jobs belong to a process that no longer exists
+    for id in [&resumable.id, &other.id] {
+        let path = dir.path().join(format!("{}.json", id));
+        let mut job: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        job["owner_pid"] = serde_json::json!(0);
+        std::fs::write(&path, job.to_string()).unwrap();
+    }
+
+    let after = Arc::new(JobManager::new(dir.path().to_path_buf()));
+    after.register_resumer(JobKind::ModelDownload, |job| {
+        Box::pin(async move {
+            let offset = job.checkpoint().unwrap()["offset"].as_u64().unwrap();
+            job.progress(offset + 8, Some(50));
+            Ok(())
+        })
+    });
+    assert_eq!(after.recover(), 1);
+
+    let resumed = wait_until_finished(&after, &resumable.id).await;
+    assert_eq!(resumed.state, JobState::Completed);
+    assert_eq!(resumed.progress.done, 50);
+
+    let failed = after.get(&other.id).unwrap();
+    assert_eq!(failed.state, JobState::Failed);
+}
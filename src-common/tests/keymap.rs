@@ -0,0 +1,87 @@
+//! Keymaps: key combos parse and print canonically, presets bind every
+//! action without conflicts, and user bindings are validated.
+
+use mcp_common::keymap::{scope_of, KeyCombo, Keymap, ACTIONS, PRESETS};
+
+fn combo(key: &str) -> KeyCombo {
+    key.parse().unwrap()
+}
+
+#[test]
+fn key_combos_parse_to_a_canonical_form() {
+    assert_eq!(combo("Control+Shift+Z").to_string(), "ctrl+Z");
+    assert_eq!(combo("cmd+opt+Escape").to_string(), "alt+meta+esc");
+    assert_eq!(combo("shift+tab").to_string(), "shift+tab");
+    assert_eq!(combo("Return"), KeyCombo::key("enter"));
+    // A plus at the end is the plus key
+    assert_eq!(combo("ctrl++").to_string(), "ctrl++");
+    assert_eq!(combo("+"), KeyCombo::key("+"));
+
+    assert!("hyper+a".parse::<KeyCombo>().is_err());
+    assert!("ctrl+banana".parse::<KeyCombo>().is_err());
+    assert!("ctrl+".parse::<KeyCombo>().is_err());
+
+    assert!(combo("a").is_printable() && combo("space").is_printable());
+    assert!(!combo("ctrl+a").is_printable() && !combo("enter").is_printable());
+}
+
+#[test]
+fn presets_bind_every_action_without_conflicts() {
+    for preset in PRESETS {
+        let keymap = Keymap::preset(preset).unwrap();
+        assert_eq!(keymap.bindings.len(), ACTIONS.len(), "{}", preset);
+        assert!(keymap.conflicts().is_empty(), "{}: {:?}", preset, keymap.conflicts());
+    }
+    assert!(Keymap::preset("nano").is_err());
+
+    let emacs = Keymap::preset("emacs").unwrap();
+    assert_eq!(emacs.action("tui.normal", &combo("ctrl+p")), Some("tui.normal.up"));
+    assert_eq!(emacs.action("tui.normal", &combo("up")), Some("tui.normal.up"));
+    assert_eq!(emacs.action("tui.normal", &combo("k")), None);
+    // Scopes are separate
+    assert_eq!(scope_of("tui.normal.up"), "tui.normal");
+    assert_eq!(emacs.action("tui.settings", &combo("ctrl+n")), Some("tui.settings.down"));
+}
+
+#[test]
+fn bindings_are_checked_for_conflicts() {
+    let mut keymap = Keymap::default();
+    assert!(keymap.bind("tui.normal.explode", &["x".to_string()]).is_err());
+    assert!(keymap.bind("tui.normal.new", &["ctrl+nope".to_string()]).is_err());
+
+    keymap.bind("tui.normal.new", &["d".to_string()]).unwrap();
+    let conflicts = keymap.conflicts();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].actions, vec!["tui.normal.new", "tui.normal.delete"]);
+    assert!(keymap.ensure_valid().unwrap_err().to_string().contains("'d' in tui.normal"));
+
+    // Typing in the message input must not trigger actions
+    let mut keymap = Keymap::default();
+    keymap.bind("tui.chat.send", &["s".to_string()]).unwrap();
+    assert_eq!(keymap.conflicts()[0].reason, "would stop the key from being typed");
+    keymap.bind("tui.chat.send", &["ctrl+s".to_string()]).unwrap();
+    assert!(keymap.ensure_valid().is_ok());
+}
+
+#[test]
+fn imported_keymaps_fill_gaps_from_their_preset() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("keymap.json");
+    std::fs::write(
+        &path,
+        r#"{ "preset": "vim", "bindings": { "tui.normal.new": ["o"], "tui.normal.bogus": ["z"] } }"#,
+    )
+    .unwrap();
+
+    let keymap = Keymap::import(&path).unwrap();
+    assert_eq!(keymap.keys("tui.normal.new"), vec![combo("o")]);
+    assert_eq!(keymap.keys("tui.normal.delete"), vec![combo("x")]);
+    assert!(!keymap.bindings.contains_key("tui.normal.bogus"));
+
+    // Exports round-trip
+    keymap.export(&path).unwrap();
+    assert_eq!(Keymap::import(&path).unwrap(), keymap);
+
+    std::fs::write(&path, r#"{ "bindings": { "tui.normal.new": ["q"] } }"#).unwrap();
+    assert!(Keymap::import(&path).is_err());
+}
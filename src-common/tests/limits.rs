@@ -0,0 +1,167 @@
+//! Pre-flight limits: oversized images and requests are refused with a fix,
+//! images are downscaled, and older images are left out to fit.
+
+use std::io::Cursor;
+
+use mcp_common::config::AttachmentStore;
+use mcp_common::models::message::ContentType;
+use mcp_common::models::{Message, Model, ModelCapabilities};
+use mcp_common::service::limits::{leave_out_older_images, Fit, Fix, LimitsTable, Preflight, Problem, RequestLimits};
+use mcp_common::testing::TestHarness;
+
+fn limits() -> RequestLimits {
+    RequestLimits {
+        max_request_bytes: 1024 * 1024,
+        max_image_bytes: 256 * 1024,
+        max_image_dimension: 300,
+        max_images: 2,
+    }
+}
+
+/// A PNG of noise, which doesn't compress
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut seed: u32 = 42;
+    let image = image::RgbImage::from_fn(width, height, |_, _| {
+        seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        let [r, g, b, _] = seed.to_le_bytes();
+        image::Rgb([r, g, b])
+    });
+    let mut bytes = Vec::new();
+    image::DynamicImage::ImageRgb8(image)
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Png)
+        .unwrap();
+    bytes
+}
+
+fn with_images(text: &str, urls: &[String]) -> Message {
+    let mut message = Message::user(text);
+    for url in urls {
+        message.content.parts.push(ContentType::Image {
+            url: url.clone(),
+            alt_text: None,
+        });
+    }
+    message
+}
+
+#[test]
+fn oversized_images_are_refused_and_downscaled() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = AttachmentStore::new(dir.path().to_path_buf());
+    let photo = store.add_bytes("c1", "photo.png", &png(600, 400)).unwrap();
+
+    let check = Preflight::check_with(&store, &limits(), &[with_images("Look", &[photo.url()])]);
+    assert!(!check.passed());
+    assert!(matches!(check.problems[0], Problem::ImageTooLarge { .. }));
+    assert!(check
+        .problems
+        .iter()
+        .any(|p| matches!(p, Problem::ImageTooWide { width: 600, .. })));
+    assert_eq!(check.fixes(), vec![Fix::Downscale]);
+    let error = check.into_result().unwrap_err().to_string();
+    assert!(error.contains("photo.png is"));
+    assert!(error.contains("resize it?"));
+
+    let smaller = store.downscale(&photo.id, 300, 256 * 1024).unwrap();
+    assert_eq!(smaller.file_name, "photo-small.jpg");
+    assert!(smaller.size <= 256 * 1024);
+    let (width, height) = image::io::Reader::open(store.file_path(&smaller.id))
+        .and_then(|reader| reader.with_guessed_format())
+        .unwrap()
+        .into_dimensions()
+        .unwrap();
+    assert_eq!((width, height), (300, 200));
+    assert!(Preflight::check_with(&store, &limits(), &[with_images("Look", &[smaller.url()])]).passed());
+}
+
+#[test]
+fn older_images_are_left_out_to_fit() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = AttachmentStore::new(dir.path().to_path_buf());
+    let urls: Vec<String> = (0..3)
+        .map(|i| {
+            store
+                .add_bytes("c1", &format!("{}.png", i), &png(20, 20))
+                .unwrap()
+                .url()
+        })
+        .collect();
+
+    let mut messages = vec![with_images("First", &urls[..2]), with_images("Second", &urls[2..])];
+    let check = Preflight::check_with(&store, &limits(), &messages);
+    assert_eq!(check.fixes(), vec![Fix::Split]);
+
+    assert_eq!(leave_out_older_images(&store, &limits(), &mut messages), 1);
+    assert!(messages[0].text().contains("[Image sent earlier: 0.png]"));
+    assert!(Preflight::check_with(&store, &limits(), &messages).passed());
+
+    let table = LimitsTable::at(dir.path().join("request_limits.json"));
+    assert_eq!(
+        table.for_provider("anthropic"),
+        RequestLimits::for_provider("anthropic")
+    );
+    table.set("anthropic", limits()).unwrap();
+    assert_eq!(table.for_provider("anthropic"), limits());
+    assert!(table
+        .set(
+            "local",
+            RequestLimits {
+                max_images: 1,
+                max_image_bytes: 0,
+                ..limits()
+            }
+        )
+        .is_err());
+    assert!(table.reset("anthropic").unwrap());
+    assert!(!table.reset("anthropic").unwrap());
+}
+
+#[tokio::test]
+async fn requests_over_the_limits_are_refused_before_sending() {
+    let h = TestHarness::new();
+    let model = Model {
+        id: "limits-test-model".to_string(),
+        provider: "limits-test".to_string(),
+        name: "Limits test model".to_string(),
+        version: "1".to_string(),
+        capabilities: ModelCapabilities {
+            vision: true,
+            max_context_length: 8192,
+            functions: false,
+            streaming: true,
+            json_mode: false,
+        },
+    };
+    let conversation = h.chat.create_conversation("Limits", Some(model)).await.unwrap();
+    let id = conversation.id.clone();
+
+    // More inline images than the default limit of 20
+    let inline: Vec<String> = (0..21).map(|_| "data:image/png;base64,AAAA".to_string()).collect();
+    let mut conversation = h.chat.get_conversation(&id).await.unwrap();
+    conversation.add_message(with_images("An album", &inline));
+    h.chat.update_conversation(conversation).await.unwrap();
+
+    let error = h
+        .chat
+        .send_message(&id, "Which one is best?")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("21 images, over the limit of 20"));
+    assert!(error.contains("splitting"));
+    assert!(h.provider.requests().is_empty());
+
+    h.provider.reply("The third.");
+    let fit = Fit {
+        split: true,
+        ..Fit::default()
+    };
+    let replies = h
+        .chat
+        .send_message_with_attachments(&id, "Which one is best?", &[], fit)
+        .await
+        .unwrap();
+    assert_eq!(replies.len(), 1);
+    let sent = h.provider.requests().pop().unwrap();
+    assert!(sent.iter().any(|m| m.text().contains("[Image sent earlier")));
+}
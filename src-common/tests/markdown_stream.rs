@@ -0,0 +1,158 @@
+//! Streaming markdown: open fences and lists across chunks, and only safe
+//! parts of the last line rendered.
+
+use mcp_common::utils::markdown::{safe_inline, Block, ListItem, MarkdownStream};
+
+fn paragraph(text: &str) -> Block {
+    Block::Paragraph { text: text.to_string() }
+}
+
+fn code(language: Option<&str>, text: &str, closed: bool) -> Block {
+    Block::Code {
+        language: language.map(str::to_string),
+        text: text.to_string(),
+        closed,
+    }
+}
+
+fn item(depth: usize, marker: &str, text: &str) -> ListItem {
+    ListItem {
+        depth,
+        marker: marker.to_string(),
+        text: text.to_string(),
+    }
+}
+
+#[test]
+fn complete_text_parses_into_blocks() {
+    let blocks = MarkdownStream::parse(
+        "# Setup\n\nInstall it:\n\n```bash\ncargo install papin\n```\n\n1. Run it\n2. Log in\n   with your key\n\n> Note\n> twice\n\n---\nDone",
+    );
+    assert_eq!(
+        blocks,
+        vec![
+            Block::Heading {
+                level: 1,
+                text: "Setup".to_string()
+            },
+            paragraph("Install it:"),
+            code(Some("bash"), "cargo install papin\n", true),
+            Block::List {
+                ordered: true,
+                items: vec![item(0, "1.", "Run it"), item(0, "2.", "Log in with your key")],
+            },
+            Block::Quote {
+                text: "Note\ntwice".to_string()
+            },
+            Block::Rule,
+            paragraph("Done"),
+        ]
+    );
+}
+
+#[test]
+fn fences_stay_open_across_chunks() {
+    let mut stream = MarkdownStream::new();
+    assert!(stream.push("Here:\n``").is_empty());
+    // Two backticks could still be a fence, so they wait
+    assert_eq!(stream.blocks(), vec![paragraph("Here:")]);
+
+    let finished = stream.push("`rust\nfn main() {\n");
+    assert_eq!(finished, vec![paragraph("Here:")]);
+    assert!(stream.in_code());
+    assert_eq!(stream.blocks().last(), Some(&code(Some("rust"), "fn main() {\n", false)));
+
+    // Partial code lines show as code, not prose
+    stream.push("    println!(\"**hi");
+    assert_eq!(
+        stream.blocks().last(),
+        Some(&code(Some("rust"), "fn main() {\n    println!(\"**hi\n", false))
+    );
+
+    stream.push("\");\n}\n``");
+    // A possible closing fence is held back
+    assert_eq!(
+        stream.blocks().last(),
+        Some(&code(Some("rust"), "fn main() {\n    println!(\"**hi\");\n}\n", false))
+    );
+
+    let finished = stream.push("`\nAfter");
+    assert_eq!(finished, vec![code(Some("rust"), "fn main() {\n    println!(\"**hi\");\n}\n", true)]);
+    assert!(!stream.in_code());
+    assert_eq!(stream.blocks().last(), Some(&paragraph("After")));
+
+    stream.finish();
+    assert_eq!(stream.finished().len(), 3);
+}
+
+#[test]
+fn lists_continue_across_chunks_and_blank_lines() {
+    let mut stream = MarkdownStream::new();
+    stream.push("- one\n");
+    // `-` alone could become an item or a rule
+    stream.push("-");
+    assert_eq!(
+        stream.blocks(),
+        vec![Block::List {
+            ordered: false,
+            items: vec![item(0, "-", "one")],
+        }]
+    );
+
+    stream.push(" two\n\n  - nested\n- thr");
+    assert_eq!(
+        stream.blocks(),
+        vec![Block::List {
+            ordered: false,
+            items: vec![item(0, "-", "one"), item(0, "-", "two"), item(1, "-", "nested"), item(0, "-", "thr")],
+        }]
+    );
+    assert!(stream.finished().is_empty());
+
+    // Prose after a blank line ends the list
+    let finished = stream.push("ee\n\nThat's all\n");
+    assert_eq!(finished.len(), 1);
+    assert_eq!(stream.blocks().last(), Some(&paragraph("That's all")));
+}
+
+#[test]
+fn unclosed_inline_markup_is_held_back() {
+    assert_eq!(safe_inline("Run `cargo te"), "Run ");
+    assert_eq!(safe_inline("Run `cargo test` now"), "Run `cargo test` now");
+    assert_eq!(safe_inline("This is **very"), "This is ");
+    assert_eq!(safe_inline("See [the docs](https://exa"), "See ");
+    assert_eq!(
+        safe_inline("See [the docs](https://example.com) and [1] more"),
+        "See [the docs](https://example.com) and [1] more"
+    );
+    assert_eq!(safe_inline("Footnote [1]"), "Footnote ");
+
+    let mut stream = MarkdownStream::new();
+    stream.push("Plain text and **bo");
+    assert_eq!(stream.blocks(), vec![paragraph("Plain text and ")]);
+    stream.push("ld**");
+    assert_eq!(stream.blocks(), vec![paragraph("Plain text and **bold**")]);
+
+    // A number alone could be the start of an ordered list
+    let mut stream = MarkdownStream::new();
+    stream.push("3");
+    assert!(stream.blocks().is_empty());
+    stream.push(". Third");
+    assert_eq!(
+        stream.blocks(),
+        vec![Block::List {
+            ordered: true,
+            items: vec![item(0, "3.", "Third")],
+        }]
+    );
+}
+
+#[test]
+fn unclosed_fences_end_as_open_code() {
+    let blocks = MarkdownStream::parse("```\nlet x = 1;");
+    assert_eq!(blocks, vec![code(None, "let x = 1;\n", false)]);
+
+    // Tilde fences close only with tildes
+    let blocks = MarkdownStream::parse("~~~~\n```\n~~~~\n");
+    assert_eq!(blocks, vec![code(None, "```\n", true)]);
+}
@@ -0,0 +1,97 @@
+//! Meeting summaries: transcript parsing, reading the model's notes and export.
+
+use chrono::NaiveDate;
+use mcp_common::service::meetings::{MeetingNotes, MeetingSummarizer, SummaryFormat, Transcript};
+use mcp_common::service::ChatService;
+use mcp_common::testing::TestHarness;
+use std::sync::Arc;
+
+const VTT: &str = "WEBVTT
+
+1
+00:00:01.000 --> 00:00:04.000
+<v Ada>Let's ship the beta on Friday.</v>
+
+2
+00:00:05.000 --> 00:00:07.000
+<v Grace>Agreed. I'll write the release notes.</v>
+";
+
+const REPLY: &str = r#"Here are the notes:
+```json
+{"title": "Beta planning", "summary": "The team agreed to ship the beta.",
+ "decisions": ["Ship the beta on Friday"],
+ "action_items": [{"task": "Write the release notes", "owner": "Grace", "due": "2024-05-10"},
+                  {"task": "Book a room", "owner": null, "due": "next week"}]}
+```"#;
+
+fn date() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 5, 6).unwrap()
+}
+
+#[test]
+fn transcripts_read_speakers_from_text_and_captions() {
+    let captions = Transcript::parse(VTT);
+    assert_eq!(captions.lines.len(), 2);
+    assert_eq!(captions.participants(), vec!["Ada", "Grace"]);
+    assert_eq!(captions.lines[1].text, "Agreed. I'll write the release notes.");
+
+    let mut live = Transcript::parse("[00:01:02] Ada: Morning all\nsee https://example.com");
+    live.push_text("Grace Hopper: Morning\n\n10:30 - Ada: Let's start");
+    assert_eq!(live.participants(), vec!["Ada", "Grace Hopper"]);
+    assert_eq!(live.lines[1].speaker, None);
+    assert_eq!(live.lines[3].text, "Let's start");
+    assert!(live.to_text().starts_with("Ada: Morning all\nsee https://example.com\nGrace Hopper: Morning"));
+
+    assert!("bullet".parse::<SummaryFormat>().is_ok());
+    assert!("haiku".parse::<SummaryFormat>().is_err());
+}
+
+#[test]
+fn notes_are_read_leniently_and_exported() {
+    let notes = MeetingNotes::from_reply(REPLY, date(), vec!["Ada".into(), "Grace".into()]).unwrap();
+    assert_eq!(notes.title, "Beta planning");
+    assert_eq!(notes.action_items[0].due, NaiveDate::from_ymd_opt(2024, 5, 10));
+    assert_eq!(notes.action_items[1].owner, None);
+    assert_eq!(notes.action_items[1].due, None);
+    assert!(MeetingNotes::from_reply("I couldn't follow", date(), Vec::new()).is_err());
+
+    let md = notes.to_markdown();
+    assert!(md.starts_with("# Beta planning\n\n*Monday, May 6, 2024* · Ada, Grace"));
+    assert!(md.contains("## Decisions\n\n- Ship the beta on Friday\n"));
+    assert!(md.contains("- [ ] Write the release notes (Grace, due 2024-05-10)\n"));
+    assert!(md.contains("- [ ] Book a room\n"));
+
+    let ics = notes.to_ics();
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert_eq!(ics.matches("BEGIN:VTODO").count(), 2);
+    assert!(ics.contains("DUE;VALUE=DATE:20240510\r\n"));
+    assert!(ics.lines().all(|line| line.trim_end_matches('\r').len() <= 75));
+
+    // UIDs are stable, so importing again updates the tasks
+    let uid = |ics: &str| ics.lines().find(|l| l.starts_with("UID:")).unwrap().to_string();
+    assert_eq!(uid(&ics), uid(&notes.to_ics()));
+}
+
+#[tokio::test]
+async fn summaries_use_the_model_and_leave_no_conversation() {
+    let h = TestHarness::new();
+    let chat = Arc::new(ChatService::new(h.service.clone()));
+    h.provider.reply(REPLY);
+
+    let summarizer = MeetingSummarizer::new(chat.clone()).with_format(SummaryFormat::Concise);
+    let notes = summarizer
+        .summarize(&Transcript::parse(VTT), Some("Weekly sync"), date())
+        .await
+        .unwrap();
+    assert_eq!(notes.title, "Weekly sync");
+    assert_eq!(notes.participants, vec!["Ada", "Grace"]);
+    assert_eq!(notes.decisions, vec!["Ship the beta on Friday"]);
+
+    let requests = h.provider.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(chat.list_conversations().await.unwrap().is_empty());
+    assert!(chat.list_trashed().await.unwrap().is_empty());
+
+    assert!(summarizer.summarize(&Transcript::default(), None, date()).await.is_err());
+}
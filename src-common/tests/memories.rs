@@ -0,0 +1,86 @@
+//! Memory notes: extracting facts, recalling them per workspace, and
+//! editing or forgetting them.
+
+use std::sync::Arc;
+
+use mcp_common::memory::embedding::{cosine, Embedder, HashingEmbedder};
+use mcp_common::memory::{extract_notes, format_context, MemoryStore};
+
+fn open_store(dir: &tempfile::TempDir) -> MemoryStore {
+    MemoryStore::at(dir.path().join("memories.json"), Arc::new(HashingEmbedder::default()))
+}
+
+#[test]
+fn facts_are_split_into_notes() {
+    assert_eq!(
+        extract_notes("Remember that I deploy on Fridays. My editor is Helix!"),
+        vec!["I deploy on Fridays.", "My editor is Helix!"]
+    );
+    assert_eq!(
+        extract_notes("please remember:\n- staging runs Postgres 15\n* tabs, not spaces"),
+        vec!["Staging runs Postgres 15", "Tabs, not spaces"]
+    );
+    // "Remembering" is a word of its own, and version numbers aren't sentences
+    assert_eq!(extract_notes("Remembering names is hard"), vec!["Remembering names is hard"]);
+    assert_eq!(extract_notes("We ship v2.1 soon"), vec!["We ship v2.1 soon"]);
+    assert!(extract_notes("remember: ok").is_empty());
+}
+
+#[test]
+fn similar_texts_embed_close_together() {
+    let embedder = HashingEmbedder::default();
+    let deploy = embedder.embed("The team deploys the backend on Fridays");
+    let related = embedder.embed("When does the backend deploy?");
+    let unrelated = embedder.embed("Favourite colour is green");
+    assert!(cosine(&deploy, &related) > cosine(&deploy, &unrelated));
+    assert_eq!(cosine(&deploy, &[1.0]), 0.0);
+}
+
+#[test]
+fn notes_are_recalled_within_their_workspace() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open_store(&dir);
+    store.remember(Some("acme"), "The backend deploys on Fridays.", Some("c1")).unwrap();
+    store.remember(Some("acme"), "Invoices are due monthly.", None).unwrap();
+    store.remember(None, "The backend deploys on Tuesdays.", None).unwrap();
+
+    let recalled = store.recall(Some("acme"), "When does the backend deploy?", 5);
+    assert_eq!(recalled.len(), 1);
+    assert_eq!(recalled[0].note.text, "The backend deploys on Fridays.");
+    assert_eq!(recalled[0].note.source_conversation.as_deref(), Some("c1"));
+
+    let outside = store.recall(None, "When does the backend deploy?", 5);
+    assert_eq!(outside.len(), 1);
+    assert_eq!(outside[0].note.text, "The backend deploys on Tuesdays.");
+    assert!(store.recall(Some("other"), "backend deploy", 5).is_empty());
+
+    let context = format_context(&recalled);
+    assert!(context.contains("- The backend deploys on Fridays."));
+}
+
+#[test]
+fn notes_can_be_edited_forgotten_and_reloaded() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open_store(&dir);
+    let first = store.remember(None, "My editor is Helix.", None).unwrap().remove(0);
+
+    // Saying the same thing again replaces the note instead of adding one
+    let again = store.remember(None, "my editor is helix", None).unwrap().remove(0);
+    assert_eq!(again.id, first.id);
+    assert_eq!(store.list(None).len(), 1);
+
+    let edited = store.update(&first.id, "My editor is Zed").unwrap();
+    assert_eq!(edited.text, "My editor is Zed");
+    assert!(store.update(&first.id, "  ").is_err());
+    assert_eq!(store.recall(None, "which editor", 5)[0].note.id, first.id);
+
+    store.remember(Some("acme"), "Staging runs Postgres 15", None).unwrap();
+    let reloaded = open_store(&dir);
+    assert_eq!(reloaded.all().len(), 2);
+    assert_eq!(reloaded.list(None)[0].text, "My editor is Zed");
+
+    reloaded.delete(&first.id).unwrap();
+    assert!(reloaded.list(None).is_empty());
+    assert!(reloaded.delete(&first.id).is_err());
+    assert!(store.remember(None, "remember", None).is_err());
+}
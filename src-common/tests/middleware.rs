@@ -0,0 +1,173 @@
+//! Message pipeline: middlewares run by priority, optional failures are
+//! isolated, metrics are kept per stage, and streamed replies get the same
+//! post-receive pass as unstreamed ones.
+
+use async_trait::async_trait;
+use mcp_common::error::{McpError, McpResult};
+use mcp_common::models::Message;
+use mcp_common::service::middleware::PipelineStage;
+use mcp_common::service::{MessageMiddleware, MessagePipeline, MiddlewareContext, MiddlewareOptions};
+use mcp_common::testing::TestHarness;
+use std::sync::{Arc, Mutex};
+
+/// Appends its name to the message's `trace` metadata, or fails
+struct Tracer {
+    name: &'static str,
+    fail: bool,
+    seen: Arc<Mutex<Vec<String>>>,
+}
+
+impl Tracer {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            fail: false,
+            seen: Arc::default(),
+        }
+    }
+
+    fn failing(name: &'static str) -> Self {
+        Self {
+            fail: true,
+            ..Self::new(name)
+        }
+    }
+
+    fn trace(&self, message: &mut Message) -> McpResult<()> {
+        self.seen.lock().unwrap().push(message.text());
+        let metadata = message.metadata.get_or_insert_with(Default::default);
+        let trace = metadata.entry("trace".to_string()).or_insert_with(|| serde_json::json!([]));
+        trace.as_array_mut().unwrap().push(serde_json::json!(self.name));
+        if self.fail {
+            return Err(McpError::InvalidRequest(format!("{} failed", self.name)));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MessageMiddleware for Tracer {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    async fn pre_send(&self, _ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        self.trace(message)
+    }
+
+    async fn post_receive(&self, _ctx: &mut MiddlewareContext, message: &mut Message) -> McpResult<()> {
+        self.trace(message)
+    }
+}
+
+fn options(priority: i32, required: bool) -> MiddlewareOptions {
+    MiddlewareOptions { priority, required }
+}
+
+fn trace(message: &Message) -> Vec<String> {
+    message
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get("trace"))
+        .and_then(|t| serde_json::from_value(t.clone()).ok())
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn middlewares_run_by_priority_then_registration_order() {
+    let pipeline = MessagePipeline::new();
+    pipeline.register(Arc::new(Tracer::new("late")), options(200, false));
+    pipeline.register(Arc::new(Tracer::new("first")), options(10, false));
+    pipeline.register(Arc::new(Tracer::new("second")), options(100, false));
+    pipeline.register(Arc::new(Tracer::new("third")), options(100, false));
+    assert_eq!(pipeline.names(), vec!["first", "second", "third", "late"]);
+
+    // Registering a name again replaces it at its new priority
+    pipeline.register(Arc::new(Tracer::new("first")), options(150, false));
+    assert_eq!(pipeline.names(), vec!["second", "third", "first", "late"]);
+
+    let mut message = Message::user("hi");
+    pipeline.pre_send(&mut MiddlewareContext::new("c1"), &mut message).await.unwrap();
+    assert_eq!(trace(&message), vec!["second", "third", "first", "late"]);
+
+    assert!(pipeline.unregister("third"));
+    assert!(!pipeline.unregister("third"));
+    assert_eq!(pipeline.names(), vec!["second", "first", "late"]);
+}
+
+#[tokio::test]
+async fn optional_failures_leave_the_message_untouched() {
+    let pipeline = MessagePipeline::new();
+    pipeline.register(Arc::new(Tracer::new("before")), options(1, false));
+    pipeline.register(Arc::new(Tracer::failing("broken")), options(2, false));
+    pipeline.register(Arc::new(Tracer::new("after")), options(3, false));
+
+    let mut message = Message::user("hi");
+    pipeline.pre_send(&mut MiddlewareContext::new("c1"), &mut message).await.unwrap();
+    // The failing middleware's change is discarded and the chain carries on
+    assert_eq!(trace(&message), vec!["before", "after"]);
+}
+
+#[tokio::test]
+async fn required_failures_abort_the_request() {
+    let pipeline = MessagePipeline::new();
+    let after = Tracer::new("after");
+    let after_seen = after.seen.clone();
+    pipeline.register(Arc::new(Tracer::failing("guard")), options(1, true));
+    pipeline.register(Arc::new(after), options(2, false));
+
+    let mut message = Message::user("hi");
+    let error = pipeline
+        .pre_send(&mut MiddlewareContext::new("c1"), &mut message)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("guard"));
+    assert!(trace(&message).is_empty());
+    assert!(after_seen.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn metrics_count_calls_per_stage_and_errors() {
+    let pipeline = MessagePipeline::new();
+    pipeline.register(Arc::new(Tracer::new("ok")), options(1, false));
+    pipeline.register(Arc::new(Tracer::failing("broken")), options(2, false));
+
+    let mut ctx = MiddlewareContext::new("c1");
+    let mut message = Message::user("hi");
+    pipeline.pre_send(&mut ctx, &mut message).await.unwrap();
+    pipeline.pre_send(&mut ctx, &mut message).await.unwrap();
+    pipeline.post_receive(&mut ctx, &mut message).await.unwrap();
+    pipeline.on_stream_chunk(&mut ctx, &mut message).await.unwrap();
+
+    let metrics = pipeline.metrics();
+    let ok = metrics.iter().find(|m| m.name == "ok").unwrap();
+    assert_eq!(ok.calls[&PipelineStage::PreSend], 2);
+    assert_eq!(ok.calls[&PipelineStage::PostReceive], 1);
+    assert_eq!(ok.calls[&PipelineStage::StreamChunk], 1);
+    assert_eq!(ok.errors, 0);
+
+    // The default chunk hook succeeds, so only the two hooks it implements fail
+    let broken = metrics.iter().find(|m| m.name == "broken").unwrap();
+    assert_eq!(broken.errors, 3);
+}
+
+#[tokio::test]
+async fn streamed_reply_goes_through_post_receive_once_whole() {
+    let h = TestHarness::new();
+    let tracer = Tracer::new("tracer");
+    let seen = tracer.seen.clone();
+    h.chat.pipeline().register(Arc::new(tracer), options(500, false));
+    h.provider.stream(&["Hel", "lo"]);
+    let conversation = h.chat.create_conversation("Streaming", None).await.unwrap();
+
+    let stream = h.chat.send_message_streaming(&conversation.id, "Say hello").await.unwrap();
+    let (_, error) = TestHarness::drain(stream).await;
+    assert!(error.is_none());
+
+    // Once for the prompt, once for the assembled reply
+    assert_eq!(*seen.lock().unwrap(), vec!["Say hello", "Hello"]);
+    let stored = h.chat.get_conversation(&conversation.id).await.unwrap();
+    let reply = stored.messages.last().unwrap();
+    assert_eq!(reply.text(), "Hello");
+    assert_eq!(trace(reply), vec!["tracer"]);
+}
@@ -0,0 +1,297 @@
+//! User-added model catalogs: fetching manifests, merging entries with
+//! provenance and trust levels.
+
+use mcp_common::models::huggingface::{self, parse_gguf_metadata, HfFile, HfModel};
+use mcp_common::models::registry::{
+    family_key, group_families, parameters_from_name, parse_manifest, quantization_from_name, AdapterEntry,
+    CatalogFormat, CatalogTrust, FamilyVariant, ModelRegistry,
+};
+use std::fs;
+use std::path::Path;
+
+fn manifest(dir: &Path, name: &str, models: serde_json::Value) -> String {
+    let path = dir.join(name);
+    fs::write(&path, serde_json::json!({ "name": name, "models": models }).to_string()).unwrap();
+    format!("file://{}", path.display())
+}
+
+fn model(id: &str, sha256: Option<&str>) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "name": id,
+        "file_name": format!("{}.gguf", id),
+        "download_url": format!("https://models.example.com/{}.gguf", id),
+        "sha256": sha256,
+    })
+}
+
+fn registry(dir: &Path) -> ModelRegistry {
+    ModelRegistry::open(dir.join("catalogs.json"), dir.join("tokens"))
+}
+
+#[tokio::test]
+async fn entries_merge_in_catalog_order_with_provenance() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = registry(dir.path());
+
+    let first = manifest(dir.path(), "first.json", serde_json::json!([model("shared", Some("aa")), model("only-first", None)]));
+    let second = manifest(dir.path(), "second.json", serde_json::json!([model("shared", Some("bb")), model("only-second", None)]));
+    let a = registry.add_catalog("Team Hub", &first, CatalogFormat::Manifest, CatalogTrust::Trusted).unwrap();
+    let b = registry.add_catalog("Team Hub", &second, CatalogFormat::Manifest, CatalogTrust::Trusted).unwrap();
+    assert_eq!(a.id, "team-hub");
+    assert_eq!(b.id, "team-hub-2");
+
+    assert_eq!(registry.refresh_all(false).await, 0);
+    assert!(registry.catalogs().iter().all(|c| c.refreshed_at.is_some() && !c.is_due()));
+
+    let entries = registry.entries();
+    let ids: Vec<_> = entries.iter().map(|e| e.entry.id.as_str()).collect();
+    assert_eq!(ids, ["shared", "only-first", "only-second"]);
+    assert_eq!(entries[0].catalog, "team-hub");
+    assert_eq!(entries[0].entry.sha256.as_deref(), Some("aa"));
+    assert_eq!(entries[0].also_in, ["team-hub-2"]);
+}
+
+#[tokio::test]
+async fn trust_levels_filter_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = registry(dir.path());
+
+    let url = manifest(dir.path(), "hub.json", serde_json::json!([model("pinned", Some("cc")), model("unpinned", None)]));
+    let catalog = registry.add_catalog("Hub", &url, CatalogFormat::Manifest, CatalogTrust::Verify).unwrap();
+    registry.refresh(&catalog.id).await.unwrap();
+
+    let ids: Vec<_> = registry.entries().into_iter().map(|e| e.entry.id).collect();
+    assert_eq!(ids, ["pinned"]);
+
+    registry.set_trust(&catalog.id, CatalogTrust::Blocked).unwrap();
+    assert!(registry.entries().is_empty());
+
+    registry.set_trust(&catalog.id, CatalogTrust::Trusted).unwrap();
+    assert_eq!(registry.entries().len(), 2);
+
+    // Intervals are at least an hour
+    assert_eq!(registry.set_refresh_hours(&catalog.id, 0).unwrap().refresh_hours, 1);
+}
+
+#[tokio::test]
+async fn failed_refresh_keeps_the_last_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = registry(dir.path());
+
+    let url = manifest(dir.path(), "hub.json", serde_json::json!([model("a", None)]));
+    let catalog = registry.add_catalog("Hub", &url, CatalogFormat::Manifest, CatalogTrust::Trusted).unwrap();
+    registry.refresh(&catalog.id).await.unwrap();
+
+    fs::write(dir.path().join("hub.json"), "{ not json").unwrap();
+    assert!(registry.refresh(&catalog.id).await.is_err());
+    assert!(registry.catalog(&catalog.id).unwrap().last_error.is_some());
+    assert_eq!(registry.entries().len(), 1);
+
+    // Removing the catalog drops its entries
+    registry.remove_catalog(&catalog.id).unwrap();
+    assert!(registry.entries().is_empty());
+}
+
+#[test]
+fn catalogs_need_usable_urls_and_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = registry(dir.path());
+    assert!(registry.add_catalog("Hub", "ftp://example.com/models.json", CatalogFormat::Manifest, CatalogTrust::Trusted).is_err());
+
+    let escaping = serde_json::json!({ "models": [{
+        "id": "x", "name": "x", "file_name": "../x.gguf", "download_url": "https://example.com/x.gguf"
+    }] });
+    assert!(parse_manifest(&escaping.to_string()).is_err());
+}
+
+#[test]
+fn gguf_names_describe_the_model() {
+    assert_eq!(quantization_from_name("llama-2-7b-chat.Q4_K_M").as_deref(), Some("Q4_K_M"));
+    assert_eq!(quantization_from_name("Phi-3-mini-4k-instruct-IQ3_XS").as_deref(), Some("IQ3_XS"));
+    assert_eq!(quantization_from_name("model-f16").as_deref(), Some("F16"));
+    assert_eq!(parameters_from_name("Llama-2-7B-Chat"), Some(7_000_000_000));
+    assert_eq!(parameters_from_name("qwen2-0.5b-instruct"), Some(500_000_000));
+    assert_eq!(parameters_from_name("no-size-here"), None);
+}
+
+fn gguf_string(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(&(s.len() as u64).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn gguf_header() -> Vec<u8> {
+    let mut out = b"GGUF".to_vec();
+    out.extend_from_slice(&3u32.to_le_bytes());
+    out.extend_from_slice(&291u64.to_le_bytes());
+    out.extend_from_slice(&6u64.to_le_bytes());
+
+    gguf_string(&mut out, "general.architecture");
+    out.extend_from_slice(&8u32.to_le_bytes());
+    gguf_string(&mut out, "llama");
+
+    gguf_string(&mut out, "general.size_label");
+    out.extend_from_slice(&8u32.to_le_bytes());
+    gguf_string(&mut out, "8B");
+
+    gguf_string(&mut out, "llama.rope.freq_base");
+    out.extend_from_slice(&6u32.to_le_bytes());
+    out.extend_from_slice(&500000f32.to_le_bytes());
+
+    gguf_string(&mut out, "llama.context_length");
+    out.extend_from_slice(&4u32.to_le_bytes());
+    out.extend_from_slice(&8192u32.to_le_bytes());
+
+    gguf_string(&mut out, "tokenizer.chat_template");
+    out.extend_from_slice(&8u32.to_le_bytes());
+    gguf_string(
+        &mut out,
+        "{% for m in messages %}<|start_header_id|>{{ m.role }}<|end_header_id|>\n\n{{ m.content }}<|eot_id|>{% endfor %}",
+    );
+
+    // A vocabulary too long for the fetched bytes
+    gguf_string(&mut out, "tokenizer.ggml.tokens");
+    out.extend_from_slice(&9u32.to_le_bytes());
+    out.extend_from_slice(&8u32.to_le_bytes());
+    out.extend_from_slice(&128_000u64.to_le_bytes());
+    gguf_string(&mut out, "<s>");
+    out
+}
+
+#[test]
+fn gguf_headers_are_read_until_they_are_cut_off() {
+    let metadata = parse_gguf_metadata(&gguf_header()).unwrap();
+    assert_eq!(metadata.architecture.as_deref(), Some("llama"));
+    assert_eq!(metadata.context_length, Some(8192));
+    assert_eq!(metadata.size_label.as_deref(), Some("8B"));
+    assert!(metadata.chat_template.unwrap().contains("<|eot_id|>"));
+
+    let header = gguf_header();
+    for len in 0..header.len() {
+        let _ = parse_gguf_metadata(&header[..len]);
+    }
+    assert!(parse_gguf_metadata(b"GGML0000").is_err());
+}
+
+#[test]
+fn hub_files_register_in_a_local_catalog() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = registry(dir.path());
+
+    let model = HfModel {
+        repo: "Org/Tiny-1B-GGUF".to_string(),
+        downloads: 10,
+        likes: 1,
+        license: Some("apache-2.0".to_string()),
+        gated: false,
+        parameters: Some(1_000_000_000),
+        files: Vec::new(),
+    };
+    let file = HfFile {
+        path: "q/tiny-1b.Q4_K_M.gguf".to_string(),
+        size: Some(700 << 20),
+        sha256: Some("dd".to_string()),
+        quantization: Some("Q4_K_M".to_string()),
+        fits: true,
+    };
+    let mut entry = huggingface::entry(&model, &file);
+    parse_gguf_metadata(&gguf_header()).unwrap().apply(&mut entry);
+    assert_eq!(entry.id, "hf:org/tiny-1b-gguf/tiny-1b.q4_k_m");
+    assert_eq!(entry.file_name, "tiny-1b.Q4_K_M.gguf");
+    assert_eq!(entry.download_url, "https://huggingface.co/Org/Tiny-1B-GGUF/resolve/main/q/tiny-1b.Q4_K_M.gguf");
+    assert_eq!(entry.context_size, 8192);
+    assert_eq!(entry.parameters, 8_000_000_000);
+    assert_eq!(entry.architecture.as_deref(), Some("llama"));
+    assert_eq!(entry.chat_template.as_deref(), Some("llama3"));
+
+    let registered = registry.register(huggingface::HF_CATALOG, "Hugging Face", entry.clone()).unwrap();
+    assert_eq!(registered.trust, CatalogTrust::Trusted);
+    registry.register(huggingface::HF_CATALOG, "Hugging Face", entry).unwrap();
+    assert_eq!(registry.entries().len(), 1);
+    assert_eq!(registry.catalog(huggingface::HF_CATALOG).unwrap().format, CatalogFormat::Local);
+    assert!(registry.add_catalog("Mine", "https://example.com", CatalogFormat::Local, CatalogTrust::Trusted).is_err());
+}
+
+#[test]
+fn quantizations_of_a_model_share_a_family() {
+    assert_eq!(family_key("Llama-2-7B-Chat.Q4_K_M.gguf"), "llama-2-7b-chat");
+    assert_eq!(family_key("llama-2-7b-chat-Q8_0"), "llama-2-7b-chat");
+    assert_eq!(family_key("Phi-3-mini-IQ3_XS-instruct"), "phi-3-mini-instruct");
+    assert_eq!(family_key("tinyllama"), "tinyllama");
+}
+
+#[test]
+fn families_roll_up_variants_and_disk_usage() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = registry(dir.path());
+    let variant = |id: &str, quantization: &str, size_on_disk: u64| {
+        (
+            family_key(id),
+            "Llama".to_string(),
+            FamilyVariant {
+                id: id.to_string(),
+                quantization: quantization.to_string(),
+                downloaded: size_on_disk > 0,
+                size_on_disk,
+            },
+        )
+    };
+    let models = || {
+        vec![
+            variant("llama.Q8_0", "Q8_0", 800),
+            variant("llama.Q4_K_M", "Q4_K_M", 400),
+            variant("llama.F16", "F16", 0),
+            variant("other", "", 10),
+        ]
+    };
+
+    let families = group_families(&registry, models());
+    assert_eq!(families.len(), 2);
+    let llama = &families[0];
+    assert_eq!(llama.key, "llama");
+    assert_eq!(llama.disk_usage, 1200);
+    let order: Vec<_> = llama.variants.iter().map(|v| v.id.as_str()).collect();
+    assert_eq!(order, ["llama.Q4_K_M", "llama.Q8_0", "llama.F16"]);
+    // Without a choice, the largest downloaded variant is used
+    assert_eq!(llama.preferred.as_deref(), Some("llama.Q8_0"));
+
+    registry.set_preferred_variant("llama", Some("llama.Q4_K_M")).unwrap();
+    assert_eq!(group_families(&registry, models())[0].preferred.as_deref(), Some("llama.Q4_K_M"));
+
+    // A choice that no longer exists is ignored
+    registry.set_preferred_variant("llama", Some("llama.Q2_K")).unwrap();
+    assert_eq!(group_families(&registry, models())[0].preferred.as_deref(), Some("llama.Q8_0"));
+}
+
+#[test]
+fn adapters_match_their_base_model_or_family() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = registry(dir.path());
+    let adapter = |id: &str, base_model: &str| AdapterEntry {
+        id: id.to_string(),
+        name: id.to_string(),
+        base_model: base_model.to_string(),
+        file_name: format!("{}.gguf", id),
+        download_url: None,
+        sha256: None,
+        size: None,
+        default_scale: 1.0,
+        description: None,
+        added_at: chrono::Utc::now(),
+    };
+
+    registry.add_adapter(adapter("sql", "llama")).unwrap();
+    registry.add_adapter(adapter("poetry", "llama.Q4_K_M")).unwrap();
+    registry.add_adapter(adapter("other", "mistral")).unwrap();
+    assert!(registry.add_adapter(adapter("orphan", " ")).is_err());
+
+    let ids = |model_id: &str| -> Vec<String> {
+        registry.adapters_for(model_id, &family_key(model_id)).into_iter().map(|a| a.id).collect()
+    };
+    assert_eq!(ids("llama.Q4_K_M"), ["poetry", "sql"]);
+    assert_eq!(ids("llama.Q8_0"), ["sql"]);
+
+    registry.remove_adapter("sql").unwrap();
+    assert_eq!(ids("llama.Q8_0"), Vec::<String>::new());
+    assert!(registry.adapter("sql").is_err());
+}
@@ -0,0 +1,180 @@
+//! OAuth device login: tokens live in the secret store, are refreshed
+//! before they expire, and a refused login or refresh is reported.
+
+use chrono::{Duration, Utc};
+use mcp_common::auth::{
+    access_token, poll_device_login, set_secret_store, start_device_login, Authorization, OAuthTokens, SecretStore,
+};
+use mcp_common::config::get_settings;
+use mcp_common::error::McpResult;
+use mcp_common::platform::fs::set_portable_dir;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Secrets kept in memory for the test
+#[derive(Default)]
+struct MemoryStore(Mutex<HashMap<String, String>>);
+
+impl SecretStore for MemoryStore {
+    fn get(&self, key: &str) -> McpResult<Option<String>> {
+        Ok(self.0.lock().unwrap().get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &str) -> McpResult<()> {
+        self.0.lock().unwrap().insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> McpResult<()> {
+        self.0.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+/// Scripted answers of the identity provider and the forms it was sent
+#[derive(Default)]
+struct Provider {
+    answers: VecDeque<(u16, serde_json::Value)>,
+    forms: Vec<(String, String)>,
+}
+
+/// Serve the identity provider until the test ends
+async fn serve(provider: Arc<Mutex<Provider>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let header_end = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break end + 4;
+                }
+            };
+            let head = String::from_utf8_lossy(&request[..header_end]).to_string();
+            let length: usize = head
+                .lines()
+                .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                .unwrap_or(0);
+            while request.len() < header_end + length {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let path = head.split_whitespace().nth(1).unwrap().to_string();
+            let form = String::from_utf8_lossy(&request[header_end..]).to_string();
+
+            let (status, body) = {
+                let mut provider = provider.lock().unwrap();
+                provider.forms.push((path, form));
+                provider.answers.pop_front().unwrap_or((500, serde_json::json!({})))
+            };
+            let body = body.to_string();
+            let response = format!(
+                "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    base
+}
+
+/// Point the OAuth settings at the provider, in memory only
+fn configure(base: &str) {
+    let settings = get_settings();
+    let mut settings = settings.lock().unwrap();
+    settings.auth.oauth.device_authorization_url = Some(format!("{}/device", base));
+    settings.auth.oauth.token_url = Some(format!("{}/token", base));
+    settings.auth.oauth.client_id = Some("papin".to_string());
+    settings.auth.oauth.scopes = vec!["chat".to_string()];
+}
+
+fn store_tokens(store: &MemoryStore, expires_in: Duration, refresh_token: Option<&str>) {
+    let tokens = OAuthTokens {
+        access_token: "a1".to_string(),
+        refresh_token: refresh_token.map(str::to_string),
+        expires_at: Some(Utc::now() + expires_in),
+        scope: Some("chat".to_string()),
+    };
+    store.set("oauth_tokens", &serde_json::to_string(&tokens).unwrap()).unwrap();
+}
+
+// The secret store and settings are global, so the steps run in one test
+#[tokio::test]
+async fn tokens_are_stored_refreshed_and_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    set_portable_dir(dir.path().to_path_buf()).unwrap();
+    let store = Arc::new(MemoryStore::default());
+    set_secret_store(store.clone());
+    let provider = Arc::new(Mutex::new(Provider::default()));
+    let base = serve(provider.clone()).await;
+    configure(&base);
+
+    assert!(access_token().await.unwrap_err().to_string().contains("Not logged in"));
+    store_tokens(&store, Duration::hours(1), Some("r1"));
+    assert_eq!(access_token().await.unwrap(), "a1");
+    assert!(provider.lock().unwrap().forms.is_empty());
+
+    // Close to expiry the token is refreshed; the refresh token is kept
+    // when the provider doesn't send a new one
+    store_tokens(&store, Duration::seconds(30), Some("r1"));
+    provider
+        .lock()
+        .unwrap()
+        .answers
+        .push_back((200, serde_json::json!({ "access_token": "a2", "expires_in": 3600 })));
+    assert_eq!(access_token().await.unwrap(), "a2");
+    let (path, form) = provider.lock().unwrap().forms.pop().unwrap();
+    assert_eq!(path, "/token");
+    assert_eq!(form, "grant_type=refresh_token&refresh_token=r1&client_id=papin");
+    let stored: OAuthTokens = serde_json::from_str(&store.get("oauth_tokens").unwrap().unwrap()).unwrap();
+    assert_eq!(stored.refresh_token.as_deref(), Some("r1"));
+    assert!(!stored.needs_refresh());
+
+    // A rejected refresh token means logging in again
+    store_tokens(&store, Duration::seconds(30), Some("r1"));
+    provider
+        .lock()
+        .unwrap()
+        .answers
+        .push_back((400, serde_json::json!({ "error": "invalid_grant" })));
+    assert!(access_token().await.unwrap_err().to_string().contains("log in again"));
+    assert!(store.get("oauth_tokens").unwrap().is_none());
+    store_tokens(&store, Duration::seconds(30), None);
+    assert!(access_token().await.unwrap_err().to_string().contains("log in again"));
+
+    // Device login: the user denies it after one pending poll
+    provider.lock().unwrap().answers.extend([
+        (
+            200,
+            serde_json::json!({
+                "device_code": "d1", "user_code": "ABCD-EFGH",
+                "verification_uri": "https://login.example.com/device", "expires_in": 600, "interval": 1
+            }),
+        ),
+        (400, serde_json::json!({ "error": "authorization_pending" })),
+        (400, serde_json::json!({ "error": "access_denied" })),
+    ]);
+    let authorization = start_device_login().await.unwrap();
+    assert_eq!(authorization.user_code, "ABCD-EFGH");
+    assert!(poll_device_login(&authorization).await.unwrap_err().to_string().contains("denied"));
+    let forms = provider.lock().unwrap().forms.clone();
+    assert_eq!(forms[forms.len() - 3], ("/device".to_string(), "client_id=papin&scope=chat".to_string()));
+    assert!(forms[forms.len() - 1].1.contains("device_code=d1"));
+}
+
+#[test]
+fn credentials_are_never_printed() {
+    let key = Authorization::ApiKey(" sk-secret ".to_string());
+    assert_eq!(key.header(), ("x-api-key".to_string(), "sk-secret".to_string()));
+    let bearer = Authorization::Bearer("token".to_string());
+    assert_eq!(bearer.header().1, "Bearer token");
+    assert!(!format!("{:?} {:?}", key, bearer).contains("secret"));
+}
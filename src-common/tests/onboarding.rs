@@ -0,0 +1,90 @@
+//! Onboarding: wizard answers are validated before anything is saved, API
+//! key checks tell rejected keys from unreachable APIs, and the hardware
+//! scan only offers local models that fit.
+
+use mcp_common::service::onboarding::{
+    check_api_key, scan_hardware, validate_answers, ApiKeyCheck, OnboardingAnswers, LOCAL_MODEL_CHOICES,
+    MODEL_CHOICES,
+};
+
+fn answers() -> OnboardingAnswers {
+    OnboardingAnswers {
+        api_key: Some("sk-ant-test".to_string()),
+        model: MODEL_CHOICES[1].to_string(),
+        offline_enabled: false,
+        local_model: None,
+        telemetry: false,
+        profile_name: "Robin".to_string(),
+        email: None,
+    }
+}
+
+fn rejection(answers: &OnboardingAnswers, has_api_key: bool) -> String {
+    validate_answers(answers, has_api_key).unwrap_err().to_string()
+}
+
+#[test]
+fn complete_answers_are_accepted() {
+    assert!(validate_answers(&answers(), false).is_ok());
+
+    // The stored key is kept when none is entered
+    let keep_key = OnboardingAnswers { api_key: None, ..answers() };
+    assert!(validate_answers(&keep_key, true).is_ok());
+
+    let offline = OnboardingAnswers {
+        api_key: None,
+        offline_enabled: true,
+        local_model: Some("tinyllama".to_string()),
+        email: Some("robin@example.com".to_string()),
+        ..answers()
+    };
+    assert!(validate_answers(&offline, false).is_ok());
+}
+
+#[test]
+fn incomplete_answers_are_rejected() {
+    let blank_key = OnboardingAnswers { api_key: Some("  ".to_string()), ..answers() };
+    assert!(rejection(&blank_key, true).contains("API key cannot be empty"));
+
+    let no_key = OnboardingAnswers { api_key: None, ..answers() };
+    assert!(rejection(&no_key, false).contains("unless offline mode is enabled"));
+
+    let no_model = OnboardingAnswers { model: " ".to_string(), ..answers() };
+    assert!(rejection(&no_model, false).contains("default model"));
+
+    let no_local_model = OnboardingAnswers { offline_enabled: true, ..answers() };
+    assert!(rejection(&no_local_model, false).contains("local model"));
+
+    let no_name = OnboardingAnswers { profile_name: String::new(), ..answers() };
+    assert!(rejection(&no_name, false).contains("Profile name"));
+
+    for email in ["robin", "@example.com", "robin@localhost"] {
+        let bad_email = OnboardingAnswers { email: Some(email.to_string()), ..answers() };
+        assert!(rejection(&bad_email, false).contains("Email"), "{}", email);
+    }
+}
+
+#[tokio::test]
+async fn api_key_checks_tell_rejected_keys_from_unreachable_apis() {
+    assert_eq!(
+        check_api_key("https://api.anthropic.com", " ").await,
+        ApiKeyCheck::Invalid { message: "API key is empty".to_string() }
+    );
+    // Nothing listens on port 1, so the key can't be verified either way
+    let check = check_api_key("https://127.0.0.1:1", "sk-ant-test").await;
+    assert!(matches!(check, ApiKeyCheck::Unreachable { .. }), "{:?}", check);
+}
+
+#[test]
+fn hardware_scan_only_offers_models_that_fit() {
+    let scan = scan_hardware();
+    assert!(scan.cpu_cores >= 1);
+    assert_eq!(scan.can_run_local(), !scan.local_models.is_empty());
+    assert_eq!(scan.recommended_local_model.as_ref(), scan.local_models.last());
+
+    let memory_gib = scan.memory_bytes / (1024 * 1024 * 1024);
+    for model in &scan.local_models {
+        let (_, needed) = LOCAL_MODEL_CHOICES.iter().find(|(name, _)| name == model).unwrap();
+        assert!(memory_gib >= *needed);
+    }
+}
@@ -0,0 +1,59 @@
+//! Portable mode directory resolution.
+
+use mcp_common::platform::fs::{
+    app_dir, executable_dir, portable_arg, resolve_portable, set_portable_dir, AppDir, PORTABLE_DIR, PORTABLE_MARKER,
+};
+use std::fs;
+use std::path::PathBuf;
+
+#[test]
+fn marker_file_turns_portable_mode_on() {
+    let dir = tempfile::tempdir().unwrap();
+    let exe = dir.path().join("papin");
+
+    assert_eq!(resolve_portable(&exe, None), None);
+
+    fs::write(dir.path().join(PORTABLE_MARKER), "").unwrap();
+    assert_eq!(resolve_portable(&exe, None), Some(dir.path().join(PORTABLE_DIR)));
+    // The environment wins over the marker
+    assert_eq!(resolve_portable(&exe, Some("0")), None);
+}
+
+#[test]
+fn environment_and_arguments_choose_the_directory() {
+    let exe = PathBuf::from("/media/usb/papin/papin");
+
+    assert_eq!(
+        resolve_portable(&exe, Some("true")),
+        Some(PathBuf::from("/media/usb/papin").join(PORTABLE_DIR))
+    );
+    assert_eq!(
+        resolve_portable(&exe, Some("profiles/work")),
+        Some(PathBuf::from("/media/usb/papin/profiles/work"))
+    );
+    assert_eq!(resolve_portable(&exe, Some("/tmp/papin")), Some(PathBuf::from("/tmp/papin")));
+
+    assert_eq!(
+        portable_arg(&exe, ["--verbose", "--portable"]),
+        Some(PathBuf::from("/media/usb/papin").join(PORTABLE_DIR))
+    );
+    assert_eq!(portable_arg(&exe, ["--portable=data"]), Some(PathBuf::from("/media/usb/papin/data")));
+    assert_eq!(portable_arg(&exe, ["--portables", "chat"]), None);
+}
+
+#[test]
+fn mac_bundles_keep_data_next_to_the_app() {
+    let exe = PathBuf::from("/Volumes/USB/Papin.app/Contents/MacOS/papin");
+    assert_eq!(executable_dir(&exe), Some(PathBuf::from("/Volumes/USB")));
+}
+
+#[test]
+fn portable_mode_keeps_every_directory_together() {
+    let dir = tempfile::tempdir().unwrap();
+    set_portable_dir(dir.path().to_path_buf()).unwrap();
+
+    assert_eq!(app_dir(AppDir::Config), dir.path().join("config"));
+    assert_eq!(app_dir(AppDir::Cache), dir.path().join("cache"));
+    assert_eq!(app_dir(AppDir::Data), dir.path().join("data"));
+    assert!(dir.path().join("cache").is_dir());
+}
@@ -0,0 +1,121 @@
+//! Guardrail policies for tool calls: rules over tools, arguments, paths and
+//! hosts, workspace policies on top of the global one, and the denial handed
+//! to the model.
+
+use mcp_common::models::ToolCall;
+use mcp_common::service::policy::{Effect, PolicyRule, PolicyStore};
+
+fn call(tool: &str, arguments: serde_json::Value) -> ToolCall {
+    ToolCall::new("call-1", tool, arguments)
+}
+
+#[test]
+fn the_strictest_matching_rule_decides_and_workspaces_add_their_own() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("policies.json");
+    let store = PolicyStore::at(path.clone());
+
+    store
+        .add_rule(
+            None,
+            PolicyRule {
+                paths: vec!["/home/me/project".to_string()],
+                ..PolicyRule::new(Effect::Allow)
+            },
+        )
+        .unwrap();
+    let secrets = store
+        .add_rule(
+            None,
+            PolicyRule {
+                paths: vec!["/etc".to_string()],
+                reason: Some("System files are off limits".to_string()),
+                ..PolicyRule::new(Effect::Deny)
+            },
+        )
+        .unwrap();
+    store
+        .add_rule(
+            None,
+            PolicyRule {
+                tool: Some("shell".to_string()),
+                arguments: Some(r"\brm\s+-rf\b".to_string()),
+                ..PolicyRule::new(Effect::Ask)
+            },
+        )
+        .unwrap();
+    assert!(store
+        .add_rule(
+            None,
+            PolicyRule {
+                arguments: Some("(".to_string()),
+                ..PolicyRule::new(Effect::Deny)
+            }
+        )
+        .is_err());
+
+    let global = PolicyStore::at(path).effective(None);
+    let effect = |tool: &str, arguments: serde_json::Value| global.evaluate(&call(tool, arguments)).effect;
+
+    // Paths: allow needs all of them in scope, deny any of them
+    assert_eq!(
+        effect(
+            "shell",
+            serde_json::json!({ "command": "cat /home/me/project/README.md" })
+        ),
+        Effect::Allow
+    );
+    let decision = global.evaluate(&call(
+        "shell",
+        serde_json::json!({ "command": "cp ~/notes /etc/../etc/hosts" }),
+    ));
+    assert_eq!(decision.effect, Effect::Deny);
+    assert_eq!(decision.rule.as_ref().unwrap().id, secrets.id);
+    assert_eq!(
+        effect(
+            "shell",
+            serde_json::json!({ "command": "rm -rf /home/me/project/build" })
+        ),
+        Effect::Ask
+    );
+    assert_eq!(
+        effect("shell", serde_json::json!({ "command": "rm -rf /etc" })),
+        Effect::Deny
+    );
+    assert_eq!(effect("git", serde_json::json!({ "action": "log" })), Effect::Allow);
+
+    // The model is told why, and not to retry
+    let denial = decision.denial(&call("shell", serde_json::json!({})));
+    assert_eq!(denial.tool_call_id, "call-1");
+    assert_eq!(denial.result["denied"]["decision"], "deny");
+    assert_eq!(denial.result["denied"]["reason"], "System files are off limits");
+    assert!(denial.result["note"].as_str().unwrap().contains("Do not retry"));
+
+    // A workspace denies unknown hosts on top of the global rules
+    store.set_default(Some("work"), Some(Effect::Deny)).unwrap();
+    store
+        .add_rule(
+            Some("work"),
+            PolicyRule {
+                hosts: vec!["*.example.com".to_string()],
+                ..PolicyRule::new(Effect::Allow)
+            },
+        )
+        .unwrap();
+    let work = store.effective(Some("work"));
+    let fetch = |url: &str| work.evaluate(&call("fetch", serde_json::json!({ "url": url }))).effect;
+    assert_eq!(fetch("https://api.example.com/v1"), Effect::Allow);
+    assert_eq!(fetch("https://user@evil.test/"), Effect::Deny);
+    assert!(work.evaluate(&call("git", serde_json::json!({}))).rule.is_none());
+    assert_eq!(
+        store
+            .effective(None)
+            .evaluate(&call("fetch", serde_json::json!({})))
+            .effect,
+        Effect::Allow
+    );
+
+    assert!(store.remove_rule(None, &secrets.id).unwrap());
+    assert!(!store.remove_rule(None, &secrets.id).unwrap());
+    assert_eq!(store.workspaces(), vec!["work"]);
+}
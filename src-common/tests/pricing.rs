@@ -0,0 +1,76 @@
+//! Price lists: bundled prices, fetched lists, user overrides and currencies.
+
+use mcp_common::service::pricing::{format_amount, lookup, ModelPrice, PriceSource, PriceTable};
+use std::fs;
+
+fn price(input_per_mtok: f64, output_per_mtok: f64) -> ModelPrice {
+    ModelPrice {
+        input_per_mtok,
+        output_per_mtok,
+    }
+}
+
+#[test]
+fn prices_match_the_longest_prefix_and_honour_overrides() {
+    let dir = tempfile::tempdir().unwrap();
+    let table = PriceTable::at(dir.path().join("pricing.json"));
+
+    let opus = table.price_for("claude-3-opus-20240229").unwrap();
+    assert_eq!(opus.input_per_mtok, 15.0);
+    assert!(table.price_for("llama-3-8b").is_none());
+    assert!(table.set_price("llama", price(-1.0, 0.0)).is_err());
+
+    table.set_price("claude-3-opus-2024", price(1.0, 2.0)).unwrap();
+    assert_eq!(table.price_for("claude-3-opus-20240229"), Some(price(1.0, 2.0)));
+    assert_eq!(table.price_for("claude-3-opus-20250101"), Some(opus));
+
+    table.reset_price("claude-3-opus-2024").unwrap();
+    assert_eq!(table.price_for("claude-3-opus-20240229"), Some(opus));
+    assert_eq!(lookup(&table.prices(), "claude-3-haiku-20240307"), Some(price(0.25, 1.25)));
+}
+
+#[tokio::test]
+async fn fetched_lists_sit_between_bundled_and_user_prices() {
+    let dir = tempfile::tempdir().unwrap();
+    let table = PriceTable::at(dir.path().join("pricing.json"));
+    let list = dir.path().join("prices.json");
+    fs::write(
+        &list,
+        r#"{"prices": {"claude-3-opus": {"input_per_mtok": 10.0, "output_per_mtok": 50.0},
+                       "gpt-4o": {"input_per_mtok": 5.0, "output_per_mtok": 15.0}},
+            "rates": {"EUR": 0.5}}"#,
+    )
+    .unwrap();
+    let url = format!("file://{}", list.display());
+
+    assert_eq!(table.refresh(&url).await.unwrap(), 2);
+    assert!(!table.refresh_if_stale(&url).await.unwrap());
+    assert_eq!(table.price_for("claude-3-opus-20240229"), Some(price(10.0, 50.0)));
+    assert_eq!(table.rate("eur"), Some(0.5));
+
+    table.set_price("gpt-4o", price(2.5, 10.0)).unwrap();
+    let entries = table.entries();
+    let source = |model: &str| entries.iter().find(|e| e.model == model).unwrap().source;
+    assert_eq!(source("claude-3-haiku"), PriceSource::Bundled);
+    assert_eq!(source("claude-3-opus"), PriceSource::Remote);
+    assert_eq!(source("gpt-4o"), PriceSource::User);
+    assert_eq!(table.last_refresh().unwrap().0, url);
+}
+
+#[test]
+fn currencies_convert_from_dollars() {
+    let dir = tempfile::tempdir().unwrap();
+    let table = PriceTable::at(dir.path().join("pricing.json"));
+
+    assert_eq!(table.convert(2.0, "usd"), Some(2.0));
+    assert!(table.rate("EUR").is_some());
+    assert!(table.convert(1.0, "XYZ").is_none());
+    assert!(table.set_rate("XYZ", 0.0).is_err());
+
+    table.set_rate("xyz", 3.0).unwrap();
+    assert_eq!(table.convert(2.0, "XYZ"), Some(6.0));
+    assert!(table.currencies().contains(&"XYZ".to_string()));
+
+    assert_eq!(format_amount(0.5, "EUR"), "€0.50");
+    assert_eq!(format_amount(120.4, "JPY"), "¥120");
+}
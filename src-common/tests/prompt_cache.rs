@@ -0,0 +1,141 @@
+//! Prompt caching: breakpoints go on the system prompt and history once the
+//! prefix is long enough, usage is read from the streaming events, and
+//! completion requests carry the markers.
+
+use mcp_common::models::Message;
+use mcp_common::protocol::cache::{
+    mark_breakpoints, system_blocks, CacheUsage, PromptCacheStats, CACHE_METADATA_KEY, MAX_BREAKPOINTS,
+    MIN_CACHEABLE_CHARS,
+};
+use mcp_common::protocol::McpMessage;
+use serde_json::{json, Value};
+
+fn message(role: &str, chars: usize) -> Value {
+    json!({ "role": role, "content": [{ "type": "text", "text": "x".repeat(chars) }] })
+}
+
+fn marked(message: &Value) -> bool {
+    message["content"]
+        .as_array()
+        .and_then(|b| b.last())
+        .is_some_and(|b| b.get("cache_control").is_some())
+}
+
+#[test]
+fn short_prefixes_are_not_marked() {
+    let mut system = system_blocks("Be helpful.");
+    let mut messages = vec![message("user", 100), message("assistant", 100), message("user", 10)];
+
+    assert_eq!(mark_breakpoints(Some(&mut system), &mut messages, &[]), 0);
+    assert!(system[0].get("cache_control").is_none());
+    assert!(!messages.iter().any(marked));
+}
+
+#[test]
+fn history_is_marked_once_the_prefix_with_the_system_prompt_is_long_enough() {
+    // Neither the system prompt nor the history is long enough on its own
+    let mut system = system_blocks(&"s".repeat(MIN_CACHEABLE_CHARS / 2));
+    let mut messages = vec![
+        message("user", MIN_CACHEABLE_CHARS / 4),
+        message("assistant", MIN_CACHEABLE_CHARS / 4),
+        message("user", 10),
+    ];
+
+    assert_eq!(mark_breakpoints(Some(&mut system), &mut messages, &[]), 1);
+    assert!(system[0].get("cache_control").is_none());
+    // The end of the history is marked, never the new turn
+    assert!(!marked(&messages[0]));
+    assert!(marked(&messages[1]));
+    assert!(!marked(&messages[2]));
+}
+
+#[test]
+fn system_messages_and_flagged_messages_are_breakpoints() {
+    let mut messages = vec![
+        message("system", MIN_CACHEABLE_CHARS),
+        message("user", 10),
+        message("assistant", 10),
+        message("system", 500),
+        message("user", 10),
+    ];
+
+    let count = mark_breakpoints(None, &mut messages, &[false, false, false, true, false]);
+    assert_eq!(count, 2);
+    assert!(marked(&messages[0]));
+    assert!(!marked(&messages[2]));
+    // The flagged message is also the end of the history
+    assert!(marked(&messages[3]));
+    assert!(!marked(&messages[4]));
+}
+
+#[test]
+fn only_the_latest_breakpoints_are_kept() {
+    let mut system = system_blocks(&"s".repeat(MIN_CACHEABLE_CHARS));
+    let mut messages: Vec<Value> = (0..8).map(|_| message("user", 10)).collect();
+    let mut flagged = vec![true; 7];
+    flagged.push(false);
+
+    assert_eq!(mark_breakpoints(Some(&mut system), &mut messages, &flagged), MAX_BREAKPOINTS);
+    // The latest breakpoints cover the longest prefixes, so the system prompt gives way
+    assert!(system[0].get("cache_control").is_none());
+    let marked: Vec<usize> = (0..8).filter(|&i| marked(&messages[i])).collect();
+    assert_eq!(marked, vec![3, 4, 5, 6]);
+}
+
+#[test]
+fn completion_requests_mark_the_history() {
+    let mut context = Message::system("c".repeat(MIN_CACHEABLE_CHARS));
+    context
+        .metadata
+        .get_or_insert_with(Default::default)
+        .insert(CACHE_METADATA_KEY.to_string(), json!(true));
+    let messages = vec![context, Message::user("first"), Message::assistant("reply"), Message::user("second")];
+
+    let request = McpMessage::completion_request("claude-3-sonnet", &messages, 1024, 0.7, false);
+    let sent = request.payload["messages"].as_array().unwrap();
+    let marked: Vec<bool> = sent.iter().map(marked).collect();
+    assert_eq!(marked, vec![true, false, true, false]);
+}
+
+#[test]
+fn stream_usage_comes_from_message_start_and_delta() {
+    let mut usage = CacheUsage::default();
+    usage.observe(&json!({
+        "type": "message_start",
+        "message": {
+            "usage": {
+                "input_tokens": 12,
+                "output_tokens": 1,
+                "cache_creation_input_tokens": 0,
+                "cache_read_input_tokens": 2048,
+            }
+        }
+    }));
+    usage.observe(&json!({ "type": "content_block_delta", "delta": { "text": "Hi" } }));
+    usage.observe(&json!({ "type": "message_delta", "usage": { "output_tokens": 42 } }));
+    usage.observe(&json!({ "type": "message_stop" }));
+
+    assert_eq!(
+        usage,
+        CacheUsage {
+            input_tokens: 12,
+            output_tokens: 42,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 2048,
+        }
+    );
+}
+
+#[test]
+fn stats_count_hits_and_savings() {
+    let mut stats = PromptCacheStats::default();
+    stats.record("claude-3-opus", &CacheUsage { cache_creation_input_tokens: 1_000_000, ..Default::default() });
+    stats.record("claude-3-opus", &CacheUsage { cache_read_input_tokens: 1_000_000, ..Default::default() });
+    // Responses without cache activity are not counted
+    stats.record("claude-3-opus", &CacheUsage { input_tokens: 10, ..Default::default() });
+
+    let opus = &stats.models["claude-3-opus"];
+    assert_eq!((opus.requests, opus.hits, opus.misses), (2, 1, 1));
+    assert!((opus.estimated_savings_usd - (13.5 - 3.75)).abs() < 1e-9);
+    assert_eq!(stats.hit_rate(), 0.5);
+}
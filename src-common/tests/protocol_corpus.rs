@@ -0,0 +1,117 @@
+//! Replays the fuzz corpus through the protocol parsers and checks how
+//! malformed input is handled.
+
+use mcp_common::error::McpError;
+use mcp_common::protocol::parse;
+use mcp_common::protocol::{McpMessageType, SseDecoder, SseEvent};
+use std::fs;
+use std::path::Path;
+
+fn corpus() -> Vec<(String, Vec<u8>)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/protocol_parser");
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read(&path).unwrap())
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn frame(name: &str) -> String {
+    let (_, data) = corpus().into_iter().find(|(n, _)| n == name).unwrap();
+    String::from_utf8(data).unwrap()
+}
+
+fn decode(data: &[u8], chunk_size: usize) -> Vec<SseEvent> {
+    let mut decoder = SseDecoder::new();
+    let mut events = Vec::new();
+    for chunk in data.chunks(chunk_size) {
+        events.extend(decoder.push(chunk).unwrap());
+    }
+    events.extend(decoder.finish());
+    events
+}
+
+#[test]
+fn corpus_never_panics() {
+    for (name, data) in corpus() {
+        if let Ok(text) = std::str::from_utf8(&data) {
+            if let Ok(message) = parse::parse_message(text) {
+                let _ = parse::content_text(&message.payload);
+                let _ = parse::error_message(&message.payload, "Unknown error");
+            }
+        }
+        for chunk_size in [1, 3, 64] {
+            for event in decode(&data, chunk_size) {
+                let _ = event.parse::<serde_json::Value>();
+            }
+        }
+        println!("{}: ok", name);
+    }
+}
+
+#[test]
+fn content_may_be_text_or_blocks() {
+    let message = parse::parse_message(&frame("completion_text.json")).unwrap();
+    assert_eq!(parse::content_text(&message.payload).unwrap(), "Hello");
+
+    let message = parse::parse_message(&frame("completion_blocks.json")).unwrap();
+    assert_eq!(parse::content_text(&message.payload).unwrap(), "Hello");
+}
+
+#[test]
+fn frames_with_loose_fields_are_recovered() {
+    let message = parse::parse_message(&frame("error_numeric_id.json")).unwrap();
+    assert_eq!(message.id, "5");
+    assert_eq!(message.version, "v1");
+    assert_eq!(message.message_type, McpMessageType::Error);
+    assert_eq!(parse::error_message(&message.payload, "Unknown error"), "Unknown error (overloaded)");
+}
+
+#[test]
+fn unrecoverable_frames_are_protocol_errors() {
+    for name in ["unknown_type.json", "truncated.json", "array.json", "empty.json"] {
+        let error = parse::parse_message(&frame(name)).unwrap_err();
+        assert!(matches!(error, McpError::Protocol(_)), "{}", name);
+    }
+}
+
+#[test]
+fn malformed_payloads_are_protocol_errors() {
+    let message = parse::parse_message(&frame("stream_bad_content.json")).unwrap();
+    assert!(matches!(parse::content_text(&message.payload), Err(McpError::Protocol(_))));
+
+    let message = parse::parse_message(&frame("auth_bad_success.json")).unwrap();
+    let auth = parse::parse_payload::<parse::AuthPayload>(&message.payload, "authentication");
+    assert!(matches!(auth, Err(McpError::Protocol(_))));
+}
+
+#[test]
+fn sse_events_survive_any_chunking() {
+    let data = fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/protocol_parser/sse_crlf.txt")).unwrap();
+    for chunk_size in 1..data.len() {
+        let events = decode(&data, chunk_size);
+        assert_eq!(events.len(), 2, "chunk size {}", chunk_size);
+        assert_eq!(events[0].event.as_deref(), Some("content"));
+        assert!(events[0].parse::<serde_json::Value>().unwrap().is_some());
+        assert!(events[1].is_done());
+    }
+}
+
+#[test]
+fn bad_sse_data_is_an_error_not_the_end() {
+    let data = fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/protocol_parser/sse_split_utf8.txt")).unwrap();
+    let events = decode(&data, 5);
+    assert_eq!(events.len(), 3);
+
+    // Data lines of one event are joined
+    assert!(events[0].parse::<serde_json::Value>().unwrap().is_some());
+    assert!(matches!(events[1].parse::<serde_json::Value>(), Err(McpError::Protocol(_))));
+
+    // A cut-off UTF-8 sequence at the end is replaced, not a panic
+    assert!(events[2].data.starts_with("caf"));
+}
@@ -0,0 +1,298 @@
+//! Knowledge bases: chunking and indexing documents, searching them,
+//! reindexing with compaction and a new embedder, comparing chunkings, and
+//! citing the passages a reply uses.
+
+use mcp_common::error::McpError;
+use mcp_common::memory::embedding::embedder_for;
+use mcp_common::models::Message;
+use mcp_common::rag::chunking::{chunk, ChunkingConfig, ChunkingStrategy};
+use mcp_common::rag::citations::{cite, find_sources, is_sources_message, sources_message, Citations};
+use mcp_common::rag::evaluate::{evaluate, parse_queries};
+use mcp_common::rag::index::RetrievalConfig;
+use mcp_common::rag::maintenance::{last_report, reindex, stats};
+use mcp_common::rag::KnowledgeBaseStore;
+
+fn open_store(dir: &tempfile::TempDir) -> KnowledgeBaseStore {
+    KnowledgeBaseStore::at(dir.path().join("rag"))
+}
+
+fn config(strategy: ChunkingStrategy, max_tokens: usize, overlap_tokens: usize) -> ChunkingConfig {
+    ChunkingConfig {
+        strategy,
+        max_tokens,
+        overlap_tokens,
+    }
+}
+
+#[test]
+fn sentences_stay_whole_and_overlap() {
+    let text = "First sentence here. Second one follows! Third? v2.1 stays.\n\nNew paragraph starts. And ends.";
+    assert_eq!(
+        chunk(text, &config(ChunkingStrategy::Sentences, 16, 0)),
+        vec![
+            "First sentence here. Second one follows! Third? v2.1 stays.",
+            "New paragraph starts. And ends."
+        ]
+    );
+    assert_eq!(
+        chunk(text, &config(ChunkingStrategy::Sentences, 16, 8))[1],
+        "Third? v2.1 stays.\n\nNew paragraph starts. And ends."
+    );
+}
+
+#[test]
+fn fixed_chunks_cut_between_words() {
+    let text = "word ".repeat(100);
+    let chunks = chunk(&text, &config(ChunkingStrategy::FixedTokens, 16, 4));
+    assert_eq!(chunks.len(), 11);
+    assert!(chunks.iter().all(|c| c.len() <= 64 && c.ends_with("word")));
+
+    // A word longer than a chunk is cut anyway
+    let long = format!("a {} b", "x".repeat(150));
+    let lengths: Vec<usize> = chunk(&long, &config(ChunkingStrategy::FixedTokens, 16, 0))
+        .iter()
+        .map(|c| c.len())
+        .collect();
+    assert_eq!(lengths, vec![1, 64, 64, 24]);
+}
+
+#[test]
+fn markdown_and_code_chunks_follow_structure() {
+    let markdown = "Intro text.\n\n# Title\nSome words here. More words there. Even more words in this part. \
+                    And the end of it.\n```\n# not a heading\n```\n## Sub\nShort.\n";
+    assert_eq!(
+        chunk(markdown, &config(ChunkingStrategy::MarkdownHeaders, 16, 0)),
+        vec![
+            "Intro text.",
+            "# Title\nSome words here. More words there.",
+            "# Title\n\nEven more words in this part. And the end of it.",
+            "# Title\n\n```\n# not a heading\n```",
+            "## Sub\nShort.",
+        ]
+    );
+
+    let code = "use x;\n\n/// Doc\n#[inline]\npub fn a() {\n    1\n}\n\nstruct B;\nimpl B {\n    fn c() {}\n}\n";
+    assert_eq!(
+        chunk(code, &config(ChunkingStrategy::CodeSymbols, 64, 0)),
+        vec!["use x;", "/// Doc\n#[inline]\npub fn a() {\n    1\n}", "struct B;", "impl B {\n    fn c() {}\n}"]
+    );
+
+    assert_eq!("markdown".parse::<ChunkingStrategy>().unwrap(), ChunkingStrategy::MarkdownHeaders);
+    assert!("paragraphs".parse::<ChunkingStrategy>().is_err());
+    assert!(config(ChunkingStrategy::Sentences, 8, 0).validate().is_err());
+    assert!(config(ChunkingStrategy::Sentences, 64, 40).validate().is_err());
+}
+
+#[test]
+fn documents_are_searched_until_removed() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open_store(&dir);
+    assert!(store.create("bad name", "hashing-256").is_err());
+    assert!(store.create("docs", "unknown-model").is_err());
+    store.create("docs", "hashing-256").unwrap();
+    assert!(store.create("docs", "hashing-256").is_err());
+
+    let deploy = store
+        .add_document("docs", "deploy.md", None, "Deploys run every Friday from the release branch.")
+        .unwrap();
+    store.add_document("docs", "billing.md", None, "Invoices are sent monthly.").unwrap();
+    assert!(store.add_document("docs", "empty.md", None, "  ").is_err());
+
+    let hits = store.search("docs", "when do deploys run").unwrap();
+    assert_eq!(hits[0].document_title, "deploy.md");
+    assert_eq!(hits[0].chunk_id, format!("{}:0", deploy.id));
+
+    store.remove_document("docs", "deploy.md").unwrap();
+    let hits = store.search("docs", "when do deploys run").unwrap();
+    assert!(hits.iter().all(|h| h.document_id != deploy.id));
+
+    // The removed document's chunk waits for compaction
+    let before = stats(&store, "docs").unwrap();
+    assert_eq!((before.documents, before.chunks, before.orphaned_chunks), (1, 2, 1));
+    assert_eq!(before.reclaimable_chunks(), 1);
+    assert_eq!(store.list().len(), 1);
+}
+
+#[test]
+fn reindex_compacts_and_switches_embedders() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open_store(&dir);
+    store.create("docs", "hashing-256").unwrap();
+    store.add_document("docs", "a.md", None, "Alpha notes.").unwrap();
+    store.add_document("docs", "b.md", None, "Shared paragraph.").unwrap();
+    store.add_document("docs", "copy.md", None, "Shared paragraph.").unwrap();
+    let gone = store.add_document("docs", "c.md", None, "Soon removed.").unwrap();
+    store.remove_document("docs", &gone.id).unwrap();
+    let before = stats(&store, "docs").unwrap();
+    assert_eq!((before.chunks, before.orphaned_chunks, before.duplicate_chunks), (4, 1, 1));
+
+    // Stopping keeps the old index
+    assert!(matches!(
+        reindex(&store, "docs", Some("hashing-512"), |_, _| false),
+        Err(McpError::Cancelled)
+    ));
+    assert_eq!(store.get("docs").unwrap().generation, 1);
+
+    let mut calls = Vec::new();
+    let report = reindex(&store, "docs", Some("hashing-512"), |done, total| {
+        calls.push((done, total));
+        true
+    })
+    .unwrap();
+    assert_eq!(calls.last(), Some(&(3, 3)));
+    assert_eq!(report.before, before);
+    assert_eq!(report.after.chunks, 2);
+    assert_eq!(report.after.reclaimable_chunks(), 0);
+    assert_eq!(report.after.dimensions, 512);
+    assert_eq!(report.self_retrieval, 1.0);
+    assert_eq!(last_report(&store, "docs"), Some(report));
+
+    let kb = store.get("docs").unwrap();
+    assert_eq!((kb.generation, kb.embedding_model.as_str()), (2, "hashing-512"));
+    assert!(!dir.path().join("rag/docs/index-1.json").exists());
+    assert_eq!(store.search("docs", "alpha").unwrap()[0].document_title, "a.md");
+    assert!(reindex(&store, "docs", Some("nope"), |_, _| true).is_err());
+}
+
+#[test]
+fn embedders_are_found_by_id() {
+    assert!(embedder_for("hashing-64").is_some());
+    assert!(embedder_for("hashing-0").is_none());
+    assert!(embedder_for("minilm").is_none());
+}
+
+#[test]
+fn retrieval_settings_pick_chunks() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open_store(&dir);
+    store.create("docs", "hashing-256").unwrap();
+    store.add_document("docs", "one.md", None, "Rust compiler errors explained.").unwrap();
+    store.add_document("docs", "two.md", None, "Rust compiler errors explained again.").unwrap();
+    store.add_document("docs", "three.md", None, "Rust borrow checker basics.").unwrap();
+
+    let similar = RetrievalConfig {
+        k: 2,
+        ..RetrievalConfig::default()
+    };
+    let titles = |retrieval: &RetrievalConfig| -> Vec<String> {
+        store
+            .search_with("docs", "rust compiler errors", retrieval)
+            .unwrap()
+            .into_iter()
+            .map(|h| h.document_title)
+            .collect()
+    };
+    assert_eq!(titles(&similar), vec!["one.md", "two.md"]);
+
+    // Diversity trades the near-copy for the other topic
+    let diverse = RetrievalConfig {
+        mmr_lambda: Some(0.3),
+        ..similar.clone()
+    };
+    assert_eq!(titles(&diverse), vec!["one.md", "three.md"]);
+
+    let strict = RetrievalConfig {
+        min_score: 0.99,
+        ..similar
+    };
+    assert!(titles(&strict).is_empty());
+
+    let kb = store.configure("docs", None, Some(diverse.clone())).unwrap();
+    assert_eq!(kb.retrieval, diverse);
+    assert_eq!(store.search("docs", "rust compiler errors").unwrap().len(), 2);
+    let invalid = RetrievalConfig {
+        k: 0,
+        ..RetrievalConfig::default()
+    };
+    assert!(store.configure("docs", None, Some(invalid)).is_err());
+}
+
+#[test]
+fn chunkings_are_compared_on_sample_queries() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open_store(&dir);
+    store.create("docs", "hashing-256").unwrap();
+    store
+        .add_document(
+            "docs",
+            "guide.md",
+            None,
+            "# Deploying\nDeploys run on Fridays from the release branch.\n\n# Billing\nInvoices go out monthly.",
+        )
+        .unwrap();
+
+    let queries = parse_queries(
+        r#"{"query": "when do deploys run", "documents": ["guide.md"], "contains": "fridays"}
+{"query": "how often are invoices sent", "contains": "monthly"}"#,
+    )
+    .unwrap();
+    assert_eq!(queries.len(), 2);
+    assert!(parse_queries(r#"[{"query": "unanswerable"}]"#).is_err());
+    assert!(parse_queries("[]").is_err());
+
+    let scores = evaluate(&store, "docs", &queries, &[]).unwrap();
+    assert_eq!(scores.len(), ChunkingStrategy::ALL.len());
+    let markdown = scores
+        .iter()
+        .find(|s| s.chunking.strategy == ChunkingStrategy::MarkdownHeaders)
+        .unwrap();
+    assert_eq!((markdown.chunks, markdown.hit_rate, markdown.mrr), (2, 1.0, 1.0));
+
+    let only = evaluate(&store, "docs", &queries, &[ChunkingStrategy::FixedTokens]).unwrap();
+    assert_eq!(only.len(), 1);
+    assert!(only[0].average_chunk_tokens > 0.0);
+}
+
+#[test]
+fn replies_cite_the_passages_they_use() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open_store(&dir);
+    store.create("ops", "hashing-256").unwrap();
+    store.create("finance", "hashing-512").unwrap();
+    store
+        .add_document("ops", "deploy.md", Some("docs/deploy.md"), "Deploys run on Fridays from the release branch.")
+        .unwrap();
+    store.add_document("ops", "office.md", None, "The office closes at noon on holidays.").unwrap();
+    store.add_document("finance", "billing.md", None, "Invoices go out monthly.").unwrap();
+
+    let names = vec!["ops".to_string(), "finance".to_string(), "gone".to_string()];
+    let sources = find_sources(&store, &names, "deploys on fridays, invoices monthly, office on holidays");
+    let numbers: Vec<usize> = sources.iter().map(|s| s.number).collect();
+    assert_eq!(numbers, vec![1, 2, 3]);
+    let number_of = |title: &str| sources.iter().find(|s| s.document_title == title).unwrap().number;
+    let (deploy, billing, office) = (number_of("deploy.md"), number_of("billing.md"), number_of("office.md"));
+    assert!(is_sources_message(&sources_message(&sources)));
+    assert!(!is_sources_message(&Message::system("Be brief.")));
+
+    // A marker after the full stop belongs to the sentence before it, and
+    // numbers of passages that weren't given are ignored
+    let reply = format!(
+        "Deploys run on Fridays [{}]. Invoices go out monthly. [{}, 9]\n\nThe office closes at noon on holidays. \
+         The weather is nice.",
+        deploy, billing
+    );
+    let citations = cite(&reply, &sources).unwrap();
+    let cited = format!("Deploys run on Fridays [{}].", deploy);
+    let statements: Vec<(&str, Vec<usize>, bool)> = citations
+        .statements
+        .iter()
+        .map(|s| (&reply[s.start..s.end], s.sources.clone(), s.inferred))
+        .collect();
+    assert_eq!(
+        statements,
+        vec![
+            (cited.as_str(), vec![deploy], false),
+            ("Invoices go out monthly.", vec![billing], false),
+            ("The office closes at noon on holidays.", vec![office], true),
+        ]
+    );
+    assert!(cite(&reply, &[]).is_none());
+
+    let mut message = Message::assistant(reply.clone());
+    citations.attach(&mut message);
+    let stored = Citations::of(&message).unwrap();
+    assert_eq!(stored, citations);
+    assert!(stored.footnotes().contains(&format!("[{}] deploy.md (docs/deploy.md)", deploy)));
+    assert_eq!(stored.footnotes().len(), 3);
+    assert!(Citations::of(&Message::assistant("No sources")).is_none());
+}
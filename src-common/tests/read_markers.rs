@@ -0,0 +1,103 @@
+//! Read markers: unread counts, first unread message, and merging markers
+//! from other devices.
+
+use chrono::Duration;
+use mcp_common::models::{Conversation, Message, Model};
+use mcp_common::sync::read::{conversation_for_key, first_unread, sync_key, unread_count};
+use mcp_common::sync::{ReadMarker, ReadMarkers};
+use mcp_common::utils::clock;
+
+fn conversation() -> Conversation {
+    let mut conversation = Conversation::new("Release", Model::default_claude());
+    conversation.add_message(Message::system("You are terse."));
+    conversation.add_message(Message::user("What's left?"));
+    conversation.add_message(Message::assistant("The changelog."));
+    conversation
+}
+
+#[test]
+fn replies_after_the_last_user_message_are_unread() {
+    let mut conversation = conversation();
+    assert_eq!(first_unread(&conversation, None), Some(2));
+    assert_eq!(unread_count(&conversation, None), 1);
+
+    // Writing a message means everything before it was read
+    conversation.add_message(Message::user("Thanks"));
+    assert_eq!(first_unread(&conversation, None), None);
+    assert_eq!(unread_count(&conversation, None), 0);
+
+    // A digest posting replies on its own
+    conversation.add_message(Message::assistant("Reminder: tag the release."));
+    conversation.add_message(Message::assistant("Reminder: publish the notes."));
+    assert_eq!(first_unread(&conversation, None), Some(4));
+    assert_eq!(unread_count(&conversation, None), 2);
+}
+
+#[test]
+fn markers_track_reading_and_persist() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("read_markers.json");
+    let markers = ReadMarkers::at(path.clone());
+    let mut conversation = conversation();
+
+    assert_eq!(markers.unread_count(&conversation), 1);
+    let marker = markers.mark_read(&conversation).unwrap().unwrap();
+    assert_eq!(marker.position, 3);
+    assert_eq!(markers.unread_count(&conversation), 0);
+    // Reading again changes nothing
+    assert!(markers.mark_read(&conversation).unwrap().is_none());
+
+    conversation.add_message(Message::assistant("Also the docs."));
+    assert_eq!(markers.first_unread(&conversation), Some(3));
+
+    let reopened = ReadMarkers::at(path);
+    assert_eq!(reopened.get(&conversation.id), Some(marker));
+    assert_eq!(reopened.unread_count(&conversation), 1);
+
+    reopened.remove(&conversation.id).unwrap();
+    assert!(reopened.get(&conversation.id).is_none());
+}
+
+#[test]
+fn the_marker_furthest_along_wins() {
+    let dir = tempfile::tempdir().unwrap();
+    let markers = ReadMarkers::at(dir.path().join("read_markers.json"));
+    let mut conversation = conversation();
+    conversation.add_message(Message::assistant("And the docs."));
+
+    let phone = ReadMarker {
+        conversation_id: conversation.id.clone(),
+        message_id: conversation.messages[3].id.clone(),
+        position: 4,
+        read_at: clock::now(),
+    };
+    assert!(markers.apply(phone.clone()).unwrap().is_some());
+    assert_eq!(markers.unread_count(&conversation), 0);
+
+    // A laptop that read less, later, doesn't make messages unread again
+    let laptop = ReadMarker {
+        message_id: conversation.messages[2].id.clone(),
+        position: 3,
+        read_at: clock::now() + Duration::minutes(5),
+        ..phone.clone()
+    };
+    assert!(markers.apply(laptop).unwrap().is_none());
+    assert_eq!(markers.get(&conversation.id), Some(phone.clone()));
+
+    // A marker for a message this device doesn't have falls back to its position
+    let tablet = ReadMarker {
+        message_id: "not-synced-yet".to_string(),
+        position: 5,
+        ..phone
+    };
+    assert!(markers.apply(tablet).unwrap().is_some());
+    assert_eq!(markers.first_unread(&conversation), None);
+}
+
+#[test]
+fn sync_keys_name_the_conversation() {
+    assert_eq!(sync_key("abc"), "conversation:abc/read");
+    assert_eq!(conversation_for_key("conversation:abc/read"), Some("abc"));
+    assert_eq!(conversation_for_key("conversation:abc/messages"), None);
+    assert_eq!(conversation_for_key("team:conversation:abc/read"), None);
+}
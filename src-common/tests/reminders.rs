@@ -0,0 +1,83 @@
+//! Follow-up reminders: delays, snoozing, canceling and delivery with a link
+//! back to the conversation.
+
+use chrono::Duration;
+use mcp_common::events::{get_event_bus, names, Backpressure, Topic};
+use mcp_common::reminders::{parse_delay, parse_when, ReminderStore};
+use mcp_common::testing::TestHarness;
+use mcp_common::utils::clock;
+
+#[test]
+fn delays_read_like_people_write_them() {
+    assert_eq!(parse_delay("3d").unwrap(), Duration::days(3));
+    assert_eq!(parse_delay("in 3 days").unwrap(), Duration::days(3));
+    assert_eq!(parse_delay("2 Hours").unwrap(), Duration::hours(2));
+    assert_eq!(parse_delay("30m").unwrap(), Duration::minutes(30));
+    assert_eq!(parse_delay("tomorrow").unwrap(), Duration::days(1));
+    assert!(parse_delay("0d").is_err());
+    assert!(parse_delay("soon").is_err());
+    assert!(parse_delay("2 fortnights").is_err());
+    assert!(parse_delay("400 days").is_err());
+    for huge in ["in 99999999999999 weeks", "9999999999999999 weeks"] {
+        assert!(parse_delay(huge).unwrap_err().to_string().contains("at most 365 days"));
+    }
+
+    let now = TestHarness::epoch();
+    assert_eq!(parse_when("1w", now).unwrap(), now + Duration::weeks(1));
+    assert_eq!(
+        parse_when("2024-01-02T09:00:00Z", now).unwrap(),
+        now + Duration::hours(33)
+    );
+    assert!(parse_when("2023-12-31T09:00:00Z", now).is_err());
+}
+
+#[tokio::test]
+async fn due_reminders_are_announced_once_and_can_be_snoozed() {
+    let h = TestHarness::new();
+    let store = ReminderStore::at(h.dir().join("reminders.json"));
+    let now = TestHarness::epoch();
+    let soon = store
+        .add(
+            "c1",
+            "Launch plan",
+            now + Duration::days(3),
+            Some("  Check the numbers ".to_string()),
+        )
+        .unwrap();
+    let later = store.add("c2", "Hiring", now + Duration::days(10), None).unwrap();
+    assert!(store.add("c1", "Launch plan", now, None).is_err());
+    assert_eq!(soon.note.as_deref(), Some("Check the numbers"));
+    assert_eq!(store.list(None).len(), 2);
+    assert_eq!(store.list(Some("c2"))[0].id, later.id);
+
+    let mut events = get_event_bus().subscribe(&[Topic::System], 64, Backpressure::DropNewest);
+    assert!(store.deliver_due().unwrap().is_empty());
+    h.clock.advance(Duration::days(3));
+    let due = store.deliver_due().unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].body(), "Follow up on \"Launch plan\": Check the numbers");
+    assert!(store.deliver_due().unwrap().is_empty());
+
+    let event = loop {
+        let event = events.recv().await.unwrap();
+        if event.name == names::REMINDER_DUE && event.payload["id"] == soon.id.as_str() {
+            break event;
+        }
+    };
+    assert_eq!(event.payload["link"], format!("papin://conversation/{}", "c1"));
+
+    // Snoozing sets it again, even after it was announced
+    let snoozed = store.snooze(&soon.id[..8], clock::now() + Duration::hours(1)).unwrap();
+    assert_eq!(snoozed.snoozes, 1);
+    assert!(snoozed.delivered_at.is_none());
+    h.clock.advance(Duration::hours(1));
+    assert_eq!(store.deliver_due().unwrap()[0].id, soon.id);
+
+    store.cancel(&later.id).unwrap();
+    assert!(store.cancel(&later.id).is_err());
+    assert_eq!(store.cancel_for_conversation("c1").unwrap(), 1);
+    assert!(store.list(None).is_empty());
+
+    let reopened = ReminderStore::at(h.dir().join("reminders.json"));
+    assert!(reopened.list(None).is_empty());
+}
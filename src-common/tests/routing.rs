@@ -0,0 +1,107 @@
+//! Model aliases: targets are tried in order until one's conditions hold and
+//! its model is available, and the remaining budget feeds budget conditions.
+
+use mcp_common::events::{get_event_bus, names, Backpressure, Topic};
+use mcp_common::service::routing::{
+    set_alias, set_budget_remaining, ModelAlias, RouteCondition, RouteTarget, RoutingContext, RoutingTable,
+    ALIAS_METADATA_KEY,
+};
+use serde_json::json;
+
+fn online() -> RoutingContext {
+    RoutingContext {
+        online: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn first_target_whose_conditions_hold_is_chosen() {
+    let table = RoutingTable::defaults();
+
+    let decision = table.resolve("fast", &online()).unwrap();
+    assert_eq!((decision.model.as_str(), decision.target_index), ("claude-3-haiku-20240307", 0));
+    assert_eq!(table.resolve("fast", &RoutingContext::default()).unwrap().model, "tinyllama");
+
+    // Long prompts skip the small-context target
+    let long = RoutingContext {
+        prompt_tokens: 5000,
+        ..online()
+    };
+    assert_eq!(table.resolve("offline-default", &online()).unwrap().model, "claude-3-haiku-20240307");
+    assert_eq!(table.resolve("offline-default", &long).unwrap().model, "claude-3-sonnet-20240229");
+}
+
+#[test]
+fn budget_conditions_follow_the_remaining_budget() {
+    let table = RoutingTable::defaults();
+    let with_budget = |budget_remaining| RoutingContext {
+        budget_remaining,
+        ..online()
+    };
+
+    // Untracked budgets never block a target
+    assert_eq!(table.resolve("smart", &with_budget(None)).unwrap().model, "claude-3-opus-20240229");
+    assert_eq!(table.resolve("smart", &with_budget(Some(5.0))).unwrap().model, "claude-3-opus-20240229");
+    assert_eq!(table.resolve("smart", &with_budget(Some(0.5))).unwrap().model, "claude-3-sonnet-20240229");
+}
+
+#[test]
+fn unavailable_models_are_skipped() {
+    let table = RoutingTable::defaults();
+    let ctx = RoutingContext {
+        available_models: vec!["claude-3-sonnet-20240229".to_string(), "tinyllama".to_string()],
+        ..online()
+    };
+    let decision = table.resolve("smart", &ctx).unwrap();
+    assert_eq!((decision.model.as_str(), decision.target_index), ("claude-3-sonnet-20240229", 1));
+}
+
+#[test]
+fn unknown_and_unusable_aliases_are_errors() {
+    let mut table = RoutingTable::default();
+    table.set(ModelAlias {
+        name: "cloud-only".to_string(),
+        description: String::new(),
+        targets: vec![RouteTarget::new("claude-3-haiku-20240307").when(RouteCondition::Online)],
+    });
+
+    assert!(table.resolve("missing", &online()).unwrap_err().to_string().contains("Unknown model alias"));
+    assert!(table
+        .resolve("cloud-only", &RoutingContext::default())
+        .unwrap_err()
+        .to_string()
+        .contains("No model of alias"));
+    assert!(table.resolve("cloud-only", &online()).is_ok());
+}
+
+#[test]
+fn alias_is_recorded_in_any_metadata() {
+    let mut metadata = json!({ "workspace": "acme" });
+    set_alias(&mut metadata, "fast");
+    assert_eq!(metadata, json!({ "workspace": "acme", ALIAS_METADATA_KEY: "fast" }));
+
+    // Metadata that isn't an object is replaced rather than panicking
+    let mut metadata = json!(["not", "an", "object"]);
+    set_alias(&mut metadata, "smart");
+    assert_eq!(metadata, json!({ ALIAS_METADATA_KEY: "smart" }));
+}
+
+#[tokio::test]
+async fn running_out_of_budget_is_announced_once() {
+    let mut system = get_event_bus().subscribe(&[Topic::System], 64, Backpressure::DropNewest);
+    let exceeded = |events: Vec<mcp_common::events::Event>| {
+        events.iter().filter(|e| e.name == names::BUDGET_EXCEEDED).count()
+    };
+
+    set_budget_remaining(Some(2.0));
+    set_budget_remaining(Some(-0.5));
+    set_budget_remaining(Some(-1.0));
+    assert_eq!(exceeded(system.drain()), 1);
+
+    // Topping the budget up arms the announcement again
+    set_budget_remaining(Some(10.0));
+    set_budget_remaining(Some(0.0));
+    assert_eq!(exceeded(system.drain()), 1);
+    set_budget_remaining(None);
+}
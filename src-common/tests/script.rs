@@ -0,0 +1,79 @@
+//! Scripted sessions: parsing, running on the test harness and JUnit output.
+
+use mcp_common::service::script::{parse_script, ScriptAction, ScriptRunner};
+use mcp_common::service::ChatService;
+use mcp_common::testing::TestHarness;
+use std::sync::Arc;
+
+const SMOKE: &str = r#"
+name: smoke
+steps:
+  - new: { title: Smoke test }
+  - system: Answer in one word.
+  - name: capital
+    send: What is the capital of France?
+    expect:
+      - contains: paris
+  - send: And of Italy?
+    expect:
+      - regex: "^Rome"
+"#;
+
+#[test]
+fn scripts_parse_and_reject_misplaced_checks() {
+    let script = parse_script(SMOKE).unwrap();
+    assert_eq!(script.steps.len(), 4);
+    assert!(matches!(script.steps[0].action, ScriptAction::New(ref new) if new.title.as_deref() == Some("Smoke test")));
+    assert_eq!(script.steps[2].display_name(), "capital");
+    assert_eq!(script.steps[3].display_name(), "send: And of Italy?");
+
+    // JSON is YAML too
+    assert!(parse_script(r#"{"name": "j", "steps": [{"send": "Hi"}]}"#).is_ok());
+
+    assert!(parse_script("name: empty\nsteps: []").is_err());
+    assert!(parse_script("name: s\nsteps:\n  - system: Hi\n    expect: [{contains: x}]").is_err());
+    assert!(parse_script("name: s\nsteps:\n  - send: Hi\n    expect: [{regex: '('}]").is_err());
+    assert!(parse_script("name: s\nsteps:\n  - shout: Hi").is_err());
+}
+
+#[tokio::test]
+async fn run_checks_responses_and_cleans_up() {
+    let h = TestHarness::new();
+    h.provider.reply("Paris").reply("Milan");
+    let chat = Arc::new(ChatService::new(h.service.clone()));
+
+    let report = ScriptRunner::new(chat.clone()).run(&parse_script(SMOKE).unwrap()).await;
+    assert_eq!(report.passed(), 3);
+    assert_eq!(report.failed(), 1);
+    assert_eq!(report.steps[3].output.as_deref(), Some("Milan"));
+    assert_eq!(report.steps[3].failures, vec!["expected to match /^Rome/".to_string()]);
+
+    // Both prompts went to the same conversation, which is gone afterwards
+    let requests = h.provider.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].len() > requests[0].len());
+    assert!(chat.list_conversations().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn errors_skip_the_remaining_steps() {
+    let h = TestHarness::new();
+    h.provider.fail("connection reset");
+    let chat = Arc::new(ChatService::new(h.service.clone()));
+
+    let script = parse_script("name: flaky\nkeep: true\nsteps:\n  - send: Hi\n  - send: Again").unwrap();
+    let report = ScriptRunner::new(chat.clone()).run(&script).await;
+    assert!(report.steps[0].error.is_some());
+    assert!(report.steps[1].skipped);
+    assert!(!report.all_passed());
+    assert_eq!((report.failed(), report.skipped()), (1, 1));
+
+    // `keep` leaves the conversation for inspection
+    assert_eq!(chat.list_conversations().await.unwrap().len(), 1);
+
+    let junit = report.to_junit();
+    assert!(junit.contains("tests=\"2\" failures=\"1\" skipped=\"1\""));
+    assert!(junit.contains("name=\"1. send: Hi\""));
+    assert!(junit.contains("<skipped/>"));
+    assert!(junit.contains("<failure message=\""));
+}
@@ -0,0 +1,263 @@
+//! Typed streaming events: content blocks, fences split over chunks, tool
+//! calls, running usage and cost, and the snapshot adapter.
+
+use mcp_common::models::message::ContentType;
+use mcp_common::models::{Message, ToolCall};
+use mcp_common::service::pricing::ModelPrice;
+use mcp_common::service::stream::{
+    into_snapshots, BlockKind, CostMeter, EventEncoder, StreamEvent, Usage, USAGE_METADATA_KEY,
+};
+use mcp_common::testing::{TestHarness, Turn};
+use std::collections::HashMap;
+
+/// Blocks of a reply as (kind, text) in order
+fn blocks(events: &[StreamEvent]) -> Vec<(BlockKind, String)> {
+    let mut blocks: Vec<(BlockKind, String)> = Vec::new();
+    for event in events {
+        match event {
+            StreamEvent::ContentBlockStart { index, block } => {
+                assert_eq!(*index, blocks.len());
+                blocks.push((block.clone(), String::new()));
+            }
+            StreamEvent::TextDelta { index, text } => blocks[*index].1.push_str(text),
+            _ => {}
+        }
+    }
+    blocks
+}
+
+fn code(language: Option<&str>) -> BlockKind {
+    BlockKind::Code {
+        language: language.map(str::to_string),
+    }
+}
+
+#[test]
+fn fences_split_over_chunks_become_code_blocks() {
+    let mut encoder = EventEncoder::new();
+    let mut events = Vec::new();
+    for delta in ["Here:\n``", "`rust\nfn main() {}\n", "``", "`\nDone"] {
+        events.extend(encoder.push(&Message::assistant(delta)));
+    }
+    events.extend(encoder.finish(Some(10)));
+
+    assert!(matches!(events[0], StreamEvent::MessageStart { .. }));
+    assert_eq!(
+        blocks(&events),
+        vec![
+            (BlockKind::Text, "Here:\n".to_string()),
+            (code(Some("rust")), "fn main() {}\n".to_string()),
+            (BlockKind::Text, "Done".to_string()),
+        ]
+    );
+    // Every block stops, and no text arrives outside one
+    let stops = events
+        .iter()
+        .filter(|e| matches!(e, StreamEvent::ContentBlockStop { .. }))
+        .count();
+    assert_eq!(stops, 3);
+
+    match &events[events.len() - 2] {
+        StreamEvent::Usage(usage) => {
+            assert!(usage.estimated);
+            assert_eq!(usage.input_tokens, 10);
+            assert!(usage.output_tokens > 0);
+        }
+        other => panic!("Expected usage, got {:?}", other),
+    }
+    match events.last().unwrap() {
+        StreamEvent::Done { message } => {
+            assert_eq!(message.text(), "Here:\n```rust\nfn main() {}\n```\nDone")
+        }
+        other => panic!("Expected done, got {:?}", other),
+    }
+    // Nothing after the end
+    assert!(encoder.finish(None).is_empty());
+}
+
+#[test]
+fn unclosed_fences_and_backticks_in_prose() {
+    let mut encoder = EventEncoder::new();
+    let mut events = encoder.push(&Message::assistant("Use `x`.\n``not a fence\n```\nlet a = 1;\n```"));
+    events.extend(encoder.finish(None));
+
+    assert_eq!(
+        blocks(&events),
+        vec![
+            (BlockKind::Text, "Use `x`.\n``not a fence\n".to_string()),
+            (code(None), "let a = 1;\n".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn tool_calls_get_their_own_blocks_and_reported_usage_wins() {
+    let mut encoder = EventEncoder::new();
+    let mut events = encoder.push(&Message::assistant("Checking the weather."));
+
+    let call = ToolCall::new("call_1", "weather", serde_json::json!({ "city": "Ghent" }));
+    let mut chunk = Message::assistant("");
+    chunk.content.parts = vec![ContentType::ToolCalls { calls: vec![call.clone()] }];
+    let mut metadata = HashMap::new();
+    metadata.insert(
+        USAGE_METADATA_KEY.to_string(),
+        serde_json::json!({ "input_tokens": 120, "output_tokens": 14 }),
+    );
+    chunk.metadata = Some(metadata);
+    events.extend(encoder.push(&chunk));
+    events.extend(encoder.finish(Some(1)));
+
+    let kinds = blocks(&events);
+    assert_eq!(kinds[0].0, BlockKind::Text);
+    assert_eq!(
+        kinds[1].0,
+        BlockKind::ToolCall {
+            id: "call_1".to_string(),
+            name: "weather".to_string()
+        }
+    );
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, StreamEvent::ToolCall { index: 1, call: c } if c == &call)));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        StreamEvent::Usage(u) if u.input_tokens == 120 && u.output_tokens == 14 && !u.estimated
+    )));
+
+    // Events serialize with their type for frontends
+    let json = serde_json::to_value(&events[1]).unwrap();
+    assert_eq!(json["type"], "content-block-start");
+    assert_eq!(json["block"]["kind"], "text");
+}
+
+/// A chunk reporting usage
+fn usage_chunk(text: &str, usage: serde_json::Value) -> Message {
+    let mut chunk = Message::assistant(text);
+    chunk.metadata = Some(HashMap::from([(USAGE_METADATA_KEY.to_string(), usage)]));
+    chunk
+}
+
+/// Running usage sent among events
+fn deltas(events: &[StreamEvent]) -> Vec<(u64, u64, bool)> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            StreamEvent::UsageDelta(u) => Some((u.input_tokens, u.output_tokens, u.estimated)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn usage_and_cost_run_as_the_reply_streams() {
+    let price = ModelPrice {
+        input_per_mtok: 3.0,
+        output_per_mtok: 15.0,
+    };
+    let meter = CostMeter {
+        price: Some(price),
+        currency: "USD".to_string(),
+        rate: 1.0,
+    };
+    let mut encoder = EventEncoder::new().with_prompt_tokens(1000).with_meter(meter);
+
+    // Estimated until the provider says otherwise
+    assert_eq!(deltas(&encoder.push(&Message::assistant("Twelve chars"))), vec![(1000, 3, true)]);
+    assert_eq!(encoder.usage().cost, Some(price.cost(1000, 3)));
+
+    // The prompt is reported first, the reply's tokens later
+    let prompt = usage_chunk(" more", serde_json::json!({ "input_tokens": 1200 }));
+    assert_eq!(deltas(&encoder.push(&prompt)), vec![(1200, 5, true)]);
+    let reply = usage_chunk("", serde_json::json!({ "output_tokens": 9 }));
+    assert_eq!(deltas(&encoder.push(&reply)), vec![(1200, 9, false)]);
+    assert!(deltas(&encoder.push(&Message::assistant(""))).is_empty());
+
+    let events = encoder.finish(None);
+    let usage: Vec<&Usage> = events
+        .iter()
+        .filter_map(|e| match e {
+            StreamEvent::Usage(u) => Some(u),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        usage,
+        vec![&Usage {
+            input_tokens: 1200,
+            output_tokens: 9,
+            estimated: false,
+            cost: Some(price.cost(1200, 9)),
+            currency: "USD".to_string(),
+        }]
+    );
+
+    // Without a price the cost is unknown
+    let mut unpriced = EventEncoder::new();
+    unpriced.push(&Message::assistant("Hi"));
+    assert_eq!(unpriced.usage().cost, None);
+    let json = serde_json::to_value(StreamEvent::UsageDelta(unpriced.usage())).unwrap();
+    assert_eq!(json["type"], "usage-delta");
+}
+
+#[test]
+fn snapshots_are_diffed_into_deltas() {
+    let mut encoder = EventEncoder::new();
+    let mut events = Vec::new();
+    for snapshot in ["", "Hello", "Hello wor", "Hello world"] {
+        events.extend(encoder.push_snapshot(&Message::assistant(snapshot)));
+    }
+    // A filter rewrote earlier text; later text extends the rewrite
+    events.extend(encoder.push_snapshot(&Message::assistant("Hello [redacted]")));
+    events.extend(encoder.push_snapshot(&Message::assistant("Hello [redacted]!")));
+    events.extend(encoder.finish(None));
+
+    assert_eq!(blocks(&events), vec![(BlockKind::Text, "Hello world!".to_string())]);
+    match events.last().unwrap() {
+        StreamEvent::Done { message } => assert_eq!(message.text(), "Hello [redacted]!"),
+        other => panic!("Expected done, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn chat_streams_events_and_the_adapter_restores_snapshots() {
+    let h = TestHarness::new();
+    h.provider.stream(&["Try:\n```sh\ncar", "go test\n```\n", "Good luck"]);
+    let conversation = h.chat.create_conversation("Events", None).await.unwrap();
+
+    let mut stream = h.chat.send_message_events(&conversation.id, "How do I test?").await.unwrap();
+    let mut events = Vec::new();
+    while let Some(event) = stream.recv().await {
+        events.push(event.unwrap());
+    }
+    assert_eq!(
+        blocks(&events),
+        vec![
+            (BlockKind::Text, "Try:\n".to_string()),
+            (code(Some("sh")), "cargo test\n".to_string()),
+            (BlockKind::Text, "Good luck".to_string()),
+        ]
+    );
+    assert!(matches!(events.last(), Some(StreamEvent::Done { .. })));
+
+    let stored = h.chat.get_conversation(&conversation.id).await.unwrap();
+    assert_eq!(stored.messages.last().unwrap().text(), "Try:\n```sh\ncargo test\n```\nGood luck");
+
+    // The older interface, rebuilt from events
+    h.provider.stream(&["One", " two"]);
+    let events = h.chat.send_message_events(&conversation.id, "Count").await.unwrap();
+    let (snapshots, error) = TestHarness::drain(into_snapshots(events)).await;
+    assert!(error.is_none());
+    let texts: Vec<_> = snapshots.iter().map(|s| s.text()).collect();
+    // The content filters hold back the short reply until the stream ends
+    assert_eq!(texts, vec!["One two", "One two"]);
+
+    // Errors end the event stream too
+    h.provider
+        .push(Turn::StreamThenFail(vec!["Part".to_string()], "connection reset".to_string()));
+    let mut stream = h.chat.send_message_events(&conversation.id, "More").await.unwrap();
+    let mut last = None;
+    while let Some(event) = stream.recv().await {
+        last = Some(event);
+    }
+    assert!(last.unwrap().is_err());
+}
@@ -0,0 +1,164 @@
+//! Team workspace: changes are pushed with the revision they are based on,
+//! others' changes are pulled, and roles from the server gate edits.
+
+use mcp_common::sync::{TeamRole, TeamWorkspace};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// What the fake team server answers and what it was sent
+#[derive(Default)]
+struct Server {
+    role: String,
+    sync_response: Value,
+    requests: Vec<(String, String, Value)>,
+}
+
+/// Serve the team API until the test ends
+async fn serve(server: Arc<Mutex<Server>>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let header_end = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break end + 4;
+                }
+            };
+            let head = String::from_utf8_lossy(&request[..header_end]).to_string();
+            let length = head
+                .lines()
+                .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                .unwrap_or(0);
+            while request.len() < header_end + length {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let mut words = head.split_whitespace();
+            let (method, path) = (words.next().unwrap().to_string(), words.next().unwrap().to_string());
+            let authorized = head.contains("authorization: Bearer team-token") && head.contains("x-team-protocol: 1");
+            let body = serde_json::from_slice(&request[header_end..]).unwrap_or(Value::Null);
+
+            let response = {
+                let mut server = server.lock().unwrap();
+                server.requests.push((method, path.clone(), body));
+                match path.as_str() {
+                    _ if !authorized => None,
+                    "/api/v1/team" => Some(json!({
+                        "id": "t1",
+                        "name": "Platform",
+                        "me": {"id": "u1", "name": "Ana", "role": server.role}
+                    })),
+                    "/api/v1/sync" => Some(server.sync_response.clone()),
+                    _ => None,
+                }
+            };
+            let response = match response {
+                Some(body) => {
+                    let body = body.to_string();
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                }
+                None => "HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            };
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    base
+}
+
+fn prompt(id: &str, name: &str, revision: u64) -> Value {
+    json!({
+        "kind": "prompt",
+        "item": {
+            "id": id, "name": name, "content": "Review {code}", "updated_by": "u2",
+            "updated_at": "2024-01-01T00:00:00Z", "revision": revision
+        }
+    })
+}
+
+#[tokio::test]
+async fn changes_are_pushed_and_pulled() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("team.json");
+    let workspace = TeamWorkspace::open(path.clone());
+    assert!(workspace.save_prompt("Review", "Check it", None, Vec::new()).is_err());
+
+    let server = Arc::new(Mutex::new(Server {
+        role: "member".to_string(),
+        sync_response: json!({ "cursor": "c1", "changes": [prompt("p1", "Standup", 3)] }),
+        ..Default::default()
+    }));
+    let base = serve(server.clone()).await;
+    assert!(workspace.sync_with(&base, "wrong-token").await.is_err());
+
+    let report = workspace.sync_with(&base, "team-token").await.unwrap();
+    assert_eq!((report.pushed, report.pulled), (0, 1));
+    assert_eq!(workspace.role(), Some(TeamRole::Member));
+    assert_eq!(workspace.prompt("Standup").unwrap().revision, 3);
+
+    // Saving twice before a sync pushes the latest version once
+    let first = workspace.save_prompt("Review", "Check it", None, Vec::new()).unwrap();
+    let second = workspace.save_prompt("Review", "Check it twice", None, Vec::new()).unwrap();
+    assert_eq!(first.id, second.id);
+    workspace.delete_prompt("Standup").unwrap();
+    assert!(workspace.prompt("Standup").is_none());
+
+    server.lock().unwrap().sync_response = json!({
+        "cursor": "c2",
+        "rejected": [{"id": "p1", "reason": "viewers can't delete prompts"}]
+    });
+    let report = workspace.sync_with(&base, "team-token").await.unwrap();
+    assert_eq!(report.pushed, 1);
+    assert_eq!(report.refused, vec!["p1: viewers can't delete prompts"]);
+
+    let requests = &server.lock().unwrap().requests;
+    let (method, _, body) = requests.iter().rev().find(|(_, path, _)| path == "/api/v1/sync").unwrap();
+    assert_eq!(method, "POST");
+    assert_eq!(body["cursor"], "c1");
+    let changes = body["changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0]["item"]["content"], "Check it twice");
+    assert_eq!(changes[0]["item"]["revision"], 0);
+    assert_eq!(changes[1]["item"]["deleted"], true);
+    assert_eq!(changes[1]["item"]["revision"], 3);
+
+    // The outbox is empty and the library survives reopening
+    let stored: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert!(stored["outbox"].as_array().unwrap().is_empty());
+    assert_eq!(stored["cursor"], "c2");
+    assert_eq!(TeamWorkspace::open(path).prompt("Review").unwrap().content, "Check it twice");
+}
+
+#[tokio::test]
+async fn roles_from_the_server_gate_edits() {
+    let dir = tempfile::tempdir().unwrap();
+    let workspace = TeamWorkspace::open(dir.path().join("team.json"));
+    let server = Arc::new(Mutex::new(Server {
+        role: "admin".to_string(),
+        sync_response: json!({ "cursor": "c1", "changes": [prompt("p1", "Standup", 1)] }),
+        ..Default::default()
+    }));
+    let base = serve(server.clone()).await;
+    workspace.sync_with(&base, "team-token").await.unwrap();
+    workspace.save_prompt("Retro", "What went well?", None, vec!["meetings".to_string()]).unwrap();
+
+    // Demoted on the server; the next sync picks it up
+    server.lock().unwrap().role = "viewer".to_string();
+    workspace.sync_with(&base, "team-token").await.unwrap();
+    assert_eq!(workspace.role(), Some(TeamRole::Viewer));
+    let error = workspace.save_prompt("Retro", "Changed", None, Vec::new()).unwrap_err();
+    assert!(error.to_string().contains("edit team prompts"));
+    assert!(workspace.delete_prompt("Standup").is_err());
+    assert_eq!(workspace.prompts().len(), 2);
+    assert!("owner".parse::<TeamRole>().is_err());
+}
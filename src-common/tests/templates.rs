@@ -0,0 +1,146 @@
+//! Templates: placeholders, and installing and updating from a gallery with
+//! provenance.
+
+use mcp_common::templates::{Gallery, GallerySettings, Template, TemplateKind, TemplateLibrary};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+type Files = Arc<Mutex<HashMap<String, String>>>;
+
+/// Serve files by path until the test ends
+async fn serve(files: Files) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8_lossy(&request);
+            let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+            let response = match files.lock().unwrap().get(&path) {
+                Some(body) => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                ),
+                None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            };
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    base
+}
+
+fn sha256(content: &str) -> String {
+    ring::digest::digest(&ring::digest::SHA256, content.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Publish a version of the review template
+fn publish(files: &Files, version: &str, prompt: &str) {
+    let file = serde_json::json!({ "prompt": prompt }).to_string();
+    let index = serde_json::json!({
+        "name": "Community",
+        "templates": [
+            {"id": "review", "name": "Code review", "kind": "prompt", "version": version,
+             "url": "templates/review.json", "sha256": sha256(&file), "author": "ana", "license": "MIT",
+             "tags": ["code"]},
+            {"id": "../escape", "name": "Bad", "version": "1", "url": "x.json"},
+            {"id": "broken", "name": "Broken", "version": "1", "url": "templates/broken.json",
+             "sha256": "0000"}
+        ]
+    });
+    let mut files = files.lock().unwrap();
+    files.insert("/index.json".to_string(), index.to_string());
+    files.insert("/templates/review.json".to_string(), file);
+    files.insert("/templates/broken.json".to_string(), "{\"prompt\": \"x\"}".to_string());
+}
+
+#[test]
+fn placeholders_are_filled_in() {
+    let template = Template {
+        id: "translate".to_string(),
+        name: "Translate".to_string(),
+        kind: TemplateKind::Prompt,
+        description: String::new(),
+        tags: Vec::new(),
+        prompt: "Translate into {language} for {env.TEAM}: {text} ({language})".to_string(),
+        system_prompt: None,
+        model: None,
+        source: None,
+    };
+    assert_eq!(template.placeholders(), vec!["language", "text"]);
+    let mut values = HashMap::from([("language".to_string(), "Dutch".to_string())]);
+    assert!(template.render(&values).unwrap_err().to_string().contains("text"));
+    values.insert("text".to_string(), "hello".to_string());
+    assert_eq!(
+        template.render(&values).unwrap(),
+        "Translate into Dutch for {env.TEAM}: hello (Dutch)"
+    );
+}
+
+#[tokio::test]
+async fn gallery_templates_install_with_provenance_and_update() {
+    let dir = tempfile::tempdir().unwrap();
+    let files: Files = Arc::new(Mutex::new(HashMap::new()));
+    publish(&files, "1.0.0", "Review this {language} code");
+    let base = serve(files.clone()).await;
+    let library = TemplateLibrary::at(dir.path().join("templates.json"));
+    let settings = GallerySettings::at(dir.path().join("template_gallery.json"));
+    assert!(settings.gallery().is_err());
+    assert!(settings.set_url(Some("file:///etc/index.json")).is_err());
+    settings.set_url(Some(&format!("{}/index.json", base))).unwrap();
+    let gallery: Gallery = settings.gallery().unwrap();
+
+    let index = gallery.index().await.unwrap();
+    assert_eq!(index.templates.len(), 2);
+    assert!(index.templates[0].matches("CODE"));
+    assert!(gallery
+        .install(&library, "broken")
+        .await
+        .unwrap_err()
+        .to_string()
+        .contains("checksum"));
+
+    let installed = gallery.install(&library, "review").await.unwrap();
+    let source = installed.source.clone().unwrap();
+    assert_eq!(source.version, "1.0.0");
+    assert_eq!(source.source_url, format!("{}/templates/review.json", base));
+    assert_eq!(source.author.as_deref(), Some("ana"));
+    assert_eq!(library.get("review").unwrap().prompt, "Review this {language} code");
+    assert!(gallery.updates(&library).await.unwrap().is_empty());
+
+    // A new version upstream is found and announced once
+    publish(&files, "1.1.0", "Review this {language} code for bugs");
+    let updates = gallery.updates(&library).await.unwrap();
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].installed_version, "1.0.0");
+    assert_eq!(updates[0].available_version, "1.1.0");
+    assert_eq!(settings.unannounced(&updates).unwrap().len(), 1);
+    assert!(settings.unannounced(&updates).unwrap().is_empty());
+
+    let updated = gallery.install(&library, "review").await.unwrap();
+    assert_eq!(updated.source.unwrap().version, "1.1.0");
+    assert!(gallery.updates(&library).await.unwrap().is_empty());
+
+    // Templates of the user's own are never replaced
+    library.remove("review").unwrap();
+    library
+        .save(Template {
+            source: None,
+            ..installed
+        })
+        .unwrap();
+    assert!(gallery.install(&library, "review").await.is_err());
+    assert!(gallery.updates(&library).await.unwrap().is_empty());
+}
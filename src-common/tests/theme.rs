@@ -0,0 +1,58 @@
+//! Themes: colors round-trip as hex, built-in themes are complete, and
+//! profiles pick their own theme.
+
+use mcp_common::theme::{builtin, load_theme, save_custom_theme, Rgb, ThemeSettings, BUILTIN_THEMES};
+use std::collections::BTreeMap;
+
+#[test]
+fn colors_round_trip_as_hex() {
+    let color: Rgb = "#3B82F6".parse().unwrap();
+    assert_eq!(color, Rgb { r: 0x3b, g: 0x82, b: 0xf6 });
+    assert_eq!(color.to_string(), "#3b82f6");
+    assert_eq!(serde_json::to_string(&color).unwrap(), "\"#3b82f6\"");
+
+    for invalid in ["3b82f6", "#3b82f", "#3b82fg", "#3b82f600"] {
+        assert!(invalid.parse::<Rgb>().is_err(), "{}", invalid);
+    }
+    assert!(serde_json::from_str::<Rgb>("\"blue\"").is_err());
+}
+
+#[test]
+fn built_in_themes_set_every_css_variable() {
+    for name in BUILTIN_THEMES {
+        let theme = builtin(name).unwrap();
+        assert_eq!(theme.name, *name);
+        let variables = theme.css_variables();
+        assert_eq!(variables.len(), 15);
+        assert_eq!(variables["--color-accent-foreground"], theme.palette.accent_foreground.to_string());
+
+        let css = theme.css();
+        assert!(css.starts_with(":root {\n"));
+        assert!(css.contains(if theme.dark { "color-scheme: dark;" } else { "color-scheme: light;" }));
+        assert_eq!(load_theme(name).unwrap(), theme);
+    }
+    assert!(builtin("solarized").is_none());
+}
+
+#[test]
+fn profiles_use_their_own_theme_or_the_active_one() {
+    let settings = ThemeSettings {
+        active: "dark".to_string(),
+        profiles: BTreeMap::from([("tui".to_string(), "high-contrast".to_string())]),
+    };
+    assert_eq!(settings.theme_name(Some("tui")), "high-contrast");
+    assert_eq!(settings.theme_name(Some("desktop")), "dark");
+    assert_eq!(settings.theme_name(None), "dark");
+}
+
+#[test]
+fn custom_theme_names_are_checked() {
+    // Names can't leave the themes directory
+    assert!(load_theme("../settings").is_err());
+    assert!(load_theme("").is_err());
+
+    let mut theme = builtin("light").unwrap();
+    assert!(save_custom_theme(&theme).unwrap_err().to_string().contains("built-in"));
+    theme.name = "my theme".to_string();
+    assert!(save_custom_theme(&theme).is_err());
+}
@@ -0,0 +1,181 @@
+//! Translation: language detection, code blocks, the middleware and the APIs.
+
+use async_trait::async_trait;
+use mcp_common::error::{McpError, McpResult};
+use mcp_common::service::translation::{
+    detect_language, translate_text, DeepLTranslator, GoogleTranslator, LanguagePreference, TranslationMiddleware,
+    Translator, TRANSLATION_METADATA_KEY,
+};
+use mcp_common::service::{ChatService, MiddlewareOptions};
+use mcp_common::testing::TestHarness;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Marks text with the target language and records each call
+#[derive(Default)]
+struct Tagger {
+    calls: Mutex<Vec<(Option<String>, String)>>,
+}
+
+#[async_trait]
+impl Translator for Tagger {
+    fn name(&self) -> &str {
+        "tagger"
+    }
+
+    async fn translate(&self, text: &str, source: Option<&str>, target: &str) -> McpResult<String> {
+        self.calls.lock().unwrap().push((source.map(str::to_string), target.to_string()));
+        Ok(format!("[{}] {}", target, text))
+    }
+}
+
+/// Answer one request with `status` and a JSON body, returning the request's
+/// headers and body
+async fn answer_one(listener: TcpListener, status: &'static str, json: &'static str) -> (HashMap<String, String>, String) {
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    let (headers, body_start) = loop {
+        let n = stream.read(&mut buf).await.unwrap();
+        request.extend_from_slice(&buf[..n]);
+        if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&request[..end]).to_string();
+            let headers: HashMap<String, String> = head
+                .lines()
+                .map(|line| line.split_once(':').map_or(("request-line", line), |(k, v)| (k, v)))
+                .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+                .collect();
+            break (headers, end + 4);
+        }
+    };
+    let length: usize = headers["content-length"].parse().unwrap();
+    while request.len() < body_start + length {
+        let n = stream.read(&mut buf).await.unwrap();
+        request.extend_from_slice(&buf[..n]);
+    }
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        json.len(),
+        json
+    );
+    stream.write_all(response.as_bytes()).await.unwrap();
+    (headers, String::from_utf8_lossy(&request[body_start..body_start + length]).to_string())
+}
+
+#[test]
+fn languages_are_detected_by_script_and_common_words() {
+    assert_eq!(detect_language("What is the best way to learn a new language?"), Some("en"));
+    assert_eq!(detect_language("Wie kann ich die Datei auf dem Server öffnen?"), Some("de"));
+    assert_eq!(detect_language("Je ne sais pas comment ouvrir le fichier dans le terminal"), Some("fr"));
+    assert_eq!(detect_language("¿Cómo puedo abrir el archivo con la terminal?"), Some("es"));
+    assert_eq!(detect_language("Как открыть файл в терминале?"), Some("ru"));
+    assert_eq!(detect_language("ファイルを開く方法を教えてください"), Some("ja"));
+    assert_eq!(detect_language("如何在终端中打开文件"), Some("zh"));
+
+    // Too little to go on
+    assert_eq!(detect_language("ok thanks"), None);
+    assert_eq!(detect_language("42 + 7"), None);
+}
+
+#[tokio::test]
+async fn code_blocks_are_left_untranslated() {
+    let tagger = Tagger::default();
+    let text = "Hallo\n```rust\nlet x = 1;\n```\nTschüss";
+    let translated = translate_text(&tagger, text, Some("de"), "en").await.unwrap();
+    assert_eq!(translated, "[en] Hallo\n```rust\nlet x = 1;\n```\n[en] Tschüss");
+    assert_eq!(tagger.calls.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn conversations_with_a_language_are_translated_both_ways() {
+    let h = TestHarness::new();
+    let chat = ChatService::new(h.service.clone());
+    let tagger = Arc::new(Tagger::default());
+    chat.pipeline().register(
+        Arc::new(TranslationMiddleware::with_translator(tagger.clone())),
+        MiddlewareOptions {
+            priority: 5,
+            required: false,
+        },
+    );
+    let conversation = chat.create_conversation("Hilfe", None).await.unwrap();
+
+    let invalid = LanguagePreference {
+        model: "auto".to_string(),
+        ..Default::default()
+    };
+    assert!(chat.set_conversation_language(&conversation.id, Some(invalid)).await.is_err());
+    chat.set_conversation_language(&conversation.id, Some(LanguagePreference::default()))
+        .await
+        .unwrap();
+
+    h.provider.reply("Open it with the open command.");
+    let reply = chat
+        .send_message(&conversation.id, "Wie kann ich die Datei auf dem Server öffnen?")
+        .await
+        .unwrap();
+    assert_eq!(reply.text(), "[de] Open it with the open command.");
+    let record = &reply.metadata.as_ref().unwrap()[TRANSLATION_METADATA_KEY];
+    assert_eq!(record["original"], "Open it with the open command.");
+    assert_eq!(record["from"], "en");
+
+    // The model only saw English
+    let sent = h.provider.requests().last().unwrap().last().unwrap().text();
+    assert_eq!(sent, "[en] Wie kann ich die Datei auf dem Server öffnen?");
+    assert_eq!(
+        tagger.calls.lock().unwrap().clone(),
+        vec![(Some("de".to_string()), "en".to_string()), (Some("en".to_string()), "de".to_string())]
+    );
+
+    // Undetectable messages go out as they are
+    h.provider.reply("You're welcome.");
+    let reply = chat.send_message(&conversation.id, "ok").await.unwrap();
+    assert_eq!(reply.text(), "You're welcome.");
+
+    chat.set_conversation_language(&conversation.id, None).await.unwrap();
+    h.provider.reply("Gern.");
+    let reply = chat.send_message(&conversation.id, "Vielen Dank für die schnelle Hilfe").await.unwrap();
+    assert_eq!(reply.text(), "Gern.");
+    assert_eq!(tagger.calls.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn deepl_requests_name_languages_the_way_the_api_expects() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/v2/translate", listener.local_addr().unwrap());
+    let server = tokio::spawn(answer_one(
+        listener,
+        "200 OK",
+        r#"{"translations": [{"detected_source_language": "DE", "text": "Good morning"}]}"#,
+    ));
+
+    let deepl = DeepLTranslator::new("key:fx").with_endpoint(&url);
+    assert_eq!(deepl.translate("Guten Morgen", Some("de"), "en").await.unwrap(), "Good morning");
+
+    let (headers, body) = server.await.unwrap();
+    assert_eq!(headers["authorization"], "DeepL-Auth-Key key:fx");
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["target_lang"], "EN-US");
+    assert_eq!(body["source_lang"], "DE");
+    assert_eq!(body["text"][0], "Guten Morgen");
+}
+
+#[tokio::test]
+async fn rejected_keys_are_reported_as_authentication_errors() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/translate", listener.local_addr().unwrap());
+    let server = tokio::spawn(answer_one(listener, "403 Forbidden", r#"{"error": {"message": "API key not valid"}}"#));
+
+    let google = GoogleTranslator::new("bad-key").with_endpoint(&url);
+    let result = google.translate("Bonjour", None, "en").await;
+    assert!(matches!(result, Err(McpError::Authentication(_))));
+
+    let (headers, body) = server.await.unwrap();
+    assert!(headers["request-line"].contains("key=bad-key"));
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["target"], "en");
+    assert!(body.get("source").is_none());
+}
@@ -0,0 +1,176 @@
+//! Persistent transport: dropped connections are re-established, resumable
+//! requests are re-sent exactly once and others fail with the connection.
+
+use futures::{SinkExt, StreamExt};
+use mcp_common::protocol::{PersistentTransport, TransportConfig, TransportEvent};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+type Server = tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>;
+
+/// Accept connections and hand each one to the test in order
+async fn listen() -> (String, mpsc::Receiver<Server>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel(4);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            if tx.send(socket).await.is_err() {
+                break;
+            }
+        }
+    });
+    (url, rx)
+}
+
+fn transport(url: &str) -> PersistentTransport {
+    PersistentTransport::new(TransportConfig {
+        url: url.to_string(),
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        ..TransportConfig::default()
+    })
+}
+
+/// Next text frame from the client
+async fn next_text(socket: &mut Server) -> String {
+    loop {
+        match timeout(Duration::from_secs(5), socket.next()).await.unwrap() {
+            Some(Ok(WsMessage::Text(text))) => return text,
+            Some(Ok(_)) => continue,
+            other => panic!("connection ended: {:?}", other),
+        }
+    }
+}
+
+/// Fail if the client sends another text frame soon; heartbeats are fine
+async fn assert_quiet(socket: &mut Server) {
+    let extra = timeout(Duration::from_millis(300), async {
+        loop {
+            match socket.next().await {
+                Some(Ok(WsMessage::Text(text))) => return text,
+                Some(Ok(_)) => continue,
+                other => panic!("connection ended: {:?}", other),
+            }
+        }
+    })
+    .await;
+    assert!(extra.is_err(), "unexpected frame {:?}", extra);
+}
+
+/// Wait for an event, skipping the others
+async fn wait_for(events: &mut broadcast::Receiver<TransportEvent>, wanted: impl Fn(&TransportEvent) -> bool) {
+    timeout(Duration::from_secs(5), async {
+        while !wanted(&events.recv().await.unwrap()) {}
+    })
+    .await
+    .expect("event not published");
+}
+
+#[tokio::test]
+async fn dropped_connection_is_reestablished() {
+    let (url, mut sockets) = listen().await;
+    let transport = transport(&url);
+    let mut events = transport.subscribe_events();
+    transport.start().await;
+
+    let first = sockets.recv().await.unwrap();
+    wait_for(&mut events, |e| *e == TransportEvent::Connected).await;
+    drop(first);
+
+    wait_for(&mut events, |e| matches!(e, TransportEvent::Disconnected { .. })).await;
+    let mut second = sockets.recv().await.unwrap();
+    wait_for(&mut events, |e| *e == TransportEvent::Connected).await;
+
+    // Frames no request waits for are passed on
+    let mut messages = transport.subscribe_messages();
+    second.send(WsMessage::Text(r#"{"type":"event"}"#.to_string())).await.unwrap();
+    let pushed = timeout(Duration::from_secs(5), messages.recv()).await.unwrap().unwrap();
+    assert_eq!(pushed, r#"{"type":"event"}"#);
+
+    transport.stop().await;
+}
+
+#[tokio::test]
+async fn resumable_request_is_resent_once_after_reconnect() {
+    let (url, mut sockets) = listen().await;
+    let transport = transport(&url);
+    let mut events = transport.subscribe_events();
+    transport.start().await;
+
+    // The first connection takes the request and drops without answering
+    let server = tokio::spawn(async move {
+        let mut first = sockets.recv().await.unwrap();
+        assert_eq!(next_text(&mut first).await, r#"{"id":"r1"}"#);
+        drop(first);
+
+        let mut second = sockets.recv().await.unwrap();
+        assert_eq!(next_text(&mut second).await, r#"{"id":"r1"}"#);
+        second.send(WsMessage::Text(r#"{"id":"r1","ok":true}"#.to_string())).await.unwrap();
+
+        // Nothing else arrives: the request went out once on this connection
+        assert_quiet(&mut second).await;
+    });
+
+    wait_for(&mut events, |e| *e == TransportEvent::Connected).await;
+    let response = transport
+        .request("r1", r#"{"id":"r1"}"#.to_string(), true, Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert_eq!(response, r#"{"id":"r1","ok":true}"#);
+    wait_for(&mut events, |e| *e == TransportEvent::Resumed { requests: 1 }).await;
+
+    server.await.unwrap();
+    transport.stop().await;
+}
+
+#[tokio::test]
+async fn request_made_while_connecting_is_sent_once() {
+    let (url, mut sockets) = listen().await;
+    let transport = transport(&url);
+    transport.start().await;
+
+    // Issued before the connection is up: the resume on connect sends it
+    let request = transport.request("r2", r#"{"id":"r2"}"#.to_string(), true, Duration::from_secs(5));
+    let server = async {
+        let mut socket = sockets.recv().await.unwrap();
+        assert_eq!(next_text(&mut socket).await, r#"{"id":"r2"}"#);
+        socket.send(WsMessage::Text(r#"{"id":"r2"}"#.to_string())).await.unwrap();
+        assert_quiet(&mut socket).await;
+    };
+    let (response, ()) = tokio::join!(request, server);
+    assert_eq!(response.unwrap(), r#"{"id":"r2"}"#);
+
+    transport.stop().await;
+}
+
+#[tokio::test]
+async fn non_resumable_request_fails_with_the_connection() {
+    let (url, mut sockets) = listen().await;
+    let transport = transport(&url);
+    let mut events = transport.subscribe_events();
+    transport.start().await;
+
+    let server = tokio::spawn(async move {
+        let mut socket = sockets.recv().await.unwrap();
+        next_text(&mut socket).await;
+        drop(socket);
+        // Keep accepting so the transport can reconnect
+        let _next = sockets.recv().await;
+    });
+
+    wait_for(&mut events, |e| *e == TransportEvent::Connected).await;
+    let result = transport
+        .request("r3", r#"{"id":"r3"}"#.to_string(), false, Duration::from_secs(5))
+        .await;
+    assert!(result.is_err());
+    // It is no longer waiting once failed
+    assert!(!transport.resolve("r3", Ok(String::new())).await);
+
+    server.await.unwrap();
+    transport.stop().await;
+}
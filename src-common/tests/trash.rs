@@ -0,0 +1,87 @@
+//! Trash: deleted conversations are kept with their deletion time, can be
+//! restored or purged, and are announced on the event bus.
+
+use chrono::Duration;
+use mcp_common::events::{get_event_bus, names, Backpressure, Topic};
+use mcp_common::testing::TestHarness;
+
+#[tokio::test]
+async fn trashed_conversations_are_listed_newest_first_with_their_expiry() {
+    let h = TestHarness::new();
+    let older = h.chat.create_conversation("Older", None).await.unwrap();
+    let newer = h.chat.create_conversation("Newer", None).await.unwrap();
+    h.chat.delete_conversation(&older.id).await.unwrap();
+    h.clock.advance(Duration::hours(2));
+    h.chat.delete_conversation(&newer.id).await.unwrap();
+
+    let trashed = h.chat.list_trashed().await.unwrap();
+    let titles: Vec<&str> = trashed.iter().map(|t| t.conversation.title.as_str()).collect();
+    assert_eq!(titles, vec!["Newer", "Older"]);
+    assert!(trashed[1].deleted_at - TestHarness::epoch() < Duration::seconds(1));
+    assert_eq!(trashed[1].expires_at - trashed[1].deleted_at, Duration::days(30));
+    // The deletion time is bookkeeping, not conversation metadata
+    assert!(trashed[0].conversation.metadata.get("trashed_at").is_none());
+
+    assert!(h.chat.get_conversation(&older.id).await.is_err());
+    assert!(h.storage.list_conversations().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn restoring_and_purging_are_announced() {
+    let h = TestHarness::new();
+    let restored = h.chat.create_conversation("Restored", None).await.unwrap();
+    let purged = h.chat.create_conversation("Purged", None).await.unwrap();
+    h.chat.delete_conversation(&restored.id).await.unwrap();
+    h.chat.delete_conversation(&purged.id).await.unwrap();
+
+    let mut events = get_event_bus().subscribe(&[Topic::Conversation], 64, Backpressure::DropNewest);
+    let conversation = h.chat.restore_conversation(&restored.id).await.unwrap();
+    assert_eq!(conversation.title, "Restored");
+    assert!(conversation.metadata.get("trashed_at").is_none());
+    h.chat.purge_conversation(&purged.id).await.unwrap();
+
+    let names: Vec<String> = events
+        .drain()
+        .into_iter()
+        .filter(|e| e.payload["conversation_id"] == restored.id.as_str() || e.payload["conversation_id"] == purged.id.as_str())
+        .map(|e| e.name)
+        .collect();
+    assert_eq!(names, vec![names::CONVERSATION_RESTORED, names::CONVERSATION_PURGED]);
+
+    assert_eq!(h.chat.get_conversation(&restored.id).await.unwrap().title, "Restored");
+    assert!(h.chat.list_trashed().await.unwrap().is_empty());
+
+    // Neither can be taken out of the trash again
+    assert!(h.chat.restore_conversation(&restored.id).await.is_err());
+    assert!(h.chat.purge_conversation(&purged.id).await.is_err());
+}
+
+#[tokio::test]
+async fn emptying_the_trash_purges_everything_in_it() {
+    let h = TestHarness::new();
+    assert_eq!(h.chat.empty_trash().await.unwrap(), 0);
+
+    let kept = h.chat.create_conversation("Kept", None).await.unwrap();
+    for title in ["One", "Two"] {
+        let conversation = h.chat.create_conversation(title, None).await.unwrap();
+        h.chat.delete_conversation(&conversation.id).await.unwrap();
+    }
+
+    assert_eq!(h.chat.empty_trash().await.unwrap(), 2);
+    assert!(h.chat.list_trashed().await.unwrap().is_empty());
+    assert!(h.chat.get_conversation(&kept.id).await.is_ok());
+}
+
+#[tokio::test]
+async fn deleting_again_replaces_the_trashed_copy() {
+    let h = TestHarness::new();
+    let conversation = h.chat.create_conversation("Twice", None).await.unwrap();
+    h.chat.delete_conversation(&conversation.id).await.unwrap();
+    h.chat.restore_conversation(&conversation.id).await.unwrap();
+
+    h.clock.advance(Duration::days(10));
+    h.chat.delete_conversation(&conversation.id).await.unwrap();
+    let trashed = h.chat.list_trashed().await.unwrap();
+    assert_eq!(trashed.len(), 1);
+    assert!(trashed[0].deleted_at >= TestHarness::epoch() + Duration::days(10));
+}
@@ -0,0 +1,62 @@
+//! Undo and redo of deletions, archiving and message edits within the undo
+//! window.
+
+use chrono::Duration;
+use mcp_common::config::OperationKind;
+use mcp_common::service::ChatService;
+use mcp_common::testing::TestHarness;
+
+#[tokio::test]
+async fn deletions_and_archiving_can_be_undone_and_redone() {
+    let h = TestHarness::new();
+    let trip = h.chat.create_conversation("Trip planning", None).await.unwrap();
+    let notes = h.chat.create_conversation("Notes", None).await.unwrap();
+    assert!(h.chat.undo().await.unwrap().is_none());
+
+    h.chat.delete_conversation(&trip.id).await.unwrap();
+    assert!(h.chat.get_conversation(&trip.id).await.is_err());
+    let undone = h.chat.undo().await.unwrap().unwrap();
+    assert_eq!(undone.kind, OperationKind::DeleteConversation);
+    assert_eq!(undone.description, "Delete 'Trip planning'");
+    assert_eq!(h.chat.get_conversation(&trip.id).await.unwrap().title, "Trip planning");
+
+    let redone = h.chat.redo().await.unwrap().unwrap();
+    assert_eq!(redone.id, undone.id);
+    assert!(h.chat.get_conversation(&trip.id).await.is_err());
+    assert!(h.chat.redo().await.unwrap().is_none());
+
+    // Conversations archived together come back together
+    let ids = vec![notes.id.clone()];
+    assert_eq!(h.chat.archive_conversations(&ids, true).await.unwrap(), 1);
+    assert_eq!(h.chat.archive_conversations(&ids, true).await.unwrap(), 0);
+    assert!(ChatService::is_archived(&h.chat.get_conversation(&notes.id).await.unwrap()));
+    h.chat.undo().await.unwrap();
+    assert!(!ChatService::is_archived(&h.chat.get_conversation(&notes.id).await.unwrap()));
+
+    // A new operation drops what could have been redone
+    h.chat.archive_conversations(&ids, true).await.unwrap();
+    assert!(h.chat.redo().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn edits_are_undone_until_the_window_closes() {
+    let h = TestHarness::new();
+    let conversation = h.chat.create_conversation("Draft", None).await.unwrap();
+    h.provider.reply("Sure");
+    h.chat.send_message(&conversation.id, "Write a haiku").await.unwrap();
+    let message_id = h.chat.get_conversation(&conversation.id).await.unwrap().messages[0].id.clone();
+
+    let edited = h.chat.edit_message(&conversation.id, &message_id, "Write a limerick").await.unwrap();
+    assert_eq!(edited.text(), "Write a limerick");
+    assert_eq!(h.chat.get_message_history(&message_id).await.unwrap().len(), 1);
+
+    h.chat.undo().await.unwrap().unwrap();
+    let restored = h.chat.get_conversation(&conversation.id).await.unwrap();
+    assert_eq!(restored.messages[0].text(), "Write a haiku");
+
+    h.chat.redo().await.unwrap().unwrap();
+    h.clock.advance(Duration::minutes(31));
+    assert!(h.chat.undo().await.unwrap().is_none());
+    let kept = h.chat.get_conversation(&conversation.id).await.unwrap();
+    assert_eq!(kept.messages[0].text(), "Write a limerick");
+}
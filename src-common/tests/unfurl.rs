@@ -0,0 +1,67 @@
+//! Link previews: links are found in message text, page metadata is parsed
+//! with sensible fallbacks, and fetching follows the link settings.
+
+use mcp_common::config::LinkSettings;
+use mcp_common::service::unfurl::{extract_urls, parse_preview};
+use url::Url;
+
+#[test]
+fn links_are_extracted_in_order_without_duplicates() {
+    let text = "See https://example.com/docs, then (https://rust-lang.org) and https://example.com/docs.\n\
+                Not ftp://files.example.com or <http://a.test/x?y=1>!";
+    assert_eq!(
+        extract_urls(text),
+        vec!["https://example.com/docs", "https://rust-lang.org", "http://a.test/x?y=1"]
+    );
+    assert!(extract_urls("no links here").is_empty());
+}
+
+#[test]
+fn opengraph_metadata_wins_and_urls_are_resolved() {
+    let page = Url::parse("https://blog.example.com/posts/1").unwrap();
+    let html = r#"<html><head>
+        <title>Plain title</title>
+        <meta property="og:title" content="Tom &amp; Jerry">
+        <meta name="twitter:title" content="Twitter title">
+        <meta name='description' content='A   short
+            description'>
+        <meta property="og:site_name" content="Example Blog">
+        <meta property="og:image" content="/images/cover.png">
+        <link rel="shortcut icon" href="https://cdn.example.com/icon.png">
+    </head></html>"#;
+
+    let preview = parse_preview(&page, html);
+    assert_eq!(preview.title.as_deref(), Some("Tom & Jerry"));
+    assert_eq!(preview.description.as_deref(), Some("A short description"));
+    assert_eq!(preview.site_name.as_deref(), Some("Example Blog"));
+    assert_eq!(preview.image.as_deref(), Some("https://blog.example.com/images/cover.png"));
+    assert_eq!(preview.favicon.as_deref(), Some("https://cdn.example.com/icon.png"));
+}
+
+#[test]
+fn plain_pages_fall_back_to_the_title_tag_and_default_favicon() {
+    let page = Url::parse("https://example.com/a/b").unwrap();
+    let preview = parse_preview(&page, "<TITLE>\n  Hello &lt;world&gt;\n</TITLE><meta name=\"description\" content=\"\">");
+
+    assert_eq!(preview.title.as_deref(), Some("Hello <world>"));
+    assert!(preview.description.is_none() && preview.image.is_none());
+    assert_eq!(preview.favicon.as_deref(), Some("https://example.com/favicon.ico"));
+}
+
+#[test]
+fn previews_are_only_fetched_when_enabled_and_allowed() {
+    let url = |u: &str| Url::parse(u).unwrap();
+    let mut links = LinkSettings::default();
+    assert!(!links.allows(&url("https://example.com")));
+
+    links.unfurl = true;
+    assert!(links.allows(&url("https://anything.test/page")));
+    assert!(!links.allows(&url("file:///etc/passwd")));
+
+    links.allowed_domains = vec!["*.Example.com".to_string(), "docs.rs".to_string()];
+    assert!(links.allows(&url("https://example.com/x")));
+    assert!(links.allows(&url("https://blog.example.com/x")));
+    assert!(links.allows(&url("https://docs.rs/serde")));
+    assert!(!links.allows(&url("https://notexample.com")));
+    assert!(!links.allows(&url("https://example.com.evil.test")));
+}
@@ -0,0 +1,194 @@
+//! Outgoing webhooks: signing, event filters and the retry queue.
+
+use mcp_common::auth::{secret_store, set_secret_store, SecretStore};
+use mcp_common::error::McpResult;
+use mcp_common::webhooks::{sign, verify, WebhookEvent, WebhookStore, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, Once};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Secrets kept in memory for the tests
+#[derive(Default)]
+struct MemoryStore(Mutex<HashMap<String, String>>);
+
+impl SecretStore for MemoryStore {
+    fn get(&self, key: &str) -> McpResult<Option<String>> {
+        Ok(self.0.lock().unwrap().get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &str) -> McpResult<()> {
+        self.0.lock().unwrap().insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> McpResult<()> {
+        self.0.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+/// A webhook store in `dir`, with secrets in memory
+fn open(dir: &Path) -> WebhookStore {
+    static SECRETS: Once = Once::new();
+    SECRETS.call_once(|| set_secret_store(Arc::new(MemoryStore::default())));
+    WebhookStore::at(dir.join("webhooks.json"))
+}
+
+/// Answer one request with `status`, returning its headers and body
+async fn receive_one(listener: TcpListener, status: &'static str) -> (HashMap<String, String>, Vec<u8>) {
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    let (headers, body_start) = loop {
+        let n = stream.read(&mut buf).await.unwrap();
+        request.extend_from_slice(&buf[..n]);
+        if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&request[..end]).to_string();
+            let headers: HashMap<String, String> = head
+                .lines()
+                .skip(1)
+                .filter_map(|line| line.split_once(':'))
+                .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+                .collect();
+            break (headers, end + 4);
+        }
+    };
+    let length: usize = headers["content-length"].parse().unwrap();
+    while request.len() < body_start + length {
+        let n = stream.read(&mut buf).await.unwrap();
+        request.extend_from_slice(&buf[..n]);
+    }
+    let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+    stream.write_all(response.as_bytes()).await.unwrap();
+    (headers, request[body_start..body_start + length].to_vec())
+}
+
+#[test]
+fn signatures_verify_only_unchanged_payloads() {
+    let signature = sign("whsec_test", 1700000000, b"{\"a\":1}");
+    assert!(signature.starts_with("sha256="));
+    assert!(verify("whsec_test", 1700000000, b"{\"a\":1}", &signature));
+    assert!(!verify("whsec_test", 1700000001, b"{\"a\":1}", &signature));
+    assert!(!verify("whsec_test", 1700000000, b"{\"a\":2}", &signature));
+    assert!(!verify("other", 1700000000, b"{\"a\":1}", &signature));
+    assert!(!verify("whsec_test", 1700000000, b"{\"a\":1}", "sha256=zz"));
+}
+
+#[test]
+fn webhooks_filter_events_and_validate_urls() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open(dir.path());
+
+    assert!(store.add("ftp://example.com/hook", Vec::new(), None).is_err());
+    assert!(store.add("http://example.com/hook", Vec::new(), None).is_err());
+    let all = store.add("http://localhost:9/all", Vec::new(), None).unwrap();
+    let created = store
+        .add("https://example.com/hook", vec![WebhookEvent::ConversationCreated], None)
+        .unwrap();
+    assert!(all.secret.starts_with("whsec_"));
+    assert_ne!(all.secret, created.secret);
+
+    // Secrets stay out of the webhooks file
+    let file = std::fs::read_to_string(dir.path().join("webhooks.json")).unwrap();
+    assert!(!file.contains(&all.secret));
+    assert!(store.get(&all.id).unwrap().secret.is_empty());
+    let rotated = store.rotate_secret(&all.id).unwrap();
+    assert_ne!(rotated.secret, all.secret);
+    assert_eq!(secret_store().get(&format!("webhook_{}", all.id)).unwrap(), Some(rotated.secret));
+
+    assert_eq!(store.enqueue(WebhookEvent::MessageCompleted, serde_json::json!({})).unwrap(), 1);
+    assert_eq!(store.enqueue(WebhookEvent::ConversationCreated, serde_json::json!({})).unwrap(), 2);
+    // Pausing a webhook drops what it was still waiting to send
+    store.set_enabled(&all.id[..8], false).unwrap();
+    assert_eq!(store.queue().len(), 1);
+    assert_eq!(store.enqueue(WebhookEvent::BudgetExceeded, serde_json::json!({})).unwrap(), 0);
+
+    // Everything survives a restart; removing a webhook drops its deliveries
+    let store = open(dir.path());
+    assert_eq!(store.queue().len(), 1);
+    store.remove(&created.id).unwrap();
+    assert!(store.queue().is_empty());
+    store.remove(&all.id).unwrap();
+    assert!(store.list().is_empty());
+    assert!(secret_store().get(&format!("webhook_{}", all.id)).unwrap().is_none());
+
+    assert_eq!("budget-exceeded".parse::<WebhookEvent>().unwrap(), WebhookEvent::BudgetExceeded);
+    assert!("message_sent".parse::<WebhookEvent>().is_err());
+}
+
+#[tokio::test]
+async fn deliveries_are_signed_and_failures_retried() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = open(dir.path());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://127.0.0.1:{}/hook", listener.local_addr().unwrap().port());
+    let webhook = store.add(&url, Vec::new(), None).unwrap();
+
+    let data = serde_json::json!({ "conversation_id": "c1", "message_id": "m1" });
+    store.enqueue(WebhookEvent::MessageCompleted, data.clone()).unwrap();
+    let server = tokio::spawn(receive_one(listener, "200 OK"));
+    let report = store.deliver_due().await.unwrap();
+    assert_eq!(report.delivered, 1);
+    assert!(store.queue().is_empty());
+
+    let (headers, body) = server.await.unwrap();
+    let timestamp: i64 = headers[&TIMESTAMP_HEADER.to_lowercase()].parse().unwrap();
+    assert!(verify(&webhook.secret, timestamp, &body, &headers[&SIGNATURE_HEADER.to_lowercase()]));
+    let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(payload["event"], "message_completed");
+    assert_eq!(payload["data"], data);
+
+    // An error answer keeps the delivery for a later retry
+    store.set_enabled(&webhook.id, false).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://127.0.0.1:{}/hook", listener.local_addr().unwrap().port());
+    let failing = store.add(&url, vec![WebhookEvent::MessageCompleted], None).unwrap();
+    assert_eq!(store.enqueue(WebhookEvent::MessageCompleted, data).unwrap(), 1);
+    let server = tokio::spawn(receive_one(listener, "500 Internal Server Error"));
+    let report = store.deliver_due().await.unwrap();
+    server.await.unwrap();
+    assert_eq!(report.retrying, 1);
+
+    let queue = store.queue();
+    assert_eq!(queue[0].attempts, 1);
+    assert!(queue[0].next_attempt_at > chrono::Utc::now());
+    assert!(store.get(&failing.id).unwrap().last_error.is_some());
+
+    // Not due yet, so nothing is sent
+    assert_eq!(store.deliver_due().await.unwrap().retrying, 0);
+}
+
+#[tokio::test]
+async fn secrets_in_old_files_move_to_the_secret_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let legacy = serde_json::json!({
+        "webhooks": [{
+            "id": "legacy-hook", "url": "https://example.com/hook", "secret": "whsec_old",
+            "enabled": false, "created_at": "2024-01-01T00:00:00Z"
+        }],
+        "queue": [
+            {
+                "id": "d1", "webhook_id": "legacy-hook", "event": "message_completed", "payload": {},
+                "attempts": 2, "next_attempt_at": "2024-01-01T00:00:00Z"
+            },
+            {
+                "id": "d2", "webhook_id": "removed-hook", "event": "message_completed", "payload": {},
+                "attempts": 0, "next_attempt_at": "2099-01-01T00:00:00Z"
+            }
+        ]
+    });
+    std::fs::write(dir.path().join("webhooks.json"), legacy.to_string()).unwrap();
+
+    let store = open(dir.path());
+    assert!(store.get("legacy-hook").unwrap().secret.is_empty());
+    assert_eq!(secret_store().get("webhook_legacy-hook").unwrap().as_deref(), Some("whsec_old"));
+    let file = std::fs::read_to_string(dir.path().join("webhooks.json")).unwrap();
+    assert!(!file.contains("whsec_old"));
+
+    // Deliveries left behind for paused or removed webhooks are dropped
+    let report = store.deliver_due().await.unwrap();
+    assert_eq!((report.delivered, report.dropped), (0, 2));
+    assert!(open(dir.path()).queue().is_empty());
+}
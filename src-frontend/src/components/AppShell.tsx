@@ -5,6 +5,7 @@
 
 import React, { useRef, useEffect, useState } from 'react';
 import { invoke } from '@tauri-apps/api/tauri';
+import { listen } from '@tauri-apps/api/event';
 import { FeatureFlags } from '../../src/feature_flags';
 import HelpButton from './help/HelpButton';
 
@@ -22,6 +23,10 @@ import './AppShell.css';
 import '../styles/collaboration.css';
 import HelpButton from './help/HelpButton';
 
+interface ServiceHealthReport {
+  disabled_features: string[];
+}
+
 interface AppShellProps {
   featureFlags: FeatureFlags;
   // Other props would go here in a real implementation
@@ -39,9 +44,26 @@ const AppShell: React.FC<AppShellProps> = ({ featureFlags }) => {
   // Initialize feature flags
   useEffect(() => {
     // Check if collaboration feature is enabled
-    if (featureFlags.contains(FeatureFlags.COLLABORATION)) {
-      setCollaborationEnabled(true);
+    if (!featureFlags.contains(FeatureFlags.COLLABORATION)) {
+      return;
     }
+    
+    // Hide collaboration while its backend service is failed
+    const checkHealth = async () => {
+      try {
+        const health = await invoke<ServiceHealthReport>('get_service_health');
+        setCollaborationEnabled(!health.disabled_features.includes('collaboration'));
+      } catch (error) {
+        console.error('Failed to get service health:', error);
+        setCollaborationEnabled(true);
+      }
+    };
+    
+    checkHealth();
+    const unlisten = listen('service_health_changed', checkHealth);
+    return () => {
+      unlisten.then((stop) => stop());
+    };
   }, [featureFlags]);
   
   // Initialize collaboration system
@@ -118,6 +118,10 @@ export interface CollaborationStatistics {
   messages_received: number;
   sync_operations: number;
   conflicts_resolved: number;
+  sync_bytes_sent: number;
+  sync_bytes_received: number;
+  sync_delta_bytes: number;
+  sync_bytes_saved: number;
   calls_initiated: number;
   call_duration_seconds: number;
   current_session_id?: string;
@@ -191,6 +195,10 @@ const initialState: CollaborationState = {
     messages_received: 0,
     sync_operations: 0,
     conflicts_resolved: 0,
+    sync_bytes_sent: 0,
+    sync_bytes_received: 0,
+    sync_delta_bytes: 0,
+    sync_bytes_saved: 0,
     calls_initiated: 0,
     call_duration_seconds: 0,
     connection_status: ConnectionStatus.Disconnected,
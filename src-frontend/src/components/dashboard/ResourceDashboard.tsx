@@ -50,6 +50,19 @@ interface TelemetryStats {
   last_batch_sent?: string;
 }
 
+interface ModelCacheStats {
+  requests: number;
+  hits: number;
+  misses: number;
+  tokens_written: number;
+  tokens_read: number;
+  estimated_savings_usd: number;
+}
+
+interface PromptCacheStats {
+  models: Record<string, ModelCacheStats>;
+}
+
 interface MetricsData {
   counters: Record<string, number>;
   gauges: Record<string, number>;
@@ -62,6 +75,7 @@ interface MetricsData {
   
   system: SystemResources;
   telemetry: TelemetryStats;
+  prompt_cache: PromptCacheStats;
 }
 
 const ResourceDashboard: React.FC = () => {
@@ -87,7 +101,8 @@ const ResourceDashboard: React.FC = () => {
         memoryHistory,
         apiLatencyHistory,
         systemResources,
-        telemetryStats
+        telemetryStats,
+        promptCacheStats
       ] = await Promise.all([
         invoke('get_counters_report'),
         invoke('get_gauges_report'),
@@ -97,7 +112,8 @@ const ResourceDashboard: React.FC = () => {
         invoke('get_metric_history', { metricName: 'system.memory_usage', metricType: 'Gauge' }),
         invoke('get_metric_history', { metricName: 'api.latency', metricType: 'Timer' }),
         invoke('get_system_resources'),
-        invoke('get_telemetry_stats')
+        invoke('get_telemetry_stats'),
+        invoke('get_prompt_cache_stats')
       ]);
       
       setMetrics({
@@ -109,7 +125,8 @@ const ResourceDashboard: React.FC = () => {
         memory_history: memoryHistory as MetricTimeSeries[],
         api_latency_history: apiLatencyHistory as MetricTimeSeries[],
         system: systemResources as SystemResources,
-        telemetry: telemetryStats as TelemetryStats
+        telemetry: telemetryStats as TelemetryStats,
+        prompt_cache: promptCacheStats as PromptCacheStats
       });
       
       setError(null);
@@ -227,10 +244,40 @@ const ResourceDashboard: React.FC = () => {
             {metrics.counters['message.sent'] || 0} sent / {metrics.counters['message.received'] || 0} received
           </div>
         </div>
+        
+        <div className="metric-card">
+          <h3>Prompt Cache</h3>
+          {(() => {
+            const cache = promptCacheSummary(metrics.prompt_cache);
+            return (
+              <>
+                <div className="metric-value">${cache.savings.toFixed(2)} saved</div>
+                <div className="metric-subtitle">
+                  {cache.hitRate.toFixed(0)}% hit rate · {cache.tokensRead.toLocaleString()} cached tokens read
+                </div>
+              </>
+            );
+          })()}
+        </div>
       </div>
     );
   };
 
+  // Summarize prompt cache statistics across models
+  const promptCacheSummary = (stats: PromptCacheStats) => {
+    const models = Object.values(stats.models);
+    const requests = models.reduce((sum, m) => sum + m.requests, 0);
+    const hits = models.reduce((sum, m) => sum + m.hits, 0);
+    const tokensRead = models.reduce((sum, m) => sum + m.tokens_read, 0);
+    const savings = models.reduce((sum, m) => sum + m.estimated_savings_usd, 0);
+    
+    return {
+      hitRate: requests > 0 ? (hits / requests) * 100 : 0,
+      tokensRead,
+      savings
+    };
+  };
+
   // Render CPU tab
   const renderCpuTab = () => {
     if (!metrics) return null;
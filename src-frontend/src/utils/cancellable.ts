@@ -0,0 +1,42 @@
+import { invoke } from '@tauri-apps/api/tauri';
+
+/**
+ * Invokes a cancellable backend command, tying it to an AbortSignal.
+ *
+ * A request ID is generated and passed to the command; when the signal aborts,
+ * `cancel_request` is invoked so the backend stops the work promptly.
+ *
+ * @param command The Tauri command to invoke
+ * @param args Command arguments
+ * @param options Abort signal and optional timeout in milliseconds
+ * @returns A promise that resolves with the command result
+ */
+export async function invokeCancellable<T>(
+  command: string,
+  args: Record<string, unknown> = {},
+  options: {
+    signal?: AbortSignal;
+    timeoutMs?: number;
+  } = {}
+): Promise<T> {
+  const { signal, timeoutMs } = options;
+  const requestId = crypto.randomUUID();
+
+  if (signal?.aborted) {
+    throw new DOMException('Request aborted', 'AbortError');
+  }
+
+  const onAbort = () => {
+    invoke('cancel_request', { requestId }).catch((error) => {
+      console.error('Failed to cancel request:', error);
+    });
+  };
+
+  signal?.addEventListener('abort', onAbort, { once: true });
+
+  try {
+    return await invoke<T>(command, { ...args, requestId, timeoutMs });
+  } finally {
+    signal?.removeEventListener('abort', onAbort);
+  }
+}
@@ -1,18 +1,32 @@
+use std::collections::HashMap;
 use std::sync::Arc;
-use crossterm::event::{KeyEvent, MouseEvent, KeyCode, KeyModifiers};
+use crossterm::event::{KeyEvent, MouseEvent};
 use ratatui::layout::Rect;
 use tui_textarea::TextArea;
 use tokio::sync::mpsc;
 
 use crate::error::AppError;
+use crate::util::key_combo;
 use mcp_common::{
-    models::{Conversation, Message, Model},
+    config::get_settings,
+    events::{get_event_bus, names, Backpressure, Subscription, Topic},
+    keymap::{get_keymap, Keymap},
+    theme::{active_theme, list_themes, set_active_theme, Theme},
+    tr,
+    models::{Conversation, Message, MessageRole, Model, Rating},
+    service::estimate::CostEstimate,
+    utils::markdown::MarkdownStream,
+    service::unfurl::LinkPreview,
     service::ChatService,
+    sync::{get_conflict_queue, get_read_markers, ConflictResolution, PendingConflict},
 };
 
 // Result type used in the application
 pub type AppResult<T> = std::result::Result<T, AppError>;
 
+// Theme profile of the terminal UI
+pub const THEME_PROFILE: &str = "tui";
+
 // Application mode enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppMode {
@@ -21,6 +35,7 @@ pub enum AppMode {
     Command,     // Command input
     Help,        // Help screen
     Settings,    // Settings screen
+    Conflicts,   // Sync conflict review
 }
 
 // Application state
@@ -38,23 +53,49 @@ pub struct App {
     pub selected_conversation_idx: Option<usize>,
     pub current_conversation: Option<Conversation>,
     pub message_offset: usize,
+    pub first_unread: Option<usize>,
     
     // Streaming state
     pub is_streaming: bool,
     pub stream_receiver: Option<mpsc::Receiver<Result<Message, String>>>,
     pub current_response: String,
+    pub response_markdown: MarkdownStream,
     
     // Input fields
     pub input: TextArea<'static>,
     pub command_input: TextArea<'static>,
     pub status_message: Option<(String, bool)>, // (message, is_error)
     
+    // Projected cost of the draft, and the draft the user agreed to pay for
+    pub send_estimate: Option<CostEstimate>,
+    pub cost_confirmed_for: Option<String>,
+    
     // Help
     pub show_help: bool,
     
     // Settings
     pub settings_open: bool,
     pub settings_idx: usize,
+    
+    // Sync conflicts
+    pub conflicts_open: bool,
+    pub conflicts: Vec<PendingConflict>,
+    pub conflict_idx: usize,
+    
+    // Link previews received for the current session, by URL
+    pub link_previews: HashMap<String, LinkPreview>,
+    
+    // User keybindings
+    pub keymap: Keymap,
+    
+    // Color scheme
+    pub theme: Theme,
+    
+    // Screen-reader-friendly layout
+    pub accessible: bool,
+    
+    // Shared event bus, drained on every tick
+    pub events: Subscription,
 }
 
 impl App {
@@ -69,15 +110,31 @@ impl App {
             selected_conversation_idx: None,
             current_conversation: None,
             message_offset: 0,
+            first_unread: None,
             is_streaming: false,
             stream_receiver: None,
             current_response: String::new(),
+            response_markdown: MarkdownStream::new(),
             input: TextArea::default(),
             command_input: TextArea::default(),
             status_message: None,
+            send_estimate: None,
+            cost_confirmed_for: None,
             show_help: false,
             settings_open: false,
             settings_idx: 0,
+            conflicts_open: false,
+            conflicts: Vec::new(),
+            conflict_idx: 0,
+            link_previews: HashMap::new(),
+            keymap: get_keymap().read().unwrap().clone(),
+            theme: active_theme(Some(THEME_PROFILE)),
+            accessible: get_settings().lock().unwrap().accessibility.screen_reader,
+            events: get_event_bus().subscribe(
+                &[Topic::Sync, Topic::Conversation, Topic::System],
+                128,
+                Backpressure::DropNewest,
+            ),
         };
         
         // Configure TextArea
@@ -99,7 +156,7 @@ impl App {
         self.load_conversations().await?;
         
         // Set status message
-        self.set_status("Welcome to Claude MCP TUI", false);
+        self.set_status(&tr!("tui-welcome"), false);
         
         Ok(())
     }
@@ -113,8 +170,19 @@ impl App {
                 if let Ok(Some(result)) = receiver.try_recv() {
                     match result {
                         Ok(message) => {
-                            // Update the current response
-                            self.current_response = message.text();
+                            // Update the current response; chunks are
+                            // snapshots, so only the new text is parsed
+                            let text = message.text();
+                            match text.strip_prefix(self.current_response.as_str()) {
+                                Some(delta) => {
+                                    self.response_markdown.push(delta);
+                                }
+                                None => {
+                                    self.response_markdown = MarkdownStream::new();
+                                    self.response_markdown.push(&text);
+                                }
+                            }
+                            self.current_response = text;
                             
                             // Update the conversation with the response
                             if let Some(conversation) = &mut self.current_conversation {
@@ -136,7 +204,7 @@ impl App {
                         }
                         Err(e) => {
                             // Show error
-                            self.set_status(&format!("Error: {}", e), true);
+                            self.set_status(&tr!("tui-error", error = e.to_string()), true);
                             self.is_streaming = false;
                         }
                     }
@@ -147,6 +215,48 @@ impl App {
             }
         }
         
+        // Apply events published by shared services
+        for event in self.events.drain() {
+            if event.name == names::LINK_UNFURLED {
+                if let Some(preview) = event
+                    .payload
+                    .get("preview")
+                    .and_then(|p| serde_json::from_value::<LinkPreview>(p.clone()).ok())
+                {
+                    self.link_previews.insert(preview.url.clone(), preview);
+                }
+                continue;
+            }
+            if event.name == names::THEME_CHANGED {
+                // Switches for other front ends don't apply here
+                let profile = event.payload.get("profile").and_then(|p| p.as_str());
+                if profile.map_or(true, |p| p == THEME_PROFILE) {
+                    self.theme = active_theme(Some(THEME_PROFILE));
+                }
+                continue;
+            }
+            if event.name != names::SYNC_CONFLICTS_CHANGED {
+                continue;
+            }
+            
+            if self.conflicts_open {
+                self.conflicts = get_conflict_queue().list();
+                if self.conflict_idx >= self.conflicts.len() {
+                    self.conflict_idx = self.conflicts.len().saturating_sub(1);
+                }
+            } else if let Some(pending) = event.payload.get("pending").and_then(|p| p.as_u64()) {
+                if pending > 0 {
+                    let key = self
+                        .keymap
+                        .keys("tui.normal.conflicts")
+                        .first()
+                        .map(|k| k.to_string())
+                        .unwrap_or_else(|| ":".to_string());
+                    self.set_status(&tr!("tui-conflicts-pending", count = pending, key = key), false);
+                }
+            }
+        }
+        
         // Clear status message after a period of time
         if let Some((_, _)) = &self.status_message {
             // In a real implementation, we'd check against a timestamp
@@ -154,6 +264,13 @@ impl App {
         }
     }
     
+    // Action bound to a key in a TUI scope, without the scope prefix
+    fn key_action(&self, scope: &str, key: &KeyEvent) -> Option<&'static str> {
+        self.keymap
+            .action(scope, &key_combo(key))
+            .map(|action| &action[scope.len() + 1..])
+    }
+    
     // Handle keyboard events
     pub async fn handle_key_event(&mut self, key: KeyEvent) -> AppResult<bool> {
         match self.mode {
@@ -162,6 +279,7 @@ impl App {
             AppMode::Command => self.handle_command_mode_key(key).await?,
             AppMode::Help => self.handle_help_mode_key(key)?,
             AppMode::Settings => self.handle_settings_mode_key(key).await?,
+            AppMode::Conflicts => self.handle_conflicts_mode_key(key)?,
         }
         
         Ok(self.should_quit)
@@ -196,22 +314,39 @@ impl App {
                 Ok(())
             }
             Err(e) => {
-                self.set_status(&format!("Failed to load conversations: {}", e), true);
+                self.set_status(&tr!("tui-load-conversations-failed", error = e.to_string()), true);
                 Err(AppError::Service(format!("Failed to load conversations: {}", e)))
             }
         }
     }
     
-    // Load a specific conversation
+    // Load a specific conversation and mark it read
     async fn load_conversation(&mut self, conversation_id: &str) -> AppResult<()> {
         match self.chat_service.get_conversation(conversation_id).await {
             Ok(conversation) => {
+                // Remember where unread replies start before they count as read
+                self.first_unread = get_read_markers().first_unread(&conversation);
                 self.current_conversation = Some(conversation);
                 self.message_offset = 0;
+                
+                // Restore what was typed but not sent
+                self.input = match self.chat_service.get_draft(conversation_id).await {
+                    Ok(Some(draft)) => TextArea::from(draft.text.lines()),
+                    _ => TextArea::default(),
+                };
+                self.input.set_placeholder_text("Type a message...");
+                self.refresh_estimate().await;
+                
+                if let Err(e) = self.chat_service.mark_read(conversation_id).await {
+                    self.set_status(&tr!("tui-error", error = e.to_string()), true);
+                }
+                if let Some(listed) = self.conversations.iter_mut().find(|c| c.id == conversation_id) {
+                    listed.unread = 0;
+                }
                 Ok(())
             }
             Err(e) => {
-                self.set_status(&format!("Failed to load conversation: {}", e), true);
+                self.set_status(&tr!("tui-load-conversation-failed", error = e.to_string()), true);
                 Err(AppError::Service(format!("Failed to load conversation: {}", e)))
             }
         }
@@ -256,10 +391,11 @@ impl App {
                 self.stream_receiver = Some(receiver);
                 self.is_streaming = true;
                 self.current_response = String::new();
+                self.response_markdown = MarkdownStream::new();
                 Ok(())
             }
             Err(e) => {
-                self.set_status(&format!("Failed to send message: {}", e), true);
+                self.set_status(&tr!("tui-send-failed", error = e.to_string()), true);
                 Err(AppError::Service(format!("Failed to send message: {}", e)))
             }
         }
@@ -273,16 +409,51 @@ impl App {
                 self.conversations.insert(0, conversation.clone());
                 self.selected_conversation_idx = Some(0);
                 self.current_conversation = Some(conversation);
-                self.set_status(&format!("Created conversation: {}", title), false);
+                self.set_status(&tr!("tui-conversation-created", title = title), false);
                 Ok(())
             }
             Err(e) => {
-                self.set_status(&format!("Failed to create conversation: {}", e), true);
+                self.set_status(&tr!("tui-create-failed", error = e.to_string()), true);
                 Err(AppError::Service(format!("Failed to create conversation: {}", e)))
             }
         }
     }
     
+    // Rate the latest assistant reply in the current conversation
+    async fn rate_last_response(&mut self, rating: Option<Rating>, comment: Option<String>) -> AppResult<()> {
+        let message_id = self
+            .current_conversation
+            .as_ref()
+            .and_then(|c| c.messages.iter().rev().find(|m| m.role == MessageRole::Assistant))
+            .map(|m| m.id.clone());
+        let Some(message_id) = message_id else {
+            self.set_status(&tr!("tui-no-reply-to-rate"), true);
+            return Ok(());
+        };
+        
+        match self.chat_service.rate_message(&message_id, rating, comment).await {
+            Ok(rated) => {
+                // Keep the local copy in step with storage
+                if let Some(conversation) = &mut self.current_conversation {
+                    if let Some(message) = conversation.messages.iter_mut().find(|m| m.id == message_id) {
+                        message.feedback = rated.feedback;
+                    }
+                }
+                let status = match rating {
+                    Some(Rating::Up) => "tui-rated-up",
+                    Some(Rating::Down) => "tui-rated-down",
+                    None => "tui-rating-cleared",
+                };
+                self.set_status(&tr!(status), false);
+                Ok(())
+            }
+            Err(e) => {
+                self.set_status(&tr!("tui-rate-failed", error = e.to_string()), true);
+                Err(AppError::Service(format!("Failed to rate reply: {}", e)))
+            }
+        }
+    }
+    
     // Delete the current conversation
     async fn delete_conversation(&mut self) -> AppResult<()> {
         if let Some(idx) = self.selected_conversation_idx {
@@ -313,11 +484,11 @@ impl App {
                             }
                         }
                         
-                        self.set_status(&format!("Deleted conversation: {}", title), false);
+                        self.set_status(&tr!("tui-conversation-deleted", title = title.as_str()), false);
                         Ok(())
                     }
                     Err(e) => {
-                        self.set_status(&format!("Failed to delete conversation: {}", e), true);
+                        self.set_status(&tr!("conversation-delete-failed", error = e.to_string()), true);
                         Err(AppError::Service(format!("Failed to delete conversation: {}", e)))
                     }
                 }
@@ -331,33 +502,38 @@ impl App {
     
     // Handle keys in normal mode (conversation navigation)
     async fn handle_normal_mode_key(&mut self, key: KeyEvent) -> AppResult<()> {
-        match key.code {
+        match self.key_action("tui.normal", &key) {
             // Quit application
-            KeyCode::Char('q') => {
+            Some("quit") => {
                 self.should_quit = true;
             }
             
             // Help screen
-            KeyCode::Char('?') | KeyCode::F(1) => {
+            Some("help") => {
                 self.show_help = true;
                 self.mode = AppMode::Help;
             }
             
             // Settings screen
-            KeyCode::Char('s') => {
+            Some("settings") => {
                 self.settings_open = true;
                 self.mode = AppMode::Settings;
             }
             
+            // Sync conflict review
+            Some("conflicts") => {
+                self.open_conflicts();
+            }
+            
             // Navigation - up/down
-            KeyCode::Up | KeyCode::Char('k') => {
+            Some("up") => {
                 if let Some(idx) = self.selected_conversation_idx {
                     if idx > 0 {
                         self.selected_conversation_idx = Some(idx - 1);
                     }
                 }
             }
-            KeyCode::Down | KeyCode::Char('j') => {
+            Some("down") => {
                 if let Some(idx) = self.selected_conversation_idx {
                     if idx < self.conversations.len() - 1 {
                         self.selected_conversation_idx = Some(idx + 1);
@@ -366,7 +542,7 @@ impl App {
             }
             
             // Select conversation
-            KeyCode::Enter => {
+            Some("open") => {
                 if let Some(idx) = self.selected_conversation_idx {
                     if let Some(conversation) = self.conversations.get(idx) {
                         self.load_conversation(&conversation.id).await?;
@@ -376,7 +552,7 @@ impl App {
             }
             
             // Create new conversation
-            KeyCode::Char('n') => {
+            Some("new") => {
                 // Default name with timestamp
                 let title = format!("Conversation {}", chrono::Local::now().format("%Y-%m-%d %H:%M"));
                 self.create_conversation(&title).await?;
@@ -384,7 +560,7 @@ impl App {
             }
             
             // Delete conversation
-            KeyCode::Char('d') => {
+            Some("delete") => {
                 if let Some(idx) = self.selected_conversation_idx {
                     if let Some(conversation) = self.conversations.get(idx) {
                         // In a real implementation, we'd ask for confirmation
@@ -394,19 +570,19 @@ impl App {
             }
             
             // Command mode
-            KeyCode::Char(':') => {
+            Some("command") => {
                 self.command_input = TextArea::default();
                 self.command_input.set_placeholder_text("Type a command...");
                 self.mode = AppMode::Command;
             }
             
             // Scroll through conversation history
-            KeyCode::PageUp => {
+            Some("page_up") => {
                 if self.message_offset > 0 {
                     self.message_offset -= 1;
                 }
             }
-            KeyCode::PageDown => {
+            Some("page_down") => {
                 if let Some(conversation) = &self.current_conversation {
                     if self.message_offset < conversation.messages.len() {
                         self.message_offset += 1;
@@ -414,11 +590,24 @@ impl App {
                 }
             }
             
+            Some("first_unread") => match self.first_unread {
+                Some(index) => self.message_offset = index,
+                None => self.set_status(&tr!("tui-no-unread"), false),
+            },
+            
             // Reload conversations
-            KeyCode::Char('r') => {
+            Some("reload") => {
                 self.load_conversations().await?;
             }
             
+            // Rate the latest reply
+            Some("rate_up") => {
+                self.rate_last_response(Some(Rating::Up), None).await?;
+            }
+            Some("rate_down") => {
+                self.rate_last_response(Some(Rating::Down), None).await?;
+            }
+            
             _ => {}
         }
         
@@ -427,36 +616,71 @@ impl App {
     
     // Handle keys in chat mode (message input)
     async fn handle_chat_mode_key(&mut self, key: KeyEvent) -> AppResult<()> {
-        match key.code {
-            // Send message on Ctrl+Enter
-            KeyCode::Enter if key.modifiers.contains(KeyModifiers::CONTROL) => {
+        match self.key_action("tui.chat", &key) {
+            // Send message (Ctrl+Enter by default)
+            Some("send") => {
                 let content = self.input.lines().join("\n");
                 if !content.is_empty() {
+                    // Expensive sends need a second press
+                    let needs_confirmation = self.send_estimate.as_ref().map_or(false, |e| e.needs_confirmation);
+                    if needs_confirmation && self.cost_confirmed_for.as_deref() != Some(content.as_str()) {
+                        let label = self.send_estimate.as_ref().map(|e| e.label.clone()).unwrap_or_default();
+                        self.set_status(&format!("This message costs {}; send again to confirm", label), true);
+                        self.cost_confirmed_for = Some(content);
+                        return Ok(());
+                    }
+                    
                     self.send_message(&content).await?;
                     self.input = TextArea::default();
                     self.input.set_placeholder_text("Type a message...");
+                    self.send_estimate = None;
+                    self.cost_confirmed_for = None;
                 }
             }
             
-            // Exit chat mode on Escape
-            KeyCode::Esc => {
+            // Exit chat mode (Escape by default), keeping unsent text
+            Some("leave") => {
+                self.save_draft().await;
                 self.mode = AppMode::Normal;
             }
             
             // Pass other keys to the text area
             _ => {
                 self.input.input(key);
+                self.refresh_estimate().await;
             }
         }
         
         Ok(())
     }
     
+    // Keep the unsent input of the current conversation for later
+    async fn save_draft(&mut self) {
+        let Some(conversation) = &self.current_conversation else {
+            return;
+        };
+        let text = self.input.lines().join("\n");
+        if let Err(e) = self.chat_service.set_draft(&conversation.id, &text).await {
+            self.set_status(&tr!("tui-error", error = e.to_string()), true);
+        }
+    }
+    
+    // Recompute the projected cost of the draft
+    async fn refresh_estimate(&mut self) {
+        let content = self.input.lines().join("\n");
+        self.send_estimate = match &self.current_conversation {
+            Some(conversation) if !content.trim().is_empty() => {
+                self.chat_service.estimate_cost(&conversation.id, &content).await.ok()
+            }
+            _ => None,
+        };
+    }
+    
     // Handle keys in command mode
     async fn handle_command_mode_key(&mut self, key: KeyEvent) -> AppResult<()> {
-        match key.code {
-            // Execute command on Enter
-            KeyCode::Enter => {
+        match self.key_action("tui.command", &key) {
+            // Execute command (Enter by default)
+            Some("run") => {
                 let command = self.command_input.lines().join(" ").trim().to_string();
                 self.mode = AppMode::Normal;
                 
@@ -465,8 +689,8 @@ impl App {
                 }
             }
             
-            // Exit command mode on Escape
-            KeyCode::Esc => {
+            // Exit command mode (Escape by default)
+            Some("cancel") => {
                 self.mode = AppMode::Normal;
             }
             
@@ -481,9 +705,9 @@ impl App {
     
     // Handle keys in help mode
     fn handle_help_mode_key(&mut self, key: KeyEvent) -> AppResult<()> {
-        match key.code {
-            // Exit help mode on Escape or q
-            KeyCode::Esc | KeyCode::Char('q') => {
+        match self.key_action("tui.help", &key) {
+            // Exit help mode (Escape or q by default)
+            Some("close") => {
                 self.show_help = false;
                 self.mode = AppMode::Normal;
             }
@@ -495,26 +719,26 @@ impl App {
     
     // Handle keys in settings mode
     async fn handle_settings_mode_key(&mut self, key: KeyEvent) -> AppResult<()> {
-        match key.code {
-            // Exit settings mode on Escape
-            KeyCode::Esc => {
+        match self.key_action("tui.settings", &key) {
+            // Exit settings mode (Escape by default)
+            Some("close") => {
                 self.settings_open = false;
                 self.mode = AppMode::Normal;
             }
             
             // Navigate settings
-            KeyCode::Up | KeyCode::Char('k') => {
+            Some("up") => {
                 if self.settings_idx > 0 {
                     self.settings_idx -= 1;
                 }
             }
-            KeyCode::Down | KeyCode::Char('j') => {
+            Some("down") => {
                 // In a real implementation, we'd check against max settings
                 self.settings_idx += 1;
             }
             
             // Toggle or modify settings
-            KeyCode::Enter | KeyCode::Char(' ') => {
+            Some("toggle") => {
                 // Toggle or modify the selected setting
                 // In a real implementation, we'd handle different setting types
             }
@@ -525,6 +749,81 @@ impl App {
         Ok(())
     }
     
+    // Open the sync conflict review screen
+    fn open_conflicts(&mut self) {
+        self.conflicts = get_conflict_queue().list();
+        self.conflict_idx = 0;
+        self.conflicts_open = true;
+        self.mode = AppMode::Conflicts;
+    }
+    
+    // Resolve the selected sync conflict
+    fn resolve_selected_conflict(&mut self, resolution: ConflictResolution) {
+        let Some(conflict) = self.conflicts.get(self.conflict_idx) else {
+            return;
+        };
+        
+        match get_conflict_queue().resolve(&conflict.id, resolution) {
+            Ok(resolved) => {
+                self.set_status(&tr!("tui-conflict-resolved", key = resolved.key.as_str()), false);
+                self.conflicts = get_conflict_queue().list();
+                if self.conflict_idx >= self.conflicts.len() {
+                    self.conflict_idx = self.conflicts.len().saturating_sub(1);
+                }
+            }
+            Err(e) => {
+                self.set_status(&tr!("tui-conflict-resolve-failed", error = e.to_string()), true);
+            }
+        }
+    }
+    
+    // Handle keys in conflict review mode
+    fn handle_conflicts_mode_key(&mut self, key: KeyEvent) -> AppResult<()> {
+        match self.key_action("tui.conflicts", &key) {
+            // Exit conflict review (Escape or q by default)
+            Some("close") => {
+                self.conflicts_open = false;
+                self.mode = AppMode::Normal;
+            }
+            
+            // Navigate conflicts
+            Some("up") => {
+                if self.conflict_idx > 0 {
+                    self.conflict_idx -= 1;
+                }
+            }
+            Some("down") => {
+                if self.conflict_idx + 1 < self.conflicts.len() {
+                    self.conflict_idx += 1;
+                }
+            }
+            
+            // Keep local / keep remote
+            Some("keep_local") => {
+                self.resolve_selected_conflict(ConflictResolution::KeepLocal);
+            }
+            Some("keep_remote") => {
+                self.resolve_selected_conflict(ConflictResolution::KeepRemote);
+            }
+            
+            // Merge by keeping both versions, local first
+            Some("merge") => {
+                if let Some(conflict) = self.conflicts.get(self.conflict_idx) {
+                    let value = [&conflict.local.value, &conflict.remote.value]
+                        .iter()
+                        .filter_map(|v| v.as_deref())
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    self.resolve_selected_conflict(ConflictResolution::Merge { value });
+                }
+            }
+            
+            _ => {}
+        }
+        
+        Ok(())
+    }
+    
     // Execute a command from the command prompt
     async fn execute_command(&mut self, command: &str) -> AppResult<()> {
         // Parse command
@@ -560,11 +859,106 @@ impl App {
                 self.settings_open = true;
                 self.mode = AppMode::Settings;
             }
+            "conflicts" | "c" => {
+                self.open_conflicts();
+            }
+            "keymap" => {
+                self.keymap_command(&parts[1..]);
+            }
+            "a11y" | "accessible" => {
+                let enabled = match parts.get(1).copied() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    None => !self.accessible,
+                    Some(_) => {
+                        self.set_status(&tr!("tui-a11y-usage"), true);
+                        return Ok(());
+                    }
+                };
+                self.set_accessible(enabled);
+            }
+            "theme" => {
+                if let Some(name) = parts.get(1) {
+                    match set_active_theme(name, Some(THEME_PROFILE)) {
+                        Ok(theme) => {
+                            self.theme = theme;
+                            self.set_status(&tr!("tui-theme-active", name = *name), false);
+                        }
+                        Err(e) => self.set_status(&tr!("tui-theme-error", error = e.to_string()), true),
+                    }
+                } else {
+                    self.set_status(&tr!("tui-themes", names = list_themes().join(", ")), false);
+                }
+            }
+            "rate" => {
+                let rating = match parts.get(1).copied() {
+                    Some("up") | Some("+") => Some(Rating::Up),
+                    Some("down") | Some("-") => Some(Rating::Down),
+                    Some("clear") => None,
+                    _ => {
+                        self.set_status(&tr!("tui-rate-usage"), true);
+                        return Ok(());
+                    }
+                };
+                let comment = if parts.len() > 2 { Some(parts[2..].join(" ")) } else { None };
+                self.rate_last_response(rating, comment).await?;
+            }
             _ => {
-                self.set_status(&format!("Unknown command: {}", parts[0]), true);
+                self.set_status(&tr!("tui-unknown-command", command = parts[0]), true);
             }
         }
         
         Ok(())
     }
+    
+    // Switch keymap presets or share keymaps: `keymap vim|emacs|default`,
+    // `keymap export <path>`, `keymap import <path>`
+    fn keymap_command(&mut self, args: &[&str]) {
+        let result = match args {
+            ["export", path] => self.keymap.export(std::path::Path::new(path)).map(|_| tr!("tui-keymap-exported", path = *path)),
+            ["import", path] => Keymap::import(std::path::Path::new(path)).map(|keymap| {
+                self.keymap = keymap;
+                tr!("tui-keymap-imported", path = *path)
+            }),
+            [preset] => Keymap::preset(preset).map(|keymap| {
+                self.keymap = keymap;
+                tr!("tui-keymap-active", name = *preset)
+            }),
+            _ => {
+                self.set_status(&tr!("tui-keymap-usage"), true);
+                return;
+            }
+        };
+        
+        // Imports and presets become the saved keymap
+        let result = result.and_then(|message| {
+            if !matches!(args, ["export", _]) {
+                self.keymap.save()?;
+                *get_keymap().write().unwrap() = self.keymap.clone();
+            }
+            Ok(message)
+        });
+        match result {
+            Ok(message) => self.set_status(&message, false),
+            Err(e) => self.set_status(&tr!("tui-keymap-error", error = e.to_string()), true),
+        }
+    }
+    
+    // Turn the screen reader layout on or off and remember the choice
+    pub fn set_accessible(&mut self, enabled: bool) {
+        self.accessible = enabled;
+        
+        let settings = get_settings();
+        let mut settings = settings.lock().unwrap();
+        settings.accessibility.screen_reader = enabled;
+        if let Err(e) = settings.save() {
+            drop(settings);
+            self.set_status(&tr!("tui-settings-save-failed", error = e.to_string()), true);
+            return;
+        }
+        drop(settings);
+        
+        let status = if enabled { "tui-a11y-on" } else { "tui-a11y-off" };
+        self.set_status(&tr!(status), false);
+    }
 }
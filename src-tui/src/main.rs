@@ -17,13 +17,29 @@ use ratatui::Terminal;
 
 use app::{App, AppResult};
 use event::{Event, EventHandler};
-use mcp_common::{get_mcp_service, init_mcp_service, service::ChatService};
+use mcp_common::service::script::{load_script, ScriptRunner};
+use mcp_common::{get_mcp_service, i18n, init_mcp_service, logs, platform, service::ChatService};
 
 // Entry point
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    env_logger::init();
+    // Portable mode (`--portable`) has to be settled before anything opens a file
+    platform::fs::init_from_args()?;
+    
+    // Initialize logging; lines are redacted before they are printed or kept
+    let _ = logs::init_logging(env_logger::Builder::from_default_env().build());
+    
+    // Override the detected language with `--lang <locale>`
+    if let Some(lang) = arg_value(std::env::args(), "--lang") {
+        i18n::set_locale(&lang);
+    }
+    
+    // `--script <file>` runs headless, for CI smoke tests, and exits non-zero on failure
+    if let Some(script) = arg_value(std::env::args(), "--script") {
+        let chat_service = Arc::new(ChatService::new(init_mcp_service()));
+        let passed = run_script(chat_service, &script, arg_value(std::env::args(), "--junit")).await?;
+        std::process::exit(if passed { 0 } else { 1 });
+    }
     
     // Set up terminal
     enable_raw_mode()?;
@@ -37,7 +53,10 @@ async fn main() -> Result<()> {
     let chat_service = Arc::new(ChatService::new(mcp_service));
     
     // Create app and run it
-    let app = App::new(chat_service);
+    let mut app = App::new(chat_service);
+    if std::env::args().any(|arg| arg == "--accessible") {
+        app.accessible = true;
+    }
     let res = run_app(&mut terminal, app).await;
     
     // Restore terminal
@@ -57,6 +76,45 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+// Value of an option given as `--name value` or `--name=value`
+fn arg_value(mut args: impl Iterator<Item = String>, name: &str) -> Option<String> {
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+// Run a script without the terminal UI; true when every step passed
+async fn run_script(chat_service: Arc<ChatService>, path: &str, junit: Option<String>) -> Result<bool> {
+    let script = load_script(std::path::Path::new(path))?;
+    let report = ScriptRunner::new(chat_service).run(&script).await;
+    
+    for step in &report.steps {
+        let status = if step.passed { "ok" } else if step.skipped { "skipped" } else { "FAILED" };
+        println!("{}. {} ... {}", step.index, step.name, status);
+        for problem in step.error.iter().chain(&step.failures) {
+            println!("    {}", problem);
+        }
+    }
+    println!(
+        "{}: {} passed, {} failed, {} skipped",
+        report.script,
+        report.passed(),
+        report.failed(),
+        report.skipped()
+    );
+    
+    if let Some(path) = junit {
+        std::fs::write(&path, report.to_junit())?;
+    }
+    Ok(report.all_passed())
+}
+
 // Run the application
 async fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
@@ -1,12 +1,18 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span, Text},
     widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 
 use crate::app::{App, AppMode};
+use crate::util::{color, get_role_style};
+use mcp_common::tr;
+use mcp_common::rag::citations::Citations;
+use mcp_common::service::unfurl::extract_urls;
+use mcp_common::theme::Palette;
+use mcp_common::utils::markdown::{self, MarkdownStream};
 
 /// Draw the user interface
 pub fn draw(f: &mut Frame, app: &App) {
@@ -38,10 +44,22 @@ pub fn draw(f: &mut Frame, app: &App) {
     if app.settings_open {
         draw_settings_screen(f, app);
     }
+    
+    // Draw conflict review screen if enabled
+    if app.conflicts_open {
+        draw_conflicts_screen(f, app);
+    }
 }
 
 /// Draw the status bar
 fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
+    if app.accessible {
+        draw_spoken_status(f, app, area);
+        return;
+    }
+    
+    let palette = &app.theme.palette;
+    
     let mut spans = vec![];
     
     // App mode
@@ -51,11 +69,12 @@ fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
         AppMode::Command => "COMMAND",
         AppMode::Help => "HELP",
         AppMode::Settings => "SETTINGS",
+        AppMode::Conflicts => "CONFLICTS",
     };
     
     spans.push(Span::styled(
         format!(" {} ", mode_str),
-        Style::default().bg(Color::Blue).fg(Color::White),
+        Style::default().bg(color(palette.accent)).fg(color(palette.accent_foreground)),
     ));
     
     // Current conversation
@@ -63,14 +82,14 @@ fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
         spans.push(Span::raw(" "));
         spans.push(Span::styled(
             &conversation.title,
-            Style::default().fg(Color::Green),
+            Style::default().fg(color(palette.accent)),
         ));
         
         if let Some(model) = &conversation.model {
             spans.push(Span::raw(" | "));
             spans.push(Span::styled(
                 &model.name,
-                Style::default().fg(Color::Yellow),
+                Style::default().fg(color(palette.warning)),
             ));
         }
     }
@@ -80,7 +99,7 @@ fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
         spans.push(Span::raw(" "));
         spans.push(Span::styled(
             " STREAMING ",
-            Style::default().bg(Color::LightGreen).fg(Color::Black),
+            Style::default().bg(color(palette.success)).fg(color(palette.background)),
         ));
     }
     
@@ -89,7 +108,7 @@ fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
         spans.push(Span::raw(" | "));
         spans.push(Span::styled(
             message,
-            Style::default().fg(if *is_error { Color::Red } else { Color::Green }),
+            Style::default().fg(color(if *is_error { palette.error } else { palette.success })),
         ));
     }
     
@@ -100,8 +119,69 @@ fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, area);
 }
 
+/// Status as plain sentences, for screen readers
+fn draw_spoken_status(f: &mut Frame, app: &App, area: Rect) {
+    let mode = match app.mode {
+        AppMode::Normal => "tui-mode-normal",
+        AppMode::Chatting => "tui-mode-chat",
+        AppMode::Command => "tui-mode-command",
+        AppMode::Help => "tui-mode-help",
+        AppMode::Settings => "tui-mode-settings",
+        AppMode::Conflicts => "tui-mode-conflicts",
+    };
+    
+    let mut sentences = vec![tr!("tui-a11y-mode", mode = tr!(mode))];
+    if let Some(conversation) = &app.current_conversation {
+        sentences.push(tr!("tui-a11y-conversation", title = conversation.title.as_str()));
+        if let Some(model) = &conversation.model {
+            sentences.push(tr!("tui-a11y-model", model = model.name.as_str()));
+        }
+    }
+    if app.is_streaming {
+        sentences.push(tr!("tui-a11y-streaming"));
+    }
+    if let Some((message, is_error)) = &app.status_message {
+        if *is_error {
+            sentences.push(tr!("tui-a11y-error", message = message.as_str()));
+        } else {
+            sentences.push(message.clone());
+        }
+    }
+    
+    f.render_widget(Paragraph::new(sentences.join(" ")), area);
+}
+
+/// Borders around panes; screen readers would read box drawing aloud
+fn pane_borders(app: &App) -> Borders {
+    if app.accessible {
+        Borders::NONE
+    } else {
+        Borders::ALL
+    }
+}
+
+/// Area of a popup; full screen in the accessible layout so nothing else is
+/// read alongside it
+fn popup_area(app: &App, percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    if app.accessible {
+        r
+    } else {
+        centered_rect(percent_x, percent_y, r)
+    }
+}
+
 /// Draw the main content area
 fn draw_main_area(f: &mut Frame, app: &App, area: Rect) {
+    // One pane at a time keeps the reading order linear
+    if app.accessible {
+        if app.mode == AppMode::Chatting {
+            draw_chat_area(f, app, area);
+        } else {
+            draw_conversations_list(f, app, area);
+        }
+        return;
+    }
+    
     // Split into conversations list and chat area
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -118,31 +198,61 @@ fn draw_main_area(f: &mut Frame, app: &App, area: Rect) {
     draw_chat_area(f, app, chunks[1]);
 }
 
+/// Title of the conversations list; the accessible layout adds the position
+fn list_title(app: &App) -> String {
+    let title = "Conversations".to_string();
+    if !app.accessible {
+        return title;
+    }
+    match app.selected_conversation_idx {
+        Some(idx) => format!(
+            "{} ({})",
+            title,
+            tr!("tui-a11y-position", position = idx + 1, total = app.conversations.len())
+        ),
+        None => format!("{} ({})", title, tr!("tui-a11y-no-conversations")),
+    }
+}
+
 /// Draw the conversations list
 fn draw_conversations_list(f: &mut Frame, app: &App, area: Rect) {
+    let palette = &app.theme.palette;
+    
     // Create list items
     let items: Vec<ListItem> = app
         .conversations
         .iter()
         .enumerate()
         .map(|(i, conversation)| {
-            let style = if Some(i) == app.selected_conversation_idx {
-                Style::default().bg(Color::Blue).fg(Color::White)
+            let selected = Some(i) == app.selected_conversation_idx;
+            let style = if selected {
+                Style::default().bg(color(palette.selection)).fg(color(palette.selection_foreground))
             } else {
                 Style::default()
             };
             
-            ListItem::new(conversation.title.clone()).style(style)
+            // Mark the selection in text as well as color
+            let title = if conversation.unread > 0 {
+                format!("{} ({})", conversation.title, conversation.unread)
+            } else {
+                conversation.title.clone()
+            };
+            if app.accessible {
+                let marker = if selected { "> " } else { "  " };
+                return ListItem::new(format!("{}{}", marker, title)).style(style);
+            }
+            let style = if conversation.unread > 0 { style.add_modifier(Modifier::BOLD) } else { style };
+            ListItem::new(title).style(style)
         })
         .collect();
     
     // Create the list
     let list = List::new(items)
-        .block(Block::default().title("Conversations").borders(Borders::ALL))
+        .block(Block::default().title(list_title(app)).borders(pane_borders(app)))
         .highlight_style(
             Style::default()
-                .bg(Color::Blue)
-                .fg(Color::White)
+                .bg(color(palette.selection))
+                .fg(color(palette.selection_foreground))
                 .add_modifier(Modifier::BOLD),
         );
     
@@ -152,10 +262,12 @@ fn draw_conversations_list(f: &mut Frame, app: &App, area: Rect) {
 
 /// Draw the chat area
 fn draw_chat_area(f: &mut Frame, app: &App, area: Rect) {
+    let palette = &app.theme.palette;
+    
     // Create the chat box
     let chat_box = Block::default()
         .title("Chat")
-        .borders(Borders::ALL);
+        .borders(pane_borders(app));
     
     // Render the chat box
     f.render_widget(chat_box, area);
@@ -170,40 +282,52 @@ fn draw_chat_area(f: &mut Frame, app: &App, area: Rect) {
         if !messages.is_empty() {
             let mut text_spans = Vec::new();
             
-            for message in messages {
+            for message in messages.iter().skip(app.message_offset) {
                 // Determine style based on role
-                let (prefix, style) = match message.role.as_str() {
-                    "user" => (
-                        "You: ",
-                        Style::default().fg(Color::Green),
-                    ),
-                    "assistant" => (
-                        "Claude: ",
-                        Style::default().fg(Color::Blue),
-                    ),
-                    "system" => (
-                        "System: ",
-                        Style::default().fg(Color::Yellow),
-                    ),
-                    _ => (
-                        "Unknown: ",
-                        Style::default(),
-                    ),
+                let prefix = match message.role.as_str() {
+                    "user" => "You: ",
+                    "assistant" => "Claude: ",
+                    "system" => "System: ",
+                    _ => "Unknown: ",
                 };
+                let style = get_role_style(&app.theme, message.role.as_str());
                 
-                // Add sender with style
-                text_spans.push(Line::from(Span::styled(
-                    prefix,
-                    style.add_modifier(Modifier::BOLD),
-                )));
+                // Add sender with style, noting edited messages
+                let mut sender = vec![Span::styled(prefix, style.add_modifier(Modifier::BOLD))];
+                if message.is_edited() {
+                    sender.push(Span::styled("(edited)", Style::default().fg(color(palette.muted))));
+                }
+                text_spans.push(Line::from(sender));
                 
-                // Add message content
-                for content in &message.content {
-                    if let Some(text) = &content.text {
-                        // Split by lines and add each as a span
-                        for line in text.lines() {
-                            text_spans.push(Line::from(line));
-                        }
+                // Add message content; the reply still streaming shows only
+                // what can't change as more arrives
+                let is_streaming_reply = app.is_streaming
+                    && message.role == "assistant"
+                    && std::ptr::eq(message, messages.last().unwrap());
+                let blocks = if is_streaming_reply {
+                    app.response_markdown.blocks()
+                } else {
+                    MarkdownStream::parse(&message.text())
+                };
+                text_spans.extend(markdown_lines(&blocks, palette));
+                
+                // Titles of links that have been unfurled
+                for url in extract_urls(&message.text()) {
+                    if let Some(title) = app.link_previews.get(&url).and_then(|p| p.title.as_ref()) {
+                        text_spans.push(Line::from(Span::styled(
+                            format!("  ↳ {}", title),
+                            Style::default().fg(color(palette.muted)),
+                        )));
+                    }
+                }
+                
+                // Documents of the knowledge base passages the reply cites
+                if let Some(citations) = Citations::of(message) {
+                    for footnote in citations.footnotes() {
+                        text_spans.push(Line::from(Span::styled(
+                            format!("  {}", footnote),
+                            Style::default().fg(color(palette.muted)),
+                        )));
                     }
                 }
                 
@@ -222,16 +346,57 @@ fn draw_chat_area(f: &mut Frame, app: &App, area: Rect) {
     }
 }
 
+/// Lines of rendered markdown blocks
+fn markdown_lines(blocks: &[markdown::Block], palette: &Palette) -> Vec<Line<'static>> {
+    let muted = Style::default().fg(color(palette.muted));
+    let mut lines = Vec::new();
+    for block in blocks {
+        match block {
+            markdown::Block::Paragraph { text } => {
+                lines.extend(text.lines().map(|line| Line::from(line.to_string())));
+            }
+            markdown::Block::Heading { text, .. } => {
+                lines.push(Line::from(Span::styled(
+                    text.clone(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )));
+            }
+            markdown::Block::List { items, .. } => {
+                for item in items {
+                    lines.push(Line::from(vec![
+                        Span::styled(format!("{}{} ", "  ".repeat(item.depth), item.marker), muted),
+                        Span::raw(item.text.clone()),
+                    ]));
+                }
+            }
+            markdown::Block::Quote { text } => {
+                lines.extend(text.lines().map(|line| Line::from(Span::styled(format!("│ {}", line), muted))));
+            }
+            markdown::Block::Code { language, text, .. } => {
+                lines.push(Line::from(Span::styled(
+                    format!("── {} ", language.as_deref().unwrap_or("code")),
+                    muted,
+                )));
+                let code = Style::default().fg(color(palette.accent));
+                lines.extend(text.lines().map(|line| Line::from(Span::styled(format!("  {}", line), code))));
+            }
+            markdown::Block::Rule => lines.push(Line::from(Span::styled("────────", muted))),
+        }
+    }
+    lines
+}
+
 /// Draw the input box
 fn draw_input_box(f: &mut Frame, app: &App, area: Rect) {
     // Create the input box
     let input_box = Block::default()
-        .title(match app.mode {
-            AppMode::Chatting => "Message",
-            AppMode::Command => "Command",
-            _ => "Input",
+        .title(match (app.mode, &app.send_estimate) {
+            (AppMode::Chatting, Some(estimate)) => format!("Message ({})", estimate.label),
+            (AppMode::Chatting, None) => "Message".to_string(),
+            (AppMode::Command, _) => "Command".to_string(),
+            _ => "Input".to_string(),
         })
-        .borders(Borders::ALL);
+        .borders(pane_borders(app));
     
     // Set the block
     match app.mode {
@@ -245,10 +410,22 @@ fn draw_input_box(f: &mut Frame, app: &App, area: Rect) {
         }
         _ => {
             let text = match app.mode {
-                AppMode::Normal => "Press Enter to chat, n for new, d to delete",
-                AppMode::Help => "Press q to exit help",
-                AppMode::Settings => "Press Esc to exit settings",
-                _ => "",
+                AppMode::Normal => format!(
+                    "Press {} to chat, {} for new, {} to delete",
+                    keys(app, "tui.normal.open"),
+                    keys(app, "tui.normal.new"),
+                    keys(app, "tui.normal.delete")
+                ),
+                AppMode::Help => format!("Press {} to exit help", keys(app, "tui.help.close")),
+                AppMode::Settings => format!("Press {} to exit settings", keys(app, "tui.settings.close")),
+                AppMode::Conflicts => format!(
+                    "{} keep local, {} keep remote, {} merge, {} to exit",
+                    keys(app, "tui.conflicts.keep_local"),
+                    keys(app, "tui.conflicts.keep_remote"),
+                    keys(app, "tui.conflicts.merge"),
+                    keys(app, "tui.conflicts.close")
+                ),
+                _ => String::new(),
             };
             
             let paragraph = Paragraph::new(text)
@@ -259,15 +436,25 @@ fn draw_input_box(f: &mut Frame, app: &App, area: Rect) {
     }
 }
 
+/// Keys bound to an action, for hints
+fn keys(app: &App, action: &str) -> String {
+    app.keymap
+        .keys(action)
+        .iter()
+        .map(|key| key.to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 /// Draw the help screen
 fn draw_help_screen(f: &mut Frame, app: &App) {
     // Create a centered popup
-    let area = centered_rect(60, 60, f.size());
+    let area = popup_area(app, 60, 60, f.size());
     
     // Create the help box
     let help_box = Block::default()
         .title("Help")
-        .borders(Borders::ALL);
+        .borders(pane_borders(app));
     
     // Render the help box
     f.render_widget(help_box, area);
@@ -275,31 +462,46 @@ fn draw_help_screen(f: &mut Frame, app: &App) {
     // Inner area for help content
     let inner_area = help_box.inner(area);
     
-    // Help text
+    // Help text, with keys from the user's keymap
+    let binding = |action: &str, description: &str| {
+        Line::from(format!("  {:<12} - {}", keys(app, action), description))
+    };
     let text = Text::from(vec![
         Line::from("Claude MCP TUI Commands"),
         Line::from(""),
         Line::from("General:"),
-        Line::from("  q         - Quit application"),
-        Line::from("  ?         - Show this help"),
-        Line::from("  :         - Enter command mode"),
+        binding("tui.normal.quit", "Quit application"),
+        binding("tui.normal.help", "Show this help"),
+        binding("tui.normal.command", "Enter command mode"),
+        Line::from("  :keymap default|vim|emacs - Switch keybindings"),
+        Line::from("  :theme [name] - List or switch color themes"),
+        Line::from("  :a11y [on|off] - Screen reader layout"),
         Line::from(""),
         Line::from("Navigation:"),
-        Line::from("  j/k       - Move up/down in lists"),
-        Line::from("  Enter     - Select conversation"),
-        Line::from("  Esc       - Return to normal mode"),
+        binding("tui.normal.up", "Move up in lists"),
+        binding("tui.normal.down", "Move down in lists"),
+        binding("tui.normal.open", "Select conversation"),
+        binding("tui.chat.leave", "Return to normal mode"),
         Line::from(""),
         Line::from("Conversations:"),
-        Line::from("  n         - Create new conversation"),
-        Line::from("  d         - Delete current conversation"),
-        Line::from("  r         - Reload conversations"),
+        binding("tui.normal.new", "Create new conversation"),
+        binding("tui.normal.delete", "Delete current conversation"),
+        binding("tui.normal.reload", "Reload conversations"),
         Line::from(""),
         Line::from("Chat:"),
-        Line::from("  Ctrl+Enter - Send message"),
-        Line::from("  PageUp/Down - Scroll through history"),
+        binding("tui.chat.send", "Send message"),
+        binding("tui.normal.page_up", "Scroll up through history"),
+        binding("tui.normal.page_down", "Scroll down through history"),
+        binding("tui.normal.first_unread", "Jump to the first unread message"),
+        binding("tui.normal.rate_up", "Rate the latest reply up"),
+        binding("tui.normal.rate_down", "Rate the latest reply down"),
+        Line::from("  :rate up|down|clear [reason] - Rate with a reason"),
         Line::from(""),
         Line::from("Settings:"),
-        Line::from("  s         - Open settings"),
+        binding("tui.normal.settings", "Open settings"),
+        Line::from(""),
+        Line::from("Sync:"),
+        binding("tui.normal.conflicts", "Review sync conflicts"),
     ]);
     
     // Create the text widget
@@ -311,13 +513,15 @@ fn draw_help_screen(f: &mut Frame, app: &App) {
 
 /// Draw the settings screen
 fn draw_settings_screen(f: &mut Frame, app: &App) {
+    let palette = &app.theme.palette;
+    
     // Create a centered popup
-    let area = centered_rect(60, 60, f.size());
+    let area = popup_area(app, 60, 60, f.size());
     
     // Create the settings box
     let settings_box = Block::default()
         .title("Settings")
-        .borders(Borders::ALL);
+        .borders(pane_borders(app));
     
     // Render the settings box
     f.render_widget(settings_box, area);
@@ -330,7 +534,7 @@ fn draw_settings_screen(f: &mut Frame, app: &App) {
         ListItem::new("API Key Configuration"),
         ListItem::new("Default Model: Claude-3-Opus"),
         ListItem::new("Enable Message Streaming: Yes"),
-        ListItem::new("Dark Mode: Enabled"),
+        ListItem::new(format!("Theme: {}", app.theme.name)),
         ListItem::new("Show System Messages: Yes"),
     ];
     
@@ -338,8 +542,8 @@ fn draw_settings_screen(f: &mut Frame, app: &App) {
     let list = List::new(items)
         .highlight_style(
             Style::default()
-                .bg(Color::Blue)
-                .fg(Color::White)
+                .bg(color(palette.selection))
+                .fg(color(palette.selection_foreground))
                 .add_modifier(Modifier::BOLD),
         )
         .highlight_symbol("> ");
@@ -352,6 +556,83 @@ fn draw_settings_screen(f: &mut Frame, app: &App) {
     );
 }
 
+/// Draw the sync conflict review screen
+fn draw_conflicts_screen(f: &mut Frame, app: &App) {
+    let palette = &app.theme.palette;
+    
+    // Create a centered popup
+    let area = popup_area(app, 80, 80, f.size());
+    
+    // Create the conflicts box
+    let conflicts_box = Block::default()
+        .title(format!("Sync Conflicts ({})", app.conflicts.len()))
+        .borders(pane_borders(app));
+    
+    // Render the conflicts box
+    f.render_widget(conflicts_box.clone(), area);
+    
+    // Inner area for conflicts content
+    let inner_area = conflicts_box.inner(area);
+    
+    if app.conflicts.is_empty() {
+        f.render_widget(Paragraph::new("No conflicts to review"), inner_area);
+        return;
+    }
+    
+    // Split into the conflict list and the two versions
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(30),
+            Constraint::Percentage(35),
+            Constraint::Percentage(35),
+        ])
+        .split(inner_area);
+    
+    // Conflict list
+    let items: Vec<ListItem> = app
+        .conflicts
+        .iter()
+        .map(|c| ListItem::new(c.key.clone()))
+        .collect();
+    
+    let list = List::new(items)
+        .highlight_style(
+            Style::default()
+                .bg(color(palette.selection))
+                .fg(color(palette.selection_foreground))
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+    
+    f.render_stateful_widget(
+        list,
+        chunks[0],
+        &mut ratatui::widgets::ListState::default().with_selected(Some(app.conflict_idx)),
+    );
+    
+    // Both versions of the selected conflict
+    if let Some(conflict) = app.conflicts.get(app.conflict_idx) {
+        let version = |title: &str, version: &mcp_common::sync::ConflictVersion| {
+            Paragraph::new(version.value.clone().unwrap_or_else(|| "(deleted)".to_string()))
+                .block(
+                    Block::default()
+                        .title(format!(
+                            "{} - {} ({})",
+                            title,
+                            version.device_id,
+                            version.timestamp.format("%Y-%m-%d %H:%M")
+                        ))
+                        .borders(pane_borders(app)),
+                )
+                .wrap(Wrap { trim: false })
+        };
+        
+        f.render_widget(version("Local", &conflict.local), chunks[1]);
+        f.render_widget(version("Remote", &conflict.remote), chunks[2]);
+    }
+}
+
 /// Helper function to create a centered rect
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -1,5 +1,8 @@
 use chrono::{DateTime, Local};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use mcp_common::keymap::KeyCombo;
 use mcp_common::models::Message;
+use mcp_common::theme::{Rgb, Theme};
 use ratatui::style::{Color, Style};
 
 /// Format a timestamp as a readable string
@@ -7,14 +10,20 @@ pub fn format_timestamp(timestamp: &DateTime<Local>) -> String {
     timestamp.format("%Y-%m-%d %H:%M").to_string()
 }
 
+/// Convert a theme color to a terminal color
+pub fn color(rgb: Rgb) -> Color {
+    Color::Rgb(rgb.r, rgb.g, rgb.b)
+}
+
 /// Get the color for a message role
-pub fn get_role_color(role: &str) -> Color {
-    match role {
-        "user" => Color::Green,
-        "assistant" => Color::Blue,
-        "system" => Color::Yellow,
-        _ => Color::White,
-    }
+pub fn get_role_color(theme: &Theme, role: &str) -> Color {
+    let palette = &theme.palette;
+    color(match role {
+        "user" => palette.user,
+        "assistant" => palette.assistant,
+        "system" => palette.system,
+        _ => palette.foreground,
+    })
 }
 
 /// Format a message for display
@@ -35,8 +44,8 @@ pub fn format_message(message: &Message) -> String {
 }
 
 /// Get the style for a message role
-pub fn get_role_style(role: &str) -> Style {
-    Style::default().fg(get_role_color(role))
+pub fn get_role_style(theme: &Theme, role: &str) -> Style {
+    Style::default().fg(get_role_color(theme, role))
 }
 
 /// Truncate a string to a maximum length with ellipsis
@@ -84,3 +93,38 @@ pub fn parse_command(command: &str) -> Vec<String> {
     
     tokens
 }
+
+/// Convert a terminal key event to a keymap combo
+pub fn key_combo(key: &KeyEvent) -> KeyCombo {
+    let name = match key.code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::BackTab => "backtab".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Insert => "insert".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::F(n) => format!("f{}", n),
+        _ => String::new(),
+    };
+    
+    // Shift is already part of the character for printable keys
+    let printable = matches!(key.code, KeyCode::Char(c) if c != ' ');
+    KeyCombo {
+        ctrl: key.modifiers.contains(KeyModifiers::CONTROL),
+        alt: key.modifiers.contains(KeyModifiers::ALT),
+        shift: !printable && key.modifiers.contains(KeyModifiers::SHIFT),
+        meta: key.modifiers.contains(KeyModifiers::SUPER),
+        key: name,
+    }
+}
@@ -6,6 +6,7 @@ use serde_json::Value;
 use std::time::Duration;
 use tokio_stream::Stream;
 use futures_util::StreamExt;
+use mcp_common::protocol::{inspector, SseDecoder};
 
 /// Claude API response
 #[derive(Debug, Clone, Deserialize)]
@@ -40,13 +41,19 @@ pub struct ClaudeDeltaResponse {
     #[serde(rename = "type")]
     pub response_type: String,
     
-    /// Message ID
+    /// Message ID (absent from ping and error events)
+    #[serde(default)]
     pub message_id: String,
     
     /// Delta content
+    #[serde(default)]
     pub delta: Value,
     
-    /// Usage (only present in the final chunk)
+    /// Message being started (only present in `message_start`)
+    #[serde(default)]
+    pub message: Value,
+    
+    /// Usage (present in `message_delta`)
     pub usage: Option<Value>,
 }
 
@@ -128,6 +135,12 @@ impl ClaudeApi {
                 .unwrap_or_else(|_| HeaderValue::from_static("2023-06-01")),
         );
         
+        // Enable prompt caching; requests without cache-control markers are unaffected
+        headers.insert(
+            "anthropic-beta",
+            HeaderValue::from_static(mcp_common::protocol::cache::PROMPT_CACHING_BETA),
+        );
+        
         headers
     }
     
@@ -135,12 +148,11 @@ impl ClaudeApi {
     pub async fn create_message(&self, body: &Value) -> Result<ClaudeResponse, Box<dyn std::error::Error>> {
         let url = format!("{}/v1/messages", self.base_url);
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .headers(self.create_headers())
-            .json(body)
-            .send()
-            .await?;
+            .json(body);
+        let response = inspector::send(request).await?;
         
         self.handle_response(response).await
     }
@@ -152,44 +164,31 @@ impl ClaudeApi {
     ) -> Result<impl Stream<Item = Result<ClaudeDeltaResponse, Box<dyn std::error::Error + Send + Sync>>>, Box<dyn std::error::Error>> {
         let url = format!("{}/v1/messages", self.base_url);
         
-        let response = self.client
+        let request = self.client
             .post(&url)
             .headers(self.create_headers())
-            .json(body)
-            .send()
-            .await?;
+            .json(body);
+        let response = inspector::send(request).await?;
         
         if !response.status().is_success() {
             return Err(self.handle_error_response(response).await?);
         }
         
-        let stream = response.bytes_stream().map(|result| {
-            result.map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync>)
-                .and_then(|bytes| {
-                    // Parse SSE event
-                    let text = String::from_utf8_lossy(&bytes);
-                    let lines: Vec<&str> = text.lines().collect();
-                    
-                    for line in lines {
-                        if line.starts_with("data: ") {
-                            let data = &line[6..]; // Skip "data: "
-                            
-                            if data == "[DONE]" {
-                                continue;
-                            }
-                            
-                            match serde_json::from_str::<ClaudeDeltaResponse>(data) {
-                                Ok(response) => return Ok(response),
-                                Err(e) => return Err(Box::new(e) as Box<dyn std::error::Error + Send + Sync>),
-                            }
-                        }
-                    }
-                    
-                    Err(Box::new(std::io::Error::new(
-                        std::io::ErrorKind::InvalidData,
-                        "Invalid SSE event",
-                    )) as Box<dyn std::error::Error + Send + Sync>)
-                })
+        // Chunks may hold several events or only part of one
+        let mut decoder = SseDecoder::new();
+        let stream = response.bytes_stream().flat_map(move |result| {
+            let deltas: Vec<Result<ClaudeDeltaResponse, Box<dyn std::error::Error + Send + Sync>>> = match result {
+                Ok(bytes) => match decoder.push(&bytes) {
+                    Ok(events) => events
+                        .iter()
+                        .filter_map(|event| event.parse::<ClaudeDeltaResponse>().transpose())
+                        .map(|delta| delta.map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>))
+                        .collect(),
+                    Err(e) => vec![Err(Box::new(e) as Box<dyn std::error::Error + Send + Sync>)],
+                },
+                Err(err) => vec![Err(Box::new(err) as Box<dyn std::error::Error + Send + Sync>)],
+            };
+            futures_util::stream::iter(deltas)
         });
         
         Ok(stream)
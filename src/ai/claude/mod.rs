@@ -13,12 +13,16 @@ use crate::utils::config;
 use crate::utils::events::{events, get_event_system};
 use async_trait::async_trait;
 use log::{debug, error, info, warn};
+use mcp_common::protocol::cache::{self, CacheUsage};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, SystemTime};
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
+/// Default system prompt for REST requests
+const SYSTEM_PROMPT: &str = "You are Claude, an AI assistant created by Anthropic. You are helpful, harmless, and honest.";
+
 /// Claude AI provider
 pub struct ClaudeProvider {
     /// Provider configuration
@@ -102,6 +106,7 @@ impl ClaudeProvider {
                     max_context_length: 200_000,
                     functions: true,
                     streaming: true,
+                    json_mode: false,
                 },
             },
             Model {
@@ -114,6 +119,7 @@ impl ClaudeProvider {
                     max_context_length: 180_000,
                     functions: true,
                     streaming: true,
+                    json_mode: false,
                 },
             },
             Model {
@@ -126,6 +132,7 @@ impl ClaudeProvider {
                     max_context_length: 150_000,
                     functions: true,
                     streaming: true,
+                    json_mode: false,
                 },
             },
         ];
@@ -160,7 +167,7 @@ impl ClaudeProvider {
                         "text": text
                     }));
                 }
-                ContentType::Image { url, media_type } => {
+                ContentType::Image { url, media_type, .. } => {
                     contents.push(serde_json::json!({
                         "type": "image",
                         "source": {
@@ -194,6 +201,48 @@ impl ClaudeProvider {
         })
     }
     
+    /// Whether prompt caching is enabled (on unless the `prompt_caching` setting is false)
+    fn prompt_caching_enabled(&self) -> bool {
+        self.config
+            .settings
+            .get("prompt_caching")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true)
+    }
+    
+    /// Build a Messages API request body, marking stable prefixes for caching
+    fn build_request_body(&self, model_id: &str, message: &Message, stream: bool) -> serde_json::Value {
+        let mut messages = vec![self.convert_to_claude_format(message)];
+        
+        let system = if self.prompt_caching_enabled() {
+            let flagged = message
+                .metadata
+                .as_ref()
+                .and_then(|m| m.get(cache::CACHE_METADATA_KEY))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            let mut system = cache::system_blocks(SYSTEM_PROMPT);
+            cache::mark_breakpoints(Some(&mut system), &mut messages, &[flagged]);
+            system
+        } else {
+            serde_json::Value::String(SYSTEM_PROMPT.to_string())
+        };
+        
+        let mut body = serde_json::json!({
+            "model": model_id,
+            "messages": messages,
+            "max_tokens": 4096,
+            "temperature": 0.7,
+            "system": system
+        });
+        
+        if stream {
+            body["stream"] = serde_json::Value::Bool(true);
+        }
+        
+        body
+    }
+    
     /// Convert Claude API response to Message
     fn convert_from_claude_response(&self, response: &ClaudeResponse) -> Message {
         // Extract text content from response
@@ -304,20 +353,14 @@ impl ModelProvider for ClaudeProvider {
         }
         
         // Otherwise use REST API
-        let claude_message = self.convert_to_claude_format(&message);
-        
-        // Create request body
-        let request_body = serde_json::json!({
-            "model": model_id,
-            "messages": [claude_message],
-            "max_tokens": 4096,
-            "temperature": 0.7,
-            "system": "You are Claude, an AI assistant created by Anthropic. You are helpful, harmless, and honest."
-        });
+        let request_body = self.build_request_body(model_id, &message, false);
         
         // Send request
         match self.api_client.create_message(&request_body).await {
-            Ok(response) => Ok(self.convert_from_claude_response(&response)),
+            Ok(response) => {
+                cache::record_usage(model_id, &CacheUsage::from_usage(&response.usage));
+                Ok(self.convert_from_claude_response(&response))
+            }
             Err(e) => Err(MessageError::NetworkError(e.to_string())),
         }
     }
@@ -350,17 +393,7 @@ impl ModelProvider for ClaudeProvider {
         }
         
         // Otherwise use REST API with streaming
-        let claude_message = self.convert_to_claude_format(&message);
-        
-        // Create request body
-        let request_body = serde_json::json!({
-            "model": model_id,
-            "messages": [claude_message],
-            "max_tokens": 4096,
-            "temperature": 0.7,
-            "system": "You are Claude, an AI assistant created by Anthropic. You are helpful, harmless, and honest.",
-            "stream": true
-        });
+        let request_body = self.build_request_body(model_id, &message, true);
         
         // Create stream handler
         let stream_id = Uuid::new_v4().to_string();
@@ -370,6 +403,7 @@ impl ModelProvider for ClaudeProvider {
             stream_id.clone(),
             tx.clone(),
             message.id.clone(),
+            model_id.to_string(),
         );
         
         // Store stream handler
@@ -3,6 +3,7 @@ use crate::models::messages::{ContentType, Message, MessageError, MessageRole};
 use crate::utils::events::{events, get_event_system};
 use futures_util::StreamExt;
 use log::{debug, error, warn};
+use mcp_common::protocol::cache::{self, CacheUsage};
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 use tokio::sync::{mpsc, oneshot};
@@ -20,11 +21,17 @@ pub struct ClaudeStreamHandler {
     /// Original message ID
     original_message_id: String,
     
+    /// Model generating the response
+    model_id: String,
+    
     /// Cancel channel
     cancel: Arc<Mutex<Option<oneshot::Sender<()>>>>,
     
     /// Accumulated text
     accumulated_text: Arc<Mutex<String>>,
+    
+    /// Token usage reported so far
+    usage: Arc<Mutex<CacheUsage>>,
 }
 
 impl ClaudeStreamHandler {
@@ -33,13 +40,16 @@ impl ClaudeStreamHandler {
         stream_id: String,
         tx: mpsc::Sender<Result<Message, MessageError>>,
         original_message_id: String,
+        model_id: String,
     ) -> Self {
         Self {
             stream_id,
             tx,
             original_message_id,
+            model_id,
             cancel: Arc::new(Mutex::new(None)),
             accumulated_text: Arc::new(Mutex::new(String::new())),
+            usage: Arc::new(Mutex::new(CacheUsage::default())),
         }
     }
     
@@ -118,7 +128,15 @@ impl ClaudeStreamHandler {
     /// Process delta response
     async fn process_delta(&self, delta: ClaudeDeltaResponse, message_id: &str) {
         match delta.response_type.as_str() {
+            "message_start" => {
+                // Input and cache token counts are only reported here
+                self.usage.lock().unwrap().update(&delta.message["usage"]);
+            }
             "message_delta" => {
+                if let Some(usage) = &delta.usage {
+                    self.usage.lock().unwrap().update(usage);
+                }
+                
                 // Check if delta contains content
                 if let Some(delta_content) = delta.delta.get("content") {
                     // Only process text content for now
@@ -160,11 +178,9 @@ impl ClaudeStreamHandler {
                 }
             }
             "message_stop" => {
-                // Final message, check if we have usage info
-                if let Some(usage) = delta.usage {
-                    // In a real implementation, we would include usage in metadata
-                    debug!("Stream stopped with usage: {:?}", usage);
-                }
+                let usage = *self.usage.lock().unwrap();
+                debug!("Stream stopped with usage: {:?}", usage);
+                cache::record_usage(&self.model_id, &usage);
             }
             _ => {
                 // Ignore other message types
@@ -0,0 +1,340 @@
+use super::inference::InferenceEngine;
+use super::models::LocalModelInfo;
+use crate::utils::config;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::time::Instant;
+
+/// Config key for GPU settings
+const GPU_CONFIG_KEY: &str = "ai.local.gpu";
+
+/// Inference backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GpuBackend {
+    /// NVIDIA CUDA
+    Cuda,
+
+    /// AMD ROCm
+    Rocm,
+
+    /// Apple Metal
+    Metal,
+
+    /// Vulkan (cross-vendor)
+    Vulkan,
+
+    /// CPU only
+    Cpu,
+}
+
+impl fmt::Display for GpuBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GpuBackend::Cuda => write!(f, "CUDA"),
+            GpuBackend::Rocm => write!(f, "ROCm"),
+            GpuBackend::Metal => write!(f, "Metal"),
+            GpuBackend::Vulkan => write!(f, "Vulkan"),
+            GpuBackend::Cpu => write!(f, "CPU"),
+        }
+    }
+}
+
+/// Detected hardware acceleration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccelerationInfo {
+    /// Backends usable on this machine, best first; always ends with CPU
+    pub available_backends: Vec<GpuBackend>,
+
+    /// Number of GPU devices detected
+    pub device_count: usize,
+}
+
+impl AccelerationInfo {
+    /// Detect available acceleration backends
+    pub fn detect() -> Self {
+        let mut available_backends = Vec::new();
+        let mut device_count = 0;
+
+        if cfg!(target_os = "macos") {
+            available_backends.push(GpuBackend::Metal);
+            device_count = 1;
+        }
+
+        // NVIDIA devices show up as /dev/nvidia0, /dev/nvidia1, ...
+        let nvidia_devices = (0..16)
+            .take_while(|i| Path::new(&format!("/dev/nvidia{}", i)).exists())
+            .count();
+        if nvidia_devices > 0 {
+            available_backends.push(GpuBackend::Cuda);
+            device_count = device_count.max(nvidia_devices);
+        }
+
+        // ROCm requires the kernel fusion driver
+        if Path::new("/dev/kfd").exists() {
+            available_backends.push(GpuBackend::Rocm);
+            let render_nodes = std::fs::read_dir("/dev/dri")
+                .map(|entries| {
+                    entries
+                        .filter_map(|e| e.ok())
+                        .filter(|e| e.file_name().to_string_lossy().starts_with("renderD"))
+                        .count()
+                })
+                .unwrap_or(1);
+            device_count = device_count.max(render_nodes);
+        }
+
+        if Self::has_vulkan_loader() {
+            available_backends.push(GpuBackend::Vulkan);
+            device_count = device_count.max(1);
+        }
+
+        available_backends.push(GpuBackend::Cpu);
+
+        Self {
+            available_backends,
+            device_count,
+        }
+    }
+
+    /// Check whether a Vulkan loader is installed
+    fn has_vulkan_loader() -> bool {
+        let candidates = [
+            "/usr/lib/x86_64-linux-gnu/libvulkan.so.1",
+            "/usr/lib64/libvulkan.so.1",
+            "/usr/lib/libvulkan.so.1",
+            "C:\\Windows\\System32\\vulkan-1.dll",
+        ];
+        candidates.iter().any(|p| Path::new(p).exists())
+    }
+
+    /// Whether a backend is available
+    pub fn supports(&self, backend: GpuBackend) -> bool {
+        self.available_backends.contains(&backend)
+    }
+}
+
+/// User GPU configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuConfig {
+    /// Preferred backends in order; the first available one is used (empty = automatic)
+    pub backend_preference: Vec<GpuBackend>,
+
+    /// Device index on multi-GPU machines
+    pub device_index: usize,
+
+    /// Per-model number of layers to offload to the GPU (-1 = all)
+    pub layer_offload_overrides: HashMap<String, i32>,
+
+    /// Best backend found by the last benchmark
+    pub benchmarked_backend: Option<GpuBackend>,
+}
+
+impl Default for GpuConfig {
+    fn default() -> Self {
+        Self {
+            backend_preference: Vec::new(),
+            device_index: 0,
+            layer_offload_overrides: HashMap::new(),
+            benchmarked_backend: None,
+        }
+    }
+}
+
+impl GpuConfig {
+    /// Load the GPU configuration from the application config
+    pub fn load() -> Self {
+        let config = config::get_config();
+        let config = config.lock().unwrap();
+
+        config
+            .get_value(GPU_CONFIG_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persist the GPU configuration
+    pub fn save(&self) -> Result<(), String> {
+        let value = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        config::set_value(GPU_CONFIG_KEY, value)?;
+        config::save_config().map_err(|e| e.to_string())
+    }
+
+    /// Choose the backend to use given the detected hardware
+    pub fn select_backend(&self, info: &AccelerationInfo) -> GpuBackend {
+        self.backend_preference
+            .iter()
+            .copied()
+            .find(|b| info.supports(*b))
+            .or_else(|| self.benchmarked_backend.filter(|b| info.supports(*b)))
+            .unwrap_or_else(|| info.available_backends[0])
+    }
+
+    /// Number of layers to offload for a model (-1 = all, 0 = none)
+    pub fn layers_for_model(&self, model_id: &str, backend: GpuBackend) -> i32 {
+        if backend == GpuBackend::Cpu {
+            return 0;
+        }
+        self.layer_offload_overrides.get(model_id).copied().unwrap_or(-1)
+    }
+
+    /// Validate device selection against detected hardware
+    pub fn validate(&self, info: &AccelerationInfo) -> Result<(), String> {
+        if info.device_count > 0 && self.device_index >= info.device_count {
+            return Err(format!(
+                "Device index {} out of range ({} devices detected)",
+                self.device_index, info.device_count
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Resolved acceleration settings passed to the inference engine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccelerationSettings {
+    /// Backend to use
+    pub backend: GpuBackend,
+
+    /// Device index
+    pub device_index: usize,
+
+    /// Layers to offload (-1 = all)
+    pub gpu_layers: i32,
+}
+
+/// Benchmark result for a single backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendBenchmark {
+    /// Backend measured
+    pub backend: GpuBackend,
+
+    /// Tokens generated
+    pub tokens: usize,
+
+    /// Measured throughput
+    pub tokens_per_second: f64,
+
+    /// Error if the backend failed
+    pub error: Option<String>,
+}
+
+/// Measure tokens/sec on each available backend and store the best choice
+pub fn run_gpu_benchmark(
+    engine: &InferenceEngine,
+    model_info: &LocalModelInfo,
+    max_tokens: usize,
+) -> Result<Vec<BackendBenchmark>, String> {
+    let info = AccelerationInfo::detect();
+    let mut gpu_config = GpuConfig::load();
+    let mut results = Vec::new();
+
+    for backend in info.available_backends.iter().copied() {
+        debug!("Benchmarking backend {}", backend);
+
+        engine.set_acceleration(AccelerationSettings {
+            backend,
+            device_index: gpu_config.device_index,
+            gpu_layers: gpu_config.layers_for_model(&model_info.id, backend),
+        });
+
+        if let Err(e) = engine.load_model(model_info) {
+            warn!("Backend {} failed to load model: {:?}", backend, e);
+            results.push(BackendBenchmark {
+                backend,
+                tokens: 0,
+                tokens_per_second: 0.0,
+                error: Some(format!("{:?}", e)),
+            });
+            continue;
+        }
+
+        let start = Instant::now();
+        let mut tokens = 0;
+        let outcome = engine.generate_streaming("Benchmark prompt", max_tokens, |_| {
+            tokens += 1;
+            true
+        });
+        let elapsed = start.elapsed().as_secs_f64();
+
+        results.push(BackendBenchmark {
+            backend,
+            tokens,
+            tokens_per_second: if elapsed > 0.0 { tokens as f64 / elapsed } else { 0.0 },
+            error: outcome.err().map(|e| format!("{:?}", e)),
+        });
+    }
+
+    let best = results
+        .iter()
+        .filter(|r| r.error.is_none() && r.tokens > 0)
+        .max_by(|a, b| a.tokens_per_second.total_cmp(&b.tokens_per_second))
+        .map(|r| r.backend);
+
+    if let Some(best) = best {
+        info!("Fastest backend for {}: {}", model_info.id, best);
+        gpu_config.benchmarked_backend = Some(best);
+        gpu_config.save()?;
+    }
+
+    // Restore the configured backend
+    engine.set_acceleration(resolve_acceleration(&gpu_config, &info, &model_info.id));
+
+    Ok(results)
+}
+
+/// Resolve acceleration settings for a model
+pub fn resolve_acceleration(
+    gpu_config: &GpuConfig,
+    info: &AccelerationInfo,
+    model_id: &str,
+) -> AccelerationSettings {
+    let backend = gpu_config.select_backend(info);
+    AccelerationSettings {
+        backend,
+        device_index: gpu_config.device_index,
+        gpu_layers: gpu_config.layers_for_model(model_id, backend),
+    }
+}
+
+/// VRAM usage of the configured GPU in percent, used by the memory
+/// pressure monitor. AMD cards report it through sysfs; NVIDIA cards are
+/// asked through `nvidia-smi`. `None` when neither is available.
+pub fn vram_usage_percent() -> Option<f32> {
+    let device = GpuConfig::load().device_index;
+    
+    let card = Path::new("/sys/class/drm").join(format!("card{}", device)).join("device");
+    let read = |name: &str| -> Option<f64> {
+        std::fs::read_to_string(card.join(name)).ok()?.trim().parse().ok()
+    };
+    if let (Some(used), Some(total)) = (read("mem_info_vram_used"), read("mem_info_vram_total")) {
+        if total > 0.0 {
+            return Some((used / total * 100.0) as f32);
+        }
+    }
+    
+    if !Path::new(&format!("/dev/nvidia{}", device)).exists() {
+        return None;
+    }
+    let output = std::process::Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=memory.used,memory.total",
+            "--format=csv,noheader,nounits",
+            &format!("--id={}", device),
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let line = String::from_utf8_lossy(&output.stdout);
+    let mut fields = line.trim().split(',').map(|f| f.trim().parse::<f64>());
+    match (fields.next(), fields.next()) {
+        (Some(Ok(used)), Some(Ok(total))) if total > 0.0 => Some((used / total * 100.0) as f32),
+        _ => {
+            debug!("Unexpected nvidia-smi output: {}", line.trim());
+            None
+        }
+    }
+}
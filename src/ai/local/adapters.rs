@@ -0,0 +1,335 @@
+//! LoRA adapters for local models: importing and downloading adapter files,
+//! choosing which adapters a conversation applies, and resolving that choice
+//! against the model a message is sent to.
+
+use super::models::LocalModelInfo;
+use crate::ai::ModelError;
+use crate::models::messages::Message;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use mcp_common::jobs::{get_job_manager, Job, JobHandle, JobKind};
+use mcp_common::models::registry::{file_sha256, get_model_registry, slug, AdapterEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio_stream::StreamExt;
+
+/// Message metadata key carrying a conversation's adapter selection
+pub const METADATA_KEY: &str = "lora_adapters";
+
+/// Adapter selections of each conversation, kept in the adapter directory
+const SELECTIONS_FILE: &str = "conversations.json";
+
+lazy_static! {
+    static ref SELECTIONS_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// An adapter a conversation applies, with its scaling factor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdapterSelection {
+    /// Adapter ID
+    pub adapter_id: String,
+
+    /// Scaling factor; the adapter's default when unset
+    #[serde(default)]
+    pub scale: Option<f32>,
+}
+
+/// An adapter file ready to apply to a loaded model
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedAdapter {
+    /// Adapter ID
+    pub id: String,
+
+    /// Adapter file
+    pub path: PathBuf,
+
+    /// Scaling factor
+    pub scale: f32,
+}
+
+/// An adapter with whether its file is on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdapterInfo {
+    /// The registry entry
+    #[serde(flatten)]
+    pub adapter: AdapterEntry,
+
+    /// Whether the file is on disk
+    pub is_downloaded: bool,
+}
+
+/// Directory adapter files are kept in
+pub fn adapter_dir(model_dir: &Path) -> PathBuf {
+    model_dir.join("adapters")
+}
+
+fn adapter_path(model_dir: &Path, adapter: &AdapterEntry) -> PathBuf {
+    adapter_dir(model_dir).join(&adapter.file_name)
+}
+
+/// Known adapters, optionally only those fitting a model
+pub fn list(model_dir: &Path, model: Option<&LocalModelInfo>) -> Vec<AdapterInfo> {
+    let registry = get_model_registry();
+    let adapters = match model {
+        Some(model) => registry.adapters_for(&model.id, &model.family()),
+        None => registry.adapters(),
+    };
+    adapters
+        .into_iter()
+        .map(|adapter| AdapterInfo {
+            is_downloaded: adapter_path(model_dir, &adapter).exists(),
+            adapter,
+        })
+        .collect()
+}
+
+fn new_entry(name: &str, base_model: &str, file_name: &str, scale: Option<f32>) -> AdapterEntry {
+    AdapterEntry {
+        id: slug(name),
+        name: name.to_string(),
+        base_model: base_model.to_string(),
+        file_name: file_name.to_string(),
+        download_url: None,
+        sha256: None,
+        size: None,
+        default_scale: scale.unwrap_or(1.0),
+        description: None,
+        added_at: Utc::now(),
+    }
+}
+
+/// Copy an adapter file into the adapter directory and register it
+pub fn import(
+    model_dir: &Path,
+    source: &Path,
+    name: Option<&str>,
+    base_model: &str,
+    scale: Option<f32>,
+) -> Result<AdapterEntry, String> {
+    let file_name = source
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| format!("Not an adapter file: {}", source.display()))?;
+    let stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+    let mut adapter = new_entry(name.unwrap_or(stem), base_model, file_name, scale);
+
+    let dest = adapter_path(model_dir, &adapter);
+    fs::create_dir_all(adapter_dir(model_dir)).map_err(|e| e.to_string())?;
+    fs::copy(source, &dest).map_err(|e| format!("Failed to copy adapter: {}", e))?;
+    adapter.size = fs::metadata(&dest).map(|m| m.len()).ok();
+    adapter.sha256 = file_sha256(&dest).ok();
+
+    if let Err(e) = get_model_registry().add_adapter(adapter.clone()) {
+        let _ = fs::remove_file(&dest);
+        return Err(e.to_string());
+    }
+    info!("Imported adapter {} for {}", adapter.id, base_model);
+    Ok(adapter)
+}
+
+/// Register an adapter served over HTTP and download it as a background job
+pub fn spawn_download(
+    model_dir: &Path,
+    url: &str,
+    name: &str,
+    base_model: &str,
+    sha256: Option<String>,
+    scale: Option<f32>,
+) -> Result<Job, String> {
+    let file_name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .filter(|f| !f.is_empty())
+        .ok_or_else(|| format!("No file name in {}", url))?;
+    if name.trim().is_empty() {
+        return Err("Adapter needs a name".to_string());
+    }
+    let mut adapter = new_entry(name, base_model, file_name, scale);
+    adapter.download_url = Some(url.to_string());
+    adapter.sha256 = sha256;
+    get_model_registry().add_adapter(adapter.clone()).map_err(|e| e.to_string())?;
+
+    let model_dir = model_dir.to_path_buf();
+    Ok(get_job_manager().spawn(JobKind::ModelDownload, &adapter.name.clone(), false, move |job| async move {
+        download(&model_dir, &adapter, &job).await.map_err(|e| match e {
+            ModelError::Cancelled => mcp_common::error::McpError::Cancelled,
+            e => mcp_common::error::McpError::Unknown(format!("Failed to download adapter: {:?}", e)),
+        })
+    }))
+}
+
+async fn download(model_dir: &Path, adapter: &AdapterEntry, job: &JobHandle) -> Result<(), ModelError> {
+    let url = adapter.download_url.as_deref().ok_or(ModelError::InvalidRequest)?;
+    let dest = adapter_path(model_dir, adapter);
+    let temp_path = dest.with_extension("download");
+    tokio::fs::create_dir_all(adapter_dir(model_dir))
+        .await
+        .map_err(|_| ModelError::SystemError)?;
+
+    let mut request = reqwest::Client::new().get(url);
+    if let Some(token) = get_model_registry().download_token(None, url) {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.map_err(|_| ModelError::NetworkError)?;
+    if !response.status().is_success() {
+        return Err(ModelError::NetworkError);
+    }
+
+    let total = response.content_length();
+    let mut done = 0;
+    let mut file = tokio::fs::File::create(&temp_path)
+        .await
+        .map_err(|_| ModelError::SystemError)?;
+    let mut stream = response.bytes_stream();
+    while let Some(item) = stream.next().await {
+        let chunk = item.map_err(|_| ModelError::NetworkError)?;
+        tokio::io::AsyncWriteExt::write_all(&mut file, &chunk)
+            .await
+            .map_err(|_| ModelError::SystemError)?;
+        done += chunk.len() as u64;
+        job.progress(done, total);
+        if job.is_cancelled() {
+            drop(file);
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(ModelError::Cancelled);
+        }
+    }
+    drop(file);
+
+    if let Some(expected) = &adapter.sha256 {
+        let path = temp_path.clone();
+        let actual = tokio::task::spawn_blocking(move || file_sha256(&path))
+            .await
+            .map_err(|_| ModelError::SystemError)?
+            .map_err(|_| ModelError::SystemError)?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            error!("Checksum mismatch for adapter {}: expected {}, got {}", adapter.id, expected, actual);
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(ModelError::ChecksumMismatch);
+        }
+    }
+    tokio::fs::rename(&temp_path, &dest)
+        .await
+        .map_err(|_| ModelError::SystemError)?;
+
+    let mut downloaded = adapter.clone();
+    downloaded.size = Some(done);
+    if let Err(e) = get_model_registry().add_adapter(downloaded) {
+        warn!("Failed to record size of adapter {}: {}", adapter.id, e);
+    }
+    info!("Downloaded adapter {}", adapter.id);
+    Ok(())
+}
+
+/// Delete an adapter's file and forget it; conversations using it stop
+/// applying it
+pub fn delete(model_dir: &Path, adapter_id: &str) -> Result<(), String> {
+    let adapter = get_model_registry().remove_adapter(adapter_id).map_err(|e| e.to_string())?;
+    let path = adapter_path(model_dir, &adapter);
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to delete adapter: {}", e))?;
+    }
+    info!("Deleted adapter {}", adapter_id);
+    Ok(())
+}
+
+fn load_selections(model_dir: &Path) -> BTreeMap<String, Vec<AdapterSelection>> {
+    fs::read_to_string(adapter_dir(model_dir).join(SELECTIONS_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Adapters a conversation applies
+pub fn conversation_adapters(model_dir: &Path, conversation_id: &str) -> Vec<AdapterSelection> {
+    load_selections(model_dir).remove(conversation_id).unwrap_or_default()
+}
+
+/// Choose the adapters a conversation applies; an empty list uses the base
+/// model alone
+pub fn set_conversation_adapters(
+    model_dir: &Path,
+    conversation_id: &str,
+    selection: Vec<AdapterSelection>,
+) -> Result<(), String> {
+    let registry = get_model_registry();
+    for choice in &selection {
+        registry.adapter(&choice.adapter_id).map_err(|e| e.to_string())?;
+        if choice.scale.map_or(false, |s| !(s > 0.0)) {
+            return Err(format!("Scale of {} must be positive", choice.adapter_id));
+        }
+    }
+
+    let _guard = SELECTIONS_LOCK.lock().unwrap();
+    let mut selections = load_selections(model_dir);
+    if selection.is_empty() {
+        selections.remove(conversation_id);
+    } else {
+        selections.insert(conversation_id.to_string(), selection);
+    }
+    fs::create_dir_all(adapter_dir(model_dir)).map_err(|e| e.to_string())?;
+    let content = serde_json::to_string_pretty(&selections).map_err(|e| e.to_string())?;
+    fs::write(adapter_dir(model_dir).join(SELECTIONS_FILE), content).map_err(|e| e.to_string())
+}
+
+/// Attach a conversation's adapter selection to a message bound for a model
+pub fn attach(model_dir: &Path, conversation_id: &str, mut message: Message) -> Message {
+    let selection = conversation_adapters(model_dir, conversation_id);
+    if !selection.is_empty() {
+        if let Ok(value) = serde_json::to_value(&selection) {
+            message
+                .metadata
+                .get_or_insert_with(Default::default)
+                .insert(METADATA_KEY.to_string(), value);
+        }
+    }
+    message
+}
+
+/// Adapter selection attached to a message
+pub fn from_message(message: &Message) -> Vec<AdapterSelection> {
+    message
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get(METADATA_KEY))
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Adapter files to apply to a model. Adapters for another base model, or
+/// whose file isn't downloaded, are skipped.
+pub fn resolve(model_dir: &Path, model: &LocalModelInfo, selection: &[AdapterSelection]) -> Vec<AppliedAdapter> {
+    let registry = get_model_registry();
+    let family = model.family();
+    selection
+        .iter()
+        .filter_map(|choice| {
+            let adapter = match registry.adapter(&choice.adapter_id) {
+                Ok(adapter) => adapter,
+                Err(_) => {
+                    warn!("Skipping unknown adapter {}", choice.adapter_id);
+                    return None;
+                }
+            };
+            if !adapter.applies_to(&model.id, &family) {
+                warn!("Skipping adapter {}: made for {}, not {}", adapter.id, adapter.base_model, model.id);
+                return None;
+            }
+            let path = adapter_path(model_dir, &adapter);
+            if !path.exists() {
+                warn!("Skipping adapter {}: not downloaded", adapter.id);
+                return None;
+            }
+            Some(AppliedAdapter {
+                id: adapter.id,
+                path,
+                scale: choice.scale.unwrap_or(adapter.default_scale),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,507 @@
+//! Fine-tuning local models on the user's own conversations.
+//!
+//! Conversations or rated responses are exported as chat-format training
+//! JSONL, handed to a fine-tuning backend, and the adapter the backend
+//! produces is registered for its base model. Two backends are supported:
+//!
+//! - a local command (`ai.finetune.command`), run with `--dataset`,
+//!   `--base-model`, `--output` and the hyperparameters. It reports progress
+//!   as JSON lines on stdout, e.g. `{"progress": 0.4, "message": "epoch 2/5"}`,
+//!   and writes the adapter to the output directory or names it with
+//!   `{"adapter": "/path/to/adapter.gguf"}`.
+//! - an HTTP service (`ai.finetune.url`): `POST /jobs` starts a run,
+//!   `GET /jobs/<id>` reports `status`, `progress`, `message` and, when
+//!   done, `adapter_url`, and `DELETE /jobs/<id>` stops it.
+
+use super::models::LocalModelInfo;
+use crate::services::chat::get_chat_service;
+use crate::utils::config;
+use crate::utils::events::{notify, Notification, NotificationLevel};
+use log::{info, warn};
+use mcp_common::error::{McpError, McpResult};
+use mcp_common::jobs::{get_job_manager, Job, JobHandle, JobKind};
+use mcp_common::models::registry::slug;
+use mcp_common::models::{Conversation, MessageRole, Rating};
+use mcp_common::service::feedback::FeedbackRecord;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// How often the HTTP backend is polled
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Progress is reported in thousandths
+const PROGRESS_SCALE: u64 = 1000;
+
+/// What the training data is made of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatasetSource {
+    /// Every user/assistant exchange of every conversation
+    Conversations,
+
+    /// Only responses rated up, with the prompts that produced them
+    Feedback,
+}
+
+/// One message of a training example
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrainingMessage {
+    /// "system", "user" or "assistant"
+    pub role: String,
+
+    /// Message text
+    pub content: String,
+}
+
+/// One line of the training JSONL
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrainingExample {
+    /// The exchange to learn from
+    pub messages: Vec<TrainingMessage>,
+}
+
+/// A fine-tuning run to start
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FineTuneRequest {
+    /// Name of the resulting adapter
+    pub name: String,
+
+    /// ID of the local chat model to fine-tune
+    pub base_model: String,
+
+    /// Training data
+    pub source: DatasetSource,
+
+    /// Training epochs; the backend's default when unset
+    #[serde(default)]
+    pub epochs: Option<u32>,
+
+    /// Learning rate; the backend's default when unset
+    #[serde(default)]
+    pub learning_rate: Option<f64>,
+
+    /// LoRA rank; the backend's default when unset
+    #[serde(default)]
+    pub lora_rank: Option<u32>,
+}
+
+/// The configured fine-tuning backend
+#[derive(Debug, Clone, PartialEq)]
+pub enum Backend {
+    /// A local program
+    Command(String),
+
+    /// An HTTP service
+    Http(String),
+}
+
+impl Backend {
+    /// Backend from `ai.finetune.command` or, failing that, `ai.finetune.url`
+    pub fn configured() -> Option<Self> {
+        let set = |key: &str| config::get_string(key).filter(|v| !v.trim().is_empty());
+        set("ai.finetune.command")
+            .map(Backend::Command)
+            .or_else(|| set("ai.finetune.url").map(|url| Backend::Http(url.trim_end_matches('/').to_string())))
+    }
+}
+
+fn role_name(role: &MessageRole) -> Option<&'static str> {
+    match role {
+        MessageRole::System => Some("system"),
+        MessageRole::User => Some("user"),
+        MessageRole::Assistant => Some("assistant"),
+        MessageRole::Tool => None,
+    }
+}
+
+/// Training examples of a conversation: each assistant reply with the
+/// system prompt and the exchanges leading up to it
+pub fn examples_from_conversation(conversation: &Conversation) -> Vec<TrainingExample> {
+    let mut history: Vec<TrainingMessage> = Vec::new();
+    let mut examples = Vec::new();
+
+    for message in &conversation.messages {
+        let role = match role_name(&message.role) {
+            Some(role) => role,
+            None => continue,
+        };
+        let content = message.text();
+        if content.trim().is_empty() {
+            continue;
+        }
+        history.push(TrainingMessage {
+            role: role.to_string(),
+            content,
+        });
+        if message.role == MessageRole::Assistant && history.iter().any(|m| m.role == "user") {
+            examples.push(TrainingExample {
+                messages: history.clone(),
+            });
+        }
+    }
+    examples
+}
+
+/// Training examples of responses rated up
+pub fn examples_from_feedback(records: &[FeedbackRecord]) -> Vec<TrainingExample> {
+    records
+        .iter()
+        .filter(|r| r.rating == Rating::Up && !r.prompt.trim().is_empty())
+        .map(|r| {
+            let mut messages = Vec::new();
+            if let Some(system) = &r.system {
+                messages.push(TrainingMessage {
+                    role: "system".to_string(),
+                    content: system.clone(),
+                });
+            }
+            messages.push(TrainingMessage {
+                role: "user".to_string(),
+                content: r.prompt.clone(),
+            });
+            messages.push(TrainingMessage {
+                role: "assistant".to_string(),
+                content: r.response.clone(),
+            });
+            TrainingExample { messages }
+        })
+        .collect()
+}
+
+/// Training examples of the current conversations or feedback
+pub fn collect_examples(source: DatasetSource) -> Vec<TrainingExample> {
+    let chat = get_chat_service();
+    match source {
+        DatasetSource::Conversations => chat
+            .conversations_with_messages()
+            .iter()
+            .flat_map(examples_from_conversation)
+            .collect(),
+        DatasetSource::Feedback => examples_from_feedback(&chat.feedback_records()),
+    }
+}
+
+/// Write training examples as JSONL; returns the number written
+pub fn write_jsonl(examples: &[TrainingExample], path: &Path) -> McpResult<usize> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut writer = BufWriter::new(fs::File::create(path)?);
+    for example in examples {
+        writeln!(writer, "{}", serde_json::to_string(example)?)?;
+    }
+    writer.flush()?;
+    Ok(examples.len())
+}
+
+/// Export training data to a file; returns the number of examples
+pub fn export_dataset(source: DatasetSource, path: &Path) -> McpResult<usize> {
+    write_jsonl(&collect_examples(source), path)
+}
+
+/// A progress line of the fine-tuning command
+#[derive(Debug, Default, Deserialize)]
+struct ProgressLine {
+    #[serde(default)]
+    progress: Option<f64>,
+
+    #[serde(default)]
+    message: Option<String>,
+
+    #[serde(default)]
+    adapter: Option<PathBuf>,
+}
+
+/// Status of a run of the HTTP backend
+#[derive(Debug, Deserialize)]
+struct RemoteStatus {
+    status: String,
+
+    #[serde(default)]
+    progress: Option<f64>,
+
+    #[serde(default)]
+    message: Option<String>,
+
+    #[serde(default)]
+    adapter_url: Option<String>,
+
+    #[serde(default)]
+    error: Option<String>,
+}
+
+fn report(job: &JobHandle, progress: Option<f64>, message: Option<String>) {
+    if let Some(progress) = progress {
+        let done = (progress.clamp(0.0, 1.0) * PROGRESS_SCALE as f64) as u64;
+        job.progress(done, Some(PROGRESS_SCALE));
+    }
+    if let Some(message) = message {
+        job.message(message);
+    }
+}
+
+/// Start fine-tuning as a background job. The dataset is exported before
+/// the job starts so an empty one is reported right away.
+pub fn spawn_finetune(request: FineTuneRequest) -> Result<Job, String> {
+    let backend = Backend::configured()
+        .ok_or_else(|| "No fine-tuning backend; set ai.finetune.command or ai.finetune.url".to_string())?;
+    if request.name.trim().is_empty() {
+        return Err("The adapter needs a name".to_string());
+    }
+    let provider = super::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    let model = provider
+        .all_models()
+        .into_iter()
+        .find(|m| m.id == request.base_model && m.is_downloaded)
+        .ok_or_else(|| format!("{} is not a downloaded local model", request.base_model))?;
+
+    let examples = collect_examples(request.source);
+    if examples.is_empty() {
+        return Err("There is no training data yet".to_string());
+    }
+    let work_dir = super::model_dir().join("finetune").join(slug(&request.name));
+    let dataset = work_dir.join("dataset.jsonl");
+    write_jsonl(&examples, &dataset).map_err(|e| e.to_string())?;
+    info!("Exported {} training examples to {}", examples.len(), dataset.display());
+
+    let title = format!("Fine-tune {} as {}", model.name, request.name);
+    Ok(get_job_manager().spawn(JobKind::FineTune, &title, false, move |job| async move {
+        let output = work_dir.join("output");
+        fs::create_dir_all(&output)?;
+        let adapter = match &backend {
+            Backend::Command(command) => run_command(command, &request, &model, &dataset, &output, &job).await?,
+            Backend::Http(url) => run_remote(url, &request, &dataset, &output, &job).await?,
+        };
+
+        job.message("Registering adapter");
+        let entry = provider
+            .import_adapter(&adapter, Some(&request.name), &model.id, None)
+            .map_err(McpError::Unknown)?;
+        notify(Notification::new(
+            NotificationLevel::Info,
+            "Fine-tuning finished",
+            format!("Adapter {} is ready for {}", entry.name, model.name),
+        ));
+        Ok(())
+    }))
+}
+
+/// Run the fine-tuning command; returns the adapter it produced
+async fn run_command(
+    command: &str,
+    request: &FineTuneRequest,
+    model: &LocalModelInfo,
+    dataset: &Path,
+    output: &Path,
+    job: &JobHandle,
+) -> McpResult<PathBuf> {
+    let mut cmd = tokio::process::Command::new(command);
+    cmd.arg("--dataset")
+        .arg(dataset)
+        .arg("--base-model")
+        .arg(&model.path)
+        .arg("--output")
+        .arg(output);
+    if let Some(epochs) = request.epochs {
+        cmd.arg("--epochs").arg(epochs.to_string());
+    }
+    if let Some(learning_rate) = request.learning_rate {
+        cmd.arg("--learning-rate").arg(learning_rate.to_string());
+    }
+    if let Some(rank) = request.lora_rank {
+        cmd.arg("--lora-rank").arg(rank.to_string());
+    }
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| McpError::Unknown(format!("Failed to start {}: {}", command, e)))?;
+
+    let mut lines = BufReader::new(child.stdout.take().expect("stdout is piped")).lines();
+    let mut adapter = None;
+    loop {
+        tokio::select! {
+            _ = job.cancelled() => {
+                let _ = child.kill().await;
+                return Err(McpError::Cancelled);
+            }
+            line = lines.next_line() => match line? {
+                Some(line) => match serde_json::from_str::<ProgressLine>(&line) {
+                    Ok(update) => {
+                        report(job, update.progress, update.message);
+                        adapter = update.adapter.or(adapter);
+                    }
+                    Err(_) => log::debug!("finetune: {}", line),
+                },
+                None => break,
+            }
+        }
+    }
+
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err(McpError::Unknown(format!("Fine-tuning command failed with {}", status)));
+    }
+    adapter.or_else(|| find_adapter(output)).ok_or_else(|| {
+        McpError::Unknown(format!("Fine-tuning produced no adapter in {}", output.display()))
+    })
+}
+
+/// The adapter file a command wrote to its output directory
+fn find_adapter(output: &Path) -> Option<PathBuf> {
+    fs::read_dir(output)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .find(|path| {
+            matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("gguf") | Some("bin") | Some("safetensors")
+            )
+        })
+}
+
+/// Run fine-tuning on the HTTP backend; returns the downloaded adapter
+async fn run_remote(
+    url: &str,
+    request: &FineTuneRequest,
+    dataset: &Path,
+    output: &Path,
+    job: &JobHandle,
+) -> McpResult<PathBuf> {
+    let examples: Vec<serde_json::Value> = fs::read_to_string(dataset)?
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let client = reqwest::Client::new();
+    let started: serde_json::Value = client
+        .post(format!("{}/jobs", url))
+        .json(&serde_json::json!({
+            "name": request.name,
+            "base_model": request.base_model,
+            "epochs": request.epochs,
+            "learning_rate": request.learning_rate,
+            "lora_rank": request.lora_rank,
+            "dataset": examples,
+        }))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| McpError::Connection(e.to_string()))?
+        .json()
+        .await
+        .map_err(|e| McpError::Protocol(e.to_string()))?;
+    let remote_id = started
+        .get("id")
+        .and_then(|id| id.as_str())
+        .ok_or_else(|| McpError::Protocol("Fine-tuning service returned no job ID".to_string()))?
+        .to_string();
+    let status_url = format!("{}/jobs/{}", url, remote_id);
+
+    loop {
+        tokio::select! {
+            _ = job.cancelled() => {
+                if let Err(e) = client.delete(&status_url).send().await {
+                    warn!("Failed to stop remote fine-tuning {}: {}", remote_id, e);
+                }
+                return Err(McpError::Cancelled);
+            }
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+
+        let status: RemoteStatus = client
+            .get(&status_url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| McpError::Connection(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| McpError::Protocol(e.to_string()))?;
+        report(job, status.progress, status.message);
+
+        match status.status.as_str() {
+            "succeeded" | "completed" => {
+                let adapter_url = status
+                    .adapter_url
+                    .ok_or_else(|| McpError::Protocol("Fine-tuning service returned no adapter".to_string()))?;
+                job.message("Downloading adapter");
+                let bytes = client
+                    .get(&adapter_url)
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .map_err(|e| McpError::Connection(e.to_string()))?
+                    .bytes()
+                    .await
+                    .map_err(|e| McpError::Connection(e.to_string()))?;
+                let file_name = adapter_url
+                    .split(['?', '#'])
+                    .next()
+                    .and_then(|path| path.rsplit('/').next())
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or("adapter.gguf");
+                let path = output.join(file_name);
+                fs::write(&path, &bytes)?;
+                return Ok(path);
+            }
+            "failed" | "cancelled" => {
+                return Err(McpError::Unknown(
+                    status.error.unwrap_or_else(|| format!("Fine-tuning {}", status.status)),
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_common::models::{Message, Model};
+
+    #[test]
+    fn conversation_examples_end_at_each_reply() {
+        let mut conversation = Conversation::new("Test", Model::default());
+        conversation.messages = vec![
+            Message::system("Be brief."),
+            Message::user("Hi"),
+            Message::assistant("Hello"),
+            Message::user("Bye"),
+            Message::assistant("Goodbye"),
+        ];
+
+        let examples = examples_from_conversation(&conversation);
+        assert_eq!(examples.len(), 2);
+        assert_eq!(examples[0].messages.len(), 3);
+        assert_eq!(examples[1].messages.last().unwrap().content, "Goodbye");
+        assert_eq!(examples[1].messages[0].role, "system");
+    }
+
+    #[test]
+    fn feedback_examples_keep_only_upvoted_responses() {
+        let record = |rating: Rating| FeedbackRecord {
+            conversation_id: "c".to_string(),
+            message_id: "m".to_string(),
+            model: "tinyllama".to_string(),
+            system: None,
+            prompt: "Question".to_string(),
+            response: "Answer".to_string(),
+            rating,
+            comment: None,
+            rated_at: chrono::Utc::now(),
+        };
+
+        let examples = examples_from_feedback(&[record(Rating::Up), record(Rating::Down)]);
+        assert_eq!(examples.len(), 1);
+        assert_eq!(examples[0].messages[0].role, "user");
+        assert_eq!(examples[0].messages[1].content, "Answer");
+    }
+}
@@ -1,6 +1,12 @@
+use super::acceleration::{AccelerationSettings, GpuBackend};
+use super::adapters::AppliedAdapter;
 use super::models::LocalModelInfo;
 use crate::ai::ModelError;
+use crate::models::messages::Message;
+use crate::optimization::ThreadSettings;
 use log::{debug, error, info, warn};
+use mcp_common::models::grammar::{self, Grammar, OutputFormat};
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
@@ -23,6 +29,57 @@ pub enum InferenceError {
     SystemError(String),
 }
 
+/// Message metadata key carrying the generation options of a request
+pub const OPTIONS_METADATA_KEY: &str = "generation_options";
+
+/// How a reply is generated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenerationOptions {
+    /// Most tokens to generate
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: usize,
+
+    /// Shape the reply must take; decoding is constrained to it
+    #[serde(default)]
+    pub output: OutputFormat,
+}
+
+fn default_max_tokens() -> usize {
+    512
+}
+
+impl Default for GenerationOptions {
+    fn default() -> Self {
+        Self {
+            max_tokens: default_max_tokens(),
+            output: OutputFormat::Text,
+        }
+    }
+}
+
+impl GenerationOptions {
+    /// Attach generation options to a message for the local provider
+    pub fn attach(&self, mut message: Message) -> Message {
+        if let Ok(value) = serde_json::to_value(self) {
+            message
+                .metadata
+                .get_or_insert_with(Default::default)
+                .insert(OPTIONS_METADATA_KEY.to_string(), value);
+        }
+        message
+    }
+
+    /// Generation options attached to a message, or the defaults
+    pub fn from_message(message: &Message) -> Self {
+        message
+            .metadata
+            .as_ref()
+            .and_then(|m| m.get(OPTIONS_METADATA_KEY))
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    }
+}
+
 /// Simulated inference engine
 /// 
 /// Note: This is a placeholder for a real inference engine like llama.cpp
@@ -33,17 +90,53 @@ pub struct InferenceEngine {
     
     /// Currently loaded model
     current_model: Arc<Mutex<Option<LocalModelInfo>>>,
+    
+    /// Thread count and core affinity used for inference
+    thread_settings: Arc<Mutex<ThreadSettings>>,
+    
+    /// GPU backend, device and layer offload used for inference
+    acceleration: Arc<Mutex<AccelerationSettings>>,
+    
+    /// LoRA adapters applied on top of the loaded model
+    adapters: Arc<Mutex<Vec<AppliedAdapter>>>,
 }
 
 impl InferenceEngine {
     /// Create a new inference engine
-    pub fn new(model_dir: &Path) -> Result<Self, InferenceError> {
+    pub fn new(model_dir: &Path, thread_settings: ThreadSettings) -> Result<Self, InferenceError> {
         Ok(Self {
             model_dir: model_dir.to_path_buf(),
             current_model: Arc::new(Mutex::new(None)),
+            thread_settings: Arc::new(Mutex::new(thread_settings)),
+            acceleration: Arc::new(Mutex::new(AccelerationSettings {
+                backend: GpuBackend::Cpu,
+                device_index: 0,
+                gpu_layers: 0,
+            })),
+            adapters: Arc::new(Mutex::new(Vec::new())),
         })
     }
     
+    /// Update acceleration settings; takes effect on the next model load
+    pub fn set_acceleration(&self, acceleration: AccelerationSettings) {
+        *self.acceleration.lock().unwrap() = acceleration;
+    }
+    
+    /// Get the acceleration settings used for inference
+    pub fn acceleration(&self) -> AccelerationSettings {
+        self.acceleration.lock().unwrap().clone()
+    }
+    
+    /// Update thread settings; takes effect on the next model load
+    pub fn set_thread_settings(&self, thread_settings: ThreadSettings) {
+        *self.thread_settings.lock().unwrap() = thread_settings;
+    }
+    
+    /// Get the thread settings used for inference
+    pub fn thread_settings(&self) -> ThreadSettings {
+        self.thread_settings.lock().unwrap().clone()
+    }
+    
     /// Load a model
     pub fn load_model(&self, model_info: &LocalModelInfo) -> Result<(), InferenceError> {
         // Check if model file exists
@@ -54,30 +147,113 @@ impl InferenceEngine {
             )));
         }
         
-        // Update current model
+        // Update current model; adapters only fit the model they were applied to
         let mut current_model = self.current_model.lock().unwrap();
+        if current_model.as_ref().map_or(true, |m| m.id != model_info.id) {
+            self.adapters.lock().unwrap().clear();
+        }
         *current_model = Some(model_info.clone());
         
         // In a real implementation, this would load the model into memory
-        // using the inference library's API
+        // using the inference library's API, passing the thread count and
+        // core affinity from the thread settings
+        let thread_settings = self.thread_settings.lock().unwrap();
+        let acceleration = self.acceleration.lock().unwrap();
         
-        info!("Loaded model {}", model_info.name);
+        info!(
+            "Loaded model {} ({} threads, affinity {:?}, backend {} device {}, {} GPU layers)",
+            model_info.name,
+            thread_settings.inference_threads,
+            thread_settings.affinity,
+            acceleration.backend,
+            acceleration.device_index,
+            acceleration.gpu_layers
+        );
         Ok(())
     }
     
+    /// ID of the loaded model, if any
+    pub fn loaded_model(&self) -> Option<String> {
+        self.current_model.lock().unwrap().as_ref().map(|m| m.id.clone())
+    }
+    
+    /// Unload a model, freeing its memory; does nothing when another model
+    /// has replaced it since
+    pub fn unload_model(&self, model_id: &str) -> bool {
+        let mut current_model = self.current_model.lock().unwrap();
+        if current_model.as_ref().is_none_or(|m| m.id != model_id) {
+            return false;
+        }
+        *current_model = None;
+        self.adapters.lock().unwrap().clear();
+        
+        // In a real implementation, this would release the model's weights
+        // and any memory offloaded to the GPU
+        info!("Unloaded model {}", model_id);
+        true
+    }
+    
+    /// Apply LoRA adapters to the loaded model, replacing those applied
+    /// before. The base model stays loaded, so switching adapters between
+    /// conversations is cheap.
+    pub fn apply_adapters(&self, adapters: Vec<AppliedAdapter>) -> Result<(), InferenceError> {
+        if self.current_model.lock().unwrap().is_none() {
+            return Err(InferenceError::RuntimeError("No model loaded".to_string()));
+        }
+        for adapter in &adapters {
+            if !adapter.path.exists() {
+                return Err(InferenceError::ModelLoadError(format!(
+                    "Adapter file not found: {}",
+                    adapter.path.display()
+                )));
+            }
+        }
+        
+        let mut applied = self.adapters.lock().unwrap();
+        if *applied == adapters {
+            return Ok(());
+        }
+        
+        // In a real implementation, this would detach the previous adapters
+        // and attach the new ones with their scaling factors
+        for adapter in &adapters {
+            info!("Applied adapter {} (scale {})", adapter.id, adapter.scale);
+        }
+        if adapters.is_empty() && !applied.is_empty() {
+            info!("Removed {} adapters", applied.len());
+        }
+        *applied = adapters;
+        Ok(())
+    }
+    
+    /// LoRA adapters applied to the loaded model
+    pub fn adapters(&self) -> Vec<AppliedAdapter> {
+        self.adapters.lock().unwrap().clone()
+    }
+    
     /// Generate text from a prompt
     pub fn generate(&self, prompt: &str) -> Result<String, InferenceError> {
+        self.generate_with_options(prompt, &GenerationOptions::default())
+    }
+    
+    /// Generate text from a prompt, constrained to the requested output format
+    pub fn generate_with_options(&self, prompt: &str, options: &GenerationOptions) -> Result<String, InferenceError> {
         // Get current model
         let current_model = self.current_model.lock().unwrap();
         let model_info = current_model.as_ref().ok_or_else(|| {
             InferenceError::RuntimeError("No model loaded".to_string())
         })?;
+        let grammar = constraint(&options.output)?;
         
         // In a real implementation, this would use the inference library's API
-        // to generate text from the prompt
+        // to generate text from the prompt, passing the grammar to the sampler
+        // so only tokens it accepts can be drawn
         
         // For now, return a simulated response
-        let response = self.simulate_response(model_info, prompt);
+        let response = match grammar {
+            Some(grammar) => self.simulate_constrained_response(&options.output, &grammar)?,
+            None => self.simulate_response(model_info, prompt),
+        };
         
         Ok(response)
     }
@@ -86,7 +262,7 @@ impl InferenceEngine {
     pub fn generate_streaming<F>(
         &self,
         prompt: &str,
-        max_tokens: usize,
+        options: &GenerationOptions,
         callback: F,
     ) -> Result<(), InferenceError>
     where
@@ -97,12 +273,20 @@ impl InferenceEngine {
         let model_info = current_model.as_ref().ok_or_else(|| {
             InferenceError::RuntimeError("No model loaded".to_string())
         })?;
+        let grammar = constraint(&options.output)?;
         
         // In a real implementation, this would use the inference library's API
         // to generate text from the prompt with streaming via callback
         
         // For now, return a simulated streaming response
-        self.simulate_streaming_response(model_info, prompt, max_tokens, callback)
+        match grammar {
+            Some(grammar) => {
+                let response = self.simulate_constrained_response(&options.output, &grammar)?;
+                simulate_chunks(&response, options.max_tokens, callback);
+                Ok(())
+            }
+            None => self.simulate_streaming_response(model_info, prompt, options.max_tokens, callback),
+        }
     }
     
     /// Simulate a response for testing
@@ -120,6 +304,19 @@ impl InferenceEngine {
         format!("{}\n\nNote: This response was generated by the local {} model running on your device.", response_template, model_info.name)
     }
     
+    /// Simulate a response that the output format's grammar accepts
+    fn simulate_constrained_response(&self, output: &OutputFormat, grammar: &Grammar) -> Result<String, InferenceError> {
+        let response = match output {
+            OutputFormat::JsonSchema { schema } => Some(
+                grammar::example(schema)
+                    .map_err(|e| InferenceError::InvalidInput(e.to_string()))?
+                    .to_string(),
+            ),
+            _ => grammar.sample(),
+        };
+        response.ok_or_else(|| InferenceError::InvalidInput("The grammar accepts no text".to_string()))
+    }
+    
     /// Simulate a streaming response for testing
     fn simulate_streaming_response<F>(
         &self,
@@ -169,3 +366,22 @@ impl InferenceEngine {
         hash
     }
 }
+
+/// Grammar that decoding is constrained to, if any
+fn constraint(output: &OutputFormat) -> Result<Option<Grammar>, InferenceError> {
+    output.grammar().map_err(|e| InferenceError::InvalidInput(e.to_string()))
+}
+
+/// Stream text in small chunks, keeping its whitespace intact
+fn simulate_chunks<F>(text: &str, max_tokens: usize, mut callback: F)
+where
+    F: FnMut(&str) -> bool,
+{
+    let chars: Vec<char> = text.chars().collect();
+    for chunk in chars.chunks(4).take(max_tokens) {
+        if !callback(&chunk.iter().collect::<String>()) {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+}
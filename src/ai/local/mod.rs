@@ -1,15 +1,36 @@
+pub mod acceleration;
+pub mod adapters;
+pub mod finetune;
 mod inference;
-mod models;
+pub mod models;
+pub mod prompt;
+pub mod translation;
+pub mod updates;
 
-use self::inference::InferenceEngine;
-use self::models::LocalModelInfo;
+use self::acceleration::{AccelerationInfo, BackendBenchmark, GpuConfig};
+use self::adapters::AdapterSelection;
+use self::inference::{InferenceEngine, InferenceError};
+pub use self::inference::{GenerationOptions, OPTIONS_METADATA_KEY};
+use self::models::{LocalModelInfo, ModelKind};
 use crate::ai::{ModelError, ModelProvider, ModelProviderConfig, ModelStatus, ProviderType};
 use crate::models::messages::{ContentType, Message, MessageContent, MessageError, MessageRole};
 use crate::models::Model;
+use crate::optimization::{get_pressure_monitor, get_thread_settings};
+use crate::utils::cancellation::get_cancellation_registry;
 use crate::utils::config;
-use crate::utils::events::{events, get_event_system};
+use crate::utils::events::{
+    events, get_event_system, notify, Notification, NotificationAction, NotificationLevel,
+};
 use async_trait::async_trait;
+use tokio_stream::StreamExt;
 use log::{debug, error, info, warn};
+use serde::Serialize;
+use mcp_common::error::{McpError, McpResult};
+use mcp_common::jobs::{get_job_manager, Job, JobHandle, JobKind};
+use mcp_common::platform::fs::{app_dir, AppDir};
+use mcp_common::models::registry::{
+    file_sha256, get_model_registry, group_families, AdapterEntry, FamilyVariant, ModelFamily,
+};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex, RwLock};
@@ -41,21 +62,8 @@ pub struct LocalProvider {
 impl LocalProvider {
     /// Create a new local provider
     pub fn new() -> Result<Self, ModelError> {
-        // Load configuration
-        let config = config::get_config();
-        let config_guard = config.lock().unwrap();
-        
         // Get model directory
-        let model_dir = if let Some(dir) = config_guard.get_string("ai.local.model_dir") {
-            PathBuf::from(dir)
-        } else {
-            // Use default location
-            if let Some(proj_dirs) = directories::ProjectDirs::from("com", "claude", "mcp") {
-                proj_dirs.data_dir().join("models")
-            } else {
-                PathBuf::from("models")
-            }
-        };
+        let model_dir = model_dir();
         
         // Create model directory if it doesn't exist
         if !model_dir.exists() {
@@ -79,7 +87,7 @@ impl LocalProvider {
         };
         
         // Try to initialize inference engine
-        let inference_engine = match InferenceEngine::new(&model_dir) {
+        let inference_engine = match InferenceEngine::new(&model_dir, get_thread_settings()) {
             Ok(engine) => Some(engine),
             Err(e) => {
                 warn!("Failed to initialize inference engine: {:?}", e);
@@ -87,58 +95,22 @@ impl LocalProvider {
             }
         };
         
-        // Discover available local models
-        let default_models = vec![
-            LocalModelInfo {
-                id: "tinyllama".to_string(),
-                name: "TinyLlama".to_string(),
-                path: model_dir.join("tinyllama.bin"),
-                parameters: 1_000_000_000,
-                quantization: "q4_0".to_string(),
-                context_size: 2048,
-                is_downloaded: false,
-                download_url: Some("https://huggingface.co/TinyLlama/TinyLlama-1.1B-Chat-v1.0/resolve/main/ggml-model-q4_0.gguf".to_string()),
-                model: Model {
-                    id: "tinyllama".to_string(),
-                    provider: "local".to_string(),
-                    name: "TinyLlama 1.1B".to_string(),
-                    version: "1.1".to_string(),
-                    capabilities: crate::models::ModelCapabilities {
-                        vision: false,
-                        max_context_length: 2048,
-                        functions: false,
-                        streaming: true,
-                    },
-                },
-            },
-            LocalModelInfo {
-                id: "redpajama-mini".to_string(),
-                name: "RedPajama Mini".to_string(),
-                path: model_dir.join("redpajama-mini.bin"),
-                parameters: 1_400_000_000,
-                quantization: "q4_0".to_string(),
-                context_size: 2048,
-                is_downloaded: false,
-                download_url: Some("https://huggingface.co/weyaxi/redpajama.cpp/resolve/main/redpajama-mini-q4_0.bin".to_string()),
-                model: Model {
-                    id: "redpajama-mini".to_string(),
-                    provider: "local".to_string(),
-                    name: "RedPajama Mini".to_string(),
-                    version: "1.0".to_string(),
-                    capabilities: crate::models::ModelCapabilities {
-                        vision: false,
-                        max_context_length: 2048,
-                        functions: false,
-                        streaming: true,
-                    },
-                },
-            },
-        ];
+        // Discover available local models, then those of user-added catalogs
+        let mut known_models = models::default_catalog(&model_dir);
+        for entry in get_model_registry().entries() {
+            if known_models.iter().any(|m| m.id == entry.entry.id) {
+                continue;
+            }
+            match models::from_registry(&model_dir, &entry) {
+                Some(model) => known_models.push(model),
+                None => debug!("Skipping catalog model {} of unknown kind {}", entry.entry.id, entry.entry.kind),
+            }
+        }
         
         let provider = Self {
             config: provider_config,
             inference_engine: Arc::new(Mutex::new(inference_engine)),
-            models: Arc::new(RwLock::new(default_models)),
+            models: Arc::new(RwLock::new(known_models)),
             model_status: Arc::new(RwLock::new(HashMap::new())),
             active_streams: Arc::new(Mutex::new(HashMap::new())),
             model_dir,
@@ -150,17 +122,18 @@ impl LocalProvider {
         Ok(provider)
     }
     
-    /// Update the download status of all models
-    fn update_model_download_status(&self) {
-        let mut models = self.models.write().unwrap();
-        for model in models.iter_mut() {
-            model.is_downloaded = model.path.exists();
+    /// Re-apply the current thread settings to the inference engine
+    pub fn apply_thread_settings(&self) {
+        if let Some(engine) = self.inference_engine.lock().unwrap().as_ref() {
+            engine.set_thread_settings(get_thread_settings());
         }
     }
     
-    /// Download a model
-    pub async fn download_model(&self, model_id: &str) -> Result<(), ModelError> {
-        // Find model info
+    /// Benchmark all available GPU backends with a model and store the fastest
+    pub async fn gpu_benchmark(&self, model_id: &str) -> Result<Vec<BackendBenchmark>, ModelError> {
+        // Make sure the model is downloaded and the engine exists
+        self.load_model(model_id, &[]).await?;
+        
         let model_info = {
             let models = self.models.read().unwrap();
             models
@@ -170,30 +143,358 @@ impl LocalProvider {
                 .ok_or(ModelError::InvalidRequest)?
         };
         
+        let engine_guard = self.inference_engine.lock().unwrap();
+        let engine = engine_guard.as_ref().ok_or(ModelError::SystemError)?;
+        
+        acceleration::run_gpu_benchmark(engine, &model_info, 64).map_err(|e| {
+            error!("GPU benchmark failed: {}", e);
+            ModelError::SystemError
+        })
+    }
+    
+    /// All models of a kind
+    pub fn models_of_kind(&self, kind: ModelKind) -> Vec<LocalModelInfo> {
+        self.models
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|m| m.kind == kind)
+            .cloned()
+            .collect()
+    }
+    
+    /// All known models
+    pub fn all_models(&self) -> Vec<LocalModelInfo> {
+        self.models.read().unwrap().clone()
+    }
+    
+    /// Resolve the model a subsystem should use for a kind.
+    ///
+    /// Prefers the configured default (`ai.local.default_models.<kind>`), then any
+    /// downloaded model of that kind, then the first catalog entry.
+    pub fn resolve_model(&self, kind: ModelKind) -> Option<LocalModelInfo> {
+        let candidates = self.models_of_kind(kind);
+        let configured = config::get_string(&format!("ai.local.default_models.{}", kind.as_str()));
+        
+        configured
+            .and_then(|id| candidates.iter().find(|m| m.id == id).cloned())
+            .map(|m| self.preferred_variant_of(&m))
+            .or_else(|| candidates.iter().find(|m| m.is_downloaded).cloned())
+            .or_else(|| candidates.into_iter().next())
+    }
+    
+    /// The variant of a model's family the user prefers, if it's downloaded;
+    /// otherwise the model itself
+    fn preferred_variant_of(&self, model: &LocalModelInfo) -> LocalModelInfo {
+        get_model_registry()
+            .preferred_variant(&model.family())
+            .and_then(|id| self.all_models().into_iter().find(|m| m.id == id && m.is_downloaded))
+            .unwrap_or_else(|| model.clone())
+    }
+    
+    /// Models grouped into families of quantizations, with disk usage
+    pub fn families(&self) -> Vec<ModelFamily> {
+        let models = self.all_models();
+        let variants = models.iter().map(|m| {
+            let size_on_disk = if m.is_downloaded {
+                std::fs::metadata(&m.path).map(|meta| meta.len()).unwrap_or(0)
+            } else {
+                0
+            };
+            let variant = FamilyVariant {
+                id: m.id.clone(),
+                quantization: m.quantization.clone(),
+                downloaded: m.is_downloaded,
+                size_on_disk,
+            };
+            (m.family(), m.model.name.clone(), variant)
+        });
+        group_families(&get_model_registry(), variants)
+    }
+    
+    /// One family of quantizations
+    pub fn family(&self, family: &str) -> Result<ModelFamily, ModelError> {
+        self.families()
+            .into_iter()
+            .find(|f| f.key == family)
+            .ok_or(ModelError::InvalidRequest)
+    }
+    
+    /// Choose which quantization of a family is used
+    pub fn set_preferred_variant(&self, family: &str, model_id: &str) -> Result<(), String> {
+        let family = self.family(family).map_err(|_| format!("No model family {}", family))?;
+        if !family.variants.iter().any(|v| v.id == model_id) {
+            return Err(format!("{} is not a variant of {}", model_id, family.key));
+        }
+        get_model_registry()
+            .set_preferred_variant(&family.key, Some(model_id))
+            .map_err(|e| e.to_string())
+    }
+    
+    /// Delete every downloaded variant of a family; returns the bytes freed
+    pub fn delete_family(&self, family: &str) -> Result<u64, ModelError> {
+        let family = self.family(family)?;
+        for id in family.downloaded() {
+            self.delete_model(id)?;
+        }
+        if let Err(e) = get_model_registry().set_preferred_variant(&family.key, None) {
+            warn!("Failed to forget preferred variant of {}: {}", family.key, e);
+        }
+        Ok(family.disk_usage)
+    }
+    
+    /// Set the default model for a kind
+    pub fn set_default_model(&self, kind: ModelKind, model_id: &str) -> Result<(), String> {
+        if !self.models_of_kind(kind).iter().any(|m| m.id == model_id) {
+            return Err(format!("No {} model with ID {}", kind, model_id));
+        }
+        config::set_value(
+            &format!("ai.local.default_models.{}", kind.as_str()),
+            serde_json::Value::String(model_id.to_string()),
+        )?;
+        config::save_config().map_err(|e| e.to_string())
+    }
+    
+    /// Remove a downloaded model from disk
+    pub fn delete_model(&self, model_id: &str) -> Result<(), ModelError> {
+        let model_info = {
+            let models = self.models.read().unwrap();
+            models
+                .iter()
+                .find(|m| m.id == model_id)
+                .cloned()
+                .ok_or(ModelError::InvalidRequest)?
+        };
+        
+        if model_info.path.exists() {
+            std::fs::remove_file(&model_info.path).map_err(|e| {
+                error!("Failed to delete model {}: {}", model_id, e);
+                ModelError::SystemError
+            })?;
+            info!("Deleted {} model {}", model_info.kind, model_id);
+        }
+        if let Err(e) = updates::forget(&self.model_dir, &model_info) {
+            warn!("Failed to delete previous version of {}: {}", model_id, e);
+        }
+        
+        if let Some(engine) = self.inference_engine.lock().unwrap().as_ref() {
+            engine.unload_model(model_id);
+        }
+        get_pressure_monitor().unregister_model(model_id);
+        
+        self.update_model_download_status();
+        self.model_status.write().unwrap().remove(model_id);
+        Ok(())
+    }
+    
+    /// Update the download status of all models
+    fn update_model_download_status(&self) {
+        let mut models = self.models.write().unwrap();
+        for model in models.iter_mut() {
+            model.is_downloaded = model.path.exists();
+        }
+    }
+    
+    /// Download a model
+    pub async fn download_model(&self, model_id: &str) -> Result<(), ModelError> {
+        self.download_model_with_progress(model_id, None).await
+    }
+    
+    /// Download a model as a background job, reporting progress and
+    /// stopping when the job is canceled. A partial download left by an
+    /// earlier attempt is continued rather than started over.
+    pub async fn download_model_with_progress(
+        &self,
+        model_id: &str,
+        job: Option<&JobHandle>,
+    ) -> Result<(), ModelError> {
+        // Find model info
+        let model_info = self.model_info(model_id)?;
+        
         // Check if already downloaded
         if model_info.is_downloaded {
             return Ok(());
         }
         
-        // Check if download URL is available
-        let download_url = model_info
-            .download_url
-            .clone()
-            .ok_or(ModelError::InvalidRequest)?;
-        
         // Update model status
         {
             let mut statuses = self.model_status.write().unwrap();
             statuses.insert(model_id.to_string(), ModelStatus::Loading);
         }
         
+        let temp_path = match self.fetch_model_file(&model_info, job).await {
+            Ok(path) => path,
+            Err(e) => {
+                self.model_status.write().unwrap().remove(model_id);
+                return Err(e);
+            }
+        };
+        
+        // Rename temp file to final file
+        tokio::fs::rename(&temp_path, &model_info.path)
+            .await
+            .map_err(|_| ModelError::SystemError)?;
+        if let Err(e) = updates::record_install(&self.model_dir, &model_info) {
+            warn!("Failed to record installed version of {}: {}", model_id, e);
+        }
+        
+        // Update model download status
+        self.update_model_download_status();
+        
+        // Update model status
+        {
+            let mut statuses = self.model_status.write().unwrap();
+            statuses.insert(model_id.to_string(), ModelStatus::Available);
+        }
+        
+        // Let the memory pressure monitor unload the model when it sits idle
+        let engine = self.inference_engine.clone();
+        let id = model_id.to_string();
+        get_pressure_monitor().register_model(model_id, move || {
+            // A model that is generating is not idle
+            let guard = engine.try_lock().map_err(|_| format!("Model {} is in use", id))?;
+            if let Some(engine) = guard.as_ref() {
+                engine.unload_model(&id);
+            }
+            Ok(())
+        });
+        
+        Ok(())
+    }
+    
+    /// Download the catalog's current version of a model over the installed
+    /// one, keeping the installed version on disk for rollback. Models that
+    /// aren't downloaded yet are simply downloaded.
+    pub async fn update_model_with_progress(
+        &self,
+        model_id: &str,
+        job: Option<&JobHandle>,
+    ) -> Result<(), ModelError> {
+        let model_info = self.model_info(model_id)?;
+        if !model_info.is_downloaded {
+            return self.download_model_with_progress(model_id, job).await;
+        }
+        
+        // The installed version stays usable until the new one is verified
+        let temp_path = self.fetch_model_file(&model_info, job).await?;
+        let model_dir = self.model_dir.clone();
+        let info = model_info.clone();
+        tokio::task::spawn_blocking(move || updates::install_update(&model_dir, &info, &temp_path))
+            .await
+            .map_err(|_| ModelError::SystemError)?
+            .map_err(|e| {
+                error!("Failed to install update of {}: {}", model_id, e);
+                ModelError::SystemError
+            })?;
+        info!("Updated {} to version {}", model_id, model_info.model.version);
+        
+        self.update_model_download_status();
+        Ok(())
+    }
+    
+    /// Switch a model between its installed and previous version
+    pub fn switch_model_version(&self, model_id: &str, version: &str) -> Result<(), String> {
+        let model_info = self.model_info(model_id).map_err(|_| format!("No model with ID {}", model_id))?;
+        updates::switch_version(&self.model_dir, &model_info, version)?;
+        info!("Switched {} to version {}", model_id, version);
+        Ok(())
+    }
+    
+    /// Available updates, rollbacks and disk usage for the updates panel
+    pub fn model_updates(&self) -> updates::UpdatesPanel {
+        updates::panel(&self.model_dir, &self.all_models())
+    }
+    
+    /// LoRA adapters, optionally only those fitting a model
+    pub fn adapters(&self, model_id: Option<&str>) -> Result<Vec<adapters::AdapterInfo>, ModelError> {
+        let model = model_id.map(|id| self.model_info(id)).transpose()?;
+        Ok(adapters::list(&self.model_dir, model.as_ref()))
+    }
+    
+    /// Copy a LoRA adapter file into the model directory for a base model
+    /// (a model ID or family)
+    pub fn import_adapter(
+        &self,
+        path: &std::path::Path,
+        name: Option<&str>,
+        base_model: &str,
+        scale: Option<f32>,
+    ) -> Result<AdapterEntry, String> {
+        self.check_base_model(base_model)?;
+        adapters::import(&self.model_dir, path, name, base_model, scale)
+    }
+    
+    /// Download a LoRA adapter for a base model as a background job
+    pub fn download_adapter(
+        &self,
+        url: &str,
+        name: &str,
+        base_model: &str,
+        sha256: Option<String>,
+        scale: Option<f32>,
+    ) -> Result<Job, String> {
+        self.check_base_model(base_model)?;
+        adapters::spawn_download(&self.model_dir, url, name, base_model, sha256, scale)
+    }
+    
+    /// Delete a LoRA adapter's file and forget it
+    pub fn delete_adapter(&self, adapter_id: &str) -> Result<(), String> {
+        adapters::delete(&self.model_dir, adapter_id)
+    }
+    
+    fn check_base_model(&self, base_model: &str) -> Result<(), String> {
+        let known = self
+            .models_of_kind(ModelKind::Chat)
+            .iter()
+            .any(|m| m.id == base_model || m.family() == base_model);
+        if known {
+            Ok(())
+        } else {
+            Err(format!("No chat model or family {}", base_model))
+        }
+    }
+    
+    /// Downloaded models with a newer version in their catalog
+    pub fn check_model_updates(&self) -> Vec<updates::ModelUpdate> {
+        updates::check_updates(&self.model_dir, &self.all_models())
+    }
+    
+    fn model_info(&self, model_id: &str) -> Result<LocalModelInfo, ModelError> {
+        let models = self.models.read().unwrap();
+        models
+            .iter()
+            .find(|m| m.id == model_id)
+            .cloned()
+            .ok_or(ModelError::InvalidRequest)
+    }
+    
+    /// Download a model's file next to it and verify its checksum; returns
+    /// the path of the downloaded file
+    async fn fetch_model_file(
+        &self,
+        model_info: &LocalModelInfo,
+        job: Option<&JobHandle>,
+    ) -> Result<PathBuf, ModelError> {
+        // Check if download URL is available
+        let download_url = model_info
+            .download_url
+            .clone()
+            .ok_or(ModelError::InvalidRequest)?;
+        
         // Create temporary file
         let temp_path = model_info.path.with_extension("download");
+        let existing = tokio::fs::metadata(&temp_path).await.map(|m| m.len()).unwrap_or(0);
         
-        // Download model
+        // Download model, continuing a partial download if there is one
         let client = reqwest::Client::new();
-        let response = client
-            .get(&download_url)
+        let mut request = client.get(&download_url);
+        if let Some(token) = get_model_registry().download_token(model_info.catalog.as_deref(), &download_url) {
+            request = request.bearer_auth(token);
+        }
+        if existing > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+        }
+        let response = request
             .send()
             .await
             .map_err(|_| ModelError::NetworkError)?;
@@ -202,6 +503,14 @@ impl LocalProvider {
             return Err(ModelError::NetworkError);
         }
         
+        // Servers that ignore the range send the whole file again
+        let resumed = existing > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+        let mut done = if resumed { existing } else { 0 };
+        let total = response.content_length().map(|len| len + done);
+        if resumed {
+            info!("Resuming download of {} at {} bytes", model_info.id, existing);
+        }
+        
         // Create parent directory if it doesn't exist
         if let Some(parent) = model_info.path.parent() {
             if !parent.exists() {
@@ -213,7 +522,12 @@ impl LocalProvider {
         }
         
         // Save to file
-        let mut file = tokio::fs::File::create(&temp_path)
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&temp_path)
             .await
             .map_err(|_| ModelError::SystemError)?;
         
@@ -223,27 +537,45 @@ impl LocalProvider {
             tokio::io::AsyncWriteExt::write_all(&mut file, &chunk)
                 .await
                 .map_err(|_| ModelError::SystemError)?;
+            
+            done += chunk.len() as u64;
+            if let Some(job) = job {
+                job.progress(done, total);
+                if job.is_cancelled() {
+                    // The partial file is kept so a new download can continue it
+                    return Err(ModelError::Cancelled);
+                }
+            }
         }
         
-        // Rename temp file to final file
-        tokio::fs::rename(&temp_path, &model_info.path)
+        tokio::io::AsyncWriteExt::flush(&mut file)
             .await
             .map_err(|_| ModelError::SystemError)?;
+        drop(file);
         
-        // Update model download status
-        self.update_model_download_status();
-        
-        // Update model status
-        {
-            let mut statuses = self.model_status.write().unwrap();
-            statuses.insert(model_id.to_string(), ModelStatus::Available);
+        // Catalogs can pin the file's checksum
+        if let Some(expected) = &model_info.sha256 {
+            if let Some(job) = job {
+                job.message("Verifying checksum");
+            }
+            let path = temp_path.clone();
+            let actual = tokio::task::spawn_blocking(move || file_sha256(&path))
+                .await
+                .map_err(|_| ModelError::SystemError)?
+                .map_err(|_| ModelError::SystemError)?;
+            if !actual.eq_ignore_ascii_case(expected) {
+                error!("Checksum mismatch for {}: expected {}, got {}", model_info.id, expected, actual);
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                return Err(ModelError::ChecksumMismatch);
+            }
         }
         
-        Ok(())
+        Ok(temp_path)
     }
     
-    /// Load a model into the inference engine
-    async fn load_model(&self, model_id: &str) -> Result<(), ModelError> {
+    /// Load a model into the inference engine with the LoRA adapters a
+    /// message selects
+    async fn load_model(&self, model_id: &str, selection: &[AdapterSelection]) -> Result<(), ModelError> {
         // Find model info
         let model_info = {
             let models = self.models.read().unwrap();
@@ -254,6 +586,11 @@ impl LocalProvider {
                 .ok_or(ModelError::InvalidRequest)?
         };
         
+        // Embedding, reranker and speech models are run by their own subsystems
+        if !matches!(model_info.kind, ModelKind::Chat | ModelKind::Translation) {
+            return Err(ModelError::InvalidRequest);
+        }
+        
         // Check if model is downloaded
         if !model_info.is_downloaded {
             // Try to download the model
@@ -263,7 +600,7 @@ impl LocalProvider {
         // Check if inference engine is initialized
         let mut engine_guard = self.inference_engine.lock().unwrap();
         if engine_guard.is_none() {
-            *engine_guard = match InferenceEngine::new(&self.model_dir) {
+            *engine_guard = match InferenceEngine::new(&self.model_dir, get_thread_settings()) {
                 Ok(engine) => Some(engine),
                 Err(e) => {
                     error!("Failed to initialize inference engine: {:?}", e);
@@ -274,10 +611,27 @@ impl LocalProvider {
         
         // Load model into inference engine
         if let Some(engine) = engine_guard.as_mut() {
+            engine.set_acceleration(acceleration::resolve_acceleration(
+                &GpuConfig::load(),
+                &AccelerationInfo::detect(),
+                model_id,
+            ));
+            
+            // Loading a model replaces the one loaded before
+            let previous = engine.loaded_model();
             if let Err(e) = engine.load_model(&model_info) {
                 error!("Failed to load model {}: {:?}", model_id, e);
                 return Err(ModelError::SystemError);
             }
+            if let Some(previous) = previous.filter(|id| id != model_id) {
+                get_pressure_monitor().unregister_model(&previous);
+            }
+            
+            let applied = adapters::resolve(&self.model_dir, &model_info, selection);
+            if let Err(e) = engine.apply_adapters(applied) {
+                error!("Failed to apply adapters to {}: {:?}", model_id, e);
+                return Err(ModelError::SystemError);
+            }
         } else {
             return Err(ModelError::SystemError);
         }
@@ -294,32 +648,32 @@ impl LocalProvider {
     /// Process a message with the local model
     async fn process_message(&self, model_id: &str, message: &Message) -> Result<String, ModelError> {
         // Load model if needed
-        self.load_model(model_id).await?;
+        self.load_model(model_id, &adapters::from_message(message)).await?;
         
-        // Extract text from message
-        let mut prompt = String::new();
-        
-        // Process message content
-        for part in &message.content.parts {
-            match part {
-                ContentType::Text { text } => {
-                    prompt.push_str(text);
-                }
-                _ => {
-                    // Local models only support text input for now
-                    warn!("Local model only supports text input");
-                }
-            }
+        // Lay out the conversation with the model's chat template
+        if message.content.parts.iter().any(|part| !matches!(part, ContentType::Text { .. })) {
+            // Local models only support text input for now
+            warn!("Local model only supports text input");
         }
+        let prompt = prompt::render(&self.model_info(model_id)?, message).prompt;
+        let options = GenerationOptions::from_message(message);
         
         // Generate response using inference engine
         let engine_guard = self.inference_engine.lock().unwrap();
         if let Some(engine) = engine_guard.as_ref() {
-            match engine.generate(&prompt) {
-                Ok(response) => Ok(response),
+            let result = engine.generate_with_options(&prompt, &options);
+            get_pressure_monitor().touch_model(model_id);
+            match result {
+                Ok(response) => {
+                    check_output(&options, &response)?;
+                    Ok(response)
+                }
                 Err(e) => {
                     error!("Inference error: {:?}", e);
-                    Err(ModelError::SystemError)
+                    Err(match e {
+                        InferenceError::InvalidInput(_) => ModelError::InvalidRequest,
+                        _ => ModelError::SystemError,
+                    })
                 }
             }
         } else {
@@ -327,6 +681,27 @@ impl LocalProvider {
         }
     }
     
+    /// Translate text with a translation model. `source` and `target` are
+    /// the language tags the model was trained with, e.g. `eng_Latn` for
+    /// NLLB or `__en__` for M2M-100.
+    pub async fn translate(&self, model_id: &str, text: &str, source: &str, target: &str) -> Result<String, ModelError> {
+        if self.model_info(model_id)?.kind != ModelKind::Translation {
+            return Err(ModelError::InvalidRequest);
+        }
+        self.load_model(model_id, &[]).await?;
+        
+        // The source tag starts the input and the target tag the output
+        let prompt = format!("{} {}</s> {}", source, text, target);
+        let engine_guard = self.inference_engine.lock().unwrap();
+        let engine = engine_guard.as_ref().ok_or(ModelError::SystemError)?;
+        let result = engine.generate_with_options(&prompt, &GenerationOptions::default());
+        get_pressure_monitor().touch_model(model_id);
+        result.map(|text| text.trim().to_string()).map_err(|e| {
+            error!("Translation error: {:?}", e);
+            ModelError::SystemError
+        })
+    }
+    
     /// Process a streaming message with the local model
     async fn process_streaming(
         &self,
@@ -335,23 +710,15 @@ impl LocalProvider {
         tx: mpsc::Sender<Result<Message, MessageError>>,
     ) -> Result<(), ModelError> {
         // Load model if needed
-        self.load_model(model_id).await?;
+        self.load_model(model_id, &adapters::from_message(message)).await?;
         
-        // Extract text from message
-        let mut prompt = String::new();
-        
-        // Process message content
-        for part in &message.content.parts {
-            match part {
-                ContentType::Text { text } => {
-                    prompt.push_str(text);
-                }
-                _ => {
-                    // Local models only support text input for now
-                    warn!("Local model only supports text input");
-                }
-            }
+        // Lay out the conversation with the model's chat template
+        if message.content.parts.iter().any(|part| !matches!(part, ContentType::Text { .. })) {
+            // Local models only support text input for now
+            warn!("Local model only supports text input");
         }
+        let prompt = prompt::render(&self.model_info(model_id)?, message).prompt;
+        let options = GenerationOptions::from_message(message);
         
         // Generate streaming response using inference engine
         let engine_guard = self.inference_engine.lock().unwrap();
@@ -359,7 +726,7 @@ impl LocalProvider {
             let mut accumulated_text = String::new();
             let response_id = Uuid::new_v4().to_string();
             
-            match engine.generate_streaming(&prompt, 512, |token| {
+            let result = engine.generate_streaming(&prompt, &options, |token| {
                 // Accumulate text
                 accumulated_text.push_str(token);
                 
@@ -384,8 +751,12 @@ impl LocalProvider {
                 
                 // Continue generating
                 true
-            }) {
+            });
+            get_pressure_monitor().touch_model(model_id);
+            match result {
                 Ok(_) => {
+                    check_output(&options, &accumulated_text)?;
+                    
                     // Send final message with complete text
                     let final_message = Message {
                         id: response_id,
@@ -407,7 +778,10 @@ impl LocalProvider {
                 }
                 Err(e) => {
                     error!("Inference error: {:?}", e);
-                    Err(ModelError::SystemError)
+                    Err(match e {
+                        InferenceError::InvalidInput(_) => ModelError::InvalidRequest,
+                        _ => ModelError::SystemError,
+                    })
                 }
             }
         } else {
@@ -416,6 +790,16 @@ impl LocalProvider {
     }
 }
 
+/// Check a reply against the output format it was constrained to. The
+/// grammar can't enforce everything a JSON schema says, and streams can be
+/// cut short, so the final text is validated too.
+fn check_output(options: &GenerationOptions, response: &str) -> Result<(), ModelError> {
+    options.output.check(response).map(|_| ()).map_err(|e| {
+        error!("Constrained output rejected: {}", e);
+        ModelError::InvalidRequest
+    })
+}
+
 #[async_trait]
 impl ModelProvider for LocalProvider {
     fn provider_type(&self) -> ProviderType {
@@ -431,9 +815,13 @@ impl ModelProvider for LocalProvider {
     }
     
     async fn available_models(&self) -> Result<Vec<Model>, ModelError> {
-        // Return cached model list
+        // Only chat models can hold a conversation
         let models = self.models.read().unwrap();
-        let result = models.iter().map(|m| m.model.clone()).collect();
+        let result = models
+            .iter()
+            .filter(|m| m.kind == ModelKind::Chat)
+            .map(|m| m.model.clone())
+            .collect();
         Ok(result)
     }
     
@@ -652,3 +1040,177 @@ impl Clone for LocalProvider {
         }
     }
 }
+
+/// Local provider shared by chat, commands and background tasks
+static LOCAL_PROVIDER: once_cell::sync::OnceCell<LocalProvider> = once_cell::sync::OnceCell::new();
+
+/// Get the shared local provider, so every caller sees the same loaded
+/// model and inference engine
+pub fn get_local_provider() -> Result<LocalProvider, ModelError> {
+    LOCAL_PROVIDER.get_or_try_init(LocalProvider::new).cloned()
+}
+
+/// Local models the retrieval and speech subsystems use
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SubsystemModels {
+    /// Embeds documents and queries for retrieval
+    pub embedding: Option<LocalModelInfo>,
+
+    /// Reranks retrieved passages
+    pub reranker: Option<LocalModelInfo>,
+
+    /// Transcribes dictation and meeting audio
+    pub speech_to_text: Option<LocalModelInfo>,
+
+    /// Reads replies aloud
+    pub text_to_speech: Option<LocalModelInfo>,
+}
+
+/// Resolve each subsystem's model through the registry, so they follow the
+/// configured defaults and preferred variants like chat models do
+pub fn subsystem_models() -> SubsystemModels {
+    let Ok(provider) = get_local_provider() else {
+        return SubsystemModels::default();
+    };
+    SubsystemModels {
+        embedding: provider.resolve_model(ModelKind::Embedding),
+        reranker: provider.resolve_model(ModelKind::Reranker),
+        speech_to_text: provider.resolve_model(ModelKind::Stt),
+        text_to_speech: provider.resolve_model(ModelKind::Tts),
+    }
+}
+
+/// Directory local models are downloaded to: `ai.local.model_dir`, or the
+/// app's data directory
+pub fn model_dir() -> PathBuf {
+    if let Some(dir) = config::get_string("ai.local.model_dir") {
+        PathBuf::from(dir)
+    } else {
+        app_dir(AppDir::Data).join("models")
+    }
+}
+
+/// Download a local model as a background job that survives restarts
+pub fn spawn_model_download(model_id: &str, request_id: Option<&str>) -> Result<Job, ModelError> {
+    spawn_download_job(model_id, false, request_id)
+}
+
+/// Download the latest version of a local model as a background job,
+/// keeping the installed version for rollback
+pub fn spawn_model_update(model_id: &str) -> Result<Job, ModelError> {
+    spawn_download_job(model_id, true, None)
+}
+
+fn spawn_download_job(model_id: &str, update: bool, request_id: Option<&str>) -> Result<Job, ModelError> {
+    let provider = get_local_provider()?;
+    let model = provider
+        .all_models()
+        .into_iter()
+        .find(|m| m.id == model_id)
+        .ok_or(ModelError::InvalidRequest)?;
+    let title = if update {
+        format!("{} {}", model.name, model.model.version)
+    } else {
+        model.name
+    };
+    
+    let model_id = model_id.to_string();
+    let request = request_id.map(|id| (id.to_string(), get_cancellation_registry().register(id, None)));
+    Ok(get_job_manager().spawn(JobKind::ModelDownload, &title, true, move |job| async move {
+        job.save_checkpoint(serde_json::json!({ "model_id": model_id, "update": update }));
+        let Some((request_id, ctx)) = request else {
+            return run_model_download(provider, &model_id, update, &job).await;
+        };
+        
+        // Canceling the request cancels the job, so it ends as cancelled rather than failed
+        let result = tokio::select! {
+            result = run_model_download(provider, &model_id, update, &job) => result,
+            _ = ctx.token.cancelled() => {
+                let _ = get_job_manager().cancel(&job.id());
+                Err(McpError::Cancelled)
+            }
+        };
+        get_cancellation_registry().complete(&request_id);
+        result
+    }))
+}
+
+/// Continue model downloads interrupted by a shutdown from their partial files
+pub fn register_download_resumer() {
+    get_job_manager().register_resumer(JobKind::ModelDownload, |job| {
+        Box::pin(async move {
+            let checkpoint = job
+                .checkpoint()
+                .ok_or_else(|| McpError::InvalidRequest("Download job has no model".to_string()))?;
+            let model_id = checkpoint
+                .get("model_id")
+                .and_then(|id| id.as_str())
+                .map(String::from)
+                .ok_or_else(|| McpError::InvalidRequest("Download job has no model".to_string()))?;
+            let update = checkpoint.get("update").and_then(|u| u.as_bool()).unwrap_or(false);
+            let provider = get_local_provider().map_err(|e| McpError::Unknown(format!("{:?}", e)))?;
+            run_model_download(provider, &model_id, update, &job).await
+        })
+    });
+}
+
+async fn run_model_download(provider: LocalProvider, model_id: &str, update: bool, job: &JobHandle) -> McpResult<()> {
+    let result = if update {
+        provider.update_model_with_progress(model_id, Some(job)).await
+    } else {
+        provider.download_model_with_progress(model_id, Some(job)).await
+    };
+    match result {
+        Ok(()) => Ok(()),
+        Err(ModelError::Cancelled) => Err(McpError::Cancelled),
+        Err(e) => Err(McpError::Unknown(format!("Failed to download model: {:?}", e))),
+    }
+}
+
+/// Periodically look for newer versions of downloaded models, tell the user
+/// about each one once, and download them when automatic updates are on and
+/// they fit in the disk quota
+pub fn spawn_model_update_checker(interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let provider = match get_local_provider() {
+                Ok(provider) => provider,
+                Err(e) => {
+                    warn!("Model update check skipped: {:?}", e);
+                    continue;
+                }
+            };
+            let found = updates::take_unannounced(&provider.check_model_updates());
+            if found.is_empty() {
+                continue;
+            }
+            
+            let auto_update = updates::UpdateSettings::load().auto_update;
+            for update in &found {
+                if auto_update && update.fits_quota {
+                    match spawn_model_update(&update.model_id) {
+                        Ok(_) => info!("Updating {} to {}", update.model_id, update.latest_version),
+                        Err(e) => warn!("Failed to start update of {}: {:?}", update.model_id, e),
+                    }
+                }
+            }
+            
+            let body = match found.as_slice() {
+                [update] => format!(
+                    "{} {} is available (installed: {})",
+                    update.name, update.latest_version, update.installed_version
+                ),
+                _ => format!("{} local models have updates", found.len()),
+            };
+            let title = if auto_update { "Updating local models" } else { "Model updates available" };
+            notify(
+                Notification::new(NotificationLevel::Info, title, body).with_action(NotificationAction::Command {
+                    label: "Show updates".to_string(),
+                    command: "open_model_updates".to_string(),
+                }),
+            );
+        }
+    })
+}
@@ -1,33 +1,327 @@
-use crate::models::Model;
-use std::path::PathBuf;
+use crate::models::{Model, ModelCapabilities};
+use mcp_common::models::registry::{family_key, RegistryEntry};
+use mcp_common::models::ChatTemplate;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// What a local model is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    /// Text generation for conversations
+    Chat,
+    /// Text embeddings for retrieval
+    Embedding,
+    /// Relevance scoring of retrieved passages
+    Reranker,
+    /// Text to speech
+    Tts,
+    /// Speech to text
+    Stt,
+    /// Translation between languages (NLLB, M2M-100)
+    Translation,
+}
+
+impl ModelKind {
+    /// Stable identifier used in config keys
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelKind::Chat => "chat",
+            ModelKind::Embedding => "embedding",
+            ModelKind::Reranker => "reranker",
+            ModelKind::Tts => "tts",
+            ModelKind::Stt => "stt",
+            ModelKind::Translation => "translation",
+        }
+    }
+}
+
+impl Default for ModelKind {
+    fn default() -> Self {
+        ModelKind::Chat
+    }
+}
+
+impl fmt::Display for ModelKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
 
 /// Information about a local model
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocalModelInfo {
     /// Model ID
     pub id: String,
-    
+
     /// Model name
     pub name: String,
-    
+
+    /// What the model is used for
+    #[serde(default)]
+    pub kind: ModelKind,
+
     /// Path to model file
     pub path: PathBuf,
-    
+
     /// Number of parameters
     pub parameters: u64,
-    
+
     /// Quantization type
     pub quantization: String,
-    
+
     /// Context size (max tokens)
     pub context_size: usize,
-    
+
     /// Whether the model is downloaded
     pub is_downloaded: bool,
-    
+
     /// URL to download the model
     pub download_url: Option<String>,
-    
+
+    /// Catalog the model comes from; built-in models have none
+    #[serde(default)]
+    pub catalog: Option<String>,
+
+    /// Hex SHA-256 the downloaded file must match
+    #[serde(default)]
+    pub sha256: Option<String>,
+
+    /// License identifier, if the catalog gives one
+    #[serde(default)]
+    pub license: Option<String>,
+
+    /// Family of quantizations the model belongs to; by default derived
+    /// from its ID
+    #[serde(default)]
+    pub family: Option<String>,
+
+    /// GGUF architecture, if known
+    #[serde(default)]
+    pub architecture: Option<String>,
+
+    /// Chat template override, e.g. "chatml"; detected when unset
+    #[serde(default)]
+    pub chat_template: Option<String>,
+
     /// Model metadata
     pub model: Model,
 }
+
+impl LocalModelInfo {
+    /// Family of quantizations the model belongs to
+    pub fn family(&self) -> String {
+        self.family.clone().unwrap_or_else(|| family_key(&self.id))
+    }
+
+    /// Chat template the model's prompts are laid out with
+    pub fn chat_template(&self) -> ChatTemplate {
+        ChatTemplate::resolve(
+            self.chat_template.as_deref(),
+            None,
+            self.architecture.as_deref(),
+            &[&self.id, &self.name, &self.family()],
+        )
+    }
+}
+
+/// Catalog entry for a model that isn't used for chat
+fn auxiliary_model(
+    model_dir: &Path,
+    id: &str,
+    name: &str,
+    kind: ModelKind,
+    file: &str,
+    parameters: u64,
+    quantization: &str,
+    context_size: usize,
+    version: &str,
+    download_url: &str,
+) -> LocalModelInfo {
+    LocalModelInfo {
+        id: id.to_string(),
+        name: name.to_string(),
+        kind,
+        path: model_dir.join(kind.as_str()).join(file),
+        parameters,
+        quantization: quantization.to_string(),
+        context_size,
+        is_downloaded: false,
+        download_url: Some(download_url.to_string()),
+        catalog: None,
+        sha256: None,
+        license: None,
+        family: None,
+        architecture: None,
+        chat_template: None,
+        model: Model {
+            id: id.to_string(),
+            provider: "local".to_string(),
+            name: name.to_string(),
+            version: version.to_string(),
+            capabilities: ModelCapabilities {
+                vision: false,
+                max_context_length: context_size,
+                functions: false,
+                streaming: false,
+                json_mode: false,
+            },
+        },
+    }
+}
+
+/// Models the local provider knows how to download
+pub fn default_catalog(model_dir: &Path) -> Vec<LocalModelInfo> {
+    vec![
+        LocalModelInfo {
+            id: "tinyllama".to_string(),
+            name: "TinyLlama".to_string(),
+            kind: ModelKind::Chat,
+            path: model_dir.join("tinyllama.bin"),
+            parameters: 1_000_000_000,
+            quantization: "q4_0".to_string(),
+            context_size: 2048,
+            is_downloaded: false,
+            download_url: Some("https://huggingface.co/TinyLlama/TinyLlama-1.1B-Chat-v1.0/resolve/main/ggml-model-q4_0.gguf".to_string()),
+            catalog: None,
+            sha256: None,
+            license: None,
+            family: None,
+            architecture: Some("llama".to_string()),
+            chat_template: Some("zephyr".to_string()),
+            model: Model {
+                id: "tinyllama".to_string(),
+                provider: "local".to_string(),
+                name: "TinyLlama 1.1B".to_string(),
+                version: "1.1".to_string(),
+                capabilities: ModelCapabilities {
+                    vision: false,
+                    max_context_length: 2048,
+                    functions: false,
+                    streaming: true,
+                    json_mode: true,
+                },
+            },
+        },
+        LocalModelInfo {
+            id: "redpajama-mini".to_string(),
+            name: "RedPajama Mini".to_string(),
+            kind: ModelKind::Chat,
+            path: model_dir.join("redpajama-mini.bin"),
+            parameters: 1_400_000_000,
+            quantization: "q4_0".to_string(),
+            context_size: 2048,
+            is_downloaded: false,
+            download_url: Some("https://huggingface.co/weyaxi/redpajama.cpp/resolve/main/redpajama-mini-q4_0.bin".to_string()),
+            catalog: None,
+            sha256: None,
+            license: None,
+            family: None,
+            architecture: Some("gptneox".to_string()),
+            chat_template: None,
+            model: Model {
+                id: "redpajama-mini".to_string(),
+                provider: "local".to_string(),
+                name: "RedPajama Mini".to_string(),
+                version: "1.0".to_string(),
+                capabilities: ModelCapabilities {
+                    vision: false,
+                    max_context_length: 2048,
+                    functions: false,
+                    streaming: true,
+                    json_mode: true,
+                },
+            },
+        },
+        auxiliary_model(
+            model_dir,
+            "nomic-embed-text",
+            "Nomic Embed Text v1.5",
+            ModelKind::Embedding,
+            "nomic-embed-text-v1.5.Q8_0.gguf",
+            137_000_000,
+            "q8_0",
+            8192,
+            "1.5",
+            "https://huggingface.co/nomic-ai/nomic-embed-text-v1.5-GGUF/resolve/main/nomic-embed-text-v1.5.Q8_0.gguf",
+        ),
+        auxiliary_model(
+            model_dir,
+            "bge-reranker-v2-m3",
+            "BGE Reranker v2 M3",
+            ModelKind::Reranker,
+            "bge-reranker-v2-m3-Q8_0.gguf",
+            568_000_000,
+            "q8_0",
+            8192,
+            "1.0",
+            "https://huggingface.co/gpustack/bge-reranker-v2-m3-GGUF/resolve/main/bge-reranker-v2-m3-Q8_0.gguf",
+        ),
+        auxiliary_model(
+            model_dir,
+            "whisper-base",
+            "Whisper Base",
+            ModelKind::Stt,
+            "ggml-base.bin",
+            74_000_000,
+            "f16",
+            448,
+            "1.0",
+            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin",
+        ),
+        auxiliary_model(
+            model_dir,
+            "piper-en-us-lessac",
+            "Piper en_US Lessac",
+            ModelKind::Tts,
+            "en_US-lessac-medium.onnx",
+            63_000_000,
+            "f32",
+            0,
+            "1.0",
+            "https://huggingface.co/rhasspy/piper-voices/resolve/main/en/en_US/lessac/medium/en_US-lessac-medium.onnx",
+        ),
+    ]
+}
+
+/// Local model for an entry of a user-added catalog. Entries of a kind this
+/// version doesn't know are skipped.
+pub fn from_registry(model_dir: &Path, entry: &RegistryEntry) -> Option<LocalModelInfo> {
+    let e = &entry.entry;
+    let kind: ModelKind = serde_json::from_value(serde_json::Value::String(e.kind.clone())).ok()?;
+    let path = match kind {
+        ModelKind::Chat => model_dir.join(&e.file_name),
+        _ => model_dir.join(kind.as_str()).join(&e.file_name),
+    };
+    Some(LocalModelInfo {
+        id: e.id.clone(),
+        name: e.name.clone(),
+        kind,
+        path,
+        parameters: e.parameters,
+        quantization: e.quantization.clone(),
+        context_size: e.context_size,
+        is_downloaded: false,
+        download_url: Some(e.download_url.clone()),
+        catalog: Some(entry.catalog.clone()),
+        sha256: e.sha256.clone(),
+        license: e.license.clone(),
+        family: Some(e.family()),
+        architecture: e.architecture.clone(),
+        chat_template: e.chat_template.clone(),
+        model: Model {
+            id: e.id.clone(),
+            provider: "local".to_string(),
+            name: e.name.clone(),
+            version: e.version.clone(),
+            capabilities: ModelCapabilities {
+                vision: false,
+                max_context_length: e.context_size,
+                functions: false,
+                streaming: kind == ModelKind::Chat,
+                json_mode: kind == ModelKind::Chat,
+            },
+        },
+    })
+}
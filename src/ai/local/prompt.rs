@@ -0,0 +1,174 @@
+//! Prompts for local chat models: the conversation so far, laid out with the
+//! chat template of the model it is sent to. The last prompt sent to each
+//! model is kept so the final text can be inspected when a model misbehaves.
+
+use super::models::LocalModelInfo;
+use crate::models::messages::{ContentType, Message};
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use log::debug;
+use mcp_common::models::{ChatTemplate, ChatTurn, MessageRole};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Message metadata key carrying the earlier turns of the conversation
+pub const HISTORY_METADATA_KEY: &str = "chat_history";
+
+lazy_static! {
+    static ref LAST_PROMPTS: Mutex<HashMap<String, RenderedPrompt>> = Mutex::new(HashMap::new());
+}
+
+/// A prompt as sent to a local model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderedPrompt {
+    /// Model the prompt was sent to
+    pub model_id: String,
+
+    /// Template it was laid out with
+    pub template: ChatTemplate,
+
+    /// Final prompt text
+    pub prompt: String,
+
+    /// Sequences that end the reply
+    pub stop_sequences: Vec<String>,
+
+    /// When the prompt was rendered
+    pub rendered_at: DateTime<Utc>,
+}
+
+/// Text of a message; images and tool content are left out
+fn text_of(message: &Message) -> String {
+    message
+        .content
+        .parts
+        .iter()
+        .filter_map(|part| match part {
+            ContentType::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A message as a turn of the conversation
+pub fn turn_of(message: &Message) -> ChatTurn {
+    ChatTurn::new(MessageRole::from(message.role.clone()), text_of(message))
+}
+
+/// Turns of the earlier messages of a conversation, leaving out a message
+/// being sent and messages without text
+pub fn history_turns(history: &[Message], message: &Message) -> Vec<ChatTurn> {
+    history
+        .iter()
+        .filter(|m| m.id != message.id)
+        .map(turn_of)
+        .filter(|turn| !turn.text.is_empty())
+        .collect()
+}
+
+/// Attach the earlier messages of the conversation to a message for a local
+/// model, which gets one message at a time
+pub fn attach(history: &[Message], message: Message) -> Message {
+    let turns = history_turns(history, &message);
+    attach_turns(turns, message)
+}
+
+/// Attach earlier turns, already fitted to the model's context, to a message
+pub fn attach_turns(turns: Vec<ChatTurn>, mut message: Message) -> Message {
+    if !turns.is_empty() {
+        if let Ok(value) = serde_json::to_value(&turns) {
+            message
+                .metadata
+                .get_or_insert_with(Default::default)
+                .insert(HISTORY_METADATA_KEY.to_string(), value);
+        }
+    }
+    message
+}
+
+/// Turns of the conversation a message continues, ending with the message
+pub fn turns(message: &Message) -> Vec<ChatTurn> {
+    let mut turns: Vec<ChatTurn> = message
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get(HISTORY_METADATA_KEY))
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+        .unwrap_or_default();
+    turns.push(turn_of(message));
+    turns
+}
+
+/// Lay out a message and the conversation before it for a model, and keep
+/// the result as the model's last prompt
+pub fn render(model: &LocalModelInfo, message: &Message) -> RenderedPrompt {
+    let template = model.chat_template();
+    let rendered = RenderedPrompt {
+        model_id: model.id.clone(),
+        template,
+        prompt: template.render(&turns(message)),
+        stop_sequences: template.stop_sequences().iter().map(|s| s.to_string()).collect(),
+        rendered_at: Utc::now(),
+    };
+    debug!("Rendered {} prompt for {} ({} chars)", template, model.id, rendered.prompt.len());
+
+    LAST_PROMPTS.lock().unwrap().insert(model.id.clone(), rendered.clone());
+    rendered
+}
+
+/// Last prompt sent to a model since the app started
+pub fn last_prompt(model_id: &str) -> Option<RenderedPrompt> {
+    LAST_PROMPTS.lock().unwrap().get(model_id).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::local::models::default_catalog;
+    use crate::models::messages::MessageRole as Role;
+
+    fn model(chat_template: Option<&str>) -> LocalModelInfo {
+        let mut model = default_catalog(std::path::Path::new("/models"))
+            .into_iter()
+            .find(|m| m.id == "tinyllama")
+            .unwrap();
+        model.chat_template = chat_template.map(str::to_string);
+        model
+    }
+
+    fn message(role: Role, text: &str) -> Message {
+        let mut message = Message::new_user_text(text.to_string());
+        message.role = role;
+        message
+    }
+
+    #[test]
+    fn history_travels_with_the_message() {
+        let history = vec![
+            message(Role::System, "Be brief."),
+            message(Role::User, "Hi"),
+            message(Role::Assistant, "Hello!"),
+        ];
+        let next = attach(&history, message(Role::User, "How are you?"));
+
+        let turns = turns(&next);
+        assert_eq!(turns.len(), 4);
+        assert_eq!(turns[0], ChatTurn::new(MessageRole::System, "Be brief."));
+        assert_eq!(turns[3], ChatTurn::new(MessageRole::User, "How are you?"));
+    }
+
+    #[test]
+    fn prompts_use_the_model_template_and_are_kept() {
+        let next = attach(&[message(Role::System, "Be brief.")], message(Role::User, "Hi"));
+
+        let rendered = render(&model(None), &next);
+        assert_eq!(rendered.template, ChatTemplate::Zephyr);
+        assert_eq!(rendered.prompt, "<|system|>\nBe brief.</s>\n<|user|>\nHi</s>\n<|assistant|>\n");
+
+        // The catalog entry's template wins
+        let rendered = render(&model(Some("chatml")), &next);
+        assert!(rendered.prompt.ends_with("<|im_start|>assistant\n"));
+        assert_eq!(last_prompt("tinyllama"), Some(rendered));
+    }
+}
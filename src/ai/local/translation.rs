@@ -0,0 +1,44 @@
+//! Local translation: runs the registry's NLLB and M2M-100 models for the
+//! shared translation service, which can't load models itself.
+
+use super::get_local_provider;
+use async_trait::async_trait;
+use mcp_common::error::{McpError, McpResult};
+use mcp_common::models::registry::RegistryEntry;
+use mcp_common::service::translation::{nllb_code, set_local_engine, LocalTranslationEngine};
+use std::sync::Arc;
+
+/// Translation engine backed by the shared local provider
+struct LocalProviderEngine;
+
+/// Language tag a model expects: FLORES-200 codes for NLLB, `__xx__` for
+/// M2M-100
+fn language_tag(model: &RegistryEntry, language: &str) -> McpResult<String> {
+    let nllb = model.entry.family().to_lowercase().contains("nllb")
+        || model.entry.architecture.as_deref().is_some_and(|a| a.contains("nllb"));
+    if nllb {
+        nllb_code(language)
+            .map(str::to_string)
+            .ok_or_else(|| McpError::InvalidRequest(format!("{} can't translate '{}'", model.entry.name, language)))
+    } else {
+        Ok(format!("__{}__", language.split('-').next().unwrap_or(language)))
+    }
+}
+
+#[async_trait]
+impl LocalTranslationEngine for LocalProviderEngine {
+    async fn translate(&self, model: &RegistryEntry, text: &str, source: &str, target: &str) -> McpResult<String> {
+        let source = language_tag(model, source)?;
+        let target = language_tag(model, target)?;
+        let provider = get_local_provider().map_err(|e| McpError::Unknown(format!("{:?}", e)))?;
+        provider
+            .translate(&model.entry.id, text, &source, &target)
+            .await
+            .map_err(|e| McpError::Protocol(format!("Local translation with {} failed: {:?}", model.entry.id, e)))
+    }
+}
+
+/// Let the translation service run local models. Call once at startup.
+pub fn register() {
+    set_local_engine(Arc::new(LocalProviderEngine));
+}
@@ -0,0 +1,503 @@
+//! Model updates: which version of each local model is installed, which
+//! catalogs offer something newer, and the previous version kept on disk
+//! so an update can be rolled back.
+
+use super::models::LocalModelInfo;
+use crate::utils::config;
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use log::{error, warn};
+use mcp_common::models::registry::get_model_registry;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Installed versions, kept next to the models
+const INSTALLED_FILE: &str = "installed.json";
+
+/// Config key for the disk quota of the model directory, in GiB
+pub const DISK_QUOTA_KEY: &str = "ai.local.disk_quota_gb";
+
+/// Config key for downloading updates without asking
+pub const AUTO_UPDATE_KEY: &str = "ai.local.auto_update_models";
+
+lazy_static! {
+    /// Updates the user was already told about, as (model, version)
+    static ref NOTIFIED: Mutex<HashSet<(String, String)>> = Mutex::new(HashSet::new());
+
+    /// When updates were last checked
+    static ref CHECKED_AT: Mutex<Option<DateTime<Utc>>> = Mutex::new(None);
+}
+
+/// One installed version of a model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstalledVersion {
+    /// Catalog version
+    pub version: String,
+
+    /// Hex SHA-256 the catalog gave for the file
+    #[serde(default)]
+    pub sha256: Option<String>,
+
+    /// When the version was downloaded
+    pub installed_at: DateTime<Utc>,
+}
+
+impl InstalledVersion {
+    fn of(model: &LocalModelInfo) -> Self {
+        Self {
+            version: model.model.version.clone(),
+            sha256: model.sha256.clone(),
+            installed_at: Utc::now(),
+        }
+    }
+}
+
+/// Installed version of a model and the one it replaced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledModel {
+    /// Version in use
+    #[serde(flatten)]
+    pub current: InstalledVersion,
+
+    /// Version kept for rollback
+    #[serde(default)]
+    pub previous: Option<InstalledVersion>,
+}
+
+/// A newer version of a downloaded model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelUpdate {
+    /// Model ID
+    pub model_id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// Version on disk
+    pub installed_version: String,
+
+    /// Version the catalog offers
+    pub latest_version: String,
+
+    /// What changed, if the catalog says
+    pub changelog: Option<String>,
+
+    /// Download size in bytes, if known
+    pub size: Option<u64>,
+
+    /// Whether the download fits in the disk quota
+    pub fits_quota: bool,
+}
+
+/// A previous version that can be switched back to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelRollback {
+    /// Model ID
+    pub model_id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// Version in use
+    pub current_version: String,
+
+    /// Version kept on disk
+    pub previous_version: String,
+}
+
+/// Everything the updates panel shows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdatesPanel {
+    /// Available updates
+    pub updates: Vec<ModelUpdate>,
+
+    /// Models with a previous version on disk
+    pub rollbacks: Vec<ModelRollback>,
+
+    /// Bytes the model directory may use; `None` when unlimited
+    pub disk_quota: Option<u64>,
+
+    /// Bytes the model directory uses
+    pub disk_usage: u64,
+
+    /// Whether updates are downloaded without asking
+    pub auto_update: bool,
+
+    /// When updates were last checked
+    pub checked_at: Option<DateTime<Utc>>,
+}
+
+/// Update settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateSettings {
+    /// Download updates without asking
+    pub auto_update: bool,
+
+    /// Disk quota of the model directory in GiB; `None` for unlimited
+    #[serde(default)]
+    pub disk_quota_gb: Option<f64>,
+}
+
+impl UpdateSettings {
+    /// Current settings
+    pub fn load() -> Self {
+        Self {
+            auto_update: config::get_bool(AUTO_UPDATE_KEY).unwrap_or(false),
+            disk_quota_gb: config::get_number(DISK_QUOTA_KEY).filter(|gb| *gb > 0.0),
+        }
+    }
+
+    /// Save the settings
+    pub fn save(&self) -> Result<(), String> {
+        config::set_value(AUTO_UPDATE_KEY, serde_json::Value::Bool(self.auto_update))?;
+        let quota = match self.disk_quota_gb.filter(|gb| *gb > 0.0) {
+            Some(gb) => serde_json::json!(gb),
+            None => serde_json::Value::Null,
+        };
+        config::set_value(DISK_QUOTA_KEY, quota)?;
+        config::save_config().map_err(|e| e.to_string())
+    }
+
+    /// Disk quota in bytes
+    pub fn disk_quota(&self) -> Option<u64> {
+        self.disk_quota_gb.map(|gb| (gb * 1024.0 * 1024.0 * 1024.0) as u64)
+    }
+}
+
+/// Where the previous version of a model is kept
+pub fn previous_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".previous");
+    path.with_file_name(name)
+}
+
+/// Installed versions by model ID
+pub fn load_installed(model_dir: &Path) -> BTreeMap<String, InstalledModel> {
+    let path = model_dir.join(INSTALLED_FILE);
+    match fs::read_to_string(&path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable {}: {}", path.display(), e);
+            BTreeMap::new()
+        }),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+fn save_installed(model_dir: &Path, installed: &BTreeMap<String, InstalledModel>) -> io::Result<()> {
+    fs::create_dir_all(model_dir)?;
+    let content = serde_json::to_string_pretty(installed).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    fs::write(model_dir.join(INSTALLED_FILE), content)
+}
+
+/// Record a fresh download of a model
+pub fn record_install(model_dir: &Path, model: &LocalModelInfo) -> io::Result<()> {
+    let mut installed = load_installed(model_dir);
+    let previous = installed.remove(&model.id).and_then(|m| m.previous);
+    installed.insert(
+        model.id.clone(),
+        InstalledModel {
+            current: InstalledVersion::of(model),
+            previous,
+        },
+    );
+    save_installed(model_dir, &installed)
+}
+
+/// Forget a deleted model's versions and delete its previous version
+pub fn forget(model_dir: &Path, model: &LocalModelInfo) -> io::Result<()> {
+    let previous = previous_path(&model.path);
+    if previous.exists() {
+        fs::remove_file(&previous)?;
+    }
+    let mut installed = load_installed(model_dir);
+    if installed.remove(&model.id).is_some() {
+        save_installed(model_dir, &installed)?;
+    }
+    Ok(())
+}
+
+/// Installed version of a downloaded model. Models downloaded before
+/// versions were recorded are taken to be the catalog's current version.
+pub fn installed_version(model_dir: &Path, model: &LocalModelInfo) -> Option<InstalledModel> {
+    if !model.is_downloaded {
+        return None;
+    }
+    if let Some(installed) = load_installed(model_dir).remove(&model.id) {
+        return Some(installed);
+    }
+    if let Err(e) = record_install(model_dir, model) {
+        warn!("Failed to record installed version of {}: {}", model.id, e);
+    }
+    Some(InstalledModel {
+        current: InstalledVersion::of(model),
+        previous: None,
+    })
+}
+
+/// Move a verified download into place, keeping the version it replaces
+/// as the previous version. An older previous version is deleted.
+pub fn install_update(model_dir: &Path, model: &LocalModelInfo, download: &Path) -> io::Result<()> {
+    let replaced = installed_version(model_dir, model).map(|m| m.current);
+    let previous = previous_path(&model.path);
+
+    if previous.exists() {
+        fs::remove_file(&previous)?;
+    }
+    if model.path.exists() {
+        fs::rename(&model.path, &previous)?;
+    }
+    if let Err(e) = fs::rename(download, &model.path) {
+        // Put the old version back so the model stays usable
+        let _ = fs::rename(&previous, &model.path);
+        return Err(e);
+    }
+
+    let mut installed = load_installed(model_dir);
+    installed.insert(
+        model.id.clone(),
+        InstalledModel {
+            current: InstalledVersion::of(model),
+            previous: replaced.filter(|_| previous.exists()),
+        },
+    );
+    save_installed(model_dir, &installed)
+}
+
+/// Swap a model with its previous version, so switching again undoes it
+pub fn switch_version(model_dir: &Path, model: &LocalModelInfo, version: &str) -> Result<(), String> {
+    let mut installed = load_installed(model_dir);
+    let record = installed
+        .get(&model.id)
+        .cloned()
+        .ok_or_else(|| format!("No installed version of {}", model.id))?;
+    if record.current.version == version {
+        return Ok(());
+    }
+    let previous = record
+        .previous
+        .clone()
+        .filter(|p| p.version == version)
+        .ok_or_else(|| format!("Version {} of {} is not on disk", version, model.id))?;
+
+    let previous_file = previous_path(&model.path);
+    if !previous_file.exists() {
+        return Err(format!("Version {} of {} is not on disk", version, model.id));
+    }
+    let swap = model.path.with_extension("switching");
+    let swapped = fs::rename(&model.path, &swap)
+        .and_then(|_| fs::rename(&previous_file, &model.path))
+        .and_then(|_| fs::rename(&swap, &previous_file));
+    if let Err(e) = swapped {
+        error!("Failed to switch {} to version {}: {}", model.id, version, e);
+        if swap.exists() && !model.path.exists() {
+            let _ = fs::rename(&swap, &model.path);
+        }
+        return Err(format!("Failed to switch version: {}", e));
+    }
+
+    installed.insert(
+        model.id.clone(),
+        InstalledModel {
+            current: previous,
+            previous: Some(record.current),
+        },
+    );
+    save_installed(model_dir, &installed).map_err(|e| e.to_string())
+}
+
+/// Bytes used by everything in the model directory
+pub fn disk_usage(dir: &Path) -> u64 {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => disk_usage(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Whether an update of `model` needing `size` bytes fits in the quota.
+/// Installing deletes the old previous version, so its space is reused.
+pub fn fits_quota(model: &LocalModelInfo, size: Option<u64>, usage: u64, quota: Option<u64>) -> bool {
+    let quota = match quota {
+        Some(quota) => quota,
+        None => return true,
+    };
+    let needed = size.unwrap_or_else(|| file_size(&model.path));
+    let freed = file_size(&previous_path(&model.path));
+    usage.saturating_sub(freed) + needed <= quota
+}
+
+/// Downloaded models whose catalog offers a different version or file
+pub fn check_updates(model_dir: &Path, models: &[LocalModelInfo]) -> Vec<ModelUpdate> {
+    let catalog: HashMap<String, (Option<String>, Option<u64>)> = get_model_registry()
+        .entries()
+        .into_iter()
+        .map(|e| (e.entry.id.clone(), (e.entry.changelog.clone(), e.entry.size)))
+        .collect();
+    let settings = UpdateSettings::load();
+    let usage = disk_usage(model_dir);
+    let quota = settings.disk_quota();
+
+    let updates = models
+        .iter()
+        .filter_map(|model| {
+            let installed = installed_version(model_dir, model)?.current;
+            let new_file = match (&installed.sha256, &model.sha256) {
+                (Some(old), Some(new)) => !old.eq_ignore_ascii_case(new),
+                _ => false,
+            };
+            if installed.version == model.model.version && !new_file {
+                return None;
+            }
+            let (changelog, size) = catalog.get(&model.id).cloned().unwrap_or((None, None));
+            Some(ModelUpdate {
+                model_id: model.id.clone(),
+                name: model.name.clone(),
+                installed_version: installed.version,
+                latest_version: model.model.version.clone(),
+                changelog,
+                size,
+                fits_quota: fits_quota(model, size, usage, quota),
+            })
+        })
+        .collect();
+
+    *CHECKED_AT.lock().unwrap() = Some(Utc::now());
+    updates
+}
+
+/// Models with a previous version to switch back to
+pub fn rollbacks(model_dir: &Path, models: &[LocalModelInfo]) -> Vec<ModelRollback> {
+    let installed = load_installed(model_dir);
+    models
+        .iter()
+        .filter(|m| m.is_downloaded && previous_path(&m.path).exists())
+        .filter_map(|m| {
+            let record = installed.get(&m.id)?;
+            Some(ModelRollback {
+                model_id: m.id.clone(),
+                name: m.name.clone(),
+                current_version: record.current.version.clone(),
+                previous_version: record.previous.as_ref()?.version.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Updates and rollbacks for the updates panel
+pub fn panel(model_dir: &Path, models: &[LocalModelInfo]) -> UpdatesPanel {
+    let settings = UpdateSettings::load();
+    UpdatesPanel {
+        updates: check_updates(model_dir, models),
+        rollbacks: rollbacks(model_dir, models),
+        disk_quota: settings.disk_quota(),
+        disk_usage: disk_usage(model_dir),
+        auto_update: settings.auto_update,
+        checked_at: *CHECKED_AT.lock().unwrap(),
+    }
+}
+
+/// Updates not announced yet; each version is announced once per run
+pub fn take_unannounced(updates: &[ModelUpdate]) -> Vec<ModelUpdate> {
+    let mut notified = NOTIFIED.lock().unwrap();
+    updates
+        .iter()
+        .filter(|u| notified.insert((u.model_id.clone(), u.latest_version.clone())))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Model, ModelCapabilities};
+
+    fn model(dir: &Path, version: &str) -> LocalModelInfo {
+        let path = dir.join("tiny.gguf");
+        LocalModelInfo {
+            id: "tiny".to_string(),
+            name: "Tiny".to_string(),
+            kind: Default::default(),
+            is_downloaded: path.exists(),
+            path,
+            parameters: 1,
+            quantization: "Q4_0".to_string(),
+            context_size: 2048,
+            download_url: None,
+            catalog: None,
+            sha256: None,
+            license: None,
+            family: None,
+            architecture: None,
+            chat_template: None,
+            model: Model {
+                id: "tiny".to_string(),
+                provider: "local".to_string(),
+                name: "Tiny".to_string(),
+                version: version.to_string(),
+                capabilities: ModelCapabilities {
+                    vision: false,
+                    max_context_length: 2048,
+                    functions: false,
+                    streaming: true,
+                    json_mode: false,
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn update_keeps_previous_version_for_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("tiny.gguf"), b"v1").unwrap();
+        record_install(dir.path(), &model(dir.path(), "1.0")).unwrap();
+
+        let download = dir.path().join("tiny.download");
+        fs::write(&download, b"v2").unwrap();
+        let updated = model(dir.path(), "2.0");
+        install_update(dir.path(), &updated, &download).unwrap();
+
+        assert_eq!(fs::read(&updated.path).unwrap(), b"v2");
+        assert_eq!(fs::read(previous_path(&updated.path)).unwrap(), b"v1");
+        let rollbacks = rollbacks(dir.path(), &[updated.clone()]);
+        assert_eq!(rollbacks[0].previous_version, "1.0");
+
+        switch_version(dir.path(), &updated, "1.0").unwrap();
+        assert_eq!(fs::read(&updated.path).unwrap(), b"v1");
+        let record = &load_installed(dir.path())["tiny"];
+        assert_eq!(record.current.version, "1.0");
+        assert_eq!(record.previous.as_ref().unwrap().version, "2.0");
+
+        switch_version(dir.path(), &updated, "2.0").unwrap();
+        assert_eq!(fs::read(&updated.path).unwrap(), b"v2");
+        assert!(switch_version(dir.path(), &updated, "3.0").is_err());
+    }
+
+    #[test]
+    fn quota_counts_space_freed_by_previous_version() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("tiny.gguf"), vec![0u8; 10]).unwrap();
+        fs::write(dir.path().join("tiny.gguf.previous"), vec![0u8; 10]).unwrap();
+        let model = model(dir.path(), "1.0");
+        let usage = disk_usage(dir.path());
+
+        assert_eq!(usage, 20);
+        assert!(fits_quota(&model, Some(10), usage, Some(20)));
+        assert!(!fits_quota(&model, Some(11), usage, Some(20)));
+        assert!(fits_quota(&model, Some(1000), usage, None));
+    }
+}
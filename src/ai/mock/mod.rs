@@ -0,0 +1,580 @@
+use crate::ai::{ModelError, ModelProvider, ModelProviderConfig, ModelStatus, ProviderType};
+use crate::models::messages::{ContentType, Message, MessageContent, MessageError, MessageRole};
+use crate::models::{Model, ModelCapabilities};
+use crate::utils::config;
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use mcp_common::platform::fs::{app_dir, AppDir};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Environment variable overriding the `ai.provider_mode` setting
+pub const PROVIDER_MODE_ENV: &str = "CLAUDE_MCP_PROVIDER_MODE";
+
+/// Environment variable overriding the `ai.fixtures_dir` setting
+pub const FIXTURES_DIR_ENV: &str = "CLAUDE_MCP_FIXTURES_DIR";
+
+/// File holding the recorded model list
+const MODELS_FIXTURE: &str = "models.json";
+
+/// Pause between streamed words of canned replies, so the UI shows streaming
+const CANNED_WORD_DELAY: Duration = Duration::from_millis(20);
+
+/// How the online provider is backed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderMode {
+    /// The real provider (default)
+    Live,
+
+    /// Canned replies; no API key or network needed
+    Mock,
+
+    /// The real provider, saving every interaction as a fixture
+    Record,
+
+    /// Recorded fixtures only; requests without a fixture fail
+    Replay,
+}
+
+impl FromStr for ProviderMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "live" => Ok(ProviderMode::Live),
+            "mock" => Ok(ProviderMode::Mock),
+            "record" => Ok(ProviderMode::Record),
+            "replay" => Ok(ProviderMode::Replay),
+            other => Err(format!("Unknown provider mode '{}'; use live, mock, record or replay", other)),
+        }
+    }
+}
+
+impl ProviderMode {
+    /// Mode selected by the environment, then the configuration
+    pub fn current() -> Self {
+        let configured = std::env::var(PROVIDER_MODE_ENV)
+            .ok()
+            .or_else(|| config::get_string("ai.provider_mode"));
+        match configured.map(|mode| mode.parse()) {
+            Some(Ok(mode)) => mode,
+            Some(Err(e)) => {
+                warn!("{}; using the live provider", e);
+                ProviderMode::Live
+            }
+            None => ProviderMode::Live,
+        }
+    }
+}
+
+/// Directory of the fixture files, from the environment or the configuration
+pub fn fixtures_dir() -> PathBuf {
+    if let Some(dir) = std::env::var(FIXTURES_DIR_ENV)
+        .ok()
+        .or_else(|| config::get_string("ai.fixtures_dir"))
+    {
+        return PathBuf::from(dir);
+    }
+    app_dir(AppDir::Data).join("fixtures")
+}
+
+/// A recorded provider interaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    /// Model the request was sent to
+    pub model_id: String,
+
+    /// Request message
+    pub request: Message,
+
+    /// Complete response, for `complete` calls
+    #[serde(default)]
+    pub response: Option<Message>,
+
+    /// Streamed chunks, for `stream` calls
+    #[serde(default)]
+    pub chunks: Vec<Message>,
+
+    /// Error the provider answered with
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Kind of call a fixture was recorded for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CallKind {
+    Complete,
+    Stream,
+}
+
+impl CallKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CallKind::Complete => "complete",
+            CallKind::Stream => "stream",
+        }
+    }
+}
+
+/// Fixture files in a directory, one per interaction
+#[derive(Debug, Clone)]
+pub struct FixtureStore {
+    dir: PathBuf,
+}
+
+impl FixtureStore {
+    /// Use fixtures in a directory
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Directory of the fixture files
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    /// Fixture name for a request; depends only on the call kind, model, role
+    /// and content, so ids and timestamps don't break replay
+    fn key(kind: CallKind, model_id: &str, message: &Message) -> String {
+        let role = serde_json::to_string(&message.role).unwrap_or_default();
+        let content = serde_json::to_string(&message.content).unwrap_or_default();
+
+        // FNV-1a; stable across builds, unlike the std hasher
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in [model_id, &role, &content].join("\n").bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        format!("{}-{:016x}", kind.as_str(), hash)
+    }
+
+    fn path(&self, kind: CallKind, model_id: &str, message: &Message) -> PathBuf {
+        self.dir.join(format!("{}.json", Self::key(kind, model_id, message)))
+    }
+
+    fn load(&self, kind: CallKind, model_id: &str, message: &Message) -> Option<Fixture> {
+        let path = self.path(kind, model_id, message);
+        let json = fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&json) {
+            Ok(fixture) => Some(fixture),
+            Err(e) => {
+                warn!("Ignoring invalid fixture {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    fn save(&self, kind: CallKind, fixture: &Fixture) {
+        let path = self.path(kind, &fixture.model_id, &fixture.request);
+        let result = fs::create_dir_all(&self.dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| serde_json::to_string_pretty(fixture).map_err(|e| e.to_string()))
+            .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => debug!("Recorded fixture {}", path.display()),
+            Err(e) => warn!("Failed to record fixture {}: {}", path.display(), e),
+        }
+    }
+
+    fn load_models(&self) -> Option<Vec<Model>> {
+        let json = fs::read_to_string(self.dir.join(MODELS_FIXTURE)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    fn save_models(&self, models: &[Model]) {
+        let result = fs::create_dir_all(&self.dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| serde_json::to_string_pretty(models).map_err(|e| e.to_string()))
+            .and_then(|json| fs::write(self.dir.join(MODELS_FIXTURE), json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            warn!("Failed to record the model list: {}", e);
+        }
+    }
+}
+
+/// What backs the mock provider
+enum Backend {
+    /// Canned replies
+    Canned,
+    /// A real provider whose interactions are recorded
+    Record(Arc<dyn ModelProvider>),
+    /// Recorded fixtures
+    Replay,
+}
+
+/// Stand-in for the online provider, for offline development and tests.
+///
+/// Depending on the [`ProviderMode`] it answers with canned replies, records a
+/// real provider's interactions to fixture files, or replays those fixtures
+/// deterministically.
+pub struct MockProvider {
+    /// Provider configuration
+    config: ModelProviderConfig,
+
+    /// What answers requests
+    backend: Backend,
+
+    /// Fixture files
+    fixtures: FixtureStore,
+}
+
+impl MockProvider {
+    fn with_backend(name: &str, backend: Backend, fixtures: FixtureStore) -> Self {
+        let config = ModelProviderConfig {
+            provider_type: ProviderType::Claude,
+            name: name.to_string(),
+            base_url: String::new(),
+            default_model: Self::canned_model().id,
+            fallback_model: None,
+            enable_mcp: false,
+            ..ModelProviderConfig::default()
+        };
+        Self {
+            config,
+            backend,
+            fixtures,
+        }
+    }
+
+    /// Answer every request with a canned reply
+    pub fn canned() -> Self {
+        Self::with_backend("Mock", Backend::Canned, FixtureStore::new(fixtures_dir()))
+    }
+
+    /// Forward requests to a real provider and record them as fixtures
+    pub fn recording(inner: Arc<dyn ModelProvider>, fixtures: FixtureStore) -> Self {
+        let mut provider = Self::with_backend("Mock (recording)", Backend::Record(inner.clone()), fixtures);
+        provider.config.default_model = inner.config().default_model.clone();
+        provider.config.enable_streaming = inner.config().enable_streaming;
+        provider
+    }
+
+    /// Answer requests from recorded fixtures only
+    pub fn replaying(fixtures: FixtureStore) -> Self {
+        Self::with_backend("Mock (replay)", Backend::Replay, fixtures)
+    }
+
+    /// Provider for a mode; `None` for the live provider
+    pub fn for_mode(mode: ProviderMode, live: Option<Arc<dyn ModelProvider>>) -> Option<Self> {
+        let fixtures = FixtureStore::new(fixtures_dir());
+        match mode {
+            ProviderMode::Live => None,
+            ProviderMode::Mock => Some(Self::canned()),
+            ProviderMode::Replay => {
+                info!("Replaying provider fixtures from {}", fixtures.dir().display());
+                Some(Self::replaying(fixtures))
+            }
+            ProviderMode::Record => match live {
+                Some(inner) => {
+                    info!("Recording provider fixtures to {}", fixtures.dir().display());
+                    Some(Self::recording(inner, fixtures))
+                }
+                None => {
+                    warn!("Nothing to record without the live provider (is the API key set?); using canned replies");
+                    Some(Self::canned())
+                }
+            },
+        }
+    }
+
+    fn canned_model() -> Model {
+        Model {
+            id: "mock".to_string(),
+            provider: "mock".to_string(),
+            name: "Mock model".to_string(),
+            version: "1".to_string(),
+            capabilities: ModelCapabilities {
+                vision: false,
+                max_context_length: 200_000,
+                functions: false,
+                streaming: true,
+                json_mode: false,
+            },
+        }
+    }
+
+    fn assistant_text(id: &str, model_id: &str, text: String) -> Message {
+        Message {
+            id: id.to_string(),
+            role: MessageRole::Assistant,
+            content: MessageContent {
+                parts: vec![ContentType::Text { text }],
+            },
+            metadata: Some(HashMap::from([
+                ("model".to_string(), serde_json::json!(model_id)),
+                ("provider".to_string(), serde_json::json!("mock")),
+            ])),
+            created_at: SystemTime::now(),
+        }
+    }
+
+    fn canned_reply(message: &Message) -> String {
+        let prompt = message
+            .content
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                ContentType::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("This is a mock reply to: {}", prompt.trim())
+    }
+
+    fn missing_fixture(kind: CallKind, model_id: &str) -> MessageError {
+        MessageError::Unknown(format!(
+            "No {} fixture for this request to {}; record one with {}=record",
+            kind.as_str(),
+            model_id,
+            PROVIDER_MODE_ENV
+        ))
+    }
+
+    fn replay_complete(&self, model_id: &str, message: &Message) -> Result<Message, MessageError> {
+        if let Some(fixture) = self.fixtures.load(CallKind::Complete, model_id, message) {
+            if let Some(error) = fixture.error {
+                return Err(MessageError::ProtocolError(error));
+            }
+            return fixture
+                .response
+                .ok_or_else(|| Self::missing_fixture(CallKind::Complete, model_id));
+        }
+
+        // A streamed recording ends with the complete message
+        self.fixtures
+            .load(CallKind::Stream, model_id, message)
+            .and_then(|fixture| fixture.chunks.last().cloned())
+            .ok_or_else(|| Self::missing_fixture(CallKind::Complete, model_id))
+    }
+
+    fn replay_stream(&self, model_id: &str, message: &Message) -> Result<Vec<Result<Message, MessageError>>, MessageError> {
+        if let Some(fixture) = self.fixtures.load(CallKind::Stream, model_id, message) {
+            let mut chunks: Vec<_> = fixture.chunks.into_iter().map(Ok).collect();
+            if let Some(error) = fixture.error {
+                chunks.push(Err(MessageError::ProtocolError(error)));
+            }
+            return Ok(chunks);
+        }
+
+        // A complete recording streams as a single chunk
+        self.replay_complete(model_id, message)
+            .map(|response| vec![Ok(response)])
+            .map_err(|_| Self::missing_fixture(CallKind::Stream, model_id))
+    }
+}
+
+#[async_trait]
+impl ModelProvider for MockProvider {
+    fn provider_type(&self) -> ProviderType {
+        self.config.provider_type
+    }
+
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn config(&self) -> &ModelProviderConfig {
+        &self.config
+    }
+
+    async fn available_models(&self) -> Result<Vec<Model>, ModelError> {
+        match &self.backend {
+            Backend::Canned => Ok(vec![Self::canned_model()]),
+            Backend::Record(inner) => {
+                let models = inner.available_models().await?;
+                self.fixtures.save_models(&models);
+                Ok(models)
+            }
+            Backend::Replay => Ok(self
+                .fixtures
+                .load_models()
+                .unwrap_or_else(|| vec![Self::canned_model()])),
+        }
+    }
+
+    async fn is_available(&self, model_id: &str) -> bool {
+        match &self.backend {
+            // Any model works, so the UI's default model does too
+            Backend::Canned | Backend::Replay => true,
+            Backend::Record(inner) => inner.is_available(model_id).await,
+        }
+    }
+
+    async fn model_status(&self, model_id: &str) -> ModelStatus {
+        match &self.backend {
+            Backend::Canned | Backend::Replay => ModelStatus::Available,
+            Backend::Record(inner) => inner.model_status(model_id).await,
+        }
+    }
+
+    async fn complete(&self, model_id: &str, message: Message) -> Result<Message, MessageError> {
+        match &self.backend {
+            Backend::Canned => Ok(Self::assistant_text(
+                &Uuid::new_v4().to_string(),
+                model_id,
+                Self::canned_reply(&message),
+            )),
+            Backend::Record(inner) => {
+                let result = inner.complete(model_id, message.clone()).await;
+                let fixture = Fixture {
+                    model_id: model_id.to_string(),
+                    request: message,
+                    response: result.as_ref().ok().cloned(),
+                    chunks: Vec::new(),
+                    error: result.as_ref().err().map(|e| e.to_string()),
+                };
+                self.fixtures.save(CallKind::Complete, &fixture);
+                result
+            }
+            Backend::Replay => self.replay_complete(model_id, &message),
+        }
+    }
+
+    async fn stream(
+        &self,
+        model_id: &str,
+        message: Message,
+    ) -> Result<mpsc::Receiver<Result<Message, MessageError>>, MessageError> {
+        let (tx, rx) = mpsc::channel(32);
+
+        match &self.backend {
+            Backend::Canned => {
+                let id = Uuid::new_v4().to_string();
+                let model_id = model_id.to_string();
+                let reply = Self::canned_reply(&message);
+                tokio::spawn(async move {
+                    // Chunks carry the text so far, like the real providers
+                    let mut text = String::new();
+                    for word in reply.split_inclusive(' ') {
+                        text.push_str(word);
+                        if tx.send(Ok(Self::assistant_text(&id, &model_id, text.clone()))).await.is_err() {
+                            return;
+                        }
+                        tokio::time::sleep(CANNED_WORD_DELAY).await;
+                    }
+                });
+            }
+            Backend::Record(inner) => {
+                let mut upstream = inner.stream(model_id, message.clone()).await?;
+                let fixtures = self.fixtures.clone();
+                let mut fixture = Fixture {
+                    model_id: model_id.to_string(),
+                    request: message,
+                    response: None,
+                    chunks: Vec::new(),
+                    error: None,
+                };
+                tokio::spawn(async move {
+                    while let Some(chunk) = upstream.recv().await {
+                        match &chunk {
+                            Ok(message) => fixture.chunks.push(message.clone()),
+                            Err(e) => fixture.error = Some(e.to_string()),
+                        }
+                        if tx.send(chunk).await.is_err() {
+                            // Don't keep a partial recording of a canceled stream
+                            return;
+                        }
+                    }
+                    fixtures.save(CallKind::Stream, &fixture);
+                });
+            }
+            Backend::Replay => {
+                let chunks = self.replay_stream(model_id, &message)?;
+                tokio::spawn(async move {
+                    for chunk in chunks {
+                        if tx.send(chunk).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        }
+
+        Ok(rx)
+    }
+
+    async fn cancel_stream(&self, stream_id: &str) -> Result<(), MessageError> {
+        match &self.backend {
+            Backend::Record(inner) => inner.cancel_stream(stream_id).await,
+            // Canned and replayed streams end when their receiver is dropped
+            Backend::Canned | Backend::Replay => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store() -> (tempfile::TempDir, FixtureStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FixtureStore::new(dir.path().to_path_buf());
+        (dir, store)
+    }
+
+    #[test]
+    fn test_fixture_key_ignores_id_and_time() {
+        let a = Message::new_user_text("Hello");
+        let b = Message::new_user_text("Hello");
+        let c = Message::new_user_text("Hello there");
+        assert_ne!(a.id, b.id);
+
+        let key = |m: &Message| FixtureStore::key(CallKind::Complete, "mock", m);
+        assert_eq!(key(&a), key(&b));
+        assert_ne!(key(&a), key(&c));
+        assert_ne!(key(&a), FixtureStore::key(CallKind::Stream, "mock", &a));
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let (_dir, store) = temp_store();
+        let recorder = MockProvider::recording(Arc::new(MockProvider::canned()), store.clone());
+        let request = Message::new_user_text("What is Rust?");
+
+        let recorded = recorder.complete("mock", request.clone()).await.unwrap();
+        let mut stream = recorder.stream("mock", request.clone()).await.unwrap();
+        let mut recorded_chunks = Vec::new();
+        while let Some(chunk) = stream.recv().await {
+            recorded_chunks.push(chunk.unwrap());
+        }
+
+        let replayer = MockProvider::replaying(store);
+        let replayed = replayer.complete("mock", Message::new_user_text("What is Rust?")).await.unwrap();
+        assert_eq!(replayed.id, recorded.id);
+        assert_eq!(replayed.text_content(), recorded.text_content());
+
+        let mut stream = replayer.stream("mock", request).await.unwrap();
+        let mut replayed_chunks = Vec::new();
+        while let Some(chunk) = stream.recv().await {
+            replayed_chunks.push(chunk.unwrap());
+        }
+        assert_eq!(replayed_chunks.len(), recorded_chunks.len());
+        assert_eq!(
+            replayed_chunks.last().unwrap().text_content(),
+            recorded_chunks.last().unwrap().text_content()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_without_fixture_fails() {
+        let (_dir, store) = temp_store();
+        let replayer = MockProvider::replaying(store);
+        let result = replayer.complete("mock", Message::new_user_text("Unrecorded")).await;
+        assert!(matches!(result, Err(MessageError::Unknown(_))));
+    }
+
+    #[test]
+    fn test_provider_mode_parsing() {
+        assert_eq!("Replay".parse::<ProviderMode>().unwrap(), ProviderMode::Replay);
+        assert!("offline".parse::<ProviderMode>().is_err());
+    }
+}
@@ -1,9 +1,11 @@
 pub mod claude;
 pub mod local;
+pub mod mock;
 pub mod router;
 
 use crate::models::messages::{Message, MessageError};
 use crate::models::Model;
+use crate::utils::cancellation::RequestContext;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -80,6 +82,12 @@ pub enum ModelError {
     /// Not implemented
     NotImplemented,
     
+    /// Canceled by the user
+    Cancelled,
+    
+    /// A downloaded file didn't match its checksum
+    ChecksumMismatch,
+    
     /// Unknown error
     Unknown,
 }
@@ -173,6 +181,51 @@ pub trait ModelProvider: Send + Sync {
     /// Cancel a streaming message
     async fn cancel_stream(&self, stream_id: &str) -> Result<(), MessageError>;
     
+    /// Complete a message, aborting when the context is canceled or its deadline passes
+    async fn complete_with_context(
+        &self,
+        model_id: &str,
+        message: Message,
+        ctx: &RequestContext,
+    ) -> Result<Message, MessageError> {
+        ctx.run(self.complete(model_id, message)).await
+    }
+    
+    /// Stream a message, ending the stream with an error when the context is canceled
+    /// or its deadline passes.
+    ///
+    /// Dropping the upstream receiver makes the provider's producer stop on its next send.
+    async fn stream_with_context(
+        &self,
+        model_id: &str,
+        message: Message,
+        ctx: &RequestContext,
+    ) -> Result<mpsc::Receiver<Result<Message, MessageError>>, MessageError> {
+        let mut upstream = ctx.run(self.stream(model_id, message)).await?;
+        let (tx, rx) = mpsc::channel(32);
+        let ctx = ctx.clone();
+        
+        tokio::spawn(async move {
+            loop {
+                let next = ctx.run(async { Ok::<_, MessageError>(upstream.recv().await) }).await;
+                match next {
+                    Ok(Some(chunk)) => {
+                        if tx.send(chunk).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        break;
+                    }
+                }
+            }
+        });
+        
+        Ok(rx)
+    }
+    
     /// Check if provider supports a feature
     fn supports_feature(&self, feature: &str) -> bool {
         match feature {
@@ -187,13 +240,17 @@ pub trait ModelProvider: Send + Sync {
 pub fn get_all_providers() -> Vec<Arc<dyn ModelProvider>> {
     let mut providers = Vec::new();
     
-    // Claude provider
-    if let Ok(claude_provider) = claude::ClaudeProvider::new() {
-        providers.push(Arc::new(claude_provider) as Arc<dyn ModelProvider>);
+    // Claude provider, or the mock standing in for it
+    let claude_provider = claude::ClaudeProvider::new()
+        .ok()
+        .map(|provider| Arc::new(provider) as Arc<dyn ModelProvider>);
+    match mock::MockProvider::for_mode(mock::ProviderMode::current(), claude_provider.clone()) {
+        Some(mock_provider) => providers.push(Arc::new(mock_provider) as Arc<dyn ModelProvider>),
+        None => providers.extend(claude_provider),
     }
     
     // Local provider
-    if let Ok(local_provider) = local::LocalProvider::new() {
+    if let Ok(local_provider) = local::get_local_provider() {
         providers.push(Arc::new(local_provider) as Arc<dyn ModelProvider>);
     }
     
@@ -1,10 +1,12 @@
 use crate::ai::{get_all_providers, ModelError, ModelProvider, ModelProviderConfig, ModelStatus, ProviderType};
 use crate::models::messages::{Message, MessageError};
 use crate::models::Model;
+use crate::utils::cancellation::RequestContext;
 use crate::utils::config;
 use crate::utils::events::{events, get_event_system};
 use async_trait::async_trait;
 use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, SystemTime};
@@ -32,7 +34,10 @@ pub struct ModelRouter {
 }
 
 /// Network status for determining connection availability
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Serialized as `{"state": "limited", "reason": "captive_portal"}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", content = "reason", rename_all = "snake_case")]
 pub enum NetworkStatus {
     /// Network is connected
     Connected,
@@ -43,10 +48,34 @@ pub enum NetworkStatus {
     /// Network connection is unstable
     Unstable,
     
+    /// Network is reachable but internet access is restricted
+    Limited(LimitedReason),
+    
     /// Network status is unknown
     Unknown,
 }
 
+/// Reason for limited connectivity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitedReason {
+    /// Traffic is intercepted by a captive portal (login page)
+    CaptivePortal,
+    
+    /// DNS resolves but connections to the internet fail
+    DnsOnly,
+}
+
+impl LimitedReason {
+    /// Machine-readable name of the reason
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitedReason::CaptivePortal => "captive_portal",
+            LimitedReason::DnsOnly => "dns_only",
+        }
+    }
+}
+
 /// Provider selection strategy
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RouterStrategy {
@@ -136,8 +165,13 @@ impl ModelRouter {
                     NetworkStatus::Connected => "connected",
                     NetworkStatus::Disconnected => "disconnected",
                     NetworkStatus::Unstable => "unstable",
+                    NetworkStatus::Limited(_) => "limited",
                     NetworkStatus::Unknown => "unknown",
-                }
+                },
+                "reason": match status {
+                    NetworkStatus::Limited(reason) => Some(reason.as_str()),
+                    _ => None,
+                },
             }),
         );
     }
@@ -169,6 +203,11 @@ impl ModelRouter {
             .cloned()
     }
     
+    /// Get the current network status
+    pub fn network_status(&self) -> NetworkStatus {
+        *self.network_status.read().unwrap()
+    }
+    
     /// Check if network is available
     pub fn is_network_available(&self) -> bool {
         let status = self.network_status.read().unwrap();
@@ -288,6 +327,29 @@ impl ModelRouter {
         models
     }
     
+    /// Replace images with OCR text when the target model can't see them
+    async fn prepare_message(
+        &self,
+        provider: &Arc<dyn ModelProvider>,
+        model_id: &str,
+        message: Message,
+    ) -> Message {
+        // Unknown models are assumed to accept images; the provider will reject them otherwise
+        let supports_vision = provider
+            .available_models()
+            .await
+            .ok()
+            .and_then(|models| models.into_iter().find(|m| m.id == model_id))
+            .map(|m| m.capabilities.vision)
+            .unwrap_or(true);
+        
+        // OCR runs an external process
+        let fallback = message.clone();
+        tokio::task::spawn_blocking(move || crate::ocr::prepare_for_model(message, supports_vision))
+            .await
+            .unwrap_or(fallback)
+    }
+    
     /// Complete a message with the appropriate model
     pub async fn complete(&self, model_id: &str, message: Message) -> Result<Message, MessageError> {
         // Select provider
@@ -295,6 +357,8 @@ impl ModelRouter {
             .select_provider_for_model(model_id)
             .ok_or_else(|| MessageError::ProtocolError(format!("No provider found for model {}", model_id)))?;
         
+        let message = self.prepare_message(&provider, &final_model_id, message).await;
+        
         // Complete with selected provider
         provider.complete(&final_model_id, message).await
     }
@@ -310,10 +374,42 @@ impl ModelRouter {
             .select_provider_for_model(model_id)
             .ok_or_else(|| MessageError::ProtocolError(format!("No provider found for model {}", model_id)))?;
         
+        let message = self.prepare_message(&provider, &final_model_id, message).await;
+        
         // Stream with selected provider
         provider.stream(&final_model_id, message).await
     }
     
+    /// Complete a message, honoring the request's cancellation token and deadline
+    pub async fn complete_with_context(
+        &self,
+        model_id: &str,
+        message: Message,
+        ctx: &RequestContext,
+    ) -> Result<Message, MessageError> {
+        let (provider, final_model_id) = self
+            .select_provider_for_model(model_id)
+            .ok_or_else(|| MessageError::ProtocolError(format!("No provider found for model {}", model_id)))?;
+        
+        let message = self.prepare_message(&provider, &final_model_id, message).await;
+        provider.complete_with_context(&final_model_id, message, ctx).await
+    }
+    
+    /// Stream a message, honoring the request's cancellation token and deadline
+    pub async fn stream_with_context(
+        &self,
+        model_id: &str,
+        message: Message,
+        ctx: &RequestContext,
+    ) -> Result<mpsc::Receiver<Result<Message, MessageError>>, MessageError> {
+        let (provider, final_model_id) = self
+            .select_provider_for_model(model_id)
+            .ok_or_else(|| MessageError::ProtocolError(format!("No provider found for model {}", model_id)))?;
+        
+        let message = self.prepare_message(&provider, &final_model_id, message).await;
+        provider.stream_with_context(&final_model_id, message, ctx).await
+    }
+    
     /// Cancel a streaming message
     pub async fn cancel_stream(&self, stream_id: &str) -> Result<(), MessageError> {
         // Try cancelling with all providers
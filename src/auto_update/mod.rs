@@ -5,6 +5,7 @@ use reqwest::Client;
 use log::{info, error, warn};
 use std::sync::{Arc, Mutex};
 use std::path::PathBuf;
+use mcp_common::platform::fs::{app_dir, AppDir};
 
 /// Configuration for the auto-updater
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,11 +55,7 @@ pub struct UpdateManager {
 impl UpdateManager {
     /// Create a new update manager
     pub fn new(app: AppHandle<Wry>) -> Self {
-        let config_path = app
-            .path_resolver()
-            .app_config_dir()
-            .unwrap()
-            .join("updater_config.json");
+        let config_path = app_dir(AppDir::Config).join("updater_config.json");
         
         let config = if config_path.exists() {
             match std::fs::read_to_string(&config_path) {
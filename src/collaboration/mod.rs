@@ -6,6 +6,7 @@
 // - Session management for multi-device usage
 // - Cross-device synchronization
 // - Infrastructure for audio/video communication
+// - One generation at a time per shared conversation
 
 pub mod presence;
 pub mod rtc;
@@ -419,6 +420,23 @@ impl CollaborationManager {
             None => return Ok(()),  // No active session
         };
         
+        // Give up our generation locks so others aren't left waiting for the timeout
+        let user_id = self.current_user.read().unwrap().id.clone();
+        let conversation_id = self.sessions.read().unwrap().get(&session_id).map(|s| s.conversation_id.clone());
+        if let Some(conversation_id) = conversation_id {
+            let status = self.get_generation_status(&conversation_id)?;
+            let held: Vec<String> = status.holder
+                .map(|lock| lock.request)
+                .into_iter()
+                .chain(status.queue)
+                .filter(|request| request.user_id == user_id)
+                .map(|request| request.request_id)
+                .collect();
+            for request_id in held {
+                self.release_generation(&conversation_id, &request_id)?;
+            }
+        }
+        
         // Leave session in managers
         self.session_manager.write().unwrap().leave_session(&session_id)?;
         self.presence_manager.write().unwrap().leave_session(&session_id)?;
@@ -645,6 +663,17 @@ impl CollaborationManager {
         Ok(())
     }
     
+    /// Synchronize a conversation's messages, sending only what changed since the last sync
+    pub fn sync_messages(&self, conversation_id: &str, messages: &[Message]) -> Result<usize> {
+        // Get current session ID
+        let session_id = match *self.current_session_id.read().unwrap() {
+            Some(ref id) => id.clone(),
+            None => return Ok(0),  // No active session
+        };
+        
+        self.sync_manager.write().unwrap().sync_messages(&session_id, conversation_id, messages)
+    }
+    
     /// Send a message in the collaborative session
     pub fn send_message(&self, message: &Message) -> Result<()> {
         // Get current session ID
@@ -669,6 +698,71 @@ impl CollaborationManager {
         Ok(())
     }
     
+    /// Session the conversation is shared in, if it belongs to the current session
+    fn session_for_conversation(&self, conversation_id: &str) -> Option<String> {
+        let session_id = self.current_session_id.read().unwrap().clone()?;
+        let shared = self.sessions.read().unwrap()
+            .get(&session_id)
+            .map_or(false, |session| session.conversation_id == conversation_id);
+        
+        if shared { Some(session_id) } else { None }
+    }
+    
+    /// Ask for the generation lock of a conversation; None if the conversation
+    /// isn't shared in the current session and needs no lock
+    pub fn request_generation(&self, conversation_id: &str, request_id: &str) -> Result<Option<sessions::LockStatus>> {
+        let session_id = match self.session_for_conversation(conversation_id) {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+        
+        let user = self.current_user.read().unwrap().clone();
+        let request = sessions::GenerationRequest {
+            request_id: request_id.to_string(),
+            user_id: user.id,
+            user_name: user.name,
+            device_id: user.device_id,
+            requested_at: SystemTime::now(),
+        };
+        
+        let status = self.sync_manager.write().unwrap().request_generation(&session_id, conversation_id, request)?;
+        
+        Ok(Some(status))
+    }
+    
+    /// Extend the generation lock held by a request
+    pub fn renew_generation(&self, conversation_id: &str, request_id: &str) -> Result<()> {
+        let session_id = match self.session_for_conversation(conversation_id) {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        
+        self.sync_manager.write().unwrap().renew_generation(&session_id, conversation_id, request_id)?;
+        
+        Ok(())
+    }
+    
+    /// Release the generation lock held by a request, or withdraw it from the queue
+    pub fn release_generation(&self, conversation_id: &str, request_id: &str) -> Result<()> {
+        let session_id = match self.session_for_conversation(conversation_id) {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        
+        if let Some(next) = self.sync_manager.write().unwrap().release_generation(&session_id, conversation_id, request_id)? {
+            info!("Generation lock on {} passed to {}", conversation_id, next.request.user_name);
+        }
+        
+        Ok(())
+    }
+    
+    /// Who is generating in a conversation and who is waiting
+    pub fn get_generation_status(&self, conversation_id: &str) -> Result<sessions::GenerationStatus> {
+        let locks = self.sync_manager.read().unwrap().generation_locks();
+        let status = locks.lock().unwrap().status(conversation_id);
+        Ok(status)
+    }
+    
     /// Start an audio call in the current session
     pub fn start_audio_call(&self) -> Result<()> {
         // Check if audio is enabled
@@ -847,6 +941,10 @@ impl CollaborationManager {
             messages_received: sync_stats.messages_received,
             sync_operations: sync_stats.sync_operations,
             conflicts_resolved: sync_stats.conflicts_resolved,
+            sync_bytes_sent: sync_stats.bytes_sent,
+            sync_bytes_received: sync_stats.bytes_received,
+            sync_delta_bytes: sync_stats.delta_bytes,
+            sync_bytes_saved: sync_stats.bytes_saved(),
             calls_initiated: rtc_stats.calls_initiated,
             call_duration_seconds: rtc_stats.call_duration_seconds,
             current_session_id: self.current_session_id.read().unwrap().clone(),
@@ -887,6 +985,18 @@ pub struct CollaborationStatistics {
     /// Number of conflicts resolved
     pub conflicts_resolved: usize,
     
+    /// Compressed sync bytes sent
+    pub sync_bytes_sent: usize,
+    
+    /// Compressed sync bytes received
+    pub sync_bytes_received: usize,
+    
+    /// Uncompressed size of the sync deltas sent
+    pub sync_delta_bytes: usize,
+    
+    /// Bytes saved by delta sync and compression compared to full conversations
+    pub sync_bytes_saved: usize,
+    
     /// Number of calls initiated
     pub calls_initiated: usize,
     
@@ -5,8 +5,9 @@
 // - User management and permissions
 // - Session discovery
 // - Cross-device session coordination
+// - Per-conversation generation locks
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime};
@@ -427,3 +428,336 @@ pub struct SessionStatistics {
     /// Number of invitations received
     pub invitations_received: usize,
 }
+
+/// How long a generation lock is held without a heartbeat before it is released
+pub const GENERATION_LOCK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A user's request to generate a reply in a conversation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenerationRequest {
+    /// Request ID, shared with the generation it guards
+    pub request_id: String,
+    
+    /// Requesting user ID
+    pub user_id: String,
+    
+    /// Requesting user name
+    pub user_name: String,
+    
+    /// Device the generation runs on
+    pub device_id: String,
+    
+    /// When the request was made
+    pub requested_at: SystemTime,
+}
+
+impl GenerationRequest {
+    /// Whether this request was made before another; ties go to the lower ID
+    /// so every device orders them the same way
+    fn precedes(&self, other: &GenerationRequest) -> bool {
+        (self.requested_at, &self.request_id) < (other.requested_at, &other.request_id)
+    }
+}
+
+/// The generation currently running in a conversation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenerationLock {
+    /// Conversation ID
+    pub conversation_id: String,
+    
+    /// Request holding the lock
+    pub request: GenerationRequest,
+    
+    /// When the lock was granted
+    pub acquired_at: SystemTime,
+    
+    /// When the lock is released unless renewed
+    pub expires_at: SystemTime,
+}
+
+impl GenerationLock {
+    /// Whether the lock has run out at the given time
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Outcome of asking for a conversation's generation lock
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LockStatus {
+    /// The request holds the lock and may generate
+    Acquired { lock: GenerationLock },
+    
+    /// The request waits behind the current holder
+    Queued {
+        holder: GenerationLock,
+        /// 1-based position in the queue
+        position: usize,
+    },
+}
+
+/// Who is generating in a conversation and who is waiting
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationStatus {
+    /// Current lock holder
+    pub holder: Option<GenerationLock>,
+    
+    /// Waiting requests, in the order they will be granted
+    pub queue: Vec<GenerationRequest>,
+}
+
+/// Per-conversation generation locks
+///
+/// Only one generation runs per conversation at a time; other requests queue
+/// behind it and are granted in order as the holder releases the lock or lets
+/// it time out. Every device applies the same requests and releases, so they
+/// agree on the holder: when two requests race, the earlier one wins.
+#[derive(Debug)]
+pub struct GenerationLocks {
+    /// Lock timeout
+    timeout: Duration,
+    
+    /// Current holders by conversation ID
+    locks: HashMap<String, GenerationLock>,
+    
+    /// Waiting requests by conversation ID
+    queues: HashMap<String, VecDeque<GenerationRequest>>,
+}
+
+impl Default for GenerationLocks {
+    fn default() -> Self {
+        Self::new(GENERATION_LOCK_TIMEOUT)
+    }
+}
+
+impl GenerationLocks {
+    /// Create lock tracking with the given timeout
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            locks: HashMap::new(),
+            queues: HashMap::new(),
+        }
+    }
+    
+    /// Request the lock of a conversation, queueing behind the holder if it is taken
+    pub fn acquire(&mut self, conversation_id: &str, request: GenerationRequest) -> LockStatus {
+        self.expire_stale(SystemTime::now());
+        
+        let holder = match self.locks.get(conversation_id) {
+            Some(holder) if holder.request.request_id == request.request_id => {
+                return LockStatus::Acquired { lock: holder.clone() };
+            }
+            Some(holder) => holder.clone(),
+            None => {
+                return LockStatus::Acquired { lock: self.grant(conversation_id, request) };
+            }
+        };
+        
+        let queue = self.queues.entry(conversation_id.to_string()).or_default();
+        if let Some(index) = queue.iter().position(|r| r.request_id == request.request_id) {
+            return LockStatus::Queued { holder, position: index + 1 };
+        }
+        
+        // A request that raced the holder and was made first takes the lock
+        if request.precedes(&holder.request) {
+            let index = queue.iter().position(|r| holder.request.precedes(r)).unwrap_or(queue.len());
+            queue.insert(index, holder.request);
+            let lock = self.grant(conversation_id, request);
+            return LockStatus::Acquired { lock };
+        }
+        
+        // Keep the queue in request order so every device grants the same way
+        let index = queue.iter().position(|r| request.precedes(r)).unwrap_or(queue.len());
+        queue.insert(index, request);
+        let position = index + 1;
+        
+        record_gauge("collaboration.generation_queue", queue.len() as f64, None);
+        
+        LockStatus::Queued { holder, position }
+    }
+    
+    /// Extend the lock held by a request; fails if the request doesn't hold it
+    pub fn renew(&mut self, conversation_id: &str, request_id: &str) -> Result<GenerationLock> {
+        let timeout = self.timeout;
+        match self.locks.get_mut(conversation_id) {
+            Some(lock) if lock.request.request_id == request_id => {
+                lock.expires_at = SystemTime::now() + timeout;
+                Ok(lock.clone())
+            }
+            _ => Err(format!("Request {} doesn't hold the generation lock", request_id).into()),
+        }
+    }
+    
+    /// Release the lock held by a request, or withdraw it from the queue.
+    /// Returns the lock granted to the next request in line, if any.
+    pub fn release(&mut self, conversation_id: &str, request_id: &str) -> Option<GenerationLock> {
+        let holds_lock = self
+            .locks
+            .get(conversation_id)
+            .map_or(false, |lock| lock.request.request_id == request_id);
+        
+        if !holds_lock {
+            if let Some(queue) = self.queues.get_mut(conversation_id) {
+                queue.retain(|r| r.request_id != request_id);
+            }
+            return None;
+        }
+        
+        self.locks.remove(conversation_id);
+        self.grant_next(conversation_id)
+    }
+    
+    /// Release locks that weren't renewed in time; returns the locks granted in their place
+    pub fn expire_stale(&mut self, now: SystemTime) -> Vec<GenerationLock> {
+        let expired: Vec<String> = self
+            .locks
+            .iter()
+            .filter(|(_, lock)| lock.is_expired(now))
+            .map(|(id, _)| id.clone())
+            .collect();
+        
+        let mut granted = Vec::new();
+        for conversation_id in expired {
+            if let Some(lock) = self.locks.remove(&conversation_id) {
+                warn!(
+                    "Generation lock of {} on conversation {} timed out",
+                    lock.request.user_name, conversation_id
+                );
+                record_counter("collaboration.generation_lock_expired", 1.0, None);
+            }
+            granted.extend(self.grant_next(&conversation_id));
+        }
+        
+        granted
+    }
+    
+    /// Forget the locks and queue of a conversation
+    pub fn remove_conversation(&mut self, conversation_id: &str) {
+        self.locks.remove(conversation_id);
+        self.queues.remove(conversation_id);
+    }
+    
+    /// Current lock holder of a conversation
+    pub fn holder(&self, conversation_id: &str) -> Option<GenerationLock> {
+        self.locks
+            .get(conversation_id)
+            .filter(|lock| !lock.is_expired(SystemTime::now()))
+            .cloned()
+    }
+    
+    /// Holder and queue of a conversation
+    pub fn status(&self, conversation_id: &str) -> GenerationStatus {
+        GenerationStatus {
+            holder: self.holder(conversation_id),
+            queue: self
+                .queues
+                .get(conversation_id)
+                .map(|queue| queue.iter().cloned().collect())
+                .unwrap_or_default(),
+        }
+    }
+    
+    /// Give the lock of a conversation to a request
+    fn grant(&mut self, conversation_id: &str, request: GenerationRequest) -> GenerationLock {
+        let now = SystemTime::now();
+        let lock = GenerationLock {
+            conversation_id: conversation_id.to_string(),
+            request,
+            acquired_at: now,
+            expires_at: now + self.timeout,
+        };
+        
+        debug!("Granted generation lock on {} to {}", conversation_id, lock.request.user_name);
+        record_counter("collaboration.generation_lock_granted", 1.0, None);
+        
+        self.locks.insert(conversation_id.to_string(), lock.clone());
+        lock
+    }
+    
+    /// Give the lock of a conversation to the next request in line
+    fn grant_next(&mut self, conversation_id: &str) -> Option<GenerationLock> {
+        let next = self.queues.get_mut(conversation_id)?.pop_front()?;
+        if self.queues.get(conversation_id).map_or(false, |queue| queue.is_empty()) {
+            self.queues.remove(conversation_id);
+        }
+        Some(self.grant(conversation_id, next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn request(id: &str, user: &str, requested_at: SystemTime) -> GenerationRequest {
+        GenerationRequest {
+            request_id: id.to_string(),
+            user_id: user.to_string(),
+            user_name: user.to_string(),
+            device_id: format!("{}-laptop", user),
+            requested_at,
+        }
+    }
+    
+    #[test]
+    fn requests_queue_behind_the_holder() {
+        let mut locks = GenerationLocks::default();
+        let now = SystemTime::now();
+        
+        let first = locks.acquire("conv", request("a", "alice", now));
+        assert!(matches!(first, LockStatus::Acquired { .. }));
+        
+        match locks.acquire("conv", request("b", "bob", now + Duration::from_secs(1))) {
+            LockStatus::Queued { holder, position } => {
+                assert_eq!(holder.request.user_name, "alice");
+                assert_eq!(position, 1);
+            }
+            other => panic!("expected to queue, got {:?}", other),
+        }
+        
+        // Asking again keeps the place in line
+        let again = locks.acquire("conv", request("b", "bob", now + Duration::from_secs(1)));
+        assert!(matches!(again, LockStatus::Queued { position: 1, .. }));
+        
+        // Other conversations aren't affected
+        assert!(matches!(locks.acquire("other", request("c", "carol", now)), LockStatus::Acquired { .. }));
+        
+        let next = locks.release("conv", "a").unwrap();
+        assert_eq!(next.request.request_id, "b");
+        assert!(locks.status("conv").queue.is_empty());
+        assert!(locks.release("conv", "b").is_none());
+        assert!(locks.holder("conv").is_none());
+    }
+    
+    #[test]
+    fn the_earlier_of_two_racing_requests_wins() {
+        let mut locks = GenerationLocks::default();
+        let now = SystemTime::now();
+        
+        // This device granted bob's request before alice's earlier one arrived
+        locks.acquire("conv", request("b", "bob", now + Duration::from_millis(5)));
+        let status = locks.acquire("conv", request("a", "alice", now));
+        
+        assert!(matches!(status, LockStatus::Acquired { .. }));
+        let status = locks.status("conv");
+        assert_eq!(status.holder.unwrap().request.request_id, "a");
+        assert_eq!(status.queue[0].request_id, "b");
+    }
+    
+    #[test]
+    fn stale_locks_pass_to_the_next_request() {
+        let mut locks = GenerationLocks::default();
+        let now = SystemTime::now();
+        locks.acquire("conv", request("a", "alice", now));
+        locks.acquire("conv", request("b", "bob", now + Duration::from_secs(1)));
+        
+        assert!(locks.expire_stale(now).is_empty());
+        assert!(locks.renew("conv", "b").is_err());
+        assert!(locks.renew("conv", "a").is_ok());
+        
+        let granted = locks.expire_stale(now + GENERATION_LOCK_TIMEOUT * 2);
+        assert_eq!(granted.len(), 1);
+        assert_eq!(granted[0].request.request_id, "b");
+    }
+}
@@ -5,8 +5,15 @@
 // - Conflict resolution
 // - Cross-device state persistence
 // - Operational transformation for concurrent edits
+// - Delta sync: only changed messages/metadata are shipped, as compressed,
+//   sequence-numbered batches; changes that arrive ahead of a gap are held
+//   until the missing ones are sent again
+// - Generation locks: requests for and releases of a conversation's
+//   generation lock travel as changes, so every device agrees on the holder
 
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime};
@@ -14,6 +21,7 @@ use std::time::{Duration, Instant, SystemTime};
 use log::{debug, info, warn, error};
 use serde::{Serialize, Deserialize};
 
+use crate::collaboration::sessions::{GenerationLock, GenerationLocks, GenerationRequest, LockStatus};
 use crate::error::Result;
 use crate::models::messages::{Conversation, Message};
 use crate::observability::metrics::{record_counter, record_gauge, record_histogram};
@@ -41,6 +49,15 @@ pub enum Operation {
     
     /// Set conversation title
     SetTitle(String),
+    
+    /// Ask for the conversation's generation lock
+    RequestGeneration(GenerationRequest),
+    
+    /// Extend a held generation lock
+    RenewGeneration(String),
+    
+    /// Release a generation lock or withdraw a queued request
+    ReleaseGeneration(String),
 }
 
 /// Change record for syncing
@@ -69,8 +86,54 @@ pub struct Change {
     
     /// Vector clock for causality tracking
     pub vector_clock: HashMap<String, u64>,
+    
+    /// Per-conversation sequence number assigned by the originating device
+    #[serde(default)]
+    pub sequence: u64,
+}
+
+/// Compression level used for delta payloads
+const DELTA_COMPRESSION_LEVEL: i32 = 3;
+
+/// Applied changes kept per conversation for peers catching up
+const MAX_APPLIED_CHANGES: usize = 1000;
+
+/// Out-of-order changes held per conversation while waiting for a gap to fill
+const MAX_PENDING_CHANGES: usize = 500;
+
+/// A batch of changes to one conversation, as shipped between devices
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeltaPayload {
+    /// Conversation ID
+    pub conversation_id: String,
+    
+    /// Device that produced the batch
+    pub device_id: String,
+    
+    /// Highest sequence number the receiver is known to have
+    pub base_sequence: u64,
+    
+    /// Changes after `base_sequence`, in order
+    pub changes: Vec<Change>,
 }
 
+impl DeltaPayload {
+    /// Serialize and compress the payload
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let json = serde_json::to_vec(self)
+            .map_err(|e| format!("Failed to serialize delta: {}", e))?;
+        zstd::encode_all(&json[..], DELTA_COMPRESSION_LEVEL)
+            .map_err(|e| format!("Failed to compress delta: {}", e).into())
+    }
+    
+    /// Decompress and deserialize a payload
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let json = zstd::decode_all(bytes)
+            .map_err(|e| format!("Failed to decompress delta: {}", e))?;
+        serde_json::from_slice(&json)
+            .map_err(|e| format!("Failed to deserialize delta: {}", e).into())
+    }
+}
 /// Status of a sync operation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SyncStatus {
@@ -87,6 +150,19 @@ pub enum SyncStatus {
     Conflict,
 }
 
+/// Changes to ask a device for again, after a gap in its sequence numbers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResyncRequest {
+    /// Conversation ID
+    pub conversation_id: String,
+    
+    /// Device whose changes are missing
+    pub device_id: String,
+    
+    /// Last sequence number applied from it; the device resends what came after
+    pub after_sequence: u64,
+}
+
 /// Active conversation being synchronized
 struct SyncedConversation {
     /// Conversation ID
@@ -101,16 +177,96 @@ struct SyncedConversation {
     /// Vector clock tracking causality
     vector_clock: HashMap<String, u64>,
     
-    /// Pending changes to be applied
+    /// Changes received ahead of a gap, applied once the gap is filled
     pending_changes: VecDeque<Change>,
     
+    /// Last sequence number applied from each device with missing changes
+    missing: HashMap<String, u64>,
+    
     /// Applied changes
     applied_changes: Vec<Change>,
     
     /// Last sync status
     last_status: SyncStatus,
+    
+    /// Sequence number of the last local change
+    sequence: u64,
+    
+    /// Highest sequence number applied from each remote device
+    peer_sequences: HashMap<String, u64>,
+    
+    /// Content hash of each message as last synced
+    message_hashes: HashMap<String, u64>,
+    
+    /// Serialized size of each message as last synced
+    message_sizes: HashMap<String, usize>,
+    
+    /// Title as last synced
+    title: Option<String>,
+    
+    /// Metadata as last synced
+    metadata: serde_json::Map<String, serde_json::Value>,
 }
 
+impl SyncedConversation {
+    fn new(id: &str, session_id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            session_id: session_id.to_string(),
+            last_sync: Instant::now(),
+            vector_clock: HashMap::new(),
+            pending_changes: VecDeque::new(),
+            missing: HashMap::new(),
+            applied_changes: Vec::new(),
+            last_status: SyncStatus::Success,
+            sequence: 0,
+            peer_sequences: HashMap::new(),
+            message_hashes: HashMap::new(),
+            message_sizes: HashMap::new(),
+            title: None,
+            metadata: serde_json::Map::new(),
+        }
+    }
+    
+    /// Keep an applied change, dropping the oldest past the cap
+    fn remember(&mut self, change: Change) {
+        self.applied_changes.push(change);
+        if self.applied_changes.len() > MAX_APPLIED_CHANGES {
+            let excess = self.applied_changes.len() - MAX_APPLIED_CHANGES;
+            self.applied_changes.drain(..excess);
+        }
+    }
+    
+    /// Hold a change that arrived ahead of a gap
+    fn hold(&mut self, change: Change) {
+        let held = self
+            .pending_changes
+            .iter()
+            .any(|c| c.device_id == change.device_id && c.sequence == change.sequence);
+        if held {
+            return;
+        }
+        if self.pending_changes.len() >= MAX_PENDING_CHANGES {
+            self.pending_changes.pop_front();
+        }
+        self.pending_changes.push_back(change);
+    }
+    
+    /// Bytes a full snapshot of the conversation would take
+    fn snapshot_bytes(&self) -> usize {
+        let metadata_bytes = serde_json::to_vec(&self.metadata).map(|v| v.len()).unwrap_or(0);
+        self.message_sizes.values().sum::<usize>()
+            + self.title.as_ref().map(|t| t.len()).unwrap_or(0)
+            + metadata_bytes
+    }
+}
+
+/// Hash a message's serialized form
+fn message_hash(serialized: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    serialized.hash(&mut hasher);
+    hasher.finish()
+}
 /// Synchronization manager for cross-device data sync
 pub struct SyncManager {
     /// User ID
@@ -136,6 +292,9 @@ pub struct SyncManager {
     
     /// Statistics
     statistics: Arc<RwLock<SyncStatistics>>,
+    
+    /// Generation locks of the synced conversations
+    generation_locks: Arc<Mutex<GenerationLocks>>,
 }
 
 impl SyncManager {
@@ -156,8 +315,11 @@ impl SyncManager {
                 conflicts_resolved: 0,
                 bytes_sent: 0,
                 bytes_received: 0,
+                full_sync_bytes: 0,
+                delta_bytes: 0,
                 last_sync_time: None,
             })),
+            generation_locks: Arc::new(Mutex::new(GenerationLocks::default())),
         })
     }
     
@@ -172,9 +334,19 @@ impl SyncManager {
         let outgoing_changes = self.outgoing_changes.clone();
         let statistics = self.statistics.clone();
         let sync_interval = self.sync_interval_ms;
+        let device_id = self.device_id.clone();
+        let generation_locks = self.generation_locks.clone();
         
         thread::spawn(move || {
             while *running.read().unwrap() {
+                // Release generation locks whose holder stopped renewing them
+                for lock in generation_locks.lock().unwrap().expire_stale(SystemTime::now()) {
+                    info!(
+                        "Generation lock on {} passed to {}",
+                        lock.conversation_id, lock.request.user_name
+                    );
+                }
+                
                 // Process incoming changes
                 let mut incoming = incoming_changes.lock().unwrap();
                 if !incoming.is_empty() {
@@ -192,22 +364,45 @@ impl SyncManager {
                     incoming.clear();
                 }
                 
-                // Process outgoing changes
+                // Process outgoing changes as one compressed delta per conversation
                 let mut outgoing = outgoing_changes.lock().unwrap();
                 if !outgoing.is_empty() {
                     let count = outgoing.len();
                     
-                    // In a real implementation, we would send these changes to other clients
-                    debug!("Sending {} outgoing changes", count);
+                    let mut batches: HashMap<String, Vec<Change>> = HashMap::new();
+                    for change in outgoing.drain(..) {
+                        batches.entry(change.conversation_id.clone()).or_default().push(change);
+                    }
+                    
+                    let mut bytes_sent = 0;
+                    let mut delta_bytes = 0;
+                    for (conversation_id, changes) in batches {
+                        let payload = DeltaPayload {
+                            conversation_id,
+                            device_id: device_id.clone(),
+                            base_sequence: changes.first().map(|c| c.sequence.saturating_sub(1)).unwrap_or(0),
+                            changes,
+                        };
+                        
+                        delta_bytes += serde_json::to_vec(&payload).map(|v| v.len()).unwrap_or(0);
+                        match payload.encode() {
+                            // In a real implementation, we would send the payload to other clients
+                            Ok(bytes) => bytes_sent += bytes.len(),
+                            Err(e) => warn!("Failed to encode delta: {}", e),
+                        }
+                    }
+                    
+                    debug!("Sent {} outgoing changes in {} bytes", count, bytes_sent);
                     
                     // Update statistics
                     let mut stats = statistics.write().unwrap();
                     stats.messages_sent += count;
                     stats.sync_operations += count;
+                    stats.bytes_sent += bytes_sent;
+                    stats.delta_bytes += delta_bytes;
                     stats.last_sync_time = Some(SystemTime::now());
                     
-                    // Clear processed changes
-                    outgoing.clear();
+                    record_counter("collaboration.sync_bytes_sent", bytes_sent as f64, None);
                 }
                 
                 // Sleep for sync interval
@@ -241,15 +436,7 @@ impl SyncManager {
     /// Initialize synchronization for a session
     pub fn init_session(&mut self, session_id: &str, conversation_id: &str) -> Result<()> {
         // Create a synced conversation
-        let conversation = SyncedConversation {
-            id: conversation_id.to_string(),
-            session_id: session_id.to_string(),
-            last_sync: Instant::now(),
-            vector_clock: HashMap::new(),
-            pending_changes: VecDeque::new(),
-            applied_changes: Vec::new(),
-            last_status: SyncStatus::Success,
-        };
+        let conversation = SyncedConversation::new(conversation_id, session_id);
         
         // Store it
         self.conversations.insert(conversation_id.to_string(), conversation);
@@ -267,15 +454,7 @@ impl SyncManager {
         }
         
         // Create a synced conversation
-        let conversation = SyncedConversation {
-            id: conversation_id.to_string(),
-            session_id: session_id.to_string(),
-            last_sync: Instant::now(),
-            vector_clock: HashMap::new(),
-            pending_changes: VecDeque::new(),
-            applied_changes: Vec::new(),
-            last_status: SyncStatus::Success,
-        };
+        let conversation = SyncedConversation::new(conversation_id, session_id);
         
         // Store it
         self.conversations.insert(conversation_id.to_string(), conversation);
@@ -294,9 +473,12 @@ impl SyncManager {
             .collect();
             
         // Remove conversations
+        let mut generation_locks = self.generation_locks.lock().unwrap();
         for id in conversation_ids {
             self.conversations.remove(&id);
+            generation_locks.remove_conversation(&id);
         }
+        drop(generation_locks);
         
         info!("Left sync for session {}", session_id);
         
@@ -317,12 +499,39 @@ impl SyncManager {
             }
         };
         
+        // Only ship the title and metadata keys that changed
+        let mut operations = Vec::new();
+        if synced.title.as_deref() != Some(conversation.title.as_str()) {
+            operations.push(Operation::SetTitle(conversation.title.clone()));
+            synced.title = Some(conversation.title.clone());
+        }
+        if let Some(metadata) = conversation.metadata.as_object() {
+            for (key, value) in metadata {
+                if synced.metadata.get(key) != Some(value) {
+                    operations.push(Operation::UpdateMetadata {
+                        key: key.clone(),
+                        value: value.to_string(),
+                    });
+                    synced.metadata.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        
+        let full_bytes = synced.snapshot_bytes();
+        let changes: Vec<Change> = operations
+            .into_iter()
+            .map(|operation| Self::record_local_change(synced, &self.user_id, &self.device_id, session_id, operation))
+            .collect();
+        
         // Update last sync time
         synced.last_sync = Instant::now();
         
+        self.outgoing_changes.lock().unwrap().extend(changes);
+        
         // Update statistics
         let mut stats = self.statistics.write().unwrap();
         stats.sync_operations += 1;
+        stats.full_sync_bytes += full_bytes;
         stats.last_sync_time = Some(SystemTime::now());
         
         record_counter("collaboration.sync_operation", 1.0, None);
@@ -330,6 +539,162 @@ impl SyncManager {
         Ok(())
     }
     
+    /// Synchronize a conversation's messages, shipping only added, edited and deleted ones
+    pub fn sync_messages(&mut self, session_id: &str, conversation_id: &str, messages: &[Message]) -> Result<usize> {
+        if !self.conversations.contains_key(conversation_id) {
+            self.init_session(session_id, conversation_id)?;
+        }
+        let synced = self.conversations.get_mut(conversation_id).unwrap();
+        
+        let mut operations = Vec::new();
+        let mut seen = std::collections::HashSet::new();
+        
+        for message in messages {
+            let serialized = serde_json::to_vec(message)
+                .map_err(|e| format!("Failed to serialize message: {}", e))?;
+            let hash = message_hash(&serialized);
+            seen.insert(message.id.clone());
+            
+            match synced.message_hashes.get(&message.id) {
+                None => operations.push(Operation::AddMessage(message.clone())),
+                Some(known) if *known != hash => operations.push(Operation::UpdateMessage {
+                    id: message.id.clone(),
+                    content: serde_json::to_string(&message.content)
+                        .map_err(|e| format!("Failed to serialize message content: {}", e))?,
+                }),
+                Some(_) => continue,
+            }
+            
+            synced.message_hashes.insert(message.id.clone(), hash);
+            synced.message_sizes.insert(message.id.clone(), serialized.len());
+        }
+        
+        let deleted: Vec<String> = synced
+            .message_hashes
+            .keys()
+            .filter(|id| !seen.contains(*id))
+            .cloned()
+            .collect();
+        for id in deleted {
+            synced.message_hashes.remove(&id);
+            synced.message_sizes.remove(&id);
+            operations.push(Operation::DeleteMessage(id));
+        }
+        
+        let full_bytes = synced.snapshot_bytes();
+        let count = operations.len();
+        let changes: Vec<Change> = operations
+            .into_iter()
+            .map(|operation| Self::record_local_change(synced, &self.user_id, &self.device_id, session_id, operation))
+            .collect();
+        synced.last_sync = Instant::now();
+        
+        self.outgoing_changes.lock().unwrap().extend(changes);
+        
+        let mut stats = self.statistics.write().unwrap();
+        stats.sync_operations += 1;
+        stats.full_sync_bytes += full_bytes;
+        stats.last_sync_time = Some(SystemTime::now());
+        
+        debug!("Delta sync for conversation {}: {} change(s)", conversation_id, count);
+        
+        Ok(count)
+    }
+    
+    /// Record a local change: bump the vector clock and sequence number
+    fn record_local_change(
+        synced: &mut SyncedConversation,
+        user_id: &str,
+        device_id: &str,
+        session_id: &str,
+        operation: Operation,
+    ) -> Change {
+        *synced.vector_clock.entry(user_id.to_string()).or_insert(0) += 1;
+        synced.sequence += 1;
+        
+        let change = Change {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            device_id: device_id.to_string(),
+            session_id: session_id.to_string(),
+            conversation_id: synced.id.clone(),
+            operation,
+            timestamp: SystemTime::now(),
+            vector_clock: synced.vector_clock.clone(),
+            sequence: synced.sequence,
+        };
+        
+        synced.remember(change.clone());
+        change
+    }
+    
+    /// Changes to a conversation after the given sequence number, for a peer catching up
+    pub fn changes_since(&self, conversation_id: &str, sequence: u64) -> Vec<Change> {
+        self.conversations
+            .get(conversation_id)
+            .map(|synced| {
+                synced
+                    .applied_changes
+                    .iter()
+                    .filter(|c| c.device_id == self.device_id && c.sequence > sequence)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    
+    /// Changes to ask other devices for again, one per device with a gap
+    pub fn resync_requests(&self) -> Vec<ResyncRequest> {
+        let mut requests: Vec<ResyncRequest> = self
+            .conversations
+            .values()
+            .flat_map(|synced| {
+                synced.missing.iter().map(move |(device_id, after_sequence)| ResyncRequest {
+                    conversation_id: synced.id.clone(),
+                    device_id: device_id.clone(),
+                    after_sequence: *after_sequence,
+                })
+            })
+            .collect();
+        requests.sort_by(|a, b| (&a.conversation_id, &a.device_id).cmp(&(&b.conversation_id, &b.device_id)));
+        requests
+    }
+    
+    /// Apply a compressed delta received from another device; a delta made
+    /// against another sequence number than the last one applied from that
+    /// device is rejected and the missing changes asked for again
+    pub fn apply_delta(&mut self, bytes: &[u8]) -> Result<Vec<SyncStatus>> {
+        let payload = DeltaPayload::decode(bytes)?;
+        
+        self.statistics.write().unwrap().bytes_received += bytes.len();
+        record_counter("collaboration.sync_bytes_received", bytes.len() as f64, None);
+        
+        let last = self
+            .conversations
+            .get(&payload.conversation_id)
+            .and_then(|synced| synced.peer_sequences.get(&payload.device_id))
+            .copied()
+            .unwrap_or(0);
+        if payload.base_sequence != last {
+            if let Some(synced) = self.conversations.get_mut(&payload.conversation_id) {
+                synced.missing.insert(payload.device_id.clone(), last);
+            }
+            record_counter("collaboration.delta_rejected", 1.0, None);
+            return Err(format!(
+                "Delta from {} for conversation {} follows change {}, but change {} was applied last",
+                payload.device_id, payload.conversation_id, payload.base_sequence, last
+            )
+            .into());
+        }
+        
+        let mut results = Vec::new();
+        for change in payload.changes {
+            results.push(self.process_change(change)?);
+        }
+        
+        Ok(results)
+    }
+    
     /// Send a message through sync
     pub fn send_message(&mut self, session_id: &str, message: &Message) -> Result<()> {
         let conversation_id = &message.conversation_id;
@@ -344,27 +709,23 @@ impl SyncManager {
             }
         };
         
-        // Increment vector clock for this user
-        let user_count = synced.vector_clock.entry(self.user_id.clone()).or_insert(0);
-        *user_count += 1;
+        // Remember the message so later delta syncs don't resend it
+        if let Ok(serialized) = serde_json::to_vec(message) {
+            synced.message_hashes.insert(message.id.clone(), message_hash(&serialized));
+            synced.message_sizes.insert(message.id.clone(), serialized.len());
+        }
         
         // Create change record
-        let change = Change {
-            id: uuid::Uuid::new_v4().to_string(),
-            user_id: self.user_id.clone(),
-            device_id: self.device_id.clone(),
-            session_id: session_id.to_string(),
-            conversation_id: conversation_id.to_string(),
-            operation: Operation::AddMessage(message.clone()),
-            timestamp: SystemTime::now(),
-            vector_clock: synced.vector_clock.clone(),
-        };
+        let change = Self::record_local_change(
+            synced,
+            &self.user_id,
+            &self.device_id,
+            session_id,
+            Operation::AddMessage(message.clone()),
+        );
         
         // Add to outgoing changes
-        self.outgoing_changes.lock().unwrap().push_back(change.clone());
-        
-        // Add to applied changes
-        synced.applied_changes.push(change);
+        self.outgoing_changes.lock().unwrap().push_back(change);
         
         // Update last sync time
         synced.last_sync = Instant::now();
@@ -379,27 +740,170 @@ impl SyncManager {
         Ok(())
     }
     
-    /// Process an incoming change
+    /// Ask for the generation lock of a conversation and tell the other devices
+    pub fn request_generation(
+        &mut self,
+        session_id: &str,
+        conversation_id: &str,
+        request: GenerationRequest,
+    ) -> Result<LockStatus> {
+        let status = self.generation_locks.lock().unwrap().acquire(conversation_id, request.clone());
+        self.send_lock_operation(session_id, conversation_id, Operation::RequestGeneration(request))?;
+        
+        record_counter("collaboration.generation_requested", 1.0, None);
+        
+        Ok(status)
+    }
+    
+    /// Extend a held generation lock and tell the other devices
+    pub fn renew_generation(
+        &mut self,
+        session_id: &str,
+        conversation_id: &str,
+        request_id: &str,
+    ) -> Result<GenerationLock> {
+        let lock = self.generation_locks.lock().unwrap().renew(conversation_id, request_id)?;
+        self.send_lock_operation(session_id, conversation_id, Operation::RenewGeneration(request_id.to_string()))?;
+        
+        Ok(lock)
+    }
+    
+    /// Release a generation lock or withdraw a queued request, and tell the
+    /// other devices; returns the lock granted to the next request in line
+    pub fn release_generation(
+        &mut self,
+        session_id: &str,
+        conversation_id: &str,
+        request_id: &str,
+    ) -> Result<Option<GenerationLock>> {
+        let next = self.generation_locks.lock().unwrap().release(conversation_id, request_id);
+        self.send_lock_operation(session_id, conversation_id, Operation::ReleaseGeneration(request_id.to_string()))?;
+        
+        Ok(next)
+    }
+    
+    /// Generation locks of the synced conversations
+    pub fn generation_locks(&self) -> Arc<Mutex<GenerationLocks>> {
+        self.generation_locks.clone()
+    }
+    
+    /// Queue a generation lock operation for the other devices
+    fn send_lock_operation(&mut self, session_id: &str, conversation_id: &str, operation: Operation) -> Result<()> {
+        if !self.conversations.contains_key(conversation_id) {
+            self.init_session(session_id, conversation_id)?;
+        }
+        let synced = self.conversations.get_mut(conversation_id).unwrap();
+        
+        let change = Self::record_local_change(synced, &self.user_id, &self.device_id, session_id, operation);
+        self.outgoing_changes.lock().unwrap().push_back(change);
+        
+        Ok(())
+    }
+    
+    /// Apply a generation lock operation from another device
+    fn apply_lock_operation(&self, conversation_id: &str, operation: &Operation) {
+        let mut locks = self.generation_locks.lock().unwrap();
+        match operation {
+            Operation::RequestGeneration(request) => {
+                locks.acquire(conversation_id, request.clone());
+            }
+            Operation::RenewGeneration(request_id) => {
+                if let Err(e) = locks.renew(conversation_id, request_id) {
+                    debug!("Ignoring generation lock renewal: {}", e);
+                }
+            }
+            Operation::ReleaseGeneration(request_id) => {
+                locks.release(conversation_id, request_id);
+            }
+            _ => {}
+        }
+    }
+    
+    /// Process an incoming change; changes ahead of a gap in the sending
+    /// device's sequence numbers are held until the gap is filled
     pub fn process_change(&mut self, change: Change) -> Result<SyncStatus> {
-        let conversation_id = &change.conversation_id;
+        let conversation_id = change.conversation_id.clone();
         
         // Get synced conversation
-        let synced = match self.conversations.get_mut(conversation_id) {
-            Some(conv) => conv,
-            None => {
-                // Initialize new sync if session exists
-                if let Some((session_id, _)) = self.conversations.iter()
-                    .find(|(_, conv)| conv.session_id == change.session_id) {
-                    self.init_session(&session_id, conversation_id)?;
-                    self.conversations.get_mut(conversation_id).unwrap()
-                } else {
-                    return Err(format!("No active session for change in conversation {}", conversation_id).into());
-                }
+        if !self.conversations.contains_key(&conversation_id) {
+            // Initialize new sync if session exists
+            if let Some((session_id, _)) = self.conversations.iter()
+                .find(|(_, conv)| conv.session_id == change.session_id)
+                .map(|(id, conv)| (conv.session_id.clone(), id.clone())) {
+                self.init_session(&session_id, &conversation_id)?;
+            } else {
+                return Err(format!("No active session for change in conversation {}", conversation_id).into());
+            }
+        }
+        let synced = self.conversations.get_mut(&conversation_id).unwrap();
+        
+        if change.sequence > 0 {
+            let last = synced.peer_sequences.get(&change.device_id).copied().unwrap_or(0);
+            
+            // Skip changes already applied from this device
+            if change.sequence <= last {
+                debug!("Skipping duplicate change {} from {}", change.sequence, change.device_id);
+                return Ok(SyncStatus::Success);
+            }
+            
+            // Hold changes that skip ahead until the missing ones are sent again
+            if change.sequence > last + 1 {
+                warn!(
+                    "Changes {}-{} from {} to conversation {} are missing; asking for them again",
+                    last + 1,
+                    change.sequence - 1,
+                    change.device_id,
+                    conversation_id
+                );
+                synced.missing.insert(change.device_id.clone(), last);
+                synced.hold(change);
+                record_counter("collaboration.change_held", 1.0, None);
+                return Ok(SyncStatus::InProgress);
             }
+        }
+        
+        let device_id = change.device_id.clone();
+        let sequenced = change.sequence > 0;
+        let mut status = self.apply_change(change)?;
+        if !sequenced {
+            return Ok(status);
+        }
+        
+        // Apply held changes that are now next in line
+        loop {
+            let synced = self.conversations.get_mut(&conversation_id).unwrap();
+            let next = synced.peer_sequences.get(&device_id).copied().unwrap_or(0) + 1;
+            let Some(index) = synced
+                .pending_changes
+                .iter()
+                .position(|c| c.device_id == device_id && c.sequence == next)
+            else {
+                break;
+            };
+            let held = synced.pending_changes.remove(index).unwrap();
+            if self.apply_change(held)? == SyncStatus::Conflict {
+                status = SyncStatus::Conflict;
+            }
+        }
+        
+        let synced = self.conversations.get_mut(&conversation_id).unwrap();
+        if !synced.pending_changes.iter().any(|c| c.device_id == device_id) {
+            synced.missing.remove(&device_id);
+        }
+        
+        Ok(status)
+    }
+    
+    /// Apply a change from another device that is next in line
+    fn apply_change(&mut self, change: Change) -> Result<SyncStatus> {
+        let conversation_id = change.conversation_id.clone();
+        let mut vector_clock = match self.conversations.get(&conversation_id) {
+            Some(synced) => synced.vector_clock.clone(),
+            None => return Err(format!("No active session for change in conversation {}", conversation_id).into()),
         };
         
         // Check for conflicts
-        let has_conflict = self.detect_conflict(&synced.vector_clock, &change.vector_clock);
+        let has_conflict = self.detect_conflict(&vector_clock, &change.vector_clock);
         
         if has_conflict {
             // Handle conflict based on operation type
@@ -416,17 +920,27 @@ impl SyncManager {
         }
         
         // Merge vector clocks
-        self.merge_vector_clocks(&mut synced.vector_clock, &change.vector_clock);
+        self.merge_vector_clocks(&mut vector_clock, &change.vector_clock);
         
         // Apply the change
         // In a real implementation, we would apply the change to the conversation
+        let operation = change.operation.clone();
+        
+        let synced = self.conversations.get_mut(&conversation_id).unwrap();
+        synced.vector_clock = vector_clock;
+        if change.sequence > 0 {
+            synced.peer_sequences.insert(change.device_id.clone(), change.sequence);
+        }
         
         // Add to applied changes
-        synced.applied_changes.push(change);
+        synced.remember(change);
         
         // Update last sync time
         synced.last_sync = Instant::now();
         
+        // Lock operations take effect on this device too
+        self.apply_lock_operation(&conversation_id, &operation);
+        
         // Update statistics
         let mut stats = self.statistics.write().unwrap();
         stats.messages_received += 1;
@@ -501,6 +1015,128 @@ pub struct SyncStatistics {
     /// Bytes received
     pub bytes_received: usize,
     
+    /// Bytes full conversation snapshots would have taken
+    pub full_sync_bytes: usize,
+    
+    /// Uncompressed size of the deltas sent
+    pub delta_bytes: usize,
+    
     /// Last sync time
     pub last_sync_time: Option<SystemTime>,
 }
+
+impl SyncStatistics {
+    /// Bytes saved compared to shipping full conversations
+    pub fn bytes_saved(&self) -> usize {
+        self.full_sync_bytes.saturating_sub(self.bytes_sent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn manager() -> SyncManager {
+        let mut manager = SyncManager::new("alice".to_string(), "alice-laptop".to_string(), 1000).unwrap();
+        manager.init_session("session", "conv").unwrap();
+        manager
+    }
+    
+    fn change(sequence: u64) -> Change {
+        Change {
+            id: format!("change-{}", sequence),
+            user_id: "bob".to_string(),
+            device_id: "bob-laptop".to_string(),
+            session_id: "session".to_string(),
+            conversation_id: "conv".to_string(),
+            operation: Operation::SetTitle(format!("Title {}", sequence)),
+            timestamp: SystemTime::now(),
+            vector_clock: HashMap::from([("bob".to_string(), sequence)]),
+            sequence,
+        }
+    }
+    
+    fn delta(base_sequence: u64, sequences: &[u64]) -> Vec<u8> {
+        DeltaPayload {
+            conversation_id: "conv".to_string(),
+            device_id: "bob-laptop".to_string(),
+            base_sequence,
+            changes: sequences.iter().map(|&sequence| change(sequence)).collect(),
+        }
+        .encode()
+        .unwrap()
+    }
+    
+    fn applied(manager: &SyncManager) -> Vec<u64> {
+        manager.conversations["conv"].applied_changes.iter().map(|c| c.sequence).collect()
+    }
+    
+    #[test]
+    fn changes_after_a_gap_wait_for_the_missing_ones() {
+        let mut manager = manager();
+        assert_eq!(manager.process_change(change(1)).unwrap(), SyncStatus::Success);
+        assert_eq!(manager.process_change(change(3)).unwrap(), SyncStatus::InProgress);
+        assert_eq!(manager.process_change(change(4)).unwrap(), SyncStatus::InProgress);
+        assert_eq!(applied(&manager), vec![1]);
+        assert_eq!(
+            manager.resync_requests(),
+            vec![ResyncRequest {
+                conversation_id: "conv".to_string(),
+                device_id: "bob-laptop".to_string(),
+                after_sequence: 1,
+            }]
+        );
+        
+        // The missing change lets the held ones through, in order
+        assert_eq!(manager.process_change(change(2)).unwrap(), SyncStatus::Success);
+        assert_eq!(applied(&manager), vec![1, 2, 3, 4]);
+        assert!(manager.resync_requests().is_empty());
+        assert!(manager.conversations["conv"].pending_changes.is_empty());
+    }
+    
+    #[test]
+    fn duplicate_changes_are_applied_once() {
+        let mut manager = manager();
+        manager.process_change(change(1)).unwrap();
+        assert_eq!(manager.process_change(change(1)).unwrap(), SyncStatus::Success);
+        
+        manager.process_change(change(3)).unwrap();
+        manager.process_change(change(3)).unwrap();
+        assert_eq!(manager.conversations["conv"].pending_changes.len(), 1);
+        
+        manager.process_change(change(2)).unwrap();
+        manager.process_change(change(2)).unwrap();
+        assert_eq!(applied(&manager), vec![1, 2, 3]);
+    }
+    
+    #[test]
+    fn deltas_against_another_base_are_rejected() {
+        let mut manager = manager();
+        manager.apply_delta(&delta(0, &[1, 2])).unwrap();
+        
+        // Changes 3 and 4 never arrived
+        assert!(manager.apply_delta(&delta(4, &[5, 6])).is_err());
+        assert_eq!(applied(&manager), vec![1, 2]);
+        assert_eq!(manager.resync_requests()[0].after_sequence, 2);
+        
+        // A stale base is rejected too
+        assert!(manager.apply_delta(&delta(1, &[2, 3])).is_err());
+        
+        manager.apply_delta(&delta(2, &[3, 4, 5, 6])).unwrap();
+        assert_eq!(applied(&manager), vec![1, 2, 3, 4, 5, 6]);
+        assert!(manager.resync_requests().is_empty());
+    }
+    
+    #[test]
+    fn applied_history_is_capped() {
+        let mut manager = manager();
+        let total = MAX_APPLIED_CHANGES as u64 + 10;
+        for sequence in 1..=total {
+            manager.process_change(change(sequence)).unwrap();
+        }
+        let applied = applied(&manager);
+        assert_eq!(applied.len(), MAX_APPLIED_CHANGES);
+        assert_eq!(applied[0], 11);
+        assert_eq!(manager.conversations["conv"].peer_sequences["bob-laptop"], total);
+    }
+}
@@ -0,0 +1,33 @@
+use mcp_common::config::{get_settings, AccessibilitySettings};
+use mcp_common::events::{get_event_bus, names, Topic};
+
+/// Get the accessibility settings (screen reader, reduced motion, large text)
+#[tauri::command]
+pub fn get_accessibility_settings() -> AccessibilitySettings {
+    get_settings().lock().unwrap().accessibility.clone()
+}
+
+/// Whether the frontend may animate, combining the animation and reduced
+/// motion settings
+#[tauri::command]
+pub fn get_motion_allowed() -> bool {
+    let settings = get_settings();
+    let settings = settings.lock().unwrap();
+    settings.accessibility.allows_motion(&settings.ui)
+}
+
+/// Update the accessibility settings; open windows receive an
+/// `accessibility_changed` event
+#[tauri::command]
+pub fn update_accessibility_settings(accessibility: AccessibilitySettings) -> Result<(), String> {
+    {
+        let settings = get_settings();
+        let mut settings = settings.lock().unwrap();
+        settings.accessibility = accessibility.clone();
+        settings.save().map_err(|e| e.to_string())?;
+    }
+
+    let payload = serde_json::to_value(&accessibility).map_err(|e| e.to_string())?;
+    get_event_bus().emit(Topic::System, names::ACCESSIBILITY_CHANGED, payload);
+    Ok(())
+}
@@ -0,0 +1,246 @@
+use crate::tools::LocalTools;
+use log::warn;
+use mcp_common::agent::replay::{get_agent_run_store, ReplayFrame, ReplayOverrides, Rerun, RunDiff, RunSummary};
+use mcp_common::agent::team::{get_agent_team as agent_team, AgentProfile, AgentTeam, ExecutionGraph, TeamRunner};
+use mcp_common::agent::{get_agent_runs, AgentBudget, AgentControl, AgentRunner, AgentTranscript};
+use mcp_common::service::ChatService;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+
+/// Chat service agent runs talk to the model through
+static AGENT_CHAT: Lazy<Arc<ChatService>> = Lazy::new(|| Arc::new(ChatService::new(mcp_common::get_mcp_service())));
+
+/// Controls of the run in progress in a conversation
+fn run_in(conversation_id: &str) -> Result<AgentControl, String> {
+    get_agent_runs()
+        .get(conversation_id)
+        .ok_or_else(|| format!("No agent is running in conversation {}", conversation_id))
+}
+
+/// Start an agent working on a task in a conversation with the local tools.
+/// Its steps arrive as `agent_step` events and its end as an
+/// `agent_status_changed` event.
+#[tauri::command]
+pub async fn start_agent_run(
+    conversation_id: String,
+    task: String,
+    max_steps: Option<u32>,
+    max_seconds: Option<u64>,
+    max_tokens: Option<u64>,
+) -> Result<(), String> {
+    if get_agent_runs().get(&conversation_id).is_some() {
+        return Err(format!("An agent is already running in conversation {}", conversation_id));
+    }
+
+    let defaults = AgentBudget::default();
+    let budget = AgentBudget {
+        max_steps: max_steps.unwrap_or(defaults.max_steps),
+        max_seconds: max_seconds.unwrap_or(defaults.max_seconds),
+        max_tokens: max_tokens.unwrap_or(defaults.max_tokens),
+    };
+    let chat_service = AGENT_CHAT.clone();
+    let workspace = chat_service
+        .workspace(&conversation_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let environment = chat_service
+        .environment(&conversation_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tauri::async_runtime::spawn(async move {
+        let tools = LocalTools::new(workspace, environment);
+        if let Err(e) = AgentRunner::new(budget)
+            .run(&chat_service, &conversation_id, &task, &tools)
+            .await
+        {
+            warn!("Agent run in {} failed to start: {}", conversation_id, e);
+        }
+    });
+    Ok(())
+}
+
+/// Pause the agent running in a conversation after its current step
+#[tauri::command]
+pub fn pause_agent_run(conversation_id: String) -> Result<(), String> {
+    run_in(&conversation_id)?.pause();
+    Ok(())
+}
+
+/// Resume a paused agent
+#[tauri::command]
+pub fn resume_agent_run(conversation_id: String) -> Result<(), String> {
+    let control = run_in(&conversation_id)?;
+    if !control.is_paused() {
+        return Err(format!("The agent in conversation {} isn't paused", conversation_id));
+    }
+    control.resume();
+    Ok(())
+}
+
+/// Stop the agent running in a conversation after its current step
+#[tauri::command]
+pub fn abort_agent_run(conversation_id: String) -> Result<(), String> {
+    run_in(&conversation_id)?.abort();
+    Ok(())
+}
+
+/// Conversations with an agent running
+#[tauri::command]
+pub fn list_agent_runs() -> Result<Vec<String>, String> {
+    Ok(get_agent_runs().running())
+}
+
+/// Transcript of the latest agent run in a conversation
+#[tauri::command]
+pub async fn get_agent_transcript(conversation_id: String) -> Result<Option<AgentTranscript>, String> {
+    let conversation = AGENT_CHAT
+        .get_conversation(&conversation_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(AgentTranscript::latest(&conversation))
+}
+
+/// Start the agent team working on a task in a conversation with the local
+/// tools; the routing rules pick the first agent and handoffs the rest
+#[tauri::command]
+pub async fn start_team_run(conversation_id: String, task: String) -> Result<(), String> {
+    if get_agent_runs().get(&conversation_id).is_some() {
+        return Err(format!("An agent is already running in conversation {}", conversation_id));
+    }
+
+    let team = agent_team().get();
+    if team.agents.is_empty() {
+        return Err("The team has no agents".to_string());
+    }
+    let chat_service = AGENT_CHAT.clone();
+    let workspace = chat_service
+        .workspace(&conversation_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let environment = chat_service
+        .environment(&conversation_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tauri::async_runtime::spawn(async move {
+        let tools = LocalTools::new(workspace, environment);
+        if let Err(e) = TeamRunner::new(team)
+            .run(&chat_service, &conversation_id, &task, &tools)
+            .await
+        {
+            warn!("Team run in {} failed: {}", conversation_id, e);
+        }
+    });
+    Ok(())
+}
+
+/// The agents of the team and the rules routing tasks to them
+#[tauri::command]
+pub fn get_agent_team() -> Result<AgentTeam, String> {
+    Ok(agent_team().get())
+}
+
+/// Add an agent to the team, or replace the one of its name
+#[tauri::command]
+pub fn set_agent_profile(profile: AgentProfile) -> Result<(), String> {
+    agent_team().set_agent(profile).map_err(|e| e.to_string())
+}
+
+/// Remove an agent and the rules routing to it
+#[tauri::command]
+pub fn remove_agent_profile(name: String) -> Result<bool, String> {
+    agent_team().remove_agent(&name).map_err(|e| e.to_string())
+}
+
+/// Start tasks matching a pattern with an agent
+#[tauri::command]
+pub fn add_agent_route(pattern: String, agent: String) -> Result<(), String> {
+    agent_team().add_rule(&pattern, &agent).map_err(|e| e.to_string())
+}
+
+/// Remove a routing rule
+#[tauri::command]
+pub fn remove_agent_route(pattern: String) -> Result<bool, String> {
+    agent_team().remove_rule(&pattern).map_err(|e| e.to_string())
+}
+
+/// The agent runs of a conversation and the handoffs between them, as JSON
+/// or, with `dot`, in Graphviz's DOT language
+#[tauri::command]
+pub async fn export_agent_graph(conversation_id: String, dot: bool) -> Result<String, String> {
+    let conversation = AGENT_CHAT
+        .get_conversation(&conversation_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let graph = ExecutionGraph::of(&conversation);
+    if dot {
+        Ok(graph.to_dot())
+    } else {
+        serde_json::to_string_pretty(&graph).map_err(|e| e.to_string())
+    }
+}
+
+/// Agent runs kept for replays, newest first; only a conversation's with
+/// one given
+#[tauri::command]
+pub fn list_kept_agent_runs(conversation_id: Option<String>) -> Result<Vec<RunSummary>, String> {
+    Ok(get_agent_run_store().list(conversation_id.as_deref()))
+}
+
+/// Every reply of a kept run, with what the model was given and what its
+/// tool calls returned
+#[tauri::command]
+pub fn get_agent_run_frames(run_id: String) -> Result<Vec<ReplayFrame>, String> {
+    let record = get_agent_run_store().get(&run_id).map_err(|e| e.to_string())?;
+    Ok(record.frames())
+}
+
+/// Run a kept run again from one of its steps with the local tools, in a
+/// copy of its conversation; returns the copy's ID
+#[tauri::command]
+pub async fn rerun_agent_run(run_id: String, from_step: u32, overrides: ReplayOverrides) -> Result<String, String> {
+    let record = get_agent_run_store().get(&run_id).map_err(|e| e.to_string())?;
+    let chat_service = AGENT_CHAT.clone();
+    let rerun = Rerun::prepare(&chat_service, &record, from_step, overrides)
+        .await
+        .map_err(|e| e.to_string())?;
+    let conversation_id = rerun.conversation_id().to_string();
+    let workspace = chat_service
+        .workspace(&conversation_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let environment = chat_service
+        .environment(&conversation_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tauri::async_runtime::spawn(async move {
+        let tools = LocalTools::new(workspace, environment);
+        if let Err(e) = rerun.run(&chat_service, &tools).await {
+            warn!("Replay of agent run {} failed to start: {}", run_id, e);
+        }
+    });
+    Ok(conversation_id)
+}
+
+/// Compare a kept run with another, by default the run it replays or the
+/// one before it in its conversation
+#[tauri::command]
+pub fn diff_agent_runs(run_id: String, other: Option<String>) -> Result<RunDiff, String> {
+    let store = get_agent_run_store();
+    let after = store.get(&run_id).map_err(|e| e.to_string())?;
+    let before = match other {
+        Some(other) => store.get(&other).map_err(|e| e.to_string())?,
+        None => store
+            .previous(&after)
+            .ok_or_else(|| "There is no earlier run to compare with".to_string())?,
+    };
+    Ok(RunDiff::between(&before, &after))
+}
+
+/// Forget a kept run
+#[tauri::command]
+pub fn delete_kept_agent_run(run_id: String) -> Result<bool, String> {
+    get_agent_run_store().remove(&run_id).map_err(|e| e.to_string())
+}
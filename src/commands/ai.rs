@@ -1,7 +1,30 @@
-use crate::ai::router::NetworkStatus;
-use crate::models::messages::{Message, MessageError};
-use crate::models::Model;
+use crate::ai::local::acceleration::{AccelerationInfo, BackendBenchmark, GpuConfig};
+use crate::ai::local::models::{LocalModelInfo, ModelKind};
+use crate::ai::local::adapters::{self, AdapterInfo, AdapterSelection};
+use crate::ai::local::finetune::{self, DatasetSource, FineTuneRequest};
+use crate::ai::local::updates::{UpdateSettings, UpdatesPanel};
+use crate::ai::local::prompt::{self, RenderedPrompt};
+use crate::ai::local::{self, spawn_model_download, spawn_model_update, GenerationOptions};
+use crate::ai::router::{get_model_router, LimitedReason, NetworkStatus};
+use crate::collaboration::get_collaboration_manager;
+use crate::context::overflow::{get_overflow_settings, OverflowStrategy};
+use crate::collaboration::sessions::{LockStatus, GENERATION_LOCK_TIMEOUT};
+use crate::models::messages::{Message, MessageError, MessageStatus};
+use crate::models::{Model, ModelCapabilities};
 use crate::services::ai::get_ai_service;
+use crate::services::chat::get_chat_service;
+use crate::telemetry::latency::{get_latency_monitor, LatencySla, ProviderLatency};
+use crate::telemetry::AnomalyReport;
+use crate::utils::cancellation::get_cancellation_registry;
+use mcp_common::jobs::Job;
+use mcp_common::protocol::cache::{get_prompt_cache_stats as prompt_cache_stats, PromptCacheStats};
+use mcp_common::models::registry::{AdapterEntry, ModelFamily};
+use mcp_common::models::OutputFormat;
+use mcp_common::config::get_settings;
+use mcp_common::service::capabilities::{CapabilityOverrides, CapabilityTable, Feature};
+use mcp_common::service::estimate::estimate_tokens;
+use mcp_common::service::pricing::PriceTable;
+use mcp_common::service::stream::{CostMeter, EventEncoder, StreamEvent};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tauri::State;
@@ -12,6 +35,306 @@ pub async fn get_available_models() -> Result<Vec<Model>, String> {
     Ok(get_ai_service().available_models().await)
 }
 
+/// A model and what it can do with the user's corrections applied
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelCapabilityInfo {
+    /// The model, with the capabilities it reports
+    pub model: Model,
+    
+    /// Capabilities after corrections
+    pub effective: ModelCapabilities,
+}
+
+/// Get what each available model can do
+#[tauri::command]
+pub async fn get_model_capabilities() -> Result<Vec<ModelCapabilityInfo>, String> {
+    let table = CapabilityTable::new();
+    Ok(get_ai_service()
+        .available_models()
+        .await
+        .into_iter()
+        .map(|model| ModelCapabilityInfo {
+            effective: table.for_model(&model),
+            model,
+        })
+        .collect())
+}
+
+/// Get the user's capability corrections by provider or model ID prefix
+#[tauri::command]
+pub fn get_capability_overrides() -> CapabilityOverrides {
+    CapabilityTable::new().overrides()
+}
+
+/// Correct whether a provider, or models by ID prefix, have a feature
+#[tauri::command]
+pub fn set_model_capability(target: String, feature: Feature, supported: bool) -> Result<(), String> {
+    CapabilityTable::new()
+        .set(&target, feature, supported)
+        .map_err(|e| e.to_string())
+}
+
+/// Drop capability corrections of a provider or model prefix
+#[tauri::command]
+pub fn reset_model_capability(target: String, feature: Option<Feature>) -> Result<bool, String> {
+    CapabilityTable::new().reset(&target, feature).map_err(|e| e.to_string())
+}
+
+/// Get detected GPU acceleration backends
+#[tauri::command]
+pub fn get_acceleration_info() -> Result<AccelerationInfo, String> {
+    Ok(AccelerationInfo::detect())
+}
+
+/// Get the GPU configuration for local models
+#[tauri::command]
+pub fn get_gpu_config() -> Result<GpuConfig, String> {
+    Ok(GpuConfig::load())
+}
+
+/// Update the GPU configuration for local models
+#[tauri::command]
+pub fn update_gpu_config(config: GpuConfig) -> Result<(), String> {
+    config.validate(&AccelerationInfo::detect())?;
+    config.save()
+}
+
+/// Measure tokens/sec on each available backend and store the best choice
+#[tauri::command]
+pub async fn gpu_benchmark(model_id: String) -> Result<Vec<BackendBenchmark>, String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    provider
+        .gpu_benchmark(&model_id)
+        .await
+        .map_err(|e| format!("GPU benchmark failed: {:?}", e))
+}
+
+/// List local models, optionally only those of one kind (chat, embedding, reranker, tts, stt)
+#[tauri::command]
+pub fn list_local_models(kind: Option<ModelKind>) -> Result<Vec<LocalModelInfo>, String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    Ok(match kind {
+        Some(kind) => provider.models_of_kind(kind),
+        None => provider.all_models(),
+    })
+}
+
+/// Start downloading a local model of any kind; progress arrives as job events
+///
+/// Passing a request ID lets the frontend stop the download with `cancel_request`
+/// as well as `cancel_job`.
+#[tauri::command]
+pub async fn download_local_model(model_id: String, request_id: Option<String>) -> Result<Job, String> {
+    spawn_model_download(&model_id, request_id.as_deref())
+        .map_err(|e| format!("Failed to download model: {:?}", e))
+}
+
+/// Remove a downloaded local model from disk
+#[tauri::command]
+pub fn delete_local_model(model_id: String) -> Result<(), String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    provider
+        .delete_model(&model_id)
+        .map_err(|e| format!("Failed to delete model: {:?}", e))
+}
+
+/// Local models grouped into families of quantizations, with disk usage
+#[tauri::command]
+pub fn list_model_families() -> Result<Vec<ModelFamily>, String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    Ok(provider.families())
+}
+
+/// The quantizations of one model family
+#[tauri::command]
+pub fn list_model_variants(family: String) -> Result<ModelFamily, String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    provider
+        .family(&family)
+        .map_err(|_| format!("No model family {}", family))
+}
+
+/// Choose which quantization of a model family is used
+#[tauri::command]
+pub fn set_preferred_variant(family: String, model_id: String) -> Result<(), String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    provider.set_preferred_variant(&family, &model_id)
+}
+
+/// Remove every downloaded quantization of a model family; returns the bytes freed
+#[tauri::command]
+pub fn delete_model_family(family: String) -> Result<u64, String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    provider
+        .delete_family(&family)
+        .map_err(|e| format!("Failed to delete model family: {:?}", e))
+}
+
+/// Available model updates, versions to roll back to and disk usage for
+/// the updates panel
+#[tauri::command]
+pub fn get_model_updates() -> Result<UpdatesPanel, String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    Ok(provider.model_updates())
+}
+
+/// Start downloading the latest version of a local model; the installed
+/// version is kept for rollback
+#[tauri::command]
+pub async fn update_model(model_id: String) -> Result<Job, String> {
+    spawn_model_update(&model_id).map_err(|e| format!("Failed to update model: {:?}", e))
+}
+
+/// Switch a local model between its installed and previous version
+#[tauri::command]
+pub fn switch_model_version(model_id: String, version: String) -> Result<(), String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    provider.switch_model_version(&model_id, &version)
+}
+
+/// Turn automatic model updates on or off and set the disk quota
+#[tauri::command]
+pub fn set_model_update_settings(settings: UpdateSettings) -> Result<(), String> {
+    settings.save()
+}
+
+/// LoRA adapters, optionally only those that fit a local model
+#[tauri::command]
+pub fn list_lora_adapters(model_id: Option<String>) -> Result<Vec<AdapterInfo>, String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    provider
+        .adapters(model_id.as_deref())
+        .map_err(|_| format!("No model with ID {}", model_id.unwrap_or_default()))
+}
+
+/// Import a LoRA adapter file for a base model ID or family
+#[tauri::command]
+pub fn import_lora_adapter(
+    path: String,
+    base_model: String,
+    name: Option<String>,
+    scale: Option<f32>,
+) -> Result<AdapterEntry, String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    provider.import_adapter(std::path::Path::new(&path), name.as_deref(), &base_model, scale)
+}
+
+/// Start downloading a LoRA adapter for a base model ID or family
+#[tauri::command]
+pub async fn download_lora_adapter(
+    url: String,
+    name: String,
+    base_model: String,
+    sha256: Option<String>,
+    scale: Option<f32>,
+) -> Result<Job, String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    provider.download_adapter(&url, &name, &base_model, sha256, scale)
+}
+
+/// Delete a LoRA adapter
+#[tauri::command]
+pub fn delete_lora_adapter(adapter_id: String) -> Result<(), String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    provider.delete_adapter(&adapter_id)
+}
+
+/// LoRA adapters a conversation applies to local models
+#[tauri::command]
+pub fn get_conversation_adapters(conversation_id: String) -> Vec<AdapterSelection> {
+    adapters::conversation_adapters(&local::model_dir(), &conversation_id)
+}
+
+/// Choose the LoRA adapters a conversation applies, with their scaling factors
+#[tauri::command]
+pub fn set_conversation_adapters(conversation_id: String, adapters: Vec<AdapterSelection>) -> Result<(), String> {
+    adapters::set_conversation_adapters(&local::model_dir(), &conversation_id, adapters)
+}
+
+/// What a conversation does when it outgrows the model's context window
+#[tauri::command]
+pub fn get_context_overflow_strategy(conversation_id: String) -> OverflowStrategy {
+    get_overflow_settings().get(&conversation_id)
+}
+
+/// Choose what a conversation does when it outgrows the model's context
+/// window; no strategy goes back to the default
+#[tauri::command]
+pub fn set_context_overflow_strategy(conversation_id: String, strategy: Option<OverflowStrategy>) {
+    get_overflow_settings().set(&conversation_id, strategy)
+}
+
+/// Export conversations or upvoted responses as training JSONL; returns the
+/// number of examples written
+#[tauri::command]
+pub fn export_training_data(path: String, source: DatasetSource) -> Result<usize, String> {
+    finetune::export_dataset(source, std::path::Path::new(&path)).map_err(|e| e.to_string())
+}
+
+/// Start fine-tuning a local model; the resulting adapter is registered for
+/// the model when the job completes
+#[tauri::command]
+pub async fn start_finetune(request: FineTuneRequest) -> Result<Job, String> {
+    finetune::spawn_finetune(request)
+}
+
+/// Choose which local model a subsystem uses for a kind
+#[tauri::command]
+pub fn set_default_local_model(kind: ModelKind, model_id: String) -> Result<(), String> {
+    let provider = local::get_local_provider().map_err(|e| format!("{:?}", e))?;
+    provider.set_default_model(kind, &model_id)
+}
+
+/// Local models the retrieval and speech subsystems currently use
+#[tauri::command]
+pub fn get_subsystem_models() -> local::SubsystemModels {
+    local::subsystem_models()
+}
+
+/// Get the last prompt sent to a local model, as laid out by its chat
+/// template, for debugging
+#[tauri::command]
+pub fn get_local_prompt(model_id: String) -> Result<Option<RenderedPrompt>, String> {
+    Ok(prompt::last_prompt(&model_id))
+}
+
+/// Output of a structured generation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructuredOutput {
+    /// Text the model produced
+    pub text: String,
+
+    /// Parsed value, for JSON schema output
+    pub value: Option<serde_json::Value>,
+}
+
+/// Generate output of a given shape: text matching a GBNF grammar, or JSON
+/// matching a schema. Local models are constrained while decoding, so this
+/// works offline; output of other models is only validated.
+#[tauri::command]
+pub async fn generate_structured(
+    model_id: String,
+    prompt: String,
+    format: OutputFormat,
+) -> Result<StructuredOutput, String> {
+    // Reject a bad grammar or schema before spending a generation on it
+    format.grammar().map_err(|e| e.to_string())?;
+    
+    let options = GenerationOptions {
+        output: format.clone(),
+        ..Default::default()
+    };
+    let message = options.attach(Message::new_user_text(prompt));
+    let reply = get_model_router()
+        .complete(&model_id, message)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    let text = reply.text_content().unwrap_or_default().to_string();
+    let value = format.check(&text).map_err(|e| e.to_string())?;
+    Ok(StructuredOutput { text, value })
+}
+
 /// Set network status
 #[tauri::command]
 pub fn set_network_status(status: String) -> Result<(), String> {
@@ -19,6 +342,8 @@ pub fn set_network_status(status: String) -> Result<(), String> {
         "connected" => NetworkStatus::Connected,
         "disconnected" => NetworkStatus::Disconnected,
         "unstable" => NetworkStatus::Unstable,
+        "captive_portal" => NetworkStatus::Limited(LimitedReason::CaptivePortal),
+        "dns_only" => NetworkStatus::Limited(LimitedReason::DnsOnly),
         _ => NetworkStatus::Unknown,
     };
     
@@ -26,21 +351,68 @@ pub fn set_network_status(status: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Take the generation lock of a conversation shared in a collaboration
+/// session, so two participants don't generate into it at once.
+///
+/// Fails while someone else's generation runs. A caller that passed its own
+/// request ID keeps its place in the queue and can retry with it; otherwise
+/// the request is withdrawn.
+fn take_generation_lock(conversation_id: &str, request_id: &str, keep_place: bool) -> Result<(), String> {
+    let manager = match get_collaboration_manager() {
+        Ok(manager) => manager,
+        Err(_) => return Ok(()),
+    };
+    
+    match manager.request_generation(conversation_id, request_id).map_err(|e| e.to_string())? {
+        Some(LockStatus::Queued { holder, position }) => {
+            if !keep_place {
+                release_generation_lock(conversation_id, request_id);
+            }
+            Err(format!(
+                "{} is generating in this conversation (you are number {} in line)",
+                holder.request.user_name, position
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Release a conversation's generation lock once a request finishes
+fn release_generation_lock(conversation_id: &str, request_id: &str) {
+    if let Ok(manager) = get_collaboration_manager() {
+        if let Err(e) = manager.release_generation(conversation_id, request_id) {
+            log::warn!("Failed to release generation lock on {}: {}", conversation_id, e);
+        }
+    }
+}
+
 /// Send a message to a model
 #[tauri::command]
 pub async fn send_message(
     conversation_id: String,
     model_id: String,
     content: String,
+    request_id: Option<String>,
+    timeout_ms: Option<u64>,
 ) -> Result<serde_json::Value, String> {
     // Create a message
     let message = Message::new_user_text(content);
     
+    // Register the request so the frontend can abort it
+    let keep_place = request_id.is_some();
+    let request_id = request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    take_generation_lock(&conversation_id, &request_id, keep_place)?;
+    let registry = get_cancellation_registry();
+    let ctx = registry.register(&request_id, timeout_ms.map(std::time::Duration::from_millis));
+    
     // Send message
-    match get_ai_service()
-        .send_message(&conversation_id, &model_id, message)
-        .await
-    {
+    let result = get_ai_service()
+        .send_message_with_context(&conversation_id, &model_id, message, &ctx)
+        .await;
+    registry.complete(&request_id);
+    release_generation_lock(&conversation_id, &request_id);
+    
+    match result {
         Ok(response) => {
             // Convert to json
             let mut map = serde_json::Map::new();
@@ -96,6 +468,37 @@ pub async fn send_message(
     }
 }
 
+/// Emit typed stream events as `stream-event`, next to the whole-message
+/// `stream-update` snapshots older frontends listen for
+fn emit_stream_events(window: &tauri::Window, stream_id: &str, events: Vec<StreamEvent>) {
+    for event in events {
+        let _ = window.emit(
+            "stream-event",
+            serde_json::json!({
+                "stream_id": stream_id,
+                "event": event,
+            }),
+        );
+    }
+}
+
+/// Encoder of a reply's stream events, estimating the prompt from the
+/// conversation and pricing tokens at the model's price
+async fn stream_encoder(conversation_id: &str, model_id: &str, content: &str) -> EventEncoder {
+    let history: u64 = get_chat_service()
+        .conversation_with_messages(conversation_id)
+        .map_or(0, |c| c.messages.iter().map(|m| estimate_tokens(&m.text())).sum());
+    let prices = PriceTable::new();
+    let price = match get_ai_service().available_models().await.into_iter().find(|m| m.id == model_id) {
+        Some(model) => prices.price_for_model(&model),
+        None => prices.price_for(model_id),
+    };
+    let currency = get_settings().lock().unwrap().cost.currency.clone();
+    EventEncoder::new()
+        .with_prompt_tokens(history + estimate_tokens(content))
+        .with_meter(CostMeter::new(price, &prices, &currency))
+}
+
 /// Stream a message to a model
 #[tauri::command]
 pub async fn stream_message(
@@ -103,25 +506,52 @@ pub async fn stream_message(
     conversation_id: String,
     model_id: String,
     content: String,
+    request_id: Option<String>,
+    timeout_ms: Option<u64>,
 ) -> Result<String, String> {
     // Create a message
+    let mut encoder = stream_encoder(&conversation_id, &model_id, &content).await;
     let message = Message::new_user_text(content);
     
-    // Generate a unique stream ID
-    let stream_id = uuid::Uuid::new_v4().to_string();
+    // The stream ID doubles as the request ID used for cancellation and locking
+    let keep_place = request_id.is_some();
+    let stream_id = request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    take_generation_lock(&conversation_id, &stream_id, keep_place)?;
+    let registry = get_cancellation_registry();
+    let ctx = registry.register(&stream_id, timeout_ms.map(std::time::Duration::from_millis));
     
     // Start streaming
     match get_ai_service()
-        .stream_message(&conversation_id, &model_id, message)
+        .stream_message_with_context(&conversation_id, &model_id, message, &ctx)
         .await
     {
         Ok(mut stream) => {
+            super::chat::clear_draft(&conversation_id);
+            
             // Process stream in a separate task
             let window_clone = window.clone();
             let stream_id_clone = stream_id.clone();
+            let conversation_id_clone = conversation_id.clone();
             
             tauri::async_runtime::spawn(async move {
+                let mut last_renewal = std::time::Instant::now();
                 while let Some(response) = stream.recv().await {
+                    // Keep the generation lock while the reply is still coming in
+                    if last_renewal.elapsed() > GENERATION_LOCK_TIMEOUT / 2 {
+                        if let Ok(manager) = get_collaboration_manager() {
+                            let _ = manager.renew_generation(&conversation_id_clone, &stream_id_clone);
+                        }
+                        last_renewal = std::time::Instant::now();
+                    }
+                    
+                    // Typed events for frontends that render blocks as they arrive
+                    let snapshot: mcp_common::models::Message = response.message.clone().into();
+                    let mut events = encoder.push_snapshot(&snapshot);
+                    if response.status == MessageStatus::Complete {
+                        events.extend(encoder.finish(None));
+                    }
+                    emit_stream_events(&window_clone, &stream_id_clone, events);
+                    
                     // Convert to json
                     let mut map = serde_json::Map::new();
                     
@@ -180,6 +610,9 @@ pub async fn stream_message(
                     let _ = window_clone.emit("stream-update", serde_json::Value::Object(map));
                 }
                 
+                get_cancellation_registry().complete(&stream_id_clone);
+                release_generation_lock(&conversation_id_clone, &stream_id_clone);
+                
                 // Emit stream end event
                 let _ = window_clone.emit(
                     "stream-end",
@@ -191,10 +624,22 @@ pub async fn stream_message(
             
             Ok(stream_id)
         }
-        Err(e) => Err(format!("Failed to start streaming: {}", e)),
+        Err(e) => {
+            registry.complete(&stream_id);
+            release_generation_lock(&conversation_id, &stream_id);
+            Err(format!("Failed to start streaming: {}", e))
+        }
     }
 }
 
+/// Cancel an in-flight request by the ID passed when it was started
+///
+/// Returns false if the request already finished.
+#[tauri::command]
+pub fn cancel_request(request_id: String) -> Result<bool, String> {
+    Ok(get_cancellation_registry().cancel(&request_id))
+}
+
 /// Cancel a streaming message
 #[tauri::command]
 pub async fn cancel_streaming(
@@ -294,3 +739,36 @@ pub fn create_conversation(title: String, model_id: String) -> Result<serde_json
 pub fn delete_conversation(id: String) -> Result<(), String> {
     get_ai_service().delete_conversation(&id)
 }
+
+/// Get prompt cache hit/miss counts and estimated savings
+#[tauri::command]
+pub fn get_prompt_cache_stats() -> Result<PromptCacheStats, String> {
+    Ok(prompt_cache_stats())
+}
+
+/// Rolling time-to-first-token and total latency percentiles per provider
+#[tauri::command]
+pub fn get_latency_stats() -> Result<Vec<ProviderLatency>, String> {
+    Ok(get_latency_monitor().lock().unwrap().stats())
+}
+
+/// Latency SLA breaches of the last `days` days (default 7)
+#[tauri::command]
+pub fn get_latency_alerts(days: Option<u32>) -> Result<AnomalyReport, String> {
+    Ok(get_latency_monitor()
+        .lock()
+        .unwrap()
+        .alert_report(days.unwrap_or(7), chrono::Utc::now()))
+}
+
+/// Get the p95 latency thresholds and whether breaches raise a notification
+#[tauri::command]
+pub fn get_latency_sla() -> Result<LatencySla, String> {
+    Ok(LatencySla::load())
+}
+
+/// Set the p95 latency thresholds; an empty threshold turns its alert off
+#[tauri::command]
+pub fn set_latency_sla(sla: LatencySla) -> Result<(), String> {
+    sla.save()
+}
@@ -0,0 +1,67 @@
+use crate::services::chat::get_chat_service;
+use crate::tools::git::get_repo_bindings;
+use chrono::Utc;
+use mcp_common::service::apply::{self, ApplicationRecord, ApplyReport, FilePreview, Patch};
+use std::path::PathBuf;
+
+/// Parse the changes in a message and find the repository they apply to
+fn load_patch(conversation_id: &str, message_id: &str) -> Result<(Patch, PathBuf), String> {
+    let root = get_repo_bindings()
+        .get(conversation_id)
+        .ok_or_else(|| "This conversation is not bound to a repository".to_string())?;
+
+    let message = get_chat_service()
+        .get_messages(conversation_id)
+        .into_iter()
+        .find(|m| m.message.id == message_id)
+        .ok_or_else(|| format!("Message with ID {} not found", message_id))?;
+
+    let text = mcp_common::models::Message::from(message.message).text();
+    let patch = apply::parse_patch(&text);
+    if patch.is_empty() {
+        return Err("The message contains no diff or file blocks".to_string());
+    }
+
+    Ok((patch, root))
+}
+
+/// Preview a message's diff or file blocks against the bound repository
+#[tauri::command]
+pub fn preview_message_patch(conversation_id: String, message_id: String) -> Result<Vec<FilePreview>, String> {
+    let (patch, root) = load_patch(&conversation_id, &message_id)?;
+    apply::preview(&patch, &root).map_err(|e| e.to_string())
+}
+
+/// Apply the selected hunks of a message's changes; nothing is written if any hunk fails
+#[tauri::command]
+pub fn apply_message_patch(
+    conversation_id: String,
+    message_id: String,
+    hunks: Option<Vec<usize>>,
+    dry_run: bool,
+) -> Result<ApplyReport, String> {
+    let (patch, root) = load_patch(&conversation_id, &message_id)?;
+
+    // Check the whole selection before touching any file
+    let report = apply::apply(&patch, &root, hunks.as_deref(), true).map_err(|e| e.to_string())?;
+    if dry_run {
+        return Ok(report);
+    }
+
+    let report = apply::apply(&patch, &root, hunks.as_deref(), false).map_err(|e| e.to_string())?;
+
+    let service = get_chat_service();
+    if let Some(mut conversation) = service.get_conversation(&conversation_id) {
+        let record = ApplicationRecord {
+            message_id,
+            repository: root,
+            files: report.files.clone(),
+            hunks: report.applied_hunks.clone(),
+            applied_at: Utc::now(),
+        };
+        apply::add_record(&mut conversation.metadata, &record);
+        service.update_conversation(conversation)?;
+    }
+
+    Ok(report)
+}
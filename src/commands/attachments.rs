@@ -0,0 +1,105 @@
+use base64::Engine as _;
+use mcp_common::config::{get_attachment_store, Attachment};
+use mcp_common::models::message::ContentType;
+use mcp_common::models::Message;
+use mcp_common::service::limits::{LimitsTable, Preflight, RequestLimits};
+
+/// Attach a file from disk to a conversation
+#[tauri::command]
+pub fn add_attachment(conversation_id: String, path: String) -> Result<Attachment, String> {
+    get_attachment_store()
+        .add_file(&conversation_id, std::path::Path::new(&path))
+        .map_err(|e| e.to_string())
+}
+
+/// Attach pasted or dropped content (base64) to a conversation
+#[tauri::command]
+pub fn add_attachment_data(conversation_id: String, file_name: String, data: String) -> Result<Attachment, String> {
+    let content = base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map_err(|e| format!("Invalid attachment data: {}", e))?;
+    get_attachment_store()
+        .add_bytes(&conversation_id, &file_name, &content)
+        .map_err(|e| e.to_string())
+}
+
+/// List attachments, optionally for one conversation
+#[tauri::command]
+pub fn list_attachments(conversation_id: Option<String>) -> Vec<Attachment> {
+    get_attachment_store().list(conversation_id.as_deref())
+}
+
+/// Total attachment size of a conversation in bytes
+#[tauri::command]
+pub fn get_attachment_usage(conversation_id: String) -> u64 {
+    get_attachment_store().usage().get(&conversation_id).copied().unwrap_or(0)
+}
+
+/// Thumbnail of an image attachment as a data URL
+#[tauri::command]
+pub fn get_attachment_preview(id: String) -> Option<String> {
+    get_attachment_store().thumbnail(&id).map(|png| {
+        format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(png)
+        )
+    })
+}
+
+/// Open an attachment with the default application
+#[tauri::command]
+pub fn open_attachment(id: String) -> Result<(), String> {
+    get_attachment_store().open(&id).map_err(|e| e.to_string())
+}
+
+/// Delete an attachment
+#[tauri::command]
+pub fn delete_attachment(id: String) -> Result<(), String> {
+    get_attachment_store().delete(&id).map_err(|e| e.to_string())
+}
+
+/// Request size limits of a provider, the user's or the documented ones
+#[tauri::command]
+pub fn get_request_limits(provider: String) -> RequestLimits {
+    LimitsTable::new().for_provider(&provider)
+}
+
+/// Set a provider's request size limits
+#[tauri::command]
+pub fn set_request_limits(provider: String, limits: RequestLimits) -> Result<(), String> {
+    LimitsTable::new().set(&provider, limits).map_err(|e| e.to_string())
+}
+
+/// Go back to a provider's documented limits
+#[tauri::command]
+pub fn reset_request_limits(provider: String) -> Result<bool, String> {
+    LimitsTable::new().reset(&provider).map_err(|e| e.to_string())
+}
+
+/// Check a message with image attachments against a provider's limits
+/// before sending it
+#[tauri::command]
+pub fn check_attachments(provider: String, text: String, attachment_ids: Vec<String>) -> Result<Preflight, String> {
+    let store = get_attachment_store();
+    let mut message = Message::user(text);
+    for id in &attachment_ids {
+        let attachment = store.get(id).ok_or_else(|| format!("Attachment {} not found", id))?;
+        if attachment.is_image() {
+            message.content.parts.push(ContentType::Image {
+                url: attachment.url(),
+                alt_text: Some(attachment.file_name),
+            });
+        }
+    }
+    let limits = LimitsTable::new().for_provider(&provider);
+    Ok(Preflight::check_with(&store, &limits, &[message]))
+}
+
+/// Store a smaller copy of an image within a provider's limits
+#[tauri::command]
+pub fn downscale_attachment(id: String, provider: String) -> Result<Attachment, String> {
+    let limits = LimitsTable::new().for_provider(&provider);
+    get_attachment_store()
+        .downscale(&id, limits.max_image_dimension, limits.max_image_bytes)
+        .map_err(|e| e.to_string())
+}
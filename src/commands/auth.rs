@@ -1,4 +1,8 @@
 use crate::services::auth::get_auth_service;
+use mcp_common::auth::{self, DeviceAuthorization, OAuthStatus};
+use mcp_common::config::{get_settings, OAuthSettings};
+use mcp_common::service::credentials::{self, ConnectionHealth, CredentialCheck};
+use serde::Serialize;
 use tauri::State;
 
 /// Set API key
@@ -24,3 +28,70 @@ pub fn get_organization_id() -> Option<String> {
 pub fn logout() -> Result<(), String> {
     get_auth_service().logout()
 }
+
+/// Connection health with the check behind it
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionHealthInfo {
+    pub health: ConnectionHealth,
+    pub check: Option<CredentialCheck>,
+}
+
+/// Latest connection health, without a network call
+#[tauri::command]
+pub fn get_connection_health() -> ConnectionHealthInfo {
+    let (health, check) = credentials::connection_health();
+    ConnectionHealthInfo { health, check }
+}
+
+/// Check the configured API key, reusing a recent result unless `refresh`
+#[tauri::command]
+pub async fn validate_credentials(refresh: Option<bool>) -> CredentialCheck {
+    if refresh.unwrap_or(false) {
+        credentials::revalidate().await
+    } else {
+        credentials::validate().await
+    }
+}
+
+/// OAuth gateway configuration
+#[tauri::command]
+pub fn get_oauth_settings() -> OAuthSettings {
+    get_settings().lock().unwrap().auth.oauth.clone()
+}
+
+/// Configure the OAuth gateway used for device-code login
+#[tauri::command]
+pub fn update_oauth_settings(oauth: OAuthSettings) -> Result<(), String> {
+    let settings = get_settings();
+    let mut settings = settings.lock().unwrap();
+    settings.auth.oauth = oauth;
+    settings.save().map_err(|e| e.to_string())
+}
+
+/// Start a device-code login; the frontend shows the user code and
+/// verification page, then calls `complete_oauth_login`
+#[tauri::command]
+pub async fn start_oauth_login() -> Result<DeviceAuthorization, String> {
+    auth::start_device_login().await.map_err(|e| e.to_string())
+}
+
+/// Wait for the user to approve the login and switch to OAuth
+#[tauri::command]
+pub async fn complete_oauth_login(authorization: DeviceAuthorization) -> Result<OAuthStatus, String> {
+    auth::poll_device_login(&authorization)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(auth::oauth_status())
+}
+
+/// OAuth login state
+#[tauri::command]
+pub fn get_oauth_status() -> OAuthStatus {
+    auth::oauth_status()
+}
+
+/// Forget the OAuth tokens and go back to the API key
+#[tauri::command]
+pub fn oauth_logout() -> Result<(), String> {
+    auth::logout().map_err(|e| e.to_string())
+}
@@ -0,0 +1,116 @@
+use crate::ai::local::spawn_model_download;
+use mcp_common::jobs::Job;
+use mcp_common::models::huggingface::{self, HardwareLimits, HfModel};
+use mcp_common::models::registry::{get_model_registry, CatalogFormat, CatalogSource, CatalogTrust, RegistryEntry};
+use mcp_common::models::ChatTemplate;
+
+/// List user-added model catalogs in priority order
+#[tauri::command]
+pub fn list_model_catalogs() -> Vec<CatalogSource> {
+    get_model_registry().catalogs()
+}
+
+/// Models offered by catalogs, with the catalog each comes from
+#[tauri::command]
+pub fn list_catalog_models() -> Vec<RegistryEntry> {
+    get_model_registry().entries()
+}
+
+/// Add a model catalog and fetch it
+#[tauri::command]
+pub async fn add_model_catalog(
+    name: String,
+    url: String,
+    format: CatalogFormat,
+    trust: Option<CatalogTrust>,
+    token: Option<String>,
+) -> Result<CatalogSource, String> {
+    let registry = get_model_registry();
+    let catalog = registry
+        .add_catalog(&name, &url, format, trust.unwrap_or_default())
+        .map_err(|e| e.to_string())?;
+    if token.is_some() {
+        registry.set_token(&catalog.id, token.as_deref()).map_err(|e| e.to_string())?;
+    }
+    // A failed first fetch is recorded on the catalog and retried later
+    let _ = registry.refresh(&catalog.id).await;
+    registry.catalog(&catalog.id).map_err(|e| e.to_string())
+}
+
+/// Remove a model catalog; downloaded models stay on disk
+#[tauri::command]
+pub fn remove_model_catalog(id: String) -> Result<(), String> {
+    get_model_registry().remove_catalog(&id).map_err(|e| e.to_string())
+}
+
+/// Change how far a catalog's models are trusted
+#[tauri::command]
+pub fn set_model_catalog_trust(id: String, trust: CatalogTrust) -> Result<CatalogSource, String> {
+    get_model_registry().set_trust(&id, trust).map_err(|e| e.to_string())
+}
+
+/// Change how often a catalog is fetched
+#[tauri::command]
+pub fn set_model_catalog_refresh_hours(id: String, hours: u32) -> Result<CatalogSource, String> {
+    get_model_registry().set_refresh_hours(&id, hours).map_err(|e| e.to_string())
+}
+
+/// Store or remove the access token of a catalog
+#[tauri::command]
+pub fn set_model_catalog_token(id: String, token: Option<String>) -> Result<CatalogSource, String> {
+    get_model_registry().set_token(&id, token.as_deref()).map_err(|e| e.to_string())
+}
+
+/// Fetch one catalog, or all of them, now
+#[tauri::command]
+pub async fn refresh_model_catalogs(id: Option<String>) -> Result<Vec<CatalogSource>, String> {
+    let registry = get_model_registry();
+    match id {
+        Some(id) => {
+            registry.refresh(&id).await.map_err(|e| e.to_string())?;
+        }
+        None => {
+            registry.refresh_all(true).await;
+        }
+    }
+    Ok(registry.catalogs())
+}
+
+/// Search the Hugging Face Hub for GGUF models that fit this machine, or of
+/// any size with `all_sizes`
+#[tauri::command]
+pub async fn search_hf_models(query: String, limit: Option<usize>, all_sizes: Option<bool>) -> Result<Vec<HfModel>, String> {
+    let limits = if all_sizes.unwrap_or(false) {
+        HardwareLimits::unlimited()
+    } else {
+        HardwareLimits::detect()
+    };
+    huggingface::search(&query, limits, limit.unwrap_or(20))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Register a GGUF file from the Hub as a local model, and start
+/// downloading it with `download`. `chat_template` overrides the template
+/// read from the file.
+#[tauri::command]
+pub async fn register_hf_model(
+    repo: String,
+    path: String,
+    download: Option<bool>,
+    chat_template: Option<ChatTemplate>,
+) -> Result<Option<Job>, String> {
+    let entry = huggingface::register(&repo, &path, chat_template).await.map_err(|e| e.to_string())?;
+    if !download.unwrap_or(false) {
+        return Ok(None);
+    }
+    spawn_model_download(&entry.entry.id, None)
+        .map(Some)
+        .map_err(|e| format!("Failed to download model: {:?}", e))
+}
+
+/// Store or remove the Hugging Face token used for gated and private models
+#[tauri::command]
+pub fn set_hf_token(token: Option<String>) -> Result<(), String> {
+    huggingface::set_token(token.as_deref()).map_err(|e| e.to_string())
+}
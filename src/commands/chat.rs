@@ -1,6 +1,14 @@
 use crate::models::messages::{Message, MessageError};
 use crate::models::{Conversation, Model};
+use crate::offline::get_offline_manager;
 use crate::services::chat::get_chat_service;
+use crate::utils::cancellation::get_cancellation_registry;
+use mcp_common::config::{get_journal, get_settings, get_storage_manager, CostSettings, Draft, JournalEntry};
+use mcp_common::models::{MessageVersion, Rating};
+use mcp_common::service::analytics::{self, UsageAnalytics};
+use mcp_common::service::estimate::{self, CostEstimate};
+use mcp_common::service::pricing::{ModelPrice, PriceEntry, PriceTable};
+use mcp_common::sync::{get_read_markers, ReadMarker};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tauri::State;
@@ -34,14 +42,37 @@ pub fn get_conversation(id: String) -> Result<Conversation, String> {
         .ok_or_else(|| format!("Conversation with ID {} not found", id))
 }
 
-/// Get all conversations
+/// Get all conversations, with their unread counts
 #[tauri::command]
 pub fn get_conversations() -> Vec<Conversation> {
-    let service = get_chat_service();
-    
-    // In a real implementation, we would fetch all conversations
-    // For now, we'll return an empty list
-    Vec::new()
+    let markers = get_read_markers();
+    let mut conversations = get_chat_service().conversations_with_messages();
+    for conversation in &mut conversations {
+        conversation.unread = markers.unread_count(conversation);
+    }
+    conversations
+}
+
+/// Mark a conversation read up to its last message, here and on synced devices
+#[tauri::command]
+pub fn mark_conversation_read(conversation_id: String) -> Result<Option<ReadMarker>, String> {
+    let conversation = get_chat_service()
+        .conversation_with_messages(&conversation_id)
+        .ok_or_else(|| format!("Conversation with ID {} not found", conversation_id))?;
+    let marker = get_read_markers().mark_read(&conversation).map_err(|e| e.to_string())?;
+    if let Some(marker) = &marker {
+        get_offline_manager().get_sync_manager().add_read_marker(marker);
+    }
+    Ok(marker)
+}
+
+/// Index of the first unread message of a conversation, to jump to
+#[tauri::command]
+pub fn get_first_unread(conversation_id: String) -> Result<Option<usize>, String> {
+    let conversation = get_chat_service()
+        .conversation_with_messages(&conversation_id)
+        .ok_or_else(|| format!("Conversation with ID {} not found", conversation_id))?;
+    Ok(get_read_markers().first_unread(&conversation))
 }
 
 /// Delete a conversation
@@ -50,6 +81,77 @@ pub fn delete_conversation(id: String) -> Result<(), String> {
     get_chat_service().delete_conversation(&id)
 }
 
+/// Undo the latest deletion (Ctrl+Z); returns what was undone, if anything
+#[tauri::command]
+pub fn undo_last_operation() -> Result<Option<JournalEntry>, String> {
+    get_chat_service().undo()
+}
+
+/// Redo the most recently undone operation (Ctrl+Shift+Z)
+#[tauri::command]
+pub fn redo_last_operation() -> Result<Option<JournalEntry>, String> {
+    get_chat_service().redo()
+}
+
+/// Operations that can still be undone or redone, newest first
+#[tauri::command]
+pub fn get_undo_history() -> Vec<JournalEntry> {
+    get_journal().list()
+}
+
+/// Unsent draft of a conversation, to restore its input
+#[tauri::command]
+pub fn get_draft(conversation_id: String) -> Result<Option<Draft>, String> {
+    get_storage_manager().load_draft(&conversation_id).map_err(|e| e.to_string())
+}
+
+/// Save the unsent input of a conversation; blank text deletes the draft
+#[tauri::command]
+pub fn set_draft(conversation_id: String, text: String) -> Result<Option<Draft>, String> {
+    store_draft(&conversation_id, &text).map_err(|e| e.to_string())
+}
+
+/// Delete the draft of a conversation once its message was sent
+pub(crate) fn clear_draft(conversation_id: &str) {
+    if let Err(e) = store_draft(conversation_id, "") {
+        log::warn!("Failed to clear the draft of {}: {}", conversation_id, e);
+    }
+}
+
+/// Save or delete a draft, and queue it for other devices if drafts sync
+fn store_draft(conversation_id: &str, text: &str) -> mcp_common::error::McpResult<Option<Draft>> {
+    let storage = get_storage_manager();
+    let draft = Draft {
+        conversation_id: conversation_id.to_string(),
+        text: text.to_string(),
+        updated_at: mcp_common::utils::clock::now(),
+    };
+    if text.trim().is_empty() {
+        storage.delete_draft(conversation_id)?;
+    } else {
+        storage.save_draft(&draft)?;
+    }
+    mcp_common::service::ChatService::emit_draft_changed(conversation_id);
+    
+    if get_settings().lock().unwrap().drafts.sync {
+        // A blank draft travels too, so other devices clear theirs
+        get_offline_manager().get_sync_manager().add_draft(&draft);
+    }
+    Ok((!text.trim().is_empty()).then_some(draft))
+}
+
+/// Earlier versions of an edited message, oldest first
+#[tauri::command]
+pub fn get_message_history(message_id: String) -> Result<Vec<MessageVersion>, String> {
+    get_chat_service()
+        .conversations_with_messages()
+        .iter()
+        .flat_map(|c| c.messages.iter())
+        .find(|m| m.id == message_id)
+        .map(|m| m.edit_history())
+        .ok_or_else(|| format!("Message with ID {} not found", message_id))
+}
+
 /// Get conversation message history
 #[tauri::command]
 pub fn get_messages(conversation_id: String) -> Result<Vec<serde_json::Value>, String> {
@@ -118,16 +220,27 @@ pub fn get_messages(conversation_id: String) -> Result<Vec<serde_json::Value>, S
 pub async fn send_message(
     conversation_id: String,
     content: String,
+    request_id: Option<String>,
+    timeout_ms: Option<u64>,
 ) -> Result<serde_json::Value, String> {
     // Create a message
     let message = Message::new_user_text(content);
     
+    // Register the request so the frontend can abort it
+    let request_id = request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let registry = get_cancellation_registry();
+    let ctx = registry.register(&request_id, timeout_ms.map(std::time::Duration::from_millis));
+    
     // Send message
-    match get_chat_service()
-        .send_message(&conversation_id, message)
-        .await
-    {
+    let result = get_chat_service()
+        .send_message_with_context(&conversation_id, message, &ctx)
+        .await;
+    registry.complete(&request_id);
+    
+    match result {
         Ok(response) => {
+            clear_draft(&conversation_id);
+            
             // Convert to json
             let mut map = serde_json::Map::new();
             
@@ -181,3 +294,118 @@ pub async fn send_message(
         Err(e) => Err(format!("Failed to send message: {}", e)),
     }
 }
+
+/// Rate a message; a missing rating clears it
+#[tauri::command]
+pub fn rate_message(
+    message_id: String,
+    rating: Option<Rating>,
+    comment: Option<String>,
+) -> Result<Message, String> {
+    get_chat_service()
+        .rate_message(&message_id, rating, comment)
+        .map(|rated| rated.message)
+}
+
+/// Projected tokens and cost of sending `content`, for the label next to the
+/// send button; `needs_confirmation` asks the UI to confirm before sending
+#[tauri::command]
+pub fn estimate_message_cost(conversation_id: String, content: String) -> Result<CostEstimate, String> {
+    let conversation = get_chat_service()
+        .conversation_with_messages(&conversation_id)
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))?;
+    let (max_tokens, cost) = {
+        let settings = get_settings();
+        let settings = settings.lock().unwrap();
+        (settings.model.max_tokens as u64, settings.cost.clone())
+    };
+    
+    Ok(estimate::estimate(&conversation, &content, &PriceTable::new(), max_tokens, &cost))
+}
+
+/// Prices per million tokens by model ID prefix, with where each comes from
+#[tauri::command]
+pub fn get_model_prices() -> Vec<PriceEntry> {
+    PriceTable::new().entries()
+}
+
+/// Set the price of models whose ID starts with `model`
+#[tauri::command]
+pub fn set_model_price(model: String, price: ModelPrice) -> Result<(), String> {
+    PriceTable::new().set_price(&model, price).map_err(|e| e.to_string())
+}
+
+/// Go back to the built-in price of a model
+#[tauri::command]
+pub fn reset_model_price(model: String) -> Result<(), String> {
+    PriceTable::new().reset_price(&model).map_err(|e| e.to_string())
+}
+
+/// Fetch the configured price list now; returns the number of prices it lists
+#[tauri::command]
+pub async fn refresh_model_prices() -> Result<usize, String> {
+    let url = get_settings()
+        .lock()
+        .unwrap()
+        .cost
+        .price_list_url
+        .clone()
+        .ok_or_else(|| "No price list URL is configured".to_string())?;
+    PriceTable::new().refresh(&url).await.map_err(|e| e.to_string())
+}
+
+/// Currencies costs can be shown in
+#[tauri::command]
+pub fn get_currencies() -> Vec<String> {
+    PriceTable::new().currencies()
+}
+
+/// Set the units of a currency per US dollar
+#[tauri::command]
+pub fn set_exchange_rate(currency: String, rate: f64) -> Result<(), String> {
+    PriceTable::new().set_rate(&currency, rate).map_err(|e| e.to_string())
+}
+
+/// Get the cost display and warning settings
+#[tauri::command]
+pub fn get_cost_settings() -> CostSettings {
+    get_settings().lock().unwrap().cost.clone()
+}
+
+/// Update the cost display and warning settings
+#[tauri::command]
+pub fn update_cost_settings(cost: CostSettings) -> Result<(), String> {
+    let settings = get_settings();
+    let mut settings = settings.lock().unwrap();
+    settings.cost = cost;
+    settings.save().map_err(|e| e.to_string())
+}
+
+/// Export rated messages with their prompts as JSONL; returns the number written
+#[tauri::command]
+pub fn export_feedback(path: String) -> Result<usize, String> {
+    let records = get_chat_service().feedback_records();
+    
+    let mut output = String::new();
+    for record in &records {
+        output.push_str(&serde_json::to_string(record).map_err(|e| e.to_string())?);
+        output.push('\n');
+    }
+    std::fs::write(&path, output).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    
+    Ok(records.len())
+}
+
+/// Weekly usage of the last `weeks` weeks (12 by default) for the analytics dashboard
+#[tauri::command]
+pub fn get_usage_analytics(weeks: Option<usize>) -> Result<UsageAnalytics, String> {
+    let conversations = get_chat_service().conversations_with_messages();
+    let currency = get_settings().lock().unwrap().cost.currency.clone();
+    Ok(analytics::compute(
+        &conversations,
+        weeks.unwrap_or(12),
+        mcp_common::utils::clock::now(),
+        &PriceTable::new(),
+        &currency,
+    ))
+}
@@ -14,7 +14,9 @@ use crate::collaboration::{
     get_collaboration_manager
 };
 use crate::collaboration::presence::{CursorPosition, Selection};
+use crate::collaboration::sessions::{GenerationStatus, LockStatus};
 use crate::error::Result;
+use crate::services::supervisor::get_supervisor;
 use crate::models::messages::{Conversation, Message};
 
 /// Register collaboration commands with Tauri
@@ -43,8 +45,15 @@ pub fn register_commands(builder: tauri::Builder<Wry>) -> tauri::Builder<Wry> {
         
         // Sync commands
         sync_conversation,
+        sync_messages,
         send_message,
         
+        // Generation lock commands
+        request_generation,
+        renew_generation,
+        release_generation,
+        get_generation_status,
+        
         // AV commands
         start_audio_call,
         start_video_call,
@@ -72,7 +81,9 @@ pub fn register_commands(builder: tauri::Builder<Wry>) -> tauri::Builder<Wry> {
 /// Initialize the collaboration system
 #[tauri::command]
 pub async fn init_collaboration_system(config: Option<CollaborationConfig>) -> Result<()> {
-    init_collaboration(config)
+    let result = init_collaboration(config);
+    get_supervisor().report("collaboration", result.as_ref().map(|_| ()).map_err(|e| e.to_string()));
+    result
 }
 
 /// Get the current collaboration configuration
@@ -185,6 +196,13 @@ pub async fn sync_conversation(conversation: Conversation) -> Result<()> {
     manager.sync_conversation(&conversation)
 }
 
+/// Synchronize a conversation's messages as a delta
+#[tauri::command]
+pub async fn sync_messages(conversation_id: String, messages: Vec<Message>) -> Result<usize> {
+    let manager = get_collaboration_manager()?;
+    manager.sync_messages(&conversation_id, &messages)
+}
+
 /// Send a message in the collaborative session
 #[tauri::command]
 pub async fn send_message(message: Message) -> Result<()> {
@@ -192,6 +210,34 @@ pub async fn send_message(message: Message) -> Result<()> {
     manager.send_message(&message)
 }
 
+/// Ask for the generation lock of a shared conversation
+#[tauri::command]
+pub async fn request_generation(conversation_id: String, request_id: String) -> Result<Option<LockStatus>> {
+    let manager = get_collaboration_manager()?;
+    manager.request_generation(&conversation_id, &request_id)
+}
+
+/// Extend a held generation lock
+#[tauri::command]
+pub async fn renew_generation(conversation_id: String, request_id: String) -> Result<()> {
+    let manager = get_collaboration_manager()?;
+    manager.renew_generation(&conversation_id, &request_id)
+}
+
+/// Release a generation lock or leave the queue
+#[tauri::command]
+pub async fn release_generation(conversation_id: String, request_id: String) -> Result<()> {
+    let manager = get_collaboration_manager()?;
+    manager.release_generation(&conversation_id, &request_id)
+}
+
+/// Get who is generating in a conversation and who is waiting
+#[tauri::command]
+pub async fn get_generation_status(conversation_id: String) -> Result<GenerationStatus> {
+    let manager = get_collaboration_manager()?;
+    manager.get_generation_status(&conversation_id)
+}
+
 /// Start an audio call in the current session
 #[tauri::command]
 pub async fn start_audio_call() -> Result<()> {
@@ -0,0 +1,38 @@
+use mcp_common::config::{get_settings, DebugSettings};
+use mcp_common::protocol::inspector::{get_inspector, Exchange};
+
+/// Exchanges returned when the dev tools panel doesn't ask for a number
+const DEFAULT_EXCHANGE_LIMIT: usize = 20;
+
+/// Get the developer diagnostics settings
+#[tauri::command]
+pub fn get_debug_settings() -> DebugSettings {
+    get_settings().lock().unwrap().debug.clone()
+}
+
+/// Update the developer diagnostics settings (inspector toggle, buffer and body sizes)
+#[tauri::command]
+pub fn update_debug_settings(debug: DebugSettings) -> Result<(), String> {
+    let settings = get_settings();
+    let mut settings = settings.lock().unwrap();
+    settings.debug = debug;
+    settings.save().map_err(|e| e.to_string())
+}
+
+/// Most recent captured API exchanges, newest first
+#[tauri::command]
+pub fn get_captured_requests(limit: Option<usize>) -> Vec<Exchange> {
+    get_inspector().recent(limit.unwrap_or(DEFAULT_EXCHANGE_LIMIT))
+}
+
+/// A captured API exchange by ID
+#[tauri::command]
+pub fn get_captured_request(id: u64) -> Option<Exchange> {
+    get_inspector().get(id)
+}
+
+/// Forget all captured API exchanges
+#[tauri::command]
+pub fn clear_captured_requests() {
+    get_inspector().clear();
+}
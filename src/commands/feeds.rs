@@ -0,0 +1,119 @@
+use crate::utils::events::{notify, Notification, NotificationLevel};
+use log::{debug, warn};
+use mcp_common::events::{get_event_bus, names, Backpressure, Topic};
+use mcp_common::feeds::{
+    get_feed_store, spawn_feed_scheduler, Digest, DigestTarget, Feed, FeedDigester, FeedFilter, NewFeed,
+};
+use mcp_common::service::ChatService;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Chat service the digests are written with and posted through
+static FEED_CHAT: Lazy<Arc<ChatService>> = Lazy::new(|| Arc::new(ChatService::new(mcp_common::get_mcp_service())));
+
+/// Check feeds in the background, and raise digests meant for
+/// notifications as notifications
+pub fn start_feed_scheduler() {
+    spawn_feed_scheduler(FEED_CHAT.clone(), Duration::from_secs(60));
+
+    let mut subscription = get_event_bus().subscribe(&[Topic::System], 64, Backpressure::DropNewest);
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = subscription.recv().await {
+            if event.name != names::FEED_DIGEST {
+                continue;
+            }
+            match serde_json::from_value::<Digest>(event.payload) {
+                Ok(digest) if digest.target == DigestTarget::Notification => {
+                    let title = format!("{}: {} new", digest.feed_title, digest.items.len());
+                    notify(Notification::new(NotificationLevel::Info, title, digest.summary.trim()));
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Unreadable feed digest: {}", e),
+            }
+        }
+        debug!("Feed digest notifier stopped");
+    });
+}
+
+/// Subscribed feeds
+#[tauri::command]
+pub fn list_feeds() -> Vec<Feed> {
+    get_feed_store().list()
+}
+
+/// Subscribe to a feed
+#[tauri::command]
+pub async fn add_feed(feed: NewFeed) -> Result<Feed, String> {
+    if let DigestTarget::Conversation { conversation_id } = &feed.target {
+        FEED_CHAT
+            .get_conversation(conversation_id)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    get_feed_store().add(feed).map_err(|e| e.to_string())
+}
+
+/// Unsubscribe from a feed
+#[tauri::command]
+pub fn remove_feed(id: String) -> Result<(), String> {
+    get_feed_store().remove(&id).map_err(|e| e.to_string())
+}
+
+/// Change a feed's title, filter, target, model, interval or state
+#[tauri::command]
+pub fn update_feed(
+    id: String,
+    title: Option<String>,
+    filter: Option<FeedFilter>,
+    target: Option<DigestTarget>,
+    model: Option<Option<String>>,
+    interval_minutes: Option<u32>,
+    enabled: Option<bool>,
+) -> Result<Feed, String> {
+    get_feed_store()
+        .update(&id, |feed| {
+            if let Some(title) = title {
+                feed.title = title;
+            }
+            if let Some(filter) = filter {
+                feed.filter = filter;
+            }
+            if let Some(target) = target {
+                feed.target = target;
+            }
+            if let Some(model) = model {
+                feed.model = model;
+            }
+            if let Some(interval) = interval_minutes {
+                feed.interval_minutes = interval;
+            }
+            if let Some(enabled) = enabled {
+                feed.enabled = enabled;
+            }
+        })
+        .map_err(|e| e.to_string())
+}
+
+/// Check a feed now; returns the digest delivered, if there was anything new
+#[tauri::command]
+pub async fn run_feed_now(id: String) -> Result<Option<Digest>, String> {
+    FeedDigester::new(FEED_CHAT.clone())
+        .run(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Mark everything a feed holds now as read
+#[tauri::command]
+pub async fn mark_feed_read(id: String) -> Result<Feed, String> {
+    let store = get_feed_store();
+    let feed = store.get(&id).map_err(|e| e.to_string())?;
+    let parsed = FeedDigester::new(FEED_CHAT.clone())
+        .fetch(&feed.url)
+        .await
+        .map_err(|e| e.to_string())?;
+    store
+        .mark_read(&feed.id, parsed.items.into_iter().map(|item| item.id))
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,16 @@
+use mcp_common::service::filters::{get_filter_settings, FilterSettings};
+
+/// Get the content filter profiles
+#[tauri::command]
+pub fn get_content_filters() -> FilterSettings {
+    get_filter_settings().read().unwrap().clone()
+}
+
+/// Replace the content filter profiles (default and per-workspace)
+#[tauri::command]
+pub fn update_content_filters(filters: FilterSettings) -> Result<(), String> {
+    filters.validate().map_err(|e| e.to_string())?;
+    filters.save().map_err(|e| e.to_string())?;
+    *get_filter_settings().write().unwrap() = filters;
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use mcp_common::integrations::github::{self, get_github_client, GitHubRef, RateLimit, Snippet};
+use mcp_common::integrations::{ContextItem, CONTEXT_METADATA_KEY};
+
+use crate::models::messages::Message;
+use crate::services::chat::get_chat_service;
+
+/// Store the GitHub token, or remove it with `None`
+#[tauri::command]
+pub fn set_github_token(token: Option<String>) -> Result<(), String> {
+    github::set_token(token.as_deref()).map_err(|e| e.to_string())
+}
+
+/// Whether GitHub requests are authenticated
+#[tauri::command]
+pub fn has_github_token() -> bool {
+    get_github_client().has_token()
+}
+
+/// Lines of a file, from a blob link or `owner/repo/path@ref#L10-L20`
+#[tauri::command]
+pub async fn github_snippet(reference: String) -> Result<Snippet, String> {
+    let reference: GitHubRef = reference.parse().map_err(|e: mcp_common::error::McpError| e.to_string())?;
+    let GitHubRef::File {
+        owner,
+        repo,
+        path,
+        reference,
+        lines,
+    } = reference
+    else {
+        return Err("Not a file".to_string());
+    };
+    get_github_client()
+        .snippet(&owner, &repo, &path, reference.as_deref(), lines)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Add a file, issue or pull request to a conversation as context
+#[tauri::command]
+pub async fn import_github_context(conversation_id: String, reference: String) -> Result<ContextItem, String> {
+    let reference: GitHubRef = reference.parse().map_err(|e: mcp_common::error::McpError| e.to_string())?;
+    let chat_service = get_chat_service();
+    if chat_service.get_conversation(&conversation_id).is_none() {
+        return Err(format!("Conversation {} not found", conversation_id));
+    }
+
+    let item = get_github_client().fetch(&reference).await.map_err(|e| e.to_string())?;
+    let message = Message::new_user_text(item.text.clone()).with_metadata(CONTEXT_METADATA_KEY, item.metadata());
+    chat_service.add_context_message(&conversation_id, message);
+    Ok(item)
+}
+
+/// GitHub's API rate limit
+#[tauri::command]
+pub async fn github_rate_limit() -> Result<Option<RateLimit>, String> {
+    get_github_client().check_rate_limit().await.map_err(|e| e.to_string())
+}
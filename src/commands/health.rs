@@ -0,0 +1,13 @@
+use crate::services::supervisor::{get_supervisor, HealthReport, ServiceHealth};
+
+/// Health of the app's subsystems and the features disabled by failures
+#[tauri::command]
+pub fn get_service_health() -> HealthReport {
+    get_supervisor().health()
+}
+
+/// Restart a subsystem now instead of waiting for its next automatic restart
+#[tauri::command]
+pub async fn restart_service(name: String) -> Result<ServiceHealth, String> {
+    get_supervisor().restart(&name)
+}
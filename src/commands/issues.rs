@@ -0,0 +1,77 @@
+use mcp_common::integrations::issues::{
+    self, get_issue_importer, get_issue_settings, Issue, IssueField, IssueSettings, IssueSummary, IssueTracker,
+};
+use mcp_common::integrations::CONTEXT_METADATA_KEY;
+
+use crate::models::messages::Message;
+use crate::services::chat::get_chat_service;
+
+/// Get the issue tracker settings
+#[tauri::command]
+pub fn get_issue_tracker_settings() -> IssueSettings {
+    get_issue_settings().read().unwrap().clone()
+}
+
+/// Replace the issue tracker settings
+#[tauri::command]
+pub fn update_issue_tracker_settings(settings: IssueSettings) -> Result<(), String> {
+    settings.save().map_err(|e| e.to_string())?;
+    *get_issue_settings().write().unwrap() = settings;
+    get_issue_importer().clear_cache();
+    Ok(())
+}
+
+/// Store the token of Jira or Linear, or remove it with `None`
+#[tauri::command]
+pub fn set_issue_tracker_token(tracker: IssueTracker, token: Option<String>) -> Result<(), String> {
+    issues::set_token(tracker, token.as_deref()).map_err(|e| e.to_string())
+}
+
+/// Whether a tracker has its token, and Jira its site
+#[tauri::command]
+pub fn is_issue_tracker_configured(tracker: IssueTracker) -> bool {
+    issues::is_configured(tracker)
+}
+
+/// Read an issue from its link or key
+#[tauri::command]
+pub async fn get_issue(reference: String, refresh: Option<bool>) -> Result<Issue, String> {
+    get_issue_importer()
+        .fetch(&reference, refresh.unwrap_or(false))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Find issues by text
+#[tauri::command]
+pub async fn search_issues(
+    query: String,
+    tracker: Option<IssueTracker>,
+    limit: Option<usize>,
+) -> Result<Vec<IssueSummary>, String> {
+    get_issue_importer()
+        .search(tracker, &query, limit.unwrap_or(issues::MAX_SEARCH_RESULTS))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Add an issue to a conversation as context, with the given fields or the
+/// configured ones
+#[tauri::command]
+pub async fn import_issue_context(
+    conversation_id: String,
+    reference: String,
+    fields: Option<Vec<IssueField>>,
+) -> Result<Issue, String> {
+    let chat_service = get_chat_service();
+    if chat_service.get_conversation(&conversation_id).is_none() {
+        return Err(format!("Conversation {} not found", conversation_id));
+    }
+
+    let issue = get_issue_importer().fetch(&reference, false).await.map_err(|e| e.to_string())?;
+    let fields = fields.unwrap_or_else(|| get_issue_settings().read().unwrap().fields.clone());
+    let item = issue.to_context(&fields);
+    let message = Message::new_user_text(item.text.clone()).with_metadata(CONTEXT_METADATA_KEY, item.metadata());
+    chat_service.add_context_message(&conversation_id, message);
+    Ok(issue)
+}
@@ -0,0 +1,31 @@
+use mcp_common::jobs::{get_job_manager, Job};
+
+/// List background jobs, newest first
+#[tauri::command]
+pub fn list_jobs() -> Vec<Job> {
+    get_job_manager().list()
+}
+
+/// Get a background job
+#[tauri::command]
+pub fn get_job(id: String) -> Result<Job, String> {
+    get_job_manager().get(&id).map_err(|e| e.to_string())
+}
+
+/// Ask a background job to stop
+#[tauri::command]
+pub fn cancel_job(id: String) -> Result<Job, String> {
+    get_job_manager().cancel(&id).map_err(|e| e.to_string())
+}
+
+/// Continue a job interrupted by a shutdown
+#[tauri::command]
+pub async fn resume_job(id: String) -> Result<Job, String> {
+    get_job_manager().resume(&id).map_err(|e| e.to_string())
+}
+
+/// Forget finished jobs
+#[tauri::command]
+pub fn clear_finished_jobs() -> usize {
+    get_job_manager().clear_finished()
+}
@@ -0,0 +1,70 @@
+use mcp_common::keymap::{get_keymap, KeyConflict, Keymap, ACTIONS};
+use serde::Serialize;
+use std::path::Path;
+
+/// A bindable action with its current keys, for the settings UI
+#[derive(Debug, Clone, Serialize)]
+pub struct KeymapAction {
+    pub id: String,
+    pub description: String,
+    pub keys: Vec<String>,
+}
+
+/// Make a keymap the saved and active one
+fn activate(keymap: Keymap) -> Result<Keymap, String> {
+    keymap.save().map_err(|e| e.to_string())?;
+    *get_keymap().write().unwrap() = keymap.clone();
+    Ok(keymap)
+}
+
+/// Get the user keymap
+#[tauri::command]
+pub fn get_keymap_settings() -> Keymap {
+    get_keymap().read().unwrap().clone()
+}
+
+/// List every bindable action with its current keys
+#[tauri::command]
+pub fn get_keymap_actions() -> Vec<KeymapAction> {
+    let keymap = get_keymap().read().unwrap().clone();
+    ACTIONS
+        .iter()
+        .map(|action| KeymapAction {
+            id: action.id.to_string(),
+            description: action.description.to_string(),
+            keys: keymap.keys(action.id).iter().map(|k| k.to_string()).collect(),
+        })
+        .collect()
+}
+
+/// Rebind an action; rejected if it would conflict with another binding
+#[tauri::command]
+pub fn update_keybinding(action: String, keys: Vec<String>) -> Result<Keymap, String> {
+    let mut keymap = get_keymap().read().unwrap().clone();
+    keymap.bind(&action, &keys).map_err(|e| e.to_string())?;
+    activate(keymap)
+}
+
+/// Replace the keymap with a built-in preset (default, vim, emacs)
+#[tauri::command]
+pub fn apply_keymap_preset(preset: String) -> Result<Keymap, String> {
+    activate(Keymap::preset(&preset).map_err(|e| e.to_string())?)
+}
+
+/// Export the keymap to a file
+#[tauri::command]
+pub fn export_keymap(path: String) -> Result<(), String> {
+    get_keymap().read().unwrap().export(Path::new(&path)).map_err(|e| e.to_string())
+}
+
+/// Import a keymap from a file and make it active
+#[tauri::command]
+pub fn import_keymap(path: String) -> Result<Keymap, String> {
+    activate(Keymap::import(Path::new(&path)).map_err(|e| e.to_string())?)
+}
+
+/// Conflicting bindings in the current keymap
+#[tauri::command]
+pub fn get_keymap_conflicts() -> Vec<KeyConflict> {
+    get_keymap().read().unwrap().conflicts()
+}
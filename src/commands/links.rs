@@ -0,0 +1,36 @@
+use mcp_common::config::{get_settings, LinkSettings};
+use mcp_common::service::unfurl::{get_unfurler, LinkPreview};
+
+/// Get the link preview settings
+#[tauri::command]
+pub fn get_link_settings() -> LinkSettings {
+    get_settings().lock().unwrap().links.clone()
+}
+
+/// Update the link preview settings (privacy toggle and domain allowlist)
+#[tauri::command]
+pub fn update_link_settings(links: LinkSettings) -> Result<(), String> {
+    let settings = get_settings();
+    let mut settings = settings.lock().unwrap();
+    settings.links = links;
+    settings.save().map_err(|e| e.to_string())
+}
+
+/// Get a link's preview, fetching it when allowed and not cached
+#[tauri::command]
+pub async fn get_link_preview(url: String) -> Result<Option<LinkPreview>, String> {
+    get_unfurler().unfurl(&url).await.map_err(|e| e.to_string())
+}
+
+/// Cached previews for links, without fetching anything
+#[tauri::command]
+pub fn get_cached_link_previews(urls: Vec<String>) -> Vec<LinkPreview> {
+    let unfurler = get_unfurler();
+    urls.iter().filter_map(|url| unfurler.cached(url)).collect()
+}
+
+/// Forget all cached link previews
+#[tauri::command]
+pub fn clear_link_previews() {
+    get_unfurler().clear();
+}
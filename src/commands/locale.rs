@@ -0,0 +1,29 @@
+use mcp_common::config::get_settings;
+use mcp_common::i18n;
+
+/// Get the locale backend messages are formatted in
+#[tauri::command]
+pub fn get_locale() -> String {
+    i18n::current_locale()
+}
+
+/// List locales with message catalogs
+#[tauri::command]
+pub fn get_available_locales() -> Vec<String> {
+    i18n::available_locales().into_iter().map(String::from).collect()
+}
+
+/// Save the message language and switch to it; `None` goes back to the
+/// system language. Returns the locale in use.
+#[tauri::command]
+pub fn set_locale(locale: Option<String>) -> Result<String, String> {
+    {
+        let settings = get_settings();
+        let mut settings = settings.lock().unwrap();
+        settings.ui.language = locale.clone();
+        settings.save().map_err(|e| e.to_string())?;
+    }
+
+    let requested = locale.unwrap_or_else(i18n::detect_locale);
+    Ok(i18n::set_locale(&requested))
+}
@@ -0,0 +1,49 @@
+use chrono::NaiveDate;
+use mcp_common::service::meetings::{MeetingNotes, MeetingSummarizer, SummaryFormat, Transcript};
+use mcp_common::service::ChatService;
+use mcp_common::utils::clock;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+
+/// Chat service the summaries are generated with; its conversations are
+/// removed again after each summary
+static MEETING_CHAT: Lazy<Arc<ChatService>> = Lazy::new(|| Arc::new(ChatService::new(mcp_common::get_mcp_service())));
+
+/// Summarize a meeting transcript into notes and action items
+#[tauri::command]
+pub async fn summarize_meeting(
+    transcript: String,
+    title: Option<String>,
+    date: Option<NaiveDate>,
+    format: Option<SummaryFormat>,
+    model: Option<String>,
+) -> Result<MeetingNotes, String> {
+    let chat_service = MEETING_CHAT.clone();
+    let mut summarizer = MeetingSummarizer::new(chat_service.clone()).with_format(format.unwrap_or_default());
+    if let Some(name) = model {
+        let available = chat_service.available_models().await.map_err(|e| e.to_string())?;
+        let model = available
+            .into_iter()
+            .find(|m| m.id == name || m.name == name)
+            .ok_or_else(|| format!("Unknown model: {}", name))?;
+        summarizer = summarizer.with_model(model);
+    }
+
+    let date = date.unwrap_or_else(|| clock::now().date_naive());
+    summarizer
+        .summarize(&Transcript::parse(&transcript), title.as_deref(), date)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Meeting notes as a Markdown document
+#[tauri::command]
+pub fn export_meeting_markdown(notes: MeetingNotes) -> String {
+    notes.to_markdown()
+}
+
+/// Action items of a meeting as an iCalendar file of tasks
+#[tauri::command]
+pub fn export_meeting_ics(notes: MeetingNotes) -> String {
+    notes.to_ics()
+}
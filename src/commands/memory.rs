@@ -0,0 +1,29 @@
+use crate::services::chat::get_chat_service;
+use mcp_common::memory::{get_memory_store, MemoryNote};
+
+/// Notes remembered for a workspace, or for conversations outside any
+#[tauri::command]
+pub fn list_memories(workspace: Option<String>) -> Vec<MemoryNote> {
+    get_memory_store().list(workspace.as_deref())
+}
+
+/// Remember facts from a conversation for later ones in its workspace
+#[tauri::command]
+pub fn remember(conversation_id: String, text: String) -> Result<Vec<MemoryNote>, String> {
+    let workspace = get_chat_service().workspace_of(&conversation_id);
+    get_memory_store()
+        .remember(workspace.as_deref(), &text, Some(&conversation_id))
+        .map_err(|e| e.to_string())
+}
+
+/// Change the text of a remembered note
+#[tauri::command]
+pub fn update_memory(id: String, text: String) -> Result<MemoryNote, String> {
+    get_memory_store().update(&id, &text).map_err(|e| e.to_string())
+}
+
+/// Forget a remembered note
+#[tauri::command]
+pub fn delete_memory(id: String) -> Result<(), String> {
+    get_memory_store().delete(&id).map_err(|e| e.to_string())
+}
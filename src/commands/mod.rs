@@ -1,10 +1,42 @@
+pub mod accessibility;
+pub mod agent;
 pub mod ai;
+pub mod apply;
+pub mod attachments;
 pub mod auth;
+pub mod catalogs;
 pub mod chat;
 pub mod collaboration;
+pub mod debug;
+pub mod feeds;
+pub mod filters;
+pub mod github;
+pub mod health;
+pub mod issues;
+pub mod jobs;
+pub mod keymap;
+pub mod links;
+pub mod locale;
 pub mod mcp;
+pub mod meetings;
+pub mod memory;
+pub mod ocr;
 pub mod offline;
+pub mod onboarding;
+pub mod optimization;
+pub mod platform;
+pub mod rag;
+pub mod reminders;
 pub mod security;
+pub mod share;
+pub mod snapshots;
+pub mod team;
+pub mod templates;
+pub mod terminal;
+pub mod theme;
+pub mod tools;
+pub mod translation;
+pub mod webhooks;
 
 use tauri::Wry;
 
@@ -18,15 +50,43 @@ pub fn register_commands(builder: tauri::Builder<Wry>) -> tauri::Builder<Wry> {
             auth::validate_api_key,
             auth::get_organization_id,
             auth::logout,
+            auth::get_connection_health,
+            auth::validate_credentials,
+            auth::get_oauth_settings,
+            auth::update_oauth_settings,
+            auth::start_oauth_login,
+            auth::complete_oauth_login,
+            auth::get_oauth_status,
+            auth::oauth_logout,
             
             // Chat commands
             chat::get_available_models,
             chat::create_conversation,
             chat::get_conversation,
             chat::get_conversations,
+            chat::mark_conversation_read,
+            chat::get_first_unread,
+            chat::get_draft,
+            chat::set_draft,
             chat::delete_conversation,
+            chat::undo_last_operation,
+            chat::redo_last_operation,
+            chat::get_undo_history,
             chat::get_messages,
+            chat::get_message_history,
             chat::send_message,
+            chat::rate_message,
+            chat::estimate_message_cost,
+            chat::get_model_prices,
+            chat::set_model_price,
+            chat::reset_model_price,
+            chat::refresh_model_prices,
+            chat::get_currencies,
+            chat::set_exchange_rate,
+            chat::get_cost_settings,
+            chat::update_cost_settings,
+            chat::export_feedback,
+            chat::get_usage_analytics,
             
             // MCP commands
             mcp::connect,
@@ -35,18 +95,348 @@ pub fn register_commands(builder: tauri::Builder<Wry>) -> tauri::Builder<Wry> {
             
             // AI commands
             ai::get_available_models,
+            ai::get_model_capabilities,
+            ai::get_capability_overrides,
+            ai::set_model_capability,
+            ai::reset_model_capability,
             ai::set_network_status,
             ai::send_message,
             ai::stream_message,
             ai::cancel_streaming,
+            ai::cancel_request,
             ai::get_messages,
             ai::create_conversation,
             ai::delete_conversation,
+            ai::get_acceleration_info,
+            ai::get_gpu_config,
+            ai::update_gpu_config,
+            ai::gpu_benchmark,
+            ai::list_local_models,
+            ai::download_local_model,
+            ai::delete_local_model,
+            ai::list_model_families,
+            ai::list_model_variants,
+            ai::set_preferred_variant,
+            ai::delete_model_family,
+            ai::get_model_updates,
+            ai::update_model,
+            ai::switch_model_version,
+            ai::set_model_update_settings,
+            ai::list_lora_adapters,
+            ai::import_lora_adapter,
+            ai::download_lora_adapter,
+            ai::delete_lora_adapter,
+            ai::get_conversation_adapters,
+            ai::set_conversation_adapters,
+            ai::get_context_overflow_strategy,
+            ai::set_context_overflow_strategy,
+            ai::export_training_data,
+            ai::start_finetune,
+            ai::set_default_local_model,
+            ai::get_subsystem_models,
+            ai::get_local_prompt,
+            ai::generate_structured,
+            ai::get_prompt_cache_stats,
+            ai::get_latency_stats,
+            ai::get_latency_alerts,
+            ai::get_latency_sla,
+            ai::set_latency_sla,
+            
+            // Model catalog commands
+            catalogs::list_model_catalogs,
+            catalogs::list_catalog_models,
+            catalogs::add_model_catalog,
+            catalogs::remove_model_catalog,
+            catalogs::set_model_catalog_trust,
+            catalogs::set_model_catalog_token,
+            catalogs::set_model_catalog_refresh_hours,
+            catalogs::refresh_model_catalogs,
+            catalogs::search_hf_models,
+            catalogs::register_hf_model,
+            catalogs::set_hf_token,
+            
+            // Tool commands
+            tools::list_local_tools,
+            tools::run_sandboxed_code,
+            tools::bind_conversation_repo,
+            tools::unbind_conversation_repo,
+            tools::get_conversation_repo,
+            tools::list_environment_variables,
+            tools::set_environment_variable,
+            tools::unset_environment_variable,
+            tools::get_tool_dry_run,
+            tools::set_tool_dry_run,
+            tools::list_simulated_tool_calls,
+            tools::clear_simulated_tool_calls,
+            tools::get_tool_policy,
+            tools::add_tool_policy_rule,
+            tools::remove_tool_policy_rule,
+            tools::set_tool_policy_default,
+            tools::test_tool_policy,
+            
+            // Agent commands
+            agent::start_agent_run,
+            agent::pause_agent_run,
+            agent::resume_agent_run,
+            agent::abort_agent_run,
+            agent::list_agent_runs,
+            agent::get_agent_transcript,
+            agent::start_team_run,
+            agent::get_agent_team,
+            agent::set_agent_profile,
+            agent::remove_agent_profile,
+            agent::add_agent_route,
+            agent::remove_agent_route,
+            agent::export_agent_graph,
+            agent::list_kept_agent_runs,
+            agent::get_agent_run_frames,
+            agent::rerun_agent_run,
+            agent::diff_agent_runs,
+            agent::delete_kept_agent_run,
+            
+            // Attachment commands
+            attachments::add_attachment,
+            attachments::add_attachment_data,
+            attachments::list_attachments,
+            attachments::get_attachment_usage,
+            attachments::get_attachment_preview,
+            attachments::open_attachment,
+            attachments::delete_attachment,
+            attachments::get_request_limits,
+            attachments::set_request_limits,
+            attachments::reset_request_limits,
+            attachments::check_attachments,
+            attachments::downscale_attachment,
+            
+            // Link preview commands
+            links::get_link_settings,
+            links::update_link_settings,
+            links::get_link_preview,
+            links::get_cached_link_previews,
+            links::clear_link_previews,
+            
+            // Accessibility commands
+            accessibility::get_accessibility_settings,
+            accessibility::get_motion_allowed,
+            accessibility::update_accessibility_settings,
+            
+            // Locale commands
+            locale::get_locale,
+            locale::get_available_locales,
+            locale::set_locale,
+            
+            // Onboarding commands
+            onboarding::get_onboarding_state,
+            onboarding::check_api_key,
+            onboarding::scan_onboarding_hardware,
+            onboarding::complete_onboarding,
+            
+            // Content filter commands
+            filters::get_content_filters,
+            filters::update_content_filters,
+            
+            // Translation commands
+            translation::get_translation_settings,
+            translation::update_translation_settings,
+            translation::set_translation_api_key,
+            translation::is_translation_configured,
+            translation::set_conversation_language,
+            translation::detect_language,
+            translation::translate_text,
+            
+            // GitHub commands
+            github::set_github_token,
+            github::has_github_token,
+            github::github_snippet,
+            github::import_github_context,
+            github::github_rate_limit,
+            
+            // Issue tracker commands
+            issues::get_issue_tracker_settings,
+            issues::update_issue_tracker_settings,
+            issues::set_issue_tracker_token,
+            issues::is_issue_tracker_configured,
+            issues::get_issue,
+            issues::search_issues,
+            issues::import_issue_context,
+            
+            // Memory commands
+            memory::list_memories,
+            memory::remember,
+            memory::update_memory,
+            memory::delete_memory,
+            
+            // Knowledge base commands
+            rag::list_knowledge_bases,
+            rag::get_knowledge_base_stats,
+            rag::get_last_reindex,
+            rag::configure_knowledge_base,
+            rag::search_knowledge_base,
+            rag::evaluate_chunking,
+            rag::set_conversation_knowledge_bases,
+            rag::get_message_citations,
+            rag::reindex_knowledge_base,
+            
+            // Feed commands
+            feeds::list_feeds,
+            feeds::add_feed,
+            feeds::remove_feed,
+            feeds::update_feed,
+            feeds::run_feed_now,
+            feeds::mark_feed_read,
+            reminders::list_reminders,
+            reminders::set_reminder,
+            reminders::snooze_reminder,
+            reminders::cancel_reminder,
+            
+            // Template commands
+            templates::list_templates,
+            templates::remove_template,
+            templates::get_template_gallery,
+            templates::set_template_gallery,
+            templates::browse_template_gallery,
+            templates::preview_gallery_template,
+            templates::install_gallery_template,
+            templates::check_template_updates,
+            
+            // Sharing commands
+            snapshots::render_snapshot,
+            snapshots::publish_snapshot,
+            snapshots::list_snapshot_shares,
+            snapshots::revoke_snapshot_share,
+            snapshots::get_sharing_settings,
+            snapshots::update_sharing_settings,
+            snapshots::set_s3_share_keys,
+            
+            // Keymap commands
+            keymap::get_keymap_settings,
+            keymap::get_keymap_actions,
+            keymap::update_keybinding,
+            keymap::apply_keymap_preset,
+            keymap::export_keymap,
+            keymap::import_keymap,
+            keymap::get_keymap_conflicts,
+            
+            // Theme commands
+            theme::list_themes,
+            theme::get_active_theme,
+            theme::get_theme_css,
+            theme::set_theme,
+            theme::save_custom_theme,
+            theme::delete_custom_theme,
+            
+            // Team workspace commands
+            team::get_team_status,
+            team::join_team,
+            team::leave_team,
+            team::sync_team,
+            team::list_team_prompts,
+            team::save_team_prompt,
+            team::delete_team_prompt,
+            team::list_shared_conversations,
+            team::share_conversation_with_team,
+            team::unshare_conversation_with_team,
+            team::list_team_members,
+            team::invite_team_member,
+            team::set_team_member_role,
+            team::remove_team_member,
+            
+            // Request inspector commands
+            debug::get_debug_settings,
+            debug::update_debug_settings,
+            debug::get_captured_requests,
+            debug::get_captured_request,
+            debug::clear_captured_requests,
+            
+            // Service health commands
+            health::get_service_health,
+            health::restart_service,
+            
+            // Job commands
+            jobs::list_jobs,
+            jobs::get_job,
+            jobs::cancel_job,
+            jobs::resume_job,
+            jobs::clear_finished_jobs,
+            
+            // OCR commands
+            ocr::get_ocr_config,
+            ocr::update_ocr_config,
+            ocr::is_ocr_available,
+            ocr::extract_image_text,
+            
+            // Platform commands
+            platform::get_platform_info,
+            platform::copy_to_clipboard,
+            platform::read_clipboard,
+            
+            // Share target commands
+            share::take_shared_drafts,
+            share::get_share_target_status,
+            share::set_share_target_enabled,
+            
+            // Webhook commands
+            webhooks::list_webhooks,
+            webhooks::add_webhook,
+            webhooks::remove_webhook,
+            webhooks::set_webhook_enabled,
+            webhooks::set_webhook_events,
+            webhooks::rotate_webhook_secret,
+            webhooks::test_webhook,
+            webhooks::get_webhook_queue,
+            
+            // Meeting commands
+            meetings::summarize_meeting,
+            meetings::export_meeting_markdown,
+            meetings::export_meeting_ics,
+            
+            // Terminal commands
+            terminal::create_terminal,
+            terminal::write_terminal,
+            terminal::resize_terminal,
+            terminal::attach_terminal,
+            terminal::close_terminal,
+            terminal::list_terminals,
+            
+            // Patch apply commands
+            apply::preview_message_patch,
+            apply::apply_message_patch,
         ]);
     
     // Register offline commands
     let builder = offline::register_offline_commands(builder);
     
+    // Register optimization commands
+    let builder = builder
+        .manage(optimization::OptimizationState::new())
+        .invoke_handler(tauri::generate_handler![
+            // Memory and cache commands
+            optimization::init_optimizations,
+            optimization::get_memory_stats,
+            optimization::get_memory_limits,
+            optimization::update_memory_limits,
+            optimization::force_gc,
+            optimization::get_api_cache_stats,
+            optimization::get_api_cache_config,
+            optimization::update_api_cache_config,
+            optimization::clear_api_cache,
+            optimization::get_resource_cache_stats,
+            optimization::get_resource_cache_config,
+            optimization::update_resource_cache_config,
+            optimization::clear_resource_cache,
+            
+            // Memory pressure commands
+            optimization::get_memory_pressure_status,
+            optimization::get_memory_pressure_config,
+            optimization::update_memory_pressure_config,
+            optimization::set_model_pinned,
+            
+            // Thread pool commands
+            optimization::get_thread_config,
+            optimization::update_thread_config,
+            optimization::get_thread_settings,
+        ]);
+    
     // Register security commands
     let builder = builder
         .invoke_handler(tauri::generate_handler![
@@ -0,0 +1,27 @@
+use crate::ocr::{OcrConfig, OcrEngine};
+
+/// Get the OCR settings
+#[tauri::command]
+pub fn get_ocr_config() -> OcrConfig {
+    OcrConfig::load()
+}
+
+/// Update the OCR settings, e.g. to switch between sending images and text
+#[tauri::command]
+pub fn update_ocr_config(config: OcrConfig) -> Result<(), String> {
+    config.save()
+}
+
+/// Whether OCR is available on this machine
+#[tauri::command]
+pub fn is_ocr_available() -> bool {
+    OcrEngine::new(&OcrConfig::load()).is_available()
+}
+
+/// Extract text from a pasted or attached image so it can be stored with the image part
+#[tauri::command]
+pub async fn extract_image_text(url: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || OcrEngine::new(&OcrConfig::load()).extract_from_url(&url))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -4,8 +4,11 @@ use log::{debug, info, warn, error};
 use std::sync::Arc;
 
 use crate::offline::{self, ConnectivityStatus, OfflineConfig, OfflineStats};
+use crate::offline::sync::{DeviceSyncScope, SyncDevice};
+use mcp_common::sync::{get_conflict_queue, ConflictResolution, PendingConflict};
 use crate::models::messages::{Message, Conversation};
 use crate::error::Result;
+use crate::utils::cancellation::get_cancellation_registry;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OfflineResponse {
@@ -44,6 +47,12 @@ pub async fn get_connectivity_status() -> Result<ConnectivityStatus> {
     Ok(offline::get_connectivity_status())
 }
 
+/// Run a detailed network check (captive portal / DNS-only detection)
+#[command]
+pub async fn check_network_status() -> Result<offline::connectivity::NetworkCheck> {
+    Ok(offline::connectivity::check_and_report_network_status(std::time::Duration::from_secs(5)).await)
+}
+
 /// Enable offline mode
 #[command]
 pub async fn enable_offline_mode() -> Result<OfflineResponse> {
@@ -69,9 +78,25 @@ pub async fn process_message_offline(message: Message, conversation: Conversatio
 }
 
 /// Sync offline changes to the cloud
+///
+/// Passing a request ID lets the frontend stop the sync with `cancel_request`.
 #[command]
-pub async fn sync_offline_changes() -> Result<OfflineResponse> {
-    match offline::sync_changes() {
+pub async fn sync_offline_changes(request_id: Option<String>) -> Result<OfflineResponse> {
+    let registry = get_cancellation_registry();
+    let token = request_id
+        .as_deref()
+        .map(|id| registry.register(id, None).token)
+        .unwrap_or_default();
+    
+    let sync_manager = offline::get_offline_manager().get_sync_manager();
+    let result = tokio::task::spawn_blocking(move || sync_manager.manual_sync(&token))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    if let Some(id) = &request_id {
+        registry.complete(id);
+    }
+    
+    match result {
         Ok(_) => Ok(OfflineResponse::success("Changes synced successfully", None)),
         Err(e) => Ok(OfflineResponse::error(&format!("Failed to sync changes: {}", e))),
     }
@@ -83,6 +108,78 @@ pub async fn get_pending_sync_count() -> Result<usize> {
     offline::get_pending_sync_count()
 }
 
+/// List sync conflicts waiting for review
+#[command]
+pub async fn list_sync_conflicts() -> Result<Vec<PendingConflict>> {
+    Ok(get_conflict_queue().list())
+}
+
+/// Get a sync conflict with both its local and remote versions
+#[command]
+pub async fn get_sync_conflict(id: String) -> Result<Option<PendingConflict>> {
+    Ok(get_conflict_queue().get(&id))
+}
+
+/// Resolve a sync conflict; the chosen value is applied on the next sync
+#[command]
+pub async fn resolve_sync_conflict(id: String, resolution: ConflictResolution) -> Result<OfflineResponse> {
+    let queue = get_conflict_queue();
+    match queue.resolve(&id, resolution) {
+        Ok(resolved) => {
+            Ok(OfflineResponse::success(
+                &format!("Conflict for '{}' resolved", resolved.key),
+                serde_json::to_value(&resolved).ok(),
+            ))
+        }
+        Err(e) => Ok(OfflineResponse::error(&format!("Failed to resolve conflict: {}", e))),
+    }
+}
+
+/// Opt a conversation in to or out of sync
+#[command]
+pub async fn set_conversation_sync(conversation_id: String, enabled: bool) -> Result<OfflineResponse> {
+    offline::get_offline_manager()
+        .get_sync_manager()
+        .set_conversation_sync(&conversation_id, enabled);
+    Ok(OfflineResponse::success(
+        if enabled { "Conversation will sync" } else { "Conversation is local-only" },
+        None,
+    ))
+}
+
+/// List devices taking part in sync
+#[command]
+pub async fn list_sync_devices() -> Result<Vec<SyncDevice>> {
+    Ok(offline::get_offline_manager().get_sync_manager().list_devices())
+}
+
+/// Give a sync device a user-facing name
+#[command]
+pub async fn rename_sync_device(device_id: String, name: String) -> Result<OfflineResponse> {
+    match offline::get_offline_manager().get_sync_manager().rename_device(&device_id, &name) {
+        Ok(_) => Ok(OfflineResponse::success("Device renamed", None)),
+        Err(e) => Ok(OfflineResponse::error(&e)),
+    }
+}
+
+/// Set which data a device may sync
+#[command]
+pub async fn set_sync_device_scope(device_id: String, scope: DeviceSyncScope) -> Result<OfflineResponse> {
+    match offline::get_offline_manager().get_sync_manager().set_device_scope(&device_id, scope) {
+        Ok(_) => Ok(OfflineResponse::success("Device sync scope updated", None)),
+        Err(e) => Ok(OfflineResponse::error(&e)),
+    }
+}
+
+/// Revoke a device's sync access
+#[command]
+pub async fn revoke_sync_device(device_id: String) -> Result<OfflineResponse> {
+    match offline::get_offline_manager().get_sync_manager().revoke_device(&device_id) {
+        Ok(_) => Ok(OfflineResponse::success("Device sync access revoked", None)),
+        Err(e) => Ok(OfflineResponse::error(&e)),
+    }
+}
+
 /// Get the offline configuration
 #[command]
 pub async fn get_offline_config() -> Result<OfflineConfig> {
@@ -124,11 +221,20 @@ pub fn register_offline_commands(builder: tauri::Builder<tauri::Wry>) -> tauri::
     builder.invoke_handler(tauri::generate_handler![
         is_offline_mode_active,
         get_connectivity_status,
+        check_network_status,
         enable_offline_mode,
         disable_offline_mode,
         process_message_offline,
         sync_offline_changes,
         get_pending_sync_count,
+        list_sync_conflicts,
+        get_sync_conflict,
+        resolve_sync_conflict,
+        set_conversation_sync,
+        list_sync_devices,
+        rename_sync_device,
+        set_sync_device_scope,
+        revoke_sync_device,
         get_offline_config,
         update_offline_config,
         get_offline_stats,
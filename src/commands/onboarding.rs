@@ -0,0 +1,47 @@
+use mcp_common::config::get_settings;
+use mcp_common::service::onboarding::{self, ApiKeyCheck, HardwareScan, OnboardingAnswers, OnboardingState};
+use serde::Serialize;
+
+use crate::ai::local::acceleration::AccelerationInfo;
+
+/// Hardware scan plus GPU acceleration, for the offline step
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingHardware {
+    #[serde(flatten)]
+    pub scan: HardwareScan,
+    pub can_run_local: bool,
+    pub acceleration: AccelerationInfo,
+}
+
+/// Current configuration to prefill the onboarding wizard
+#[tauri::command]
+pub fn get_onboarding_state() -> OnboardingState {
+    onboarding::onboarding_state()
+}
+
+/// Check an API key with a cheap authenticated call
+#[tauri::command]
+pub async fn check_api_key(api_key: String) -> ApiKeyCheck {
+    let api_url = get_settings().lock().unwrap().api.url.clone();
+    onboarding::check_api_key(&api_url, &api_key).await
+}
+
+/// Scan CPU, memory and GPUs to see which local models can run
+#[tauri::command]
+pub fn scan_onboarding_hardware() -> OnboardingHardware {
+    let scan = onboarding::scan_hardware();
+    OnboardingHardware {
+        can_run_local: scan.can_run_local(),
+        scan,
+        acceleration: AccelerationInfo::detect(),
+    }
+}
+
+/// Validate the wizard answers and write the configuration
+#[tauri::command]
+pub async fn complete_onboarding(answers: OnboardingAnswers) -> Result<(), String> {
+    onboarding::complete_onboarding(answers)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
@@ -1,10 +1,13 @@
-use crate::optimization::{OptimizationManager, MemoryLimits, CacheConfig, MemoryStats, CacheStats};
+use crate::optimization::{
+    OptimizationManager, MemoryLimits, CacheConfig, MemoryStats, CacheStats, PressureConfig, PressureStatus,
+    ThreadConfig, ThreadSettings, CpuTopology,
+};
 use std::sync::{Arc, Mutex};
 use tauri::{command, State};
 
 /// State for the optimization manager
 pub struct OptimizationState {
-    manager: Arc<Mutex<Option<OptimizationManager>>>,
+    manager: Arc<Mutex<Option<Arc<OptimizationManager>>>>,
 }
 
 impl OptimizationState {
@@ -15,13 +18,16 @@ impl OptimizationState {
     }
     
     pub fn initialize(&self) {
-        let manager = OptimizationManager::new();
-        manager.start();
-        *self.manager.lock().unwrap() = Some(manager);
+        let mut manager = self.manager.lock().unwrap();
+        if manager.is_none() {
+            let started = OptimizationManager::new();
+            started.start();
+            *manager = Some(Arc::new(started));
+        }
     }
     
-    pub fn get_manager(&self) -> Option<OptimizationManager> {
-        self.manager.lock().unwrap().as_ref().cloned()
+    pub fn get_manager(&self) -> Option<Arc<OptimizationManager>> {
+        self.manager.lock().unwrap().clone()
     }
 }
 
@@ -170,9 +176,75 @@ pub fn clear_resource_cache(state: State<'_, OptimizationState>) -> Result<Strin
     }
 }
 
-/// Register optimization commands with Tauri
-pub fn register_commands(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
-    app.manage(OptimizationState::new());
+/// Get memory pressure watcher status
+#[command]
+pub fn get_memory_pressure_status(state: State<'_, OptimizationState>) -> Result<PressureStatus, String> {
+    match state.get_manager() {
+        Some(manager) => Ok(manager.pressure_monitor().get_status()),
+        None => Err("Optimization manager not initialized".into()),
+    }
+}
+
+/// Get memory pressure watcher configuration
+#[command]
+pub fn get_memory_pressure_config(state: State<'_, OptimizationState>) -> Result<PressureConfig, String> {
+    match state.get_manager() {
+        Some(manager) => Ok(manager.pressure_monitor().get_config()),
+        None => Err("Optimization manager not initialized".into()),
+    }
+}
+
+/// Update memory pressure watcher configuration
+#[command]
+pub fn update_memory_pressure_config(
+    config: PressureConfig,
+    state: State<'_, OptimizationState>
+) -> Result<String, String> {
+    match state.get_manager() {
+        Some(manager) => {
+            manager.pressure_monitor().update_config(config)?;
+            Ok("Memory pressure configuration updated".into())
+        }
+        None => Err("Optimization manager not initialized".into()),
+    }
+}
+
+/// Pin or unpin a local model so it is never unloaded under memory pressure
+#[command]
+pub fn set_model_pinned(
+    model_id: String,
+    pinned: bool,
+    state: State<'_, OptimizationState>
+) -> Result<String, String> {
+    match state.get_manager() {
+        Some(manager) => {
+            manager.pressure_monitor().set_pinned(&model_id, pinned)?;
+            Ok(format!("Model {} {}", model_id, if pinned { "pinned" } else { "unpinned" }))
+        }
+        None => Err("Optimization manager not initialized".into()),
+    }
+}
+
+/// Get thread pool configuration
+#[command]
+pub fn get_thread_config() -> Result<ThreadConfig, String> {
+    Ok(crate::optimization::get_thread_config())
+}
+
+/// Update thread pool configuration
+#[command]
+pub fn update_thread_config(config: ThreadConfig) -> Result<String, String> {
+    crate::optimization::update_thread_config(config)?;
     
-    Ok(())
-}
\ No newline at end of file
+    // Inference picks up the new settings with its next model load
+    if let Ok(provider) = crate::ai::local::get_local_provider() {
+        provider.apply_thread_settings();
+    }
+    Ok("Thread configuration updated; background pool changes apply after restart".into())
+}
+
+/// Get the effective thread settings and detected CPU topology
+#[command]
+pub fn get_thread_settings() -> Result<(ThreadSettings, CpuTopology), String> {
+    Ok((crate::optimization::get_thread_settings(), CpuTopology::detect()))
+}
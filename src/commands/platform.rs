@@ -0,0 +1,22 @@
+use tauri::AppHandle;
+
+use crate::system::platform::{self, PlatformInfo};
+
+/// Get the window system, the clipboard and shortcut integrations in use and
+/// their limitations
+#[tauri::command]
+pub fn get_platform_info() -> PlatformInfo {
+    platform::info()
+}
+
+/// Copy text to the system clipboard
+#[tauri::command]
+pub fn copy_to_clipboard(app: AppHandle, text: String) -> Result<(), String> {
+    platform::copy_text(&app, &text)
+}
+
+/// Read text from the system clipboard
+#[tauri::command]
+pub fn read_clipboard(app: AppHandle) -> Result<Option<String>, String> {
+    platform::read_text(&app)
+}
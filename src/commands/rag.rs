@@ -0,0 +1,99 @@
+use mcp_common::jobs::Job;
+use mcp_common::rag::chunking::{ChunkingConfig, ChunkingStrategy};
+use mcp_common::rag::citations::{Citations, KNOWLEDGE_BASES_METADATA_KEY};
+use mcp_common::rag::evaluate::{self, SampleQuery, StrategyScore};
+use mcp_common::rag::index::RetrievalConfig;
+use mcp_common::rag::maintenance::{self, IndexStats, ReindexReport};
+use mcp_common::rag::{get_knowledge_bases, KnowledgeBase, SearchHit};
+
+use crate::services::chat::get_chat_service;
+
+/// List knowledge bases
+#[tauri::command]
+pub fn list_knowledge_bases() -> Vec<KnowledgeBase> {
+    get_knowledge_bases().list()
+}
+
+/// Index size and health of a knowledge base
+#[tauri::command]
+pub fn get_knowledge_base_stats(name: String) -> Result<IndexStats, String> {
+    maintenance::stats(&get_knowledge_bases(), &name).map_err(|e| e.to_string())
+}
+
+/// Report of the last reindex of a knowledge base
+#[tauri::command]
+pub fn get_last_reindex(name: String) -> Option<ReindexReport> {
+    maintenance::last_report(&get_knowledge_bases(), &name)
+}
+
+/// Change the chunking or retrieval settings of a knowledge base
+#[tauri::command]
+pub fn configure_knowledge_base(
+    name: String,
+    chunking: Option<ChunkingConfig>,
+    retrieval: Option<RetrievalConfig>,
+) -> Result<KnowledgeBase, String> {
+    get_knowledge_bases()
+        .configure(&name, chunking, retrieval)
+        .map_err(|e| e.to_string())
+}
+
+/// Chunks of a knowledge base a query finds
+#[tauri::command]
+pub fn search_knowledge_base(name: String, query: String) -> Result<Vec<SearchHit>, String> {
+    get_knowledge_bases().search(&name, &query).map_err(|e| e.to_string())
+}
+
+/// Compare chunking strategies on sample queries; every strategy when none
+/// are given
+#[tauri::command]
+pub fn evaluate_chunking(
+    name: String,
+    queries: Vec<SampleQuery>,
+    strategies: Vec<ChunkingStrategy>,
+) -> Result<Vec<StrategyScore>, String> {
+    evaluate::evaluate(&get_knowledge_bases(), &name, &queries, &strategies).map_err(|e| e.to_string())
+}
+
+/// Rebuild a knowledge base's index in the background, optionally with
+/// another embedding model
+#[tauri::command]
+pub async fn reindex_knowledge_base(name: String, model: Option<String>) -> Result<Job, String> {
+    maintenance::spawn_reindex(&name, model.as_deref()).map_err(|e| e.to_string())
+}
+
+/// Search knowledge bases for every message of a conversation and cite the
+/// passages used; none stops searching
+#[tauri::command]
+pub fn set_conversation_knowledge_bases(conversation_id: String, names: Vec<String>) -> Result<(), String> {
+    let store = get_knowledge_bases();
+    for name in &names {
+        store.get(name).map_err(|e| e.to_string())?;
+    }
+
+    let chat_service = get_chat_service();
+    let mut conversation = chat_service
+        .get_conversation(&conversation_id)
+        .ok_or_else(|| format!("Conversation {} not found", conversation_id))?;
+    if names.is_empty() {
+        if let Some(metadata) = conversation.metadata.as_object_mut() {
+            metadata.remove(KNOWLEDGE_BASES_METADATA_KEY);
+        }
+    } else {
+        conversation.metadata[KNOWLEDGE_BASES_METADATA_KEY] = serde_json::json!(names);
+    }
+    chat_service.update_conversation(conversation)
+}
+
+/// Passages a reply was given and which of its statements are taken from
+/// them, for numbered footnotes; None for a reply given none
+#[tauri::command]
+pub fn get_message_citations(message_id: String) -> Result<Option<Citations>, String> {
+    get_chat_service()
+        .conversations_with_messages()
+        .iter()
+        .flat_map(|c| c.messages.iter())
+        .find(|m| m.id == message_id)
+        .map(Citations::of)
+        .ok_or_else(|| format!("Message with ID {} not found", message_id))
+}
@@ -0,0 +1,66 @@
+use crate::utils::events::{notify, Notification, NotificationAction, NotificationLevel};
+use log::{debug, warn};
+use mcp_common::events::{get_event_bus, names, Backpressure, Topic};
+use mcp_common::reminders::{get_reminder_store, parse_delay, parse_when, spawn_reminder_scheduler, Reminder};
+use mcp_common::utils::clock;
+use std::time::Duration;
+
+/// Announce due reminders in the background, each as a notification that
+/// opens its conversation
+pub fn start_reminder_scheduler() {
+    spawn_reminder_scheduler(Duration::from_secs(60));
+
+    let mut subscription = get_event_bus().subscribe(&[Topic::System], 64, Backpressure::DropNewest);
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = subscription.recv().await {
+            if event.name != names::REMINDER_DUE {
+                continue;
+            }
+            match serde_json::from_value::<Reminder>(event.payload) {
+                Ok(reminder) => notify(
+                    Notification::new(NotificationLevel::Info, "Reminder", reminder.body()).with_action(
+                        NotificationAction::OpenUrl {
+                            label: "Open conversation".to_string(),
+                            url: reminder.link(),
+                        },
+                    ),
+                ),
+                Err(e) => warn!("Unreadable reminder: {}", e),
+            }
+        }
+        debug!("Reminder notifier stopped");
+    });
+}
+
+/// Reminders soonest first, optionally of one conversation
+#[tauri::command]
+pub fn list_reminders(conversation_id: Option<String>) -> Vec<Reminder> {
+    get_reminder_store().list(conversation_id.as_deref())
+}
+
+/// Remind the user about a conversation later; `when` is a delay like
+/// "3d" or "in 3 days", or an RFC 3339 time
+#[tauri::command]
+pub async fn set_reminder(conversation_id: String, when: String, note: Option<String>) -> Result<Reminder, String> {
+    let conversation = mcp_common::get_mcp_service()
+        .get_conversation(&conversation_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let due_at = parse_when(&when, clock::now()).map_err(|e| e.to_string())?;
+    get_reminder_store()
+        .add(&conversation.id, &conversation.title, due_at, note)
+        .map_err(|e| e.to_string())
+}
+
+/// Set a reminder again for later, by a delay like "1h" or "tomorrow"
+#[tauri::command]
+pub fn snooze_reminder(id: String, delay: String) -> Result<Reminder, String> {
+    let until = clock::now() + parse_delay(&delay).map_err(|e| e.to_string())?;
+    get_reminder_store().snooze(&id, until).map_err(|e| e.to_string())
+}
+
+/// Cancel a reminder
+#[tauri::command]
+pub fn cancel_reminder(id: String) -> Result<Reminder, String> {
+    get_reminder_store().cancel(&id).map_err(|e| e.to_string())
+}
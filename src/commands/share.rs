@@ -0,0 +1,23 @@
+use crate::system::share::{self, SharedDraft};
+
+/// Take the drafts shared from other apps that haven't been opened yet
+#[tauri::command]
+pub fn take_shared_drafts() -> Vec<SharedDraft> {
+    share::take_drafts()
+}
+
+/// Whether "Send to Papin" is offered by the system
+#[tauri::command]
+pub fn get_share_target_status() -> bool {
+    share::is_registered()
+}
+
+/// Offer or stop offering "Send to Papin" in other apps
+#[tauri::command]
+pub fn set_share_target_enabled(enabled: bool) -> Result<(), String> {
+    if enabled {
+        share::register()
+    } else {
+        share::unregister()
+    }
+}
@@ -0,0 +1,87 @@
+use crate::services::chat::get_chat_service;
+use mcp_common::models::Conversation;
+use mcp_common::snapshots::{
+    get_share_settings, get_share_store, render_html, spawn_share_expiry, S3Credentials, Share, ShareSettings,
+    ShareTarget, Sharer, SnapshotOptions,
+};
+use std::time::Duration;
+
+/// A conversation with its messages, as the snapshot renderer expects
+fn load_conversation(conversation_id: &str) -> Result<Conversation, String> {
+    let service = get_chat_service();
+    let mut conversation = service
+        .get_conversation(conversation_id)
+        .ok_or_else(|| format!("Conversation with ID {} not found", conversation_id))?;
+    conversation.messages = service
+        .get_messages(conversation_id)
+        .into_iter()
+        .map(|m| m.message.into())
+        .collect();
+    Ok(conversation)
+}
+
+/// Revoke shares past their expiry in the background
+pub fn start_share_expiry() {
+    spawn_share_expiry(Duration::from_secs(3600));
+}
+
+/// Render a conversation to a self-contained HTML document
+#[tauri::command]
+pub fn render_snapshot(conversation_id: String, options: SnapshotOptions) -> Result<String, String> {
+    let conversation = load_conversation(&conversation_id)?;
+    Ok(render_html(&conversation, &options))
+}
+
+/// Upload a conversation snapshot and return the share with its link
+#[tauri::command]
+pub async fn publish_snapshot(
+    conversation_id: String,
+    target: Option<ShareTarget>,
+    options: SnapshotOptions,
+) -> Result<Share, String> {
+    let conversation = load_conversation(&conversation_id)?;
+    Sharer::new()
+        .publish(&conversation, target, &options)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Uploaded snapshots, revoked ones included
+#[tauri::command]
+pub fn list_snapshot_shares() -> Vec<Share> {
+    get_share_store().list()
+}
+
+/// Delete an uploaded snapshot so its link stops working
+#[tauri::command]
+pub async fn revoke_snapshot_share(id: String) -> Result<Share, String> {
+    Sharer::new().revoke(&id).await.map_err(|e| e.to_string())
+}
+
+/// Current sharing settings
+#[tauri::command]
+pub fn get_sharing_settings() -> ShareSettings {
+    get_share_settings().read().unwrap().clone()
+}
+
+/// Replace the sharing settings
+#[tauri::command]
+pub fn update_sharing_settings(settings: ShareSettings) -> Result<(), String> {
+    settings.save().map_err(|e| e.to_string())?;
+    *get_share_settings().write().unwrap() = settings;
+    Ok(())
+}
+
+/// Store the S3 access keys, or remove them when not given
+#[tauri::command]
+pub fn set_s3_share_keys(access_key_id: Option<String>, secret_access_key: Option<String>) -> Result<(), String> {
+    let credentials = match (access_key_id, secret_access_key) {
+        (Some(access_key_id), Some(secret_access_key)) => Some(S3Credentials {
+            access_key_id,
+            secret_access_key,
+        }),
+        (None, None) => None,
+        _ => return Err("Give both S3 keys, or neither to remove them".to_string()),
+    };
+    S3Credentials::store(credentials.as_ref()).map_err(|e| e.to_string())
+}
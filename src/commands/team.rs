@@ -0,0 +1,109 @@
+use crate::services::chat::get_chat_service;
+use mcp_common::sync::{
+    get_team_workspace, SharedConversation, SharedPrompt, TeamInfo, TeamMember, TeamRole, TeamStatus,
+    TeamSyncReport,
+};
+
+/// Team mode settings and sync state
+#[tauri::command]
+pub fn get_team_status() -> TeamStatus {
+    get_team_workspace().status()
+}
+
+/// Join a self-hosted team server
+#[tauri::command]
+pub async fn join_team(url: String, token: String) -> Result<TeamInfo, String> {
+    get_team_workspace().join(&url, &token).await.map_err(|e| e.to_string())
+}
+
+/// Leave the team and remove its data from this device
+#[tauri::command]
+pub fn leave_team() -> Result<(), String> {
+    get_team_workspace().leave().map_err(|e| e.to_string())
+}
+
+/// Push local changes and pull the team's
+#[tauri::command]
+pub async fn sync_team() -> Result<TeamSyncReport, String> {
+    get_team_workspace().sync().await.map_err(|e| e.to_string())
+}
+
+/// Prompts in the team library
+#[tauri::command]
+pub fn list_team_prompts() -> Vec<SharedPrompt> {
+    get_team_workspace().prompts()
+}
+
+/// Add a prompt to the team library, or update the one with that name
+#[tauri::command]
+pub fn save_team_prompt(
+    name: String,
+    content: String,
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<SharedPrompt, String> {
+    get_team_workspace()
+        .save_prompt(&name, &content, description, tags.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+/// Remove a prompt from the team library
+#[tauri::command]
+pub fn delete_team_prompt(name: String) -> Result<(), String> {
+    get_team_workspace().delete_prompt(&name).map_err(|e| e.to_string())
+}
+
+/// Conversations shared with the team
+#[tauri::command]
+pub fn list_shared_conversations() -> Vec<SharedConversation> {
+    get_team_workspace().shared_conversations()
+}
+
+/// Share a conversation's title and details with the team
+#[tauri::command]
+pub fn share_conversation_with_team(conversation_id: String) -> Result<SharedConversation, String> {
+    let conversation = get_chat_service()
+        .get_conversation(&conversation_id)
+        .ok_or_else(|| format!("Conversation {} not found", conversation_id))?;
+    get_team_workspace()
+        .share_conversation(&conversation)
+        .map_err(|e| e.to_string())
+}
+
+/// Stop sharing a conversation with the team
+#[tauri::command]
+pub fn unshare_conversation_with_team(conversation_id: String) -> Result<(), String> {
+    get_team_workspace()
+        .unshare_conversation(&conversation_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Team members
+#[tauri::command]
+pub async fn list_team_members() -> Result<Vec<TeamMember>, String> {
+    get_team_workspace().members().await.map_err(|e| e.to_string())
+}
+
+/// Invite someone to the team (admins only)
+#[tauri::command]
+pub async fn invite_team_member(email: String, role: TeamRole) -> Result<TeamMember, String> {
+    get_team_workspace().invite(&email, role).await.map_err(|e| e.to_string())
+}
+
+/// Change a member's role (admins only)
+#[tauri::command]
+pub async fn set_team_member_role(member_id: String, role: TeamRole) -> Result<TeamMember, String> {
+    get_team_workspace()
+        .set_role(&member_id, role)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Remove a member from the team (admins only)
+#[tauri::command]
+pub async fn remove_team_member(member_id: String) -> Result<(), String> {
+    get_team_workspace()
+        .remove_member(&member_id)
+        .await
+        .map_err(|e| e.to_string())
+}
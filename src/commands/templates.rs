@@ -0,0 +1,107 @@
+use crate::utils::events::{notify, Notification, NotificationAction, NotificationLevel};
+use log::{debug, warn};
+use mcp_common::events::{get_event_bus, names, Backpressure, Topic};
+use mcp_common::templates::{
+    get_gallery_settings, get_template_library, spawn_template_update_checker, GalleryIndex, Template, TemplateUpdate,
+};
+use std::time::Duration;
+
+/// Check the gallery for updates of installed templates once a day, with a
+/// notification for each new batch
+pub fn start_template_update_checker() {
+    spawn_template_update_checker(Duration::from_secs(24 * 3600));
+
+    let mut subscription = get_event_bus().subscribe(&[Topic::System], 64, Backpressure::DropNewest);
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = subscription.recv().await {
+            if event.name != names::TEMPLATE_UPDATES_AVAILABLE {
+                continue;
+            }
+            match serde_json::from_value::<Vec<TemplateUpdate>>(event.payload["updates"].clone()) {
+                Ok(updates) => {
+                    let body = match updates.as_slice() {
+                        [update] => format!("{} {} is available", update.name, update.available_version),
+                        _ => format!("{} of your templates have updates", updates.len()),
+                    };
+                    notify(
+                        Notification::new(NotificationLevel::Info, "Template updates", body).with_action(
+                            NotificationAction::Command {
+                                label: "Show updates".to_string(),
+                                command: "open_template_updates".to_string(),
+                            },
+                        ),
+                    );
+                }
+                Err(e) => warn!("Unreadable template updates: {}", e),
+            }
+        }
+        debug!("Template update notifier stopped");
+    });
+}
+
+/// Installed templates by name
+#[tauri::command]
+pub fn list_templates() -> Vec<Template> {
+    get_template_library().list()
+}
+
+/// Remove an installed template
+#[tauri::command]
+pub fn remove_template(id: String) -> Result<Template, String> {
+    get_template_library().remove(&id).map_err(|e| e.to_string())
+}
+
+/// URL of the gallery's index, if one is set
+#[tauri::command]
+pub fn get_template_gallery() -> Option<String> {
+    get_gallery_settings().url()
+}
+
+/// Use a gallery, or none
+#[tauri::command]
+pub fn set_template_gallery(url: Option<String>) -> Result<(), String> {
+    get_gallery_settings()
+        .set_url(url.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// The gallery's templates, optionally only those matching a search term
+#[tauri::command]
+pub async fn browse_template_gallery(search: Option<String>) -> Result<GalleryIndex, String> {
+    let gallery = get_gallery_settings().gallery().map_err(|e| e.to_string())?;
+    let mut index = gallery.index().await.map_err(|e| e.to_string())?;
+    if let Some(term) = search.filter(|t| !t.trim().is_empty()) {
+        index.templates.retain(|entry| entry.matches(term.trim()));
+    }
+    Ok(index)
+}
+
+/// Download a gallery template to show before installing it
+#[tauri::command]
+pub async fn preview_gallery_template(id: String) -> Result<Template, String> {
+    let gallery = get_gallery_settings().gallery().map_err(|e| e.to_string())?;
+    gallery.preview(&id).await.map_err(|e| e.to_string())
+}
+
+/// Install a template from the gallery, or update it
+#[tauri::command]
+pub async fn install_gallery_template(id: String) -> Result<Template, String> {
+    let gallery = get_gallery_settings().gallery().map_err(|e| e.to_string())?;
+    gallery
+        .install(&get_template_library(), &id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Installed templates with another version in the gallery
+#[tauri::command]
+pub async fn check_template_updates() -> Result<Vec<TemplateUpdate>, String> {
+    let settings = get_gallery_settings();
+    let gallery = settings.gallery().map_err(|e| e.to_string())?;
+    let updates = gallery
+        .updates(&get_template_library())
+        .await
+        .map_err(|e| e.to_string())?;
+    settings.unannounced(&updates).map_err(|e| e.to_string())?;
+    Ok(updates)
+}
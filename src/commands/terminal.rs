@@ -0,0 +1,39 @@
+use crate::services::terminal::{get_pty_manager, TerminalInfo, TerminalOptions};
+
+/// Start a terminal session; output arrives as `terminal_output` events
+#[tauri::command]
+pub fn create_terminal(options: Option<TerminalOptions>) -> Result<String, String> {
+    get_pty_manager().create(options.unwrap_or_default())
+}
+
+/// Send keyboard input to a terminal session
+#[tauri::command]
+pub fn write_terminal(session_id: String, data: String) -> Result<(), String> {
+    get_pty_manager().write(&session_id, data.as_bytes())
+}
+
+/// Resize a terminal session
+#[tauri::command]
+pub fn resize_terminal(session_id: String, cols: u16, rows: u16) -> Result<(), String> {
+    get_pty_manager().resize(&session_id, cols, rows)
+}
+
+/// Get a session's recent output, e.g. when a view opens after the session started
+#[tauri::command]
+pub fn attach_terminal(session_id: String) -> Result<String, String> {
+    get_pty_manager()
+        .subscribe(&session_id)
+        .map(|(history, _)| history)
+}
+
+/// Kill a terminal session
+#[tauri::command]
+pub fn close_terminal(session_id: String) -> Result<(), String> {
+    get_pty_manager().close(&session_id)
+}
+
+/// List terminal sessions
+#[tauri::command]
+pub fn list_terminals() -> Vec<TerminalInfo> {
+    get_pty_manager().list()
+}
@@ -0,0 +1,59 @@
+use mcp_common::theme::{self, Theme};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Theme profile of the desktop app
+const THEME_PROFILE: &str = "desktop";
+
+/// A theme with the CSS variables the frontend applies
+#[derive(Debug, Clone, Serialize)]
+pub struct ThemeInfo {
+    pub theme: Theme,
+    pub css_variables: BTreeMap<String, String>,
+}
+
+impl From<Theme> for ThemeInfo {
+    fn from(theme: Theme) -> Self {
+        let css_variables = theme.css_variables();
+        Self { theme, css_variables }
+    }
+}
+
+/// List built-in and custom themes
+#[tauri::command]
+pub fn list_themes() -> Vec<String> {
+    theme::list_themes()
+}
+
+/// Get the theme of the desktop app, or of another profile
+#[tauri::command]
+pub fn get_active_theme(profile: Option<String>) -> ThemeInfo {
+    theme::active_theme(Some(profile.as_deref().unwrap_or(THEME_PROFILE))).into()
+}
+
+/// Get the desktop theme as a `:root` stylesheet
+#[tauri::command]
+pub fn get_theme_css() -> String {
+    theme::active_theme(Some(THEME_PROFILE)).css()
+}
+
+/// Switch the theme of a profile, or the default theme when `profile` is
+/// omitted; running front ends receive a `theme_changed` event
+#[tauri::command]
+pub fn set_theme(name: String, profile: Option<String>) -> Result<ThemeInfo, String> {
+    theme::set_active_theme(&name, profile.as_deref())
+        .map(ThemeInfo::from)
+        .map_err(|e| e.to_string())
+}
+
+/// Create or update a custom theme
+#[tauri::command]
+pub fn save_custom_theme(theme: Theme) -> Result<(), String> {
+    theme::save_custom_theme(&theme).map_err(|e| e.to_string())
+}
+
+/// Delete a custom theme
+#[tauri::command]
+pub fn delete_custom_theme(name: String) -> Result<(), String> {
+    theme::delete_custom_theme(&name).map_err(|e| e.to_string())
+}
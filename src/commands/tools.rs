@@ -0,0 +1,173 @@
+use crate::tools::{self, git::get_repo_bindings, sandbox::{CodeSandbox, Language, SandboxLimits, SandboxOutput}};
+use mcp_common::environment::{get_environments, EnvValue, Scope, MASK};
+use mcp_common::models::{Tool, ToolCall};
+use mcp_common::service::dry_run::{self, get_dry_run_log, SimulatedCall};
+use mcp_common::service::policy::{get_policies, Decision, Effect, Policy, PolicyRule};
+use std::collections::BTreeMap;
+
+/// List tools the app can execute locally
+#[tauri::command]
+pub fn list_local_tools() -> Result<Vec<Tool>, String> {
+    Ok(tools::local_tools())
+}
+
+/// Run a Python or JavaScript snippet in the WASM sandbox
+#[tauri::command]
+pub async fn run_sandboxed_code(
+    language: Language,
+    code: String,
+    timeout_ms: Option<u64>,
+) -> Result<SandboxOutput, String> {
+    let mut limits = SandboxLimits::default();
+    if let Some(timeout_ms) = timeout_ms {
+        limits.timeout = std::time::Duration::from_millis(timeout_ms);
+    }
+
+    CodeSandbox::new(limits)?.execute(language, &code).await
+}
+
+/// Bind a conversation to a local git repository for the git tool
+#[tauri::command]
+pub fn bind_conversation_repo(conversation_id: String, repo_path: String) -> Result<String, String> {
+    get_repo_bindings()
+        .bind(&conversation_id, std::path::Path::new(&repo_path))
+        .map(|workdir| workdir.display().to_string())
+}
+
+/// Remove a conversation's repository binding
+#[tauri::command]
+pub fn unbind_conversation_repo(conversation_id: String) -> Result<(), String> {
+    get_repo_bindings().unbind(&conversation_id);
+    Ok(())
+}
+
+/// Get the repository bound to a conversation
+#[tauri::command]
+pub fn get_conversation_repo(conversation_id: String) -> Result<Option<String>, String> {
+    Ok(get_repo_bindings()
+        .get(&conversation_id)
+        .map(|path| path.display().to_string()))
+}
+
+/// Scope named by a workspace or a conversation
+fn environment_scope(workspace: Option<String>, conversation_id: Option<String>) -> Result<Scope, String> {
+    match (workspace, conversation_id) {
+        (Some(workspace), None) => Ok(Scope::Workspace(workspace)),
+        (None, Some(id)) => Ok(Scope::Conversation(id)),
+        _ => Err("Name either a workspace or a conversation".to_string()),
+    }
+}
+
+/// List the environment variables of a workspace or conversation, with
+/// secret values masked
+#[tauri::command]
+pub fn list_environment_variables(
+    workspace: Option<String>,
+    conversation_id: Option<String>,
+) -> Result<BTreeMap<String, String>, String> {
+    let scope = environment_scope(workspace, conversation_id)?;
+    Ok(get_environments()
+        .list(&scope)
+        .into_iter()
+        .map(|(name, value)| match value {
+            EnvValue::Plain { value } => (name, value),
+            EnvValue::Secret { .. } => (name, MASK.to_string()),
+        })
+        .collect())
+}
+
+/// Set an environment variable of a workspace or conversation; secret
+/// values are kept in the vault
+#[tauri::command]
+pub fn set_environment_variable(
+    workspace: Option<String>,
+    conversation_id: Option<String>,
+    name: String,
+    value: String,
+    secret: bool,
+) -> Result<(), String> {
+    let scope = environment_scope(workspace, conversation_id)?;
+    get_environments()
+        .set(&scope, &name, &value, secret)
+        .map_err(|e| e.to_string())
+}
+
+/// Remove an environment variable of a workspace or conversation
+#[tauri::command]
+pub fn unset_environment_variable(
+    workspace: Option<String>,
+    conversation_id: Option<String>,
+    name: String,
+) -> Result<bool, String> {
+    let scope = environment_scope(workspace, conversation_id)?;
+    get_environments().unset(&scope, &name).map_err(|e| e.to_string())
+}
+
+/// Whether tool calls are dry runs
+#[tauri::command]
+pub fn get_tool_dry_run() -> Result<bool, String> {
+    Ok(dry_run::is_enabled())
+}
+
+/// Turn dry runs of tool calls on or off
+#[tauri::command]
+pub fn set_tool_dry_run(enabled: bool) -> Result<(), String> {
+    dry_run::set_enabled(enabled).map_err(|e| e.to_string())
+}
+
+/// Tool calls of a conversation recorded instead of executed, with what
+/// they would have done
+#[tauri::command]
+pub fn list_simulated_tool_calls(conversation_id: String) -> Result<Vec<SimulatedCall>, String> {
+    Ok(get_dry_run_log().list(&conversation_id))
+}
+
+/// Forget the tool calls recorded in a conversation
+#[tauri::command]
+pub fn clear_simulated_tool_calls(conversation_id: String) -> Result<usize, String> {
+    get_dry_run_log().clear(&conversation_id).map_err(|e| e.to_string())
+}
+
+/// Tool policy of a workspace, or the global one without a workspace
+#[tauri::command]
+pub fn get_tool_policy(workspace: Option<String>) -> Result<Policy, String> {
+    Ok(get_policies().get(workspace.as_deref()))
+}
+
+/// Add a rule to the tool policy of a workspace, or the global one; returns
+/// the rule with its ID
+#[tauri::command]
+pub fn add_tool_policy_rule(workspace: Option<String>, rule: PolicyRule) -> Result<PolicyRule, String> {
+    get_policies()
+        .add_rule(workspace.as_deref(), rule)
+        .map_err(|e| e.to_string())
+}
+
+/// Remove a rule from the tool policy of a workspace, or the global one
+#[tauri::command]
+pub fn remove_tool_policy_rule(workspace: Option<String>, id: String) -> Result<bool, String> {
+    get_policies()
+        .remove_rule(workspace.as_deref(), &id)
+        .map_err(|e| e.to_string())
+}
+
+/// Set what happens to tool calls no rule matches in a workspace, or
+/// everywhere
+#[tauri::command]
+pub fn set_tool_policy_default(workspace: Option<String>, effect: Option<Effect>) -> Result<(), String> {
+    get_policies()
+        .set_default(workspace.as_deref(), effect)
+        .map_err(|e| e.to_string())
+}
+
+/// What the tool policies of a workspace would do with a call, without
+/// making it
+#[tauri::command]
+pub fn test_tool_policy(
+    workspace: Option<String>,
+    tool: String,
+    arguments: serde_json::Value,
+) -> Result<Decision, String> {
+    let call = ToolCall::new("policy-test", tool, arguments);
+    Ok(get_policies().effective(workspace.as_deref()).evaluate(&call))
+}
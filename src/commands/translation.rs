@@ -0,0 +1,69 @@
+use mcp_common::service::translation::{
+    self, translator_for, LanguagePreference, TranslationProvider, TranslationSettings, LANGUAGE_METADATA_KEY,
+};
+
+use crate::services::chat::get_chat_service;
+
+/// Get the translation settings
+#[tauri::command]
+pub fn get_translation_settings() -> TranslationSettings {
+    translation::get_translation_settings().read().unwrap().clone()
+}
+
+/// Replace the translation settings
+#[tauri::command]
+pub fn update_translation_settings(mut settings: TranslationSettings) -> Result<(), String> {
+    settings.defaults = settings.defaults.validated().map_err(|e| e.to_string())?;
+    settings.save().map_err(|e| e.to_string())?;
+    *translation::get_translation_settings().write().unwrap() = settings;
+    Ok(())
+}
+
+/// Store the API key of DeepL or Google, or remove it with `None`
+#[tauri::command]
+pub fn set_translation_api_key(provider: TranslationProvider, key: Option<String>) -> Result<(), String> {
+    translation::set_api_key(provider, key.as_deref()).map_err(|e| e.to_string())
+}
+
+/// Whether a provider has its API key or local model
+#[tauri::command]
+pub fn is_translation_configured(provider: TranslationProvider) -> bool {
+    translation::is_configured(provider)
+}
+
+/// Translate a conversation, or stop translating it with `None`
+#[tauri::command]
+pub fn set_conversation_language(conversation_id: String, preference: Option<LanguagePreference>) -> Result<(), String> {
+    let chat_service = get_chat_service();
+    let mut conversation = chat_service
+        .get_conversation(&conversation_id)
+        .ok_or_else(|| format!("Conversation {} not found", conversation_id))?;
+    match preference {
+        Some(preference) => {
+            let preference = preference.validated().map_err(|e| e.to_string())?;
+            conversation.metadata[LANGUAGE_METADATA_KEY] = serde_json::to_value(preference).map_err(|e| e.to_string())?;
+        }
+        None => {
+            if let Some(metadata) = conversation.metadata.as_object_mut() {
+                metadata.remove(LANGUAGE_METADATA_KEY);
+            }
+        }
+    }
+    chat_service.update_conversation(conversation)
+}
+
+/// Language of a text as an ISO 639-1 code, if it can be told
+#[tauri::command]
+pub fn detect_language(text: String) -> Option<String> {
+    translation::detect_language(&text).map(str::to_string)
+}
+
+/// Translate text with the configured provider
+#[tauri::command]
+pub async fn translate_text(text: String, target: String, source: Option<String>) -> Result<String, String> {
+    let settings = translation::get_translation_settings().read().unwrap().clone();
+    let translator = translator_for(settings.provider, &settings).map_err(|e| e.to_string())?;
+    translation::translate_text(translator.as_ref(), &text, source.as_deref(), &target)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,51 @@
+use mcp_common::webhooks::{get_webhook_store, Delivery, Webhook, WebhookEvent};
+
+/// Registered webhooks
+#[tauri::command]
+pub fn list_webhooks() -> Vec<Webhook> {
+    get_webhook_store().list()
+}
+
+/// Register a URL to be called on events, all of them when none are given
+#[tauri::command]
+pub fn add_webhook(url: String, events: Option<Vec<WebhookEvent>>, description: Option<String>) -> Result<Webhook, String> {
+    get_webhook_store()
+        .add(&url, events.unwrap_or_default(), description)
+        .map_err(|e| e.to_string())
+}
+
+/// Remove a webhook and its waiting deliveries
+#[tauri::command]
+pub fn remove_webhook(id: String) -> Result<(), String> {
+    get_webhook_store().remove(&id).map_err(|e| e.to_string())
+}
+
+/// Pause or resume a webhook
+#[tauri::command]
+pub fn set_webhook_enabled(id: String, enabled: bool) -> Result<Webhook, String> {
+    get_webhook_store().set_enabled(&id, enabled).map_err(|e| e.to_string())
+}
+
+/// Change the events a webhook is called on
+#[tauri::command]
+pub fn set_webhook_events(id: String, events: Vec<WebhookEvent>) -> Result<Webhook, String> {
+    get_webhook_store().set_events(&id, events).map_err(|e| e.to_string())
+}
+
+/// Replace a webhook's signing secret
+#[tauri::command]
+pub fn rotate_webhook_secret(id: String) -> Result<Webhook, String> {
+    get_webhook_store().rotate_secret(&id).map_err(|e| e.to_string())
+}
+
+/// Send a test ping to a webhook
+#[tauri::command]
+pub async fn test_webhook(id: String) -> Result<(), String> {
+    get_webhook_store().test(&id).await.map_err(|e| e.to_string())
+}
+
+/// Deliveries waiting to be retried
+#[tauri::command]
+pub fn get_webhook_queue() -> Vec<Delivery> {
+    get_webhook_store().queue()
+}
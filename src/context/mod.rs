@@ -0,0 +1,31 @@
+pub mod overflow;
+pub mod watcher;
+
+use crate::tools::git::get_repo_bindings;
+use watcher::get_file_watcher;
+
+/// Number of recently changed files offered as context
+const RECENT_FILES_LIMIT: usize = 20;
+
+/// Summary of recent edits in the conversation's bound project, for retrieval.
+///
+/// Returns `None` when the conversation isn't bound or nothing changed since
+/// the project was opened.
+pub fn project_context(conversation_id: &str) -> Option<String> {
+    let root = get_repo_bindings().get(conversation_id)?;
+    let changes = get_file_watcher().recently_changed(&root, RECENT_FILES_LIMIT);
+    if changes.is_empty() {
+        return None;
+    }
+
+    let mut context = format!("Recently changed files in {}:\n", root.display());
+    for change in changes {
+        context.push_str(&format!(
+            "- {} ({}, {})\n",
+            change.path,
+            change.kind,
+            change.changed_at.format("%H:%M:%S")
+        ));
+    }
+    Some(context)
+}
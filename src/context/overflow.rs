@@ -0,0 +1,505 @@
+//! What happens when a conversation outgrows a model's context window.
+//!
+//! Each conversation picks a strategy: keep the most recent turns (sliding
+//! window), replace the oldest turns with a summary, keep the opening
+//! exchange and the most recent turns (drop-middle), or refuse to send.
+//! System turns and the message being sent are always kept. Whenever turns
+//! are dropped or compressed an event says which, so the UI can show it.
+
+use crate::utils::config;
+use crate::utils::events::{events, get_event_system};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use mcp_common::config::data_path;
+use mcp_common::models::{ChatTurn, MessageRole};
+use mcp_common::service::estimate::estimate_tokens;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// Tokens the role markers of a turn take, on top of its text
+const TURN_OVERHEAD_TOKENS: usize = 4;
+
+/// Tokens kept free for the reply
+pub const REPLY_RESERVE_TOKENS: usize = 512;
+
+/// Share of the budget a summary of dropped turns may take
+const SUMMARY_SHARE: usize = 4;
+
+/// Characters of each dropped turn kept in a summary
+const SUMMARY_LINE_CHARS: usize = 200;
+
+/// Characters of a dropped turn shown in the overflow event
+const PREVIEW_CHARS: usize = 80;
+
+/// How to make a conversation fit a model's context window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowStrategy {
+    /// Drop the oldest turns
+    SlidingWindow,
+
+    /// Replace the oldest turns with a short summary of them
+    SummaryCompression,
+
+    /// Keep the opening exchange and the latest turns, dropping those between
+    DropMiddle,
+
+    /// Don't send; the user decides what to remove
+    FailWithError,
+}
+
+impl OverflowStrategy {
+    /// Stable identifier used in settings
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverflowStrategy::SlidingWindow => "sliding_window",
+            OverflowStrategy::SummaryCompression => "summary_compression",
+            OverflowStrategy::DropMiddle => "drop_middle",
+            OverflowStrategy::FailWithError => "fail_with_error",
+        }
+    }
+}
+
+impl Default for OverflowStrategy {
+    fn default() -> Self {
+        OverflowStrategy::SlidingWindow
+    }
+}
+
+impl fmt::Display for OverflowStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for OverflowStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "sliding_window" | "sliding" => Ok(OverflowStrategy::SlidingWindow),
+            "summary_compression" | "summary" => Ok(OverflowStrategy::SummaryCompression),
+            "drop_middle" => Ok(OverflowStrategy::DropMiddle),
+            "fail_with_error" | "fail" => Ok(OverflowStrategy::FailWithError),
+            _ => Err(format!("Unknown context overflow strategy '{}'", s)),
+        }
+    }
+}
+
+/// A turn left out of the context
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DroppedTurn {
+    /// Position of the turn in the conversation
+    pub index: usize,
+
+    /// Who wrote it
+    pub role: MessageRole,
+
+    /// Estimated tokens
+    pub tokens: usize,
+
+    /// Start of its text
+    pub preview: String,
+}
+
+/// What was done to make a conversation fit, for the UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OverflowEvent {
+    /// Conversation that overflowed
+    pub conversation_id: String,
+
+    /// Strategy applied
+    pub strategy: OverflowStrategy,
+
+    /// Tokens available for earlier turns
+    pub budget_tokens: usize,
+
+    /// Tokens of the earlier turns before fitting
+    pub tokens_before: usize,
+
+    /// Tokens sent after fitting
+    pub tokens_after: usize,
+
+    /// Turns dropped or folded into the summary
+    pub dropped: Vec<DroppedTurn>,
+
+    /// Summary that replaced the dropped turns
+    pub summary: Option<String>,
+
+    /// Whether the message was sent at all
+    pub sent: bool,
+
+    /// One-line description of what happened
+    pub description: String,
+}
+
+/// Earlier turns cut down to a budget
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fitted {
+    /// Turns to send
+    pub turns: Vec<ChatTurn>,
+
+    /// Turns dropped or summarized
+    pub dropped: Vec<DroppedTurn>,
+
+    /// Summary standing in for the dropped turns
+    pub summary: Option<String>,
+
+    /// Tokens of the turns before fitting
+    pub tokens_before: usize,
+
+    /// Tokens of the turns sent
+    pub tokens_after: usize,
+}
+
+/// Estimated tokens of a turn
+pub fn turn_tokens(turn: &ChatTurn) -> usize {
+    estimate_tokens(&turn.text) as usize + TURN_OVERHEAD_TOKENS
+}
+
+fn total_tokens(turns: &[ChatTurn]) -> usize {
+    turns.iter().map(turn_tokens).sum()
+}
+
+fn preview(text: &str, chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= chars {
+        text
+    } else {
+        format!("{}…", text.chars().take(chars).collect::<String>().trim_end())
+    }
+}
+
+fn role_label(role: &MessageRole) -> &'static str {
+    match role {
+        MessageRole::System => "System",
+        MessageRole::User => "User",
+        MessageRole::Assistant => "Assistant",
+        MessageRole::Tool => "Tool",
+    }
+}
+
+/// Short summary of dropped turns: the opening of each, most recent kept
+/// when they don't all fit
+fn summarize(turns: &[&ChatTurn], budget: usize) -> Option<String> {
+    let header = "Summary of the earlier conversation:";
+    let mut lines = Vec::new();
+    let mut tokens = estimate_tokens(header) as usize + TURN_OVERHEAD_TOKENS;
+    for turn in turns.iter().rev() {
+        let first_sentence = turn.text.split_inclusive(['.', '?', '!', '\n']).next().unwrap_or("");
+        let line = format!("- {}: {}", role_label(&turn.role), preview(first_sentence, SUMMARY_LINE_CHARS));
+        let line_tokens = estimate_tokens(&line) as usize + 1;
+        if tokens + line_tokens > budget {
+            break;
+        }
+        tokens += line_tokens;
+        lines.push(line);
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    lines.reverse();
+    Some(format!("{}\n{}", header, lines.join("\n")))
+}
+
+/// Cut the earlier turns of a conversation down to a token budget.
+///
+/// Fails only with [`OverflowStrategy::FailWithError`]; the other strategies
+/// drop turns oldest first, never the system turns, until the rest fits.
+pub fn fit(turns: Vec<ChatTurn>, budget: usize, strategy: OverflowStrategy) -> Result<Fitted, String> {
+    let tokens_before = total_tokens(&turns);
+    if tokens_before <= budget {
+        return Ok(Fitted {
+            turns,
+            dropped: Vec::new(),
+            summary: None,
+            tokens_before,
+            tokens_after: tokens_before,
+        });
+    }
+    if strategy == OverflowStrategy::FailWithError {
+        return Err(format!(
+            "The conversation is about {} tokens over the model's context window; remove earlier messages or pick another overflow strategy",
+            tokens_before - budget
+        ));
+    }
+
+    // Drop-middle keeps the opening exchange: the first user turn and its reply
+    let protected_head = if strategy == OverflowStrategy::DropMiddle { 2 } else { 0 };
+    let summary_budget = if strategy == OverflowStrategy::SummaryCompression {
+        budget / SUMMARY_SHARE
+    } else {
+        0
+    };
+    let target = budget - summary_budget;
+
+    let mut keep = vec![true; turns.len()];
+    let mut tokens = tokens_before;
+    let candidates: Vec<usize> = turns
+        .iter()
+        .enumerate()
+        .filter(|(_, turn)| turn.role != MessageRole::System)
+        .map(|(i, _)| i)
+        .skip(protected_head)
+        .collect();
+    for i in candidates {
+        if tokens <= target {
+            break;
+        }
+        keep[i] = false;
+        tokens -= turn_tokens(&turns[i]);
+    }
+
+    let dropped: Vec<DroppedTurn> = turns
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !keep[*i])
+        .map(|(index, turn)| DroppedTurn {
+            index,
+            role: turn.role.clone(),
+            tokens: turn_tokens(turn),
+            preview: preview(&turn.text, PREVIEW_CHARS),
+        })
+        .collect();
+    let summary = if strategy == OverflowStrategy::SummaryCompression {
+        let dropped_turns: Vec<&ChatTurn> = turns.iter().enumerate().filter(|(i, _)| !keep[*i]).map(|(_, t)| t).collect();
+        summarize(&dropped_turns, summary_budget)
+    } else {
+        None
+    };
+
+    // The summary takes the place of the turns it stands for
+    let first_dropped = keep.iter().position(|k| !k);
+    let mut fitted = Vec::new();
+    for (i, turn) in turns.into_iter().enumerate() {
+        if Some(i) == first_dropped {
+            if let Some(summary) = &summary {
+                fitted.push(ChatTurn::new(MessageRole::System, summary.clone()));
+            }
+        }
+        if keep[i] {
+            fitted.push(turn);
+        }
+    }
+
+    Ok(Fitted {
+        tokens_after: total_tokens(&fitted),
+        turns: fitted,
+        dropped,
+        summary,
+        tokens_before,
+    })
+}
+
+/// What happened, in a sentence
+fn describe(strategy: OverflowStrategy, fitted: &Fitted, window: usize) -> String {
+    let count = fitted.dropped.len();
+    let turns = if count == 1 { "message" } else { "messages" };
+    let tokens: usize = fitted.dropped.iter().map(|d| d.tokens).sum();
+    let verb = match strategy {
+        OverflowStrategy::SummaryCompression if fitted.summary.is_some() => "Summarized",
+        OverflowStrategy::DropMiddle => "Left out",
+        _ => "Dropped",
+    };
+    let which = match strategy {
+        OverflowStrategy::DropMiddle => format!("{} {} from the middle of the conversation", count, turns),
+        _ => format!("the {} oldest {}", count, turns),
+    };
+    format!(
+        "{} {} (about {} tokens) to fit the {}-token context window",
+        verb, which, tokens, window
+    )
+}
+
+/// Overflow strategy of each conversation, persisted across restarts
+pub struct OverflowSettings {
+    path: PathBuf,
+    strategies: Mutex<HashMap<String, OverflowStrategy>>,
+}
+
+impl OverflowSettings {
+    fn open(path: PathBuf) -> Self {
+        let strategies = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            strategies: Mutex::new(strategies),
+        }
+    }
+
+    /// Strategy used when a conversation hasn't picked one, from
+    /// `ai.context.overflow_strategy`
+    pub fn default_strategy(&self) -> OverflowStrategy {
+        let config = config::get_config();
+        let config = config.lock().unwrap();
+        config
+            .get_string("ai.context.overflow_strategy")
+            .and_then(|name| name.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// Strategy a conversation uses
+    pub fn get(&self, conversation_id: &str) -> OverflowStrategy {
+        let chosen = self.strategies.lock().unwrap().get(conversation_id).copied();
+        chosen.unwrap_or_else(|| self.default_strategy())
+    }
+
+    /// Pick a conversation's strategy; None goes back to the default
+    pub fn set(&self, conversation_id: &str, strategy: Option<OverflowStrategy>) {
+        let mut strategies = self.strategies.lock().unwrap();
+        match strategy {
+            Some(strategy) => strategies.insert(conversation_id.to_string(), strategy),
+            None => strategies.remove(conversation_id),
+        };
+        self.save(&strategies);
+    }
+
+    fn save(&self, strategies: &HashMap<String, OverflowStrategy>) {
+        match serde_json::to_string_pretty(strategies) {
+            Ok(content) => {
+                if let Err(e) = fs::write(&self.path, content) {
+                    warn!("Failed to save context overflow settings: {}", e);
+                }
+            }
+            Err(e) => warn!("Failed to serialize context overflow settings: {}", e),
+        }
+    }
+}
+
+lazy_static! {
+    static ref OVERFLOW_SETTINGS: Arc<OverflowSettings> =
+        Arc::new(OverflowSettings::open(data_path("context_overflow.json")));
+}
+
+/// Get the global overflow settings
+pub fn get_overflow_settings() -> Arc<OverflowSettings> {
+    OVERFLOW_SETTINGS.clone()
+}
+
+/// Fit the earlier turns of a conversation into a model's context window,
+/// next to the message being sent and room for the reply, with the
+/// conversation's strategy. Emits an event whenever turns are dropped,
+/// summarized, or the message is refused.
+pub fn fit_history(
+    conversation_id: &str,
+    window: usize,
+    history: Vec<ChatTurn>,
+    message: &ChatTurn,
+) -> Result<Vec<ChatTurn>, String> {
+    let strategy = get_overflow_settings().get(conversation_id);
+    let budget = window.saturating_sub(REPLY_RESERVE_TOKENS + turn_tokens(message));
+    let tokens_before = total_tokens(&history);
+
+    let (result, event) = match fit(history, budget, strategy) {
+        Ok(fitted) if fitted.dropped.is_empty() => return Ok(fitted.turns),
+        Ok(fitted) => {
+            let event = OverflowEvent {
+                conversation_id: conversation_id.to_string(),
+                strategy,
+                budget_tokens: budget,
+                tokens_before: fitted.tokens_before,
+                tokens_after: fitted.tokens_after,
+                description: describe(strategy, &fitted, window),
+                dropped: fitted.dropped,
+                summary: fitted.summary,
+                sent: true,
+            };
+            (Ok(fitted.turns), event)
+        }
+        Err(e) => {
+            let event = OverflowEvent {
+                conversation_id: conversation_id.to_string(),
+                strategy,
+                budget_tokens: budget,
+                tokens_before,
+                tokens_after: 0,
+                dropped: Vec::new(),
+                summary: None,
+                sent: false,
+                description: e.clone(),
+            };
+            (Err(e), event)
+        }
+    };
+
+    info!("Context of {} overflowed: {}", conversation_id, event.description);
+    match serde_json::to_value(&event) {
+        Ok(payload) => get_event_system().emit(events::CONVERSATION_CONTEXT_TRIMMED, payload),
+        Err(e) => warn!("Failed to serialize overflow event: {}", e),
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Vec<ChatTurn> {
+        let mut turns = vec![ChatTurn::new(MessageRole::System, "Be brief.")];
+        for i in 0..6 {
+            turns.push(ChatTurn::new(MessageRole::User, format!("Question {}. {}", i, "words ".repeat(20))));
+            turns.push(ChatTurn::new(MessageRole::Assistant, format!("Answer {}. {}", i, "words ".repeat(20))));
+        }
+        turns
+    }
+
+    fn texts(turns: &[ChatTurn]) -> Vec<String> {
+        turns.iter().map(|t| t.text.split('.').next().unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn turns_that_fit_are_left_alone() {
+        let fitted = fit(conversation(), 10_000, OverflowStrategy::FailWithError).unwrap();
+        assert_eq!(fitted.turns, conversation());
+        assert!(fitted.dropped.is_empty());
+    }
+
+    #[test]
+    fn sliding_window_keeps_the_latest_turns() {
+        let budget = total_tokens(&conversation()) / 2;
+        let fitted = fit(conversation(), budget, OverflowStrategy::SlidingWindow).unwrap();
+
+        assert!(fitted.tokens_after <= budget);
+        assert_eq!(texts(&fitted.turns)[0], "Be brief");
+        assert_eq!(texts(&fitted.turns).last().unwrap(), "Answer 5");
+        assert_eq!(fitted.dropped[0].index, 1);
+        assert_eq!(fitted.dropped.len() + fitted.turns.len(), conversation().len());
+    }
+
+    #[test]
+    fn drop_middle_keeps_the_opening_exchange() {
+        let budget = total_tokens(&conversation()) / 2;
+        let fitted = fit(conversation(), budget, OverflowStrategy::DropMiddle).unwrap();
+
+        assert_eq!(texts(&fitted.turns)[..3], ["Be brief", "Question 0", "Answer 0"]);
+        assert_eq!(texts(&fitted.turns).last().unwrap(), "Answer 5");
+        assert_eq!(fitted.dropped[0].index, 3);
+    }
+
+    #[test]
+    fn summaries_replace_the_oldest_turns() {
+        let budget = total_tokens(&conversation()) / 2;
+        let fitted = fit(conversation(), budget, OverflowStrategy::SummaryCompression).unwrap();
+
+        assert!(fitted.tokens_after <= budget);
+        let summary = fitted.summary.clone().unwrap();
+        assert!(summary.contains("- Assistant: Answer 3."));
+        assert_eq!(fitted.turns[1], ChatTurn::new(MessageRole::System, summary));
+        assert_eq!(texts(&fitted.turns).last().unwrap(), "Answer 5");
+    }
+
+    #[test]
+    fn fail_with_error_refuses() {
+        let error = fit(conversation(), 50, OverflowStrategy::FailWithError).unwrap_err();
+        assert!(error.contains("tokens over"));
+        assert_eq!("summary".parse::<OverflowStrategy>(), Ok(OverflowStrategy::SummaryCompression));
+    }
+}
@@ -0,0 +1,265 @@
+use chrono::{DateTime, Utc};
+use git2::Repository;
+use log::{debug, info, warn};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Maximum number of changed files remembered per project
+const MAX_INDEXED_FILES: usize = 1000;
+
+/// Kind of change seen for a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Created => write!(f, "created"),
+            ChangeKind::Modified => write!(f, "modified"),
+            ChangeKind::Removed => write!(f, "removed"),
+        }
+    }
+}
+
+/// A file that changed while the project was watched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedFile {
+    /// Path relative to the project root
+    pub path: String,
+
+    /// Latest kind of change
+    pub kind: ChangeKind,
+
+    /// When the latest change was seen
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Incremental index of changed files in one project
+#[derive(Debug, Default)]
+pub struct ChangeIndex {
+    files: HashMap<String, ChangedFile>,
+}
+
+impl ChangeIndex {
+    /// Record a change, evicting the oldest entry when the index is full
+    pub fn record(&mut self, path: &str, kind: ChangeKind, changed_at: DateTime<Utc>) {
+        // A file created and then edited is still new to the conversation
+        let kind = match (self.files.get(path).map(|f| f.kind), kind) {
+            (Some(ChangeKind::Created), ChangeKind::Modified) => ChangeKind::Created,
+            _ => kind,
+        };
+
+        self.files.insert(
+            path.to_string(),
+            ChangedFile {
+                path: path.to_string(),
+                kind,
+                changed_at,
+            },
+        );
+
+        if self.files.len() > MAX_INDEXED_FILES {
+            if let Some(oldest) = self
+                .files
+                .values()
+                .min_by_key(|f| f.changed_at)
+                .map(|f| f.path.clone())
+            {
+                self.files.remove(&oldest);
+            }
+        }
+    }
+
+    /// Most recently changed files first
+    pub fn recent(&self, limit: usize) -> Vec<ChangedFile> {
+        let mut files: Vec<_> = self.files.values().cloned().collect();
+        files.sort_by(|a, b| b.changed_at.cmp(&a.changed_at));
+        files.truncate(limit);
+        files
+    }
+
+    /// Forget all changes
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+}
+
+/// A watched project
+struct WatchedProject {
+    /// Keeps the OS watch alive
+    _watcher: RecommendedWatcher,
+    index: Arc<Mutex<ChangeIndex>>,
+}
+
+/// Watches bound project folders and indexes changed files
+pub struct FileWatcher {
+    projects: Mutex<HashMap<PathBuf, WatchedProject>>,
+}
+
+impl FileWatcher {
+    /// Create a watcher with no projects
+    pub fn new() -> Self {
+        Self {
+            projects: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start watching a project; does nothing if it is already watched
+    pub fn watch(&self, root: &Path) -> Result<(), String> {
+        let root = root.canonicalize().map_err(|e| e.to_string())?;
+        let mut projects = self.projects.lock().unwrap();
+        if projects.contains_key(&root) {
+            return Ok(());
+        }
+
+        let index = Arc::new(Mutex::new(ChangeIndex::default()));
+        // Used only for .gitignore checks; projects outside git index everything
+        let repo = Mutex::new(Repository::open(&root).ok());
+
+        let handler_index = index.clone();
+        let handler_root = root.clone();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            let event = match result {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!("File watcher error: {}", e);
+                    return;
+                }
+            };
+
+            let kind = match event.kind {
+                EventKind::Create(_) => ChangeKind::Created,
+                EventKind::Modify(_) => ChangeKind::Modified,
+                EventKind::Remove(_) => ChangeKind::Removed,
+                _ => return,
+            };
+
+            let repo = repo.lock().unwrap();
+            let mut index = handler_index.lock().unwrap();
+            for path in event.paths {
+                if path.is_dir() {
+                    continue;
+                }
+                let Ok(relative) = path.strip_prefix(&handler_root) else {
+                    continue;
+                };
+                if is_ignored(repo.as_ref(), relative) {
+                    continue;
+                }
+                index.record(&relative.to_string_lossy(), kind, Utc::now());
+            }
+        })
+        .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+
+        info!("Watching {} for changes", root.display());
+        projects.insert(
+            root,
+            WatchedProject {
+                _watcher: watcher,
+                index,
+            },
+        );
+        Ok(())
+    }
+
+    /// Stop watching a project
+    pub fn unwatch(&self, root: &Path) {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        if self.projects.lock().unwrap().remove(&root).is_some() {
+            debug!("Stopped watching {}", root.display());
+        }
+    }
+
+    /// Most recently changed files in a project, newest first
+    pub fn recently_changed(&self, root: &Path, limit: usize) -> Vec<ChangedFile> {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        self.projects
+            .lock()
+            .unwrap()
+            .get(&root)
+            .map(|project| project.index.lock().unwrap().recent(limit))
+            .unwrap_or_default()
+    }
+
+    /// Forget the recorded changes of a project, e.g. after they were discussed
+    pub fn clear(&self, root: &Path) {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        if let Some(project) = self.projects.lock().unwrap().get(&root) {
+            project.index.lock().unwrap().clear();
+        }
+    }
+}
+
+/// Whether a changed path should stay out of the index
+fn is_ignored(repo: Option<&Repository>, relative: &Path) -> bool {
+    if relative.components().any(|c| matches!(c, Component::Normal(name) if name == ".git")) {
+        return true;
+    }
+    repo.map(|repo| repo.is_path_ignored(relative).unwrap_or(false))
+        .unwrap_or(false)
+}
+
+static FILE_WATCHER: Lazy<FileWatcher> = Lazy::new(FileWatcher::new);
+
+/// Get the global file watcher
+pub fn get_file_watcher() -> &'static FileWatcher {
+    &FILE_WATCHER
+}
+
+/// Watch every project currently bound to a conversation
+pub fn watch_bound_projects() {
+    for root in crate::tools::git::get_repo_bindings().repositories() {
+        if let Err(e) = get_file_watcher().watch(&root) {
+            warn!("{}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_index_orders_by_recency() {
+        let now = Utc::now();
+        let mut index = ChangeIndex::default();
+        index.record("a.rs", ChangeKind::Modified, now - Duration::seconds(10));
+        index.record("b.rs", ChangeKind::Modified, now);
+
+        let recent = index.recent(10);
+        assert_eq!(recent[0].path, "b.rs");
+        assert_eq!(recent[1].path, "a.rs");
+        assert_eq!(index.recent(1).len(), 1);
+    }
+
+    #[test]
+    fn test_created_then_modified_stays_created() {
+        let now = Utc::now();
+        let mut index = ChangeIndex::default();
+        index.record("new.rs", ChangeKind::Created, now);
+        index.record("new.rs", ChangeKind::Modified, now);
+
+        assert_eq!(index.recent(1)[0].kind, ChangeKind::Created);
+    }
+
+    #[test]
+    fn test_git_directory_is_ignored() {
+        assert!(is_ignored(None, Path::new(".git/index")));
+        assert!(!is_ignored(None, Path::new("src/main.rs")));
+    }
+}
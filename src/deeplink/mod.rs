@@ -0,0 +1,290 @@
+//! `papin://` links from browsers and other apps
+//!
+//! Links are untrusted input: they are length-limited, parsed into a fixed
+//! set of actions with validated arguments, and rate-limited. None of them
+//! acts on its own; they open a view or a pre-filled draft and the user
+//! confirms anything further, such as joining a session.
+
+pub mod register;
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::system::share::{self, SharedContent};
+use crate::utils::events::{events, get_event_system};
+
+/// URL scheme the app handles
+pub const SCHEME: &str = "papin";
+
+/// Longest link accepted
+const MAX_LINK_LENGTH: usize = 16 * 1024;
+
+/// Longest id or token accepted
+const MAX_ID_LENGTH: usize = 128;
+
+/// Longest text an `ask` or `share` link can pre-fill, in characters
+const MAX_TEXT_CHARS: usize = 10_000;
+
+/// Links handled per `RATE_WINDOW`; a page can't flood the app with links
+const RATE_LIMIT: usize = 5;
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// When recent links were handled
+static RECENT: Lazy<Mutex<VecDeque<Instant>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// What a link asks the app to do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DeepLink {
+    /// `papin://conversation/<id>`: open a conversation
+    Conversation { id: String },
+
+    /// `papin://ask?text=...`: open a quick ask, pre-filled with the text if any
+    Ask { text: String },
+
+    /// `papin://join-session/<token>`: offer to join a collaboration session
+    JoinSession { token: String },
+
+    /// `papin://share?text=...`: open a new conversation with shared text
+    Share { text: String },
+}
+
+/// Why a link was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkError {
+    /// Not a `papin://` URL, or not a URL at all
+    NotALink,
+    /// Longer than the app accepts
+    TooLong,
+    /// Unknown action
+    UnknownAction(String),
+    /// Missing or malformed argument
+    InvalidArgument(&'static str),
+}
+
+impl std::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkError::NotALink => write!(f, "not a {}:// link", SCHEME),
+            LinkError::TooLong => write!(f, "link is longer than {} bytes", MAX_LINK_LENGTH),
+            LinkError::UnknownAction(action) => write!(f, "unknown action '{}'", action),
+            LinkError::InvalidArgument(name) => write!(f, "missing or invalid {}", name),
+        }
+    }
+}
+
+/// Ids and tokens are short and URL-safe; anything else is refused rather
+/// than passed on
+fn valid_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_ID_LENGTH
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The single path segment after the action, e.g. the id in `conversation/<id>`
+fn single_segment(url: &Url) -> Option<String> {
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    match segments.as_slice() {
+        [segment] => Some(segment.to_string()),
+        _ => None,
+    }
+}
+
+/// Text from the `text` query parameter, without control characters other
+/// than line breaks and tabs
+fn text_param(url: &Url) -> Result<String, LinkError> {
+    let text = url
+        .query_pairs()
+        .find(|(name, _)| name == "text")
+        .map(|(_, value)| value.into_owned())
+        .ok_or(LinkError::InvalidArgument("text"))?;
+    let text: String = text
+        .chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+        .take(MAX_TEXT_CHARS)
+        .collect();
+    if text.trim().is_empty() {
+        return Err(LinkError::InvalidArgument("text"));
+    }
+    Ok(text)
+}
+
+impl DeepLink {
+    /// Parse and validate a link
+    pub fn parse(link: &str) -> Result<Self, LinkError> {
+        if link.len() > MAX_LINK_LENGTH {
+            return Err(LinkError::TooLong);
+        }
+        let url = Url::parse(link.trim()).map_err(|_| LinkError::NotALink)?;
+        if url.scheme() != SCHEME {
+            return Err(LinkError::NotALink);
+        }
+        // Credentials and ports have no meaning here; links carrying them are suspicious
+        if !url.username().is_empty() || url.password().is_some() || url.port().is_some() {
+            return Err(LinkError::NotALink);
+        }
+
+        let action = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        match action.as_str() {
+            "conversation" => {
+                let id = single_segment(&url)
+                    .filter(|id| valid_id(id))
+                    .ok_or(LinkError::InvalidArgument("conversation id"))?;
+                Ok(DeepLink::Conversation { id })
+            }
+            // A bare `papin://ask` opens an empty quick ask, e.g. from a desktop shortcut
+            "ask" => Ok(DeepLink::Ask {
+                text: text_param(&url).unwrap_or_default(),
+            }),
+            "join-session" => {
+                let token = single_segment(&url)
+                    .filter(|token| valid_id(token))
+                    .ok_or(LinkError::InvalidArgument("session token"))?;
+                Ok(DeepLink::JoinSession { token })
+            }
+            "share" => Ok(DeepLink::Share { text: text_param(&url)? }),
+            _ => Err(LinkError::UnknownAction(action)),
+        }
+    }
+
+    /// Whether an argument looks like a link for this app
+    pub fn is_link(arg: &str) -> bool {
+        let prefix = format!("{}://", SCHEME);
+        arg.len() > prefix.len() && arg.get(..prefix.len()).map_or(false, |p| p.eq_ignore_ascii_case(&prefix))
+    }
+
+    /// Action name, for logs
+    pub fn action(&self) -> &'static str {
+        match self {
+            DeepLink::Conversation { .. } => "conversation",
+            DeepLink::Ask { .. } => "ask",
+            DeepLink::JoinSession { .. } => "join-session",
+            DeepLink::Share { .. } => "share",
+        }
+    }
+}
+
+/// Record a link and say whether it is within the rate limit
+fn allow(now: Instant) -> bool {
+    let mut recent = RECENT.lock().unwrap();
+    while recent.front().map_or(false, |at| now.duration_since(*at) > RATE_WINDOW) {
+        recent.pop_front();
+    }
+    if recent.len() >= RATE_LIMIT {
+        return false;
+    }
+    recent.push_back(now);
+    true
+}
+
+/// Handle a link from the command line, another instance or the OS
+pub fn handle(app: &AppHandle, link: &str) {
+    let link = match DeepLink::parse(link) {
+        Ok(link) => link,
+        Err(e) => {
+            warn!("Ignoring link: {}", e);
+            return;
+        }
+    };
+    if !allow(Instant::now()) {
+        warn!("Ignoring link: too many links opened at once");
+        return;
+    }
+    info!("Opening {} link", link.action());
+
+    match &link {
+        DeepLink::Ask { text } => get_event_system().emit(
+            events::QUICK_ASK_REQUESTED,
+            serde_json::json!({ "source": "link", "text": text }),
+        ),
+        DeepLink::Share { text } => {
+            // `deliver` brings the window up itself
+            share::deliver(app, &SharedContent { text: Some(text.clone()), files: Vec::new() });
+            return;
+        }
+        // Opening a conversation or a join prompt is up to the frontend; joining needs the user's go-ahead
+        DeepLink::Conversation { .. } | DeepLink::JoinSession { .. } => get_event_system().emit(
+            events::DEEP_LINK_OPENED,
+            serde_json::to_value(&link).unwrap_or_default(),
+        ),
+    }
+
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Handle every link among launch arguments
+pub fn handle_args<I: IntoIterator<Item = String>>(app: &AppHandle, args: I) {
+    for arg in args.into_iter().filter(|arg| DeepLink::is_link(arg)) {
+        handle(app, &arg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_actions() {
+        assert_eq!(
+            DeepLink::parse("papin://conversation/abc-123").unwrap(),
+            DeepLink::Conversation { id: "abc-123".to_string() }
+        );
+        assert_eq!(
+            DeepLink::parse("papin://ask?text=What%20is%20MCP%3F").unwrap(),
+            DeepLink::Ask { text: "What is MCP?".to_string() }
+        );
+        assert_eq!(DeepLink::parse("papin://ask").unwrap(), DeepLink::Ask { text: String::new() });
+        assert_eq!(
+            DeepLink::parse("PAPIN://join-session/tok_9/").unwrap(),
+            DeepLink::JoinSession { token: "tok_9".to_string() }
+        );
+        assert_eq!(
+            DeepLink::parse("papin://share?text=line%0Aone%07").unwrap(),
+            DeepLink::Share { text: "line\none".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_reject_bad_links() {
+        assert_eq!(DeepLink::parse("https://conversation/abc"), Err(LinkError::NotALink));
+        assert_eq!(DeepLink::parse("not a link"), Err(LinkError::NotALink));
+        assert_eq!(DeepLink::parse("papin://user:pw@ask?text=hi"), Err(LinkError::NotALink));
+        assert_eq!(
+            DeepLink::parse("papin://delete-all"),
+            Err(LinkError::UnknownAction("delete-all".to_string()))
+        );
+        assert!(DeepLink::parse("papin://conversation/a%2F..%2Fb").is_err());
+        assert!(DeepLink::parse("papin://conversation/a/b").is_err());
+        assert!(DeepLink::parse("papin://join-session/%3Cscript%3E").is_err());
+        assert!(DeepLink::parse("papin://share?text=%20").is_err());
+        assert_eq!(
+            DeepLink::parse(&format!("papin://ask?text={}", "a".repeat(MAX_LINK_LENGTH))),
+            Err(LinkError::TooLong)
+        );
+    }
+
+    #[test]
+    fn test_is_link() {
+        assert!(DeepLink::is_link("papin://ask?text=hi"));
+        assert!(!DeepLink::is_link("--share-text"));
+        assert!(!DeepLink::is_link("papin://"));
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let start = Instant::now() + Duration::from_secs(3600);
+        let allowed = (0..RATE_LIMIT + 2).filter(|_| allow(start)).count();
+        assert_eq!(allowed, RATE_LIMIT);
+        assert!(allow(start + RATE_WINDOW + Duration::from_secs(1)));
+    }
+}
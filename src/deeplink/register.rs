@@ -0,0 +1,82 @@
+//! Registering the app as the handler of `papin://` links
+//!
+//! Windows and Linux are registered per user when the app starts, so the
+//! handler follows the app when it moves; on macOS the bundle's Info.plist
+//! declares the scheme.
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+use log::info;
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+use crate::system::executable;
+#[cfg(target_os = "windows")]
+use crate::system::reg;
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+use super::SCHEME;
+
+/// Point `papin://` at this executable
+#[cfg(target_os = "windows")]
+pub fn register() -> Result<(), String> {
+    let exe = executable()?;
+    let exe = exe.to_string_lossy();
+    let key = format!(r"HKCU\Software\Classes\{}", SCHEME);
+    reg(&["add", &key, "/ve", "/d", "URL:Papin link", "/f"])?;
+    reg(&["add", &key, "/v", "URL Protocol", "/d", "", "/f"])?;
+    reg(&["add", &format!(r"{}\DefaultIcon", key), "/ve", "/d", &exe, "/f"])?;
+    reg(&[
+        "add",
+        &format!(r"{}\shell\open\command", key),
+        "/ve",
+        "/d",
+        &format!("\"{}\" \"%1\"", exe),
+        "/f",
+    ])?;
+    info!("Registered the {}:// link handler", SCHEME);
+    Ok(())
+}
+
+/// Desktop entry that handles the scheme
+#[cfg(target_os = "linux")]
+const DESKTOP_FILE: &str = "papin-links.desktop";
+
+/// Install a desktop entry for `x-scheme-handler/papin` and make it the default
+#[cfg(target_os = "linux")]
+pub fn register() -> Result<(), String> {
+    let dir = dirs::data_dir().ok_or("No applications directory")?.join("applications");
+    let path = dir.join(DESKTOP_FILE);
+    let entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=Papin\n\
+         Exec=\"{}\" %u\n\
+         Icon=papin\n\
+         MimeType=x-scheme-handler/{};\n\
+         NoDisplay=true\n\
+         Terminal=false\n",
+        executable()?.display(),
+        SCHEME
+    );
+
+    // Rewrite only when the entry changed, e.g. after the app moved
+    if std::fs::read_to_string(&path).ok().as_deref() == Some(entry.as_str()) {
+        return Ok(());
+    }
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    std::fs::write(&path, entry).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let status = std::process::Command::new("xdg-mime")
+        .args(["default", DESKTOP_FILE, &format!("x-scheme-handler/{}", SCHEME)])
+        .status();
+    if !matches!(status, Ok(s) if s.success()) {
+        return Err("xdg-mime could not make Papin the link handler".to_string());
+    }
+    info!("Registered the {}:// link handler", SCHEME);
+    Ok(())
+}
+
+/// The bundle's Info.plist declares the scheme
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn register() -> Result<(), String> {
+    Ok(())
+}
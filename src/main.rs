@@ -5,28 +5,42 @@
 
 mod collaboration;
 mod commands;
+mod context;
+mod deeplink;
 mod feature_flags;
 mod models;
+mod ocr;
+mod optimization;
 mod protocols;
 mod security;
 mod services;
 mod shell_loader;
+mod system;
+mod telemetry;
+mod tools;
+mod tray;
 mod utils;
 
 use env_logger::Env;
-use log::{error, info};
+use log::{info, warn};
 use std::sync::{Arc, Mutex};
 use tauri::{Manager, WindowBuilder, WindowUrl};
 use tokio::runtime::Runtime;
 
+use crate::collaboration::init_collaboration;
 use crate::feature_flags::{FeatureFlags, FeatureManager};
 use crate::security::{init_security_manager, SecurityConfig, PermissionLevel};
-use crate::shell_loader::{launch_with_fast_shell, ShellLoader};
+use crate::services::supervisor::{get_supervisor, Subsystem};
+use crate::shell_loader::{launch_with_fast_shell, startup_report, ShellLoader, StartupReport};
 use crate::utils::config::Config;
 
 // Global runtime handle for async operations
 lazy_static::lazy_static! {
-    static ref RUNTIME: Runtime = Runtime::new().expect("Failed to create Tokio runtime");
+    static ref RUNTIME: Runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(optimization::get_thread_settings().background_threads)
+        .enable_all()
+        .build()
+        .expect("Failed to create Tokio runtime");
 }
 
 // Global feature manager
@@ -93,11 +107,32 @@ async fn get_enabled_features() -> Result<Vec<String>, String> {
     Ok(enabled_features)
 }
 
+#[tauri::command]
+async fn get_startup_report() -> Result<StartupReport, String> {
+    Ok(startup_report())
+}
+
 fn main() {
-    // Initialize logging
-    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+    // Portable mode has to be settled before anything opens a file
+    if let Err(e) = mcp_common::platform::fs::init_from_args() {
+        eprintln!("Failed to start in portable mode: {}", e);
+    }
+    
+    // Initialize logging; lines are redacted before they are printed or kept
+    let console = env_logger::Builder::from_env(Env::default().default_filter_or("info")).build();
+    if let Err(e) = mcp_common::logs::init_logging(console) {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
     info!("Starting Claude MCP Client");
     
+    // Hand the launch to a running instance unless asked for a separate one
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let instance = match system::instance::start(&args) {
+        system::instance::Startup::Forwarded => return,
+        system::instance::Startup::Primary(primary) => Some(primary),
+        system::instance::Startup::Separate => None,
+    };
+    
     // Load config
     let config = Config::global();
     
@@ -108,7 +143,9 @@ fn main() {
     builder = commands::register_commands(builder);
     
     builder
-        .setup(|app| {
+        .system_tray(tray::build())
+        .on_system_tray_event(tray::handle_event)
+        .setup(move |app| {
             // Get the main window or create it
             let window = app.get_window("main").unwrap_or_else(|| {
                 WindowBuilder::new(
@@ -125,8 +162,43 @@ fn main() {
             
             // Store app handle in state
             let app_handle = app.handle();
+            utils::events::bridge_to_tauri(app_handle.clone());
+            tray::watch(app_handle.clone());
+            system::shortcuts::register(&app_handle);
+            
+            // Open text or files shared from another app, and papin:// links, from this and later launches
+            system::instance::open_args(&app_handle, &args, None);
+            if let Some(instance) = instance {
+                instance.serve(app_handle.clone());
+            }
+            // A portable copy leaves the system's link handlers alone
+            if !mcp_common::platform::fs::is_portable() {
+                if let Err(e) = deeplink::register::register() {
+                    warn!("Failed to register the papin:// link handler: {}", e);
+                }
+            }
+            context::watcher::watch_bound_projects();
+            
+            // Failed subsystems are restarted on this runtime
+            let _runtime = RUNTIME.enter();
+            
+            // Keep the connection health indicator and team workspace current, call webhooks, check feeds,
+            // announce reminders and check the template gallery for updates
+            mcp_common::service::credentials::spawn_health_monitor(std::time::Duration::from_secs(60));
+            mcp_common::sync::spawn_team_sync(std::time::Duration::from_secs(300));
+            mcp_common::auth::spawn_token_refresher(std::time::Duration::from_secs(60));
+            mcp_common::models::registry::spawn_catalog_refresher(std::time::Duration::from_secs(3600));
+            mcp_common::webhooks::spawn_webhook_dispatcher(std::time::Duration::from_secs(30));
+            commands::feeds::start_feed_scheduler();
+            commands::reminders::start_reminder_scheduler();
+            commands::templates::start_template_update_checker();
+            commands::snapshots::start_share_expiry();
             app.manage(Arc::new(Mutex::new(app_handle)));
             
+            // Unload idle local models when RAM or VRAM runs low
+            optimization::get_pressure_monitor().set_vram_probe(crate::ai::local::acceleration::vram_usage_percent);
+            app.state::<commands::optimization::OptimizationState>().initialize();
+            
             // Initialize security manager
             let security_config = SecurityConfig {
                 e2ee_enabled: true,
@@ -140,10 +212,40 @@ fn main() {
                 clipboard_security_enabled: true,
             };
             
-            if let Err(e) = init_security_manager(Some(security_config)) {
-                error!("Failed to initialize security manager: {}", e);
-            } else {
-                info!("Security manager initialized");
+            // Subsystems that fail are tracked, retried and their features disabled
+            let supervisor = get_supervisor();
+            supervisor.register(
+                Subsystem::new("security", move || {
+                    init_security_manager(Some(security_config.clone())).map_err(|e| e.to_string())?;
+                    // OAuth tokens go to the platform vault
+                    mcp_common::auth::set_secret_store(Arc::new(security::VaultSecretStore));
+                    Ok(())
+                })
+                .gates("secure_credentials")
+                .gates("e2ee"),
+            );
+            supervisor.register(
+                Subsystem::new("collaboration", || init_collaboration(None).map_err(|e| e.to_string()))
+                    .depends_on("security")
+                    .gates("collaboration"),
+            );
+            
+            // Continue model downloads and other jobs cut short by the last shutdown
+            crate::ai::local::register_download_resumer();
+            crate::ai::local::translation::register();
+            let resumed = mcp_common::jobs::get_job_manager().recover();
+            if resumed > 0 {
+                info!("Resumed {} interrupted jobs", resumed);
+            }
+            crate::ai::local::spawn_model_update_checker(std::time::Duration::from_secs(6 * 3600));
+            
+            // Keep the optional remote price list current
+            if let Some(url) = mcp_common::config::get_settings().lock().unwrap().cost.price_list_url.clone() {
+                RUNTIME.spawn(async move {
+                    if let Err(e) = mcp_common::service::pricing::PriceTable::new().refresh_if_stale(&url).await {
+                        warn!("Failed to refresh prices from {}: {}", url, e);
+                    }
+                });
             }
             
             // Start shell loader (this happens in Tokio runtime)
@@ -162,7 +264,20 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             get_app_info,
             get_enabled_features,
+            get_startup_report,
         ])
-        .run(tauri::generate_context!())
-        .expect("Error running Tauri application");
+        .build(tauri::generate_context!())
+        .expect("Error building Tauri application")
+        .run(|_app, _event| {
+            // Files opened with the app or dropped onto its Dock icon, and papin:// links
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = &_event {
+                if let Some(content) = system::share::SharedContent::from_file_urls(urls) {
+                    system::share::deliver(_app, &content);
+                }
+                for url in urls.iter().filter(|url| url.scheme() == deeplink::SCHEME) {
+                    deeplink::handle(_app, url.as_str());
+                }
+            }
+        });
 }
\ No newline at end of file
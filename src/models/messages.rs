@@ -4,6 +4,9 @@ use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
+/// Metadata key holding a message's user feedback
+pub const FEEDBACK_METADATA_KEY: &str = "feedback";
+
 /// Error type for message-related operations
 #[derive(Debug, thiserror::Error)]
 pub enum MessageError {
@@ -25,6 +28,9 @@ pub enum MessageError {
     #[error("Connection closed")]
     ConnectionClosed,
     
+    #[error("Request cancelled")]
+    Cancelled,
+    
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
@@ -47,7 +53,13 @@ pub enum ContentType {
     Text { text: String },
     
     #[serde(rename = "image")]
-    Image { url: String, media_type: String },
+    Image {
+        url: String,
+        media_type: String,
+        /// Text extracted from the image, used for models without vision
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ocr_text: Option<String>,
+    },
     
     #[serde(rename = "tool_call")]
     ToolCall {
@@ -163,6 +175,31 @@ impl Message {
         self
     }
     
+    /// User feedback on the message, if it was rated
+    pub fn feedback(&self) -> Option<mcp_common::models::MessageFeedback> {
+        self.metadata
+            .as_ref()
+            .and_then(|m| m.get(FEEDBACK_METADATA_KEY))
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+    }
+    
+    /// Set or clear the user feedback
+    pub fn set_feedback(&mut self, feedback: Option<mcp_common::models::MessageFeedback>) {
+        match feedback {
+            Some(feedback) => {
+                let value = serde_json::to_value(feedback).unwrap_or_default();
+                self.metadata
+                    .get_or_insert_with(HashMap::new)
+                    .insert(FEEDBACK_METADATA_KEY.to_string(), value);
+            }
+            None => {
+                if let Some(metadata) = self.metadata.as_mut() {
+                    metadata.remove(FEEDBACK_METADATA_KEY);
+                }
+            }
+        }
+    }
+    
     /// Get text content if message contains only text
     pub fn text_content(&self) -> Option<&str> {
         if self.content.parts.len() == 1 {
@@ -176,6 +213,181 @@ impl Message {
     }
 }
 
+/// Conversions to and from the shared mcp-common message types.
+///
+/// These shims let code move to `mcp_common::models::Message` one module at a
+/// time; they go away once nothing in the desktop app uses the local types.
+mod shared {
+    use super::*;
+    use mcp_common::models as common;
+    use mcp_common::models::message::ContentType as CommonContent;
+
+    impl From<MessageRole> for common::MessageRole {
+        fn from(role: MessageRole) -> Self {
+            match role {
+                MessageRole::User => common::MessageRole::User,
+                MessageRole::Assistant => common::MessageRole::Assistant,
+                MessageRole::System => common::MessageRole::System,
+                MessageRole::Tool => common::MessageRole::Tool,
+            }
+        }
+    }
+
+    impl From<common::MessageRole> for MessageRole {
+        fn from(role: common::MessageRole) -> Self {
+            match role {
+                common::MessageRole::User => MessageRole::User,
+                common::MessageRole::Assistant => MessageRole::Assistant,
+                common::MessageRole::System => MessageRole::System,
+                common::MessageRole::Tool => MessageRole::Tool,
+            }
+        }
+    }
+
+    /// Guess an image media type from its URL
+    fn media_type_for(url: &str) -> String {
+        let lower = url.to_lowercase();
+        let media_type = if lower.ends_with(".jpg") || lower.ends_with(".jpeg") {
+            "image/jpeg"
+        } else if lower.ends_with(".gif") {
+            "image/gif"
+        } else if lower.ends_with(".webp") {
+            "image/webp"
+        } else {
+            "image/png"
+        };
+        media_type.to_string()
+    }
+
+    impl From<Message> for common::Message {
+        fn from(message: Message) -> Self {
+            // Feedback rides in metadata on the desktop side
+            let feedback = message.feedback();
+            let mut metadata = message.metadata;
+            if let Some(map) = metadata.as_mut() {
+                map.remove(FEEDBACK_METADATA_KEY);
+            }
+
+            let parts = message
+                .content
+                .parts
+                .into_iter()
+                .map(|part| match part {
+                    ContentType::Text { text } => CommonContent::Text { text },
+                    ContentType::Image { url, ocr_text, .. } => CommonContent::Image { url, alt_text: ocr_text },
+                    ContentType::ToolCall { id, name, arguments } => CommonContent::ToolCalls {
+                        calls: vec![common::ToolCall {
+                            id,
+                            arguments: serde_json::from_str(&arguments)
+                                .unwrap_or(serde_json::Value::String(arguments)),
+                            name,
+                        }],
+                    },
+                    ContentType::ToolResult { tool_call_id, result } => CommonContent::ToolResults {
+                        results: vec![serde_json::json!({
+                            "tool_call_id": tool_call_id,
+                            "result": result,
+                        })],
+                    },
+                })
+                .collect();
+
+            Self {
+                id: message.id,
+                role: message.role.into(),
+                content: common::MessageContent { parts },
+                metadata,
+                feedback,
+                created_at: message.created_at,
+            }
+        }
+    }
+
+    impl From<common::Message> for Message {
+        fn from(message: common::Message) -> Self {
+            let mut parts = Vec::new();
+
+            for part in message.content.parts {
+                match part {
+                    CommonContent::Text { text } => parts.push(ContentType::Text { text }),
+                    CommonContent::Image { url, alt_text } => {
+                        let media_type = media_type_for(&url);
+                        parts.push(ContentType::Image {
+                            url,
+                            media_type,
+                            ocr_text: alt_text,
+                        });
+                    }
+                    CommonContent::ToolCalls { calls } => {
+                        parts.extend(calls.into_iter().map(|call| ContentType::ToolCall {
+                            id: call.id,
+                            name: call.name,
+                            arguments: match call.arguments {
+                                serde_json::Value::String(s) => s,
+                                other => other.to_string(),
+                            },
+                        }));
+                    }
+                    CommonContent::ToolResults { results } => {
+                        parts.extend(results.into_iter().map(|result| ContentType::ToolResult {
+                            tool_call_id: result
+                                .get("tool_call_id")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or_default()
+                                .to_string(),
+                            result: match result.get("result") {
+                                Some(serde_json::Value::String(s)) => s.clone(),
+                                Some(other) => other.to_string(),
+                                None => result.to_string(),
+                            },
+                        }));
+                    }
+                }
+            }
+
+            let mut converted = Self {
+                id: message.id,
+                role: message.role.into(),
+                content: MessageContent { parts },
+                metadata: message.metadata,
+                created_at: message.created_at,
+            };
+            converted.set_feedback(message.feedback);
+            converted
+        }
+    }
+
+    impl From<common::MessageError> for MessageError {
+        fn from(error: common::MessageError) -> Self {
+            match error {
+                common::MessageError::Timeout(duration) => MessageError::Timeout(duration),
+                common::MessageError::Network(msg) => MessageError::NetworkError(msg),
+                common::MessageError::Auth(msg) => MessageError::AuthError(msg),
+                common::MessageError::RateLimit(msg) => MessageError::ProtocolError(format!("Rate limited: {}", msg)),
+                common::MessageError::BadRequest(msg) => MessageError::ProtocolError(msg),
+                common::MessageError::Unknown(msg) => MessageError::Unknown(msg),
+            }
+        }
+    }
+
+    impl From<mcp_common::error::McpError> for MessageError {
+        fn from(error: mcp_common::error::McpError) -> Self {
+            use mcp_common::error::McpError;
+
+            match error {
+                McpError::Message(inner) => inner.into(),
+                McpError::Connection(msg) => MessageError::NetworkError(msg),
+                McpError::Authentication(msg) => MessageError::AuthError(msg),
+                McpError::Protocol(msg) | McpError::InvalidRequest(msg) => MessageError::ProtocolError(msg),
+                McpError::Serialization(e) => MessageError::SerializationError(e.to_string()),
+                McpError::Cancelled => MessageError::Cancelled,
+                McpError::Timeout(duration) => MessageError::Timeout(duration),
+                other => MessageError::Unknown(other.to_string()),
+            }
+        }
+    }
+}
+
 /// Time serialization helpers for serde
 mod time_serde {
     use serde::{Deserialize, Deserializer, Serializer};
@@ -0,0 +1,219 @@
+use crate::models::messages::{ContentType, Message};
+use crate::utils::config;
+use base64::Engine as _;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Config key holding the OCR settings
+const OCR_CONFIG_KEY: &str = "ocr";
+
+/// How image parts are sent to a model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageMode {
+    /// Raw image for vision models, OCR text for the rest
+    Auto,
+    /// Always send the raw image
+    Image,
+    /// Always send the OCR text
+    Text,
+}
+
+impl Default for ImageMode {
+    fn default() -> Self {
+        ImageMode::Auto
+    }
+}
+
+/// OCR settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrConfig {
+    /// How image parts are sent
+    #[serde(default)]
+    pub image_mode: ImageMode,
+
+    /// Path to the tesseract binary (default: found on PATH)
+    #[serde(default)]
+    pub tesseract_path: Option<PathBuf>,
+
+    /// Tesseract language codes, e.g. "eng+deu"
+    #[serde(default = "default_languages")]
+    pub languages: String,
+}
+
+fn default_languages() -> String {
+    "eng".to_string()
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        Self {
+            image_mode: ImageMode::Auto,
+            tesseract_path: None,
+            languages: default_languages(),
+        }
+    }
+}
+
+impl OcrConfig {
+    /// Load the OCR settings from the app config
+    pub fn load() -> Self {
+        let config = config::get_config();
+        let config = config.lock().unwrap();
+
+        config
+            .get_value(OCR_CONFIG_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persist the OCR settings
+    pub fn save(&self) -> Result<(), String> {
+        let value = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        config::set_value(OCR_CONFIG_KEY, value)?;
+        config::save_config().map_err(|e| e.to_string())
+    }
+
+    /// Whether images should be replaced by their text for a model
+    pub fn send_as_text(&self, supports_vision: bool) -> bool {
+        match self.image_mode {
+            ImageMode::Auto => !supports_vision,
+            ImageMode::Image => false,
+            ImageMode::Text => true,
+        }
+    }
+}
+
+/// Text extraction using the tesseract command-line tool
+pub struct OcrEngine {
+    binary: PathBuf,
+    languages: String,
+}
+
+impl OcrEngine {
+    /// Create an engine from the settings
+    pub fn new(config: &OcrConfig) -> Self {
+        Self {
+            binary: config
+                .tesseract_path
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("tesseract")),
+            languages: config.languages.clone(),
+        }
+    }
+
+    /// Whether the tesseract binary can be run
+    pub fn is_available(&self) -> bool {
+        Command::new(&self.binary)
+            .arg("--version")
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    /// Extract text from encoded image bytes (PNG, JPEG, ...)
+    pub fn extract_text(&self, image: &[u8]) -> Result<String, String> {
+        let mut file = tempfile::NamedTempFile::new().map_err(|e| e.to_string())?;
+        file.write_all(image).map_err(|e| e.to_string())?;
+
+        let output = Command::new(&self.binary)
+            .arg(file.path())
+            .arg("stdout")
+            .args(["-l", &self.languages])
+            .output()
+            .map_err(|e| format!("Failed to run tesseract: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "tesseract failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Extract text from an image part's URL (a data URL or a local path)
+    pub fn extract_from_url(&self, url: &str) -> Result<String, String> {
+        let bytes = decode_image_url(url)?;
+        self.extract_text(&bytes)
+    }
+}
+
+/// Decode the image bytes referenced by an image part
+pub fn decode_image_url(url: &str) -> Result<Vec<u8>, String> {
+    if let Some(id) = url.strip_prefix(mcp_common::config::ATTACHMENT_URL_SCHEME) {
+        return mcp_common::config::get_attachment_store()
+            .read(id)
+            .map_err(|e| e.to_string());
+    }
+
+    if let Some(data) = url.strip_prefix("data:") {
+        // Accept both "data:image/png;base64,<data>" and the bare "data:<data>" form
+        let encoded = data.split_once("base64,").map(|(_, d)| d).unwrap_or(data);
+        return base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| format!("Invalid image data: {}", e));
+    }
+
+    let path = url.strip_prefix("file://").unwrap_or(url);
+    std::fs::read(path).map_err(|e| format!("Failed to read image {}: {}", path, e))
+}
+
+/// Run OCR on every image part that has no text yet, storing it on the part.
+///
+/// Failures are logged and leave the part without text.
+pub fn annotate_images(message: &mut Message, engine: &OcrEngine) {
+    for part in message.content.parts.iter_mut() {
+        if let ContentType::Image { url, ocr_text, .. } = part {
+            if ocr_text.is_some() {
+                continue;
+            }
+            match engine.extract_from_url(url) {
+                Ok(text) => *ocr_text = Some(text),
+                Err(e) => warn!("OCR failed for image in message {}: {}", message.id, e),
+            }
+        }
+    }
+}
+
+/// Prepare a message's images for a model.
+///
+/// When the settings call for text, images are replaced with their OCR text
+/// (extracting it first if needed); otherwise the message is sent unchanged.
+pub fn prepare_for_model(mut message: Message, supports_vision: bool) -> Message {
+    let config = OcrConfig::load();
+    let has_images = message
+        .content
+        .parts
+        .iter()
+        .any(|part| matches!(part, ContentType::Image { .. }));
+
+    if !has_images || !config.send_as_text(supports_vision) {
+        return message;
+    }
+
+    let engine = OcrEngine::new(&config);
+    annotate_images(&mut message, &engine);
+    debug!("Sending images of message {} as OCR text", message.id);
+
+    message.content.parts = message
+        .content
+        .parts
+        .into_iter()
+        .map(|part| match part {
+            ContentType::Image { ocr_text, .. } => ContentType::Text {
+                text: match ocr_text {
+                    Some(text) if !text.is_empty() => format!("[Text extracted from image]\n{}", text),
+                    _ => "[Image omitted: no text could be extracted]".to_string(),
+                },
+            },
+            other => other,
+        })
+        .collect();
+
+    message
+}
@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use log::{debug, info, warn};
+use serde::{Serialize, Deserialize};
+use mcp_common::tr;
+
+use crate::ai::router::{get_model_router, LimitedReason, NetworkStatus};
+use crate::utils::events::{notify, Notification, NotificationAction, NotificationLevel};
+
+/// URL that returns HTTP 204 with an empty body when internet access is unrestricted
+pub const CAPTIVE_PORTAL_PROBE_URL: &str = "http://connectivitycheck.gstatic.com/generate_204";
+
+/// Host resolved to distinguish DNS-only connectivity
+const DNS_PROBE_HOST: &str = "api.anthropic.com:443";
+
+/// Default endpoints probed for connectivity (host:port)
+pub fn default_endpoints() -> Vec<String> {
+    vec![
+        "api.anthropic.com:443".to_string(),
+        "1.1.1.1:443".to_string(),
+        "8.8.8.8:53".to_string(),
+    ]
+}
+
+/// Connectivity probe configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeConfig {
+    /// Endpoints to try, as host:port
+    pub endpoints: Vec<String>,
+    /// Timeout for each TCP connect attempt (in milliseconds)
+    pub connect_timeout_ms: u64,
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: default_endpoints(),
+            connect_timeout_ms: 2000,
+        }
+    }
+}
+
+/// Checks connectivity by opening TCP connections, without external binaries
+pub struct ConnectivityProbe {
+    config: ProbeConfig,
+}
+
+impl ConnectivityProbe {
+    /// Create a new probe
+    pub fn new(config: ProbeConfig) -> Self {
+        Self { config }
+    }
+
+    /// Returns true if any endpoint accepts a TCP connection.
+    ///
+    /// Endpoints are tried concurrently so the worst case is a single timeout.
+    pub fn is_online(&self) -> bool {
+        let timeout = Duration::from_millis(self.config.connect_timeout_ms);
+        let (tx, rx) = mpsc::channel();
+
+        for endpoint in self.config.endpoints.clone() {
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                let _ = tx.send(Self::try_connect(&endpoint, timeout));
+            });
+        }
+        drop(tx);
+
+        // Allow for DNS resolution on top of the connect timeout
+        let deadline = timeout * 2;
+        let started = std::time::Instant::now();
+        while let Some(remaining) = deadline.checked_sub(started.elapsed()) {
+            match rx.recv_timeout(remaining) {
+                Ok(true) => return true,
+                Ok(false) => continue,
+                Err(_) => break,
+            }
+        }
+
+        false
+    }
+
+    /// Try a single endpoint
+    fn try_connect(endpoint: &str, timeout: Duration) -> bool {
+        let addrs = match endpoint.to_socket_addrs() {
+            Ok(addrs) => addrs,
+            Err(e) => {
+                debug!("Failed to resolve {}: {}", endpoint, e);
+                return false;
+            }
+        };
+
+        for addr in addrs {
+            if TcpStream::connect_timeout(&addr, timeout).is_ok() {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+/// Result of a detailed network status check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NetworkCheck {
+    /// Detected status
+    pub status: NetworkStatus,
+    /// Captive portal login page, if one redirected the probe
+    pub login_url: Option<String>,
+}
+
+/// Classify the response of the captive portal probe
+fn classify_probe_response(status: u16, location: Option<String>, body_len: usize) -> NetworkCheck {
+    match status {
+        204 => NetworkCheck {
+            status: NetworkStatus::Connected,
+            login_url: None,
+        },
+        // An empty 200 is occasionally returned by transparent proxies
+        200 if body_len == 0 => NetworkCheck {
+            status: NetworkStatus::Connected,
+            login_url: None,
+        },
+        301 | 302 | 303 | 307 | 308 => NetworkCheck {
+            status: NetworkStatus::Limited(LimitedReason::CaptivePortal),
+            login_url: location,
+        },
+        // Any other content means something intercepted the request
+        _ => NetworkCheck {
+            status: NetworkStatus::Limited(LimitedReason::CaptivePortal),
+            login_url: Some(CAPTIVE_PORTAL_PROBE_URL.to_string()),
+        },
+    }
+}
+
+/// Detect captive portals and DNS-only connectivity
+pub async fn detect_network_status(timeout: Duration) -> NetworkCheck {
+    let client = match reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(timeout)
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Failed to build probe client: {}", e);
+            return NetworkCheck {
+                status: NetworkStatus::Unknown,
+                login_url: None,
+            };
+        }
+    };
+
+    match client.get(CAPTIVE_PORTAL_PROBE_URL).send().await {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from);
+            let body_len = response.bytes().await.map(|b| b.len()).unwrap_or(0);
+            classify_probe_response(status, location, body_len)
+        }
+        Err(e) => {
+            debug!("Captive portal probe failed: {}", e);
+
+            // If names still resolve, the network is up but traffic is blocked
+            let resolves = tokio::net::lookup_host(DNS_PROBE_HOST)
+                .await
+                .map(|mut addrs| addrs.next().is_some())
+                .unwrap_or(false);
+
+            NetworkCheck {
+                status: if resolves {
+                    NetworkStatus::Limited(LimitedReason::DnsOnly)
+                } else {
+                    NetworkStatus::Disconnected
+                },
+                login_url: None,
+            }
+        }
+    }
+}
+
+/// Check the network, update the router status, and notify the user about limited connectivity
+pub async fn check_and_report_network_status(timeout: Duration) -> NetworkCheck {
+    let check = detect_network_status(timeout).await;
+    let router = get_model_router();
+    let changed = router.network_status() != check.status;
+    router.set_network_status(check.status);
+
+    if changed {
+        match check.status {
+            NetworkStatus::Limited(LimitedReason::CaptivePortal) => {
+                info!("Captive portal detected");
+                let mut notification = Notification::new(
+                    NotificationLevel::Warning,
+                    tr!("notification-captive-portal-title"),
+                    tr!("notification-captive-portal-body"),
+                );
+                if let Some(url) = &check.login_url {
+                    notification = notification.with_action(NotificationAction::OpenUrl {
+                        label: tr!("notification-captive-portal-action"),
+                        url: url.clone(),
+                    });
+                }
+                notify(notification);
+            }
+            NetworkStatus::Limited(LimitedReason::DnsOnly) => {
+                info!("Limited connectivity: DNS only");
+                notify(Notification::new(
+                    NotificationLevel::Warning,
+                    tr!("notification-dns-only-title"),
+                    tr!("notification-dns-only-body"),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    check
+}
+
+/// Snapshot of network interface state used to detect changes
+type InterfaceFingerprint = BTreeMap<String, String>;
+
+/// Shortest and longest pause between interface scans
+const WATCH_INTERVAL_MIN: Duration = Duration::from_millis(500);
+const WATCH_INTERVAL_MAX: Duration = Duration::from_secs(8);
+
+/// Notifies when the platform's network interfaces change.
+///
+/// On Linux this watches interface state in /sys/class/net, which changes
+/// when links go up/down or interfaces appear. Scans are frequent right
+/// after a change, when links tend to flap, and back off while nothing
+/// happens. Other platforms get no notifications and rely on the periodic
+/// check alone.
+pub struct NetworkChangeWatcher {
+    running: Arc<Mutex<bool>>,
+}
+
+impl NetworkChangeWatcher {
+    /// Start watching; returns the watcher and a receiver signalled on each change
+    pub fn start() -> (Self, Receiver<()>) {
+        let (tx, rx) = mpsc::channel();
+        let running = Arc::new(Mutex::new(true));
+
+        if cfg!(target_os = "linux") {
+            let running_clone = running.clone();
+            std::thread::spawn(move || Self::watch(running_clone, tx));
+        } else {
+            debug!("Network change notifications not available on this platform");
+        }
+
+        (Self { running }, rx)
+    }
+
+    /// Stop watching
+    pub fn stop(&self) {
+        *self.running.lock().unwrap() = false;
+    }
+
+    fn watch(running: Arc<Mutex<bool>>, tx: Sender<()>) {
+        let mut last = Self::fingerprint();
+        let mut pause = WATCH_INTERVAL_MIN;
+
+        while *running.lock().unwrap() {
+            std::thread::sleep(pause);
+
+            let current = Self::fingerprint();
+            if current != last {
+                debug!("Network interfaces changed: {:?}", current);
+                last = current;
+                pause = WATCH_INTERVAL_MIN;
+                if tx.send(()).is_err() {
+                    break;
+                }
+            } else {
+                pause = (pause * 2).min(WATCH_INTERVAL_MAX);
+            }
+        }
+    }
+
+    fn fingerprint() -> InterfaceFingerprint {
+        let mut interfaces = BTreeMap::new();
+
+        let entries = match std::fs::read_dir("/sys/class/net") {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Cannot read network interfaces: {}", e);
+                return interfaces;
+            }
+        };
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == "lo" {
+                continue;
+            }
+            let state = std::fs::read_to_string(entry.path().join("operstate"))
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default();
+            interfaces.insert(name, state);
+        }
+
+        interfaces
+    }
+}
+
+impl Drop for NetworkChangeWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_probe_response() {
+        assert_eq!(classify_probe_response(204, None, 0).status, NetworkStatus::Connected);
+
+        let redirected = classify_probe_response(302, Some("http://login.example".to_string()), 0);
+        assert_eq!(redirected.status, NetworkStatus::Limited(LimitedReason::CaptivePortal));
+        assert_eq!(redirected.login_url.as_deref(), Some("http://login.example"));
+
+        let injected = classify_probe_response(200, None, 512);
+        assert_eq!(injected.status, NetworkStatus::Limited(LimitedReason::CaptivePortal));
+    }
+
+    #[test]
+    fn test_network_check_serializes_status_as_enum() {
+        let check = classify_probe_response(302, Some("http://login.example".to_string()), 0);
+        assert_eq!(
+            serde_json::to_value(&check).unwrap(),
+            serde_json::json!({
+                "status": {"state": "limited", "reason": "captive_portal"},
+                "login_url": "http://login.example",
+            })
+        );
+
+        let connected = classify_probe_response(204, None, 0);
+        assert_eq!(serde_json::to_value(&connected.status).unwrap(), serde_json::json!({"state": "connected"}));
+    }
+
+    #[test]
+    fn test_unresolvable_endpoint_is_offline() {
+        let probe = ConnectivityProbe::new(ProbeConfig {
+            endpoints: vec!["invalid.invalid:443".to_string()],
+            connect_timeout_ms: 200,
+        });
+        assert!(!probe.is_online());
+    }
+
+    #[test]
+    fn test_no_endpoints_is_offline() {
+        let probe = ConnectivityProbe::new(ProbeConfig {
+            endpoints: Vec::new(),
+            connect_timeout_ms: 200,
+        });
+        assert!(!probe.is_online());
+    }
+
+    #[test]
+    fn test_local_listener_is_online() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let probe = ConnectivityProbe::new(ProbeConfig {
+            endpoints: vec![addr.to_string()],
+            connect_timeout_ms: 500,
+        });
+        assert!(probe.is_online());
+    }
+}
@@ -1,5 +1,6 @@
 pub mod llm;
 pub mod checkpointing;
+pub mod connectivity;
 pub mod sync;
 
 use std::sync::{Arc, Mutex};
@@ -9,7 +10,13 @@ use log::{debug, info, warn, error};
 
 use self::llm::LocalLLM;
 use self::checkpointing::CheckpointManager;
+use self::connectivity::{ConnectivityProbe, NetworkChangeWatcher, ProbeConfig};
 use self::sync::{SyncManager, SyncConfig};
+use crate::ai::router::NetworkStatus;
+use mcp_common::platform::fs::{app_dir, AppDir};
+
+/// File in the data directory holding the sync configuration
+const SYNC_CONFIG_FILE: &str = "sync.json";
 
 /// Offline mode status
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -39,6 +46,9 @@ pub struct OfflineConfig {
     pub network_timeout_ms: u64,
     /// Maximum number of checkpoints to keep
     pub max_checkpoints: usize,
+    /// Endpoints (host:port) used to check connectivity
+    #[serde(default = "connectivity::default_endpoints")]
+    pub connectivity_endpoints: Vec<String>,
     /// Sync configuration
     pub sync: SyncConfig,
 }
@@ -52,6 +62,7 @@ impl Default for OfflineConfig {
             connectivity_check_interval: 30,
             network_timeout_ms: 5000,
             max_checkpoints: 10,
+            connectivity_endpoints: connectivity::default_endpoints(),
             sync: SyncConfig::default(),
         }
     }
@@ -65,6 +76,8 @@ pub struct OfflineManager {
     checkpoint_manager: Arc<Mutex<CheckpointManager>>,
     sync_manager: Arc<SyncManager>,
     running: Arc<Mutex<bool>>,
+    /// Connection state reported by a persistent transport, if one is active
+    transport_state: Arc<Mutex<Option<bool>>>,
 }
 
 impl Default for OfflineManager {
@@ -83,6 +96,22 @@ impl OfflineManager {
             checkpoint_manager: Arc::new(Mutex::new(CheckpointManager::new())),
             sync_manager: Arc::new(SyncManager::new()),
             running: Arc::new(Mutex::new(false)),
+            transport_state: Arc::new(Mutex::new(None)),
+        }
+    }
+    
+    /// Create an offline manager whose sync configuration persists across launches
+    pub fn load() -> Self {
+        let sync_manager = SyncManager::with_config_file(app_dir(AppDir::Data).join(SYNC_CONFIG_FILE));
+        let config = OfflineConfig {
+            sync: sync_manager.get_config(),
+            ..OfflineConfig::default()
+        };
+        
+        Self {
+            config: Arc::new(Mutex::new(config)),
+            sync_manager: Arc::new(sync_manager),
+            ..Self::new()
         }
     }
     
@@ -109,14 +138,30 @@ impl OfflineManager {
         let status = self.status.clone();
         let config = self.config.clone();
         let running_clone = self.running.clone();
+        let transport_state = self.transport_state.clone();
+        // Captive portal detection is async; run it on the caller's runtime when there is one
+        let runtime = tokio::runtime::Handle::try_current().ok();
         
         std::thread::spawn(move || {
+            // Re-check immediately when the platform reports a network change
+            let (_watcher, network_changes) = NetworkChangeWatcher::start();
+            
             while *running_clone.lock().unwrap() {
-                // Check network connectivity
-                let is_online = Self::check_network_connectivity();
-                let current_status = { *status.lock().unwrap() };
                 let config_values = { config.lock().unwrap().clone() };
                 
+                // Prefer the persistent transport's connection state; only probe when none is active
+                let reported = { *transport_state.lock().unwrap() };
+                let is_online = reported.unwrap_or_else(|| {
+                    Self::probe(&config_values).is_online()
+                        && runtime.as_ref().map_or(true, |runtime| {
+                            // Endpoints answer behind a captive portal too; only a clean probe counts
+                            let timeout = Duration::from_millis(config_values.network_timeout_ms.min(5000));
+                            let check = runtime.block_on(connectivity::check_and_report_network_status(timeout));
+                            !matches!(check.status, NetworkStatus::Disconnected | NetworkStatus::Limited(_))
+                        })
+                });
+                let current_status = { *status.lock().unwrap() };
+                
                 if config_values.auto_switch {
                     // Automatically switch modes based on connectivity
                     if is_online && current_status == OfflineStatus::Offline {
@@ -160,8 +205,8 @@ impl OfflineManager {
                     }
                 }
                 
-                // Sleep for the configured interval
-                std::thread::sleep(Duration::from_secs(config_values.connectivity_check_interval));
+                // Wait for the configured interval or until the network changes
+                let _ = network_changes.recv_timeout(Duration::from_secs(config_values.connectivity_check_interval));
             }
         });
     }
@@ -175,17 +220,25 @@ impl OfflineManager {
         self.sync_manager.stop();
     }
     
+    /// Report the connection state of a persistent transport.
+    ///
+    /// While a transport reports its state, the manager uses it instead of
+    /// probing the network. Pass `None` when the transport is shut down.
+    pub fn report_transport_state(&self, connected: Option<bool>) {
+        *self.transport_state.lock().unwrap() = connected;
+    }
+    
+    /// Build a connectivity probe from the configuration
+    fn probe(config: &OfflineConfig) -> ConnectivityProbe {
+        ConnectivityProbe::new(ProbeConfig {
+            endpoints: config.connectivity_endpoints.clone(),
+            connect_timeout_ms: config.network_timeout_ms.min(5000),
+        })
+    }
+    
     /// Check network connectivity
-    fn check_network_connectivity() -> bool {
-        // Simple ping to check connectivity
-        let result = std::process::Command::new("ping")
-            .args(&["-c", "1", "-W", "2", "8.8.8.8"])
-            .output();
-        
-        match result {
-            Ok(output) => output.status.success(),
-            Err(_) => false,
-        }
+    fn check_network_connectivity(&self) -> bool {
+        Self::probe(&self.get_config()).is_online()
     }
     
     /// Manually switch to offline mode
@@ -233,7 +286,7 @@ impl OfflineManager {
         }
         
         // Check connectivity
-        if !Self::check_network_connectivity() {
+        if !self.check_network_connectivity() {
             return Err("Network is not available".to_string());
         }
         
@@ -264,7 +317,10 @@ impl OfflineManager {
     
     /// Get offline configuration
     pub fn get_config(&self) -> OfflineConfig {
-        self.config.lock().unwrap().clone()
+        let mut config = self.config.lock().unwrap().clone();
+        // Device and conversation changes go to the sync manager directly
+        config.sync = self.sync_manager.get_config();
+        config
     }
     
     /// Update offline configuration
@@ -292,6 +348,14 @@ impl OfflineManager {
     }
 }
 
+/// Global offline manager instance
+static OFFLINE_MANAGER: once_cell::sync::OnceCell<Arc<OfflineManager>> = once_cell::sync::OnceCell::new();
+
+/// Get the global offline manager instance
+pub fn get_offline_manager() -> Arc<OfflineManager> {
+    OFFLINE_MANAGER.get_or_init(|| Arc::new(OfflineManager::load())).clone()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,10 +1,19 @@
 use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant, SystemTime};
 use serde::{Serialize, Deserialize};
 use log::{debug, info, warn, error};
 use chrono::{DateTime, Utc};
 
+use mcp_common::config::{get_storage_manager, Draft};
+use mcp_common::events::{get_event_bus, names, Topic};
+use mcp_common::sync::read::{self, get_read_markers, ReadMarker};
+use mcp_common::sync::{get_conflict_queue, ConflictVersion, TEAM_KEY_PREFIX};
+
+use crate::utils::cancellation::CancellationToken;
+
 /// Sync operation type
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum SyncOperationType {
@@ -80,6 +89,59 @@ pub struct SyncStatus {
     pub error: Option<String>,
 }
 
+/// Prefix of sync keys that belong to a conversation (`conversation:<id>` or `conversation:<id>/...`)
+pub const CONVERSATION_KEY_PREFIX: &str = "conversation:";
+
+/// Which data a device may sync
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeviceSyncScope {
+    /// Everything that is not local-only
+    All,
+    /// Only the listed conversations
+    Conversations { ids: HashSet<String> },
+    /// Nothing; the device's sync access was revoked
+    Revoked,
+}
+
+impl DeviceSyncScope {
+    /// Scope of a device the user has not approved yet: no conversations
+    pub fn restricted() -> Self {
+        DeviceSyncScope::Conversations { ids: HashSet::new() }
+    }
+}
+
+/// A device that takes part in sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncDevice {
+    /// Device ID
+    pub device_id: String,
+    /// User-facing name
+    pub name: String,
+    /// What the device may sync
+    pub scope: DeviceSyncScope,
+    /// When changes from the device were last seen
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+impl SyncDevice {
+    /// Create a device that syncs nothing until the user widens its scope
+    pub fn new(device_id: &str) -> Self {
+        Self {
+            device_id: device_id.to_string(),
+            name: device_id.to_string(),
+            scope: DeviceSyncScope::restricted(),
+            last_seen: None,
+        }
+    }
+}
+
+/// Get the conversation a sync key belongs to
+pub fn conversation_id_for_key(key: &str) -> Option<&str> {
+    let rest = key.strip_prefix(CONVERSATION_KEY_PREFIX)?;
+    Some(rest.split('/').next().unwrap_or(rest))
+}
+
 /// Sync configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncConfig {
@@ -97,6 +159,60 @@ pub struct SyncConfig {
     pub sync_on_startup: bool,
     /// Whether to sync on shutdown
     pub sync_on_shutdown: bool,
+    /// Whether conversations sync unless opted out; when false only opted-in conversations sync
+    #[serde(default = "default_sync_by_default")]
+    pub sync_conversations_by_default: bool,
+    /// Conversations explicitly opted in to sync
+    #[serde(default)]
+    pub included_conversations: HashSet<String>,
+    /// Conversations kept local-only
+    #[serde(default)]
+    pub excluded_conversations: HashSet<String>,
+    /// Known devices and their sync scopes
+    #[serde(default)]
+    pub devices: Vec<SyncDevice>,
+}
+
+fn default_sync_by_default() -> bool {
+    true
+}
+
+impl SyncConfig {
+    /// Whether a conversation is allowed to leave this device
+    pub fn is_conversation_synced(&self, conversation_id: &str) -> bool {
+        if self.excluded_conversations.contains(conversation_id) {
+            return false;
+        }
+        self.sync_conversations_by_default || self.included_conversations.contains(conversation_id)
+    }
+    
+    /// Whether a key may be synced by this device
+    pub fn allows_local(&self, key: &str) -> bool {
+        match conversation_id_for_key(key) {
+            Some(conversation_id) => self.is_conversation_synced(conversation_id),
+            None => true,
+        }
+    }
+    
+    /// Whether a change from a remote device may be applied locally;
+    /// changes from unknown devices never are
+    pub fn allows_remote(&self, device_id: &str, key: &str) -> bool {
+        if !self.allows_local(key) {
+            return false;
+        }
+        
+        let Some(device) = self.devices.iter().find(|d| d.device_id == device_id) else {
+            return false;
+        };
+        
+        match &device.scope {
+            DeviceSyncScope::All => true,
+            DeviceSyncScope::Conversations { ids } => conversation_id_for_key(key)
+                .map(|id| ids.contains(id))
+                .unwrap_or(false),
+            DeviceSyncScope::Revoked => false,
+        }
+    }
 }
 
 impl Default for SyncConfig {
@@ -109,6 +225,10 @@ impl Default for SyncConfig {
             default_resolution: SyncResolutionStrategy::UseRemote,
             sync_on_startup: true,
             sync_on_shutdown: true,
+            sync_conversations_by_default: true,
+            included_conversations: HashSet::new(),
+            excluded_conversations: HashSet::new(),
+            devices: Vec::new(),
         }
     }
 }
@@ -134,6 +254,10 @@ pub struct SyncManager {
     pending_operations: Arc<Mutex<Vec<SyncOperation>>>,
     resolved_conflicts: Arc<Mutex<HashMap<String, SyncConflict>>>,
     running: Arc<Mutex<bool>>,
+    /// Canceled when the manager stops, interrupting the sync loop
+    shutdown: Arc<Mutex<CancellationToken>>,
+    /// File the sync configuration is saved to, if it persists
+    config_path: Option<PathBuf>,
 }
 
 impl Default for SyncManager {
@@ -159,6 +283,28 @@ impl SyncManager {
             pending_operations: Arc::new(Mutex::new(Vec::new())),
             resolved_conflicts: Arc::new(Mutex::new(HashMap::new())),
             running: Arc::new(Mutex::new(false)),
+            shutdown: Arc::new(Mutex::new(CancellationToken::new())),
+            config_path: None,
+        }
+    }
+    
+    /// Create a sync manager whose configuration (device ID, conversation
+    /// opt-outs and device scopes) is loaded from and saved to a file
+    pub fn with_config_file(path: PathBuf) -> Self {
+        let config = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable sync config {}: {}", path.display(), e);
+                SyncConfig::default()
+            }),
+            Err(_) => SyncConfig::default(),
+        };
+        // Saved right away so the device ID stays the same across launches
+        save_config(Some(&path), &config);
+        
+        Self {
+            config: Arc::new(Mutex::new(config)),
+            config_path: Some(path),
+            ..Self::new()
         }
     }
     
@@ -170,11 +316,15 @@ impl SyncManager {
         }
         *running = true;
         
+        let shutdown = CancellationToken::new();
+        *self.shutdown.lock().unwrap() = shutdown.clone();
+        
         let config = self.config.clone();
         let status = self.status.clone();
         let pending_operations = self.pending_operations.clone();
         let resolved_conflicts = self.resolved_conflicts.clone();
         let running_clone = self.running.clone();
+        let config_path = self.config_path.clone();
         
         // Start background sync task
         std::thread::spawn(move || {
@@ -183,11 +333,14 @@ impl SyncManager {
                 let cfg = config.lock().unwrap();
                 if cfg.enabled && cfg.sync_on_startup {
                     drop(cfg);
+                    let token = shutdown.child_token();
                     let _ = Self::perform_sync(
                         &config,
                         &status,
                         &pending_operations,
                         &resolved_conflicts,
+                        config_path.as_deref(),
+                        &token,
                     );
                 }
             }
@@ -199,11 +352,14 @@ impl SyncManager {
                     Duration::from_secs(cfg.interval_seconds)
                 };
                 
-                // Sleep for the configured interval
-                std::thread::sleep(interval);
+                // Sleep for the configured interval, waking early on shutdown
+                let wake_at = Instant::now() + interval;
+                while Instant::now() < wake_at && !shutdown.is_cancelled() {
+                    std::thread::sleep(Duration::from_millis(200).min(wake_at - Instant::now()));
+                }
                 
                 // Check if we're still running
-                if !*running_clone.lock().unwrap() {
+                if shutdown.is_cancelled() || !*running_clone.lock().unwrap() {
                     break;
                 }
                 
@@ -214,11 +370,14 @@ impl SyncManager {
                 };
                 
                 if should_sync {
+                    let token = shutdown.child_token();
                     let _ = Self::perform_sync(
                         &config,
                         &status,
                         &pending_operations,
                         &resolved_conflicts,
+                        config_path.as_deref(),
+                        &token,
                     );
                 }
             }
@@ -228,11 +387,14 @@ impl SyncManager {
                 let cfg = config.lock().unwrap();
                 if cfg.enabled && cfg.sync_on_shutdown {
                     drop(cfg);
+                    let token = CancellationToken::new();
                     let _ = Self::perform_sync(
                         &config,
                         &status,
                         &pending_operations,
                         &resolved_conflicts,
+                        config_path.as_deref(),
+                        &token,
                     );
                 }
             }
@@ -243,6 +405,17 @@ impl SyncManager {
     pub fn stop(&self) {
         let mut running = self.running.lock().unwrap();
         *running = false;
+        self.shutdown.lock().unwrap().cancel();
+    }
+    
+    /// Abort a sync in progress, leaving pending operations for the next attempt
+    fn abort_sync(status: &Arc<Mutex<SyncStatus>>) -> Result<SyncResult, String> {
+        info!("Sync cancelled");
+        let mut stat = status.lock().unwrap();
+        stat.syncing = false;
+        stat.progress = 0.0;
+        stat.error = Some("Sync cancelled".to_string());
+        Err("Sync cancelled".to_string())
     }
     
     /// Perform a synchronization
@@ -251,6 +424,8 @@ impl SyncManager {
         status: &Arc<Mutex<SyncStatus>>,
         pending_operations: &Arc<Mutex<Vec<SyncOperation>>>,
         resolved_conflicts: &Arc<Mutex<HashMap<String, SyncConflict>>>,
+        config_path: Option<&Path>,
+        cancel: &CancellationToken,
     ) -> Result<SyncResult, String> {
         // Check if sync is enabled
         {
@@ -273,16 +448,37 @@ impl SyncManager {
             stat.error = None;
         }
         
-        // Collect local changes
-        let local_changes = {
+        // Apply conflicts the user resolved since the last sync as local changes;
+        // team workspace conflicts are applied by the team sync
+        let device_id = config.lock().unwrap().device_id.clone();
+        for resolved in get_conflict_queue().take_resolved_where(|key| !key.starts_with(TEAM_KEY_PREFIX)) {
+            pending_operations.lock().unwrap().push(SyncOperation {
+                operation_type: if resolved.value.is_some() {
+                    SyncOperationType::Update
+                } else {
+                    SyncOperationType::Delete
+                },
+                key: resolved.key,
+                value: resolved.value,
+                timestamp: Utc::now(),
+                device_id: device_id.clone(),
+                operation_id: generate_operation_id(),
+            });
+        }
+        
+        // Collect local changes, leaving out local-only conversations
+        let (local_changes, local_deletions) = {
+            let cfg = config.lock().unwrap().clone();
             let operations = pending_operations.lock().unwrap();
             
             let mut changes = HashMap::new();
-            for op in operations.iter() {
+            let mut deletions = HashSet::new();
+            for op in operations.iter().filter(|op| cfg.allows_local(&op.key)) {
+                track_deletion(&mut deletions, op);
                 changes.insert(op.key.clone(), op.value.clone().unwrap_or_default());
             }
             
-            changes
+            (changes, deletions)
         };
         
         // Update status
@@ -292,8 +488,42 @@ impl SyncManager {
             stat.progress = 0.2;
         }
         
-        // Simulate getting remote changes
-        let remote_changes = generate_mock_remote_changes(&local_changes);
+        if cancel.is_cancelled() {
+            return Self::abort_sync(status);
+        }
+        
+        // Simulate getting remote changes, dropping those outside each device's scope
+        let (remote_changes, remote_deletions) = {
+            let mut cfg = config.lock().unwrap();
+            let now = Utc::now();
+            let mut changes = HashMap::new();
+            let mut deletions = HashSet::new();
+            
+            for op in generate_mock_remote_changes(&local_changes) {
+                match cfg.devices.iter_mut().find(|d| d.device_id == op.device_id) {
+                    Some(device) => device.last_seen = Some(now),
+                    None => {
+                        let mut device = SyncDevice::new(&op.device_id);
+                        device.last_seen = Some(now);
+                        cfg.devices.push(device);
+                    }
+                }
+                
+                if cfg.allows_remote(&op.device_id, &op.key) {
+                    track_deletion(&mut deletions, &op);
+                    changes.insert(op.key, op.value.unwrap_or_default());
+                } else {
+                    debug!("Ignoring change to '{}' from device {} outside its sync scope", op.key, op.device_id);
+                }
+            }
+            
+            save_config(config_path, &cfg);
+            (changes, deletions)
+        };
+        
+        // Read markers and drafts never conflict: the marker furthest into a
+        // conversation and the newest draft win
+        let remote_changes = apply_drafts(apply_read_markers(remote_changes));
         
         // Update status
         {
@@ -302,6 +532,10 @@ impl SyncManager {
             stat.progress = 0.4;
         }
         
+        if cancel.is_cancelled() {
+            return Self::abort_sync(status);
+        }
+        
         // Perform sync (merging local and remote changes)
         let result = Self::sync(local_changes, remote_changes);
         
@@ -312,14 +546,56 @@ impl SyncManager {
             stat.progress = 0.8;
         }
         
-        // Store resolved conflicts
+        // Store resolved conflicts; queue the ones that can't be merged for review
+        let mut queued = 0;
         {
             let mut conflicts = resolved_conflicts.lock().unwrap();
             for conflict in &result.conflicts {
-                conflicts.insert(conflict.key.clone(), conflict.clone());
+                let mut conflict = conflict.clone();
+                if local_deletions.contains(&conflict.key) {
+                    mark_deleted(&mut conflict.local_operation);
+                }
+                if remote_deletions.contains(&conflict.key) {
+                    mark_deleted(&mut conflict.remote_operation);
+                }
+                
+                match auto_merge(
+                    conflict.local_operation.value.as_deref(),
+                    conflict.remote_operation.value.as_deref(),
+                ) {
+                    Some(merged) => {
+                        conflict.resolution = SyncResolutionStrategy::Merge;
+                        conflict.resolved_value = Some(merged);
+                    }
+                    None => {
+                        let version = |op: &SyncOperation| ConflictVersion {
+                            value: op.value.clone(),
+                            device_id: op.device_id.clone(),
+                            timestamp: op.timestamp,
+                        };
+                        match get_conflict_queue().enqueue(
+                            &conflict.key,
+                            version(&conflict.local_operation),
+                            version(&conflict.remote_operation),
+                        ) {
+                            Ok(_) => {
+                                conflict.resolution = SyncResolutionStrategy::Manual;
+                                conflict.resolved_value = None;
+                                queued += 1;
+                            }
+                            Err(e) => error!("Failed to queue conflict for '{}': {}", conflict.key, e),
+                        }
+                    }
+                }
+                
+                conflicts.insert(conflict.key.clone(), conflict);
             }
         }
         
+        if queued > 0 {
+            info!("{} sync conflict(s) need review", queued);
+        }
+        
         // Clear pending operations if sync was successful
         if result.success {
             let mut operations = pending_operations.lock().unwrap();
@@ -447,11 +723,17 @@ impl SyncManager {
     
     /// Update sync configuration
     pub fn update_config(&self, config: SyncConfig) {
+        save_config(self.config_path.as_deref(), &config);
         *self.config.lock().unwrap() = config;
     }
     
     /// Add a pending operation
     pub fn add_operation(&self, operation: SyncOperation) {
+        if !self.config.lock().unwrap().allows_local(&operation.key) {
+            debug!("Not queuing '{}' for sync: conversation is local-only", operation.key);
+            return;
+        }
+        
         let mut operations = self.pending_operations.lock().unwrap();
         operations.push(operation);
         
@@ -460,6 +742,96 @@ impl SyncManager {
         status.local_changes = operations.len();
     }
     
+    /// Queue a read marker so other devices see the conversation as read
+    pub fn add_read_marker(&self, marker: &ReadMarker) {
+        match serde_json::to_string(marker) {
+            Ok(value) => self.replace_operation(marker.sync_key(), value, marker.read_at),
+            Err(e) => error!("Failed to serialize read marker for {}: {}", marker.conversation_id, e),
+        }
+    }
+    
+    /// Queue a draft, or a cleared one with blank text, for other devices
+    pub fn add_draft(&self, draft: &Draft) {
+        match serde_json::to_string(draft) {
+            Ok(value) => self.replace_operation(draft.sync_key(), value, draft.updated_at),
+            Err(e) => error!("Failed to serialize draft for {}: {}", draft.conversation_id, e),
+        }
+    }
+    
+    /// Queue an update that supersedes any queued one of the same key; only
+    /// the latest value needs to travel
+    fn replace_operation(&self, key: String, value: String, timestamp: DateTime<Utc>) {
+        self.pending_operations.lock().unwrap().retain(|op| op.key != key);
+        let device_id = self.config.lock().unwrap().device_id.clone();
+        self.add_operation(SyncOperation {
+            operation_type: SyncOperationType::Update,
+            key,
+            value: Some(value),
+            timestamp,
+            device_id,
+            operation_id: generate_operation_id(),
+        });
+    }
+    
+    /// Opt a conversation in to or out of sync
+    pub fn set_conversation_sync(&self, conversation_id: &str, enabled: bool) {
+        let mut config = self.config.lock().unwrap();
+        if enabled {
+            config.excluded_conversations.remove(conversation_id);
+            config.included_conversations.insert(conversation_id.to_string());
+        } else {
+            config.included_conversations.remove(conversation_id);
+            config.excluded_conversations.insert(conversation_id.to_string());
+        }
+        save_config(self.config_path.as_deref(), &config);
+        drop(config);
+        
+        // Drop already-queued changes of a conversation that became local-only
+        if !enabled {
+            let mut operations = self.pending_operations.lock().unwrap();
+            operations.retain(|op| conversation_id_for_key(&op.key) != Some(conversation_id));
+            self.status.lock().unwrap().local_changes = operations.len();
+        }
+    }
+    
+    /// Whether a conversation syncs
+    pub fn is_conversation_synced(&self, conversation_id: &str) -> bool {
+        self.config.lock().unwrap().is_conversation_synced(conversation_id)
+    }
+    
+    /// List known devices
+    pub fn list_devices(&self) -> Vec<SyncDevice> {
+        self.config.lock().unwrap().devices.clone()
+    }
+    
+    /// Give a device a user-facing name
+    pub fn rename_device(&self, device_id: &str, name: &str) -> Result<(), String> {
+        self.update_device(device_id, |device| device.name = name.to_string())
+    }
+    
+    /// Set what a device may sync
+    pub fn set_device_scope(&self, device_id: &str, scope: DeviceSyncScope) -> Result<(), String> {
+        self.update_device(device_id, |device| device.scope = scope)
+    }
+    
+    /// Revoke a device's sync access; its changes are ignored from now on
+    pub fn revoke_device(&self, device_id: &str) -> Result<(), String> {
+        info!("Revoking sync access for device {}", device_id);
+        self.set_device_scope(device_id, DeviceSyncScope::Revoked)
+    }
+    
+    fn update_device<F: FnOnce(&mut SyncDevice)>(&self, device_id: &str, update: F) -> Result<(), String> {
+        let mut config = self.config.lock().unwrap();
+        match config.devices.iter_mut().find(|d| d.device_id == device_id) {
+            Some(device) => {
+                update(device);
+                save_config(self.config_path.as_deref(), &config);
+                Ok(())
+            }
+            None => Err(format!("Device '{}' not found", device_id)),
+        }
+    }
+    
     /// Get all pending operations
     pub fn get_pending_operations(&self) -> Vec<SyncOperation> {
         self.pending_operations.lock().unwrap().clone()
@@ -483,23 +855,118 @@ impl SyncManager {
         }
     }
     
-    /// Manual sync
-    pub fn manual_sync(&self) -> Result<SyncResult, String> {
+    /// Manual sync, stopping early when the token is canceled
+    pub fn manual_sync(&self, cancel: &CancellationToken) -> Result<SyncResult, String> {
         Self::perform_sync(
             &self.config,
             &self.status,
             &self.pending_operations,
             &self.resolved_conflicts,
+            self.config_path.as_deref(),
+            cancel,
         )
     }
 }
 
+/// Merge two edits when one only appends to the other.
+///
+/// Returns None when the edits diverge and need manual review. A deletion
+/// (None) never merges with an edit.
+fn auto_merge(local: Option<&str>, remote: Option<&str>) -> Option<String> {
+    let (local, remote) = (local?, remote?);
+    if local.starts_with(remote) {
+        Some(local.to_string())
+    } else if remote.starts_with(local) {
+        Some(remote.to_string())
+    } else {
+        None
+    }
+}
+
+/// Record whether an operation leaves its key deleted; later operations win
+fn track_deletion(deletions: &mut HashSet<String>, op: &SyncOperation) {
+    if op.value.is_none() {
+        deletions.insert(op.key.clone());
+    } else {
+        deletions.remove(&op.key);
+    }
+}
+
+/// Turn a conflict side back into the deletion it came from
+fn mark_deleted(op: &mut SyncOperation) {
+    op.operation_type = SyncOperationType::Delete;
+    op.value = None;
+}
+
+/// Apply read markers among remote changes, returning the other changes
+fn apply_read_markers(changes: HashMap<String, String>) -> HashMap<String, String> {
+    let markers = get_read_markers();
+    let mut rest = HashMap::new();
+    
+    for (key, value) in changes {
+        if read::conversation_for_key(&key).is_none() {
+            rest.insert(key, value);
+            continue;
+        }
+        match serde_json::from_str::<ReadMarker>(&value) {
+            Ok(marker) => {
+                if let Err(e) = markers.apply(marker) {
+                    error!("Failed to apply read marker '{}': {}", key, e);
+                }
+            }
+            Err(e) => warn!("Ignoring malformed read marker '{}': {}", key, e),
+        }
+    }
+    
+    rest
+}
+
+/// Apply drafts among remote changes, returning the other changes
+fn apply_drafts(changes: HashMap<String, String>) -> HashMap<String, String> {
+    let storage = get_storage_manager();
+    let mut rest = HashMap::new();
+    
+    for (key, value) in changes {
+        if !Draft::is_sync_key(&key) {
+            rest.insert(key, value);
+            continue;
+        }
+        match serde_json::from_str::<Draft>(&value) {
+            Ok(draft) => match storage.apply_draft(&draft) {
+                Ok(true) => get_event_bus().emit(
+                    Topic::Conversation,
+                    names::DRAFT_CHANGED,
+                    serde_json::json!({ "conversation_id": draft.conversation_id }),
+                ),
+                Ok(false) => {}
+                Err(e) => error!("Failed to apply draft '{}': {}", key, e),
+            },
+            Err(e) => warn!("Ignoring malformed draft '{}': {}", key, e),
+        }
+    }
+    
+    rest
+}
+
 /// Generate a unique device ID
 fn generate_device_id() -> String {
     use uuid::Uuid;
     Uuid::new_v4().to_string()
 }
 
+/// Save the sync configuration when it persists
+fn save_config(path: Option<&Path>, config: &SyncConfig) {
+    let Some(path) = path else {
+        return;
+    };
+    let result = serde_json::to_string_pretty(config)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        error!("Failed to save sync config to {}: {}", path.display(), e);
+    }
+}
+
 /// Generate a unique operation ID
 fn generate_operation_id() -> String {
     use uuid::Uuid;
@@ -507,7 +974,7 @@ fn generate_operation_id() -> String {
 }
 
 /// Generate mock remote changes for testing
-fn generate_mock_remote_changes(local_changes: &HashMap<String, String>) -> HashMap<String, String> {
+fn generate_mock_remote_changes(local_changes: &HashMap<String, String>) -> Vec<SyncOperation> {
     let mut remote_changes = HashMap::new();
     
     // Copy some local changes to simulate same changes
@@ -533,6 +1000,17 @@ fn generate_mock_remote_changes(local_changes: &HashMap<String, String>) -> Hash
     }
     
     remote_changes
+        .into_iter()
+        .enumerate()
+        .map(|(i, (key, value))| SyncOperation {
+            operation_type: SyncOperationType::Update,
+            key,
+            value: Some(value),
+            timestamp: Utc::now(),
+            device_id: format!("remote-device-{}", i % 2),
+            operation_id: generate_operation_id(),
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -609,4 +1087,163 @@ mod tests {
         assert_eq!(result.remote_applied, 1); // key3
         assert_eq!(result.conflicts.len(), 0);
     }
+    
+    #[test]
+    fn test_auto_merge() {
+        assert_eq!(auto_merge(Some("hello world"), Some("hello")), Some("hello world".to_string()));
+        assert_eq!(auto_merge(Some("hello"), Some("hello there")), Some("hello there".to_string()));
+        assert_eq!(auto_merge(Some("hello world"), Some("hello there")), None);
+    }
+    
+    #[test]
+    fn test_deletion_conflicts_with_edit() {
+        // An empty string is a prefix of everything; a deletion must not merge like one
+        assert_eq!(auto_merge(None, Some("hello")), None);
+        assert_eq!(auto_merge(Some("hello"), None), None);
+        assert_eq!(auto_merge(Some(""), Some("hello")), Some("hello".to_string()));
+        
+        let update = |value: Option<&str>| SyncOperation {
+            operation_type: SyncOperationType::Update,
+            key: "conversation:work".to_string(),
+            value: value.map(String::from),
+            timestamp: Utc::now(),
+            device_id: "laptop".to_string(),
+            operation_id: generate_operation_id(),
+        };
+        let mut deletions = HashSet::new();
+        track_deletion(&mut deletions, &update(None));
+        assert!(deletions.contains("conversation:work"));
+        track_deletion(&mut deletions, &update(Some("restored")));
+        assert!(deletions.is_empty());
+        
+        let mut op = update(Some(""));
+        mark_deleted(&mut op);
+        assert!(matches!(op.operation_type, SyncOperationType::Delete));
+        assert_eq!(op.value, None);
+    }
+    
+    #[test]
+    fn test_local_only_conversations_are_not_synced() {
+        let manager = SyncManager::new();
+        manager.set_conversation_sync("secret", false);
+        
+        manager.add_operation(SyncOperation {
+            operation_type: SyncOperationType::Update,
+            key: "conversation:secret/messages".to_string(),
+            value: Some("hello".to_string()),
+            timestamp: Utc::now(),
+            device_id: "local".to_string(),
+            operation_id: generate_operation_id(),
+        });
+        
+        assert!(manager.get_pending_operations().is_empty());
+        assert!(!manager.is_conversation_synced("secret"));
+        assert!(manager.is_conversation_synced("other"));
+    }
+    
+    #[test]
+    fn test_read_markers_are_not_synced_as_changes() {
+        let mut changes = HashMap::new();
+        changes.insert("conversation:abc/read".to_string(), "not a marker".to_string());
+        changes.insert("conversation:abc/messages".to_string(), "hello".to_string());
+        
+        let rest = apply_read_markers(changes);
+        assert_eq!(rest.len(), 1);
+        assert!(rest.contains_key("conversation:abc/messages"));
+    }
+    
+    #[test]
+    fn test_read_markers_follow_conversation_sync() {
+        let manager = SyncManager::new();
+        manager.set_conversation_sync("secret", false);
+        
+        let marker = |conversation_id: &str, position| ReadMarker {
+            conversation_id: conversation_id.to_string(),
+            message_id: format!("m{}", position),
+            position,
+            read_at: Utc::now(),
+        };
+        manager.add_read_marker(&marker("secret", 1));
+        manager.add_read_marker(&marker("work", 1));
+        manager.add_read_marker(&marker("work", 3));
+        
+        let pending = manager.get_pending_operations();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].key, "conversation:work/read");
+        assert!(pending[0].value.as_deref().unwrap().contains("\"m3\""));
+    }
+    
+    #[test]
+    fn test_device_scopes() {
+        let mut config = SyncConfig::default();
+        config.devices.push(SyncDevice {
+            scope: DeviceSyncScope::All,
+            ..SyncDevice::new("phone")
+        });
+        config.devices.push(SyncDevice::new("new-phone"));
+        config.devices.push(SyncDevice {
+            scope: DeviceSyncScope::Conversations { ids: HashSet::from(["work".to_string()]) },
+            ..SyncDevice::new("tablet")
+        });
+        config.devices.push(SyncDevice {
+            scope: DeviceSyncScope::Revoked,
+            ..SyncDevice::new("old-laptop")
+        });
+        
+        assert!(config.allows_remote("phone", "conversation:home"));
+        assert!(config.allows_remote("tablet", "conversation:work/messages"));
+        assert!(!config.allows_remote("tablet", "conversation:home"));
+        assert!(!config.allows_remote("old-laptop", "conversation:work"));
+        
+        // Devices the user has not approved, or never saw, sync nothing
+        assert!(!config.allows_remote("new-phone", "conversation:home"));
+        assert!(!config.allows_remote("stranger", "conversation:home"));
+    }
+    
+    #[test]
+    fn test_sync_config_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sync.json");
+        
+        let manager = SyncManager::with_config_file(path.clone());
+        let device_id = manager.get_config().device_id;
+        let mut config = manager.get_config();
+        config.devices.push(SyncDevice {
+            scope: DeviceSyncScope::All,
+            ..SyncDevice::new("phone")
+        });
+        manager.update_config(config);
+        manager.set_conversation_sync("secret", false);
+        manager.revoke_device("phone").unwrap();
+        
+        // A restart sees the same device ID, opt-outs and revocations
+        let reloaded = SyncManager::with_config_file(path);
+        let config = reloaded.get_config();
+        assert_eq!(config.device_id, device_id);
+        assert!(!config.is_conversation_synced("secret"));
+        assert_eq!(config.devices[0].scope, DeviceSyncScope::Revoked);
+        assert!(!config.allows_remote("phone", "conversation:home"));
+    }
+    
+    #[test]
+    fn test_cancelled_manual_sync_keeps_pending_operations() {
+        let manager = SyncManager::new();
+        manager.add_operation(SyncOperation {
+            operation_type: SyncOperationType::Update,
+            key: "conversation:work".to_string(),
+            value: Some("draft".to_string()),
+            timestamp: Utc::now(),
+            device_id: "laptop".to_string(),
+            operation_id: generate_operation_id(),
+        });
+        
+        let token = CancellationToken::new();
+        token.cancel();
+        assert!(manager.manual_sync(&token).is_err());
+        
+        let status = manager.get_status();
+        assert!(!status.syncing);
+        assert_eq!(status.error.as_deref(), Some("Sync cancelled"));
+        assert_eq!(manager.get_pending_operations().len(), 1);
+    }
 }
@@ -1,8 +1,17 @@
 mod memory;
 mod cache;
+mod pressure;
+mod threads;
 
 pub use memory::{MemoryManager, MemoryLimits, MemoryStats};
 pub use cache::{Cache, CacheConfig, CacheStats};
+pub use pressure::{
+    get_pressure_monitor, MemoryPressureMonitor, MemoryUsage, PressureConfig, PressureLevel, PressureStatus,
+};
+pub use threads::{
+    get_thread_config, get_thread_pool_size, get_thread_settings, update_thread_config,
+    CpuTopology, ThreadConfig, ThreadSettings,
+};
 
 use log::{info, debug, warn};
 use std::sync::{Arc, Mutex};
@@ -13,6 +22,7 @@ pub struct OptimizationManager {
     memory_manager: Arc<MemoryManager>,
     api_cache: Arc<Cache<String, String>>,
     resource_cache: Arc<Cache<String, Vec<u8>>>,
+    pressure_monitor: Arc<MemoryPressureMonitor>,
 }
 
 impl OptimizationManager {
@@ -43,10 +53,14 @@ impl OptimizationManager {
         };
         let resource_cache = Arc::new(Cache::new(resource_cache_config));
         
+        // Local models register with the global memory pressure monitor
+        let pressure_monitor = get_pressure_monitor();
+        
         Self {
             memory_manager,
             api_cache,
             resource_cache,
+            pressure_monitor,
         }
     }
     
@@ -62,6 +76,9 @@ impl OptimizationManager {
         // Register memory optimization handlers
         self.register_memory_optimizations();
         
+        // Start memory pressure monitor
+        self.pressure_monitor.start();
+        
         info!("Optimization manager started");
     }
     
@@ -74,6 +91,9 @@ impl OptimizationManager {
         self.api_cache.stop_cleanup();
         self.resource_cache.stop_cleanup();
         
+        // Stop memory pressure monitor
+        self.pressure_monitor.stop();
+        
         info!("Optimization manager stopped");
     }
     
@@ -102,6 +122,14 @@ impl OptimizationManager {
                 debug!("Resource cache cleared: {} entries removed", stats_before.size - stats_after.size);
             });
         }
+        
+        // Trim caches through the memory manager when pressure is detected
+        {
+            let memory_manager = memory_manager.clone();
+            self.pressure_monitor.set_cache_trimmer(move || {
+                memory_manager.force_gc(false);
+            });
+        }
     }
     
     /// Register window-related optimizations for Tauri
@@ -141,6 +169,11 @@ impl OptimizationManager {
     pub fn resource_cache(&self) -> Arc<Cache<String, Vec<u8>>> {
         self.resource_cache.clone()
     }
+    
+    /// Get the memory pressure monitor
+    pub fn pressure_monitor(&self) -> Arc<MemoryPressureMonitor> {
+        self.pressure_monitor.clone()
+    }
 }
 
 impl Drop for OptimizationManager {
@@ -0,0 +1,431 @@
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
+use std::time::{Duration, Instant};
+use tokio::time::interval;
+
+use crate::utils::events::{events, get_event_system};
+
+lazy_static! {
+    static ref PRESSURE_MONITOR: Arc<MemoryPressureMonitor> =
+        Arc::new(MemoryPressureMonitor::new(PressureConfig::default()));
+}
+
+/// Get the global memory pressure monitor that loaded models register with
+pub fn get_pressure_monitor() -> Arc<MemoryPressureMonitor> {
+    PRESSURE_MONITOR.clone()
+}
+
+/// Memory pressure thresholds and unloading behaviour
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PressureConfig {
+    /// Enable the memory pressure watcher
+    pub enabled: bool,
+    /// System RAM usage (percent) at which pressure is entered
+    pub ram_high_percent: f32,
+    /// System RAM usage (percent) below which pressure is cleared
+    pub ram_low_percent: f32,
+    /// VRAM usage (percent) at which pressure is entered
+    pub vram_high_percent: f32,
+    /// VRAM usage (percent) below which pressure is cleared
+    pub vram_low_percent: f32,
+    /// Minimum idle time in seconds before a model may be unloaded
+    pub idle_unload_secs: u64,
+    /// Interval in seconds between pressure checks
+    pub check_interval_secs: u64,
+    /// Trim caches when pressure is detected
+    pub trim_caches: bool,
+}
+
+impl Default for PressureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ram_high_percent: 90.0,
+            ram_low_percent: 80.0,
+            vram_high_percent: 92.0,
+            vram_low_percent: 80.0,
+            idle_unload_secs: 120,
+            check_interval_secs: 10,
+            trim_caches: true,
+        }
+    }
+}
+
+/// Current memory pressure state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PressureLevel {
+    /// Memory usage is below the low-water marks
+    Normal,
+    /// Memory usage crossed a high-water mark and has not yet recovered
+    High,
+}
+
+/// Snapshot of memory usage used for pressure decisions
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    /// System RAM usage in percent
+    pub ram_percent: f32,
+    /// VRAM usage in percent, if a probe is available
+    pub vram_percent: Option<f32>,
+}
+
+/// Status of the pressure watcher returned to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PressureStatus {
+    /// Current pressure level
+    pub level: PressureLevel,
+    /// Last observed memory usage
+    pub usage: MemoryUsage,
+    /// Models currently registered as loaded
+    pub loaded_models: Vec<String>,
+    /// Models pinned against automatic unloading
+    pub pinned_models: Vec<String>,
+    /// Number of models unloaded due to memory pressure
+    pub unload_count: usize,
+}
+
+/// A loaded model tracked by the watcher
+struct ManagedModel {
+    /// When the model was last used
+    last_used: Instant,
+    /// Whether the model is pinned
+    pinned: bool,
+    /// Callback that unloads the model
+    unload: Box<dyn Fn() -> Result<(), String> + Send + Sync>,
+}
+
+/// VRAM probe returning usage in percent
+type VramProbe = Box<dyn Fn() -> Option<f32> + Send + Sync>;
+
+/// Cache trimming callback
+type CacheTrimmer = Box<dyn Fn() + Send + Sync>;
+
+/// Watches system memory and unloads idle local models under pressure
+pub struct MemoryPressureMonitor {
+    config: Arc<Mutex<PressureConfig>>,
+    level: Arc<Mutex<PressureLevel>>,
+    usage: Arc<Mutex<MemoryUsage>>,
+    models: Arc<Mutex<HashMap<String, ManagedModel>>>,
+    vram_probe: Arc<Mutex<Option<VramProbe>>>,
+    cache_trimmer: Arc<Mutex<Option<CacheTrimmer>>>,
+    unload_count: Arc<Mutex<usize>>,
+    running: Arc<AtomicBool>,
+}
+
+impl MemoryPressureMonitor {
+    /// Create a new memory pressure monitor
+    pub fn new(config: PressureConfig) -> Self {
+        Self {
+            config: Arc::new(Mutex::new(config)),
+            level: Arc::new(Mutex::new(PressureLevel::Normal)),
+            usage: Arc::new(Mutex::new(MemoryUsage::default())),
+            models: Arc::new(Mutex::new(HashMap::new())),
+            vram_probe: Arc::new(Mutex::new(None)),
+            cache_trimmer: Arc::new(Mutex::new(None)),
+            unload_count: Arc::new(Mutex::new(0)),
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Start the background watcher
+    pub fn start(&self) {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let monitor = self.clone();
+        tokio::spawn(async move {
+            let period = monitor.config.lock().unwrap().check_interval_secs.max(1);
+            let mut check_interval = interval(Duration::from_secs(period));
+
+            while monitor.running.load(Ordering::SeqCst) {
+                check_interval.tick().await;
+
+                if !monitor.config.lock().unwrap().enabled {
+                    continue;
+                }
+
+                let usage = monitor.sample_usage();
+                monitor.evaluate(usage);
+            }
+        });
+
+        info!("Memory pressure monitor started");
+    }
+
+    /// Stop the background watcher
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+
+    /// Register a loaded model along with the callback used to unload it
+    pub fn register_model<F>(&self, model_id: &str, unload: F)
+    where
+        F: Fn() -> Result<(), String> + Send + Sync + 'static,
+    {
+        let mut models = self.models.lock().unwrap();
+        let pinned = models.get(model_id).map(|m| m.pinned).unwrap_or(false);
+        models.insert(model_id.to_string(), ManagedModel {
+            last_used: Instant::now(),
+            pinned,
+            unload: Box::new(unload),
+        });
+    }
+
+    /// Remove a model that was unloaded by other means
+    pub fn unregister_model(&self, model_id: &str) {
+        self.models.lock().unwrap().remove(model_id);
+    }
+
+    /// Mark a model as recently used
+    pub fn touch_model(&self, model_id: &str) {
+        if let Some(model) = self.models.lock().unwrap().get_mut(model_id) {
+            model.last_used = Instant::now();
+        }
+    }
+
+    /// Pin or unpin a model against automatic unloading
+    pub fn set_pinned(&self, model_id: &str, pinned: bool) -> Result<(), String> {
+        let mut models = self.models.lock().unwrap();
+        match models.get_mut(model_id) {
+            Some(model) => {
+                model.pinned = pinned;
+                Ok(())
+            }
+            None => Err(format!("Model {} is not loaded", model_id)),
+        }
+    }
+
+    /// Set the probe used to read VRAM usage
+    pub fn set_vram_probe<F>(&self, probe: F)
+    where
+        F: Fn() -> Option<f32> + Send + Sync + 'static,
+    {
+        *self.vram_probe.lock().unwrap() = Some(Box::new(probe));
+    }
+
+    /// Set the callback used to trim caches under pressure
+    pub fn set_cache_trimmer<F>(&self, trimmer: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        *self.cache_trimmer.lock().unwrap() = Some(Box::new(trimmer));
+    }
+
+    /// Get the current configuration
+    pub fn get_config(&self) -> PressureConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    /// Update the configuration
+    pub fn update_config(&self, config: PressureConfig) -> Result<(), String> {
+        if config.ram_low_percent >= config.ram_high_percent
+            || config.vram_low_percent >= config.vram_high_percent
+        {
+            return Err("Low-water marks must be below high-water marks".to_string());
+        }
+
+        *self.config.lock().unwrap() = config;
+        Ok(())
+    }
+
+    /// Get the current watcher status
+    pub fn get_status(&self) -> PressureStatus {
+        let models = self.models.lock().unwrap();
+        let mut loaded_models: Vec<String> = models.keys().cloned().collect();
+        loaded_models.sort();
+        let mut pinned_models: Vec<String> = models
+            .iter()
+            .filter(|(_, m)| m.pinned)
+            .map(|(id, _)| id.clone())
+            .collect();
+        pinned_models.sort();
+
+        PressureStatus {
+            level: *self.level.lock().unwrap(),
+            usage: *self.usage.lock().unwrap(),
+            loaded_models,
+            pinned_models,
+            unload_count: *self.unload_count.lock().unwrap(),
+        }
+    }
+
+    /// Read current memory usage
+    fn sample_usage(&self) -> MemoryUsage {
+        let ram_percent = match sys_info::mem_info() {
+            Ok(mem) if mem.total > 0 => {
+                let used = mem.total.saturating_sub(mem.avail);
+                used as f32 / mem.total as f32 * 100.0
+            }
+            _ => 0.0,
+        };
+
+        let vram_percent = self.vram_probe.lock().unwrap().as_ref().and_then(|probe| probe());
+
+        MemoryUsage { ram_percent, vram_percent }
+    }
+
+    /// Compute the next pressure level, applying hysteresis
+    fn next_level(config: &PressureConfig, current: PressureLevel, usage: &MemoryUsage) -> PressureLevel {
+        let vram = usage.vram_percent.unwrap_or(0.0);
+
+        match current {
+            PressureLevel::Normal => {
+                if usage.ram_percent >= config.ram_high_percent || vram >= config.vram_high_percent {
+                    PressureLevel::High
+                } else {
+                    PressureLevel::Normal
+                }
+            }
+            PressureLevel::High => {
+                if usage.ram_percent <= config.ram_low_percent && vram <= config.vram_low_percent {
+                    PressureLevel::Normal
+                } else {
+                    PressureLevel::High
+                }
+            }
+        }
+    }
+
+    /// Evaluate a usage sample and react to pressure changes
+    pub fn evaluate(&self, usage: MemoryUsage) {
+        let config = self.get_config();
+        *self.usage.lock().unwrap() = usage;
+
+        let (previous, next) = {
+            let mut level = self.level.lock().unwrap();
+            let previous = *level;
+            *level = Self::next_level(&config, previous, &usage);
+            (previous, *level)
+        };
+
+        if previous != next {
+            info!("Memory pressure changed: {:?} -> {:?}", previous, next);
+            get_event_system().emit(
+                events::MEMORY_PRESSURE_CHANGED,
+                serde_json::json!({
+                    "level": next,
+                    "ram_percent": usage.ram_percent,
+                    "vram_percent": usage.vram_percent,
+                }),
+            );
+        }
+
+        // Only act on the transition into pressure; staying in High does not
+        // unload further models until the level has recovered once
+        if previous == PressureLevel::Normal && next == PressureLevel::High {
+            self.relieve_pressure(&config);
+        }
+    }
+
+    /// Trim caches and unload idle, unpinned models
+    fn relieve_pressure(&self, config: &PressureConfig) {
+        if config.trim_caches {
+            if let Some(trimmer) = self.cache_trimmer.lock().unwrap().as_ref() {
+                debug!("Trimming caches due to memory pressure");
+                trimmer();
+            }
+        }
+
+        let idle_threshold = Duration::from_secs(config.idle_unload_secs);
+        let mut models = self.models.lock().unwrap();
+
+        let candidates: Vec<String> = models
+            .iter()
+            .filter(|(_, m)| !m.pinned && m.last_used.elapsed() >= idle_threshold)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for model_id in candidates {
+            let result = models.get(&model_id).map(|m| (m.unload)());
+            match result {
+                Some(Ok(())) => {
+                    models.remove(&model_id);
+                    *self.unload_count.lock().unwrap() += 1;
+                    info!("Unloaded idle model {} to free memory", model_id);
+                    get_event_system().emit(
+                        events::MODEL_UNLOADED,
+                        serde_json::json!({
+                            "model_id": model_id,
+                            "reason": "memory_pressure",
+                        }),
+                    );
+                }
+                Some(Err(e)) => warn!("Failed to unload model {}: {}", model_id, e),
+                None => {}
+            }
+        }
+    }
+}
+
+impl Clone for MemoryPressureMonitor {
+    fn clone(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            level: self.level.clone(),
+            usage: self.usage.clone(),
+            models: self.models.clone(),
+            vram_probe: self.vram_probe.clone(),
+            cache_trimmer: self.cache_trimmer.clone(),
+            unload_count: self.unload_count.clone(),
+            running: self.running.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pressure_hysteresis() {
+        let config = PressureConfig::default();
+        let usage = |ram| MemoryUsage { ram_percent: ram, vram_percent: None };
+
+        let level = MemoryPressureMonitor::next_level(&config, PressureLevel::Normal, &usage(91.0));
+        assert_eq!(level, PressureLevel::High);
+
+        // Between the marks we stay in the current level
+        let level = MemoryPressureMonitor::next_level(&config, level, &usage(85.0));
+        assert_eq!(level, PressureLevel::High);
+
+        let level = MemoryPressureMonitor::next_level(&config, level, &usage(79.0));
+        assert_eq!(level, PressureLevel::Normal);
+
+        let level = MemoryPressureMonitor::next_level(&config, level, &usage(85.0));
+        assert_eq!(level, PressureLevel::Normal);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_models_are_not_unloaded() {
+        let config = PressureConfig {
+            idle_unload_secs: 0,
+            ..PressureConfig::default()
+        };
+        let monitor = MemoryPressureMonitor::new(config);
+
+        monitor.register_model("pinned", || Ok(()));
+        monitor.register_model("idle", || Ok(()));
+        monitor.set_pinned("pinned", true).unwrap();
+
+        monitor.evaluate(MemoryUsage { ram_percent: 95.0, vram_percent: None });
+
+        let status = monitor.get_status();
+        assert_eq!(status.level, PressureLevel::High);
+        assert_eq!(status.loaded_models, vec!["pinned".to_string()]);
+        assert_eq!(status.unload_count, 1);
+    }
+
+    #[test]
+    fn test_invalid_thresholds_rejected() {
+        let monitor = MemoryPressureMonitor::new(PressureConfig::default());
+        let config = PressureConfig {
+            ram_low_percent: 95.0,
+            ..PressureConfig::default()
+        };
+        assert!(monitor.update_config(config).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use serde::{Serialize, Deserialize};
+use std::sync::{Arc, Mutex};
+
+use crate::utils::config;
+
+lazy_static! {
+    static ref THREAD_CONFIG: Arc<Mutex<ThreadConfig>> = Arc::new(Mutex::new(ThreadConfig::load()));
+}
+
+/// User-controllable thread pool configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadConfig {
+    /// Number of threads used for local inference (None = automatic)
+    pub inference_threads: Option<usize>,
+    /// Restrict inference threads to performance cores on hybrid CPUs
+    pub avoid_efficiency_cores: bool,
+    /// Pin inference threads to specific cores
+    pub pin_inference_threads: bool,
+    /// Explicit core indices used when pinning (empty = automatic)
+    pub inference_cores: Vec<usize>,
+    /// Number of worker threads for background tasks (None = automatic)
+    pub background_threads: Option<usize>,
+}
+
+impl Default for ThreadConfig {
+    fn default() -> Self {
+        Self {
+            inference_threads: None,
+            avoid_efficiency_cores: true,
+            pin_inference_threads: false,
+            inference_cores: Vec::new(),
+            background_threads: None,
+        }
+    }
+}
+
+/// CPU topology as far as it can be detected
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuTopology {
+    /// Number of logical cores
+    pub logical_cores: usize,
+    /// Core indices of performance cores (all cores on non-hybrid CPUs)
+    pub performance_cores: Vec<usize>,
+    /// Core indices of efficiency cores (empty on non-hybrid CPUs)
+    pub efficiency_cores: Vec<usize>,
+}
+
+impl CpuTopology {
+    /// Whether the CPU mixes performance and efficiency cores
+    pub fn is_hybrid(&self) -> bool {
+        !self.efficiency_cores.is_empty()
+    }
+
+    /// Detect the CPU topology of the current machine
+    pub fn detect() -> Self {
+        let logical_cores = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+
+        // Intel hybrid CPUs on Linux expose separate PMUs for P- and E-cores
+        #[cfg(target_os = "linux")]
+        {
+            let read_cpus = |path: &str| {
+                std::fs::read_to_string(path)
+                    .ok()
+                    .map(|s| parse_cpu_list(s.trim()))
+                    .unwrap_or_default()
+            };
+
+            let performance_cores = read_cpus("/sys/devices/cpu_core/cpus");
+            let efficiency_cores = read_cpus("/sys/devices/cpu_atom/cpus");
+
+            if !performance_cores.is_empty() && !efficiency_cores.is_empty() {
+                return Self {
+                    logical_cores,
+                    performance_cores,
+                    efficiency_cores,
+                };
+            }
+        }
+
+        Self {
+            logical_cores,
+            performance_cores: (0..logical_cores).collect(),
+            efficiency_cores: Vec::new(),
+        }
+    }
+}
+
+/// Effective thread settings applied to local providers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadSettings {
+    /// Number of inference threads
+    pub inference_threads: usize,
+    /// Cores to pin inference threads to (empty = no pinning)
+    pub affinity: Vec<usize>,
+    /// Number of background worker threads
+    pub background_threads: usize,
+}
+
+/// Parse a Linux CPU list such as "0-7,16,18-19"
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cores = Vec::new();
+
+    for part in list.split(',').filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) {
+                    cores.extend(start..=end);
+                }
+            }
+            None => {
+                if let Ok(core) = part.parse::<usize>() {
+                    cores.push(core);
+                }
+            }
+        }
+    }
+
+    cores
+}
+
+impl ThreadConfig {
+    /// Load thread configuration from the application config
+    fn load() -> Self {
+        let config = config::get_config();
+        let config = config.lock().unwrap();
+
+        match config.get_value("performance.threads") {
+            Some(value) => serde_json::from_value(value.clone()).unwrap_or_else(|e| {
+                warn!("Invalid thread configuration, using defaults: {}", e);
+                Self::default()
+            }),
+            None => Self::default(),
+        }
+    }
+
+    /// Resolve the configuration against the detected CPU topology
+    pub fn resolve(&self, topology: &CpuTopology) -> ThreadSettings {
+        let usable_cores = if self.avoid_efficiency_cores && topology.is_hybrid() {
+            topology.performance_cores.clone()
+        } else {
+            (0..topology.logical_cores).collect::<Vec<_>>()
+        };
+
+        // Leave one core free for the UI when choosing automatically
+        let auto_inference = usable_cores.len().saturating_sub(1).max(1);
+        let inference_threads = self
+            .inference_threads
+            .unwrap_or(auto_inference)
+            .clamp(1, topology.logical_cores.max(1));
+
+        let affinity = if !self.pin_inference_threads {
+            Vec::new()
+        } else if !self.inference_cores.is_empty() {
+            self.inference_cores
+                .iter()
+                .copied()
+                .filter(|core| *core < topology.logical_cores)
+                .collect()
+        } else {
+            usable_cores.into_iter().take(inference_threads).collect()
+        };
+
+        let background_threads = self
+            .background_threads
+            .unwrap_or_else(|| (topology.logical_cores / 4).max(2))
+            .max(1);
+
+        ThreadSettings {
+            inference_threads,
+            affinity,
+            background_threads,
+        }
+    }
+}
+
+/// Get the current thread configuration
+pub fn get_thread_config() -> ThreadConfig {
+    THREAD_CONFIG.lock().unwrap().clone()
+}
+
+/// Update and persist the thread configuration
+pub fn update_thread_config(thread_config: ThreadConfig) -> Result<(), String> {
+    if thread_config.inference_threads == Some(0) || thread_config.background_threads == Some(0) {
+        return Err("Thread counts must be greater than zero".to_string());
+    }
+
+    let value = serde_json::to_value(&thread_config).map_err(|e| e.to_string())?;
+    config::set_value("performance.threads", value)?;
+    config::save_config().map_err(|e| e.to_string())?;
+
+    *THREAD_CONFIG.lock().unwrap() = thread_config;
+    info!("Thread configuration updated");
+    Ok(())
+}
+
+/// Get the effective thread settings for this machine
+pub fn get_thread_settings() -> ThreadSettings {
+    let settings = get_thread_config().resolve(&CpuTopology::detect());
+    debug!("Effective thread settings: {:?}", settings);
+    settings
+}
+
+/// Get the number of threads to use for local inference
+pub fn get_thread_pool_size() -> usize {
+    get_thread_settings().inference_threads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hybrid_topology() -> CpuTopology {
+        CpuTopology {
+            logical_cores: 16,
+            performance_cores: (0..8).collect(),
+            efficiency_cores: (8..16).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11"), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert!(parse_cpu_list("").is_empty());
+    }
+
+    #[test]
+    fn test_resolve_avoids_efficiency_cores() {
+        let config = ThreadConfig {
+            pin_inference_threads: true,
+            ..ThreadConfig::default()
+        };
+
+        let settings = config.resolve(&hybrid_topology());
+        assert_eq!(settings.inference_threads, 7);
+        assert!(settings.affinity.iter().all(|core| *core < 8));
+    }
+
+    #[test]
+    fn test_resolve_explicit_counts() {
+        let config = ThreadConfig {
+            inference_threads: Some(64),
+            background_threads: Some(3),
+            ..ThreadConfig::default()
+        };
+
+        let settings = config.resolve(&hybrid_topology());
+        assert_eq!(settings.inference_threads, 16);
+        assert_eq!(settings.background_threads, 3);
+        assert!(settings.affinity.is_empty());
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 use std::sync::Arc;
+use mcp_common::platform::fs::{app_dir, AppDir};
 
 /// Permission manager
 pub struct PermissionManager {
@@ -153,22 +154,7 @@ impl PermissionManager {
     
     /// Get config directory
     fn get_config_dir(&self) -> Result<std::path::PathBuf, String> {
-        let home_dir = dirs::home_dir()
-            .ok_or_else(|| "Could not determine home directory".to_string())?;
-            
-        #[cfg(target_os = "windows")]
-        let config_dir = home_dir.join("AppData").join("Roaming").join("mcp").join("plugins");
-        
-        #[cfg(target_os = "macos")]
-        let config_dir = home_dir.join("Library").join("Application Support").join("mcp").join("plugins");
-        
-        #[cfg(target_os = "linux")]
-        let config_dir = home_dir.join(".config").join("mcp").join("plugins");
-        
-        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-        let config_dir = home_dir.join(".mcp").join("plugins");
-        
-        Ok(config_dir)
+        Ok(app_dir(AppDir::Config).join("plugins"))
     }
     
     /// Check if initial permissions for a plugin are allowed
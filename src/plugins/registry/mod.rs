@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
+use mcp_common::platform::fs::{app_dir, AppDir};
 use crate::plugins::types::{Plugin, PluginInfo, PluginDetails};
 
 /// Plugin registry
@@ -416,22 +417,7 @@ impl PluginRegistry {
 
 /// Get the plugins directory
 fn get_plugins_dir() -> Result<PathBuf, String> {
-    let home_dir = dirs::home_dir()
-        .ok_or_else(|| "Could not determine home directory".to_string())?;
-        
-    #[cfg(target_os = "windows")]
-    let plugins_dir = home_dir.join("AppData").join("Roaming").join("mcp").join("plugins").join("installed");
-    
-    #[cfg(target_os = "macos")]
-    let plugins_dir = home_dir.join("Library").join("Application Support").join("mcp").join("plugins").join("installed");
-    
-    #[cfg(target_os = "linux")]
-    let plugins_dir = home_dir.join(".config").join("mcp").join("plugins").join("installed");
-    
-    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
-    let plugins_dir = home_dir.join(".mcp").join("plugins").join("installed");
-    
-    Ok(plugins_dir)
+    Ok(app_dir(AppDir::Config).join("plugins").join("installed"))
 }
 
 impl Default for PluginRegistry {
@@ -2,24 +2,27 @@ use crate::models::messages::{Message, MessageError};
 use crate::protocols::mcp::error::McpError;
 use crate::protocols::mcp::message::{McpMessage, McpMessagePayload, McpResponseMessage};
 use crate::protocols::mcp::types::{McpCompletionRequest, McpMessageRole, McpMessageType};
-use crate::protocols::mcp::websocket::WebSocketClient;
 use crate::protocols::mcp::McpConfig;
 use crate::protocols::ConnectionStatus;
+use crate::offline::get_offline_manager;
 use log::{debug, error, info, warn};
+use mcp_common::protocol::{PersistentTransport, TransportConfig, TransportEvent};
 use serde_json::json;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio::sync::mpsc::{self, Receiver, Sender};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
-use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
 use tokio::time::timeout;
 use uuid::Uuid;
 
 /// Struct responsible for handling MCP communication
 pub struct McpClient {
-    /// WebSocket client for communication
-    websocket: Arc<WebSocketClient>,
+    /// Persistent connection that keeps a heartbeat, reconnects and
+    /// re-sends completion requests cut off by a dropped connection
+    transport: Arc<PersistentTransport>,
     
     /// Client configuration
     config: McpConfig,
@@ -27,8 +30,8 @@ pub struct McpClient {
     /// Connection status
     status: Arc<RwLock<ConnectionStatus>>,
     
-    /// Request tracking map (request ID -> response channel)
-    pending_requests: Arc<Mutex<HashMap<String, oneshot::Sender<Result<McpResponseMessage, McpError>>>>>,
+    /// Tasks following the transport's connection state and frames
+    tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
     
     /// Channel for receiving unsolicited messages (events, etc.)
     event_tx: UnboundedSender<McpMessage>,
@@ -42,12 +45,19 @@ impl McpClient {
     /// Create a new MCP client with the given configuration
     pub fn new(config: McpConfig) -> Self {
         let (event_tx, _) = mpsc::unbounded_channel();
+        let transport = PersistentTransport::new(TransportConfig {
+            url: config.url.clone(),
+            connect_timeout: config.connection_timeout,
+            initial_backoff: config.reconnect_backoff,
+            max_reconnect_attempts: config.max_reconnect_attempts,
+            ..TransportConfig::default()
+        });
         
         Self {
-            websocket: Arc::new(WebSocketClient::new(&config.url)),
+            transport: Arc::new(transport),
             config,
             status: Arc::new(RwLock::new(ConnectionStatus::Disconnected)),
-            pending_requests: Arc::new(Mutex::new(HashMap::new())),
+            tasks: Arc::new(Mutex::new(Vec::new())),
             event_tx,
             streaming_channels: Arc::new(Mutex::new(HashMap::new())),
         }
@@ -60,41 +70,56 @@ impl McpClient {
         rx
     }
     
-    /// Connect to the MCP server
+    /// Connect to the MCP server. The transport keeps reconnecting in the
+    /// background after the first connection is made.
     pub async fn connect(&self) -> Result<(), McpError> {
         let mut status = self.status.write().unwrap();
         *status = ConnectionStatus::Connecting;
         drop(status);
         
-        // Connect to WebSocket
-        let ws_client = self.websocket.clone();
-        let pending_requests = self.pending_requests.clone();
-        let streaming_channels = self.streaming_channels.clone();
-        let status_clone = self.status.clone();
-        let event_tx = self.event_tx.clone();
+        // Follow connection state and route incoming frames
+        let mut connected = self.transport.subscribe_events();
+        {
+            let mut tasks = self.tasks.lock().unwrap();
+            for task in tasks.drain(..) {
+                task.abort();
+            }
+            tasks.push(tokio::spawn(Self::track_connection(
+                self.transport.subscribe_events(),
+                self.status.clone(),
+                self.streaming_channels.clone(),
+            )));
+            tasks.push(tokio::spawn(Self::handle_incoming_messages(
+                self.transport.clone(),
+                self.transport.subscribe_messages(),
+                self.streaming_channels.clone(),
+                self.event_tx.clone(),
+            )));
+        }
+        self.transport.start().await;
         
-        match ws_client.connect().await {
-            Ok(_) => {
-                let mut status = self.status.write().unwrap();
-                *status = ConnectionStatus::Connected;
-                
-                // Start message handler
-                tokio::spawn(async move {
-                    Self::handle_incoming_messages(
-                        ws_client.clone(),
-                        pending_requests,
-                        streaming_channels,
-                        status_clone,
-                        event_tx,
-                    )
-                    .await;
-                });
-                
-                Ok(())
+        // Wait for the first connection
+        let first = timeout(self.config.connection_timeout, async {
+            loop {
+                match connected.recv().await {
+                    Ok(TransportEvent::Connected) => return Ok(()),
+                    Ok(TransportEvent::GaveUp { attempts }) => {
+                        return Err(format!("Gave up after {} attempts", attempts));
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return Err("Transport closed".to_string()),
+                }
             }
+        })
+        .await
+        .unwrap_or_else(|_| Err("Connection timed out".to_string()));
+        
+        match first {
+            Ok(()) => Ok(()),
             Err(e) => {
+                self.disconnect().await?;
                 let mut status = self.status.write().unwrap();
-                *status = ConnectionStatus::ConnectionError(e.to_string());
+                *status = ConnectionStatus::ConnectionError(e.clone());
                 Err(McpError::ConnectionError(e))
             }
         }
@@ -102,24 +127,21 @@ impl McpClient {
     
     /// Disconnect from the MCP server
     pub async fn disconnect(&self) -> Result<(), McpError> {
-        // Close WebSocket connection
-        self.websocket.disconnect().await?;
+        // Close the connection and stop following it
+        self.transport.stop().await;
+        for task in self.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+        get_offline_manager().report_transport_state(None);
         
         // Update status
-        let mut status = self.status.write().unwrap();
-        *status = ConnectionStatus::Disconnected;
-        
-        // Cancel all pending requests
-        let mut pending = self.pending_requests.lock().unwrap();
-        for (_, sender) in pending.drain() {
-            let _ = sender.send(Err(McpError::ConnectionClosed));
+        {
+            let mut status = self.status.write().unwrap();
+            *status = ConnectionStatus::Disconnected;
         }
         
-        // Cancel all streaming channels
-        let mut streaming = self.streaming_channels.lock().unwrap();
-        for (_, sender) in streaming.drain() {
-            let _ = sender.send(Err(McpError::ConnectionClosed)).await;
-        }
+        // Cancel all streaming channels; waiting requests fail with the transport
+        Self::close_streams(&self.streaming_channels).await;
         
         Ok(())
     }
@@ -134,29 +156,31 @@ impl McpClient {
         // Convert to MCP message format
         let mcp_message = Self::convert_to_mcp_message(message)?;
         
-        // Setup response channel
-        let (tx, rx) = oneshot::channel();
-        {
-            let mut pending = self.pending_requests.lock().unwrap();
-            pending.insert(mcp_message.id.clone(), tx);
-        }
-        
-        // Send message
-        self.websocket
-            .send(serde_json::to_string(&mcp_message)?)
+        // Completion requests carry the message ID, so the server drops a
+        // copy re-sent after a reconnect; they survive a dropped connection
+        let response = self
+            .transport
+            .request(
+                &mcp_message.id,
+                serde_json::to_string(&mcp_message)?,
+                true,
+                self.config.request_timeout,
+            )
             .await
             .map_err(|e| MessageError::NetworkError(e.to_string()))?;
         
-        // Wait for response with timeout
-        match timeout(Duration::from_secs(60), rx).await {
-            Ok(result) => match result {
-                Ok(response) => match response {
-                    Ok(mcp_response) => Self::convert_from_mcp_response(mcp_response),
-                    Err(e) => Err(MessageError::ProtocolError(e.to_string())),
-                },
-                Err(_) => Err(MessageError::Unknown("Response channel closed".to_string())),
-            },
-            Err(_) => Err(MessageError::Timeout(Duration::from_secs(60))),
+        Self::convert_from_mcp_response(Self::parse_response(&response)?)
+    }
+    
+    /// Parse a response frame routed to a request
+    fn parse_response(frame: &str) -> Result<McpResponseMessage, MessageError> {
+        let message = serde_json::from_str::<McpMessage>(frame)
+            .map_err(|e| MessageError::SerializationError(e.to_string()))?;
+        match message.payload {
+            McpMessagePayload::Error { code, message, .. } => {
+                Err(MessageError::ProtocolError(format!("{}: {}", code, message)))
+            }
+            _ => Ok(McpResponseMessage::Completion(message)),
         }
     }
     
@@ -188,49 +212,49 @@ impl McpClient {
             streaming.insert(streaming_id.clone(), tx);
         }
         
-        // Setup response channel for initial acknowledgment
-        let (ack_tx, ack_rx) = oneshot::channel();
-        {
-            let mut pending = self.pending_requests.lock().unwrap();
-            pending.insert(mcp_message.id.clone(), ack_tx);
-        }
-        
-        // Send message
-        self.websocket
-            .send(serde_json::to_string(&mcp_message)?)
-            .await
-            .map_err(|e| MessageError::NetworkError(e.to_string()))?;
-        
-        // Wait for initial acknowledgment
-        match timeout(Duration::from_secs(10), ack_rx).await {
-            Ok(result) => match result {
-                Ok(response) => {
-                    match response {
-                        Ok(_) => {
-                            // Create message adapter channel
-                            let (adapter_tx, adapter_rx) = mpsc::channel(32);
-                            
-                            // Spawn adapter task to convert MCP messages to app messages
-                            let streaming_clone = self.streaming_channels.clone();
-                            let streaming_id_clone = streaming_id.clone();
-                            
-                            tokio::spawn(async move {
-                                let streaming = streaming_clone.lock().unwrap();
-                                if let Some(rx_channel) = streaming.get(&streaming_id_clone) {
-                                    // TODO: Implement streaming message adapter
-                                    // This would convert the MCP protocol streaming messages
-                                    // into the application's Message format
-                                }
-                            });
-                            
-                            Ok(adapter_rx)
-                        }
-                        Err(e) => Err(MessageError::ProtocolError(e.to_string())),
+        // Send and wait for the initial acknowledgment; a stream cut off
+        // halfway cannot be resumed, so it fails with the connection
+        let ack = self
+            .transport
+            .request(
+                &mcp_message.id,
+                serde_json::to_string(&mcp_message)?,
+                false,
+                Duration::from_secs(10),
+            )
+            .await;
+        match ack {
+            Ok(frame) => {
+                match Self::parse_response(&frame) {
+                    Ok(_) => {
+                        // Create message adapter channel
+                        let (adapter_tx, adapter_rx) = mpsc::channel(32);
+                        
+                        // Spawn adapter task to convert MCP messages to app messages
+                        let streaming_clone = self.streaming_channels.clone();
+                        let streaming_id_clone = streaming_id.clone();
+                        
+                        tokio::spawn(async move {
+                            let streaming = streaming_clone.lock().unwrap();
+                            if let Some(rx_channel) = streaming.get(&streaming_id_clone) {
+                                // TODO: Implement streaming message adapter
+                                // This would convert the MCP protocol streaming messages
+                                // into the application's Message format
+                            }
+                        });
+                        
+                        Ok(adapter_rx)
+                    }
+                    Err(e) => {
+                        self.streaming_channels.lock().unwrap().remove(&streaming_id);
+                        Err(e)
                     }
                 }
-                Err(_) => Err(MessageError::Unknown("Response channel closed".to_string())),
-            },
-            Err(_) => Err(MessageError::Timeout(Duration::from_secs(10))),
+            }
+            Err(e) => {
+                self.streaming_channels.lock().unwrap().remove(&streaming_id);
+                Err(MessageError::NetworkError(e.to_string()))
+            }
         }
     }
     
@@ -252,7 +276,7 @@ impl McpClient {
             },
         };
         
-        self.websocket
+        self.transport
             .send(serde_json::to_string(&cancel_message)?)
             .await
             .map_err(|e| MessageError::NetworkError(e.to_string()))?;
@@ -260,102 +284,101 @@ impl McpClient {
         Ok(())
     }
     
-    /// Handle incoming messages from the WebSocket
+    /// Follow the transport's connection state. The offline manager uses it
+    /// instead of probing the network while the client is connected.
+    async fn track_connection(
+        mut events: broadcast::Receiver<TransportEvent>,
+        status: Arc<RwLock<ConnectionStatus>>,
+        streaming_channels: Arc<Mutex<HashMap<String, Sender<Result<McpMessage, McpError>>>>>,
+    ) {
+        loop {
+            match events.recv().await {
+                Ok(TransportEvent::Connected) => {
+                    *status.write().unwrap() = ConnectionStatus::Connected;
+                    get_offline_manager().report_transport_state(Some(true));
+                }
+                Ok(TransportEvent::Disconnected { reason }) => {
+                    warn!("MCP connection lost: {}", reason);
+                    *status.write().unwrap() = ConnectionStatus::ConnectionError(reason);
+                    get_offline_manager().report_transport_state(Some(false));
+                    
+                    // Streams end with the connection they ran on
+                    Self::close_streams(&streaming_channels).await;
+                }
+                Ok(TransportEvent::Reconnecting { attempt, delay_ms }) => {
+                    debug!("Reconnecting to MCP server in {}ms (attempt {})", delay_ms, attempt);
+                }
+                Ok(TransportEvent::Resumed { requests }) => {
+                    info!("Re-sent {} request(s) after reconnecting", requests);
+                }
+                Ok(TransportEvent::GaveUp { attempts }) => {
+                    error!("Gave up reconnecting to MCP server after {} attempts", attempts);
+                    get_offline_manager().report_transport_state(Some(false));
+                    break;
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+    
+    /// Fail every open stream
+    async fn close_streams(
+        streaming_channels: &Mutex<HashMap<String, Sender<Result<McpMessage, McpError>>>>,
+    ) {
+        let senders: Vec<_> = streaming_channels.lock().unwrap().drain().map(|(_, sender)| sender).collect();
+        for sender in senders {
+            let _ = sender.send(Err(McpError::ConnectionClosed)).await;
+        }
+    }
+    
+    /// Handle frames the transport did not match to a waiting request
     async fn handle_incoming_messages(
-        websocket: Arc<WebSocketClient>,
-        pending_requests: Arc<Mutex<HashMap<String, oneshot::Sender<Result<McpResponseMessage, McpError>>>>>,
+        transport: Arc<PersistentTransport>,
+        mut frames: broadcast::Receiver<String>,
         streaming_channels: Arc<Mutex<HashMap<String, Sender<Result<McpMessage, McpError>>>>>,
-        status: Arc<RwLock<ConnectionStatus>>,
         event_tx: UnboundedSender<McpMessage>,
     ) {
         loop {
-            match websocket.receive().await {
-                Ok(message) => {
-                    match serde_json::from_str::<McpMessage>(&message) {
-                        Ok(mcp_message) => {
-                            // Handle based on message type
-                            match mcp_message.type_ {
-                                McpMessageType::CompletionResponse => {
-                                    // Check if this is part of a pending request
-                                    let mut pending = pending_requests.lock().unwrap();
-                                    if let Some(sender) = pending.remove(&mcp_message.id) {
-                                        let _ = sender.send(Ok(McpResponseMessage::Completion(mcp_message)));
-                                    } else {
-                                        // This could be an unsolicited message or event
-                                        let _ = event_tx.send(mcp_message);
-                                    }
-                                }
-                                McpMessageType::StreamingMessage => {
-                                    // Extract streaming ID from payload
-                                    if let McpMessagePayload::StreamingMessage { streaming_id, .. } = 
-                                        &mcp_message.payload 
-                                    {
-                                        let streaming = streaming_channels.lock().unwrap();
-                                        if let Some(sender) = streaming.get(streaming_id) {
-                                            let _ = sender.send(Ok(mcp_message.clone())).await;
-                                        }
-                                    }
-                                }
-                                McpMessageType::StreamingEnd => {
-                                    // Extract streaming ID from payload
-                                    if let McpMessagePayload::StreamingEnd { streaming_id } = 
-                                        &mcp_message.payload 
-                                    {
-                                        let mut streaming = streaming_channels.lock().unwrap();
-                                        if let Some(sender) = streaming.remove(streaming_id) {
-                                            let _ = sender.send(Ok(mcp_message.clone())).await;
-                                        }
-                                    }
-                                }
-                                McpMessageType::Error => {
-                                    // Extract request_id from payload
-                                    if let McpMessagePayload::Error { request_id, .. } = 
-                                        &mcp_message.payload 
-                                    {
-                                        let mut pending = pending_requests.lock().unwrap();
-                                        if let Some(sender) = pending.remove(request_id) {
-                                            if let McpMessagePayload::Error { code, message, .. } = 
-                                                mcp_message.payload.clone() 
-                                            {
-                                                let _ = sender.send(Err(
-                                                    McpError::ProtocolError(format!("{}: {}", code, message))
-                                                ));
-                                            }
-                                        }
-                                    }
-                                }
-                                _ => {
-                                    // Handle other message types or unsolicited messages
-                                    let _ = event_tx.send(mcp_message);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            error!("Failed to parse MCP message: {}", e);
-                        }
-                    }
+            let frame = match frames.recv().await {
+                Ok(frame) => frame,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Dropped {} MCP frames", skipped);
+                    continue;
                 }
+                Err(RecvError::Closed) => break,
+            };
+            let mcp_message = match serde_json::from_str::<McpMessage>(&frame) {
+                Ok(mcp_message) => mcp_message,
                 Err(e) => {
-                    error!("WebSocket error: {}", e);
-                    
-                    // Update connection status
-                    let mut status_guard = status.write().unwrap();
-                    *status_guard = ConnectionStatus::ConnectionError(e.to_string());
-                    
-                    // Cancel all pending requests
-                    let mut pending = pending_requests.lock().unwrap();
-                    for (_, sender) in pending.drain() {
-                        let _ = sender.send(Err(McpError::ConnectionClosed));
+                    error!("Failed to parse MCP message: {}", e);
+                    continue;
+                }
+            };
+            
+            // Handle based on message type
+            match &mcp_message.payload {
+                McpMessagePayload::StreamingMessage { streaming_id, .. } => {
+                    let sender = streaming_channels.lock().unwrap().get(streaming_id).cloned();
+                    if let Some(sender) = sender {
+                        let _ = sender.send(Ok(mcp_message.clone())).await;
                     }
-                    
-                    // Cancel all streaming channels
-                    let mut streaming = streaming_channels.lock().unwrap();
-                    for (_, sender) in streaming.drain() {
-                        let _ = sender.send(Err(McpError::ConnectionClosed)).await;
+                }
+                McpMessagePayload::StreamingEnd { streaming_id } => {
+                    let sender = streaming_channels.lock().unwrap().remove(streaming_id);
+                    if let Some(sender) = sender {
+                        let _ = sender.send(Ok(mcp_message.clone())).await;
                     }
-                    
-                    // Break the loop, ending the handler
-                    break;
+                }
+                McpMessagePayload::Error { request_id, .. } => {
+                    // Errors name the request in their payload rather than their ID
+                    if !transport.resolve(request_id, Ok(frame.clone())).await {
+                        let _ = event_tx.send(mcp_message);
+                    }
+                }
+                _ => {
+                    // Unsolicited messages and events
+                    let _ = event_tx.send(mcp_message);
                 }
             }
         }
@@ -381,7 +404,7 @@ impl McpClient {
                         "text": text
                     }));
                 }
-                crate::models::messages::ContentType::Image { url, media_type } => {
+                crate::models::messages::ContentType::Image { url, media_type, .. } => {
                     parts.push(json!({
                         "type": "image",
                         "source": {
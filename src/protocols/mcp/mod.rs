@@ -4,7 +4,6 @@ mod error;
 mod message;
 mod protocol;
 mod types;
-mod websocket;
 
 // Export the key components
 pub use client::McpClient;
@@ -13,7 +12,6 @@ pub use error::McpError;
 pub use message::{McpMessage, McpMessagePayload, McpResponseMessage};
 pub use protocol::McpProtocolHandler;
 pub use types::*;
-pub use websocket::WebSocketClient;
 
 use crate::protocols::{ProtocolFactory, ProtocolHandler};
 use serde::{Deserialize, Serialize};
@@ -327,6 +327,41 @@ pub fn get_security_manager() -> Result<Arc<SecurityManager>> {
     }
 }
 
+/// Secret store backed by the platform credential manager, so secrets of
+/// shared code (such as OAuth tokens) live in the vault rather than in files
+pub struct VaultSecretStore;
+
+impl mcp_common::auth::SecretStore for VaultSecretStore {
+    fn get(&self, key: &str) -> mcp_common::error::McpResult<Option<String>> {
+        let manager = get_security_manager().map_err(vault_error)?;
+        let credentials = manager.get_credential_manager();
+        if !credentials.read().unwrap().has_credential(key).map_err(vault_error)? {
+            return Ok(None);
+        }
+        manager.get_credential(key).map(Some).map_err(vault_error)
+    }
+    
+    fn set(&self, key: &str, value: &str) -> mcp_common::error::McpResult<()> {
+        get_security_manager()
+            .and_then(|manager| manager.store_credential(key, value))
+            .map_err(vault_error)
+    }
+    
+    fn delete(&self, key: &str) -> mcp_common::error::McpResult<()> {
+        let manager = get_security_manager().map_err(vault_error)?;
+        let credentials = manager.get_credential_manager();
+        let credentials = credentials.read().unwrap();
+        if credentials.has_credential(key).map_err(vault_error)? {
+            credentials.delete_credential(key).map_err(vault_error)?;
+        }
+        Ok(())
+    }
+}
+
+fn vault_error(e: impl std::fmt::Display) -> mcp_common::error::McpError {
+    mcp_common::error::McpError::Config(format!("Credential vault error: {}", e))
+}
+
 // Helper functions for common security operations
 
 /// Check if a permission is granted
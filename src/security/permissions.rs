@@ -287,6 +287,39 @@ impl PermissionManager {
             now,
         )?;
         
+        self.add_permission_internal(
+            &mut permissions,
+            "shell_execution",
+            "Shell Commands",
+            "Allow the assistant to run shell commands in the embedded terminal",
+            PermissionLevel::AskEveryTime,
+            "System",
+            false,
+            now,
+        )?;
+        
+        self.add_permission_internal(
+            &mut permissions,
+            "issue_tracker_access",
+            "Issue Trackers",
+            "Allow the assistant to read and search issues in Jira and Linear",
+            PermissionLevel::AskFirstTime,
+            "Data",
+            false,
+            now,
+        )?;
+        
+        self.add_permission_internal(
+            &mut permissions,
+            "tool_policy_ask",
+            "Guarded Tool Calls",
+            "Allow tool calls that a guardrail policy asks about",
+            PermissionLevel::AskEveryTime,
+            "System",
+            false,
+            now,
+        )?;
+        
         self.add_permission_internal(
             &mut permissions,
             "e2ee",
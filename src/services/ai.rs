@@ -1,11 +1,16 @@
+use crate::ai::local::{self, adapters, prompt};
 use crate::ai::router::{get_model_router, NetworkStatus, RouterStrategy};
+use crate::context::overflow;
 use crate::models::messages::{Message, MessageError, ConversationMessage, MessageStatus};
 use crate::models::{Conversation, Model};
+use crate::telemetry::latency;
+use crate::utils::cancellation::RequestContext;
 use crate::utils::config;
 use crate::utils::events::{events, get_event_system};
 use log::{debug, error, info, warn};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
@@ -156,12 +161,60 @@ impl AiService {
             .unwrap_or_default()
     }
     
+    /// Name of the provider a model's requests go to, for latency tracking
+    fn provider_name(&self, model_id: &str) -> String {
+        self.router
+            .select_provider_for_model(model_id)
+            .map(|(provider, _)| provider.provider_type().to_string().to_lowercase())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+    
+    /// Attach what a local model needs besides the message itself: the
+    /// conversation's LoRA adapters and, as local models get one message at
+    /// a time, the completed turns before it, fitted to the model's context
+    /// window with the conversation's overflow strategy
+    fn attach_local_context(
+        &self,
+        provider: &str,
+        conversation_id: &str,
+        model_id: &str,
+        message: Message,
+    ) -> Result<Message, MessageError> {
+        let message = adapters::attach(&local::model_dir(), conversation_id, message);
+        if provider != "local" {
+            return Ok(message);
+        }
+        
+        let history: Vec<Message> = self
+            .get_messages(conversation_id)
+            .into_iter()
+            .filter(|m| m.status == MessageStatus::Complete)
+            .map(|m| m.message)
+            .collect();
+        let turns = prompt::history_turns(&history, &message);
+        let turns = overflow::fit_history(conversation_id, local_context_window(model_id), turns, &prompt::turn_of(&message))
+            .map_err(MessageError::ProtocolError)?;
+        Ok(prompt::attach_turns(turns, message))
+    }
+    
     /// Send a message in a conversation
     pub async fn send_message(
         &self,
         conversation_id: &str,
         model_id: &str,
         message: Message,
+    ) -> Result<ConversationMessage, MessageError> {
+        self.send_message_with_context(conversation_id, model_id, message, &RequestContext::new())
+            .await
+    }
+    
+    /// Send a message, aborting when the context is canceled or its deadline passes
+    pub async fn send_message_with_context(
+        &self,
+        conversation_id: &str,
+        model_id: &str,
+        message: Message,
+        ctx: &RequestContext,
     ) -> Result<ConversationMessage, MessageError> {
         // Store message in history with 'sending' status
         let conversation_message = ConversationMessage {
@@ -174,9 +227,21 @@ impl AiService {
         
         self.add_message_to_history(conversation_id, conversation_message.clone());
         
-        // Send message through router
-        match self.router.complete(model_id, message).await {
+        // Send message through router, with the conversation's LoRA adapters
+        // and earlier turns for local models to apply
+        let provider = self.provider_name(model_id);
+        let message = match self.attach_local_context(&provider, conversation_id, model_id, message) {
+            Ok(message) => message,
+            Err(e) => {
+                self.update_message_status(conversation_id, &conversation_message.message.id, MessageStatus::Failed);
+                return Err(e);
+            }
+        };
+        let started = Instant::now();
+        match self.router.complete_with_context(model_id, message, ctx).await {
             Ok(response) => {
+                latency::record_request(&provider, None, started.elapsed());
+                
                 // Create response message
                 let response_message = ConversationMessage {
                     message: response,
@@ -199,11 +264,11 @@ impl AiService {
                 Ok(response_message)
             }
             Err(e) => {
-                // Update message status to failed
+                // Update message status to failed or cancelled
                 self.update_message_status(
                     conversation_id,
                     &conversation_message.message.id,
-                    MessageStatus::Failed,
+                    status_for_error(&e),
                 );
                 
                 Err(e)
@@ -217,6 +282,18 @@ impl AiService {
         conversation_id: &str,
         model_id: &str,
         message: Message,
+    ) -> Result<mpsc::Receiver<ConversationMessage>, MessageError> {
+        self.stream_message_with_context(conversation_id, model_id, message, &RequestContext::new())
+            .await
+    }
+    
+    /// Stream a message, ending the stream when the context is canceled or its deadline passes
+    pub async fn stream_message_with_context(
+        &self,
+        conversation_id: &str,
+        model_id: &str,
+        message: Message,
+        ctx: &RequestContext,
     ) -> Result<mpsc::Receiver<ConversationMessage>, MessageError> {
         // Create streaming channel for UI
         let (tx, rx) = mpsc::channel(32);
@@ -233,7 +310,16 @@ impl AiService {
         self.add_message_to_history(conversation_id, conversation_message.clone());
         
         // Start streaming through router
-        match self.router.stream(model_id, message).await {
+        let provider = self.provider_name(model_id);
+        let message = match self.attach_local_context(&provider, conversation_id, model_id, message) {
+            Ok(message) => message,
+            Err(e) => {
+                self.update_message_status(conversation_id, &conversation_message.message.id, MessageStatus::Failed);
+                return Err(e);
+            }
+        };
+        let started = Instant::now();
+        match self.router.stream_with_context(model_id, message, ctx).await {
             Ok(mut stream) => {
                 // Create initial response message
                 let response_id = Uuid::new_v4().to_string();
@@ -285,6 +371,7 @@ impl AiService {
                 
                 tokio::spawn(async move {
                     let mut full_text = String::new();
+                    let mut first_token = None;
                     
                     // Process streaming messages
                     while let Some(result) = stream.recv().await {
@@ -292,6 +379,8 @@ impl AiService {
                             Ok(chunk) => {
                                 // Extract text content
                                 if let Some(text) = chunk.text_content() {
+                                    first_token.get_or_insert_with(|| started.elapsed());
+                                    
                                     // Append to full text
                                     full_text.push_str(text);
                                     
@@ -323,8 +412,8 @@ impl AiService {
                             Err(e) => {
                                 error!("Streaming error: {}", e);
                                 
-                                // Update status to failed
-                                response_message.status = MessageStatus::Failed;
+                                // Update status to failed or cancelled
+                                response_message.status = status_for_error(&e);
                                 
                                 // Update in history
                                 {
@@ -348,6 +437,8 @@ impl AiService {
                     
                     // If we got here, streaming is complete
                     if response_message.status == MessageStatus::Streaming {
+                        latency::record_request(&provider, first_token, started.elapsed());
+                        
                         response_message.status = MessageStatus::Complete;
                         response_message.completed_at = Some(std::time::SystemTime::now());
                         response_message.partial_content = None;
@@ -373,11 +464,11 @@ impl AiService {
                 Ok(rx)
             }
             Err(e) => {
-                // Update message status to failed
+                // Update message status to failed or cancelled
                 self.update_message_status(
                     conversation_id,
                     &conversation_message.message.id,
-                    MessageStatus::Failed,
+                    status_for_error(&e),
                 );
                 
                 Err(e)
@@ -484,6 +575,24 @@ impl AiService {
     }
 }
 
+/// Context window of a local model; the smallest common one when the
+/// model isn't known
+fn local_context_window(model_id: &str) -> usize {
+    local::get_local_provider()
+        .ok()
+        .and_then(|provider| provider.all_models().into_iter().find(|m| m.id == model_id))
+        .map(|model| model.context_size)
+        .unwrap_or(2048)
+}
+
+/// Message status recorded for a failed request
+fn status_for_error(error: &MessageError) -> MessageStatus {
+    match error {
+        MessageError::Cancelled => MessageStatus::Cancelled,
+        _ => MessageStatus::Failed,
+    }
+}
+
 /// Global AI service instance
 static AI_SERVICE: once_cell::sync::OnceCell<AiService> = once_cell::sync::OnceCell::new();
 
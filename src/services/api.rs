@@ -1,5 +1,6 @@
 use crate::utils::config;
 use log::{debug, error, info, warn};
+use mcp_common::protocol::inspector;
 use reqwest::{Client, Response, StatusCode};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
@@ -84,12 +85,12 @@ impl ApiService {
     pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ApiError> {
         let url = format!("{}{}", self.base_url, path);
         
-        let response = self
+        let request = self
             .client
             .get(&url)
             .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .send()
+            .header("anthropic-version", "2023-06-01");
+        let response = inspector::send(request)
             .await
             .map_err(|e| ApiError::NetworkError(e.to_string()))?;
         
@@ -104,14 +105,14 @@ impl ApiService {
     ) -> Result<T, ApiError> {
         let url = format!("{}{}", self.base_url, path);
         
-        let response = self
+        let request = self
             .client
             .post(&url)
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
             .header("content-type", "application/json")
-            .json(data)
-            .send()
+            .json(data);
+        let response = inspector::send(request)
             .await
             .map_err(|e| ApiError::NetworkError(e.to_string()))?;
         
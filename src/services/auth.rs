@@ -1,7 +1,8 @@
 use crate::services::api::{get_api_service, ApiError};
 use crate::utils::config;
+use mcp_common::config::get_settings;
+use mcp_common::service::credentials::{self, CredentialStatus};
 use log::{debug, error, info, warn};
-use serde::{Deserialize, Serialize};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, SystemTime};
 
@@ -23,14 +24,6 @@ pub struct AuthService {
     is_authenticated: Arc<RwLock<bool>>,
 }
 
-/// API key validation response
-#[derive(Debug, Serialize, Deserialize)]
-struct ApiKeyValidation {
-    valid: bool,
-    expires_at: Option<String>,
-    organization_id: Option<String>,
-}
-
 impl AuthService {
     /// Create a new authentication service
     pub fn new() -> Self {
@@ -77,6 +70,9 @@ impl AuthService {
         // Save config to disk
         config::save_config().map_err(|e| e.to_string())?;
         
+        // The health indicator should pick up the new key
+        credentials::invalidate();
+        
         Ok(())
     }
     
@@ -93,39 +89,30 @@ impl AuthService {
             return Err("API key is empty".to_string());
         }
         
-        // In a real implementation, we would call the API to validate the key
-        // For now, we'll simulate a successful validation if the key is non-empty
-        
-        // Simulated API key validation
-        let validation = ApiKeyValidation {
-            valid: true,
-            expires_at: Some("2025-12-31T00:00:00Z".to_string()),
-            organization_id: Some("org_123456".to_string()),
-        };
-        
-        // Update state based on validation
-        if validation.valid {
-            // Set organization ID if provided
-            if let Some(org_id) = validation.organization_id {
-                let mut org_guard = self.organization_id.write().unwrap();
-                *org_guard = Some(org_id);
-            }
-            
-            // Mark as authenticated
-            {
-                let mut auth_guard = self.is_authenticated.write().unwrap();
-                *auth_guard = true;
+        // A cheap authenticated call; rate limits and exhausted credit still
+        // mean the key itself is good
+        let api_url = get_settings().lock().unwrap().api.url.clone();
+        let check = credentials::validate_key(&api_url, &api_key).await;
+        debug!("API key check: {:?} ({})", check.status, check.message);
+
+        match check.status {
+            CredentialStatus::Network | CredentialStatus::ServiceError => {
+                warn!("Couldn't validate the API key: {}", check.message);
+                Err(check.message)
             }
-            
-            Ok(true)
-        } else {
-            // Mark as not authenticated
-            {
+            status => {
+                let valid = status.key_accepted();
+                if valid {
+                    if let Some(org_id) = check.organization_id {
+                        let mut org_guard = self.organization_id.write().unwrap();
+                        *org_guard = Some(org_id);
+                    }
+                }
+
                 let mut auth_guard = self.is_authenticated.write().unwrap();
-                *auth_guard = false;
+                *auth_guard = valid;
+                Ok(valid)
             }
-            
-            Ok(false)
         }
     }
     
@@ -1,8 +1,20 @@
 use crate::models::messages::{Message, MessageError, ConversationMessage, MessageStatus};
 use crate::models::{Conversation, Model};
 use crate::services::mcp::{get_mcp_service, McpService};
+use crate::utils::cancellation::RequestContext;
 use crate::utils::config;
+use crate::utils::events::{events, get_event_system};
 use log::{debug, error, info, warn};
+use mcp_common::config::{get_journal, JournalEntry, OperationKind, Snapshot};
+use mcp_common::models::{MessageFeedback, Rating};
+use mcp_common::rag::citations::{self, Source, SOURCES_METADATA_KEY};
+use mcp_common::rag::get_knowledge_bases;
+use mcp_common::service::experiments::get_experiment_store;
+use mcp_common::service::feedback::{self, FeedbackRecord};
+use mcp_common::service::filters::{self, WORKSPACE_CONTEXT_KEY};
+use mcp_common::service::translation::{TranslationMiddleware, LANGUAGE_METADATA_KEY};
+use mcp_common::service::MiddlewareContext;
+use mcp_common::service::unfurl::get_unfurler;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
 use tokio::sync::mpsc;
@@ -53,24 +65,118 @@ impl ChatService {
         self.mcp_service.get_conversation(id)
     }
     
-    /// Delete a conversation
+    /// Replace a stored conversation (title or metadata)
+    pub fn update_conversation(&self, conversation: Conversation) -> Result<(), String> {
+        self.mcp_service.update_conversation(conversation)
+    }
+    
+    /// Delete a conversation; the deletion can be undone within the undo window
     pub fn delete_conversation(&self, id: &str) -> Result<(), String> {
+        let before = self.snapshot(id);
+        
         // Remove from MCP service
         let result = self.mcp_service.delete_conversation(id);
         
         // Remove message history
         if result.is_ok() {
-            let mut conversations = self.conversations.write().unwrap();
-            conversations.remove(id);
+            {
+                let mut conversations = self.conversations.write().unwrap();
+                conversations.remove(id);
+                
+                // Notify listeners that conversation was deleted
+                let mut listeners = self.message_listeners.lock().unwrap();
+                listeners.remove(id);
+            }
             
-            // Notify listeners that conversation was deleted
-            let mut listeners = self.message_listeners.lock().unwrap();
-            listeners.remove(id);
+            // Attachments are removed once the deletion can no longer be undone
+            let title = before.conversation.as_ref().map(|c| c.title.clone()).unwrap_or_default();
+            let after = Snapshot {
+                conversation_id: id.to_string(),
+                conversation: None,
+            };
+            if let Err(e) = get_journal().record(
+                OperationKind::DeleteConversation,
+                &format!("Delete '{}'", title),
+                vec![before],
+                vec![after],
+            ) {
+                warn!("Failed to journal deletion of {}: {}", id, e);
+            }
         }
         
         result
     }
     
+    /// A conversation together with its message history
+    fn snapshot(&self, id: &str) -> Snapshot {
+        let conversation = self.mcp_service.get_conversation(id).map(|mut conversation| {
+            if let Some(messages) = self.conversations.read().unwrap().get(id) {
+                conversation.messages = messages.iter().map(|m| m.message.clone().into()).collect();
+            }
+            conversation
+        });
+        Snapshot {
+            conversation_id: id.to_string(),
+            conversation,
+        }
+    }
+    
+    /// Put a conversation and its history back the way a snapshot has them
+    fn restore(&self, snapshot: &Snapshot) -> mcp_common::error::McpResult<()> {
+        let id = &snapshot.conversation_id;
+        match &snapshot.conversation {
+            Some(conversation) => {
+                let history = conversation
+                    .messages
+                    .iter()
+                    .map(|m| ConversationMessage {
+                        message: m.clone().into(),
+                        parent_ids: Vec::new(),
+                        completed_at: Some(std::time::SystemTime::now()),
+                        partial_content: None,
+                        status: MessageStatus::Complete,
+                    })
+                    .collect();
+                self.mcp_service.restore_conversation(conversation.clone());
+                self.conversations.write().unwrap().insert(id.clone(), history);
+            }
+            None => {
+                // Already gone is fine
+                let _ = self.mcp_service.delete_conversation(id);
+                self.conversations.write().unwrap().remove(id);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Undo the latest journaled operation
+    pub fn undo(&self) -> Result<Option<JournalEntry>, String> {
+        let entry = get_journal()
+            .undo(|snapshot| self.restore(snapshot))
+            .map_err(|e| e.to_string())?;
+        if let Some(entry) = &entry {
+            get_event_system().emit(
+                events::OPERATION_UNDONE,
+                serde_json::json!({ "entry_id": entry.id, "description": entry.description }),
+            );
+        }
+        Ok(entry)
+    }
+    
+    /// Redo the most recently undone operation
+    pub fn redo(&self) -> Result<Option<JournalEntry>, String> {
+        let entry = get_journal()
+            .redo(|snapshot| self.restore(snapshot))
+            .map_err(|e| e.to_string())?;
+        if let Some(entry) = &entry {
+            get_event_system().emit(
+                events::OPERATION_REDONE,
+                serde_json::json!({ "entry_id": entry.id, "description": entry.description }),
+            );
+        }
+        Ok(entry)
+    }
+    
     /// Get conversation message history
     pub fn get_messages(&self, conversation_id: &str) -> Vec<ConversationMessage> {
         let conversations = self.conversations.read().unwrap();
@@ -80,12 +186,149 @@ impl ChatService {
             .unwrap_or_default()
     }
     
+    /// Rate a message, or clear its rating with `None`
+    pub fn rate_message(
+        &self,
+        message_id: &str,
+        rating: Option<Rating>,
+        comment: Option<String>,
+    ) -> Result<ConversationMessage, String> {
+        let mut rated = None;
+        
+        {
+            let mut conversations = self.conversations.write().unwrap();
+            for (conversation_id, messages) in conversations.iter_mut() {
+                if let Some(msg) = messages.iter_mut().find(|m| m.message.id == message_id) {
+                    msg.message.set_feedback(rating.map(|rating| MessageFeedback {
+                        rating,
+                        comment: comment.clone(),
+                        rated_at: chrono::Utc::now(),
+                    }));
+                    rated = Some((conversation_id.clone(), msg.clone()));
+                    break;
+                }
+            }
+        }
+        
+        let (conversation_id, message) = rated.ok_or_else(|| format!("Message {} not found", message_id))?;
+        
+        // Ratings of enrolled conversations feed the experiment report
+        if let Err(e) = get_experiment_store().record_rating(&conversation_id, message_id, rating) {
+            warn!("Failed to record rating for experiment: {}", e);
+        }
+        
+        get_event_system().emit(
+            events::MESSAGE_RATED,
+            serde_json::json!({
+                "conversation_id": conversation_id,
+                "message_id": message_id,
+                "feedback": message.message.feedback(),
+            }),
+        );
+        self.notify_listeners(&conversation_id, &message);
+        Ok(message)
+    }
+    
+    /// Rated messages of all conversations with the prompts that produced them
+    pub fn feedback_records(&self) -> Vec<FeedbackRecord> {
+        self.conversations_with_messages()
+            .iter()
+            .flat_map(feedback::collect)
+            .collect()
+    }
+    
+    /// All active conversations with their message history
+    pub fn conversations_with_messages(&self) -> Vec<Conversation> {
+        let ids: Vec<String> = self.conversations.read().unwrap().keys().cloned().collect();
+        ids.iter().filter_map(|id| self.conversation_with_messages(id)).collect()
+    }
+    
+    /// A conversation with its message history
+    pub fn conversation_with_messages(&self, id: &str) -> Option<Conversation> {
+        let messages = self.conversations.read().unwrap().get(id)?.clone();
+        let mut conversation = self.mcp_service.get_conversation(id)?;
+        conversation.messages = messages.into_iter().map(|m| m.message.into()).collect();
+        Some(conversation)
+    }
+    
+    /// Workspace a conversation belongs to, which selects its content filters and memories
+    pub(crate) fn workspace_of(&self, conversation_id: &str) -> Option<String> {
+        self.get_conversation(conversation_id)
+            .and_then(|c| c.metadata.get(WORKSPACE_CONTEXT_KEY).and_then(|w| w.as_str()).map(str::to_string))
+    }
+    
+    /// Translation context of a conversation, carrying its language preference
+    fn translation_context(&self, conversation_id: &str) -> MiddlewareContext {
+        let mut ctx = MiddlewareContext::new(conversation_id);
+        if let Some(language) = self
+            .get_conversation(conversation_id)
+            .and_then(|c| c.metadata.get(LANGUAGE_METADATA_KEY).cloned())
+        {
+            ctx.values.insert(LANGUAGE_METADATA_KEY.to_string(), language);
+        }
+        ctx
+    }
+    
+    /// Add a message to a conversation without sending it, e.g. imported context
+    pub fn add_context_message(&self, conversation_id: &str, message: Message) -> ConversationMessage {
+        let conversation_message = ConversationMessage {
+            message,
+            parent_ids: Vec::new(),
+            completed_at: None,
+            partial_content: None,
+            status: MessageStatus::Complete,
+        };
+        self.add_message_to_history(conversation_id, conversation_message.clone());
+        conversation_message
+    }
+    
+    /// Put the passages of the conversation's knowledge bases found for a
+    /// message in its history ahead of it, in place of those found for the
+    /// last one
+    fn add_sources(&self, conversation_id: &str, message: &Message) -> Vec<Source> {
+        let names = self
+            .get_conversation(conversation_id)
+            .map(|c| citations::knowledge_bases(&c.metadata))
+            .unwrap_or_default();
+        if let Some(messages) = self.conversations.write().unwrap().get_mut(conversation_id) {
+            messages.retain(|m| {
+                !m.message
+                    .metadata
+                    .as_ref()
+                    .map_or(false, |metadata| metadata.contains_key(SOURCES_METADATA_KEY))
+            });
+        }
+        if names.is_empty() {
+            return Vec::new();
+        }
+        
+        let query = message.text_content().unwrap_or_default();
+        let sources = citations::find_sources(&get_knowledge_bases(), &names, query);
+        if !sources.is_empty() {
+            self.add_context_message(conversation_id, citations::sources_message(&sources).into());
+        }
+        sources
+    }
+    
     /// Send a message in a conversation
     pub async fn send_message(
         &self,
         conversation_id: &str,
         message: Message,
     ) -> Result<ConversationMessage, MessageError> {
+        self.send_message_with_context(conversation_id, message, &RequestContext::new())
+            .await
+    }
+    
+    /// Send a message, aborting when the context is canceled or its deadline passes
+    pub async fn send_message_with_context(
+        &self,
+        conversation_id: &str,
+        message: Message,
+        ctx: &RequestContext,
+    ) -> Result<ConversationMessage, MessageError> {
+        let sources = self.add_sources(conversation_id, &message);
+        
         // Store message in history with 'sending' status
         let conversation_message = ConversationMessage {
             message: message.clone(),
@@ -96,13 +339,18 @@ impl ChatService {
         };
         
         self.add_message_to_history(conversation_id, conversation_message.clone());
+        let workspace = self.workspace_of(conversation_id);
+        let mut translation = self.translation_context(conversation_id);
+        let message = translate_request(&mut translation, message).await;
         
         // Send message through MCP service
-        match self.mcp_service.send_message(conversation_id, message).await {
+        match ctx.run(self.mcp_service.send_message(conversation_id, message)).await {
             Ok(response) => {
+                let response = translate_reply(&translation, response).await;
+                
                 // Create response message
                 let response_message = ConversationMessage {
-                    message: response,
+                    message: cite_reply(&sources, filter_response(workspace.as_deref(), response)),
                     parent_ids: vec![conversation_message.message.id.clone()],
                     completed_at: Some(std::time::SystemTime::now()),
                     partial_content: None,
@@ -122,12 +370,12 @@ impl ChatService {
                 Ok(response_message)
             }
             Err(e) => {
-                // Update message status to failed
-                self.update_message_status(
-                    conversation_id,
-                    &conversation_message.message.id,
-                    MessageStatus::Failed,
-                );
+                // Update message status to failed or cancelled
+                let status = match e {
+                    MessageError::Cancelled => MessageStatus::Cancelled,
+                    _ => MessageStatus::Failed,
+                };
+                self.update_message_status(conversation_id, &conversation_message.message.id, status);
                 
                 Err(e)
             }
@@ -142,6 +390,7 @@ impl ChatService {
     ) -> Result<mpsc::Receiver<ConversationMessage>, MessageError> {
         // Create streaming channel for UI
         let (tx, rx) = mpsc::channel(32);
+        let sources = self.add_sources(conversation_id, &message);
         
         // Store message in history with 'sending' status
         let conversation_message = ConversationMessage {
@@ -153,6 +402,8 @@ impl ChatService {
         };
         
         self.add_message_to_history(conversation_id, conversation_message.clone());
+        let mut translation = self.translation_context(conversation_id);
+        let message = translate_request(&mut translation, message).await;
         
         // Start streaming through MCP service
         match self.mcp_service.stream_message(conversation_id, message).await {
@@ -193,6 +444,7 @@ impl ChatService {
                 // Handle streaming in a separate task
                 let conversations = self.conversations.clone();
                 let tx_clone = tx.clone();
+                let workspace = self.workspace_of(conversation_id);
                 let conversation_id = conversation_id.to_string();
                 
                 tokio::spawn(async move {
@@ -207,13 +459,17 @@ impl ChatService {
                                     // Append to full text
                                     full_text.push_str(text);
                                     
-                                    // Update response message
-                                    response_message.partial_content = Some(full_text.clone());
+                                    // Update response message, filtering the whole text so
+                                    // matches split across chunks are caught
                                     response_message.message.content.parts = vec![
                                         crate::models::messages::ContentType::Text {
                                             text: full_text.clone(),
                                         },
                                     ];
+                                    response_message.message =
+                                        filter_response(workspace.as_deref(), response_message.message.clone());
+                                    response_message.partial_content =
+                                        response_message.message.text_content().map(str::to_string);
                                     
                                     // Update in history
                                     {
@@ -260,6 +516,14 @@ impl ChatService {
                     
                     // If we got here, streaming is complete
                     if response_message.status == MessageStatus::Streaming {
+                        // Chunks arrive in the model's language; the complete reply is translated
+                        response_message.message = cite_reply(
+                            &sources,
+                            filter_response(
+                                workspace.as_deref(),
+                                translate_reply(&translation, response_message.message.clone()).await,
+                            ),
+                        );
                         response_message.status = MessageStatus::Complete;
                         response_message.completed_at = Some(std::time::SystemTime::now());
                         response_message.partial_content = None;
@@ -277,6 +541,8 @@ impl ChatService {
                             }
                         }
                         
+                        unfurl_links(&conversation_id, &response_message);
+                        
                         // Send final update to UI
                         let _ = tx_clone.send(response_message).await;
                     }
@@ -354,6 +620,11 @@ impl ChatService {
             conversation_messages.push(message.clone());
         }
         
+        // Streaming replies are unfurled once they complete
+        if message.status != MessageStatus::Streaming {
+            unfurl_links(conversation_id, &message);
+        }
+        
         // Notify listeners
         self.notify_listeners(conversation_id, &message);
     }
@@ -399,6 +670,61 @@ impl ChatService {
     }
 }
 
+/// Run the content filters over a reply before it is stored or shown
+fn filter_response(workspace: Option<&str>, message: Message) -> Message {
+    let mut filtered: mcp_common::models::Message = message.clone().into();
+    match filters::filter_message(workspace, &mut filtered) {
+        Ok(()) => filtered.into(),
+        Err(e) => {
+            warn!("Content filters failed: {}", e);
+            message
+        }
+    }
+}
+
+/// Translate a user message into the model's language when its conversation
+/// asks for it; the original is sent when translation fails
+async fn translate_request(ctx: &mut MiddlewareContext, message: Message) -> Message {
+    let mut translated: mcp_common::models::Message = message.clone().into();
+    match TranslationMiddleware::default().translate_request(ctx, &mut translated).await {
+        Ok(()) => translated.into(),
+        Err(e) => {
+            warn!("Translation failed, sending the original: {}", e);
+            message
+        }
+    }
+}
+
+/// Translate a reply into the user's language when its conversation asks for it
+async fn translate_reply(ctx: &MiddlewareContext, message: Message) -> Message {
+    let mut translated: mcp_common::models::Message = message.clone().into();
+    match TranslationMiddleware::default().translate_reply(ctx, &mut translated).await {
+        Ok(()) => translated.into(),
+        Err(e) => {
+            warn!("Translating the reply failed: {}", e);
+            message
+        }
+    }
+}
+
+/// Keep which knowledge base passages the statements of a reply are taken
+/// from in its metadata
+fn cite_reply(sources: &[Source], message: Message) -> Message {
+    let mut cited: mcp_common::models::Message = message.clone().into();
+    match citations::cite(&cited.text(), sources) {
+        Some(citations) => {
+            citations.attach(&mut cited);
+            cited.into()
+        }
+        None => message,
+    }
+}
+
+/// Fetch previews for the links in a message in the background
+fn unfurl_links(conversation_id: &str, message: &ConversationMessage) {
+    get_unfurler().unfurl_message(conversation_id, &message.message.clone().into());
+}
+
 /// Global chat service instance
 static CHAT_SERVICE: once_cell::sync::OnceCell<ChatService> = once_cell::sync::OnceCell::new();
 
@@ -121,6 +121,25 @@ impl McpService {
         self.conversations.read().unwrap().get(id).cloned()
     }
     
+    /// Replace a stored conversation
+    pub fn update_conversation(&self, conversation: Conversation) -> Result<(), String> {
+        let mut conversations = self.conversations.write().unwrap();
+        
+        match conversations.get_mut(&conversation.id) {
+            Some(existing) => {
+                *existing = conversation;
+                Ok(())
+            }
+            None => Err(format!("Conversation with ID {} not found", conversation.id)),
+        }
+    }
+    
+    /// Put back a conversation, e.g. when a deletion is undone
+    pub fn restore_conversation(&self, conversation: Conversation) {
+        let mut conversations = self.conversations.write().unwrap();
+        conversations.insert(conversation.id.clone(), conversation);
+    }
+    
     /// Delete a conversation
     pub fn delete_conversation(&self, id: &str) -> Result<(), String> {
         let mut conversations = self.conversations.write().unwrap();
@@ -143,11 +162,15 @@ impl McpService {
         }
         
         // Add conversation context in metadata
+        let mut metadata = HashMap::from([(
+            "conversation_id".to_string(),
+            serde_json::to_value(conversation_id).unwrap(),
+        )]);
+        if let Some(project_context) = crate::context::project_context(conversation_id) {
+            metadata.insert("project_context".to_string(), serde_json::json!(project_context));
+        }
         let message_with_context = Message {
-            metadata: Some(HashMap::from([(
-                "conversation_id".to_string(),
-                serde_json::to_value(conversation_id).unwrap(),
-            )])),
+            metadata: Some(metadata),
             ..message
         };
         
@@ -203,11 +226,15 @@ impl McpService {
         }
         
         // Add conversation context in metadata
+        let mut metadata = HashMap::from([(
+            "conversation_id".to_string(),
+            serde_json::to_value(conversation_id).unwrap(),
+        )]);
+        if let Some(project_context) = crate::context::project_context(conversation_id) {
+            metadata.insert("project_context".to_string(), serde_json::json!(project_context));
+        }
         let message_with_context = Message {
-            metadata: Some(HashMap::from([(
-                "conversation_id".to_string(),
-                serde_json::to_value(conversation_id).unwrap(),
-            )])),
+            metadata: Some(metadata),
             ..message.clone()
         };
         
@@ -3,6 +3,8 @@ pub mod api;
 pub mod auth;
 pub mod chat;
 pub mod mcp;
+pub mod supervisor;
+pub mod terminal;
 
 // Export key service types
 pub use ai::AiService;
@@ -10,3 +12,4 @@ pub use api::ApiService;
 pub use auth::AuthService;
 pub use chat::ChatService;
 pub use mcp::McpService;
+pub use supervisor::{get_supervisor, ServiceSupervisor, Subsystem};
@@ -0,0 +1,539 @@
+//! Supervision of the subsystems started with the app.
+//!
+//! Each subsystem is tracked as running, degraded or failed. Failed subsystems
+//! are retried with backoff, subsystems wait for the ones they depend on, and
+//! features gated by a failed subsystem are reported as disabled so the UI can
+//! hide them instead of erroring on use.
+
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use mcp_common::events::{get_event_bus, names, Topic};
+use mcp_common::protocol::backoff_delay;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Delay before the first automatic restart
+const RESTART_INITIAL_DELAY: Duration = Duration::from_secs(2);
+
+/// Longest delay between automatic restarts
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(300);
+
+/// Automatic restarts before a subsystem is left failed until restarted by hand
+const MAX_AUTO_RESTARTS: u32 = 5;
+
+/// State of a subsystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceState {
+    /// Being started
+    Starting,
+
+    /// Working normally
+    Running,
+
+    /// Working with reduced functionality
+    Degraded,
+
+    /// Not working; its features are disabled
+    Failed,
+}
+
+/// Health of a subsystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceHealth {
+    /// Subsystem name
+    pub name: String,
+
+    /// Current state
+    pub state: ServiceState,
+
+    /// Why the subsystem is degraded or failed
+    pub message: Option<String>,
+
+    /// Subsystems that must run before this one starts
+    pub depends_on: Vec<String>,
+
+    /// Features disabled while this subsystem is failed
+    pub features: Vec<String>,
+
+    /// Failed starts since it last ran
+    pub attempts: u32,
+
+    /// When the next automatic restart happens
+    pub next_retry_at: Option<DateTime<Utc>>,
+
+    /// When the state last changed
+    pub since: DateTime<Utc>,
+}
+
+/// Health of all subsystems, for the UIs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// Subsystems in start order
+    pub services: Vec<ServiceHealth>,
+
+    /// Features unavailable because a subsystem failed
+    pub disabled_features: Vec<String>,
+}
+
+/// Starts a subsystem; may be called again to restart it
+pub type StartFn = Arc<dyn Fn() -> Result<(), String> + Send + Sync>;
+
+/// A subsystem to supervise
+pub struct Subsystem {
+    name: String,
+    depends_on: Vec<String>,
+    features: Vec<String>,
+    start: StartFn,
+}
+
+impl Subsystem {
+    /// Create a subsystem started by a function
+    pub fn new(name: &str, start: impl Fn() -> Result<(), String> + Send + Sync + 'static) -> Self {
+        Self {
+            name: name.to_string(),
+            depends_on: Vec::new(),
+            features: Vec::new(),
+            start: Arc::new(start),
+        }
+    }
+
+    /// Start only once another subsystem runs
+    pub fn depends_on(mut self, name: &str) -> Self {
+        self.depends_on.push(name.to_string());
+        self
+    }
+
+    /// Disable a feature while this subsystem is failed
+    pub fn gates(mut self, feature: &str) -> Self {
+        self.features.push(feature.to_string());
+        self
+    }
+}
+
+struct Entry {
+    start: StartFn,
+    health: ServiceHealth,
+
+    /// Waiting for a dependency rather than failed itself
+    blocked: bool,
+
+    /// Bumped on manual restarts so pending automatic ones are dropped
+    generation: u64,
+}
+
+impl Entry {
+    fn set_state(&mut self, state: ServiceState, message: Option<String>) {
+        self.health.state = state;
+        self.health.message = message;
+        self.health.since = Utc::now();
+    }
+}
+
+/// Tracks and restarts the app's subsystems
+pub struct ServiceSupervisor {
+    entries: Mutex<Vec<Entry>>,
+}
+
+impl ServiceSupervisor {
+    /// Create a supervisor without subsystems
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add a subsystem and start it. Dependencies must be registered first.
+    pub fn register(self: &Arc<Self>, subsystem: Subsystem) -> ServiceHealth {
+        let name = subsystem.name.clone();
+        self.entries.lock().unwrap().push(Entry {
+            start: subsystem.start,
+            health: ServiceHealth {
+                name: subsystem.name,
+                state: ServiceState::Starting,
+                message: None,
+                depends_on: subsystem.depends_on,
+                features: subsystem.features,
+                attempts: 0,
+                next_retry_at: None,
+                since: Utc::now(),
+            },
+            blocked: false,
+            generation: 0,
+        });
+        self.start(&name);
+        self.service_health(&name).expect("Subsystem was just registered")
+    }
+
+    /// Restart a subsystem now, resetting its automatic restarts
+    pub fn restart(self: &Arc<Self>, name: &str) -> Result<ServiceHealth, String> {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = Self::find(&mut entries, name)?;
+            entry.health.attempts = 0;
+            entry.health.next_retry_at = None;
+            entry.generation += 1;
+        }
+        self.start(name);
+        self.service_health(name).ok_or_else(|| format!("Unknown service '{}'", name))
+    }
+
+    /// Record the outcome of a subsystem started outside the supervisor,
+    /// e.g. when the UI initializes it with a new configuration
+    pub fn report(self: &Arc<Self>, name: &str, result: Result<(), String>) {
+        let generation = {
+            let mut entries = self.entries.lock().unwrap();
+            match Self::find(&mut entries, name) {
+                Ok(entry) => {
+                    entry.generation += 1;
+                    entry.health.attempts = 0;
+                    entry.generation
+                }
+                Err(e) => {
+                    warn!("{}", e);
+                    return;
+                }
+            }
+        };
+        self.finish(name, generation, result);
+    }
+
+    /// Mark a running subsystem as working with reduced functionality
+    pub fn mark_degraded(&self, name: &str, reason: &str) {
+        self.update(name, |entry| {
+            if entry.health.state != ServiceState::Failed {
+                entry.set_state(ServiceState::Degraded, Some(reason.to_string()));
+            }
+        });
+    }
+
+    /// Mark a degraded subsystem as working normally again
+    pub fn mark_running(&self, name: &str) {
+        self.update(name, |entry| {
+            if entry.health.state == ServiceState::Degraded {
+                entry.set_state(ServiceState::Running, None);
+            }
+        });
+    }
+
+    /// Health of one subsystem
+    pub fn service_health(&self, name: &str) -> Option<ServiceHealth> {
+        let entries = self.entries.lock().unwrap();
+        entries.iter().find(|e| e.health.name == name).map(|e| e.health.clone())
+    }
+
+    /// Health of all subsystems
+    pub fn health(&self) -> HealthReport {
+        let entries = self.entries.lock().unwrap();
+        let mut disabled_features: Vec<String> = entries
+            .iter()
+            .filter(|e| matches!(e.health.state, ServiceState::Starting | ServiceState::Failed))
+            .flat_map(|e| e.health.features.iter().cloned())
+            .collect();
+        disabled_features.sort();
+        disabled_features.dedup();
+
+        HealthReport {
+            services: entries.iter().map(|e| e.health.clone()).collect(),
+            disabled_features,
+        }
+    }
+
+    /// Whether every subsystem gating a feature is up
+    pub fn is_feature_available(&self, feature: &str) -> bool {
+        !self.health().disabled_features.iter().any(|f| f == feature)
+    }
+
+    /// Fail with a readable error when a feature is unavailable
+    pub fn require(&self, feature: &str) -> Result<(), String> {
+        let entries = self.entries.lock().unwrap();
+        let failed = entries.iter().find(|e| {
+            matches!(e.health.state, ServiceState::Starting | ServiceState::Failed)
+                && e.health.features.iter().any(|f| f == feature)
+        });
+        match failed {
+            Some(entry) => Err(format!(
+                "{} is unavailable: {} {}",
+                feature,
+                entry.health.name,
+                entry.health.message.as_deref().unwrap_or("is not running"),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn find<'a>(entries: &'a mut [Entry], name: &str) -> Result<&'a mut Entry, String> {
+        entries
+            .iter_mut()
+            .find(|e| e.health.name == name)
+            .ok_or_else(|| format!("Unknown service '{}'", name))
+    }
+
+    /// Change an entry and publish its new health
+    fn update(&self, name: &str, change: impl FnOnce(&mut Entry)) {
+        let health = {
+            let mut entries = self.entries.lock().unwrap();
+            let Ok(entry) = Self::find(&mut entries, name) else {
+                return;
+            };
+            change(entry);
+            entry.health.clone()
+        };
+        Self::publish(&health);
+    }
+
+    fn start(self: &Arc<Self>, name: &str) {
+        let (start, generation) = {
+            let mut entries = self.entries.lock().unwrap();
+            let waiting_for = match entries.iter().find(|e| e.health.name == name) {
+                Some(entry) => entry.health.depends_on.iter().find(|dependency| {
+                    !entries.iter().any(|e| {
+                        &e.health.name == *dependency
+                            && matches!(e.health.state, ServiceState::Running | ServiceState::Degraded)
+                    })
+                }),
+                None => return,
+            }
+            .cloned();
+
+            let Ok(entry) = Self::find(&mut entries, name) else {
+                return;
+            };
+            if let Some(dependency) = waiting_for {
+                // Started again once the dependency runs
+                entry.blocked = true;
+                entry.health.next_retry_at = None;
+                entry.set_state(ServiceState::Failed, Some(format!("waiting for {}", dependency)));
+                let health = entry.health.clone();
+                drop(entries);
+                warn!("Not starting {}: {} is not running", name, dependency);
+                Self::publish(&health);
+                return;
+            }
+
+            entry.blocked = false;
+            entry.set_state(ServiceState::Starting, None);
+            (entry.start.clone(), entry.generation)
+        };
+
+        // Started without the lock, so the subsystem can mark itself degraded
+        let result = start();
+        self.finish(name, generation, result);
+    }
+
+    fn finish(self: &Arc<Self>, name: &str, generation: u64, result: Result<(), String>) {
+        let (health, dependents) = {
+            let mut entries = self.entries.lock().unwrap();
+            let Ok(entry) = Self::find(&mut entries, name) else {
+                return;
+            };
+            if entry.generation != generation {
+                // Restarted by hand meanwhile
+                return;
+            }
+
+            match result {
+                Ok(()) => {
+                    info!("Service {} started", name);
+                    entry.health.attempts = 0;
+                    entry.health.next_retry_at = None;
+                    if entry.health.state != ServiceState::Degraded {
+                        entry.set_state(ServiceState::Running, None);
+                    }
+                }
+                Err(e) => {
+                    error!("Service {} failed to start: {}", name, e);
+                    entry.health.attempts += 1;
+                    entry.set_state(ServiceState::Failed, Some(e));
+                    entry.health.next_retry_at = if entry.health.attempts <= MAX_AUTO_RESTARTS {
+                        self.schedule_restart(name, entry.health.attempts, generation)
+                    } else {
+                        None
+                    };
+                }
+            }
+            let health = entry.health.clone();
+
+            // Dependents waiting for this one can start now; running ones lose it
+            let running = health.state != ServiceState::Failed;
+            let mut dependents = Vec::new();
+            for dependent in entries.iter_mut().filter(|e| e.health.depends_on.iter().any(|d| d == name)) {
+                if running && dependent.blocked {
+                    dependents.push(dependent.health.name.clone());
+                } else if !running && dependent.health.state == ServiceState::Running {
+                    dependent.set_state(ServiceState::Degraded, Some(format!("{} failed", name)));
+                    Self::publish(&dependent.health);
+                }
+            }
+            (health, dependents)
+        };
+
+        Self::publish(&health);
+        for dependent in dependents {
+            self.start(&dependent);
+        }
+    }
+
+    /// Restart a failed subsystem after a backoff delay. Returns when, or
+    /// `None` outside a Tokio runtime.
+    fn schedule_restart(self: &Arc<Self>, name: &str, attempt: u32, generation: u64) -> Option<DateTime<Utc>> {
+        let runtime = tokio::runtime::Handle::try_current().ok()?;
+        let delay = backoff_delay(attempt - 1, RESTART_INITIAL_DELAY, RESTART_MAX_DELAY);
+        let supervisor = self.clone();
+        let name = name.to_string();
+        runtime.spawn(async move {
+            tokio::time::sleep(delay).await;
+            let current = {
+                let entries = supervisor.entries.lock().unwrap();
+                entries
+                    .iter()
+                    .find(|e| e.health.name == name)
+                    .map_or(false, |e| e.generation == generation && e.health.state == ServiceState::Failed)
+            };
+            if current {
+                info!("Restarting service {} (attempt {})", name, attempt + 1);
+                supervisor.start(&name);
+            }
+        });
+        Some(Utc::now() + chrono::Duration::from_std(delay).ok()?)
+    }
+
+    fn publish(health: &ServiceHealth) {
+        get_event_bus().emit(
+            Topic::System,
+            names::SERVICE_HEALTH_CHANGED,
+            serde_json::to_value(health).unwrap_or_default(),
+        );
+    }
+}
+
+impl Default for ServiceSupervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Supervisor of the app's subsystems
+    static ref SUPERVISOR: Arc<ServiceSupervisor> = Arc::new(ServiceSupervisor::new());
+}
+
+/// Get the app's service supervisor
+pub fn get_supervisor() -> Arc<ServiceSupervisor> {
+    SUPERVISOR.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    fn flaky(ok: Arc<AtomicBool>) -> impl Fn() -> Result<(), String> + Send + Sync {
+        move || {
+            if ok.load(Ordering::SeqCst) {
+                Ok(())
+            } else {
+                Err("port in use".to_string())
+            }
+        }
+    }
+
+    #[test]
+    fn failed_subsystems_disable_their_features() {
+        let supervisor = Arc::new(ServiceSupervisor::new());
+        supervisor.register(Subsystem::new("security", || Ok(())).gates("credentials"));
+        let health = supervisor.register(Subsystem::new("collaboration", || Err("no server".to_string())).gates("collaboration"));
+
+        assert_eq!(health.state, ServiceState::Failed);
+        assert_eq!(health.attempts, 1);
+        // No runtime in this test, so nothing is scheduled
+        assert!(health.next_retry_at.is_none());
+        assert!(supervisor.is_feature_available("credentials"));
+        assert!(!supervisor.is_feature_available("collaboration"));
+        assert!(supervisor.require("collaboration").unwrap_err().contains("no server"));
+        assert_eq!(supervisor.health().disabled_features, vec!["collaboration".to_string()]);
+    }
+
+    #[test]
+    fn dependents_wait_for_their_dependencies() {
+        let supervisor = Arc::new(ServiceSupervisor::new());
+        let security_ok = Arc::new(AtomicBool::new(false));
+        let starts = Arc::new(AtomicUsize::new(0));
+        let counter = starts.clone();
+
+        supervisor.register(Subsystem::new("security", flaky(security_ok.clone())));
+        let health = supervisor.register(
+            Subsystem::new("collaboration", move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            })
+            .depends_on("security")
+            .gates("collaboration"),
+        );
+        assert_eq!(health.state, ServiceState::Failed);
+        assert_eq!(health.message.as_deref(), Some("waiting for security"));
+        assert_eq!(starts.load(Ordering::SeqCst), 0);
+
+        security_ok.store(true, Ordering::SeqCst);
+        supervisor.restart("security").unwrap();
+        assert_eq!(supervisor.service_health("collaboration").unwrap().state, ServiceState::Running);
+        assert_eq!(starts.load(Ordering::SeqCst), 1);
+        assert!(supervisor.is_feature_available("collaboration"));
+    }
+
+    #[test]
+    fn running_dependents_degrade_when_a_dependency_fails() {
+        let supervisor = Arc::new(ServiceSupervisor::new());
+        let security_ok = Arc::new(AtomicBool::new(true));
+        supervisor.register(Subsystem::new("security", flaky(security_ok.clone())));
+        supervisor.register(Subsystem::new("collaboration", || Ok(())).depends_on("security").gates("collaboration"));
+
+        security_ok.store(false, Ordering::SeqCst);
+        supervisor.restart("security").unwrap();
+        let health = supervisor.service_health("collaboration").unwrap();
+        assert_eq!(health.state, ServiceState::Degraded);
+        assert_eq!(health.message.as_deref(), Some("security failed"));
+        // Degraded subsystems keep their features
+        assert!(supervisor.is_feature_available("collaboration"));
+    }
+
+    #[test]
+    fn subsystems_can_report_degraded_while_starting() {
+        let supervisor = Arc::new(ServiceSupervisor::new());
+        let handle = supervisor.clone();
+        let health = supervisor.register(Subsystem::new("performance_monitor", move || {
+            handle.mark_degraded("performance_monitor", "GPU counters unavailable");
+            Ok(())
+        }));
+        assert_eq!(health.state, ServiceState::Degraded);
+
+        supervisor.mark_running("performance_monitor");
+        assert_eq!(supervisor.service_health("performance_monitor").unwrap().state, ServiceState::Running);
+    }
+
+    #[test]
+    fn reported_outcomes_replace_the_startup_result() {
+        let supervisor = Arc::new(ServiceSupervisor::new());
+        supervisor.register(Subsystem::new("collaboration", || Err("disabled".to_string())).gates("collaboration"));
+
+        supervisor.report("collaboration", Ok(()));
+        let health = supervisor.service_health("collaboration").unwrap();
+        assert_eq!(health.state, ServiceState::Running);
+        assert_eq!(health.attempts, 0);
+        assert!(supervisor.restart("unknown").is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_subsystems_restart_with_backoff() {
+        let supervisor = Arc::new(ServiceSupervisor::new());
+        let ok = Arc::new(AtomicBool::new(false));
+        let health = supervisor.register(Subsystem::new("telemetry", flaky(ok.clone())));
+        assert!(health.next_retry_at.is_some());
+
+        ok.store(true, Ordering::SeqCst);
+        tokio::time::sleep(RESTART_INITIAL_DELAY * 2).await;
+        tokio::task::yield_now().await;
+        assert_eq!(supervisor.service_health("telemetry").unwrap().state, ServiceState::Running);
+    }
+}
@@ -0,0 +1,310 @@
+use crate::utils::events::{events, get_event_system};
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+/// Bytes of output kept per session so a view attaching late sees recent history
+const SCROLLBACK_BYTES: usize = 64 * 1024;
+
+/// Options for a new terminal session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TerminalOptions {
+    /// Program to run (default: the user's shell)
+    #[serde(default)]
+    pub command: Option<String>,
+
+    /// Program arguments
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Working directory
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+
+    /// Extra environment variables
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    /// Columns (default 80)
+    #[serde(default)]
+    pub cols: Option<u16>,
+
+    /// Rows (default 24)
+    #[serde(default)]
+    pub rows: Option<u16>,
+
+    /// Label shown in the terminal tab
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// Output of a terminal session
+#[derive(Debug, Clone)]
+pub enum TerminalEvent {
+    /// Raw output, ANSI sequences included
+    Output(String),
+    /// The process exited with this code
+    Exit(Option<u32>),
+}
+
+/// Summary of a terminal session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalInfo {
+    /// Session ID
+    pub id: String,
+
+    /// Label shown in the terminal tab
+    pub title: String,
+
+    /// Current size as (cols, rows)
+    pub size: (u16, u16),
+
+    /// Exit code once the process has exited
+    pub exit_code: Option<u32>,
+
+    /// Whether the process has exited
+    pub exited: bool,
+}
+
+struct TerminalSession {
+    info: Mutex<TerminalInfo>,
+    master: Mutex<Box<dyn MasterPty + Send>>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    killer: Mutex<Box<dyn ChildKiller + Send + Sync>>,
+    scrollback: Mutex<VecDeque<u8>>,
+    tx: broadcast::Sender<TerminalEvent>,
+}
+
+/// Manager for pseudo-terminal sessions embedded in the app
+pub struct PtyManager {
+    sessions: Mutex<HashMap<String, Arc<TerminalSession>>>,
+}
+
+impl PtyManager {
+    /// Create a new manager
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Default shell for the platform
+    fn default_shell() -> String {
+        if cfg!(windows) {
+            std::env::var("COMSPEC").unwrap_or_else(|_| "powershell.exe".to_string())
+        } else {
+            std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string())
+        }
+    }
+
+    /// Start a session and return its ID
+    pub fn create(&self, options: TerminalOptions) -> Result<String, String> {
+        let size = PtySize {
+            rows: options.rows.unwrap_or(24),
+            cols: options.cols.unwrap_or(80),
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+
+        let pair = native_pty_system()
+            .openpty(size)
+            .map_err(|e| format!("Failed to open PTY: {}", e))?;
+
+        let program = options.command.clone().unwrap_or_else(Self::default_shell);
+        let mut command = CommandBuilder::new(&program);
+        command.args(&options.args);
+        if let Some(cwd) = &options.cwd {
+            command.cwd(cwd);
+        }
+        for (key, value) in &options.env {
+            command.env(key, value);
+        }
+        command.env("TERM", "xterm-256color");
+
+        let mut child = pair
+            .slave
+            .spawn_command(command)
+            .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+        // The slave end belongs to the child now
+        drop(pair.slave);
+
+        let mut reader = pair.master.try_clone_reader().map_err(|e| e.to_string())?;
+        let writer = pair.master.take_writer().map_err(|e| e.to_string())?;
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let (tx, _) = broadcast::channel(256);
+        let session = Arc::new(TerminalSession {
+            info: Mutex::new(TerminalInfo {
+                id: id.clone(),
+                title: options.title.clone().unwrap_or_else(|| program.clone()),
+                size: (size.cols, size.rows),
+                exit_code: None,
+                exited: false,
+            }),
+            master: Mutex::new(pair.master),
+            writer: Mutex::new(writer),
+            killer: Mutex::new(child.clone_killer()),
+            scrollback: Mutex::new(VecDeque::with_capacity(SCROLLBACK_BYTES)),
+            tx,
+        });
+
+        self.sessions.lock().unwrap().insert(id.clone(), session.clone());
+
+        // PTY reads block, so pump output on a dedicated thread
+        let session_id = id.clone();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+            let mut pending = Vec::new();
+
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        {
+                            let mut scrollback = session.scrollback.lock().unwrap();
+                            scrollback.extend(&buf[..n]);
+                            let excess = scrollback.len().saturating_sub(SCROLLBACK_BYTES);
+                            scrollback.drain(..excess);
+                        }
+
+                        let data = decode_utf8(&mut pending, &buf[..n]);
+                        if data.is_empty() {
+                            continue;
+                        }
+                        let _ = session.tx.send(TerminalEvent::Output(data.clone()));
+                        get_event_system().emit(
+                            events::TERMINAL_OUTPUT,
+                            serde_json::json!({ "session_id": session_id, "data": data }),
+                        );
+                    }
+                }
+            }
+
+            let exit_code = child.wait().ok().map(|status| status.exit_code());
+            {
+                let mut info = session.info.lock().unwrap();
+                info.exited = true;
+                info.exit_code = exit_code;
+            }
+            let _ = session.tx.send(TerminalEvent::Exit(exit_code));
+            get_event_system().emit(
+                events::TERMINAL_EXITED,
+                serde_json::json!({ "session_id": session_id, "exit_code": exit_code }),
+            );
+            debug!("Terminal session {} exited with {:?}", session_id, exit_code);
+        });
+
+        info!("Started terminal session {} running {}", id, program);
+        get_event_system().emit(
+            events::TERMINAL_CREATED,
+            serde_json::json!({ "session_id": id, "title": options.title.unwrap_or(program) }),
+        );
+
+        Ok(id)
+    }
+
+    fn session(&self, id: &str) -> Result<Arc<TerminalSession>, String> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("Terminal session {} not found", id))
+    }
+
+    /// Send input to a session
+    pub fn write(&self, id: &str, data: &[u8]) -> Result<(), String> {
+        let session = self.session(id)?;
+        let mut writer = session.writer.lock().unwrap();
+        writer
+            .write_all(data)
+            .and_then(|_| writer.flush())
+            .map_err(|e| format!("Failed to write to terminal: {}", e))
+    }
+
+    /// Resize a session
+    pub fn resize(&self, id: &str, cols: u16, rows: u16) -> Result<(), String> {
+        let session = self.session(id)?;
+        session
+            .master
+            .lock()
+            .unwrap()
+            .resize(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| format!("Failed to resize terminal: {}", e))?;
+        session.info.lock().unwrap().size = (cols, rows);
+        Ok(())
+    }
+
+    /// Recent output and a receiver for everything after it
+    pub fn subscribe(&self, id: &str) -> Result<(String, broadcast::Receiver<TerminalEvent>), String> {
+        let session = self.session(id)?;
+        // Hold the scrollback lock so no output slips between snapshot and subscription
+        let scrollback = session.scrollback.lock().unwrap();
+        let rx = session.tx.subscribe();
+        let (front, back) = scrollback.as_slices();
+        let history = String::from_utf8_lossy(&[front, back].concat()).into_owned();
+        Ok((history, rx))
+    }
+
+    /// Kill a session's process and forget it
+    pub fn close(&self, id: &str) -> Result<(), String> {
+        let session = self
+            .sessions
+            .lock()
+            .unwrap()
+            .remove(id)
+            .ok_or_else(|| format!("Terminal session {} not found", id))?;
+
+        if !session.info.lock().unwrap().exited {
+            if let Err(e) = session.killer.lock().unwrap().kill() {
+                warn!("Failed to kill terminal session {}: {}", id, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// All sessions
+    pub fn list(&self) -> Vec<TerminalInfo> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .values()
+            .map(|s| s.info.lock().unwrap().clone())
+            .collect()
+    }
+}
+
+/// Decode a chunk, carrying an incomplete trailing UTF-8 sequence over to the next read
+fn decode_utf8(pending: &mut Vec<u8>, chunk: &[u8]) -> String {
+    pending.extend_from_slice(chunk);
+
+    let valid_up_to = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        // `error_len() == None` means the input ended mid-sequence
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+
+    let rest = pending.split_off(valid_up_to);
+    let text = String::from_utf8_lossy(pending).into_owned();
+    *pending = rest;
+    text
+}
+
+static PTY_MANAGER: Lazy<PtyManager> = Lazy::new(PtyManager::new);
+
+/// Get the global PTY manager
+pub fn get_pty_manager() -> &'static PtyManager {
+    &PTY_MANAGER
+}
@@ -1,5 +1,7 @@
 use lazy_static::lazy_static;
 use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tauri::{Manager, Window};
@@ -37,6 +39,170 @@ pub enum LoadState {
     Error(String),
 }
 
+/// Total startup budget before the shell is considered slow (in milliseconds)
+const TOTAL_STARTUP_BUDGET_MS: u64 = 1000;
+
+lazy_static! {
+    /// Global startup tracker, populated by the shell loader during launch
+    static ref STARTUP_TRACKER: Arc<Mutex<StartupTracker>> = Arc::new(Mutex::new(StartupTracker::new()));
+}
+
+/// Timing record for a single startup subsystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartupPhase {
+    /// Subsystem name (e.g. "config", "mcp_service")
+    pub name: String,
+    
+    /// Offset from startup begin when the phase started (in milliseconds)
+    pub started_at_ms: u64,
+    
+    /// Phase duration (in milliseconds)
+    pub duration_ms: u64,
+    
+    /// Budget allotted to this phase (in milliseconds)
+    pub budget_ms: u64,
+    
+    /// Whether the phase was deferred until after first paint
+    pub deferred: bool,
+    
+    /// Whether the phase exceeded its budget
+    pub over_budget: bool,
+}
+
+/// Summary of the startup process returned to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartupReport {
+    /// Time until the shell was visible (first paint, in milliseconds)
+    pub first_paint_ms: Option<u64>,
+    
+    /// Time until everything was loaded (in milliseconds)
+    pub fully_loaded_ms: Option<u64>,
+    
+    /// Total startup budget (in milliseconds)
+    pub total_budget_ms: u64,
+    
+    /// Per-subsystem timings in the order they completed
+    pub phases: Vec<StartupPhase>,
+    
+    /// Human readable warnings for exceeded budgets
+    pub warnings: Vec<String>,
+}
+
+/// Records per-subsystem startup timings and checks them against budgets
+pub struct StartupTracker {
+    /// When startup began
+    start_time: Option<Instant>,
+    
+    /// Time until first paint
+    first_paint: Option<Duration>,
+    
+    /// Time until fully loaded
+    fully_loaded: Option<Duration>,
+    
+    /// Completed phases
+    phases: Vec<StartupPhase>,
+    
+    /// Budget warnings raised so far
+    warnings: Vec<String>,
+}
+
+impl StartupTracker {
+    /// Create a new, empty tracker
+    pub fn new() -> Self {
+        Self {
+            start_time: None,
+            first_paint: None,
+            fully_loaded: None,
+            phases: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+    
+    /// Reset the tracker and mark the beginning of startup
+    pub fn begin(&mut self) {
+        *self = Self::new();
+        self.start_time = Some(Instant::now());
+    }
+    
+    /// Offset from startup begin
+    fn offset(&self) -> Duration {
+        self.start_time.map(|start| start.elapsed()).unwrap_or_default()
+    }
+    
+    /// Record a completed phase
+    pub fn record_phase(&mut self, name: &str, started: Instant, budget_ms: u64, deferred: bool) {
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let started_at_ms = self
+            .start_time
+            .map(|start| started.saturating_duration_since(start).as_millis() as u64)
+            .unwrap_or(0);
+        let over_budget = duration_ms > budget_ms;
+        
+        if over_budget {
+            let warning = format!(
+                "Startup phase '{}' took {}ms (budget {}ms)",
+                name, duration_ms, budget_ms
+            );
+            warn!("{}", warning);
+            self.warnings.push(warning);
+        } else {
+            debug!("Startup phase '{}' completed in {}ms", name, duration_ms);
+        }
+        
+        self.phases.push(StartupPhase {
+            name: name.to_string(),
+            started_at_ms,
+            duration_ms,
+            budget_ms,
+            deferred,
+            over_budget,
+        });
+    }
+    
+    /// Mark the moment the shell became visible
+    pub fn mark_first_paint(&mut self) {
+        self.first_paint = Some(self.offset());
+    }
+    
+    /// Mark the moment all subsystems finished loading
+    pub fn mark_fully_loaded(&mut self) {
+        let elapsed = self.offset();
+        self.fully_loaded = Some(elapsed);
+        
+        if elapsed.as_millis() as u64 > TOTAL_STARTUP_BUDGET_MS {
+            let warning = format!(
+                "Startup took {}ms (budget {}ms)",
+                elapsed.as_millis(),
+                TOTAL_STARTUP_BUDGET_MS
+            );
+            warn!("{}", warning);
+            self.warnings.push(warning);
+        }
+    }
+    
+    /// Build a report of the recorded timings
+    pub fn report(&self) -> StartupReport {
+        StartupReport {
+            first_paint_ms: self.first_paint.map(|d| d.as_millis() as u64),
+            fully_loaded_ms: self.fully_loaded.map(|d| d.as_millis() as u64),
+            total_budget_ms: TOTAL_STARTUP_BUDGET_MS,
+            phases: self.phases.clone(),
+            warnings: self.warnings.clone(),
+        }
+    }
+}
+
+impl Default for StartupTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Get the report for the current (or last) startup
+pub fn startup_report() -> StartupReport {
+    STARTUP_TRACKER.lock().unwrap().report()
+}
+
 /// Structure to manage application loading state and process
 pub struct ShellLoader {
     /// Current loading state
@@ -82,6 +248,7 @@ impl ShellLoader {
         let (tx, rx) = mpsc::channel(16);
         self.tx = Some(tx);
         self.start_time = Some(Instant::now());
+        STARTUP_TRACKER.lock().unwrap().begin();
         
         let state_clone = self.state.clone();
         let tx_clone = self.tx.clone().unwrap();
@@ -107,14 +274,12 @@ impl ShellLoader {
         // Update state to shell loading
         Self::update_state(&state, &tx, LoadState::ShellLoading).await;
         
-        // Wait for minimal shell to be ready (simulated here)
-        tokio::time::sleep(Duration::from_millis(20)).await;
-        
         // Shell is ready, make window visible
         Self::update_state(&state, &tx, LoadState::ShellReady).await;
         if let Some(window) = &window {
             window.show().unwrap();
         }
+        STARTUP_TRACKER.lock().unwrap().mark_first_paint();
         
         // Load core services
         Self::update_state(&state, &tx, LoadState::CoreServicesLoading).await;
@@ -134,47 +299,67 @@ impl ShellLoader {
                 Self::load_secondary_features(&feature_flags).await;
                 
                 // Everything is loaded
+                STARTUP_TRACKER.lock().unwrap().mark_fully_loaded();
                 Self::update_state(&state, &tx, LoadState::FullyLoaded).await;
             });
         } else {
             // Load everything synchronously
             Self::update_state(&state, &tx, LoadState::SecondaryLoading).await;
             Self::load_secondary_features(&feature_flags).await;
+            STARTUP_TRACKER.lock().unwrap().mark_fully_loaded();
             Self::update_state(&state, &tx, LoadState::FullyLoaded).await;
         }
     }
     
+    /// Run a subsystem's initialization as a startup phase and record its timing
+    async fn run_phase<T>(name: &str, budget_ms: u64, deferred: bool, init: impl Future<Output = T>) -> T {
+        let started = Instant::now();
+        debug!("Initializing {}: {}", if deferred { "deferred service" } else { "core service" }, name);
+        
+        let output = init.await;
+        
+        STARTUP_TRACKER
+            .lock()
+            .unwrap()
+            .record_phase(name, started, budget_ms, deferred);
+        output
+    }
+    
     /// Initialize core services required for basic functionality
-    async fn initialize_core_services(feature_flags: &FeatureFlags) {
-        // Simulate initialization of essential services
-        let services = [
-            ("config", 30),
-            ("auth", 40),
-            ("api", 50),
-        ];
-        
-        for (service, delay_ms) in services {
-            debug!("Initializing core service: {}", service);
-            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
-        }
+    async fn initialize_core_services(_feature_flags: &FeatureFlags) {
+        Self::run_phase("config", 50, false, async {
+            mcp_common::config::get_settings();
+        })
+        .await;
+        
+        Self::run_phase("mcp_service", 100, false, async {
+            mcp_common::get_mcp_service();
+        })
+        .await;
     }
     
-    /// Load secondary features that are not needed for initial startup
+    /// Load secondary features that are not needed for initial startup.
+    ///
+    /// These are deferred until after first paint so they never delay the shell.
     async fn load_secondary_features(feature_flags: &FeatureFlags) {
+        Self::run_phase("offline_manager", 150, true, async {
+            crate::offline::get_offline_manager().start();
+        })
+        .await;
+        
         // Only load features that are enabled in feature flags
         if feature_flags.contains(FeatureFlags::PLUGINS) {
-            debug!("Loading plugins");
-            tokio::time::sleep(Duration::from_millis(100)).await;
-        }
-        
-        if feature_flags.contains(FeatureFlags::ADVANCED_UI) {
-            debug!("Loading advanced UI components");
-            tokio::time::sleep(Duration::from_millis(150)).await;
+            Self::run_phase("plugin_runtime", 200, true, crate::plugins::init_plugin_manager()).await;
         }
         
         if feature_flags.contains(FeatureFlags::HISTORY) {
-            debug!("Loading conversation history");
-            tokio::time::sleep(Duration::from_millis(80)).await;
+            let conversations = Self::run_phase("history", 120, true, async {
+                mcp_common::config::get_storage_manager().list_conversations()
+            })
+            .await;
+            if let Err(e) = conversations {
+                warn!("Failed to load conversation history: {}", e);
+            }
         }
     }
     
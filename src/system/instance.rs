@@ -0,0 +1,289 @@
+//! Single-instance enforcement
+//!
+//! The first instance listens on a loopback port and records it, with a
+//! random token, in `instance.json`. Later launches hand their arguments
+//! (shared files, `papin://` links) to it and exit, and the running
+//! instance brings its window up. `--new-instance` opts out, for running
+//! side by side with another profile.
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::share::{self, SharedContent};
+use crate::deeplink;
+
+/// Launch argument that starts a separate instance
+pub const NEW_INSTANCE_ARG: &str = "--new-instance";
+
+/// File the running instance records its port and token in
+const INSTANCE_FILE: &str = "instance.json";
+
+/// How long a launch waits for the running instance
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Largest message accepted from another launch
+const MAX_MESSAGE_BYTES: u64 = 256 * 1024;
+
+/// Where to find the running instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct InstanceInfo {
+    port: u16,
+    token: String,
+    pid: u32,
+}
+
+/// Arguments a later launch hands over
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Forwarded {
+    token: String,
+
+    /// Launch arguments, without the program name
+    pub args: Vec<String>,
+
+    /// Working directory of the launch, to resolve relative file paths
+    pub cwd: Option<PathBuf>,
+}
+
+/// How this launch goes on
+pub enum Startup {
+    /// Another instance took the arguments; exit
+    Forwarded,
+    /// This is the running instance; serve later launches once the app is up
+    Primary(Primary),
+    /// `--new-instance`, or the instance file couldn't be written
+    Separate,
+}
+
+/// Decide whether this launch runs the app or hands off to a running instance
+pub fn start(args: &[String]) -> Startup {
+    if args.iter().any(|arg| arg == NEW_INSTANCE_ARG) {
+        info!("Starting a separate instance");
+        return Startup::Separate;
+    }
+
+    let path = mcp_common::config::data_path(INSTANCE_FILE);
+    let cwd = std::env::current_dir().ok();
+    if forward(&path, args, cwd.as_deref()) {
+        return Startup::Forwarded;
+    }
+
+    match Primary::claim(&path) {
+        Ok(primary) => Startup::Primary(primary),
+        Err(e) => {
+            warn!("Running without single-instance detection: {}", e);
+            Startup::Separate
+        }
+    }
+}
+
+/// Hand arguments to the instance recorded in `path`; false when none answers
+fn forward(path: &Path, args: &[String], cwd: Option<&Path>) -> bool {
+    let Some(info) = fs::read(path).ok().and_then(|data| serde_json::from_slice::<InstanceInfo>(&data).ok()) else {
+        return false;
+    };
+
+    let send = || -> io::Result<bool> {
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, info.port));
+        let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+
+        let message = Forwarded {
+            token: info.token.clone(),
+            args: args.to_vec(),
+            cwd: cwd.map(Path::to_path_buf),
+        };
+        serde_json::to_writer(&mut stream, &message)?;
+        stream.write_all(b"\n")?;
+
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        Ok(reply.trim() == "ok")
+    };
+
+    match send() {
+        Ok(true) => {
+            info!("Passed the launch to the running instance (pid {})", info.pid);
+            true
+        }
+        Ok(false) => false,
+        Err(e) => {
+            // A stale file from an instance that didn't shut down cleanly
+            debug!("No running instance answered: {}", e);
+            false
+        }
+    }
+}
+
+/// The running instance's listener
+pub struct Primary {
+    listener: TcpListener,
+    token: String,
+    path: PathBuf,
+}
+
+impl Primary {
+    /// Listen on a loopback port and record it for later launches
+    fn claim(path: &Path) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let info = InstanceInfo {
+            port: listener.local_addr()?.port(),
+            token: uuid::Uuid::new_v4().to_string(),
+            pid: std::process::id(),
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_vec(&info)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+
+        Ok(Self {
+            listener,
+            token: info.token,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Wait for the next launch with the right token
+    fn next(&self) -> io::Result<Forwarded> {
+        loop {
+            let (stream, _) = self.listener.accept()?;
+            stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+
+            let mut line = String::new();
+            if let Err(e) = BufReader::new((&stream).take(MAX_MESSAGE_BYTES)).read_line(&mut line) {
+                debug!("Failed to read from another launch: {}", e);
+                continue;
+            }
+            match serde_json::from_str::<Forwarded>(&line) {
+                Ok(message) if message.token == self.token => {
+                    let _ = (&stream).write_all(b"ok\n");
+                    return Ok(message);
+                }
+                _ => {
+                    warn!("Ignoring a connection without the instance token");
+                    let _ = (&stream).write_all(b"denied\n");
+                }
+            }
+        }
+    }
+
+    /// Open what later launches pass on, for as long as the app runs
+    pub fn serve(self, app: AppHandle) {
+        std::thread::spawn(move || loop {
+            match self.next() {
+                Ok(message) => {
+                    info!("Another launch passed {} argument(s)", message.args.len());
+                    focus(&app);
+                    open_args(&app, &message.args, message.cwd.as_deref());
+                }
+                Err(e) => {
+                    warn!("Stopped listening for other launches: {}", e);
+                    self.release();
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Remove the instance file, unless another instance took it over
+    pub fn release(&self) {
+        let ours = fs::read(&self.path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<InstanceInfo>(&data).ok())
+            .map_or(false, |info| info.token == self.token);
+        if ours {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn focus(app: &AppHandle) {
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Open shared content and links among launch arguments. Relative file
+/// paths are resolved against the launch's working directory.
+pub fn open_args(app: &AppHandle, args: &[String], cwd: Option<&Path>) {
+    if let Some(mut content) = SharedContent::from_args(args.iter().cloned()) {
+        if let Some(cwd) = cwd {
+            content.files = content.files.into_iter().map(|file| cwd.join(file)).collect();
+        }
+        share::deliver(app, &content);
+    }
+    deeplink::handle_args(app, args.iter().cloned());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forward_to_primary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(INSTANCE_FILE);
+        let primary = Primary::claim(&path).unwrap();
+
+        let args = vec!["papin://ask?text=hi".to_string()];
+        let client = {
+            let path = path.clone();
+            let args = args.clone();
+            std::thread::spawn(move || forward(&path, &args, Some(Path::new("/work"))))
+        };
+
+        let message = primary.next().unwrap();
+        assert_eq!(message.args, args);
+        assert_eq!(message.cwd.as_deref(), Some(Path::new("/work")));
+        assert!(client.join().unwrap());
+
+        primary.release();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_wrong_token_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(INSTANCE_FILE);
+        let primary = Primary::claim(&path).unwrap();
+
+        // A client with a stale token is turned away, then a real one gets through
+        let port = primary.listener.local_addr().unwrap().port();
+        let forged = dir.path().join("forged.json");
+        fs::write(&forged, format!(r#"{{"port": {}, "token": "guess", "pid": 1}}"#, port)).unwrap();
+        let clients = std::thread::spawn(move || {
+            let refused = forward(&forged, &["--share-text".to_string(), "x".to_string()], None);
+            let accepted = forward(&path, &[], None);
+            (refused, accepted)
+        });
+
+        assert!(primary.next().unwrap().args.is_empty());
+        assert_eq!(clients.join().unwrap(), (false, true));
+    }
+
+    #[test]
+    fn test_stale_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(INSTANCE_FILE);
+        assert!(!forward(&path, &[], None));
+
+        // Nothing listens on the recorded port any more
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap().port();
+        fs::write(&path, format!(r#"{{"port": {}, "token": "t", "pid": 1}}"#, port)).unwrap();
+        assert!(!forward(&path, &[], None));
+    }
+}
@@ -0,0 +1,32 @@
+//! Integration with the desktop environment
+pub mod instance;
+pub mod platform;
+pub mod share;
+pub mod shortcuts;
+
+/// Path of the running app, for the commands other apps launch
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+pub(crate) fn executable() -> Result<std::path::PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Failed to find the app executable: {}", e))
+}
+
+/// Run `reg.exe` to change the current user's registry
+#[cfg(target_os = "windows")]
+pub(crate) fn reg(args: &[&str]) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    /// Keeps a console window from flashing up
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let status = std::process::Command::new("reg")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| format!("Failed to run reg: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("reg {} failed", args[0]))
+    }
+}
@@ -0,0 +1,305 @@
+//! Desktop platform capabilities and fallbacks
+//!
+//! Global shortcuts and the clipboard behave differently under Wayland:
+//! key grabs only reach XWayland windows and clipboard access needs focus.
+//! The probe picks a backend for each integration, preferring the
+//! compositor-friendly ones (the GlobalShortcuts portal, wl-clipboard) and
+//! falling back to X11 tools or the app's own clipboard.
+
+use serde::Serialize;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use tauri::{AppHandle, ClipboardManager};
+
+/// Window system the app runs under
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayServer {
+    Wayland,
+    X11,
+    Windows,
+    MacOs,
+    Unknown,
+}
+
+/// How the clipboard is read and written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardBackend {
+    /// The app's own clipboard access
+    Native,
+    /// `wl-copy` and `wl-paste`
+    WlClipboard,
+    /// `xclip`, through XWayland on Wayland
+    Xclip,
+    /// `xsel`, through XWayland on Wayland
+    Xsel,
+}
+
+/// How system-wide shortcuts are registered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutBackend {
+    /// The app grabs the keys itself
+    Native,
+    /// The compositor binds them through the GlobalShortcuts portal
+    Portal,
+    /// No system-wide shortcuts; the tray and in-app keys still work
+    Unavailable,
+}
+
+/// What the probe found
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Capabilities {
+    /// Window system
+    pub display_server: DisplayServer,
+
+    /// Desktop environment (`XDG_CURRENT_DESKTOP`), on Linux
+    pub desktop: Option<String>,
+
+    /// Whether X11 apps can run next to Wayland ones
+    pub xwayland: bool,
+
+    /// Clipboard backend in use
+    pub clipboard: ClipboardBackend,
+
+    /// Shortcut backend to try
+    pub shortcuts: ShortcutBackend,
+}
+
+/// Platform details for the settings and diagnostics views
+#[derive(Debug, Clone, Serialize)]
+pub struct PlatformInfo {
+    /// Operating system and architecture
+    pub os: String,
+
+    /// Probed capabilities
+    #[serde(flatten)]
+    pub capabilities: Capabilities,
+
+    /// Shortcut backend that registered, once registration ran
+    pub active_shortcuts: Option<ShortcutBackend>,
+
+    /// Limitations the user should know about
+    pub notes: Vec<String>,
+}
+
+/// Capabilities, probed once
+static CAPABILITIES: Mutex<Option<Capabilities>> = Mutex::new(None);
+
+/// Shortcut backend that registered
+static ACTIVE_SHORTCUTS: Mutex<Option<ShortcutBackend>> = Mutex::new(None);
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+fn has_command(name: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(name).is_file()))
+        .unwrap_or(false)
+}
+
+/// Whether the desktop portal offers GlobalShortcuts
+fn portal_has_shortcuts() -> bool {
+    Command::new("gdbus")
+        .args([
+            "introspect",
+            "--session",
+            "--dest",
+            "org.freedesktop.portal.Desktop",
+            "--object-path",
+            "/org/freedesktop/portal/desktop",
+        ])
+        .stderr(Stdio::null())
+        .output()
+        .map(|out| String::from_utf8_lossy(&out.stdout).contains("org.freedesktop.portal.GlobalShortcuts"))
+        .unwrap_or(false)
+}
+
+/// Work out the display server from the session environment
+fn display_server(os: &str, env: &dyn Fn(&str) -> Option<String>) -> DisplayServer {
+    match os {
+        "windows" => DisplayServer::Windows,
+        "macos" => DisplayServer::MacOs,
+        _ => match env("XDG_SESSION_TYPE").as_deref() {
+            Some("wayland") => DisplayServer::Wayland,
+            Some("x11") => DisplayServer::X11,
+            _ if env("WAYLAND_DISPLAY").is_some() => DisplayServer::Wayland,
+            _ if env("DISPLAY").is_some() => DisplayServer::X11,
+            _ => DisplayServer::Unknown,
+        },
+    }
+}
+
+/// Probe with the environment, installed commands and portal support given
+fn probe_with(
+    os: &str,
+    env: &dyn Fn(&str) -> Option<String>,
+    has_command: &dyn Fn(&str) -> bool,
+    portal_shortcuts: &dyn Fn() -> bool,
+) -> Capabilities {
+    let display_server = display_server(os, env);
+    let xwayland = display_server == DisplayServer::Wayland && env("DISPLAY").is_some();
+
+    let clipboard = match display_server {
+        DisplayServer::Wayland if has_command("wl-copy") && has_command("wl-paste") => ClipboardBackend::WlClipboard,
+        DisplayServer::Wayland if xwayland && has_command("xclip") => ClipboardBackend::Xclip,
+        DisplayServer::Wayland if xwayland && has_command("xsel") => ClipboardBackend::Xsel,
+        _ => ClipboardBackend::Native,
+    };
+
+    let shortcuts = match display_server {
+        DisplayServer::Wayland if portal_shortcuts() => ShortcutBackend::Portal,
+        DisplayServer::Wayland | DisplayServer::Unknown => ShortcutBackend::Unavailable,
+        _ => ShortcutBackend::Native,
+    };
+
+    Capabilities {
+        display_server,
+        desktop: env("XDG_CURRENT_DESKTOP"),
+        xwayland,
+        clipboard,
+        shortcuts,
+    }
+}
+
+/// Probe the platform, once
+pub fn capabilities() -> Capabilities {
+    CAPABILITIES
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| probe_with(std::env::consts::OS, &env_var, &has_command, &portal_has_shortcuts))
+        .clone()
+}
+
+/// Record which shortcut backend registered
+pub fn set_active_shortcuts(backend: ShortcutBackend) {
+    *ACTIVE_SHORTCUTS.lock().unwrap() = Some(backend);
+}
+
+/// Platform details and the integrations in use
+pub fn info() -> PlatformInfo {
+    let capabilities = capabilities();
+    let active_shortcuts = *ACTIVE_SHORTCUTS.lock().unwrap();
+
+    let mut notes = Vec::new();
+    if capabilities.display_server == DisplayServer::Wayland {
+        if capabilities.clipboard == ClipboardBackend::Native {
+            notes.push("Install wl-clipboard to copy and paste while the window isn't focused".to_string());
+        }
+        if capabilities.shortcuts == ShortcutBackend::Unavailable {
+            notes.push(
+                "Your desktop doesn't offer global shortcuts to apps; use the tray menu or a desktop shortcut running `papin papin://ask`"
+                    .to_string(),
+            );
+        }
+    }
+    if active_shortcuts == Some(ShortcutBackend::Unavailable) && capabilities.shortcuts != ShortcutBackend::Unavailable {
+        notes.push("The global shortcut couldn't be registered; another app may be using it".to_string());
+    }
+
+    PlatformInfo {
+        os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        capabilities,
+        active_shortcuts,
+        notes,
+    }
+}
+
+/// Run a clipboard tool, feeding it `input` when given; returns its output
+fn run_tool(program: &str, args: &[&str], input: Option<&str>) -> Result<String, String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!("{} failed", program));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Copy text with the probed clipboard backend
+pub fn copy_text(app: &AppHandle, text: &str) -> Result<(), String> {
+    match capabilities().clipboard {
+        ClipboardBackend::Native => app.clipboard_manager().write_text(text).map_err(|e| e.to_string()),
+        ClipboardBackend::WlClipboard => run_tool("wl-copy", &[], Some(text)).map(|_| ()),
+        ClipboardBackend::Xclip => run_tool("xclip", &["-selection", "clipboard"], Some(text)).map(|_| ()),
+        ClipboardBackend::Xsel => run_tool("xsel", &["--clipboard", "--input"], Some(text)).map(|_| ()),
+    }
+}
+
+/// Read text with the probed clipboard backend
+pub fn read_text(app: &AppHandle) -> Result<Option<String>, String> {
+    let text = match capabilities().clipboard {
+        ClipboardBackend::Native => return app.clipboard_manager().read_text().map_err(|e| e.to_string()),
+        ClipboardBackend::WlClipboard => run_tool("wl-paste", &["--no-newline"], None),
+        ClipboardBackend::Xclip => run_tool("xclip", &["-selection", "clipboard", "-o"], None),
+        ClipboardBackend::Xsel => run_tool("xsel", &["--clipboard", "--output"], None),
+    };
+    // The tools fail on an empty clipboard
+    Ok(text.ok().filter(|t| !t.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| vars.iter().find(|(n, _)| *n == name).map(|(_, v)| v.to_string())
+    }
+
+    #[test]
+    fn test_wayland_fallbacks() {
+        let vars = [("XDG_SESSION_TYPE", "wayland"), ("DISPLAY", ":0"), ("XDG_CURRENT_DESKTOP", "GNOME")];
+        let caps = probe_with("linux", &env(&vars), &|cmd| cmd.starts_with("wl-"), &|| true);
+        assert_eq!(caps.display_server, DisplayServer::Wayland);
+        assert_eq!(caps.clipboard, ClipboardBackend::WlClipboard);
+        assert_eq!(caps.shortcuts, ShortcutBackend::Portal);
+        assert!(caps.xwayland);
+        assert_eq!(caps.desktop.as_deref(), Some("GNOME"));
+
+        // Without wl-clipboard, X11 tools work through XWayland
+        let caps = probe_with("linux", &env(&vars), &|cmd| cmd == "xsel", &|| false);
+        assert_eq!(caps.clipboard, ClipboardBackend::Xsel);
+        assert_eq!(caps.shortcuts, ShortcutBackend::Unavailable);
+
+        // Without XWayland there is only the app's own clipboard
+        let vars = [("WAYLAND_DISPLAY", "wayland-0")];
+        let caps = probe_with("linux", &env(&vars), &|cmd| cmd == "xclip", &|| false);
+        assert_eq!(caps.display_server, DisplayServer::Wayland);
+        assert_eq!(caps.clipboard, ClipboardBackend::Native);
+    }
+
+    #[test]
+    fn test_native_platforms() {
+        let vars = [("XDG_SESSION_TYPE", "x11"), ("DISPLAY", ":0")];
+        let caps = probe_with("linux", &env(&vars), &|_| true, &|| true);
+        assert_eq!(caps.display_server, DisplayServer::X11);
+        assert_eq!(caps.clipboard, ClipboardBackend::Native);
+        assert_eq!(caps.shortcuts, ShortcutBackend::Native);
+
+        for (os, server) in [("windows", DisplayServer::Windows), ("macos", DisplayServer::MacOs)] {
+            let caps = probe_with(os, &env(&[]), &|_| false, &|| false);
+            assert_eq!(caps.display_server, server);
+            assert_eq!(caps.shortcuts, ShortcutBackend::Native);
+        }
+
+        let caps = probe_with("linux", &env(&[]), &|_| true, &|| true);
+        assert_eq!(caps.display_server, DisplayServer::Unknown);
+        assert_eq!(caps.shortcuts, ShortcutBackend::Unavailable);
+    }
+}
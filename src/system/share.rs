@@ -0,0 +1,365 @@
+//! "Send to Papin": text and files shared from other apps
+//!
+//! The platform integrations all end up launching the app with share
+//! arguments (`--share-text`, `--share-file`) or, on macOS, opening files
+//! with it. The content becomes a draft for a new quick conversation that
+//! the frontend picks up with `take_shared_drafts`.
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::utils::events::{events, get_event_system};
+#[cfg(target_os = "windows")]
+use super::reg;
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+use super::executable;
+
+/// Launch argument followed by shared text
+pub const TEXT_ARG: &str = "--share-text";
+
+/// Launch argument followed by one or more shared files
+pub const FILE_ARG: &str = "--share-file";
+
+/// Most files taken from one share
+const MAX_FILES: usize = 20;
+
+/// Longest shared text kept, in characters
+const MAX_TEXT_CHARS: usize = 100_000;
+
+/// Longest title taken from shared text, in characters
+const MAX_TITLE_CHARS: usize = 60;
+
+/// Name of the entries added to context menus
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+const MENU_LABEL: &str = "Send to Papin";
+
+/// Drafts waiting for the frontend
+static PENDING: Lazy<Mutex<Vec<SharedDraft>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Text and files another app shared
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SharedContent {
+    /// Shared text, if any
+    pub text: Option<String>,
+
+    /// Shared files
+    pub files: Vec<PathBuf>,
+}
+
+/// A file to attach to the new conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedFile {
+    /// Path on disk, for `add_attachment`
+    pub path: String,
+
+    /// File name
+    pub name: String,
+
+    /// Size in bytes
+    pub size: u64,
+}
+
+/// A new quick conversation pre-filled with shared content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedDraft {
+    /// Conversation title
+    pub title: String,
+
+    /// Text to put in the message box
+    pub text: String,
+
+    /// Files to attach
+    pub files: Vec<SharedFile>,
+}
+
+impl SharedContent {
+    /// Read share arguments from the command line. Everything after
+    /// `--share-file` up to the next flag is a file, so desktop entries can
+    /// pass several.
+    pub fn from_args<I, S>(args: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut content = Self::default();
+        let mut args = args.into_iter().map(Into::into).peekable();
+
+        while let Some(arg) = args.next() {
+            if let Some(text) = arg.strip_prefix(&format!("{}=", TEXT_ARG)) {
+                content.push_text(text);
+            } else if arg == TEXT_ARG {
+                if let Some(text) = args.next() {
+                    content.push_text(&text);
+                }
+            } else if let Some(file) = arg.strip_prefix(&format!("{}=", FILE_ARG)) {
+                content.files.push(PathBuf::from(file));
+            } else if arg == FILE_ARG {
+                while let Some(file) = args.next_if(|next| !next.starts_with("--")) {
+                    content.files.push(PathBuf::from(file));
+                }
+            }
+        }
+
+        (!content.is_empty()).then_some(content)
+    }
+
+    /// Read `file://` URLs, which macOS sends when files are opened with the app
+    pub fn from_file_urls(urls: &[Url]) -> Option<Self> {
+        let files: Vec<PathBuf> = urls
+            .iter()
+            .filter(|url| url.scheme() == "file")
+            .filter_map(|url| url.to_file_path().ok())
+            .collect();
+        (!files.is_empty()).then_some(Self { text: None, files })
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let text = match self.text.take() {
+            Some(existing) => format!("{}\n\n{}", existing, text),
+            None => text.to_string(),
+        };
+        self.text = Some(text);
+    }
+
+    /// Whether nothing was shared
+    pub fn is_empty(&self) -> bool {
+        self.text.as_deref().map_or(true, |t| t.trim().is_empty()) && self.files.is_empty()
+    }
+
+    /// Turn the content into a conversation draft. Files that don't exist
+    /// or aren't regular files are dropped.
+    pub fn to_draft(&self) -> SharedDraft {
+        let text: String = self.text.as_deref().unwrap_or_default().trim().chars().take(MAX_TEXT_CHARS).collect();
+
+        let files: Vec<SharedFile> = self
+            .files
+            .iter()
+            .filter_map(|path| {
+                let metadata = std::fs::metadata(path).ok().filter(|m| m.is_file())?;
+                Some(SharedFile {
+                    path: path.to_string_lossy().into_owned(),
+                    name: file_name(path),
+                    size: metadata.len(),
+                })
+            })
+            .take(MAX_FILES)
+            .collect();
+
+        let title = match (text.lines().find(|l| !l.trim().is_empty()), files.as_slice()) {
+            (Some(line), _) => truncate(line.trim(), MAX_TITLE_CHARS),
+            (None, [file]) => file.name.clone(),
+            (None, files) if !files.is_empty() => format!("{} shared files", files.len()),
+            _ => "Shared content".to_string(),
+        };
+
+        SharedDraft { title, text, files }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        format!("{}…", text.chars().take(max - 1).collect::<String>())
+    }
+}
+
+/// Queue shared content as a draft, tell the frontend and bring the window up
+pub fn deliver(app: &AppHandle, content: &SharedContent) {
+    let draft = content.to_draft();
+    if draft.text.is_empty() && draft.files.is_empty() {
+        warn!("Ignoring share with no readable content");
+        return;
+    }
+    info!("Received shared content: {} file(s)", draft.files.len());
+
+    PENDING.lock().unwrap().push(draft.clone());
+    get_event_system().emit(
+        events::SHARE_RECEIVED,
+        serde_json::to_value(&draft).unwrap_or_default(),
+    );
+
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Drafts shared since the frontend last asked, oldest first
+pub fn take_drafts() -> Vec<SharedDraft> {
+    std::mem::take(&mut *PENDING.lock().unwrap())
+}
+
+/// Registry key of the Explorer context menu entry
+#[cfg(target_os = "windows")]
+const REGISTRY_KEY: &str = r"HKCU\Software\Classes\*\shell\Papin";
+
+/// Add "Send to Papin" to the Explorer context menu of every file
+#[cfg(target_os = "windows")]
+pub fn register() -> Result<(), String> {
+    let exe = executable()?;
+    let exe = exe.to_string_lossy();
+    let command = format!("\"{}\" {} \"%1\"", exe, FILE_ARG);
+    reg(&["add", REGISTRY_KEY, "/v", "MUIVerb", "/d", MENU_LABEL, "/f"])?;
+    reg(&["add", REGISTRY_KEY, "/v", "Icon", "/d", &exe, "/f"])?;
+    reg(&["add", &format!(r"{}\command", REGISTRY_KEY), "/ve", "/d", &command, "/f"])?;
+    info!("Registered the Explorer context menu entry");
+    Ok(())
+}
+
+/// Remove the Explorer context menu entry
+#[cfg(target_os = "windows")]
+pub fn unregister() -> Result<(), String> {
+    if is_registered() {
+        reg(&["delete", REGISTRY_KEY, "/f"])?;
+    }
+    Ok(())
+}
+
+/// Whether the Explorer context menu entry exists
+#[cfg(target_os = "windows")]
+pub fn is_registered() -> bool {
+    reg(&["query", REGISTRY_KEY]).is_ok()
+}
+
+/// Desktop entry file managers and the portal share picker offer files to
+#[cfg(target_os = "linux")]
+fn desktop_entry() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("applications").join("papin-share.desktop"))
+}
+
+/// Install a desktop entry so file managers offer "Send to Papin" for text,
+/// documents and images
+#[cfg(target_os = "linux")]
+pub fn register() -> Result<(), String> {
+    let path = desktop_entry().ok_or("No applications directory")?;
+    let exe = executable()?;
+    let entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name={}\n\
+         Exec=\"{}\" {} %F\n\
+         Icon=papin\n\
+         MimeType=text/plain;text/markdown;text/csv;text/html;application/json;application/pdf;image/png;image/jpeg;image/webp;\n\
+         NoDisplay=true\n\
+         Terminal=false\n",
+        MENU_LABEL,
+        exe.display(),
+        FILE_ARG
+    );
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, entry).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    // Refresh the MIME cache where the tool is installed; menus pick the entry up on their own otherwise
+    if let Some(dir) = path.parent() {
+        let _ = std::process::Command::new("update-desktop-database").arg(dir).status();
+    }
+    info!("Installed desktop entry {}", path.display());
+    Ok(())
+}
+
+/// Remove the desktop entry
+#[cfg(target_os = "linux")]
+pub fn unregister() -> Result<(), String> {
+    match desktop_entry() {
+        Some(path) if path.exists() => std::fs::remove_file(&path).map_err(|e| e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Whether the desktop entry is installed
+#[cfg(target_os = "linux")]
+pub fn is_registered() -> bool {
+    desktop_entry().map_or(false, |path| path.exists())
+}
+
+/// On macOS the app bundle declares the document types it accepts, so
+/// "Open With" and dropping onto the Dock icon work without registering
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn register() -> Result<(), String> {
+    Ok(())
+}
+
+/// Nothing to remove; the bundle's declarations go with the app
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn unregister() -> Result<(), String> {
+    Ok(())
+}
+
+/// Always registered through the app bundle
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn is_registered() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_arguments() {
+        let content = SharedContent::from_args(["--share-text", "hello", "--share-file", "a.txt", "b.md", "--verbose"]).unwrap();
+        assert_eq!(content.text.as_deref(), Some("hello"));
+        assert_eq!(content.files, vec![PathBuf::from("a.txt"), PathBuf::from("b.md")]);
+
+        let content = SharedContent::from_args(["--share-file=c.txt", "--share-text=one", "--share-text=two"]).unwrap();
+        assert_eq!(content.text.as_deref(), Some("one\n\ntwo"));
+        assert_eq!(content.files, vec![PathBuf::from("c.txt")]);
+
+        assert!(SharedContent::from_args(["--verbose"]).is_none());
+        assert!(SharedContent::from_args(["--share-text", "  "]).is_none());
+    }
+
+    #[test]
+    fn test_file_urls() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.md");
+        let urls = vec![
+            Url::from_file_path(&file).unwrap(),
+            Url::parse("https://example.com/page").unwrap(),
+        ];
+        let content = SharedContent::from_file_urls(&urls).unwrap();
+        assert_eq!(content.files, vec![file]);
+        assert!(SharedContent::from_file_urls(&urls[1..]).is_none());
+    }
+
+    #[test]
+    fn test_draft() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.md");
+        std::fs::write(&file, "# Notes").unwrap();
+
+        let content = SharedContent {
+            text: None,
+            files: vec![file.clone(), dir.path().join("missing.txt"), dir.path().to_path_buf()],
+        };
+        let draft = content.to_draft();
+        assert_eq!(draft.title, "notes.md");
+        assert_eq!(draft.files.len(), 1);
+        assert_eq!(draft.files[0].size, 7);
+
+        let content = SharedContent {
+            text: Some(format!("\n  {}\nmore", "word ".repeat(20))),
+            files: vec![],
+        };
+        let draft = content.to_draft();
+        assert_eq!(draft.title.chars().count(), MAX_TITLE_CHARS);
+        assert!(draft.title.ends_with('…'));
+        assert!(draft.text.ends_with("more"));
+    }
+}
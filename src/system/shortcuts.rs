@@ -0,0 +1,121 @@
+//! System-wide quick-ask shortcut
+//!
+//! The app grabs the keys itself where it can. Under Wayland it asks the
+//! compositor through the GlobalShortcuts portal instead, and when neither
+//! works the tray menu and `papin://ask` remain.
+
+use log::{info, warn};
+use tauri::{AppHandle, GlobalShortcutManager, Manager};
+
+use super::platform::{self, ShortcutBackend};
+use crate::utils::config;
+use crate::utils::events::{events, get_event_system};
+
+/// Config key holding the quick-ask accelerator; empty turns it off
+pub const QUICK_ASK_KEY: &str = "shortcuts.quick_ask";
+
+/// Default quick-ask accelerator
+pub const DEFAULT_QUICK_ASK: &str = "CmdOrCtrl+Shift+Space";
+
+/// Shortcut id shown in the desktop's shortcut settings
+#[cfg(target_os = "linux")]
+const PORTAL_SHORTCUT_ID: &str = "quick-ask";
+
+fn quick_ask(app: &AppHandle) {
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+    get_event_system().emit(events::QUICK_ASK_REQUESTED, serde_json::json!({ "source": "shortcut" }));
+}
+
+/// Turn a Tauri accelerator (`CmdOrCtrl+Shift+Space`) into a portal trigger
+/// (`CTRL+SHIFT+space`)
+pub fn to_portal_trigger(accelerator: &str) -> String {
+    accelerator
+        .split('+')
+        .map(|part| match part.trim().to_ascii_lowercase().as_str() {
+            "cmdorctrl" | "commandorcontrol" | "ctrl" | "control" => "CTRL".to_string(),
+            "shift" => "SHIFT".to_string(),
+            "alt" | "option" => "ALT".to_string(),
+            "super" | "cmd" | "command" | "meta" => "LOGO".to_string(),
+            key => key.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// Bind the shortcut through the portal and open a quick ask on each press,
+/// for as long as the app runs
+#[cfg(target_os = "linux")]
+async fn run_portal(app: AppHandle, accelerator: String) -> ashpd::Result<()> {
+    use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+    use tokio_stream::StreamExt;
+
+    let portal = GlobalShortcuts::new().await?;
+    let session = portal.create_session().await?;
+    let shortcut = NewShortcut::new(PORTAL_SHORTCUT_ID, "Open a quick ask")
+        .preferred_trigger(to_portal_trigger(&accelerator).as_str());
+    portal.bind_shortcuts(&session, &[shortcut], None).await?.response()?;
+
+    platform::set_active_shortcuts(ShortcutBackend::Portal);
+    info!("Bound the quick-ask shortcut through the desktop portal");
+
+    let mut activated = portal.receive_activated().await?;
+    while let Some(event) = activated.next().await {
+        if event.shortcut_id() == PORTAL_SHORTCUT_ID {
+            quick_ask(&app);
+        }
+    }
+    Ok(())
+}
+
+/// Register the quick-ask shortcut with the best backend the platform has
+pub fn register(app: &AppHandle) {
+    let accelerator = config::get_string(QUICK_ASK_KEY).unwrap_or_else(|| DEFAULT_QUICK_ASK.to_string());
+    if accelerator.trim().is_empty() {
+        return;
+    }
+
+    match platform::capabilities().shortcuts {
+        ShortcutBackend::Native => {
+            let handle = app.clone();
+            match app.global_shortcut_manager().register(&accelerator, move || quick_ask(&handle)) {
+                Ok(()) => {
+                    platform::set_active_shortcuts(ShortcutBackend::Native);
+                    info!("Registered the quick-ask shortcut {}", accelerator);
+                }
+                Err(e) => {
+                    platform::set_active_shortcuts(ShortcutBackend::Unavailable);
+                    warn!("Failed to register the quick-ask shortcut {}: {}", accelerator, e);
+                }
+            }
+        }
+        #[cfg(target_os = "linux")]
+        ShortcutBackend::Portal => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = run_portal(app, accelerator).await {
+                    platform::set_active_shortcuts(ShortcutBackend::Unavailable);
+                    warn!("The desktop portal didn't bind the quick-ask shortcut: {}", e);
+                }
+            });
+        }
+        _ => {
+            platform::set_active_shortcuts(ShortcutBackend::Unavailable);
+            info!("No global shortcuts on this desktop; quick ask is in the tray menu");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_portal_trigger() {
+        assert_eq!(to_portal_trigger(DEFAULT_QUICK_ASK), "CTRL+SHIFT+space");
+        assert_eq!(to_portal_trigger("Super+Alt+K"), "LOGO+ALT+k");
+    }
+}
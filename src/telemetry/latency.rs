@@ -0,0 +1,424 @@
+//! Response latency per provider: time to first token and total time of
+//! recent requests, rolling percentiles over them, and alerts when the p95
+//! goes over the configured SLA.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::{percentile, Anomaly, AnomalyReport, PerformanceReport};
+use crate::observability::metrics;
+use crate::utils::config;
+use crate::utils::events::{
+    events, get_event_system, notify, Notification, NotificationAction, NotificationLevel,
+};
+
+/// Config key for the p95 total latency SLA, in milliseconds
+pub const SLA_P95_KEY: &str = "telemetry.latency.p95_sla_ms";
+
+/// Config key for the p95 time-to-first-token SLA, in milliseconds
+pub const SLA_TTFT_P95_KEY: &str = "telemetry.latency.ttft_p95_sla_ms";
+
+/// Config key for also raising a notification on SLA breaches
+pub const NOTIFY_KEY: &str = "telemetry.latency.notify";
+
+/// Anomaly type of SLA breaches
+pub const LATENCY_SLA_ANOMALY: &str = "latency_sla";
+
+/// Requests kept per provider for the rolling percentiles
+const WINDOW_SIZE: usize = 200;
+
+/// Requests needed before a provider's p95 is checked against the SLA
+const MIN_SAMPLES: usize = 20;
+
+/// Alerts kept for the performance panel
+const MAX_ALERTS: usize = 100;
+
+/// Metric names of the two latencies
+const TTFT_METRIC: &str = "time_to_first_token";
+const TOTAL_METRIC: &str = "response_latency";
+
+/// Latency SLA settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencySla {
+    /// Highest acceptable p95 of the total response time, in milliseconds
+    #[serde(default)]
+    pub p95_ms: Option<f64>,
+
+    /// Highest acceptable p95 of the time to first token, in milliseconds
+    #[serde(default)]
+    pub ttft_p95_ms: Option<f64>,
+
+    /// Raise a notification on breaches, not only the in-app alert
+    #[serde(default)]
+    pub notify: bool,
+}
+
+impl LatencySla {
+    /// Current settings
+    pub fn load() -> Self {
+        Self {
+            p95_ms: config::get_number(SLA_P95_KEY).filter(|ms| *ms > 0.0),
+            ttft_p95_ms: config::get_number(SLA_TTFT_P95_KEY).filter(|ms| *ms > 0.0),
+            notify: config::get_bool(NOTIFY_KEY).unwrap_or(false),
+        }
+    }
+
+    /// Save the settings
+    pub fn save(&self) -> Result<(), String> {
+        let threshold = |ms: Option<f64>| match ms.filter(|ms| *ms > 0.0) {
+            Some(ms) => serde_json::json!(ms),
+            None => serde_json::Value::Null,
+        };
+        config::set_value(SLA_P95_KEY, threshold(self.p95_ms))?;
+        config::set_value(SLA_TTFT_P95_KEY, threshold(self.ttft_p95_ms))?;
+        config::set_value(NOTIFY_KEY, serde_json::Value::Bool(self.notify))?;
+        config::save_config().map_err(|e| e.to_string())
+    }
+
+    /// Threshold for a metric
+    fn threshold(&self, metric: &str) -> Option<f64> {
+        match metric {
+            TTFT_METRIC => self.ttft_p95_ms,
+            _ => self.p95_ms,
+        }
+    }
+}
+
+/// Latencies of one request
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    /// When the request finished
+    at: DateTime<Utc>,
+
+    /// Time to the first token, for streamed responses
+    ttft_ms: Option<f64>,
+
+    /// Time to the complete response
+    total_ms: f64,
+}
+
+/// Rolling latency percentiles of a provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderLatency {
+    /// Provider name
+    pub provider: String,
+
+    /// Time to first token of streamed responses; `None` without any
+    pub time_to_first_token: Option<PerformanceReport>,
+
+    /// Total response time
+    pub total: PerformanceReport,
+
+    /// Whether a p95 is currently over its SLA
+    pub breached: bool,
+}
+
+/// Rolling latency windows per provider
+pub struct LatencyMonitor {
+    /// Requests kept per provider
+    window_size: usize,
+
+    /// Requests needed before the SLA is checked
+    min_samples: usize,
+
+    /// Recent requests by provider, oldest first
+    samples: HashMap<String, VecDeque<Sample>>,
+
+    /// (provider, metric) pairs currently over their SLA
+    breached: HashSet<(String, String)>,
+
+    /// Raised alerts with the time they were raised, oldest first
+    alerts: VecDeque<(DateTime<Utc>, Anomaly)>,
+}
+
+impl LatencyMonitor {
+    /// Create a monitor keeping `window_size` requests per provider
+    pub fn new(window_size: usize, min_samples: usize) -> Self {
+        Self {
+            window_size: window_size.max(1),
+            min_samples: min_samples.max(1),
+            samples: HashMap::new(),
+            breached: HashSet::new(),
+            alerts: VecDeque::new(),
+        }
+    }
+
+    /// Record a request and check the provider's p95 against the SLA.
+    /// Returns alerts for metrics that just went over their threshold; a
+    /// metric alerts again only after its p95 has recovered.
+    pub fn record(
+        &mut self,
+        provider: &str,
+        ttft: Option<Duration>,
+        total: Duration,
+        at: DateTime<Utc>,
+        sla: &LatencySla,
+    ) -> Vec<Anomaly> {
+        let window = self.samples.entry(provider.to_string()).or_default();
+        window.push_back(Sample {
+            at,
+            ttft_ms: ttft.map(|d| d.as_secs_f64() * 1000.0),
+            total_ms: total.as_secs_f64() * 1000.0,
+        });
+        while window.len() > self.window_size {
+            window.pop_front();
+        }
+
+        let mut raised = Vec::new();
+        for metric in [TTFT_METRIC, TOTAL_METRIC] {
+            let key = (provider.to_string(), metric.to_string());
+            let values = self.values(provider, metric);
+            let threshold = match sla.threshold(metric) {
+                Some(threshold) if values.len() >= self.min_samples => threshold,
+                _ => {
+                    self.breached.remove(&key);
+                    continue;
+                }
+            };
+
+            let p95 = percentile(&values, 95.0);
+            if p95 <= threshold {
+                if self.breached.remove(&key) {
+                    info!("{} {} p95 back under its SLA: {:.0}ms", provider, metric, p95);
+                }
+                continue;
+            }
+            if !self.breached.insert(key) {
+                continue;
+            }
+
+            let anomaly = Anomaly {
+                anomaly_type: LATENCY_SLA_ANOMALY.to_string(),
+                date: at.date_naive(),
+                value: p95,
+                threshold,
+                description: format!(
+                    "{} {} p95 = {:.0}ms (SLA {:.0}ms)",
+                    provider,
+                    metric.replace('_', " "),
+                    p95,
+                    threshold
+                ),
+            };
+            self.alerts.push_back((at, anomaly.clone()));
+            while self.alerts.len() > MAX_ALERTS {
+                self.alerts.pop_front();
+            }
+            raised.push(anomaly);
+        }
+        raised
+    }
+
+    /// Sorted values of a metric for a provider
+    fn values(&self, provider: &str, metric: &str) -> Vec<f64> {
+        let mut values: Vec<f64> = self
+            .samples
+            .get(provider)
+            .into_iter()
+            .flatten()
+            .filter_map(|s| match metric {
+                TTFT_METRIC => s.ttft_ms,
+                _ => Some(s.total_ms),
+            })
+            .collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values
+    }
+
+    /// Percentile report of a metric for a provider
+    fn report(&self, provider: &str, metric: &str) -> Option<PerformanceReport> {
+        let values = self.values(provider, metric);
+        let window = self.samples.get(provider)?;
+        if values.is_empty() {
+            return None;
+        }
+
+        Some(PerformanceReport {
+            metric_name: metric.to_string(),
+            start_time: window.front()?.at,
+            end_time: window.back()?.at,
+            count: values.len(),
+            min: values[0],
+            max: values[values.len() - 1],
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            p50: percentile(&values, 50.0),
+            p90: percentile(&values, 90.0),
+            p95: percentile(&values, 95.0),
+            p99: percentile(&values, 99.0),
+            unit: "ms".to_string(),
+        })
+    }
+
+    /// Rolling percentiles of every provider, by name
+    pub fn stats(&self) -> Vec<ProviderLatency> {
+        let mut providers: Vec<&String> = self.samples.keys().collect();
+        providers.sort();
+
+        providers
+            .into_iter()
+            .filter_map(|provider| {
+                Some(ProviderLatency {
+                    provider: provider.clone(),
+                    time_to_first_token: self.report(provider, TTFT_METRIC),
+                    total: self.report(provider, TOTAL_METRIC)?,
+                    breached: self.breached.iter().any(|(p, _)| p == provider),
+                })
+            })
+            .collect()
+    }
+
+    /// Alerts raised since a time, oldest first
+    pub fn alerts_since(&self, since: DateTime<Utc>) -> Vec<Anomaly> {
+        self.alerts
+            .iter()
+            .filter(|(at, _)| *at >= since)
+            .map(|(_, anomaly)| anomaly.clone())
+            .collect()
+    }
+
+    /// Alerts raised in the last `days` days
+    pub fn alert_report(&self, days: u32, now: DateTime<Utc>) -> AnomalyReport {
+        let start_time = now - chrono::Duration::days(days as i64);
+        AnomalyReport {
+            start_time,
+            end_time: now,
+            anomalies: self.alerts_since(start_time),
+        }
+    }
+
+    /// Forget all samples and alerts
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.breached.clear();
+        self.alerts.clear();
+    }
+}
+
+lazy_static! {
+    static ref LATENCY_MONITOR: Mutex<LatencyMonitor> = Mutex::new(LatencyMonitor::new(WINDOW_SIZE, MIN_SAMPLES));
+}
+
+/// Get the global latency monitor
+pub fn get_latency_monitor() -> &'static Mutex<LatencyMonitor> {
+    &LATENCY_MONITOR
+}
+
+/// Record a completed request to a provider: feed the metrics histograms
+/// and the rolling windows, and alert when a p95 goes over its SLA
+pub fn record_request(provider: &str, ttft: Option<Duration>, total: Duration) {
+    let tags = HashMap::from([("provider".to_string(), provider.to_string())]);
+    if let Some(ttft) = ttft {
+        metrics::record_histogram(TTFT_METRIC, ttft.as_secs_f64() * 1000.0, Some(tags.clone()));
+    }
+    metrics::record_histogram(TOTAL_METRIC, total.as_secs_f64() * 1000.0, Some(tags));
+
+    let sla = LatencySla::load();
+    let raised = LATENCY_MONITOR
+        .lock()
+        .unwrap()
+        .record(provider, ttft, total, Utc::now(), &sla);
+
+    for anomaly in raised {
+        warn!("Latency SLA breached: {}", anomaly.description);
+        match serde_json::to_value(&anomaly) {
+            Ok(payload) => get_event_system().emit(events::LATENCY_ALERT, payload),
+            Err(e) => warn!("Failed to serialize latency alert: {}", e),
+        }
+        if sla.notify {
+            notify(
+                Notification::new(NotificationLevel::Warning, "Responses are slow", anomaly.description.clone())
+                    .with_action(NotificationAction::Command {
+                        label: "Show latency".to_string(),
+                        command: "open_performance".to_string(),
+                    }),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sla(p95_ms: f64) -> LatencySla {
+        LatencySla {
+            p95_ms: Some(p95_ms),
+            ttft_p95_ms: None,
+            notify: false,
+        }
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_percentiles_roll_over_the_window() {
+        let mut monitor = LatencyMonitor::new(10, 5);
+        let now = Utc::now();
+        for i in 1..=20 {
+            monitor.record("claude", Some(ms(i * 10)), ms(i * 100), now, &LatencySla::default());
+        }
+
+        let stats = monitor.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].total.count, 10);
+        assert_eq!(stats[0].total.min, 1100.0);
+        assert_eq!(stats[0].total.p50, 1550.0);
+        assert_eq!(stats[0].time_to_first_token.as_ref().unwrap().max, 200.0);
+        assert!(!stats[0].breached);
+    }
+
+    #[test]
+    fn test_breach_alerts_once_until_recovered() {
+        let mut monitor = LatencyMonitor::new(10, 5);
+        let now = Utc::now();
+        let sla = sla(1000.0);
+
+        // Too few samples to judge
+        for _ in 0..4 {
+            assert!(monitor.record("local", None, ms(3000), now, &sla).is_empty());
+        }
+
+        let raised = monitor.record("local", None, ms(3000), now, &sla);
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].anomaly_type, LATENCY_SLA_ANOMALY);
+        assert_eq!(raised[0].threshold, 1000.0);
+        assert!(monitor.record("local", None, ms(3000), now, &sla).is_empty());
+        assert!(monitor.stats()[0].breached);
+
+        // Fast requests push the slow ones out of the window
+        for _ in 0..10 {
+            assert!(monitor.record("local", None, ms(100), now, &sla).is_empty());
+        }
+        assert!(!monitor.stats()[0].breached);
+
+        for _ in 0..10 {
+            monitor.record("local", None, ms(3000), now, &sla);
+        }
+        assert_eq!(monitor.alerts_since(now).len(), 2);
+        assert_eq!(monitor.alert_report(1, now).anomalies.len(), 2);
+    }
+
+    #[test]
+    fn test_providers_are_judged_separately() {
+        let mut monitor = LatencyMonitor::new(10, 1);
+        let now = Utc::now();
+        let sla = LatencySla {
+            p95_ms: None,
+            ttft_p95_ms: Some(500.0),
+            notify: false,
+        };
+
+        assert_eq!(monitor.record("claude", Some(ms(900)), ms(2000), now, &sla).len(), 1);
+        // Responses without a first-token time don't count toward that SLA
+        assert!(monitor.record("local", None, ms(9000), now, &sla).is_empty());
+        assert!(monitor.stats()[1].time_to_first_token.is_none());
+    }
+}
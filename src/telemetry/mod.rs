@@ -8,6 +8,8 @@ use tokio::time;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+pub mod latency;
+
 /// Telemetry event type
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum TelemetryEventType {
@@ -785,6 +787,9 @@ impl TelemetryAnalyzer {
             }
         }
         
+        // Include latency SLA breaches seen by this client
+        anomalies.extend(latency::get_latency_monitor().lock().unwrap().alerts_since(start_time));
+        
         // Detect crash rate increases
         let crash_events: Vec<_> = events.iter()
             .filter(|event| event.event_type == TelemetryEventType::Crash)
@@ -0,0 +1,514 @@
+use git2::{BlameOptions, DiffFormat, DiffOptions, ObjectType, Repository, Sort, TreeWalkMode, TreeWalkResult};
+use crate::context::watcher::get_file_watcher;
+use lazy_static::lazy_static;
+use log::{debug, warn};
+use mcp_common::config::data_path;
+use mcp_common::models::{Tool, ToolCall, ToolResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Name of the git context tool
+pub const TOOL_NAME: &str = "git";
+
+/// Upper bound on entries returned by listings and history
+const MAX_ENTRIES: usize = 500;
+
+/// Upper bound on diff output in bytes
+const MAX_DIFF_BYTES: usize = 128 * 1024;
+
+/// Read-only operation requested by the model
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum GitRequest {
+    /// Files tracked at a revision, optionally under a directory
+    ListFiles {
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default)]
+        rev: Option<String>,
+    },
+
+    /// Uncommitted changes, or changes between a revision and the working tree
+    Diff {
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default)]
+        rev: Option<String>,
+    },
+
+    /// Line-by-line authorship of a file
+    Blame {
+        path: String,
+        #[serde(default)]
+        start_line: Option<usize>,
+        #[serde(default)]
+        end_line: Option<usize>,
+    },
+
+    /// Commit history, optionally limited to a path
+    Log {
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+
+    /// Files edited on disk since the project was bound or the app started
+    RecentChanges {
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+}
+
+/// A commit in the history
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitInfo {
+    pub id: String,
+    pub author: String,
+    pub time: i64,
+    pub summary: String,
+}
+
+/// A blamed range of lines
+#[derive(Debug, Clone, Serialize)]
+pub struct BlameHunk {
+    pub start_line: usize,
+    pub lines: usize,
+    pub commit: String,
+    pub author: String,
+}
+
+/// Conversation to repository bindings, persisted across restarts
+pub struct RepoBindings {
+    path: PathBuf,
+    bindings: Mutex<HashMap<String, PathBuf>>,
+}
+
+impl RepoBindings {
+    fn open(path: PathBuf) -> Self {
+        let bindings = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            bindings: Mutex::new(bindings),
+        }
+    }
+
+    /// Bind a conversation to a repository; the path must be inside a git work tree
+    pub fn bind(&self, conversation_id: &str, repo_path: &Path) -> Result<PathBuf, String> {
+        let repo = Repository::discover(repo_path)
+            .map_err(|e| format!("{} is not a git repository: {}", repo_path.display(), e))?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| "Bare repositories are not supported".to_string())?
+            .canonicalize()
+            .map_err(|e| e.to_string())?;
+
+        let mut bindings = self.bindings.lock().unwrap();
+        bindings.insert(conversation_id.to_string(), workdir.clone());
+        self.save(&bindings);
+
+        if let Err(e) = get_file_watcher().watch(&workdir) {
+            warn!("{}", e);
+        }
+        Ok(workdir)
+    }
+
+    /// Remove a conversation's binding
+    pub fn unbind(&self, conversation_id: &str) {
+        let mut bindings = self.bindings.lock().unwrap();
+        if let Some(workdir) = bindings.remove(conversation_id) {
+            self.save(&bindings);
+
+            // Keep watching while another conversation still uses the project
+            if !bindings.values().any(|path| path == &workdir) {
+                get_file_watcher().unwatch(&workdir);
+            }
+        }
+    }
+
+    /// Repository bound to a conversation
+    pub fn get(&self, conversation_id: &str) -> Option<PathBuf> {
+        self.bindings.lock().unwrap().get(conversation_id).cloned()
+    }
+
+    /// Distinct repositories bound to any conversation
+    pub fn repositories(&self) -> Vec<PathBuf> {
+        let mut repositories: Vec<PathBuf> = self.bindings.lock().unwrap().values().cloned().collect();
+        repositories.sort();
+        repositories.dedup();
+        repositories
+    }
+
+    fn save(&self, bindings: &HashMap<String, PathBuf>) {
+        match serde_json::to_string_pretty(bindings) {
+            Ok(content) => {
+                if let Err(e) = fs::write(&self.path, content) {
+                    warn!("Failed to save repository bindings: {}", e);
+                }
+            }
+            Err(e) => warn!("Failed to serialize repository bindings: {}", e),
+        }
+    }
+}
+
+lazy_static! {
+    static ref REPO_BINDINGS: Arc<RepoBindings> = Arc::new(RepoBindings::open(data_path("repo_bindings.json")));
+}
+
+/// Get the global repository bindings
+pub fn get_repo_bindings() -> Arc<RepoBindings> {
+    REPO_BINDINGS.clone()
+}
+
+/// Read-only view of a repository, scoped to its work tree
+pub struct GitContext {
+    repo: Repository,
+    workdir: PathBuf,
+}
+
+impl GitContext {
+    /// Open the repository at a bound work tree
+    pub fn open(workdir: &Path) -> Result<Self, String> {
+        let repo = Repository::open(workdir).map_err(|e| format!("Failed to open repository: {}", e))?;
+        Ok(Self {
+            repo,
+            workdir: workdir.to_path_buf(),
+        })
+    }
+
+    /// Validate a model-supplied path and return it relative to the work tree.
+    ///
+    /// Absolute paths, parent components and symlinks escaping the work tree
+    /// are rejected.
+    pub fn scope_path(&self, path: &str) -> Result<PathBuf, String> {
+        let relative = Path::new(path.trim_start_matches("./"));
+        if relative.is_absolute()
+            || relative
+                .components()
+                .any(|c| matches!(c, Component::ParentDir | Component::Prefix(_) | Component::RootDir))
+        {
+            return Err(format!("Path '{}' is outside the repository", path));
+        }
+
+        let full = self.workdir.join(relative);
+        if let Ok(canonical) = full.canonicalize() {
+            if !canonical.starts_with(&self.workdir) {
+                return Err(format!("Path '{}' is outside the repository", path));
+            }
+        }
+
+        // Never expose git internals
+        if relative.components().next().map(|c| c.as_os_str() == ".git").unwrap_or(false) {
+            return Err("The .git directory is not accessible".to_string());
+        }
+
+        Ok(relative.to_path_buf())
+    }
+
+    /// Run a request
+    pub fn execute(&self, request: &GitRequest) -> Result<serde_json::Value, String> {
+        debug!("Git tool request: {:?}", request);
+
+        match request {
+            GitRequest::ListFiles { path, rev } => {
+                let files = self.list_files(path.as_deref(), rev.as_deref())?;
+                Ok(serde_json::json!({ "files": files, "truncated": files.len() >= MAX_ENTRIES }))
+            }
+            GitRequest::Diff { path, rev } => {
+                let diff = self.diff(path.as_deref(), rev.as_deref())?;
+                Ok(serde_json::json!({ "diff": diff }))
+            }
+            GitRequest::Blame { path, start_line, end_line } => {
+                let hunks = self.blame(path, *start_line, *end_line)?;
+                Ok(serde_json::json!({ "hunks": hunks }))
+            }
+            GitRequest::Log { path, limit } => {
+                let commits = self.log(path.as_deref(), limit.unwrap_or(20).min(MAX_ENTRIES))?;
+                Ok(serde_json::json!({ "commits": commits }))
+            }
+            GitRequest::RecentChanges { limit } => {
+                let changes = get_file_watcher().recently_changed(&self.workdir, limit.unwrap_or(20).min(MAX_ENTRIES));
+                Ok(serde_json::json!({ "changes": changes }))
+            }
+        }
+    }
+
+    fn list_files(&self, path: Option<&str>, rev: Option<&str>) -> Result<Vec<String>, String> {
+        let prefix = path.map(|p| self.scope_path(p)).transpose()?;
+        let tree = self
+            .repo
+            .revparse_single(rev.unwrap_or("HEAD"))
+            .and_then(|obj| obj.peel_to_tree())
+            .map_err(|e| format!("Unknown revision: {}", e))?;
+
+        let mut files = Vec::new();
+        tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            if files.len() >= MAX_ENTRIES {
+                return TreeWalkResult::Abort;
+            }
+            if entry.kind() == Some(ObjectType::Blob) {
+                let file = format!("{}{}", dir, entry.name().unwrap_or_default());
+                if prefix.as_ref().map(|p| Path::new(&file).starts_with(p)).unwrap_or(true) {
+                    files.push(file);
+                }
+            }
+            TreeWalkResult::Ok
+        })
+        .map_err(|e| e.to_string())?;
+
+        Ok(files)
+    }
+
+    fn diff(&self, path: Option<&str>, rev: Option<&str>) -> Result<String, String> {
+        let mut options = DiffOptions::new();
+        if let Some(path) = path {
+            options.pathspec(self.scope_path(path)?);
+        }
+
+        let tree = self
+            .repo
+            .revparse_single(rev.unwrap_or("HEAD"))
+            .and_then(|obj| obj.peel_to_tree())
+            .map_err(|e| format!("Unknown revision: {}", e))?;
+        let diff = self
+            .repo
+            .diff_tree_to_workdir_with_index(Some(&tree), Some(&mut options))
+            .map_err(|e| e.to_string())?;
+
+        let mut output = String::new();
+        let mut truncated = false;
+        diff.print(DiffFormat::Patch, |_, _, line| {
+            if output.len() >= MAX_DIFF_BYTES {
+                truncated = true;
+                return false;
+            }
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                output.push(line.origin());
+            }
+            output.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })
+        .or_else(|e| if truncated { Ok(()) } else { Err(e.to_string()) })?;
+
+        if truncated {
+            output.push_str("\n... diff truncated");
+        }
+        Ok(output)
+    }
+
+    fn blame(&self, path: &str, start_line: Option<usize>, end_line: Option<usize>) -> Result<Vec<BlameHunk>, String> {
+        let relative = self.scope_path(path)?;
+        let mut options = BlameOptions::new();
+        if let Some(start) = start_line {
+            options.min_line(start);
+        }
+        if let Some(end) = end_line {
+            options.max_line(end);
+        }
+
+        let blame = self
+            .repo
+            .blame_file(&relative, Some(&mut options))
+            .map_err(|e| format!("Failed to blame {}: {}", path, e))?;
+
+        Ok(blame
+            .iter()
+            .take(MAX_ENTRIES)
+            .map(|hunk| BlameHunk {
+                start_line: hunk.final_start_line(),
+                lines: hunk.lines_in_hunk(),
+                commit: hunk.final_commit_id().to_string(),
+                author: hunk.final_signature().name().unwrap_or_default().to_string(),
+            })
+            .collect())
+    }
+
+    fn log(&self, path: Option<&str>, limit: usize) -> Result<Vec<CommitInfo>, String> {
+        let scoped = path.map(|p| self.scope_path(p)).transpose()?;
+
+        let mut walk = self.repo.revwalk().map_err(|e| e.to_string())?;
+        walk.set_sorting(Sort::TIME).map_err(|e| e.to_string())?;
+        walk.push_head().map_err(|e| e.to_string())?;
+
+        let mut commits = Vec::new();
+        for oid in walk {
+            if commits.len() >= limit {
+                break;
+            }
+            let commit = self
+                .repo
+                .find_commit(oid.map_err(|e| e.to_string())?)
+                .map_err(|e| e.to_string())?;
+
+            if let Some(path) = &scoped {
+                if !self.commit_touches(&commit, path) {
+                    continue;
+                }
+            }
+
+            commits.push(CommitInfo {
+                id: commit.id().to_string(),
+                author: commit.author().name().unwrap_or_default().to_string(),
+                time: commit.time().seconds(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+            });
+        }
+
+        Ok(commits)
+    }
+
+    /// Whether a commit changed anything under a path compared to its first parent
+    fn commit_touches(&self, commit: &git2::Commit, path: &Path) -> bool {
+        let tree = match commit.tree() {
+            Ok(tree) => tree,
+            Err(_) => return false,
+        };
+        let parent_tree = commit.parent(0).ok().and_then(|p| p.tree().ok());
+
+        let mut options = DiffOptions::new();
+        options.pathspec(path);
+        self.repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut options))
+            .map(|diff| diff.deltas().len() > 0)
+            .unwrap_or(false)
+    }
+}
+
+/// Tool definition advertised to models
+pub fn tool_definition() -> Tool {
+    Tool::new(
+        TOOL_NAME,
+        "Read context from the git repository bound to this conversation: list files, \
+         show diffs, blame a file, view commit history, or list files recently edited on disk. Read-only; paths are relative to the repository root.",
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["list_files", "diff", "blame", "log", "recent_changes"]
+                },
+                "path": { "type": "string", "description": "File or directory relative to the repository root" },
+                "rev": { "type": "string", "description": "Revision for list_files and diff (default HEAD)" },
+                "start_line": { "type": "integer", "description": "First line for blame" },
+                "end_line": { "type": "integer", "description": "Last line for blame" },
+                "limit": { "type": "integer", "description": "Maximum commits for log or files for recent_changes (default 20)" }
+            },
+            "required": ["action"]
+        }),
+    )
+}
+
+/// What a `git` tool call would do, for dry runs. The tool only reads.
+pub fn describe_tool_call(conversation_id: &str, call: &ToolCall) -> Vec<String> {
+    let Some(workdir) = get_repo_bindings().get(conversation_id) else {
+        return vec!["Fail: this conversation is not bound to a repository".to_string()];
+    };
+    let arguments = match &call.arguments {
+        serde_json::Value::String(s) => serde_json::from_str(s).unwrap_or(serde_json::Value::Null),
+        other => other.clone(),
+    };
+    let request: GitRequest = match serde_json::from_value(arguments) {
+        Ok(request) => request,
+        Err(e) => return vec![format!("Fail with invalid arguments: {}", e)],
+    };
+
+    let under = |path: &Option<String>| path.as_deref().map(|p| format!(" under {}", p)).unwrap_or_default();
+    let effect = match &request {
+        GitRequest::ListFiles { path, rev } => {
+            format!("List the files at {}{}", rev.as_deref().unwrap_or("HEAD"), under(path))
+        }
+        GitRequest::Diff { path, rev: None } => format!("Show uncommitted changes{}", under(path)),
+        GitRequest::Diff { path, rev: Some(rev) } => format!("Show changes since {}{}", rev, under(path)),
+        GitRequest::Blame { path, .. } => format!("Blame {}", path),
+        GitRequest::Log { path, limit } => format!("Show up to {} commits{}", limit.unwrap_or(20), under(path)),
+        GitRequest::RecentChanges { limit } => format!("List up to {} files edited on disk", limit.unwrap_or(20)),
+    };
+    vec![format!("{} in {}, without changing it", effect, workdir.display())]
+}
+
+/// Execute a `git` tool call for a conversation
+pub fn execute_tool_call(conversation_id: &str, call: &ToolCall) -> ToolResult {
+    let result = (|| {
+        let workdir = get_repo_bindings()
+            .get(conversation_id)
+            .ok_or_else(|| "This conversation is not bound to a repository".to_string())?;
+
+        let arguments = match &call.arguments {
+            serde_json::Value::String(s) => serde_json::from_str(s).map_err(|e| format!("Invalid arguments: {}", e))?,
+            other => other.clone(),
+        };
+        let request: GitRequest =
+            serde_json::from_value(arguments).map_err(|e| format!("Invalid arguments: {}", e))?;
+
+        GitContext::open(&workdir)?.execute(&request)
+    })();
+
+    ToolResult {
+        tool_call_id: call.id.clone(),
+        name: call.name.clone(),
+        result: match result {
+            Ok(value) => value,
+            Err(e) => serde_json::json!({ "error": e }),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init_repo() -> (tempfile::TempDir, GitContext) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "fn main() {}\n").unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("src/lib.rs")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial commit", &tree, &[])
+            .unwrap();
+
+        let workdir = dir.path().canonicalize().unwrap();
+        (dir, GitContext::open(&workdir).unwrap())
+    }
+
+    #[test]
+    fn test_scope_path_rejects_escapes() {
+        let (_dir, context) = init_repo();
+        assert!(context.scope_path("src/lib.rs").is_ok());
+        assert!(context.scope_path("../etc/passwd").is_err());
+        assert!(context.scope_path("/etc/passwd").is_err());
+        assert!(context.scope_path(".git/config").is_err());
+    }
+
+    #[test]
+    fn test_list_files_and_log() {
+        let (_dir, context) = init_repo();
+
+        let files = context.list_files(Some("src"), None).unwrap();
+        assert_eq!(files, vec!["src/lib.rs".to_string()]);
+
+        let commits = context.log(Some("src/lib.rs"), 10).unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].summary, "Initial commit");
+    }
+
+    #[test]
+    fn test_diff_shows_working_tree_changes() {
+        let (dir, context) = init_repo();
+        fs::write(dir.path().join("src/lib.rs"), "fn main() { println!(\"hi\"); }\n").unwrap();
+
+        let diff = context.diff(None, None).unwrap();
+        assert!(diff.contains("+fn main() { println!(\"hi\"); }"));
+    }
+}
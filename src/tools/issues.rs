@@ -0,0 +1,177 @@
+use crate::security;
+use mcp_common::integrations::issues::{get_issue_importer, get_issue_settings, IssueField, IssueTracker};
+use mcp_common::models::{Tool, ToolCall, ToolResult};
+use serde::Deserialize;
+
+/// Name of the issue tracker tool
+pub const TOOL_NAME: &str = "issues";
+
+/// Permission that gates reading issues
+pub const PERMISSION: &str = "issue_tracker_access";
+
+/// Results of a search when the model doesn't say
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+/// Request from the model
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum IssueRequest {
+    /// Read one issue
+    Get {
+        issue: String,
+        #[serde(default)]
+        fields: Option<Vec<IssueField>>,
+    },
+
+    /// Find issues by text
+    Search {
+        query: String,
+        #[serde(default)]
+        tracker: Option<IssueTracker>,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+}
+
+/// Tool definition advertised to models
+pub fn tool_definition() -> Tool {
+    Tool::new(
+        TOOL_NAME,
+        "Read or search issues in the user's Jira and Linear trackers. \
+         `get` returns an issue with its description, comments and linked pull requests; \
+         `search` finds issues by text. The user must allow access first.",
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["get", "search"]
+                },
+                "issue": {
+                    "type": "string",
+                    "description": "Issue key such as ENG-42, or its link (for get)"
+                },
+                "fields": {
+                    "type": "array",
+                    "items": {
+                        "type": "string",
+                        "enum": ["status", "assignee", "labels", "description", "comments", "pull_requests"]
+                    },
+                    "description": "Parts of the issue to return (for get; default: the user's choice)"
+                },
+                "query": {
+                    "type": "string",
+                    "description": "Text to look for (for search)"
+                },
+                "tracker": {
+                    "type": "string",
+                    "enum": ["jira", "linear"],
+                    "description": "Tracker to search (default: the user's default tracker)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Most results to return (for search, default 10)"
+                }
+            },
+            "required": ["action"]
+        }),
+    )
+}
+
+async fn run(request: IssueRequest) -> Result<serde_json::Value, String> {
+    let reason = match &request {
+        IssueRequest::Get { issue, .. } => format!("Read issue {}", issue),
+        IssueRequest::Search { query, .. } => format!("Search issues for \"{}\"", query),
+    };
+    let approved = security::request_permission(PERMISSION, &reason).map_err(|e| e.to_string())?;
+    if !approved {
+        return Err("The user declined access to their issue trackers".to_string());
+    }
+
+    let importer = get_issue_importer();
+    match request {
+        IssueRequest::Get { issue, fields } => {
+            let fields = fields.unwrap_or_else(|| get_issue_settings().read().unwrap().fields.clone());
+            let issue = importer.fetch(&issue, false).await.map_err(|e| e.to_string())?;
+            Ok(serde_json::json!({
+                "key": issue.key,
+                "url": issue.url,
+                "issue": issue.to_markdown(&fields),
+            }))
+        }
+        IssueRequest::Search { query, tracker, limit } => {
+            let results = importer
+                .search(tracker, &query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(serde_json::json!({ "issues": results }))
+        }
+    }
+}
+
+/// What an `issues` tool call would do, for dry runs
+pub fn describe_tool_call(call: &ToolCall) -> Vec<String> {
+    let arguments = match &call.arguments {
+        serde_json::Value::String(s) => serde_json::from_str(s),
+        other => serde_json::from_value(other.clone()),
+    };
+    let effect = match arguments {
+        Ok(IssueRequest::Get { issue, .. }) => format!("Read issue {} from its tracker", issue),
+        Ok(IssueRequest::Search { query, tracker, limit }) => format!(
+            "Search {} for \"{}\", returning up to {} issues",
+            tracker.map_or("the configured trackers", |t| t.as_str()),
+            query,
+            limit.unwrap_or(DEFAULT_SEARCH_LIMIT)
+        ),
+        Err(e) => return vec![format!("Fail with invalid arguments: {}", e)],
+    };
+    vec![format!("Ask permission for {}", PERMISSION), effect]
+}
+
+/// Execute an `issues` tool call
+pub async fn execute_tool_call(call: &ToolCall) -> ToolResult {
+    let arguments = match &call.arguments {
+        serde_json::Value::String(s) => serde_json::from_str(s),
+        other => serde_json::from_value(other.clone()),
+    };
+
+    let result = match arguments {
+        Ok(request) => run(request).await,
+        Err(e) => Err(format!("Invalid arguments: {}", e)),
+    };
+
+    ToolResult {
+        tool_call_id: call.id.clone(),
+        name: call.name.clone(),
+        result: match result {
+            Ok(value) => value,
+            Err(e) => serde_json::json!({ "error": e }),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_are_read_from_arguments() {
+        let request: IssueRequest = serde_json::from_value(serde_json::json!({
+            "action": "get",
+            "issue": "ENG-42",
+            "fields": ["description", "pull_requests"]
+        }))
+        .unwrap();
+        assert!(matches!(
+            request,
+            IssueRequest::Get { ref issue, fields: Some(ref fields) }
+                if issue == "ENG-42" && fields == &[IssueField::Description, IssueField::PullRequests]
+        ));
+
+        let request: IssueRequest =
+            serde_json::from_value(serde_json::json!({ "action": "search", "query": "login", "tracker": "jira" })).unwrap();
+        assert!(matches!(request, IssueRequest::Search { tracker: Some(IssueTracker::Jira), limit: None, .. }));
+
+        assert!(serde_json::from_value::<IssueRequest>(serde_json::json!({ "action": "delete" })).is_err());
+    }
+}
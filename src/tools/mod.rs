@@ -0,0 +1,133 @@
+pub mod git;
+pub mod issues;
+pub mod sandbox;
+pub mod shell;
+
+use crate::security;
+use async_trait::async_trait;
+use log::warn;
+use mcp_common::agent::ToolExecutor;
+use mcp_common::environment::Environment;
+use mcp_common::models::{Tool, ToolCall, ToolResult};
+use mcp_common::service::dry_run::{self, get_dry_run_log};
+use mcp_common::service::policy::{get_policies, Effect};
+
+/// Permission asked for calls a tool policy asks about
+pub const POLICY_PERMISSION: &str = "tool_policy_ask";
+
+/// Tools the desktop app can execute locally
+pub fn local_tools() -> Vec<Tool> {
+    vec![
+        git::tool_definition(),
+        issues::tool_definition(),
+        sandbox::tool_definition(),
+        shell::tool_definition(),
+    ]
+}
+
+/// What a call to a local tool would do, for dry runs; `None` for unknown tools
+pub fn describe_tool_call(conversation_id: &str, call: &ToolCall, environment: &Environment) -> Option<Vec<String>> {
+    Some(match call.name.as_str() {
+        git::TOOL_NAME => git::describe_tool_call(conversation_id, call),
+        issues::TOOL_NAME => issues::describe_tool_call(call),
+        sandbox::TOOL_NAME => sandbox::describe_tool_call(call),
+        shell::TOOL_NAME => shell::describe_tool_call(conversation_id, call, environment),
+        _ => return None,
+    })
+}
+
+/// Record a call with what it would do instead of executing it
+fn simulate_tool_call(conversation_id: &str, call: &ToolCall, environment: &Environment) -> Option<ToolResult> {
+    let effects = describe_tool_call(conversation_id, call, environment)?
+        .iter()
+        .map(|effect| environment.mask(effect))
+        .collect();
+    let mut recorded = call.clone();
+    environment.mask_json(&mut recorded.arguments);
+
+    match get_dry_run_log().record(conversation_id, &recorded, effects) {
+        Ok(simulated) => Some(simulated.result()),
+        Err(e) => {
+            warn!("Failed to record dry run of {}: {}", call.name, e);
+            Some(ToolResult::new(
+                call.id.clone(),
+                call.name.clone(),
+                serde_json::json!({ "simulated": true, "error": "The dry run could not be recorded" }),
+            ))
+        }
+    }
+}
+
+/// Execute a tool call if it names a local tool; returns `None` for unknown tools.
+///
+/// `{env.NAME}` references in the arguments are filled in from the
+/// conversation's environment, and its secret values are masked in the result.
+/// The call is checked against the tool policies of the conversation's
+/// workspace first; the model gets a denial for calls they refuse or the user
+/// declines. With dry runs on, the call is only recorded with what it would do.
+pub async fn execute_tool_call(
+    conversation_id: &str,
+    workspace: Option<&str>,
+    call: &ToolCall,
+    environment: &Environment,
+) -> Option<ToolResult> {
+    let mut call = call.clone();
+    environment.expand_json(&mut call.arguments);
+
+    let decision = get_policies().check(workspace, conversation_id, &call);
+    match decision.effect {
+        Effect::Allow => {}
+        Effect::Deny => return Some(decision.denial(&call)),
+        // Dry runs don't act, so there is nothing to ask about yet
+        Effect::Ask if dry_run::is_enabled() => {}
+        Effect::Ask => {
+            let reason = environment.mask(&format!("Call {} with {}", call.name, call.arguments));
+            let approved = security::request_permission(POLICY_PERMISSION, &reason).unwrap_or_else(|e| {
+                warn!("Failed to ask about a call to {}: {}", call.name, e);
+                false
+            });
+            if !approved {
+                return Some(decision.denial(&call));
+            }
+        }
+    }
+
+    if dry_run::is_enabled() {
+        return simulate_tool_call(conversation_id, &call, environment);
+    }
+    let mut result = match call.name.as_str() {
+        git::TOOL_NAME => git::execute_tool_call(conversation_id, &call),
+        issues::TOOL_NAME => issues::execute_tool_call(&call).await,
+        sandbox::TOOL_NAME => sandbox::execute_tool_call(&call).await,
+        shell::TOOL_NAME => shell::execute_tool_call(conversation_id, &call, environment).await,
+        _ => return None,
+    };
+    environment.mask_json(&mut result.result);
+    Some(result)
+}
+
+/// The local tools, for agent runs in a conversation with its workspace and
+/// environment
+pub struct LocalTools {
+    workspace: Option<String>,
+    environment: Environment,
+}
+
+impl LocalTools {
+    /// Local tools seeing a conversation's environment, under its
+    /// workspace's policies
+    pub fn new(workspace: Option<String>, environment: Environment) -> Self {
+        Self { workspace, environment }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for LocalTools {
+    fn tools(&self) -> Vec<Tool> {
+        local_tools()
+    }
+
+    async fn execute(&self, conversation_id: &str, call: &ToolCall) -> Option<ToolResult> {
+        execute_tool_call(conversation_id, self.workspace.as_deref(), call, &self.environment).await
+    }
+}
@@ -0,0 +1,398 @@
+use log::{debug, warn};
+use mcp_common::config::data_path;
+use mcp_common::models::{Tool, ToolCall, ToolResult};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use wasi_common::pipe::WritePipe;
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::{ambient_authority, Dir, WasiCtx, WasiCtxBuilder};
+
+/// Name of the code execution tool
+pub const TOOL_NAME: &str = "run_code";
+
+/// Language of a snippet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Python,
+    #[serde(alias = "js")]
+    JavaScript,
+}
+
+impl Language {
+    /// Directory under `runtimes/` holding the interpreter
+    fn runtime_dir(&self) -> &'static str {
+        match self {
+            Language::Python => "python",
+            Language::JavaScript => "quickjs",
+        }
+    }
+
+    /// Interpreter module file name
+    fn module_file(&self) -> &'static str {
+        match self {
+            Language::Python => "python.wasm",
+            Language::JavaScript => "qjs.wasm",
+        }
+    }
+
+    /// Interpreter arguments to evaluate a snippet
+    fn args(&self, code: &str) -> Vec<String> {
+        match self {
+            Language::Python => vec!["python".to_string(), "-c".to_string(), code.to_string()],
+            Language::JavaScript => vec!["qjs".to_string(), "--std".to_string(), "-e".to_string(), code.to_string()],
+        }
+    }
+}
+
+/// Resource limits for one execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxLimits {
+    /// Maximum linear memory in bytes
+    pub memory_bytes: usize,
+
+    /// Wall-clock time limit
+    pub timeout: Duration,
+
+    /// Output beyond this many bytes per stream is truncated
+    pub max_output_bytes: usize,
+}
+
+impl Default for SandboxLimits {
+    fn default() -> Self {
+        Self {
+            memory_bytes: 256 * 1024 * 1024,
+            timeout: Duration::from_secs(10),
+            max_output_bytes: 64 * 1024,
+        }
+    }
+}
+
+/// Result of running a snippet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxOutput {
+    /// Captured standard output
+    pub stdout: String,
+
+    /// Captured standard error
+    pub stderr: String,
+
+    /// Exit code of the interpreter, if it exited normally
+    pub exit_code: Option<i32>,
+
+    /// Whether the time limit was hit
+    pub timed_out: bool,
+
+    /// Error raised by the sandbox itself (limits, traps)
+    pub error: Option<String>,
+
+    /// Execution time in milliseconds
+    pub duration_ms: u64,
+}
+
+impl SandboxOutput {
+    /// Whether the snippet ran to completion without errors
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0) && self.error.is_none() && !self.timed_out
+    }
+}
+
+/// Per-execution store state
+struct SandboxState {
+    wasi: WasiCtx,
+    limits: StoreLimits,
+}
+
+/// Runs Python and JavaScript snippets inside a WASI sandbox.
+///
+/// Interpreters are WASI builds of CPython and QuickJS placed under
+/// `runtimes/` in the data directory. Guests get no network access (WASI
+/// preview 1 has no sockets), no host environment, and only a read-only view
+/// of the interpreter's own standard library.
+pub struct CodeSandbox {
+    engine: Engine,
+    runtimes_dir: PathBuf,
+    limits: SandboxLimits,
+}
+
+impl CodeSandbox {
+    /// Create a sandbox using the default runtime directory
+    pub fn new(limits: SandboxLimits) -> Result<Self, String> {
+        Self::with_runtimes_dir(data_path("runtimes"), limits)
+    }
+
+    /// Create a sandbox with interpreters from a specific directory
+    pub fn with_runtimes_dir(runtimes_dir: PathBuf, limits: SandboxLimits) -> Result<Self, String> {
+        let mut config = Config::new();
+        // Epoch interruption lets a timer thread stop runaway guests
+        config.epoch_interruption(true);
+
+        let engine = Engine::new(&config).map_err(|e| format!("Failed to create WASM engine: {}", e))?;
+
+        Ok(Self {
+            engine,
+            runtimes_dir,
+            limits,
+        })
+    }
+
+    /// Whether the interpreter for a language is installed
+    pub fn is_available(&self, language: Language) -> bool {
+        self.module_path(language).exists()
+    }
+
+    fn module_path(&self, language: Language) -> PathBuf {
+        self.runtimes_dir
+            .join(language.runtime_dir())
+            .join(language.module_file())
+    }
+
+    /// Run a snippet and capture its output
+    pub async fn execute(&self, language: Language, code: &str) -> Result<SandboxOutput, String> {
+        let module_path = self.module_path(language);
+        if !module_path.exists() {
+            return Err(format!(
+                "The {:?} runtime is not installed (expected {})",
+                language,
+                module_path.display()
+            ));
+        }
+
+        let engine = self.engine.clone();
+        let limits = self.limits.clone();
+        let lib_dir = self.runtimes_dir.join(language.runtime_dir()).join("lib");
+        let args = language.args(code);
+
+        // Stop the guest once the time limit passes
+        let timer_engine = engine.clone();
+        let timeout = limits.timeout;
+        let timer = tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            timer_engine.increment_epoch();
+        });
+
+        let result = tokio::task::spawn_blocking(move || {
+            run_module(&engine, &module_path, &lib_dir, &args, &limits)
+        })
+        .await
+        .map_err(|e| format!("Sandbox task failed: {}", e))?;
+
+        timer.abort();
+        result
+    }
+}
+
+/// Instantiate and run an interpreter module
+fn run_module(
+    engine: &Engine,
+    module_path: &PathBuf,
+    lib_dir: &PathBuf,
+    args: &[String],
+    limits: &SandboxLimits,
+) -> Result<SandboxOutput, String> {
+    let started = Instant::now();
+
+    let module = Module::from_file(engine, module_path)
+        .map_err(|e| format!("Failed to load {}: {}", module_path.display(), e))?;
+
+    let mut linker: Linker<SandboxState> = Linker::new(engine);
+    wasmtime_wasi::add_to_linker(&mut linker, |state| &mut state.wasi)
+        .map_err(|e| format!("Failed to link WASI: {}", e))?;
+
+    let stdout = WritePipe::new_in_memory();
+    let stderr = WritePipe::new_in_memory();
+
+    let mut builder = WasiCtxBuilder::new();
+    builder
+        .args(args)
+        .map_err(|e| format!("Invalid arguments: {}", e))?
+        .stdout(Box::new(stdout.clone()))
+        .stderr(Box::new(stderr.clone()));
+
+    if lib_dir.exists() {
+        let dir = Dir::open_ambient_dir(lib_dir, ambient_authority())
+            .map_err(|e| format!("Failed to open runtime library: {}", e))?;
+        builder
+            .preopened_dir(dir, "/lib")
+            .map_err(|e| format!("Failed to mount runtime library: {}", e))?;
+        builder
+            .env("PYTHONHOME", "/lib")
+            .map_err(|e| format!("Invalid environment: {}", e))?;
+    }
+
+    let state = SandboxState {
+        wasi: builder.build(),
+        limits: StoreLimitsBuilder::new()
+            .memory_size(limits.memory_bytes)
+            .instances(1)
+            .build(),
+    };
+
+    let mut store = Store::new(engine, state);
+    store.limiter(|state| &mut state.limits);
+    store.set_epoch_deadline(1);
+
+    let mut exit_code = None;
+    let mut timed_out = false;
+    let mut error = None;
+
+    let run = linker
+        .instantiate(&mut store, &module)
+        .and_then(|instance| instance.get_typed_func::<(), ()>(&mut store, "_start"))
+        .and_then(|start| start.call(&mut store, ()));
+
+    match run {
+        Ok(()) => exit_code = Some(0),
+        Err(e) => {
+            if let Some(exit) = e.downcast_ref::<wasi_common::I32Exit>() {
+                exit_code = Some(exit.0);
+            } else if matches!(e.downcast_ref::<wasmtime::Trap>(), Some(wasmtime::Trap::Interrupt)) {
+                timed_out = true;
+                error = Some(format!("Execution exceeded {:?}", limits.timeout));
+            } else {
+                warn!("Sandboxed code trapped: {}", e);
+                error = Some(e.to_string());
+            }
+        }
+    }
+
+    // The pipes can only be read once the store releases them
+    drop(store);
+
+    let read = |pipe: WritePipe<std::io::Cursor<Vec<u8>>>| {
+        let bytes = pipe
+            .try_into_inner()
+            .map(|cursor| cursor.into_inner())
+            .unwrap_or_default();
+        truncate_output(&bytes, limits.max_output_bytes)
+    };
+
+    let output = SandboxOutput {
+        stdout: read(stdout),
+        stderr: read(stderr),
+        exit_code,
+        timed_out,
+        error,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+
+    debug!("Sandbox run finished in {}ms", output.duration_ms);
+    Ok(output)
+}
+
+/// Decode output, cutting it off at the byte limit
+fn truncate_output(bytes: &[u8], max_bytes: usize) -> String {
+    if bytes.len() <= max_bytes {
+        return String::from_utf8_lossy(bytes).to_string();
+    }
+
+    let mut text = String::from_utf8_lossy(&bytes[..max_bytes]).to_string();
+    text.push_str(&format!("\n... output truncated ({} bytes total)", bytes.len()));
+    text
+}
+
+/// Tool definition advertised to models
+pub fn tool_definition() -> Tool {
+    Tool::new(
+        TOOL_NAME,
+        "Run a short Python or JavaScript snippet in an offline sandbox and return its output. \
+         Use print()/console.log() to produce results. No network or file access.",
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "language": {
+                    "type": "string",
+                    "enum": ["python", "javascript"],
+                    "description": "Language of the snippet"
+                },
+                "code": {
+                    "type": "string",
+                    "description": "Source code to run"
+                }
+            },
+            "required": ["language", "code"]
+        }),
+    )
+}
+
+/// Arguments of a `run_code` call
+#[derive(Debug, Deserialize)]
+struct RunCodeArgs {
+    language: Language,
+    code: String,
+}
+
+/// Parse tool call arguments, which may arrive as an object or a JSON string
+fn parse_args(arguments: &serde_json::Value) -> Result<RunCodeArgs, String> {
+    let value = match arguments {
+        serde_json::Value::String(s) => serde_json::from_str(s).map_err(|e| format!("Invalid arguments: {}", e))?,
+        other => other.clone(),
+    };
+    serde_json::from_value(value).map_err(|e| format!("Invalid arguments: {}", e))
+}
+
+/// What a `run_code` tool call would do, for dry runs
+pub fn describe_tool_call(call: &ToolCall) -> Vec<String> {
+    match parse_args(&call.arguments) {
+        Ok(args) => vec![format!(
+            "Run {} lines of {:?} in the WASM sandbox, without network or file access",
+            args.code.lines().count(),
+            args.language
+        )],
+        Err(e) => vec![format!("Fail: {}", e)],
+    }
+}
+
+/// Execute a `run_code` tool call and wrap the output as a tool result
+pub async fn execute_tool_call(call: &ToolCall) -> ToolResult {
+    let result = match parse_args(&call.arguments) {
+        Ok(args) => match CodeSandbox::new(SandboxLimits::default()) {
+            Ok(sandbox) => sandbox
+                .execute(args.language, &args.code)
+                .await
+                .and_then(|output| serde_json::to_value(output).map_err(|e| e.to_string())),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+
+    ToolResult {
+        tool_call_id: call.id.clone(),
+        name: call.name.clone(),
+        result: match result {
+            Ok(value) => value,
+            Err(e) => serde_json::json!({ "error": e }),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args_accepts_object_and_string() {
+        let object = serde_json::json!({ "language": "python", "code": "print(1)" });
+        assert_eq!(parse_args(&object).unwrap().language, Language::Python);
+
+        let string = serde_json::Value::String(r#"{"language":"js","code":"1"}"#.to_string());
+        assert_eq!(parse_args(&string).unwrap().language, Language::JavaScript);
+
+        assert!(parse_args(&serde_json::json!({ "language": "ruby", "code": "" })).is_err());
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        assert_eq!(truncate_output(b"hello", 10), "hello");
+        assert!(truncate_output(b"hello world", 5).starts_with("hello\n... output truncated"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_runtime_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = CodeSandbox::with_runtimes_dir(dir.path().to_path_buf(), SandboxLimits::default()).unwrap();
+        assert!(!sandbox.is_available(Language::Python));
+        assert!(sandbox.execute(Language::Python, "print(1)").await.is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::security;
+use crate::services::terminal::{get_pty_manager, TerminalEvent, TerminalOptions};
+use crate::tools::git::get_repo_bindings;
+use log::{info, warn};
+use mcp_common::environment::Environment;
+use mcp_common::models::{Tool, ToolCall, ToolResult};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Name of the shell tool
+pub const TOOL_NAME: &str = "shell";
+
+/// Permission that gates every shell execution
+pub const PERMISSION: &str = "shell_execution";
+
+/// Default time a command may run before the tool returns
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Output returned to the model is capped; the terminal view keeps everything
+const MAX_OUTPUT_CHARS: usize = 16 * 1024;
+
+/// Arguments of a `shell` call
+#[derive(Debug, Deserialize)]
+struct ShellArgs {
+    command: String,
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+}
+
+/// Remove ANSI escape sequences so the model sees plain text
+pub fn strip_ansi(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            if c != '\r' {
+                result.push(c);
+            }
+            continue;
+        }
+
+        match chars.next() {
+            // CSI: parameters until a final byte in @..~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: until BEL or ST
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    result
+}
+
+/// Tool definition advertised to models
+pub fn tool_definition() -> Tool {
+    Tool::new(
+        TOOL_NAME,
+        "Run a shell command in the user's embedded terminal and return its output. \
+         The user must approve each command. Runs in the conversation's bound repository when there is one.",
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": "Command line to run"
+                },
+                "timeout_secs": {
+                    "type": "integer",
+                    "description": "Seconds to wait for the command to finish (default 60)"
+                }
+            },
+            "required": ["command"]
+        }),
+    )
+}
+
+/// Run an approved command in a new terminal session, with the conversation's
+/// environment variables, and collect its output
+async fn run(conversation_id: &str, args: ShellArgs, environment: &Environment) -> Result<serde_json::Value, String> {
+    let approved = security::request_permission(PERMISSION, &format!("Run `{}`", args.command))
+        .map_err(|e| e.to_string())?;
+    if !approved {
+        return Err("The user declined to run this command".to_string());
+    }
+
+    let (shell, flag) = if cfg!(windows) { ("cmd.exe", "/C") } else { ("/bin/sh", "-c") };
+    let manager = get_pty_manager();
+    let session_id = manager.create(TerminalOptions {
+        command: Some(shell.to_string()),
+        args: vec![flag.to_string(), args.command.clone()],
+        cwd: get_repo_bindings().get(conversation_id),
+        env: environment.vars(),
+        title: Some(args.command.clone()),
+        ..Default::default()
+    })?;
+    info!("Running approved shell command in terminal {}", session_id);
+
+    // The session streams live to the terminal view; collect a copy for the model
+    let (mut output, mut rx) = manager.subscribe(&session_id)?;
+    let timeout = args.timeout_secs.map(Duration::from_secs).unwrap_or(DEFAULT_TIMEOUT);
+
+    let collect = async {
+        loop {
+            match rx.recv().await {
+                Ok(TerminalEvent::Output(data)) => output.push_str(&data),
+                Ok(TerminalEvent::Exit(code)) => return Some(code),
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    warn!("Shell tool skipped {} terminal chunks", n);
+                }
+                Err(_) => return Some(None),
+            }
+        }
+    };
+
+    let exit = tokio::time::timeout(timeout, collect).await.ok().flatten();
+
+    let mut text = strip_ansi(&output);
+    if text.chars().count() > MAX_OUTPUT_CHARS {
+        let skip = text.chars().count() - MAX_OUTPUT_CHARS;
+        text = format!("... {} characters omitted\n{}", skip, text.chars().skip(skip).collect::<String>());
+    }
+
+    Ok(serde_json::json!({
+        "session_id": session_id,
+        "output": text,
+        "exit_code": exit.flatten(),
+        // The command keeps running in the terminal after a timeout
+        "timed_out": exit.is_none(),
+    }))
+}
+
+/// Read the arguments, which may arrive as an object or a JSON string
+fn parse_args(arguments: &serde_json::Value) -> serde_json::Result<ShellArgs> {
+    match arguments {
+        serde_json::Value::String(s) => serde_json::from_str(s),
+        other => serde_json::from_value(other.clone()),
+    }
+}
+
+/// What a `shell` tool call would do, for dry runs
+pub fn describe_tool_call(conversation_id: &str, call: &ToolCall, environment: &Environment) -> Vec<String> {
+    let args = match parse_args(&call.arguments) {
+        Ok(args) => args,
+        Err(e) => return vec![format!("Fail with invalid arguments: {}", e)],
+    };
+
+    let cwd = match get_repo_bindings().get(conversation_id) {
+        Some(path) => path.display().to_string(),
+        None => "the home directory".to_string(),
+    };
+    let mut effects = vec![
+        format!("Ask permission for {}", PERMISSION),
+        format!("Run `{}` in a new terminal in {}", args.command, cwd),
+    ];
+    if !environment.is_empty() {
+        let names: Vec<&str> = environment.variables().keys().map(String::as_str).collect();
+        effects.push(format!("Set the environment variables {}", names.join(", ")));
+    }
+    let timeout = args.timeout_secs.map(Duration::from_secs).unwrap_or(DEFAULT_TIMEOUT);
+    effects.push(format!("Return its output after it exits or {} seconds", timeout.as_secs()));
+    effects
+}
+
+/// Execute a `shell` tool call for a conversation
+pub async fn execute_tool_call(conversation_id: &str, call: &ToolCall, environment: &Environment) -> ToolResult {
+    let result = match parse_args(&call.arguments) {
+        Ok(args) => run(conversation_id, args, environment).await,
+        Err(e) => Err(format!("Invalid arguments: {}", e)),
+    };
+
+    ToolResult {
+        tool_call_id: call.id.clone(),
+        name: call.name.clone(),
+        result: match result {
+            Ok(value) => value,
+            Err(e) => serde_json::json!({ "error": e }),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\x1b[1;32mok\x1b[0m\r\n"), "ok\n");
+        assert_eq!(strip_ansi("\x1b]0;title\x07plain"), "plain");
+        assert_eq!(strip_ansi("no escapes"), "no escapes");
+    }
+}
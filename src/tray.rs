@@ -0,0 +1,218 @@
+use log::{debug, warn};
+use mcp_common::config::get_settings;
+use mcp_common::events::{get_event_bus, names, Backpressure, Topic};
+use mcp_common::jobs::{get_job_manager, JobKind};
+use mcp_common::sync::{is_sync_paused, set_sync_paused};
+use std::time::Duration;
+use tauri::{
+    AppHandle, CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem,
+};
+
+use crate::offline::{self, OfflineStatus};
+use crate::utils::events::{events, get_event_system};
+
+/// Menu item ids
+const STATUS: &str = "status";
+const MODEL: &str = "model";
+const DOWNLOAD: &str = "download";
+const QUICK_ASK: &str = "quick_ask";
+const TOGGLE_OFFLINE: &str = "toggle_offline";
+const TOGGLE_SYNC: &str = "toggle_sync";
+const SHOW: &str = "show";
+const QUIT: &str = "quit";
+
+/// How often the menu is refreshed when no event arrives; the offline
+/// manager switches modes without publishing an event
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+/// What the tray menu shows
+#[derive(Debug, Clone, PartialEq)]
+struct TrayState {
+    offline: OfflineStatus,
+    model: String,
+    download: Option<String>,
+    sync_paused: bool,
+}
+
+impl TrayState {
+    fn current() -> Self {
+        let offline = offline::get_offline_manager().get_status();
+        let model = match offline {
+            OfflineStatus::Offline | OfflineStatus::GoingOffline => {
+                offline::get_offline_manager().get_llm().get_config().model_id
+            }
+            _ => get_settings().lock().unwrap().api.model.clone(),
+        };
+
+        let downloads: Vec<_> = get_job_manager()
+            .list()
+            .into_iter()
+            .filter(|job| job.kind == JobKind::ModelDownload && !job.state.is_finished())
+            .collect();
+        let download = match downloads.as_slice() {
+            [] => None,
+            [job] => Some(match job.progress.fraction() {
+                Some(fraction) => format!("Downloading {}: {:.0}%", job.title, fraction * 100.0),
+                None => format!("Downloading {}", job.title),
+            }),
+            jobs => Some(format!("{} downloads running", jobs.len())),
+        };
+
+        Self {
+            offline,
+            model,
+            download,
+            sync_paused: is_sync_paused(),
+        }
+    }
+
+    fn status_title(&self) -> &'static str {
+        match self.offline {
+            OfflineStatus::Online => "● Online",
+            OfflineStatus::Offline => "○ Offline",
+            OfflineStatus::GoingOffline => "Going offline…",
+            OfflineStatus::GoingOnline => "Going online…",
+        }
+    }
+
+    fn is_offline(&self) -> bool {
+        matches!(self.offline, OfflineStatus::Offline | OfflineStatus::GoingOffline)
+    }
+}
+
+/// Tray icon with status lines and quick actions
+pub fn build() -> SystemTray {
+    let menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new(STATUS, "Checking connection…").disabled())
+        .add_item(CustomMenuItem::new(MODEL, "Model: –").disabled())
+        .add_item(CustomMenuItem::new(DOWNLOAD, "No downloads").disabled())
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new(QUICK_ASK, "New quick ask…"))
+        .add_item(CustomMenuItem::new(TOGGLE_OFFLINE, "Go offline"))
+        .add_item(CustomMenuItem::new(TOGGLE_SYNC, "Pause sync"))
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new(SHOW, "Show Papin"))
+        .add_item(CustomMenuItem::new(QUIT, "Quit"));
+
+    SystemTray::new().with_menu(menu).with_tooltip("Papin")
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Handle clicks on the tray icon and its menu
+pub fn handle_event(app: &AppHandle, event: SystemTrayEvent) {
+    match event {
+        SystemTrayEvent::LeftClick { .. } => show_main_window(app),
+        SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
+            QUICK_ASK => {
+                show_main_window(app);
+                get_event_system().emit(events::QUICK_ASK_REQUESTED, serde_json::json!({ "source": "tray" }));
+            }
+            TOGGLE_OFFLINE => {
+                let app = app.clone();
+                // Switching modes checkpoints or probes the network; keep it off the UI thread
+                std::thread::spawn(move || {
+                    let manager = offline::get_offline_manager();
+                    let result = if TrayState::current().is_offline() {
+                        manager.go_online()
+                    } else {
+                        manager.go_offline()
+                    };
+                    match result {
+                        Ok(()) => get_event_system().emit(
+                            events::OFFLINE_STATUS_CHANGED,
+                            serde_json::to_value(manager.get_status()).unwrap_or_default(),
+                        ),
+                        Err(e) => warn!("Failed to switch offline mode from the tray: {}", e),
+                    }
+                    refresh(&app, &TrayState::current());
+                });
+            }
+            TOGGLE_SYNC => set_sync_paused(!is_sync_paused()),
+            SHOW => show_main_window(app),
+            QUIT => app.exit(0),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+/// Update the menu titles to match the state
+fn refresh(app: &AppHandle, state: &TrayState) {
+    let tray = app.tray_handle();
+    let titles = [
+        (STATUS, state.status_title().to_string()),
+        (MODEL, format!("Model: {}", state.model)),
+        (DOWNLOAD, state.download.clone().unwrap_or_else(|| "No downloads".to_string())),
+        (TOGGLE_OFFLINE, if state.is_offline() { "Go online" } else { "Go offline" }.to_string()),
+        (TOGGLE_SYNC, if state.sync_paused { "Resume sync" } else { "Pause sync" }.to_string()),
+    ];
+    for (id, title) in titles {
+        if let Err(e) = tray.get_item(id).set_title(title) {
+            warn!("Failed to update tray item {}: {}", id, e);
+        }
+    }
+
+    let tooltip = match &state.download {
+        Some(download) => format!("Papin · {} · {}", state.status_title(), download),
+        None => format!("Papin · {}", state.status_title()),
+    };
+    let _ = tray.set_tooltip(&tooltip);
+}
+
+/// Whether an event can change what the tray shows
+fn affects_tray(name: &str) -> bool {
+    [
+        names::JOB_PROGRESS,
+        names::JOB_FINISHED,
+        names::CONNECTION_HEALTH_CHANGED,
+        names::SYNC_PAUSED_CHANGED,
+        names::TEAM_SYNCED,
+        events::NETWORK_STATUS_CHANGED,
+        events::OFFLINE_STATUS_CHANGED,
+        events::MODEL_UNLOADED,
+    ]
+    .contains(&name)
+}
+
+/// Keep the tray menu current from backend events
+pub fn watch(app: AppHandle) {
+    let mut subscription = get_event_bus().subscribe(
+        &[Topic::Model, Topic::Sync, Topic::System],
+        64,
+        Backpressure::DropNewest,
+    );
+
+    tauri::async_runtime::spawn(async move {
+        let mut shown = TrayState::current();
+        refresh(&app, &shown);
+
+        let mut ticker = tokio::time::interval(REFRESH_INTERVAL);
+        loop {
+            tokio::select! {
+                event = subscription.recv() => match event {
+                    Some(event) if affects_tray(&event.name) => {
+                        // Progress arrives in bursts; read everything queued before redrawing
+                        subscription.drain();
+                    }
+                    Some(_) => continue,
+                    None => break,
+                },
+                _ = ticker.tick() => {}
+            }
+
+            let state = TrayState::current();
+            if state != shown {
+                refresh(&app, &state);
+                shown = state;
+            }
+        }
+        debug!("Tray watcher stopped");
+    });
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use log::debug;
+pub use mcp_common::utils::cancellation::{CancellationToken, RequestContext};
+
+/// Tracks contexts of in-flight requests so the frontend can cancel them by ID
+pub struct CancellationRegistry {
+    requests: Mutex<HashMap<String, RequestContext>>,
+}
+
+impl CancellationRegistry {
+    fn new() -> Self {
+        Self {
+            requests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Register a request and return its context
+    pub fn register(&self, request_id: &str, timeout: Option<Duration>) -> RequestContext {
+        let context = match timeout {
+            Some(timeout) => RequestContext::with_timeout(timeout),
+            None => RequestContext::new(),
+        };
+        self.requests
+            .lock()
+            .unwrap()
+            .insert(request_id.to_string(), context.clone());
+        context
+    }
+
+    /// Cancel a request; returns false if it is unknown or already finished
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.requests.lock().unwrap().remove(request_id) {
+            Some(context) => {
+                debug!("Cancelling request {}", request_id);
+                context.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget a finished request
+    pub fn complete(&self, request_id: &str) {
+        self.requests.lock().unwrap().remove(request_id);
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: Arc<CancellationRegistry> = Arc::new(CancellationRegistry::new());
+}
+
+/// Get the global cancellation registry
+pub fn get_cancellation_registry() -> Arc<CancellationRegistry> {
+    REGISTRY.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_cancel() {
+        let registry = CancellationRegistry::new();
+        let context = registry.register("req-1", None);
+        assert!(registry.cancel("req-1"));
+        assert!(context.token.is_cancelled());
+        assert!(!registry.cancel("req-1"));
+    }
+
+    #[test]
+    fn test_completed_request_is_not_cancelled() {
+        let registry = CancellationRegistry::new();
+        let context = registry.register("req-2", Some(Duration::from_secs(60)));
+        assert!(context.deadline.is_some());
+        registry.complete("req-2");
+        assert!(!registry.cancel("req-2"));
+        assert!(!context.token.is_cancelled());
+    }
+}
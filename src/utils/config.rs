@@ -1,4 +1,4 @@
-use directories::ProjectDirs;
+use mcp_common::platform::fs::{app_dir, AppDir};
 use lazy_static::lazy_static;
 use log::{error, info};
 use serde_json::{Map, Value};
@@ -80,16 +80,7 @@ impl Config {
     
     /// Get the path to the config file
     fn get_config_path() -> PathBuf {
-        if let Some(proj_dirs) = ProjectDirs::from("com", "claude", "mcp") {
-            let config_dir = proj_dirs.config_dir();
-            fs::create_dir_all(config_dir).unwrap_or_else(|e| {
-                error!("Failed to create config directory: {}", e);
-            });
-            config_dir.join("config.json")
-        } else {
-            // Fallback to current directory if we can't get the project directories
-            PathBuf::from("config.json")
-        }
+        app_dir(AppDir::Config).join("config.json")
     }
     
     /// Load configuration from a file
@@ -1,7 +1,11 @@
 use log::{debug, info, warn};
+use mcp_common::events::{get_event_bus, Backpressure, Topic};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
 use tokio::sync::mpsc;
+use uuid::Uuid;
 
 /// Event type ID
 pub type EventType = &'static str;
@@ -58,8 +62,10 @@ impl EventSystem {
         event_handlers.push(Box::new(handler));
     }
     
-    /// Emit an event
+    /// Emit an event to local handlers and the shared event bus
     pub fn emit(&self, event_type: EventType, payload: EventPayload) {
+        get_event_bus().emit(Topic::for_event_name(event_type), event_type, payload.clone());
+        
         if let Err(e) = self.tx.send((event_type, payload)) {
             warn!("Failed to emit event {}: {}", event_type, e);
         }
@@ -106,6 +112,88 @@ pub fn get_event_system() -> &'static EventSystem {
     })
 }
 
+/// Forward every event on the shared bus to the frontend as a Tauri event
+pub fn bridge_to_tauri(app_handle: AppHandle) {
+    let mut subscription = get_event_bus().subscribe_all(256, Backpressure::DropNewest);
+    
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = subscription.recv().await {
+            if let Err(e) = app_handle.emit_all(&event.name, event.payload) {
+                warn!("Failed to forward event {} to frontend: {}", event.name, e);
+            }
+        }
+        debug!("Tauri event bridge stopped");
+    });
+}
+
+/// Notification severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Action the user can take from a notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationAction {
+    /// Open a URL in the system browser
+    OpenUrl { label: String, url: String },
+
+    /// Invoke a frontend command by name
+    Command { label: String, command: String },
+}
+
+/// User-facing notification delivered to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    /// Notification ID
+    pub id: String,
+
+    /// Severity
+    pub level: NotificationLevel,
+
+    /// Short title
+    pub title: String,
+
+    /// Body text
+    pub body: String,
+
+    /// Available actions
+    pub actions: Vec<NotificationAction>,
+}
+
+impl Notification {
+    /// Create a new notification without actions
+    pub fn new(level: NotificationLevel, title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            level,
+            title: title.into(),
+            body: body.into(),
+            actions: Vec::new(),
+        }
+    }
+
+    /// Add an action to the notification
+    pub fn with_action(mut self, action: NotificationAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+}
+
+/// Publish a notification to the frontend
+pub fn notify(notification: Notification) {
+    debug!("Notification: {} - {}", notification.title, notification.body);
+
+    match serde_json::to_value(&notification) {
+        Ok(payload) => get_event_system().emit(events::NOTIFICATION, payload),
+        Err(e) => warn!("Failed to serialize notification: {}", e),
+    }
+}
+
 /// Event types
 pub mod events {
     /// Connection status changed
@@ -117,6 +205,9 @@ pub mod events {
     /// Message sent
     pub const MESSAGE_SENT: &str = "message_sent";
     
+    /// Feedback on a message was given or cleared
+    pub const MESSAGE_RATED: &str = "message_rated";
+    
     /// Message status changed
     pub const MESSAGE_STATUS_CHANGED: &str = "message_status_changed";
     
@@ -126,6 +217,55 @@ pub mod events {
     /// Conversation deleted
     pub const CONVERSATION_DELETED: &str = "conversation_deleted";
     
+    /// Earlier messages were dropped or summarized to fit a model's context
+    /// window, or a message was refused for not fitting
+    pub const CONVERSATION_CONTEXT_TRIMMED: &str = "conversation_context_trimmed";
+    
+    /// A journaled operation was undone
+    pub const OPERATION_UNDONE: &str = "operation_undone";
+    
+    /// An undone operation was redone
+    pub const OPERATION_REDONE: &str = "operation_redone";
+    
     /// Authentication status changed
     pub const AUTH_STATUS_CHANGED: &str = "auth_status_changed";
+    
+    /// Network status changed
+    pub const NETWORK_STATUS_CHANGED: &str = "network_status_changed";
+    
+    /// Offline mode was switched on or off
+    pub const OFFLINE_STATUS_CHANGED: &str = "offline_status_changed";
+    
+    /// The user asked for a new quick ask from outside the main window
+    pub const QUICK_ASK_REQUESTED: &str = "quick_ask_requested";
+    
+    /// Another app shared text or files; the payload is the draft to open
+    pub const SHARE_RECEIVED: &str = "share_received";
+    
+    /// A `papin://` link asked to open a conversation or join a session
+    pub const DEEP_LINK_OPENED: &str = "deep_link_opened";
+    
+    /// User-facing notification
+    pub const NOTIFICATION: &str = "notification";
+    
+    /// Memory pressure level changed
+    pub const MEMORY_PRESSURE_CHANGED: &str = "memory_pressure_changed";
+    
+    /// Model unloaded to free memory
+    pub const MODEL_UNLOADED: &str = "model_unloaded";
+    
+    /// Sync conflicts waiting for review changed
+    pub const SYNC_CONFLICTS_CHANGED: &str = "sync_conflicts_changed";
+    
+    /// Terminal session started
+    pub const TERMINAL_CREATED: &str = "terminal_created";
+    
+    /// Terminal session produced output
+    pub const TERMINAL_OUTPUT: &str = "terminal_output";
+    
+    /// Terminal session process exited
+    pub const TERMINAL_EXITED: &str = "terminal_exited";
+    
+    /// A provider's response latency went over or back under its SLA
+    pub const LATENCY_ALERT: &str = "latency_alert";
 }
@@ -1,3 +1,4 @@
+pub mod cancellation;
 pub mod config;
 pub mod events;
 pub mod lazy_loader;